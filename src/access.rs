@@ -0,0 +1,249 @@
+//! Access requests: when a chat outside its channel's allowlist (`allowed_groups`,
+//! `discord_allowed_channels`, `slack_allowed_channels`) messages the bot, the control chats get
+//! a request with Approve / Deny buttons. Approving records the chat in the `chat_access` table,
+//! which is checked on top of the static lists, so no config edit or restart is needed.
+//!
+//! The same table holds entries made with the `allow_chat` / `block_chat` tools: "allowed"
+//! admits a chat the config list leaves out, "blocked" refuses a chat it would admit (private
+//! chats included). Entries may expire.
+
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use tracing::{info, warn};
+
+use crate::channels::telegram::AppState;
+use crate::db::{call_blocking, AccessRequest};
+
+/// Prefix of the inline button callback data.
+pub const CALLBACK_PREFIX: &str = "access:";
+const MESSAGE_EXCERPT_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDecision {
+    Approve,
+    Deny,
+}
+
+pub fn callback_data(decision: AccessDecision, id: i64) -> String {
+    let verb = match decision {
+        AccessDecision::Approve => "approve",
+        AccessDecision::Deny => "deny",
+    };
+    format!("{CALLBACK_PREFIX}{verb}:{id}")
+}
+
+pub fn parse_callback(data: &str) -> Option<(AccessDecision, i64)> {
+    let (verb, id) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+    let decision = match verb {
+        "approve" => AccessDecision::Approve,
+        "deny" => AccessDecision::Deny,
+        _ => return None,
+    };
+    Some((decision, id.parse().ok()?))
+}
+
+fn request_notice(request: &AccessRequest) -> String {
+    let title = request
+        .chat_title
+        .as_deref()
+        .map(|t| format!(" \"{t}\""))
+        .unwrap_or_default();
+    let excerpt: String = request
+        .message
+        .chars()
+        .take(MESSAGE_EXCERPT_CHARS)
+        .collect();
+    format!(
+        "🔑 Access request #{}: {} chat {}{} (from {}).\n\n{}",
+        request.id, request.channel, request.chat_id, title, request.requester, excerpt
+    )
+}
+
+/// Whether the bot may answer `chat_id`. `static_allowed` is the verdict of the channel's
+/// config allowlist; a DB entry overrides it. A chat refused by the config list with no DB
+/// entry files an access request (once per chat) that is sent to the control chats.
+pub async fn admit(
+    state: &AppState,
+    channel: &str,
+    chat_id: i64,
+    static_allowed: bool,
+    chat_title: Option<&str>,
+    requester: &str,
+    text: &str,
+) -> bool {
+    match call_blocking(state.db.clone(), move |d| d.get_chat_access_status(chat_id)).await {
+        Ok(Some(status)) => return status == "allowed",
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read chat access for {chat_id}: {e}"),
+    }
+    if static_allowed {
+        return true;
+    }
+    if state.config.control_chat_ids.is_empty() {
+        return false;
+    }
+    let (ch, title, who, message) = (
+        channel.to_string(),
+        chat_title.map(str::to_string),
+        requester.to_string(),
+        text.chars().take(MESSAGE_EXCERPT_CHARS).collect::<String>(),
+    );
+    let id = match call_blocking(state.db.clone(), move |d| {
+        d.create_access_request(&ch, chat_id, title.as_deref(), &who, &message)
+    })
+    .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => return false,
+        Err(e) => {
+            warn!("Failed to file access request for chat {chat_id}: {e}");
+            return false;
+        }
+    };
+    let Ok(Some(request)) =
+        call_blocking(state.db.clone(), move |d| d.get_access_request(id)).await
+    else {
+        return false;
+    };
+    info!("Access request #{id} from {channel} chat {chat_id}");
+
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("✅ Approve", callback_data(AccessDecision::Approve, id)),
+        InlineKeyboardButton::callback("🚫 Deny", callback_data(AccessDecision::Deny, id)),
+    ]]);
+    let notice = request_notice(&request);
+    for control_chat in &state.config.control_chat_ids {
+        if let Err(e) = state
+            .bot
+            .send_message(ChatId(*control_chat), &notice)
+            .reply_markup(keyboard.clone())
+            .await
+        {
+            warn!("Failed to send access request #{id} to control chat {control_chat}: {e}");
+        }
+    }
+    false
+}
+
+/// Approve or deny request `id` from `resolver_chat`, which must be a control chat.
+pub async fn decide(
+    state: &AppState,
+    id: i64,
+    decision: AccessDecision,
+    resolver_chat: i64,
+) -> Result<String, String> {
+    if !state.config.control_chat_ids.contains(&resolver_chat) {
+        return Err("Access requests can only be decided from a control chat".into());
+    }
+    let request = call_blocking(state.db.clone(), move |d| d.get_access_request(id))
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Access request #{id} not found"))?;
+    let status = match decision {
+        AccessDecision::Approve => "approved",
+        AccessDecision::Deny => "denied",
+    };
+    let resolved = call_blocking(state.db.clone(), move |d| {
+        d.resolve_access_request(id, status)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resolved {
+        return Err(format!(
+            "Access request #{id} was already {}",
+            request.status
+        ));
+    }
+
+    let chat_id = request.chat_id;
+    if decision == AccessDecision::Approve {
+        let channel = request.channel.clone();
+        let note = format!("approved request #{id}");
+        call_blocking(state.db.clone(), move |d| {
+            d.set_chat_access(chat_id, &channel, "allowed", None, Some(&note))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        if request.channel == "telegram" {
+            if let Err(e) = state
+                .bot
+                .send_message(
+                    ChatId(chat_id),
+                    "Access approved. I'm now available in this chat.",
+                )
+                .await
+            {
+                warn!("Failed to tell chat {chat_id} about its approved access: {e}");
+            }
+        }
+    }
+    info!("Access request #{id} for chat {chat_id} {status}");
+    Ok(format!(
+        "{} chat {chat_id} ({})",
+        status_verb(decision),
+        request.channel
+    ))
+}
+
+fn status_verb(decision: AccessDecision) -> &'static str {
+    match decision {
+        AccessDecision::Approve => "Approved",
+        AccessDecision::Deny => "Denied",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_callback_roundtrip() {
+        let data = callback_data(AccessDecision::Deny, 12);
+        assert_eq!(data, "access:deny:12");
+        assert_eq!(parse_callback(&data), Some((AccessDecision::Deny, 12)));
+        assert_eq!(parse_callback("review:approve:1"), None);
+        assert_eq!(parse_callback("access:maybe:1"), None);
+    }
+
+    #[test]
+    fn test_requests_and_chat_access() {
+        let dir = std::env::temp_dir().join(format!("microclaw_access_{}", uuid::Uuid::new_v4()));
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+
+        let id = db
+            .create_access_request("telegram", -100, Some("Team"), "alice", "hi bot")
+            .unwrap()
+            .unwrap();
+        // One open request per chat
+        assert!(db
+            .create_access_request("telegram", -100, None, "bob", "hello?")
+            .unwrap()
+            .is_none());
+        let request = db.get_access_request(id).unwrap().unwrap();
+        assert_eq!(
+            request_notice(&request),
+            format!("🔑 Access request #{id}: telegram chat -100 \"Team\" (from alice).\n\nhi bot")
+        );
+
+        assert!(db.resolve_access_request(id, "approved").unwrap());
+        assert!(!db.resolve_access_request(id, "denied").unwrap());
+        assert_eq!(db.get_chat_access_status(-100).unwrap(), None);
+        db.set_chat_access(-100, "telegram", "allowed", None, None)
+            .unwrap();
+        assert_eq!(
+            db.get_chat_access_status(-100).unwrap().as_deref(),
+            Some("allowed")
+        );
+        db.set_chat_access(
+            -100,
+            "telegram",
+            "allowed",
+            Some("2000-01-01T00:00:00Z"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(db.get_chat_access_status(-100).unwrap(), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
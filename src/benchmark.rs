@@ -0,0 +1,524 @@
+//! Model benchmark: every `benchmark.interval_days` the eval suite (`benchmark.suite`, or a
+//! built-in set of everyday requests) runs against the main model and each candidate in
+//! `benchmark.models`. Quality is the share of scenarios passed (assertions and LLM judge), cost
+//! comes from the token usage and `usage::price_for`, latency from the wall time of the model's
+//! calls. The report is written to `<runtime>/benchmarks/` and summarised in a chat, naming the
+//! model that currently gives the best value. The date of the newest report is the schedule.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::claude::{Message, MessagesResponse, ToolDefinition};
+use crate::config::{BenchmarkConfig, Config, LlmFallbackConfig};
+use crate::db::call_blocking;
+use crate::error::MicroClawError;
+use crate::eval::{EvalSuite, ScenarioResult};
+use crate::llm::LlmProvider;
+use crate::telegram::AppState;
+use crate::usage::{estimate_cost, TurnUsage};
+
+const CHECK_EVERY: Duration = Duration::from_secs(3600);
+const REPORT_PREFIX: &str = "benchmark-";
+/// Models whose pass rate is within this of the best one count as equally good; the cheapest
+/// (then fastest) of them is the best value.
+const QUALITY_TOLERANCE: f64 = 0.1;
+
+/// Used when `benchmark.suite` is not set.
+const BUILTIN_SUITE: &str = r#"
+name: household
+scenarios:
+  - name: dinner from the fridge
+    prompt: "What can I cook for dinner with chicken thighs, rice, spinach and a lemon? Under 40 minutes please."
+    judge: "Suggests one or two concrete dishes using those ingredients, with short steps that fit in 40 minutes."
+  - name: weekly reminder
+    prompt: "Remind me to take the bins out every Tuesday at 7pm."
+    mock_tools:
+      schedule_task: "Scheduled task #12: 'Take the bins out' (cron 0 0 19 * * 2)"
+    assert:
+      tools_called: [schedule_task]
+    judge: "Confirms a weekly reminder on Tuesdays at 7pm."
+  - name: umbrella
+    prompt: "Do I need an umbrella in Paris this afternoon?"
+    mock_tools:
+      web_search: "Paris today: 14°C, showers from 2pm (80% chance), clearing in the evening."
+      web_fetch: "Paris today: 14°C, showers from 2pm (80% chance), clearing in the evening."
+    judge: "Recommends an umbrella because of the afternoon showers."
+  - name: note to the neighbours
+    prompt: "Write a short, friendly note to the neighbours asking them to keep the noise down after 10pm on weeknights."
+    judge: "A polite, friendly note of a few sentences that mentions weeknights after 10pm."
+  - name: explain to a child
+    prompt: "Explain in two or three sentences why the sky is blue, for an 8-year-old."
+    judge: "Correct (sunlight is scattered, blue the most) and simple enough for a child, in at most three or four sentences."
+"#;
+
+/// Token usage and wall time of the calls made through a provider.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Meter {
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Wraps a provider and meters every call.
+pub struct MeteredLlm {
+    inner: Box<dyn LlmProvider>,
+    meter: Mutex<Meter>,
+}
+
+impl MeteredLlm {
+    pub fn new(inner: Box<dyn LlmProvider>) -> Self {
+        MeteredLlm {
+            inner,
+            meter: Mutex::new(Meter::default()),
+        }
+    }
+
+    pub fn meter(&self) -> Meter {
+        *self.meter.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MeteredLlm {
+    async fn send_message(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let started = Instant::now();
+        let result = self.inner.send_message(system, messages, tools).await;
+        let mut meter = self.meter.lock().unwrap();
+        meter.calls += 1;
+        meter.elapsed_ms += started.elapsed().as_millis() as u64;
+        if let Some(usage) = result.as_ref().ok().and_then(|r| r.usage.as_ref()) {
+            meter.input_tokens += usage.input_tokens as u64;
+            meter.output_tokens += usage.output_tokens as u64;
+        }
+        result
+    }
+}
+
+/// One model's results over the suite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelResult {
+    /// `provider/model`
+    pub label: String,
+    /// The main model, as configured today.
+    pub current: bool,
+    pub passed: usize,
+    pub scenarios: usize,
+    /// Per scenario.
+    pub avg_latency_ms: u64,
+    /// Whole suite; `None` when the model has no known price.
+    pub cost_usd: Option<f64>,
+    /// "scenario: reason"
+    pub failures: Vec<String>,
+}
+
+impl ModelResult {
+    pub fn quality(&self) -> f64 {
+        if self.scenarios == 0 {
+            0.0
+        } else {
+            self.passed as f64 / self.scenarios as f64
+        }
+    }
+
+    fn from_run(
+        config: &Config,
+        label: String,
+        model: &str,
+        current: bool,
+        results: &[ScenarioResult],
+        meter: Meter,
+    ) -> Self {
+        let usage = TurnUsage {
+            model: model.to_string(),
+            input_tokens: meter.input_tokens,
+            output_tokens: meter.output_tokens,
+            ..TurnUsage::default()
+        };
+        ModelResult {
+            label,
+            current,
+            passed: results.iter().filter(|r| r.passed()).count(),
+            scenarios: results.len(),
+            avg_latency_ms: meter.elapsed_ms / results.len().max(1) as u64,
+            cost_usd: estimate_cost(config, &usage),
+            failures: results
+                .iter()
+                .flat_map(|r| r.failures.iter().map(move |f| format!("{}: {f}", r.name)))
+                .collect(),
+        }
+    }
+}
+
+/// Index of the best-value model: among those that passed something and are within
+/// `QUALITY_TOLERANCE` of the best pass rate, the cheapest (priced before unpriced), then fastest.
+pub fn best_value(results: &[ModelResult]) -> Option<usize> {
+    let best_quality = results.iter().map(ModelResult::quality).fold(0.0, f64::max);
+    results
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| {
+            // The epsilon keeps 9/10 within 0.1 of 10/10 despite float rounding
+            r.passed > 0 && r.quality() + 1e-9 >= best_quality - QUALITY_TOLERANCE
+        })
+        .min_by(|(_, a), (_, b)| {
+            let cost = |r: &ModelResult| r.cost_usd.unwrap_or(f64::INFINITY);
+            cost(a)
+                .total_cmp(&cost(b))
+                .then(a.avg_latency_ms.cmp(&b.avg_latency_ms))
+        })
+        .map(|(i, _)| i)
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map(|c| format!("~${c:.4}"))
+        .unwrap_or_else(|| "unknown cost".into())
+}
+
+fn format_latency(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+fn describe(r: &ModelResult) -> String {
+    format!(
+        "{}/{} passed, {} per run, {} avg",
+        r.passed,
+        r.scenarios,
+        format_cost(r.cost_usd),
+        format_latency(r.avg_latency_ms)
+    )
+}
+
+pub fn render_report(suite: &str, results: &[ModelResult], today: NaiveDate) -> String {
+    let best = best_value(results);
+    let mut out = format!(
+        "# Model benchmark {today}\n\nSuite: {suite}. Quality is the share of scenarios passed; cost is for one run of the suite; latency is per scenario.\n\n| Model | Quality | Cost | Latency |\n|---|---|---|---|\n"
+    );
+    for (i, r) in results.iter().enumerate() {
+        let mut name = r.label.clone();
+        if r.current {
+            name.push_str(" (current)");
+        }
+        if best == Some(i) {
+            name = format!("**{name}** ✓");
+        }
+        out.push_str(&format!(
+            "| {name} | {}/{} | {} | {} |\n",
+            r.passed,
+            r.scenarios,
+            format_cost(r.cost_usd),
+            format_latency(r.avg_latency_ms)
+        ));
+    }
+    for r in results.iter().filter(|r| !r.failures.is_empty()) {
+        out.push_str(&format!("\n## Failures: {}\n\n", r.label));
+        for f in &r.failures {
+            out.push_str(&format!("- {f}\n"));
+        }
+    }
+    out
+}
+
+pub fn summary(suite: &str, results: &[ModelResult]) -> String {
+    let current = results.iter().find(|r| r.current);
+    let Some(best) = best_value(results).map(|i| &results[i]) else {
+        return format!(
+            "Model benchmark ({suite}): no model passed any scenario; see the report for details."
+        );
+    };
+    let mut text = if best.current {
+        format!(
+            "Model benchmark ({suite}): your current model {} still gives the best value ({}).",
+            best.label,
+            describe(best)
+        )
+    } else {
+        format!(
+            "Model benchmark ({suite}): {} gives the best value ({}).",
+            best.label,
+            describe(best)
+        )
+    };
+    if let Some(current) = current.filter(|_| !best.current) {
+        text.push_str(&format!(
+            " Current model {}: {}.",
+            current.label,
+            describe(current)
+        ));
+    }
+    text
+}
+
+fn load_suite(settings: &BenchmarkConfig) -> Result<EvalSuite, String> {
+    match settings.suite.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(path) => crate::eval::load_suite(Path::new(path.trim())),
+        None => serde_yaml::from_str(BUILTIN_SUITE).map_err(|e| e.to_string()),
+    }
+}
+
+/// Config for one candidate: its provider, model and key over the main ones, without fallbacks
+/// (the benchmark measures the model itself).
+fn candidate_config(config: &Config, candidate: &LlmFallbackConfig) -> Config {
+    let mut candidate_config = config.clone();
+    candidate_config.llm_provider = candidate.provider.clone();
+    candidate_config.model = candidate.model.clone();
+    if let Some(key) = candidate.api_key.as_ref().filter(|k| !k.trim().is_empty()) {
+        candidate_config.api_key = key.clone();
+    }
+    if candidate.provider != config.llm_provider || candidate.base_url.is_some() {
+        candidate_config.llm_base_url = candidate.base_url.clone();
+    }
+    candidate_config.llm_fallbacks.clear();
+    candidate_config
+}
+
+fn report_dir(config: &Config) -> PathBuf {
+    PathBuf::from(config.runtime_data_dir()).join("benchmarks")
+}
+
+/// Date of the newest report.
+fn last_report_date(dir: &Path) -> Option<NaiveDate> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let date = name
+                .strip_prefix(REPORT_PREFIX)?
+                .strip_suffix(".md")?
+                .to_string();
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()
+        })
+        .max()
+}
+
+/// Run the suite against every model, write today's report and post the summary.
+pub async fn run_benchmark(state: &Arc<AppState>) -> Result<String, String> {
+    let config = &state.config;
+    let settings = config
+        .benchmark
+        .clone()
+        .ok_or("benchmark is not configured")?;
+    let suite = load_suite(&settings)?;
+    let system_prompt = crate::eval::eval_system_prompt(config, &state.memory, &state.skills);
+    let tool_defs = state.tools.definitions();
+
+    let mut judge_config = config.clone();
+    if let Some(model) = settings
+        .judge_model
+        .clone()
+        .or_else(|| suite.judge_model.clone())
+        .filter(|m| !m.trim().is_empty())
+    {
+        judge_config.model = model;
+    }
+    let judge = crate::llm::create_provider(&judge_config);
+
+    let main = LlmFallbackConfig {
+        provider: config.llm_provider.clone(),
+        model: config.model.clone(),
+        api_key: None,
+        base_url: config.llm_base_url.clone(),
+    };
+    let mut results = Vec::new();
+    for (i, candidate) in std::iter::once(&main).chain(&settings.models).enumerate() {
+        let label = format!("{}/{}", candidate.provider, candidate.model);
+        if i > 0 && results.iter().any(|r: &ModelResult| r.label == label) {
+            continue;
+        }
+        info!("Benchmarking {label} ({} scenarios)", suite.scenarios.len());
+        let llm = MeteredLlm::new(crate::llm::create_provider(&candidate_config(
+            config, candidate,
+        )));
+        let scenario_results =
+            crate::eval::run_suite(&suite, &llm, judge.as_ref(), &system_prompt, &tool_defs).await;
+        results.push(ModelResult::from_run(
+            config,
+            label,
+            &candidate.model,
+            i == 0,
+            &scenario_results,
+            llm.meter(),
+        ));
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let dir = report_dir(config);
+    let path = dir.join(format!("{REPORT_PREFIX}{today}.md"));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&path, render_report(&suite.name, &results, today)))
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+
+    let text = format!(
+        "{} Report: {}",
+        summary(&suite.name, &results),
+        path.display()
+    );
+    if let Some(chat_id) = settings
+        .chat_id
+        .or_else(|| config.control_chat_ids.first().copied())
+    {
+        let persona_id =
+            call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+        deliver_and_store_bot_message(
+            &state.bot,
+            state.db.clone(),
+            &config.bot_username,
+            chat_id,
+            persona_id,
+            &text,
+        )
+        .await?;
+    }
+    Ok(text)
+}
+
+/// Run the benchmark whenever the last report is `interval_days` old.
+pub fn spawn_benchmark(state: Arc<AppState>) {
+    let Some(settings) = state.config.benchmark.clone() else {
+        return;
+    };
+    let dir = report_dir(&state.config);
+    info!(
+        "Model benchmark every {} days ({} candidates)",
+        settings.interval_days,
+        settings.models.len()
+    );
+    tokio::spawn(async move {
+        loop {
+            let today = chrono::Local::now().date_naive();
+            let due = last_report_date(&dir)
+                .is_none_or(|last| (today - last).num_days() >= settings.interval_days.max(1));
+            if due {
+                match run_benchmark(&state).await {
+                    Ok(text) => info!("{text}"),
+                    Err(e) => warn!("Model benchmark failed: {e}"),
+                }
+            }
+            tokio::time::sleep(CHECK_EVERY).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::{MessageContent, ResponseContentBlock, Usage};
+
+    fn result(label: &str, passed: usize, cost: Option<f64>, latency: u64) -> ModelResult {
+        ModelResult {
+            label: label.into(),
+            current: false,
+            passed,
+            scenarios: 10,
+            avg_latency_ms: latency,
+            cost_usd: cost,
+            failures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_suite_parses() {
+        let suite: EvalSuite = serde_yaml::from_str(BUILTIN_SUITE).unwrap();
+        assert_eq!(suite.name, "household");
+        assert!(suite.scenarios.iter().all(|s| s.judge.is_some()));
+    }
+
+    #[test]
+    fn test_best_value_prefers_cheap_models_of_similar_quality() {
+        let results = vec![
+            result("anthropic/claude-sonnet-4", 10, Some(0.05), 3000),
+            result("openai/gpt-4.1-mini", 9, Some(0.004), 1500),
+            result("openai/gpt-4.1-nano", 6, Some(0.001), 900),
+        ];
+        assert_eq!(best_value(&results), Some(1));
+
+        // Unknown prices lose to known ones; ties go to the faster model
+        let results = vec![
+            result("llamacpp/qwen", 10, None, 800),
+            result("a/x", 10, Some(0.01), 2000),
+            result("b/y", 10, Some(0.01), 1000),
+        ];
+        assert_eq!(best_value(&results), Some(2));
+
+        assert_eq!(best_value(&[result("a/x", 0, Some(0.01), 10)]), None);
+    }
+
+    #[test]
+    fn test_summary_names_best_and_current() {
+        let mut current = result("anthropic/claude-sonnet-4", 10, Some(0.05), 3000);
+        current.current = true;
+        let results = vec![
+            current.clone(),
+            result("openai/gpt-4.1-mini", 10, Some(0.004), 1500),
+        ];
+        let text = summary("household", &results);
+        assert!(text.contains(
+            "openai/gpt-4.1-mini gives the best value (10/10 passed, ~$0.0040 per run, 1.5s avg)"
+        ));
+        assert!(text.contains("Current model anthropic/claude-sonnet-4"));
+
+        let text = summary("household", &[current]);
+        assert!(text
+            .contains("your current model anthropic/claude-sonnet-4 still gives the best value"));
+
+        let report = render_report(
+            "household",
+            &results,
+            NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+        );
+        assert!(
+            report.contains("| anthropic/claude-sonnet-4 (current) | 10/10 | ~$0.0500 | 3.0s |")
+        );
+        assert!(report.contains("| **openai/gpt-4.1-mini** ✓ |"));
+    }
+
+    struct FixedLlm;
+
+    #[async_trait]
+    impl LlmProvider for FixedLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text { text: "ok".into() }],
+                stop_reason: Some("end_turn".into()),
+                usage: Some(Usage {
+                    input_tokens: 120,
+                    output_tokens: 30,
+                }),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metered_llm_sums_usage() {
+        let llm = MeteredLlm::new(Box::new(FixedLlm));
+        for _ in 0..2 {
+            let messages = vec![Message {
+                role: "user".into(),
+                content: MessageContent::Text("hi".into()),
+            }];
+            llm.send_message("", messages, None).await.unwrap();
+        }
+        let meter = llm.meter();
+        assert_eq!(meter.calls, 2);
+        assert_eq!((meter.input_tokens, meter.output_tokens), (240, 60));
+    }
+}
@@ -1,12 +1,231 @@
-use std::sync::Arc;
+//! Delivery to chats on any platform. Each integration implements [`Channel`] (send text and
+//! attachments, typing, reactions, message size limit and markup dialect), so cross-channel
+//! features such as proactive messages, scheduled replies and attachments are written once
+//! against the trait. Receiving stays in each integration, since the transports differ (long
+//! polling, gateway, webhooks, sockets); they hand incoming turns to [`reply_with_agent`].
+//!
+//! Channels that can send without an incoming message (Telegram, Discord, WhatsApp, Slack,
+//! Signal, Teams) are registered at startup; [`channel_for_chat`] picks one from the stored chat type.
 
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use async_trait::async_trait;
 use teloxide::prelude::*;
-use teloxide::types::ParseMode;
+use tracing::{error, warn};
 
-use crate::channels::telegram::markdown_to_telegram_html;
+use crate::channels::telegram::{markdown_to_telegram_html, AgentRequestContext, AppState};
+use crate::config::Config;
 use crate::db::{call_blocking, Database, StoredMessage};
 use crate::tools::auth_context_from_input;
 
+/// Markup a channel renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatDialect {
+    /// Telegram's HTML subset; markdown from the model is converted.
+    TelegramHtml,
+    /// Markdown (or a close variant) is shown as-is.
+    Markdown,
+    /// No markup: bold markers, inline code ticks and heading hashes are dropped.
+    Plain,
+}
+
+impl FormatDialect {
+    pub fn render(self, text: &str) -> String {
+        match self {
+            FormatDialect::TelegramHtml => markdown_to_telegram_html(text),
+            FormatDialect::Markdown => text.to_string(),
+            FormatDialect::Plain => text
+                .lines()
+                .map(|line| {
+                    let line = line.replace("**", "").replace('`', "");
+                    match line.trim_start_matches('#') {
+                        rest if rest.len() < line.len() && rest.starts_with(' ') => {
+                            rest.trim_start().to_string()
+                        }
+                        _ => line,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Split `text` into chunks of at most `max_len` bytes, preferring line breaks and never cutting
+/// a UTF-8 character. Text that fits (including empty text) is a single chunk.
+pub fn split_text(text: &str, max_len: usize) -> Vec<String> {
+    if text.len() <= max_len {
+        return vec![text.to_string()];
+    }
+    let mut chunks = Vec::new();
+    let mut remaining = text;
+    while !remaining.is_empty() {
+        let mut chunk_len = if remaining.len() <= max_len {
+            remaining.len()
+        } else {
+            remaining[..remaining.floor_char_boundary(max_len)]
+                .rfind('\n')
+                .filter(|&i| i > 0)
+                .unwrap_or(max_len)
+        };
+        while !remaining.is_char_boundary(chunk_len) {
+            chunk_len -= 1;
+        }
+        chunks.push(remaining[..chunk_len].to_string());
+        remaining = &remaining[chunk_len..];
+        if remaining.starts_with('\n') {
+            remaining = &remaining[1..];
+        }
+    }
+    chunks
+}
+
+/// One messaging platform, addressed by the numeric chat ids stored in the database.
+#[async_trait]
+pub trait Channel: Send + Sync {
+    /// Channel name, as used for `caller_channel` and in errors.
+    fn name(&self) -> &'static str;
+
+    /// Longest message the platform accepts, in bytes.
+    fn max_message_len(&self) -> usize;
+
+    fn dialect(&self) -> FormatDialect {
+        FormatDialect::Markdown
+    }
+
+    /// Send one already rendered chunk that fits [`Channel::max_message_len`].
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String>;
+
+    /// Render `text` in the channel's dialect and send it, split to the size limit.
+    async fn send_text(&self, chat_id: i64, text: &str) -> Result<(), String> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let rendered = self.dialect().render(text);
+        for chunk in split_text(&rendered, self.max_message_len()) {
+            self.send_chunk(chat_id, &chunk).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_attachment(
+        &self,
+        _chat_id: i64,
+        _path: &Path,
+        _caption: Option<&str>,
+    ) -> Result<(), String> {
+        Err(format!(
+            "attachment sending is not supported for {} chat",
+            self.name()
+        ))
+    }
+
+    /// Show a typing indicator; platforms clear it after a few seconds.
+    async fn send_typing(&self, _chat_id: i64) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn react(&self, _chat_id: i64, _message_id: &str, _emoji: &str) -> Result<(), String> {
+        Err(format!(
+            "Reactions are not supported for {} chats",
+            self.name()
+        ))
+    }
+}
+
+/// Web UI chats: the stored bot message is the delivery, so sending does nothing.
+pub struct WebChannel;
+
+#[async_trait]
+impl Channel for WebChannel {
+    fn name(&self) -> &'static str {
+        "web"
+    }
+
+    fn max_message_len(&self) -> usize {
+        usize::MAX
+    }
+
+    async fn send_chunk(&self, _chat_id: i64, _chunk: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+fn registry() -> &'static RwLock<HashMap<&'static str, Arc<dyn Channel>>> {
+    static CHANNELS: OnceLock<RwLock<HashMap<&'static str, Arc<dyn Channel>>>> = OnceLock::new();
+    CHANNELS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Make a channel available for outgoing messages, replacing one with the same name.
+pub fn register_channel(channel: Arc<dyn Channel>) {
+    registry().write().unwrap().insert(channel.name(), channel);
+}
+
+/// Register the channels whose credentials are configured. Called once at startup, before
+/// anything sends proactively.
+pub fn register_configured_channels(config: &Config, db: &Arc<Database>) {
+    if let Some(channel) = crate::discord::DiscordChannel::from_config(config) {
+        register_channel(Arc::new(channel));
+    }
+    if let Some(channel) = crate::whatsapp::WhatsAppChannel::from_config(config) {
+        register_channel(Arc::new(channel));
+    }
+    if let Some(channel) = crate::slack::SlackChannel::from_config(config) {
+        register_channel(Arc::new(channel));
+    }
+    if let Some(channel) = crate::signal::SignalChannel::from_config(config, db.clone()) {
+        register_channel(Arc::new(channel));
+    }
+    if let Some(channel) = crate::teams::TeamsChannel::from_config(config, db.clone()) {
+        register_channel(Arc::new(channel));
+    }
+}
+
+/// Channel name for a stored chat type. Telegram chat types ("private", "group",
+/// "telegram_supergroup", ...) and unknown ones map to "telegram".
+pub fn channel_name(chat_type: &str) -> &'static str {
+    match chat_type {
+        "discord" => "discord",
+        "whatsapp" => "whatsapp",
+        "slack" => "slack",
+        "signal" => "signal",
+        "teams" => "teams",
+        "telegram_business" => "telegram_business",
+        "web" => "web",
+        _ => "telegram",
+    }
+}
+
+/// The channel that delivers to chats of `chat_type`. Telegram is always available through
+/// `bot`; other platforms must have been registered.
+pub fn channel_for_chat_type(chat_type: &str, bot: &Bot) -> Result<Arc<dyn Channel>, String> {
+    match channel_name(chat_type) {
+        "web" => Ok(Arc::new(WebChannel)),
+        name => match registry().read().unwrap().get(name) {
+            Some(channel) => Ok(channel.clone()),
+            None if name == "telegram" => Ok(Arc::new(
+                crate::channels::telegram::TelegramChannel::new(bot.clone()),
+            )),
+            None => Err(format!("No running channel delivers to {name} chats")),
+        },
+    }
+}
+
+/// The channel for a stored chat; unknown chats are treated as Telegram.
+pub async fn channel_for_chat(
+    db: Arc<Database>,
+    bot: &Bot,
+    chat_id: i64,
+) -> Result<Arc<dyn Channel>, String> {
+    let chat_type = call_blocking(db, move |d| d.get_chat_type(chat_id))
+        .await
+        .map_err(|e| format!("Failed to read chat type: {e}"))?
+        .unwrap_or_default();
+    channel_for_chat_type(&chat_type, bot)
+}
+
 pub async fn is_web_chat(db: Arc<Database>, chat_id: i64) -> bool {
     matches!(
         call_blocking(db, move |d| d.get_chat_type(chat_id)).await,
@@ -30,6 +249,28 @@ pub async fn enforce_channel_policy(
     Ok(())
 }
 
+pub async fn store_bot_message(
+    db: Arc<Database>,
+    bot_username: &str,
+    chat_id: i64,
+    persona_id: i64,
+    content: String,
+) -> Result<(), String> {
+    let msg = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        chat_id,
+        persona_id,
+        sender_name: bot_username.to_string(),
+        content,
+        is_from_bot: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    call_blocking(db, move |d| d.store_message(&msg))
+        .await
+        .map_err(|e| format!("Failed to store sent message: {e}"))
+}
+
+/// Send `text` to a chat on its own channel and store it as a bot message.
 pub async fn deliver_and_store_bot_message(
     bot: &Bot,
     db: Arc<Database>,
@@ -38,59 +279,148 @@ pub async fn deliver_and_store_bot_message(
     persona_id: i64,
     text: &str,
 ) -> Result<(), String> {
-    if is_web_chat(db.clone(), chat_id).await {
-        let msg = StoredMessage {
-            id: uuid::Uuid::new_v4().to_string(),
-            chat_id,
-            persona_id,
-            sender_name: bot_username.to_string(),
-            content: text.to_string(),
-            is_from_bot: true,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        };
-        call_blocking(db.clone(), move |d| d.store_message(&msg))
+    let channel = channel_for_chat(db.clone(), bot, chat_id).await?;
+    if let Err(e) = channel.send_text(chat_id, text).await {
+        // Chat may have been deleted or bot removed; still store so conversation history is intact (e.g. web UI can show reply).
+        if e.contains("chat not found")
+            || e.contains("Chat not found")
+            || e.contains("user is deactivated")
+        {
+            warn!(
+                target: "channel",
+                chat_id = chat_id,
+                error = %e,
+                "{} delivery failed (chat unavailable); storing message anyway",
+                channel.name()
+            );
+        } else {
+            return Err(format!("Failed to send message: {e}"));
+        }
+    }
+    store_bot_message(db, bot_username, chat_id, persona_id, text.to_string()).await
+}
+
+/// Typing indicators expire after about 5-10 seconds depending on the platform.
+const TYPING_REFRESH: std::time::Duration = std::time::Duration::from_secs(4);
+
+/// Run the agent on a turn whose user message is already stored, send the reply back through
+/// `channel` and store it. Keeps a typing indicator up meanwhile; failures are sent to the chat
+/// as "Error: ...".
+pub async fn reply_with_agent(
+    state: &Arc<AppState>,
+    channel: Arc<dyn Channel>,
+    context: AgentRequestContext<'_>,
+) {
+    let chat_id = context.chat_id;
+    let persona_id = context.persona_id;
+    let typing = {
+        let channel = channel.clone();
+        tokio::spawn(async move {
+            loop {
+                let _ = channel.send_typing(chat_id).await;
+                tokio::time::sleep(TYPING_REFRESH).await;
+            }
+        })
+    };
+    let result = crate::telegram::process_with_agent(state, context, None, None).await;
+    typing.abort();
+
+    match result {
+        Ok(response) => {
+            if response.is_empty() {
+                return;
+            }
+            let delivery_started = std::time::Instant::now();
+            if let Err(e) = channel.send_text(chat_id, &response).await {
+                error!("Failed to send {} reply: {e}", channel.name());
+            }
+            crate::turn_metrics::record_delivery(state.db.clone(), chat_id, delivery_started)
+                .await;
+            if let Err(e) = store_bot_message(
+                state.db.clone(),
+                &state.config.bot_username,
+                chat_id,
+                persona_id,
+                response,
+            )
             .await
-            .map_err(|e| format!("Failed to store web message: {e}"))
-    } else {
-        let formatted = markdown_to_telegram_html(text);
-        let send_result = bot
-            .send_message(ChatId(chat_id), &formatted)
-            .parse_mode(ParseMode::Html)
-            .await;
-        let msg = StoredMessage {
-            id: uuid::Uuid::new_v4().to_string(),
-            chat_id,
-            persona_id,
-            sender_name: bot_username.to_string(),
-            content: text.to_string(),
-            is_from_bot: true,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        };
-        match &send_result {
-            Ok(_) => {}
-            Err(e) => {
-                let err_str = e.to_string();
-                // Chat may have been deleted or bot removed; still store so conversation history is intact (e.g. web UI can show reply).
-                if err_str.contains("chat not found")
-                    || err_str.contains("Chat not found")
-                    || err_str.contains("user is deactivated")
-                {
-                    tracing::warn!(
-                        target: "channel",
-                        chat_id = chat_id,
-                        error = %err_str,
-                        "Telegram delivery failed (chat unavailable); storing message anyway"
-                    );
-                    call_blocking(db.clone(), move |d| d.store_message(&msg))
-                        .await
-                        .map_err(|e| format!("Failed to store message: {e}"))?;
-                    return Ok(());
-                }
-                return Err(format!("Failed to send message: {e}"));
+            {
+                error!("{e}");
             }
         }
-        call_blocking(db.clone(), move |d| d.store_message(&msg))
+        Err(e) => {
+            error!("Error processing {} message: {e}", channel.name());
+            let _ = channel.send_text(chat_id, &format!("Error: {e}")).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_text() {
+        assert_eq!(split_text("", 10), vec![""]);
+        assert_eq!(split_text("aaaa\nbbbb\ncc", 9), vec!["aaaa", "bbbb\ncc"]);
+        assert_eq!(split_text(&"a".repeat(25), 10).len(), 3);
+        // Never splits inside a multi-byte character
+        let chunks = split_text(&"é".repeat(10), 5);
+        assert!(chunks.iter().all(|c| c.len() <= 5 && !c.is_empty()));
+        assert_eq!(chunks.concat(), "é".repeat(10));
+    }
+
+    #[test]
+    fn test_plain_dialect() {
+        assert_eq!(
+            FormatDialect::Plain.render("## Title\nUse **bold** and `code`\n#hashtag"),
+            "Title\nUse bold and code\n#hashtag"
+        );
+        assert_eq!(FormatDialect::Markdown.render("**x**"), "**x**");
+    }
+
+    #[tokio::test]
+    async fn test_channel_for_chat_type() {
+        let bot = Bot::new("123456:TEST_TOKEN");
+        assert_eq!(channel_for_chat_type("web", &bot).unwrap().name(), "web");
+        for chat_type in ["private", "telegram_group", ""] {
+            let channel = channel_for_chat_type(chat_type, &bot).unwrap();
+            assert_eq!(channel.name(), "telegram");
+            assert_eq!(channel.dialect(), FormatDialect::TelegramHtml);
+        }
+
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.signal = Some(crate::config::SignalConfig {
+            signal_cli_path: "signal-cli".into(),
+            signal_account: "+15550000".into(),
+            allowed_groups: Vec::new(),
+        });
+        config.teams = Some(crate::config::TeamsConfig {
+            app_id: "app".into(),
+            app_password: "secret".into(),
+            tenant_id: None,
+            webhook_port: 3978,
+            allowed_channels: Vec::new(),
+        });
+        let dir = std::env::temp_dir().join(format!("microclaw_channel_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        register_configured_channels(&config, &db);
+        for name in ["signal", "teams"] {
+            let channel = channel_for_chat_type(name, &bot).unwrap();
+            assert_eq!(channel.name(), name);
+            // No address recorded yet: the chat never wrote
+            let err = channel.send_text(42, "hi").await.unwrap_err();
+            assert!(err.contains("chat 42"), "{err}");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let web = WebChannel;
+        let err = web
+            .send_attachment(1, Path::new("a.txt"), None)
             .await
-            .map_err(|e| format!("Failed to store sent message: {e}"))
+            .unwrap_err();
+        assert!(err.contains("not supported for web chat"));
     }
 }
@@ -1,20 +1,261 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 
 use serenity::async_trait;
-use serenity::model::channel::Message as DiscordMessage;
+use serenity::builder::{
+    CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, EditInteractionResponse,
+};
+use serenity::model::application::{
+    CommandDataOptionValue, CommandInteraction, CommandOptionType, ComponentInteraction,
+    Interaction,
+};
+use serenity::model::channel::{Message as DiscordMessage, Reaction, ReactionType};
 use serenity::model::gateway::Ready;
-use serenity::model::id::ChannelId;
+use serenity::model::id::{ChannelId, GuildId};
 use serenity::prelude::*;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
+use crate::channel::{split_text, Channel};
 use crate::claude::Message as ClaudeMessage;
+use crate::config::Config;
 use crate::db::call_blocking;
 use crate::db::StoredMessage;
 use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
 use crate::telegram::{archive_conversation, AgentRequestContext, AppState};
 
+/// Reply to a custom command that can't run where it was invoked (Discord registers them per
+/// guild, not per channel).
+const UNAVAILABLE_COMMAND: &str = "This command isn't available in this channel.";
+
 struct Handler {
     app_state: Arc<AppState>,
+    /// Channels seen per guild, used to register their custom commands as guild commands.
+    guild_channels: Mutex<HashMap<GuildId, HashSet<i64>>>,
+    /// Last registered custom command signature per guild (skip redundant updates).
+    registered_commands: Mutex<HashMap<GuildId, String>>,
+}
+
+impl Handler {
+    /// Register the custom commands of the guild's known channels as Discord guild commands.
+    async fn sync_guild_commands(&self, ctx: &Context, guild_id: GuildId) {
+        let channels: Vec<i64> = self
+            .guild_channels
+            .lock()
+            .await
+            .get(&guild_id)
+            .map(|c| c.iter().copied().collect())
+            .unwrap_or_default();
+        let commands = match call_blocking(self.app_state.db.clone(), move |db| {
+            db.list_custom_commands(&channels)
+        })
+        .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("Discord: failed to load custom commands: {e}");
+                return;
+            }
+        };
+        let mut by_name: Vec<(String, String)> = Vec::new();
+        for c in commands {
+            if !by_name.iter().any(|(name, _)| *name == c.name) {
+                by_name.push((c.name, c.description));
+            }
+        }
+        by_name.sort();
+        let signature = by_name
+            .iter()
+            .map(|(name, desc)| format!("{name}:{desc}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        {
+            let mut registered = self.registered_commands.lock().await;
+            if registered.get(&guild_id) == Some(&signature) {
+                return;
+            }
+            registered.insert(guild_id, signature);
+        }
+        let builders: Vec<CreateCommand> = by_name
+            .into_iter()
+            .map(|(name, desc)| {
+                CreateCommand::new(name)
+                    .description(desc.chars().take(100).collect::<String>())
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "args",
+                        "Arguments for the command",
+                    ))
+            })
+            .collect();
+        if let Err(e) = guild_id.set_commands(&ctx.http, builders).await {
+            warn!("Discord: failed to register custom commands for guild {guild_id}: {e}");
+            self.registered_commands.lock().await.remove(&guild_id);
+        }
+    }
+
+    /// Record a vote from a poll button (custom_id `poll:<poll_id>:<option>`).
+    async fn handle_poll_vote(&self, ctx: &Context, component: ComponentInteraction) {
+        let Some(rest) = component
+            .data
+            .custom_id
+            .strip_prefix(crate::polls::DISCORD_POLL_PREFIX)
+        else {
+            return;
+        };
+        let Some((poll_id, option)) = rest.rsplit_once(':') else {
+            return;
+        };
+        let Ok(option) = option.parse::<usize>() else {
+            return;
+        };
+        let reply = match crate::polls::record_vote(
+            self.app_state.db.clone(),
+            poll_id,
+            &component.user.name,
+            option,
+        )
+        .await
+        {
+            Ok(msg) => msg,
+            Err(e) => e,
+        };
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(reply)
+                .ephemeral(true),
+        );
+        if let Err(e) = component.create_response(&ctx.http, response).await {
+            warn!("Discord: failed to acknowledge poll vote: {e}");
+        }
+    }
+
+    /// Run a custom command invoked through Discord's application command UI.
+    async fn handle_custom_command(&self, ctx: &Context, command: CommandInteraction) {
+        let channel_id = command.channel_id.get() as i64;
+        if !self.app_state.config.discord_allowed_channels.is_empty()
+            && !self
+                .app_state
+                .config
+                .discord_allowed_channels
+                .contains(&(channel_id as u64))
+        {
+            reply_ephemeral(ctx, &command, UNAVAILABLE_COMMAND).await;
+            return;
+        }
+        let args = command
+            .data
+            .options
+            .iter()
+            .find_map(|o| match &o.value {
+                CommandDataOptionValue::String(s) if o.name == "args" => Some(s.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let invocation = format!("/{} {args}", command.data.name);
+        let Some(text) = crate::custom_commands::expand_custom_command(
+            self.app_state.db.clone(),
+            channel_id,
+            &invocation,
+        )
+        .await
+        else {
+            // Guild commands are shared by all channels; this one defines no such command
+            reply_ephemeral(ctx, &command, UNAVAILABLE_COMMAND).await;
+            return;
+        };
+        let persona_id = call_blocking(self.app_state.db.clone(), move |db| {
+            db.get_current_persona_id(channel_id)
+        })
+        .await
+        .unwrap_or(0);
+        if persona_id == 0 {
+            reply_ephemeral(ctx, &command, UNAVAILABLE_COMMAND).await;
+            return;
+        }
+        if let Err(e) = command.defer(&ctx.http).await {
+            error!("Discord: failed to acknowledge command: {e}");
+            return;
+        }
+
+        let title = format!("discord-{}", command.channel_id.get());
+        let _ = call_blocking(self.app_state.db.clone(), move |db| {
+            db.upsert_chat(channel_id, Some(&title), "discord")
+        })
+        .await;
+        let stored = StoredMessage {
+            id: command.id.get().to_string(),
+            chat_id: channel_id,
+            persona_id,
+            sender_name: command.user.name.clone(),
+            content: text,
+            is_from_bot: false,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let _ = call_blocking(self.app_state.db.clone(), move |db| db.store_message(&stored))
+            .await;
+
+        let result = crate::telegram::process_with_agent(
+            &self.app_state,
+            AgentRequestContext {
+                caller_channel: "discord",
+                chat_id: channel_id,
+                chat_type: if command.guild_id.is_some() {
+                    "group"
+                } else {
+                    "private"
+                },
+                persona_id,
+            },
+            None,
+            None,
+        )
+        .await;
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Error processing Discord command: {e}");
+                let _ = command
+                    .edit_response(&ctx.http, EditInteractionResponse::new().content(format!("Error: {e}")))
+                    .await;
+                return;
+            }
+        };
+        if response.is_empty() {
+            let _ = command
+                .edit_response(&ctx.http, EditInteractionResponse::new().content("Done."))
+                .await;
+            return;
+        }
+        if response.len() <= 2000 {
+            let _ = command
+                .edit_response(&ctx.http, EditInteractionResponse::new().content(response.as_str()))
+                .await;
+        } else {
+            let _ = command
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new().content(format!("/{}:", command.data.name)),
+                )
+                .await;
+            send_discord_response(ctx, command.channel_id, &response).await;
+        }
+        let bot_msg = StoredMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            chat_id: channel_id,
+            persona_id,
+            sender_name: self.app_state.config.bot_username.clone(),
+            content: response,
+            is_from_bot: true,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let _ = call_blocking(self.app_state.db.clone(), move |db| db.store_message(&bot_msg))
+            .await;
+        if let Some(guild_id) = command.guild_id {
+            self.sync_guild_commands(ctx, guild_id).await;
+        }
+    }
 }
 
 #[async_trait]
@@ -29,17 +270,33 @@ impl EventHandler for Handler {
         let channel_id = msg.channel_id.get() as i64;
         let sender_name = msg.author.name.clone();
 
-        // Check allowed channels (empty = all)
-        if !self.app_state.config.discord_allowed_channels.is_empty()
-            && !self
-                .app_state
-                .config
-                .discord_allowed_channels
-                .contains(&(channel_id as u64))
+        // Check allowed channels (empty = all), overridden by the DB list; unknown channels
+        // file an access request
+        let allowed = &self.app_state.config.discord_allowed_channels;
+        let static_allowed = allowed.is_empty() || allowed.contains(&(channel_id as u64));
+        if !crate::access::admit(
+            &self.app_state,
+            "discord",
+            channel_id,
+            static_allowed,
+            Some(&format!("discord-{}", msg.channel_id.get())),
+            &sender_name,
+            &text,
+        )
+        .await
         {
             return;
         }
 
+        if let Some(guild_id) = msg.guild_id {
+            self.guild_channels
+                .lock()
+                .await
+                .entry(guild_id)
+                .or_default()
+                .insert(channel_id);
+        }
+
         // Single entry point: parse slash command first. If command, run backend handler and return — never send to LLM.
         if let Some(cmd) = parse_slash_command(&text) {
             match cmd {
@@ -72,6 +329,93 @@ impl EventHandler for Handler {
                     };
                     let _ = msg.channel_id.say(&ctx.http, &text).await;
                 }
+                SlashCommand::Footer => {
+                    let resp = crate::usage::handle_footer_command(
+                        self.app_state.db.clone(),
+                        channel_id,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Undelete => {
+                    let resp = crate::session_trash::handle_undelete_command(
+                        self.app_state.db.clone(),
+                        channel_id,
+                        &text,
+                        self.app_state.config.deleted_session_retention_days,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Ack => {
+                    let resp = crate::incidents::handle_ack_command(
+                        &self.app_state.config,
+                        self.app_state.db.clone(),
+                        channel_id,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Push => {
+                    let resp = crate::push::handle_push_command(
+                        &self.app_state.config,
+                        self.app_state.db.clone(),
+                        channel_id,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Debate => {
+                    let resp = crate::roundtable::handle_debate_command(
+                        &self.app_state.config,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Thinking => {
+                    let resp = crate::thinking::handle_thinking_command(
+                        &self.app_state.config,
+                        self.app_state.db.clone(),
+                        channel_id,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Memory => {
+                    let resp = crate::memory_commands::handle_memory_command(
+                        self.app_state.db.clone(),
+                        &self.app_state.memory,
+                        channel_id,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::Forget => {
+                    let resp = crate::memory_commands::handle_forget_command(
+                        self.app_state.db.clone(),
+                        &self.app_state.memory,
+                        channel_id,
+                        &text,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &resp).await;
+                }
+                SlashCommand::CatchUp => {
+                    let text = crate::tools::catch_me_up::summarize_catch_up(
+                        self.app_state.db.clone(),
+                        self.app_state.llm.as_ref(),
+                        channel_id,
+                        &sender_name,
+                    )
+                    .await;
+                    let _ = msg.channel_id.say(&ctx.http, &text).await;
+                }
                 SlashCommand::Archive => {
                     let pid = call_blocking(self.app_state.db.clone(), move |db| db.get_current_persona_id(channel_id)).await.unwrap_or(0);
                     if pid == 0 {
@@ -102,6 +446,16 @@ impl EventHandler for Handler {
             return;
         }
 
+        // Custom commands (define_command) typed as text expand into a normal prompt
+        let custom_command = crate::custom_commands::expand_custom_command(
+            self.app_state.db.clone(),
+            channel_id,
+            &text,
+        )
+        .await;
+        let is_custom_command = custom_command.is_some();
+        let text = custom_command.unwrap_or(text);
+
         if text.is_empty() {
             return;
         }
@@ -112,6 +466,31 @@ impl EventHandler for Handler {
             return;
         }
 
+        // Determine if we should respond
+        let should_respond = if is_custom_command {
+            true
+        } else if msg.guild_id.is_some() {
+            // In a guild: only respond to @mentions
+            let cache = &ctx.cache;
+            let bot_id = cache.current_user().id;
+            msg.mentions.iter().any(|u| u.id == bot_id)
+        } else {
+            // DM: respond to all messages
+            true
+        };
+
+        // Translation, moderation and inbound hooks; dropped messages are not stored or answered
+        let inbound = crate::middleware::InboundContext {
+            chat_id: channel_id,
+            chat_type: "discord",
+            sender_name: &sender_name,
+            will_respond: should_respond,
+        };
+        let Some(text) = crate::middleware::run_inbound(&self.app_state, inbound, text).await
+        else {
+            return;
+        };
+
         // Store the chat and message
         let title = format!("discord-{}", msg.channel_id.get());
         let _ = call_blocking(self.app_state.db.clone(), move |db| {
@@ -133,17 +512,6 @@ impl EventHandler for Handler {
         })
         .await;
 
-        // Determine if we should respond
-        let should_respond = if msg.guild_id.is_some() {
-            // In a guild: only respond to @mentions
-            let cache = &ctx.cache;
-            let bot_id = cache.current_user().id;
-            msg.mentions.iter().any(|u| u.id == bot_id)
-        } else {
-            // DM: respond to all messages
-            true
-        };
-
         if !should_respond {
             return;
         }
@@ -155,12 +523,16 @@ impl EventHandler for Handler {
             text.chars().take(100).collect::<String>()
         );
 
-        // Start typing indicator
-        let typing = msg.channel_id.start_typing(&ctx.http);
-
-        // Process with Claude (reuses the same agentic loop as Telegram)
-        match crate::telegram::process_with_agent(
+        let channel = match crate::channel::channel_for_chat_type("discord", &self.app_state.bot) {
+            Ok(channel) => channel,
+            Err(e) => {
+                error!("Discord: {e}");
+                return;
+            }
+        };
+        crate::channel::reply_with_agent(
             &self.app_state,
+            channel,
             AgentRequestContext {
                 caller_channel: "discord",
                 chat_id: channel_id,
@@ -171,36 +543,39 @@ impl EventHandler for Handler {
                 },
                 persona_id,
             },
-            None,
-            None,
         )
-        .await
+        .await;
+
+        // Pick up commands added or removed during this turn
+        if let Some(guild_id) = msg.guild_id {
+            self.sync_guild_commands(&ctx, guild_id).await;
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::Command(command) => self.handle_custom_command(&ctx, command).await,
+            Interaction::Component(component) => self.handle_poll_vote(&ctx, component).await,
+            _ => {}
+        }
+    }
+
+    /// Reactions on the bot's replies count as feedback for the feedback report and A/B
+    /// experiments.
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        let bot_id = ctx.cache.current_user().id;
+        if reaction.user_id == Some(bot_id)
+            || reaction.message_author_id.is_some_and(|author| author != bot_id)
         {
-            Ok(response) => {
-                drop(typing);
-                if !response.is_empty() {
-                    send_discord_response(&ctx, msg.channel_id, &response).await;
-
-                    // Store bot response
-                    let bot_msg = StoredMessage {
-                        id: uuid::Uuid::new_v4().to_string(),
-                        chat_id: channel_id,
-                        persona_id,
-                        sender_name: self.app_state.config.bot_username.clone(),
-                        content: response,
-                        is_from_bot: true,
-                        timestamp: chrono::Utc::now().to_rfc3339(),
-                    };
-                    let _ = call_blocking(self.app_state.db.clone(), move |db| {
-                        db.store_message(&bot_msg)
-                    })
+            return;
+        }
+        if let ReactionType::Unicode(emoji) = &reaction.emoji {
+            let chat_id = reaction.channel_id.get() as i64;
+            crate::feedback_report::record_reaction(self.app_state.db.clone(), chat_id, emoji)
+                .await;
+            if !self.app_state.config.experiments.is_empty() {
+                crate::experiments::record_reaction(self.app_state.db.clone(), chat_id, emoji)
                     .await;
-                }
-            }
-            Err(e) => {
-                drop(typing);
-                error!("Error processing Discord message: {e}");
-                let _ = msg.channel_id.say(&ctx.http, format!("Error: {e}")).await;
             }
         }
     }
@@ -210,40 +585,176 @@ impl EventHandler for Handler {
     }
 }
 
+/// Answer an application command with a message only the invoking user sees.
+async fn reply_ephemeral(ctx: &Context, command: &CommandInteraction, text: &str) {
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(text)
+            .ephemeral(true),
+    );
+    if let Err(e) = command.create_response(&ctx.http, response).await {
+        warn!("Discord: failed to answer command /{}: {e}", command.data.name);
+    }
+}
+
 /// Split and send long messages (Discord limit is 2000 chars).
 async fn send_discord_response(ctx: &Context, channel_id: ChannelId, text: &str) {
-    const MAX_LEN: usize = 2000;
-
-    if text.len() <= MAX_LEN {
-        let _ = channel_id.say(&ctx.http, text).await;
-        return;
+    for chunk in split_text(text, DISCORD_MAX_LEN) {
+        let _ = channel_id.say(&ctx.http, chunk).await;
     }
+}
 
-    let mut remaining = text;
-    while !remaining.is_empty() {
-        let chunk_len = if remaining.len() <= MAX_LEN {
-            remaining.len()
-        } else {
-            remaining[..MAX_LEN].rfind('\n').unwrap_or(MAX_LEN)
-        };
+const DISCORD_API: &str = "https://discord.com/api/v10";
+const DISCORD_MAX_LEN: usize = 2000;
 
-        let chunk = &remaining[..chunk_len];
-        let _ = channel_id.say(&ctx.http, chunk).await;
-        remaining = &remaining[chunk_len..];
+/// Discord as a [`Channel`], over the REST API so it can send without the gateway connection.
+pub struct DiscordChannel {
+    token: String,
+    http_client: reqwest::Client,
+}
 
-        if remaining.starts_with('\n') {
-            remaining = &remaining[1..];
+impl DiscordChannel {
+    pub fn new(token: String) -> Self {
+        DiscordChannel {
+            token,
+            http_client: reqwest::Client::new(),
         }
     }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .discord_bot_token
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| DiscordChannel::new(t.to_string()))
+    }
+
+    fn request(&self, method: reqwest::Method, url: reqwest::Url) -> reqwest::RequestBuilder {
+        let auth = format!("Bot {}", self.token);
+        self.http_client
+            .request(method, url)
+            .header(reqwest::header::AUTHORIZATION, auth)
+    }
+
+    fn url(&self, segments: &[&str]) -> Result<reqwest::Url, String> {
+        let mut url = reqwest::Url::parse(DISCORD_API).map_err(|e| e.to_string())?;
+        url.path_segments_mut()
+            .map_err(|_| "invalid Discord API URL".to_string())?
+            .extend(segments);
+        Ok(url)
+    }
+}
+
+async fn check_discord_response(
+    resp: Result<reqwest::Response, reqwest::Error>,
+    action: &str,
+) -> Result<(), String> {
+    let resp = resp.map_err(|e| format!("Failed to {action}: {e}"))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to {action}: HTTP {status} {}",
+            body.chars().take(300).collect::<String>()
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Channel for DiscordChannel {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn max_message_len(&self) -> usize {
+        DISCORD_MAX_LEN
+    }
+
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String> {
+        let url = self.url(&["channels", &chat_id.to_string(), "messages"])?;
+        let resp = self
+            .request(reqwest::Method::POST, url)
+            .json(&serde_json::json!({ "content": chunk }))
+            .send()
+            .await;
+        check_discord_response(resp, "send Discord message").await
+    }
+
+    async fn send_attachment(
+        &self,
+        chat_id: i64,
+        path: &Path,
+        caption: Option<&str>,
+    ) -> Result<(), String> {
+        let filename = path
+            .file_name()
+            .and_then(|v| v.to_str())
+            .unwrap_or("attachment.bin")
+            .to_string();
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| format!("Failed to read attachment file: {e}"))?;
+
+        let payload = serde_json::json!({ "content": caption.unwrap_or_default() });
+        let form = reqwest::multipart::Form::new()
+            .text("payload_json", payload.to_string())
+            .part(
+                "files[0]",
+                reqwest::multipart::Part::bytes(bytes).file_name(filename),
+            );
+        let url = self.url(&["channels", &chat_id.to_string(), "messages"])?;
+        let resp = self
+            .request(reqwest::Method::POST, url)
+            .multipart(form)
+            .send()
+            .await;
+        check_discord_response(resp, "send Discord attachment").await
+    }
+
+    async fn send_typing(&self, chat_id: i64) -> Result<(), String> {
+        let url = self.url(&["channels", &chat_id.to_string(), "typing"])?;
+        let resp = self
+            .request(reqwest::Method::POST, url)
+            .header(reqwest::header::CONTENT_LENGTH, "0")
+            .send()
+            .await;
+        check_discord_response(resp, "send Discord typing indicator").await
+    }
+
+    async fn react(&self, chat_id: i64, message_id: &str, emoji: &str) -> Result<(), String> {
+        let chat = chat_id.to_string();
+        let url = self.url(&[
+            "channels",
+            &chat,
+            "messages",
+            message_id,
+            "reactions",
+            emoji,
+            "@me",
+        ])?;
+        let resp = self
+            .request(reqwest::Method::PUT, url)
+            .header(reqwest::header::CONTENT_LENGTH, "0")
+            .send()
+            .await;
+        check_discord_response(resp, "add Discord reaction").await
+    }
 }
 
 /// Start the Discord bot. Called from run_bot() if discord_bot_token is configured.
 pub async fn start_discord_bot(app_state: Arc<AppState>, token: &str) {
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
-        | GatewayIntents::MESSAGE_CONTENT;
+        | GatewayIntents::MESSAGE_CONTENT
+        | GatewayIntents::GUILD_MESSAGE_REACTIONS
+        | GatewayIntents::DIRECT_MESSAGE_REACTIONS;
 
-    let handler = Handler { app_state };
+    let handler = Handler {
+        app_state,
+        guild_channels: Mutex::new(HashMap::new()),
+        registered_commands: Mutex::new(HashMap::new()),
+    };
 
     let mut client = match Client::builder(token, intents).event_handler(handler).await {
         Ok(c) => c,
@@ -1,3 +1,8 @@
 pub mod discord;
+pub mod signal;
+pub mod slack;
+pub mod teams;
 pub mod telegram;
+pub mod telegram_business;
 pub mod whatsapp;
+pub mod wyoming;
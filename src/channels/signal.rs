@@ -0,0 +1,657 @@
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::channel::{split_text, Channel};
+use crate::claude::Message as ClaudeMessage;
+use crate::config::{Config, SignalConfig};
+use crate::db::call_blocking;
+use crate::db::{Database, StoredMessage};
+use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
+use crate::telegram::{archive_conversation, AgentRequestContext, AppState};
+
+/// Long Signal messages are turned into attachments by clients; keep replies below that.
+const MAX_MESSAGE_LEN: usize = 2000;
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+/// Placeholder signal-cli puts in the text where a mention was.
+const MENTION_PLACEHOLDER: char = '\u{FFFC}';
+/// Chat setting holding the Signal address of a chat (chat ids are hashes of it).
+const ADDRESS_SETTING: &str = "signal_address";
+
+// --- signal-cli JSON-RPC notification types ---
+
+#[derive(Debug, Deserialize)]
+struct Notification {
+    method: Option<String>,
+    params: Option<ReceiveParams>,
+    /// Set on responses to our own requests.
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReceiveParams {
+    envelope: Envelope,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    source_number: Option<String>,
+    source_uuid: Option<String>,
+    source_name: Option<String>,
+    timestamp: Option<i64>,
+    data_message: Option<DataMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataMessage {
+    message: Option<String>,
+    #[serde(default)]
+    mentions: Vec<Mention>,
+    group_info: Option<GroupInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mention {
+    number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupInfo {
+    group_id: String,
+}
+
+/// Where a message came from and where the reply goes.
+#[derive(Debug, Clone, PartialEq)]
+enum Conversation {
+    /// Direct message; the sender's number (or UUID when the number is hidden).
+    Direct(String),
+    /// Group, by its base64 group id.
+    Group(String),
+}
+
+impl Conversation {
+    /// `direct:<address>` or `group:<group id>`, as stored in [`ADDRESS_SETTING`].
+    fn to_setting(&self) -> String {
+        match self {
+            Conversation::Direct(address) => format!("direct:{address}"),
+            Conversation::Group(group_id) => format!("group:{group_id}"),
+        }
+    }
+
+    fn from_setting(value: &str) -> Option<Self> {
+        match value.split_once(':')? {
+            ("direct", address) => Some(Conversation::Direct(address.to_string())),
+            ("group", group_id) => Some(Conversation::Group(group_id.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// An incoming user message worth handling.
+#[derive(Debug, PartialEq)]
+struct IncomingMessage {
+    conversation: Conversation,
+    sender: String,
+    text: String,
+    timestamp: i64,
+    /// The bot's account was @mentioned.
+    mentioned: bool,
+}
+
+/// Parse one line of signal-cli output into a user message. Receipts, typing notices, sync
+/// messages and responses to our own requests yield `None`.
+fn parse_line(raw: &str, account: &str) -> Option<IncomingMessage> {
+    let notification: Notification = serde_json::from_str(raw).ok()?;
+    if let Some(err) = notification.error {
+        warn!("signal-cli request failed: {err}");
+        return None;
+    }
+    if notification.method.as_deref() != Some("receive") {
+        return None;
+    }
+    let envelope = notification.params?.envelope;
+    let data = envelope.data_message?;
+    let address = envelope
+        .source_number
+        .clone()
+        .or(envelope.source_uuid.clone())?;
+    let mentioned = data
+        .mentions
+        .iter()
+        .any(|m| m.number.as_deref() == Some(account));
+    let text = data
+        .message?
+        .replace(MENTION_PLACEHOLDER, " ")
+        .trim()
+        .to_string();
+    Some(IncomingMessage {
+        conversation: match data.group_info {
+            Some(group) => Conversation::Group(group.group_id),
+            None => Conversation::Direct(address.clone()),
+        },
+        sender: envelope
+            .source_name
+            .filter(|n| !n.is_empty())
+            .unwrap_or(address),
+        text,
+        timestamp: envelope.timestamp.unwrap_or_default(),
+        mentioned,
+    })
+}
+
+/// Signal addresses (numbers, UUIDs, group ids) are strings; map them to a stable chat id with
+/// 64-bit FNV-1a. Groups are negative, as in Telegram.
+fn chat_id_for(conversation: &Conversation) -> i64 {
+    let key = match conversation {
+        Conversation::Direct(a) | Conversation::Group(a) => a,
+    };
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let id = (hash >> 1) as i64;
+    match conversation {
+        Conversation::Direct(_) => id.max(1),
+        Conversation::Group(_) => -id.max(1),
+    }
+}
+
+// --- Shared state for the signal-cli process ---
+
+struct SignalState {
+    app_state: Arc<AppState>,
+    account: String,
+    stdin: Mutex<ChildStdin>,
+    next_id: AtomicU64,
+}
+
+/// JSON-RPC `send` request for one chunk of text.
+fn send_request(id: u64, conversation: &Conversation, text: &str) -> serde_json::Value {
+    let mut params = serde_json::json!({ "message": text });
+    match conversation {
+        Conversation::Direct(address) => params["recipient"] = serde_json::json!([address]),
+        Conversation::Group(group_id) => params["groupId"] = serde_json::json!(group_id),
+    }
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "send",
+        "params": params,
+        "id": id,
+    })
+}
+
+/// The signal-cli process currently running, for messages sent outside a reply.
+fn running() -> &'static RwLock<Option<Arc<SignalState>>> {
+    static RUNNING: OnceLock<RwLock<Option<Arc<SignalState>>>> = OnceLock::new();
+    RUNNING.get_or_init(|| RwLock::new(None))
+}
+
+// --- Send message via the JSON-RPC `send` method ---
+
+/// Write one `send` request for a chunk that fits [`MAX_MESSAGE_LEN`].
+async fn send_chunk(
+    state: &SignalState,
+    conversation: &Conversation,
+    chunk: &str,
+) -> Result<(), String> {
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let mut line = send_request(id, conversation, chunk).to_string();
+    line.push('\n');
+    let mut stdin = state.stdin.lock().await;
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send Signal message: {e}"))?;
+    let _ = stdin.flush().await;
+    Ok(())
+}
+
+async fn send_signal_message(state: &SignalState, conversation: &Conversation, text: &str) {
+    for chunk in split_text(text, MAX_MESSAGE_LEN)
+        .into_iter()
+        .filter(|c| !c.is_empty())
+    {
+        if let Err(e) = send_chunk(state, conversation, &chunk).await {
+            error!("{e}");
+            return;
+        }
+    }
+}
+
+/// Signal as a [`Channel`], through the running signal-cli process. Chats are addressed by the
+/// address recorded when they last wrote.
+pub struct SignalChannel {
+    db: Arc<Database>,
+}
+
+impl SignalChannel {
+    pub fn from_config(config: &Config, db: Arc<Database>) -> Option<Self> {
+        config.signal.as_ref().map(|_| SignalChannel { db })
+    }
+}
+
+#[async_trait]
+impl Channel for SignalChannel {
+    fn name(&self) -> &'static str {
+        "signal"
+    }
+
+    fn max_message_len(&self) -> usize {
+        MAX_MESSAGE_LEN
+    }
+
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String> {
+        let address = call_blocking(self.db.clone(), move |d| {
+            d.get_chat_setting(chat_id, ADDRESS_SETTING)
+        })
+        .await
+        .map_err(|e| format!("Failed to read Signal address: {e}"))?;
+        let conversation = address
+            .as_deref()
+            .and_then(Conversation::from_setting)
+            .ok_or_else(|| format!("No Signal address known for chat {chat_id}"))?;
+        let state = running()
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "signal-cli is not running".to_string())?;
+        send_chunk(&state, &conversation, chunk).await
+    }
+}
+
+/// Run a slash command and return its reply.
+async fn run_slash_command(
+    state: &SignalState,
+    cmd: SlashCommand,
+    chat_id: i64,
+    sender_name: &str,
+    text: &str,
+) -> String {
+    let app = &state.app_state;
+    match cmd {
+        SlashCommand::Reset => {
+            let pid = call_blocking(app.db.clone(), move |db| db.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+            if pid > 0 {
+                let _ =
+                    call_blocking(app.db.clone(), move |db| db.delete_session(chat_id, pid)).await;
+            }
+            "Conversation cleared. Principles and per-persona memory are unchanged.".into()
+        }
+        SlashCommand::Skills => app.skills.list_skills_formatted(),
+        SlashCommand::Persona => {
+            crate::persona::handle_persona_command(
+                app.db.clone(),
+                chat_id,
+                text.trim(),
+                Some(&app.config),
+            )
+            .await
+        }
+        SlashCommand::Schedule => {
+            match call_blocking(app.db.clone(), |db| {
+                db.get_all_scheduled_tasks_for_display()
+            })
+            .await
+            {
+                Ok(t) => crate::tools::schedule::format_tasks_list_all(&t),
+                Err(e) => format!("Error listing tasks: {e}"),
+            }
+        }
+        SlashCommand::Footer => {
+            crate::usage::handle_footer_command(app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Undelete => {
+            crate::session_trash::handle_undelete_command(
+                app.db.clone(),
+                chat_id,
+                text,
+                app.config.deleted_session_retention_days,
+            )
+            .await
+        }
+        SlashCommand::Ack => {
+            crate::incidents::handle_ack_command(&app.config, app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Push => {
+            crate::push::handle_push_command(&app.config, app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Debate => crate::roundtable::handle_debate_command(&app.config, text).await,
+        SlashCommand::Thinking => {
+            crate::thinking::handle_thinking_command(&app.config, app.db.clone(), chat_id, text)
+                .await
+        }
+        SlashCommand::Memory => {
+            crate::memory_commands::handle_memory_command(
+                app.db.clone(),
+                &app.memory,
+                chat_id,
+                text,
+            )
+            .await
+        }
+        SlashCommand::Forget => {
+            crate::memory_commands::handle_forget_command(
+                app.db.clone(),
+                &app.memory,
+                chat_id,
+                text,
+            )
+            .await
+        }
+        SlashCommand::CatchUp => {
+            crate::tools::catch_me_up::summarize_catch_up(
+                app.db.clone(),
+                app.llm.as_ref(),
+                chat_id,
+                sender_name,
+            )
+            .await
+        }
+        SlashCommand::Archive => {
+            let pid = call_blocking(app.db.clone(), move |db| db.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+            if pid == 0 {
+                return "No session to archive.".into();
+            }
+            let Ok(Some((json, _))) =
+                call_blocking(app.db.clone(), move |db| db.load_session(chat_id, pid)).await
+            else {
+                return "No session to archive.".into();
+            };
+            let messages: Vec<ClaudeMessage> = serde_json::from_str(&json).unwrap_or_default();
+            if messages.is_empty() {
+                return "No session to archive.".into();
+            }
+            archive_conversation(&app.config.runtime_data_dir(), chat_id, &messages);
+            format!("Archived {} messages.", messages.len())
+        }
+    }
+}
+
+async fn handle_message(state: &SignalState, signal: &SignalConfig, msg: IncomingMessage) {
+    let config = &state.app_state.config;
+    let chat_id = chat_id_for(&msg.conversation);
+    let (is_group, title) = match &msg.conversation {
+        Conversation::Group(group_id) => (true, format!("signal-group-{group_id}")),
+        Conversation::Direct(address) => (false, format!("signal-{address}")),
+    };
+    // Check allowed groups (empty = all), overridden by the DB list; unknown groups file an
+    // access request
+    let static_allowed = match &msg.conversation {
+        Conversation::Group(group_id) => {
+            signal.allowed_groups.is_empty() || signal.allowed_groups.contains(group_id)
+        }
+        Conversation::Direct(_) => true,
+    };
+    if !crate::access::admit(
+        &state.app_state,
+        "signal",
+        chat_id,
+        static_allowed,
+        Some(&title),
+        &msg.sender,
+        &msg.text,
+    )
+    .await
+    {
+        return;
+    }
+    let handle = format!("@{}", config.bot_username.to_lowercase());
+    let mentioned = msg.mentioned || msg.text.to_lowercase().contains(&handle);
+    let text = msg.text.clone();
+    let sender_name = msg.sender.clone();
+
+    // Single entry point: parse slash command first. If command, run backend handler and return — never send to LLM.
+    if let Some(cmd) = parse_slash_command(&text) {
+        if !is_group || mentioned {
+            let reply = run_slash_command(state, cmd, chat_id, &sender_name, &text).await;
+            send_signal_message(state, &msg.conversation, &reply).await;
+        }
+        return;
+    }
+
+    // Custom commands (define_command) typed as text expand into a normal prompt
+    let custom_command =
+        crate::custom_commands::expand_custom_command(state.app_state.db.clone(), chat_id, &text)
+            .await;
+    let is_custom_command = custom_command.is_some();
+    let text = custom_command.unwrap_or(text);
+
+    if text.is_empty() {
+        return;
+    }
+
+    // Resolve persona
+    let persona_id = call_blocking(state.app_state.db.clone(), move |db| {
+        db.get_current_persona_id(chat_id)
+    })
+    .await
+    .unwrap_or(0);
+    if persona_id == 0 {
+        return;
+    }
+
+    // In groups only respond to @mentions; direct messages get a reply to everything
+    let will_respond = is_custom_command || !is_group || mentioned;
+
+    // Translation, moderation and inbound hooks; dropped messages are not stored or answered
+    let ctx = crate::middleware::InboundContext {
+        chat_id,
+        chat_type: "signal",
+        sender_name: &sender_name,
+        will_respond,
+    };
+    let Some(text) = crate::middleware::run_inbound(&state.app_state, ctx, text).await else {
+        return;
+    };
+
+    // Store the chat (and its address, for scheduled and proactive messages) and message
+    let address = msg.conversation.to_setting();
+    let _ = call_blocking(state.app_state.db.clone(), move |db| {
+        db.upsert_chat(chat_id, Some(&title), "signal")?;
+        db.set_chat_setting(chat_id, ADDRESS_SETTING, Some(&address))
+    })
+    .await;
+    let stored = StoredMessage {
+        id: format!("signal-{}-{}", chat_id, msg.timestamp),
+        chat_id,
+        persona_id,
+        sender_name: sender_name.clone(),
+        content: text.clone(),
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(state.app_state.db.clone(), move |db| {
+        db.store_message(&stored)
+    })
+    .await;
+
+    if !will_respond {
+        return;
+    }
+
+    info!(
+        "Signal message from {} in chat {}: {}",
+        sender_name,
+        chat_id,
+        text.chars().take(100).collect::<String>()
+    );
+
+    // Process with Claude (reuses the same agentic loop as Telegram)
+    match crate::telegram::process_with_agent(
+        &state.app_state,
+        AgentRequestContext {
+            caller_channel: "signal",
+            chat_id,
+            chat_type: if is_group { "group" } else { "private" },
+            persona_id,
+        },
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(response) => {
+            if !response.is_empty() {
+                let delivery_started = std::time::Instant::now();
+                send_signal_message(state, &msg.conversation, &response).await;
+                crate::turn_metrics::record_delivery(
+                    state.app_state.db.clone(),
+                    chat_id,
+                    delivery_started,
+                )
+                .await;
+
+                // Store bot response
+                let bot_msg = StoredMessage {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    chat_id,
+                    persona_id,
+                    sender_name: config.bot_username.clone(),
+                    content: response,
+                    is_from_bot: true,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                let _ = call_blocking(state.app_state.db.clone(), move |db| {
+                    db.store_message(&bot_msg)
+                })
+                .await;
+            }
+        }
+        Err(e) => {
+            error!("Error processing Signal message: {e}");
+            send_signal_message(state, &msg.conversation, &format!("Error: {e}")).await;
+        }
+    }
+}
+
+/// Run `signal-cli jsonRpc` and handle messages until the process exits.
+async fn run_signal_cli(
+    app_state: &Arc<AppState>,
+    signal: &Arc<SignalConfig>,
+) -> anyhow::Result<()> {
+    let mut child = Command::new(&signal.signal_cli_path)
+        .args(["-a", &signal.signal_account, "jsonRpc"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("signal-cli stdin unavailable"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("signal-cli stdout unavailable"))?;
+    let state = Arc::new(SignalState {
+        app_state: app_state.clone(),
+        account: signal.signal_account.clone(),
+        stdin: Mutex::new(stdin),
+        next_id: AtomicU64::new(1),
+    });
+    info!("signal-cli started for {}", state.account);
+    *running().write().unwrap() = Some(state.clone());
+
+    let mut lines = BufReader::new(stdout).lines();
+    let read = loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                let Some(message) = parse_line(&line, &state.account) else {
+                    continue;
+                };
+                let (state, signal) = (state.clone(), signal.clone());
+                tokio::spawn(async move { handle_message(&state, &signal, message).await });
+            }
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+    running().write().unwrap().take();
+    read?;
+    let status = child.wait().await?;
+    anyhow::bail!("signal-cli exited ({status})")
+}
+
+/// Start the Signal bot. Called from run_bot() if a `signal` section is configured.
+pub async fn start_signal_bot(app_state: Arc<AppState>, signal: SignalConfig) {
+    let signal = Arc::new(signal);
+    info!("Starting Signal bot...");
+    loop {
+        if let Err(e) = run_signal_cli(&app_state, &signal).await {
+            error!("Signal error: {e}");
+        }
+        tokio::time::sleep(RESTART_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let raw = r#"{"jsonrpc":"2.0","method":"receive","params":{"envelope":{"source":"+111","sourceNumber":"+111","sourceUuid":"u1","sourceName":"Alice","timestamp":42,"dataMessage":{"timestamp":42,"message":"￼ what's up","mentions":[{"number":"+999","uuid":"bot","start":0,"length":1}],"groupInfo":{"groupId":"Zm9v","type":"DELIVER"}}},"account":"+999"}}"#;
+        let msg = parse_line(raw, "+999").unwrap();
+        assert_eq!(msg.conversation, Conversation::Group("Zm9v".into()));
+        assert_eq!(
+            (msg.sender.as_str(), msg.text.as_str()),
+            ("Alice", "what's up")
+        );
+        assert!(msg.mentioned);
+        assert_eq!(msg.timestamp, 42);
+
+        let direct = r#"{"jsonrpc":"2.0","method":"receive","params":{"envelope":{"sourceUuid":"u2","timestamp":1,"dataMessage":{"message":"hi"}}}}"#;
+        let msg = parse_line(direct, "+999").unwrap();
+        assert_eq!(msg.conversation, Conversation::Direct("u2".into()));
+        assert_eq!(msg.sender, "u2");
+        assert!(!msg.mentioned);
+
+        let receipt = r#"{"jsonrpc":"2.0","method":"receive","params":{"envelope":{"sourceNumber":"+111","receiptMessage":{"isRead":true}}}}"#;
+        assert!(parse_line(receipt, "+999").is_none());
+        assert!(parse_line(r#"{"jsonrpc":"2.0","result":{},"id":1}"#, "+999").is_none());
+    }
+
+    #[test]
+    fn test_chat_ids_and_send_request() {
+        let group = Conversation::Group("Zm9v".into());
+        let direct = Conversation::Direct("+111".into());
+        assert!(chat_id_for(&group) < 0);
+        assert!(chat_id_for(&direct) > 0);
+        assert_eq!(
+            chat_id_for(&group),
+            chat_id_for(&Conversation::Group("Zm9v".into()))
+        );
+
+        let req = send_request(3, &group, "hello");
+        assert_eq!(req["params"]["groupId"], "Zm9v");
+        assert_eq!(req["params"]["message"], "hello");
+        let req = send_request(4, &direct, "hi");
+        assert_eq!(req["params"]["recipient"], serde_json::json!(["+111"]));
+        assert_eq!(req["id"], 4);
+
+        for conversation in [group, direct] {
+            assert_eq!(
+                Conversation::from_setting(&conversation.to_setting()),
+                Some(conversation)
+            );
+        }
+        assert_eq!(Conversation::from_setting("bogus"), None);
+    }
+}
@@ -0,0 +1,576 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tracing::{error, info, warn};
+
+use crate::channel::{split_text, Channel};
+use crate::claude::Message as ClaudeMessage;
+use crate::config::Config;
+use crate::db::call_blocking;
+use crate::db::StoredMessage;
+use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
+use crate::telegram::{archive_conversation, AgentRequestContext, AppState};
+
+const SLACK_API: &str = "https://slack.com/api";
+/// Slack recommends keeping messages well under its 40k hard limit.
+const MAX_MESSAGE_LEN: usize = 4000;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// --- Socket Mode envelope types ---
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(rename = "type")]
+    kind: String,
+    envelope_id: Option<String>,
+    payload: Option<EventsApiPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsApiPayload {
+    event: Option<SlackEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    subtype: Option<String>,
+    bot_id: Option<String>,
+    user: Option<String>,
+    channel: Option<String>,
+    channel_type: Option<String>,
+    #[serde(default)]
+    text: String,
+    ts: Option<String>,
+}
+
+/// An incoming user message worth handling.
+#[derive(Debug, PartialEq)]
+struct IncomingMessage {
+    channel: String,
+    user: String,
+    text: String,
+    ts: String,
+    is_dm: bool,
+    /// `app_mention` event: the bot was addressed explicitly.
+    mentioned: bool,
+}
+
+/// Parse a Socket Mode frame: the envelope id to acknowledge (if any) and the user message it
+/// carries (if any). Bot messages, edits and other subtypes are dropped.
+fn parse_envelope(raw: &str) -> Option<(Option<String>, Option<IncomingMessage>)> {
+    let envelope: Envelope = serde_json::from_str(raw).ok()?;
+    let message = if envelope.kind == "events_api" {
+        envelope.payload.and_then(|p| p.event).and_then(|event| {
+            if !matches!(event.kind.as_str(), "message" | "app_mention")
+                || event.subtype.is_some()
+                || event.bot_id.is_some()
+            {
+                return None;
+            }
+            Some(IncomingMessage {
+                channel: event.channel?,
+                user: event.user?,
+                text: event.text,
+                ts: event.ts?,
+                is_dm: event.channel_type.as_deref() == Some("im"),
+                mentioned: event.kind == "app_mention",
+            })
+        })
+    } else {
+        None
+    };
+    Some((envelope.envelope_id, message))
+}
+
+/// Slack channel IDs are uppercase base-36 strings (e.g. `C024BE91L`), so they map to a stable,
+/// reversible numeric chat id.
+pub fn chat_id_for_channel(channel: &str) -> Option<i64> {
+    i64::from_str_radix(channel, 36).ok().filter(|id| *id > 0)
+}
+
+/// Inverse of [`chat_id_for_channel`].
+pub fn channel_for_chat_id(chat_id: i64) -> String {
+    let mut n = chat_id.unsigned_abs();
+    let mut digits = Vec::new();
+    while n > 0 {
+        digits.push(std::char::from_digit((n % 36) as u32, 36).unwrap_or('0'));
+        n /= 36;
+    }
+    digits.iter().rev().collect::<String>().to_ascii_uppercase()
+}
+
+/// Whether `text` mentions the bot user, and the text with those mentions removed.
+fn strip_mention(text: &str, bot_user_id: &str) -> (bool, String) {
+    let tag = format!("<@{bot_user_id}>");
+    if !text.contains(&tag) {
+        return (false, text.to_string());
+    }
+    (true, text.replace(&tag, " ").trim().to_string())
+}
+
+// --- Shared state for the Slack client ---
+
+struct SlackState {
+    app_state: Arc<AppState>,
+    bot_token: String,
+    app_token: String,
+    bot_user_id: String,
+    http_client: reqwest::Client,
+}
+
+/// Call a Web API method and return the response body, failing on `"ok": false`.
+async fn api_call(
+    client: &reqwest::Client,
+    token: &str,
+    method: &str,
+    body: &serde_json::Value,
+) -> anyhow::Result<serde_json::Value> {
+    let resp: serde_json::Value = client
+        .post(format!("{SLACK_API}/{method}"))
+        .bearer_auth(token)
+        .json(body)
+        .send()
+        .await?
+        .json()
+        .await?;
+    if resp.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+        let err = resp
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown error");
+        anyhow::bail!("Slack {method} failed: {err}");
+    }
+    Ok(resp)
+}
+
+// --- Send message via chat.postMessage ---
+
+async fn send_slack_message(state: &SlackState, channel: &str, text: &str) {
+    for chunk in split_text(text, MAX_MESSAGE_LEN)
+        .into_iter()
+        .filter(|c| !c.is_empty())
+    {
+        let body = serde_json::json!({ "channel": channel, "text": chunk });
+        if let Err(e) = api_call(
+            &state.http_client,
+            &state.bot_token,
+            "chat.postMessage",
+            &body,
+        )
+        .await
+        {
+            error!("Failed to send Slack message: {e}");
+        }
+    }
+}
+
+/// Slack as a [`Channel`]; chat ids map back to channel ids with [`channel_for_chat_id`].
+pub struct SlackChannel {
+    bot_token: String,
+    http_client: reqwest::Client,
+}
+
+impl SlackChannel {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .slack_bot_token
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| SlackChannel {
+                bot_token: t.to_string(),
+                http_client: reqwest::Client::new(),
+            })
+    }
+}
+
+#[async_trait]
+impl Channel for SlackChannel {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn max_message_len(&self) -> usize {
+        MAX_MESSAGE_LEN
+    }
+
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String> {
+        let body = serde_json::json!({ "channel": channel_for_chat_id(chat_id), "text": chunk });
+        api_call(
+            &self.http_client,
+            &self.bot_token,
+            "chat.postMessage",
+            &body,
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Run a slash command and return its reply.
+async fn run_slash_command(
+    state: &SlackState,
+    cmd: SlashCommand,
+    chat_id: i64,
+    sender_name: &str,
+    text: &str,
+) -> String {
+    let app = &state.app_state;
+    match cmd {
+        SlashCommand::Reset => {
+            let pid = call_blocking(app.db.clone(), move |db| db.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+            if pid > 0 {
+                let _ =
+                    call_blocking(app.db.clone(), move |db| db.delete_session(chat_id, pid)).await;
+            }
+            "Conversation cleared. Principles and per-persona memory are unchanged.".into()
+        }
+        SlashCommand::Skills => app.skills.list_skills_formatted(),
+        SlashCommand::Persona => {
+            crate::persona::handle_persona_command(
+                app.db.clone(),
+                chat_id,
+                text.trim(),
+                Some(&app.config),
+            )
+            .await
+        }
+        SlashCommand::Schedule => {
+            match call_blocking(app.db.clone(), |db| {
+                db.get_all_scheduled_tasks_for_display()
+            })
+            .await
+            {
+                Ok(t) => crate::tools::schedule::format_tasks_list_all(&t),
+                Err(e) => format!("Error listing tasks: {e}"),
+            }
+        }
+        SlashCommand::Footer => {
+            crate::usage::handle_footer_command(app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Undelete => {
+            crate::session_trash::handle_undelete_command(
+                app.db.clone(),
+                chat_id,
+                text,
+                app.config.deleted_session_retention_days,
+            )
+            .await
+        }
+        SlashCommand::Ack => {
+            crate::incidents::handle_ack_command(&app.config, app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Push => {
+            crate::push::handle_push_command(&app.config, app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Debate => crate::roundtable::handle_debate_command(&app.config, text).await,
+        SlashCommand::Thinking => {
+            crate::thinking::handle_thinking_command(&app.config, app.db.clone(), chat_id, text)
+                .await
+        }
+        SlashCommand::Memory => {
+            crate::memory_commands::handle_memory_command(
+                app.db.clone(),
+                &app.memory,
+                chat_id,
+                text,
+            )
+            .await
+        }
+        SlashCommand::Forget => {
+            crate::memory_commands::handle_forget_command(
+                app.db.clone(),
+                &app.memory,
+                chat_id,
+                text,
+            )
+            .await
+        }
+        SlashCommand::CatchUp => {
+            crate::tools::catch_me_up::summarize_catch_up(
+                app.db.clone(),
+                app.llm.as_ref(),
+                chat_id,
+                sender_name,
+            )
+            .await
+        }
+        SlashCommand::Archive => {
+            let pid = call_blocking(app.db.clone(), move |db| db.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+            if pid == 0 {
+                return "No session to archive.".into();
+            }
+            let Ok(Some((json, _))) =
+                call_blocking(app.db.clone(), move |db| db.load_session(chat_id, pid)).await
+            else {
+                return "No session to archive.".into();
+            };
+            let messages: Vec<ClaudeMessage> = serde_json::from_str(&json).unwrap_or_default();
+            if messages.is_empty() {
+                return "No session to archive.".into();
+            }
+            archive_conversation(&app.config.runtime_data_dir(), chat_id, &messages);
+            format!("Archived {} messages.", messages.len())
+        }
+    }
+}
+
+async fn handle_message(state: &SlackState, msg: IncomingMessage) {
+    let config = &state.app_state.config;
+    let Some(chat_id) = chat_id_for_channel(&msg.channel) else {
+        warn!("Slack: unexpected channel id {}", msg.channel);
+        return;
+    };
+    // Check allowed channels (empty = all), overridden by the DB list; unknown channels file
+    // an access request
+    let static_allowed = config.slack_allowed_channels.is_empty()
+        || config.slack_allowed_channels.contains(&msg.channel);
+    if !crate::access::admit(
+        &state.app_state,
+        "slack",
+        chat_id,
+        static_allowed,
+        Some(&format!("slack-{}", msg.channel)),
+        &msg.user,
+        &msg.text,
+    )
+    .await
+    {
+        return;
+    }
+    let (mentioned, text) = strip_mention(&msg.text, &state.bot_user_id);
+    let mentioned = mentioned || msg.mentioned;
+    // In channels Slack sends both `message` and `app_mention` for a mention; answer the latter only
+    if !msg.is_dm && mentioned && !msg.mentioned {
+        return;
+    }
+    let sender_name = msg.user.clone();
+
+    // Single entry point: parse slash command first. If command, run backend handler and return — never send to LLM.
+    if let Some(cmd) = parse_slash_command(&text) {
+        if msg.is_dm || mentioned {
+            let reply = run_slash_command(state, cmd, chat_id, &sender_name, &text).await;
+            send_slack_message(state, &msg.channel, &reply).await;
+        }
+        return;
+    }
+
+    // Custom commands (define_command) typed as text expand into a normal prompt
+    let custom_command =
+        crate::custom_commands::expand_custom_command(state.app_state.db.clone(), chat_id, &text)
+            .await;
+    let is_custom_command = custom_command.is_some();
+    let text = custom_command.unwrap_or(text);
+
+    if text.is_empty() {
+        return;
+    }
+
+    // Resolve persona
+    let persona_id = call_blocking(state.app_state.db.clone(), move |db| {
+        db.get_current_persona_id(chat_id)
+    })
+    .await
+    .unwrap_or(0);
+    if persona_id == 0 {
+        return;
+    }
+
+    // In channels only respond to @mentions; DMs get a reply to everything
+    let will_respond = is_custom_command || msg.is_dm || mentioned;
+
+    // Translation, moderation and inbound hooks; dropped messages are not stored or answered
+    let ctx = crate::middleware::InboundContext {
+        chat_id,
+        chat_type: "slack",
+        sender_name: &sender_name,
+        will_respond,
+    };
+    let Some(text) = crate::middleware::run_inbound(&state.app_state, ctx, text).await else {
+        return;
+    };
+
+    // Store the chat and message
+    let title = format!("slack-{}", msg.channel);
+    let _ = call_blocking(state.app_state.db.clone(), move |db| {
+        db.upsert_chat(chat_id, Some(&title), "slack")
+    })
+    .await;
+    let stored = StoredMessage {
+        id: msg.ts.clone(),
+        chat_id,
+        persona_id,
+        sender_name: sender_name.clone(),
+        content: text.clone(),
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(state.app_state.db.clone(), move |db| {
+        db.store_message(&stored)
+    })
+    .await;
+
+    if !will_respond {
+        return;
+    }
+
+    info!(
+        "Slack message from {} in channel {}: {}",
+        sender_name,
+        msg.channel,
+        text.chars().take(100).collect::<String>()
+    );
+
+    let channel: Arc<dyn Channel> = Arc::new(SlackChannel {
+        bot_token: state.bot_token.clone(),
+        http_client: state.http_client.clone(),
+    });
+    crate::channel::reply_with_agent(
+        &state.app_state,
+        channel,
+        AgentRequestContext {
+            caller_channel: "slack",
+            chat_id,
+            chat_type: if msg.is_dm { "private" } else { "group" },
+            persona_id,
+        },
+    )
+    .await;
+}
+
+/// Open one Socket Mode connection and handle events until Slack closes it.
+async fn run_socket(state: &Arc<SlackState>) -> anyhow::Result<()> {
+    let resp = api_call(
+        &state.http_client,
+        &state.app_token,
+        "apps.connections.open",
+        &serde_json::json!({}),
+    )
+    .await?;
+    let url = resp
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("apps.connections.open returned no url"))?;
+    let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+    let (mut sink, mut stream) = ws.split();
+    info!("Slack Socket Mode connected");
+
+    while let Some(frame) = stream.next().await {
+        let raw = match frame? {
+            WsMessage::Text(t) => t,
+            WsMessage::Ping(p) => {
+                sink.send(WsMessage::Pong(p)).await?;
+                continue;
+            }
+            WsMessage::Close(_) => break,
+            _ => continue,
+        };
+        if raw.contains("\"type\":\"disconnect\"") {
+            info!("Slack requested reconnect");
+            break;
+        }
+        let Some((envelope_id, message)) = parse_envelope(&raw) else {
+            continue;
+        };
+        // Acknowledge within 3 seconds or Slack retries the event
+        if let Some(id) = envelope_id {
+            let ack = serde_json::json!({ "envelope_id": id }).to_string();
+            sink.send(WsMessage::Text(ack)).await?;
+        }
+        if let Some(message) = message {
+            let state = state.clone();
+            tokio::spawn(async move { handle_message(&state, message).await });
+        }
+    }
+    Ok(())
+}
+
+/// Start the Slack bot. Called from run_bot() if slack_bot_token and slack_app_token are configured.
+pub async fn start_slack_bot(app_state: Arc<AppState>, bot_token: String, app_token: String) {
+    let http_client = reqwest::Client::new();
+    let bot_user_id = match api_call(
+        &http_client,
+        &bot_token,
+        "auth.test",
+        &serde_json::json!({}),
+    )
+    .await
+    {
+        Ok(resp) => resp
+            .get("user_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        Err(e) => {
+            error!("Failed to authenticate Slack bot: {e}");
+            return;
+        }
+    };
+    let state = Arc::new(SlackState {
+        app_state,
+        bot_token,
+        app_token,
+        bot_user_id,
+        http_client,
+    });
+
+    info!("Starting Slack bot...");
+    loop {
+        if let Err(e) = run_socket(&state).await {
+            error!("Slack Socket Mode error: {e}");
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_chat_id_roundtrip() {
+        for channel in ["C024BE91L", "D0123ABCDEF", "G1"] {
+            let id = chat_id_for_channel(channel).unwrap();
+            assert_eq!(channel_for_chat_id(id), channel);
+        }
+        assert!(chat_id_for_channel("not-a-channel").is_none());
+    }
+
+    #[test]
+    fn test_parse_envelope() {
+        let raw = r#"{"type":"events_api","envelope_id":"e1","payload":{"event":{"type":"message","channel":"D1","channel_type":"im","user":"U1","text":"hi","ts":"1.2"}}}"#;
+        let (id, msg) = parse_envelope(raw).unwrap();
+        assert_eq!(id.as_deref(), Some("e1"));
+        let msg = msg.unwrap();
+        assert!(msg.is_dm && !msg.mentioned);
+        assert_eq!((msg.text.as_str(), msg.ts.as_str()), ("hi", "1.2"));
+
+        let bot = r#"{"type":"events_api","envelope_id":"e2","payload":{"event":{"type":"message","bot_id":"B1","channel":"C1","user":"U1","text":"x","ts":"1"}}}"#;
+        assert!(parse_envelope(bot).unwrap().1.is_none());
+        let edit = r#"{"type":"events_api","envelope_id":"e3","payload":{"event":{"type":"message","subtype":"message_changed","channel":"C1"}}}"#;
+        assert!(parse_envelope(edit).unwrap().1.is_none());
+        let hello = r#"{"type":"hello"}"#;
+        assert_eq!(parse_envelope(hello), Some((None, None)));
+    }
+
+    #[test]
+    fn test_strip_mention_and_split() {
+        assert_eq!(
+            strip_mention("<@UBOT> what's up", "UBOT"),
+            (true, "what's up".to_string())
+        );
+        assert_eq!(strip_mention("hello", "UBOT"), (false, "hello".to_string()));
+        let chunks = split_text("aaaa\nbbbb\ncc", 9);
+        assert_eq!(chunks, vec!["aaaa", "bbbb\ncc"]);
+    }
+}
@@ -0,0 +1,883 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::channel::{split_text, Channel};
+use crate::claude::Message as ClaudeMessage;
+use crate::config::{Config, TeamsConfig};
+use crate::db::call_blocking;
+use crate::db::{Database, StoredMessage};
+use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
+use crate::telegram::{archive_conversation, AgentRequestContext, AppState};
+
+/// Bot Framework OpenID metadata, which points at the keys that sign incoming requests.
+const OPENID_CONFIG_URL: &str = "https://login.botframework.com/v1/.well-known/openidconfiguration";
+const TOKEN_ISSUER: &str = "https://api.botframework.com";
+const TOKEN_SCOPE: &str = "https://api.botframework.com/.default";
+/// Signing keys are refetched after this, or as soon as a token names an unknown key.
+const KEYS_TTL: Duration = Duration::from_secs(24 * 3600);
+/// Allowed clock skew when checking token lifetimes.
+const CLOCK_SKEW_SECS: i64 = 300;
+/// Teams accepts about 28 KB per message; stay well below.
+const MAX_MESSAGE_LEN: usize = 20000;
+/// Chat setting holding where to post in a chat: `<service url> <conversation id>`.
+const CONVERSATION_SETTING: &str = "teams_conversation";
+
+// --- Bot Framework activity types ---
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Activity {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+    service_url: Option<String>,
+    from: Option<ChannelAccount>,
+    conversation: Option<ConversationAccount>,
+    recipient: Option<ChannelAccount>,
+    text: Option<String>,
+    #[serde(default)]
+    entities: Vec<Entity>,
+    channel_data: Option<ChannelData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelAccount {
+    id: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConversationAccount {
+    id: String,
+    /// "personal", "groupChat" or "channel".
+    conversation_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Entity {
+    #[serde(rename = "type")]
+    kind: String,
+    mentioned: Option<ChannelAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelData {
+    channel: Option<ChannelInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelInfo {
+    id: String,
+}
+
+/// An incoming user message worth handling.
+#[derive(Debug, PartialEq)]
+struct IncomingMessage {
+    service_url: String,
+    /// Conversation to reply to (for channels, the thread: `<channel>;messageid=<id>`).
+    conversation_id: String,
+    /// Conversation without the thread suffix: one chat per channel or group chat.
+    chat_key: String,
+    /// Teams channel id, for channel messages.
+    channel_id: Option<String>,
+    is_group: bool,
+    sender: String,
+    text: String,
+    activity_id: String,
+    /// The bot was @mentioned.
+    mentioned: bool,
+}
+
+/// Remove `<at>Name</at>` mention markup from message text.
+fn strip_mentions(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<at>") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("</at>") {
+            Some(end) => rest = &rest[start + end + "</at>".len()..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out.replace("&nbsp;", " ").trim().to_string()
+}
+
+/// Turn a Bot Framework activity into a user message. Other activity types (conversation
+/// updates, typing, reactions) and empty messages yield `None`.
+fn parse_activity(activity: Activity) -> Option<IncomingMessage> {
+    if activity.kind != "message" {
+        return None;
+    }
+    let conversation = activity.conversation?;
+    let from = activity.from?;
+    let bot_id = activity.recipient.map(|r| r.id).unwrap_or_default();
+    let mentioned = activity
+        .entities
+        .iter()
+        .any(|e| e.kind == "mention" && e.mentioned.as_ref().is_some_and(|m| m.id == bot_id));
+    let text = strip_mentions(activity.text.as_deref()?);
+    if text.is_empty() {
+        return None;
+    }
+    let chat_key = conversation
+        .id
+        .split(';')
+        .next()
+        .unwrap_or(&conversation.id)
+        .to_string();
+    Some(IncomingMessage {
+        service_url: activity.service_url?,
+        chat_key,
+        channel_id: activity.channel_data.and_then(|d| d.channel).map(|c| c.id),
+        is_group: conversation.conversation_type.as_deref() != Some("personal"),
+        conversation_id: conversation.id,
+        sender: from.name.filter(|n| !n.is_empty()).unwrap_or(from.id),
+        text,
+        activity_id: activity.id.unwrap_or_default(),
+        mentioned,
+    })
+}
+
+/// Teams conversation ids are strings; map them to a stable chat id with 64-bit FNV-1a.
+/// Channels and group chats are negative, as in Telegram.
+fn chat_id_for(chat_key: &str, is_group: bool) -> i64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in chat_key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let id = ((hash >> 1) as i64).max(1);
+    if is_group {
+        -id
+    } else {
+        id
+    }
+}
+
+// --- Request authentication (Bot Framework JWT, RS256) ---
+
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kid: String,
+    n: String,
+    e: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenIdConfig {
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtClaims {
+    iss: String,
+    aud: String,
+    exp: i64,
+    nbf: Option<i64>,
+    serviceurl: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum AuthError {
+    /// Signed with a key not in the cached set; refetch the keys and retry.
+    UnknownKey,
+    Invalid(String),
+}
+
+fn decode_part<T: serde::de::DeserializeOwned>(part: &str) -> Result<T, AuthError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| AuthError::Invalid(format!("bad base64: {e}")))?;
+    serde_json::from_slice(&bytes).map_err(|e| AuthError::Invalid(format!("bad JSON: {e}")))
+}
+
+/// Check a Bot Framework bearer token: RS256 signature by one of `keys`, issuer, audience (our
+/// app id), lifetime and, when present, the service URL it was issued for.
+fn verify_token(
+    token: &str,
+    keys: &[Jwk],
+    app_id: &str,
+    service_url: &str,
+    now: i64,
+) -> Result<(), AuthError> {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [header, claims, signature] = parts[..] else {
+        return Err(AuthError::Invalid("malformed token".into()));
+    };
+    let jwt_header: JwtHeader = decode_part(header)?;
+    if jwt_header.alg != "RS256" {
+        return Err(AuthError::Invalid(format!(
+            "unsupported alg {}",
+            jwt_header.alg
+        )));
+    }
+    let kid = jwt_header.kid.unwrap_or_default();
+    let key = keys
+        .iter()
+        .find(|k| k.kid == kid)
+        .ok_or(AuthError::UnknownKey)?;
+    let decode = |s: &str| {
+        URL_SAFE_NO_PAD
+            .decode(s)
+            .map_err(|e| AuthError::Invalid(format!("bad base64: {e}")))
+    };
+    let (n, e, sig) = (decode(&key.n)?, decode(&key.e)?, decode(signature)?);
+    ring::signature::RsaPublicKeyComponents { n: &n, e: &e }
+        .verify(
+            &ring::signature::RSA_PKCS1_2048_8192_SHA256,
+            format!("{header}.{claims}").as_bytes(),
+            &sig,
+        )
+        .map_err(|_| AuthError::Invalid("bad signature".into()))?;
+
+    let claims: JwtClaims = decode_part(claims)?;
+    if claims.iss != TOKEN_ISSUER {
+        return Err(AuthError::Invalid(format!(
+            "unexpected issuer {}",
+            claims.iss
+        )));
+    }
+    if claims.aud != app_id {
+        return Err(AuthError::Invalid("token is for another app".into()));
+    }
+    if claims.exp + CLOCK_SKEW_SECS < now
+        || claims.nbf.is_some_and(|nbf| nbf - CLOCK_SKEW_SECS > now)
+    {
+        return Err(AuthError::Invalid("token expired or not yet valid".into()));
+    }
+    if let Some(url) = &claims.serviceurl {
+        if url.trim_end_matches('/') != service_url.trim_end_matches('/') {
+            return Err(AuthError::Invalid("service URL mismatch".into()));
+        }
+    }
+    Ok(())
+}
+
+// --- Shared state for Teams handlers ---
+
+struct TeamsState {
+    app_state: Arc<AppState>,
+    teams: TeamsConfig,
+    http_client: reqwest::Client,
+    signing_keys: Mutex<Option<(Instant, Vec<Jwk>)>>,
+    connector: BotConnector,
+}
+
+async fn fetch_signing_keys(client: &reqwest::Client) -> anyhow::Result<Vec<Jwk>> {
+    let config: OpenIdConfig = client.get(OPENID_CONFIG_URL).send().await?.json().await?;
+    let jwks: Jwks = client.get(&config.jwks_uri).send().await?.json().await?;
+    Ok(jwks.keys)
+}
+
+async fn signing_keys(state: &TeamsState, refresh: bool) -> anyhow::Result<Vec<Jwk>> {
+    let mut cached = state.signing_keys.lock().await;
+    if let Some((fetched, keys)) = cached.as_ref() {
+        if !refresh && fetched.elapsed() < KEYS_TTL {
+            return Ok(keys.clone());
+        }
+    }
+    let keys = fetch_signing_keys(&state.http_client).await?;
+    *cached = Some((Instant::now(), keys.clone()));
+    Ok(keys)
+}
+
+/// Check the request's `Authorization: Bearer` token against the Bot Framework keys.
+async fn authenticate(state: &TeamsState, headers: &HeaderMap, service_url: &str) -> bool {
+    let Some(token) = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    let now = chrono::Utc::now().timestamp();
+    for refresh in [false, true] {
+        let keys = match signing_keys(state, refresh).await {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!("Teams: failed to fetch Bot Framework signing keys: {e}");
+                return false;
+            }
+        };
+        match verify_token(token, &keys, &state.teams.app_id, service_url, now) {
+            Ok(()) => return true,
+            Err(AuthError::UnknownKey) => continue,
+            Err(AuthError::Invalid(reason)) => {
+                warn!("Teams: rejected request: {reason}");
+                return false;
+            }
+        }
+    }
+    warn!("Teams: rejected request: token signed with an unknown key");
+    false
+}
+
+// --- Send message via the Bot Connector API ---
+
+/// Outgoing messages: a client-credentials token for the Bot Connector API, cached until shortly
+/// before it expires.
+struct BotConnector {
+    teams: TeamsConfig,
+    http_client: reqwest::Client,
+    access_token: Mutex<Option<(Instant, String)>>,
+}
+
+impl BotConnector {
+    fn new(teams: TeamsConfig) -> Self {
+        BotConnector {
+            teams,
+            http_client: reqwest::Client::new(),
+            access_token: Mutex::new(None),
+        }
+    }
+
+    async fn access_token(&self) -> anyhow::Result<String> {
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            expires_in: u64,
+        }
+        let mut cached = self.access_token.lock().await;
+        if let Some((expires, token)) = cached.as_ref() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let tenant = self
+            .teams
+            .tenant_id
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or("botframework.com");
+        let url = format!("https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token");
+        let resp = self
+            .http_client
+            .post(&url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.teams.app_id.as_str()),
+                ("client_secret", self.teams.app_password.as_str()),
+                ("scope", TOKEN_SCOPE),
+            ])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("token request returned HTTP {}", resp.status());
+        }
+        let token: TokenResponse = resp.json().await?;
+        let lifetime = Duration::from_secs(token.expires_in.saturating_sub(60));
+        *cached = Some((Instant::now() + lifetime, token.access_token.clone()));
+        Ok(token.access_token)
+    }
+
+    /// Post one chunk that fits [`MAX_MESSAGE_LEN`] to a conversation.
+    async fn send_chunk(
+        &self,
+        service_url: &str,
+        conversation_id: &str,
+        chunk: &str,
+    ) -> Result<(), String> {
+        let token = self
+            .access_token()
+            .await
+            .map_err(|e| format!("Teams: failed to get access token: {e}"))?;
+        let url = format!(
+            "{}/v3/conversations/{}/activities",
+            service_url.trim_end_matches('/'),
+            urlencoding::encode(conversation_id)
+        );
+        let body = serde_json::json!({
+            "type": "message",
+            "text": chunk,
+            "textFormat": "markdown",
+        });
+        match self
+            .http_client
+            .post(&url)
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+            .await
+        {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!(
+                "Teams: sending message returned HTTP {}",
+                resp.status()
+            )),
+            Err(e) => Err(format!("Failed to send Teams message: {e}")),
+        }
+    }
+}
+
+async fn send_teams_message(
+    state: &TeamsState,
+    service_url: &str,
+    conversation_id: &str,
+    text: &str,
+) {
+    for chunk in split_text(text, MAX_MESSAGE_LEN)
+        .into_iter()
+        .filter(|c| !c.is_empty())
+    {
+        if let Err(e) = state
+            .connector
+            .send_chunk(service_url, conversation_id, &chunk)
+            .await
+        {
+            error!("{e}");
+            return;
+        }
+    }
+}
+
+/// Teams as a [`Channel`]. Chats are addressed by the service URL and conversation recorded when
+/// they last wrote; in a Teams channel each message starts a new thread.
+pub struct TeamsChannel {
+    db: Arc<Database>,
+    connector: BotConnector,
+}
+
+impl TeamsChannel {
+    pub fn from_config(config: &Config, db: Arc<Database>) -> Option<Self> {
+        config.teams.clone().map(|teams| TeamsChannel {
+            db,
+            connector: BotConnector::new(teams),
+        })
+    }
+}
+
+#[async_trait]
+impl Channel for TeamsChannel {
+    fn name(&self) -> &'static str {
+        "teams"
+    }
+
+    fn max_message_len(&self) -> usize {
+        MAX_MESSAGE_LEN
+    }
+
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String> {
+        let address = call_blocking(self.db.clone(), move |d| {
+            d.get_chat_setting(chat_id, CONVERSATION_SETTING)
+        })
+        .await
+        .map_err(|e| format!("Failed to read Teams conversation: {e}"))?;
+        let (service_url, conversation_id) = address
+            .as_deref()
+            .and_then(|a| a.split_once(' '))
+            .ok_or_else(|| format!("No Teams conversation known for chat {chat_id}"))?;
+        self.connector
+            .send_chunk(service_url, conversation_id, chunk)
+            .await
+    }
+}
+
+/// Run a slash command and return its reply.
+async fn run_slash_command(
+    state: &TeamsState,
+    cmd: SlashCommand,
+    chat_id: i64,
+    sender_name: &str,
+    text: &str,
+) -> String {
+    let app = &state.app_state;
+    match cmd {
+        SlashCommand::Reset => {
+            let pid = call_blocking(app.db.clone(), move |db| db.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+            if pid > 0 {
+                let _ =
+                    call_blocking(app.db.clone(), move |db| db.delete_session(chat_id, pid)).await;
+            }
+            "Conversation cleared. Principles and per-persona memory are unchanged.".into()
+        }
+        SlashCommand::Skills => app.skills.list_skills_formatted(),
+        SlashCommand::Persona => {
+            crate::persona::handle_persona_command(
+                app.db.clone(),
+                chat_id,
+                text.trim(),
+                Some(&app.config),
+            )
+            .await
+        }
+        SlashCommand::Schedule => {
+            match call_blocking(app.db.clone(), |db| {
+                db.get_all_scheduled_tasks_for_display()
+            })
+            .await
+            {
+                Ok(t) => crate::tools::schedule::format_tasks_list_all(&t),
+                Err(e) => format!("Error listing tasks: {e}"),
+            }
+        }
+        SlashCommand::Footer => {
+            crate::usage::handle_footer_command(app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Undelete => {
+            crate::session_trash::handle_undelete_command(
+                app.db.clone(),
+                chat_id,
+                text,
+                app.config.deleted_session_retention_days,
+            )
+            .await
+        }
+        SlashCommand::Ack => {
+            crate::incidents::handle_ack_command(&app.config, app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Push => {
+            crate::push::handle_push_command(&app.config, app.db.clone(), chat_id, text).await
+        }
+        SlashCommand::Debate => crate::roundtable::handle_debate_command(&app.config, text).await,
+        SlashCommand::Thinking => {
+            crate::thinking::handle_thinking_command(&app.config, app.db.clone(), chat_id, text)
+                .await
+        }
+        SlashCommand::Memory => {
+            crate::memory_commands::handle_memory_command(
+                app.db.clone(),
+                &app.memory,
+                chat_id,
+                text,
+            )
+            .await
+        }
+        SlashCommand::Forget => {
+            crate::memory_commands::handle_forget_command(
+                app.db.clone(),
+                &app.memory,
+                chat_id,
+                text,
+            )
+            .await
+        }
+        SlashCommand::CatchUp => {
+            crate::tools::catch_me_up::summarize_catch_up(
+                app.db.clone(),
+                app.llm.as_ref(),
+                chat_id,
+                sender_name,
+            )
+            .await
+        }
+        SlashCommand::Archive => {
+            let pid = call_blocking(app.db.clone(), move |db| db.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+            if pid == 0 {
+                return "No session to archive.".into();
+            }
+            let Ok(Some((json, _))) =
+                call_blocking(app.db.clone(), move |db| db.load_session(chat_id, pid)).await
+            else {
+                return "No session to archive.".into();
+            };
+            let messages: Vec<ClaudeMessage> = serde_json::from_str(&json).unwrap_or_default();
+            if messages.is_empty() {
+                return "No session to archive.".into();
+            }
+            archive_conversation(&app.config.runtime_data_dir(), chat_id, &messages);
+            format!("Archived {} messages.", messages.len())
+        }
+    }
+}
+
+async fn handle_message(state: &TeamsState, msg: IncomingMessage) {
+    let config = &state.app_state.config;
+    let chat_id = chat_id_for(&msg.chat_key, msg.is_group);
+    let title = format!("teams-{}", msg.chat_key);
+    // Check allowed channels (empty = all), overridden by the DB list; unknown channels file an
+    // access request
+    let allowed = &state.teams.allowed_channels;
+    let static_allowed = !msg.is_group
+        || allowed.is_empty()
+        || allowed.contains(&msg.chat_key)
+        || msg.channel_id.as_ref().is_some_and(|c| allowed.contains(c));
+    if !crate::access::admit(
+        &state.app_state,
+        "teams",
+        chat_id,
+        static_allowed,
+        Some(&title),
+        &msg.sender,
+        &msg.text,
+    )
+    .await
+    {
+        return;
+    }
+    let text = msg.text.clone();
+    let sender_name = msg.sender.clone();
+
+    // Single entry point: parse slash command first. If command, run backend handler and return — never send to LLM.
+    if let Some(cmd) = parse_slash_command(&text) {
+        if !msg.is_group || msg.mentioned {
+            let reply = run_slash_command(state, cmd, chat_id, &sender_name, &text).await;
+            send_teams_message(state, &msg.service_url, &msg.conversation_id, &reply).await;
+        }
+        return;
+    }
+
+    // Custom commands (define_command) typed as text expand into a normal prompt
+    let custom_command =
+        crate::custom_commands::expand_custom_command(state.app_state.db.clone(), chat_id, &text)
+            .await;
+    let is_custom_command = custom_command.is_some();
+    let text = custom_command.unwrap_or(text);
+
+    // Resolve persona
+    let persona_id = call_blocking(state.app_state.db.clone(), move |db| {
+        db.get_current_persona_id(chat_id)
+    })
+    .await
+    .unwrap_or(0);
+    if persona_id == 0 {
+        return;
+    }
+
+    // In channels and group chats only respond to @mentions; personal chats get a reply to everything
+    let will_respond = is_custom_command || !msg.is_group || msg.mentioned;
+
+    // Translation, moderation and inbound hooks; dropped messages are not stored or answered
+    let ctx = crate::middleware::InboundContext {
+        chat_id,
+        chat_type: "teams",
+        sender_name: &sender_name,
+        will_respond,
+    };
+    let Some(text) = crate::middleware::run_inbound(&state.app_state, ctx, text).await else {
+        return;
+    };
+
+    // Store the chat (and where to post in it, for scheduled and proactive messages) and message
+    let address = format!("{} {}", msg.service_url, msg.chat_key);
+    let _ = call_blocking(state.app_state.db.clone(), move |db| {
+        db.upsert_chat(chat_id, Some(&title), "teams")?;
+        db.set_chat_setting(chat_id, CONVERSATION_SETTING, Some(&address))
+    })
+    .await;
+    let stored = StoredMessage {
+        id: if msg.activity_id.is_empty() {
+            uuid::Uuid::new_v4().to_string()
+        } else {
+            format!("teams-{}", msg.activity_id)
+        },
+        chat_id,
+        persona_id,
+        sender_name: sender_name.clone(),
+        content: text.clone(),
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(state.app_state.db.clone(), move |db| {
+        db.store_message(&stored)
+    })
+    .await;
+
+    if !will_respond {
+        return;
+    }
+
+    info!(
+        "Teams message from {} in chat {}: {}",
+        sender_name,
+        chat_id,
+        text.chars().take(100).collect::<String>()
+    );
+
+    // Process with Claude (reuses the same agentic loop as Telegram)
+    match crate::telegram::process_with_agent(
+        &state.app_state,
+        AgentRequestContext {
+            caller_channel: "teams",
+            chat_id,
+            chat_type: if msg.is_group { "group" } else { "private" },
+            persona_id,
+        },
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(response) => {
+            if !response.is_empty() {
+                let delivery_started = std::time::Instant::now();
+                send_teams_message(state, &msg.service_url, &msg.conversation_id, &response).await;
+                crate::turn_metrics::record_delivery(
+                    state.app_state.db.clone(),
+                    chat_id,
+                    delivery_started,
+                )
+                .await;
+
+                // Store bot response
+                let bot_msg = StoredMessage {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    chat_id,
+                    persona_id,
+                    sender_name: config.bot_username.clone(),
+                    content: response,
+                    is_from_bot: true,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                let _ = call_blocking(state.app_state.db.clone(), move |db| {
+                    db.store_message(&bot_msg)
+                })
+                .await;
+            }
+        }
+        Err(e) => {
+            error!("Error processing Teams message: {e}");
+            send_teams_message(
+                state,
+                &msg.service_url,
+                &msg.conversation_id,
+                &format!("Error: {e}"),
+            )
+            .await;
+        }
+    }
+}
+
+// --- Incoming activities (POST /api/messages) ---
+
+async fn handle_activity(
+    State(state): State<Arc<TeamsState>>,
+    headers: HeaderMap,
+    Json(activity): Json<Activity>,
+) -> StatusCode {
+    let service_url = activity.service_url.clone().unwrap_or_default();
+    if !authenticate(&state, &headers, &service_url).await {
+        return StatusCode::UNAUTHORIZED;
+    }
+    // Respond immediately; the reply is sent through the Bot Connector API
+    if let Some(message) = parse_activity(activity) {
+        tokio::spawn(async move { handle_message(&state, message).await });
+    }
+    StatusCode::OK
+}
+
+/// Start the Teams messaging endpoint. Called from run_bot() if a `teams` section is configured.
+pub async fn start_teams_server(app_state: Arc<AppState>, teams: TeamsConfig) {
+    let port = teams.webhook_port;
+    let state = Arc::new(TeamsState {
+        app_state,
+        connector: BotConnector::new(teams.clone()),
+        teams,
+        http_client: reqwest::Client::new(),
+        signing_keys: Mutex::new(None),
+    });
+
+    let app = Router::new()
+        .route("/api/messages", post(handle_activity))
+        .with_state(state);
+
+    let addr = format!("0.0.0.0:{port}");
+    info!("Teams messaging endpoint listening on {addr}/api/messages");
+
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Failed to bind Teams messaging endpoint on {addr}: {e}");
+            return;
+        }
+    };
+
+    if let Err(e) = axum::serve(listener, app).await {
+        error!("Teams server error: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_activity() {
+        let raw = r#"{"type":"message","id":"1699","serviceUrl":"https://smba.trafficmanager.net/emea/","from":{"id":"29:abc","name":"Alice"},"conversation":{"id":"19:chan@thread.tacv2;messageid=1699","conversationType":"channel"},"recipient":{"id":"28:bot","name":"homebot"},"text":"<at>homebot</at>&nbsp;what's on today?","entities":[{"type":"mention","mentioned":{"id":"28:bot","name":"homebot"},"text":"<at>homebot</at>"}],"channelData":{"channel":{"id":"19:chan@thread.tacv2"}}}"#;
+        let msg = parse_activity(serde_json::from_str(raw).unwrap()).unwrap();
+        assert_eq!(msg.text, "what's on today?");
+        assert_eq!(msg.sender, "Alice");
+        assert_eq!(msg.chat_key, "19:chan@thread.tacv2");
+        assert_eq!(msg.conversation_id, "19:chan@thread.tacv2;messageid=1699");
+        assert_eq!(msg.channel_id.as_deref(), Some("19:chan@thread.tacv2"));
+        assert!(msg.is_group && msg.mentioned);
+        assert!(chat_id_for(&msg.chat_key, true) < 0);
+
+        let personal = r#"{"type":"message","serviceUrl":"https://x/","from":{"id":"29:abc"},"conversation":{"id":"a:1","conversationType":"personal"},"recipient":{"id":"28:bot"},"text":"hi"}"#;
+        let msg = parse_activity(serde_json::from_str(personal).unwrap()).unwrap();
+        assert!(!msg.is_group && !msg.mentioned);
+        assert_eq!(msg.sender, "29:abc");
+
+        let update = r#"{"type":"conversationUpdate","serviceUrl":"https://x/","conversation":{"id":"a:1"}}"#;
+        assert!(parse_activity(serde_json::from_str(update).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_verify_token_rejects_bad_tokens() {
+        let keys = vec![Jwk {
+            kid: "k1".into(),
+            n: "AQAB".into(),
+            e: "AQAB".into(),
+        }];
+        let part = |v: serde_json::Value| URL_SAFE_NO_PAD.encode(v.to_string());
+        let header = part(serde_json::json!({"alg": "RS256", "kid": "other"}));
+        let claims = part(serde_json::json!({"iss": TOKEN_ISSUER, "aud": "app", "exp": 0}));
+        assert_eq!(
+            verify_token(
+                &format!("{header}.{claims}.c2ln"),
+                &keys,
+                "app",
+                "https://x",
+                0
+            ),
+            Err(AuthError::UnknownKey)
+        );
+        let header = part(serde_json::json!({"alg": "RS256", "kid": "k1"}));
+        assert_eq!(
+            verify_token(
+                &format!("{header}.{claims}.c2ln"),
+                &keys,
+                "app",
+                "https://x",
+                0
+            ),
+            Err(AuthError::Invalid("bad signature".into()))
+        );
+        let header = part(serde_json::json!({"alg": "none"}));
+        assert!(matches!(
+            verify_token(&format!("{header}.{claims}."), &keys, "app", "https://x", 0),
+            Err(AuthError::Invalid(_))
+        ));
+        assert!(verify_token("not-a-token", &keys, "app", "https://x", 0).is_err());
+    }
+}
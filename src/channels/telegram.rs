@@ -2,11 +2,14 @@ use std::path::Path;
 use std::sync::Arc;
 
 use teloxide::prelude::*;
-use teloxide::types::{BotCommand, ChatAction, ParseMode, ThreadId};
+use teloxide::types::{ChatAction, InputFile, MessageId, ParseMode, ReactionType, ThreadId};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::{error, info, warn};
 
-use crate::claude::{ContentBlock, ImageSource, Message, MessageContent, ResponseContentBlock};
+use crate::channel::{deliver_and_store_bot_message, split_text, Channel, FormatDialect};
+use crate::claude::{
+    ContentBlock, ImageSource, Message, MessageContent, ResponseContentBlock, ToolChoice,
+};
 use crate::config::Config;
 use crate::db::{call_blocking, Database, StoredMessage};
 use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
@@ -18,7 +21,7 @@ use crate::tools::{ToolAuthContext, ToolRegistry};
 
 /// Escape XML special characters in user-supplied content to prevent prompt injection.
 /// User messages are wrapped in XML tags; escaping ensures the content cannot break out.
-fn sanitize_xml(s: &str) -> String {
+pub(crate) fn sanitize_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -26,7 +29,7 @@ fn sanitize_xml(s: &str) -> String {
 }
 
 /// Format a user message with XML escaping and wrapping to clearly delimit user content.
-fn format_user_message(sender_name: &str, content: &str) -> String {
+pub(crate) fn format_user_message(sender_name: &str, content: &str) -> String {
     format!(
         "<user_message sender=\"{}\">{}</user_message>",
         sanitize_xml(sender_name),
@@ -61,6 +64,11 @@ pub enum AgentEvent {
         name: String,
         input: serde_json::Value,
     },
+    /// A progress line reported by a running tool.
+    ToolProgress {
+        name: String,
+        message: String,
+    },
     ToolResult {
         name: String,
         is_error: bool,
@@ -88,30 +96,9 @@ pub async fn run_bot(
     let bot = Bot::new(&config.telegram_bot_token);
     let db = Arc::new(db);
 
-    // Register slash commands so they appear in the Telegram menu
-    let commands = [
-        BotCommand {
-            command: "reset".into(),
-            description: "Clear conversation (memory unchanged)".into(),
-        },
-        BotCommand {
-            command: "skills".into(),
-            description: "List available skills".into(),
-        },
-        BotCommand {
-            command: "persona".into(),
-            description: "List / switch / new / delete personas".into(),
-        },
-        BotCommand {
-            command: "archive".into(),
-            description: "Archive conversation to markdown".into(),
-        },
-        BotCommand {
-            command: "schedule".into(),
-            description: "List and manage scheduled jobs".into(),
-        },
-    ];
-    if let Err(e) = bot.set_my_commands(commands).await {
+    // Register slash commands (per private/group scope) so they appear in the Telegram menu
+    let has_skills = !skills.discover_skills().is_empty();
+    if let Err(e) = crate::custom_commands::register_telegram_menus(&bot, has_skills).await {
         error!("Failed to set Telegram bot commands: {}", e);
     }
 
@@ -140,8 +127,49 @@ pub async fn run_bot(
         tools,
     });
 
+    // Start local web server if enabled
+    if state.config.web_enabled {
+        let web_state = state.clone();
+        info!(
+            "Starting Web UI server on {}:{}",
+            state.config.web_host, state.config.web_port
+        );
+        tokio::spawn(async move {
+            crate::web::start_web_server(web_state).await;
+        });
+    }
+
+    // With instance_lock, standbys stop here (web UI read-only) until they hold the lock
+    crate::instance_lock::acquire_leadership(&state).await;
+
+    match call_blocking(state.db.clone(), |d| d.fail_interrupted_background_jobs()).await {
+        Ok(n) if n > 0 => {
+            info!("Marked {n} background job(s) interrupted by the restart as failed")
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to clean up interrupted background jobs: {e}"),
+    }
+
+    // Other platforms' channels, for scheduled and proactive messages
+    crate::channel::register_configured_channels(&state.config, &state.db);
+    if state.config.telegram_business.is_some() {
+        crate::channel::register_channel(Arc::new(
+            crate::telegram_business::TelegramBusinessChannel::new(
+                state.bot.clone(),
+                state.db.clone(),
+            ),
+        ));
+    }
+
     // Start scheduler
     crate::scheduler::spawn_scheduler(state.clone());
+    crate::heartbeat::spawn_heartbeat(state.clone());
+    crate::workspace_crypto::spawn_sync(state.clone());
+    crate::incidents::spawn_escalation(state.clone());
+    crate::vault_maintenance::spawn_vault_maintenance(state.clone());
+    crate::benchmark::spawn_benchmark(state.clone());
+    crate::heartbeat::announce(&state, crate::heartbeat::Lifecycle::Startup).await;
+    crate::release_notes::announce_upgrade(&state).await;
 
     // Start WhatsApp webhook server if configured
     if let (Some(token), Some(phone_id), Some(verify)) = (
@@ -170,28 +198,196 @@ pub async fn run_bot(
         });
     }
 
-    // Start local web server if enabled
-    if state.config.web_enabled {
-        let web_state = state.clone();
-        info!(
-            "Starting Web UI server on {}:{}",
-            state.config.web_host, state.config.web_port
-        );
+    // Start Slack bot (Socket Mode) if configured
+    if let (Some(bot_token), Some(app_token)) = (
+        state.config.slack_bot_token.clone(),
+        state.config.slack_app_token.clone(),
+    ) {
+        let slack_state = state.clone();
+        info!("Starting Slack bot");
         tokio::spawn(async move {
-            crate::web::start_web_server(web_state).await;
+            crate::slack::start_slack_bot(slack_state, bot_token, app_token).await;
         });
     }
 
-    let handler = Update::filter_message().endpoint(handle_message);
+    // Start Signal bot (signal-cli JSON-RPC) if configured
+    if let Some(signal) = state.config.signal.clone() {
+        let signal_state = state.clone();
+        info!("Starting Signal bot");
+        tokio::spawn(async move {
+            crate::signal::start_signal_bot(signal_state, signal).await;
+        });
+    }
+
+    // Start Teams messaging endpoint (Bot Framework) if configured
+    if let Some(teams) = state.config.teams.clone() {
+        let teams_state = state.clone();
+        info!("Starting Teams bot on port {}", teams.webhook_port);
+        tokio::spawn(async move {
+            crate::teams::start_teams_server(teams_state, teams).await;
+        });
+    }
+
+    // Start the Wyoming voice bridge for Home Assistant voice satellites if configured
+    if let Some(wyoming) = state.config.wyoming.clone() {
+        let wyoming_state = state.clone();
+        tokio::spawn(async move {
+            crate::wyoming::start_wyoming_server(wyoming_state, wyoming).await;
+        });
+    }
+
+    let handler = dptree::entry()
+        .branch(Update::filter_message().endpoint(handle_message))
+        .branch(Update::filter_poll_answer().endpoint(handle_poll_answer))
+        .branch(Update::filter_callback_query().endpoint(handle_callback_query))
+        .branch(Update::filter_message_reaction_updated().endpoint(handle_reaction))
+        .branch(
+            Update::filter_business_connection()
+                .endpoint(crate::telegram_business::handle_connection),
+        )
+        .branch(
+            Update::filter_business_message().endpoint(crate::telegram_business::handle_message),
+        );
 
     Dispatcher::builder(bot, handler)
         .default_handler(|_| async {})
-        .dependencies(dptree::deps![state])
+        .dependencies(dptree::deps![state.clone()])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
         .await;
 
+    crate::heartbeat::announce(&state, crate::heartbeat::Lifecycle::Shutdown).await;
+    crate::instance_lock::release(&state).await;
+    let seal_state = state.clone();
+    match tokio::task::spawn_blocking(move || {
+        crate::workspace_crypto::unmount(&seal_state.config, Some(&seal_state.db))
+    })
+    .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!("Failed to seal the encrypted workspace: {e}"),
+        Err(e) => error!("Sealing the encrypted workspace panicked: {e}"),
+    }
+    Ok(())
+}
+
+/// Record votes on polls created by the create_poll tool.
+async fn handle_poll_answer(
+    answer: teloxide::types::PollAnswer,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let voter = match &answer.voter {
+        teloxide::types::MaybeAnonymousUser::User(u) => {
+            u.username.clone().unwrap_or_else(|| u.first_name.clone())
+        }
+        teloxide::types::MaybeAnonymousUser::Chat(c) => {
+            c.title().unwrap_or("anonymous").to_string()
+        }
+    };
+    let option_ids = answer.option_ids.iter().map(|i| *i as usize).collect();
+    if let Err(e) =
+        crate::polls::record_telegram_answer(state.db.clone(), &answer.poll_id.0, &voter, option_ids)
+            .await
+    {
+        error!("Failed to record poll answer: {e}");
+    }
+    Ok(())
+}
+
+/// Inline button presses: Approve / Revert on cursor-agent code reviews.
+async fn handle_callback_query(
+    bot: Bot,
+    query: teloxide::types::CallbackQuery,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some((decision, id)) = query
+        .data
+        .as_deref()
+        .and_then(crate::access::parse_callback)
+    {
+        return handle_access_callback(bot, query, state, decision, id).await;
+    }
+    let Some((action, id)) = query
+        .data
+        .as_deref()
+        .and_then(crate::code_review::parse_callback)
+    else {
+        bot.answer_callback_query(query.id).await?;
+        return Ok(());
+    };
+    let Some(message) = query.message else {
+        bot.answer_callback_query(query.id).await?;
+        return Ok(());
+    };
+    let chat = message.chat().id;
+    let result = crate::code_review::apply(state.db.clone(), id, action, chat.0).await;
+    let notice = match &result {
+        Ok(text) => text.clone(),
+        Err(e) => e.clone(),
+    };
+    bot.answer_callback_query(query.id).text(&notice).await?;
+    if result.is_ok() {
+        if let Err(e) = bot.edit_message_reply_markup(chat, message.id()).await {
+            warn!("Failed to remove review buttons: {e}");
+        }
+        bot.send_message(chat, format!("Review #{id}: {notice}"))
+            .reply_parameters(teloxide::types::ReplyParameters::new(message.id()))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Approve / Deny buttons on an access request posted to a control chat.
+async fn handle_access_callback(
+    bot: Bot,
+    query: teloxide::types::CallbackQuery,
+    state: Arc<AppState>,
+    decision: crate::access::AccessDecision,
+    id: i64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(message) = query.message else {
+        bot.answer_callback_query(query.id).await?;
+        return Ok(());
+    };
+    let chat = message.chat().id;
+    let result = crate::access::decide(&state, id, decision, chat.0).await;
+    let notice = match &result {
+        Ok(text) => text.clone(),
+        Err(e) => e.clone(),
+    };
+    bot.answer_callback_query(query.id).text(&notice).await?;
+    if result.is_ok() {
+        if let Err(e) = bot.edit_message_reply_markup(chat, message.id()).await {
+            warn!("Failed to remove access request buttons: {e}");
+        }
+        bot.send_message(chat, format!("Access request #{id}: {notice}"))
+            .reply_parameters(teloxide::types::ReplyParameters::new(message.id()))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Count reactions as feedback on the chat's latest reply (feedback report) and its latest
+/// A/B experiment reply.
+async fn handle_reaction(
+    reaction: teloxide::types::MessageReactionUpdated,
+    state: Arc<AppState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let chat_id = reaction.chat.id.0;
+    for emoji in reaction.new_reaction.iter().filter_map(|r| r.emoji()) {
+        if crate::feedback_report::record_reaction(state.db.clone(), chat_id, emoji).await {
+            break;
+        }
+    }
+    if state.config.experiments.is_empty() {
+        return Ok(());
+    }
+    for emoji in reaction.new_reaction.iter().filter_map(|r| r.emoji()) {
+        if crate::experiments::record_reaction(state.db.clone(), chat_id, emoji).await {
+            break;
+        }
+    }
     Ok(())
 }
 
@@ -255,6 +451,90 @@ async fn handle_message(
                     error!("schedule_cmd: failed to send response: {e}");
                 }
             }
+            SlashCommand::Footer => {
+                let resp = crate::usage::handle_footer_command(state.db.clone(), chat_id, &text).await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Undelete => {
+                let resp = crate::session_trash::handle_undelete_command(
+                    state.db.clone(),
+                    chat_id,
+                    &text,
+                    state.config.deleted_session_retention_days,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Ack => {
+                let resp = crate::incidents::handle_ack_command(
+                    &state.config,
+                    state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Push => {
+                let resp = crate::push::handle_push_command(
+                    &state.config,
+                    state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Debate => {
+                let resp = crate::roundtable::handle_debate_command(&state.config, &text).await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Thinking => {
+                let resp = crate::thinking::handle_thinking_command(
+                    &state.config,
+                    state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Memory => {
+                let resp = crate::memory_commands::handle_memory_command(
+                    state.db.clone(),
+                    &state.memory,
+                    chat_id,
+                    &text,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::Forget => {
+                let resp = crate::memory_commands::handle_forget_command(
+                    state.db.clone(),
+                    &state.memory,
+                    chat_id,
+                    &text,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &resp, msg.thread_id).await;
+            }
+            SlashCommand::CatchUp => {
+                let sender_name = msg
+                    .from
+                    .as_ref()
+                    .map(|u| u.username.clone().unwrap_or_else(|| u.first_name.clone()))
+                    .unwrap_or_else(|| "Unknown".into());
+                let _ = bot.send_chat_action(msg.chat.id, ChatAction::Typing).await;
+                let text = crate::tools::catch_me_up::summarize_catch_up(
+                    state.db.clone(),
+                    state.llm.as_ref(),
+                    chat_id,
+                    &sender_name,
+                )
+                .await;
+                send_response(&bot, msg.chat.id, &text, msg.thread_id).await;
+            }
             SlashCommand::Archive => {
                 let pid = call_blocking(state.db.clone(), move |db| db.get_current_persona_id(chat_id)).await.unwrap_or(0);
                 let send_archive_msg = |text: &str| {
@@ -287,6 +567,16 @@ async fn handle_message(
         return Ok(());
     }
 
+    remember_forum_topic(&state, &msg).await;
+
+    // Custom commands (define_command) expand into a normal prompt and always get a reply
+    let custom_command =
+        crate::custom_commands::expand_custom_command(state.db.clone(), chat_id, &text).await;
+    let is_custom_command = custom_command.is_some();
+    if let Some(expanded) = custom_command {
+        text = expanded;
+    }
+
     if let Some(photos) = msg.photo() {
         // Pick the largest photo (last in the array)
         if let Some(photo) = photos.last() {
@@ -397,7 +687,7 @@ async fn handle_message(
 
     // Handle voice messages
     if let Some(voice) = msg.voice() {
-        if let Some(ref openai_key) = state.config.openai_api_key {
+        if crate::transcribe::is_configured(&state.config) {
             match download_telegram_file(&bot, &voice.file.id.0).await {
                 Ok(bytes) => {
                     let sender_name = msg
@@ -405,7 +695,12 @@ async fn handle_message(
                         .as_ref()
                         .map(|u| u.username.clone().unwrap_or_else(|| u.first_name.clone()))
                         .unwrap_or_else(|| "Unknown".into());
-                    match crate::transcribe::transcribe_audio(openai_key, &bytes).await {
+                    let mime = voice
+                        .mime_type
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| "audio/ogg".into());
+                    match crate::transcribe::transcribe(&state.config, &bytes, &mime).await {
                         Ok(transcription) => {
                             text = format!(
                                 "[voice message from {}]: {}",
@@ -430,7 +725,7 @@ async fn handle_message(
             let _ = bot
                 .send_message(
                     msg.chat.id,
-                    "Voice messages not supported (no Whisper API key configured)",
+                    "Voice messages not supported (no Whisper backend configured)",
                 )
                 .await;
             return Ok(());
@@ -471,10 +766,22 @@ async fn handle_message(
         return Ok(());
     }
 
-    // Check group allowlist
-    if (db_chat_type == "telegram_group" || db_chat_type == "telegram_supergroup")
-        && !state.config.allowed_groups.is_empty()
-        && !state.config.allowed_groups.contains(&chat_id)
+    // Check group allowlist (config list, overridden by the DB list; unknown groups file an
+    // access request)
+    let static_allowed = !(db_chat_type == "telegram_group"
+        || db_chat_type == "telegram_supergroup")
+        || state.config.allowed_groups.is_empty()
+        || state.config.allowed_groups.contains(&chat_id);
+    if !crate::access::admit(
+        &state,
+        "telegram",
+        chat_id,
+        static_allowed,
+        chat_title.as_deref(),
+        &sender_name,
+        &text,
+    )
+    .await
     {
         // Store message but don't process
         let chat_title_owned = chat_title.clone();
@@ -511,9 +818,30 @@ async fn handle_message(
             timestamp: chrono::Utc::now().to_rfc3339(),
         };
         let _ = call_blocking(state.db.clone(), move |db| db.store_message(&stored)).await;
+        remember_message_thread(&state, &msg).await;
         return Ok(());
     }
 
+    // Determine if we should respond (use get_active_persona_id for current selection)
+    let should_respond = is_custom_command || match runtime_chat_type {
+        "private" => true,
+        _ => {
+            let bot_mention = format!("@{}", state.config.bot_username);
+            text.contains(&bot_mention)
+        }
+    };
+
+    // Translation, moderation and inbound hooks; dropped messages are not stored or answered
+    let ctx = crate::middleware::InboundContext {
+        chat_id,
+        chat_type: db_chat_type,
+        sender_name: &sender_name,
+        will_respond: should_respond,
+    };
+    let Some(text) = crate::middleware::run_inbound(&state, ctx, text).await else {
+        return Ok(());
+    };
+
     // Store the chat and message
     let chat_title_owned = chat_title.clone();
     let chat_type_owned = db_chat_type.to_string();
@@ -550,20 +878,33 @@ async fn handle_message(
         timestamp: chrono::Utc::now().to_rfc3339(),
     };
     let _ = call_blocking(state.db.clone(), move |db| db.store_message(&stored)).await;
-
-    // Determine if we should respond (use get_active_persona_id for current selection)
-    let should_respond = match runtime_chat_type {
-        "private" => true,
-        _ => {
-            let bot_mention = format!("@{}", state.config.bot_username);
-            text.contains(&bot_mention)
-        }
-    };
+    remember_message_thread(&state, &msg).await;
 
     if !should_respond {
         return Ok(());
     }
 
+    // New private chats answer a few onboarding questions before the agent takes over
+    if runtime_chat_type == "private" && !is_custom_command {
+        if let Some(reply) =
+            crate::onboarding::handle_message(&state, chat_id, persona_id, &text).await
+        {
+            if let Err(e) = deliver_and_store_bot_message(
+                &bot,
+                state.db.clone(),
+                &state.config.bot_username,
+                chat_id,
+                persona_id,
+                &reply,
+            )
+            .await
+            {
+                error!("Failed to send onboarding reply to chat {chat_id}: {e}");
+            }
+            return Ok(());
+        }
+    }
+
     info!(
         "Processing message from {} in chat {}: {}",
         sender_name,
@@ -606,31 +947,49 @@ async fn handle_message(
         let event_thread_id = thread_id_spawn;
         const STATUS_API_TIMEOUT_SECS: u64 = 5;
         let mut event_handle = tokio::spawn(async move {
+            // Progress lines edit the status message at most this often (Telegram rate limits edits).
+            const PROGRESS_EDIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+            let mut tool_status = String::new();
+            let mut last_progress_edit: Option<std::time::Instant> = None;
             while let Some(event) = event_rx.recv().await {
-                if let AgentEvent::ToolStart { name, input } = event {
-                    let text = format_tool_status(&name, &input);
-                    let current_id = *status_msg_id_ev.lock().await;
-                    // Wrap each Telegram API call with a timeout so a slow/hung
-                    // API response never blocks the event handler indefinitely.
-                    if let Some(mid) = current_id {
-                        let _ = tokio::time::timeout(
-                            std::time::Duration::from_secs(STATUS_API_TIMEOUT_SECS),
-                            event_bot.edit_message_text(event_chat_id, mid, &text),
-                        )
-                        .await;
-                    } else {
-                        let mut req = event_bot.send_message(event_chat_id, &text);
-                        if let Some(tid) = event_thread_id {
-                            req = req.message_thread_id(tid);
-                        }
-                        if let Ok(Ok(sent)) = tokio::time::timeout(
-                            std::time::Duration::from_secs(STATUS_API_TIMEOUT_SECS),
-                            req,
-                        )
-                        .await
+                let text = match event {
+                    AgentEvent::ToolStart { name, input } => {
+                        tool_status = format_tool_status(&name, &input);
+                        last_progress_edit = None;
+                        tool_status.clone()
+                    }
+                    AgentEvent::ToolProgress { message, .. } => {
+                        if last_progress_edit.is_some_and(|t| t.elapsed() < PROGRESS_EDIT_INTERVAL)
                         {
-                            *status_msg_id_ev.lock().await = Some(sent.id);
+                            continue;
                         }
+                        last_progress_edit = Some(std::time::Instant::now());
+                        let line: String = message.chars().take(200).collect();
+                        format!("{tool_status}\n↳ {line}")
+                    }
+                    _ => continue,
+                };
+                let current_id = *status_msg_id_ev.lock().await;
+                // Wrap each Telegram API call with a timeout so a slow/hung
+                // API response never blocks the event handler indefinitely.
+                if let Some(mid) = current_id {
+                    let _ = tokio::time::timeout(
+                        std::time::Duration::from_secs(STATUS_API_TIMEOUT_SECS),
+                        event_bot.edit_message_text(event_chat_id, mid, &text),
+                    )
+                    .await;
+                } else {
+                    let mut req = event_bot.send_message(event_chat_id, &text);
+                    if let Some(tid) = event_thread_id {
+                        req = req.message_thread_id(tid);
+                    }
+                    if let Ok(Ok(sent)) = tokio::time::timeout(
+                        std::time::Duration::from_secs(STATUS_API_TIMEOUT_SECS),
+                        req,
+                    )
+                    .await
+                    {
+                        *status_msg_id_ev.lock().await = Some(sent.id);
                     }
                 }
             }
@@ -675,18 +1034,23 @@ async fn handle_message(
         typing_handle.abort();
 
         match result {
-            Ok(response) => {
-                let to_send = if response.trim().is_empty() {
-                    "Done.".to_string()
-                } else {
-                    response
-                };
+            Ok(response) if response.trim().is_empty() => {
+                // Acknowledged with a reaction or sticker; nothing more to send
+            }
+            Ok(to_send) => {
                 info!(
                     "Sending response to chat {}: {} chars",
                     chat_id_spawn,
                     to_send.len()
                 );
+                let delivery_started = std::time::Instant::now();
                 send_response(&bot_spawn, chat_id_spawn, &to_send, thread_id_spawn).await;
+                crate::turn_metrics::record_delivery(
+                    state_spawn.db.clone(),
+                    chat_id_spawn.0,
+                    delivery_started,
+                )
+                .await;
 
                 let bot_msg = StoredMessage {
                     id: uuid::Uuid::new_v4().to_string(),
@@ -748,6 +1112,24 @@ fn guess_image_media_type(data: &[u8]) -> String {
     }
 }
 
+/// Run a tool unless a feature flag turned it off for the chat (the model may still name a tool
+/// it was not offered).
+async fn execute_unless_disabled(
+    state: &AppState,
+    disabled_tools: &[&str],
+    name: &str,
+    input: serde_json::Value,
+    auth: &ToolAuthContext,
+) -> crate::tools::ToolResult {
+    if disabled_tools.contains(&name) {
+        return crate::tools::ToolResult::error(format!(
+            "Tool '{name}' is turned off for this chat by a feature flag."
+        ))
+        .with_error_type("feature_disabled");
+    }
+    state.tools.execute_with_auth(name, input, auth).await
+}
+
 pub async fn process_with_agent(
     state: &AppState,
     context: AgentRequestContext<'_>,
@@ -766,11 +1148,35 @@ pub async fn process_with_agent_with_events(
 ) -> anyhow::Result<String> {
     let chat_id = context.chat_id;
     let persona_id = context.persona_id;
+    // Stage timings for slow_report
+    let mut timer = crate::turn_metrics::TurnTimer::start();
 
+    let stage_started = std::time::Instant::now();
+    if let Err(e) = crate::chat_archive::rehydrate(
+        state.db.clone(),
+        &state.config.runtime_data_dir(),
+        chat_id,
+    )
+    .await
+    {
+        warn!("Failed to restore archived chat {chat_id}: {e}");
+    }
+    if override_prompt.is_none() {
+        if let Ok(Some(received_at)) =
+            call_blocking(state.db.clone(), move |db| db.last_user_message_time(chat_id)).await
+        {
+            timer.set_queue_wait(&received_at);
+        }
+    }
+    timer.add("db", stage_started);
+
+    let stage_started = std::time::Instant::now();
     // Build system prompt: principles from workspace_dir/AGENTS.md only; memory from per-persona MEMORY.md + daily log
     let principles_content = state.memory.read_groups_root_memory().unwrap_or_default();
     let memory_context = state.memory.build_memory_context(chat_id, persona_id);
-    let skills_catalog = state.skills.build_skills_catalog();
+    let skill_audience =
+        crate::skills::SkillAudience::load(state.db.clone(), chat_id, persona_id).await;
+    let skills_catalog = state.skills.build_skills_catalog_for(&skill_audience);
     // Workspace shared directory: only working_dir/shared (or workspace_dir/shared when unified). No fallback to repo-root shared/.
     let workspace_dir = Path::new(state.config.working_dir()).join("shared");
     let workspace_path = workspace_dir.to_string_lossy();
@@ -821,6 +1227,8 @@ pub async fn process_with_agent_with_events(
             if !c.trim().is_empty() {
                 parts.push(format!("- Index: {}", c.trim()));
             }
+        } else if use_native && v.origin_vault_path.is_some() {
+            parts.push("- Index: use `index_vault` tool after vault changes".to_string());
         }
         if parts.is_empty() {
             None
@@ -832,9 +1240,11 @@ pub async fn process_with_agent_with_events(
             ))
         }
     });
-    let tz: chrono_tz::Tz = state.config.timezone.parse().unwrap_or(chrono_tz::Tz::UTC);
+    let timezone =
+        crate::onboarding::chat_timezone(state.db.clone(), chat_id, &state.config.timezone).await;
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::Tz::UTC);
     let current_time_in_tz = chrono::Utc::now().with_timezone(&tz).format("%Y-%m-%d %H:%M:%S %Z").to_string();
-    let system_prompt = build_system_prompt(
+    let mut system_prompt = build_system_prompt(
         &state.config.bot_username,
         &principles_content,
         &agents_md_path,
@@ -845,10 +1255,39 @@ pub async fn process_with_agent_with_events(
         &workspace_path,
         &skills_dir_for_prompt,
         vault_paths_section.as_deref(),
-        &state.config.timezone,
+        &timezone,
         &current_time_in_tz,
     );
+    if let Some(section) = crate::onboarding::prompt_section(state.db.clone(), chat_id).await {
+        system_prompt.push_str("\n\n");
+        system_prompt.push_str(&section);
+    }
+    if let Some(section) = crate::home_assistant::presence_prompt_section(&state.config).await {
+        system_prompt.push_str("\n\n");
+        system_prompt.push_str(&section);
+    }
+    if context.caller_channel == "wyoming" {
+        system_prompt.push_str("\n\n");
+        system_prompt.push_str(crate::wyoming::SPOKEN_REPLY_HINT);
+    }
+
+    // A/B experiments: enrolled chats get a variant per turn (alternate prompt and/or model)
+    let experiment = crate::experiments::assign(&state.config, chat_id);
+    let experiment_llm = experiment.as_ref().and_then(|a| {
+        if let Some(extra) = a.system_prompt_append.as_deref() {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(extra);
+        }
+        a.model.as_ref().map(|model| {
+            let mut experiment_config = state.config.clone();
+            experiment_config.model = model.clone();
+            crate::llm::create_provider(&experiment_config)
+        })
+    });
+    let llm = experiment_llm.as_deref().unwrap_or(state.llm.as_ref());
+    timer.add("context", stage_started);
 
+    let stage_started = std::time::Instant::now();
     // Try to resume from session
     let mut messages = if let Some((json, updated_at)) =
         call_blocking(state.db.clone(), move |db| db.load_session(chat_id, persona_id)).await?
@@ -893,7 +1332,9 @@ pub async fn process_with_agent_with_events(
     // Strip tool_use / tool_result block messages from prior agentic loops.
     // Only keep plain text messages (clean human turns and final assistant responses).
     messages.retain(|m| matches!(&m.content, MessageContent::Text(_)));
+    timer.add("db", stage_started);
 
+    let stage_started = std::time::Instant::now();
     // If override_prompt is provided (from scheduler), add it as a user message
     if let Some(prompt) = override_prompt {
         messages.push(Message {
@@ -902,6 +1343,27 @@ pub async fn process_with_agent_with_events(
         });
     }
 
+    let flags = crate::feature_flags::chat_flags(&state.config, state.db.clone(), chat_id).await;
+    let disabled_tools = crate::feature_flags::disabled_tools(&flags);
+    let vision_enabled = flags
+        .iter()
+        .any(|f| f.flag == crate::feature_flags::FeatureFlag::Vision.name() && f.enabled);
+    let image_data = match image_data {
+        Some(_) if !vision_enabled => {
+            if let Some(last_msg) = messages.last_mut() {
+                if let (true, MessageContent::Text(t)) =
+                    (last_msg.role == "user", &mut last_msg.content)
+                {
+                    t.push_str(
+                        "\n[An image was attached, but image input is turned off for this chat.]",
+                    );
+                }
+            }
+            None
+        }
+        other => other,
+    };
+
     // If image_data is present, convert the last user message to a blocks-based message with the image
     if let Some((base64_data, media_type)) = image_data {
         if let Some(last_msg) = messages.last_mut() {
@@ -925,9 +1387,15 @@ pub async fn process_with_agent_with_events(
         }
     }
 
+    // Documents go to the provider's Files API when configured, instead of through read_file
+    crate::provider_files::attach_documents(&state.config, state.db.clone(), chat_id, &mut messages)
+        .await;
+
     // Keep smallest suffix with at least 2 user and 2 assistant messages (chronological)
     messages = trim_to_recent_balanced(messages);
 
+    timer.add("context", stage_started);
+
     // Ensure we have at least one message
     if messages.is_empty() {
         return Ok("I didn't receive any message to process.".into());
@@ -935,6 +1403,7 @@ pub async fn process_with_agent_with_events(
 
     // Compact if messages exceed threshold
     if messages.len() > state.config.max_session_messages {
+        let stage_started = std::time::Instant::now();
         // Pre-compaction memory flush: run one silent agent turn so the model can write
         // important facts to memory before we summarize the transcript away.
         messages = run_memory_flush_before_compaction(
@@ -953,9 +1422,33 @@ pub async fn process_with_agent_with_events(
             state.config.compact_keep_recent,
         )
         .await;
+        // Tag the conversation since the last tagged segment for find_conversations (background).
+        let tag_config = state.config.clone();
+        let tag_db = state.db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::topics::tag_conversation_since_last_segment(
+                &tag_config,
+                tag_db,
+                chat_id,
+                persona_id,
+            )
+            .await
+            {
+                tracing::warn!("Topic tagging failed for chat {chat_id}: {e}");
+            }
+        });
+        timer.add("compaction", stage_started);
     }
 
-    let tool_defs = state.tools.definitions();
+    let mut tool_defs = state.tools.definitions();
+    tool_defs.retain(|d| !disabled_tools.contains(&d.name.as_str()));
+    // Provider-side search replaces the local one
+    if tool_defs
+        .iter()
+        .any(|d| d.name == crate::llm::PROVIDER_WEB_SEARCH_TOOL)
+    {
+        tool_defs.retain(|d| d.name != "web_search");
+    }
     let tool_auth = ToolAuthContext {
         caller_channel: context.caller_channel.to_string(),
         caller_chat_id: chat_id,
@@ -966,6 +1459,7 @@ pub async fn process_with_agent_with_events(
     // Orchestrator: plan-first step (optional). Use timeout so a slow/hung call doesn't block the reply.
     const ORCHESTRATOR_TIMEOUT_SECS: u64 = 30;
     if state.config.orchestrator_enabled {
+        let stage_started = std::time::Instant::now();
         let last_user_msg = messages
             .iter()
             .rev()
@@ -1098,6 +1592,7 @@ pub async fn process_with_agent_with_events(
                 // Fall back to main agent
             }
         }
+        timer.add("orchestrator", stage_started);
     }
 
     // Agentic tool-use loop. Timeouts prevent hangs:
@@ -1106,12 +1601,39 @@ pub async fn process_with_agent_with_events(
     // Both timeouts are critical to ensure the bot always sends a response.
     const LLM_ROUND_TIMEOUT_SECS: u64 = 180;
     const TOOL_EXECUTION_TIMEOUT_SECS: u64 = 120;
+    // Set when a react/send_sticker call succeeded: an empty final text then means "no reply"
+    let mut acknowledged = false;
+    let turn_started = std::time::Instant::now();
+    let mut turn_usage = crate::usage::TurnUsage {
+        model: experiment
+            .as_ref()
+            .and_then(|a| a.model.clone())
+            .unwrap_or_else(|| state.config.model.clone()),
+        ..Default::default()
+    };
+    if experiment.is_some() {
+        if let Some(text) = crate::experiments::last_user_text(&messages) {
+            crate::experiments::note_user_message(state.db.clone(), chat_id, text).await;
+        }
+    }
+    // Set to None after a call was held for approval, so the model asks the user to confirm
+    // instead of retrying it right away
+    let mut next_tool_choice = ToolChoice::Auto;
+    // Fallback provider that answered a round of this turn, if the primary was unavailable
+    let failover = Arc::new(std::sync::Mutex::new(None::<String>));
     for iteration in 0..state.config.max_tool_iterations {
         if let Some(tx) = event_tx {
             let _ = tx.send(AgentEvent::Iteration {
                 iteration: iteration + 1,
             });
         }
+        // The last round must produce a reply rather than another tool call
+        let tool_choice = if iteration + 1 == state.config.max_tool_iterations {
+            ToolChoice::None
+        } else {
+            std::mem::take(&mut next_tool_choice)
+        };
+        let llm_started = std::time::Instant::now();
         let response = {
             let messages = messages.clone();
             let tool_defs = tool_defs.clone();
@@ -1120,7 +1642,18 @@ pub async fn process_with_agent_with_events(
             // so event_tx does not need to drive streaming here.
             match tokio::time::timeout(
                 std::time::Duration::from_secs(LLM_ROUND_TIMEOUT_SECS),
-                state.llm.send_message(&system_prompt, messages, Some(tool_defs)),
+                crate::llm::with_failover_report(
+                    {
+                        let failover = failover.clone();
+                        move |label| *failover.lock().unwrap() = Some(label)
+                    },
+                    llm.send_message_with_tool_choice(
+                        &system_prompt,
+                        messages,
+                        Some(tool_defs),
+                        tool_choice,
+                    ),
+                ),
             )
             .await
             {
@@ -1136,7 +1669,12 @@ pub async fn process_with_agent_with_events(
                 }
             }
         };
+        timer.add("llm", llm_started);
 
+        if let Some(usage) = &response.usage {
+            turn_usage.input_tokens += u64::from(usage.input_tokens);
+            turn_usage.output_tokens += u64::from(usage.output_tokens);
+        }
         let stop_reason = response.stop_reason.as_deref().unwrap_or("end_turn");
 
         if stop_reason == "end_turn" || stop_reason == "max_tokens" {
@@ -1150,29 +1688,70 @@ pub async fn process_with_agent_with_events(
                 .collect::<Vec<_>>()
                 .join("");
 
-            // Add final assistant message and save session (keep full text including thinking)
+            // Add final assistant message and save session. The thinking is stored on its own
+            // so it does not count against the context on later turns.
+            let (reply, thinking) = crate::thinking::split_thinking(&text);
             messages.push(Message {
                 role: "assistant".into(),
-                content: MessageContent::Text(text.clone()),
+                content: MessageContent::Text(reply),
             });
+            let stage_started = std::time::Instant::now();
+            crate::thinking::record(state.db.clone(), chat_id, persona_id, thinking).await;
             strip_images_for_session(&mut messages);
             if let Ok(json) = serde_json::to_string(&messages) {
+                // Redactions made during this turn also apply to the in-memory copy
+                let json = crate::tools::message_redaction::apply_pending_scrubs(chat_id, json);
                 let _ = call_blocking(state.db.clone(), move |db| db.save_session(chat_id, persona_id, &json))
                     .await;
             }
 
-            // Strip <think> blocks unless show_thinking is enabled
-            let display_text = if state.config.show_thinking {
-                text
-            } else {
-                strip_thinking(&text)
-            };
-            // Ensure we never return empty when the turn is done — user should always get a reply
-            let final_text = if display_text.trim().is_empty() {
-                "Done.".to_string()
-            } else {
-                display_text
+            turn_usage.duration_ms = turn_started.elapsed().as_millis() as u64;
+            if let Some(assignment) = &experiment {
+                crate::experiments::record_run(
+                    state.db.clone(),
+                    assignment,
+                    chat_id,
+                    persona_id,
+                    &turn_usage,
+                    iteration as u64 + 1,
+                    if stop_reason == "max_tokens" { "max_tokens" } else { "ok" },
+                )
+                .await;
+            }
+            let footer = crate::usage::record_turn(
+                &state.config,
+                state.db.clone(),
+                chat_id,
+                persona_id,
+                &turn_usage,
+            )
+            .await;
+            timer.add("db", stage_started);
+            let stage_started = std::time::Instant::now();
+            let notice = failover.lock().unwrap().take().map(|label| {
+                format!("(The primary model was unavailable; this reply came from {label}.)")
+            });
+            let footer = match (notice, footer) {
+                (Some(notice), Some(footer)) => Some(format!("{notice}\n{footer}")),
+                (notice, footer) => notice.or(footer),
             };
+            // Formatting, redaction, output filter, hooks and footer. An empty reply becomes
+            // "Done." unless it was a reaction or sticker.
+            let final_text = crate::middleware::run_outbound(
+                &state.config,
+                state.db.clone(),
+                crate::middleware::OutboundContext {
+                    chat_id,
+                    footer,
+                    allow_empty: acknowledged,
+                },
+                text,
+            )
+            .await;
+            timer.add("outbound", stage_started);
+            timer
+                .record(state.db.clone(), chat_id, persona_id, context.caller_channel)
+                .await;
             if let Some(tx) = event_tx {
                 let _ = tx.send(AgentEvent::FinalResponse {
                     text: final_text.clone(),
@@ -1226,12 +1805,31 @@ pub async fn process_with_agent_with_events(
                         });
                     }
                     info!("Executing tool: {} (iteration {})", name, iteration + 1);
+                    turn_usage.tool_calls += 1;
                     let started = std::time::Instant::now();
 
-                    // Execute tool with timeout
+                    // Execute tool with timeout, forwarding its progress lines as events
+                    let progress_tx = event_tx.cloned();
+                    let progress_name = name.clone();
                     let result = match tokio::time::timeout(
                         std::time::Duration::from_secs(TOOL_EXECUTION_TIMEOUT_SECS),
-                        state.tools.execute_with_auth(name, input.clone(), &tool_auth),
+                        crate::tools::with_progress(
+                            move |message| {
+                                if let Some(tx) = &progress_tx {
+                                    let _ = tx.send(AgentEvent::ToolProgress {
+                                        name: progress_name.clone(),
+                                        message,
+                                    });
+                                }
+                            },
+                            execute_unless_disabled(
+                                state,
+                                &disabled_tools,
+                                name,
+                                input.clone(),
+                                &tool_auth,
+                            ),
+                        ),
                     )
                     .await {
                         Ok(tool_result) => tool_result,
@@ -1276,6 +1874,13 @@ pub async fn process_with_agent_with_events(
                             error_type: result.error_type.clone(),
                         });
                     }
+                    timer.add(format!("tool:{name}"), started);
+                    if !result.is_error && matches!(name.as_str(), "react" | "send_sticker") {
+                        acknowledged = true;
+                    }
+                    if result.error_type.as_deref() == Some("approval_required") {
+                        next_tool_choice = ToolChoice::None;
+                    }
                     tool_results.push(ContentBlock::ToolResult {
                         tool_use_id: id.clone(),
                         content: result.content,
@@ -1305,6 +1910,9 @@ pub async fn process_with_agent_with_events(
                     });
                     strip_images_for_session(&mut messages);
                     if let Ok(json) = serde_json::to_string(&messages) {
+                        // Redactions made during this turn also apply to the in-memory copy
+                        let json =
+                            crate::tools::message_redaction::apply_pending_scrubs(chat_id, json);
                         let _ = call_blocking(state.db.clone(), move |db| db.save_session(chat_id, persona_id, &json))
                             .await;
                     }
@@ -1339,13 +1947,28 @@ pub async fn process_with_agent_with_events(
         });
         strip_images_for_session(&mut messages);
         if let Ok(json) = serde_json::to_string(&messages) {
+            // Redactions made during this turn also apply to the in-memory copy
+            let json = crate::tools::message_redaction::apply_pending_scrubs(chat_id, json);
             let _ =
                 call_blocking(state.db.clone(), move |db| db.save_session(chat_id, persona_id, &json)).await;
         }
+        timer
+            .record(state.db.clone(), chat_id, persona_id, context.caller_channel)
+            .await;
 
         return Ok(if text.is_empty() {
             "(no response)".into()
         } else {
+            let text = crate::middleware::run_outbound(
+                &state.config,
+                state.db.clone(),
+                crate::middleware::OutboundContext {
+                    chat_id,
+                    ..Default::default()
+                },
+                text,
+            )
+            .await;
             if let Some(tx) = event_tx {
                 let _ = tx.send(AgentEvent::FinalResponse { text: text.clone() });
             }
@@ -1363,9 +1986,28 @@ pub async fn process_with_agent_with_events(
     });
     strip_images_for_session(&mut messages);
     if let Ok(json) = serde_json::to_string(&messages) {
+        // Redactions made during this turn also apply to the in-memory copy
+        let json = crate::tools::message_redaction::apply_pending_scrubs(chat_id, json);
         let _ = call_blocking(state.db.clone(), move |db| db.save_session(chat_id, persona_id, &json)).await;
     }
 
+    if let Some(assignment) = &experiment {
+        turn_usage.duration_ms = turn_started.elapsed().as_millis() as u64;
+        crate::experiments::record_run(
+            state.db.clone(),
+            assignment,
+            chat_id,
+            persona_id,
+            &turn_usage,
+            state.config.max_tool_iterations as u64,
+            "max_iterations",
+        )
+        .await;
+    }
+    timer
+        .record(state.db.clone(), chat_id, persona_id, context.caller_channel)
+        .await;
+
     if let Some(tx) = event_tx {
         let _ = tx.send(AgentEvent::FinalResponse {
             text: max_iter_msg.clone(),
@@ -1399,7 +2041,7 @@ async fn load_messages_from_db(
     ))
 }
 
-fn build_system_prompt(
+pub(crate) fn build_system_prompt(
     bot_username: &str,
     principles_content: &str,
     agents_md_path: &str,
@@ -1421,18 +2063,27 @@ fn build_system_prompt(
 - Search file contents using regex
 - Read and write persistent memory
 - Search the web (web_search) and fetch web pages (web_fetch)
+- Exact arithmetic, unit and currency conversion (calculate) — always use this instead of computing numbers yourself
+- Translate text (translate) and manage the chat's glossary of preferred translations for household terms (translation_glossary)
 - Send messages mid-conversation (send_message) — use this to send intermediate updates
 - Schedule tasks (schedule_task, list_scheduled_tasks, pause/resume/cancel_scheduled_task, get_task_history)
 - Export chat history to markdown (export_chat)
+- Turn markdown (weekly reports, summaries, exports) into a printable PDF sent to the chat (render_pdf)
+- Catch a user up on group messages they missed since their last activity (catch_me_up), grouped by forum topic; users can also send /catchup
 - Understand images sent by users (they appear as image content blocks)
 - Delegate self-contained sub-tasks to a parallel agent (sub_agent)
 - Run the Cursor CLI agent (cursor_agent) for research or code tasks; use list_cursor_agent_runs to monitor project status and see recent run outcomes
+- Build or update a skill with cursor-agent and wait for a validated SKILL.md (build_skill); list or restore earlier versions of a skill (rollback_skill)
 - Activate agent skills (activate_skill) for specialized tasks. **You MUST implement any new tool as a skill:** create a folder under the skills directory ({skills_dir_display}/<name>/) with SKILL.md (description, when to use, how to invoke). **Store credentials and config for that tool inside the skill folder** (e.g. .env or config file there) so all personas can use it. Do not create tools only in your workspace or only document in TOOLS.md — skills are the only way to add on-demand tools.
 - Read and update tiered memory (read_tiered_memory, write_tiered_memory) — per-persona MEMORY.md with Tier 1 (long-term principles-like), Tier 2 (active projects), Tier 3 (recent focus/mood); evaluate conversation flow and update tiers when appropriate; Tier 1 only on explicit user ask, Tier 3 often (e.g. daily). Not a todo list.
 
 ## Conversation Memory
 - **Working memory (exact)**: The last few turns of this conversation (at least 2 from you and 2 from the user) are provided verbatim above. When the most recent message is from the user, treat it as often being a direct reply to your last message; use it to continue the conversation coherently.
 - **Long-term conversation recall**: Use `search_chat_history` to search ALL past messages in this chat by keyword/phrase. Always search before saying "I don't remember" or asking the user to repeat something.
+- **Topic recall**: Use `find_conversations` (about: "car insurance") to find past discussions by topic; it matches topic tags and finds conversations even when the wording differed.
+- **Custom commands**: Use `define_command` when someone wants their own slash shortcut (e.g. /standup) mapped to a prompt template; it shows up in the chat's command menu.
+- **Reactions**: For simple acknowledgements ("thanks", "done", "see you"), use `react` with an emoji (or `send_sticker` when a sticker pack is configured) and end your turn without text instead of writing a full reply.
+- **Polls**: Use `create_poll` for group decisions (optionally closes_in_minutes and on_close, e.g. "order whichever pizza wins"); use `poll` for results, to close early, or to record votes when someone replies with an option number on web/WhatsApp.
 - **Vault knowledge base**: Use the `search_vault` tool (when available) to semantically search the ORIGIN vault. Do NOT use grep, read_file, or other file tools for vault retrieval — search_vault is the correct tool. The vault is a knowledge base, NOT conversation history."#,
         skills_dir_display = skills_dir_display
     );
@@ -1504,7 +2155,7 @@ Be concise and helpful. When executing commands or tools, show the relevant resu
     prompt
 }
 
-fn history_to_claude_messages(history: &[StoredMessage], _bot_username: &str) -> Vec<Message> {
+pub(crate) fn history_to_claude_messages(history: &[StoredMessage], _bot_username: &str) -> Vec<Message> {
     let mut messages = Vec::new();
 
     for msg in history {
@@ -1651,7 +2302,7 @@ pub(crate) fn trim_to_recent_balanced(messages: Vec<Message>) -> Vec<Message> {
 #[allow(dead_code)]
 /// Strip `<think>...</think>` blocks from model output.
 /// Handles multiline content and multiple think blocks.
-fn strip_thinking(text: &str) -> String {
+pub(crate) fn strip_thinking(text: &str) -> String {
     let mut result = String::with_capacity(text.len());
     let mut rest = text;
     while let Some(start) = rest.find("<think>") {
@@ -1668,7 +2319,7 @@ fn strip_thinking(text: &str) -> String {
     result.trim().to_string()
 }
 
-/// Convert common LLM markdown (code blocks, inline code, bold, italic) to Telegram HTML
+/// Convert common LLM markdown (code blocks, inline code, links, bold, italic) to Telegram HTML
 /// so messages render cleanly. Escapes &, <, > for Telegram parse_mode=HTML.
 pub fn markdown_to_telegram_html(text: &str) -> String {
     // 1) Escape HTML so we can safely add tags
@@ -1726,6 +2377,38 @@ pub fn markdown_to_telegram_html(text: &str) -> String {
     result.push_str(rest);
     s = result;
 
+    // 3b) Links: [text](url) for web and obsidian:// URLs. `_` and `*` in the URL are
+    // percent-encoded so the bold/italic passes below leave the href intact.
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while let Some(open) = rest.find('[') {
+        result.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let link = rest.find("](").and_then(|mid| {
+            let label = &rest[1..mid];
+            let close = rest[mid + 2..].find(')')? + mid + 2;
+            let url = &rest[mid + 2..close];
+            let allowed = ["http://", "https://", "obsidian://"]
+                .iter()
+                .any(|scheme| url.starts_with(scheme));
+            (allowed && !label.contains(['[', '\n']) && !url.contains([' ', '"', '\n']))
+                .then_some((label, url, close))
+        });
+        match link {
+            Some((label, url, close)) => {
+                let href = url.replace('_', "%5F").replace('*', "%2A");
+                result.push_str(&format!("<a href=\"{href}\">{label}</a>"));
+                rest = &rest[close + 1..];
+            }
+            None => {
+                result.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    s = result;
+
     // 4) **bold** then __bold__
     for (md_open, md_close, tag) in [("**", "**", "b"), ("__", "__", "b")] {
         let mut result = String::with_capacity(s.len());
@@ -1780,25 +2463,130 @@ pub fn markdown_to_telegram_html(text: &str) -> String {
 
 #[cfg(test)]
 fn split_response_text(text: &str) -> Vec<String> {
-    const MAX_LEN: usize = 4096;
-    if text.len() <= MAX_LEN {
-        return vec![text.to_string()];
-    }
-    let mut chunks = Vec::new();
-    let mut remaining = text;
-    while !remaining.is_empty() {
-        let chunk_len = if remaining.len() <= MAX_LEN {
-            remaining.len()
-        } else {
-            remaining[..MAX_LEN].rfind('\n').unwrap_or(MAX_LEN)
-        };
-        chunks.push(remaining[..chunk_len].to_string());
-        remaining = &remaining[chunk_len..];
-        if remaining.starts_with('\n') {
-            remaining = &remaining[1..];
+    crate::channel::split_text(text, TELEGRAM_MAX_LEN)
+}
+
+/// Record forum topic names (from the topic-created service message, or the root message a
+/// topic message replies to) so digests can group messages by topic.
+async fn remember_forum_topic(state: &Arc<AppState>, msg: &teloxide::types::Message) {
+    let topic = match msg.forum_topic_created() {
+        Some(created) => Some((msg.id.0 as i64, created.name.clone())),
+        None => msg.reply_to_message().and_then(|root| {
+            root.forum_topic_created()
+                .map(|created| (root.id.0 as i64, created.name.clone()))
+        }),
+    };
+    if let Some((thread_id, name)) = topic {
+        let chat_id = msg.chat.id.0;
+        let _ = call_blocking(state.db.clone(), move |db| {
+            db.upsert_forum_topic(chat_id, thread_id, &name)
+        })
+        .await;
+    }
+}
+
+/// Remember which forum topic a stored message belongs to.
+async fn remember_message_thread(state: &Arc<AppState>, msg: &teloxide::types::Message) {
+    if !msg.is_topic_message {
+        return;
+    }
+    if let Some(thread_id) = msg.thread_id {
+        let chat_id = msg.chat.id.0;
+        let message_id = msg.id.0.to_string();
+        let thread_id = thread_id.0 .0 as i64;
+        let _ = call_blocking(state.db.clone(), move |db| {
+            db.set_message_thread(chat_id, &message_id, thread_id)
+        })
+        .await;
+    }
+}
+
+/// Telegram's message size limit, in bytes.
+const TELEGRAM_MAX_LEN: usize = 4096;
+
+/// Telegram as a [`Channel`]. Replies go out as HTML, retried as plain text when Telegram
+/// rejects the markup.
+pub struct TelegramChannel {
+    bot: Bot,
+}
+
+impl TelegramChannel {
+    pub fn new(bot: Bot) -> Self {
+        TelegramChannel { bot }
+    }
+}
+
+#[async_trait::async_trait]
+impl Channel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn max_message_len(&self) -> usize {
+        TELEGRAM_MAX_LEN
+    }
+
+    fn dialect(&self) -> FormatDialect {
+        FormatDialect::TelegramHtml
+    }
+
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String> {
+        self.bot
+            .send_message(ChatId(chat_id), chunk)
+            .parse_mode(ParseMode::Html)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn send_text(&self, chat_id: i64, text: &str) -> Result<(), String> {
+        if let Err(e) = send_response_result(&self.bot, ChatId(chat_id), text, None).await {
+            warn!("HTML send failed ({}), retrying as plain text", e);
+            send_response_plain(&self.bot, ChatId(chat_id), text, None)
+                .await
+                .map_err(|e| e.to_string())?;
         }
+        Ok(())
+    }
+
+    async fn send_attachment(
+        &self,
+        chat_id: i64,
+        path: &Path,
+        caption: Option<&str>,
+    ) -> Result<(), String> {
+        let mut req = self
+            .bot
+            .send_document(ChatId(chat_id), InputFile::file(path.to_path_buf()));
+        if let Some(c) = caption {
+            req = req.caption(c);
+        }
+        req.await
+            .map(|_| ())
+            .map_err(|e| format!("Failed to send Telegram attachment: {e}"))
+    }
+
+    async fn send_typing(&self, chat_id: i64) -> Result<(), String> {
+        self.bot
+            .send_chat_action(ChatId(chat_id), ChatAction::Typing)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn react(&self, chat_id: i64, message_id: &str, emoji: &str) -> Result<(), String> {
+        let mid: i32 = message_id
+            .parse()
+            .map_err(|_| format!("Invalid Telegram message id: {message_id}"))?;
+        self.bot
+            .set_message_reaction(ChatId(chat_id), MessageId(mid))
+            .reaction(vec![ReactionType::Emoji {
+                emoji: emoji.to_string(),
+            }])
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Failed to set Telegram reaction: {e}"))
     }
-    chunks
 }
 
 /// Send text to a chat, optionally in a forum topic. Returns Result for error handling.
@@ -1829,45 +2617,21 @@ async fn send_response_result_impl(
     thread_id: Option<ThreadId>,
     plain_text: bool,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    const MAX_LEN: usize = 4096;
-
     let formatted = if plain_text {
         text.to_string()
     } else {
         markdown_to_telegram_html(text)
     };
 
-    let send_one = |b: &Bot, cid: ChatId, txt: &str| {
-        let mut req = b.send_message(cid, txt);
+    for chunk in split_text(&formatted, TELEGRAM_MAX_LEN) {
+        let mut req = bot.send_message(chat_id, chunk);
         if !plain_text {
             req = req.parse_mode(ParseMode::Html);
         }
         if let Some(tid) = thread_id {
             req = req.message_thread_id(tid);
         }
-        req
-    };
-
-    if formatted.len() <= MAX_LEN {
-        send_one(bot, chat_id, &formatted).await?;
-        return Ok(());
-    }
-
-    let mut remaining = formatted.as_str();
-    while !remaining.is_empty() {
-        let chunk_len = if remaining.len() <= MAX_LEN {
-            remaining.len()
-        } else {
-            remaining[..MAX_LEN].rfind('\n').unwrap_or(MAX_LEN)
-        };
-
-        let chunk = &remaining[..chunk_len];
-        send_one(bot, chat_id, chunk).await?;
-        remaining = &remaining[chunk_len..];
-
-        if remaining.starts_with('\n') {
-            remaining = &remaining[1..];
-        }
+        req.await?;
     }
     Ok(())
 }
@@ -1919,6 +2683,9 @@ fn message_to_text(msg: &Message) -> String {
                     ContentBlock::Image { .. } => {
                         parts.push("[image]".into());
                     }
+                    ContentBlock::ProviderFile { filename, .. } => {
+                        parts.push(format!("[document {filename}]"));
+                    }
                 }
             }
             parts.join("\n")
@@ -2244,6 +3011,13 @@ mod tests {
             markdown_to_telegram_html("**bold** and *italic*"),
             "<b>bold</b> and <i>italic</i>"
         );
+        // Links (vault citations open in Obsidian)
+        assert_eq!(
+            markdown_to_telegram_html(
+                "[home › Plans](obsidian://open?vault=V&file=my_notes%2Fhome) or [x](javascript:alert(1))"
+            ),
+            "<a href=\"obsidian://open?vault=V&amp;file=my%5Fnotes%2Fhome\">home › Plans</a> or [x](javascript:alert(1))"
+        );
         // Fenced code block
         let input = "text\n```rust\nfn main() {}\n```\nmore";
         let out = markdown_to_telegram_html(input);
@@ -2543,6 +3317,7 @@ mod tests {
                     id: "t1".into(),
                     name: "bash".into(),
                     input: serde_json::json!({"command": "ls"}),
+                    thought_signature: None,
                 },
             ]),
         };
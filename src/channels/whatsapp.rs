@@ -1,5 +1,7 @@
+use std::path::Path;
 use std::sync::Arc;
 
+use async_trait::async_trait;
 use axum::extract::{Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
@@ -8,6 +10,8 @@ use axum::{Json, Router};
 use serde::Deserialize;
 use tracing::{error, info};
 
+use crate::channel::{split_text, Channel};
+use crate::config::Config;
 use crate::db::call_blocking;
 use crate::db::StoredMessage;
 use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
@@ -59,6 +63,7 @@ struct WhatsAppMessage {
     #[serde(rename = "type")]
     msg_type: String,
     text: Option<WhatsAppText>,
+    audio: Option<WhatsAppMedia>,
     #[allow(dead_code)]
     timestamp: Option<String>,
 }
@@ -68,6 +73,13 @@ struct WhatsAppText {
     body: String,
 }
 
+/// Media reference of an `audio` message (voice notes included).
+#[derive(Debug, Deserialize)]
+struct WhatsAppMedia {
+    id: String,
+    mime_type: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct WhatsAppContact {
     profile: Option<WhatsAppProfile>,
@@ -79,6 +91,23 @@ struct WhatsAppProfile {
     name: Option<String>,
 }
 
+/// What an incoming message carries for the agent.
+#[derive(Debug)]
+enum InboundContent<'a> {
+    Text(&'a str),
+    /// Voice note or audio file, transcribed before the agent sees it.
+    Voice(&'a WhatsAppMedia),
+}
+
+/// Content of `message`; None for message types that are not handled.
+fn inbound_content(message: &WhatsAppMessage) -> Option<InboundContent<'_>> {
+    match (message.msg_type.as_str(), &message.text, &message.audio) {
+        ("text", Some(t), _) => Some(InboundContent::Text(&t.body)),
+        ("audio", _, Some(audio)) => Some(InboundContent::Voice(audio)),
+        _ => None,
+    }
+}
+
 // --- Shared state for WhatsApp handlers ---
 
 struct WhatsAppState {
@@ -89,6 +118,16 @@ struct WhatsAppState {
     http_client: reqwest::Client,
 }
 
+impl WhatsAppState {
+    fn channel(&self) -> WhatsAppChannel {
+        WhatsAppChannel {
+            access_token: self.access_token.clone(),
+            phone_number_id: self.phone_number_id.clone(),
+            http_client: self.http_client.clone(),
+        }
+    }
+}
+
 // --- Webhook verification (GET /webhook) ---
 
 async fn verify_webhook(
@@ -130,13 +169,14 @@ async fn process_webhook(state: &WhatsAppState, payload: WebhookPayload) -> anyh
             };
 
             for message in &value.messages {
-                if message.msg_type != "text" {
-                    // Only handle text messages for now
-                    continue;
-                }
-
-                let text = match &message.text {
-                    Some(t) => t.body.clone(),
+                let text = match inbound_content(message) {
+                    Some(InboundContent::Text(text)) => text.to_string(),
+                    Some(InboundContent::Voice(audio)) => {
+                        match transcribe_voice(state, &value, message, audio).await {
+                            Some(text) => text,
+                            None => continue,
+                        }
+                    }
                     None => continue,
                 };
 
@@ -201,6 +241,149 @@ async fn process_webhook(state: &WhatsAppState, payload: WebhookPayload) -> anyh
                             )
                             .await;
                         }
+                        SlashCommand::Footer => {
+                            let resp = crate::usage::handle_footer_command(
+                                state.app_state.db.clone(),
+                                chat_id,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Undelete => {
+                            let resp = crate::session_trash::handle_undelete_command(
+                                state.app_state.db.clone(),
+                                chat_id,
+                                &text,
+                                state.app_state.config.deleted_session_retention_days,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Ack => {
+                            let resp = crate::incidents::handle_ack_command(
+                                &state.app_state.config,
+                                state.app_state.db.clone(),
+                                chat_id,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Push => {
+                            let resp = crate::push::handle_push_command(
+                                &state.app_state.config,
+                                state.app_state.db.clone(),
+                                chat_id,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Debate => {
+                            let resp = crate::roundtable::handle_debate_command(
+                                &state.app_state.config,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Thinking => {
+                            let resp = crate::thinking::handle_thinking_command(
+                                &state.app_state.config,
+                                state.app_state.db.clone(),
+                                chat_id,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Memory => {
+                            let resp = crate::memory_commands::handle_memory_command(
+                                state.app_state.db.clone(),
+                                &state.app_state.memory,
+                                chat_id,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::Forget => {
+                            let resp = crate::memory_commands::handle_forget_command(
+                                state.app_state.db.clone(),
+                                &state.app_state.memory,
+                                chat_id,
+                                &text,
+                            )
+                            .await;
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                &resp,
+                            )
+                            .await;
+                        }
+                        SlashCommand::CatchUp => {
+                            send_whatsapp_message(
+                                &state.http_client,
+                                &state.access_token,
+                                &state.phone_number_id,
+                                &message.from,
+                                "Catch-up digests are only available in group chats.",
+                            )
+                            .await;
+                        }
                         SlashCommand::Archive => {
                             let pid = call_blocking(state.app_state.db.clone(), move |db| db.get_current_persona_id(chat_id)).await.unwrap_or(0);
                             if pid == 0 {
@@ -260,14 +443,7 @@ async fn process_webhook(state: &WhatsAppState, payload: WebhookPayload) -> anyh
                     continue;
                 }
 
-                // Find sender name from contacts
-                let sender_name = value
-                    .contacts
-                    .iter()
-                    .find(|c| c.wa_id == message.from)
-                    .and_then(|c| c.profile.as_ref())
-                    .and_then(|p| p.name.clone())
-                    .unwrap_or_else(|| message.from.clone());
+                let sender_name = sender_name(&value, &message.from);
 
                 // Resolve persona
                 let persona_id = call_blocking(state.app_state.db.clone(), move |db| db.get_current_persona_id(chat_id)).await.unwrap_or(0);
@@ -275,6 +451,27 @@ async fn process_webhook(state: &WhatsAppState, payload: WebhookPayload) -> anyh
                     continue;
                 }
 
+                // Custom commands (define_command) expand into a normal prompt
+                let text = crate::custom_commands::expand_custom_command(
+                    state.app_state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await
+                .unwrap_or(text);
+
+                // Translation, moderation and inbound hooks
+                let ctx = crate::middleware::InboundContext {
+                    chat_id,
+                    chat_type: "whatsapp",
+                    sender_name: &sender_name,
+                    will_respond: true,
+                };
+                let Some(text) = crate::middleware::run_inbound(&state.app_state, ctx, text).await
+                else {
+                    continue;
+                };
+
                 // Store message in DB
                 let sender_name_for_chat = sender_name.clone();
                 let _ = call_blocking(state.app_state.db.clone(), move |db| {
@@ -302,59 +499,18 @@ async fn process_webhook(state: &WhatsAppState, payload: WebhookPayload) -> anyh
                     text.chars().take(100).collect::<String>()
                 );
 
-                // Process with Claude (reuses the same agentic loop as Telegram)
-                match crate::telegram::process_with_agent(
+                let channel: Arc<dyn Channel> = Arc::new(state.channel());
+                crate::channel::reply_with_agent(
                     &state.app_state,
+                    channel,
                     AgentRequestContext {
                         caller_channel: "whatsapp",
                         chat_id,
                         chat_type: "private",
                         persona_id,
                     },
-                    None,
-                    None,
                 )
-                .await
-                {
-                    Ok(response) => {
-                        if !response.is_empty() {
-                            send_whatsapp_message(
-                                &state.http_client,
-                                &state.access_token,
-                                &state.phone_number_id,
-                                &message.from,
-                                &response,
-                            )
-                            .await;
-
-                            // Store bot response
-                            let bot_msg = StoredMessage {
-                                id: uuid::Uuid::new_v4().to_string(),
-                                chat_id,
-                                persona_id,
-                                sender_name: state.app_state.config.bot_username.clone(),
-                                content: response,
-                                is_from_bot: true,
-                                timestamp: chrono::Utc::now().to_rfc3339(),
-                            };
-                            let _ = call_blocking(state.app_state.db.clone(), move |db| {
-                                db.store_message(&bot_msg)
-                            })
-                            .await;
-                        }
-                    }
-                    Err(e) => {
-                        error!("Error processing WhatsApp message: {e}");
-                        send_whatsapp_message(
-                            &state.http_client,
-                            &state.access_token,
-                            &state.phone_number_id,
-                            &message.from,
-                            &format!("Error: {e}"),
-                        )
-                        .await;
-                    }
-                }
+                .await;
             }
         }
     }
@@ -362,70 +518,301 @@ async fn process_webhook(state: &WhatsAppState, payload: WebhookPayload) -> anyh
     Ok(())
 }
 
+/// Display name of `from`, from the webhook's contacts.
+fn sender_name(value: &WebhookValue, from: &str) -> String {
+    value
+        .contacts
+        .iter()
+        .find(|c| c.wa_id == from)
+        .and_then(|c| c.profile.as_ref())
+        .and_then(|p| p.name.clone())
+        .unwrap_or_else(|| from.to_string())
+}
+
+/// Download a voice note and transcribe it into the message text the agent sees. Returns None
+/// (after telling the sender) when no Whisper backend is configured.
+async fn transcribe_voice(
+    state: &WhatsAppState,
+    value: &WebhookValue,
+    message: &WhatsAppMessage,
+    audio: &WhatsAppMedia,
+) -> Option<String> {
+    let config = &state.app_state.config;
+    if !crate::transcribe::is_configured(config) {
+        send_whatsapp_message(
+            &state.http_client,
+            &state.access_token,
+            &state.phone_number_id,
+            &message.from,
+            "Voice messages not supported (no Whisper backend configured)",
+        )
+        .await;
+        return None;
+    }
+    let sender = crate::telegram::sanitize_xml(&sender_name(value, &message.from));
+    let download =
+        download_whatsapp_media(&state.http_client, &state.access_token, &audio.id).await;
+    let result = match download {
+        Ok((bytes, mime)) => {
+            let mime = audio.mime_type.clone().unwrap_or(mime);
+            crate::transcribe::transcribe(config, &bytes, &mime).await
+        }
+        Err(e) => Err(e),
+    };
+    Some(match result {
+        Ok(transcription) => format!(
+            "[voice message from {sender}]: {}",
+            crate::telegram::sanitize_xml(&transcription)
+        ),
+        Err(e) => {
+            error!("WhatsApp voice transcription failed: {e}");
+            format!("[voice message from {sender}]: [transcription failed: {e}]")
+        }
+    })
+}
+
+/// Largest voice message downloaded for transcription (the OpenAI Whisper upload limit).
+const MAX_MEDIA_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Read a response body of at most `max` bytes, refusing larger ones before and while reading.
+async fn read_capped(mut resp: reqwest::Response, max: u64) -> Result<Vec<u8>, String> {
+    if let Some(len) = resp.content_length().filter(|len| *len > max) {
+        return Err(format!("media is {len} bytes, over the {max} byte limit"));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        if bytes.len() as u64 + chunk.len() as u64 > max {
+            return Err(format!("media is over the {max} byte limit"));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Fetch media by id: the Graph API returns a short-lived URL that needs the same token.
+async fn download_whatsapp_media(
+    client: &reqwest::Client,
+    access_token: &str,
+    media_id: &str,
+) -> Result<(Vec<u8>, String), String> {
+    let meta: serde_json::Value = client
+        .get(format!("https://graph.facebook.com/v23.0/{media_id}"))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to look up WhatsApp media: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid WhatsApp media response: {e}"))?;
+    let url = meta
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "WhatsApp media response has no url".to_string())?;
+    let mime = meta
+        .get("mime_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("audio/ogg")
+        .to_string();
+    let resp = client
+        .get(url)
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download WhatsApp media: {e}"))?;
+    let bytes = read_capped(resp, MAX_MEDIA_BYTES)
+        .await
+        .map_err(|e| format!("Failed to download WhatsApp media: {e}"))?;
+    Ok((bytes, mime))
+}
+
 // --- Send message via WhatsApp Cloud API ---
 
-async fn send_whatsapp_message(
+const WHATSAPP_MAX_LEN: usize = 4096;
+
+async fn post_whatsapp_text(
     client: &reqwest::Client,
     access_token: &str,
     phone_number_id: &str,
     to: &str,
     text: &str,
-) {
+) -> Result<(), String> {
     let url = format!("https://graph.facebook.com/v21.0/{phone_number_id}/messages");
+    let body = serde_json::json!({
+        "messaging_product": "whatsapp",
+        "to": to,
+        "text": { "body": text }
+    });
+    let resp = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send WhatsApp message: {e}"))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("WhatsApp API error {status}: {body}"));
+    }
+    Ok(())
+}
 
-    // Split long messages (WhatsApp limit ~4096 chars)
-    const MAX_LEN: usize = 4096;
-    let chunks = split_text(text, MAX_LEN);
+pub(crate) async fn send_whatsapp_message(
+    client: &reqwest::Client,
+    access_token: &str,
+    phone_number_id: &str,
+    to: &str,
+    text: &str,
+) {
+    for chunk in split_text(text, WHATSAPP_MAX_LEN) {
+        if let Err(e) = post_whatsapp_text(client, access_token, phone_number_id, to, &chunk).await
+        {
+            error!("{e}");
+        }
+    }
+}
 
-    for chunk in chunks {
-        let body = serde_json::json!({
-            "messaging_product": "whatsapp",
-            "to": to,
-            "text": { "body": chunk }
-        });
+/// WhatsApp Cloud API as a [`Channel`]; chat ids are the recipients' phone numbers.
+pub struct WhatsAppChannel {
+    access_token: String,
+    phone_number_id: String,
+    http_client: reqwest::Client,
+}
 
-        match client
-            .post(&url)
-            .header("Authorization", format!("Bearer {access_token}"))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-        {
-            Ok(resp) => {
-                if !resp.status().is_success() {
-                    let status = resp.status();
-                    let body = resp.text().await.unwrap_or_default();
-                    error!("WhatsApp API error {status}: {body}");
-                }
-            }
-            Err(e) => {
-                error!("Failed to send WhatsApp message: {e}");
-            }
+impl WhatsAppChannel {
+    pub fn new(access_token: String, phone_number_id: String) -> Self {
+        WhatsAppChannel {
+            access_token,
+            phone_number_id,
+            http_client: reqwest::Client::new(),
         }
     }
+
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let access_token = config
+            .whatsapp_access_token
+            .as_deref()
+            .filter(|v| !v.trim().is_empty())?;
+        let phone_number_id = config
+            .whatsapp_phone_number_id
+            .as_deref()
+            .filter(|v| !v.trim().is_empty())?;
+        Some(WhatsAppChannel::new(
+            access_token.to_string(),
+            phone_number_id.to_string(),
+        ))
+    }
 }
 
-fn split_text(text: &str, max_len: usize) -> Vec<String> {
-    if text.len() <= max_len {
-        return vec![text.to_string()];
+#[async_trait]
+impl Channel for WhatsAppChannel {
+    fn name(&self) -> &'static str {
+        "whatsapp"
+    }
+
+    fn max_message_len(&self) -> usize {
+        WHATSAPP_MAX_LEN
+    }
+
+    async fn send_chunk(&self, chat_id: i64, chunk: &str) -> Result<(), String> {
+        post_whatsapp_text(
+            &self.http_client,
+            &self.access_token,
+            &self.phone_number_id,
+            &chat_id.to_string(),
+            chunk,
+        )
+        .await
     }
 
-    let mut chunks = Vec::new();
-    let mut remaining = text;
-    while !remaining.is_empty() {
-        let chunk_len = if remaining.len() <= max_len {
-            remaining.len()
-        } else {
-            remaining[..max_len].rfind('\n').unwrap_or(max_len)
-        };
-        chunks.push(remaining[..chunk_len].to_string());
-        remaining = &remaining[chunk_len..];
-        if remaining.starts_with('\n') {
-            remaining = &remaining[1..];
+    async fn send_attachment(
+        &self,
+        chat_id: i64,
+        path: &Path,
+        caption: Option<&str>,
+    ) -> Result<(), String> {
+        let filename = path
+            .file_name()
+            .and_then(|v| v.to_str())
+            .unwrap_or("attachment.bin")
+            .to_string();
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| format!("Failed to read attachment file: {e}"))?;
+
+        let upload_url = format!(
+            "https://graph.facebook.com/v23.0/{}/media",
+            self.phone_number_id
+        );
+        let form = reqwest::multipart::Form::new()
+            .text("messaging_product", "whatsapp")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(bytes)
+                    .file_name(filename.clone())
+                    .mime_str("application/octet-stream")
+                    .map_err(|e| format!("Invalid attachment mime: {e}"))?,
+            );
+        let upload_resp = self
+            .http_client
+            .post(upload_url)
+            .bearer_auth(&self.access_token)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload WhatsApp media: {e}"))?;
+        if !upload_resp.status().is_success() {
+            let status = upload_resp.status();
+            let body = upload_resp.text().await.unwrap_or_default();
+            return Err(format!(
+                "Failed to upload WhatsApp media: HTTP {status} {}",
+                body.chars().take(300).collect::<String>()
+            ));
+        }
+        let upload_json: serde_json::Value = upload_resp
+            .json()
+            .await
+            .map_err(|e| format!("Invalid WhatsApp media upload response: {e}"))?;
+        let media_id = upload_json
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "WhatsApp media upload did not return id".to_string())?;
+
+        let mut document = serde_json::json!({ "id": media_id, "filename": filename });
+        if let Some(c) = caption {
+            document["caption"] = serde_json::json!(c);
+        }
+        let payload = serde_json::json!({
+            "messaging_product": "whatsapp",
+            "to": chat_id.to_string(),
+            "type": "document",
+            "document": document,
+        });
+        let send_url = format!(
+            "https://graph.facebook.com/v23.0/{}/messages",
+            self.phone_number_id
+        );
+        let send_resp = self
+            .http_client
+            .post(send_url)
+            .bearer_auth(&self.access_token)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send WhatsApp attachment: {e}"))?;
+        if !send_resp.status().is_success() {
+            let status = send_resp.status();
+            let body = send_resp.text().await.unwrap_or_default();
+            return Err(format!(
+                "Failed to send WhatsApp attachment: HTTP {status} {}",
+                body.chars().take(300).collect::<String>()
+            ));
         }
+        Ok(())
     }
-    chunks
 }
 
 // --- Start the WhatsApp webhook server ---
@@ -465,3 +852,71 @@ pub async fn start_whatsapp_server(
         error!("WhatsApp webhook server error: {e}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inbound_content_routes_voice_to_transcription() {
+        let payload: WebhookPayload = serde_json::from_value(serde_json::json!({
+            "entry": [{ "changes": [{ "value": { "messages": [
+                { "from": "15551234567", "id": "m1", "type": "text", "text": { "body": "hi" } },
+                {
+                    "from": "15551234567", "id": "m2", "type": "audio",
+                    "audio": { "id": "media-7", "mime_type": "audio/ogg; codecs=opus" }
+                },
+                { "from": "15551234567", "id": "m3", "type": "image", "image": { "id": "x" } },
+                { "from": "15551234567", "id": "m4", "type": "audio" }
+            ] } }] }]
+        }))
+        .unwrap();
+        let value = payload.entry[0].changes[0].value.as_ref().unwrap();
+        let routed: Vec<_> = value.messages.iter().map(inbound_content).collect();
+
+        assert!(matches!(routed[0], Some(InboundContent::Text("hi"))));
+        match &routed[1] {
+            Some(InboundContent::Voice(audio)) => {
+                assert_eq!(audio.id, "media-7");
+                assert_eq!(audio.mime_type.as_deref(), Some("audio/ogg; codecs=opus"));
+            }
+            other => panic!("expected a voice message, got {other:?}"),
+        }
+        assert!(routed[2].is_none());
+        assert!(routed[3].is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_capped_refuses_large_media() {
+        use axum::body::Body;
+
+        let app = Router::new()
+            .route("/sized", get(|| async { vec![7u8; 2048] }))
+            .route(
+                "/streamed",
+                get(|| async {
+                    let chunks = (0..4).map(|_| Ok::<_, std::io::Error>(vec![7u8; 512]));
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        let client = reqwest::Client::new();
+        let fetch = |path: &str| client.get(format!("http://{addr}{path}")).send();
+
+        // Refused from Content-Length
+        let resp = fetch("/sized").await.unwrap();
+        assert_eq!(resp.content_length(), Some(2048));
+        assert!(read_capped(resp, 1024).await.unwrap_err().contains("2048 bytes"));
+        // Refused while streaming when no length is announced
+        let resp = fetch("/streamed").await.unwrap();
+        assert_eq!(resp.content_length(), None);
+        assert!(read_capped(resp, 1024).await.is_err());
+
+        let resp = fetch("/streamed").await.unwrap();
+        assert_eq!(read_capped(resp, 2048).await.unwrap().len(), 2048);
+    }
+}
@@ -0,0 +1,411 @@
+//! Voice bridge over the Wyoming protocol. Home Assistant connects to this as a `handle`
+//! (conversation) service: voice satellites send a `transcript`, the agent answers in the voice
+//! chat, and the reply goes back as `handled` for Home Assistant to speak.
+//!
+//! Wire format: each event is a JSON header line (`type`, optional inline `data`, `data_length`,
+//! `payload_length`), followed by `data_length` bytes of JSON data and `payload_length` bytes of
+//! binary payload (audio, unused here).
+
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, error, info, warn};
+
+use crate::channel::store_bot_message;
+use crate::config::WyomingConfig;
+use crate::db::{call_blocking, StoredMessage};
+use crate::telegram::{AgentRequestContext, AppState};
+
+const WYOMING_VERSION: &str = "1.5.4";
+/// Largest data section accepted; transcripts are small.
+const MAX_DATA_LEN: usize = 1024 * 1024;
+/// Largest payload skipped before the connection is dropped.
+const MAX_PAYLOAD_LEN: u64 = 16 * 1024 * 1024;
+
+/// Appended to the system prompt for spoken requests.
+pub const SPOKEN_REPLY_HINT: &str = "This request was spoken to a voice assistant and your reply will be read aloud. Answer in one to three short sentences of plain speech: no markdown, lists, tables, code, links or emoji.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WyomingEvent {
+    pub event_type: String,
+    pub data: serde_json::Value,
+}
+
+/// Read the next event, or `None` at end of stream. Binary payloads are skipped.
+pub async fn read_event<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> std::io::Result<Option<WyomingEvent>> {
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+    let header: serde_json::Value =
+        serde_json::from_str(line.trim()).map_err(|e| invalid(format!("bad header: {e}")))?;
+    let event_type = header
+        .get("type")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid("header without type".into()))?
+        .to_string();
+    let mut data = header.get("data").cloned().unwrap_or_else(|| json!({}));
+
+    let data_len = header
+        .get("data_length")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as usize;
+    if data_len > MAX_DATA_LEN {
+        return Err(invalid(format!(
+            "data section too large ({data_len} bytes)"
+        )));
+    }
+    if data_len > 0 {
+        let mut buf = vec![0u8; data_len];
+        reader.read_exact(&mut buf).await?;
+        let extra: serde_json::Value =
+            serde_json::from_slice(&buf).map_err(|e| invalid(format!("bad data: {e}")))?;
+        match (data.as_object_mut(), extra) {
+            (Some(fields), serde_json::Value::Object(extra)) => fields.extend(extra),
+            (_, extra) => data = extra,
+        }
+    }
+
+    let payload_len = header
+        .get("payload_length")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    if payload_len > MAX_PAYLOAD_LEN {
+        return Err(invalid(format!("payload too large ({payload_len} bytes)")));
+    }
+    if payload_len > 0 {
+        tokio::io::copy(&mut reader.take(payload_len), &mut tokio::io::sink()).await?;
+    }
+    Ok(Some(WyomingEvent { event_type, data }))
+}
+
+/// Serialize an event with its data in a separate section.
+pub fn encode_event(event_type: &str, data: &serde_json::Value) -> Vec<u8> {
+    let data_bytes = data.to_string().into_bytes();
+    let header = json!({
+        "type": event_type,
+        "version": WYOMING_VERSION,
+        "data_length": data_bytes.len(),
+    });
+    let mut out = header.to_string().into_bytes();
+    out.push(b'\n');
+    out.extend(data_bytes);
+    out
+}
+
+async fn write_event<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    event_type: &str,
+    data: &serde_json::Value,
+) -> std::io::Result<()> {
+    writer.write_all(&encode_event(event_type, data)).await?;
+    writer.flush().await
+}
+
+/// `info` reply to `describe`: one handle program for the bot.
+pub fn info_event(bot_username: &str, languages: &[String]) -> serde_json::Value {
+    let attribution = json!({"name": "MicroClaw", "url": env!("CARGO_PKG_REPOSITORY")});
+    let description = format!("{bot_username} agent (skills, tools and memory)");
+    json!({
+        "handle": [{
+            "name": "microclaw",
+            "attribution": attribution,
+            "installed": true,
+            "description": description,
+            "version": env!("CARGO_PKG_VERSION"),
+            "models": [{
+                "name": bot_username,
+                "attribution": attribution,
+                "installed": true,
+                "description": description,
+                "version": env!("CARGO_PKG_VERSION"),
+                "languages": languages,
+            }],
+        }]
+    })
+}
+
+/// Chat the spoken requests run in.
+pub fn voice_chat_id(config: &WyomingConfig) -> i64 {
+    config.chat_id.unwrap_or_else(|| {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in b"wyoming:voice" {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        ((hash >> 1) as i64).max(1)
+    })
+}
+
+/// Make a reply fit for text-to-speech: drop markdown markup, code fences and link targets.
+pub fn speakable(text: &str) -> String {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("```") || line.is_empty() {
+            continue;
+        }
+        let line = line.trim_start_matches('#').trim_start();
+        let line = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .unwrap_or(line);
+        // [label](url) -> label
+        let mut out = String::new();
+        let mut rest = line;
+        while let Some(start) = rest.find('[') {
+            let Some(mid) = rest[start..].find("](").map(|i| start + i) else {
+                break;
+            };
+            let Some(end) = rest[mid..].find(')').map(|i| mid + i) else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+            out.push_str(&rest[start + 1..mid]);
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        let out: String = out
+            .chars()
+            .filter(|c| !matches!(c, '*' | '`' | '_'))
+            .collect();
+        lines.push(out.trim().to_string());
+    }
+    lines.join(" ")
+}
+
+/// Run one spoken request through the agent in the voice chat and return the reply to speak.
+async fn answer(state: &Arc<AppState>, wyoming: &WyomingConfig, text: &str) -> String {
+    let chat_id = voice_chat_id(wyoming);
+    let persona_id = call_blocking(state.db.clone(), move |db| {
+        db.upsert_chat(chat_id, Some("voice"), "web")?;
+        db.get_current_persona_id(chat_id)
+    })
+    .await
+    .unwrap_or(0);
+    if persona_id == 0 {
+        return "Sorry, I couldn't open the voice chat.".into();
+    }
+
+    let ctx = crate::middleware::InboundContext {
+        chat_id,
+        chat_type: "wyoming",
+        sender_name: "voice",
+        will_respond: true,
+    };
+    let Some(text) = crate::middleware::run_inbound(state, ctx, text.to_string()).await else {
+        return String::new();
+    };
+    let stored = StoredMessage {
+        id: format!("wyoming-{}", uuid::Uuid::new_v4()),
+        chat_id,
+        persona_id,
+        sender_name: "voice".into(),
+        content: text,
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(state.db.clone(), move |db| db.store_message(&stored)).await;
+
+    match crate::telegram::process_with_agent(
+        state,
+        AgentRequestContext {
+            caller_channel: "wyoming",
+            chat_id,
+            chat_type: "private",
+            persona_id,
+        },
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(response) => {
+            if response.is_empty() {
+                return String::new();
+            }
+            let spoken = speakable(&response);
+            if let Err(e) = store_bot_message(
+                state.db.clone(),
+                &state.config.bot_username,
+                chat_id,
+                persona_id,
+                response,
+            )
+            .await
+            {
+                error!("{e}");
+            }
+            spoken
+        }
+        Err(e) => {
+            error!("Error processing voice request: {e}");
+            "Sorry, something went wrong.".into()
+        }
+    }
+}
+
+async fn handle_connection(state: Arc<AppState>, wyoming: Arc<WyomingConfig>, stream: TcpStream) {
+    let peer = stream
+        .peer_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_default();
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    loop {
+        let event = match read_event(&mut reader).await {
+            Ok(Some(event)) => event,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Wyoming: dropping connection from {peer}: {e}");
+                break;
+            }
+        };
+        let result = match event.event_type.as_str() {
+            "describe" => {
+                let info = info_event(&state.config.bot_username, &wyoming.languages);
+                write_event(&mut writer, "info", &info).await
+            }
+            "ping" => write_event(&mut writer, "pong", &event.data).await,
+            "transcript" => {
+                let text = event
+                    .data
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
+                let context = event.data.get("context").cloned();
+                if text.is_empty() {
+                    write_event(&mut writer, "not-handled", &json!({"context": context})).await
+                } else {
+                    info!(
+                        "Voice request: {}",
+                        text.chars().take(100).collect::<String>()
+                    );
+                    let reply = answer(&state, &wyoming, &text).await;
+                    write_event(
+                        &mut writer,
+                        "handled",
+                        &json!({"text": reply, "context": context}),
+                    )
+                    .await
+                }
+            }
+            other => {
+                debug!("Wyoming: ignoring {other} event");
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            warn!("Wyoming: write to {peer} failed: {e}");
+            break;
+        }
+    }
+}
+
+/// Listen for Wyoming connections until the process exits.
+pub async fn start_wyoming_server(app_state: Arc<AppState>, wyoming: WyomingConfig) {
+    let addr = format!("{}:{}", wyoming.host, wyoming.port);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Failed to bind Wyoming voice bridge on {addr}: {e}");
+            return;
+        }
+    };
+    info!("Wyoming voice bridge listening on {addr}");
+    let wyoming = Arc::new(wyoming);
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(
+                    app_state.clone(),
+                    wyoming.clone(),
+                    stream,
+                ));
+            }
+            Err(e) => warn!("Wyoming accept failed: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_and_encode_events() {
+        let mut raw = Vec::new();
+        raw.extend(b"{\"type\": \"describe\"}\n");
+        raw.extend(b"\n");
+        let data = br#"{"text": "turn on the kitchen lights"}"#;
+        raw.extend(
+            format!(
+                "{{\"type\": \"transcript\", \"data\": {{\"language\": \"en\"}}, \"data_length\": {}, \"payload_length\": 4}}\n",
+                data.len()
+            )
+            .as_bytes(),
+        );
+        raw.extend(data);
+        raw.extend(b"\x00\x01\x02\x03");
+        raw.extend(encode_event("ping", &json!({"text": "x"})));
+
+        let mut reader = BufReader::new(&raw[..]);
+        let describe = read_event(&mut reader).await.unwrap().unwrap();
+        assert_eq!(describe.event_type, "describe");
+        let transcript = read_event(&mut reader).await.unwrap().unwrap();
+        assert_eq!(transcript.event_type, "transcript");
+        assert_eq!(
+            transcript.data,
+            json!({"language": "en", "text": "turn on the kitchen lights"})
+        );
+        // The payload was skipped and the encoded event reads back
+        let ping = read_event(&mut reader).await.unwrap().unwrap();
+        assert_eq!(ping.data, json!({"text": "x"}));
+        assert!(read_event(&mut reader).await.unwrap().is_none());
+
+        let mut reader = BufReader::new(&b"not json\n"[..]);
+        assert!(read_event(&mut reader).await.is_err());
+    }
+
+    #[test]
+    fn test_info_and_speakable() {
+        let info = info_event("homebot", &["en".into(), "de".into()]);
+        let program = &info["handle"][0];
+        assert_eq!(program["name"], "microclaw");
+        assert_eq!(program["models"][0]["languages"], json!(["en", "de"]));
+
+        assert_eq!(
+            speakable("## Lights\n- **Kitchen** is on\n- See [the dashboard](http://ha.local)\n```\ncode\n```"),
+            "Lights Kitchen is on See the dashboard code"
+        );
+        let config = WyomingConfig {
+            host: "0.0.0.0".into(),
+            port: 10800,
+            chat_id: None,
+            languages: vec![],
+        };
+        assert!(voice_chat_id(&config) > 0);
+        assert_eq!(
+            voice_chat_id(&WyomingConfig {
+                chat_id: Some(7),
+                ..config
+            }),
+            7
+        );
+    }
+}
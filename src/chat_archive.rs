@@ -0,0 +1,295 @@
+//! Cold storage for chats: a chat whose newest message is older than
+//! `chat_archive.inactive_months` has its messages and saved sessions written to
+//! `<runtime>/archive/chat_<id>.json.gz` and removed from the database, so the SQLite file stays
+//! small over years of use. Conversation summaries, memory, settings and scheduled tasks stay.
+//! The agent loop and the web history call `rehydrate` first, which puts an archived chat back
+//! as it was; deleting a chat from the web UI restores it first so the normal deletion applies.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::db::{call_blocking, ChatArchive, ChatSessionRow, Database, StoredMessage};
+use crate::telegram::AppState;
+
+/// The scheduler calls `archive_inactive` every minute; the scan runs at most this often.
+const SCAN_EVERY: Duration = Duration::from_secs(3600);
+/// Chats archived per scan.
+const MAX_PER_SCAN: usize = 20;
+
+static LAST_SCAN: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ArchivedMessage {
+    id: String,
+    persona_id: i64,
+    sender_name: String,
+    content: String,
+    is_from_bot: bool,
+    timestamp: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ArchivedSession {
+    persona_id: i64,
+    messages_json: String,
+    updated_at: String,
+}
+
+/// Contents of an archive file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ArchiveFile {
+    chat_id: i64,
+    archived_at: String,
+    messages: Vec<ArchivedMessage>,
+    sessions: Vec<ArchivedSession>,
+}
+
+/// Archive file of a chat, relative to the runtime directory (as recorded in the database).
+fn archive_rel_path(chat_id: i64) -> PathBuf {
+    Path::new("archive").join(format!("chat_{chat_id}.json.gz"))
+}
+
+pub fn archive_path(config: &Config, chat_id: i64) -> PathBuf {
+    Path::new(&config.runtime_data_dir()).join(archive_rel_path(chat_id))
+}
+
+fn write_archive(path: &Path, archive: &ArchiveFile) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_vec(archive).map_err(|e| e.to_string())?;
+    // Written next to the target and renamed, so a crash never leaves half an archive
+    let tmp = path.with_extension("gz.tmp");
+    let file = std::fs::File::create(&tmp).map_err(|e| e.to_string())?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .and_then(|file| file.sync_all())
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&tmp);
+            e.to_string()
+        })
+}
+
+fn read_archive(path: &Path) -> Result<ArchiveFile, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut json = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut json)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_slice(&json).map_err(|e| e.to_string())
+}
+
+/// Move one chat's messages and sessions to its archive file. Returns the number of messages.
+fn archive_chat(config: &Config, db: &Database, chat_id: i64) -> Result<usize, String> {
+    let messages = db.get_chat_messages(chat_id).map_err(|e| e.to_string())?;
+    let sessions = db.get_chat_sessions(chat_id).map_err(|e| e.to_string())?;
+    let Some(last_message_at) = messages.last().map(|m| m.timestamp.clone()) else {
+        return Ok(0);
+    };
+    let sessions_until = sessions.iter().map(|s| s.updated_at.clone()).max();
+    let archived_at = chrono::Utc::now().to_rfc3339();
+    let path = archive_path(config, chat_id);
+    write_archive(
+        &path,
+        &ArchiveFile {
+            chat_id,
+            archived_at: archived_at.clone(),
+            messages: messages
+                .iter()
+                .map(|m| ArchivedMessage {
+                    id: m.id.clone(),
+                    persona_id: m.persona_id,
+                    sender_name: m.sender_name.clone(),
+                    content: m.content.clone(),
+                    is_from_bot: m.is_from_bot,
+                    timestamp: m.timestamp.clone(),
+                })
+                .collect(),
+            sessions: sessions
+                .into_iter()
+                .map(|s| ArchivedSession {
+                    persona_id: s.persona_id,
+                    messages_json: s.messages_json,
+                    updated_at: s.updated_at,
+                })
+                .collect(),
+        },
+    )?;
+    // Only rows up to what was written are deleted; a message arriving meanwhile stays
+    db.archive_chat_data(
+        &ChatArchive {
+            chat_id,
+            path: archive_rel_path(chat_id).to_string_lossy().to_string(),
+            message_count: messages.len() as i64,
+            last_message_at,
+            archived_at,
+        },
+        sessions_until.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(messages.len())
+}
+
+/// Archive chats inactive for `inactive_months` (run by the scheduler; scans at most hourly).
+pub async fn archive_inactive(state: &Arc<AppState>) {
+    let Some(settings) = state.config.chat_archive.clone() else {
+        return;
+    };
+    {
+        let mut last = LAST_SCAN.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < SCAN_EVERY) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    let cutoff = (chrono::Utc::now()
+        - chrono::Duration::days(settings.inactive_months as i64 * 30))
+    .to_rfc3339();
+    let config = state.config.clone();
+    let result = call_blocking(state.db.clone(), move |db| {
+        let mut archived = 0;
+        for chat_id in db.chats_inactive_since(&cutoff, MAX_PER_SCAN)? {
+            match archive_chat(&config, db, chat_id) {
+                Ok(n) => {
+                    info!("Archived chat {chat_id} ({n} messages)");
+                    archived += 1;
+                }
+                Err(e) => warn!("Failed to archive chat {chat_id}: {e}"),
+            }
+        }
+        if archived > 0 && settings.vacuum {
+            db.vacuum()?;
+        }
+        Ok(archived)
+    })
+    .await;
+    if let Err(e) = result {
+        error!("Chat archiving failed: {e}");
+    }
+}
+
+/// Bring an archived chat back into the database from under `runtime_dir`. Returns the number
+/// of messages restored (0 when the chat is not archived). A missing or unreadable archive file
+/// is an error and the chat stays recorded as archived, so restoring the file later still works.
+pub async fn rehydrate(
+    db: Arc<Database>,
+    runtime_dir: &str,
+    chat_id: i64,
+) -> Result<usize, String> {
+    // Located from the runtime dir, not a stored path, so a moved workspace still finds it
+    let path = Path::new(runtime_dir).join(archive_rel_path(chat_id));
+    call_blocking(db, move |db| {
+        if db.get_chat_archive(chat_id)?.is_none() {
+            return Ok(Ok(0));
+        }
+        let file = match read_archive(&path) {
+            Ok(file) => file,
+            Err(e) => return Ok(Err(format!("failed to read {}: {e}", path.display()))),
+        };
+        let messages: Vec<StoredMessage> = file
+            .messages
+            .into_iter()
+            .map(|m| StoredMessage {
+                id: m.id,
+                chat_id,
+                persona_id: m.persona_id,
+                sender_name: m.sender_name,
+                content: m.content,
+                is_from_bot: m.is_from_bot,
+                timestamp: m.timestamp,
+            })
+            .collect();
+        let sessions: Vec<ChatSessionRow> = file
+            .sessions
+            .into_iter()
+            .map(|s| ChatSessionRow {
+                persona_id: s.persona_id,
+                messages_json: s.messages_json,
+                updated_at: s.updated_at,
+            })
+            .collect();
+        db.restore_chat_data(chat_id, &messages, &sessions)?;
+        let _ = std::fs::remove_file(&path);
+        info!(
+            "Restored archived chat {chat_id} ({} messages)",
+            messages.len()
+        );
+        Ok(Ok(messages.len()))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &Path) -> Config {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = dir.to_string_lossy().to_string();
+        config
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_rehydrate() {
+        let dir = std::env::temp_dir().join(format!("microclaw_archive_{}", uuid::Uuid::new_v4()));
+        let config = config(&dir);
+        let db = Arc::new(Database::new(&config.runtime_data_dir()).unwrap());
+        for (id, ts) in [("1", "2024-03-01T10:00:00Z"), ("2", "2024-03-01T10:01:00Z")] {
+            db.store_message(&StoredMessage {
+                id: id.into(),
+                chat_id: 5,
+                persona_id: 1,
+                sender_name: "alice".into(),
+                content: format!("message {id}"),
+                is_from_bot: id == "2",
+                timestamp: ts.into(),
+            })
+            .unwrap();
+        }
+        db.save_session(5, 1, r#"[{"role":"user","content":"hi"}]"#)
+            .unwrap();
+
+        assert_eq!(archive_chat(&config, &db, 5).unwrap(), 2);
+        assert!(archive_path(&config, 5).exists());
+        assert!(db.get_chat_messages(5).unwrap().is_empty());
+        assert!(db.load_session(5, 1).unwrap().is_none());
+
+        assert_eq!(
+            db.get_chat_archive(5).unwrap().unwrap().path,
+            archive_rel_path(5).to_string_lossy()
+        );
+
+        // A missing file is an error, and the chat stays archived
+        let runtime_dir = config.runtime_data_dir();
+        let moved = dir.join("chat_5.json.gz");
+        std::fs::rename(archive_path(&config, 5), &moved).unwrap();
+        assert!(rehydrate(db.clone(), &runtime_dir, 5).await.is_err());
+        assert!(db.get_chat_archive(5).unwrap().is_some());
+        std::fs::rename(&moved, archive_path(&config, 5)).unwrap();
+
+        assert_eq!(rehydrate(db.clone(), &runtime_dir, 5).await.unwrap(), 2);
+        let messages = db.get_chat_messages(5).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[1].is_from_bot);
+        assert!(db.load_session(5, 1).unwrap().is_some());
+        assert!(!archive_path(&config, 5).exists());
+        // Not archived any more
+        assert_eq!(rehydrate(db, &runtime_dir, 5).await.unwrap(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -5,6 +5,76 @@ pub struct ToolDefinition {
     pub name: String,
     pub description: String,
     pub input_schema: serde_json::Value,
+    /// Example inputs (few-shot). Not sent as a field: providers that benefit get them appended
+    /// to the description, and `tool_help` shows them.
+    #[serde(default, skip_serializing)]
+    pub examples: Vec<serde_json::Value>,
+}
+
+impl ToolDefinition {
+    /// The description followed by the example inputs, one JSON object per line.
+    pub fn description_with_examples(&self) -> String {
+        if self.examples.is_empty() {
+            return self.description.clone();
+        }
+        let mut out = format!("{}\n\nExample inputs:", self.description);
+        for example in &self.examples {
+            out.push_str(&format!("\n{example}"));
+        }
+        out
+    }
+}
+
+/// Whether (and which) tool the model must call in a round.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides (provider default).
+    #[default]
+    Auto,
+    /// Some tool must be called.
+    Any,
+    /// This tool must be called.
+    Tool(String),
+    /// No tool may be called; the model answers in text.
+    None,
+}
+
+impl ToolChoice {
+    /// Anthropic `tool_choice`; `None` for the default.
+    pub fn anthropic(&self) -> Option<serde_json::Value> {
+        match self {
+            ToolChoice::Auto => None,
+            ToolChoice::Any => Some(serde_json::json!({"type": "any"})),
+            ToolChoice::Tool(name) => Some(serde_json::json!({"type": "tool", "name": name})),
+            ToolChoice::None => Some(serde_json::json!({"type": "none"})),
+        }
+    }
+
+    /// OpenAI-compatible `tool_choice`; `None` for the default.
+    pub fn openai(&self) -> Option<serde_json::Value> {
+        match self {
+            ToolChoice::Auto => None,
+            ToolChoice::Any => Some(serde_json::json!("required")),
+            ToolChoice::Tool(name) => Some(serde_json::json!({
+                "type": "function",
+                "function": {"name": name}
+            })),
+            ToolChoice::None => Some(serde_json::json!("none")),
+        }
+    }
+
+    /// Gemini `toolConfig`; `None` for the default.
+    pub fn gemini(&self) -> Option<serde_json::Value> {
+        let config = match self {
+            ToolChoice::Auto => return None,
+            ToolChoice::Any => serde_json::json!({"mode": "ANY"}),
+            ToolChoice::Tool(name) => {
+                serde_json::json!({"mode": "ANY", "allowedFunctionNames": [name]})
+            }
+            ToolChoice::None => serde_json::json!({"mode": "NONE"}),
+        };
+        Some(serde_json::json!({ "functionCallingConfig": config }))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +108,26 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    /// A document uploaded to a provider's Files API (see `provider_files`), referenced by id.
+    /// Each provider translates it; the others get [`ContentBlock::provider_file_placeholder`].
+    #[serde(rename = "provider_file")]
+    ProviderFile {
+        /// "anthropic" or "openai": the only provider that can resolve `file_id`.
+        provider: String,
+        file_id: String,
+        filename: String,
+        media_type: String,
+        /// Load into the code execution container instead of reading as a document.
+        #[serde(default)]
+        container: bool,
+    },
+}
+
+impl ContentBlock {
+    /// Text standing in for a `ProviderFile` the current provider cannot read.
+    pub fn provider_file_placeholder(filename: &str) -> String {
+        format!("[document {filename} is attached to another provider; read it from its saved_path]")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +152,8 @@ pub struct MessagesRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<ToolDefinition>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
 }
 
@@ -233,6 +325,7 @@ mod tests {
                 content: MessageContent::Text("hi".into()),
             }],
             tools: None,
+            tool_choice: None,
             stream: None,
         };
         let json = serde_json::to_value(&req).unwrap();
@@ -252,12 +345,16 @@ mod tests {
                 name: "bash".into(),
                 description: "Run bash".into(),
                 input_schema: json!({"type": "object"}),
+                examples: Vec::new(),
             }]),
+            tool_choice: ToolChoice::Tool("bash".into()).anthropic(),
             stream: None,
         };
         let json = serde_json::to_value(&req).unwrap();
         assert!(json["tools"].is_array());
         assert_eq!(json["tools"][0]["name"], "bash");
+        assert_eq!(json["tool_choice"], json!({"type": "tool", "name": "bash"}));
+        assert!(json["tools"][0].get("examples").is_none());
     }
 
     #[test]
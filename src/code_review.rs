@@ -0,0 +1,379 @@
+//! Review of cursor-agent changes: when a run changes a git repository that was clean before
+//! it, the diff is summarized by the LLM and posted to the chat with Approve / Revert buttons.
+//! Approve commits the changes; Revert resets the repository to the commit it was at before
+//! the run.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ParseMode};
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::channels::telegram::markdown_to_telegram_html;
+use crate::claude::{Message, MessageContent, ResponseContentBlock};
+use crate::config::Config;
+use crate::db::{call_blocking, Database, StoredMessage};
+use crate::text::truncate_chars_with;
+
+/// Prefix of the inline button callback data.
+pub const CALLBACK_PREFIX: &str = "review:";
+const MAX_DIFF_CHARS: usize = 40_000;
+const MAX_NEW_FILES: usize = 20;
+const MAX_NEW_FILE_LINES: usize = 200;
+
+const REVIEW_SYSTEM: &str = "You review code changes made by an automated coding agent. Reply with a short review in Markdown: one or two sentences summarizing what changed, then a bullet list of risks or problems worth checking (bugs, security, deleted code, missing tests). Say so plainly if nothing looks risky. Keep it under 200 words.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewAction {
+    Approve,
+    Revert,
+}
+
+/// Repository state captured before a run.
+#[derive(Debug, Clone)]
+pub struct ReviewBase {
+    pub repo_dir: PathBuf,
+    pub head: Option<String>,
+}
+
+pub fn callback_data(action: ReviewAction, id: i64) -> String {
+    let verb = match action {
+        ReviewAction::Approve => "approve",
+        ReviewAction::Revert => "revert",
+    };
+    format!("{CALLBACK_PREFIX}{verb}:{id}")
+}
+
+pub fn parse_callback(data: &str) -> Option<(ReviewAction, i64)> {
+    let (verb, id) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+    let action = match verb {
+        "approve" => ReviewAction::Approve,
+        "revert" => ReviewAction::Revert,
+        _ => return None,
+    };
+    Some((action, id.parse().ok()?))
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| format!("git {}: {e}", args.first().copied().unwrap_or("")))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or(""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Capture the repository containing `dir` if it has no uncommitted changes (otherwise a
+/// revert could destroy the user's own work, so no review is offered).
+pub async fn snapshot(dir: &Path) -> Option<ReviewBase> {
+    let top = git(dir, &["rev-parse", "--show-toplevel"]).await.ok()?;
+    let repo_dir = PathBuf::from(top.trim());
+    let status = git(&repo_dir, &["status", "--porcelain"]).await.ok()?;
+    if !status.trim().is_empty() {
+        return None;
+    }
+    let head = git(&repo_dir, &["rev-parse", "--verify", "-q", "HEAD"])
+        .await
+        .ok()
+        .map(|s| s.trim().to_string());
+    Some(ReviewBase { repo_dir, head })
+}
+
+/// Diffstat and diff of everything changed since `base` (commits, worktree and new files), or
+/// `None` when nothing changed.
+pub async fn collect_diff(base: &ReviewBase) -> Result<Option<(String, String)>, String> {
+    let dir = &base.repo_dir;
+    let (stat, mut diff) = match &base.head {
+        Some(head) => (
+            git(dir, &["diff", "--stat", head]).await?,
+            git(dir, &["diff", head]).await?,
+        ),
+        None => (
+            git(dir, &["diff", "--stat", "--cached"]).await?,
+            git(dir, &["diff", "--cached"]).await?,
+        ),
+    };
+    let untracked = git(dir, &["ls-files", "--others", "--exclude-standard"]).await?;
+    let new_files: Vec<&str> = untracked.lines().filter(|l| !l.is_empty()).collect();
+    let mut stat = stat.trim_end().to_string();
+    for path in new_files.iter().take(MAX_NEW_FILES) {
+        stat.push_str(&format!("\n {path} (new)"));
+        let content = tokio::fs::read(dir.join(path)).await.unwrap_or_default();
+        let content = String::from_utf8_lossy(&content);
+        diff.push_str(&format!("\n--- new file: {path}\n"));
+        for line in content.lines().take(MAX_NEW_FILE_LINES) {
+            diff.push('+');
+            diff.push_str(line);
+            diff.push('\n');
+        }
+    }
+    if new_files.len() > MAX_NEW_FILES {
+        stat.push_str(&format!(
+            "\n ... and {} more new files",
+            new_files.len() - MAX_NEW_FILES
+        ));
+    }
+    if diff.trim().is_empty() {
+        return Ok(None);
+    }
+    let diff = truncate_chars_with(&diff, MAX_DIFF_CHARS, "\n... (diff truncated)");
+    Ok(Some((stat.trim_start_matches('\n').to_string(), diff)))
+}
+
+async fn summarize(config: &Config, prompt_preview: &str, diff: &str) -> String {
+    let messages = vec![Message {
+        role: "user".into(),
+        content: MessageContent::Text(format!(
+            "Task given to the agent:\n{prompt_preview}\n\nDiff:\n{diff}"
+        )),
+    }];
+    match crate::llm::create_provider(config)
+        .send_message(REVIEW_SYSTEM, messages, None)
+        .await
+    {
+        Ok(response) => response
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ResponseContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("")
+            .trim()
+            .to_string(),
+        Err(e) => format!("(Automatic review unavailable: {e})"),
+    }
+}
+
+pub fn review_message(id: i64, stat: &str, summary: &str) -> String {
+    format!("🔍 **Review #{id} of cursor-agent changes**\n\n```\n{stat}\n```\n\n{summary}")
+}
+
+/// Review the changes a run made since `base` and post them to `chat_id` with buttons.
+pub async fn review_run(
+    config: Config,
+    bot: Bot,
+    db: Arc<Database>,
+    chat_id: i64,
+    base: ReviewBase,
+    prompt_preview: String,
+) {
+    let (stat, diff) = match collect_diff(&base).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(
+                "Code review: failed to diff {}: {e}",
+                base.repo_dir.display()
+            );
+            return;
+        }
+    };
+    let summary = summarize(&config, &prompt_preview, &diff).await;
+    let (repo, head, preview) = (
+        base.repo_dir.to_string_lossy().to_string(),
+        base.head.clone(),
+        prompt_preview.clone(),
+    );
+    let id = match call_blocking(db.clone(), move |d| {
+        d.create_code_review(chat_id, &repo, head.as_deref(), &preview)
+    })
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            warn!("Code review: failed to record review: {e}");
+            return;
+        }
+    };
+    let text = review_message(id, &stat, &summary);
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("✅ Approve", callback_data(ReviewAction::Approve, id)),
+        InlineKeyboardButton::callback("↩️ Revert", callback_data(ReviewAction::Revert, id)),
+    ]]);
+    if let Err(e) = bot
+        .send_message(ChatId(chat_id), markdown_to_telegram_html(&text))
+        .parse_mode(ParseMode::Html)
+        .reply_markup(keyboard)
+        .await
+    {
+        warn!("Code review: failed to post review #{id} to chat {chat_id}: {e}");
+        return;
+    }
+    let persona_id = call_blocking(db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .unwrap_or(0);
+    let msg = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        chat_id,
+        persona_id,
+        sender_name: config.bot_username.clone(),
+        content: text,
+        is_from_bot: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(db, move |d| d.store_message(&msg)).await;
+    info!("Code review #{id} posted to chat {chat_id}");
+}
+
+fn commit_message(prompt_preview: &str) -> String {
+    let first = prompt_preview.lines().next().unwrap_or("").trim();
+    let short: String = first.chars().take(72).collect();
+    format!("cursor-agent: {short}")
+}
+
+async fn approve(repo: &Path, prompt_preview: &str) -> Result<String, String> {
+    if git(repo, &["status", "--porcelain"])
+        .await?
+        .trim()
+        .is_empty()
+    {
+        return Ok("Approved (the agent already committed its changes).".into());
+    }
+    git(repo, &["add", "-A"]).await?;
+    let message = commit_message(prompt_preview);
+    let mut args = vec!["commit", "-q", "-m", &message];
+    // Commit without a configured identity rather than failing.
+    if git(repo, &["config", "user.email"]).await.is_err() {
+        args.splice(
+            0..0,
+            [
+                "-c",
+                "user.name=MicroClaw",
+                "-c",
+                "user.email=microclaw@localhost",
+            ],
+        );
+    }
+    git(repo, &args).await?;
+    let head = git(repo, &["rev-parse", "--short", "HEAD"]).await?;
+    Ok(format!("Approved and committed as {}.", head.trim()))
+}
+
+async fn revert(repo: &Path, base_commit: Option<&str>) -> Result<String, String> {
+    match base_commit {
+        Some(head) => {
+            git(repo, &["reset", "-q", "--hard", head]).await?;
+        }
+        None => {
+            git(
+                repo,
+                &["rm", "-r", "-q", "--cached", "--ignore-unmatch", "."],
+            )
+            .await?;
+        }
+    }
+    git(repo, &["clean", "-fdq"]).await?;
+    Ok("Reverted the changes.".into())
+}
+
+/// Apply a button press on review `id` from `chat_id`. Returns the text to show the user.
+pub async fn apply(
+    db: Arc<Database>,
+    id: i64,
+    action: ReviewAction,
+    chat_id: i64,
+) -> Result<String, String> {
+    let review = call_blocking(db.clone(), move |d| d.get_code_review(id))
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Review #{id} not found"))?;
+    if review.chat_id != chat_id {
+        return Err(format!("Review #{id} belongs to another chat"));
+    }
+    if review.status != "pending" {
+        return Err(format!("Review #{id} was already {}", review.status));
+    }
+    let repo = PathBuf::from(&review.repo_dir);
+    let (result, status) = match action {
+        ReviewAction::Approve => (approve(&repo, &review.prompt_preview).await?, "approved"),
+        ReviewAction::Revert => (
+            revert(&repo, review.base_commit.as_deref()).await?,
+            "reverted",
+        ),
+    };
+    call_blocking(db, move |d| d.resolve_code_review(id, status))
+        .await
+        .map_err(|e| e.to_string())?;
+    info!("Code review #{id} {status}");
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback_round_trip() {
+        let data = callback_data(ReviewAction::Revert, 42);
+        assert_eq!(data, "review:revert:42");
+        assert_eq!(parse_callback(&data), Some((ReviewAction::Revert, 42)));
+        assert_eq!(
+            parse_callback("review:approve:7"),
+            Some((ReviewAction::Approve, 7))
+        );
+        assert_eq!(parse_callback("review:merge:7"), None);
+        assert_eq!(parse_callback("poll:1"), None);
+        assert_eq!(
+            commit_message("Fix the login bug\nand more"),
+            "cursor-agent: Fix the login bug"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_diff_and_revert() {
+        let dir = std::env::temp_dir().join(format!("microclaw_review_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        if git(&dir, &["init", "-q"]).await.is_err() {
+            return; // git not installed
+        }
+        std::fs::write(dir.join("a.txt"), "one\n").unwrap();
+        assert!(
+            snapshot(&dir).await.is_none(),
+            "dirty tree must not be reviewed"
+        );
+        git(&dir, &["add", "-A"]).await.unwrap();
+        git(
+            &dir,
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-q",
+                "-m",
+                "init",
+            ],
+        )
+        .await
+        .unwrap();
+
+        let base = snapshot(&dir).await.unwrap();
+        assert!(base.head.is_some());
+        assert!(collect_diff(&base).await.unwrap().is_none());
+
+        std::fs::write(dir.join("a.txt"), "two\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "new\n").unwrap();
+        let (stat, diff) = collect_diff(&base).await.unwrap().unwrap();
+        assert!(stat.contains("a.txt") && stat.contains("b.txt (new)"));
+        assert!(diff.contains("+two") && diff.contains("+new"));
+
+        revert(&dir, base.head.as_deref()).await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "one\n");
+        assert!(!dir.join("b.txt").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::MicroClawError;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 fn default_telegram_bot_token() -> String {
     String::new()
@@ -41,6 +42,21 @@ fn default_max_session_messages() -> usize {
 fn default_compact_keep_recent() -> usize {
     20
 }
+fn default_memory_flush_idle_minutes() -> u64 {
+    30
+}
+fn default_deleted_session_retention_days() -> u64 {
+    30
+}
+fn default_task_max_retries() -> u32 {
+    2
+}
+fn default_task_retry_backoff_secs() -> u64 {
+    60
+}
+fn default_scheduler_max_concurrent_tasks() -> usize {
+    4
+}
 fn default_whatsapp_webhook_port() -> u16 {
     8080
 }
@@ -65,6 +81,12 @@ fn default_web_max_requests_per_window() -> usize {
 fn default_web_rate_window_seconds() -> u64 {
     10
 }
+fn default_web_max_reads_per_window() -> usize {
+    120
+}
+fn default_web_max_writes_per_window() -> usize {
+    30
+}
 fn default_web_run_history_limit() -> usize {
     512
 }
@@ -81,104 +103,1192 @@ fn default_browser_headless() -> bool {
     false
 }
 
-#[cfg(target_os = "windows")]
-pub(crate) fn default_cursor_agent_cli_path() -> String {
-    "cursor-agent.cmd".into()
+#[cfg(target_os = "windows")]
+pub(crate) fn default_cursor_agent_cli_path() -> String {
+    "cursor-agent.cmd".into()
+}
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn default_cursor_agent_cli_path() -> String {
+    "cursor-agent".into()
+}
+
+fn default_cursor_agent_model() -> String {
+    String::new()
+}
+
+fn default_cursor_agent_digest_days() -> u64 {
+    7
+}
+
+fn default_cursor_agent_timeout_secs() -> u64 {
+    600
+}
+
+fn default_orchestrator_enabled() -> bool {
+    true
+}
+
+fn default_orchestrator_model() -> String {
+    String::new()
+}
+
+fn is_local_web_host(host: &str) -> bool {
+    let h = host.trim().to_ascii_lowercase();
+    h == "127.0.0.1" || h == "localhost" || h == "::1"
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SocialPlatformConfig {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SocialConfig {
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub tiktok: SocialPlatformConfig,
+    #[serde(default)]
+    pub instagram: SocialPlatformConfig,
+    #[serde(default)]
+    pub linkedin: SocialPlatformConfig,
+    /// Google account (Gmail and Drive read tools). Scopes are requested per tool as needed.
+    #[serde(default)]
+    pub google: SocialPlatformConfig,
+    /// Spotify account (playback control tools).
+    #[serde(default)]
+    pub spotify: SocialPlatformConfig,
+    /// Poll every authorized feed this often (minutes) and alert the chat about new posts and
+    /// metric milestones. 0 disables the monitor.
+    #[serde(default)]
+    pub monitor_interval_mins: u64,
+    /// View/like counts that trigger a milestone alert when a post passes them
+    /// (default 100, 1k, 10k, 100k, 1M).
+    #[serde(default)]
+    pub milestones: Vec<u64>,
+}
+
+/// Optional vault/vector DB config for ORIGIN Obsidian vault integration.
+/// Paths are relative to workspace_dir unless absolute.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VaultConfig {
+    /// ORIGIN vault path relative to workspace_dir (e.g. "shared/ORIGIN").
+    #[serde(default)]
+    pub origin_vault_path: Option<String>,
+    /// ChromaDB persistence dir relative to workspace_dir (e.g. "shared/vault_db").
+    #[serde(default)]
+    pub vector_db_path: Option<String>,
+    /// Git repo URL to clone/pull vault (for sync service). Env: VAULT_ORIGIN_VAULT_REPO or VAULT_GIT_URL.
+    #[serde(default)]
+    pub origin_vault_repo: Option<String>,
+    /// Embedding server URL (e.g. "http://10.0.1.211:8080" for llama.cpp).
+    #[serde(default)]
+    pub embedding_server_url: Option<String>,
+    /// Search command; use "{query}" as placeholder for the query.
+    #[serde(default)]
+    pub vault_search_command: Option<String>,
+    /// Index command to run after vault updates.
+    #[serde(default)]
+    pub vault_index_command: Option<String>,
+    /// Override principles file path relative to workspace_dir (e.g. "shared/ORIGIN/AGENTS.md"). Default: "AGENTS.md" at workspace root.
+    #[serde(default)]
+    pub principles_path: Option<String>,
+    /// ChromaDB HTTP server URL (e.g. "http://localhost:8000"). Required for the native search_vault tool.
+    #[serde(default)]
+    pub vector_db_url: Option<String>,
+    /// ChromaDB collection name (default: "vault").
+    #[serde(default)]
+    pub vector_db_collection: Option<String>,
+    /// Model name sent to OpenAI-compatible embedding servers (URLs ending in /v1); also part of
+    /// the embedding cache key, so changing it re-embeds everything.
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    /// Texts per embedding request when indexing (default: 32).
+    #[serde(default)]
+    pub embedding_batch_size: Option<usize>,
+    /// Cache embeddings on disk by content hash and model (default: true).
+    #[serde(default)]
+    pub embedding_cache: Option<bool>,
+    /// Vault name in Obsidian, for `obsidian://` citation links (default: the vault directory's name).
+    #[serde(default)]
+    pub obsidian_vault_name: Option<String>,
+    /// Periodic maintenance report (broken links, orphaned attachments, stale daily notes).
+    #[serde(default)]
+    pub maintenance: Option<VaultMaintenanceConfig>,
+}
+
+fn default_maintenance_interval_days() -> i64 {
+    7
+}
+
+fn default_maintenance_report_folder() -> String {
+    "Maintenance".into()
+}
+
+fn default_stale_daily_after_days() -> i64 {
+    14
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VaultMaintenanceConfig {
+    /// Days between reports (default: 7).
+    #[serde(default = "default_maintenance_interval_days")]
+    pub interval_days: i64,
+    /// Chat that gets the summary (default: the first control chat).
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    /// Vault folder for the report notes (default: "Maintenance"); not scanned itself.
+    #[serde(default = "default_maintenance_report_folder")]
+    pub report_folder: String,
+    /// Folder of the daily notes (default: any note named YYYY-MM-DD).
+    #[serde(default)]
+    pub daily_notes_folder: Option<String>,
+    /// Daily notes older than this with open tasks, or empty, are reported (default: 14).
+    #[serde(default = "default_stale_daily_after_days")]
+    pub stale_after_days: i64,
+}
+
+impl Default for VaultMaintenanceConfig {
+    fn default() -> Self {
+        VaultMaintenanceConfig {
+            interval_days: default_maintenance_interval_days(),
+            chat_id: None,
+            report_folder: default_maintenance_report_folder(),
+            daily_notes_folder: None,
+            stale_after_days: default_stale_daily_after_days(),
+        }
+    }
+}
+
+/// Optional translation config. Without a DeepL key, the configured LLM translates.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TranslationConfig {
+    /// DeepL API key. Keys ending in ":fx" use the free API endpoint.
+    #[serde(default)]
+    pub deepl_api_key: Option<String>,
+    /// Override the DeepL API base URL (e.g. "https://api.deepl.com").
+    #[serde(default)]
+    pub deepl_api_url: Option<String>,
+    /// Detect the language of inbound messages the agent answers and translate them to
+    /// `target_language` before processing.
+    #[serde(default)]
+    pub auto_translate_inbound: bool,
+    /// Language the agent works in (ISO 639-1, e.g. "en"). Default: "en".
+    #[serde(default)]
+    pub target_language: Option<String>,
+}
+
+impl TranslationConfig {
+    pub fn target_language(&self) -> &str {
+        self.target_language
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or("en")
+    }
+}
+
+/// Optional output filter for replies in shared chats (e.g. groups that include kids).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OutputFilterConfig {
+    /// Chat types the filter applies to (e.g. "telegram_group", "discord", "web").
+    /// Default: telegram_group, telegram_supergroup, telegram_channel, discord.
+    #[serde(default)]
+    pub chat_types: Vec<String>,
+    /// Additional chat ids that are always filtered, whatever their type.
+    #[serde(default)]
+    pub chat_ids: Vec<i64>,
+    /// Words or phrases to filter (case-insensitive, whole words).
+    #[serde(default)]
+    pub words: Vec<String>,
+    /// Wordlist file, one entry per line, `#` comments (relative to workspace root or absolute).
+    #[serde(default)]
+    pub wordlist_path: Option<String>,
+    /// "mask" (default) replaces matched words with asterisks; "block" withholds the whole reply.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Sent instead of a withheld reply. Default: "Sorry, I can't share that reply here."
+    #[serde(default)]
+    pub blocked_message: Option<String>,
+    /// Also check replies with the OpenAI moderation API (uses `openai_api_key`); flagged replies are withheld.
+    #[serde(default)]
+    pub moderation_api: bool,
+    /// Override the moderation endpoint (default: https://api.openai.com/v1/moderations).
+    #[serde(default)]
+    pub moderation_api_url: Option<String>,
+}
+
+fn default_moderate_public_ask() -> bool {
+    true
+}
+
+/// Optional inbound moderation: spam heuristics, prompt-injection patterns and link checks for
+/// public-facing chats. Flagged messages are quarantined until reviewed from a control chat.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InboundModerationConfig {
+    /// Chat types to moderate (e.g. "discord", "telegram_supergroup"). Default: discord.
+    #[serde(default)]
+    pub chat_types: Vec<String>,
+    /// Additional chat ids that are always moderated, whatever their type.
+    #[serde(default)]
+    pub chat_ids: Vec<i64>,
+    /// Moderate questions sent to the public `/ask` endpoint. Default: true.
+    #[serde(default = "default_moderate_public_ask")]
+    pub public_ask: bool,
+    /// Chat that receives quarantine notices. Default: the first control chat.
+    #[serde(default)]
+    pub review_chat_id: Option<i64>,
+    /// Links allowed per message before it is held. Default: 3.
+    #[serde(default)]
+    pub max_links: Option<usize>,
+    /// Domains whose links are always held (subdomains included).
+    #[serde(default)]
+    pub blocked_domains: Vec<String>,
+    /// Messages per sender per minute before further messages are held. Default: 8.
+    #[serde(default)]
+    pub max_messages_per_minute: Option<usize>,
+    /// Extra prompt-injection patterns (case-insensitive regexes) on top of the built-in set.
+    #[serde(default)]
+    pub injection_patterns: Vec<String>,
+}
+
+/// Outbound network policy for tools that fetch URLs chosen by the agent (web_fetch, browser).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EgressConfig {
+    /// When non-empty, only these domains (and their subdomains) can be reached.
+    #[serde(default)]
+    pub allow_domains: Vec<String>,
+    /// Domains (and their subdomains) that can never be reached.
+    #[serde(default)]
+    pub deny_domains: Vec<String>,
+    /// Allow loopback, private, link-local and other internal addresses. Default: false.
+    #[serde(default)]
+    pub allow_private_networks: bool,
+    /// Hosts or IPs exempt from the private-address block (e.g. "homeassistant.local").
+    #[serde(default)]
+    pub allow_private_hosts: Vec<String>,
+}
+
+/// A named browser profile (separate cookie jar and saved logins) that only the listed personas
+/// may use with the browser tool. Every persona also has its own private profile.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BrowserProfileConfig {
+    /// Profile name (letters, digits, '-' and '_'), e.g. "banking".
+    pub name: String,
+    /// Persona names allowed to use the profile, e.g. ["default"].
+    #[serde(default)]
+    pub personas: Vec<String>,
+    /// Chats allowed to use the profile. Empty = any chat (still limited by persona).
+    #[serde(default)]
+    pub chat_ids: Vec<i64>,
+}
+
+/// Delivery priority of a proactive message source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationPriority {
+    /// Sent immediately, never held for a digest.
+    Urgent,
+    /// Held and merged with other messages to the same chat within the window.
+    Normal,
+    /// Opens (or updates) an incident: paged to the incident chat until acknowledged.
+    Incident,
+}
+
+fn default_notification_window_secs() -> u64 {
+    300
+}
+
+/// Optional digesting of proactive messages (feed alerts, scheduled task results): messages to
+/// the same chat within the window are merged into one grouped message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// How long (seconds) the first held message waits for others before the digest is sent.
+    /// 0 disables digesting. Default: 300.
+    #[serde(default = "default_notification_window_secs")]
+    pub window_secs: u64,
+    /// Per-source priority overrides, e.g. `social_monitor: urgent` or `task_failure: incident`.
+    /// Sources: social_monitor, feeds, scheduled_task, task_failure and task_escalation (both
+    /// urgent by default).
+    #[serde(default)]
+    pub priorities: HashMap<String, NotificationPriority>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        NotificationConfig {
+            window_secs: default_notification_window_secs(),
+            priorities: HashMap::new(),
+        }
+    }
+}
+
+fn default_heartbeat_interval_secs() -> u64 {
+    300
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Optional dead man's switch: ping a monitoring URL (healthchecks.io style) while the bot is
+/// healthy, and tell the admin chat when the bot starts or shuts down.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    /// URL pinged (GET) on schedule and after scheduler cycles. None = no pings.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Minimum seconds between pings. Default: 300.
+    #[serde(default = "default_heartbeat_interval_secs")]
+    pub interval_secs: u64,
+    /// Chat that receives startup/shutdown notices. Default: the first control chat.
+    #[serde(default)]
+    pub admin_chat_id: Option<i64>,
+    /// Send startup/shutdown notices. Default: true.
+    #[serde(default = "default_true")]
+    pub notify_lifecycle: bool,
+}
+
+fn default_incident_repeat_after_mins() -> u64 {
+    5
+}
+
+fn default_incident_max_repeats() -> u32 {
+    6
+}
+
+/// Incidents: urgent alerts that skip digests and the `proactive_messages` flag and are paged
+/// again, with growing gaps, until someone acknowledges them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IncidentConfig {
+    /// Chat that receives incident pages. Default: the first control chat.
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    /// Mentions added to every page, e.g. `@alice`.
+    #[serde(default)]
+    pub ping_users: Vec<String>,
+    /// Minutes before the first repeat page; each later repeat waits twice as long. Default: 5.
+    #[serde(default = "default_incident_repeat_after_mins")]
+    pub repeat_after_mins: u64,
+    /// Repeat pages before paging stops (the incident stays open). Default: 6.
+    #[serde(default = "default_incident_max_repeats")]
+    pub max_repeats: u32,
+}
+
+impl Default for IncidentConfig {
+    fn default() -> Self {
+        IncidentConfig {
+            chat_id: None,
+            ping_users: Vec::new(),
+            repeat_after_mins: default_incident_repeat_after_mins(),
+            max_repeats: default_incident_max_repeats(),
+        }
+    }
+}
+
+/// Push notification service for chats that chose `/push on`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushProvider {
+    Ntfy,
+    Gotify,
+}
+
+/// Proactive messages (feed alerts, scheduled task results, digests) for chats that prefer push
+/// are sent to ntfy or Gotify instead of the chat, with a button that opens the chat in the web UI.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PushConfig {
+    pub provider: PushProvider,
+    /// Server URL. Default for ntfy: https://ntfy.sh; required for Gotify.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// ntfy topic for chats that did not pick their own with `/push on <topic>`.
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// ntfy access token, or the Gotify application token (required for Gotify).
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Public URL of the web UI for the "Open chat" link. Default: `social.base_url`.
+    #[serde(default)]
+    pub web_url: Option<String>,
+}
+
+/// A participant in `roundtable` discussions (`/debate`, the `roundtable` tool).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundtablePersonaConfig {
+    pub name: String,
+    /// How this persona approaches the question (its system prompt).
+    pub prompt: String,
+    /// Default: the main `llm_provider` (with its `api_key` and `llm_base_url`).
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Default: the main `model`.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Default: the primary `api_key`.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Default: the provider's standard endpoint when `provider` is set.
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+fn default_roundtable_personas() -> Vec<RoundtablePersonaConfig> {
+    vec![
+        RoundtablePersonaConfig {
+            name: "Advocate".into(),
+            prompt: "You look for the strongest case for the most promising option and how to make it work.".into(),
+            provider: None,
+            model: None,
+            api_key: None,
+            base_url: None,
+        },
+        RoundtablePersonaConfig {
+            name: "Skeptic".into(),
+            prompt: "You look for risks, hidden costs, weak assumptions and overlooked alternatives.".into(),
+            provider: None,
+            model: None,
+            api_key: None,
+            base_url: None,
+        },
+    ]
+}
+
+fn default_roundtable_turns() -> u32 {
+    2
+}
+
+fn default_roundtable_max_tokens() -> u32 {
+    800
+}
+
+/// Roundtable: personas discuss a question for `turns` rounds, then a synthesizer writes the
+/// answer. Without this section two built-in personas (Advocate, Skeptic) on the main model take
+/// part.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundtableConfig {
+    /// At least two, with distinct names.
+    #[serde(default = "default_roundtable_personas")]
+    pub personas: Vec<RoundtablePersonaConfig>,
+    /// Rounds in which every persona speaks once (1-6).
+    #[serde(default = "default_roundtable_turns")]
+    pub turns: u32,
+    /// Model that writes the final answer. Default: the main `model`.
+    #[serde(default)]
+    pub synthesizer_model: Option<String>,
+    /// Output limit for each contribution.
+    #[serde(default = "default_roundtable_max_tokens")]
+    pub max_tokens: u32,
+}
+
+impl Default for RoundtableConfig {
+    fn default() -> Self {
+        RoundtableConfig {
+            personas: default_roundtable_personas(),
+            turns: default_roundtable_turns(),
+            synthesizer_model: None,
+            max_tokens: default_roundtable_max_tokens(),
+        }
+    }
+}
+
+fn default_benchmark_interval_days() -> i64 {
+    30
+}
+
+/// Model benchmark job: every `interval_days` the eval suite runs against the main model and
+/// each candidate, and a report says which gives the best value (quality, cost, latency).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchmarkConfig {
+    /// Candidates compared with the main `model`.
+    #[serde(default)]
+    pub models: Vec<LlmFallbackConfig>,
+    /// Eval suite YAML (see `microclaw eval`). Default: a built-in suite of everyday requests.
+    #[serde(default)]
+    pub suite: Option<String>,
+    /// Model for the LLM judge. Default: the suite's `judge_model`, then the main `model`.
+    #[serde(default)]
+    pub judge_model: Option<String>,
+    /// Days between runs (default: 30).
+    #[serde(default = "default_benchmark_interval_days")]
+    pub interval_days: i64,
+    /// Chat that gets the summary (default: the first control chat).
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+}
+
+fn default_chat_archive_inactive_months() -> u32 {
+    6
+}
+
+/// Cold storage: chats without messages for `inactive_months` are moved out of the database into
+/// a compressed file and brought back when they are used again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChatArchiveConfig {
+    #[serde(default = "default_chat_archive_inactive_months")]
+    pub inactive_months: u32,
+    /// Shrink the database file after archiving (default: true).
+    #[serde(default = "default_true")]
+    pub vacuum: bool,
+}
+
+fn default_telegram_business_history_messages() -> usize {
+    20
+}
+
+/// Auto-replies in the owner's Telegram Business inbox. Conversations are answered only after
+/// being switched on one by one (`business_inbox` tool), by a responder without tools.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TelegramBusinessConfig {
+    /// Telegram user ids whose business accounts may connect (default: the control chats).
+    #[serde(default)]
+    pub owner_ids: Vec<i64>,
+    /// Markdown file (relative to workspace_dir) with how to answer on the owner's behalf.
+    #[serde(default)]
+    pub instructions_path: Option<String>,
+    /// Earlier messages of the conversation sent with each reply (default: 20).
+    #[serde(default = "default_telegram_business_history_messages")]
+    pub history_messages: usize,
+    /// Tell the owner about new conversations and each auto-reply (default: true).
+    #[serde(default = "default_true")]
+    pub notify: bool,
+}
+
+fn default_provider_search_max_uses() -> u32 {
+    5
+}
+
+fn default_provider_search_openai_model() -> String {
+    "gpt-4o-mini-search-preview".into()
+}
+
+/// Provider-side web search, used in chats with the `provider_search` feature flag on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProviderSearchConfig {
+    /// Anthropic: most searches the model may run per request.
+    #[serde(default = "default_provider_search_max_uses")]
+    pub max_uses: u32,
+    /// OpenAI: search-capable model that answers `provider_web_search` calls (chat completions
+    /// cannot combine web search with function tools).
+    #[serde(default = "default_provider_search_openai_model")]
+    pub openai_model: String,
+}
+
+impl Default for ProviderSearchConfig {
+    fn default() -> Self {
+        ProviderSearchConfig {
+            max_uses: default_provider_search_max_uses(),
+            openai_model: default_provider_search_openai_model(),
+        }
+    }
+}
+
+fn default_provider_files_min_kb() -> u64 {
+    100
+}
+
+fn default_provider_files_max_mb() -> u64 {
+    32
+}
+
+fn default_provider_files_retention_hours() -> u64 {
+    24
+}
+
+/// Documents sent to the bot are uploaded to the LLM provider's Files API (Anthropic; OpenAI for
+/// PDFs) and attached by id instead of being read into the context with `read_file`. Other
+/// providers keep the saved-file path as before. Uploads are deleted after `retention_hours`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProviderFilesConfig {
+    /// Smaller documents are left to `read_file`.
+    #[serde(default = "default_provider_files_min_kb")]
+    pub min_kb: u64,
+    /// Larger documents are not uploaded.
+    #[serde(default = "default_provider_files_max_mb")]
+    pub max_mb: u64,
+    /// Anthropic only: load data files (csv, xlsx, json) into a code execution container and
+    /// give the model the code execution tool to analyse them.
+    #[serde(default)]
+    pub code_interpreter: bool,
+    #[serde(default = "default_provider_files_retention_hours")]
+    pub retention_hours: u64,
+}
+
+fn default_workspace_passphrase_env() -> String {
+    "MICROCLAW_WORKSPACE_PASSPHRASE".into()
+}
+
+fn default_workspace_sync_interval_secs() -> u64 {
+    300
+}
+
+/// Encrypted workspace: the runtime directory (database, memory, exports, logs) is stored as an
+/// encrypted archive, `runtime.enc` in the workspace, and unpacked into `mount_dir` while the bot
+/// runs. See `workspace_crypto`.
+///
+/// Only what was sealed is kept: writes since the last seal (up to `sync_interval_secs`) are lost
+/// if the process dies and the memory-backed mount goes with it, e.g. on a reboot or power loss.
+/// After a crash without a reboot the mount is still there and is picked up on the next start.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceEncryptionConfig {
+    /// Environment variable holding the passphrase. Default: MICROCLAW_WORKSPACE_PASSPHRASE.
+    #[serde(default = "default_workspace_passphrase_env")]
+    pub passphrase_env: String,
+    /// File whose first line is the passphrase; used when the variable is unset.
+    #[serde(default)]
+    pub passphrase_file: Option<String>,
+    /// Where the decrypted runtime lives while running. Default: a directory under /dev/shm
+    /// (memory only) when it exists, else under the temp dir.
+    #[serde(default)]
+    pub mount_dir: Option<String>,
+    /// Seconds between re-encrypting the live runtime into the archive (skipped when nothing
+    /// changed), and so the most recent work a crash can lose. Default: 300.
+    #[serde(default = "default_workspace_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+impl WorkspaceEncryptionConfig {
+    /// Directory holding the decrypted runtime of the workspace at `workspace_root`.
+    pub fn mount_path(&self, workspace_root: &Path) -> PathBuf {
+        if let Some(dir) = self.mount_dir.as_deref().map(str::trim) {
+            if !dir.is_empty() {
+                return PathBuf::from(dir);
+            }
+        }
+        let shm = Path::new("/dev/shm");
+        let base = if shm.is_dir() {
+            shm.to_path_buf()
+        } else {
+            std::env::temp_dir()
+        };
+        // Stable per workspace, so a restart finds a copy left by a crash
+        let digest = ring::digest::digest(
+            &ring::digest::SHA256,
+            workspace_root.to_string_lossy().as_bytes(),
+        );
+        let id: String = digest.as_ref()[..6]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        base.join(format!("microclaw-runtime-{id}"))
+    }
+}
+
+fn default_signal_cli_path() -> String {
+    "signal-cli".into()
+}
+
+/// Signal messenger via a local signal-cli (`signal-cli -a <account> jsonRpc`). The account
+/// must already be registered or linked with signal-cli.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignalConfig {
+    /// signal-cli executable. Default: signal-cli (on PATH).
+    #[serde(default = "default_signal_cli_path")]
+    pub signal_cli_path: String,
+    /// The bot's Signal account (phone number, e.g. +15551234567).
+    pub signal_account: String,
+    /// Group ids (base64, as signal-cli prints them) to respond in (empty = all).
+    #[serde(default)]
+    pub allowed_groups: Vec<String>,
+}
+
+fn default_wyoming_host() -> String {
+    "0.0.0.0".into()
+}
+
+fn default_wyoming_port() -> u16 {
+    10800
+}
+
+fn default_wyoming_languages() -> Vec<String> {
+    vec!["en".into()]
+}
+
+/// Voice bridge: a Wyoming `handle` service Home Assistant can use as a conversation agent, so
+/// voice satellites send their transcripts here and speak the reply.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WyomingConfig {
+    #[serde(default = "default_wyoming_host")]
+    pub host: String,
+    #[serde(default = "default_wyoming_port")]
+    pub port: u16,
+    /// Chat the spoken requests run in (shown in the web UI). Default: a dedicated "voice" chat.
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    /// Languages advertised to Home Assistant.
+    #[serde(default = "default_wyoming_languages")]
+    pub languages: Vec<String>,
+}
+
+fn default_python_interpreter() -> String {
+    "python3".into()
+}
+
+fn default_python_timeout_secs() -> u64 {
+    60
+}
+
+fn default_python_memory_mb() -> u64 {
+    2048
+}
+
+/// `python` tool: code runs in a per-chat virtualenv under `workspace_dir/shared/.python`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PythonConfig {
+    /// Interpreter the virtualenvs are created from.
+    #[serde(default = "default_python_interpreter")]
+    pub interpreter: String,
+    /// Default run timeout; a call may ask for up to 10 times this.
+    #[serde(default = "default_python_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Address-space limit of the interpreter process (Unix only; 0 = unlimited).
+    #[serde(default = "default_python_memory_mb")]
+    pub memory_mb: u64,
+    /// Let the agent `pip install` packages into the chat's virtualenv.
+    #[serde(default = "default_true")]
+    pub allow_packages: bool,
+}
+
+impl Default for PythonConfig {
+    fn default() -> Self {
+        PythonConfig {
+            interpreter: default_python_interpreter(),
+            timeout_secs: default_python_timeout_secs(),
+            memory_mb: default_python_memory_mb(),
+            allow_packages: true,
+        }
+    }
+}
+
+fn default_browser_download_max_mb() -> u64 {
+    100
+}
+
+fn default_browser_download_blocked_types() -> Vec<String> {
+    [
+        "exe", "msi", "bat", "cmd", "com", "scr", "dll", "ps1", "vbs", "jar", "app", "dmg", "pkg",
+        "deb", "rpm", "apk",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+/// Files the browser tool downloads are moved to `workspace_dir/shared/downloads/chat_<id>`
+/// (with a `.meta.json` sidecar) if they pass these limits; others are deleted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BrowserDownloadConfig {
+    /// Largest file kept, in MB (0 = unlimited).
+    #[serde(default = "default_browser_download_max_mb")]
+    pub max_mb: u64,
+    /// File extensions kept, e.g. ["pdf", "csv"]. Empty = any type not blocked.
+    #[serde(default)]
+    pub allowed_types: Vec<String>,
+    /// File extensions always rejected (executables and installers by default).
+    #[serde(default = "default_browser_download_blocked_types")]
+    pub blocked_types: Vec<String>,
+}
+
+impl Default for BrowserDownloadConfig {
+    fn default() -> Self {
+        BrowserDownloadConfig {
+            max_mb: default_browser_download_max_mb(),
+            allowed_types: Vec::new(),
+            blocked_types: default_browser_download_blocked_types(),
+        }
+    }
+}
+
+fn default_google_calendar_id() -> String {
+    "primary".into()
+}
+
+/// Calendar behind `list_events`/`create_event`: a CalDAV calendar when `caldav_url` is set,
+/// otherwise Google Calendar through each chat's Google connection (needs `social.google`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CalendarConfig {
+    /// Calendar collection URL, e.g.
+    /// https://cloud.example.com/remote.php/dav/calendars/alice/personal/
+    #[serde(default)]
+    pub caldav_url: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Chats allowed to use the CalDAV calendar. Empty = any chat.
+    #[serde(default)]
+    pub chat_ids: Vec<i64>,
+    /// Google calendar to use (default: the account's primary calendar).
+    #[serde(default = "default_google_calendar_id")]
+    pub google_calendar_id: String,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            caldav_url: None,
+            username: None,
+            password: None,
+            chat_ids: Vec::new(),
+            google_calendar_id: default_google_calendar_id(),
+        }
+    }
+}
+
+fn default_transcription_model() -> String {
+    "whisper-1".into()
+}
+
+/// Local Whisper server for voice messages, used instead of the OpenAI API.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TranscriptionConfig {
+    /// Transcription endpoint, e.g. http://localhost:8080/inference (whisper.cpp server) or
+    /// http://localhost:8000/v1/audio/transcriptions (OpenAI-compatible servers).
+    pub url: String,
+    #[serde(default = "default_transcription_model")]
+    pub model: String,
+    /// Spoken language (ISO 639-1, e.g. "en"); default: detected per message.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Bearer token, if the server needs one.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Embedding the web UI in other pages (Home Assistant dashboards, a homepage) and its
+/// Content-Security-Policy.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WebEmbedConfig {
+    /// Origins allowed to show the UI in an iframe, e.g. ["https://ha.example.com:8123"]. The
+    /// UI's own origin is always allowed.
+    #[serde(default)]
+    pub frame_ancestors: Vec<String>,
+    /// Replaces the default policy (`frame-ancestors` is still added unless the policy sets it).
+    #[serde(default)]
+    pub content_security_policy: Option<String>,
+}
+
+impl WebEmbedConfig {
+    /// Whether `origin` is a bare `scheme://host[:port]` (wildcard subdomains allowed), so it
+    /// cannot add directives to the policy.
+    pub fn valid_origin(origin: &str) -> bool {
+        let Some((scheme, host)) = origin.split_once("://") else {
+            return false;
+        };
+        matches!(scheme, "http" | "https")
+            && !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".-:*[]".contains(c))
+    }
+}
+
+/// Home Assistant REST API access (long-lived access token from the HA user profile).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HomeAssistantConfig {
+    /// Base URL, e.g. http://homeassistant.local:8123
+    pub url: String,
+    pub token: String,
+    /// Entities that tell who is home (default: every `person.*` entity). `device_tracker.*`
+    /// and on/off sensors work too; `home` or `on` counts as home.
+    #[serde(default)]
+    pub presence_entities: Vec<String>,
+    /// Chat of each person, for notifying whoever is home, e.g. `person.alice: 123456789`.
+    #[serde(default)]
+    pub presence_chats: HashMap<String, i64>,
+    /// Tell the agent who is home in the system prompt.
+    #[serde(default = "default_true")]
+    pub presence_in_prompt: bool,
+    /// Domains the `home_assistant` tool may call services in, e.g. ["light", "switch",
+    /// "climate"]. Empty = any domain.
+    #[serde(default)]
+    pub service_domains: Vec<String>,
+}
+
+fn default_teams_webhook_port() -> u16 {
+    3978
+}
+
+/// Microsoft Teams through the Bot Framework: Azure Bot registration credentials, and the port
+/// its messaging endpoint (`/api/messages`) listens on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TeamsConfig {
+    /// Microsoft App ID of the bot registration.
+    pub app_id: String,
+    /// Client secret of the bot registration.
+    pub app_password: String,
+    /// Tenant of a single-tenant bot registration (unset = multi-tenant).
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    #[serde(default = "default_teams_webhook_port")]
+    pub webhook_port: u16,
+    /// Teams channel or group chat conversation ids to respond in (empty = all). Personal
+    /// chats are always allowed.
+    #[serde(default)]
+    pub allowed_channels: Vec<String>,
+}
+
+fn default_feed_interval_mins() -> u64 {
+    60
+}
+
+/// RSS/Atom feed watched by the scheduler: new items are posted into `chat_id` as a message
+/// from the feed, and the agent replies to it (by default with a summary).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeedConfig {
+    pub url: String,
+    pub chat_id: i64,
+    /// Display name (default: the feed's own title).
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_feed_interval_mins")]
+    pub interval_mins: u64,
+    /// What the agent should do with new items (default: summarize them briefly).
+    #[serde(default)]
+    pub instructions: Option<String>,
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    10
 }
-#[cfg(not(target_os = "windows"))]
-pub(crate) fn default_cursor_agent_cli_path() -> String {
-    "cursor-agent".into()
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MiddlewareStage {
+    /// User messages, before they are stored and answered.
+    Inbound,
+    /// Replies, before they are delivered.
+    Outbound,
 }
 
-fn default_cursor_agent_model() -> String {
-    String::new()
+/// Shell command run on every message of a middleware stage: text on stdin, replacement on
+/// stdout; exit code 2 drops the message (see `middleware`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MiddlewareHookConfig {
+    pub stage: MiddlewareStage,
+    pub command: String,
+    /// Only these chats (empty = all).
+    #[serde(default)]
+    pub chat_ids: Vec<i64>,
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
 }
 
-fn default_cursor_agent_timeout_secs() -> u64 {
-    600
+/// Provider/model the agent switches to when the ones before it in the chain are rate limited
+/// or failing (HTTP 429/5xx, unreachable).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LlmFallbackConfig {
+    pub provider: String,
+    pub model: String,
+    /// Default: the primary `api_key`.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Default: the provider's standard endpoint.
+    #[serde(default)]
+    pub base_url: Option<String>,
 }
 
-fn default_orchestrator_enabled() -> bool {
-    true
+/// How much of the model's `<think>` reasoning a chat sees (`show_thinking`, per-chat override
+/// with `/thinking`). Old configs with `true`/`false` still load as `full`/`off`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThinkingLevel {
+    /// Reasoning is removed from replies (it can still be read with `show_last_thinking`).
+    #[default]
+    Off,
+    /// A one-line excerpt of the reasoning above the reply.
+    Summary,
+    /// The full reasoning is left in the reply.
+    Full,
 }
 
-fn default_orchestrator_model() -> String {
-    String::new()
+impl ThinkingLevel {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "false" | "0" | "no" => Some(ThinkingLevel::Off),
+            "summary" => Some(ThinkingLevel::Summary),
+            "full" | "true" | "1" | "yes" | "on" => Some(ThinkingLevel::Full),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThinkingLevel::Off => "off",
+            ThinkingLevel::Summary => "summary",
+            ThinkingLevel::Full => "full",
+        }
+    }
 }
 
-fn is_local_web_host(host: &str) -> bool {
-    let h = host.trim().to_ascii_lowercase();
-    h == "127.0.0.1" || h == "localhost" || h == "::1"
+impl<'de> Deserialize<'de> for ThinkingLevel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bool(bool),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Bool(true) => Ok(ThinkingLevel::Full),
+            Raw::Bool(false) => Ok(ThinkingLevel::Off),
+            Raw::Text(text) => ThinkingLevel::parse(&text).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "invalid show_thinking '{text}' (expected off, summary or full)"
+                ))
+            }),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct SocialPlatformConfig {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
+/// Model price in USD per million tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
 }
 
+/// A/B experiment: routes a share of turns in consenting chats through an alternate system
+/// prompt and/or model, recording outcome metrics per variant (see `experiments_report`).
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct SocialConfig {
-    #[serde(default)]
-    pub base_url: Option<String>,
+pub struct ExperimentConfig {
+    /// Experiment name, used to group results.
+    pub name: String,
+    /// Chats that agreed to take part. Other chats are never enrolled.
     #[serde(default)]
-    pub tiktok: SocialPlatformConfig,
+    pub chat_ids: Vec<i64>,
+    /// Percentage (0-100) of turns routed to the treatment variant; the rest are control.
+    #[serde(default = "default_experiment_percent")]
+    pub percent: u8,
+    /// Text appended to the system prompt for treatment turns.
     #[serde(default)]
-    pub instagram: SocialPlatformConfig,
+    pub system_prompt_append: Option<String>,
+    /// Model used for treatment turns (same provider). None = configured model.
     #[serde(default)]
-    pub linkedin: SocialPlatformConfig,
+    pub model: Option<String>,
 }
 
-/// Optional vault/vector DB config for ORIGIN Obsidian vault integration.
-/// Paths are relative to workspace_dir unless absolute.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct VaultConfig {
-    /// ORIGIN vault path relative to workspace_dir (e.g. "shared/ORIGIN").
-    #[serde(default)]
-    pub origin_vault_path: Option<String>,
-    /// ChromaDB persistence dir relative to workspace_dir (e.g. "shared/vault_db").
-    #[serde(default)]
-    pub vector_db_path: Option<String>,
-    /// Git repo URL to clone/pull vault (for sync service). Env: VAULT_ORIGIN_VAULT_REPO or VAULT_GIT_URL.
-    #[serde(default)]
-    pub origin_vault_repo: Option<String>,
-    /// Embedding server URL (e.g. "http://10.0.1.211:8080" for llama.cpp).
+fn default_experiment_percent() -> u8 {
+    50
+}
+
+/// Optional public `/ask` endpoint: lets a personal website route visitor questions
+/// into a designated chat, answered by a tool-less guest persona.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublicAskConfig {
+    /// Chat that receives website questions (stored under the guest persona).
+    pub chat_id: i64,
+    /// Persona name used for website visitors. Default: "guest".
     #[serde(default)]
-    pub embedding_server_url: Option<String>,
-    /// Search command; use "{query}" as placeholder for the query.
+    pub persona: Option<String>,
+    /// Site tokens accepted in the `X-Ask-Token` header or `token` field.
     #[serde(default)]
-    pub vault_search_command: Option<String>,
-    /// Index command to run after vault updates.
+    pub tokens: Vec<String>,
+    /// Captcha secret (Cloudflare Turnstile / hCaptcha style siteverify).
     #[serde(default)]
-    pub vault_index_command: Option<String>,
-    /// Override principles file path relative to workspace_dir (e.g. "shared/ORIGIN/AGENTS.md"). Default: "AGENTS.md" at workspace root.
+    pub captcha_secret: Option<String>,
+    /// Captcha verification URL. Default: Cloudflare Turnstile siteverify.
     #[serde(default)]
-    pub principles_path: Option<String>,
-    /// ChromaDB HTTP server URL (e.g. "http://localhost:8000"). Required for the native search_vault tool.
+    pub captcha_verify_url: Option<String>,
+    /// Origin allowed to call the endpoint from a browser (CORS), e.g. "https://example.com".
     #[serde(default)]
-    pub vector_db_url: Option<String>,
-    /// ChromaDB collection name (default: "vault").
+    pub allowed_origin: Option<String>,
+    /// Markdown file (relative to workspace_dir) with what the guest persona may say about you.
     #[serde(default)]
-    pub vector_db_collection: Option<String>,
+    pub instructions_path: Option<String>,
+    /// Max questions per client per window. Default: 3.
+    #[serde(default = "default_public_ask_max_requests_per_window")]
+    pub max_requests_per_window: usize,
+    /// Max questions across all clients per window. Default: 20.
+    #[serde(default = "default_public_ask_global_max_requests_per_window")]
+    pub global_max_requests_per_window: usize,
+    /// Rate limit window in seconds. Default: 60.
+    #[serde(default = "default_public_ask_rate_window_seconds")]
+    pub rate_window_seconds: u64,
+    /// Max question length in characters. Default: 1000.
+    #[serde(default = "default_public_ask_max_question_chars")]
+    pub max_question_chars: usize,
+    /// Also post each question and answer to the designated chat. Default: true.
+    #[serde(default = "default_public_ask_notify")]
+    pub notify: bool,
+}
+
+fn default_public_ask_max_requests_per_window() -> usize {
+    3
+}
+fn default_public_ask_global_max_requests_per_window() -> usize {
+    20
+}
+fn default_public_ask_rate_window_seconds() -> u64 {
+    60
+}
+fn default_public_ask_max_question_chars() -> usize {
+    1000
+}
+fn default_public_ask_notify() -> bool {
+    true
+}
+
+impl Default for PublicAskConfig {
+    fn default() -> Self {
+        Self {
+            chat_id: 0,
+            persona: None,
+            tokens: Vec::new(),
+            captcha_secret: None,
+            captcha_verify_url: None,
+            allowed_origin: None,
+            instructions_path: None,
+            max_requests_per_window: default_public_ask_max_requests_per_window(),
+            global_max_requests_per_window: default_public_ask_global_max_requests_per_window(),
+            rate_window_seconds: default_public_ask_rate_window_seconds(),
+            max_question_chars: default_public_ask_max_question_chars(),
+            notify: default_public_ask_notify(),
+        }
+    }
+}
+
+impl PublicAskConfig {
+    pub fn persona_name(&self) -> &str {
+        self.persona
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or("guest")
+    }
+
+    pub fn captcha_verify_url(&self) -> &str {
+        self.captcha_verify_url
+            .as_deref()
+            .unwrap_or("https://challenges.cloudflare.com/turnstile/v0/siteverify")
+    }
+
+    /// The endpoint must be protected by at least one of site tokens or captcha.
+    pub fn is_protected(&self) -> bool {
+        !self.tokens.is_empty() || self.captcha_secret.is_some()
+    }
 }
 
 impl SocialConfig {
+    /// OAuth app credentials for `platform`.
+    pub fn platform(&self, platform: &str) -> Option<&SocialPlatformConfig> {
+        match platform {
+            "tiktok" => Some(&self.tiktok),
+            "instagram" => Some(&self.instagram),
+            "linkedin" => Some(&self.linkedin),
+            "google" => Some(&self.google),
+            "spotify" => Some(&self.spotify),
+            _ => None,
+        }
+    }
+
     pub fn is_platform_enabled(&self, platform: &str) -> bool {
-        let (id, secret) = match platform {
-            "tiktok" => (
-                self.tiktok.client_id.as_deref().unwrap_or(""),
-                self.tiktok.client_secret.as_deref().unwrap_or(""),
-            ),
-            "instagram" => (
-                self.instagram.client_id.as_deref().unwrap_or(""),
-                self.instagram.client_secret.as_deref().unwrap_or(""),
-            ),
-            "linkedin" => (
-                self.linkedin.client_id.as_deref().unwrap_or(""),
-                self.linkedin.client_secret.as_deref().unwrap_or(""),
-            ),
-            _ => return false,
+        let Some(cfg) = self.platform(platform) else {
+            return false;
         };
+        let id = cfg.client_id.as_deref().unwrap_or("");
+        let secret = cfg.client_secret.as_deref().unwrap_or("");
         !id.trim().is_empty() && !secret.trim().is_empty()
     }
 }
@@ -197,6 +1307,13 @@ pub struct Config {
     pub model: String,
     #[serde(default)]
     pub llm_base_url: Option<String>,
+    /// Tried in order when the primary provider is rate limited or failing.
+    #[serde(default)]
+    pub llm_fallbacks: Vec<LlmFallbackConfig>,
+    /// Anthropic only: mark the system prompt, tool definitions and conversation history as
+    /// cacheable, so repeated rounds of a long chat read them from the prompt cache. Default: true.
+    #[serde(default = "default_true")]
+    pub anthropic_prompt_caching: bool,
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
     #[serde(default = "default_max_tool_iterations")]
@@ -220,6 +1337,31 @@ pub struct Config {
     pub max_session_messages: usize,
     #[serde(default = "default_compact_keep_recent")]
     pub compact_keep_recent: usize,
+    /// After a session has been idle this many minutes, one cheap LLM call folds what was new
+    /// in it into the persona's Tier 3 memory; 0 = off. Default: 30.
+    #[serde(default = "default_memory_flush_idle_minutes")]
+    pub memory_flush_idle_minutes: u64,
+    /// Model for the idle memory flush (e.g. a smaller one). Empty = main model.
+    #[serde(default)]
+    pub memory_flush_model: String,
+    /// Walk new private chats through a short onboarding (name, timezone, language,
+    /// notifications, goals, persona) on their first message. Default: true.
+    #[serde(default = "default_true")]
+    pub onboarding_enabled: bool,
+    /// Retries of a failed scheduled task before the control chats are alerted, for tasks
+    /// without their own `max_retries`. Default: 2.
+    #[serde(default = "default_task_max_retries")]
+    pub task_max_retries: u32,
+    /// Delay before the first retry of a failed task, doubled for each further one. Default: 60.
+    #[serde(default = "default_task_retry_backoff_secs")]
+    pub task_retry_backoff_secs: u64,
+    /// Scheduled task runs that execute at once; further due tasks wait for a free worker.
+    /// Default: 4.
+    #[serde(default = "default_scheduler_max_concurrent_tasks")]
+    pub scheduler_max_concurrent_tasks: usize,
+    /// Days a reset (deleted) session stays restorable with undelete_session before it is purged.
+    #[serde(default = "default_deleted_session_retention_days")]
+    pub deleted_session_retention_days: u64,
     #[serde(default)]
     pub whatsapp_access_token: Option<String>,
     #[serde(default)]
@@ -232,8 +1374,45 @@ pub struct Config {
     pub discord_bot_token: Option<String>,
     #[serde(default)]
     pub discord_allowed_channels: Vec<u64>,
+    /// Slack bot token (xoxb-...), used to post replies. Slack runs when this and slack_app_token are set.
+    #[serde(default)]
+    pub slack_bot_token: Option<String>,
+    /// Slack app-level token (xapp-...) with connections:write, used for Socket Mode.
+    #[serde(default)]
+    pub slack_app_token: Option<String>,
+    /// Slack channel IDs to respond in (empty = all).
+    #[serde(default)]
+    pub slack_allowed_channels: Vec<String>,
+    /// Optional Signal channel through signal-cli.
+    #[serde(default)]
+    pub signal: Option<SignalConfig>,
+    /// Optional Microsoft Teams channel through the Bot Framework.
+    #[serde(default)]
+    pub teams: Option<TeamsConfig>,
+    /// Optional voice bridge for Home Assistant voice satellites (Wyoming protocol).
+    #[serde(default)]
+    pub wyoming: Option<WyomingConfig>,
+    /// Optional Home Assistant connection (presence, `who_is_home`, `home_assistant`).
+    #[serde(default)]
+    pub home_assistant: Option<HomeAssistantConfig>,
+    /// Optional overrides for the `python` tool (interpreter, limits).
+    #[serde(default)]
+    pub python: Option<PythonConfig>,
+    /// Optional limits for files downloaded by the browser tool.
     #[serde(default)]
-    pub show_thinking: bool,
+    pub browser_downloads: Option<BrowserDownloadConfig>,
+    /// Optional iframe embedding allowlist and CSP override for the web UI.
+    #[serde(default)]
+    pub web_embed: Option<WebEmbedConfig>,
+    /// Optional local Whisper server for voice messages (default: OpenAI with `openai_api_key`).
+    #[serde(default)]
+    pub transcription: Option<TranscriptionConfig>,
+    /// Optional CalDAV calendar (or Google calendar id) for the calendar tools.
+    #[serde(default)]
+    pub calendar: Option<CalendarConfig>,
+    /// Default thinking level for chats without a `/thinking` override.
+    #[serde(default)]
+    pub show_thinking: ThinkingLevel,
     #[serde(default = "default_web_enabled")]
     pub web_enabled: bool,
     #[serde(default = "default_web_host")]
@@ -242,12 +1421,22 @@ pub struct Config {
     pub web_port: u16,
     #[serde(default)]
     pub web_auth_token: Option<String>,
+    /// Reverse proxies (IP addresses) whose forwarded client address headers are trusted for
+    /// per-client rate limits. Requests from anyone else are keyed by their own address.
+    #[serde(default)]
+    pub web_trusted_proxies: Vec<String>,
     #[serde(default = "default_web_max_inflight_per_session")]
     pub web_max_inflight_per_session: usize,
     #[serde(default = "default_web_max_requests_per_window")]
     pub web_max_requests_per_window: usize,
     #[serde(default = "default_web_rate_window_seconds")]
     pub web_rate_window_seconds: u64,
+    /// Read (GET) API requests allowed per bearer token, or per client without one, in the rate window.
+    #[serde(default = "default_web_max_reads_per_window")]
+    pub web_max_reads_per_window: usize,
+    /// Write API requests allowed per bearer token, or per client without one, in the rate window.
+    #[serde(default = "default_web_max_writes_per_window")]
+    pub web_max_writes_per_window: usize,
     #[serde(default = "default_web_run_history_limit")]
     pub web_run_history_limit: usize,
     #[serde(default = "default_web_session_idle_ttl_seconds")]
@@ -275,6 +1464,13 @@ pub struct Config {
     /// Timeout in seconds for cursor-agent runs. Default: 600.
     #[serde(default = "default_cursor_agent_timeout_secs")]
     pub cursor_agent_timeout_secs: u64,
+    /// After a cursor-agent run changes a clean git project, post an LLM review of the diff with approve (commit) / revert buttons. Default: true.
+    #[serde(default = "default_true")]
+    pub cursor_agent_review: bool,
+    /// Days between cursor-agent digests (runs per project, success rate, durations, notable
+    /// outputs) posted to each chat that ran cursor_agent in that window; 0 = off. Default: 7.
+    #[serde(default = "default_cursor_agent_digest_days")]
+    pub cursor_agent_digest_days: u64,
     #[serde(default)]
     pub social: Option<SocialConfig>,
     /// Optional vault/vector DB config for ORIGIN Obsidian vault integration.
@@ -286,6 +1482,88 @@ pub struct Config {
     /// Optional model override for orchestrator (e.g. faster/cheaper). If empty, use main model.
     #[serde(default = "default_orchestrator_model")]
     pub orchestrator_model: String,
+    /// Optional translation config (translate tool, glossaries, inbound auto-translation).
+    #[serde(default)]
+    pub translation: Option<TranslationConfig>,
+    /// Optional public, rate-limited `/ask` endpoint for a personal website.
+    #[serde(default)]
+    pub public_ask: Option<PublicAskConfig>,
+    /// YAML sticker/GIF pack for the send_sticker tool (relative to workspace root or absolute).
+    #[serde(default)]
+    pub sticker_pack_path: Option<String>,
+    /// Optional filter applied to replies in group-facing chats (wordlists and/or a moderation API).
+    #[serde(default)]
+    pub output_filter: Option<OutputFilterConfig>,
+    /// Optional moderation of inbound messages in chats exposed beyond the household.
+    #[serde(default)]
+    pub inbound_moderation: Option<InboundModerationConfig>,
+    /// Outbound network policy for fetch/browser tools. Private IP ranges are blocked by default.
+    #[serde(default)]
+    pub egress: EgressConfig,
+    /// Named browser profiles with saved logins, each restricted to specific personas.
+    #[serde(default)]
+    pub browser_profiles: Vec<BrowserProfileConfig>,
+    /// Per-model prices (USD per million tokens) for usage cost estimates; overrides built-in prices.
+    #[serde(default)]
+    pub model_prices: HashMap<String, ModelPrice>,
+    /// A/B prompt/model experiments. The first experiment listing a chat applies to it.
+    #[serde(default)]
+    pub experiments: Vec<ExperimentConfig>,
+    /// Days between feedback reports (low-rated replies and failed runs, with suggested prompt
+    /// and skill changes) sent to the control chats; 0 = off. Also on demand via `feedback_report`.
+    #[serde(default)]
+    pub feedback_report_days: u64,
+    /// Coordinate redundant instances sharing this workspace: only the one holding the lock
+    /// polls the channels and runs the scheduler; the others serve the web UI read-only.
+    #[serde(default)]
+    pub instance_lock: bool,
+    /// RSS/Atom feeds whose new items are posted into a chat for the agent to act on.
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+    /// Script hooks run on inbound messages or outbound replies, after the built-in stages.
+    #[serde(default)]
+    pub middleware_hooks: Vec<MiddlewareHookConfig>,
+    /// Default state of each per-chat feature flag (`proactive_messages`, `vision`, `web_search`,
+    /// `social_tools`); unlisted flags are on. Chats can override them.
+    #[serde(default)]
+    pub feature_flags: HashMap<String, bool>,
+    /// On the first start after an upgrade, post the release notes of the new version (including
+    /// config changes) to the control chats. Default: true.
+    #[serde(default = "default_true")]
+    pub announce_release_notes: bool,
+    /// Optional digesting of proactive messages so bursts arrive as one grouped message.
+    #[serde(default)]
+    pub notifications: Option<NotificationConfig>,
+    /// Optional heartbeat pings and startup/shutdown notices to the admin chat.
+    #[serde(default)]
+    pub heartbeat: Option<HeartbeatConfig>,
+    /// Optional encryption at rest of the runtime directory.
+    #[serde(default)]
+    pub workspace_encryption: Option<WorkspaceEncryptionConfig>,
+    /// Incident paging settings (defaults apply when unset).
+    #[serde(default)]
+    pub incidents: Option<IncidentConfig>,
+    /// Optional ntfy/Gotify push delivery of proactive messages, per chat with `/push on`.
+    #[serde(default)]
+    pub push: Option<PushConfig>,
+    /// Optional upload of large documents to the provider's Files API.
+    #[serde(default)]
+    pub provider_files: Option<ProviderFilesConfig>,
+    /// Personas and rounds for `/debate` and the `roundtable` tool (built-in pair when unset).
+    #[serde(default)]
+    pub roundtable: Option<RoundtableConfig>,
+    /// Optional monthly benchmark of candidate models with the eval harness.
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkConfig>,
+    /// Optional archiving of long-inactive chats to compressed files.
+    #[serde(default)]
+    pub chat_archive: Option<ChatArchiveConfig>,
+    /// Settings for provider-side web search (defaults when unset).
+    #[serde(default)]
+    pub provider_search: Option<ProviderSearchConfig>,
+    /// Optional auto-replies in the owner's Telegram Business inbox.
+    #[serde(default)]
+    pub telegram_business: Option<TelegramBusinessConfig>,
 }
 
 impl Config {
@@ -299,12 +1577,20 @@ impl Config {
         &self.workspace_dir
     }
 
-    /// Runtime data directory (db, memory, exports, etc.).
+    /// Runtime data directory (db, memory, exports, etc.). With workspace encryption this is the
+    /// unpacked copy in the mount directory.
     pub fn runtime_data_dir(&self) -> String {
-        self.data_root_dir()
-            .join("runtime")
-            .to_string_lossy()
-            .to_string()
+        match &self.workspace_encryption {
+            Some(enc) => enc
+                .mount_path(&self.workspace_root_absolute())
+                .to_string_lossy()
+                .to_string(),
+            None => self
+                .data_root_dir()
+                .join("runtime")
+                .to_string_lossy()
+                .to_string(),
+        }
     }
 
     /// Skills directory under data root.
@@ -405,6 +1691,17 @@ impl Config {
             .unwrap_or_default()
     }
 
+    fn env_vec_string(key: &str) -> Vec<String> {
+        Self::env(key)
+            .map(|s| {
+                s.split(',')
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn env_vec_u64(key: &str) -> Vec<u64> {
         Self::env(key)
             .map(|s| {
@@ -465,6 +1762,22 @@ impl Config {
                     principles_path: Self::env("VAULT_PRINCIPLES_PATH"),
                     vector_db_url: Self::env("VAULT_VECTOR_DB_URL"),
                     vector_db_collection: Self::env("VAULT_VECTOR_DB_COLLECTION"),
+                    embedding_model: Self::env("VAULT_EMBEDDING_MODEL"),
+                    embedding_batch_size: Self::env("VAULT_EMBEDDING_BATCH_SIZE")
+                        .and_then(|v| v.parse().ok()),
+                    embedding_cache: Some(Self::env_bool("VAULT_EMBEDDING_CACHE", true)),
+                    obsidian_vault_name: Self::env("VAULT_OBSIDIAN_NAME"),
+                    maintenance: Self::env_bool("VAULT_MAINTENANCE", false).then(|| {
+                        VaultMaintenanceConfig {
+                            interval_days: Self::env("VAULT_MAINTENANCE_INTERVAL_DAYS")
+                                .and_then(|v| v.parse().ok())
+                                .unwrap_or_else(default_maintenance_interval_days),
+                            chat_id: Self::env("VAULT_MAINTENANCE_CHAT_ID")
+                                .and_then(|v| v.parse().ok()),
+                            daily_notes_folder: Self::env("VAULT_DAILY_NOTES_FOLDER"),
+                            ..VaultMaintenanceConfig::default()
+                        }
+                    }),
                 })
             } else {
                 None
@@ -475,7 +1788,10 @@ impl Config {
             let has_social = Self::env("SOCIAL_BASE_URL").is_some()
                 || Self::env("SOCIAL_TIKTOK_CLIENT_ID").is_some()
                 || Self::env("SOCIAL_INSTAGRAM_CLIENT_ID").is_some()
-                || Self::env("SOCIAL_LINKEDIN_CLIENT_ID").is_some();
+                || Self::env("SOCIAL_LINKEDIN_CLIENT_ID").is_some()
+                || Self::env("SOCIAL_GOOGLE_CLIENT_ID").is_some()
+                || Self::env("SOCIAL_SPOTIFY_CLIENT_ID").is_some()
+                || Self::env("SOCIAL_MONITOR_INTERVAL_MINS").is_some();
             if has_social {
                 Some(SocialConfig {
                     base_url: Self::env("SOCIAL_BASE_URL"),
@@ -491,12 +1807,116 @@ impl Config {
                         client_id: Self::env("SOCIAL_LINKEDIN_CLIENT_ID"),
                         client_secret: Self::env("SOCIAL_LINKEDIN_CLIENT_SECRET"),
                     },
+                    google: SocialPlatformConfig {
+                        client_id: Self::env("SOCIAL_GOOGLE_CLIENT_ID"),
+                        client_secret: Self::env("SOCIAL_GOOGLE_CLIENT_SECRET"),
+                    },
+                    spotify: SocialPlatformConfig {
+                        client_id: Self::env("SOCIAL_SPOTIFY_CLIENT_ID"),
+                        client_secret: Self::env("SOCIAL_SPOTIFY_CLIENT_SECRET"),
+                    },
+                    monitor_interval_mins: Self::env("SOCIAL_MONITOR_INTERVAL_MINS")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0),
+                    milestones: Self::env_vec_u64("SOCIAL_MILESTONES"),
+                })
+            } else {
+                None
+            }
+        };
+
+        let translation = {
+            let has_translation = Self::env("TRANSLATION_DEEPL_API_KEY").is_some()
+                || Self::env("TRANSLATION_AUTO_INBOUND").is_some()
+                || Self::env("TRANSLATION_TARGET_LANGUAGE").is_some();
+            if has_translation {
+                Some(TranslationConfig {
+                    deepl_api_key: Self::env("TRANSLATION_DEEPL_API_KEY"),
+                    deepl_api_url: Self::env("TRANSLATION_DEEPL_API_URL"),
+                    auto_translate_inbound: Self::env_bool("TRANSLATION_AUTO_INBOUND", false),
+                    target_language: Self::env("TRANSLATION_TARGET_LANGUAGE"),
+                })
+            } else {
+                None
+            }
+        };
+
+        let output_filter = {
+            let words = Self::env("OUTPUT_FILTER_WORDS");
+            let wordlist_path = Self::env("OUTPUT_FILTER_WORDLIST_PATH");
+            let moderation_api = Self::env_bool("OUTPUT_FILTER_MODERATION_API", false);
+            if words.is_some() || wordlist_path.is_some() || moderation_api {
+                Some(OutputFilterConfig {
+                    chat_types: Self::env_vec_string("OUTPUT_FILTER_CHAT_TYPES"),
+                    chat_ids: Self::env_vec_i64("OUTPUT_FILTER_CHAT_IDS"),
+                    words: Self::env_vec_string("OUTPUT_FILTER_WORDS"),
+                    wordlist_path,
+                    action: Self::env("OUTPUT_FILTER_ACTION"),
+                    blocked_message: Self::env("OUTPUT_FILTER_BLOCKED_MESSAGE"),
+                    moderation_api,
+                    moderation_api_url: Self::env("OUTPUT_FILTER_MODERATION_API_URL"),
                 })
             } else {
                 None
             }
         };
 
+        let inbound_moderation = if Self::env_bool("INBOUND_MODERATION_ENABLED", false) {
+            Some(InboundModerationConfig {
+                chat_types: Self::env_vec_string("INBOUND_MODERATION_CHAT_TYPES"),
+                chat_ids: Self::env_vec_i64("INBOUND_MODERATION_CHAT_IDS"),
+                public_ask: Self::env_bool(
+                    "INBOUND_MODERATION_PUBLIC_ASK",
+                    default_moderate_public_ask(),
+                ),
+                review_chat_id: Self::env("INBOUND_MODERATION_REVIEW_CHAT_ID")
+                    .and_then(|s| s.parse().ok()),
+                max_links: Self::env("INBOUND_MODERATION_MAX_LINKS").and_then(|s| s.parse().ok()),
+                blocked_domains: Self::env_vec_string("INBOUND_MODERATION_BLOCKED_DOMAINS"),
+                max_messages_per_minute: Self::env("INBOUND_MODERATION_MAX_MESSAGES_PER_MINUTE")
+                    .and_then(|s| s.parse().ok()),
+                injection_patterns: Vec::new(),
+            })
+        } else {
+            None
+        };
+
+        let public_ask = Self::env("PUBLIC_ASK_CHAT_ID")
+            .and_then(|s| s.parse::<i64>().ok())
+            .map(|chat_id| PublicAskConfig {
+                chat_id,
+                persona: Self::env("PUBLIC_ASK_PERSONA"),
+                tokens: Self::env("PUBLIC_ASK_TOKENS")
+                    .map(|s| {
+                        s.split(',')
+                            .map(|t| t.trim().to_string())
+                            .filter(|t| !t.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                captcha_secret: Self::env("PUBLIC_ASK_CAPTCHA_SECRET"),
+                captcha_verify_url: Self::env("PUBLIC_ASK_CAPTCHA_VERIFY_URL"),
+                allowed_origin: Self::env("PUBLIC_ASK_ALLOWED_ORIGIN"),
+                instructions_path: Self::env("PUBLIC_ASK_INSTRUCTIONS_PATH"),
+                max_requests_per_window: Self::env_usize(
+                    "PUBLIC_ASK_MAX_REQUESTS_PER_WINDOW",
+                    default_public_ask_max_requests_per_window(),
+                ),
+                global_max_requests_per_window: Self::env_usize(
+                    "PUBLIC_ASK_GLOBAL_MAX_REQUESTS_PER_WINDOW",
+                    default_public_ask_global_max_requests_per_window(),
+                ),
+                rate_window_seconds: Self::env_u64(
+                    "PUBLIC_ASK_RATE_WINDOW_SECONDS",
+                    default_public_ask_rate_window_seconds(),
+                ),
+                max_question_chars: Self::env_usize(
+                    "PUBLIC_ASK_MAX_CHARS",
+                    default_public_ask_max_question_chars(),
+                ),
+                notify: Self::env_bool("PUBLIC_ASK_NOTIFY", default_public_ask_notify()),
+            });
+
         Config {
             telegram_bot_token: Self::env("TELEGRAM_BOT_TOKEN").unwrap_or_default(),
             bot_username: Self::env("BOT_USERNAME").unwrap_or_default(),
@@ -504,6 +1924,19 @@ impl Config {
             api_key: Self::env("LLM_API_KEY").unwrap_or_else(default_api_key),
             model: Self::env("LLM_MODEL").unwrap_or_default(),
             llm_base_url: Self::env("LLM_BASE_URL"),
+            // "provider:model" entries, e.g. "openai:gpt-4o-mini,anthropic:claude-3-5-haiku-latest"
+            llm_fallbacks: Self::env_vec_string("LLM_FALLBACK_PROVIDERS")
+                .into_iter()
+                .filter_map(|entry| {
+                    let (provider, model) = entry.split_once(':')?;
+                    Some(LlmFallbackConfig {
+                        provider: provider.trim().to_string(),
+                        model: model.trim().to_string(),
+                        api_key: Self::env("LLM_FALLBACK_API_KEY"),
+                        base_url: None,
+                    })
+                })
+                .collect(),
             max_tokens: Self::env_u32("MAX_TOKENS", default_max_tokens()),
             max_tool_iterations: Self::env_usize("MAX_TOOL_ITERATIONS", default_max_tool_iterations()),
             max_history_messages: Self::env_usize("MAX_HISTORY_MESSAGES", default_max_history_messages()),
@@ -516,17 +1949,156 @@ impl Config {
             control_chat_ids: Self::env_vec_i64("CONTROL_CHAT_IDS"),
             max_session_messages: Self::env_usize("MAX_SESSION_MESSAGES", default_max_session_messages()),
             compact_keep_recent: Self::env_usize("COMPACT_KEEP_RECENT", default_compact_keep_recent()),
+            memory_flush_idle_minutes: Self::env_u64(
+                "MEMORY_FLUSH_IDLE_MINUTES",
+                default_memory_flush_idle_minutes(),
+            ),
+            memory_flush_model: Self::env("MEMORY_FLUSH_MODEL").unwrap_or_default(),
+            onboarding_enabled: Self::env_bool("ONBOARDING_ENABLED", true),
+            task_max_retries: Self::env("TASK_MAX_RETRIES")
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or_else(default_task_max_retries),
+            task_retry_backoff_secs: Self::env_u64(
+                "TASK_RETRY_BACKOFF_SECS",
+                default_task_retry_backoff_secs(),
+            ),
+            scheduler_max_concurrent_tasks: Self::env_usize(
+                "SCHEDULER_MAX_CONCURRENT_TASKS",
+                default_scheduler_max_concurrent_tasks(),
+            ),
+            deleted_session_retention_days: Self::env_u64(
+                "DELETED_SESSION_RETENTION_DAYS",
+                default_deleted_session_retention_days(),
+            ),
             whatsapp_access_token: Self::env("WHATSAPP_ACCESS_TOKEN"),
             whatsapp_phone_number_id: Self::env("WHATSAPP_PHONE_NUMBER_ID"),
             whatsapp_verify_token: Self::env("WHATSAPP_VERIFY_TOKEN"),
             whatsapp_webhook_port: Self::env_u16("WHATSAPP_WEBHOOK_PORT", default_whatsapp_webhook_port()),
             discord_bot_token: Self::env("DISCORD_BOT_TOKEN"),
             discord_allowed_channels: Self::env_vec_u64("DISCORD_ALLOWED_CHANNELS"),
-            show_thinking: Self::env_bool("SHOW_THINKING", false),
+            slack_bot_token: Self::env("SLACK_BOT_TOKEN"),
+            slack_app_token: Self::env("SLACK_APP_TOKEN"),
+            slack_allowed_channels: Self::env_vec_string("SLACK_ALLOWED_CHANNELS"),
+            signal: Self::env("SIGNAL_ACCOUNT").map(|signal_account| SignalConfig {
+                signal_cli_path: Self::env("SIGNAL_CLI_PATH")
+                    .unwrap_or_else(default_signal_cli_path),
+                signal_account,
+                allowed_groups: Self::env_vec_string("SIGNAL_ALLOWED_GROUPS"),
+            }),
+            teams: match (Self::env("TEAMS_APP_ID"), Self::env("TEAMS_APP_PASSWORD")) {
+                (Some(app_id), Some(app_password)) => Some(TeamsConfig {
+                    app_id,
+                    app_password,
+                    tenant_id: Self::env("TEAMS_TENANT_ID"),
+                    webhook_port: Self::env_u16("TEAMS_WEBHOOK_PORT", default_teams_webhook_port()),
+                    allowed_channels: Self::env_vec_string("TEAMS_ALLOWED_CHANNELS"),
+                }),
+                _ => None,
+            },
+            wyoming: Self::env("WYOMING_PORT").map(|port| WyomingConfig {
+                host: Self::env("WYOMING_HOST").unwrap_or_else(default_wyoming_host),
+                port: port.parse().unwrap_or_else(|_| default_wyoming_port()),
+                chat_id: Self::env("WYOMING_CHAT_ID").and_then(|v| v.parse().ok()),
+                languages: {
+                    let languages = Self::env_vec_string("WYOMING_LANGUAGES");
+                    if languages.is_empty() {
+                        default_wyoming_languages()
+                    } else {
+                        languages
+                    }
+                },
+            }),
+            home_assistant: match (
+                Self::env("HOME_ASSISTANT_URL"),
+                Self::env("HOME_ASSISTANT_TOKEN"),
+            ) {
+                (Some(url), Some(token)) => Some(HomeAssistantConfig {
+                    url,
+                    token,
+                    presence_entities: Self::env_vec_string("HA_PRESENCE_ENTITIES"),
+                    presence_chats: Self::env_vec_string("HA_PRESENCE_CHATS")
+                        .iter()
+                        .filter_map(|entry| {
+                            let (entity, chat_id) = entry.split_once('=')?;
+                            Some((entity.trim().to_string(), chat_id.trim().parse().ok()?))
+                        })
+                        .collect(),
+                    presence_in_prompt: Self::env_bool("HA_PRESENCE_IN_PROMPT", true),
+                    service_domains: Self::env_vec_string("HA_SERVICE_DOMAINS"),
+                }),
+                _ => None,
+            },
+            python: {
+                let has_python = Self::env("PYTHON_INTERPRETER").is_some()
+                    || Self::env("PYTHON_TIMEOUT_SECS").is_some()
+                    || Self::env("PYTHON_MEMORY_MB").is_some()
+                    || Self::env("PYTHON_ALLOW_PACKAGES").is_some();
+                has_python.then(|| PythonConfig {
+                    interpreter: Self::env("PYTHON_INTERPRETER")
+                        .unwrap_or_else(default_python_interpreter),
+                    timeout_secs: Self::env_u64(
+                        "PYTHON_TIMEOUT_SECS",
+                        default_python_timeout_secs(),
+                    ),
+                    memory_mb: Self::env_u64("PYTHON_MEMORY_MB", default_python_memory_mb()),
+                    allow_packages: Self::env_bool("PYTHON_ALLOW_PACKAGES", true),
+                })
+            },
+            browser_downloads: {
+                let has_limits = Self::env("BROWSER_DOWNLOAD_MAX_MB").is_some()
+                    || Self::env("BROWSER_DOWNLOAD_ALLOWED_TYPES").is_some()
+                    || Self::env("BROWSER_DOWNLOAD_BLOCKED_TYPES").is_some();
+                has_limits.then(|| BrowserDownloadConfig {
+                    max_mb: Self::env_u64(
+                        "BROWSER_DOWNLOAD_MAX_MB",
+                        default_browser_download_max_mb(),
+                    ),
+                    allowed_types: Self::env_vec_string("BROWSER_DOWNLOAD_ALLOWED_TYPES"),
+                    blocked_types: if Self::env("BROWSER_DOWNLOAD_BLOCKED_TYPES").is_some() {
+                        Self::env_vec_string("BROWSER_DOWNLOAD_BLOCKED_TYPES")
+                    } else {
+                        default_browser_download_blocked_types()
+                    },
+                })
+            },
+            transcription: Self::env("WHISPER_URL").map(|url| TranscriptionConfig {
+                url,
+                model: Self::env("WHISPER_MODEL").unwrap_or_else(default_transcription_model),
+                language: Self::env("WHISPER_LANGUAGE"),
+                api_key: Self::env("WHISPER_API_KEY"),
+            }),
+            calendar: {
+                let has_calendar =
+                    Self::env("CALDAV_URL").is_some() || Self::env("GOOGLE_CALENDAR_ID").is_some();
+                has_calendar.then(|| CalendarConfig {
+                    caldav_url: Self::env("CALDAV_URL"),
+                    username: Self::env("CALDAV_USERNAME"),
+                    password: Self::env("CALDAV_PASSWORD"),
+                    chat_ids: Self::env_vec_i64("CALDAV_CHAT_IDS"),
+                    google_calendar_id: Self::env("GOOGLE_CALENDAR_ID")
+                        .unwrap_or_else(default_google_calendar_id),
+                })
+            },
+            web_embed: {
+                let frame_ancestors = Self::env_vec_string("WEB_FRAME_ANCESTORS");
+                let content_security_policy = Self::env("WEB_CONTENT_SECURITY_POLICY");
+                if frame_ancestors.is_empty() && content_security_policy.is_none() {
+                    None
+                } else {
+                    Some(WebEmbedConfig {
+                        frame_ancestors,
+                        content_security_policy,
+                    })
+                }
+            },
+            show_thinking: Self::env("SHOW_THINKING")
+                .and_then(|v| ThinkingLevel::parse(&v))
+                .unwrap_or_default(),
             web_enabled: Self::env_bool("WEB_ENABLED", default_web_enabled()),
             web_host: Self::env("WEB_HOST").unwrap_or_else(default_web_host),
             web_port: Self::env_u16("WEB_PORT", default_web_port()),
             web_auth_token: Self::env("WEB_AUTH_TOKEN"),
+            web_trusted_proxies: Self::env_vec_string("WEB_TRUSTED_PROXIES"),
             web_max_inflight_per_session: Self::env_usize(
                 "WEB_MAX_INFLIGHT_PER_SESSION",
                 default_web_max_inflight_per_session(),
@@ -539,6 +2111,14 @@ impl Config {
                 "WEB_RATE_WINDOW_SECONDS",
                 default_web_rate_window_seconds(),
             ),
+            web_max_reads_per_window: Self::env_usize(
+                "WEB_MAX_READS_PER_WINDOW",
+                default_web_max_reads_per_window(),
+            ),
+            web_max_writes_per_window: Self::env_usize(
+                "WEB_MAX_WRITES_PER_WINDOW",
+                default_web_max_writes_per_window(),
+            ),
             web_run_history_limit: Self::env_usize(
                 "WEB_RUN_HISTORY_LIMIT",
                 default_web_run_history_limit(),
@@ -563,6 +2143,11 @@ impl Config {
                 "CURSOR_AGENT_TIMEOUT_SECS",
                 default_cursor_agent_timeout_secs(),
             ),
+            cursor_agent_review: Self::env_bool("CURSOR_AGENT_REVIEW", true),
+            cursor_agent_digest_days: Self::env_u64(
+                "CURSOR_AGENT_DIGEST_DAYS",
+                default_cursor_agent_digest_days(),
+            ),
             social,
             vault,
             orchestrator_enabled: Self::env_bool(
@@ -570,18 +2155,273 @@ impl Config {
                 default_orchestrator_enabled(),
             ),
             orchestrator_model: Self::env("ORCHESTRATOR_MODEL").unwrap_or_default(),
+            translation,
+            public_ask,
+            sticker_pack_path: Self::env("STICKER_PACK_PATH"),
+            output_filter,
+            inbound_moderation,
+            egress: EgressConfig {
+                allow_domains: Self::env_vec_string("EGRESS_ALLOW_DOMAINS"),
+                deny_domains: Self::env_vec_string("EGRESS_DENY_DOMAINS"),
+                allow_private_networks: Self::env_bool("EGRESS_ALLOW_PRIVATE_NETWORKS", false),
+                allow_private_hosts: Self::env_vec_string("EGRESS_ALLOW_PRIVATE_HOSTS"),
+            },
+            browser_profiles: Self::env_vec_string("BROWSER_PROFILES")
+                .iter()
+                .map(|entry| {
+                    let (name, personas) = entry.split_once(':').unwrap_or((entry, ""));
+                    BrowserProfileConfig {
+                        name: name.trim().to_string(),
+                        personas: personas
+                            .split('|')
+                            .map(|p| p.trim().to_string())
+                            .filter(|p| !p.is_empty())
+                            .collect(),
+                        chat_ids: Vec::new(),
+                    }
+                })
+                .collect(),
+            model_prices: Self::env_vec_string("MODEL_PRICES")
+                .iter()
+                .filter_map(|entry| {
+                    let (model, prices) = entry.split_once('=')?;
+                    let (input, output) = prices.split_once('/')?;
+                    Some((
+                        model.trim().to_string(),
+                        ModelPrice {
+                            input: input.trim().parse().ok()?,
+                            output: output.trim().parse().ok()?,
+                        },
+                    ))
+                })
+                .collect(),
+            experiments: Self::env("EXPERIMENT_NAME")
+                .map(|name| ExperimentConfig {
+                    name: name.trim().to_string(),
+                    chat_ids: Self::env_vec_i64("EXPERIMENT_CHAT_IDS"),
+                    percent: Self::env("EXPERIMENT_PERCENT")
+                        .and_then(|v| v.trim().parse().ok())
+                        .unwrap_or_else(default_experiment_percent)
+                        .min(100),
+                    system_prompt_append: Self::env("EXPERIMENT_SYSTEM_PROMPT_APPEND"),
+                    model: Self::env("EXPERIMENT_MODEL"),
+                })
+                .into_iter()
+                .collect(),
+            feedback_report_days: Self::env_u64("FEEDBACK_REPORT_DAYS", 0),
+            instance_lock: Self::env_bool("INSTANCE_LOCK", false),
+            feeds: Self::env("FEED_CHAT_ID")
+                .and_then(|id| id.trim().parse::<i64>().ok())
+                .map(|chat_id| {
+                    Self::env_vec_string("FEED_URLS")
+                        .into_iter()
+                        .map(|url| FeedConfig {
+                            url,
+                            chat_id,
+                            name: None,
+                            interval_mins: Self::env_u64(
+                                "FEED_INTERVAL_MINS",
+                                default_feed_interval_mins(),
+                            ),
+                            instructions: Self::env("FEED_INSTRUCTIONS"),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            middleware_hooks: [
+                ("MIDDLEWARE_INBOUND_HOOK", MiddlewareStage::Inbound),
+                ("MIDDLEWARE_OUTBOUND_HOOK", MiddlewareStage::Outbound),
+            ]
+            .into_iter()
+            .filter_map(|(key, stage)| {
+                Self::env(key).map(|command| MiddlewareHookConfig {
+                    stage,
+                    command,
+                    chat_ids: Vec::new(),
+                    timeout_secs: Self::env_u64(
+                        "MIDDLEWARE_HOOK_TIMEOUT_SECS",
+                        default_hook_timeout_secs(),
+                    ),
+                })
+            })
+            .collect(),
+            feature_flags: Self::env_vec_string("FEATURE_FLAGS_OFF")
+                .into_iter()
+                .map(|flag| (flag, false))
+                .chain(
+                    Self::env_vec_string("FEATURE_FLAGS_ON")
+                        .into_iter()
+                        .map(|flag| (flag, true)),
+                )
+                .collect(),
+            announce_release_notes: Self::env_bool("ANNOUNCE_RELEASE_NOTES", true),
+            anthropic_prompt_caching: Self::env_bool("ANTHROPIC_PROMPT_CACHING", true),
+            notifications: Self::env("NOTIFICATION_DIGEST_WINDOW_SECS").map(|window| {
+                NotificationConfig {
+                    window_secs: window
+                        .trim()
+                        .parse()
+                        .unwrap_or_else(|_| default_notification_window_secs()),
+                    priorities: Self::env_vec_string("NOTIFICATION_URGENT_SOURCES")
+                        .into_iter()
+                        .map(|s| (s, NotificationPriority::Urgent))
+                        .collect(),
+                }
+            }),
+            heartbeat: {
+                let url = Self::env("HEARTBEAT_URL");
+                let admin_chat_id =
+                    Self::env("HEARTBEAT_ADMIN_CHAT_ID").and_then(|s| s.trim().parse().ok());
+                if url.is_some() || admin_chat_id.is_some() {
+                    Some(HeartbeatConfig {
+                        url,
+                        interval_secs: Self::env("HEARTBEAT_INTERVAL_SECS")
+                            .and_then(|s| s.trim().parse().ok())
+                            .unwrap_or_else(default_heartbeat_interval_secs),
+                        admin_chat_id,
+                        notify_lifecycle: Self::env_bool("HEARTBEAT_NOTIFY_LIFECYCLE", true),
+                    })
+                } else {
+                    None
+                }
+            },
+            workspace_encryption: Self::env_bool("WORKSPACE_ENCRYPTION", false).then(|| {
+                WorkspaceEncryptionConfig {
+                    passphrase_env: default_workspace_passphrase_env(),
+                    passphrase_file: Self::env("WORKSPACE_ENCRYPTION_PASSPHRASE_FILE"),
+                    mount_dir: Self::env("WORKSPACE_ENCRYPTION_MOUNT_DIR"),
+                    sync_interval_secs: Self::env("WORKSPACE_ENCRYPTION_SYNC_SECS")
+                        .and_then(|s| s.trim().parse().ok())
+                        .unwrap_or_else(default_workspace_sync_interval_secs),
+                }
+            }),
+            incidents: {
+                let chat_id = Self::env("INCIDENT_CHAT_ID").and_then(|s| s.trim().parse().ok());
+                let ping_users = Self::env_vec_string("INCIDENT_PING_USERS");
+                let repeat_after = Self::env("INCIDENT_REPEAT_AFTER_MINS");
+                let max_repeats = Self::env("INCIDENT_MAX_REPEATS");
+                if chat_id.is_some()
+                    || !ping_users.is_empty()
+                    || repeat_after.is_some()
+                    || max_repeats.is_some()
+                {
+                    Some(IncidentConfig {
+                        chat_id,
+                        ping_users,
+                        repeat_after_mins: repeat_after
+                            .and_then(|s| s.trim().parse().ok())
+                            .unwrap_or_else(default_incident_repeat_after_mins),
+                        max_repeats: max_repeats
+                            .and_then(|s| s.trim().parse().ok())
+                            .unwrap_or_else(default_incident_max_repeats),
+                    })
+                } else {
+                    None
+                }
+            },
+            push: Self::env("PUSH_PROVIDER").and_then(|provider| {
+                let provider = match provider.to_lowercase().as_str() {
+                    "ntfy" => PushProvider::Ntfy,
+                    "gotify" => PushProvider::Gotify,
+                    _ => return None,
+                };
+                Some(PushConfig {
+                    provider,
+                    url: Self::env("PUSH_URL"),
+                    topic: Self::env("PUSH_TOPIC"),
+                    token: Self::env("PUSH_TOKEN"),
+                    web_url: Self::env("PUSH_WEB_URL"),
+                })
+            }),
+            provider_files: Self::env_bool("PROVIDER_FILES", false).then(|| {
+                ProviderFilesConfig {
+                    min_kb: Self::env_u64("PROVIDER_FILES_MIN_KB", default_provider_files_min_kb()),
+                    max_mb: Self::env_u64("PROVIDER_FILES_MAX_MB", default_provider_files_max_mb()),
+                    code_interpreter: Self::env_bool("PROVIDER_FILES_CODE_INTERPRETER", false),
+                    retention_hours: Self::env_u64(
+                        "PROVIDER_FILES_RETENTION_HOURS",
+                        default_provider_files_retention_hours(),
+                    ),
+                }
+            }),
+            roundtable: {
+                let has_roundtable = Self::env("ROUNDTABLE_TURNS").is_some()
+                    || Self::env("ROUNDTABLE_SYNTHESIZER_MODEL").is_some();
+                has_roundtable.then(|| RoundtableConfig {
+                    turns: Self::env_u64("ROUNDTABLE_TURNS", default_roundtable_turns() as u64)
+                        as u32,
+                    synthesizer_model: Self::env("ROUNDTABLE_SYNTHESIZER_MODEL"),
+                    ..RoundtableConfig::default()
+                })
+            },
+            // "provider:model" entries, like LLM_FALLBACK_PROVIDERS
+            benchmark: {
+                let models: Vec<LlmFallbackConfig> = Self::env_vec_string("BENCHMARK_MODELS")
+                    .into_iter()
+                    .filter_map(|entry| {
+                        let (provider, model) = entry.split_once(':')?;
+                        Some(LlmFallbackConfig {
+                            provider: provider.trim().to_string(),
+                            model: model.trim().to_string(),
+                            api_key: Self::env("BENCHMARK_API_KEY"),
+                            base_url: None,
+                        })
+                    })
+                    .collect();
+                (!models.is_empty()).then(|| BenchmarkConfig {
+                    models,
+                    suite: Self::env("BENCHMARK_SUITE"),
+                    judge_model: Self::env("BENCHMARK_JUDGE_MODEL"),
+                    interval_days: Self::env("BENCHMARK_INTERVAL_DAYS")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or_else(default_benchmark_interval_days),
+                    chat_id: Self::env("BENCHMARK_CHAT_ID").and_then(|v| v.parse().ok()),
+                })
+            },
+            chat_archive: Self::env("CHAT_ARCHIVE_AFTER_MONTHS")
+                .and_then(|v| v.parse().ok())
+                .map(|inactive_months| ChatArchiveConfig {
+                    inactive_months,
+                    vacuum: Self::env_bool("CHAT_ARCHIVE_VACUUM", true),
+                }),
+            provider_search: (Self::env("PROVIDER_SEARCH_MAX_USES").is_some()
+                || Self::env("PROVIDER_SEARCH_OPENAI_MODEL").is_some())
+            .then(|| ProviderSearchConfig {
+                max_uses: Self::env_u64(
+                    "PROVIDER_SEARCH_MAX_USES",
+                    default_provider_search_max_uses() as u64,
+                ) as u32,
+                openai_model: Self::env("PROVIDER_SEARCH_OPENAI_MODEL")
+                    .unwrap_or_else(default_provider_search_openai_model),
+            }),
+            telegram_business: (Self::env_bool("TELEGRAM_BUSINESS", false)
+                || Self::env("TELEGRAM_BUSINESS_OWNER_IDS").is_some())
+            .then(|| TelegramBusinessConfig {
+                owner_ids: Self::env_vec_i64("TELEGRAM_BUSINESS_OWNER_IDS"),
+                instructions_path: Self::env("TELEGRAM_BUSINESS_INSTRUCTIONS"),
+                history_messages: Self::env_usize(
+                    "TELEGRAM_BUSINESS_HISTORY_MESSAGES",
+                    default_telegram_business_history_messages(),
+                ),
+                notify: Self::env_bool("TELEGRAM_BUSINESS_NOTIFY", true),
+            }),
         }
     }
 
     /// Apply post-deserialization normalization and validation.
     pub(crate) fn post_deserialize(&mut self) -> Result<(), MicroClawError> {
         self.llm_provider = self.llm_provider.trim().to_lowercase();
+        if matches!(self.llm_provider.as_str(), "llama.cpp" | "llama-cpp") {
+            self.llm_provider = "llamacpp".into();
+        }
 
         // Apply provider-specific default model if empty
         if self.model.is_empty() {
             self.model = match self.llm_provider.as_str() {
                 "anthropic" => "claude-sonnet-4-5-20250929".into(),
                 "ollama" => "llama3.2".into(),
+                // llama-server serves whatever model it was started with
+                "llamacpp" => "default".into(),
                 "google" => "gemini-2.5-flash".into(),
                 _ => "gpt-5.2".into(),
             };
@@ -620,6 +2460,88 @@ impl Config {
                 "web_auth_token is required when web_enabled=true and web_host is not local".into(),
             ));
         }
+        if let Some(embed) = &self.web_embed {
+            if let Some(bad) = embed
+                .frame_ancestors
+                .iter()
+                .find(|o| !WebEmbedConfig::valid_origin(o))
+            {
+                return Err(MicroClawError::Config(format!(
+                    "web_embed.frame_ancestors entry '{bad}' must be an origin like https://ha.example.com:8123"
+                )));
+            }
+        }
+        if let Some(push) = &self.push {
+            if push.provider == PushProvider::Gotify
+                && (push.url.is_none() || push.token.is_none())
+            {
+                return Err(MicroClawError::Config(
+                    "push.url and push.token (application token) are required for Gotify".into(),
+                ));
+            }
+        }
+        if let Some(roundtable) = &self.roundtable {
+            let mut names: Vec<String> = roundtable
+                .personas
+                .iter()
+                .map(|p| p.name.trim().to_lowercase())
+                .collect();
+            names.sort();
+            names.dedup();
+            if roundtable.personas.len() < 2
+                || names.len() != roundtable.personas.len()
+                || names.iter().any(|n| n.is_empty())
+            {
+                return Err(MicroClawError::Config(
+                    "roundtable.personas needs at least two personas with distinct names".into(),
+                ));
+            }
+            if !(1..=6).contains(&roundtable.turns) {
+                return Err(MicroClawError::Config(
+                    "roundtable.turns must be between 1 and 6".into(),
+                ));
+            }
+        }
+        if let Some(benchmark) = &self.benchmark {
+            if benchmark.models.is_empty() {
+                return Err(MicroClawError::Config(
+                    "benchmark.models needs at least one candidate model".into(),
+                ));
+            }
+            if benchmark.interval_days < 1 {
+                return Err(MicroClawError::Config(
+                    "benchmark.interval_days must be at least 1".into(),
+                ));
+            }
+        }
+        if self
+            .chat_archive
+            .as_ref()
+            .is_some_and(|a| a.inactive_months == 0)
+        {
+            return Err(MicroClawError::Config(
+                "chat_archive.inactive_months must be at least 1".into(),
+            ));
+        }
+        if let Some(business) = &self.telegram_business {
+            if business.owner_ids.is_empty() && self.control_chat_ids.iter().all(|id| *id <= 0) {
+                return Err(MicroClawError::Config(
+                    "telegram_business needs owner_ids or a private control chat".into(),
+                ));
+            }
+        }
+        if let Some(search) = &self.provider_search {
+            if search.max_uses == 0 {
+                return Err(MicroClawError::Config(
+                    "provider_search.max_uses must be at least 1".into(),
+                ));
+            }
+            if search.openai_model.trim().is_empty() {
+                return Err(MicroClawError::Config(
+                    "provider_search.openai_model must not be empty".into(),
+                ));
+            }
+        }
         if self.web_max_inflight_per_session == 0 {
             self.web_max_inflight_per_session = default_web_max_inflight_per_session();
         }
@@ -629,6 +2551,12 @@ impl Config {
         if self.web_rate_window_seconds == 0 {
             self.web_rate_window_seconds = default_web_rate_window_seconds();
         }
+        if self.web_max_reads_per_window == 0 {
+            self.web_max_reads_per_window = default_web_max_reads_per_window();
+        }
+        if self.web_max_writes_per_window == 0 {
+            self.web_max_writes_per_window = default_web_max_writes_per_window();
+        }
         if self.web_run_history_limit == 0 {
             self.web_run_history_limit = default_web_run_history_limit();
         }
@@ -657,6 +2585,8 @@ impl Config {
                 &mut social.tiktok,
                 &mut social.instagram,
                 &mut social.linkedin,
+                &mut social.google,
+                &mut social.spotify,
             ] {
                 if let Some(ref id) = platform_cfg.client_id {
                     if id.trim().is_empty() {
@@ -671,13 +2601,32 @@ impl Config {
             }
         }
 
+        if let Some(unknown) = self
+            .feature_flags
+            .keys()
+            .find(|name| crate::feature_flags::FeatureFlag::parse(name).is_none())
+        {
+            return Err(MicroClawError::Config(format!(
+                "Unknown feature flag '{unknown}' (known: {})",
+                crate::feature_flags::FeatureFlag::names()
+            )));
+        }
+
         // Validate required fields
-        if self.telegram_bot_token.is_empty() && self.discord_bot_token.is_none() {
+        if self.telegram_bot_token.is_empty()
+            && self.discord_bot_token.is_none()
+            && self.slack_bot_token.is_none()
+            && self.signal.is_none()
+            && self.teams.is_none()
+        {
             return Err(MicroClawError::Config(
-                "At least one of telegram_bot_token or discord_bot_token must be set".into(),
+                "At least one of telegram_bot_token or discord_bot_token (or slack_bot_token, signal, teams) must be set"
+                    .into(),
             ));
         }
-        if self.api_key.is_empty() && self.llm_provider != "ollama" {
+        if self.api_key.is_empty()
+            && !matches!(self.llm_provider.as_str(), "ollama" | "llamacpp")
+        {
             return Err(MicroClawError::Config("api_key is required".into()));
         }
 
@@ -758,6 +2707,7 @@ mod tests {
             api_key: "key".into(),
             model: "claude-sonnet-4-5-20250929".into(),
             llm_base_url: None,
+            llm_fallbacks: Vec::new(),
             max_tokens: 8192,
             max_tool_iterations: 100,
             max_history_messages: 50,
@@ -769,20 +2719,42 @@ mod tests {
             control_chat_ids: vec![],
             max_session_messages: 40,
             compact_keep_recent: 20,
+            memory_flush_idle_minutes: 30,
+            memory_flush_model: String::new(),
+            onboarding_enabled: true,
+            task_max_retries: 2,
+            task_retry_backoff_secs: 60,
+            scheduler_max_concurrent_tasks: 4,
+            deleted_session_retention_days: 30,
             whatsapp_access_token: None,
             whatsapp_phone_number_id: None,
             whatsapp_verify_token: None,
             whatsapp_webhook_port: 8080,
             discord_bot_token: None,
             discord_allowed_channels: vec![],
-            show_thinking: false,
+            slack_bot_token: None,
+            slack_app_token: None,
+            slack_allowed_channels: vec![],
+            signal: None,
+            teams: None,
+            wyoming: None,
+            home_assistant: None,
+            python: None,
+            browser_downloads: None,
+            web_embed: None,
+            transcription: None,
+            calendar: None,
+            show_thinking: ThinkingLevel::Off,
             web_enabled: true,
             web_host: "127.0.0.1".into(),
             web_port: 10961,
             web_auth_token: None,
+            web_trusted_proxies: vec![],
             web_max_inflight_per_session: 2,
             web_max_requests_per_window: 8,
             web_rate_window_seconds: 10,
+            web_max_reads_per_window: 120,
+            web_max_writes_per_window: 30,
             web_run_history_limit: 512,
             web_session_idle_ttl_seconds: 300,
             browser_managed: false,
@@ -794,10 +2766,39 @@ mod tests {
             cursor_agent_cli_path: default_cursor_agent_cli_path(),
             cursor_agent_model: String::new(),
             cursor_agent_timeout_secs: 600,
+            cursor_agent_review: true,
+            cursor_agent_digest_days: 7,
             social: None,
             vault: None,
             orchestrator_enabled: true,
             orchestrator_model: String::new(),
+            translation: None,
+            public_ask: None,
+            sticker_pack_path: None,
+            output_filter: None,
+            inbound_moderation: None,
+            egress: Default::default(),
+            browser_profiles: Vec::new(),
+            model_prices: Default::default(),
+            experiments: Vec::new(),
+            feedback_report_days: 0,
+            instance_lock: false,
+            feeds: Vec::new(),
+            middleware_hooks: Vec::new(),
+            feature_flags: Default::default(),
+            announce_release_notes: true,
+            anthropic_prompt_caching: true,
+            notifications: None,
+            heartbeat: None,
+            workspace_encryption: None,
+            incidents: None,
+            push: None,
+            provider_files: None,
+            roundtable: None,
+            benchmark: None,
+            chat_archive: None,
+            provider_search: None,
+            telegram_business: None,
         }
     }
 
@@ -927,6 +2928,27 @@ mod tests {
         config.post_deserialize().unwrap();
     }
 
+    #[test]
+    fn test_post_deserialize_signal_only() {
+        let yaml = "bot_username: bot\napi_key: key\nsignal:\n  signal_account: \"+15551234567\"\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        let signal = config.signal.unwrap();
+        assert_eq!(signal.signal_cli_path, "signal-cli");
+        assert!(signal.allowed_groups.is_empty());
+    }
+
+    #[test]
+    fn test_post_deserialize_teams_only() {
+        let yaml =
+            "bot_username: bot\napi_key: key\nteams:\n  app_id: app\n  app_password: secret\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        let teams = config.teams.unwrap();
+        assert_eq!(teams.webhook_port, 3978);
+        assert!(teams.tenant_id.is_none());
+    }
+
     #[test]
     fn test_post_deserialize_openai_default_model() {
         let yaml =
@@ -944,6 +2966,15 @@ mod tests {
         assert_eq!(config.model, "llama3.2");
     }
 
+    #[test]
+    fn test_post_deserialize_llamacpp_alias_and_empty_key() {
+        let yaml = "telegram_bot_token: tok\nbot_username: bot\nllm_provider: llama.cpp\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(config.llm_provider, "llamacpp");
+        assert_eq!(config.model, "default");
+    }
+
     #[test]
     fn test_post_deserialize_empty_base_url_becomes_none() {
         let yaml = "telegram_bot_token: tok\nbot_username: bot\napi_key: key\nllm_base_url: '  '\n";
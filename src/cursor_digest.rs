@@ -0,0 +1,288 @@
+//! Cursor-agent digest: groups the `cursor_agent_runs` of each chat by project (registered
+//! project, else working directory) and posts a summary — runs, success rate, durations and
+//! notable outputs — to that chat every `cursor_agent_digest_days`, so long-running coding
+//! projects stay visible.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::db::{call_blocking, CursorAgentRun, Project};
+use crate::telegram::AppState;
+use crate::text::{one_line, truncate_chars};
+
+/// Chat setting holding when the chat's last digest was sent.
+const LAST_DIGEST_SETTING: &str = "cursor_digest_sent_at";
+const NOTABLE_PER_PROJECT: usize = 3;
+const PREVIEW_CHARS: usize = 100;
+
+/// `1h 05m`, `4m 12s` or `38s`.
+fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+fn run_seconds(run: &CursorAgentRun) -> Option<i64> {
+    let start = chrono::DateTime::parse_from_rfc3339(&run.started_at).ok()?;
+    let end = chrono::DateTime::parse_from_rfc3339(&run.finished_at).ok()?;
+    Some((end - start).num_seconds())
+}
+
+/// Tool calls recorded in the run's structured steps.
+fn tool_calls(run: &CursorAgentRun) -> usize {
+    run.steps_json
+        .as_deref()
+        .and_then(|s| serde_json::from_str::<Vec<serde_json::Value>>(s).ok())
+        .map(|steps| {
+            steps
+                .iter()
+                .filter(|s| s.get("kind").and_then(|k| k.as_str()) == Some("tool_started"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Registered project containing the run's working directory, else the directory's name.
+fn project_label(workdir: Option<&str>, projects: &[Project]) -> String {
+    let Some(dir) = workdir.filter(|d| !d.is_empty()) else {
+        return "workspace".into();
+    };
+    let dir = Path::new(dir);
+    if let Some(project) = projects
+        .iter()
+        .filter(|p| dir.starts_with(&p.path))
+        .max_by_key(|p| p.path.len())
+    {
+        return project.name.clone();
+    }
+    dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+/// One line for a notable run: failures with their exit code, else the latest output.
+fn notable_line(run: &CursorAgentRun) -> String {
+    let outcome = if run.success {
+        "✓".to_string()
+    } else {
+        match run.exit_code {
+            Some(code) => format!("✗ exit {code}"),
+            None => "✗".to_string(),
+        }
+    };
+    let output = run
+        .output_preview
+        .as_deref()
+        .map(|o| truncate_chars(&one_line(o), PREVIEW_CHARS))
+        .filter(|o| !o.is_empty())
+        .unwrap_or_else(|| "(no output)".into());
+    format!(
+        "#{} {outcome} \"{}\" → {output}",
+        run.id,
+        truncate_chars(&one_line(&run.prompt_preview), PREVIEW_CHARS / 2)
+    )
+}
+
+/// Build the digest for one chat's runs; None when there were none.
+pub fn build_digest(runs: &[CursorAgentRun], projects: &[Project], days: u64) -> Option<String> {
+    if runs.is_empty() {
+        return None;
+    }
+    let mut groups: BTreeMap<String, Vec<&CursorAgentRun>> = BTreeMap::new();
+    for run in runs {
+        groups
+            .entry(project_label(run.workdir.as_deref(), projects))
+            .or_default()
+            .push(run);
+    }
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+    let succeeded = runs.iter().filter(|r| r.success).count();
+    let mut out = format!(
+        "🛠 Cursor agent digest (last {days} days)\n{} runs in {} project(s), {succeeded} succeeded ({}%).",
+        runs.len(),
+        groups.len(),
+        succeeded * 100 / runs.len()
+    );
+    for (project, items) in groups {
+        let ok = items.iter().filter(|r| r.success).count();
+        let durations: Vec<i64> = items.iter().filter_map(|r| run_seconds(r)).collect();
+        let calls: usize = items.iter().map(|r| tool_calls(r)).sum();
+        out.push_str(&format!(
+            "\n\n• {project} — {} runs, {ok}/{} succeeded ({}%)",
+            items.len(),
+            items.len(),
+            ok * 100 / items.len()
+        ));
+        if !durations.is_empty() {
+            let total: i64 = durations.iter().sum();
+            out.push_str(&format!(
+                "\n  Time: {} total, {} avg, {} longest",
+                format_duration(total),
+                format_duration(total / durations.len() as i64),
+                format_duration(durations.iter().copied().max().unwrap_or(0))
+            ));
+        }
+        if calls > 0 {
+            out.push_str(&format!("\n  Tool calls: {calls}"));
+        }
+        // Failures first (newest first), then the latest successful output
+        let mut notable: Vec<&CursorAgentRun> =
+            items.iter().rev().filter(|r| !r.success).copied().collect();
+        if let Some(latest_ok) = items.iter().rev().find(|r| r.success) {
+            notable.push(latest_ok);
+        }
+        for run in notable.into_iter().take(NOTABLE_PER_PROJECT) {
+            out.push_str(&format!("\n  {}", notable_line(run)));
+        }
+    }
+    out.push_str("\n\nUse list_cursor_agent_runs with run_id for a run's steps.");
+    Some(out)
+}
+
+/// Post a digest to each chat with cursor-agent runs when `cursor_agent_digest_days` have
+/// passed since its last one. Called every scheduler cycle.
+pub async fn send_due_digests(state: &Arc<AppState>) {
+    let days = state.config.cursor_agent_digest_days;
+    if days == 0 {
+        return;
+    }
+    let now = chrono::Utc::now();
+    let since = (now - chrono::Duration::days(days as i64)).to_rfc3339();
+    let loaded = call_blocking(state.db.clone(), move |d| {
+        Ok((d.list_cursor_agent_runs_since(&since)?, d.list_projects()?))
+    })
+    .await;
+    let (runs, projects) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            warn!("cursor digest: failed to load runs: {e}");
+            return;
+        }
+    };
+    let mut by_chat: BTreeMap<i64, Vec<CursorAgentRun>> = BTreeMap::new();
+    for run in runs {
+        by_chat.entry(run.chat_id).or_default().push(run);
+    }
+    for (chat_id, runs) in by_chat {
+        let last = call_blocking(state.db.clone(), move |d| {
+            d.get_chat_setting(chat_id, LAST_DIGEST_SETTING)
+        })
+        .await
+        .ok()
+        .flatten();
+        let Some(last) = last else {
+            // First runs in this chat: start the clock instead of posting a partial digest
+            let started = now.to_rfc3339();
+            let _ = call_blocking(state.db.clone(), move |d| {
+                d.set_chat_setting(chat_id, LAST_DIGEST_SETTING, Some(&started))
+            })
+            .await;
+            continue;
+        };
+        let due = chrono::DateTime::parse_from_rfc3339(&last)
+            .ok()
+            .is_none_or(|t| {
+                now - t.with_timezone(&chrono::Utc) >= chrono::Duration::days(days as i64)
+            });
+        if !due {
+            continue;
+        }
+        let Some(digest) = build_digest(&runs, &projects, days) else {
+            continue;
+        };
+        let persona_id =
+            call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+        if let Err(e) = deliver_and_store_bot_message(
+            &state.bot,
+            state.db.clone(),
+            &state.config.bot_username,
+            chat_id,
+            persona_id,
+            &digest,
+        )
+        .await
+        {
+            warn!("cursor digest: failed to send to chat {chat_id}: {e}");
+            continue;
+        }
+        info!("Posted cursor-agent digest to chat {chat_id}");
+        let sent = now.to_rfc3339();
+        if let Err(e) = call_blocking(state.db.clone(), move |d| {
+            d.set_chat_setting(chat_id, LAST_DIGEST_SETTING, Some(&sent))
+        })
+        .await
+        {
+            warn!("cursor digest: failed to record the digest for chat {chat_id}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: i64, workdir: &str, secs: i64, success: bool, output: &str) -> CursorAgentRun {
+        let start = chrono::DateTime::parse_from_rfc3339("2026-10-12T09:00:00Z").unwrap();
+        CursorAgentRun {
+            id,
+            chat_id: 1,
+            channel: "telegram".into(),
+            prompt_preview: format!("task {id}"),
+            workdir: Some(workdir.into()),
+            started_at: start.to_rfc3339(),
+            finished_at: (start + chrono::Duration::seconds(secs)).to_rfc3339(),
+            success,
+            exit_code: if success { Some(0) } else { Some(1) },
+            output_preview: Some(output.into()),
+            output_path: None,
+            steps_json: Some(
+                r#"[{"kind":"tool_started","tool":"edit"},{"kind":"tool_completed","tool":"edit","ok":true}]"#
+                    .into(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_build_digest_groups_by_project() {
+        assert!(build_digest(&[], &[], 7).is_none());
+        let projects = vec![Project {
+            name: "shop".into(),
+            path: "/ws/shop".into(),
+            template: "rust".into(),
+            chat_id: 1,
+            created_at: String::new(),
+        }];
+        let digest = build_digest(
+            &[
+                run(1, "/ws/shop", 120, true, "Added the cart page"),
+                run(2, "/ws/shop/api", 300, false, "cargo build failed"),
+                run(3, "/ws/shop", 60, true, "Fixed the build"),
+                run(4, "/ws/notes", 45, true, "Summarised notes"),
+            ],
+            &projects,
+            7,
+        )
+        .unwrap();
+        assert!(digest.contains("4 runs in 2 project(s), 3 succeeded (75%)."));
+        assert!(digest.contains("• shop — 3 runs, 2/3 succeeded (66%)"));
+        assert!(digest.contains("Time: 8m 00s total, 2m 40s avg, 5m 00s longest"));
+        assert!(digest.contains("Tool calls: 3"));
+        assert!(digest.contains("#2 ✗ exit 1 \"task 2\" → cargo build failed"));
+        assert!(digest.contains("#3 ✓ \"task 3\" → Fixed the build"));
+        assert!(!digest.contains("Added the cart page"));
+        assert!(digest.contains("• notes — 1 runs, 1/1 succeeded (100%)"));
+    }
+}
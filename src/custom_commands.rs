@@ -0,0 +1,301 @@
+//! Per-chat custom slash commands: a command name (e.g. `/standup`) maps to a stored prompt
+//! template. Built-in commands (see `slash_commands`) always win; a matching custom command
+//! is expanded into a normal message and sent to the agent.
+//!
+//! Template placeholders: `{{args}}` is everything after the command, `{{1}}`, `{{2}}`, ...
+//! are whitespace-separated arguments ("double quotes" group words). Without placeholders,
+//! arguments are appended to the prompt.
+
+use std::sync::Arc;
+
+use teloxide::prelude::*;
+use teloxide::types::{BotCommand, BotCommandScope, Recipient};
+
+use crate::db::{call_blocking, Database};
+
+pub const MAX_CUSTOM_COMMANDS_PER_CHAT: usize = 50;
+
+/// Names handled by `slash_commands::parse`; custom commands may not shadow them.
+pub const BUILTIN_COMMAND_NAMES: &[&str] = &[
+    "ack",
+    "archive",
+    "catch_me_up",
+    "catchmeup",
+    "catchup",
+    "debate",
+    "footer",
+    "forget",
+    "jobs",
+    "memory",
+    "persona",
+    "personas",
+    "push",
+    "reset",
+    "schedule",
+    "scheduled",
+    "scheduled_job",
+    "scheduledjob",
+    "skills",
+    "thinking",
+];
+
+/// Which Telegram command menu a list is for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuScope {
+    Private,
+    Group,
+}
+
+impl MenuScope {
+    /// Telegram group and channel chat ids are negative.
+    pub fn for_chat(chat_id: i64) -> Self {
+        if chat_id < 0 {
+            MenuScope::Group
+        } else {
+            MenuScope::Private
+        }
+    }
+}
+
+/// Built-in menu entries: (command, description, only in this scope).
+const MENU_COMMANDS: &[(&str, &str, Option<MenuScope>)] = &[
+    ("reset", "Clear conversation (memory unchanged)", None),
+    ("undelete", "Restore a conversation cleared by /reset", None),
+    (
+        "catchup",
+        "Summarize messages you missed",
+        Some(MenuScope::Group),
+    ),
+    ("memory", "Show and edit what I remember", None),
+    ("forget", "Forget memory entries mentioning some text", None),
+    ("schedule", "List and manage scheduled jobs", None),
+    ("persona", "List / switch / new / delete personas", None),
+    ("skills", "List available skills", None),
+    ("footer", "Show or toggle the usage footer on replies", None),
+    ("push", "Get alerts as push notifications instead", None),
+    ("debate", "Let personas discuss a question, then sum up", None),
+    ("thinking", "Show reasoning: off, summary or full; last", None),
+    ("archive", "Archive conversation to markdown", None),
+    ("ack", "Acknowledge an incident alert", None),
+];
+
+/// Built-in commands shown in the Telegram command menu for `scope`. `/skills` is left out
+/// when no skills are installed.
+pub fn telegram_commands(scope: MenuScope, has_skills: bool) -> Vec<BotCommand> {
+    MENU_COMMANDS
+        .iter()
+        .filter(|(_, _, only)| only.is_none_or(|only| only == scope))
+        .filter(|(command, ..)| has_skills || *command != "skills")
+        .map(|(command, description, ..)| BotCommand {
+            command: (*command).into(),
+            description: (*description).into(),
+        })
+        .collect()
+}
+
+/// Register the built-in command menus: one for private chats, one for groups, and the group
+/// list as the default for any other scope.
+pub async fn register_telegram_menus(bot: &Bot, has_skills: bool) -> Result<(), String> {
+    let group = telegram_commands(MenuScope::Group, has_skills);
+    for (scope, commands) in [
+        (BotCommandScope::Default, group.clone()),
+        (
+            BotCommandScope::AllPrivateChats,
+            telegram_commands(MenuScope::Private, has_skills),
+        ),
+        (BotCommandScope::AllGroupChats, group),
+    ] {
+        bot.set_my_commands(commands)
+            .scope(scope)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Validate and normalize a command name: leading `/` stripped, lowercase, 1-32 of `[a-z0-9_]`.
+pub fn normalize_command_name(raw: &str) -> Result<String, String> {
+    let name = raw.trim().trim_start_matches('/').to_lowercase();
+    if name.is_empty() || name.len() > 32 {
+        return Err("Command name must be 1-32 characters".into());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err("Command name may only contain letters, digits and underscores".into());
+    }
+    if BUILTIN_COMMAND_NAMES.contains(&name.as_str()) {
+        return Err(format!("/{name} is a built-in command"));
+    }
+    Ok(name)
+}
+
+/// Split "/name@bot rest of text" into ("name", "rest of text").
+pub fn split_command(text: &str) -> Option<(String, String)> {
+    let rest = text.trim().strip_prefix('/')?;
+    let (head, args) = match rest.find(char::is_whitespace) {
+        Some(i) => (&rest[..i], rest[i..].trim()),
+        None => (rest, ""),
+    };
+    let name = head.split('@').next().unwrap_or("").to_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, args.to_string()))
+}
+
+fn split_args(args: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in args.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    out.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+/// Fill a template with the command's arguments.
+pub fn expand_template(template: &str, args: &str) -> String {
+    let has_placeholders = template.contains("{{");
+    let positional = split_args(args);
+    let mut out = template.replace("{{args}}", args);
+    for i in (1..=9).rev() {
+        let value = positional.get(i - 1).map(String::as_str).unwrap_or("");
+        out = out.replace(&format!("{{{{{i}}}}}"), value);
+    }
+    if !has_placeholders && !args.is_empty() {
+        out.push_str("\n\n");
+        out.push_str(args);
+    }
+    out.trim().to_string()
+}
+
+/// If `text` invokes a custom command defined for `chat_id`, return the expanded prompt.
+pub async fn expand_custom_command(db: Arc<Database>, chat_id: i64, text: &str) -> Option<String> {
+    let (name, args) = split_command(text)?;
+    if BUILTIN_COMMAND_NAMES.contains(&name.as_str()) {
+        return None;
+    }
+    let command = call_blocking(db, move |d| d.get_custom_command(chat_id, &name))
+        .await
+        .ok()
+        .flatten()?;
+    Some(expand_template(&command.template, &args))
+}
+
+/// Show the chat's custom commands in its Telegram command menu (alongside built-ins).
+/// With no custom commands, the chat-scoped menu is removed so the default applies.
+pub async fn sync_telegram_chat_commands(
+    bot: &Bot,
+    db: Arc<Database>,
+    chat_id: i64,
+) -> Result<(), String> {
+    let custom = call_blocking(db, move |d| d.list_custom_commands(&[chat_id]))
+        .await
+        .map_err(|e| e.to_string())?;
+    let scope = BotCommandScope::Chat {
+        chat_id: Recipient::Id(ChatId(chat_id)),
+    };
+    if custom.is_empty() {
+        bot.delete_my_commands()
+            .scope(scope)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    let mut commands = telegram_commands(MenuScope::for_chat(chat_id), true);
+    commands.extend(custom.into_iter().map(|c| BotCommand {
+        command: c.name,
+        description: c.description.chars().take(256).collect(),
+    }));
+    bot.set_my_commands(commands)
+        .scope(scope)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_names_sorted_and_parsed() {
+        let mut sorted = BUILTIN_COMMAND_NAMES.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, BUILTIN_COMMAND_NAMES);
+        for name in BUILTIN_COMMAND_NAMES {
+            assert!(
+                crate::slash_commands::parse(&format!("/{name}")).is_some(),
+                "/{name} should be a built-in"
+            );
+        }
+    }
+
+    #[test]
+    fn test_menu_commands_per_scope() {
+        let names = |scope, has_skills| -> Vec<String> {
+            telegram_commands(scope, has_skills)
+                .into_iter()
+                .map(|c| c.command)
+                .collect()
+        };
+        let private = names(MenuScope::Private, true);
+        let group = names(MenuScope::Group, false);
+        assert!(!private.contains(&"catchup".to_string()));
+        assert!(group.contains(&"catchup".to_string()));
+        assert!(private.contains(&"skills".to_string()));
+        assert!(!group.contains(&"skills".to_string()));
+        for (command, ..) in MENU_COMMANDS {
+            assert!(crate::slash_commands::parse(&format!("/{command}")).is_some());
+        }
+        assert_eq!(MenuScope::for_chat(-100123), MenuScope::Group);
+        assert_eq!(MenuScope::for_chat(42), MenuScope::Private);
+    }
+
+    #[test]
+    fn test_normalize_command_name() {
+        assert_eq!(normalize_command_name("/Standup").unwrap(), "standup");
+        assert!(normalize_command_name("reset").is_err());
+        assert!(normalize_command_name("bad-name").is_err());
+        assert!(normalize_command_name("").is_err());
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("/standup@HomeBot team a"),
+            Some(("standup".into(), "team a".into()))
+        );
+        assert_eq!(
+            split_command("/groceries"),
+            Some(("groceries".into(), "".into()))
+        );
+        assert_eq!(split_command("hello"), None);
+    }
+
+    #[test]
+    fn test_expand_template() {
+        assert_eq!(
+            expand_template("Standup for {{1}} on {{2}}: {{args}}", "\"team a\" monday"),
+            "Standup for team a on monday: \"team a\" monday"
+        );
+        assert_eq!(
+            expand_template("Plan meals", "vegetarian"),
+            "Plan meals\n\nvegetarian"
+        );
+        assert_eq!(expand_template("Hi {{1}}", ""), "Hi");
+    }
+}
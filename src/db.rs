@@ -66,6 +66,127 @@ pub struct SocialOAuthToken {
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub expires_at: Option<String>,
+    /// Space- or comma-separated scopes granted to the token, as reported by the provider.
+    pub scopes: Option<String>,
+    pub connected_at: Option<String>,
+}
+
+/// A social OAuth flow in progress: single-use state token bound to the chat that asked for
+/// the link, the PKCE verifier (where the platform supports PKCE) and the browser that opened
+/// the link.
+#[derive(Debug, Clone)]
+pub struct OAuthPendingState {
+    pub state_token: String,
+    pub platform: String,
+    pub chat_id: i64,
+    pub expires_at: String,
+    pub code_verifier: Option<String>,
+    pub browser_binding: Option<String>,
+    /// Scopes to request instead of the platform default (incremental Google authorization).
+    pub scopes: Option<String>,
+}
+
+/// One post's metrics as captured by the social feed monitor. Metrics the platform does not
+/// expose are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SocialPostSnapshot {
+    pub post_id: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub views: Option<i64>,
+    pub likes: Option<i64>,
+    pub comments: Option<i64>,
+    pub shares: Option<i64>,
+}
+
+/// A stored post snapshot with the feed it belongs to and when it was captured.
+#[derive(Debug, Clone)]
+pub struct SocialSnapshotRow {
+    pub platform: String,
+    pub captured_at: String,
+    pub post: SocialPostSnapshot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocialFollowerCount {
+    pub platform: String,
+    pub followers: i64,
+    pub captured_at: String,
+}
+
+/// A long-running tool job (background command, download, indexing...) and its output so far.
+#[derive(Debug, Clone)]
+pub struct BackgroundJob {
+    pub id: i64,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    /// Tool or subsystem that started the job (e.g. "bash").
+    pub kind: String,
+    pub description: String,
+    /// running, succeeded, failed or cancelled.
+    pub status: String,
+    /// Tail of the job's output.
+    pub output: String,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// A pending or resolved review of the changes a cursor-agent run made to a git repository.
+#[derive(Debug, Clone)]
+pub struct CodeReview {
+    pub id: i64,
+    pub chat_id: i64,
+    /// Top level of the reviewed repository.
+    pub repo_dir: String,
+    /// HEAD before the run; `None` when the repository had no commits.
+    pub base_commit: Option<String>,
+    pub prompt_preview: String,
+    /// pending, approved or reverted.
+    pub status: String,
+    pub created_at: String,
+}
+
+/// A request from an unknown chat to use the bot, decided from a control chat.
+#[derive(Debug, Clone)]
+pub struct AccessRequest {
+    pub id: i64,
+    /// telegram, discord or slack.
+    pub channel: String,
+    pub chat_id: i64,
+    pub chat_title: Option<String>,
+    pub requester: String,
+    /// First message the chat sent (excerpt).
+    pub message: String,
+    /// pending, approved or denied.
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// A DB allowlist entry, overriding the channel's config allowlist for one chat.
+#[derive(Debug, Clone)]
+pub struct ChatAccess {
+    pub chat_id: i64,
+    pub channel: String,
+    /// allowed or blocked.
+    pub status: String,
+    /// RFC 3339; `None` = permanent.
+    pub expires_at: Option<String>,
+    pub note: Option<String>,
+    pub updated_at: String,
+}
+
+/// A named project directory created by `create_project`.
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub name: String,
+    pub path: String,
+    /// rust, python, node or plain.
+    pub template: String,
+    /// Chat that created the project.
+    pub chat_id: i64,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +201,18 @@ pub struct ScheduledTask {
     pub last_run: Option<String>,
     pub status: String, // "active", "paused", "completed", "cancelled"
     pub created_at: String,
+    /// Retries after a failed run; None = `task_max_retries` from the config.
+    pub max_retries: Option<u32>,
+    /// Failed attempts of the current run so far.
+    pub retry_count: u32,
+    /// Set by run-now: the scheduler runs the task once on its next tick, even when paused.
+    pub run_requested_at: Option<String>,
+    /// IANA timezone the cron expression is read in; None = the chat's timezone, else
+    /// `config.timezone`.
+    pub timezone: Option<String>,
+    /// "skip", "queue" or "parallel" when a run is due while the last one is still going;
+    /// None = skip.
+    pub concurrency: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -95,8 +228,285 @@ pub struct CursorAgentRun {
     pub exit_code: Option<i32>,
     pub output_preview: Option<String>,
     pub output_path: Option<String>,
+    /// JSON array of the run's parsed stream-json steps (tool calls, messages, result).
+    pub steps_json: Option<String>,
+}
+
+/// A stored message plus its forum topic (if the chat is a forum supergroup).
+#[derive(Debug, Clone)]
+pub struct ThreadedMessage {
+    pub message: StoredMessage,
+    pub thread_id: Option<i64>,
+    pub topic_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomCommand {
+    pub chat_id: i64,
+    pub name: String,
+    pub description: String,
+    pub template: String,
+    pub updated_at: String,
+}
+
+/// A poll created by the create_poll tool. Options are stored as a JSON array.
+#[derive(Debug, Clone)]
+pub struct Poll {
+    pub id: String,
+    pub chat_id: i64,
+    pub question: String,
+    pub options: Vec<String>,
+    pub multiple_answers: bool,
+    /// Channel message carrying the poll (Telegram message id / Discord message id).
+    pub message_id: Option<String>,
+    /// Telegram poll id, used to match incoming poll answers.
+    pub external_poll_id: Option<String>,
+    pub closes_at: Option<String>,
+    /// Prompt run by the agent with the results when the poll closes.
+    pub on_close: Option<String>,
+    pub status: String,
+    pub created_at: String,
+}
+
+/// An inbound message held by moderation until reviewed from a control chat.
+#[derive(Debug, Clone)]
+pub struct QuarantinedMessage {
+    pub id: String,
+    pub chat_id: i64,
+    /// Chat type at the time it was held, or "public_ask" for website questions.
+    pub chat_type: String,
+    pub sender_name: String,
+    pub content: String,
+    /// Why the message was held, e.g. "prompt injection: ignore previous instructions".
+    pub reasons: String,
+    /// pending, approved (awaiting release), released or rejected.
+    pub status: String,
+    pub created_at: String,
+}
+
+/// A saved restore point for one chat/persona (see the snapshot_session tool).
+#[derive(Debug, Clone)]
+pub struct SessionSnapshot {
+    pub id: String,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub label: String,
+    /// JSON payload: messages, session, memory and chat settings.
+    pub data: String,
+    pub created_at: String,
+}
+
+/// A session removed by a reset, kept until the retention period ends (see undelete_session).
+#[derive(Debug, Clone)]
+pub struct DeletedSession {
+    pub id: i64,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub messages_json: String,
+    /// When the session was last saved.
+    pub updated_at: String,
+    pub deleted_at: String,
+}
+
+/// A chat whose messages and sessions were moved to a compressed file (see `chat_archive`).
+#[derive(Debug, Clone)]
+pub struct ChatArchive {
+    pub chat_id: i64,
+    /// Archive file, relative to the runtime directory.
+    pub path: String,
+    pub message_count: i64,
+    /// Newest archived message.
+    pub last_message_at: String,
+    pub archived_at: String,
+}
+
+/// A saved session, as moved to and from cold storage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatSessionRow {
+    pub persona_id: i64,
+    pub messages_json: String,
+    pub updated_at: String,
+}
+
+/// A session due for the idle memory flush.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdleSession {
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub updated_at: String,
+    /// `updated_at` of the session when it was last flushed.
+    pub flushed_at: Option<String>,
+}
+
+/// A reaction on a bot reply, with the reply and the user message it answered.
+#[derive(Debug, Clone)]
+pub struct ResponseFeedback {
+    pub chat_id: i64,
+    pub persona_id: i64,
+    /// 1 positive, -1 negative.
+    pub score: i64,
+    pub emoji: String,
+    pub prompt: String,
+    pub reply: String,
+    pub created_at: String,
+}
+
+/// A failed scheduled task run, background job or (experiment-enrolled) agent turn.
+#[derive(Debug, Clone)]
+pub struct FailedRun {
+    /// "scheduled_task", "background_job" or "agent_turn".
+    pub source: String,
+    pub chat_id: i64,
+    /// What ran: "task #id: prompt", "kind: description" or "experiment/variant".
+    pub label: String,
+    pub error: String,
+    pub created_at: String,
+}
+
+/// A saved copy of a skill folder, taken before the skill was updated.
+#[derive(Debug, Clone)]
+pub struct SkillVersion {
+    pub id: i64,
+    pub skill_name: String,
+    /// Absolute path of the copy under `skills/.versions/<name>/<timestamp>`.
+    pub version_path: String,
+    /// What was about to change the skill (e.g. `build_skill`, `sync_skills`, `rollback`).
+    pub reason: String,
+    pub chat_id: Option<i64>,
+    pub created_at: String,
+}
+
+/// One agent turn enrolled in an A/B experiment.
+#[derive(Debug, Clone, Default)]
+pub struct ExperimentRun {
+    pub experiment: String,
+    pub variant: String,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub model: String,
+    pub iterations: u64,
+    pub tool_calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub duration_ms: u64,
+    pub outcome: String,
+}
+
+/// Stage timings of one agent turn; `stages` is a JSON array of `{"stage", "ms"}`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TurnTiming {
+    pub id: i64,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub channel: String,
+    pub queue_ms: Option<u64>,
+    pub agent_ms: u64,
+    pub delivery_ms: Option<u64>,
+    pub stages: String,
+    pub created_at: String,
+}
+
+/// Content the agent saved with `save_artifact` (a plan, table, recipe...) to find it again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Artifact {
+    pub id: i64,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub title: String,
+    pub kind: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// An entry on one of a chat's todo lists (`todo_add` / `todo_list` / `todo_complete`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TodoItem {
+    pub id: i64,
+    pub list: String,
+    pub text: String,
+    pub due: Option<String>,
+    pub done: bool,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// A conversation in the owner's Telegram Business inbox. `chat_id` is our own id for it, since
+/// the Telegram chat id is the other person's user id and may also be their chat with the bot.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BusinessChat {
+    pub id: i64,
+    pub chat_id: i64,
+    pub connection_id: String,
+    pub telegram_chat_id: i64,
+    pub title: String,
+    pub auto_reply: bool,
+    pub owner_user_id: i64,
+    pub created_at: String,
+}
+
+/// Aggregated outcome metrics for one experiment variant.
+#[derive(Debug, Clone, Default)]
+pub struct ExperimentVariantStats {
+    pub variant: String,
+    pub runs: u64,
+    pub avg_iterations: f64,
+    pub avg_tool_calls: f64,
+    pub avg_duration_ms: f64,
+    pub avg_output_tokens: f64,
+    pub non_ok: u64,
+    pub positive: u64,
+    pub negative: u64,
+    pub retried: u64,
+}
+
+/// A tagged span of conversation (created when a session is compacted).
+#[derive(Debug, Clone)]
+pub struct ConversationSegment {
+    pub id: i64,
+    pub chat_id: i64,
+    pub persona_id: i64,
+    pub start_ts: String,
+    pub end_ts: String,
+    pub summary: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// An urgent alert that pages the incident chat until someone acknowledges it.
+#[derive(Debug, Clone)]
+pub struct Incident {
+    pub id: i64,
+    /// Dedup key: a new alert with the key of an open incident is logged as an update.
+    pub key: String,
+    pub title: String,
+    pub source: String,
+    /// open, acknowledged or resolved.
+    pub status: String,
+    /// Chat that receives the pages.
+    pub chat_id: i64,
+    /// Dedicated session where the incident's updates are logged.
+    pub session_chat_id: i64,
+    /// Repeat pages sent so far.
+    pub repeats: i64,
+    pub next_ping_at: Option<String>,
+    pub acknowledged_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GlossaryTerm {
+    pub chat_id: i64,
+    pub source_term: String,
+    pub target_lang: String,
+    pub translation: String,
+    pub updated_at: String,
 }
 
+const BUSINESS_CHAT_SELECT: &str =
+    "SELECT b.id, b.chat_id, b.connection_id, b.telegram_chat_id, b.title, b.auto_reply,
+            c.owner_user_id, b.created_at
+     FROM business_chats b LEFT JOIN business_connections c ON c.id = b.connection_id";
+
 impl Database {
     pub fn new(data_dir: &str) -> Result<Self, MicroClawError> {
         let db_path = Path::new(data_dir).join("microclaw.db");
@@ -105,6 +515,8 @@ impl Database {
         let conn = Connection::open(db_path)?;
         // PRAGMA journal_mode returns a row; use query_row to consume it (execute_batch fails with extra_check)
         let _: String = conn.query_row("PRAGMA journal_mode=WAL", [], |r| r.get(0))?;
+        // Redundant instances may share the database (see instance_lock); wait on their writes
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
 
         conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS chats (
@@ -149,7 +561,12 @@ impl Database {
                 next_run TEXT NOT NULL,
                 last_run TEXT,
                 status TEXT NOT NULL DEFAULT 'active',
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                max_retries INTEGER,
+                retry_count INTEGER NOT NULL DEFAULT 0,
+                run_requested_at TEXT,
+                timezone TEXT,
+                concurrency TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_scheduled_tasks_status_next
@@ -183,6 +600,8 @@ impl Database {
                 access_token TEXT NOT NULL,
                 refresh_token TEXT,
                 expires_at TEXT,
+                scopes TEXT,
+                connected_at TEXT,
                 PRIMARY KEY (platform, chat_id)
             );
 
@@ -190,7 +609,10 @@ impl Database {
                 state_token TEXT PRIMARY KEY,
                 platform TEXT NOT NULL,
                 chat_id INTEGER NOT NULL,
-                expires_at TEXT NOT NULL
+                expires_at TEXT NOT NULL,
+                code_verifier TEXT,
+                browser_binding TEXT,
+                scopes TEXT
             );
 
             CREATE TABLE IF NOT EXISTS cursor_agent_runs (
@@ -204,354 +626,4241 @@ impl Database {
                 success INTEGER NOT NULL,
                 exit_code INTEGER,
                 output_preview TEXT,
-                output_path TEXT
+                output_path TEXT,
+                steps_json TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_cursor_agent_runs_chat_id
                 ON cursor_agent_runs(chat_id);
             CREATE INDEX IF NOT EXISTS idx_cursor_agent_runs_finished_at
-                ON cursor_agent_runs(finished_at DESC);",
-        )?;
+                ON cursor_agent_runs(finished_at DESC);
 
-        Self::migrate_persona_schema(&conn)?;
-        Self::migrate_fts(&conn)?;
-
-        Ok(Database {
-            conn: Mutex::new(conn),
-        })
-    }
+            CREATE TABLE IF NOT EXISTS translation_glossary (
+                chat_id INTEGER NOT NULL,
+                source_term TEXT NOT NULL,
+                target_lang TEXT NOT NULL,
+                translation TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, source_term, target_lang)
+            );
 
-    fn migrate_persona_schema(conn: &Connection) -> Result<(), MicroClawError> {
-        // Check if messages has persona_id (new schema)
-        let has_persona = conn
-            .prepare("PRAGMA table_info(messages)")
-            .and_then(|mut stmt| {
-                let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
-                Ok(rows
-                    .filter_map(|r| r.ok())
-                    .any(|c| c == "persona_id"))
-            })
-            .unwrap_or(false);
+            CREATE TABLE IF NOT EXISTS message_threads (
+                chat_id INTEGER NOT NULL,
+                message_id TEXT NOT NULL,
+                thread_id INTEGER NOT NULL,
+                PRIMARY KEY (chat_id, message_id)
+            );
 
-        if has_persona {
-            return Ok(());
-        }
+            CREATE TABLE IF NOT EXISTS forum_topics (
+                chat_id INTEGER NOT NULL,
+                thread_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                PRIMARY KEY (chat_id, thread_id)
+            );
 
-        // Add active_persona_id to chats if missing
-        let has_active = conn
-            .prepare("PRAGMA table_info(chats)")
-            .and_then(|mut stmt| {
-                let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
-                Ok(rows.filter_map(|r| r.ok()).any(|c| c == "active_persona_id"))
-            })
-            .unwrap_or(false);
-        if !has_active {
-            conn.execute("ALTER TABLE chats ADD COLUMN active_persona_id INTEGER", [])?;
-        }
+            CREATE TABLE IF NOT EXISTS catch_up_state (
+                chat_id INTEGER NOT NULL,
+                user_name TEXT NOT NULL,
+                last_caught_up_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, user_name)
+            );
 
-        // Create personas table if not exists (might not exist in very old DB)
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS personas (
+            CREATE TABLE IF NOT EXISTS conversation_segments (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 chat_id INTEGER NOT NULL,
-                name TEXT NOT NULL,
-                model_override TEXT,
-                UNIQUE(chat_id, name)
+                persona_id INTEGER NOT NULL,
+                start_ts TEXT NOT NULL,
+                end_ts TEXT NOT NULL,
+                summary TEXT,
+                created_at TEXT NOT NULL
             );
-            CREATE INDEX IF NOT EXISTS idx_personas_chat_id ON personas(chat_id);",
-        )?;
 
-        // Collect all chat_ids
-        let chat_ids: Vec<i64> = {
-            let mut out = Vec::new();
-            let mut stmt = conn.prepare(
-                "SELECT chat_id FROM chats UNION SELECT chat_id FROM sessions UNION SELECT chat_id FROM messages",
-            )?;
-            let rows = stmt.query_map([], |row| row.get::<_, i64>(0))?;
-            for r in rows {
-                if let Ok(id) = r {
-                    if !out.contains(&id) {
-                        out.push(id);
-                    }
-                }
-            }
-            out
-        };
+            CREATE INDEX IF NOT EXISTS idx_conversation_segments_chat
+                ON conversation_segments(chat_id, persona_id, end_ts);
 
-        // Create default persona for each chat, set active
-        let now = chrono::Utc::now().to_rfc3339();
-        for cid in &chat_ids {
-            conn.execute(
-                "INSERT OR IGNORE INTO chats (chat_id, chat_title, chat_type, last_message_time, active_persona_id)
-                 VALUES (?1, NULL, 'private', ?2, NULL)",
-                params![cid, now],
-            )?;
-            conn.execute(
-                "INSERT OR IGNORE INTO personas (chat_id, name, model_override) VALUES (?1, 'default', NULL)",
-                params![cid],
-            )?;
-            let persona_id: i64 = conn.query_row(
-                "SELECT id FROM personas WHERE chat_id = ?1 AND name = 'default'",
-                params![cid],
-                |row| row.get(0),
-            )?;
-            conn.execute(
-                "UPDATE chats SET active_persona_id = ?1 WHERE chat_id = ?2",
-                params![persona_id, cid],
-            )?;
-        }
+            CREATE TABLE IF NOT EXISTS conversation_tags (
+                segment_id INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (segment_id, tag)
+            );
 
-        // Migrate sessions
-        conn.execute_batch(
-            "CREATE TABLE sessions_new (
+            CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag
+                ON conversation_tags(tag);
+
+            CREATE TABLE IF NOT EXISTS custom_commands (
                 chat_id INTEGER NOT NULL,
-                persona_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                template TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS polls (
+                id TEXT PRIMARY KEY,
+                chat_id INTEGER NOT NULL,
+                question TEXT NOT NULL,
+                options TEXT NOT NULL,
+                multiple_answers INTEGER NOT NULL DEFAULT 0,
+                message_id TEXT,
+                external_poll_id TEXT,
+                closes_at TEXT,
+                on_close TEXT,
+                status TEXT NOT NULL DEFAULT 'open',
+                created_at TEXT NOT NULL,
+                closed_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_polls_chat_status
+                ON polls(chat_id, status);
+
+            CREATE INDEX IF NOT EXISTS idx_polls_external
+                ON polls(external_poll_id);
+
+            CREATE TABLE IF NOT EXISTS poll_votes (
+                poll_id TEXT NOT NULL,
+                voter TEXT NOT NULL,
+                option_index INTEGER NOT NULL,
+                voted_at TEXT NOT NULL,
+                PRIMARY KEY (poll_id, voter, option_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS filtered_outputs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                chat_type TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                original TEXT NOT NULL,
+                delivered TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_filtered_outputs_chat
+                ON filtered_outputs(chat_id, created_at);
+
+            CREATE TABLE IF NOT EXISTS quarantine (
+                id TEXT PRIMARY KEY,
+                chat_id INTEGER NOT NULL,
+                chat_type TEXT NOT NULL,
+                sender_name TEXT NOT NULL,
+                content TEXT NOT NULL,
+                reasons TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                created_at TEXT NOT NULL,
+                reviewed_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_quarantine_status
+                ON quarantine(status, created_at);
+
+            CREATE TABLE IF NOT EXISTS chat_settings (
+                chat_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, key)
+            );
+
+            CREATE TABLE IF NOT EXISTS llm_usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                input_tokens INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                tool_calls INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                cost_usd REAL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_llm_usage_chat
+                ON llm_usage(chat_id, created_at);
+
+            CREATE TABLE IF NOT EXISTS turn_timings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                channel TEXT NOT NULL,
+                queue_ms INTEGER,
+                agent_ms INTEGER NOT NULL,
+                delivery_ms INTEGER,
+                stages TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_turn_timings_created
+                ON turn_timings(created_at);
+
+            CREATE TABLE IF NOT EXISTS artifacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_artifacts_chat
+                ON artifacts(chat_id, id);
+
+            CREATE TABLE IF NOT EXISTS todo_lists (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE(chat_id, persona_id, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS todo_items (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                list_id INTEGER NOT NULL,
+                chat_id INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                due TEXT,
+                done INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                completed_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_todo_items_list
+                ON todo_items(list_id, done);
+
+            CREATE TABLE IF NOT EXISTS business_connections (
+                id TEXT PRIMARY KEY,
+                owner_user_id INTEGER NOT NULL,
+                enabled INTEGER NOT NULL,
+                can_reply INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS business_chats (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL UNIQUE,
+                connection_id TEXT NOT NULL,
+                telegram_chat_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                auto_reply INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS thinking_transcripts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_thinking_transcripts_chat
+                ON thinking_transcripts(chat_id, id);
+
+            CREATE TABLE IF NOT EXISTS experiment_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                experiment TEXT NOT NULL,
+                variant TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                iterations INTEGER NOT NULL,
+                tool_calls INTEGER NOT NULL,
+                input_tokens INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                feedback INTEGER NOT NULL DEFAULT 0,
+                retried INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_experiment_runs_chat
+                ON experiment_runs(chat_id, created_at);
+
+            CREATE TABLE IF NOT EXISTS session_snapshots (
+                id TEXT PRIMARY KEY,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                label TEXT NOT NULL,
+                data TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_session_snapshots_chat
+                ON session_snapshots(chat_id, persona_id, created_at);
+
+            CREATE TABLE IF NOT EXISTS skill_versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                skill_name TEXT NOT NULL,
+                version_path TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                chat_id INTEGER,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_skill_versions_name
+                ON skill_versions(skill_name, created_at);
+
+            CREATE TABLE IF NOT EXISTS social_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                platform TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                post_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                url TEXT,
+                views INTEGER,
+                likes INTEGER,
+                comments INTEGER,
+                shares INTEGER,
+                captured_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_social_snapshots_feed
+                ON social_snapshots(platform, chat_id, captured_at);
+
+            CREATE TABLE IF NOT EXISTS social_follower_counts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                platform TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                followers INTEGER NOT NULL,
+                captured_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_social_follower_counts_chat
+                ON social_follower_counts(chat_id, captured_at);
+
+            CREATE TABLE IF NOT EXISTS background_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL DEFAULT 0,
+                kind TEXT NOT NULL,
+                description TEXT NOT NULL,
+                status TEXT NOT NULL,
+                output TEXT NOT NULL DEFAULT '',
+                error TEXT,
+                created_at TEXT NOT NULL,
+                finished_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_background_jobs_chat
+                ON background_jobs(chat_id, id);
+
+            CREATE TABLE IF NOT EXISTS chat_env (
+                chat_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, key)
+            );
+
+            CREATE TABLE IF NOT EXISTS code_reviews (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                repo_dir TEXT NOT NULL,
+                base_commit TEXT,
+                prompt_preview TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS projects (
+                name TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                template TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS access_requests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                channel TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                chat_title TEXT,
+                requester TEXT NOT NULL,
+                message TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                created_at TEXT NOT NULL,
+                resolved_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_access_requests_chat_id
+                ON access_requests(chat_id);
+
+            CREATE TABLE IF NOT EXISTS chat_access (
+                chat_id INTEGER PRIMARY KEY,
+                channel TEXT NOT NULL,
+                status TEXT NOT NULL,
+                expires_at TEXT,
+                note TEXT,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS deleted_sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
                 messages_json TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
+                deleted_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_deleted_sessions_chat_id
+                ON deleted_sessions(chat_id);
+
+            CREATE TABLE IF NOT EXISTS session_memory_flushes (
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                session_updated_at TEXT NOT NULL,
                 PRIMARY KEY (chat_id, persona_id)
             );
-            INSERT INTO sessions_new (chat_id, persona_id, messages_json, updated_at)
-            SELECT s.chat_id, p.id, s.messages_json, s.updated_at
-            FROM sessions s
-            JOIN personas p ON p.chat_id = s.chat_id AND p.name = 'default';
-            DROP TABLE sessions;
-            ALTER TABLE sessions_new RENAME TO sessions;",
+
+            CREATE TABLE IF NOT EXISTS response_feedback (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                score INTEGER NOT NULL,
+                emoji TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                reply TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_response_feedback_created
+                ON response_feedback(created_at);
+
+            CREATE TABLE IF NOT EXISTS feedback_reports (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                report TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS instance_leases (
+                name TEXT PRIMARY KEY,
+                holder TEXT NOT NULL,
+                heartbeat_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS feed_items (
+                chat_id INTEGER NOT NULL,
+                feed_url TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                seen_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, feed_url, item_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS feature_flags (
+                chat_id INTEGER NOT NULL,
+                flag TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, flag)
+            );
+
+            CREATE TABLE IF NOT EXISTS incidents (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                key TEXT NOT NULL,
+                title TEXT NOT NULL,
+                source TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'open',
+                chat_id INTEGER NOT NULL,
+                session_chat_id INTEGER NOT NULL,
+                repeats INTEGER NOT NULL DEFAULT 0,
+                next_ping_at TEXT,
+                acknowledged_by TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_incidents_status
+                ON incidents(status, next_ping_at);
+
+            CREATE TABLE IF NOT EXISTS provider_files (
+                provider TEXT NOT NULL,
+                file_id TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (provider, file_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS chat_archives (
+                chat_id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL,
+                message_count INTEGER NOT NULL,
+                last_message_at TEXT NOT NULL,
+                archived_at TEXT NOT NULL
+            );",
+        )?;
+
+        Self::migrate_persona_schema(&conn)?;
+        Self::migrate_fts(&conn)?;
+        Self::migrate_social_oauth(&conn)?;
+        Self::migrate_cursor_agent_runs(&conn)?;
+        Self::migrate_scheduled_tasks(&conn)?;
+
+        Ok(Database {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn migrate_persona_schema(conn: &Connection) -> Result<(), MicroClawError> {
+        // Check if messages has persona_id (new schema)
+        let has_persona = conn
+            .prepare("PRAGMA table_info(messages)")
+            .and_then(|mut stmt| {
+                let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+                Ok(rows
+                    .filter_map(|r| r.ok())
+                    .any(|c| c == "persona_id"))
+            })
+            .unwrap_or(false);
+
+        if has_persona {
+            return Ok(());
+        }
+
+        // Add active_persona_id to chats if missing
+        let has_active = conn
+            .prepare("PRAGMA table_info(chats)")
+            .and_then(|mut stmt| {
+                let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+                Ok(rows.filter_map(|r| r.ok()).any(|c| c == "active_persona_id"))
+            })
+            .unwrap_or(false);
+        if !has_active {
+            conn.execute("ALTER TABLE chats ADD COLUMN active_persona_id INTEGER", [])?;
+        }
+
+        // Create personas table if not exists (might not exist in very old DB)
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS personas (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                model_override TEXT,
+                UNIQUE(chat_id, name)
+            );
+            CREATE INDEX IF NOT EXISTS idx_personas_chat_id ON personas(chat_id);",
+        )?;
+
+        // Collect all chat_ids
+        let chat_ids: Vec<i64> = {
+            let mut out = Vec::new();
+            let mut stmt = conn.prepare(
+                "SELECT chat_id FROM chats UNION SELECT chat_id FROM sessions UNION SELECT chat_id FROM messages",
+            )?;
+            let rows = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+            for r in rows {
+                if let Ok(id) = r {
+                    if !out.contains(&id) {
+                        out.push(id);
+                    }
+                }
+            }
+            out
+        };
+
+        // Create default persona for each chat, set active
+        let now = chrono::Utc::now().to_rfc3339();
+        for cid in &chat_ids {
+            conn.execute(
+                "INSERT OR IGNORE INTO chats (chat_id, chat_title, chat_type, last_message_time, active_persona_id)
+                 VALUES (?1, NULL, 'private', ?2, NULL)",
+                params![cid, now],
+            )?;
+            conn.execute(
+                "INSERT OR IGNORE INTO personas (chat_id, name, model_override) VALUES (?1, 'default', NULL)",
+                params![cid],
+            )?;
+            let persona_id: i64 = conn.query_row(
+                "SELECT id FROM personas WHERE chat_id = ?1 AND name = 'default'",
+                params![cid],
+                |row| row.get(0),
+            )?;
+            conn.execute(
+                "UPDATE chats SET active_persona_id = ?1 WHERE chat_id = ?2",
+                params![persona_id, cid],
+            )?;
+        }
+
+        // Migrate sessions
+        conn.execute_batch(
+            "CREATE TABLE sessions_new (
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                messages_json TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, persona_id)
+            );
+            INSERT INTO sessions_new (chat_id, persona_id, messages_json, updated_at)
+            SELECT s.chat_id, p.id, s.messages_json, s.updated_at
+            FROM sessions s
+            JOIN personas p ON p.chat_id = s.chat_id AND p.name = 'default';
+            DROP TABLE sessions;
+            ALTER TABLE sessions_new RENAME TO sessions;",
+        )?;
+
+        // Migrate messages
+        conn.execute_batch(
+            "CREATE TABLE messages_new (
+                id TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                persona_id INTEGER NOT NULL,
+                sender_name TEXT NOT NULL,
+                content TEXT NOT NULL,
+                is_from_bot INTEGER NOT NULL DEFAULT 0,
+                timestamp TEXT NOT NULL,
+                PRIMARY KEY (id, chat_id, persona_id)
+            );
+            CREATE INDEX idx_messages_new_chat_ts ON messages_new(chat_id, persona_id, timestamp);
+            INSERT INTO messages_new SELECT m.id, m.chat_id, p.id, m.sender_name, m.content, m.is_from_bot, m.timestamp
+            FROM messages m
+            JOIN personas p ON p.chat_id = m.chat_id AND p.name = 'default';
+            DROP TABLE messages;
+            ALTER TABLE messages_new RENAME TO messages;
+            CREATE INDEX IF NOT EXISTS idx_messages_chat_timestamp ON messages(chat_id, persona_id, timestamp);",
+        )?;
+
+        Ok(())
+    }
+
+    /// Older databases lack the scopes / connected_at columns on social_oauth_tokens and the
+    /// PKCE / browser binding / scope columns on oauth_pending_states.
+    fn migrate_social_oauth(conn: &Connection) -> Result<(), MicroClawError> {
+        for (table, new_columns) in [
+            ("social_oauth_tokens", &["scopes", "connected_at"][..]),
+            (
+                "oauth_pending_states",
+                &["code_verifier", "browser_binding", "scopes"][..],
+            ),
+        ] {
+            let columns: Vec<String> = conn
+                .prepare(&format!("PRAGMA table_info({table})"))
+                .and_then(|mut stmt| {
+                    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+                    Ok(rows.filter_map(|r| r.ok()).collect())
+                })
+                .unwrap_or_default();
+            for column in new_columns {
+                if !columns.iter().any(|c| c == *column) {
+                    conn.execute(
+                        &format!("ALTER TABLE {table} ADD COLUMN {column} TEXT"),
+                        [],
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn migrate_cursor_agent_runs(conn: &Connection) -> Result<(), MicroClawError> {
+        let has_steps = conn
+            .prepare("PRAGMA table_info(cursor_agent_runs)")
+            .and_then(|mut stmt| {
+                let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+                Ok(rows.filter_map(|r| r.ok()).any(|c| c == "steps_json"))
+            })
+            .unwrap_or(true);
+        if !has_steps {
+            conn.execute(
+                "ALTER TABLE cursor_agent_runs ADD COLUMN steps_json TEXT",
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Retry, run-now, timezone and concurrency columns on scheduled_tasks.
+    fn migrate_scheduled_tasks(conn: &Connection) -> Result<(), MicroClawError> {
+        let columns: Vec<String> = conn
+            .prepare("PRAGMA table_info(scheduled_tasks)")
+            .and_then(|mut stmt| {
+                let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+                Ok(rows.filter_map(|r| r.ok()).collect())
+            })
+            .unwrap_or_default();
+        for (column, definition) in [
+            ("max_retries", "INTEGER"),
+            ("retry_count", "INTEGER NOT NULL DEFAULT 0"),
+            ("run_requested_at", "TEXT"),
+            ("timezone", "TEXT"),
+            ("concurrency", "TEXT"),
+        ] {
+            if !columns.iter().any(|c| c == column) {
+                conn.execute(
+                    &format!("ALTER TABLE scheduled_tasks ADD COLUMN {column} {definition}"),
+                    [],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn migrate_fts(conn: &Connection) -> Result<(), MicroClawError> {
+        // Create FTS5 virtual table and triggers (after all table migrations)
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                content,
+                content='messages',
+                content_rowid='rowid'
+            );
+            CREATE TRIGGER IF NOT EXISTS messages_fts_ai AFTER INSERT ON messages BEGIN
+                INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS messages_fts_bd BEFORE DELETE ON messages BEGIN
+                INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS messages_fts_au AFTER UPDATE ON messages BEGIN
+                INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+                INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+            END;",
+        )?;
+
+        // One-time migration: populate FTS from existing messages if FTS is empty but messages has data
+        let fts_count: i64 =
+            conn.query_row("SELECT count(*) FROM messages_fts", [], |r| r.get(0))?;
+        let msg_count: i64 =
+            conn.query_row("SELECT count(*) FROM messages", [], |r| r.get(0))?;
+        if fts_count == 0 && msg_count > 0 {
+            conn.execute("INSERT INTO messages_fts(rowid, content) SELECT rowid, content FROM messages", [])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn upsert_chat(
+        &self,
+        chat_id: i64,
+        chat_title: Option<&str>,
+        chat_type: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO chats (chat_id, chat_title, chat_type, last_message_time)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(chat_id) DO UPDATE SET
+                chat_title = COALESCE(?2, chat_title),
+                chat_type = ?3,
+                last_message_time = ?4",
+            params![chat_id, chat_title, chat_type, now],
+        )?;
+        Ok(())
+    }
+
+    /// Store many messages in one transaction (same upsert semantics as `store_message`).
+    pub fn store_messages(&self, msgs: &[StoredMessage]) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        for msg in msgs {
+            tx.execute(
+                "INSERT OR REPLACE INTO messages (id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    msg.id,
+                    msg.chat_id,
+                    msg.persona_id,
+                    msg.sender_name,
+                    msg.content,
+                    msg.is_from_bot as i32,
+                    msg.timestamp,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn store_message(&self, msg: &StoredMessage) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO messages (id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                msg.id,
+                msg.chat_id,
+                msg.persona_id,
+                msg.sender_name,
+                msg.content,
+                msg.is_from_bot as i32,
+                msg.timestamp,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_message(
+        &self,
+        chat_id: i64,
+        message_id: &str,
+    ) -> Result<Option<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages
+             WHERE chat_id = ?1 AND id = ?2",
+            params![chat_id, message_id],
+            |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    content: row.get(4)?,
+                    is_from_bot: row.get::<_, i32>(5)? != 0,
+                    timestamp: row.get(6)?,
+                })
+            },
+        );
+        match result {
+            Ok(m) => Ok(Some(m)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete one stored message (all personas) and its thread mapping. The FTS index follows
+    /// through the messages_fts triggers.
+    pub fn delete_message(&self, chat_id: i64, message_id: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let rows = tx.execute(
+            "DELETE FROM messages WHERE chat_id = ?1 AND id = ?2",
+            params![chat_id, message_id],
+        )?;
+        tx.execute(
+            "DELETE FROM message_threads WHERE chat_id = ?1 AND message_id = ?2",
+            params![chat_id, message_id],
+        )?;
+        tx.commit()?;
+        Ok(rows > 0)
+    }
+
+    /// Mask stored messages of a chat: occurrences of `text` become `replacement`, in message
+    /// `message_id` only or (when None) in every message of the chat. Without `text` the whole
+    /// content of `message_id` is replaced. Returns the number of messages changed.
+    pub fn redact_messages(
+        &self,
+        chat_id: i64,
+        message_id: Option<&str>,
+        text: Option<&str>,
+        replacement: &str,
+    ) -> Result<usize, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = match text {
+            Some(text) => conn.execute(
+                "UPDATE messages SET content = replace(content, ?3, ?4)
+                 WHERE chat_id = ?1 AND (?2 IS NULL OR id = ?2) AND instr(content, ?3) > 0",
+                params![chat_id, message_id, text, replacement],
+            )?,
+            None => conn.execute(
+                "UPDATE messages SET content = ?3 WHERE chat_id = ?1 AND id = ?2",
+                params![chat_id, message_id, replacement],
+            )?,
+        };
+        Ok(rows)
+    }
+
+    /// Most recent message in the chat, optionally only from the bot (or only from users)
+    /// and/or containing `contains` (case-insensitive).
+    pub fn find_latest_message(
+        &self,
+        chat_id: i64,
+        from_bot: Option<bool>,
+        contains: Option<&str>,
+    ) -> Result<Option<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let pattern = contains.map(|c| {
+            format!(
+                "%{}%",
+                c.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+            )
+        });
+        let result = conn.query_row(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages
+             WHERE chat_id = ?1
+               AND (?2 IS NULL OR is_from_bot = ?2)
+               AND (?3 IS NULL OR content LIKE ?3 ESCAPE '\\')
+             ORDER BY timestamp DESC
+             LIMIT 1",
+            params![chat_id, from_bot.map(|b| b as i32), pattern],
+            |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    content: row.get(4)?,
+                    is_from_bot: row.get::<_, i32>(5)? != 0,
+                    timestamp: row.get(6)?,
+                })
+            },
+        );
+        match result {
+            Ok(m) => Ok(Some(m)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get_chat_title(&self, chat_id: i64) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT chat_title FROM chats WHERE chat_id = ?1",
+            params![chat_id],
+            |row| row.get::<_, Option<String>>(0),
+        );
+        match result {
+            Ok(t) => Ok(t),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Most recent message from a user (not the bot) in the chat, across personas.
+    pub fn get_latest_user_message(
+        &self,
+        chat_id: i64,
+    ) -> Result<Option<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages
+             WHERE chat_id = ?1 AND is_from_bot = 0
+             ORDER BY timestamp DESC
+             LIMIT 1",
+            params![chat_id],
+            |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    content: row.get(4)?,
+                    is_from_bot: row.get::<_, i32>(5)? != 0,
+                    timestamp: row.get(6)?,
+                })
+            },
+        );
+        match result {
+            Ok(m) => Ok(Some(m)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get_recent_messages(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages
+             WHERE chat_id = ?1 AND persona_id = ?2
+             ORDER BY timestamp DESC
+             LIMIT ?3",
+        )?;
+
+        let messages = stmt
+            .query_map(params![chat_id, persona_id, limit as i64], |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    content: row.get(4)?,
+                    is_from_bot: row.get::<_, i32>(5)? != 0,
+                    timestamp: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Reverse so oldest first
+        let mut messages = messages;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    pub fn get_all_messages(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages
+             WHERE chat_id = ?1 AND persona_id = ?2
+             ORDER BY timestamp ASC",
+        )?;
+        let messages = stmt
+            .query_map(params![chat_id, persona_id], |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    content: row.get(4)?,
+                    is_from_bot: row.get::<_, i32>(5)? != 0,
+                    timestamp: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    pub fn get_chats_by_type(
+        &self,
+        chat_type: &str,
+        limit: usize,
+    ) -> Result<Vec<ChatSummary>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT
+                c.chat_id,
+                c.chat_title,
+                c.chat_type,
+                c.last_message_time,
+                (
+                    SELECT m.content
+                    FROM messages m
+                    WHERE m.chat_id = c.chat_id
+                    ORDER BY m.timestamp DESC
+                    LIMIT 1
+                ) AS last_message_preview
+             FROM chats c
+             WHERE c.chat_type = ?1
+             ORDER BY c.last_message_time DESC
+             LIMIT ?2",
+        )?;
+        let chats = stmt
+            .query_map(params![chat_type, limit as i64], |row| {
+                Ok(ChatSummary {
+                    chat_id: row.get(0)?,
+                    chat_title: row.get(1)?,
+                    chat_type: row.get(2)?,
+                    last_message_time: row.get(3)?,
+                    last_message_preview: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(chats)
+    }
+
+    pub fn get_recent_chats(&self, limit: usize) -> Result<Vec<ChatSummary>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT
+                c.chat_id,
+                c.chat_title,
+                c.chat_type,
+                c.last_message_time,
+                (
+                    SELECT m.content
+                    FROM messages m
+                    WHERE m.chat_id = c.chat_id
+                    ORDER BY m.timestamp DESC
+                    LIMIT 1
+                ) AS last_message_preview
+             FROM chats c
+             ORDER BY c.last_message_time DESC
+             LIMIT ?1",
+        )?;
+        let chats = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(ChatSummary {
+                    chat_id: row.get(0)?,
+                    chat_title: row.get(1)?,
+                    chat_type: row.get(2)?,
+                    last_message_time: row.get(3)?,
+                    last_message_preview: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(chats)
+    }
+
+    pub fn get_chat_type(&self, chat_id: i64) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT chat_type FROM chats WHERE chat_id = ?1",
+            params![chat_id],
+            |row| row.get::<_, String>(0),
+        );
+        match result {
+            Ok(v) => Ok(Some(v)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get messages since the bot's last response in this chat/persona.
+    /// Falls back to `fallback_limit` most recent messages if bot never responded.
+    pub fn get_messages_since_last_bot_response(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        max: usize,
+        fallback: usize,
+    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+
+        // Find timestamp of last bot message
+        let last_bot_ts: Option<String> = conn
+            .query_row(
+                "SELECT timestamp FROM messages
+                 WHERE chat_id = ?1 AND persona_id = ?2 AND is_from_bot = 1
+                 ORDER BY timestamp DESC LIMIT 1",
+                params![chat_id, persona_id],
+                |row| row.get(0),
+            )
+            .ok();
+
+        let mut messages = if let Some(ts) = last_bot_ts {
+            let mut stmt = conn.prepare(
+                "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+                 FROM messages
+                 WHERE chat_id = ?1 AND persona_id = ?2 AND timestamp >= ?3
+                 ORDER BY timestamp DESC
+                 LIMIT ?4",
+            )?;
+            let rows = stmt
+                .query_map(params![chat_id, persona_id, ts, max as i64], |row| {
+                    Ok(StoredMessage {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        persona_id: row.get(2)?,
+                        sender_name: row.get(3)?,
+                        content: row.get(4)?,
+                        is_from_bot: row.get::<_, i32>(5)? != 0,
+                        timestamp: row.get(6)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows
+        } else {
+            let mut stmt = conn.prepare(
+                "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+                 FROM messages
+                 WHERE chat_id = ?1 AND persona_id = ?2
+                 ORDER BY timestamp DESC
+                 LIMIT ?3",
+            )?;
+            let rows = stmt
+                .query_map(params![chat_id, persona_id, fallback as i64], |row| {
+                    Ok(StoredMessage {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        persona_id: row.get(2)?,
+                        sender_name: row.get(3)?,
+                        content: row.get(4)?,
+                        is_from_bot: row.get::<_, i32>(5)? != 0,
+                        timestamp: row.get(6)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            rows
+        };
+
+        messages.reverse();
+        Ok(messages)
+    }
+
+    // --- Scheduled tasks ---
+
+    const TASK_COLUMNS: &'static str = "id, chat_id, prompt, schedule_type, schedule_value,
+         next_run, last_run, status, created_at, max_retries, retry_count, run_requested_at,
+         timezone, concurrency";
+
+    fn row_to_task(row: &rusqlite::Row<'_>) -> rusqlite::Result<ScheduledTask> {
+        Ok(ScheduledTask {
+            id: row.get(0)?,
+            chat_id: row.get(1)?,
+            prompt: row.get(2)?,
+            schedule_type: row.get(3)?,
+            schedule_value: row.get(4)?,
+            next_run: row.get(5)?,
+            last_run: row.get(6)?,
+            status: row.get(7)?,
+            created_at: row.get(8)?,
+            max_retries: row.get(9)?,
+            retry_count: row.get(10)?,
+            run_requested_at: row.get(11)?,
+            timezone: row.get(12)?,
+            concurrency: row.get(13)?,
+        })
+    }
+
+    pub fn create_scheduled_task(
+        &self,
+        chat_id: i64,
+        prompt: &str,
+        schedule_type: &str,
+        schedule_value: &str,
+        next_run: &str,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO scheduled_tasks (chat_id, prompt, schedule_type, schedule_value, next_run, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6)",
+            params![chat_id, prompt, schedule_type, schedule_value, next_run, now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Active tasks whose next run has come, plus tasks with a pending run-now request.
+    pub fn get_due_tasks(&self, now: &str) -> Result<Vec<ScheduledTask>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_tasks
+             WHERE (status = 'active' AND next_run <= ?1)
+                OR (run_requested_at IS NOT NULL AND status != 'cancelled')",
+            Self::TASK_COLUMNS
+        ))?;
+        let tasks = stmt
+            .query_map(params![now], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    pub fn get_all_active_tasks(&self) -> Result<Vec<ScheduledTask>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_tasks
+             WHERE status IN ('active', 'paused')
+             ORDER BY id",
+            Self::TASK_COLUMNS
+        ))?;
+        let tasks = stmt
+            .query_map([], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    /// All scheduled tasks for /schedule and list_scheduled_tasks: active, paused, and completed (all chats/personas).
+    pub fn get_all_scheduled_tasks_for_display(&self) -> Result<Vec<ScheduledTask>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_tasks
+             WHERE status IN ('active', 'paused', 'completed')
+             ORDER BY id",
+            Self::TASK_COLUMNS
+        ))?;
+        let tasks = stmt
+            .query_map([], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    pub fn get_tasks_for_chat(&self, chat_id: i64) -> Result<Vec<ScheduledTask>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_tasks
+             WHERE chat_id = ?1 AND status IN ('active', 'paused')
+             ORDER BY id",
+            Self::TASK_COLUMNS
+        ))?;
+        let tasks = stmt
+            .query_map(params![chat_id], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tasks)
+    }
+
+    pub fn get_task_by_id(&self, task_id: i64) -> Result<Option<ScheduledTask>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!(
+                "SELECT {} FROM scheduled_tasks WHERE id = ?1",
+                Self::TASK_COLUMNS
+            ),
+            params![task_id],
+            Self::row_to_task,
+        );
+        match result {
+            Ok(task) => Ok(Some(task)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn update_task_status(&self, task_id: i64, status: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET status = ?1 WHERE id = ?2",
+            params![status, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn update_task_after_run(
+        &self,
+        task_id: i64,
+        last_run: &str,
+        next_run: Option<&str>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        match next_run {
+            Some(next) => {
+                conn.execute(
+                    "UPDATE scheduled_tasks SET last_run = ?1, next_run = ?2 WHERE id = ?3",
+                    params![last_run, next, task_id],
+                )?;
+            }
+            None => {
+                // One-shot task, mark completed
+                conn.execute(
+                    "UPDATE scheduled_tasks SET last_run = ?1, status = 'completed' WHERE id = ?2",
+                    params![last_run, task_id],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set how often a task is retried after failing; None = the configured default.
+    pub fn set_task_max_retries(
+        &self,
+        task_id: i64,
+        max_retries: Option<u32>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET max_retries = ?1 WHERE id = ?2",
+            params![max_retries, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn set_task_timezone(
+        &self,
+        task_id: i64,
+        timezone: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET timezone = ?1 WHERE id = ?2",
+            params![timezone, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn set_task_concurrency(
+        &self,
+        task_id: i64,
+        concurrency: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET concurrency = ?1 WHERE id = ?2",
+            params![concurrency, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Move a due task to its next run without running it (skipped because it is still busy).
+    pub fn skip_task_run(&self, task_id: i64, next_run: &str) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE scheduled_tasks SET next_run = ?1 WHERE id = ?2",
+            params![next_run, task_id],
+        )?;
+        Ok(())
+    }
+
+    /// Queue retry `retry_count` of a failed task at `next_run`. One-shot tasks (completed when
+    /// claimed) become active again; paused or cancelled tasks are left alone.
+    pub fn schedule_task_retry(
+        &self,
+        task_id: i64,
+        retry_count: u32,
+        next_run: &str,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET retry_count = ?1, next_run = ?2, status = 'active'
+             WHERE id = ?3 AND status IN ('active', 'completed')",
+            params![retry_count, next_run, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Clear the failed attempts after a successful or finally failed run.
+    pub fn reset_task_retries(&self, task_id: i64) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE scheduled_tasks SET retry_count = 0 WHERE id = ?1 AND retry_count > 0",
+            params![task_id],
+        )?;
+        Ok(())
+    }
+
+    /// Ask the scheduler to run a task once on its next tick without touching its schedule.
+    /// Cancelled tasks cannot be run.
+    pub fn request_task_run(&self, task_id: i64, requested_at: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET run_requested_at = ?1 WHERE id = ?2 AND status != 'cancelled'",
+            params![requested_at, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn clear_task_run_request(&self, task_id: i64) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE scheduled_tasks SET run_requested_at = NULL WHERE id = ?1",
+            params![task_id],
+        )?;
+        Ok(())
+    }
+
+    // --- Task run logs ---
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_task_run(
+        &self,
+        task_id: i64,
+        chat_id: i64,
+        started_at: &str,
+        finished_at: &str,
+        duration_ms: i64,
+        success: bool,
+        result_summary: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO task_run_logs (task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                task_id,
+                chat_id,
+                started_at,
+                finished_at,
+                duration_ms,
+                success as i32,
+                result_summary,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn get_task_run_logs(
+        &self,
+        task_id: i64,
+        limit: usize,
+    ) -> Result<Vec<TaskRunLog>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary
+             FROM task_run_logs
+             WHERE task_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let logs = stmt
+            .query_map(params![task_id, limit as i64], |row| {
+                Ok(TaskRunLog {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    chat_id: row.get(2)?,
+                    started_at: row.get(3)?,
+                    finished_at: row.get(4)?,
+                    duration_ms: row.get(5)?,
+                    success: row.get::<_, i32>(6)? != 0,
+                    result_summary: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(logs)
+    }
+
+    // --- Cursor agent runs ---
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_cursor_agent_run(
+        &self,
+        chat_id: i64,
+        channel: &str,
+        prompt_preview: &str,
+        workdir: Option<&str>,
+        started_at: &str,
+        finished_at: &str,
+        success: bool,
+        exit_code: Option<i32>,
+        output_preview: Option<&str>,
+        output_path: Option<&str>,
+        steps_json: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO cursor_agent_runs (chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path, steps_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                chat_id,
+                channel,
+                prompt_preview,
+                workdir,
+                started_at,
+                finished_at,
+                success as i32,
+                exit_code,
+                output_preview,
+                output_path,
+                steps_json,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn get_cursor_agent_run(&self, id: i64) -> Result<Option<CursorAgentRun>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path, steps_json
+             FROM cursor_agent_runs WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(CursorAgentRun {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    channel: row.get(2)?,
+                    prompt_preview: row.get(3)?,
+                    workdir: row.get(4)?,
+                    started_at: row.get(5)?,
+                    finished_at: row.get(6)?,
+                    success: row.get::<_, i32>(7)? != 0,
+                    exit_code: row.get(8)?,
+                    output_preview: row.get(9)?,
+                    output_path: row.get(10)?,
+                    steps_json: row.get(11)?,
+                })
+            },
+        );
+        match result {
+            Ok(run) => Ok(Some(run)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get recent cursor-agent runs, optionally filtered by chat_id. Ordered by finished_at DESC.
+    pub fn get_cursor_agent_runs(
+        &self,
+        chat_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<CursorAgentRun>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let runs: Vec<CursorAgentRun> = match chat_id {
+            Some(cid) => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path, steps_json
+                     FROM cursor_agent_runs WHERE chat_id = ?1 ORDER BY finished_at DESC LIMIT ?2",
+                )?;
+                let rows = stmt.query_map(params![cid, limit as i64], |row| {
+                    Ok(CursorAgentRun {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        channel: row.get(2)?,
+                        prompt_preview: row.get(3)?,
+                        workdir: row.get(4)?,
+                        started_at: row.get(5)?,
+                        finished_at: row.get(6)?,
+                        success: row.get::<_, i32>(7)? != 0,
+                        exit_code: row.get(8)?,
+                        output_preview: row.get(9)?,
+                        output_path: row.get(10)?,
+                        steps_json: row.get(11)?,
+                    })
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            }
+            None => {
+                let mut stmt = conn.prepare(
+                    "SELECT id, chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path, steps_json
+                     FROM cursor_agent_runs ORDER BY finished_at DESC LIMIT ?1",
+                )?;
+                let rows = stmt.query_map(params![limit as i64], |row| {
+                    Ok(CursorAgentRun {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        channel: row.get(2)?,
+                        prompt_preview: row.get(3)?,
+                        workdir: row.get(4)?,
+                        started_at: row.get(5)?,
+                        finished_at: row.get(6)?,
+                        success: row.get::<_, i32>(7)? != 0,
+                        exit_code: row.get(8)?,
+                        output_preview: row.get(9)?,
+                        output_path: row.get(10)?,
+                        steps_json: row.get(11)?,
+                    })
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        Ok(runs)
+    }
+
+    /// Cursor-agent runs finished at or after `since` (RFC 3339), oldest first.
+    pub fn list_cursor_agent_runs_since(
+        &self,
+        since: &str,
+    ) -> Result<Vec<CursorAgentRun>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path, steps_json
+             FROM cursor_agent_runs WHERE finished_at >= ?1 ORDER BY finished_at ASC",
+        )?;
+        let runs = stmt
+            .query_map(params![since], |row| {
+                Ok(CursorAgentRun {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    channel: row.get(2)?,
+                    prompt_preview: row.get(3)?,
+                    workdir: row.get(4)?,
+                    started_at: row.get(5)?,
+                    finished_at: row.get(6)?,
+                    success: row.get::<_, i32>(7)? != 0,
+                    exit_code: row.get(8)?,
+                    output_preview: row.get(9)?,
+                    output_path: row.get(10)?,
+                    steps_json: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(runs)
+    }
+
+    #[allow(dead_code)]
+    pub fn delete_task(&self, task_id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM scheduled_tasks WHERE id = ?1",
+            params![task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    // --- Sessions ---
+
+    pub fn save_session(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        messages_json: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO sessions (chat_id, persona_id, messages_json, updated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(chat_id, persona_id) DO UPDATE SET
+                messages_json = ?3,
+                updated_at = ?4",
+            params![chat_id, persona_id, messages_json, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_session(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+    ) -> Result<Option<(String, String)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT messages_json, updated_at FROM sessions WHERE chat_id = ?1 AND persona_id = ?2",
+            params![chat_id, persona_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        );
+        match result {
+            Ok(pair) => Ok(Some(pair)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Soft-delete: the session moves to deleted_sessions, where undelete_session can bring it
+    /// back until purge_deleted_sessions removes it.
+    pub fn delete_session(&self, chat_id: i64, persona_id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO deleted_sessions (chat_id, persona_id, messages_json, updated_at, deleted_at)
+             SELECT chat_id, persona_id, messages_json, updated_at, ?3
+             FROM sessions WHERE chat_id = ?1 AND persona_id = ?2",
+            params![chat_id, persona_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        let rows = tx.execute(
+            "DELETE FROM sessions WHERE chat_id = ?1 AND persona_id = ?2",
+            params![chat_id, persona_id],
+        )?;
+        tx.commit()?;
+        Ok(rows > 0)
+    }
+
+    /// Deleted sessions of a chat, newest first.
+    pub fn list_deleted_sessions(
+        &self,
+        chat_id: i64,
+    ) -> Result<Vec<DeletedSession>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, messages_json, updated_at, deleted_at
+             FROM deleted_sessions WHERE chat_id = ?1 ORDER BY id DESC",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id], |row| {
+                Ok(DeletedSession {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    messages_json: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    deleted_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Restore deleted session `id`. The session currently held by that chat/persona, if any, is
+    /// soft-deleted in its place. When the persona no longer exists (the chat was wiped), the
+    /// session goes to `fallback_persona_id`. Returns the restored session, or None if `id` is
+    /// unknown.
+    pub fn undelete_session(
+        &self,
+        id: i64,
+        fallback_persona_id: i64,
+    ) -> Result<Option<DeletedSession>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let found = tx.query_row(
+            "SELECT id, chat_id, persona_id, messages_json, updated_at, deleted_at
+             FROM deleted_sessions WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(DeletedSession {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    messages_json: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    deleted_at: row.get(5)?,
+                })
+            },
+        );
+        let mut session = match found {
+            Ok(s) => s,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let persona_exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM personas WHERE id = ?1 AND chat_id = ?2)",
+            params![session.persona_id, session.chat_id],
+            |row| row.get(0),
+        )?;
+        if !persona_exists {
+            session.persona_id = fallback_persona_id;
+        }
+        tx.execute(
+            "INSERT INTO deleted_sessions (chat_id, persona_id, messages_json, updated_at, deleted_at)
+             SELECT chat_id, persona_id, messages_json, updated_at, ?3
+             FROM sessions WHERE chat_id = ?1 AND persona_id = ?2",
+            params![
+                session.chat_id,
+                session.persona_id,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO sessions (chat_id, persona_id, messages_json, updated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                session.chat_id,
+                session.persona_id,
+                session.messages_json,
+                session.updated_at
+            ],
+        )?;
+        tx.execute("DELETE FROM deleted_sessions WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(Some(session))
+    }
+
+    /// Apply `rewrite` to the messages JSON of every session of a chat, deleted ones included;
+    /// it returns the new JSON, or None to leave a session unchanged. Returns how many changed.
+    pub fn rewrite_sessions(
+        &self,
+        chat_id: i64,
+        rewrite: impl Fn(&str) -> Option<String>,
+    ) -> Result<usize, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let mut changed = 0;
+        for (select, update) in [
+            (
+                "SELECT persona_id, messages_json FROM sessions WHERE chat_id = ?1",
+                "UPDATE sessions SET messages_json = ?3 WHERE chat_id = ?1 AND persona_id = ?2",
+            ),
+            (
+                "SELECT id, messages_json FROM deleted_sessions WHERE chat_id = ?1",
+                "UPDATE deleted_sessions SET messages_json = ?3 WHERE chat_id = ?1 AND id = ?2",
+            ),
+        ] {
+            let rows = {
+                let mut stmt = tx.prepare(select)?;
+                let rows = stmt
+                    .query_map(params![chat_id], |row| {
+                        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows
+            };
+            for (key, json) in rows {
+                if let Some(new_json) = rewrite(&json) {
+                    tx.execute(update, params![chat_id, key, new_json])?;
+                    changed += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    // --- Instance leases ---
+
+    /// Take or renew lease `name` for `holder`. Succeeds when the lease is free, already held
+    /// by `holder`, or its holder's last heartbeat is older than `stale_before` (RFC 3339).
+    pub fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        stale_before: &str,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let n = conn.execute(
+            "INSERT INTO instance_leases (name, holder, heartbeat_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET holder = excluded.holder,
+                 heartbeat_at = excluded.heartbeat_at
+             WHERE instance_leases.holder = excluded.holder
+                OR instance_leases.heartbeat_at < ?4",
+            params![name, holder, chrono::Utc::now().to_rfc3339(), stale_before],
+        )?;
+        Ok(n > 0)
+    }
+
+    pub fn release_lease(&self, name: &str, holder: &str) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM instance_leases WHERE name = ?1 AND holder = ?2",
+            params![name, holder],
+        )?;
+        Ok(())
+    }
+
+    // --- Feed items ---
+
+    pub fn has_feed_items(&self, chat_id: i64, feed_url: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM feed_items WHERE chat_id = ?1 AND feed_url = ?2",
+            params![chat_id, feed_url],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Mark `item_ids` of a feed as seen now and return the ones not seen before. Items that
+    /// dropped out of the feed and were last seen before `prune_before` are forgotten.
+    pub fn record_feed_items(
+        &self,
+        chat_id: i64,
+        feed_url: &str,
+        item_ids: &[String],
+        prune_before: &str,
+    ) -> Result<Vec<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut new_ids = Vec::new();
+        for id in item_ids {
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO feed_items (chat_id, feed_url, item_id, seen_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![chat_id, feed_url, id, now],
+            )?;
+            if inserted > 0 {
+                new_ids.push(id.clone());
+            } else {
+                tx.execute(
+                    "UPDATE feed_items SET seen_at = ?4
+                     WHERE chat_id = ?1 AND feed_url = ?2 AND item_id = ?3",
+                    params![chat_id, feed_url, id, now],
+                )?;
+            }
+        }
+        tx.execute(
+            "DELETE FROM feed_items WHERE chat_id = ?1 AND feed_url = ?2 AND seen_at < ?3",
+            params![chat_id, feed_url, prune_before],
+        )?;
+        tx.commit()?;
+        Ok(new_ids)
+    }
+
+    // --- Provider files ---
+
+    pub fn record_provider_file(
+        &self,
+        provider: &str,
+        file_id: &str,
+        chat_id: i64,
+        path: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO provider_files (provider, file_id, chat_id, path, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![provider, file_id, chat_id, path, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// The latest upload of `path` to `provider`, if it has not been deleted.
+    pub fn find_provider_file(
+        &self,
+        provider: &str,
+        path: &str,
+    ) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT file_id FROM provider_files WHERE provider = ?1 AND path = ?2
+             ORDER BY created_at DESC LIMIT 1",
+            params![provider, path],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn has_provider_file(&self, provider: &str, file_id: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM provider_files WHERE provider = ?1 AND file_id = ?2",
+            params![provider, file_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// `(provider, file_id)` of uploads created before `before` (RFC 3339).
+    pub fn provider_files_before(
+        &self,
+        before: &str,
+    ) -> Result<Vec<(String, String)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT provider, file_id FROM provider_files WHERE created_at < ?1 ORDER BY created_at",
+        )?;
+        let rows = stmt
+            .query_map(params![before], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn delete_provider_file(&self, provider: &str, file_id: &str) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM provider_files WHERE provider = ?1 AND file_id = ?2",
+            params![provider, file_id],
+        )?;
+        Ok(())
+    }
+
+    // --- Chat archives ---
+
+    /// Chats whose newest message is older than `before` (RFC 3339) and that are not archived,
+    /// oldest first.
+    pub fn chats_inactive_since(
+        &self,
+        before: &str,
+        limit: usize,
+    ) -> Result<Vec<i64>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT chat_id FROM messages
+             WHERE chat_id NOT IN (SELECT chat_id FROM chat_archives)
+             GROUP BY chat_id
+             HAVING MAX(timestamp) < ?1
+             ORDER BY MAX(timestamp)
+             LIMIT ?2",
+        )?;
+        let ids = stmt
+            .query_map(params![before, limit as i64], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Every message of a chat, all personas, oldest first.
+    pub fn get_chat_messages(&self, chat_id: i64) -> Result<Vec<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages WHERE chat_id = ?1
+             ORDER BY timestamp ASC",
+        )?;
+        let messages = stmt
+            .query_map(params![chat_id], |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    content: row.get(4)?,
+                    is_from_bot: row.get::<_, i32>(5)? != 0,
+                    timestamp: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    pub fn get_chat_sessions(&self, chat_id: i64) -> Result<Vec<ChatSessionRow>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT persona_id, messages_json, updated_at FROM sessions WHERE chat_id = ?1",
+        )?;
+        let sessions = stmt
+            .query_map(params![chat_id], |row| {
+                Ok(ChatSessionRow {
+                    persona_id: row.get(0)?,
+                    messages_json: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    /// Record the archive and drop the archived rows: messages up to `archive.last_message_at`
+    /// and sessions not saved after `sessions_until`, so anything newer stays.
+    pub fn archive_chat_data(
+        &self,
+        archive: &ChatArchive,
+        sessions_until: Option<&str>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM messages WHERE chat_id = ?1 AND timestamp <= ?2",
+            params![archive.chat_id, archive.last_message_at],
+        )?;
+        if let Some(until) = sessions_until {
+            tx.execute(
+                "DELETE FROM sessions WHERE chat_id = ?1 AND updated_at <= ?2",
+                params![archive.chat_id, until],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO chat_archives
+                (chat_id, path, message_count, last_message_at, archived_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                archive.chat_id,
+                archive.path,
+                archive.message_count,
+                archive.last_message_at,
+                archive.archived_at
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_chat_archive(&self, chat_id: i64) -> Result<Option<ChatArchive>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT chat_id, path, message_count, last_message_at, archived_at
+             FROM chat_archives WHERE chat_id = ?1",
+            params![chat_id],
+            |row| {
+                Ok(ChatArchive {
+                    chat_id: row.get(0)?,
+                    path: row.get(1)?,
+                    message_count: row.get(2)?,
+                    last_message_at: row.get(3)?,
+                    archived_at: row.get(4)?,
+                })
+            },
+        );
+        match result {
+            Ok(archive) => Ok(Some(archive)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Put archived rows back and forget the archive. Rows that exist again (same message id,
+    /// a session saved since) are kept as they are.
+    pub fn restore_chat_data(
+        &self,
+        chat_id: i64,
+        messages: &[StoredMessage],
+        sessions: &[ChatSessionRow],
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        for m in messages {
+            tx.execute(
+                "INSERT OR IGNORE INTO messages (id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    m.id,
+                    chat_id,
+                    m.persona_id,
+                    m.sender_name,
+                    m.content,
+                    m.is_from_bot as i32,
+                    m.timestamp
+                ],
+            )?;
+        }
+        for s in sessions {
+            tx.execute(
+                "INSERT OR IGNORE INTO sessions (chat_id, persona_id, messages_json, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![chat_id, s.persona_id, s.messages_json, s.updated_at],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM chat_archives WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Rebuild the database file so space freed by deletions goes back to the filesystem.
+    pub fn vacuum(&self) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    // --- Feature flags ---
+
+    /// Override a feature flag for a chat; `None` removes the override.
+    pub fn set_feature_flag(
+        &self,
+        chat_id: i64,
+        flag: &str,
+        enabled: Option<bool>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        match enabled {
+            Some(enabled) => conn.execute(
+                "INSERT INTO feature_flags (chat_id, flag, enabled, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(chat_id, flag) DO UPDATE SET enabled = ?3, updated_at = ?4",
+                params![chat_id, flag, enabled, chrono::Utc::now().to_rfc3339()],
+            )?,
+            None => conn.execute(
+                "DELETE FROM feature_flags WHERE chat_id = ?1 AND flag = ?2",
+                params![chat_id, flag],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// The flag overrides of a chat, by flag name.
+    pub fn list_feature_flags(&self, chat_id: i64) -> Result<Vec<(String, bool)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT flag, enabled FROM feature_flags WHERE chat_id = ?1 ORDER BY flag")?;
+        let rows = stmt
+            .query_map(params![chat_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    // --- Incidents ---
+
+    const INCIDENT_COLUMNS: &'static str = "id, key, title, source, status, chat_id,
+         session_chat_id, repeats, next_ping_at, acknowledged_by, created_at, updated_at";
+
+    fn row_to_incident(row: &rusqlite::Row<'_>) -> rusqlite::Result<Incident> {
+        Ok(Incident {
+            id: row.get(0)?,
+            key: row.get(1)?,
+            title: row.get(2)?,
+            source: row.get(3)?,
+            status: row.get(4)?,
+            chat_id: row.get(5)?,
+            session_chat_id: row.get(6)?,
+            repeats: row.get(7)?,
+            next_ping_at: row.get(8)?,
+            acknowledged_by: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
+        })
+    }
+
+    /// Open an incident; returns its id.
+    pub fn create_incident(
+        &self,
+        key: &str,
+        title: &str,
+        source: &str,
+        chat_id: i64,
+        next_ping_at: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO incidents (key, title, source, status, chat_id, session_chat_id,
+                next_ping_at, created_at, updated_at)
+             VALUES (?1, ?2, ?3, 'open', ?4, 0, ?5, ?6, ?6)",
+            params![key, title, source, chat_id, next_ping_at, now],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_incident_session(
+        &self,
+        id: i64,
+        session_chat_id: i64,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE incidents SET session_chat_id = ?2 WHERE id = ?1",
+            params![id, session_chat_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_incident(&self, id: i64) -> Result<Option<Incident>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!(
+                "SELECT {} FROM incidents WHERE id = ?1",
+                Self::INCIDENT_COLUMNS
+            ),
+            params![id],
+            Self::row_to_incident,
+        );
+        match result {
+            Ok(i) => Ok(Some(i)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The unresolved incident with this key, if any.
+    pub fn find_active_incident(&self, key: &str) -> Result<Option<Incident>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!(
+                "SELECT {} FROM incidents WHERE key = ?1 AND status != 'resolved'
+                 ORDER BY id DESC LIMIT 1",
+                Self::INCIDENT_COLUMNS
+            ),
+            params![key],
+            Self::row_to_incident,
+        );
+        match result {
+            Ok(i) => Ok(Some(i)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Newest first; resolved ones only when asked.
+    pub fn list_incidents(
+        &self,
+        include_resolved: bool,
+        limit: usize,
+    ) -> Result<Vec<Incident>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM incidents WHERE ?1 OR status != 'resolved' ORDER BY id DESC LIMIT ?2",
+            Self::INCIDENT_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map(
+                params![include_resolved, limit as i64],
+                Self::row_to_incident,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Open incidents whose next page is due at `now` (RFC 3339).
+    pub fn due_incident_pages(&self, now: &str) -> Result<Vec<Incident>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM incidents
+             WHERE status = 'open' AND next_ping_at IS NOT NULL AND next_ping_at <= ?1
+             ORDER BY id",
+            Self::INCIDENT_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map(params![now], Self::row_to_incident)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Record a repeat page and when the next one is due (`None` stops paging).
+    pub fn record_incident_page(
+        &self,
+        id: i64,
+        repeats: i64,
+        next_ping_at: Option<&str>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE incidents SET repeats = ?2, next_ping_at = ?3, updated_at = ?4 WHERE id = ?1",
+            params![id, repeats, next_ping_at, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Move an unresolved incident to `acknowledged` or `resolved`, which stops the paging.
+    /// Returns false when it does not exist or is already resolved.
+    pub fn set_incident_status(
+        &self,
+        id: i64,
+        status: &str,
+        by: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE incidents
+             SET status = ?2, next_ping_at = NULL, updated_at = ?3,
+                 acknowledged_by = COALESCE(acknowledged_by, ?4)
+             WHERE id = ?1 AND status != 'resolved'",
+            params![id, status, chrono::Utc::now().to_rfc3339(), by],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Permanently remove sessions deleted before `cutoff` (RFC 3339). Returns how many.
+    pub fn purge_deleted_sessions(&self, cutoff: &str) -> Result<usize, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let n = conn.execute(
+            "DELETE FROM deleted_sessions WHERE deleted_at < ?1",
+            params![cutoff],
+        )?;
+        Ok(n)
+    }
+
+    /// Sessions last updated between `active_since` and `idle_before` whose memory has not been
+    /// flushed since, oldest first.
+    pub fn list_sessions_to_flush(
+        &self,
+        idle_before: &str,
+        active_since: &str,
+        limit: usize,
+    ) -> Result<Vec<IdleSession>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.chat_id, s.persona_id, s.updated_at, f.session_updated_at
+             FROM sessions s
+             LEFT JOIN session_memory_flushes f
+                ON f.chat_id = s.chat_id AND f.persona_id = s.persona_id
+             WHERE s.updated_at < ?1 AND s.updated_at >= ?2
+               AND (f.session_updated_at IS NULL OR f.session_updated_at < s.updated_at)
+             ORDER BY s.updated_at ASC
+             LIMIT ?3",
+        )?;
+        let rows = stmt
+            .query_map(params![idle_before, active_since, limit as i64], |row| {
+                Ok(IdleSession {
+                    chat_id: row.get(0)?,
+                    persona_id: row.get(1)?,
+                    updated_at: row.get(2)?,
+                    flushed_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Record that the session as of `session_updated_at` has been flushed to memory.
+    pub fn record_session_memory_flush(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        session_updated_at: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO session_memory_flushes (chat_id, persona_id, session_updated_at)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(chat_id, persona_id) DO UPDATE SET session_updated_at = ?3",
+            params![chat_id, persona_id, session_updated_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_chat_data(&self, chat_id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let mut affected = 0usize;
+
+        affected += tx.execute("UPDATE chats SET active_persona_id = NULL WHERE chat_id = ?1", params![chat_id])?;
+        // Sessions are soft-deleted like single resets, so undelete_session still works
+        tx.execute(
+            "INSERT INTO deleted_sessions (chat_id, persona_id, messages_json, updated_at, deleted_at)
+             SELECT chat_id, persona_id, messages_json, updated_at, ?2
+             FROM sessions WHERE chat_id = ?1",
+            params![chat_id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        affected += tx.execute("DELETE FROM sessions WHERE chat_id = ?1", params![chat_id])?;
+        affected += tx.execute("DELETE FROM messages WHERE chat_id = ?1", params![chat_id])?;
+        affected += tx.execute("DELETE FROM personas WHERE chat_id = ?1", params![chat_id])?;
+        affected += tx.execute(
+            "DELETE FROM scheduled_tasks WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        affected += tx.execute(
+            "DELETE FROM social_oauth_tokens WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        affected += tx.execute(
+            "DELETE FROM translation_glossary WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        affected += tx.execute(
+            "DELETE FROM conversation_tags WHERE segment_id IN
+             (SELECT id FROM conversation_segments WHERE chat_id = ?1)",
+            params![chat_id],
+        )?;
+        affected += tx.execute(
+            "DELETE FROM poll_votes WHERE poll_id IN
+             (SELECT id FROM polls WHERE chat_id = ?1)",
+            params![chat_id],
+        )?;
+        for table in [
+            "message_threads",
+            "forum_topics",
+            "catch_up_state",
+            "conversation_segments",
+            "custom_commands",
+            "polls",
+            "filtered_outputs",
+            "quarantine",
+            "chat_settings",
+            "llm_usage",
+            "turn_timings",
+            "thinking_transcripts",
+            "artifacts",
+            "todo_items",
+            "todo_lists",
+            "business_chats",
+            "experiment_runs",
+            "session_snapshots",
+            "social_snapshots",
+            "social_follower_counts",
+            "background_jobs",
+            "chat_env",
+            "code_reviews",
+            "access_requests",
+            "response_feedback",
+            "feed_items",
+            "feature_flags",
+            "chat_archives",
+            "session_memory_flushes",
+        ] {
+            affected += tx.execute(
+                &format!("DELETE FROM {table} WHERE chat_id = ?1"),
+                params![chat_id],
+            )?;
+        }
+        affected += tx.execute("DELETE FROM chats WHERE chat_id = ?1", params![chat_id])?;
+
+        tx.commit()?;
+        Ok(affected > 0)
+    }
+
+    // --- Social OAuth tokens ---
+
+    pub fn upsert_social_token(
+        &self,
+        platform: &str,
+        chat_id: i64,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        expires_at: Option<&str>,
+        scopes: Option<&str>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO social_oauth_tokens
+                (platform, chat_id, access_token, refresh_token, expires_at, scopes, connected_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(platform, chat_id) DO UPDATE SET
+                access_token = ?3,
+                refresh_token = ?4,
+                expires_at = ?5,
+                scopes = ?6,
+                connected_at = ?7",
+            params![
+                platform,
+                chat_id,
+                access_token,
+                refresh_token,
+                expires_at,
+                scopes,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_social_token(
+        &self,
+        platform: &str,
+        chat_id: i64,
+    ) -> Result<Option<SocialOAuthToken>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT platform, chat_id, access_token, refresh_token, expires_at, scopes, connected_at
+             FROM social_oauth_tokens
+             WHERE platform = ?1 AND chat_id = ?2",
+            params![platform, chat_id],
+            |row| {
+                Ok(SocialOAuthToken {
+                    platform: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    access_token: row.get(2)?,
+                    refresh_token: row.get(3)?,
+                    expires_at: row.get(4)?,
+                    scopes: row.get(5)?,
+                    connected_at: row.get(6)?,
+                })
+            },
+        );
+        match result {
+            Ok(t) => Ok(Some(t)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace the access token after a refresh, keeping scopes and connected_at. The refresh
+    /// token is only replaced when the provider issued a new one.
+    pub fn update_social_access_token(
+        &self,
+        platform: &str,
+        chat_id: i64,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        expires_at: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE social_oauth_tokens
+             SET access_token = ?3, refresh_token = COALESCE(?4, refresh_token), expires_at = ?5
+             WHERE platform = ?1 AND chat_id = ?2",
+            params![platform, chat_id, access_token, refresh_token, expires_at],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Stored tokens of one chat, or of every chat when `chat_id` is `None`.
+    pub fn list_social_tokens(
+        &self,
+        chat_id: Option<i64>,
+    ) -> Result<Vec<SocialOAuthToken>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT platform, chat_id, access_token, refresh_token, expires_at, scopes, connected_at
+             FROM social_oauth_tokens
+             WHERE ?1 IS NULL OR chat_id = ?1
+             ORDER BY chat_id, platform",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id], |row| {
+                Ok(SocialOAuthToken {
+                    platform: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    access_token: row.get(2)?,
+                    refresh_token: row.get(3)?,
+                    expires_at: row.get(4)?,
+                    scopes: row.get(5)?,
+                    connected_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn delete_social_token(&self, platform: &str, chat_id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM social_oauth_tokens WHERE platform = ?1 AND chat_id = ?2",
+            params![platform, chat_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    // --- Translation glossary ---
+
+    pub fn upsert_glossary_term(
+        &self,
+        chat_id: i64,
+        source_term: &str,
+        target_lang: &str,
+        translation: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO translation_glossary (chat_id, source_term, target_lang, translation, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(chat_id, source_term, target_lang) DO UPDATE SET
+                translation = ?4,
+                updated_at = ?5",
+            params![chat_id, source_term, target_lang, translation, now],
+        )?;
+        Ok(())
+    }
+
+    /// Glossary terms for a chat, optionally limited to one target language.
+    pub fn get_glossary_terms(
+        &self,
+        chat_id: i64,
+        target_lang: Option<&str>,
+    ) -> Result<Vec<GlossaryTerm>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT chat_id, source_term, target_lang, translation, updated_at
+             FROM translation_glossary
+             WHERE chat_id = ?1 AND (?2 IS NULL OR target_lang = ?2)
+             ORDER BY target_lang, source_term",
+        )?;
+        let terms = stmt
+            .query_map(params![chat_id, target_lang], |row| {
+                Ok(GlossaryTerm {
+                    chat_id: row.get(0)?,
+                    source_term: row.get(1)?,
+                    target_lang: row.get(2)?,
+                    translation: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(terms)
+    }
+
+    /// Delete a glossary term. With no target language, removes the term for all languages.
+    pub fn delete_glossary_term(
+        &self,
+        chat_id: i64,
+        source_term: &str,
+        target_lang: Option<&str>,
+    ) -> Result<usize, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM translation_glossary
+             WHERE chat_id = ?1 AND source_term = ?2 AND (?3 IS NULL OR target_lang = ?3)",
+            params![chat_id, source_term, target_lang],
+        )?;
+        Ok(rows)
+    }
+
+    // --- Forum topics and catch-up ---
+
+    pub fn set_message_thread(
+        &self,
+        chat_id: i64,
+        message_id: &str,
+        thread_id: i64,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO message_threads (chat_id, message_id, thread_id)
+             VALUES (?1, ?2, ?3)",
+            params![chat_id, message_id, thread_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_forum_topic(
+        &self,
+        chat_id: i64,
+        thread_id: i64,
+        name: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO forum_topics (chat_id, thread_id, name) VALUES (?1, ?2, ?3)
+             ON CONFLICT(chat_id, thread_id) DO UPDATE SET name = ?3",
+            params![chat_id, thread_id, name],
+        )?;
+        Ok(())
+    }
+
+    /// Start of a user's unread window: their last message before the latest message from
+    /// anyone else, or their last catch-up if that is newer. `None` when the user has no
+    /// recorded activity in the chat.
+    pub fn get_catch_up_since(
+        &self,
+        chat_id: i64,
+        user_name: &str,
+    ) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let last_active: Option<String> = conn.query_row(
+            "SELECT MAX(timestamp) FROM messages
+             WHERE chat_id = ?1 AND sender_name = ?2 AND is_from_bot = 0
+               AND timestamp < (
+                   SELECT COALESCE(MAX(timestamp), '') FROM messages
+                   WHERE chat_id = ?1 AND sender_name != ?2
+               )",
+            params![chat_id, user_name],
+            |row| row.get(0),
+        )?;
+        let caught_up: Option<String> = match conn.query_row(
+            "SELECT last_caught_up_at FROM catch_up_state WHERE chat_id = ?1 AND user_name = ?2",
+            params![chat_id, user_name],
+            |row| row.get(0),
+        ) {
+            Ok(ts) => Some(ts),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(last_active.max(caught_up))
+    }
+
+    /// Messages from others after `since` (or the most recent ones when `None`), oldest first,
+    /// capped at the newest `limit`.
+    pub fn get_messages_for_catch_up(
+        &self,
+        chat_id: i64,
+        user_name: &str,
+        since: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ThreadedMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.chat_id, m.persona_id, m.sender_name, m.content, m.is_from_bot,
+                    m.timestamp, t.thread_id, f.name
+             FROM messages m
+             LEFT JOIN message_threads t ON t.chat_id = m.chat_id AND t.message_id = m.id
+             LEFT JOIN forum_topics f ON f.chat_id = m.chat_id AND f.thread_id = t.thread_id
+             WHERE m.chat_id = ?1 AND m.sender_name != ?2 AND (?3 IS NULL OR m.timestamp > ?3)
+             ORDER BY m.timestamp DESC
+             LIMIT ?4",
+        )?;
+        let mut messages = stmt
+            .query_map(params![chat_id, user_name, since, limit as i64], |row| {
+                Ok(ThreadedMessage {
+                    message: StoredMessage {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        persona_id: row.get(2)?,
+                        sender_name: row.get(3)?,
+                        content: row.get(4)?,
+                        is_from_bot: row.get::<_, i32>(5)? != 0,
+                        timestamp: row.get(6)?,
+                    },
+                    thread_id: row.get(7)?,
+                    topic_name: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    pub fn mark_caught_up(
+        &self,
+        chat_id: i64,
+        user_name: &str,
+        at: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO catch_up_state (chat_id, user_name, last_caught_up_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(chat_id, user_name) DO UPDATE SET last_caught_up_at = ?3",
+            params![chat_id, user_name, at],
+        )?;
+        Ok(())
+    }
+
+    // --- Conversation topics ---
+
+    pub fn get_last_segment_end(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+    ) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let end: Option<String> = conn.query_row(
+            "SELECT MAX(end_ts) FROM conversation_segments WHERE chat_id = ?1 AND persona_id = ?2",
+            params![chat_id, persona_id],
+            |row| row.get(0),
+        )?;
+        Ok(end)
+    }
+
+    /// Messages after `after` (exclusive), oldest first.
+    pub fn get_messages_after(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
+             FROM messages
+             WHERE chat_id = ?1 AND persona_id = ?2 AND (?3 IS NULL OR timestamp > ?3)
+             ORDER BY timestamp ASC
+             LIMIT ?4",
+        )?;
+        let messages = stmt
+            .query_map(
+                params![chat_id, persona_id, after, limit as i64],
+                |row| {
+                    Ok(StoredMessage {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        persona_id: row.get(2)?,
+                        sender_name: row.get(3)?,
+                        content: row.get(4)?,
+                        is_from_bot: row.get::<_, i32>(5)? != 0,
+                        timestamp: row.get(6)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    pub fn insert_conversation_segment(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        start_ts: &str,
+        end_ts: &str,
+        summary: Option<&str>,
+        tags: &[String],
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        tx.execute(
+            "INSERT INTO conversation_segments (chat_id, persona_id, start_ts, end_ts, summary, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![chat_id, persona_id, start_ts, end_ts, summary, now],
+        )?;
+        let segment_id = tx.last_insert_rowid();
+        for tag in tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_tags (segment_id, tag) VALUES (?1, ?2)",
+                params![segment_id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(segment_id)
+    }
+
+    /// All tagged segments for a chat/persona, newest first.
+    pub fn get_conversation_segments(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+    ) -> Result<Vec<ConversationSegment>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.chat_id, s.persona_id, s.start_ts, s.end_ts, s.summary, t.tag
+             FROM conversation_segments s
+             LEFT JOIN conversation_tags t ON t.segment_id = s.id
+             WHERE s.chat_id = ?1 AND s.persona_id = ?2
+             ORDER BY s.end_ts DESC, s.id DESC, t.tag",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id, persona_id], |row| {
+                Ok((
+                    ConversationSegment {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        persona_id: row.get(2)?,
+                        start_ts: row.get(3)?,
+                        end_ts: row.get(4)?,
+                        summary: row.get(5)?,
+                        tags: Vec::new(),
+                    },
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut segments: Vec<ConversationSegment> = Vec::new();
+        for (segment, tag) in rows {
+            if segments.last().map(|s| s.id) != Some(segment.id) {
+                segments.push(segment);
+            }
+            if let (Some(tag), Some(last)) = (tag, segments.last_mut()) {
+                last.tags.push(tag);
+            }
+        }
+        Ok(segments)
+    }
+
+    // --- Custom commands ---
+
+    pub fn upsert_custom_command(
+        &self,
+        chat_id: i64,
+        name: &str,
+        description: &str,
+        template: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO custom_commands (chat_id, name, description, template, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(chat_id, name) DO UPDATE SET
+                description = ?3,
+                template = ?4,
+                updated_at = ?5",
+            params![chat_id, name, description, template, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_custom_command(
+        &self,
+        chat_id: i64,
+        name: &str,
+    ) -> Result<Option<CustomCommand>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT chat_id, name, description, template, updated_at
+             FROM custom_commands WHERE chat_id = ?1 AND name = ?2",
+            params![chat_id, name],
+            |row| {
+                Ok(CustomCommand {
+                    chat_id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    template: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            },
+        );
+        match result {
+            Ok(c) => Ok(Some(c)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Custom commands for the given chats, ordered by chat then name.
+    pub fn list_custom_commands(
+        &self,
+        chat_ids: &[i64],
+    ) -> Result<Vec<CustomCommand>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT chat_id, name, description, template, updated_at
+             FROM custom_commands WHERE chat_id = ?1 ORDER BY name",
+        )?;
+        let mut commands = Vec::new();
+        for chat_id in chat_ids {
+            let rows = stmt
+                .query_map(params![chat_id], |row| {
+                    Ok(CustomCommand {
+                        chat_id: row.get(0)?,
+                        name: row.get(1)?,
+                        description: row.get(2)?,
+                        template: row.get(3)?,
+                        updated_at: row.get(4)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            commands.extend(rows);
+        }
+        Ok(commands)
+    }
+
+    pub fn delete_custom_command(&self, chat_id: i64, name: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM custom_commands WHERE chat_id = ?1 AND name = ?2",
+            params![chat_id, name],
+        )?;
+        Ok(rows > 0)
+    }
+
+    // --- Polls ---
+
+    const POLL_COLUMNS: &'static str = "id, chat_id, question, options, multiple_answers, message_id,
+         external_poll_id, closes_at, on_close, status, created_at";
+
+    fn row_to_poll(row: &rusqlite::Row<'_>) -> rusqlite::Result<Poll> {
+        let options: String = row.get(3)?;
+        Ok(Poll {
+            id: row.get(0)?,
+            chat_id: row.get(1)?,
+            question: row.get(2)?,
+            options: serde_json::from_str(&options).unwrap_or_default(),
+            multiple_answers: row.get::<_, i32>(4)? != 0,
+            message_id: row.get(5)?,
+            external_poll_id: row.get(6)?,
+            closes_at: row.get(7)?,
+            on_close: row.get(8)?,
+            status: row.get(9)?,
+            created_at: row.get(10)?,
+        })
+    }
+
+    pub fn create_poll(&self, poll: &Poll) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let options = serde_json::to_string(&poll.options)?;
+        conn.execute(
+            "INSERT INTO polls (id, chat_id, question, options, multiple_answers, message_id,
+                external_poll_id, closes_at, on_close, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                poll.id,
+                poll.chat_id,
+                poll.question,
+                options,
+                poll.multiple_answers as i32,
+                poll.message_id,
+                poll.external_poll_id,
+                poll.closes_at,
+                poll.on_close,
+                poll.status,
+                poll.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_poll(&self, id: &str) -> Result<Option<Poll>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!("SELECT {} FROM polls WHERE id = ?1", Self::POLL_COLUMNS),
+            params![id],
+            Self::row_to_poll,
+        );
+        match result {
+            Ok(p) => Ok(Some(p)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn get_poll_by_external_id(
+        &self,
+        external_poll_id: &str,
+    ) -> Result<Option<Poll>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!(
+                "SELECT {} FROM polls WHERE external_poll_id = ?1",
+                Self::POLL_COLUMNS
+            ),
+            params![external_poll_id],
+            Self::row_to_poll,
+        );
+        match result {
+            Ok(p) => Ok(Some(p)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Polls in a chat, newest first (optionally only open ones).
+    pub fn list_polls(&self, chat_id: i64, only_open: bool) -> Result<Vec<Poll>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM polls WHERE chat_id = ?1 AND (?2 = 0 OR status = 'open')
+             ORDER BY created_at DESC",
+            Self::POLL_COLUMNS
+        ))?;
+        let polls = stmt
+            .query_map(params![chat_id, only_open as i32], Self::row_to_poll)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(polls)
+    }
+
+    /// Open polls whose closing time has passed.
+    pub fn get_due_polls(&self, now: &str) -> Result<Vec<Poll>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM polls
+             WHERE status = 'open' AND closes_at IS NOT NULL AND closes_at <= ?1",
+            Self::POLL_COLUMNS
+        ))?;
+        let polls = stmt
+            .query_map(params![now], Self::row_to_poll)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(polls)
+    }
+
+    pub fn set_poll_closes_at(&self, id: &str, closes_at: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE polls SET closes_at = ?2 WHERE id = ?1 AND status = 'open'",
+            params![id, closes_at],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Mark a poll closed. Returns false if it was already closed (someone else claimed it).
+    pub fn close_poll(&self, id: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE polls SET status = 'closed', closed_at = ?2 WHERE id = ?1 AND status = 'open'",
+            params![id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Replace a voter's choices (an empty list retracts the vote).
+    pub fn set_poll_votes(
+        &self,
+        poll_id: &str,
+        voter: &str,
+        option_indices: &[usize],
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM poll_votes WHERE poll_id = ?1 AND voter = ?2",
+            params![poll_id, voter],
+        )?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for idx in option_indices {
+            tx.execute(
+                "INSERT OR IGNORE INTO poll_votes (poll_id, voter, option_index, voted_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![poll_id, voter, *idx as i64, now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// All votes for a poll as (voter, option_index), ordered by voting time.
+    pub fn get_poll_votes(&self, poll_id: &str) -> Result<Vec<(String, usize)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT voter, option_index FROM poll_votes WHERE poll_id = ?1
+             ORDER BY voted_at, voter",
+        )?;
+        let votes = stmt
+            .query_map(params![poll_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(votes)
+    }
+
+    // --- Output filter log ---
+
+    pub fn log_filtered_output(
+        &self,
+        chat_id: i64,
+        chat_type: &str,
+        reason: &str,
+        original: &str,
+        delivered: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO filtered_outputs (chat_id, chat_type, reason, original, delivered, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                chat_id,
+                chat_type,
+                reason,
+                original,
+                delivered,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent filtered replies for a chat as (reason, original, delivered, created_at).
+    pub fn list_filtered_outputs(
+        &self,
+        chat_id: i64,
+        limit: usize,
+    ) -> Result<Vec<(String, String, String, String)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT reason, original, delivered, created_at FROM filtered_outputs
+             WHERE chat_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id, limit as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    // --- Inbound moderation quarantine ---
+
+    const QUARANTINE_COLUMNS: &'static str =
+        "id, chat_id, chat_type, sender_name, content, reasons, status, created_at";
+
+    fn row_to_quarantined(row: &rusqlite::Row<'_>) -> rusqlite::Result<QuarantinedMessage> {
+        Ok(QuarantinedMessage {
+            id: row.get(0)?,
+            chat_id: row.get(1)?,
+            chat_type: row.get(2)?,
+            sender_name: row.get(3)?,
+            content: row.get(4)?,
+            reasons: row.get(5)?,
+            status: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    }
+
+    pub fn quarantine_message(&self, entry: &QuarantinedMessage) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO quarantine (id, chat_id, chat_type, sender_name, content, reasons, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                entry.id,
+                entry.chat_id,
+                entry.chat_type,
+                entry.sender_name,
+                entry.content,
+                entry.reasons,
+                entry.status,
+                entry.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_quarantined(&self, id: &str) -> Result<Option<QuarantinedMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!(
+                "SELECT {} FROM quarantine WHERE id = ?1",
+                Self::QUARANTINE_COLUMNS
+            ),
+            params![id],
+            Self::row_to_quarantined,
+        );
+        match result {
+            Ok(q) => Ok(Some(q)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Quarantined messages with the given status, oldest first.
+    pub fn list_quarantine(
+        &self,
+        status: &str,
+        limit: usize,
+    ) -> Result<Vec<QuarantinedMessage>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM quarantine WHERE status = ?1 ORDER BY created_at LIMIT ?2",
+            Self::QUARANTINE_COLUMNS
+        ))?;
+        let entries = stmt
+            .query_map(params![status, limit as i64], Self::row_to_quarantined)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Move an entry from one status to another. Returns false if it was not in `from`
+    /// (already reviewed or released), so concurrent reviewers cannot act twice.
+    pub fn set_quarantine_status(
+        &self,
+        id: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let n = conn.execute(
+            "UPDATE quarantine SET status = ?3, reviewed_at = ?4 WHERE id = ?1 AND status = ?2",
+            params![id, from, to, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Number of messages stored from a sender in a chat since `since` (RFC 3339).
+    pub fn count_messages_from_since(
+        &self,
+        chat_id: i64,
+        sender_name: &str,
+        since: &str,
+    ) -> Result<usize, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let n: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM messages
+             WHERE chat_id = ?1 AND sender_name = ?2 AND is_from_bot = 0 AND timestamp >= ?3",
+            params![chat_id, sender_name, since],
+            |row| row.get(0),
+        )?;
+        Ok(n as usize)
+    }
+
+    /// Fold the WAL into the database file and run `f` while writes are held off, so `f` can
+    /// copy the files as a consistent snapshot.
+    pub fn with_checkpoint<T>(&self, f: impl FnOnce() -> T) -> Result<T, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        let result = f();
+        drop(conn);
+        Ok(result)
+    }
+
+    /// Write a consistent copy of the database to `path` (which must not exist yet), safe while
+    /// another process keeps writing.
+    pub fn snapshot_to(&self, path: &Path) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    // --- Chat settings ---
+
+    pub fn get_chat_setting(&self, chat_id: i64, key: &str) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT value FROM chat_settings WHERE chat_id = ?1 AND key = ?2",
+            params![chat_id, key],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(v) => Ok(Some(v)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set a per-chat setting; `None` removes it.
+    pub fn set_chat_setting(
+        &self,
+        chat_id: i64,
+        key: &str,
+        value: Option<&str>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        match value {
+            Some(value) => conn.execute(
+                "INSERT INTO chat_settings (chat_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(chat_id, key) DO UPDATE SET value = ?3, updated_at = ?4",
+                params![chat_id, key, value, chrono::Utc::now().to_rfc3339()],
+            )?,
+            None => conn.execute(
+                "DELETE FROM chat_settings WHERE chat_id = ?1 AND key = ?2",
+                params![chat_id, key],
+            )?,
+        };
+        Ok(())
+    }
+
+    pub fn list_chat_settings(&self, chat_id: i64) -> Result<Vec<(String, String)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT key, value FROM chat_settings WHERE chat_id = ?1 ORDER BY key",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Replace all settings of a chat.
+    pub fn replace_chat_settings(
+        &self,
+        chat_id: i64,
+        settings: &[(String, String)],
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM chat_settings WHERE chat_id = ?1", params![chat_id])?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for (key, value) in settings {
+            tx.execute(
+                "INSERT INTO chat_settings (chat_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                params![chat_id, key, value, now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // --- LLM usage accounting ---
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_llm_usage(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        model: &str,
+        input_tokens: u64,
+        output_tokens: u64,
+        tool_calls: u64,
+        duration_ms: u64,
+        cost_usd: Option<f64>,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO llm_usage
+                (chat_id, persona_id, model, input_tokens, output_tokens, tool_calls, duration_ms, cost_usd, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                chat_id,
+                persona_id,
+                model,
+                input_tokens as i64,
+                output_tokens as i64,
+                tool_calls as i64,
+                duration_ms as i64,
+                cost_usd,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Usage totals for a chat since `since` (RFC 3339): (turns, input tokens, output tokens, cost USD).
+    pub fn get_llm_usage_totals(
+        &self,
+        chat_id: i64,
+        since: &str,
+    ) -> Result<(u64, u64, u64, f64), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let totals = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0),
+                    COALESCE(SUM(cost_usd), 0.0)
+             FROM llm_usage WHERE chat_id = ?1 AND created_at >= ?2",
+            params![chat_id, since],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)? as u64,
+                    row.get::<_, i64>(1)? as u64,
+                    row.get::<_, i64>(2)? as u64,
+                    row.get::<_, f64>(3)?,
+                ))
+            },
+        )?;
+        Ok(totals)
+    }
+
+    // --- Turn timings ---
+
+    /// Store a turn's timings and drop those created before `prune_before`. Returns the row id.
+    pub fn record_turn_timing(
+        &self,
+        timing: &TurnTiming,
+        prune_before: &str,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO turn_timings
+                (chat_id, persona_id, channel, queue_ms, agent_ms, delivery_ms, stages, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                timing.chat_id,
+                timing.persona_id,
+                timing.channel,
+                timing.queue_ms.map(|v| v as i64),
+                timing.agent_ms as i64,
+                timing.delivery_ms.map(|v| v as i64),
+                timing.stages,
+                timing.created_at
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM turn_timings WHERE created_at < ?1",
+            params![prune_before],
+        )?;
+        Ok(id)
+    }
+
+    /// Set the delivery time on the chat's latest turn that has none yet.
+    pub fn set_turn_delivery(&self, chat_id: i64, delivery_ms: u64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE turn_timings SET delivery_ms = ?2
+             WHERE id = (SELECT MAX(id) FROM turn_timings WHERE chat_id = ?1)
+               AND delivery_ms IS NULL",
+            params![chat_id, delivery_ms as i64],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Turns since `since` (RFC 3339), slowest first by queue + agent + delivery time.
+    pub fn slowest_turns(
+        &self,
+        since: &str,
+        chat_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<TurnTiming>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, channel, queue_ms, agent_ms, delivery_ms, stages, created_at
+             FROM turn_timings
+             WHERE created_at >= ?1 AND (?2 IS NULL OR chat_id = ?2)
+             ORDER BY COALESCE(queue_ms, 0) + agent_ms + COALESCE(delivery_ms, 0) DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt
+            .query_map(params![since, chat_id, limit as i64], |row| {
+                Ok(TurnTiming {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    channel: row.get(3)?,
+                    queue_ms: row.get::<_, Option<i64>>(4)?.map(|v| v as u64),
+                    agent_ms: row.get::<_, i64>(5)? as u64,
+                    delivery_ms: row.get::<_, Option<i64>>(6)?.map(|v| v as u64),
+                    stages: row.get(7)?,
+                    created_at: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    // --- Artifacts ---
+
+    pub fn save_artifact(&self, artifact: &Artifact) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO artifacts (chat_id, persona_id, title, kind, content, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                artifact.chat_id,
+                artifact.persona_id,
+                artifact.title,
+                artifact.kind,
+                artifact.content,
+                artifact.created_at
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// The chat's artifacts, newest first, optionally of one kind and/or with `query` in the title.
+    pub fn list_artifacts(
+        &self,
+        chat_id: i64,
+        kind: Option<&str>,
+        query: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Artifact>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let pattern = query.map(|q| format!("%{}%", q.to_lowercase()));
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, title, kind, content, created_at FROM artifacts
+             WHERE chat_id = ?1 AND (?2 IS NULL OR kind = ?2)
+               AND (?3 IS NULL OR lower(title) LIKE ?3)
+             ORDER BY id DESC LIMIT ?4",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id, kind, pattern, limit as i64], Self::artifact_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn get_artifact(&self, chat_id: i64, id: i64) -> Result<Option<Artifact>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, chat_id, persona_id, title, kind, content, created_at FROM artifacts
+             WHERE chat_id = ?1 AND id = ?2",
+            params![chat_id, id],
+            Self::artifact_from_row,
+        );
+        match result {
+            Ok(a) => Ok(Some(a)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn artifact_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Artifact> {
+        Ok(Artifact {
+            id: row.get(0)?,
+            chat_id: row.get(1)?,
+            persona_id: row.get(2)?,
+            title: row.get(3)?,
+            kind: row.get(4)?,
+            content: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }
+
+    pub fn delete_artifact(&self, chat_id: i64, id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM artifacts WHERE chat_id = ?1 AND id = ?2",
+            params![chat_id, id],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // --- Todo lists ---
+
+    /// Add an item to the chat's (and persona's) list `list`, creating the list if needed.
+    pub fn add_todo(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        list: &str,
+        text: &str,
+        due: Option<&str>,
+        created_at: &str,
+    ) -> Result<i64, MicroClawError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO todo_lists (chat_id, persona_id, name, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![chat_id, persona_id, list, created_at],
+        )?;
+        let list_id: i64 = tx.query_row(
+            "SELECT id FROM todo_lists WHERE chat_id = ?1 AND persona_id = ?2 AND name = ?3",
+            params![chat_id, persona_id, list],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO todo_items (list_id, chat_id, text, due, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![list_id, chat_id, text, due, created_at],
         )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(id)
+    }
 
-        // Migrate messages
-        conn.execute_batch(
-            "CREATE TABLE messages_new (
-                id TEXT NOT NULL,
-                chat_id INTEGER NOT NULL,
-                persona_id INTEGER NOT NULL,
-                sender_name TEXT NOT NULL,
-                content TEXT NOT NULL,
-                is_from_bot INTEGER NOT NULL DEFAULT 0,
-                timestamp TEXT NOT NULL,
-                PRIMARY KEY (id, chat_id, persona_id)
-            );
-            CREATE INDEX idx_messages_new_chat_ts ON messages_new(chat_id, persona_id, timestamp);
-            INSERT INTO messages_new SELECT m.id, m.chat_id, p.id, m.sender_name, m.content, m.is_from_bot, m.timestamp
-            FROM messages m
-            JOIN personas p ON p.chat_id = m.chat_id AND p.name = 'default';
-            DROP TABLE messages;
-            ALTER TABLE messages_new RENAME TO messages;
-            CREATE INDEX IF NOT EXISTS idx_messages_chat_timestamp ON messages(chat_id, persona_id, timestamp);",
+    /// Items of the chat's lists (or of `list` only), by list, open items first, then oldest first.
+    pub fn list_todos(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        list: Option<&str>,
+        include_done: bool,
+    ) -> Result<Vec<TodoItem>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT i.id, l.name, i.text, i.due, i.done, i.created_at, i.completed_at
+             FROM todo_items i JOIN todo_lists l ON l.id = i.list_id
+             WHERE l.chat_id = ?1 AND l.persona_id = ?2 AND (?3 IS NULL OR l.name = ?3)
+               AND (?4 OR i.done = 0)
+             ORDER BY l.name, i.done, i.id",
         )?;
+        let rows = stmt
+            .query_map(params![chat_id, persona_id, list, include_done], |row| {
+                Ok(TodoItem {
+                    id: row.get(0)?,
+                    list: row.get(1)?,
+                    text: row.get(2)?,
+                    due: row.get(3)?,
+                    done: row.get::<_, i64>(4)? != 0,
+                    created_at: row.get(5)?,
+                    completed_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Mark an open item done. Returns its text, or `None` if no such open item is on the
+    /// chat's (and persona's) lists.
+    pub fn complete_todo(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        id: i64,
+        completed_at: &str,
+    ) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "UPDATE todo_items SET done = 1, completed_at = ?4
+             WHERE id = ?3 AND done = 0 AND list_id IN
+               (SELECT id FROM todo_lists WHERE chat_id = ?1 AND persona_id = ?2)
+             RETURNING text",
+            params![chat_id, persona_id, id, completed_at],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(text) => Ok(Some(text)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // --- Telegram Business ---
 
+    pub fn upsert_business_connection(
+        &self,
+        id: &str,
+        owner_user_id: i64,
+        enabled: bool,
+        can_reply: bool,
+        updated_at: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO business_connections (id, owner_user_id, enabled, can_reply, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(id) DO UPDATE SET owner_user_id = ?2, enabled = ?3, can_reply = ?4,
+                updated_at = ?5",
+            params![id, owner_user_id, enabled, can_reply, updated_at],
+        )?;
         Ok(())
     }
 
-    fn migrate_fts(conn: &Connection) -> Result<(), MicroClawError> {
-        // Create FTS5 virtual table and triggers (after all table migrations)
-        conn.execute_batch(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
-                content,
-                content='messages',
-                content_rowid='rowid'
-            );
-            CREATE TRIGGER IF NOT EXISTS messages_fts_ai AFTER INSERT ON messages BEGIN
-                INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
-            END;
-            CREATE TRIGGER IF NOT EXISTS messages_fts_bd BEFORE DELETE ON messages BEGIN
-                INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
-            END;
-            CREATE TRIGGER IF NOT EXISTS messages_fts_au AFTER UPDATE ON messages BEGIN
-                INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
-                INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
-            END;",
+    /// `(owner_user_id, enabled, can_reply)` of a connection we have heard about.
+    pub fn get_business_connection(
+        &self,
+        id: &str,
+    ) -> Result<Option<(i64, bool, bool)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT owner_user_id, enabled, can_reply FROM business_connections WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        );
+        match result {
+            Ok(c) => Ok(Some(c)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The business chat stored as `chat_id`, adding it (auto-replies off) if it is new.
+    /// Returns the chat and whether it was added.
+    pub fn ensure_business_chat(
+        &self,
+        chat: &BusinessChat,
+    ) -> Result<(BusinessChat, bool), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let added = conn.execute(
+            "INSERT OR IGNORE INTO business_chats
+                (chat_id, connection_id, telegram_chat_id, title, auto_reply, created_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+            params![
+                chat.chat_id,
+                chat.connection_id,
+                chat.telegram_chat_id,
+                chat.title,
+                chat.created_at
+            ],
+        )? > 0;
+        let stored = conn.query_row(
+            &format!("{BUSINESS_CHAT_SELECT} WHERE b.chat_id = ?1"),
+            params![chat.chat_id],
+            Self::business_chat_from_row,
         )?;
+        Ok((stored, added))
+    }
 
-        // One-time migration: populate FTS from existing messages if FTS is empty but messages has data
-        let fts_count: i64 =
-            conn.query_row("SELECT count(*) FROM messages_fts", [], |r| r.get(0))?;
-        let msg_count: i64 =
-            conn.query_row("SELECT count(*) FROM messages", [], |r| r.get(0))?;
-        if fts_count == 0 && msg_count > 0 {
-            conn.execute("INSERT INTO messages_fts(rowid, content) SELECT rowid, content FROM messages", [])?;
+    pub fn get_business_chat(&self, chat_id: i64) -> Result<Option<BusinessChat>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            &format!("{BUSINESS_CHAT_SELECT} WHERE b.chat_id = ?1"),
+            params![chat_id],
+            Self::business_chat_from_row,
+        );
+        match result {
+            Ok(c) => Ok(Some(c)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
         }
+    }
 
-        Ok(())
+    /// Business chats, oldest first; only `owner_user_id`'s when given.
+    pub fn list_business_chats(
+        &self,
+        owner_user_id: Option<i64>,
+    ) -> Result<Vec<BusinessChat>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{BUSINESS_CHAT_SELECT} WHERE ?1 IS NULL OR c.owner_user_id = ?1 ORDER BY b.id"
+        ))?;
+        let rows = stmt
+            .query_map(params![owner_user_id], Self::business_chat_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
     }
 
-    pub fn upsert_chat(
+    pub fn set_business_auto_reply(&self, id: i64, auto_reply: bool) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE business_chats SET auto_reply = ?2 WHERE id = ?1",
+            params![id, auto_reply],
+        )?;
+        Ok(updated > 0)
+    }
+
+    fn business_chat_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BusinessChat> {
+        Ok(BusinessChat {
+            id: row.get(0)?,
+            chat_id: row.get(1)?,
+            connection_id: row.get(2)?,
+            telegram_chat_id: row.get(3)?,
+            title: row.get(4)?,
+            auto_reply: row.get(5)?,
+            owner_user_id: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+            created_at: row.get(7)?,
+        })
+    }
+
+    // --- Thinking transcripts ---
+
+    /// Store a turn's thinking, keeping only the chat's newest `keep` transcripts.
+    pub fn save_thinking(
         &self,
         chat_id: i64,
-        chat_title: Option<&str>,
-        chat_type: &str,
-    ) -> Result<(), MicroClawError> {
+        persona_id: i64,
+        content: &str,
+        keep: usize,
+    ) -> Result<i64, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let now = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "INSERT INTO chats (chat_id, chat_title, chat_type, last_message_time)
-             VALUES (?1, ?2, ?3, ?4)
-             ON CONFLICT(chat_id) DO UPDATE SET
-                chat_title = COALESCE(?2, chat_title),
-                chat_type = ?3,
-                last_message_time = ?4",
-            params![chat_id, chat_title, chat_type, now],
+            "INSERT INTO thinking_transcripts (chat_id, persona_id, content, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![chat_id, persona_id, content, chrono::Utc::now().to_rfc3339()],
         )?;
-        Ok(())
+        let id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM thinking_transcripts WHERE chat_id = ?1 AND id NOT IN
+             (SELECT id FROM thinking_transcripts WHERE chat_id = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![chat_id, keep as i64],
+        )?;
+        Ok(id)
     }
 
-    pub fn store_message(&self, msg: &StoredMessage) -> Result<(), MicroClawError> {
+    /// The chat's newest thinking transcripts as `(content, created_at)`, newest first.
+    pub fn recent_thinking(
+        &self,
+        chat_id: i64,
+        limit: usize,
+    ) -> Result<Vec<(String, String)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT content, created_at FROM thinking_transcripts
+             WHERE chat_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id, limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Timestamp of the chat's newest message not from the bot.
+    pub fn last_user_message_time(&self, chat_id: i64) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let ts = conn.query_row(
+            "SELECT MAX(timestamp) FROM messages WHERE chat_id = ?1 AND is_from_bot = 0",
+            params![chat_id],
+            |row| row.get::<_, Option<String>>(0),
+        )?;
+        Ok(ts)
+    }
+
+    /// Whether the bot has ever replied in the chat.
+    pub fn has_bot_messages(&self, chat_id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let found = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM messages WHERE chat_id = ?1 AND is_from_bot = 1)",
+            params![chat_id],
+            |row| row.get::<_, bool>(0),
+        )?;
+        Ok(found)
+    }
+
+    // --- Experiments ---
+
+    pub fn record_experiment_run(&self, run: &ExperimentRun) -> Result<i64, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO messages (id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO experiment_runs
+                (experiment, variant, chat_id, persona_id, model, iterations, tool_calls,
+                 input_tokens, output_tokens, duration_ms, outcome, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             params![
-                msg.id,
-                msg.chat_id,
-                msg.persona_id,
-                msg.sender_name,
-                msg.content,
-                msg.is_from_bot as i32,
-                msg.timestamp,
+                run.experiment,
+                run.variant,
+                run.chat_id,
+                run.persona_id,
+                run.model,
+                run.iterations as i64,
+                run.tool_calls as i64,
+                run.input_tokens as i64,
+                run.output_tokens as i64,
+                run.duration_ms as i64,
+                run.outcome,
+                chrono::Utc::now().to_rfc3339()
             ],
         )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Id of the most recent experiment run in a chat created at or after `since` (RFC 3339).
+    pub fn latest_experiment_run(&self, chat_id: i64, since: &str) -> Result<Option<i64>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id FROM experiment_runs WHERE chat_id = ?1 AND created_at >= ?2
+             ORDER BY created_at DESC, id DESC LIMIT 1",
+            params![chat_id, since],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set user feedback on a run: 1 positive, -1 negative, 0 cleared.
+    pub fn set_experiment_feedback(&self, id: i64, feedback: i64) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE experiment_runs SET feedback = ?2 WHERE id = ?1",
+            params![id, feedback.signum()],
+        )?;
         Ok(())
     }
 
-    pub fn get_recent_messages(
+    pub fn mark_experiment_retried(&self, id: i64) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE experiment_runs SET retried = 1 WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    /// Experiment names with their run counts, most recently active first.
+    pub fn list_experiments(&self) -> Result<Vec<(String, u64)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT experiment, COUNT(*) FROM experiment_runs
+             GROUP BY experiment ORDER BY MAX(created_at) DESC",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn experiment_variant_stats(
         &self,
-        chat_id: i64,
-        persona_id: i64,
-        limit: usize,
-    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        experiment: &str,
+    ) -> Result<Vec<ExperimentVariantStats>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
-             FROM messages
-             WHERE chat_id = ?1 AND persona_id = ?2
-             ORDER BY timestamp DESC
-             LIMIT ?3",
+            "SELECT variant, COUNT(*), AVG(iterations), AVG(tool_calls), AVG(duration_ms),
+                    AVG(output_tokens), SUM(outcome != 'ok'), SUM(feedback > 0), SUM(feedback < 0),
+                    SUM(retried)
+             FROM experiment_runs WHERE experiment = ?1
+             GROUP BY variant ORDER BY variant",
         )?;
+        let rows = stmt
+            .query_map(params![experiment], |row| {
+                Ok(ExperimentVariantStats {
+                    variant: row.get(0)?,
+                    runs: row.get::<_, i64>(1)? as u64,
+                    avg_iterations: row.get(2)?,
+                    avg_tool_calls: row.get(3)?,
+                    avg_duration_ms: row.get(4)?,
+                    avg_output_tokens: row.get(5)?,
+                    non_ok: row.get::<_, i64>(6)? as u64,
+                    positive: row.get::<_, i64>(7)? as u64,
+                    negative: row.get::<_, i64>(8)? as u64,
+                    retried: row.get::<_, i64>(9)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
 
-        let messages = stmt
-            .query_map(params![chat_id, persona_id, limit as i64], |row| {
-                Ok(StoredMessage {
-                    id: row.get(0)?,
+    // --- Response feedback ---
+
+    pub fn record_response_feedback(&self, feedback: &ResponseFeedback) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO response_feedback (chat_id, persona_id, score, emoji, prompt, reply, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                feedback.chat_id,
+                feedback.persona_id,
+                feedback.score.signum(),
+                feedback.emoji,
+                feedback.prompt,
+                feedback.reply,
+                feedback.created_at
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Feedback created at or after `since` (RFC 3339), oldest first.
+    pub fn list_response_feedback(&self, since: &str) -> Result<Vec<ResponseFeedback>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT chat_id, persona_id, score, emoji, prompt, reply, created_at
+             FROM response_feedback WHERE created_at >= ?1
+             ORDER BY created_at, id",
+        )?;
+        let rows = stmt
+            .query_map(params![since], |row| {
+                Ok(ResponseFeedback {
+                    chat_id: row.get(0)?,
+                    persona_id: row.get(1)?,
+                    score: row.get(2)?,
+                    emoji: row.get(3)?,
+                    prompt: row.get(4)?,
+                    reply: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Failed scheduled task runs, failed background jobs and non-ok experiment turns since
+    /// `since` (RFC 3339), oldest first.
+    pub fn list_failed_runs(&self, since: &str) -> Result<Vec<FailedRun>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT 'scheduled_task', l.chat_id,
+                    'task #' || l.task_id || COALESCE(': ' || t.prompt, ''),
+                    COALESCE(l.result_summary, ''),
+                    l.finished_at
+             FROM task_run_logs l LEFT JOIN scheduled_tasks t ON t.id = l.task_id
+             WHERE l.success = 0 AND l.finished_at >= ?1
+             UNION ALL
+             SELECT 'background_job', chat_id, kind || ': ' || description, COALESCE(error, ''),
+                    COALESCE(finished_at, created_at)
+             FROM background_jobs WHERE status = 'failed' AND created_at >= ?1
+             UNION ALL
+             SELECT 'agent_turn', chat_id, experiment || '/' || variant, outcome, created_at
+             FROM experiment_runs WHERE outcome != 'ok' AND created_at >= ?1
+             ORDER BY 5",
+        )?;
+        let rows = stmt
+            .query_map(params![since], |row| {
+                Ok(FailedRun {
+                    source: row.get(0)?,
                     chat_id: row.get(1)?,
-                    persona_id: row.get(2)?,
-                    sender_name: row.get(3)?,
-                    content: row.get(4)?,
-                    is_from_bot: row.get::<_, i32>(5)? != 0,
-                    timestamp: row.get(6)?,
+                    label: row.get(2)?,
+                    error: row.get(3)?,
+                    created_at: row.get(4)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
 
-        // Reverse so oldest first
-        let mut messages = messages;
-        messages.reverse();
-        Ok(messages)
+    pub fn save_feedback_report(&self, report: &str) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO feedback_reports (report, created_at) VALUES (?1, ?2)",
+            params![report, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// When the last feedback report was saved.
+    pub fn last_feedback_report_at(&self) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let at = conn.query_row("SELECT MAX(created_at) FROM feedback_reports", [], |row| {
+            row.get(0)
+        })?;
+        Ok(at)
+    }
+
+    // --- Session snapshots ---
+
+    pub fn create_session_snapshot(&self, snapshot: &SessionSnapshot) -> Result<(), MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO session_snapshots (id, chat_id, persona_id, label, data, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                snapshot.id,
+                snapshot.chat_id,
+                snapshot.persona_id,
+                snapshot.label,
+                snapshot.data,
+                snapshot.created_at
+            ],
+        )?;
+        Ok(())
     }
 
-    pub fn get_all_messages(
+    /// Snapshots for a chat/persona, newest first, without their payload (`data` is empty).
+    pub fn list_session_snapshots(
         &self,
         chat_id: i64,
         persona_id: i64,
-    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        limit: usize,
+    ) -> Result<Vec<SessionSnapshot>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
-             FROM messages
+            "SELECT id, chat_id, persona_id, label, created_at FROM session_snapshots
              WHERE chat_id = ?1 AND persona_id = ?2
-             ORDER BY timestamp ASC",
+             ORDER BY created_at DESC LIMIT ?3",
         )?;
-        let messages = stmt
-            .query_map(params![chat_id, persona_id], |row| {
-                Ok(StoredMessage {
+        let rows = stmt
+            .query_map(params![chat_id, persona_id, limit as i64], |row| {
+                Ok(SessionSnapshot {
                     id: row.get(0)?,
                     chat_id: row.get(1)?,
                     persona_id: row.get(2)?,
-                    sender_name: row.get(3)?,
-                    content: row.get(4)?,
-                    is_from_bot: row.get::<_, i32>(5)? != 0,
-                    timestamp: row.get(6)?,
+                    label: row.get(3)?,
+                    data: String::new(),
+                    created_at: row.get(4)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(messages)
+        Ok(rows)
     }
 
-    pub fn get_chats_by_type(
+    pub fn get_session_snapshot(&self, id: &str) -> Result<Option<SessionSnapshot>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, chat_id, persona_id, label, data, created_at FROM session_snapshots WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(SessionSnapshot {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    persona_id: row.get(2)?,
+                    label: row.get(3)?,
+                    data: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            },
+        );
+        match result {
+            Ok(s) => Ok(Some(s)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace a chat/persona's stored message history.
+    pub fn replace_chat_messages(
         &self,
-        chat_type: &str,
-        limit: usize,
-    ) -> Result<Vec<ChatSummary>, MicroClawError> {
+        chat_id: i64,
+        persona_id: i64,
+        messages: &[StoredMessage],
+    ) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT
-                c.chat_id,
-                c.chat_title,
-                c.chat_type,
-                c.last_message_time,
-                (
-                    SELECT m.content
-                    FROM messages m
-                    WHERE m.chat_id = c.chat_id
-                    ORDER BY m.timestamp DESC
-                    LIMIT 1
-                ) AS last_message_preview
-             FROM chats c
-             WHERE c.chat_type = ?1
-             ORDER BY c.last_message_time DESC
-             LIMIT ?2",
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM messages WHERE chat_id = ?1 AND persona_id = ?2",
+            params![chat_id, persona_id],
         )?;
-        let chats = stmt
-            .query_map(params![chat_type, limit as i64], |row| {
-                Ok(ChatSummary {
-                    chat_id: row.get(0)?,
-                    chat_title: row.get(1)?,
-                    chat_type: row.get(2)?,
-                    last_message_time: row.get(3)?,
-                    last_message_preview: row.get(4)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(chats)
+        for m in messages {
+            tx.execute(
+                "INSERT INTO messages (id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    m.id,
+                    chat_id,
+                    persona_id,
+                    m.sender_name,
+                    m.content,
+                    m.is_from_bot as i32,
+                    m.timestamp
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
     }
 
-    pub fn get_recent_chats(&self, limit: usize) -> Result<Vec<ChatSummary>, MicroClawError> {
+    // --- Skill versions ---
+
+    pub fn record_skill_version(
+        &self,
+        skill_name: &str,
+        version_path: &str,
+        reason: &str,
+        chat_id: Option<i64>,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO skill_versions (skill_name, version_path, reason, chat_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                skill_name,
+                version_path,
+                reason,
+                chat_id,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Saved versions of a skill, newest first.
+    pub fn list_skill_versions(
+        &self,
+        skill_name: &str,
+        limit: usize,
+    ) -> Result<Vec<SkillVersion>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT
-                c.chat_id,
-                c.chat_title,
-                c.chat_type,
-                c.last_message_time,
-                (
-                    SELECT m.content
-                    FROM messages m
-                    WHERE m.chat_id = c.chat_id
-                    ORDER BY m.timestamp DESC
-                    LIMIT 1
-                ) AS last_message_preview
-             FROM chats c
-             ORDER BY c.last_message_time DESC
-             LIMIT ?1",
+            "SELECT id, skill_name, version_path, reason, chat_id, created_at FROM skill_versions
+             WHERE skill_name = ?1 ORDER BY id DESC LIMIT ?2",
         )?;
-        let chats = stmt
-            .query_map(params![limit as i64], |row| {
-                Ok(ChatSummary {
-                    chat_id: row.get(0)?,
-                    chat_title: row.get(1)?,
-                    chat_type: row.get(2)?,
-                    last_message_time: row.get(3)?,
-                    last_message_preview: row.get(4)?,
+        let rows = stmt
+            .query_map(params![skill_name, limit as i64], |row| {
+                Ok(SkillVersion {
+                    id: row.get(0)?,
+                    skill_name: row.get(1)?,
+                    version_path: row.get(2)?,
+                    reason: row.get(3)?,
+                    chat_id: row.get(4)?,
+                    created_at: row.get(5)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(chats)
+        Ok(rows)
     }
 
-    pub fn get_chat_type(&self, chat_id: i64) -> Result<Option<String>, MicroClawError> {
+    pub fn get_skill_version(&self, id: i64) -> Result<Option<SkillVersion>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let result = conn.query_row(
-            "SELECT chat_type FROM chats WHERE chat_id = ?1",
-            params![chat_id],
-            |row| row.get::<_, String>(0),
+            "SELECT id, skill_name, version_path, reason, chat_id, created_at FROM skill_versions
+             WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(SkillVersion {
+                    id: row.get(0)?,
+                    skill_name: row.get(1)?,
+                    version_path: row.get(2)?,
+                    reason: row.get(3)?,
+                    chat_id: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            },
         );
         match result {
             Ok(v) => Ok(Some(v)),
@@ -560,592 +4869,671 @@ impl Database {
         }
     }
 
-    /// Get messages since the bot's last response in this chat/persona.
-    /// Falls back to `fallback_limit` most recent messages if bot never responded.
-    pub fn get_messages_since_last_bot_response(
+    // --- Social snapshots ---
+
+    /// Store one poll of a feed; all rows share `captured_at`.
+    pub fn insert_social_snapshots(
         &self,
+        platform: &str,
         chat_id: i64,
-        persona_id: i64,
-        max: usize,
-        fallback: usize,
-    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        posts: &[SocialPostSnapshot],
+        captured_at: &str,
+    ) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
-
-        // Find timestamp of last bot message
-        let last_bot_ts: Option<String> = conn
-            .query_row(
-                "SELECT timestamp FROM messages
-                 WHERE chat_id = ?1 AND persona_id = ?2 AND is_from_bot = 1
-                 ORDER BY timestamp DESC LIMIT 1",
-                params![chat_id, persona_id],
-                |row| row.get(0),
-            )
-            .ok();
-
-        let mut messages = if let Some(ts) = last_bot_ts {
-            let mut stmt = conn.prepare(
-                "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
-                 FROM messages
-                 WHERE chat_id = ?1 AND persona_id = ?2 AND timestamp >= ?3
-                 ORDER BY timestamp DESC
-                 LIMIT ?4",
-            )?;
-            let rows = stmt
-                .query_map(params![chat_id, persona_id, ts, max as i64], |row| {
-                    Ok(StoredMessage {
-                        id: row.get(0)?,
-                        chat_id: row.get(1)?,
-                        persona_id: row.get(2)?,
-                        sender_name: row.get(3)?,
-                        content: row.get(4)?,
-                        is_from_bot: row.get::<_, i32>(5)? != 0,
-                        timestamp: row.get(6)?,
-                    })
-                })?
-                .collect::<Result<Vec<_>, _>>()?;
-            rows
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT id, chat_id, persona_id, sender_name, content, is_from_bot, timestamp
-                 FROM messages
-                 WHERE chat_id = ?1 AND persona_id = ?2
-                 ORDER BY timestamp DESC
-                 LIMIT ?3",
+        let tx = conn.unchecked_transaction()?;
+        for p in posts {
+            tx.execute(
+                "INSERT INTO social_snapshots
+                    (platform, chat_id, post_id, title, url, views, likes, comments, shares, captured_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    platform,
+                    chat_id,
+                    p.post_id,
+                    p.title,
+                    p.url,
+                    p.views,
+                    p.likes,
+                    p.comments,
+                    p.shares,
+                    captured_at
+                ],
             )?;
-            let rows = stmt
-                .query_map(params![chat_id, persona_id, fallback as i64], |row| {
-                    Ok(StoredMessage {
-                        id: row.get(0)?,
-                        chat_id: row.get(1)?,
-                        persona_id: row.get(2)?,
-                        sender_name: row.get(3)?,
-                        content: row.get(4)?,
-                        is_from_bot: row.get::<_, i32>(5)? != 0,
-                        timestamp: row.get(6)?,
-                    })
-                })?
-                .collect::<Result<Vec<_>, _>>()?;
-            rows
-        };
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
-        messages.reverse();
-        Ok(messages)
+    /// Posts from the most recent poll of a feed (empty if it was never polled).
+    pub fn latest_social_snapshots(
+        &self,
+        platform: &str,
+        chat_id: i64,
+    ) -> Result<Vec<SocialPostSnapshot>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT post_id, title, url, views, likes, comments, shares FROM social_snapshots
+             WHERE platform = ?1 AND chat_id = ?2
+               AND captured_at = (SELECT MAX(captured_at) FROM social_snapshots
+                                  WHERE platform = ?1 AND chat_id = ?2)
+             ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![platform, chat_id], |row| {
+                Ok(SocialPostSnapshot {
+                    post_id: row.get(0)?,
+                    title: row.get(1)?,
+                    url: row.get(2)?,
+                    views: row.get(3)?,
+                    likes: row.get(4)?,
+                    comments: row.get(5)?,
+                    shares: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
     }
 
-    // --- Scheduled tasks ---
+    /// All post snapshots of a chat captured in `[since, until]`, oldest first.
+    pub fn social_snapshots_between(
+        &self,
+        chat_id: i64,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<SocialSnapshotRow>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT platform, captured_at, post_id, title, url, views, likes, comments, shares
+             FROM social_snapshots
+             WHERE chat_id = ?1 AND captured_at >= ?2 AND captured_at <= ?3
+             ORDER BY captured_at, id",
+        )?;
+        let rows = stmt
+            .query_map(params![chat_id, since, until], |row| {
+                Ok(SocialSnapshotRow {
+                    platform: row.get(0)?,
+                    captured_at: row.get(1)?,
+                    post: SocialPostSnapshot {
+                        post_id: row.get(2)?,
+                        title: row.get(3)?,
+                        url: row.get(4)?,
+                        views: row.get(5)?,
+                        likes: row.get(6)?,
+                        comments: row.get(7)?,
+                        shares: row.get(8)?,
+                    },
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
 
-    pub fn create_scheduled_task(
+    pub fn insert_social_follower_count(
         &self,
+        platform: &str,
         chat_id: i64,
-        prompt: &str,
-        schedule_type: &str,
-        schedule_value: &str,
-        next_run: &str,
-    ) -> Result<i64, MicroClawError> {
+        followers: i64,
+        captured_at: &str,
+    ) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let now = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "INSERT INTO scheduled_tasks (chat_id, prompt, schedule_type, schedule_value, next_run, status, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6)",
-            params![chat_id, prompt, schedule_type, schedule_value, next_run, now],
+            "INSERT INTO social_follower_counts (platform, chat_id, followers, captured_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![platform, chat_id, followers, captured_at],
         )?;
-        Ok(conn.last_insert_rowid())
+        Ok(())
     }
 
-    pub fn get_due_tasks(&self, now: &str) -> Result<Vec<ScheduledTask>, MicroClawError> {
+    /// Follower counts of a chat's accounts captured in `[since, until]`, oldest first.
+    pub fn social_follower_counts_between(
+        &self,
+        chat_id: i64,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<SocialFollowerCount>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
-             FROM scheduled_tasks
-             WHERE status = 'active' AND next_run <= ?1",
+            "SELECT platform, followers, captured_at FROM social_follower_counts
+             WHERE chat_id = ?1 AND captured_at >= ?2 AND captured_at <= ?3
+             ORDER BY captured_at, id",
         )?;
-        let tasks = stmt
-            .query_map(params![now], |row| {
-                Ok(ScheduledTask {
-                    id: row.get(0)?,
-                    chat_id: row.get(1)?,
-                    prompt: row.get(2)?,
-                    schedule_type: row.get(3)?,
-                    schedule_value: row.get(4)?,
-                    next_run: row.get(5)?,
-                    last_run: row.get(6)?,
-                    status: row.get(7)?,
-                    created_at: row.get(8)?,
+        let rows = stmt
+            .query_map(params![chat_id, since, until], |row| {
+                Ok(SocialFollowerCount {
+                    platform: row.get(0)?,
+                    followers: row.get(1)?,
+                    captured_at: row.get(2)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(tasks)
+        Ok(rows)
+    }
+
+    // --- Background jobs ---
+
+    pub fn create_background_job(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        kind: &str,
+        description: &str,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO background_jobs (chat_id, persona_id, kind, description, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, 'running', ?5)",
+            params![
+                chat_id,
+                persona_id,
+                kind,
+                description,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
     }
 
-    pub fn get_all_active_tasks(&self) -> Result<Vec<ScheduledTask>, MicroClawError> {
+    /// Append to a job's output, keeping only the last `max_chars` characters.
+    pub fn append_background_job_output(
+        &self,
+        id: i64,
+        text: &str,
+        max_chars: usize,
+    ) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
-             FROM scheduled_tasks
-             WHERE status IN ('active', 'paused')
-             ORDER BY id",
+        conn.execute(
+            "UPDATE background_jobs SET output = substr(output || ?2, -?3) WHERE id = ?1",
+            params![id, text, max_chars as i64],
         )?;
-        let tasks = stmt
-            .query_map([], |row| {
-                Ok(ScheduledTask {
-                    id: row.get(0)?,
-                    chat_id: row.get(1)?,
-                    prompt: row.get(2)?,
-                    schedule_type: row.get(3)?,
-                    schedule_value: row.get(4)?,
-                    next_run: row.get(5)?,
-                    last_run: row.get(6)?,
-                    status: row.get(7)?,
-                    created_at: row.get(8)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(tasks)
+        Ok(())
     }
 
-    /// All scheduled tasks for /schedule and list_scheduled_tasks: active, paused, and completed (all chats/personas).
-    pub fn get_all_scheduled_tasks_for_display(&self) -> Result<Vec<ScheduledTask>, MicroClawError> {
+    /// Move a running job to a final status. Returns false when the job had already finished
+    /// (e.g. it was cancelled while completing).
+    pub fn finish_background_job(
+        &self,
+        id: i64,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
-             FROM scheduled_tasks
-             WHERE status IN ('active', 'paused', 'completed')
-             ORDER BY id",
+        let n = conn.execute(
+            "UPDATE background_jobs SET status = ?2, error = ?3, finished_at = ?4
+             WHERE id = ?1 AND status = 'running'",
+            params![id, status, error, chrono::Utc::now().to_rfc3339()],
         )?;
-        let tasks = stmt
-            .query_map([], |row| {
-                Ok(ScheduledTask {
-                    id: row.get(0)?,
-                    chat_id: row.get(1)?,
-                    prompt: row.get(2)?,
-                    schedule_type: row.get(3)?,
-                    schedule_value: row.get(4)?,
-                    next_run: row.get(5)?,
-                    last_run: row.get(6)?,
-                    status: row.get(7)?,
-                    created_at: row.get(8)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(tasks)
+        Ok(n > 0)
     }
 
-    pub fn get_tasks_for_chat(&self, chat_id: i64) -> Result<Vec<ScheduledTask>, MicroClawError> {
+    /// Mark jobs left running by a previous process as failed. Returns how many were marked.
+    pub fn fail_interrupted_background_jobs(&self) -> Result<usize, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
-             FROM scheduled_tasks
-             WHERE chat_id = ?1 AND status IN ('active', 'paused')
-             ORDER BY id",
+        let n = conn.execute(
+            "UPDATE background_jobs SET status = 'failed', error = 'Interrupted by a restart',
+             finished_at = ?1 WHERE status = 'running'",
+            params![chrono::Utc::now().to_rfc3339()],
         )?;
-        let tasks = stmt
-            .query_map(params![chat_id], |row| {
-                Ok(ScheduledTask {
-                    id: row.get(0)?,
-                    chat_id: row.get(1)?,
-                    prompt: row.get(2)?,
-                    schedule_type: row.get(3)?,
-                    schedule_value: row.get(4)?,
-                    next_run: row.get(5)?,
-                    last_run: row.get(6)?,
-                    status: row.get(7)?,
-                    created_at: row.get(8)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(tasks)
+        Ok(n)
     }
 
-    pub fn get_task_by_id(&self, task_id: i64) -> Result<Option<ScheduledTask>, MicroClawError> {
+    pub fn get_background_job(&self, id: i64) -> Result<Option<BackgroundJob>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let result = conn.query_row(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
-             FROM scheduled_tasks
-             WHERE id = ?1",
-            params![task_id],
-            |row| {
-                Ok(ScheduledTask {
-                    id: row.get(0)?,
-                    chat_id: row.get(1)?,
-                    prompt: row.get(2)?,
-                    schedule_type: row.get(3)?,
-                    schedule_value: row.get(4)?,
-                    next_run: row.get(5)?,
-                    last_run: row.get(6)?,
-                    status: row.get(7)?,
-                    created_at: row.get(8)?,
-                })
-            },
+            "SELECT id, chat_id, persona_id, kind, description, status, output, error, created_at,
+                    finished_at
+             FROM background_jobs WHERE id = ?1",
+            params![id],
+            Self::background_job_from_row,
         );
         match result {
-            Ok(task) => Ok(Some(task)),
+            Ok(job) => Ok(Some(job)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 
-    pub fn update_task_status(&self, task_id: i64, status: &str) -> Result<bool, MicroClawError> {
+    /// Jobs of one chat (or all chats), newest first.
+    pub fn list_background_jobs(
+        &self,
+        chat_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<BackgroundJob>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let rows = conn.execute(
-            "UPDATE scheduled_tasks SET status = ?1 WHERE id = ?2",
-            params![status, task_id],
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, persona_id, kind, description, status, output, error, created_at,
+                    finished_at
+             FROM background_jobs WHERE ?1 IS NULL OR chat_id = ?1
+             ORDER BY id DESC LIMIT ?2",
         )?;
-        Ok(rows > 0)
+        let rows = stmt
+            .query_map(
+                params![chat_id, limit as i64],
+                Self::background_job_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
     }
 
-    pub fn update_task_after_run(
-        &self,
-        task_id: i64,
-        last_run: &str,
-        next_run: Option<&str>,
-    ) -> Result<(), MicroClawError> {
-        let conn = self.conn.lock().unwrap();
-        match next_run {
-            Some(next) => {
-                conn.execute(
-                    "UPDATE scheduled_tasks SET last_run = ?1, next_run = ?2 WHERE id = ?3",
-                    params![last_run, next, task_id],
-                )?;
-            }
-            None => {
-                // One-shot task, mark completed
-                conn.execute(
-                    "UPDATE scheduled_tasks SET last_run = ?1, status = 'completed' WHERE id = ?2",
-                    params![last_run, task_id],
-                )?;
-            }
-        }
-        Ok(())
+    fn background_job_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BackgroundJob> {
+        Ok(BackgroundJob {
+            id: row.get(0)?,
+            chat_id: row.get(1)?,
+            persona_id: row.get(2)?,
+            kind: row.get(3)?,
+            description: row.get(4)?,
+            status: row.get(5)?,
+            output: row.get(6)?,
+            error: row.get(7)?,
+            created_at: row.get(8)?,
+            finished_at: row.get(9)?,
+        })
     }
 
-    // --- Task run logs ---
+    // --- Per-chat environment variables ---
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn log_task_run(
-        &self,
-        task_id: i64,
-        chat_id: i64,
-        started_at: &str,
-        finished_at: &str,
-        duration_ms: i64,
-        success: bool,
-        result_summary: Option<&str>,
-    ) -> Result<i64, MicroClawError> {
+    pub fn set_chat_env(&self, chat_id: i64, key: &str, value: &str) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO task_run_logs (task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                task_id,
-                chat_id,
-                started_at,
-                finished_at,
-                duration_ms,
-                success as i32,
-                result_summary,
-            ],
+            "INSERT INTO chat_env (chat_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(chat_id, key) DO UPDATE SET value = ?3, updated_at = ?4",
+            params![chat_id, key, value, chrono::Utc::now().to_rfc3339()],
         )?;
-        Ok(conn.last_insert_rowid())
+        Ok(())
     }
 
-    pub fn get_task_run_logs(
-        &self,
-        task_id: i64,
-        limit: usize,
-    ) -> Result<Vec<TaskRunLog>, MicroClawError> {
+    /// Remove one variable. Returns false when it was not set.
+    pub fn delete_chat_env(&self, chat_id: i64, key: &str) -> Result<bool, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary
-             FROM task_run_logs
-             WHERE task_id = ?1
-             ORDER BY id DESC
-             LIMIT ?2",
+        let n = conn.execute(
+            "DELETE FROM chat_env WHERE chat_id = ?1 AND key = ?2",
+            params![chat_id, key],
         )?;
-        let logs = stmt
-            .query_map(params![task_id, limit as i64], |row| {
-                Ok(TaskRunLog {
-                    id: row.get(0)?,
-                    task_id: row.get(1)?,
-                    chat_id: row.get(2)?,
-                    started_at: row.get(3)?,
-                    finished_at: row.get(4)?,
-                    duration_ms: row.get(5)?,
-                    success: row.get::<_, i32>(6)? != 0,
-                    result_summary: row.get(7)?,
-                })
-            })?
+        Ok(n > 0)
+    }
+
+    /// A chat's variables as (key, value), sorted by key.
+    pub fn get_chat_env(&self, chat_id: i64) -> Result<Vec<(String, String)>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT key, value FROM chat_env WHERE chat_id = ?1 ORDER BY key")?;
+        let rows = stmt
+            .query_map(params![chat_id], |row| Ok((row.get(0)?, row.get(1)?)))?
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(logs)
+        Ok(rows)
     }
 
-    // --- Cursor agent runs ---
+    // --- Code reviews ---
 
-    pub fn insert_cursor_agent_run(
+    pub fn create_code_review(
         &self,
         chat_id: i64,
-        channel: &str,
+        repo_dir: &str,
+        base_commit: Option<&str>,
         prompt_preview: &str,
-        workdir: Option<&str>,
-        started_at: &str,
-        finished_at: &str,
-        success: bool,
-        exit_code: Option<i32>,
-        output_preview: Option<&str>,
-        output_path: Option<&str>,
     ) -> Result<i64, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO cursor_agent_runs (chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT INTO code_reviews (chat_id, repo_dir, base_commit, prompt_preview, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
             params![
                 chat_id,
-                channel,
+                repo_dir,
+                base_commit,
                 prompt_preview,
-                workdir,
-                started_at,
-                finished_at,
-                success as i32,
-                exit_code,
-                output_preview,
-                output_path,
+                chrono::Utc::now().to_rfc3339()
             ],
         )?;
         Ok(conn.last_insert_rowid())
     }
 
-    /// Get recent cursor-agent runs, optionally filtered by chat_id. Ordered by finished_at DESC.
-    pub fn get_cursor_agent_runs(
-        &self,
-        chat_id: Option<i64>,
-        limit: usize,
-    ) -> Result<Vec<CursorAgentRun>, MicroClawError> {
+    pub fn get_code_review(&self, id: i64) -> Result<Option<CodeReview>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let runs: Vec<CursorAgentRun> = match chat_id {
-            Some(cid) => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path
-                     FROM cursor_agent_runs WHERE chat_id = ?1 ORDER BY finished_at DESC LIMIT ?2",
-                )?;
-                let rows = stmt.query_map(params![cid, limit as i64], |row| {
-                    Ok(CursorAgentRun {
-                        id: row.get(0)?,
-                        chat_id: row.get(1)?,
-                        channel: row.get(2)?,
-                        prompt_preview: row.get(3)?,
-                        workdir: row.get(4)?,
-                        started_at: row.get(5)?,
-                        finished_at: row.get(6)?,
-                        success: row.get::<_, i32>(7)? != 0,
-                        exit_code: row.get(8)?,
-                        output_preview: row.get(9)?,
-                        output_path: row.get(10)?,
-                    })
-                })?;
-                rows.collect::<Result<Vec<_>, _>>()?
-            }
-            None => {
-                let mut stmt = conn.prepare(
-                    "SELECT id, chat_id, channel, prompt_preview, workdir, started_at, finished_at, success, exit_code, output_preview, output_path
-                     FROM cursor_agent_runs ORDER BY finished_at DESC LIMIT ?1",
-                )?;
-                let rows = stmt.query_map(params![limit as i64], |row| {
-                    Ok(CursorAgentRun {
-                        id: row.get(0)?,
-                        chat_id: row.get(1)?,
-                        channel: row.get(2)?,
-                        prompt_preview: row.get(3)?,
-                        workdir: row.get(4)?,
-                        started_at: row.get(5)?,
-                        finished_at: row.get(6)?,
-                        success: row.get::<_, i32>(7)? != 0,
-                        exit_code: row.get(8)?,
-                        output_preview: row.get(9)?,
-                        output_path: row.get(10)?,
-                    })
-                })?;
-                rows.collect::<Result<Vec<_>, _>>()?
-            }
-        };
-        Ok(runs)
+        let result = conn.query_row(
+            "SELECT id, chat_id, repo_dir, base_commit, prompt_preview, status, created_at
+             FROM code_reviews WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(CodeReview {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    repo_dir: row.get(2)?,
+                    base_commit: row.get(3)?,
+                    prompt_preview: row.get(4)?,
+                    status: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            },
+        );
+        match result {
+            Ok(r) => Ok(Some(r)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
-    #[allow(dead_code)]
-    pub fn delete_task(&self, task_id: i64) -> Result<bool, MicroClawError> {
+    /// Move a pending review to `status`. Returns false when it was already resolved.
+    pub fn resolve_code_review(&self, id: i64, status: &str) -> Result<bool, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let rows = conn.execute(
-            "DELETE FROM scheduled_tasks WHERE id = ?1",
-            params![task_id],
+        let n = conn.execute(
+            "UPDATE code_reviews SET status = ?2 WHERE id = ?1 AND status = 'pending'",
+            params![id, status],
         )?;
-        Ok(rows > 0)
+        Ok(n > 0)
     }
 
-    // --- Sessions ---
+    // --- Projects ---
 
-    pub fn save_session(
+    /// Register a project. Returns false when the name is taken.
+    pub fn insert_project(
         &self,
+        name: &str,
+        path: &str,
+        template: &str,
         chat_id: i64,
-        persona_id: i64,
-        messages_json: &str,
-    ) -> Result<(), MicroClawError> {
+    ) -> Result<bool, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let now = chrono::Utc::now().to_rfc3339();
-        conn.execute(
-            "INSERT INTO sessions (chat_id, persona_id, messages_json, updated_at)
-             VALUES (?1, ?2, ?3, ?4)
-             ON CONFLICT(chat_id, persona_id) DO UPDATE SET
-                messages_json = ?3,
-                updated_at = ?4",
-            params![chat_id, persona_id, messages_json, now],
+        let n = conn.execute(
+            "INSERT OR IGNORE INTO projects (name, path, template, chat_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                name,
+                path,
+                template,
+                chat_id,
+                chrono::Utc::now().to_rfc3339()
+            ],
         )?;
-        Ok(())
+        Ok(n > 0)
     }
 
-    pub fn load_session(
-        &self,
-        chat_id: i64,
-        persona_id: i64,
-    ) -> Result<Option<(String, String)>, MicroClawError> {
+    pub fn get_project(&self, name: &str) -> Result<Option<Project>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
         let result = conn.query_row(
-            "SELECT messages_json, updated_at FROM sessions WHERE chat_id = ?1 AND persona_id = ?2",
-            params![chat_id, persona_id],
-            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            "SELECT name, path, template, chat_id, created_at FROM projects WHERE name = ?1",
+            params![name],
+            Self::project_from_row,
         );
         match result {
-            Ok(pair) => Ok(Some(pair)),
+            Ok(p) => Ok(Some(p)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 
-    pub fn delete_session(&self, chat_id: i64, persona_id: i64) -> Result<bool, MicroClawError> {
+    pub fn list_projects(&self) -> Result<Vec<Project>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let rows = conn.execute(
-            "DELETE FROM sessions WHERE chat_id = ?1 AND persona_id = ?2",
-            params![chat_id, persona_id],
+        let mut stmt = conn.prepare(
+            "SELECT name, path, template, chat_id, created_at FROM projects ORDER BY name",
         )?;
-        Ok(rows > 0)
+        let rows = stmt
+            .query_map([], Self::project_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
     }
 
-    pub fn delete_chat_data(&self, chat_id: i64) -> Result<bool, MicroClawError> {
-        let conn = self.conn.lock().unwrap();
-        let tx = conn.unchecked_transaction()?;
-        let mut affected = 0usize;
+    fn project_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Project> {
+        Ok(Project {
+            name: row.get(0)?,
+            path: row.get(1)?,
+            template: row.get(2)?,
+            chat_id: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
 
-        affected += tx.execute("UPDATE chats SET active_persona_id = NULL WHERE chat_id = ?1", params![chat_id])?;
-        affected += tx.execute("DELETE FROM sessions WHERE chat_id = ?1", params![chat_id])?;
-        affected += tx.execute("DELETE FROM messages WHERE chat_id = ?1", params![chat_id])?;
-        affected += tx.execute("DELETE FROM personas WHERE chat_id = ?1", params![chat_id])?;
-        affected += tx.execute(
-            "DELETE FROM scheduled_tasks WHERE chat_id = ?1",
+    // --- Access requests ---
+
+    /// File an access request for `chat_id`. Returns `None` when the chat already has a pending
+    /// or denied request (so control chats are asked once).
+    pub fn create_access_request(
+        &self,
+        channel: &str,
+        chat_id: i64,
+        chat_title: Option<&str>,
+        requester: &str,
+        message: &str,
+    ) -> Result<Option<i64>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let open: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM access_requests
+             WHERE chat_id = ?1 AND status IN ('pending', 'denied')",
             params![chat_id],
+            |row| row.get(0),
         )?;
-        affected += tx.execute(
-            "DELETE FROM social_oauth_tokens WHERE chat_id = ?1",
-            params![chat_id],
+        if open > 0 {
+            return Ok(None);
+        }
+        conn.execute(
+            "INSERT INTO access_requests (channel, chat_id, chat_title, requester, message, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                channel,
+                chat_id,
+                chat_title,
+                requester,
+                message,
+                chrono::Utc::now().to_rfc3339()
+            ],
         )?;
-        affected += tx.execute("DELETE FROM chats WHERE chat_id = ?1", params![chat_id])?;
+        Ok(Some(conn.last_insert_rowid()))
+    }
 
-        tx.commit()?;
-        Ok(affected > 0)
+    pub fn get_access_request(&self, id: i64) -> Result<Option<AccessRequest>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, channel, chat_id, chat_title, requester, message, status, created_at, resolved_at
+             FROM access_requests WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(AccessRequest {
+                    id: row.get(0)?,
+                    channel: row.get(1)?,
+                    chat_id: row.get(2)?,
+                    chat_title: row.get(3)?,
+                    requester: row.get(4)?,
+                    message: row.get(5)?,
+                    status: row.get(6)?,
+                    created_at: row.get(7)?,
+                    resolved_at: row.get(8)?,
+                })
+            },
+        );
+        match result {
+            Ok(r) => Ok(Some(r)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
-    // --- Social OAuth tokens ---
+    /// Move a pending request to `status`. Returns false when it was already resolved.
+    pub fn resolve_access_request(&self, id: i64, status: &str) -> Result<bool, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let n = conn.execute(
+            "UPDATE access_requests SET status = ?2, resolved_at = ?3
+             WHERE id = ?1 AND status = 'pending'",
+            params![id, status, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(n > 0)
+    }
 
-    pub fn upsert_social_token(
+    // --- Chat access (allowlist entries kept in the DB, on top of the config lists) ---
+
+    pub fn set_chat_access(
         &self,
-        platform: &str,
         chat_id: i64,
-        access_token: &str,
-        refresh_token: Option<&str>,
+        channel: &str,
+        status: &str,
         expires_at: Option<&str>,
+        note: Option<&str>,
     ) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO social_oauth_tokens (platform, chat_id, access_token, refresh_token, expires_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)
-             ON CONFLICT(platform, chat_id) DO UPDATE SET
-                access_token = ?3,
-                refresh_token = ?4,
-                expires_at = ?5",
-            params![platform, chat_id, access_token, refresh_token, expires_at],
+            "INSERT INTO chat_access (chat_id, channel, status, expires_at, note, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(chat_id) DO UPDATE SET channel = ?2, status = ?3, expires_at = ?4,
+                note = ?5, updated_at = ?6",
+            params![
+                chat_id,
+                channel,
+                status,
+                expires_at,
+                note,
+                chrono::Utc::now().to_rfc3339()
+            ],
         )?;
         Ok(())
     }
 
-    pub fn get_social_token(
-        &self,
-        platform: &str,
-        chat_id: i64,
-    ) -> Result<Option<SocialOAuthToken>, MicroClawError> {
+    /// Remove a chat's entry. Returns false when it had none.
+    pub fn delete_chat_access(&self, chat_id: i64) -> Result<bool, MicroClawError> {
         let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(
-            "SELECT platform, chat_id, access_token, refresh_token, expires_at
-             FROM social_oauth_tokens
-             WHERE platform = ?1 AND chat_id = ?2",
-            params![platform, chat_id],
-            |row| {
-                Ok(SocialOAuthToken {
-                    platform: row.get(0)?,
-                    chat_id: row.get(1)?,
-                    access_token: row.get(2)?,
-                    refresh_token: row.get(3)?,
-                    expires_at: row.get(4)?,
+        let n = conn.execute(
+            "DELETE FROM chat_access WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// All entries, expired ones included, by channel and chat.
+    pub fn list_chat_access(&self) -> Result<Vec<ChatAccess>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT chat_id, channel, status, expires_at, note, updated_at
+             FROM chat_access ORDER BY channel, chat_id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ChatAccess {
+                    chat_id: row.get(0)?,
+                    channel: row.get(1)?,
+                    status: row.get(2)?,
+                    expires_at: row.get(3)?,
+                    note: row.get(4)?,
+                    updated_at: row.get(5)?,
                 })
-            },
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Access status of a chat ("allowed" or "blocked"), ignoring expired entries.
+    pub fn get_chat_access_status(&self, chat_id: i64) -> Result<Option<String>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT status FROM chat_access
+             WHERE chat_id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+            params![chat_id, chrono::Utc::now().to_rfc3339()],
+            |row| row.get(0),
         );
         match result {
-            Ok(t) => Ok(Some(t)),
+            Ok(status) => Ok(Some(status)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 
-    pub fn delete_social_token(&self, platform: &str, chat_id: i64) -> Result<bool, MicroClawError> {
-        let conn = self.conn.lock().unwrap();
-        let rows = conn.execute(
-            "DELETE FROM social_oauth_tokens WHERE platform = ?1 AND chat_id = ?2",
-            params![platform, chat_id],
-        )?;
-        Ok(rows > 0)
-    }
-
     // --- OAuth pending states (short-lived mapping from state param to chat_id) ---
 
+    /// Issue a single-use state token bound to `chat_id`. Expired states are purged on the way.
     pub fn create_oauth_pending_state(
         &self,
         state_token: &str,
         platform: &str,
         chat_id: i64,
         expires_at: &str,
+        code_verifier: Option<&str>,
+        scopes: Option<&str>,
     ) -> Result<(), MicroClawError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR REPLACE INTO oauth_pending_states (state_token, platform, chat_id, expires_at)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![state_token, platform, chat_id, expires_at],
+            "DELETE FROM oauth_pending_states WHERE expires_at <= ?1",
+            params![chrono::Utc::now().to_rfc3339()],
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO oauth_pending_states
+                (state_token, platform, chat_id, expires_at, code_verifier, browser_binding, scopes)
+             VALUES (?1, ?2, ?3, ?4, ?5, NULL, ?6)",
+            params![state_token, platform, chat_id, expires_at, code_verifier, scopes],
         )?;
         Ok(())
     }
 
+    /// Attach the browser that opened the authorize link to an unexpired, not yet opened state.
+    /// Returns `None` if the state is unknown, expired, for another platform or already opened.
+    pub fn bind_oauth_pending_state(
+        &self,
+        state_token: &str,
+        platform: &str,
+        browser_binding: &str,
+    ) -> Result<Option<OAuthPendingState>, MicroClawError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE oauth_pending_states SET browser_binding = ?3
+             WHERE state_token = ?1 AND platform = ?2 AND browser_binding IS NULL
+               AND expires_at > ?4",
+            params![
+                state_token,
+                platform,
+                browser_binding,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        if updated == 0 {
+            return Ok(None);
+        }
+        Self::query_oauth_pending_state(&conn, state_token)
+    }
+
+    /// Look up and delete an unexpired state (single use) bound to `browser_binding`. A state
+    /// bound to another browser is left alone, so a replayed callback cannot burn it.
     pub fn consume_oauth_pending_state(
         &self,
         state_token: &str,
-    ) -> Result<Option<(String, i64)>, MicroClawError> {
+        browser_binding: &str,
+    ) -> Result<Option<OAuthPendingState>, MicroClawError> {
         let conn = self.conn.lock().unwrap();
+        let Some(pending) = Self::query_oauth_pending_state(&conn, state_token)? else {
+            return Ok(None);
+        };
+        if pending.browser_binding.as_deref() != Some(browser_binding) {
+            return Ok(None);
+        }
+        conn.execute(
+            "DELETE FROM oauth_pending_states WHERE state_token = ?1",
+            params![state_token],
+        )?;
+        if pending.expires_at <= chrono::Utc::now().to_rfc3339() {
+            return Ok(None);
+        }
+        Ok(Some(pending))
+    }
+
+    fn query_oauth_pending_state(
+        conn: &Connection,
+        state_token: &str,
+    ) -> Result<Option<OAuthPendingState>, MicroClawError> {
         let result = conn.query_row(
-            "SELECT platform, chat_id FROM oauth_pending_states
-             WHERE state_token = ?1 AND expires_at > datetime('now')",
+            "SELECT state_token, platform, chat_id, expires_at, code_verifier, browser_binding,
+                    scopes
+             FROM oauth_pending_states WHERE state_token = ?1",
             params![state_token],
-            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            |row| {
+                Ok(OAuthPendingState {
+                    state_token: row.get(0)?,
+                    platform: row.get(1)?,
+                    chat_id: row.get(2)?,
+                    expires_at: row.get(3)?,
+                    code_verifier: row.get(4)?,
+                    browser_binding: row.get(5)?,
+                    scopes: row.get(6)?,
+                })
+            },
         );
-        let pair = match result {
-            Ok(p) => p,
-            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
-            Err(e) => return Err(e.into()),
-        };
-        conn.execute("DELETE FROM oauth_pending_states WHERE state_token = ?1", params![state_token])?;
-        Ok(Some(pair))
+        match result {
+            Ok(p) => Ok(Some(p)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
     pub fn get_new_user_messages_since(
@@ -1779,6 +6167,71 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_task_retries() {
+        let (db, dir) = test_db();
+        let id = db
+            .create_scheduled_task(
+                100,
+                "test",
+                "once",
+                "2024-01-01T00:00:00Z",
+                "2024-01-01T00:00:00Z",
+            )
+            .unwrap();
+        let task = db.get_task_by_id(id).unwrap().unwrap();
+        assert_eq!((task.max_retries, task.retry_count), (None, 0));
+        assert!(db.set_task_max_retries(id, Some(3)).unwrap());
+        assert!(task.timezone.is_none());
+        assert!(db.set_task_timezone(id, Some("Asia/Tokyo")).unwrap());
+        assert!(db.set_task_concurrency(id, Some("queue")).unwrap());
+
+        // A failed one-shot run is completed when claimed; the retry reactivates it
+        db.update_task_after_run(id, "2024-01-01T00:00:00Z", None)
+            .unwrap();
+        assert!(db
+            .schedule_task_retry(id, 1, "2024-01-01T00:01:00Z")
+            .unwrap());
+        let task = db.get_task_by_id(id).unwrap().unwrap();
+        assert_eq!(task.status, "active");
+        assert_eq!(task.next_run, "2024-01-01T00:01:00Z");
+        assert_eq!((task.max_retries, task.retry_count), (Some(3), 1));
+        assert_eq!(task.timezone.as_deref(), Some("Asia/Tokyo"));
+        assert_eq!(task.concurrency.as_deref(), Some("queue"));
+
+        db.reset_task_retries(id).unwrap();
+        assert_eq!(db.get_task_by_id(id).unwrap().unwrap().retry_count, 0);
+        // Cancelled tasks are not revived
+        db.update_task_status(id, "cancelled").unwrap();
+        assert!(!db
+            .schedule_task_retry(id, 1, "2024-01-01T00:02:00Z")
+            .unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_task_run_request() {
+        let (db, dir) = test_db();
+        let id = db
+            .create_scheduled_task(100, "test", "cron", "0 0 8 * * *", "2030-01-01T08:00:00Z")
+            .unwrap();
+        db.update_task_status(id, "paused").unwrap();
+        assert!(db.get_due_tasks("2024-01-01T00:00:00Z").unwrap().is_empty());
+
+        // A paused task still runs on request; its schedule is untouched
+        assert!(db.request_task_run(id, "2024-01-01T00:00:00Z").unwrap());
+        let due = db.get_due_tasks("2024-01-01T00:00:00Z").unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].run_requested_at.as_deref(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(due[0].next_run, "2030-01-01T08:00:00Z");
+        db.clear_task_run_request(id).unwrap();
+        assert!(db.get_due_tasks("2024-01-01T00:00:00Z").unwrap().is_empty());
+
+        db.update_task_status(id, "cancelled").unwrap();
+        assert!(!db.request_task_run(id, "2024-01-01T00:00:00Z").unwrap());
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_delete_task() {
         let (db, dir) = test_db();
@@ -1922,6 +6375,209 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_undelete_and_purge_sessions() {
+        let (db, dir) = test_db();
+        let pid = test_persona(&db, 100);
+        db.save_session(100, pid, "[\"old\"]").unwrap();
+        db.delete_session(100, pid).unwrap();
+        db.save_session(100, pid, "[\"new\"]").unwrap();
+
+        let deleted = db.list_deleted_sessions(100).unwrap();
+        assert_eq!(deleted.len(), 1);
+        let restored = db.undelete_session(deleted[0].id, pid).unwrap().unwrap();
+        assert_eq!(restored.persona_id, pid);
+        assert_eq!(db.load_session(100, pid).unwrap().unwrap().0, "[\"old\"]");
+        // The session it replaced can be undeleted in turn
+        let deleted = db.list_deleted_sessions(100).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].messages_json, "[\"new\"]");
+        assert!(db.undelete_session(9999, pid).unwrap().is_none());
+
+        // Wiping the chat keeps its sessions restorable, into the new persona
+        db.delete_chat_data(100).unwrap();
+        assert_eq!(db.list_deleted_sessions(100).unwrap().len(), 2);
+        let new_pid = test_persona(&db, 100);
+        let newest = db.list_deleted_sessions(100).unwrap()[0].id;
+        let restored = db.undelete_session(newest, new_pid).unwrap().unwrap();
+        assert_eq!(restored.persona_id, new_pid);
+
+        assert_eq!(
+            db.purge_deleted_sessions("2000-01-01T00:00:00Z").unwrap(),
+            0
+        );
+        assert_eq!(
+            db.purge_deleted_sessions("9999-01-01T00:00:00Z").unwrap(),
+            1
+        );
+        assert!(db.list_deleted_sessions(100).unwrap().is_empty());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_instance_lease() {
+        let (db, dir) = test_db();
+        let past = "2000-01-01T00:00:00Z";
+        assert!(db.try_acquire_lease("leader", "a", past).unwrap());
+        assert!(db.try_acquire_lease("leader", "a", past).unwrap());
+        assert!(!db.try_acquire_lease("leader", "b", past).unwrap());
+        // A stale holder is taken over
+        assert!(db.try_acquire_lease("leader", "b", "9999-01-01T00:00:00Z").unwrap());
+        assert!(!db.try_acquire_lease("leader", "a", past).unwrap());
+        db.release_lease("leader", "a").unwrap();
+        assert!(!db.try_acquire_lease("leader", "a", past).unwrap());
+        db.release_lease("leader", "b").unwrap();
+        assert!(db.try_acquire_lease("leader", "a", past).unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_record_feed_items() {
+        let (db, dir) = test_db();
+        let url = "https://example.com/feed.xml";
+        let past = "2000-01-01T00:00:00Z";
+        assert!(!db.has_feed_items(1, url).unwrap());
+        let ids = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            db.record_feed_items(1, url, &ids(&["a", "b"]), past).unwrap(),
+            ids(&["a", "b"])
+        );
+        assert!(db.has_feed_items(1, url).unwrap());
+        assert_eq!(
+            db.record_feed_items(1, url, &ids(&["b", "c"]), past).unwrap(),
+            ids(&["c"])
+        );
+        // Other chats track the same feed separately
+        assert!(!db.has_feed_items(2, url).unwrap());
+        // Entries last seen before the prune cutoff are forgotten
+        db.record_feed_items(1, url, &[], "9999-01-01T00:00:00Z").unwrap();
+        assert!(!db.has_feed_items(1, url).unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_provider_files() {
+        let (db, dir) = test_db();
+        db.record_provider_file("anthropic", "file_1", 1, "/tmp/a.pdf")
+            .unwrap();
+        assert_eq!(
+            db.find_provider_file("anthropic", "/tmp/a.pdf").unwrap(),
+            Some("file_1".into())
+        );
+        assert_eq!(db.find_provider_file("openai", "/tmp/a.pdf").unwrap(), None);
+        assert!(db.has_provider_file("anthropic", "file_1").unwrap());
+        assert!(db
+            .provider_files_before("2000-01-01T00:00:00Z")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            db.provider_files_before("9999-01-01T00:00:00Z").unwrap(),
+            vec![("anthropic".to_string(), "file_1".to_string())]
+        );
+        db.delete_provider_file("anthropic", "file_1").unwrap();
+        assert!(!db.has_provider_file("anthropic", "file_1").unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_chat_archive_round_trip() {
+        let (db, dir) = test_db();
+        let msg = |id: &str, chat_id: i64, ts: &str| StoredMessage {
+            id: id.into(),
+            chat_id,
+            persona_id: 0,
+            sender_name: "alice".into(),
+            content: format!("msg {id}"),
+            is_from_bot: false,
+            timestamp: ts.into(),
+        };
+        db.store_message(&msg("1", 7, "2025-01-01T10:00:00Z")).unwrap();
+        db.store_message(&msg("2", 7, "2025-01-02T10:00:00Z")).unwrap();
+        db.store_message(&msg("3", 8, "2026-10-01T10:00:00Z")).unwrap();
+        db.save_session(7, 0, "[]").unwrap();
+        assert_eq!(
+            db.chats_inactive_since("2026-01-01T00:00:00Z", 10).unwrap(),
+            vec![7]
+        );
+
+        let messages = db.get_chat_messages(7).unwrap();
+        let sessions = db.get_chat_sessions(7).unwrap();
+        let archive = ChatArchive {
+            chat_id: 7,
+            path: "/tmp/chat_7.json.gz".into(),
+            message_count: 2,
+            last_message_at: "2025-01-02T10:00:00Z".into(),
+            archived_at: "2026-10-16T00:00:00Z".into(),
+        };
+        db.archive_chat_data(&archive, Some(&sessions[0].updated_at))
+            .unwrap();
+        assert!(db.get_chat_messages(7).unwrap().is_empty());
+        assert!(db.load_session(7, 0).unwrap().is_none());
+        assert_eq!(db.get_chat_archive(7).unwrap().unwrap().message_count, 2);
+        assert!(db
+            .chats_inactive_since("2026-01-01T00:00:00Z", 10)
+            .unwrap()
+            .is_empty());
+
+        db.restore_chat_data(7, &messages, &sessions).unwrap();
+        assert_eq!(db.get_chat_messages(7).unwrap().len(), 2);
+        assert_eq!(db.load_session(7, 0).unwrap().unwrap().0, "[]");
+        assert!(db.get_chat_archive(7).unwrap().is_none());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_turn_timings_slowest_and_delivery() {
+        let (db, dir) = test_db();
+        let timing = |chat_id: i64, agent_ms: u64, created_at: &str| TurnTiming {
+            chat_id,
+            channel: "telegram".into(),
+            agent_ms,
+            stages: "[]".into(),
+            created_at: created_at.into(),
+            ..Default::default()
+        };
+        db.record_turn_timing(&timing(1, 100, "2026-01-01T00:00:00Z"), "2025-01-01")
+            .unwrap();
+        db.record_turn_timing(&timing(1, 900, "2026-10-01T00:00:00Z"), "2025-01-01")
+            .unwrap();
+        db.record_turn_timing(&timing(2, 500, "2026-10-02T00:00:00Z"), "2026-06-01")
+            .unwrap();
+        assert!(db.set_turn_delivery(1, 50).unwrap());
+        // Already set
+        assert!(!db.set_turn_delivery(1, 70).unwrap());
+
+        let all = db.slowest_turns("2000-01-01", None, 10).unwrap();
+        // The January turn was pruned by the last insert
+        assert_eq!(
+            all.iter().map(|t| t.agent_ms).collect::<Vec<_>>(),
+            vec![900, 500]
+        );
+        assert_eq!(all[0].delivery_ms, Some(50));
+        let chat2 = db.slowest_turns("2000-01-01", Some(2), 10).unwrap();
+        assert_eq!(chat2.len(), 1);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_feature_flag_overrides() {
+        let (db, dir) = test_db();
+        db.set_feature_flag(1, "vision", Some(false)).unwrap();
+        db.set_feature_flag(1, "web_search", Some(true)).unwrap();
+        db.set_feature_flag(1, "vision", Some(true)).unwrap();
+        assert_eq!(
+            db.list_feature_flags(1).unwrap(),
+            vec![
+                ("vision".to_string(), true),
+                ("web_search".to_string(), true)
+            ]
+        );
+        db.set_feature_flag(1, "vision", None).unwrap();
+        assert_eq!(db.list_feature_flags(1).unwrap().len(), 1);
+        assert!(db.list_feature_flags(2).unwrap().is_empty());
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_get_new_user_messages_since() {
         let (db, dir) = test_db();
@@ -1995,4 +6651,70 @@ mod tests {
 
         cleanup(&dir);
     }
+
+    #[test]
+    fn test_glossary_terms_crud() {
+        let (db, dir) = test_db();
+        db.upsert_glossary_term(100, "Oma", "en", "Grandma").unwrap();
+        db.upsert_glossary_term(100, "Oma", "es", "Abuela").unwrap();
+        db.upsert_glossary_term(200, "Oma", "en", "Granny").unwrap();
+        // Upsert replaces the translation
+        db.upsert_glossary_term(100, "Oma", "en", "Nana").unwrap();
+
+        let all = db.get_glossary_terms(100, None).unwrap();
+        assert_eq!(all.len(), 2);
+        let en = db.get_glossary_terms(100, Some("en")).unwrap();
+        assert_eq!(en.len(), 1);
+        assert_eq!(en[0].translation, "Nana");
+
+        assert_eq!(db.delete_glossary_term(100, "Oma", Some("es")).unwrap(), 1);
+        assert_eq!(db.delete_glossary_term(100, "Oma", None).unwrap(), 1);
+        assert!(db.get_glossary_terms(100, None).unwrap().is_empty());
+        assert_eq!(db.get_glossary_terms(200, None).unwrap().len(), 1);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_catch_up_window_and_topics() {
+        let (db, dir) = test_db();
+        let msg = |id: &str, sender: &str, ts: &str| StoredMessage {
+            id: id.into(),
+            chat_id: 100,
+            persona_id: 1,
+            sender_name: sender.into(),
+            content: format!("msg {id}"),
+            is_from_bot: false,
+            timestamp: ts.into(),
+        };
+        db.store_message(&msg("1", "alice", "2024-01-01T00:00:01Z")).unwrap();
+        db.store_message(&msg("2", "bob", "2024-01-01T00:00:02Z")).unwrap();
+        db.store_message(&msg("3", "alice", "2024-01-01T00:00:03Z")).unwrap();
+        db.store_message(&msg("4", "bob", "2024-01-01T00:00:04Z")).unwrap();
+        db.store_message(&msg("5", "carol", "2024-01-01T00:00:05Z")).unwrap();
+        // alice's catch-up request comes after the unread messages
+        db.store_message(&msg("6", "alice", "2024-01-01T00:00:06Z")).unwrap();
+        db.set_message_thread(100, "5", 77).unwrap();
+        db.upsert_forum_topic(100, 77, "Garden").unwrap();
+
+        let since = db.get_catch_up_since(100, "alice").unwrap();
+        assert_eq!(since.as_deref(), Some("2024-01-01T00:00:03Z"));
+        let unread = db
+            .get_messages_for_catch_up(100, "alice", since.as_deref(), 50)
+            .unwrap();
+        let ids: Vec<&str> = unread.iter().map(|m| m.message.id.as_str()).collect();
+        assert_eq!(ids, vec!["4", "5"]);
+        assert_eq!(unread[1].thread_id, Some(77));
+        assert_eq!(unread[1].topic_name.as_deref(), Some("Garden"));
+
+        db.mark_caught_up(100, "alice", "2024-01-01T00:00:07Z").unwrap();
+        let since = db.get_catch_up_since(100, "alice").unwrap();
+        assert_eq!(since.as_deref(), Some("2024-01-01T00:00:07Z"));
+        assert!(db
+            .get_messages_for_catch_up(100, "alice", since.as_deref(), 50)
+            .unwrap()
+            .is_empty());
+        // No activity at all: no window start
+        assert_eq!(db.get_catch_up_since(100, "dave").unwrap(), None);
+        cleanup(&dir);
+    }
 }
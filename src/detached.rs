@@ -0,0 +1,402 @@
+//! Detached runs: processes that keep running outside an agent turn (and across bot restarts).
+//! Two backends, picked per platform: tmux sessions where tmux is available, otherwise a plain
+//! spawned process with its output redirected to a log file and its PID tracked on disk (the
+//! only option on Windows). Runs use the same "microclaw-" names as [`crate::tmux`] either way.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use thiserror::Error;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::tmux::{self, TmuxError};
+
+#[derive(Error, Debug)]
+pub enum DetachedError {
+    #[error(transparent)]
+    Tmux(#[from] TmuxError),
+
+    #[error("Detached run '{0}' not found")]
+    NotFound(String),
+
+    #[error("Detached run '{0}' already exists")]
+    Exists(String),
+
+    #[error("{0} is not supported for process-backed runs (install tmux for interactive runs)")]
+    Unsupported(&'static str),
+
+    #[error("Detached run I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Tmux,
+    Process,
+}
+
+impl Backend {
+    /// tmux when it is installed (never on Windows), else a spawned process.
+    pub async fn detect() -> Backend {
+        if cfg!(target_os = "windows") || !tmux::is_installed().await {
+            Backend::Process
+        } else {
+            Backend::Tmux
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Backend::Tmux => "tmux",
+            Backend::Process => "process",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    /// Finished; the exit code when the process reported one.
+    Exited(Option<i32>),
+    /// Process is gone and its exit was not observed (the bot restarted meanwhile).
+    Gone,
+}
+
+/// A detached run as listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedRun {
+    pub name: String,
+    pub backend: Backend,
+    pub state: RunState,
+    /// Unix timestamp the run started.
+    pub started: i64,
+}
+
+/// Where process-backed runs keep their `.pid`, `.log` and `.exit` files.
+pub fn state_dir(config: &Config) -> PathBuf {
+    PathBuf::from(config.runtime_data_dir()).join("detached")
+}
+
+fn pid_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.pid"))
+}
+
+fn log_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.log"))
+}
+
+fn exit_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.exit"))
+}
+
+/// Quote `arg` for a POSIX shell (tmux runs the session command through one).
+pub fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c))
+    {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Parse a `.pid` file: "<pid> <started unix timestamp>".
+fn parse_pid_file(content: &str) -> Option<(u32, i64)> {
+    let mut parts = content.split_whitespace();
+    let pid = parts.next()?.parse().ok()?;
+    let started = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    Some((pid, started))
+}
+
+/// Parse an `.exit` file: the exit code, or empty when the process was killed by a signal.
+fn parse_exit_file(content: &str) -> Option<i32> {
+    content.trim().parse().ok()
+}
+
+async fn pid_alive(pid: u32) -> bool {
+    let output = if cfg!(target_os = "windows") {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+            .output()
+            .await
+    } else {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .await
+    };
+    match output {
+        Ok(o) if cfg!(target_os = "windows") => {
+            String::from_utf8_lossy(&o.stdout).contains(&pid.to_string())
+        }
+        Ok(o) => o.status.success(),
+        Err(_) => false,
+    }
+}
+
+async fn kill_pid(pid: u32) -> Result<(), DetachedError> {
+    let output = if cfg!(target_os = "windows") {
+        Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .output()
+            .await?
+    } else {
+        // Runs start in their own process group; signal the whole group.
+        Command::new("kill")
+            .args(["-TERM", "--", &format!("-{pid}")])
+            .output()
+            .await?
+    };
+    if !output.status.success() {
+        warn!(
+            "Detached: kill {pid} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+async fn process_state(dir: &Path, name: &str, pid: u32) -> RunState {
+    if let Ok(content) = tokio::fs::read_to_string(exit_path(dir, name)).await {
+        return RunState::Exited(parse_exit_file(&content));
+    }
+    if pid_alive(pid).await {
+        RunState::Running
+    } else {
+        RunState::Gone
+    }
+}
+
+async fn read_pid_file(dir: &Path, name: &str) -> Option<(u32, i64)> {
+    let content = tokio::fs::read_to_string(pid_path(dir, name)).await.ok()?;
+    parse_pid_file(&content)
+}
+
+/// Start `program args` detached in `cwd` under the bot-managed name for `name`, with `env`
+/// added to the inherited environment.
+pub async fn start(
+    dir: &Path,
+    name: &str,
+    cwd: &Path,
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+) -> Result<(String, Backend), DetachedError> {
+    let name = tmux::session_name(name);
+    tmux::validate_name(&name)?;
+    let backend = Backend::detect().await;
+    match backend {
+        Backend::Tmux => {
+            // The session's shell inherits the tmux server's environment, not ours.
+            let assignments: Vec<String> = env
+                .iter()
+                .map(|(k, v)| shell_quote(&format!("{k}={v}")))
+                .collect();
+            let mut command = std::iter::once(program)
+                .chain(args.iter().map(String::as_str))
+                .map(shell_quote)
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !assignments.is_empty() {
+                command = format!("env {} {command}", assignments.join(" "));
+            }
+            tmux::create_session(&name, cwd, &command).await?;
+        }
+        Backend::Process => start_process(dir, &name, cwd, program, args, env).await?,
+    }
+    info!("Detached run {name} started ({})", backend.as_str());
+    Ok((name, backend))
+}
+
+async fn start_process(
+    dir: &Path,
+    name: &str,
+    cwd: &Path,
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+) -> Result<(), DetachedError> {
+    tokio::fs::create_dir_all(dir).await?;
+    if let Some((pid, _)) = read_pid_file(dir, name).await {
+        if process_state(dir, name, pid).await == RunState::Running {
+            return Err(DetachedError::Exists(name.to_string()));
+        }
+    }
+    let _ = tokio::fs::remove_file(exit_path(dir, name)).await;
+    let log = std::fs::File::create(log_path(dir, name))?;
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(log.try_clone()?))
+        .stderr(Stdio::from(log));
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(windows)]
+    {
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    let mut child = cmd.spawn()?;
+    let pid = child.id().unwrap_or(0);
+    tokio::fs::write(
+        pid_path(dir, name),
+        format!("{pid} {}", chrono::Utc::now().timestamp()),
+    )
+    .await?;
+
+    // Reap the child and record its exit while the bot is up; after a restart the state
+    // falls back to checking the PID.
+    let exit_file = exit_path(dir, name);
+    tokio::spawn(async move {
+        if let Ok(status) = child.wait().await {
+            let code = status.code().map(|c| c.to_string()).unwrap_or_default();
+            let _ = tokio::fs::write(exit_file, code).await;
+        }
+    });
+    Ok(())
+}
+
+/// All detached runs: live tmux sessions plus process-backed runs with state on disk.
+pub async fn list(dir: &Path) -> Result<Vec<DetachedRun>, DetachedError> {
+    let mut runs: Vec<DetachedRun> = match tmux::list_sessions().await {
+        Ok(sessions) => sessions
+            .into_iter()
+            .map(|s| DetachedRun {
+                name: s.name,
+                backend: Backend::Tmux,
+                state: RunState::Running,
+                started: s.created,
+            })
+            .collect(),
+        Err(TmuxError::NotInstalled) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(runs),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(name) = file_name.strip_suffix(".pid") else {
+            continue;
+        };
+        if let Some((pid, started)) = read_pid_file(dir, name).await {
+            runs.push(DetachedRun {
+                name: name.to_string(),
+                backend: Backend::Process,
+                state: process_state(dir, name, pid).await,
+                started,
+            });
+        }
+    }
+    runs.sort_by_key(|r| std::cmp::Reverse(r.started));
+    Ok(runs)
+}
+
+/// The last `lines` lines of a run's output.
+pub async fn output(dir: &Path, name: &str, lines: usize) -> Result<String, DetachedError> {
+    let name = tmux::session_name(name);
+    tmux::validate_name(&name)?;
+    if read_pid_file(dir, &name).await.is_none() {
+        return Ok(tmux::capture(&name, lines).await?);
+    }
+    let bytes = tokio::fs::read(log_path(dir, &name)).await?;
+    Ok(tail_lines(&String::from_utf8_lossy(&bytes), lines))
+}
+
+fn tail_lines(text: &str, lines: usize) -> String {
+    let all: Vec<&str> = text.trim_end().lines().collect();
+    all[all.len().saturating_sub(lines.max(1))..].join("\n")
+}
+
+/// Type `text` into an interactive run. Only tmux-backed runs take input.
+pub async fn send_input(dir: &Path, name: &str, text: &str) -> Result<(), DetachedError> {
+    let name = tmux::session_name(name);
+    tmux::validate_name(&name)?;
+    if read_pid_file(dir, &name).await.is_some() {
+        return Err(DetachedError::Unsupported("Sending input"));
+    }
+    Ok(tmux::send_keys(&name, text).await?)
+}
+
+/// Stop a run. Process-backed runs keep their log until started again under the same name.
+pub async fn stop(dir: &Path, name: &str) -> Result<(), DetachedError> {
+    let name = tmux::session_name(name);
+    tmux::validate_name(&name)?;
+    let Some((pid, _)) = read_pid_file(dir, &name).await else {
+        return Ok(tmux::kill_session(&name).await?);
+    };
+    match process_state(dir, &name, pid).await {
+        RunState::Running => kill_pid(pid).await,
+        _ => Err(DetachedError::NotFound(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("cursor-agent"), "cursor-agent");
+        assert_eq!(shell_quote("--model"), "--model");
+        assert_eq!(shell_quote("fix the bug"), "'fix the bug'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_state_files() {
+        assert_eq!(parse_pid_file("123 1700000000"), Some((123, 1_700_000_000)));
+        assert_eq!(parse_pid_file("123\n"), Some((123, 0)));
+        assert_eq!(parse_pid_file("junk"), None);
+        assert_eq!(parse_exit_file("0"), Some(0));
+        assert_eq!(parse_exit_file(""), None);
+        assert_eq!(tail_lines("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(tail_lines("a\n", 10), "a");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_process_backend_round_trip() {
+        let dir = std::env::temp_dir().join(format!("microclaw_detached_{}", uuid::Uuid::new_v4()));
+        let name = "microclaw-test-run";
+        start_process(
+            &dir,
+            name,
+            &std::env::temp_dir(),
+            "sh",
+            &["-c".into(), "echo one; echo $WORD >&2".into()],
+            &[("WORD".into(), "two".into())],
+        )
+        .await
+        .unwrap();
+        for _ in 0..50 {
+            if exit_path(&dir, name).exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let runs = list(&dir).await.unwrap();
+        let run = runs.iter().find(|r| r.name == name).unwrap();
+        assert_eq!(run.backend, Backend::Process);
+        assert_eq!(run.state, RunState::Exited(Some(0)));
+        assert_eq!(output(&dir, name, 10).await.unwrap(), "one\ntwo");
+        assert!(matches!(
+            send_input(&dir, name, "hi").await,
+            Err(DetachedError::Unsupported(_))
+        ));
+        assert!(matches!(
+            stop(&dir, name).await,
+            Err(DetachedError::NotFound(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,255 @@
+//! Outbound network policy for tools that fetch agent-chosen URLs. Requests go through
+//! [`EgressPolicy`], which checks domain allow/deny lists, resolves the host and rejects
+//! private/internal addresses (unless allowed), pins the checked addresses in the client so a
+//! second DNS lookup cannot swap them, and re-checks every redirect hop.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use reqwest::Url;
+
+use crate::config::{Config, EgressConfig};
+
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Clone, Debug, Default)]
+pub struct EgressPolicy {
+    cfg: EgressConfig,
+}
+
+fn normalize_domain(d: &str) -> String {
+    d.trim()
+        .trim_start_matches("*.")
+        .trim_end_matches('.')
+        .to_lowercase()
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    let domain = normalize_domain(domain);
+    !domain.is_empty() && (host == domain || host.ends_with(&format!(".{domain}")))
+}
+
+/// Loopback, private, link-local, CGNAT, multicast and other non-public addresses.
+pub fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_internal_v4(v4);
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00 // unique local fc00::/7
+                || (first & 0xffc0) == 0xfe80 // link-local fe80::/10
+        }
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // CGNAT 100.64.0.0/10
+        || (a == 198 && (b == 18 || b == 19)) // benchmarking 198.18.0.0/15
+}
+
+impl EgressPolicy {
+    pub fn new(cfg: EgressConfig) -> Self {
+        EgressPolicy { cfg }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.egress.clone())
+    }
+
+    fn private_host_allowed(&self, host: &str) -> bool {
+        self.cfg.allow_private_networks
+            || self
+                .cfg
+                .allow_private_hosts
+                .iter()
+                .any(|h| normalize_domain(h) == host)
+    }
+
+    /// Domain allow/deny checks (no network access).
+    pub fn check_host(&self, host: &str) -> Result<(), String> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        if let Some(d) = self
+            .cfg
+            .deny_domains
+            .iter()
+            .find(|d| domain_matches(&host, d))
+        {
+            return Err(format!("egress policy: {host} is denied ({})", d.trim()));
+        }
+        if !self.cfg.allow_domains.is_empty()
+            && !self
+                .cfg
+                .allow_domains
+                .iter()
+                .any(|d| domain_matches(&host, d))
+            && !self.private_host_allowed(&host)
+        {
+            return Err(format!(
+                "egress policy: {host} is not in the allowed domains"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate a URL and resolve its host. Returns the addresses that passed the checks so the
+    /// caller can pin them.
+    pub async fn check_url(&self, url: &Url) -> Result<Vec<SocketAddr>, String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!(
+                "egress policy: only http and https URLs are allowed (got {})",
+                url.scheme()
+            ));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| "egress policy: URL has no host".to_string())?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+        self.check_host(&host)?;
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host.as_str(), port))
+                .await
+                .map_err(|e| format!("failed to resolve {host}: {e}"))?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            return Err(format!("failed to resolve {host}"));
+        }
+        if !self.private_host_allowed(&host) {
+            if let Some(addr) = addrs.iter().find(|a| is_internal_ip(a.ip())) {
+                return Err(format!(
+                    "egress policy: {host} resolves to internal address {} (private networks are blocked)",
+                    addr.ip()
+                ));
+            }
+        }
+        Ok(addrs)
+    }
+
+    /// Parse and validate a URL string; bare hosts ("example.com/path") are treated as https.
+    pub async fn check_url_str(&self, url: &str) -> Result<Url, String> {
+        let url = url.trim();
+        let parsed = if url.contains("://") {
+            Url::parse(url)
+        } else {
+            Url::parse(&format!("https://{url}"))
+        }
+        .map_err(|e| format!("invalid URL {url}: {e}"))?;
+        self.check_url(&parsed).await?;
+        Ok(parsed)
+    }
+
+    /// GET `url` under the policy, following up to 5 redirects (each hop is checked).
+    pub async fn get(&self, url: &str, timeout: Duration) -> Result<reqwest::Response, String> {
+        let mut current = Url::parse(url.trim()).map_err(|e| format!("invalid URL: {e}"))?;
+        for _ in 0..=MAX_REDIRECTS {
+            let addrs = self.check_url(&current).await?;
+            let mut builder = reqwest::Client::builder()
+                .timeout(timeout)
+                .redirect(reqwest::redirect::Policy::none());
+            if let Some(host) = current.host_str().filter(|h| h.parse::<IpAddr>().is_err()) {
+                builder = builder.resolve_to_addrs(host, &addrs);
+            }
+            let client = builder.build().map_err(|e| e.to_string())?;
+            let resp = client
+                .get(current.clone())
+                .header("User-Agent", "MicroClaw/1.0")
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !resp.status().is_redirection() {
+                return Ok(resp);
+            }
+            let location = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| format!("HTTP {} without Location header", resp.status()))?;
+            current = current
+                .join(location)
+                .map_err(|e| format!("invalid redirect location: {e}"))?;
+        }
+        Err(format!("too many redirects (max {MAX_REDIRECTS})"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_internal_ip() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.1.10",
+            "172.16.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:192.168.0.1",
+        ] {
+            assert!(is_internal_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(!is_internal_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn test_check_host_lists() {
+        let policy = EgressPolicy::new(EgressConfig {
+            allow_domains: vec!["example.com".into(), "*.wikipedia.org".into()],
+            deny_domains: vec!["bad.example.com".into()],
+            ..Default::default()
+        });
+        assert!(policy.check_host("example.com").is_ok());
+        assert!(policy.check_host("en.wikipedia.org").is_ok());
+        assert!(policy.check_host("bad.example.com").is_err());
+        assert!(policy.check_host("x.bad.example.com").is_err());
+        assert!(policy.check_host("notexample.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_url_blocks_private_addresses() {
+        let policy = EgressPolicy::default();
+        for url in [
+            "http://127.0.0.1:8123/api",
+            "http://192.168.1.1/",
+            "http://[::1]/",
+            "http://localhost/",
+        ] {
+            let err = policy.check_url_str(url).await.unwrap_err();
+            assert!(err.contains("egress policy"), "{url}: {err}");
+        }
+        assert!(policy.check_url_str("file:///etc/passwd").await.is_err());
+
+        let policy = EgressPolicy::new(EgressConfig {
+            allow_private_hosts: vec!["127.0.0.1".into()],
+            ..Default::default()
+        });
+        assert!(policy.check_url_str("http://127.0.0.1:8123/").await.is_ok());
+        assert!(policy.check_url_str("http://10.0.0.1/").await.is_err());
+    }
+}
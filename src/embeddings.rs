@@ -0,0 +1,367 @@
+//! Embedding client for the vault: texts are sent to the embedding server in batches of
+//! `embedding_batch_size`, and every vector is cached on disk (`<runtime>/embedding_cache.db`)
+//! under a hash of the model name and the text, so unchanged notes are never embedded twice.
+//!
+//! The server is a llama.cpp `/embedding` endpoint, or an OpenAI-compatible `/embeddings`
+//! endpoint when the configured URL ends in `/v1`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection};
+use serde_json::json;
+
+use crate::config::Config;
+
+pub const DEFAULT_BATCH_SIZE: usize = 32;
+const CACHE_FILE: &str = "embedding_cache.db";
+
+/// Cache key of a text for a model (SHA-256, hex).
+pub fn cache_key(model: &str, text: &str) -> String {
+    let mut input = Vec::with_capacity(model.len() + text.len() + 1);
+    input.extend_from_slice(model.as_bytes());
+    input.push(0);
+    input.extend_from_slice(text.as_bytes());
+    ring::digest::digest(&ring::digest::SHA256, &input)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+/// Vectors by cache key, in a SQLite file of their own so the main database stays small.
+pub struct EmbeddingCache {
+    conn: Mutex<Connection>,
+}
+
+impl EmbeddingCache {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let conn = Connection::open(path).map_err(|e| format!("Embedding cache: {e}"))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS embedding_cache (
+                key TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+        )
+        .map_err(|e| format!("Embedding cache: {e}"))?;
+        Ok(EmbeddingCache {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn get_many(&self, keys: &[String]) -> HashMap<String, Vec<f32>> {
+        let conn = self.conn.lock().unwrap();
+        let Ok(mut stmt) = conn.prepare_cached("SELECT vector FROM embedding_cache WHERE key = ?1")
+        else {
+            return HashMap::new();
+        };
+        keys.iter()
+            .filter_map(|key| {
+                let bytes: Vec<u8> = stmt.query_row(params![key], |row| row.get(0)).ok()?;
+                Some((key.clone(), decode_vector(&bytes)))
+            })
+            .collect()
+    }
+
+    pub fn put_many(&self, model: &str, entries: &[(String, Vec<f32>)]) -> Result<(), String> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for (key, vector) in entries {
+            tx.execute(
+                "INSERT OR REPLACE INTO embedding_cache (key, model, vector, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![key, model, encode_vector(vector), now],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM embedding_cache", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map(|n| n as usize)
+        .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How an `embed` call was served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbedStats {
+    pub cached: usize,
+    pub embedded: usize,
+    pub requests: usize,
+}
+
+/// Vectors from an embedding-server response, in input order. Accepts llama.cpp (`[{index,
+/// embedding}]` or a single `{embedding}`, flat or nested) and OpenAI (`{data: [...]}`) shapes.
+pub fn parse_embedding_response(
+    value: &serde_json::Value,
+    expected: usize,
+) -> Result<Vec<Vec<f32>>, String> {
+    fn vector(v: &serde_json::Value) -> Option<Vec<f32>> {
+        let arr = v.as_array()?;
+        // llama.cpp may nest the vector once: [[...]]
+        let arr = match arr.first().and_then(|f| f.as_array()) {
+            Some(inner) => inner,
+            None => arr,
+        };
+        arr.iter().map(|x| x.as_f64().map(|f| f as f32)).collect()
+    }
+    let items: Vec<&serde_json::Value> =
+        if let Some(data) = value.get("data").and_then(|d| d.as_array()) {
+            data.iter().collect()
+        } else if let Some(list) = value.as_array() {
+            list.iter().collect()
+        } else if value.get("embedding").is_some() {
+            vec![value]
+        } else {
+            return Err("Unexpected embedding response format (no 'embedding' or 'data')".into());
+        };
+    let mut indexed: Vec<(usize, Vec<f32>)> = Vec::with_capacity(items.len());
+    for (position, item) in items.into_iter().enumerate() {
+        let index = item
+            .get("index")
+            .and_then(|i| i.as_u64())
+            .map(|i| i as usize)
+            .unwrap_or(position);
+        let v = item
+            .get("embedding")
+            .and_then(vector)
+            .filter(|v| !v.is_empty())
+            .ok_or("Embedding server returned an empty or malformed vector")?;
+        indexed.push((index, v));
+    }
+    if indexed.len() != expected {
+        return Err(format!(
+            "Embedding server returned {} vectors for {expected} inputs",
+            indexed.len()
+        ));
+    }
+    indexed.sort_by_key(|(i, _)| *i);
+    Ok(indexed.into_iter().map(|(_, v)| v).collect())
+}
+
+pub struct Embedder {
+    http: reqwest::Client,
+    url: String,
+    model: String,
+    batch_size: usize,
+    cache: Option<Arc<EmbeddingCache>>,
+}
+
+impl Embedder {
+    pub fn new(
+        url: &str,
+        model: &str,
+        batch_size: usize,
+        cache: Option<Arc<EmbeddingCache>>,
+    ) -> Self {
+        Embedder {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            batch_size: batch_size.max(1),
+            cache,
+        }
+    }
+
+    /// The vault's embedder, with the on-disk cache unless `embedding_cache: false`.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let vault = config.vault.as_ref()?;
+        let url = vault
+            .embedding_server_url
+            .as_deref()
+            .filter(|u| !u.trim().is_empty())?;
+        let cache = if vault.embedding_cache.unwrap_or(true) {
+            let path = Path::new(&config.runtime_data_dir()).join(CACHE_FILE);
+            match EmbeddingCache::open(&path) {
+                Ok(cache) => Some(Arc::new(cache)),
+                Err(e) => {
+                    tracing::warn!("{e}; embedding without a cache");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        Some(Embedder::new(
+            url.trim(),
+            vault.embedding_model.as_deref().unwrap_or("default"),
+            vault.embedding_batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            cache,
+        ))
+    }
+
+    async fn request_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, String> {
+        let (url, body) = if self.url.ends_with("/v1") {
+            (
+                format!("{}/embeddings", self.url),
+                json!({"model": self.model, "input": texts}),
+            )
+        } else {
+            (format!("{}/embedding", self.url), json!({"content": texts}))
+        };
+        let response = self
+            .http
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Embedding server unreachable: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Embedding server returned {status}: {body}"));
+        }
+        let value: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse embedding response: {e}"))?;
+        parse_embedding_response(&value, texts.len())
+    }
+
+    /// Embed `texts` (in order): cached vectors are reused, the rest are requested in batches.
+    pub async fn embed(&self, texts: &[String]) -> Result<(Vec<Vec<f32>>, EmbedStats), String> {
+        let keys: Vec<String> = texts.iter().map(|t| cache_key(&self.model, t)).collect();
+        let mut found = match &self.cache {
+            Some(cache) => {
+                let (cache, lookup) = (cache.clone(), keys.clone());
+                tokio::task::spawn_blocking(move || cache.get_many(&lookup))
+                    .await
+                    .unwrap_or_default()
+            }
+            None => HashMap::new(),
+        };
+        let mut stats = EmbedStats {
+            cached: keys.iter().filter(|k| found.contains_key(*k)).count(),
+            ..Default::default()
+        };
+
+        // Each distinct missing text is embedded once
+        let mut missing: Vec<(&String, &str)> = Vec::new();
+        for (key, text) in keys.iter().zip(texts) {
+            if !found.contains_key(key) && !missing.iter().any(|(k, _)| *k == key) {
+                missing.push((key, text));
+            }
+        }
+        for batch in missing.chunks(self.batch_size) {
+            let inputs: Vec<&str> = batch.iter().map(|(_, t)| *t).collect();
+            let vectors = self.request_batch(&inputs).await?;
+            stats.requests += 1;
+            stats.embedded += vectors.len();
+            let entries: Vec<(String, Vec<f32>)> = batch
+                .iter()
+                .map(|(k, _)| (*k).clone())
+                .zip(vectors)
+                .collect();
+            if let Some(cache) = &self.cache {
+                let (cache, model, to_store) = (cache.clone(), self.model.clone(), entries.clone());
+                match tokio::task::spawn_blocking(move || cache.put_many(&model, &to_store)).await {
+                    Ok(Err(e)) => tracing::warn!("Embedding cache write failed: {e}"),
+                    Err(e) => tracing::warn!("Embedding cache write failed: {e}"),
+                    Ok(Ok(())) => {}
+                }
+            }
+            found.extend(entries);
+        }
+
+        let vectors = keys
+            .iter()
+            .map(|k| found.get(k).cloned().ok_or("Missing embedding".to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((vectors, stats))
+    }
+
+    pub async fn embed_one(&self, text: &str) -> Result<Vec<f32>, String> {
+        let (mut vectors, _) = self.embed(&[text.to_string()]).await?;
+        vectors.pop().ok_or("Missing embedding".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_embedding_response_shapes() {
+        let llama = json!([
+            {"index": 1, "embedding": [[0.5, 0.25]]},
+            {"index": 0, "embedding": [[1.0, 0.0]]}
+        ]);
+        assert_eq!(
+            parse_embedding_response(&llama, 2).unwrap(),
+            vec![vec![1.0, 0.0], vec![0.5, 0.25]]
+        );
+        let single = json!({"embedding": [0.1, 0.2]});
+        assert_eq!(
+            parse_embedding_response(&single, 1).unwrap(),
+            vec![vec![0.1f32, 0.2]]
+        );
+        let openai = json!({"data": [{"index": 0, "embedding": [3.0]}]});
+        assert_eq!(
+            parse_embedding_response(&openai, 1).unwrap(),
+            vec![vec![3.0]]
+        );
+        assert!(parse_embedding_response(&openai, 2).is_err());
+        assert!(parse_embedding_response(&json!({"error": "x"}), 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_embed_uses_cache_before_server() {
+        let dir = std::env::temp_dir().join(format!("microclaw_embed_{}", uuid::Uuid::new_v4()));
+        let cache = Arc::new(EmbeddingCache::open(&dir.join(CACHE_FILE)).unwrap());
+        let texts = vec!["alpha".to_string(), "beta".to_string(), "alpha".to_string()];
+        cache
+            .put_many(
+                "m",
+                &[
+                    (cache_key("m", "alpha"), vec![1.0, 2.0]),
+                    (cache_key("m", "beta"), vec![3.0]),
+                ],
+            )
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_ne!(cache_key("m", "alpha"), cache_key("other", "alpha"));
+
+        // Everything is cached, so the (unreachable) server is never called
+        let embedder = Embedder::new("http://127.0.0.1:9", "m", 2, Some(cache));
+        let (vectors, stats) = embedder.embed(&texts).await.unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 2.0], vec![3.0], vec![1.0, 2.0]]);
+        assert_eq!(
+            stats,
+            EmbedStats {
+                cached: 3,
+                embedded: 0,
+                requests: 0
+            }
+        );
+        // A new text has to be requested
+        assert!(embedder.embed(&["gamma".to_string()]).await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -6,6 +6,10 @@ pub enum MicroClawError {
     #[error("LLM API error: {0}")]
     LlmApi(String),
 
+    /// Non-success HTTP response from the provider (kept apart for failover decisions).
+    #[error("LLM API error: {message}")]
+    LlmHttp { status: u16, message: String },
+
     #[error("Rate limited, retry after backoff")]
     RateLimited,
 
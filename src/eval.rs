@@ -0,0 +1,619 @@
+//! `microclaw eval`: runs a YAML suite of scenario prompts against the current config and system
+//! prompt with mocked tools (no tool is ever executed), scores each reply with assertions and/or
+//! an LLM judge, and writes a markdown report. Exits non-zero when any scenario fails, so prompt
+//! and skill changes can be checked before deploying.
+//!
+//! ```yaml
+//! name: smoke
+//! scenarios:
+//!   - name: weather uses search
+//!     prompt: "What's the weather in Paris?"
+//!     mock_tools:
+//!       web_search: "Paris: 18°C, light rain"
+//!     assert:
+//!       tools_called: [web_search]
+//!       contains: ["18"]
+//!     judge: "Mentions the rain and stays under three sentences."
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::claude::{ContentBlock, Message, MessageContent, ResponseContentBlock, ToolDefinition};
+use crate::config::Config;
+use crate::llm::LlmProvider;
+
+const DEFAULT_MAX_ITERATIONS: usize = 10;
+/// Chat id used in the system prompt for eval runs (never a real chat).
+const EVAL_CHAT_ID: i64 = 0;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EvalSuite {
+    #[serde(default)]
+    pub name: String,
+    /// Model for the LLM judge. None = configured model.
+    #[serde(default)]
+    pub judge_model: Option<String>,
+    /// Max LLM rounds per scenario (default 10).
+    #[serde(default)]
+    pub max_iterations: Option<usize>,
+    pub scenarios: Vec<EvalScenario>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EvalScenario {
+    pub name: String,
+    pub prompt: String,
+    /// Earlier turns, oldest first.
+    #[serde(default)]
+    pub history: Vec<EvalTurn>,
+    /// Canned tool results by tool name. Unmocked tool calls get a "not available" result.
+    #[serde(default)]
+    pub mock_tools: HashMap<String, String>,
+    #[serde(default, rename = "assert")]
+    pub assertions: EvalAssertions,
+    /// Criteria for the LLM judge; the scenario fails when the judge says they are not met.
+    #[serde(default)]
+    pub judge: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EvalTurn {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EvalAssertions {
+    /// Substrings the reply must contain (case-insensitive).
+    #[serde(default)]
+    pub contains: Vec<String>,
+    /// Substrings the reply must not contain (case-insensitive).
+    #[serde(default)]
+    pub not_contains: Vec<String>,
+    /// Regexes the reply must match.
+    #[serde(default)]
+    pub regex: Vec<String>,
+    #[serde(default)]
+    pub tools_called: Vec<String>,
+    #[serde(default)]
+    pub tools_not_called: Vec<String>,
+    #[serde(default)]
+    pub max_tool_calls: Option<usize>,
+}
+
+/// What the agent did in one scenario.
+#[derive(Clone, Debug, Default)]
+pub struct Transcript {
+    pub reply: String,
+    pub tool_calls: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ScenarioResult {
+    pub name: String,
+    pub transcript: Transcript,
+    pub failures: Vec<String>,
+    pub judge_note: Option<String>,
+}
+
+impl ScenarioResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+pub fn load_suite(path: &Path) -> Result<EvalSuite, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let mut suite: EvalSuite = serde_yaml::from_str(&content)
+        .map_err(|e| format!("invalid eval suite {}: {e}", path.display()))?;
+    if suite.scenarios.is_empty() {
+        return Err(format!("{} has no scenarios", path.display()));
+    }
+    if suite.name.trim().is_empty() {
+        suite.name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "eval".into());
+    }
+    Ok(suite)
+}
+
+/// Assertion failures for a transcript (empty = pass).
+pub fn check_assertions(assertions: &EvalAssertions, transcript: &Transcript) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(e) = &transcript.error {
+        failures.push(format!("agent error: {e}"));
+        return failures;
+    }
+    let reply = transcript.reply.to_lowercase();
+    for s in &assertions.contains {
+        if !reply.contains(&s.to_lowercase()) {
+            failures.push(format!("reply does not contain {s:?}"));
+        }
+    }
+    for s in &assertions.not_contains {
+        if reply.contains(&s.to_lowercase()) {
+            failures.push(format!("reply contains {s:?}"));
+        }
+    }
+    for pattern in &assertions.regex {
+        match Regex::new(pattern) {
+            Ok(re) if re.is_match(&transcript.reply) => {}
+            Ok(_) => failures.push(format!("reply does not match /{pattern}/")),
+            Err(e) => failures.push(format!("invalid regex /{pattern}/: {e}")),
+        }
+    }
+    for tool in &assertions.tools_called {
+        if !transcript.tool_calls.contains(tool) {
+            failures.push(format!("tool {tool} was not called"));
+        }
+    }
+    for tool in &assertions.tools_not_called {
+        if transcript.tool_calls.contains(tool) {
+            failures.push(format!("tool {tool} was called"));
+        }
+    }
+    if let Some(max) = assertions.max_tool_calls {
+        if transcript.tool_calls.len() > max {
+            failures.push(format!(
+                "{} tool calls (max {max})",
+                transcript.tool_calls.len()
+            ));
+        }
+    }
+    failures
+}
+
+fn response_text(content: &[ResponseContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|b| match b {
+            ResponseContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Run one scenario through the tool loop, answering tool calls from `mock_tools`.
+pub async fn run_scenario(
+    llm: &dyn LlmProvider,
+    system_prompt: &str,
+    tool_defs: &[ToolDefinition],
+    scenario: &EvalScenario,
+    max_iterations: usize,
+) -> Transcript {
+    let mut messages: Vec<Message> = scenario
+        .history
+        .iter()
+        .map(|t| Message {
+            role: if t.role == "assistant" {
+                "assistant"
+            } else {
+                "user"
+            }
+            .into(),
+            content: MessageContent::Text(t.content.clone()),
+        })
+        .collect();
+    messages.push(Message {
+        role: "user".into(),
+        content: MessageContent::Text(scenario.prompt.clone()),
+    });
+    let mut transcript = Transcript::default();
+
+    for _ in 0..max_iterations {
+        let response = match llm
+            .send_message(system_prompt, messages.clone(), Some(tool_defs.to_vec()))
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                transcript.error = Some(e.to_string());
+                return transcript;
+            }
+        };
+        if response.stop_reason.as_deref() != Some("tool_use") {
+            transcript.reply = crate::telegram::strip_thinking(&response_text(&response.content));
+            return transcript;
+        }
+
+        let mut assistant_content = Vec::new();
+        let mut results = Vec::new();
+        for block in &response.content {
+            match block {
+                ResponseContentBlock::Text { text } => {
+                    assistant_content.push(ContentBlock::Text { text: text.clone() })
+                }
+                ResponseContentBlock::ToolUse {
+                    id,
+                    name,
+                    input,
+                    thought_signature,
+                } => {
+                    assistant_content.push(ContentBlock::ToolUse {
+                        id: id.clone(),
+                        name: name.clone(),
+                        input: input.clone(),
+                        thought_signature: thought_signature.clone(),
+                    });
+                    transcript.tool_calls.push(name.clone());
+                    let (content, is_error) = match scenario.mock_tools.get(name) {
+                        Some(result) => (result.clone(), None),
+                        None => (
+                            format!("Tool {name} is not available in this environment."),
+                            Some(true),
+                        ),
+                    };
+                    results.push(ContentBlock::ToolResult {
+                        tool_use_id: id.clone(),
+                        content,
+                        is_error,
+                    });
+                }
+            }
+        }
+        messages.push(Message {
+            role: "assistant".into(),
+            content: MessageContent::Blocks(assistant_content),
+        });
+        messages.push(Message {
+            role: "user".into(),
+            content: MessageContent::Blocks(results),
+        });
+    }
+    transcript.error = Some(format!("no final reply after {max_iterations} rounds"));
+    transcript
+}
+
+/// Ask the judge whether `reply` meets `criteria`. Returns (pass, reason).
+pub async fn judge_reply(
+    llm: &dyn LlmProvider,
+    criteria: &str,
+    prompt: &str,
+    reply: &str,
+) -> Result<(bool, String), String> {
+    let system = "You grade an assistant's reply against criteria. Answer with only a JSON object: {\"pass\": true|false, \"reason\": \"one sentence\"}.";
+    let question =
+        format!("Criteria:\n{criteria}\n\nUser message:\n{prompt}\n\nAssistant reply:\n{reply}");
+    let response = llm
+        .send_message(
+            system,
+            vec![Message {
+                role: "user".into(),
+                content: MessageContent::Text(question),
+            }],
+            None,
+        )
+        .await
+        .map_err(|e| format!("judge request failed: {e}"))?;
+    parse_verdict(&response_text(&response.content))
+}
+
+fn parse_verdict(text: &str) -> Result<(bool, String), String> {
+    let start = text.find('{');
+    let end = text.rfind('}');
+    let (Some(start), Some(end)) = (start, end) else {
+        return Err(format!("judge did not return JSON: {}", text.trim()));
+    };
+    let value: serde_json::Value = serde_json::from_str(&text[start..=end])
+        .map_err(|e| format!("judge returned invalid JSON: {e}"))?;
+    let pass = value
+        .get("pass")
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| "judge verdict has no \"pass\" field".to_string())?;
+    let reason = value
+        .get("reason")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    Ok((pass, reason))
+}
+
+/// Run every scenario in the suite.
+pub async fn run_suite(
+    suite: &EvalSuite,
+    llm: &dyn LlmProvider,
+    judge: &dyn LlmProvider,
+    system_prompt: &str,
+    tool_defs: &[ToolDefinition],
+) -> Vec<ScenarioResult> {
+    let max_iterations = suite
+        .max_iterations
+        .unwrap_or(DEFAULT_MAX_ITERATIONS)
+        .max(1);
+    let mut results = Vec::new();
+    for scenario in &suite.scenarios {
+        let transcript =
+            run_scenario(llm, system_prompt, tool_defs, scenario, max_iterations).await;
+        let mut failures = check_assertions(&scenario.assertions, &transcript);
+        let mut judge_note = None;
+        if let Some(criteria) = scenario.judge.as_deref().filter(|c| !c.trim().is_empty()) {
+            if transcript.error.is_none() {
+                match judge_reply(judge, criteria, &scenario.prompt, &transcript.reply).await {
+                    Ok((true, reason)) => judge_note = Some(reason),
+                    Ok((false, reason)) => failures.push(format!("judge: {reason}")),
+                    Err(e) => failures.push(e),
+                }
+            }
+        }
+        results.push(ScenarioResult {
+            name: scenario.name.clone(),
+            transcript,
+            failures,
+            judge_note,
+        });
+    }
+    results
+}
+
+/// Markdown report for a finished suite.
+pub fn render_report(suite: &EvalSuite, model: &str, results: &[ScenarioResult]) -> String {
+    let passed = results.iter().filter(|r| r.passed()).count();
+    let mut out = format!(
+        "# Eval: {}\n\n- Model: {model}\n- Run at: {}\n- Result: {passed}/{} passed\n",
+        suite.name,
+        chrono::Utc::now().to_rfc3339(),
+        results.len()
+    );
+    for r in results {
+        out.push_str(&format!(
+            "\n## {} {}\n\n",
+            if r.passed() { "PASS" } else { "FAIL" },
+            r.name
+        ));
+        for f in &r.failures {
+            out.push_str(&format!("- {f}\n"));
+        }
+        if let Some(note) = r.judge_note.as_deref().filter(|n| !n.is_empty()) {
+            out.push_str(&format!("- judge: {note}\n"));
+        }
+        if !r.transcript.tool_calls.is_empty() {
+            out.push_str(&format!(
+                "- tools: {}\n",
+                r.transcript.tool_calls.join(", ")
+            ));
+        }
+        out.push_str(&format!("\n```\n{}\n```\n", r.transcript.reply.trim()));
+    }
+    out
+}
+
+fn default_report_path(config: &Config, suite: &EvalSuite) -> PathBuf {
+    let safe: String = suite
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    PathBuf::from(config.runtime_data_dir())
+        .join("evals")
+        .join(format!(
+            "{safe}-{}.md",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+}
+
+/// The production system prompt for a neutral chat (no chat memory), as eval runs see it.
+pub fn eval_system_prompt(
+    config: &Config,
+    memory: &crate::memory::MemoryManager,
+    skills: &crate::skills::SkillManager,
+) -> String {
+    let tz: chrono_tz::Tz = config.timezone.parse().unwrap_or(chrono_tz::Tz::UTC);
+    crate::telegram::build_system_prompt(
+        &config.bot_username,
+        &memory.read_groups_root_memory().unwrap_or_default(),
+        &memory.groups_root_memory_path_display(),
+        "",
+        EVAL_CHAT_ID,
+        0,
+        &skills.build_skills_catalog(),
+        &Path::new(config.working_dir())
+            .join("shared")
+            .to_string_lossy(),
+        &config.skills_data_dir_absolute().to_string_lossy(),
+        None,
+        &config.timezone,
+        &chrono::Utc::now()
+            .with_timezone(&tz)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+            .to_string(),
+    )
+}
+
+/// Entry point for `microclaw eval <suite.yaml> [--report <path>]`.
+pub async fn run_cli(args: &[String]) -> anyhow::Result<()> {
+    if args.is_empty() || args.iter().any(|a| a == "--help" || a == "-h") {
+        println!(
+            "Usage: microclaw eval <suite.yaml> [--report <path>]\n\nRuns the suite's scenarios against the current config and system prompt with mocked tools, scores replies with assertions and/or an LLM judge, and writes a markdown report (default: <runtime>/evals/). Exits with status 1 when any scenario fails."
+        );
+        return Ok(());
+    }
+    let suite_path = PathBuf::from(&args[0]);
+    let report_arg = args
+        .iter()
+        .position(|a| a == "--report")
+        .and_then(|i| args.get(i + 1))
+        .map(PathBuf::from);
+
+    let config = Config::load()?;
+    let suite = load_suite(&suite_path).map_err(|e| anyhow::anyhow!(e))?;
+
+    // Memory is read from and the report written to the runtime directory
+    let mounted = crate::workspace_crypto::mount(&config)?;
+    let runtime_data_dir = config.runtime_data_dir();
+    let memory = crate::memory::MemoryManager::with_principles_path(
+        &runtime_data_dir,
+        config.working_dir(),
+        config
+            .vault
+            .as_ref()
+            .and_then(|v| v.principles_path.clone()),
+    );
+    let workspace_root = config.workspace_root_absolute();
+    let skills = crate::skills::SkillManager::from_skills_dirs([
+        &workspace_root.join("skills"),
+        &workspace_root.join("shared").join("skills"),
+    ]);
+    let system_prompt = eval_system_prompt(&config, &memory, &skills);
+
+    // Real tool definitions (so the model sees the production tool list); execution is mocked.
+    let scratch = std::env::temp_dir().join(format!("microclaw-eval-{}", uuid::Uuid::new_v4()));
+    let db = Arc::new(crate::db::Database::new(&scratch.to_string_lossy())?);
+    let tool_defs =
+        crate::tools::ToolRegistry::new(&config, teloxide::Bot::new("eval"), db).definitions();
+
+    let llm = crate::llm::create_provider(&config);
+    let judge_llm = suite.judge_model.as_ref().map(|model| {
+        let mut judge_config = config.clone();
+        judge_config.model = model.clone();
+        crate::llm::create_provider(&judge_config)
+    });
+    let judge = judge_llm.as_deref().unwrap_or(llm.as_ref());
+
+    println!(
+        "Running eval '{}' ({} scenarios) with {} ...",
+        suite.name,
+        suite.scenarios.len(),
+        config.model
+    );
+    let results = run_suite(&suite, llm.as_ref(), judge, &system_prompt, &tool_defs).await;
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    for r in &results {
+        println!("{} {}", if r.passed() { "PASS" } else { "FAIL" }, r.name);
+        for f in &r.failures {
+            println!("    - {f}");
+        }
+    }
+    let report_path = report_arg.unwrap_or_else(|| default_report_path(&config, &suite));
+    if let Some(parent) = report_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&report_path, render_report(&suite, &config.model, &results))?;
+    if mounted {
+        crate::workspace_crypto::unmount(&config, None)?;
+    }
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    println!(
+        "{}/{} passed. Report: {}",
+        results.len() - failed,
+        results.len(),
+        report_path.display()
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::MessagesResponse;
+    use crate::error::MicroClawError;
+    use std::sync::Mutex;
+
+    /// Replays scripted responses in order.
+    struct ScriptedLlm {
+        responses: Mutex<Vec<MessagesResponse>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for ScriptedLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            Ok(self.responses.lock().unwrap().remove(0))
+        }
+    }
+
+    fn text(t: &str) -> MessagesResponse {
+        MessagesResponse {
+            content: vec![ResponseContentBlock::Text { text: t.into() }],
+            stop_reason: Some("end_turn".into()),
+            usage: None,
+        }
+    }
+
+    fn tool_use(name: &str) -> MessagesResponse {
+        MessagesResponse {
+            content: vec![ResponseContentBlock::ToolUse {
+                id: "t1".into(),
+                name: name.into(),
+                input: serde_json::json!({}),
+                thought_signature: None,
+            }],
+            stop_reason: Some("tool_use".into()),
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_suite_yaml_and_assertions() {
+        let suite: EvalSuite = serde_yaml::from_str(
+            "scenarios:\n  - name: s\n    prompt: hi\n    assert:\n      contains: [Hello]\n      tools_not_called: [bash]\n      max_tool_calls: 1\n",
+        )
+        .unwrap();
+        let a = &suite.scenarios[0].assertions;
+        let ok = Transcript {
+            reply: "hello there".into(),
+            ..Default::default()
+        };
+        assert!(check_assertions(a, &ok).is_empty());
+        let bad = Transcript {
+            reply: "hi".into(),
+            tool_calls: vec!["bash".into(), "glob".into()],
+            error: None,
+        };
+        assert_eq!(check_assertions(a, &bad).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_verdict() {
+        assert_eq!(
+            parse_verdict("Sure: {\"pass\": false, \"reason\": \"too long\"}").unwrap(),
+            (false, "too long".to_string())
+        );
+        assert!(parse_verdict("yes").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_suite_mocks_tools_and_uses_judge() {
+        let suite: EvalSuite = serde_yaml::from_str(
+            "name: smoke\nscenarios:\n  - name: weather\n    prompt: weather?\n    mock_tools:\n      web_search: \"18C\"\n    assert:\n      tools_called: [web_search]\n      contains: [\"18\"]\n    judge: short\n",
+        )
+        .unwrap();
+        let llm = ScriptedLlm {
+            responses: Mutex::new(vec![tool_use("web_search"), text("It is 18C.")]),
+        };
+        let judge = ScriptedLlm {
+            responses: Mutex::new(vec![text("{\"pass\": true, \"reason\": \"brief\"}")]),
+        };
+        let results = run_suite(&suite, &llm, &judge, "sys", &[]).await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].passed(), "{:?}", results[0].failures);
+        assert_eq!(
+            results[0].transcript.tool_calls,
+            vec!["web_search".to_string()]
+        );
+        let report = render_report(&suite, "m", &results);
+        assert!(report.contains("1/1 passed") && report.contains("## PASS weather"));
+    }
+}
@@ -0,0 +1,308 @@
+//! A/B prompt experiments: enrolled (consenting) chats have a share of their turns routed
+//! through an alternate system prompt and/or model. Each enrolled turn is recorded in
+//! `experiment_runs` with its run length, outcome, later user feedback (reactions) and whether
+//! the user asked for a retry, so variants can be compared with the `experiments_report` tool.
+
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::claude::{ContentBlock, Message, MessageContent};
+use crate::config::Config;
+use crate::db::{call_blocking, Database, ExperimentRun, ExperimentVariantStats};
+use crate::usage::TurnUsage;
+
+pub const CONTROL: &str = "control";
+pub const TREATMENT: &str = "treatment";
+
+/// Reactions count as feedback on the latest enrolled reply in the chat within this window.
+const FEEDBACK_WINDOW_HOURS: i64 = 24;
+/// A follow-up asking for a retry within this window marks the previous run as retried.
+const RETRY_WINDOW_MINUTES: i64 = 10;
+
+const RETRY_PHRASES: &[&str] = &[
+    "try again",
+    "retry",
+    "redo",
+    "that's wrong",
+    "thats wrong",
+    "that is wrong",
+    "that's not what",
+    "not what i asked",
+    "not what i meant",
+    "you misunderstood",
+    "wrong answer",
+];
+
+const POSITIVE_REACTIONS: &[&str] = &[
+    "👍", "❤", "❤️", "🔥", "🥰", "👏", "🎉", "🤩", "🙏", "👌", "😍", "💯", "🏆", "🤝", "✅",
+];
+const NEGATIVE_REACTIONS: &[&str] = &["👎", "🤮", "💩", "🤬", "😡", "💔", "🤡", "🥱", "❌"];
+
+/// Variant chosen for one turn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Assignment {
+    pub experiment: String,
+    pub variant: &'static str,
+    pub system_prompt_append: Option<String>,
+    pub model: Option<String>,
+}
+
+impl Assignment {
+    pub fn is_treatment(&self) -> bool {
+        self.variant == TREATMENT
+    }
+}
+
+/// Assign a turn in `chat_id` given a roll in 0..100. Chats not listed by any experiment are
+/// never enrolled.
+pub fn assign_with_roll(config: &Config, chat_id: i64, roll: u8) -> Option<Assignment> {
+    let exp = config
+        .experiments
+        .iter()
+        .find(|e| !e.name.trim().is_empty() && e.chat_ids.contains(&chat_id))?;
+    if roll < exp.percent.min(100) {
+        Some(Assignment {
+            experiment: exp.name.trim().to_string(),
+            variant: TREATMENT,
+            system_prompt_append: exp
+                .system_prompt_append
+                .clone()
+                .filter(|s| !s.trim().is_empty()),
+            model: exp.model.clone().filter(|m| !m.trim().is_empty()),
+        })
+    } else {
+        Some(Assignment {
+            experiment: exp.name.trim().to_string(),
+            variant: CONTROL,
+            system_prompt_append: None,
+            model: None,
+        })
+    }
+}
+
+pub fn assign(config: &Config, chat_id: i64) -> Option<Assignment> {
+    if config.experiments.is_empty() {
+        return None;
+    }
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    let roll = (u16::from_be_bytes([bytes[0], bytes[1]]) % 100) as u8;
+    assign_with_roll(config, chat_id, roll)
+}
+
+/// Whether a user message asks to redo the previous answer.
+pub fn is_retry_request(text: &str) -> bool {
+    let lower = text.to_lowercase().replace('’', "'");
+    RETRY_PHRASES.iter().any(|p| lower.contains(p))
+}
+
+/// Feedback score for a reaction emoji: 1, -1, or None when neutral.
+pub fn reaction_score(emoji: &str) -> Option<i64> {
+    let emoji = emoji.trim();
+    if POSITIVE_REACTIONS.contains(&emoji) {
+        Some(1)
+    } else if NEGATIVE_REACTIONS.contains(&emoji) {
+        Some(-1)
+    } else {
+        None
+    }
+}
+
+/// Text of the latest user message in a conversation.
+pub fn last_user_text(messages: &[Message]) -> Option<&str> {
+    messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .and_then(|m| match &m.content {
+            MessageContent::Text(t) => Some(t.as_str()),
+            MessageContent::Blocks(blocks) => blocks.iter().find_map(|b| match b {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            }),
+        })
+}
+
+/// Mark the chat's latest run as retried when the new user message asks for a retry.
+pub async fn note_user_message(db: Arc<Database>, chat_id: i64, text: &str) {
+    if !is_retry_request(text) {
+        return;
+    }
+    let since = (chrono::Utc::now() - chrono::Duration::minutes(RETRY_WINDOW_MINUTES)).to_rfc3339();
+    let result = call_blocking(db, move |d| {
+        if let Some(id) = d.latest_experiment_run(chat_id, &since)? {
+            d.mark_experiment_retried(id)?;
+        }
+        Ok(())
+    })
+    .await;
+    if let Err(e) = result {
+        warn!("experiments: failed to record retry for chat {chat_id}: {e}");
+    }
+}
+
+/// Record reaction feedback on the chat's latest enrolled reply. Returns true when a run was
+/// updated.
+pub async fn record_reaction(db: Arc<Database>, chat_id: i64, emoji: &str) -> bool {
+    let Some(score) = reaction_score(emoji) else {
+        return false;
+    };
+    let since = (chrono::Utc::now() - chrono::Duration::hours(FEEDBACK_WINDOW_HOURS)).to_rfc3339();
+    let result = call_blocking(db, move |d| {
+        let Some(id) = d.latest_experiment_run(chat_id, &since)? else {
+            return Ok(false);
+        };
+        d.set_experiment_feedback(id, score)?;
+        Ok(true)
+    })
+    .await;
+    result.unwrap_or_else(|e| {
+        warn!("experiments: failed to record feedback for chat {chat_id}: {e}");
+        false
+    })
+}
+
+/// Record an enrolled turn.
+pub async fn record_run(
+    db: Arc<Database>,
+    assignment: &Assignment,
+    chat_id: i64,
+    persona_id: i64,
+    usage: &TurnUsage,
+    iterations: u64,
+    outcome: &str,
+) {
+    let run = ExperimentRun {
+        experiment: assignment.experiment.clone(),
+        variant: assignment.variant.to_string(),
+        chat_id,
+        persona_id,
+        model: usage.model.clone(),
+        iterations,
+        tool_calls: usage.tool_calls,
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        duration_ms: usage.duration_ms,
+        outcome: outcome.to_string(),
+    };
+    if let Err(e) = call_blocking(db, move |d| d.record_experiment_run(&run)).await {
+        warn!("experiments: failed to record run for chat {chat_id}: {e}");
+    }
+}
+
+fn rate(n: u64, total: u64) -> String {
+    if total == 0 {
+        "-".into()
+    } else {
+        format!("{:.0}%", n as f64 * 100.0 / total as f64)
+    }
+}
+
+/// Side-by-side comparison of an experiment's variants.
+pub fn format_report(experiment: &str, stats: &[ExperimentVariantStats]) -> String {
+    if stats.is_empty() {
+        return format!("No runs recorded for experiment '{experiment}'.");
+    }
+    let mut lines = vec![format!("Experiment '{experiment}':")];
+    for s in stats {
+        let rated = s.positive + s.negative;
+        lines.push(format!(
+            "- {}: {} runs · avg {:.1} rounds, {:.1} tools, {:.1}s, {:.0} output tokens · \
+             feedback +{} / -{} ({} positive) · retried {} · errors/limits {}",
+            s.variant,
+            s.runs,
+            s.avg_iterations,
+            s.avg_tool_calls,
+            s.avg_duration_ms / 1000.0,
+            s.avg_output_tokens,
+            s.positive,
+            s.negative,
+            rate(s.positive, rated),
+            rate(s.retried, s.runs),
+            rate(s.non_ok, s.runs),
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExperimentConfig;
+
+    fn test_config() -> Config {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.experiments = vec![ExperimentConfig {
+            name: "concise".into(),
+            chat_ids: vec![10],
+            percent: 30,
+            system_prompt_append: Some("Be brief.".into()),
+            model: None,
+        }];
+        config
+    }
+
+    #[test]
+    fn test_assignment_respects_consent_and_percent() {
+        let config = test_config();
+        assert!(assign_with_roll(&config, 11, 0).is_none());
+        let treated = assign_with_roll(&config, 10, 29).unwrap();
+        assert!(treated.is_treatment());
+        assert_eq!(treated.system_prompt_append.as_deref(), Some("Be brief."));
+        let control = assign_with_roll(&config, 10, 30).unwrap();
+        assert_eq!(control.variant, CONTROL);
+        assert!(control.system_prompt_append.is_none());
+    }
+
+    #[test]
+    fn test_retry_and_reaction_heuristics() {
+        assert!(is_retry_request("[alice]: Nope, try again please"));
+        assert!(is_retry_request("That’s not what I asked"));
+        assert!(!is_retry_request("thanks, that works"));
+        assert_eq!(reaction_score("👍"), Some(1));
+        assert_eq!(reaction_score("👎"), Some(-1));
+        assert_eq!(reaction_score("🤔"), None);
+    }
+
+    #[tokio::test]
+    async fn test_runs_feedback_and_report() {
+        let dir = std::env::temp_dir().join(format!("microclaw_exp_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config = test_config();
+        let usage = TurnUsage {
+            model: "m".into(),
+            input_tokens: 100,
+            output_tokens: 50,
+            tool_calls: 2,
+            duration_ms: 1500,
+        };
+        let treated = assign_with_roll(&config, 10, 0).unwrap();
+        let control = assign_with_roll(&config, 10, 99).unwrap();
+        assert!(!record_reaction(db.clone(), 10, "👍").await);
+
+        record_run(db.clone(), &control, 10, 1, &usage, 3, "ok").await;
+        note_user_message(db.clone(), 10, "try again").await;
+        record_run(db.clone(), &treated, 10, 1, &usage, 1, "ok").await;
+        assert!(record_reaction(db.clone(), 10, "👍").await);
+
+        let stats = db.experiment_variant_stats("concise").unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].variant.as_str(), stats[0].retried), (CONTROL, 1));
+        assert_eq!(
+            (stats[1].variant.as_str(), stats[1].positive),
+            (TREATMENT, 1)
+        );
+        let report = format_report("concise", &stats);
+        assert!(
+            report.contains("control: 1 runs · avg 3.0 rounds"),
+            "{report}"
+        );
+        assert!(
+            report.contains("treatment: 1 runs · avg 1.0 rounds"),
+            "{report}"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
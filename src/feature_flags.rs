@@ -0,0 +1,219 @@
+//! Per-chat feature flags, so risky capabilities can be rolled out chat by chat instead of for
+//! everyone at once. `feature_flags` in the config sets each flag's default (on when unset,
+//! except `provider_search`);
+//! per-chat overrides live in the `feature_flags` table and are changed with the
+//! `feature_flags` tool or the web UI.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureFlag {
+    /// Messages the bot starts on its own: feed alerts, scheduled task results, digests.
+    ProactiveMessages,
+    /// Images sent by the user are passed to the model.
+    Vision,
+    WebSearch,
+    SocialTools,
+    /// Web search run by the LLM provider instead of the local `web_search` tool.
+    ProviderSearch,
+}
+
+impl FeatureFlag {
+    pub const ALL: [FeatureFlag; 5] = [
+        FeatureFlag::ProactiveMessages,
+        FeatureFlag::Vision,
+        FeatureFlag::WebSearch,
+        FeatureFlag::SocialTools,
+        FeatureFlag::ProviderSearch,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FeatureFlag::ProactiveMessages => "proactive_messages",
+            FeatureFlag::Vision => "vision",
+            FeatureFlag::WebSearch => "web_search",
+            FeatureFlag::SocialTools => "social_tools",
+            FeatureFlag::ProviderSearch => "provider_search",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name.trim())
+    }
+
+    /// Comma-separated flag names, for error messages.
+    pub fn names() -> String {
+        Self::ALL.map(|f| f.name()).join(", ")
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            FeatureFlag::ProactiveMessages => {
+                "Messages the bot sends on its own (feed alerts, scheduled task results)"
+            }
+            FeatureFlag::Vision => "Images in user messages are shown to the model",
+            FeatureFlag::WebSearch => "web_search and web_fetch tools",
+            FeatureFlag::SocialTools => "Social, Google and Spotify tools",
+            FeatureFlag::ProviderSearch => {
+                "Web search by the model provider (Anthropic, OpenAI) with cited sources, instead of web_search"
+            }
+        }
+    }
+
+    /// Tools hidden from the model while the flag is off.
+    pub fn tools(self) -> &'static [&'static str] {
+        match self {
+            FeatureFlag::ProactiveMessages | FeatureFlag::Vision => &[],
+            FeatureFlag::WebSearch => &["web_search", "web_fetch", "provider_web_search"],
+            FeatureFlag::ProviderSearch => &["provider_web_search"],
+            FeatureFlag::SocialTools => &[
+                "list_connections",
+                "disconnect_platform",
+                "fetch_tiktok_feed",
+                "fetch_instagram_feed",
+                "fetch_linkedin_feed",
+                "search_gmail",
+                "read_email",
+                "search_drive",
+                "fetch_drive_file",
+                "play_music",
+                "pause",
+                "now_playing",
+                "social_report",
+            ],
+        }
+    }
+
+    /// The flag's state when a chat has no override.
+    pub fn default_enabled(self, config: &Config) -> bool {
+        config
+            .feature_flags
+            .get(self.name())
+            .copied()
+            .unwrap_or(self != FeatureFlag::ProviderSearch)
+    }
+}
+
+/// A flag as it applies to one chat.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlagState {
+    pub flag: &'static str,
+    pub description: &'static str,
+    pub enabled: bool,
+    /// Whether the chat overrides the configured default.
+    pub overridden: bool,
+}
+
+/// Every flag for a chat with these overrides (unknown names are ignored).
+pub fn resolve(config: &Config, overrides: &[(String, bool)]) -> Vec<FlagState> {
+    FeatureFlag::ALL
+        .into_iter()
+        .map(|flag| {
+            let chat_value = overrides
+                .iter()
+                .find(|(name, _)| name == flag.name())
+                .map(|(_, enabled)| *enabled);
+            FlagState {
+                flag: flag.name(),
+                description: flag.description(),
+                enabled: chat_value.unwrap_or_else(|| flag.default_enabled(config)),
+                overridden: chat_value.is_some(),
+            }
+        })
+        .collect()
+}
+
+/// Every flag for a chat. Falls back to the defaults when the overrides cannot be read.
+pub async fn chat_flags(config: &Config, db: Arc<Database>, chat_id: i64) -> Vec<FlagState> {
+    let overrides = call_blocking(db, move |d| d.list_feature_flags(chat_id))
+        .await
+        .unwrap_or_default();
+    resolve(config, &overrides)
+}
+
+pub async fn is_enabled(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    flag: FeatureFlag,
+) -> bool {
+    chat_flags(config, db, chat_id)
+        .await
+        .iter()
+        .any(|s| s.flag == flag.name() && s.enabled)
+}
+
+/// Tools switched off for a chat.
+pub fn disabled_tools(flags: &[FlagState]) -> Vec<&'static str> {
+    let mut tools: Vec<&'static str> = Vec::new();
+    for tool in flags
+        .iter()
+        .filter(|s| !s.enabled)
+        .filter_map(|s| FeatureFlag::parse(s.flag))
+        .flat_map(|f| f.tools().iter().copied())
+    {
+        if !tools.contains(&tool) {
+            tools.push(tool);
+        }
+    }
+    tools
+}
+
+/// The flags of a chat as a readable list.
+pub fn format_flags(chat_id: i64, flags: &[FlagState]) -> String {
+    let mut out = format!("Feature flags for chat {chat_id}:");
+    for s in flags {
+        out.push_str(&format!(
+            "\n- {}: {}{} — {}",
+            s.flag,
+            if s.enabled { "on" } else { "off" },
+            if s.overridden { " (chat override)" } else { "" },
+            s.description
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_defaults_and_overrides() {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.feature_flags.insert("web_search".into(), false);
+
+        let flags = resolve(&config, &[]);
+        assert_eq!(flags.len(), FeatureFlag::ALL.len());
+        assert!(flags.iter().all(|s| !s.overridden));
+        assert_eq!(
+            disabled_tools(&flags),
+            vec!["web_search", "web_fetch", "provider_web_search"]
+        );
+
+        let overrides = vec![
+            ("web_search".to_string(), true),
+            ("vision".to_string(), false),
+            ("retired_flag".to_string(), false),
+        ];
+        let flags = resolve(&config, &overrides);
+        // provider_search is off unless turned on
+        assert_eq!(disabled_tools(&flags), vec!["provider_web_search"]);
+        let vision = flags.iter().find(|s| s.flag == "vision").unwrap();
+        assert!(!vision.enabled && vision.overridden);
+        assert!(format_flags(5, &flags).contains("- vision: off (chat override)"));
+
+        assert_eq!(
+            FeatureFlag::parse(" social_tools"),
+            Some(FeatureFlag::SocialTools)
+        );
+        assert_eq!(FeatureFlag::parse("nope"), None);
+    }
+}
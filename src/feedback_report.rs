@@ -0,0 +1,412 @@
+//! Feedback report: mines low-rated replies (negative reactions, recorded in `response_feedback`
+//! against the chat's latest bot reply) and failed runs (scheduled tasks, background jobs,
+//! experiment turns), groups them and suggests system-prompt or skill adjustments for the
+//! operator. The scheduler sends one to the control chats every `feedback_report_days`; the
+//! `feedback_report` tool builds one on demand.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::db::{call_blocking, Database, FailedRun, ResponseFeedback};
+use crate::experiments::reaction_score;
+use crate::telegram::AppState;
+use crate::text::{one_line, truncate_chars};
+
+/// Reactions count as feedback on the latest bot reply in the chat within this window.
+const FEEDBACK_WINDOW_HOURS: i64 = 24;
+/// Recent messages searched for the reply and the user message it answered.
+const RECENT_MESSAGES: usize = 20;
+const EXAMPLES_PER_GROUP: usize = 2;
+const EXAMPLE_CHARS: usize = 120;
+
+/// Record a reaction as feedback on the chat's latest bot reply. Returns true when recorded.
+pub async fn record_reaction(db: Arc<Database>, chat_id: i64, emoji: &str) -> bool {
+    let Some(score) = reaction_score(emoji) else {
+        return false;
+    };
+    let emoji = emoji.trim().to_string();
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(FEEDBACK_WINDOW_HOURS);
+    let result = call_blocking(db, move |d| {
+        let persona_id = d.get_current_persona_id(chat_id)?;
+        let recent = d.get_recent_messages(chat_id, persona_id, RECENT_MESSAGES)?;
+        let Some(pos) = recent.iter().rposition(|m| m.is_from_bot) else {
+            return Ok(false);
+        };
+        let reply = &recent[pos];
+        let recent_enough =
+            chrono::DateTime::parse_from_rfc3339(&reply.timestamp).is_ok_and(|t| t >= cutoff);
+        if !recent_enough {
+            return Ok(false);
+        }
+        let prompt = recent[..pos]
+            .iter()
+            .rev()
+            .find(|m| !m.is_from_bot)
+            .map(|m| m.content.clone())
+            .unwrap_or_default();
+        d.record_response_feedback(&ResponseFeedback {
+            chat_id,
+            persona_id,
+            score,
+            emoji,
+            prompt,
+            reply: reply.content.clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        })?;
+        Ok(true)
+    })
+    .await;
+    result.unwrap_or_else(|e| {
+        warn!("feedback: failed to record reaction in chat {chat_id}: {e}");
+        false
+    })
+}
+
+/// Rough kind of a low-rated reply, each with the adjustment it suggests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ReplyIssue {
+    Refusal,
+    ReportedFailure,
+    TooLong,
+    TooShort,
+    Question,
+    Other,
+}
+
+impl ReplyIssue {
+    fn classify(reply: &str) -> Self {
+        let lower = reply.to_lowercase().replace('’', "'");
+        let has = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+        if has(&[
+            "i can't",
+            "i cannot",
+            "i'm not able",
+            "i am not able",
+            "i won't",
+        ]) {
+            ReplyIssue::Refusal
+        } else if has(&["error", "failed", "couldn't", "could not", "unable to"]) {
+            ReplyIssue::ReportedFailure
+        } else if reply.chars().count() > 1500 {
+            ReplyIssue::TooLong
+        } else if reply.trim().chars().count() < 40 {
+            ReplyIssue::TooShort
+        } else if reply.trim_end().ends_with('?') {
+            ReplyIssue::Question
+        } else {
+            ReplyIssue::Other
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ReplyIssue::Refusal => "Refused or said it could not help",
+            ReplyIssue::ReportedFailure => "Reported an error or failure",
+            ReplyIssue::TooLong => "Very long replies",
+            ReplyIssue::TooShort => "Very short replies",
+            ReplyIssue::Question => "Answered with a question",
+            ReplyIssue::Other => "Other",
+        }
+    }
+
+    fn suggestion(self) -> &'static str {
+        match self {
+            ReplyIssue::Refusal => "State in the system prompt what the bot may do in these chats (tools, topics), or add a skill covering these requests.",
+            ReplyIssue::ReportedFailure => "Tell the bot to try another approach or tool before giving up, and to explain a workaround when it cannot finish.",
+            ReplyIssue::TooLong => "Ask for shorter replies by default (e.g. lead with the answer, details only on request).",
+            ReplyIssue::TooShort => "Ask for complete answers: include the result and the key details, not only an acknowledgement.",
+            ReplyIssue::Question => "Tell the bot to make reasonable assumptions and act, asking only when a wrong guess would be costly.",
+            ReplyIssue::Other => "Read the examples and add guidance for these requests to the system prompt or the relevant skill.",
+        }
+    }
+}
+
+/// Error text with numbers masked and whitespace collapsed, so repeats of one failure group.
+fn error_signature(error: &str) -> String {
+    let first_line = error.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let masked: String = first_line
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect();
+    let collapsed = one_line(&masked);
+    if collapsed.is_empty() {
+        "(no error message)".into()
+    } else {
+        truncate_chars(&collapsed, 80)
+    }
+}
+
+fn failure_suggestion(source: &str, signature: &str) -> &'static str {
+    let lower = signature.to_lowercase();
+    if lower.contains("max_iterations") {
+        "Turns ran out of tool iterations: add guidance to plan before calling tools (system prompt or skill), or raise max_tool_iterations."
+    } else if lower.contains("max_tokens") {
+        "Replies were cut off: ask for more concise answers or raise max_tokens."
+    } else if lower.contains("timeout") || lower.contains("timed out") {
+        "Runs are timing out: split the work into smaller steps or raise the relevant timeout."
+    } else if ["denied", "unauthorized", "forbidden", "permission"]
+        .iter()
+        .any(|w| lower.contains(w))
+    {
+        "Access problems: check credentials and which chats and tools are allowed."
+    } else if lower.contains("skill") {
+        "A skill is involved: check its SKILL.md instructions and dependencies."
+    } else if source == "scheduled_task" {
+        "Make the task prompts self-contained (tasks run without the chat's recent context) and name the tools or skills to use."
+    } else {
+        "Read the examples and add guidance for this case to the system prompt or the skill used."
+    }
+}
+
+/// Build the report text for the given window.
+pub fn build_report(feedback: &[ResponseFeedback], failures: &[FailedRun], days: u64) -> String {
+    let positive = feedback.iter().filter(|f| f.score > 0).count();
+    let negative: Vec<&ResponseFeedback> = feedback.iter().filter(|f| f.score < 0).collect();
+    if negative.is_empty() && failures.is_empty() {
+        return format!(
+            "No low-rated replies or failed runs in the last {days} days ({positive} positive reactions)."
+        );
+    }
+
+    let mut out = format!(
+        "📋 Feedback report (last {days} days)\nReactions: {positive} positive, {} negative. Failed runs: {}.",
+        negative.len(),
+        failures.len()
+    );
+
+    if !negative.is_empty() {
+        let mut groups: BTreeMap<ReplyIssue, Vec<&ResponseFeedback>> = BTreeMap::new();
+        for f in &negative {
+            groups
+                .entry(ReplyIssue::classify(&f.reply))
+                .or_default()
+                .push(f);
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by_key(|(issue, items)| (std::cmp::Reverse(items.len()), *issue));
+        out.push_str("\n\nLow-rated replies:");
+        for (issue, items) in groups {
+            out.push_str(&format!("\n• {} — {}", issue.label(), items.len()));
+            out.push_str(&format!("\n  Suggestion: {}", issue.suggestion()));
+            for f in items.iter().rev().take(EXAMPLES_PER_GROUP) {
+                out.push_str(&format!(
+                    "\n  e.g. chat {}: \"{}\" → \"{}\"",
+                    f.chat_id,
+                    truncate_chars(&one_line(&f.prompt), EXAMPLE_CHARS),
+                    truncate_chars(&one_line(&f.reply), EXAMPLE_CHARS)
+                ));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        let mut groups: BTreeMap<(String, String), Vec<&FailedRun>> = BTreeMap::new();
+        for f in failures {
+            groups
+                .entry((f.source.clone(), error_signature(&f.error)))
+                .or_default()
+                .push(f);
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+        out.push_str("\n\nFailed runs:");
+        for ((source, signature), items) in groups {
+            let mut labels: Vec<&str> = Vec::new();
+            for f in items.iter().rev() {
+                if !labels.contains(&f.label.as_str()) {
+                    labels.push(&f.label);
+                }
+            }
+            out.push_str(&format!("\n• {source}: {signature} — {}", items.len()));
+            out.push_str(&format!(
+                "\n  Suggestion: {}",
+                failure_suggestion(&source, &signature)
+            ));
+            for label in labels.iter().take(EXAMPLES_PER_GROUP) {
+                out.push_str(&format!(
+                    "\n  e.g. {}",
+                    truncate_chars(&one_line(label), EXAMPLE_CHARS)
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Build a report over the last `days` days.
+pub async fn generate(db: Arc<Database>, days: u64) -> Result<String, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+    let (feedback, failures) = call_blocking(db, move |d| {
+        Ok((
+            d.list_response_feedback(&since)?,
+            d.list_failed_runs(&since)?,
+        ))
+    })
+    .await
+    .map_err(|e| format!("Failed to load feedback: {e}"))?;
+    Ok(build_report(&feedback, &failures, days))
+}
+
+/// Send the periodic report to the control chats when `feedback_report_days` have passed since
+/// the last one. Called every scheduler cycle.
+pub async fn send_due_report(state: &Arc<AppState>) {
+    let days = state.config.feedback_report_days;
+    if days == 0 || state.config.control_chat_ids.is_empty() {
+        return;
+    }
+    let last = call_blocking(state.db.clone(), |d| d.last_feedback_report_at())
+        .await
+        .ok()
+        .flatten();
+    let due = last
+        .as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .is_none_or(|t| {
+            chrono::Utc::now() - t.with_timezone(&chrono::Utc)
+                >= chrono::Duration::days(days as i64)
+        });
+    if !due {
+        return;
+    }
+    let report = match generate(state.db.clone(), days).await {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("feedback: {e}");
+            return;
+        }
+    };
+    let saved = report.clone();
+    if let Err(e) = call_blocking(state.db.clone(), move |d| d.save_feedback_report(&saved)).await {
+        warn!("feedback: failed to save report: {e}");
+        return;
+    }
+    info!(
+        "Sending feedback report to {} control chat(s)",
+        state.config.control_chat_ids.len()
+    );
+    for &chat_id in &state.config.control_chat_ids {
+        let persona_id =
+            call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+        if let Err(e) = deliver_and_store_bot_message(
+            &state.bot,
+            state.db.clone(),
+            &state.config.bot_username,
+            chat_id,
+            persona_id,
+            &report,
+        )
+        .await
+        {
+            warn!("feedback: failed to send report to chat {chat_id}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StoredMessage;
+
+    fn feedback(score: i64, prompt: &str, reply: &str) -> ResponseFeedback {
+        ResponseFeedback {
+            chat_id: 1,
+            persona_id: 1,
+            score,
+            emoji: if score > 0 { "👍" } else { "👎" }.into(),
+            prompt: prompt.into(),
+            reply: reply.into(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn failure(source: &str, label: &str, error: &str) -> FailedRun {
+        FailedRun {
+            source: source.into(),
+            chat_id: 1,
+            label: label.into(),
+            error: error.into(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_build_report_groups_and_suggests() {
+        assert!(build_report(&[feedback(1, "hi", "hello")], &[], 7)
+            .starts_with("No low-rated replies or failed runs in the last 7 days (1 positive"));
+
+        let report = build_report(
+            &[
+                feedback(1, "a", "fine"),
+                feedback(-1, "book a table", "Sorry, I can't make bookings."),
+                feedback(-1, "book a flight", "I cannot do that from here."),
+                feedback(
+                    -1,
+                    "weather?",
+                    "Which city do you mean exactly, and for which day?",
+                ),
+            ],
+            &[
+                failure(
+                    "scheduled_task",
+                    "task #3: daily digest",
+                    "Request timed out after 30s",
+                ),
+                failure(
+                    "scheduled_task",
+                    "task #3: daily digest",
+                    "Request timed out after 45s",
+                ),
+                failure("agent_turn", "concise/treatment", "max_iterations"),
+            ],
+            7,
+        );
+        assert!(report.contains("Reactions: 1 positive, 3 negative. Failed runs: 3."));
+        assert!(report.contains("• Refused or said it could not help — 2"));
+        assert!(report.contains("\"book a flight\" → \"I cannot do that from here.\""));
+        assert!(report.contains("• Answered with a question — 1"));
+        assert!(report.contains("• scheduled_task: Request timed out after ##s — 2"));
+        assert!(report.contains("Runs are timing out"));
+        assert!(report.contains("• agent_turn: max_iterations — 1"));
+        assert_eq!(report.matches("e.g. task #3: daily digest").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_record_reaction_uses_latest_reply() {
+        let dir = std::env::temp_dir().join(format!("microclaw_feedback_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(1, None, "private").unwrap();
+        let pid = db.get_or_create_default_persona(1).unwrap();
+        assert!(!record_reaction(db.clone(), 1, "👎").await);
+        for (id, content, from_bot) in [
+            ("u1", "what's on today?", false),
+            ("b1", "Nothing planned.", true),
+        ] {
+            db.store_message(&StoredMessage {
+                id: id.into(),
+                chat_id: 1,
+                persona_id: pid,
+                sender_name: if from_bot { "bot" } else { "alice" }.into(),
+                content: content.into(),
+                is_from_bot: from_bot,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            })
+            .unwrap();
+        }
+        assert!(!record_reaction(db.clone(), 1, "🤔").await);
+        assert!(record_reaction(db.clone(), 1, "👎").await);
+
+        let stored = db.list_response_feedback("1970-01-01T00:00:00Z").unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].score, -1);
+        assert_eq!(stored[0].prompt, "what's on today?");
+        assert_eq!(stored[0].reply, "Nothing planned.");
+        let report = generate(db.clone(), 7).await.unwrap();
+        assert!(report.contains("• Very short replies — 1"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
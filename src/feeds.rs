@@ -0,0 +1,319 @@
+//! RSS/Atom feed watcher: the scheduler tick polls each configured feed every `interval_mins`,
+//! posts new items into the feed's chat as a message from the feed and runs the agent on it, so
+//! it can summarize or act on them. The first poll of a feed only records what is already
+//! there; items are remembered per chat in `feed_items`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::FeedConfig;
+use crate::db::{call_blocking, StoredMessage};
+use crate::notify::notify;
+use crate::telegram::{AgentRequestContext, AppState};
+use crate::text::truncate_chars;
+use crate::tools::web_html::html_to_text;
+
+/// New items posted per poll; the rest are only counted.
+const MAX_ITEMS_PER_POLL: usize = 10;
+const SUMMARY_PREVIEW_LEN: usize = 300;
+/// Items gone from the feed for this long are forgotten.
+const RETENTION_DAYS: i64 = 90;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_INSTRUCTIONS: &str =
+    "Summarize what's new in a few short lines and point out anything that needs attention.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedItem {
+    /// guid / id, falling back to the link or title.
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedFeed {
+    pub title: Option<String>,
+    pub items: Vec<FeedItem>,
+}
+
+/// Last poll per `chat_id|url`.
+fn last_polls() -> &'static Mutex<HashMap<String, Instant>> {
+    static LAST_POLLS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+    LAST_POLLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|c| c.tag_name().name() == name)
+        .and_then(|c| c.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+}
+
+/// Atom links are `<link href=".."/>` (preferring rel="alternate"); RSS links are text.
+fn item_link(node: roxmltree::Node) -> Option<String> {
+    let links: Vec<_> = node
+        .children()
+        .filter(|c| c.tag_name().name() == "link")
+        .collect();
+    links
+        .iter()
+        .find(|l| {
+            l.attribute("href").is_some()
+                && l.attribute("rel").unwrap_or("alternate") == "alternate"
+        })
+        .or_else(|| links.iter().find(|l| l.attribute("href").is_some()))
+        .and_then(|l| l.attribute("href"))
+        .map(String::from)
+        .or_else(|| {
+            links
+                .iter()
+                .find_map(|l| l.text())
+                .map(|t| t.trim().to_string())
+        })
+        .filter(|l| !l.is_empty())
+}
+
+fn preview(html: &str) -> Option<String> {
+    let text = html_to_text(html);
+    if text.is_empty() {
+        return None;
+    }
+    Some(truncate_chars(&text, SUMMARY_PREVIEW_LEN))
+}
+
+/// Parse RSS 2.0, RSS 1.0 (RDF) or Atom. Items are kept in feed order.
+pub fn parse_feed(xml: &str) -> Result<ParsedFeed, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| format!("invalid feed XML: {e}"))?;
+    let root = doc.root_element();
+    let title = match root.tag_name().name() {
+        "feed" => child_text(root, "title"),
+        _ => root
+            .children()
+            .find(|c| c.tag_name().name() == "channel")
+            .and_then(|c| child_text(c, "title")),
+    };
+    let items = root
+        .descendants()
+        .filter(|n| matches!(n.tag_name().name(), "item" | "entry"))
+        .filter_map(|n| {
+            let title = child_text(n, "title").map(|t| html_to_text(&t));
+            let link = item_link(n);
+            let id = child_text(n, "guid")
+                .or_else(|| child_text(n, "id"))
+                .or_else(|| link.clone())
+                .or_else(|| title.clone())?;
+            let summary = ["description", "summary", "content", "encoded"]
+                .iter()
+                .find_map(|name| child_text(n, name))
+                .and_then(|s| preview(&s));
+            Some(FeedItem {
+                id,
+                title: title.unwrap_or_else(|| "(untitled)".into()),
+                link,
+                summary,
+            })
+        })
+        .collect();
+    Ok(ParsedFeed { title, items })
+}
+
+/// The message posted into the chat for new items of one feed.
+pub fn render_items(feed_name: &str, items: &[FeedItem], instructions: &str) -> String {
+    let mut out = format!("New items from the feed \"{feed_name}\":\n");
+    for item in items.iter().take(MAX_ITEMS_PER_POLL) {
+        out.push_str(&format!("\n- {}", item.title));
+        if let Some(link) = &item.link {
+            out.push_str(&format!(" ({link})"));
+        }
+        if let Some(summary) = &item.summary {
+            out.push_str(&format!("\n  {summary}"));
+        }
+    }
+    if items.len() > MAX_ITEMS_PER_POLL {
+        out.push_str(&format!(
+            "\n\n…and {} more.",
+            items.len() - MAX_ITEMS_PER_POLL
+        ));
+    }
+    out.push_str(&format!("\n\n{instructions}"));
+    out
+}
+
+async fn fetch_feed(url: &str) -> Result<ParsedFeed, String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .get(url)
+        .header("User-Agent", "MicroClaw/1.0")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    parse_feed(&body)
+}
+
+async fn poll_feed(state: &Arc<AppState>, feed: &FeedConfig) -> Result<(), String> {
+    let parsed = fetch_feed(&feed.url).await?;
+    let chat_id = feed.chat_id;
+    let url = feed.url.clone();
+    let ids: Vec<String> = parsed.items.iter().map(|i| i.id.clone()).collect();
+    let prune_before = (chrono::Utc::now() - chrono::Duration::days(RETENTION_DAYS)).to_rfc3339();
+    let (baseline, new_ids) = call_blocking(state.db.clone(), move |d| {
+        let baseline = !d.has_feed_items(chat_id, &url)?;
+        let new_ids = d.record_feed_items(chat_id, &url, &ids, &prune_before)?;
+        Ok((baseline, new_ids))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if baseline || new_ids.is_empty() {
+        return Ok(());
+    }
+    let new_items: Vec<FeedItem> = parsed
+        .items
+        .into_iter()
+        .filter(|i| new_ids.contains(&i.id))
+        .collect();
+
+    let feed_name = feed
+        .name
+        .clone()
+        .or(parsed.title)
+        .unwrap_or_else(|| feed.url.clone());
+    info!(
+        "Feeds: {} new item(s) in \"{}\" for chat {}",
+        new_items.len(),
+        feed_name,
+        chat_id
+    );
+    let persona_id = call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .unwrap_or(0);
+    if persona_id == 0 {
+        return Err(format!("could not resolve persona for chat {chat_id}"));
+    }
+    let instructions = feed
+        .instructions
+        .as_deref()
+        .filter(|i| !i.trim().is_empty())
+        .unwrap_or(DEFAULT_INSTRUCTIONS);
+    let msg = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        chat_id,
+        persona_id,
+        sender_name: format!("feed:{feed_name}"),
+        content: render_items(&feed_name, &new_items, instructions),
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    call_blocking(state.db.clone(), move |d| d.store_message(&msg))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let channel = match call_blocking(state.db.clone(), move |d| d.get_chat_type(chat_id)).await {
+        Ok(Some(chat_type)) => crate::channel::channel_name(&chat_type),
+        _ => "telegram",
+    };
+    let response = crate::telegram::process_with_agent(
+        state,
+        AgentRequestContext {
+            caller_channel: channel,
+            chat_id,
+            chat_type: "private",
+            persona_id,
+        },
+        None,
+        None,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if response.is_empty() {
+        return Ok(());
+    }
+    notify(state, chat_id, persona_id, "feeds", &response).await
+}
+
+/// Poll every configured feed whose interval has elapsed. Called from the scheduler tick.
+pub async fn poll_feeds(state: &Arc<AppState>) {
+    for feed in &state.config.feeds {
+        if feed.interval_mins == 0 {
+            continue;
+        }
+        {
+            let key = format!("{}|{}", feed.chat_id, feed.url);
+            let interval = Duration::from_secs(feed.interval_mins * 60);
+            let mut polls = last_polls().lock().unwrap();
+            if polls.get(&key).is_some_and(|t| t.elapsed() < interval) {
+                continue;
+            }
+            polls.insert(key, Instant::now());
+        }
+        if let Err(e) = poll_feed(state, feed).await {
+            warn!(
+                "Feeds: failed to poll {} for chat {}: {e}",
+                feed.url, feed.chat_id
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rss_and_atom() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Example News</title>
+  <item><title>First &amp; best</title><link>https://example.com/1</link>
+    <guid>urn:1</guid><description>&lt;p&gt;Hello &lt;b&gt;world&lt;/b&gt;&lt;/p&gt;</description></item>
+  <item><title>Second</title><link>https://example.com/2</link></item>
+</channel></rss>"#;
+        let feed = parse_feed(rss).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Example News"));
+        assert_eq!(feed.items.len(), 2);
+        assert_eq!(feed.items[0].id, "urn:1");
+        assert_eq!(feed.items[0].title, "First & best");
+        assert_eq!(feed.items[0].summary.as_deref(), Some("Hello world"));
+        assert_eq!(feed.items[1].id, "https://example.com/2");
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+  <entry><id>tag:blog,1</id><title>Post</title>
+    <link rel="self" href="https://blog/self"/><link href="https://blog/post"/>
+    <summary>Short</summary></entry>
+</feed>"#;
+        let feed = parse_feed(atom).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Blog"));
+        assert_eq!(feed.items[0].id, "tag:blog,1");
+        assert_eq!(feed.items[0].link.as_deref(), Some("https://blog/post"));
+
+        assert!(parse_feed("not xml").is_err());
+    }
+
+    #[test]
+    fn test_render_items_caps_count() {
+        let items: Vec<FeedItem> = (0..12)
+            .map(|i| FeedItem {
+                id: i.to_string(),
+                title: format!("Item {i}"),
+                link: None,
+                summary: None,
+            })
+            .collect();
+        let out = render_items("News", &items, "Summarize.");
+        assert!(out.starts_with("New items from the feed \"News\":"));
+        assert!(out.contains("- Item 9"));
+        assert!(!out.contains("- Item 10"));
+        assert!(out.ends_with("…and 2 more.\n\nSummarize."));
+    }
+}
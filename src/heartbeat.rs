@@ -0,0 +1,164 @@
+//! Dead man's switch: pings `heartbeat.url` (healthchecks.io style) on an interval and after
+//! scheduler cycles, so the monitoring service alerts when pings stop, and posts startup and
+//! shutdown notices to the admin chat.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::config::Config;
+use crate::db::call_blocking;
+use crate::telegram::AppState;
+
+static LAST_PING: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    Startup,
+    Shutdown,
+}
+
+fn ping_url(config: &Config) -> Option<(&str, Duration)> {
+    let hb = config.heartbeat.as_ref()?;
+    let url = hb.url.as_deref().map(str::trim).filter(|u| !u.is_empty())?;
+    Some((url, Duration::from_secs(hb.interval_secs.max(1))))
+}
+
+/// Chat that receives lifecycle notices: `heartbeat.admin_chat_id`, else the first control chat.
+pub fn admin_chat_id(config: &Config) -> Option<i64> {
+    let hb = config.heartbeat.as_ref()?;
+    hb.admin_chat_id
+        .or_else(|| config.control_chat_ids.first().copied())
+}
+
+/// Text of a lifecycle notice.
+pub fn lifecycle_message(bot_username: &str, event: Lifecycle) -> String {
+    match event {
+        Lifecycle::Startup => format!(
+            "🟢 {bot_username} started (v{}).",
+            env!("CARGO_PKG_VERSION")
+        ),
+        Lifecycle::Shutdown => format!("🔴 {bot_username} is shutting down."),
+    }
+}
+
+/// Whether a ping is due, recording it as sent when it is.
+fn claim_ping(interval: Duration) -> bool {
+    let mut last = LAST_PING.lock().unwrap();
+    if last.is_some_and(|t| t.elapsed() < interval) {
+        return false;
+    }
+    *last = Some(Instant::now());
+    true
+}
+
+async fn ping(url: &str) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Heartbeat: failed to build HTTP client: {e}");
+            return;
+        }
+    };
+    match client.get(url).send().await {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => warn!("Heartbeat: ping returned HTTP {}", resp.status()),
+        Err(e) => warn!("Heartbeat: ping failed: {e}"),
+    }
+}
+
+/// Ping when the interval has elapsed since the last ping. Called at the end of every scheduler
+/// cycle and by the interval task, which share the throttle.
+pub async fn after_scheduler_cycle(state: &Arc<AppState>) {
+    if let Some((url, interval)) = ping_url(&state.config) {
+        if claim_ping(interval) {
+            ping(url).await;
+        }
+    }
+}
+
+/// Start the interval ping task (no-op without `heartbeat.url`).
+pub fn spawn_heartbeat(state: Arc<AppState>) {
+    let Some((_, interval)) = ping_url(&state.config) else {
+        return;
+    };
+    info!("Heartbeat pings every {}s", interval.as_secs());
+    tokio::spawn(async move {
+        loop {
+            after_scheduler_cycle(&state).await;
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+/// Post a startup or shutdown notice to the admin chat.
+pub async fn announce(state: &Arc<AppState>, event: Lifecycle) {
+    if !state
+        .config
+        .heartbeat
+        .as_ref()
+        .is_some_and(|hb| hb.notify_lifecycle)
+    {
+        return;
+    }
+    let Some(chat_id) = admin_chat_id(&state.config) else {
+        return;
+    };
+    let persona_id = call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .unwrap_or(0);
+    let text = lifecycle_message(&state.config.bot_username, event);
+    if let Err(e) = deliver_and_store_bot_message(
+        &state.bot,
+        state.db.clone(),
+        &state.config.bot_username,
+        chat_id,
+        persona_id,
+        &text,
+    )
+    .await
+    {
+        warn!("Heartbeat: failed to send {event:?} notice to chat {chat_id}: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_chat_and_ping_settings() {
+        let mut config: Config = serde_yaml::from_str(
+            "telegram_bot_token: tok\nbot_username: bot\napi_key: key\ncontrol_chat_ids: [7, 8]\n",
+        )
+        .unwrap();
+        assert!(admin_chat_id(&config).is_none());
+        assert!(ping_url(&config).is_none());
+
+        config.heartbeat = Some(serde_yaml::from_str("url: https://hc-ping.com/abc\n").unwrap());
+        assert_eq!(admin_chat_id(&config), Some(7));
+        assert_eq!(
+            ping_url(&config),
+            Some(("https://hc-ping.com/abc", Duration::from_secs(300)))
+        );
+        config.heartbeat.as_mut().unwrap().admin_chat_id = Some(42);
+        assert_eq!(admin_chat_id(&config), Some(42));
+
+        assert!(lifecycle_message("bot", Lifecycle::Startup).starts_with("🟢 bot started (v"));
+        assert_eq!(
+            lifecycle_message("bot", Lifecycle::Shutdown),
+            "🔴 bot is shutting down."
+        );
+    }
+
+    #[test]
+    fn test_claim_ping_throttles() {
+        assert!(claim_ping(Duration::from_secs(0)));
+        assert!(!claim_ping(Duration::from_secs(3600)));
+    }
+}
@@ -0,0 +1,276 @@
+//! Home Assistant REST client and presence: who is home, from `person.*` (or configured)
+//! entities. Presence is cached briefly so the system prompt and scheduled tasks don't query
+//! HA on every turn. The `home_assistant` tool uses the same client to read states and call
+//! services.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::config::{Config, HomeAssistantConfig};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const PRESENCE_TTL: Duration = Duration::from_secs(60);
+
+static PRESENCE_CACHE: Mutex<Option<(Instant, Vec<Presence>)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EntityState {
+    pub entity_id: String,
+    pub state: String,
+    #[serde(default)]
+    pub attributes: serde_json::Value,
+    #[serde(default)]
+    pub last_changed: Option<String>,
+}
+
+impl EntityState {
+    pub fn friendly_name(&self) -> &str {
+        self.attributes
+            .get("friendly_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.entity_id)
+    }
+}
+
+pub struct HomeAssistant {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl HomeAssistant {
+    pub fn new(config: &HomeAssistantConfig) -> Self {
+        HomeAssistant {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            base_url: config.url.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<serde_json::Value, String> {
+        self.request(reqwest::Method::GET, path, None).await
+    }
+
+    pub async fn post(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        self.request(reqwest::Method::POST, path, Some(body)).await
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value, String> {
+        let url = format!("{}/api/{}", self.base_url, path.trim_start_matches('/'));
+        let mut request = self.http.request(method, &url).bearer_auth(&self.token);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Home Assistant request failed: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!(
+                "Home Assistant returned HTTP {status}: {}",
+                body.chars().take(300).collect::<String>()
+            ));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Home Assistant response: {e}"))
+    }
+
+    pub async fn states(&self) -> Result<Vec<EntityState>, String> {
+        serde_json::from_value(self.get("states").await?)
+            .map_err(|e| format!("Invalid Home Assistant states: {e}"))
+    }
+
+    pub async fn state(&self, entity_id: &str) -> Result<EntityState, String> {
+        serde_json::from_value(self.get(&format!("states/{entity_id}")).await?)
+            .map_err(|e| format!("Invalid Home Assistant state: {e}"))
+    }
+
+    /// Call `domain.service` with `data`; returns the states that changed while it ran.
+    pub async fn call_service(
+        &self,
+        domain: &str,
+        service: &str,
+        data: &serde_json::Value,
+    ) -> Result<Vec<EntityState>, String> {
+        let changed = self
+            .post(&format!("services/{domain}/{service}"), data)
+            .await?;
+        Ok(serde_json::from_value(changed).unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Presence {
+    pub entity_id: String,
+    pub name: String,
+    /// `home`, `away`, or the zone the person is in.
+    pub state: String,
+    pub home: bool,
+    pub since: Option<String>,
+}
+
+/// Presence of the configured entities (all `person.*` when none are configured).
+pub fn presence_from_states(states: &[EntityState], entities: &[String]) -> Vec<Presence> {
+    states
+        .iter()
+        .filter(|s| {
+            if entities.is_empty() {
+                s.entity_id.starts_with("person.")
+            } else {
+                entities.contains(&s.entity_id)
+            }
+        })
+        .map(|s| {
+            let home = matches!(s.state.as_str(), "home" | "on");
+            let state = match s.state.as_str() {
+                "home" | "on" => "home".to_string(),
+                "not_home" | "off" => "away".to_string(),
+                zone => zone.to_string(),
+            };
+            Presence {
+                entity_id: s.entity_id.clone(),
+                name: s.friendly_name().to_string(),
+                state,
+                home,
+                since: s.last_changed.clone(),
+            }
+        })
+        .collect()
+}
+
+/// e.g. "At home: Alice, Bob. Away: Carol (Work)."
+pub fn format_presence(presence: &[Presence]) -> String {
+    if presence.is_empty() {
+        return "No presence entities found in Home Assistant.".into();
+    }
+    let home: Vec<&str> = presence
+        .iter()
+        .filter(|p| p.home)
+        .map(|p| p.name.as_str())
+        .collect();
+    let away: Vec<String> = presence
+        .iter()
+        .filter(|p| !p.home)
+        .map(|p| match p.state.as_str() {
+            "away" | "unknown" | "unavailable" => p.name.clone(),
+            zone => format!("{} ({zone})", p.name),
+        })
+        .collect();
+    let mut out = if home.is_empty() {
+        "Nobody is home.".to_string()
+    } else {
+        format!("At home: {}.", home.join(", "))
+    };
+    if !away.is_empty() {
+        out.push_str(&format!(" Away: {}.", away.join(", ")));
+    }
+    out
+}
+
+/// Chats (from `presence_chats`) of the people who are home, with their names.
+pub fn chats_of_people_home(
+    config: &HomeAssistantConfig,
+    presence: &[Presence],
+) -> Vec<(String, i64)> {
+    presence
+        .iter()
+        .filter(|p| p.home)
+        .filter_map(|p| {
+            config
+                .presence_chats
+                .get(&p.entity_id)
+                .map(|&chat_id| (p.name.clone(), chat_id))
+        })
+        .collect()
+}
+
+/// Current presence, from the cache when it is fresh.
+pub async fn presence(config: &HomeAssistantConfig) -> Result<Vec<Presence>, String> {
+    if let Some((at, cached)) = PRESENCE_CACHE.lock().unwrap().as_ref() {
+        if at.elapsed() < PRESENCE_TTL {
+            return Ok(cached.clone());
+        }
+    }
+    let states = HomeAssistant::new(config).states().await?;
+    let presence = presence_from_states(&states, &config.presence_entities);
+    *PRESENCE_CACHE.lock().unwrap() = Some((Instant::now(), presence.clone()));
+    Ok(presence)
+}
+
+/// System prompt section saying who is home, when enabled and Home Assistant answers.
+pub async fn presence_prompt_section(config: &Config) -> Option<String> {
+    let ha = config.home_assistant.as_ref()?;
+    if !ha.presence_in_prompt {
+        return None;
+    }
+    match presence(ha).await {
+        Ok(presence) if !presence.is_empty() => Some(format!(
+            "# Presence\n\n{} (from Home Assistant; call `who_is_home` for details or to notify whoever is home)",
+            format_presence(&presence)
+        )),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Presence unavailable: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence_from_states() {
+        let states: Vec<EntityState> = serde_json::from_value(serde_json::json!([
+            {"entity_id": "person.alice", "state": "home", "attributes": {"friendly_name": "Alice"}, "last_changed": "2026-01-01T08:00:00+00:00"},
+            {"entity_id": "person.bob", "state": "not_home", "attributes": {"friendly_name": "Bob"}},
+            {"entity_id": "person.carol", "state": "Work", "attributes": {"friendly_name": "Carol"}},
+            {"entity_id": "binary_sensor.guest_room", "state": "on", "attributes": {}},
+            {"entity_id": "light.kitchen", "state": "on", "attributes": {}}
+        ]))
+        .unwrap();
+
+        let presence = presence_from_states(&states, &[]);
+        assert_eq!(presence.len(), 3);
+        assert_eq!(
+            format_presence(&presence),
+            "At home: Alice. Away: Bob, Carol (Work)."
+        );
+        let only_sensor = presence_from_states(&states, &["binary_sensor.guest_room".into()]);
+        assert_eq!(only_sensor.len(), 1);
+        assert!(only_sensor[0].home);
+        assert_eq!(
+            format_presence(&presence[1..2]),
+            "Nobody is home. Away: Bob."
+        );
+
+        let config: HomeAssistantConfig = serde_yaml::from_str(
+            "url: http://ha.local:8123\ntoken: t\npresence_chats:\n  person.alice: 11\n  person.bob: 22\n",
+        )
+        .unwrap();
+        assert!(config.presence_in_prompt);
+        assert_eq!(
+            chats_of_people_home(&config, &presence),
+            vec![("Alice".to_string(), 11)]
+        );
+    }
+}
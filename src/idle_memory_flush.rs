@@ -0,0 +1,226 @@
+//! Idle-session memory flush: once a chat or web session has had no activity for
+//! `memory_flush_idle_minutes`, one tool-less LLM call (with `memory_flush_model` if set) folds
+//! the messages since the previous flush into the persona's Tier 3 memory, so facts from short
+//! sessions survive after their context goes stale. Runs from the scheduler loop; each session
+//! update is flushed at most once (`session_memory_flushes`).
+
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::claude::{Message, MessageContent, ResponseContentBlock};
+use crate::db::{call_blocking, StoredMessage};
+use crate::llm::LlmProvider;
+use crate::telegram::AppState;
+use crate::tools::tiered_memory::{memory_path, parse_tier_content, write_tier};
+
+/// Sessions idle for longer than this are left alone (e.g. right after enabling the flush).
+const MAX_IDLE_HOURS: i64 = 24;
+/// Sessions flushed per scheduler cycle.
+const MAX_PER_CYCLE: usize = 5;
+const MAX_MESSAGES: usize = 40;
+const MAX_TRANSCRIPT_CHARS: usize = 12_000;
+const NO_CHANGE: &str = "NO_CHANGE";
+
+const SYSTEM_PROMPT: &str = "You maintain the short-term (Tier 3) section of an assistant's memory about one user: recent focus, plans, mood, open items and facts mentioned in passing that matter for the next days. It is not a todo list or a conversation log.";
+
+/// Conversation text for the prompt: newest messages kept when over the limit.
+fn transcript(messages: &[StoredMessage]) -> String {
+    let mut lines: Vec<String> = messages
+        .iter()
+        .rev()
+        .take(MAX_MESSAGES)
+        .map(|m| format!("[{}]: {}", m.sender_name, m.content.trim()))
+        .collect();
+    lines.reverse();
+    let mut text = lines.join("\n");
+    let chars = text.chars().count();
+    if chars > MAX_TRANSCRIPT_CHARS {
+        text = text.chars().skip(chars - MAX_TRANSCRIPT_CHARS).collect();
+    }
+    text
+}
+
+fn flush_prompt(tier3: &str, transcript: &str) -> String {
+    let current = if tier3.trim().is_empty() {
+        "(empty)"
+    } else {
+        tier3.trim()
+    };
+    format!(
+        "Current Tier 3 memory:\n{current}\n\nConversation since the last update:\n{transcript}\n\n\
+         Rewrite the Tier 3 memory so it keeps anything from this conversation worth remembering \
+         over the next days, merged with the current items (drop ones that are done or stale). \
+         Reply with the new section only, as a markdown bullet list of at most 12 bullets. If \
+         nothing in the conversation is worth keeping, reply exactly {NO_CHANGE}."
+    )
+}
+
+/// The new Tier 3 content from the model's reply, or None to leave memory as it is.
+fn parse_reply(reply: &str) -> Option<String> {
+    let text = reply.trim();
+    let text = text
+        .strip_prefix("```markdown")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|t| t.strip_suffix("```"))
+        .unwrap_or(text)
+        .trim();
+    if text.is_empty() || text.starts_with(NO_CHANGE) {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+async fn ask(llm: &dyn LlmProvider, prompt: String) -> Result<String, String> {
+    let response = llm
+        .send_message(
+            SYSTEM_PROMPT,
+            vec![Message {
+                role: "user".into(),
+                content: MessageContent::Text(prompt),
+            }],
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response
+        .content
+        .iter()
+        .filter_map(|b| match b {
+            ResponseContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(""))
+}
+
+/// Flush the memory of sessions that became idle. Called every scheduler cycle.
+pub async fn flush_idle_sessions(state: &Arc<AppState>) {
+    let minutes = state.config.memory_flush_idle_minutes;
+    if minutes == 0 {
+        return;
+    }
+    let now = chrono::Utc::now();
+    let idle_before = (now - chrono::Duration::minutes(minutes as i64)).to_rfc3339();
+    let active_since = (now - chrono::Duration::hours(MAX_IDLE_HOURS)).to_rfc3339();
+    let window_start = active_since.clone();
+    let sessions = match call_blocking(state.db.clone(), move |d| {
+        d.list_sessions_to_flush(&idle_before, &active_since, MAX_PER_CYCLE)
+    })
+    .await
+    {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            warn!("memory flush: failed to list idle sessions: {e}");
+            return;
+        }
+    };
+    if sessions.is_empty() {
+        return;
+    }
+
+    let override_llm = (!state.config.memory_flush_model.trim().is_empty()).then(|| {
+        let mut config = state.config.clone();
+        config.model = state.config.memory_flush_model.trim().to_string();
+        crate::llm::create_provider(&config)
+    });
+    let llm = override_llm.as_deref().unwrap_or(state.llm.as_ref());
+    let groups_dir = std::path::Path::new(&state.config.runtime_data_dir()).join("groups");
+
+    for session in sessions {
+        let (chat_id, persona_id) = (session.chat_id, session.persona_id);
+        let after = session.flushed_at.unwrap_or_else(|| window_start.clone());
+        let messages = call_blocking(state.db.clone(), move |d| {
+            d.get_messages_after(chat_id, persona_id, Some(&after), 500)
+        })
+        .await
+        .unwrap_or_default();
+        if messages.iter().any(|m| !m.is_from_bot) {
+            let path = memory_path(&groups_dir, chat_id, persona_id);
+            let tier3 = std::fs::read_to_string(&path)
+                .map(|full| parse_tier_content(&full, 3))
+                .unwrap_or_default();
+            match ask(llm, flush_prompt(&tier3, &transcript(&messages))).await {
+                Ok(reply) => {
+                    if let Some(content) = parse_reply(&reply) {
+                        match write_tier(&path, 3, &content) {
+                            Ok(()) => info!(
+                                "memory flush: updated Tier 3 for chat {chat_id} persona {persona_id}"
+                            ),
+                            Err(e) => warn!(
+                                "memory flush: failed to write {}: {e}",
+                                path.display()
+                            ),
+                        }
+                    }
+                }
+                Err(e) => {
+                    // Leave the session unflushed so the next cycle retries it
+                    warn!("memory flush: LLM call failed for chat {chat_id}: {e}");
+                    continue;
+                }
+            }
+        }
+        if let Err(e) = call_blocking(state.db.clone(), move |d| {
+            d.record_session_memory_flush(chat_id, persona_id, &session.updated_at)
+        })
+        .await
+        {
+            warn!("memory flush: failed to record flush for chat {chat_id}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_parse_reply_and_prompt() {
+        assert_eq!(parse_reply("NO_CHANGE"), None);
+        assert_eq!(parse_reply("  \n"), None);
+        assert_eq!(
+            parse_reply("```markdown\n- Packing for Lisbon trip\n```").as_deref(),
+            Some("- Packing for Lisbon trip")
+        );
+        let prompt = flush_prompt("", "[alice]: flying to Lisbon friday");
+        assert!(prompt.starts_with("Current Tier 3 memory:\n(empty)\n\nConversation"));
+        assert!(prompt.contains("[alice]: flying to Lisbon friday"));
+    }
+
+    #[test]
+    fn test_list_sessions_to_flush_once_per_update() {
+        let dir = std::env::temp_dir().join(format!("microclaw_flush_{}", uuid::Uuid::new_v4()));
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+        db.save_session(1, 1, "[]").unwrap();
+        let now = chrono::Utc::now();
+        let later = (now + chrono::Duration::minutes(1)).to_rfc3339();
+        let earlier = (now - chrono::Duration::hours(1)).to_rfc3339();
+
+        let due = db.list_sessions_to_flush(&later, &earlier, 10).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].chat_id, due[0].persona_id), (1, 1));
+        assert_eq!(due[0].flushed_at, None);
+        assert!(db
+            .list_sessions_to_flush(&earlier, &earlier, 10)
+            .unwrap()
+            .is_empty());
+
+        db.record_session_memory_flush(1, 1, &due[0].updated_at)
+            .unwrap();
+        assert!(db
+            .list_sessions_to_flush(&later, &earlier, 10)
+            .unwrap()
+            .is_empty());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        db.save_session(1, 1, "[]").unwrap();
+        let due_again = db.list_sessions_to_flush(&later, &earlier, 10).unwrap();
+        assert_eq!(
+            due_again[0].flushed_at.as_deref(),
+            Some(due[0].updated_at.as_str())
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,429 @@
+//! `microclaw import`: loads conversation history exported from other assistants (ChatGPT data
+//! export, Claude export, Telegram Desktop chat export) into stored messages of a chosen chat, so
+//! it shows up in history search and catch-up. Message ids are derived from the export, so
+//! importing the same file twice does not duplicate messages.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::db::{Database, StoredMessage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    ChatGpt,
+    Claude,
+    Telegram,
+}
+
+impl ImportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "chatgpt" | "openai" => Some(ImportFormat::ChatGpt),
+            "claude" | "anthropic" => Some(ImportFormat::Claude),
+            "telegram" => Some(ImportFormat::Telegram),
+            _ => None,
+        }
+    }
+
+    fn id_prefix(self) -> &'static str {
+        match self {
+            ImportFormat::ChatGpt => "chatgpt",
+            ImportFormat::Claude => "claude",
+            ImportFormat::Telegram => "telegram",
+        }
+    }
+}
+
+/// One message read from an export, before it is mapped onto a chat.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedMessage {
+    pub id: String,
+    pub sender: String,
+    pub content: String,
+    pub is_assistant: bool,
+    pub timestamp: String,
+}
+
+/// Guess the export format from the JSON shape.
+pub fn detect_format(value: &Value) -> Option<ImportFormat> {
+    if value.get("messages").is_some_and(Value::is_array) {
+        return Some(ImportFormat::Telegram);
+    }
+    let first = value.as_array()?.first()?;
+    if first.get("mapping").is_some() {
+        Some(ImportFormat::ChatGpt)
+    } else if first.get("chat_messages").is_some() {
+        Some(ImportFormat::Claude)
+    } else {
+        None
+    }
+}
+
+fn unix_to_rfc3339(secs: f64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs as i64, ((secs.fract()) * 1e9) as u32)
+        .map(|t| t.to_rfc3339())
+}
+
+/// ChatGPT `conversations.json`: follows each conversation's current branch from the root.
+pub fn parse_chatgpt(value: &Value) -> Vec<ImportedMessage> {
+    let mut out = Vec::new();
+    for conv in value.as_array().into_iter().flatten() {
+        let Some(mapping) = conv.get("mapping").and_then(Value::as_object) else {
+            continue;
+        };
+        let conv_id = conv
+            .get("id")
+            .or_else(|| conv.get("conversation_id"))
+            .and_then(Value::as_str)
+            .unwrap_or("conv");
+        let conv_time = conv.get("create_time").and_then(Value::as_f64);
+
+        // Walk current_node -> root via parent links, then reverse.
+        let mut chain = Vec::new();
+        let mut node_id = conv.get("current_node").and_then(Value::as_str);
+        while let Some(id) = node_id {
+            let Some(node) = mapping.get(id) else { break };
+            chain.push((id, node));
+            if chain.len() > mapping.len() {
+                break;
+            }
+            node_id = node.get("parent").and_then(Value::as_str);
+        }
+        chain.reverse();
+
+        for (node_id, node) in chain {
+            let Some(msg) = node.get("message").filter(|m| !m.is_null()) else {
+                continue;
+            };
+            let role = msg["author"]["role"].as_str().unwrap_or("");
+            if role != "user" && role != "assistant" {
+                continue;
+            }
+            let text = msg["content"]["parts"]
+                .as_array()
+                .map(|parts| {
+                    parts
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default();
+            if text.trim().is_empty() {
+                continue;
+            }
+            let ts = msg
+                .get("create_time")
+                .and_then(Value::as_f64)
+                .or(conv_time)
+                .and_then(unix_to_rfc3339)
+                .unwrap_or_default();
+            out.push(ImportedMessage {
+                id: format!("{conv_id}:{node_id}"),
+                sender: if role == "user" { "user" } else { "ChatGPT" }.into(),
+                content: text,
+                is_assistant: role == "assistant",
+                timestamp: ts,
+            });
+        }
+    }
+    out
+}
+
+/// Claude export `conversations.json`.
+pub fn parse_claude(value: &Value) -> Vec<ImportedMessage> {
+    let mut out = Vec::new();
+    for conv in value.as_array().into_iter().flatten() {
+        let conv_id = conv.get("uuid").and_then(Value::as_str).unwrap_or("conv");
+        for (i, msg) in conv
+            .get("chat_messages")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let mut text = msg
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string();
+            if text.trim().is_empty() {
+                text = msg["content"]
+                    .as_array()
+                    .map(|blocks| {
+                        blocks
+                            .iter()
+                            .filter(|b| b["type"] == "text")
+                            .filter_map(|b| b["text"].as_str())
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    })
+                    .unwrap_or_default();
+            }
+            if text.trim().is_empty() {
+                continue;
+            }
+            let is_assistant = msg["sender"].as_str() == Some("assistant");
+            let msg_id = msg
+                .get("uuid")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .unwrap_or_else(|| i.to_string());
+            out.push(ImportedMessage {
+                id: format!("{conv_id}:{msg_id}"),
+                sender: if is_assistant { "Claude" } else { "user" }.into(),
+                content: text,
+                is_assistant,
+                timestamp: msg["created_at"].as_str().unwrap_or("").to_string(),
+            });
+        }
+    }
+    out
+}
+
+/// Telegram Desktop `result.json` (single chat export). Service messages are skipped.
+pub fn parse_telegram(value: &Value) -> Vec<ImportedMessage> {
+    let chat_export_id = value.get("id").and_then(Value::as_i64).unwrap_or(0);
+    let mut out = Vec::new();
+    for msg in value["messages"].as_array().into_iter().flatten() {
+        if msg["type"].as_str() != Some("message") {
+            continue;
+        }
+        let text = match &msg["text"] {
+            Value::String(s) => s.clone(),
+            Value::Array(parts) => parts
+                .iter()
+                .filter_map(|p| p.as_str().or_else(|| p["text"].as_str()))
+                .collect::<String>(),
+            _ => String::new(),
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        let ts = msg["date_unixtime"]
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .and_then(unix_to_rfc3339)
+            .or_else(|| {
+                msg["date"].as_str().and_then(|d| {
+                    chrono::NaiveDateTime::parse_from_str(d, "%Y-%m-%dT%H:%M:%S")
+                        .ok()
+                        .map(|t| t.and_utc().to_rfc3339())
+                })
+            })
+            .unwrap_or_default();
+        out.push(ImportedMessage {
+            id: format!("{chat_export_id}:{}", msg["id"]),
+            sender: msg["from"].as_str().unwrap_or("unknown").to_string(),
+            content: text,
+            is_assistant: false,
+            timestamp: ts,
+        });
+    }
+    out
+}
+
+pub fn parse_export(format: ImportFormat, value: &Value) -> Vec<ImportedMessage> {
+    match format {
+        ImportFormat::ChatGpt => parse_chatgpt(value),
+        ImportFormat::Claude => parse_claude(value),
+        ImportFormat::Telegram => parse_telegram(value),
+    }
+}
+
+/// Map imported messages onto a chat/persona. Messages without a timestamp get the import time.
+pub fn to_stored(
+    format: ImportFormat,
+    messages: Vec<ImportedMessage>,
+    chat_id: i64,
+    persona_id: i64,
+) -> Vec<StoredMessage> {
+    let now = chrono::Utc::now().to_rfc3339();
+    messages
+        .into_iter()
+        .map(|m| StoredMessage {
+            id: format!("import:{}:{}", format.id_prefix(), m.id),
+            chat_id,
+            persona_id,
+            sender_name: m.sender,
+            content: m.content,
+            is_from_bot: m.is_assistant,
+            timestamp: if m.timestamp.is_empty() {
+                now.clone()
+            } else {
+                m.timestamp
+            },
+        })
+        .collect()
+}
+
+/// Read the export JSON. Zip archives are read with `unzip` (conversations.json or result.json).
+fn read_export(path: &Path) -> Result<Value, String> {
+    let raw = if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+    {
+        let mut found = None;
+        for member in ["conversations.json", "result.json"] {
+            let output = std::process::Command::new("unzip")
+                .arg("-p")
+                .arg(path)
+                .arg(member)
+                .output()
+                .map_err(|e| format!("failed to run unzip (is it installed?): {e}"))?;
+            if output.status.success() && !output.stdout.is_empty() {
+                found = Some(String::from_utf8_lossy(&output.stdout).to_string());
+                break;
+            }
+        }
+        found.ok_or_else(|| {
+            format!(
+                "{} contains neither conversations.json nor result.json",
+                path.display()
+            )
+        })?
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?
+    };
+    serde_json::from_str(&raw).map_err(|e| format!("invalid JSON in {}: {e}", path.display()))
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Entry point for `microclaw import <file> --chat <id> [--persona <id>] [--format <fmt>]`.
+pub fn run_cli(args: &[String]) -> anyhow::Result<()> {
+    let usage = "Usage: microclaw import <export.zip|export.json> --chat <chat_id> [--persona <persona_id>] [--format chatgpt|claude|telegram]\n\nImports conversations from a ChatGPT data export, a Claude export, or a Telegram Desktop chat export (result.json) into the given chat's stored history. The format is detected when --format is omitted. Re-importing the same export does not create duplicates.";
+    if args.is_empty() || args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{usage}");
+        return Ok(());
+    }
+    let Some(chat_id) = flag_value(args, "--chat").and_then(|v| v.parse::<i64>().ok()) else {
+        anyhow::bail!("--chat <chat_id> is required\n\n{usage}");
+    };
+    let path = Path::new(&args[0]);
+    let value = read_export(path).map_err(|e| anyhow::anyhow!(e))?;
+    let format = match flag_value(args, "--format") {
+        Some(f) => ImportFormat::parse(f).ok_or_else(|| anyhow::anyhow!("unknown format: {f}"))?,
+        None => detect_format(&value)
+            .ok_or_else(|| anyhow::anyhow!("could not detect the export format; pass --format"))?,
+    };
+
+    let config = Config::load()?;
+    let mounted = crate::workspace_crypto::mount(&config)?;
+    let db = Database::new(&config.runtime_data_dir())?;
+    if db.get_chat_type(chat_id)?.is_none() {
+        db.upsert_chat(chat_id, Some("Imported history"), "web")?;
+    }
+    let persona_id = match flag_value(args, "--persona") {
+        Some(p) => p
+            .parse::<i64>()
+            .map_err(|_| anyhow::anyhow!("invalid --persona: {p}"))?,
+        None => db.get_current_persona_id(chat_id)?,
+    };
+
+    let messages = to_stored(format, parse_export(format, &value), chat_id, persona_id);
+    if messages.is_empty() {
+        println!("No messages found in {}.", path.display());
+    } else {
+        db.store_messages(&messages)?;
+        println!(
+            "Imported {} messages ({:?}) into chat {chat_id}, persona {persona_id}.",
+            messages.len(),
+            format
+        );
+    }
+    // Put the runtime away again; a running bot owns its unpacked copy and seals it itself
+    if mounted {
+        crate::workspace_crypto::unmount(&config, Some(&db))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_chatgpt_follows_current_branch() {
+        let export = json!([{
+            "id": "c1",
+            "create_time": 1700000000.0,
+            "current_node": "n3",
+            "mapping": {
+                "root": {"message": null, "parent": null},
+                "n1": {"parent": "root", "message": {"author": {"role": "user"}, "create_time": 1700000001.0, "content": {"parts": ["hi"]}}},
+                "n2": {"parent": "n1", "message": {"author": {"role": "assistant"}, "content": {"parts": ["old answer"]}}},
+                "n3": {"parent": "n1", "message": {"author": {"role": "assistant"}, "content": {"parts": ["hello!"]}}},
+                "sys": {"parent": "root", "message": {"author": {"role": "system"}, "content": {"parts": ["x"]}}}
+            }
+        }]);
+        assert_eq!(detect_format(&export), Some(ImportFormat::ChatGpt));
+        let msgs = parse_chatgpt(&export);
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(
+            (msgs[0].content.as_str(), msgs[0].is_assistant),
+            ("hi", false)
+        );
+        assert_eq!(
+            (msgs[1].content.as_str(), msgs[1].id.as_str()),
+            ("hello!", "c1:n3")
+        );
+        assert!(msgs[0].timestamp.starts_with("2023-11-14T22:13:21"));
+    }
+
+    #[test]
+    fn test_claude_and_telegram_exports() {
+        let claude = json!([{
+            "uuid": "u1",
+            "chat_messages": [
+                {"uuid": "m1", "sender": "human", "text": "question", "created_at": "2024-05-01T10:00:00Z"},
+                {"uuid": "m2", "sender": "assistant", "text": "", "content": [{"type": "text", "text": "answer"}], "created_at": "2024-05-01T10:00:05Z"}
+            ]
+        }]);
+        assert_eq!(detect_format(&claude), Some(ImportFormat::Claude));
+        let msgs = parse_claude(&claude);
+        assert_eq!(msgs[1].content, "answer");
+        assert!(msgs[1].is_assistant);
+
+        let telegram = json!({
+            "id": 42,
+            "messages": [
+                {"id": 1, "type": "service", "action": "create_group"},
+                {"id": 2, "type": "message", "date": "2024-01-02T03:04:05", "from": "Alice", "text": ["see ", {"type": "link", "text": "example.com"}]}
+            ]
+        });
+        assert_eq!(detect_format(&telegram), Some(ImportFormat::Telegram));
+        let msgs = parse_telegram(&telegram);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].content, "see example.com");
+        assert_eq!(msgs[0].timestamp, "2024-01-02T03:04:05+00:00");
+    }
+
+    #[test]
+    fn test_import_is_idempotent() {
+        let dir = std::env::temp_dir().join(format!("microclaw_import_{}", uuid::Uuid::new_v4()));
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+        let msgs = vec![ImportedMessage {
+            id: "c:1".into(),
+            sender: "user".into(),
+            content: "remember the blue door".into(),
+            is_assistant: false,
+            timestamp: "2024-01-01T00:00:00+00:00".into(),
+        }];
+        let stored = to_stored(ImportFormat::ChatGpt, msgs, 9, 1);
+        assert_eq!(stored[0].id, "import:chatgpt:c:1");
+        db.store_messages(&stored).unwrap();
+        db.store_messages(&stored).unwrap();
+        assert_eq!(db.get_all_messages(9, 1).unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
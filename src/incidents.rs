@@ -0,0 +1,434 @@
+//! Incident mode for urgent alerts. An incident is raised by `POST /api/incidents`, the
+//! `incident` tool, or a monitor whose source has the `incident` notification priority. It skips
+//! digests and the `proactive_messages` flag, pages the incident chat (mentioning
+//! `incidents.ping_users`) and repeats the page with doubling gaps until someone acknowledges it
+//! with `/ack`, the tool or the web API. Each incident gets its own session (`incident-<id>`,
+//! visible in the web UI) where the alert and every later update are logged.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+use teloxide::Bot;
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::config::{Config, IncidentConfig};
+use crate::db::{call_blocking, Database, Incident, StoredMessage};
+use crate::telegram::AppState;
+
+/// How often due repeat pages are checked.
+const ESCALATION_TICK: Duration = Duration::from_secs(30);
+
+fn settings(config: &Config) -> IncidentConfig {
+    config.incidents.clone().unwrap_or_default()
+}
+
+/// Chat that receives the pages: `incidents.chat_id`, else the first control chat.
+pub fn incident_chat_id(config: &Config) -> Option<i64> {
+    config
+        .incidents
+        .as_ref()
+        .and_then(|i| i.chat_id)
+        .or_else(|| config.control_chat_ids.first().copied())
+}
+
+/// Chat id for a new incident's session (stored with the incident).
+pub fn session_chat_id(incident_id: i64) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    format!("incident:{incident_id}").hash(&mut hasher);
+    (hasher.finish() & 0x3FFF_FFFF_FFFF_FFFF) as i64
+}
+
+/// Delay before repeat page number `repeats + 1`, or `None` once the repeats are used up.
+pub fn next_page_delay(settings: &IncidentConfig, repeats: u32) -> Option<Duration> {
+    if repeats >= settings.max_repeats {
+        return None;
+    }
+    let mins = settings
+        .repeat_after_mins
+        .max(1)
+        .saturating_mul(1u64 << repeats.min(16));
+    Some(Duration::from_secs(mins.saturating_mul(60)))
+}
+
+fn due_at(delay: Option<Duration>) -> Option<String> {
+    delay.map(|d| (chrono::Utc::now() + chrono::Duration::seconds(d.as_secs() as i64)).to_rfc3339())
+}
+
+/// Text of a page; `repeat` is the repeat number for reminders.
+pub fn page_text(
+    settings: &IncidentConfig,
+    incident: &Incident,
+    details: Option<&str>,
+    repeat: Option<i64>,
+) -> String {
+    let mut out = match repeat {
+        Some(n) => format!(
+            "🚨 Still unacknowledged (reminder {n}): incident #{} — {}",
+            incident.id, incident.title
+        ),
+        None => format!("🚨 INCIDENT #{} — {}", incident.id, incident.title),
+    };
+    if let Some(details) = details.map(str::trim).filter(|d| !d.is_empty()) {
+        out.push_str(&format!("\n{details}"));
+    }
+    out.push_str(&format!("\nSource: {}", incident.source));
+    if !settings.ping_users.is_empty() {
+        out.push_str(&format!("\n{}", settings.ping_users.join(" ")));
+    }
+    out.push_str(&format!(
+        "\nReply /ack {} to acknowledge. Updates are logged in session incident-{}.",
+        incident.id, incident.id
+    ));
+    out
+}
+
+/// Log a line in the incident's session.
+async fn log_update(db: Arc<Database>, incident: &Incident, text: &str) {
+    let mut msg = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        chat_id: incident.session_chat_id,
+        persona_id: 0,
+        sender_name: format!("incident:{}", incident.source),
+        content: text.to_string(),
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let result = call_blocking(db, move |d| {
+        msg.persona_id = d.get_current_persona_id(msg.chat_id)?;
+        d.store_message(&msg)
+    })
+    .await;
+    if let Err(e) = result {
+        warn!("Incident #{}: failed to log update: {e}", incident.id);
+    }
+}
+
+/// Result of raising an alert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raised {
+    pub id: i64,
+    /// False when the alert was logged as an update of an open incident with the same key.
+    pub new: bool,
+}
+
+/// Open an incident and page the incident chat, or, when an unresolved incident has the same
+/// `key`, log the alert as an update of it.
+pub async fn raise(
+    bot: &Bot,
+    db: Arc<Database>,
+    config: &Config,
+    source: &str,
+    key: &str,
+    title: &str,
+    details: Option<&str>,
+) -> Result<Raised, String> {
+    let update_text = match details.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(details) => format!("{title}\n{details}"),
+        None => title.to_string(),
+    };
+    let lookup_key = key.to_string();
+    let existing = call_blocking(db.clone(), move |d| d.find_active_incident(&lookup_key))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(incident) = existing {
+        log_update(db, &incident, &update_text).await;
+        info!("Incident #{}: logged update from {source}", incident.id);
+        return Ok(Raised {
+            id: incident.id,
+            new: false,
+        });
+    }
+
+    let chat_id = incident_chat_id(config)
+        .ok_or("No incident chat: set incidents.chat_id or control_chat_ids")?;
+    let settings = settings(config);
+    let next_ping_at = due_at(next_page_delay(&settings, 0));
+    let (key, title_owned, source_owned) = (key.to_string(), title.to_string(), source.to_string());
+    let incident = call_blocking(db.clone(), move |d| {
+        // The session id depends on the row id, so the row is created first
+        let id = d.create_incident(
+            &key,
+            &title_owned,
+            &source_owned,
+            chat_id,
+            next_ping_at.as_deref(),
+        )?;
+        let session = session_chat_id(id);
+        d.set_incident_session(id, session)?;
+        d.upsert_chat(session, Some(&format!("incident-{id}")), "web")?;
+        d.get_incident(id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or("Incident vanished after creation")?;
+
+    log_update(db.clone(), &incident, &update_text).await;
+    let text = page_text(&settings, &incident, details, None);
+    let persona_id = call_blocking(db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .unwrap_or(0);
+    if let Err(e) =
+        deliver_and_store_bot_message(bot, db, &config.bot_username, chat_id, persona_id, &text)
+            .await
+    {
+        // Paging is retried by the escalation loop
+        warn!("Incident #{}: first page failed: {e}", incident.id);
+    }
+    info!("Incident #{} opened by {source}: {title}", incident.id);
+    Ok(Raised {
+        id: incident.id,
+        new: true,
+    })
+}
+
+/// Acknowledge (stop paging) or resolve an incident, logging who did it.
+pub async fn set_status(
+    db: Arc<Database>,
+    id: i64,
+    status: &str,
+    by: &str,
+) -> Result<Incident, String> {
+    let (status_owned, by_owned) = (status.to_string(), by.to_string());
+    let incident = call_blocking(db.clone(), move |d| {
+        if !d.set_incident_status(id, &status_owned, Some(&by_owned))? {
+            return Ok(None);
+        }
+        d.get_incident(id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Incident #{id} not found or already resolved."))?;
+    log_update(db, &incident, &format!("{status} by {by}")).await;
+    Ok(incident)
+}
+
+/// One line per incident.
+pub fn format_incidents(incidents: &[Incident]) -> String {
+    if incidents.is_empty() {
+        return "No open incidents.".into();
+    }
+    incidents
+        .iter()
+        .map(|i| {
+            let mut line = format!("#{} [{}] {} ({})", i.id, i.status, i.title, i.source);
+            if let Some(by) = &i.acknowledged_by {
+                line.push_str(&format!(" — ack by {by}"));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `/ack [id]`: acknowledge an incident (default: the newest open one). Allowed from the
+/// incident chat, a control chat or the incident's session.
+pub async fn handle_ack_command(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    text: &str,
+) -> String {
+    let arg = text.split_whitespace().nth(1).map(str::to_string);
+    let incident = match arg {
+        Some(arg) => match arg.trim_start_matches('#').parse::<i64>() {
+            Ok(id) => call_blocking(db.clone(), move |d| d.get_incident(id)).await,
+            Err(_) => return "Usage: /ack [incident id]".into(),
+        },
+        None => call_blocking(db.clone(), |d| d.list_incidents(false, 50))
+            .await
+            .map(|list| list.into_iter().find(|i| i.status == "open")),
+    };
+    let incident = match incident {
+        Ok(Some(i)) => i,
+        Ok(None) => return "No open incident to acknowledge.".into(),
+        Err(e) => return format!("Error: {e}"),
+    };
+    let allowed = Some(chat_id) == incident_chat_id(config)
+        || config.control_chat_ids.contains(&chat_id)
+        || chat_id == incident.session_chat_id
+        || chat_id == incident.chat_id;
+    if !allowed {
+        return "Incidents can only be acknowledged from the incident chat or a control chat."
+            .into();
+    }
+    if incident.status != "open" {
+        return format!("Incident #{} is already {}.", incident.id, incident.status);
+    }
+    match set_status(db, incident.id, "acknowledged", &format!("chat {chat_id}")).await {
+        Ok(i) => format!("✅ Incident #{} acknowledged: {}", i.id, i.title),
+        Err(e) => e,
+    }
+}
+
+async fn send_due_pages(state: &Arc<AppState>) {
+    let now = chrono::Utc::now().to_rfc3339();
+    let due = match call_blocking(state.db.clone(), move |d| d.due_incident_pages(&now)).await {
+        Ok(due) => due,
+        Err(e) => {
+            warn!("Incidents: failed to load due pages: {e}");
+            return;
+        }
+    };
+    let settings = settings(&state.config);
+    for incident in due {
+        let repeat = incident.repeats + 1;
+        let text = page_text(&settings, &incident, None, Some(repeat));
+        let chat_id = incident.chat_id;
+        let persona_id =
+            call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+        if let Err(e) = deliver_and_store_bot_message(
+            &state.bot,
+            state.db.clone(),
+            &state.config.bot_username,
+            chat_id,
+            persona_id,
+            &text,
+        )
+        .await
+        {
+            warn!("Incident #{}: reminder failed: {e}", incident.id);
+        }
+        let next = due_at(next_page_delay(&settings, repeat as u32));
+        let id = incident.id;
+        if let Err(e) = call_blocking(state.db.clone(), move |d| {
+            d.record_incident_page(id, repeat, next.as_deref())
+        })
+        .await
+        {
+            warn!("Incident #{id}: failed to record reminder: {e}");
+        }
+    }
+}
+
+/// Start the loop that re-pages unacknowledged incidents.
+pub fn spawn_escalation(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(ESCALATION_TICK).await;
+            send_due_pages(&state).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (Arc<Database>, std::path::PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_incidents_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        (db, dir)
+    }
+
+    #[test]
+    fn test_escalation_schedule_and_page_text() {
+        let settings = IncidentConfig {
+            ping_users: vec!["@alice".into(), "@bob".into()],
+            max_repeats: 3,
+            ..IncidentConfig::default()
+        };
+        let delays: Vec<Option<u64>> = (0..4)
+            .map(|r| next_page_delay(&settings, r).map(|d| d.as_secs() / 60))
+            .collect();
+        assert_eq!(delays, vec![Some(5), Some(10), Some(20), None]);
+
+        let incident = Incident {
+            id: 7,
+            key: "ups".into(),
+            title: "UPS on battery".into(),
+            source: "api".into(),
+            status: "open".into(),
+            chat_id: 1,
+            session_chat_id: session_chat_id(7),
+            repeats: 0,
+            next_ping_at: None,
+            acknowledged_by: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        let text = page_text(&settings, &incident, Some("Load 40%"), None);
+        assert!(text.starts_with("🚨 INCIDENT #7 — UPS on battery\nLoad 40%"));
+        assert!(text.contains("@alice @bob"));
+        assert!(text.contains("/ack 7"));
+        assert!(page_text(&settings, &incident, None, Some(2)).contains("reminder 2"));
+        assert_eq!(session_chat_id(7), session_chat_id(7));
+        assert_ne!(session_chat_id(7), session_chat_id(8));
+    }
+
+    #[tokio::test]
+    async fn test_raise_dedupes_and_ack_stops_paging() {
+        let (db, dir) = test_db();
+        let mut config: Config = serde_yaml::from_str(
+            "telegram_bot_token: tok\nbot_username: bot\napi_key: key\ncontrol_chat_ids: [100]\n",
+        )
+        .unwrap();
+        config.incidents = Some(IncidentConfig::default());
+        db.upsert_chat(100, Some("ops"), "web").unwrap();
+        let bot = Bot::new("123:fake");
+        let first = raise(
+            &bot,
+            db.clone(),
+            &config,
+            "api",
+            "ups",
+            "UPS on battery",
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(first.new);
+        let again = raise(&bot, db.clone(), &config, "api", "ups", "Battery 50%", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            again,
+            Raised {
+                id: first.id,
+                new: false
+            }
+        );
+
+        let incident = db.get_incident(first.id).unwrap().unwrap();
+        assert_eq!(incident.chat_id, 100);
+        assert!(incident.next_ping_at.is_some());
+        let persona_id = db.get_current_persona_id(incident.session_chat_id).unwrap();
+        let log = db
+            .get_recent_messages(incident.session_chat_id, persona_id, 10)
+            .unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1].content, "Battery 50%");
+
+        assert!(handle_ack_command(&config, db.clone(), 555, "/ack")
+            .await
+            .contains("only be acknowledged"));
+        let reply = handle_ack_command(&config, db.clone(), 100, "/ack").await;
+        assert!(reply.contains(&format!("Incident #{} acknowledged", first.id)));
+        let incident = db.get_incident(first.id).unwrap().unwrap();
+        assert_eq!(incident.status, "acknowledged");
+        assert!(incident.next_ping_at.is_none());
+        assert!(db.due_incident_pages("9999").unwrap().is_empty());
+
+        set_status(db.clone(), first.id, "resolved", "api")
+            .await
+            .unwrap();
+        let reopened = raise(
+            &bot,
+            db.clone(),
+            &config,
+            "api",
+            "ups",
+            "UPS on battery",
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(reopened.new);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,114 @@
+//! Leader election for redundant instances sharing one workspace and database. With
+//! `instance_lock` on, only the instance holding the `leader` lease (`instance_leases`) polls the
+//! chat channels and runs the scheduler; the others serve the web UI read-only and take over once
+//! the leader's heartbeat is older than the lease.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use tracing::{error, info, warn};
+
+use crate::db::{call_blocking, Database};
+use crate::telegram::AppState;
+
+const LEASE_NAME: &str = "leader";
+/// A holder that has not renewed for this long is considered dead.
+const LEASE_SECS: i64 = 30;
+const HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Whether this instance may act (always true when `instance_lock` is off).
+static LEADER: AtomicBool = AtomicBool::new(true);
+
+pub fn is_leader() -> bool {
+    LEADER.load(Ordering::Relaxed)
+}
+
+/// This process's lease holder name: host, pid and a random suffix.
+pub fn instance_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| {
+        let host = std::env::var("HOSTNAME")
+            .ok()
+            .filter(|h| !h.trim().is_empty())
+            .unwrap_or_else(|| "host".into());
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        format!("{host}-{}-{}", std::process::id(), &suffix[..8])
+    })
+}
+
+async fn try_acquire(db: Arc<Database>) -> Result<bool, String> {
+    let stale_before = (chrono::Utc::now() - chrono::Duration::seconds(LEASE_SECS)).to_rfc3339();
+    call_blocking(db, move |d| {
+        d.try_acquire_lease(LEASE_NAME, instance_id(), &stale_before)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Wait until this instance holds the leader lease (at once when `instance_lock` is off), then
+/// keep renewing it in the background. An instance that cannot renew in time exits, so a
+/// supervisor restarts it as a standby instead of two instances polling at once.
+pub async fn acquire_leadership(state: &Arc<AppState>) {
+    if !state.config.instance_lock {
+        return;
+    }
+    LEADER.store(false, Ordering::Relaxed);
+    let mut announced = false;
+    loop {
+        match try_acquire(state.db.clone()).await {
+            Ok(true) => break,
+            Ok(false) if !announced => {
+                info!(
+                    "Instance {} is a standby: another instance holds the lock; serving the web UI read-only",
+                    instance_id()
+                );
+                announced = true;
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Instance lock: failed to check lease: {e}"),
+        }
+        tokio::time::sleep(HEARTBEAT).await;
+    }
+    LEADER.store(true, Ordering::Relaxed);
+    info!(
+        "Instance {} holds the lock and is now active",
+        instance_id()
+    );
+
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        let mut renewed = Instant::now();
+        loop {
+            tokio::time::sleep(HEARTBEAT).await;
+            match try_acquire(db.clone()).await {
+                Ok(true) => renewed = Instant::now(),
+                Ok(false) => {
+                    error!("Instance lock: lease taken over by another instance; exiting");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    warn!("Instance lock: failed to renew lease: {e}");
+                    if renewed.elapsed() >= Duration::from_secs(LEASE_SECS as u64) {
+                        error!("Instance lock: lease expired without renewal; exiting");
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Give up the lease on shutdown so a standby can take over without waiting for it to expire.
+pub async fn release(state: &Arc<AppState>) {
+    if !state.config.instance_lock || !is_leader() {
+        return;
+    }
+    if let Err(e) = call_blocking(state.db.clone(), |d| {
+        d.release_lease(LEASE_NAME, instance_id())
+    })
+    .await
+    {
+        warn!("Instance lock: failed to release lease: {e}");
+    }
+}
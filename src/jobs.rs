@@ -0,0 +1,228 @@
+//! Background jobs: lets a tool hand off long-running work (commands, downloads, indexing,
+//! transcodes) instead of blocking the agent turn. A job is recorded in `background_jobs`, streams
+//! its output there as it goes, and the chat is told when it finishes. `list_jobs`,
+//! `job_status` and `cancel_job` query and stop jobs.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use teloxide::prelude::*;
+use tokio::task::AbortHandle;
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::text::tail_chars;
+
+/// Characters of output kept per job (the tail).
+pub const MAX_JOB_OUTPUT_CHARS: usize = 20_000;
+/// Characters of output included in the completion message.
+const SUMMARY_OUTPUT_CHARS: usize = 1_500;
+
+static RUNNING: Mutex<Option<HashMap<i64, AbortHandle>>> = Mutex::new(None);
+
+/// Handle a job's work uses to report progress.
+#[derive(Clone)]
+pub struct JobContext {
+    id: i64,
+    db: Arc<Database>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Append to the job's stored output (visible through `job_status` while it runs).
+    pub async fn append_output(&self, text: &str) {
+        let (id, text) = (self.id, text.to_string());
+        if let Err(e) = call_blocking(self.db.clone(), move |d| {
+            d.append_background_job_output(id, &text, MAX_JOB_OUTPUT_CHARS)
+        })
+        .await
+        {
+            warn!("Job #{id}: failed to store output: {e}");
+        }
+    }
+}
+
+/// Message sent to the chat when a job ends.
+pub fn completion_message(id: i64, description: &str, result: &Result<String, String>) -> String {
+    match result {
+        Ok(summary) => {
+            let summary = summary.trim();
+            if summary.is_empty() {
+                format!("✅ Job #{id} finished: {description}")
+            } else {
+                format!(
+                    "✅ Job #{id} finished: {description}\n\n{}",
+                    tail_chars(summary, SUMMARY_OUTPUT_CHARS)
+                )
+            }
+        }
+        Err(e) => format!(
+            "❌ Job #{id} failed: {description}\n\n{}",
+            tail_chars(e.trim(), SUMMARY_OUTPUT_CHARS)
+        ),
+    }
+}
+
+/// Starts jobs and notifies the owning chat when they finish.
+#[derive(Clone)]
+pub struct JobRunner {
+    bot: Bot,
+    db: Arc<Database>,
+    bot_username: String,
+}
+
+impl JobRunner {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        JobRunner {
+            bot,
+            db,
+            bot_username: config.bot_username.clone(),
+        }
+    }
+
+    /// Record a job for `chat_id` and run `work` in the background. `work` returns a summary on
+    /// success or an error message; either is sent to the chat.
+    pub async fn submit<F, Fut>(
+        &self,
+        chat_id: i64,
+        persona_id: i64,
+        kind: &str,
+        description: &str,
+        work: F,
+    ) -> Result<i64, String>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        let (k, desc) = (kind.to_string(), description.to_string());
+        let id = call_blocking(self.db.clone(), move |d| {
+            d.create_background_job(chat_id, persona_id, &k, &desc)
+        })
+        .await
+        .map_err(|e| format!("Failed to record job: {e}"))?;
+        info!("Job #{id} ({kind}) started for chat {chat_id}: {description}");
+
+        let ctx = JobContext {
+            id,
+            db: self.db.clone(),
+        };
+        let runner = self.clone();
+        let description = description.to_string();
+        // Hold the registry lock across spawn so the task cannot finish (and deregister)
+        // before it is registered.
+        let mut running = RUNNING.lock().unwrap();
+        let handle = tokio::spawn(async move {
+            let result = work(ctx).await;
+            RUNNING.lock().unwrap().as_mut().map(|r| r.remove(&id));
+            let (status, error) = match &result {
+                Ok(_) => ("succeeded", None),
+                Err(e) => ("failed", Some(e.clone())),
+            };
+            let finished = call_blocking(runner.db.clone(), move |d| {
+                d.finish_background_job(id, status, error.as_deref())
+            })
+            .await
+            .unwrap_or(false);
+            if !finished {
+                // Cancelled meanwhile; the canceller already reported it.
+                return;
+            }
+            info!("Job #{id} {status}");
+            if let Err(e) = deliver_and_store_bot_message(
+                &runner.bot,
+                runner.db.clone(),
+                &runner.bot_username,
+                chat_id,
+                persona_id,
+                &completion_message(id, &description, &result),
+            )
+            .await
+            {
+                warn!("Job #{id}: failed to notify chat {chat_id}: {e}");
+            }
+        });
+        running
+            .get_or_insert_with(HashMap::new)
+            .insert(id, handle.abort_handle());
+        Ok(id)
+    }
+}
+
+/// Stop a running job and mark it cancelled. Returns false when it was not running.
+pub async fn cancel(db: Arc<Database>, id: i64) -> Result<bool, String> {
+    let handle = RUNNING.lock().unwrap().as_mut().and_then(|r| r.remove(&id));
+    if let Some(handle) = handle {
+        handle.abort();
+    }
+    call_blocking(db, move |d| {
+        d.finish_background_job(id, "cancelled", Some("Cancelled"))
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_message() {
+        assert_eq!(
+            completion_message(3, "backup", &Ok(String::new())),
+            "✅ Job #3 finished: backup"
+        );
+        assert_eq!(
+            completion_message(3, "backup", &Ok("done\n".into())),
+            "✅ Job #3 finished: backup\n\ndone"
+        );
+        assert_eq!(
+            completion_message(4, "transcode", &Err("exit 1".into())),
+            "❌ Job #4 failed: transcode\n\nexit 1"
+        );
+        let long = "é".repeat(SUMMARY_OUTPUT_CHARS + 10);
+        assert_eq!(
+            tail_chars(&long, SUMMARY_OUTPUT_CHARS).chars().count(),
+            SUMMARY_OUTPUT_CHARS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_job_lifecycle_and_cancel() {
+        let dir = std::env::temp_dir().join(format!("microclaw_jobs_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let runner = JobRunner::new(&config, Bot::new("123:TEST"), db.clone());
+
+        let id = runner
+            .submit(7, 1, "test", "sleepy", |ctx| async move {
+                ctx.append_output("step 1\n").await;
+                tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                Ok("never".into())
+            })
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let job = db.get_background_job(id).unwrap().unwrap();
+        assert_eq!(job.status, "running");
+        assert_eq!(job.output, "step 1\n");
+
+        assert!(cancel(db.clone(), id).await.unwrap());
+        assert!(!cancel(db.clone(), id).await.unwrap());
+        let job = db.get_background_job(id).unwrap().unwrap();
+        assert_eq!(job.status, "cancelled");
+        assert!(job.finished_at.is_some());
+
+        db.append_background_job_output(id, "0123456789", 4)
+            .unwrap();
+        assert_eq!(db.get_background_job(id).unwrap().unwrap().output, "6789");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
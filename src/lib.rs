@@ -1,27 +1,80 @@
+pub mod access;
+pub mod benchmark;
 pub mod builtin_skills;
 pub mod channel;
 pub mod channels;
+pub mod chat_archive;
+pub mod onboarding;
 pub mod orchestrator;
+pub mod output_filter;
 pub mod persona;
+pub mod polls;
+pub mod public_ask;
+pub mod remote;
 pub mod slash_commands;
 pub mod claude;
+pub mod code_review;
 pub mod config;
 pub mod config_wizard;
+pub mod cursor_digest;
+pub mod custom_commands;
 pub mod db;
+pub mod detached;
 pub mod doctor;
+pub mod egress;
+pub mod embeddings;
 pub mod error;
+pub mod eval;
+pub mod experiments;
+pub mod feature_flags;
+pub mod feedback_report;
+pub mod feeds;
 pub mod gateway;
+pub mod heartbeat;
+pub mod home_assistant;
+pub mod idle_memory_flush;
+pub mod import;
+pub mod incidents;
+pub mod instance_lock;
+pub mod jobs;
 pub mod llm;
 pub mod logging;
 pub mod mcp;
 pub mod memory;
+pub mod memory_commands;
+pub mod middleware;
+pub mod moderation;
+pub mod notify;
+pub mod provider_files;
+pub mod push;
+pub mod release_notes;
+pub mod roundtable;
+pub mod schedule_phrase;
 pub mod scheduler;
+pub mod session_trash;
+pub mod state_archive;
 pub mod setup;
 pub mod skills;
+pub mod social_monitor;
 pub mod social_oauth;
+pub mod thinking;
+pub mod text;
+pub mod tmux;
 pub mod tools;
+pub mod topics;
 pub mod transcribe;
+pub mod translation;
+pub mod turn_metrics;
+pub mod usage;
+pub mod vault_index;
+pub mod vault_maintenance;
 pub mod web;
+pub mod workspace_crypto;
 pub use channels::discord;
+pub use channels::signal;
+pub use channels::slack;
+pub use channels::teams;
 pub use channels::telegram;
+pub use channels::telegram_business;
 pub use channels::whatsapp;
+pub use channels::wyoming;
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use futures_util::future::BoxFuture;
 use futures_util::StreamExt;
 use serde::Deserialize;
 use serde_json::json;
@@ -6,11 +7,14 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::warn;
 
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::claude::{
     ContentBlock, ImageSource, Message, MessageContent, MessagesRequest, MessagesResponse,
-    ResponseContentBlock, ToolDefinition, Usage,
+    ResponseContentBlock, ToolChoice, ToolDefinition, Usage,
 };
 use crate::config::Config;
 use crate::error::MicroClawError;
@@ -157,6 +161,19 @@ pub trait LlmProvider: Send + Sync {
         tools: Option<Vec<ToolDefinition>>,
     ) -> Result<MessagesResponse, MicroClawError>;
 
+    /// `send_message` with the model constrained by `tool_choice`. Providers that cannot
+    /// constrain it ignore the choice.
+    async fn send_message_with_tool_choice(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let _ = tool_choice;
+        self.send_message(system, messages, tools).await
+    }
+
     async fn send_message_stream(
         &self,
         system: &str,
@@ -176,19 +193,45 @@ pub trait LlmProvider: Send + Sync {
     }
 }
 
-pub fn create_provider(config: &Config) -> Box<dyn LlmProvider> {
+fn create_single_provider(config: &Config) -> Box<dyn LlmProvider> {
     match config.llm_provider.trim().to_lowercase().as_str() {
         "anthropic" => Box::new(AnthropicProvider::new(config)),
         "google" | "gemini" => Box::new(GeminiProvider::new(config)),
+        "llamacpp" | "llama.cpp" | "llama-cpp" => Box::new(LlamaCppProvider::new(config)),
         _ => Box::new(OpenAiProvider::new(config)),
     }
 }
 
+/// The configured provider, wrapped in a [`FailoverProvider`] when `llm_fallbacks` is set.
+pub fn create_provider(config: &Config) -> Box<dyn LlmProvider> {
+    let primary = create_single_provider(config);
+    if config.llm_fallbacks.is_empty() {
+        return primary;
+    }
+    let mut links = vec![(format!("{}/{}", config.llm_provider, config.model), primary)];
+    for fallback in &config.llm_fallbacks {
+        let mut fallback_config = config.clone();
+        fallback_config.llm_provider = fallback.provider.clone();
+        fallback_config.model = fallback.model.clone();
+        if let Some(key) = fallback.api_key.as_ref().filter(|k| !k.trim().is_empty()) {
+            fallback_config.api_key = key.clone();
+        }
+        fallback_config.llm_base_url = fallback.base_url.clone();
+        links.push((
+            format!("{}/{}", fallback.provider, fallback.model),
+            create_single_provider(&fallback_config),
+        ));
+    }
+    Box::new(FailoverProvider::new(links))
+}
+
 /// Test that a model override is reachable with the current provider/config.
 /// Returns Ok(()) on success, or an error string suitable for showing to the user.
 pub async fn test_model(config: &Config, model_override: &str) -> Result<(), String> {
     let mut test_config = config.clone();
     test_config.model = model_override.to_string();
+    // Test this model only, not the fallbacks behind it
+    test_config.llm_fallbacks.clear();
     let provider = create_provider(&test_config);
     let messages = vec![Message {
         role: "user".into(),
@@ -203,6 +246,187 @@ pub async fn test_model(config: &Config, model_override: &str) -> Result<(), Str
     }
 }
 
+// ---------------------------------------------------------------------------
+// Failover chain
+// ---------------------------------------------------------------------------
+
+const FAILOVER_BASE_COOLDOWN_SECS: u64 = 30;
+const FAILOVER_MAX_COOLDOWN_SECS: u64 = 600;
+
+type FailoverReportFn = Box<dyn Fn(String) + Send + Sync>;
+
+tokio::task_local! {
+    static FAILOVER_REPORT: FailoverReportFn;
+}
+
+/// Run an LLM call with `report` receiving the label of the fallback that answered it, if any.
+pub async fn with_failover_report<F: std::future::Future>(
+    report: impl Fn(String) + Send + Sync + 'static,
+    fut: F,
+) -> F::Output {
+    FAILOVER_REPORT.scope(Box::new(report), fut).await
+}
+
+fn http_error(status: reqwest::StatusCode, message: String) -> MicroClawError {
+    MicroClawError::LlmHttp {
+        status: status.as_u16(),
+        message,
+    }
+}
+
+/// Rate limits, server errors and unreachable endpoints move on to the next provider; request
+/// errors (bad input, auth) would fail the same way everywhere.
+pub fn is_failover_error(error: &MicroClawError) -> bool {
+    match error {
+        MicroClawError::LlmHttp { status, .. } => *status == 429 || *status >= 500,
+        MicroClawError::RateLimited => true,
+        MicroClawError::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+        _ => false,
+    }
+}
+
+struct ChainLink {
+    label: String,
+    provider: Box<dyn LlmProvider>,
+    failures: AtomicU32,
+    cooldown_until: Mutex<Option<Instant>>,
+}
+
+impl ChainLink {
+    fn cooling_down(&self) -> bool {
+        self.cooldown_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Back off exponentially: 30s, 60s, 120s, ... up to 10 minutes.
+    fn record_failure(&self) -> Duration {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        let secs = FAILOVER_BASE_COOLDOWN_SECS
+            .saturating_mul(1 << (failures - 1).min(16))
+            .min(FAILOVER_MAX_COOLDOWN_SECS);
+        let cooldown = Duration::from_secs(secs);
+        *self.cooldown_until.lock().unwrap() = Some(Instant::now() + cooldown);
+        cooldown
+    }
+
+    fn record_success(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+        *self.cooldown_until.lock().unwrap() = None;
+    }
+}
+
+/// Tries each provider in order. One that fails with a rate limit or server error is skipped
+/// while it cools down, so later requests go straight to the next; when all are cooling down
+/// they are tried in order anyway.
+pub struct FailoverProvider {
+    links: Vec<ChainLink>,
+}
+
+impl FailoverProvider {
+    pub fn new(links: Vec<(String, Box<dyn LlmProvider>)>) -> Self {
+        FailoverProvider {
+            links: links
+                .into_iter()
+                .map(|(label, provider)| ChainLink {
+                    label,
+                    provider,
+                    failures: AtomicU32::new(0),
+                    cooldown_until: Mutex::new(None),
+                })
+                .collect(),
+        }
+    }
+
+    fn attempt_order(&self) -> Vec<usize> {
+        let (ready, cooling): (Vec<usize>, Vec<usize>) =
+            (0..self.links.len()).partition(|&i| !self.links[i].cooling_down());
+        ready.into_iter().chain(cooling).collect()
+    }
+
+    async fn call<'a>(
+        &'a self,
+        send: impl Fn(&'a dyn LlmProvider) -> BoxFuture<'a, Result<MessagesResponse, MicroClawError>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let order = self.attempt_order();
+        let mut last_error = None;
+        for (attempt, &i) in order.iter().enumerate() {
+            let link = &self.links[i];
+            match send(link.provider.as_ref()).await {
+                Ok(response) => {
+                    link.record_success();
+                    if i > 0 {
+                        let _ = FAILOVER_REPORT.try_with(|report| report(link.label.clone()));
+                    }
+                    return Ok(response);
+                }
+                Err(e) if is_failover_error(&e) && attempt + 1 < order.len() => {
+                    let cooldown = link.record_failure();
+                    warn!(
+                        "LLM {} failed ({e}); skipping it for {}s and trying {}",
+                        link.label,
+                        cooldown.as_secs(),
+                        self.links[order[attempt + 1]].label
+                    );
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    if is_failover_error(&e) {
+                        link.record_failure();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| MicroClawError::LlmApi("no LLM provider".into())))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for FailoverProvider {
+    async fn send_message(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_message_with_tool_choice(system, messages, tools, ToolChoice::Auto)
+            .await
+    }
+
+    async fn send_message_with_tool_choice(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.call(|provider| {
+            provider.send_message_with_tool_choice(
+                system,
+                messages.clone(),
+                tools.clone(),
+                tool_choice.clone(),
+            )
+        })
+        .await
+    }
+
+    async fn send_message_stream(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        text_tx: Option<&UnboundedSender<String>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.call(|provider| {
+            provider.send_message_stream(system, messages.clone(), tools.clone(), text_tx)
+        })
+        .await
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Anthropic provider
 // ---------------------------------------------------------------------------
@@ -213,6 +437,218 @@ pub struct AnthropicProvider {
     model: String,
     max_tokens: u32,
     base_url: String,
+    prompt_caching: bool,
+    web_search_max_uses: u32,
+}
+
+fn cache_breakpoint(value: &mut serde_json::Value) {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("cache_control".into(), json!({"type": "ephemeral"}));
+    }
+}
+
+/// Put a cache breakpoint on the last content block of a message.
+fn cache_message(message: &mut serde_json::Value) {
+    let Some(content) = message.get_mut("content") else {
+        return;
+    };
+    if let Some(text) = content.as_str() {
+        if text.is_empty() {
+            return;
+        }
+        *content = json!([{"type": "text", "text": text}]);
+    }
+    if let Some(last) = content.as_array_mut().and_then(|blocks| blocks.last_mut()) {
+        cache_breakpoint(last);
+    }
+}
+
+/// Tool offered in chats with the `provider_search` flag: Anthropic runs it as its server-side
+/// web search; with OpenAI the tool itself asks a search model.
+pub const PROVIDER_WEB_SEARCH_TOOL: &str = "provider_web_search";
+
+/// Whether the configured provider has a web search of its own.
+pub fn supports_provider_search(config: &Config) -> bool {
+    match config.llm_provider.as_str() {
+        "anthropic" => true,
+        "openai" => config
+            .llm_base_url
+            .as_deref()
+            .is_none_or(|url| url.contains("api.openai.com")),
+        _ => false,
+    }
+}
+
+const ANTHROPIC_FILES_BETA: &str = "files-api-2025-04-14";
+const ANTHROPIC_CODE_EXECUTION_BETA: &str = "code-execution-2025-08-25";
+
+/// Turn `provider_file` blocks into `document` blocks with a file source, or `container_upload`
+/// blocks for the code execution container; files uploaded to another provider become a note.
+/// Returns whether a container upload is present.
+fn anthropic_file_blocks(body: &mut serde_json::Value) -> bool {
+    let mut container = false;
+    let Some(messages) = body.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+        return false;
+    };
+    let blocks = messages
+        .iter_mut()
+        .filter_map(|m| m.get_mut("content").and_then(|c| c.as_array_mut()))
+        .flatten();
+    for block in blocks {
+        if block["type"] != "provider_file" {
+            continue;
+        }
+        let file_id = block["file_id"].clone();
+        let filename = block["filename"].as_str().unwrap_or_default().to_string();
+        *block = if block["provider"] != "anthropic" {
+            json!({"type": "text", "text": ContentBlock::provider_file_placeholder(&filename)})
+        } else if block["container"] == true {
+            container = true;
+            json!({"type": "container_upload", "file_id": file_id})
+        } else {
+            json!({"type": "document", "source": {"type": "file", "file_id": file_id}, "title": filename})
+        };
+    }
+    container
+}
+
+/// `anthropic-beta` header for bodies that reference uploaded files.
+fn anthropic_beta(body: &serde_json::Value) -> Option<String> {
+    let blocks = body["messages"]
+        .as_array()?
+        .iter()
+        .filter_map(|m| m["content"].as_array())
+        .flatten();
+    let (mut files, mut container) = (false, false);
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("container_upload") => container = true,
+            Some("document") if block["source"]["type"] == "file" => files = true,
+            _ => {}
+        }
+    }
+    match (files || container, container) {
+        (_, true) => Some(format!(
+            "{ANTHROPIC_FILES_BETA},{ANTHROPIC_CODE_EXECUTION_BETA}"
+        )),
+        (true, false) => Some(ANTHROPIC_FILES_BETA.to_string()),
+        _ => None,
+    }
+}
+
+/// Add a cited web source unless its URL is already listed.
+pub(crate) fn add_source(sources: &mut Vec<(String, String)>, title: Option<&str>, url: &str) {
+    if sources.iter().any(|(_, u)| u == url) {
+        return;
+    }
+    let title = title.filter(|t| !t.trim().is_empty()).unwrap_or(url);
+    sources.push((title.replace(['[', ']'], ""), url.to_string()));
+}
+
+/// Cited sources as a numbered list of markdown links after a blank line, for the renderers.
+pub(crate) fn format_sources(sources: &[(String, String)]) -> String {
+    let mut out = String::from("\n\nSources:");
+    for (i, (title, url)) in sources.iter().enumerate() {
+        out.push_str(&format!("\n{}. [{title}]({url})", i + 1));
+    }
+    out
+}
+
+/// The sources cited by a response's text blocks (web search results), in order of first citation.
+fn anthropic_sources(content: &[serde_json::Value]) -> Vec<(String, String)> {
+    let mut sources = Vec::new();
+    let citations = content
+        .iter()
+        .filter(|b| b["type"] == "text")
+        .filter_map(|b| b["citations"].as_array())
+        .flatten();
+    for citation in citations {
+        if let Some(url) = citation["url"].as_str() {
+            add_source(&mut sources, citation["title"].as_str(), url);
+        }
+    }
+    sources
+}
+
+/// Parse a Messages API response. Server tool blocks (code execution, web search) were run by
+/// the API; only the text and client `tool_use` blocks are kept, and sources cited from web
+/// search results are listed after the text.
+fn parse_anthropic_response(body: &str) -> Result<MessagesResponse, serde_json::Error> {
+    let mut value: serde_json::Value = serde_json::from_str(body)?;
+    if let Some(content) = value.get_mut("content").and_then(|c| c.as_array_mut()) {
+        let sources = anthropic_sources(content);
+        content.retain(|b| matches!(b["type"].as_str(), Some("text") | Some("tool_use")));
+        if !sources.is_empty() {
+            let at = content
+                .iter()
+                .rposition(|b| b["type"] == "text")
+                .map_or(content.len(), |i| i + 1);
+            content.insert(at, json!({"type": "text", "text": format_sources(&sources)}));
+        }
+    }
+    serde_json::from_value(value)
+}
+
+/// The JSON body of an Anthropic request. The `provider_web_search` tool becomes Anthropic's
+/// server-side web search. With prompt caching, breakpoints go on the system
+/// prompt, the last tool definition, the end of a compacted summary and the latest message, so
+/// each round re-reads the unchanged prefix from the cache instead of paying full input price
+/// (four breakpoints, the most the API accepts).
+fn anthropic_request_body(
+    request: &MessagesRequest,
+    prompt_caching: bool,
+    web_search_max_uses: u32,
+) -> serde_json::Value {
+    let mut body = serde_json::to_value(request).unwrap_or_default();
+    if let Some(tools) = body.get_mut("tools").and_then(|t| t.as_array_mut()) {
+        for tool in tools.iter_mut() {
+            if tool["name"] == PROVIDER_WEB_SEARCH_TOOL {
+                *tool = json!({
+                    "type": "web_search_20250305",
+                    "name": "web_search",
+                    "max_uses": web_search_max_uses
+                });
+            }
+        }
+    }
+    if anthropic_file_blocks(&mut body) {
+        let tool = json!({"type": "code_execution_20250825", "name": "code_execution"});
+        match body.get_mut("tools").and_then(|t| t.as_array_mut()) {
+            Some(tools) => tools.push(tool),
+            None => body["tools"] = json!([tool]),
+        }
+    }
+    if !prompt_caching {
+        return body;
+    }
+    if !request.system.is_empty() {
+        body["system"] = json!([{
+            "type": "text",
+            "text": request.system,
+            "cache_control": {"type": "ephemeral"}
+        }]);
+    }
+    if let Some(last_tool) = body
+        .get_mut("tools")
+        .and_then(|t| t.as_array_mut())
+        .and_then(|t| t.last_mut())
+    {
+        cache_breakpoint(last_tool);
+    }
+    if let Some(messages) = body.get_mut("messages").and_then(|m| m.as_array_mut()) {
+        let summarized = messages
+            .first()
+            .and_then(|m| m["content"].as_str())
+            .is_some_and(|t| t.starts_with("[Conversation Summary]"));
+        if summarized && messages.len() > 2 {
+            // The summary and its acknowledgement stay fixed until the next compaction
+            cache_message(&mut messages[1]);
+        }
+        if let Some(message) = messages.last_mut() {
+            cache_message(message);
+        }
+    }
+    body
 }
 
 impl AnthropicProvider {
@@ -226,6 +662,8 @@ impl AnthropicProvider {
                 .llm_base_url
                 .clone()
                 .unwrap_or_else(|| "https://api.anthropic.com/v1/messages".into()),
+            prompt_caching: config.anthropic_prompt_caching,
+            web_search_max_uses: config.provider_search.clone().unwrap_or_default().max_uses,
         }
     }
 
@@ -237,26 +675,32 @@ impl AnthropicProvider {
         let mut streamed_request = request.clone();
         streamed_request.stream = Some(true);
 
-        let response = self
+        let body = anthropic_request_body(
+            &streamed_request,
+            self.prompt_caching,
+            self.web_search_max_uses,
+        );
+        let mut req = self
             .http
             .post(&self.base_url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&streamed_request)
-            .send()
-            .await?;
+            .header("content-type", "application/json");
+        if let Some(beta) = anthropic_beta(&body) {
+            req = req.header("anthropic-beta", beta);
+        }
+        let response = req.json(&body).send().await?;
 
         let status = response.status();
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
             if let Ok(api_err) = serde_json::from_str::<AnthropicApiError>(&body) {
-                return Err(MicroClawError::LlmApi(format!(
-                    "{}: {}",
-                    api_err.error.error_type, api_err.error.message
-                )));
+                return Err(http_error(
+                    status,
+                    format!("{}: {}", api_err.error.error_type, api_err.error.message),
+                ));
             }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
+            return Err(http_error(status, format!("HTTP {status}: {body}")));
         }
 
         let mut byte_stream = response.bytes_stream();
@@ -615,37 +1059,57 @@ impl LlmProvider for AnthropicProvider {
         system: &str,
         messages: Vec<Message>,
         tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_message_with_tool_choice(system, messages, tools, ToolChoice::Auto)
+            .await
+    }
+
+    async fn send_message_with_tool_choice(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
     ) -> Result<MessagesResponse, MicroClawError> {
         let messages = sanitize_messages(messages);
 
+        let tool_choice = tools
+            .as_ref()
+            .filter(|t| !t.is_empty())
+            .and_then(|_| tool_choice.anthropic());
         let request = MessagesRequest {
             model: self.model.clone(),
             max_tokens: self.max_tokens,
             system: system.to_string(),
             messages,
             tools,
+            tool_choice,
             stream: None,
         };
 
+        let body =
+            anthropic_request_body(&request, self.prompt_caching, self.web_search_max_uses);
+        let beta = anthropic_beta(&body);
         let mut retries = 0u32;
         let max_retries = 3;
 
         loop {
-            let response = self
+            let mut req = self
                 .http
                 .post(&self.base_url)
                 .header("x-api-key", &self.api_key)
                 .header("anthropic-version", "2023-06-01")
-                .header("content-type", "application/json")
-                .json(&request)
-                .send()
-                .await?;
+                .header("content-type", "application/json");
+            if let Some(beta) = &beta {
+                req = req.header("anthropic-beta", beta);
+            }
+            let response = req.json(&body).send().await?;
 
             let status = response.status();
 
             if status.is_success() {
                 let body = response.text().await?;
-                let parsed: MessagesResponse = serde_json::from_str(&body).map_err(|e| {
+                let parsed = parse_anthropic_response(&body).map_err(|e| {
                     MicroClawError::LlmApi(format!("Failed to parse response: {e}\nBody: {body}"))
                 })?;
                 return Ok(parsed);
@@ -664,12 +1128,12 @@ impl LlmProvider for AnthropicProvider {
 
             let body = response.text().await.unwrap_or_default();
             if let Ok(api_err) = serde_json::from_str::<AnthropicApiError>(&body) {
-                return Err(MicroClawError::LlmApi(format!(
-                    "{}: {}",
-                    api_err.error.error_type, api_err.error.message
-                )));
+                return Err(http_error(
+                    status,
+                    format!("{}: {}", api_err.error.error_type, api_err.error.message),
+                ));
             }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
+            return Err(http_error(status, format!("HTTP {status}: {body}")));
         }
     }
 
@@ -687,6 +1151,7 @@ impl LlmProvider for AnthropicProvider {
             system: system.to_string(),
             messages,
             tools,
+            tool_choice: None,
             stream: Some(true),
         };
 
@@ -813,6 +1278,17 @@ impl LlmProvider for OpenAiProvider {
         system: &str,
         messages: Vec<Message>,
         tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_message_with_tool_choice(system, messages, tools, ToolChoice::Auto)
+            .await
+    }
+
+    async fn send_message_with_tool_choice(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
     ) -> Result<MessagesResponse, MicroClawError> {
         let oai_messages = translate_messages_to_oai(system, &messages);
 
@@ -825,6 +1301,9 @@ impl LlmProvider for OpenAiProvider {
         if let Some(ref tool_defs) = tools {
             if !tool_defs.is_empty() {
                 body["tools"] = json!(translate_tools_to_oai(tool_defs));
+                if let Some(choice) = tool_choice.openai() {
+                    body["tool_choice"] = choice;
+                }
             }
         }
 
@@ -868,9 +1347,9 @@ impl LlmProvider for OpenAiProvider {
             let text = response.text().await.unwrap_or_default();
             if let Ok(err) = serde_json::from_str::<OaiErrorResponse>(&text) {
                 let msg = format_oai_error(status, &err.error, &text);
-                return Err(MicroClawError::LlmApi(msg));
+                return Err(http_error(status, msg));
             }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {text}")));
+            return Err(http_error(status, format!("HTTP {status}: {text}")));
         }
     }
 
@@ -910,9 +1389,9 @@ impl LlmProvider for OpenAiProvider {
             let text = response.text().await.unwrap_or_default();
             if let Ok(err) = serde_json::from_str::<OaiErrorResponse>(&text) {
                 let msg = format_oai_error(status, &err.error, &text);
-                return Err(MicroClawError::LlmApi(msg));
+                return Err(http_error(status, msg));
             }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {text}")));
+            return Err(http_error(status, format!("HTTP {status}: {text}")));
         }
 
         let mut byte_stream = response.bytes_stream();
@@ -982,6 +1461,317 @@ impl LlmProvider for OpenAiProvider {
     }
 }
 
+// ---------------------------------------------------------------------------
+// llama.cpp server provider (tool calls emulated through a JSON schema)
+// ---------------------------------------------------------------------------
+
+/// Talks to a local `llama-server`. Tool definitions are described in the system prompt and the
+/// output is constrained (the server turns `response_format` into a grammar) to a JSON object of
+/// `tool_calls` and `reply`, which is parsed back into tool-use blocks. History is sent as plain
+/// text turns, so models without a tool-calling chat template work too.
+pub struct LlamaCppProvider {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+    max_tokens: u32,
+    chat_url: String,
+}
+
+impl LlamaCppProvider {
+    pub fn new(config: &Config) -> Self {
+        let base = config
+            .llm_base_url
+            .as_deref()
+            .unwrap_or("http://127.0.0.1:8080/v1");
+        let chat_url = format!("{}/chat/completions", base.trim_end_matches('/'));
+
+        LlamaCppProvider {
+            http: reqwest::Client::new(),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            max_tokens: config.max_tokens,
+            chat_url,
+        }
+    }
+}
+
+/// Instructions appended to the system prompt when tools are available.
+fn emulated_tools_prompt(tools: &[ToolDefinition]) -> String {
+    let mut out = String::from(
+        "\n\n# Tools\n\nAnswer with exactly one JSON object and nothing else:\n\
+         {\"tool_calls\": [{\"name\": \"<tool>\", \"arguments\": {...}}], \"reply\": \"<text for the user>\"}\n\
+         To use tools, list the calls in tool_calls (reply may be empty); their results come back \
+         in the next message. To answer without tools, leave tool_calls empty and put the whole \
+         answer in reply.\n\nAvailable tools:",
+    );
+    for tool in tools {
+        out.push_str(&format!(
+            "\n\n## {}\n{}\nParameters (JSON schema): {}",
+            tool.name,
+            tool.description_with_examples(),
+            tool.input_schema
+        ));
+    }
+    out
+}
+
+/// Schema for the constrained answer. `choice` narrows which tools may (or must) be called.
+fn emulated_tools_schema(tools: &[ToolDefinition], choice: &ToolChoice) -> serde_json::Value {
+    let names: Vec<&str> = match choice {
+        ToolChoice::Tool(name) => vec![name.as_str()],
+        _ => tools.iter().map(|t| t.name.as_str()).collect(),
+    };
+    let mut calls = json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "enum": names},
+                "arguments": {"type": "object"}
+            },
+            "required": ["name", "arguments"]
+        }
+    });
+    if matches!(choice, ToolChoice::Any | ToolChoice::Tool(_)) {
+        calls["minItems"] = json!(1);
+    }
+    json!({
+        "type": "object",
+        "properties": {
+            "tool_calls": calls,
+            "reply": {"type": "string"}
+        },
+        "required": ["tool_calls", "reply"]
+    })
+}
+
+/// Flatten the conversation into plain user/assistant turns: tool calls become the JSON the model
+/// is asked to produce, tool results become user text naming the tool.
+fn translate_messages_to_llamacpp(
+    system: &str,
+    messages: &[Message],
+    emulate_tools: bool,
+) -> Vec<serde_json::Value> {
+    let mut tool_names: std::collections::HashMap<&str, &str> = std::collections::HashMap::new();
+    let mut out = vec![json!({"role": "system", "content": system})];
+    for msg in messages {
+        let content = match &msg.content {
+            MessageContent::Text(text) if msg.role == "assistant" && emulate_tools => {
+                json!({"tool_calls": [], "reply": text}).to_string()
+            }
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Blocks(blocks) => {
+                let mut texts = Vec::new();
+                let mut calls = Vec::new();
+                for block in blocks {
+                    match block {
+                        ContentBlock::Text { text } => texts.push(text.clone()),
+                        ContentBlock::Image { .. } => texts.push("[image omitted]".into()),
+                        ContentBlock::ProviderFile { filename, .. } => {
+                            texts.push(ContentBlock::provider_file_placeholder(filename))
+                        }
+                        ContentBlock::ToolUse {
+                            id, name, input, ..
+                        } => {
+                            tool_names.insert(id.as_str(), name.as_str());
+                            calls.push(json!({"name": name, "arguments": input}));
+                        }
+                        ContentBlock::ToolResult {
+                            tool_use_id,
+                            content,
+                            is_error,
+                        } => {
+                            let name = tool_names.get(tool_use_id.as_str()).unwrap_or(&"tool");
+                            let label = if *is_error == Some(true) {
+                                "error"
+                            } else {
+                                "result"
+                            };
+                            texts.push(format!("Tool {label} from {name}:\n{content}"));
+                        }
+                    }
+                }
+                if msg.role == "assistant" && (emulate_tools || !calls.is_empty()) {
+                    json!({"tool_calls": calls, "reply": texts.join("\n")}).to_string()
+                } else {
+                    texts.join("\n\n")
+                }
+            }
+        };
+        out.push(json!({"role": msg.role, "content": content}));
+    }
+    out
+}
+
+/// Parse an emulated answer into its reply text and tool calls. Anything that is not the expected
+/// JSON object (a model ignoring the format, an unconstrained server) is treated as a plain reply.
+pub(crate) fn parse_emulated_tool_reply(text: &str) -> (String, Vec<(String, serde_json::Value)>) {
+    let trimmed = text.trim();
+    let json_part = match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => return (trimmed.to_string(), Vec::new()),
+    };
+    let Ok(serde_json::Value::Object(obj)) = serde_json::from_str::<serde_json::Value>(json_part)
+    else {
+        return (trimmed.to_string(), Vec::new());
+    };
+    let reply = obj
+        .get("reply")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let raw_calls = match obj.get("tool_calls").and_then(|v| v.as_array()) {
+        Some(list) => list.clone(),
+        // Some models answer with a single bare call instead of the list
+        None if obj.contains_key("name") => vec![serde_json::Value::Object(obj.clone())],
+        None => Vec::new(),
+    };
+    let mut calls = Vec::new();
+    for call in raw_calls {
+        let Some(name) = call
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        else {
+            continue;
+        };
+        let input = match call.get("arguments") {
+            Some(serde_json::Value::String(s)) => parse_tool_input(s),
+            Some(v @ serde_json::Value::Object(_)) => v.clone(),
+            _ => json!({}),
+        };
+        calls.push((name.to_string(), input));
+    }
+    if calls.is_empty() && reply.is_empty() && !obj.contains_key("reply") {
+        return (trimmed.to_string(), Vec::new());
+    }
+    (reply, calls)
+}
+
+#[async_trait]
+impl LlmProvider for LlamaCppProvider {
+    async fn send_message(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_message_with_tool_choice(system, messages, tools, ToolChoice::Auto)
+            .await
+    }
+
+    async fn send_message_with_tool_choice(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let tools = tools
+            .filter(|t| !t.is_empty())
+            .filter(|_| tool_choice != ToolChoice::None);
+        let emulate_tools = tools.is_some();
+        let system = match &tools {
+            Some(tools) => format!("{system}{}", emulated_tools_prompt(tools)),
+            None => system.to_string(),
+        };
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "messages": translate_messages_to_llamacpp(&system, &messages, emulate_tools),
+        });
+        if let Some(ref tools) = tools {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "tool_reply",
+                    "schema": emulated_tools_schema(tools, &tool_choice),
+                }
+            });
+        }
+
+        let mut retries = 0u32;
+        let max_retries = 3;
+
+        loop {
+            let mut req = self
+                .http
+                .post(&self.chat_url)
+                .header("Content-Type", "application/json")
+                .json(&body);
+            if !self.api_key.trim().is_empty() {
+                req = req.header("Authorization", format!("Bearer {}", self.api_key));
+            }
+            let response = req.send().await?;
+            let status = response.status();
+
+            if status.is_success() {
+                let text = response.text().await?;
+                let oai: OaiResponse = serde_json::from_str(&text).map_err(|e| {
+                    MicroClawError::LlmApi(format!(
+                        "Failed to parse llama.cpp response: {e}\nBody: {text}"
+                    ))
+                })?;
+                let usage = oai.usage.as_ref().map(|u| Usage {
+                    input_tokens: u.prompt_tokens,
+                    output_tokens: u.completion_tokens,
+                });
+                let Some(choice) = oai.choices.into_iter().next() else {
+                    return Err(MicroClawError::LlmApi(
+                        "llama.cpp returned no choices".into(),
+                    ));
+                };
+                let raw = choice.message.content.unwrap_or_default();
+                let (reply, calls) = if emulate_tools {
+                    parse_emulated_tool_reply(&raw)
+                } else {
+                    (raw, Vec::new())
+                };
+                let stop_reason = if calls.is_empty() {
+                    normalize_stop_reason(choice.finish_reason)
+                } else {
+                    Some("tool_use".into())
+                };
+                let mut content = Vec::new();
+                if !reply.is_empty() || calls.is_empty() {
+                    content.push(ResponseContentBlock::Text { text: reply });
+                }
+                for (name, input) in calls {
+                    content.push(ResponseContentBlock::ToolUse {
+                        id: format!("call_{}", Uuid::new_v4().simple()),
+                        name,
+                        input,
+                        thought_signature: None,
+                    });
+                }
+                return Ok(MessagesResponse {
+                    content,
+                    stop_reason,
+                    usage,
+                });
+            }
+
+            // 503 while the server is still loading the model
+            if status.as_u16() == 503 && retries < max_retries {
+                retries += 1;
+                let delay = std::time::Duration::from_secs(2u64.pow(retries));
+                warn!("llama.cpp server unavailable, retrying in {delay:?} (attempt {retries}/{max_retries})");
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            let text = response.text().await.unwrap_or_default();
+            if let Ok(err) = serde_json::from_str::<OaiErrorResponse>(&text) {
+                let msg = format_oai_error(status, &err.error, &text);
+                return Err(http_error(status, msg));
+            }
+            return Err(http_error(status, format!("HTTP {status}: {text}")));
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Google Gemini native provider
 // ---------------------------------------------------------------------------
@@ -1038,9 +1828,21 @@ impl LlmProvider for GeminiProvider {
         system: &str,
         messages: Vec<Message>,
         tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_message_with_tool_choice(system, messages, tools, ToolChoice::Auto)
+            .await
+    }
+
+    async fn send_message_with_tool_choice(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        tool_choice: ToolChoice,
     ) -> Result<MessagesResponse, MicroClawError> {
         let messages = sanitize_messages(messages);
-        let request_body = build_gemini_request(system, &messages, tools, self.max_tokens);
+        let request_body =
+            build_gemini_request(system, &messages, tools, &tool_choice, self.max_tokens);
 
         let mut retries = 0u32;
         let max_retries = 3;
@@ -1074,12 +1876,12 @@ impl LlmProvider for GeminiProvider {
 
             let body = response.text().await.unwrap_or_default();
             if let Ok(err) = serde_json::from_str::<GeminiError>(&body) {
-                return Err(MicroClawError::LlmApi(format!(
-                    "Gemini API error {}: {}",
-                    err.error.code, err.error.message
-                )));
+                return Err(http_error(
+                    status,
+                    format!("Gemini API error {}: {}", err.error.code, err.error.message),
+                ));
             }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
+            return Err(http_error(status, format!("HTTP {status}: {body}")));
         }
     }
 
@@ -1091,7 +1893,8 @@ impl LlmProvider for GeminiProvider {
         text_tx: Option<&UnboundedSender<String>>,
     ) -> Result<MessagesResponse, MicroClawError> {
         let messages = sanitize_messages(messages);
-        let request_body = build_gemini_request(system, &messages, tools, self.max_tokens);
+        let request_body =
+            build_gemini_request(system, &messages, tools, &ToolChoice::Auto, self.max_tokens);
 
         let response = self
             .http
@@ -1105,12 +1908,12 @@ impl LlmProvider for GeminiProvider {
         if !status.is_success() {
             let text = response.text().await.unwrap_or_default();
             if let Ok(err) = serde_json::from_str::<GeminiError>(&text) {
-                return Err(MicroClawError::LlmApi(format!(
-                    "Gemini API error {}: {}",
-                    err.error.code, err.error.message
-                )));
+                return Err(http_error(
+                    status,
+                    format!("Gemini API error {}: {}", err.error.code, err.error.message),
+                ));
             }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {text}")));
+            return Err(http_error(status, format!("HTTP {status}: {text}")));
         }
 
         let mut byte_stream = response.bytes_stream();
@@ -1258,6 +2061,7 @@ fn build_gemini_request(
     system: &str,
     messages: &[Message],
     tools: Option<Vec<ToolDefinition>>,
+    tool_choice: &ToolChoice,
     max_tokens: u32,
 ) -> serde_json::Value {
     let contents = translate_messages_to_gemini(messages);
@@ -1280,12 +2084,15 @@ fn build_gemini_request(
                     let parameters = sanitize_oai_parameters(&t.input_schema);
                     json!({
                         "name": t.name,
-                        "description": t.description,
+                        "description": t.description_with_examples(),
                         "parameters": parameters,
                     })
                 })
                 .collect();
             request["tools"] = json!([{ "functionDeclarations": func_decls }]);
+            if let Some(config) = tool_choice.gemini() {
+                request["toolConfig"] = config;
+            }
         }
     }
 
@@ -1379,6 +2186,11 @@ fn translate_messages_to_gemini(messages: &[Message]) -> Vec<serde_json::Value>
                                 }
                             }));
                         }
+                        ContentBlock::ProviderFile { filename, .. } => {
+                            parts.push(json!({
+                                "text": ContentBlock::provider_file_placeholder(filename)
+                            }));
+                        }
                     }
                 }
 
@@ -1637,11 +2449,14 @@ fn translate_messages_to_oai(system: &str, messages: &[Message]) -> Vec<serde_js
                             }
                         }
                     } else {
-                        // Images + text → multipart content array
-                        let has_images = blocks
-                            .iter()
-                            .any(|b| matches!(b, ContentBlock::Image { .. }));
-                        if has_images {
+                        // Images, files + text → multipart content array
+                        let has_parts = blocks.iter().any(|b| {
+                            matches!(
+                                b,
+                                ContentBlock::Image { .. } | ContentBlock::ProviderFile { .. }
+                            )
+                        });
+                        if has_parts {
                             let parts: Vec<serde_json::Value> = blocks
                                 .iter()
                                 .filter_map(|b| match b {
@@ -1660,6 +2475,16 @@ fn translate_messages_to_oai(system: &str, messages: &[Message]) -> Vec<serde_js
                                             "image_url": {"url": url}
                                         }))
                                     }
+                                    ContentBlock::ProviderFile {
+                                        provider, file_id, ..
+                                    } if provider == "openai" => Some(json!({
+                                        "type": "file",
+                                        "file": {"file_id": file_id}
+                                    })),
+                                    ContentBlock::ProviderFile { filename, .. } => Some(json!({
+                                        "type": "text",
+                                        "text": ContentBlock::provider_file_placeholder(filename)
+                                    })),
                                     _ => None,
                                 })
                                 .collect();
@@ -1783,7 +2608,7 @@ fn translate_tools_to_oai(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
                 "type": "function",
                 "function": {
                     "name": t.name,
-                    "description": t.description,
+                    "description": t.description_with_examples(),
                     "parameters": parameters,
                 }
             })
@@ -1858,6 +2683,7 @@ fn translate_oai_response(oai: OaiResponse) -> MessagesResponse {
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::sync::Arc;
 
     // -----------------------------------------------------------------------
     // translate_messages_to_oai
@@ -2036,6 +2862,36 @@ mod tests {
         assert_eq!(content[1]["text"], "describe");
     }
 
+    fn provider_file(provider: &str, container: bool) -> ContentBlock {
+        ContentBlock::ProviderFile {
+            provider: provider.into(),
+            file_id: "file_1".into(),
+            filename: "lease.pdf".into(),
+            media_type: "application/pdf".into(),
+            container,
+        }
+    }
+
+    #[test]
+    fn test_translate_messages_provider_file() {
+        let msgs = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Blocks(vec![
+                provider_file("openai", false),
+                provider_file("anthropic", false),
+                ContentBlock::Text {
+                    text: "summarize".into(),
+                },
+            ]),
+        }];
+        let out = translate_messages_to_oai("", &msgs);
+        let content = out[0]["content"].as_array().unwrap();
+        assert_eq!(content[0], json!({"type": "file", "file": {"file_id": "file_1"}}));
+        assert_eq!(content[1]["type"], "text");
+        assert!(content[1]["text"].as_str().unwrap().contains("lease.pdf"));
+        assert_eq!(content[2]["text"], "summarize");
+    }
+
     // -----------------------------------------------------------------------
     // translate_tools_to_oai
     // -----------------------------------------------------------------------
@@ -2046,6 +2902,7 @@ mod tests {
             name: "bash".into(),
             description: "Run bash".into(),
             input_schema: json!({"type": "object", "properties": {"cmd": {"type": "string"}}}),
+            examples: Vec::new(),
         }];
         let out = translate_tools_to_oai(&tools);
         assert_eq!(out.len(), 1);
@@ -2293,6 +3150,7 @@ mod tests {
             api_key: "key".into(),
             model: "claude-sonnet-4-5-20250929".into(),
             llm_base_url: None,
+            llm_fallbacks: Vec::new(),
             max_tokens: 8192,
             max_tool_iterations: 100,
             max_history_messages: 50,
@@ -2304,20 +3162,42 @@ mod tests {
             control_chat_ids: vec![],
             max_session_messages: 40,
             compact_keep_recent: 20,
+            memory_flush_idle_minutes: 30,
+            memory_flush_model: String::new(),
+            onboarding_enabled: true,
+            task_max_retries: 2,
+            task_retry_backoff_secs: 60,
+            scheduler_max_concurrent_tasks: 4,
+            deleted_session_retention_days: 30,
             whatsapp_access_token: None,
             whatsapp_phone_number_id: None,
             whatsapp_verify_token: None,
             whatsapp_webhook_port: 8080,
             discord_bot_token: None,
             discord_allowed_channels: vec![],
-            show_thinking: false,
+            slack_bot_token: None,
+            slack_app_token: None,
+            slack_allowed_channels: vec![],
+            signal: None,
+            teams: None,
+            wyoming: None,
+            home_assistant: None,
+            python: None,
+            browser_downloads: None,
+            web_embed: None,
+            transcription: None,
+            calendar: None,
+            show_thinking: crate::config::ThinkingLevel::Off,
             web_enabled: false,
             web_host: "127.0.0.1".into(),
             web_port: 3900,
             web_auth_token: None,
+            web_trusted_proxies: vec![],
             web_max_inflight_per_session: 2,
             web_max_requests_per_window: 8,
             web_rate_window_seconds: 10,
+            web_max_reads_per_window: 120,
+            web_max_writes_per_window: 30,
             web_run_history_limit: 512,
             web_session_idle_ttl_seconds: 300,
             browser_managed: false,
@@ -2329,10 +3209,39 @@ mod tests {
             cursor_agent_cli_path: "cursor-agent".into(),
             cursor_agent_model: String::new(),
             cursor_agent_timeout_secs: 600,
+            cursor_agent_review: true,
+            cursor_agent_digest_days: 7,
             social: None,
             vault: None,
             orchestrator_enabled: true,
             orchestrator_model: String::new(),
+            translation: None,
+            public_ask: None,
+            sticker_pack_path: None,
+            output_filter: None,
+            inbound_moderation: None,
+            egress: Default::default(),
+            browser_profiles: Vec::new(),
+            model_prices: Default::default(),
+            experiments: Vec::new(),
+            feedback_report_days: 0,
+            instance_lock: false,
+            feeds: Vec::new(),
+            middleware_hooks: Vec::new(),
+            feature_flags: Default::default(),
+            announce_release_notes: true,
+            anthropic_prompt_caching: true,
+            notifications: None,
+            heartbeat: None,
+            workspace_encryption: None,
+            incidents: None,
+            push: None,
+            provider_files: None,
+            roundtable: None,
+            benchmark: None,
+            chat_archive: None,
+            provider_search: None,
+            telegram_business: None,
         };
         // Should not panic
         let _provider = create_provider(&config);
@@ -2347,6 +3256,7 @@ mod tests {
             api_key: "key".into(),
             model: "gpt-5.2".into(),
             llm_base_url: None,
+            llm_fallbacks: Vec::new(),
             max_tokens: 8192,
             max_tool_iterations: 100,
             max_history_messages: 50,
@@ -2358,20 +3268,42 @@ mod tests {
             control_chat_ids: vec![],
             max_session_messages: 40,
             compact_keep_recent: 20,
+            memory_flush_idle_minutes: 30,
+            memory_flush_model: String::new(),
+            onboarding_enabled: true,
+            task_max_retries: 2,
+            task_retry_backoff_secs: 60,
+            scheduler_max_concurrent_tasks: 4,
+            deleted_session_retention_days: 30,
             whatsapp_access_token: None,
             whatsapp_phone_number_id: None,
             whatsapp_verify_token: None,
             whatsapp_webhook_port: 8080,
             discord_bot_token: None,
             discord_allowed_channels: vec![],
-            show_thinking: false,
+            slack_bot_token: None,
+            slack_app_token: None,
+            slack_allowed_channels: vec![],
+            signal: None,
+            teams: None,
+            wyoming: None,
+            home_assistant: None,
+            python: None,
+            browser_downloads: None,
+            web_embed: None,
+            transcription: None,
+            calendar: None,
+            show_thinking: crate::config::ThinkingLevel::Off,
             web_enabled: false,
             web_host: "127.0.0.1".into(),
             web_port: 3900,
             web_auth_token: None,
+            web_trusted_proxies: vec![],
             web_max_inflight_per_session: 2,
             web_max_requests_per_window: 8,
             web_rate_window_seconds: 10,
+            web_max_reads_per_window: 120,
+            web_max_writes_per_window: 30,
             web_run_history_limit: 512,
             web_session_idle_ttl_seconds: 300,
             browser_managed: false,
@@ -2383,10 +3315,39 @@ mod tests {
             cursor_agent_cli_path: "cursor-agent".into(),
             cursor_agent_model: String::new(),
             cursor_agent_timeout_secs: 600,
+            cursor_agent_review: true,
+            cursor_agent_digest_days: 7,
             social: None,
             vault: None,
             orchestrator_enabled: true,
             orchestrator_model: String::new(),
+            translation: None,
+            public_ask: None,
+            sticker_pack_path: None,
+            output_filter: None,
+            inbound_moderation: None,
+            egress: Default::default(),
+            browser_profiles: Vec::new(),
+            model_prices: Default::default(),
+            experiments: Vec::new(),
+            feedback_report_days: 0,
+            instance_lock: false,
+            feeds: Vec::new(),
+            middleware_hooks: Vec::new(),
+            feature_flags: Default::default(),
+            announce_release_notes: true,
+            anthropic_prompt_caching: true,
+            notifications: None,
+            heartbeat: None,
+            workspace_encryption: None,
+            incidents: None,
+            push: None,
+            provider_files: None,
+            roundtable: None,
+            benchmark: None,
+            chat_archive: None,
+            provider_search: None,
+            telegram_business: None,
         };
         let _provider = create_provider(&config);
     }
@@ -2507,6 +3468,193 @@ mod tests {
         assert_eq!(tail, vec!["hello".to_string()]);
     }
 
+    #[test]
+    fn test_anthropic_request_body_cache_breakpoints() {
+        let text = |role: &str, t: &str| Message {
+            role: role.into(),
+            content: MessageContent::Text(t.into()),
+        };
+        let tool = |name: &str| ToolDefinition {
+            name: name.into(),
+            description: "d".into(),
+            input_schema: json!({"type": "object"}),
+            examples: vec![],
+        };
+        let request = MessagesRequest {
+            model: "claude".into(),
+            max_tokens: 100,
+            system: "sys".into(),
+            messages: vec![
+                text("user", "[Conversation Summary]\nearlier"),
+                text("assistant", "Understood"),
+                text("user", "hi"),
+                Message {
+                    role: "assistant".into(),
+                    content: MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                        id: "t1".into(),
+                        name: "bash".into(),
+                        input: json!({}),
+                        thought_signature: None,
+                    }]),
+                },
+                Message {
+                    role: "user".into(),
+                    content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                        tool_use_id: "t1".into(),
+                        content: "ok".into(),
+                        is_error: None,
+                    }]),
+                },
+            ],
+            tools: Some(vec![tool("a"), tool("b")]),
+            tool_choice: None,
+            stream: None,
+        };
+
+        let plain = anthropic_request_body(&request, false, 5);
+        assert_eq!(plain["system"], "sys");
+        assert!(!plain.to_string().contains("cache_control"));
+
+        let body = anthropic_request_body(&request, true, 5);
+        let ephemeral = json!({"type": "ephemeral"});
+        assert_eq!(body["system"][0]["text"], "sys");
+        assert_eq!(body["system"][0]["cache_control"], ephemeral);
+        assert!(body["tools"][0].get("cache_control").is_none());
+        assert_eq!(body["tools"][1]["cache_control"], ephemeral);
+        assert_eq!(body["messages"][1]["content"][0]["text"], "Understood");
+        assert_eq!(
+            body["messages"][1]["content"][0]["cache_control"],
+            ephemeral
+        );
+        assert_eq!(body["messages"][2]["content"], "hi");
+        assert_eq!(body["messages"][4]["content"][0]["type"], "tool_result");
+        assert_eq!(
+            body["messages"][4]["content"][0]["cache_control"],
+            ephemeral
+        );
+        assert_eq!(body.to_string().matches("cache_control").count(), 4);
+    }
+
+    #[test]
+    fn test_anthropic_request_body_provider_files() {
+        let request = |blocks: Vec<ContentBlock>| MessagesRequest {
+            model: "m".into(),
+            max_tokens: 10,
+            system: String::new(),
+            messages: vec![Message {
+                role: "user".into(),
+                content: MessageContent::Blocks(blocks),
+            }],
+            tools: None,
+            tool_choice: None,
+            stream: None,
+        };
+        let text = || ContentBlock::Text {
+            text: "see file".into(),
+        };
+
+        let body = anthropic_request_body(&request(vec![text()]), false, 5);
+        assert_eq!(anthropic_beta(&body), None);
+
+        let body = anthropic_request_body(
+            &request(vec![provider_file("anthropic", false), text()]),
+            true,
+            5,
+        );
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[0]["type"], "document");
+        assert_eq!(content[0]["source"], json!({"type": "file", "file_id": "file_1"}));
+        assert!(content[0].get("cache_control").is_none());
+        assert!(body.get("tools").is_none());
+        assert_eq!(anthropic_beta(&body).as_deref(), Some(ANTHROPIC_FILES_BETA));
+
+        let body = anthropic_request_body(
+            &request(vec![
+                provider_file("anthropic", true),
+                provider_file("openai", false),
+                text(),
+            ]),
+            false,
+            5,
+        );
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[0], json!({"type": "container_upload", "file_id": "file_1"}));
+        assert_eq!(content[1]["type"], "text");
+        assert_eq!(body["tools"][0]["name"], "code_execution");
+        assert!(anthropic_beta(&body)
+            .unwrap()
+            .contains(ANTHROPIC_CODE_EXECUTION_BETA));
+    }
+
+    #[test]
+    fn test_parse_anthropic_response_skips_server_tool_blocks() {
+        let body = json!({
+            "content": [
+                {"type": "server_tool_use", "id": "srv_1", "name": "code_execution", "input": {}},
+                {"type": "bash_code_execution_tool_result", "tool_use_id": "srv_1", "content": {}},
+                {"type": "text", "text": "The total is 42."}
+            ],
+            "stop_reason": "end_turn"
+        });
+        let parsed = parse_anthropic_response(&body.to_string()).unwrap();
+        assert_eq!(parsed.content.len(), 1);
+        assert!(matches!(&parsed.content[0], ResponseContentBlock::Text { text } if text == "The total is 42."));
+    }
+
+    #[test]
+    fn test_anthropic_provider_web_search_and_sources() {
+        let request = MessagesRequest {
+            model: "claude".into(),
+            max_tokens: 100,
+            system: String::new(),
+            messages: vec![Message {
+                role: "user".into(),
+                content: MessageContent::Text("news?".into()),
+            }],
+            tools: Some(vec![ToolDefinition {
+                name: PROVIDER_WEB_SEARCH_TOOL.into(),
+                description: "search".into(),
+                input_schema: json!({"type": "object"}),
+                examples: Vec::new(),
+            }]),
+            tool_choice: None,
+            stream: None,
+        };
+        let body = anthropic_request_body(&request, false, 3);
+        assert_eq!(
+            body["tools"][0],
+            json!({"type": "web_search_20250305", "name": "web_search", "max_uses": 3})
+        );
+
+        let response = json!({
+            "content": [
+                {"type": "server_tool_use", "id": "srv_1", "name": "web_search", "input": {"query": "x"}},
+                {"type": "web_search_tool_result", "tool_use_id": "srv_1", "content": []},
+                {"type": "text", "text": "It rained", "citations": [
+                    {"type": "web_search_result_location", "url": "https://a.example", "title": "A [live]", "cited_text": "rain"}
+                ]},
+                {"type": "text", "text": " twice.", "citations": [
+                    {"type": "web_search_result_location", "url": "https://b.example", "title": "", "cited_text": "twice"},
+                    {"type": "web_search_result_location", "url": "https://a.example", "title": "A", "cited_text": "rain"}
+                ]}
+            ],
+            "stop_reason": "end_turn"
+        });
+        let parsed = parse_anthropic_response(&response.to_string()).unwrap();
+        let text: String = parsed
+            .content
+            .iter()
+            .filter_map(|b| match b {
+                ResponseContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            text,
+            "It rained twice.\n\nSources:\n1. [A live](https://a.example)\n2. [https://b.example](https://b.example)"
+        );
+    }
+
     // -----------------------------------------------------------------------
     // translate_messages_to_gemini
     // -----------------------------------------------------------------------
@@ -2705,6 +3853,181 @@ mod tests {
         }
     }
 
+    struct ScriptedProvider {
+        status: Option<u16>,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            match self.status {
+                Some(status) => Err(MicroClawError::LlmHttp {
+                    status,
+                    message: format!("HTTP {status}"),
+                }),
+                None => Ok(MessagesResponse {
+                    content: vec![ResponseContentBlock::Text { text: "ok".into() }],
+                    stop_reason: Some("end_turn".into()),
+                    usage: None,
+                }),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_provider_falls_back_and_cools_down() {
+        let primary_calls = Arc::new(AtomicU32::new(0));
+        let fallback_calls = Arc::new(AtomicU32::new(0));
+        let chain = FailoverProvider::new(vec![
+            (
+                "anthropic/a".into(),
+                Box::new(ScriptedProvider {
+                    status: Some(529),
+                    calls: primary_calls.clone(),
+                }),
+            ),
+            (
+                "openai/b".into(),
+                Box::new(ScriptedProvider {
+                    status: None,
+                    calls: fallback_calls.clone(),
+                }),
+            ),
+        ]);
+        let reported = Arc::new(std::sync::Mutex::new(None));
+        let report = {
+            let reported = reported.clone();
+            move |label| *reported.lock().unwrap() = Some(label)
+        };
+        let resp = with_failover_report(report, chain.send_message("", vec![], None)).await;
+        assert!(resp.is_ok());
+        assert_eq!(reported.lock().unwrap().as_deref(), Some("openai/b"));
+
+        // The primary is cooling down: the next call goes straight to the fallback
+        chain.send_message("", vec![], None).await.unwrap();
+        assert_eq!(primary_calls.load(Ordering::Relaxed), 1);
+        assert_eq!(fallback_calls.load(Ordering::Relaxed), 2);
+        assert_eq!(chain.links[0].failures.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failover_provider_keeps_request_errors() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let chain = FailoverProvider::new(vec![
+            (
+                "a".into(),
+                Box::new(ScriptedProvider {
+                    status: Some(400),
+                    calls: calls.clone(),
+                }),
+            ),
+            (
+                "b".into(),
+                Box::new(ScriptedProvider {
+                    status: None,
+                    calls: calls.clone(),
+                }),
+            ),
+        ]);
+        let err = chain.send_message("", vec![], None).await.unwrap_err();
+        assert!(matches!(err, MicroClawError::LlmHttp { status: 400, .. }));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert!(!is_failover_error(&MicroClawError::LlmApi("x".into())));
+        assert!(is_failover_error(&MicroClawError::LlmHttp {
+            status: 429,
+            message: String::new()
+        }));
+    }
+
+    #[test]
+    fn test_build_gemini_request_tool_choice() {
+        let tools = vec![ToolDefinition {
+            name: "submit_plan".into(),
+            description: "Submit".into(),
+            input_schema: json!({"type": "object", "properties": {}}),
+            examples: Vec::new(),
+        }];
+        let choice = ToolChoice::Tool("submit_plan".into());
+        let req = build_gemini_request("", &[], Some(tools.clone()), &choice, 100);
+        assert_eq!(
+            req["toolConfig"],
+            json!({"functionCallingConfig": {"mode": "ANY", "allowedFunctionNames": ["submit_plan"]}})
+        );
+        let req = build_gemini_request("", &[], Some(tools), &ToolChoice::Auto, 100);
+        assert!(req.get("toolConfig").is_none());
+        // Without tools there is nothing to constrain
+        let req = build_gemini_request("", &[], None, &ToolChoice::None, 100);
+        assert!(req.get("toolConfig").is_none());
+    }
+
+    #[test]
+    fn test_llamacpp_emulated_tool_calls() {
+        let tools = vec![ToolDefinition {
+            name: "read_file".into(),
+            description: "Read a file".into(),
+            input_schema: json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+            examples: Vec::new(),
+        }];
+        let schema = emulated_tools_schema(&tools, &ToolChoice::Any);
+        assert_eq!(
+            schema["properties"]["tool_calls"]["items"]["properties"]["name"]["enum"],
+            json!(["read_file"])
+        );
+        assert_eq!(schema["properties"]["tool_calls"]["minItems"], 1);
+        assert!(emulated_tools_prompt(&tools).contains("## read_file\nRead a file"));
+
+        let (reply, calls) = parse_emulated_tool_reply(
+            r#"{"tool_calls": [{"name": "read_file", "arguments": {"path": "a.txt"}}], "reply": ""}"#,
+        );
+        assert_eq!(reply, "");
+        assert_eq!(
+            calls,
+            vec![("read_file".to_string(), json!({"path": "a.txt"}))]
+        );
+        // Fenced output, string arguments and a bare call are accepted too
+        let (_, calls) = parse_emulated_tool_reply(
+            "```json\n{\"name\": \"read_file\", \"arguments\": \"{\\\"path\\\": \\\"b\\\"}\"}\n```",
+        );
+        assert_eq!(calls, vec![("read_file".to_string(), json!({"path": "b"}))]);
+        let (reply, calls) = parse_emulated_tool_reply(r#"{"tool_calls": [], "reply": "Hi!"}"#);
+        assert_eq!((reply.as_str(), calls.len()), ("Hi!", 0));
+        let (reply, calls) = parse_emulated_tool_reply("Just text {not json}");
+        assert_eq!((reply.as_str(), calls.len()), ("Just text {not json}", 0));
+
+        let messages = vec![
+            Message {
+                role: "assistant".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "read_file".into(),
+                    input: json!({"path": "a.txt"}),
+                    thought_signature: None,
+                }]),
+            },
+            Message {
+                role: "user".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                    tool_use_id: "t1".into(),
+                    content: "hello".into(),
+                    is_error: None,
+                }]),
+            },
+        ];
+        let out = translate_messages_to_llamacpp("sys", &messages, true);
+        assert_eq!(out[0]["content"], "sys");
+        let call: serde_json::Value =
+            serde_json::from_str(out[1]["content"].as_str().unwrap()).unwrap();
+        assert_eq!(call["tool_calls"][0]["name"], "read_file");
+        assert_eq!(out[2]["content"], "Tool result from read_file:\nhello");
+    }
+
     #[test]
     fn test_create_provider_google() {
         let config = Config {
@@ -2714,6 +4037,7 @@ mod tests {
             api_key: "key".into(),
             model: "gemini-2.5-flash".into(),
             llm_base_url: None,
+            llm_fallbacks: Vec::new(),
             max_tokens: 8192,
             max_tool_iterations: 100,
             max_history_messages: 50,
@@ -2725,20 +4049,42 @@ mod tests {
             control_chat_ids: vec![],
             max_session_messages: 40,
             compact_keep_recent: 20,
+            memory_flush_idle_minutes: 30,
+            memory_flush_model: String::new(),
+            onboarding_enabled: true,
+            task_max_retries: 2,
+            task_retry_backoff_secs: 60,
+            scheduler_max_concurrent_tasks: 4,
+            deleted_session_retention_days: 30,
             whatsapp_access_token: None,
             whatsapp_phone_number_id: None,
             whatsapp_verify_token: None,
             whatsapp_webhook_port: 8080,
             discord_bot_token: None,
             discord_allowed_channels: vec![],
-            show_thinking: false,
+            slack_bot_token: None,
+            slack_app_token: None,
+            slack_allowed_channels: vec![],
+            signal: None,
+            teams: None,
+            wyoming: None,
+            home_assistant: None,
+            python: None,
+            browser_downloads: None,
+            web_embed: None,
+            transcription: None,
+            calendar: None,
+            show_thinking: crate::config::ThinkingLevel::Off,
             web_enabled: false,
             web_host: "127.0.0.1".into(),
             web_port: 3900,
             web_auth_token: None,
+            web_trusted_proxies: vec![],
             web_max_inflight_per_session: 2,
             web_max_requests_per_window: 8,
             web_rate_window_seconds: 10,
+            web_max_reads_per_window: 120,
+            web_max_writes_per_window: 30,
             web_run_history_limit: 512,
             web_session_idle_ttl_seconds: 300,
             browser_managed: false,
@@ -2750,10 +4096,39 @@ mod tests {
             cursor_agent_cli_path: "cursor-agent".into(),
             cursor_agent_model: String::new(),
             cursor_agent_timeout_secs: 600,
+            cursor_agent_review: true,
+            cursor_agent_digest_days: 7,
             social: None,
             vault: None,
             orchestrator_enabled: true,
             orchestrator_model: String::new(),
+            translation: None,
+            public_ask: None,
+            sticker_pack_path: None,
+            output_filter: None,
+            inbound_moderation: None,
+            egress: Default::default(),
+            browser_profiles: Vec::new(),
+            model_prices: Default::default(),
+            experiments: Vec::new(),
+            feedback_report_days: 0,
+            instance_lock: false,
+            feeds: Vec::new(),
+            middleware_hooks: Vec::new(),
+            feature_flags: Default::default(),
+            announce_release_notes: true,
+            anthropic_prompt_caching: true,
+            notifications: None,
+            heartbeat: None,
+            workspace_encryption: None,
+            incidents: None,
+            push: None,
+            provider_files: None,
+            roundtable: None,
+            benchmark: None,
+            chat_archive: None,
+            provider_search: None,
+            telegram_business: None,
         };
         // Should not panic
         let _provider = create_provider(&config);
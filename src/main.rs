@@ -2,8 +2,8 @@ use microclaw::claude::{Message, MessageContent};
 use microclaw::config::Config;
 use microclaw::error::MicroClawError;
 use microclaw::{
-    builtin_skills, config_wizard, db, doctor, gateway, logging, mcp, memory, setup, skills,
-    telegram,
+    builtin_skills, config_wizard, db, doctor, eval, gateway, import, logging, mcp, memory, remote,
+    setup, skills, state_archive, telegram,
 };
 use std::path::Path;
 use tracing::info;
@@ -23,6 +23,11 @@ COMMANDS:
     config      Run interactive Q&A config flow (recommended)
     doctor      Run preflight diagnostics (cross-platform)
     test-llm [--with-tools]   Test LLM connection (use --with-tools to send tools like Telegram)
+    eval <suite.yaml> [--report <path>]   Run an eval suite (mocked tools) and write a report
+    import <export> --chat <id>   Import ChatGPT / Claude / Telegram export history into a chat
+    export-state [<out.tar.gz>]   Write DB, memory, skills, config template and sealed secrets to one archive
+    import-state <archive> [--workspace <dir>]   Restore an export-state archive (e.g. on new hardware)
+    remote --url <url> [--token <t>] [serve|send|sessions|history|health]   Use a primary instance's API from another machine
     setup       Run interactive setup wizard
     version     Show version information
     help        Show this help message
@@ -87,6 +92,23 @@ CONFIG FILE (.env):
       discord_bot_token           Discord bot token from Discord Developer Portal
       discord_allowed_channels    List of channel IDs to respond in (empty = all)
 
+    Slack (optional, Socket Mode):
+      slack_bot_token             Bot token (xoxb-...) used to post replies
+      slack_app_token             App-level token (xapp-...) with connections:write
+      slack_allowed_channels      List of channel IDs to respond in (empty = all)
+
+    Signal (optional, via signal-cli):
+      signal.signal_account       Registered Signal number the bot runs as
+      signal.signal_cli_path      signal-cli executable (default: signal-cli)
+      signal.allowed_groups       List of group IDs to respond in (empty = all)
+
+    Microsoft Teams (optional, via Bot Framework):
+      teams.app_id                Microsoft App ID of the Azure Bot registration
+      teams.app_password          Client secret of the bot registration
+      teams.tenant_id             Tenant of a single-tenant registration (optional)
+      teams.webhook_port          Port of the /api/messages endpoint (default: 3978)
+      teams.allowed_channels      Channel/group chat IDs to respond in (empty = all)
+
 MCP (optional):
     Place a mcp.json file in workspace_dir to connect MCP servers.
     See https://modelcontextprotocol.io for details.
@@ -101,6 +123,12 @@ EXAMPLES:
     microclaw doctor --json       Output diagnostics as JSON
     microclaw test-llm            Test LLM API connection (no tools)
     microclaw test-llm --with-tools   Test LLM with full tool list (like Telegram)
+    microclaw eval evals/smoke.yaml   Run an eval suite before deploying prompt/skill changes
+    microclaw import chatgpt-export.zip --chat 123456789   Import ChatGPT history into a chat
+    microclaw export-state state.tar.gz   Archive the assistant (secrets sealed with MICROCLAW_STATE_PASSPHRASE)
+    microclaw import-state state.tar.gz   Restore it into ./workspace and ./.env on the new machine
+    microclaw remote --url http://homeserver:10961 --token <t>   Local web UI backed by the home server
+    microclaw remote --url http://homeserver:10961 --token <t> send "hi"   Chat from the terminal
     microclaw setup               Run full-screen setup wizard
     microclaw version             Show version
     microclaw help                Show this message
@@ -133,6 +161,7 @@ async fn run_test_llm(with_tools: bool) -> anyhow::Result<()> {
         content: MessageContent::Text("Reply with exactly: OK".into()),
     }];
     let tools_arg = if with_tools {
+        let mounted = microclaw::workspace_crypto::mount(&config)?;
         let runtime_data_dir = config.runtime_data_dir();
         let db = match db::Database::new(&runtime_data_dir) {
             Ok(d) => std::sync::Arc::new(d),
@@ -149,6 +178,10 @@ async fn run_test_llm(with_tools: bool) -> anyhow::Result<()> {
         let bot = teloxide::Bot::new(token);
         let tools = microclaw::tools::ToolRegistry::new(&config, bot, db.clone());
         let defs = tools.definitions();
+        drop(tools);
+        if mounted {
+            microclaw::workspace_crypto::unmount(&config, Some(&db))?;
+        }
         println!("Testing with {} tools (same as Telegram).", defs.len());
         Some(defs)
     } else {
@@ -410,6 +443,26 @@ async fn main() -> anyhow::Result<()> {
             doctor::run_cli(&args[2..])?;
             return Ok(());
         }
+        Some("eval") => {
+            eval::run_cli(&args[2..]).await?;
+            return Ok(());
+        }
+        Some("import") => {
+            import::run_cli(&args[2..])?;
+            return Ok(());
+        }
+        Some("export-state") => {
+            state_archive::run_export_cli(&args[2..])?;
+            return Ok(());
+        }
+        Some("import-state") => {
+            state_archive::run_import_cli(&args[2..])?;
+            return Ok(());
+        }
+        Some("remote") => {
+            remote::run_cli(&args[2..]).await?;
+            return Ok(());
+        }
         Some("test-llm") => {
             let with_tools = args.get(2).map(|s| s.as_str()) == Some("--with-tools");
             run_test_llm(with_tools).await?;
@@ -453,6 +506,9 @@ async fn main() -> anyhow::Result<()> {
     };
     info!("Starting MicroClaw bot...");
 
+    // With workspace encryption, unpack the runtime before anything reads it
+    microclaw::workspace_crypto::mount_owned(&config)?;
+
     let data_root_dir = config.data_root_dir();
     let runtime_data_dir = config.runtime_data_dir();
     let workspace_root = config.workspace_root_absolute();
@@ -0,0 +1,312 @@
+//! `/memory` and `/forget` chat commands: let users see and correct what the active persona
+//! remembers in its tiered MEMORY.md without filesystem access.
+//!
+//! `/memory` lists every entry (non-blank line) of each tier with a number; `/memory edit <n>
+//! <text>` and `/memory delete <n>` change one entry, and `/forget <text>` removes every entry
+//! that mentions the text.
+
+use std::sync::Arc;
+
+use crate::db::{call_blocking, Database};
+use crate::memory::MemoryManager;
+use crate::tools::tiered_memory::{parse_tier_content, replace_tier_content, TIER_HEADERS};
+
+const MEMORY_USAGE: &str =
+    "Usage: /memory, /memory edit <number> <new text>, /memory delete <number>";
+
+/// One numbered memory entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryEntry {
+    pub tier: u8,
+    pub text: String,
+}
+
+/// Non-blank lines of each tier, in tier order. Numbering for the commands is 1-based over this list.
+pub fn memory_entries(full: &str) -> Vec<MemoryEntry> {
+    TIER_HEADERS
+        .iter()
+        .flat_map(|(tier, _)| {
+            parse_tier_content(full, *tier)
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| MemoryEntry {
+                    tier: *tier,
+                    text: l.trim_end().to_string(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Numbered listing of all tiers.
+pub fn render_memory(full: &str) -> String {
+    let entries = memory_entries(full);
+    if entries.is_empty() {
+        return "Nothing is stored in memory for this chat yet.".into();
+    }
+    let mut out = String::new();
+    for (tier, header) in TIER_HEADERS.iter() {
+        out.push_str(header.trim_start_matches("## "));
+        out.push('\n');
+        let mut any = false;
+        for (i, e) in entries.iter().enumerate() {
+            if e.tier == *tier {
+                out.push_str(&format!("{}. {}\n", i + 1, e.text.trim()));
+                any = true;
+            }
+        }
+        if !any {
+            out.push_str("(empty)\n");
+        }
+        out.push('\n');
+    }
+    out.push_str("Edit with /memory edit <number> <new text>, remove with /memory delete <number> or /forget <text>.");
+    out
+}
+
+/// Rewrite `full` with each entry passed through `f` (None drops the entry). Tiers without
+/// changes are left untouched.
+fn rewrite_entries<F>(full: &str, mut f: F) -> String
+where
+    F: FnMut(usize, &MemoryEntry) -> Option<String>,
+{
+    let entries = memory_entries(full);
+    let mut out = full.to_string();
+    for (tier, _) in TIER_HEADERS.iter() {
+        let mut changed = false;
+        let mut kept = Vec::new();
+        for (i, e) in entries.iter().enumerate().filter(|(_, e)| e.tier == *tier) {
+            match f(i, e) {
+                Some(text) => {
+                    changed |= text != e.text;
+                    kept.push(text);
+                }
+                None => changed = true,
+            }
+        }
+        if changed {
+            out = replace_tier_content(&out, *tier, &kept.join("\n"));
+        }
+    }
+    out
+}
+
+/// Keep the original list marker (e.g. "- ") when the replacement text has none.
+fn with_marker(original: &str, text: &str) -> String {
+    let trimmed = original.trim_start();
+    let indent = &original[..original.len() - trimmed.len()];
+    let text = text.trim();
+    for marker in ["- ", "* ", "+ "] {
+        if trimmed.starts_with(marker) && !text.starts_with(marker) {
+            return format!("{indent}{marker}{text}");
+        }
+    }
+    format!("{indent}{text}")
+}
+
+/// Replace (Some) or delete (None) entry `n` (1-based). Returns the new file and the old entry.
+pub fn edit_entry(full: &str, n: usize, new_text: Option<&str>) -> Option<(String, String)> {
+    let entries = memory_entries(full);
+    let old = entries.get(n.checked_sub(1)?)?.text.clone();
+    let updated = rewrite_entries(full, |i, e| {
+        if i + 1 != n {
+            return Some(e.text.clone());
+        }
+        new_text.map(|t| with_marker(&e.text, t))
+    });
+    Some((updated, old))
+}
+
+/// Remove every entry containing `needle` (case-insensitive). Returns the new file and the
+/// removed entries.
+pub fn forget_entries(full: &str, needle: &str) -> (String, Vec<String>) {
+    let needle = needle.trim().to_lowercase();
+    if needle.is_empty() {
+        return (full.to_string(), Vec::new());
+    }
+    let mut removed = Vec::new();
+    let updated = rewrite_entries(full, |_, e| {
+        if e.text.to_lowercase().contains(&needle) {
+            removed.push(e.text.trim().to_string());
+            None
+        } else {
+            Some(e.text.clone())
+        }
+    });
+    (updated, removed)
+}
+
+/// Arguments after the command word (handles `/memory@Bot`).
+fn command_args(text: &str) -> &str {
+    let t = text.trim();
+    match t.find(char::is_whitespace) {
+        Some(i) => t[i..].trim(),
+        None => "",
+    }
+}
+
+async fn memory_path(
+    db: Arc<Database>,
+    memory: &MemoryManager,
+    chat_id: i64,
+) -> Result<std::path::PathBuf, String> {
+    let persona_id = call_blocking(db, move |d| d.get_current_persona_id(chat_id))
+        .await
+        .map_err(|e| format!("Failed to resolve persona: {e}"))?;
+    Ok(memory.persona_memory_path(chat_id, persona_id))
+}
+
+/// Handle `/memory [edit <n> <text> | delete <n>]` for the chat's active persona.
+pub async fn handle_memory_command(
+    db: Arc<Database>,
+    memory: &MemoryManager,
+    chat_id: i64,
+    text: &str,
+) -> String {
+    let path = match memory_path(db, memory, chat_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let full = std::fs::read_to_string(&path).unwrap_or_default();
+    let args = command_args(text);
+    let mut parts = args.splitn(3, char::is_whitespace);
+    let action = parts.next().unwrap_or("").to_lowercase();
+    if action.is_empty() || action == "list" || action == "show" {
+        return render_memory(&full);
+    }
+    let Some(n) = parts.next().and_then(|s| s.trim().parse::<usize>().ok()) else {
+        return MEMORY_USAGE.into();
+    };
+    let new_text = parts.next().map(str::trim).filter(|s| !s.is_empty());
+    let edit = match (action.as_str(), new_text) {
+        ("edit" | "set", Some(t)) => Some(t),
+        ("delete" | "remove" | "rm", _) => None,
+        _ => return MEMORY_USAGE.into(),
+    };
+    let Some((updated, old)) = edit_entry(&full, n, edit) else {
+        return format!("There is no memory entry #{n}. Send /memory to see the list.");
+    };
+    if let Err(e) = std::fs::write(&path, updated) {
+        return format!("Failed to update memory: {e}");
+    }
+    match edit {
+        Some(t) => format!("Updated #{n}: {} → {}", old.trim(), t),
+        None => format!("Deleted #{n}: {}", old.trim()),
+    }
+}
+
+/// Handle `/forget <text>`: remove every memory entry that mentions the text.
+pub async fn handle_forget_command(
+    db: Arc<Database>,
+    memory: &MemoryManager,
+    chat_id: i64,
+    text: &str,
+) -> String {
+    let needle = command_args(text);
+    if needle.is_empty() {
+        return "Usage: /forget <text> — removes memory entries that mention the text.".into();
+    }
+    let path = match memory_path(db, memory, chat_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let full = std::fs::read_to_string(&path).unwrap_or_default();
+    let (updated, removed) = forget_entries(&full, needle);
+    if removed.is_empty() {
+        return format!("Nothing in memory mentions \"{needle}\".");
+    }
+    if let Err(e) = std::fs::write(&path, updated) {
+        return format!("Failed to update memory: {e}");
+    }
+    let mut resp = format!(
+        "Forgot {} entr{}:",
+        removed.len(),
+        if removed.len() == 1 { "y" } else { "ies" }
+    );
+    for r in removed {
+        resp.push_str(&format!(
+            "\n- {}",
+            r.trim_start_matches(['-', '*', '+', ' '])
+        ));
+    }
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD: &str = "# Memory\n\n## Tier 1 — Long term\n- Likes tea\n- Lives in Oslo\n\n## Tier 2 — Mid term\n- Garden redesign\n\n## Tier 3 — Short term\n";
+
+    #[test]
+    fn test_render_numbers_entries_across_tiers() {
+        let out = render_memory(MD);
+        assert!(
+            out.contains("Tier 1 — Long term\n1. - Likes tea\n2. - Lives in Oslo"),
+            "{out}"
+        );
+        assert!(
+            out.contains("Tier 2 — Mid term\n3. - Garden redesign"),
+            "{out}"
+        );
+        assert!(out.contains("Tier 3 — Short term\n(empty)"), "{out}");
+        assert_eq!(
+            render_memory(""),
+            "Nothing is stored in memory for this chat yet."
+        );
+    }
+
+    #[test]
+    fn test_edit_and_delete_entry() {
+        let (updated, old) = edit_entry(MD, 2, Some("Lives in Bergen")).unwrap();
+        assert_eq!(old, "- Lives in Oslo");
+        assert_eq!(
+            parse_tier_content(&updated, 1),
+            "- Likes tea\n- Lives in Bergen"
+        );
+        assert_eq!(parse_tier_content(&updated, 2), "- Garden redesign");
+
+        let (updated, old) = edit_entry(MD, 3, None).unwrap();
+        assert_eq!(old, "- Garden redesign");
+        assert_eq!(parse_tier_content(&updated, 2), "");
+        assert_eq!(memory_entries(&updated).len(), 2);
+        assert!(edit_entry(MD, 4, None).is_none());
+        assert!(edit_entry(MD, 0, None).is_none());
+    }
+
+    #[test]
+    fn test_forget_removes_matching_entries() {
+        let (updated, removed) = forget_entries(MD, "oslo");
+        assert_eq!(removed, vec!["- Lives in Oslo".to_string()]);
+        assert_eq!(parse_tier_content(&updated, 1), "- Likes tea");
+        let (unchanged, removed) = forget_entries(MD, "coffee");
+        assert!(removed.is_empty());
+        assert_eq!(unchanged, MD);
+    }
+
+    #[tokio::test]
+    async fn test_commands_update_persona_memory() {
+        let dir = std::env::temp_dir().join(format!("microclaw_memcmd_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let memory = MemoryManager::new(dir.to_str().unwrap(), dir.to_str().unwrap());
+        db.upsert_chat(5, None, "private").unwrap();
+        let pid = db.get_current_persona_id(5).unwrap();
+        let path = memory.persona_memory_path(5, pid);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, MD).unwrap();
+
+        let resp =
+            handle_memory_command(db.clone(), &memory, 5, "/memory edit 1 Likes green tea").await;
+        assert_eq!(resp, "Updated #1: - Likes tea → Likes green tea");
+        let resp = handle_forget_command(db.clone(), &memory, 5, "/forget garden").await;
+        assert!(resp.starts_with("Forgot 1 entry:"), "{resp}");
+        let listing = handle_memory_command(db.clone(), &memory, 5, "/memory").await;
+        assert!(listing.contains("1. - Likes green tea"), "{listing}");
+        assert!(!listing.contains("Garden"), "{listing}");
+        assert_eq!(
+            handle_memory_command(db, &memory, 5, "/memory edit x").await,
+            MEMORY_USAGE
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,552 @@
+//! Middleware around the agent loop. Inbound stages run on a user message before it is stored
+//! and answered (translation, moderation, script hooks); any stage may drop the message.
+//! Translation only runs on messages the agent is going to answer.
+//! Outbound stages run on every reply before delivery: formatting, vault citation links, secret
+//! redaction, the output filter, script hooks and finally the usage footer.
+//!
+//! Script hooks (`middleware_hooks`) get the text on stdin and `MICROCLAW_STAGE`,
+//! `MICROCLAW_CHAT_ID` and `MICROCLAW_CHAT_TYPE` in the environment. Exit 0 replaces the text
+//! with stdout, exit 2 drops it (inbound) or withholds the reply (outbound); anything else,
+//! including a timeout, leaves the text unchanged.
+
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::config::{Config, MiddlewareHookConfig, MiddlewareStage};
+use crate::db::Database;
+use crate::telegram::AppState;
+use crate::tools::command_runner::{build_command, shell_command};
+
+/// Hook exit code meaning "drop this message / withhold this reply".
+pub const HOOK_DROP_EXIT_CODE: i32 = 2;
+const REDACTED: &str = "[redacted]";
+/// Shorter configured values are not treated as secrets (they would match ordinary text).
+const MIN_SECRET_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct InboundContext<'a> {
+    pub chat_id: i64,
+    pub chat_type: &'a str,
+    pub sender_name: &'a str,
+    /// The agent will answer this message (not just store it, e.g. an unaddressed group message).
+    pub will_respond: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutboundContext {
+    pub chat_id: i64,
+    /// Appended by the last stage (usage footer), when the reply is not empty.
+    pub footer: Option<String>,
+    /// Keep an empty reply empty (e.g. the agent only reacted); otherwise it becomes "Done.".
+    pub allow_empty: bool,
+}
+
+#[async_trait]
+pub trait InboundStage: Send + Sync {
+    fn name(&self) -> &str;
+    /// The text to pass on, or `None` to drop the message.
+    async fn process(
+        &self,
+        state: &AppState,
+        ctx: &InboundContext<'_>,
+        text: String,
+    ) -> Option<String>;
+}
+
+#[async_trait]
+pub trait OutboundStage: Send + Sync {
+    fn name(&self) -> &str;
+    async fn process(
+        &self,
+        config: &Config,
+        db: &Arc<Database>,
+        ctx: &OutboundContext,
+        text: String,
+    ) -> String;
+}
+
+struct Translate;
+
+#[async_trait]
+impl InboundStage for Translate {
+    fn name(&self) -> &str {
+        "translate"
+    }
+
+    async fn process(
+        &self,
+        state: &AppState,
+        ctx: &InboundContext<'_>,
+        text: String,
+    ) -> Option<String> {
+        // Auto-translate into the working language (original kept alongside); messages that are
+        // only stored are not worth a translation call
+        if !ctx.will_respond {
+            return Some(text);
+        }
+        Some(
+            crate::translation::translate_inbound(
+                &state.config,
+                state.db.clone(),
+                ctx.chat_id,
+                &text,
+            )
+            .await
+            .unwrap_or(text),
+        )
+    }
+}
+
+struct Moderate;
+
+#[async_trait]
+impl InboundStage for Moderate {
+    fn name(&self) -> &str {
+        "moderate"
+    }
+
+    async fn process(
+        &self,
+        state: &AppState,
+        ctx: &InboundContext<'_>,
+        text: String,
+    ) -> Option<String> {
+        // Public-facing chats: flagged messages are held for review instead of answered
+        let held = crate::moderation::check_inbound(
+            state,
+            ctx.chat_id,
+            ctx.chat_type,
+            ctx.sender_name,
+            &text,
+        )
+        .await;
+        (!held).then_some(text)
+    }
+}
+
+struct Format;
+
+#[async_trait]
+impl OutboundStage for Format {
+    fn name(&self) -> &str {
+        "format"
+    }
+
+    async fn process(
+        &self,
+        config: &Config,
+        db: &Arc<Database>,
+        ctx: &OutboundContext,
+        text: String,
+    ) -> String {
+        let level = crate::thinking::level_for(config, db.clone(), ctx.chat_id).await;
+        let text = crate::thinking::apply(level, &text);
+        if text.trim().is_empty() && !ctx.allow_empty {
+            "Done.".to_string()
+        } else {
+            text
+        }
+    }
+}
+
+/// Vault citations (`[[note#heading]]`) become `obsidian://` links.
+struct VaultLinks {
+    vault: String,
+}
+
+#[async_trait]
+impl OutboundStage for VaultLinks {
+    fn name(&self) -> &str {
+        "vault_links"
+    }
+
+    async fn process(
+        &self,
+        _config: &Config,
+        _db: &Arc<Database>,
+        _ctx: &OutboundContext,
+        text: String,
+    ) -> String {
+        crate::vault_index::link_citations(&text, &self.vault)
+    }
+}
+
+/// Configured credentials, longest first so overlapping values are masked whole.
+fn config_secrets(config: &Config) -> Vec<String> {
+    let mut secrets: Vec<String> = [
+        Some(config.telegram_bot_token.clone()),
+        Some(config.api_key.clone()),
+        config.openai_api_key.clone(),
+        config.whatsapp_access_token.clone(),
+        config.whatsapp_verify_token.clone(),
+        config.discord_bot_token.clone(),
+        config.web_auth_token.clone(),
+        config
+            .translation
+            .as_ref()
+            .and_then(|t| t.deepl_api_key.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|s| s.trim().to_string())
+    .filter(|s| s.len() >= MIN_SECRET_LEN)
+    .collect();
+    secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    secrets.dedup();
+    secrets
+}
+
+struct Redact;
+
+#[async_trait]
+impl OutboundStage for Redact {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    async fn process(
+        &self,
+        config: &Config,
+        _db: &Arc<Database>,
+        ctx: &OutboundContext,
+        text: String,
+    ) -> String {
+        let mut text = text;
+        for secret in config_secrets(config) {
+            if text.contains(&secret) {
+                warn!(
+                    "middleware: masked a configured credential in a reply to chat {}",
+                    ctx.chat_id
+                );
+                text = text.replace(&secret, REDACTED);
+            }
+        }
+        text
+    }
+}
+
+struct SafetyFilter;
+
+#[async_trait]
+impl OutboundStage for SafetyFilter {
+    fn name(&self) -> &str {
+        "output_filter"
+    }
+
+    async fn process(
+        &self,
+        config: &Config,
+        db: &Arc<Database>,
+        ctx: &OutboundContext,
+        text: String,
+    ) -> String {
+        crate::output_filter::filter_reply(config, db.clone(), ctx.chat_id, &text).await
+    }
+}
+
+struct Footer;
+
+#[async_trait]
+impl OutboundStage for Footer {
+    fn name(&self) -> &str {
+        "footer"
+    }
+
+    async fn process(
+        &self,
+        _config: &Config,
+        _db: &Arc<Database>,
+        ctx: &OutboundContext,
+        text: String,
+    ) -> String {
+        match &ctx.footer {
+            Some(footer) if !text.trim().is_empty() => format!("{text}\n\n{footer}"),
+            _ => text,
+        }
+    }
+}
+
+enum HookOutcome {
+    Replace(String),
+    Drop,
+    Unchanged,
+}
+
+struct ScriptHook {
+    hook: MiddlewareHookConfig,
+}
+
+impl ScriptHook {
+    fn applies_to(&self, chat_id: i64) -> bool {
+        self.hook.chat_ids.is_empty() || self.hook.chat_ids.contains(&chat_id)
+    }
+
+    async fn run(&self, config: &Config, chat_id: i64, chat_type: &str, text: &str) -> HookOutcome {
+        let stage = match self.hook.stage {
+            MiddlewareStage::Inbound => "inbound",
+            MiddlewareStage::Outbound => "outbound",
+        };
+        let workspace = config.workspace_root_absolute();
+        let mut cmd = build_command(&shell_command(&self.hook.command), Some(&workspace));
+        cmd.env("MICROCLAW_STAGE", stage)
+            .env("MICROCLAW_CHAT_ID", chat_id.to_string())
+            .env("MICROCLAW_CHAT_TYPE", chat_type)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                warn!(
+                    "middleware: failed to start hook `{}`: {e}",
+                    self.hook.command
+                );
+                return HookOutcome::Unchanged;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            let input = text.to_string();
+            // Written concurrently so a hook that streams its output cannot deadlock
+            tokio::spawn(async move {
+                let _ = stdin.write_all(input.as_bytes()).await;
+            });
+        }
+        let timeout = Duration::from_secs(self.hook.timeout_secs.max(1));
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                warn!("middleware: hook `{}` failed: {e}", self.hook.command);
+                return HookOutcome::Unchanged;
+            }
+            Err(_) => {
+                warn!(
+                    "middleware: hook `{}` timed out after {}s",
+                    self.hook.command,
+                    timeout.as_secs()
+                );
+                return HookOutcome::Unchanged;
+            }
+        };
+        match output.status.code() {
+            Some(0) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                HookOutcome::Replace(stdout.trim_end_matches(['\n', '\r']).to_string())
+            }
+            Some(HOOK_DROP_EXIT_CODE) => HookOutcome::Drop,
+            code => {
+                warn!(
+                    "middleware: hook `{}` exited with {:?}: {}",
+                    self.hook.command,
+                    code,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                HookOutcome::Unchanged
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl InboundStage for ScriptHook {
+    fn name(&self) -> &str {
+        &self.hook.command
+    }
+
+    async fn process(
+        &self,
+        state: &AppState,
+        ctx: &InboundContext<'_>,
+        text: String,
+    ) -> Option<String> {
+        if !self.applies_to(ctx.chat_id) {
+            return Some(text);
+        }
+        match self
+            .run(&state.config, ctx.chat_id, ctx.chat_type, &text)
+            .await
+        {
+            HookOutcome::Replace(new_text) => Some(new_text),
+            HookOutcome::Drop => None,
+            HookOutcome::Unchanged => Some(text),
+        }
+    }
+}
+
+#[async_trait]
+impl OutboundStage for ScriptHook {
+    fn name(&self) -> &str {
+        &self.hook.command
+    }
+
+    async fn process(
+        &self,
+        config: &Config,
+        _db: &Arc<Database>,
+        ctx: &OutboundContext,
+        text: String,
+    ) -> String {
+        if !self.applies_to(ctx.chat_id) {
+            return text;
+        }
+        match self.run(config, ctx.chat_id, "", &text).await {
+            HookOutcome::Replace(new_text) => new_text,
+            HookOutcome::Drop => String::new(),
+            HookOutcome::Unchanged => text,
+        }
+    }
+}
+
+fn script_hooks(config: &Config, stage: MiddlewareStage) -> impl Iterator<Item = ScriptHook> + '_ {
+    config
+        .middleware_hooks
+        .iter()
+        .filter(move |h| h.stage == stage && !h.command.trim().is_empty())
+        .map(|h| ScriptHook { hook: h.clone() })
+}
+
+/// Inbound stages in order: normalizers, safety, then script hooks.
+pub fn inbound_stages(config: &Config) -> Vec<Box<dyn InboundStage>> {
+    let mut stages: Vec<Box<dyn InboundStage>> = vec![Box::new(Translate), Box::new(Moderate)];
+    for hook in script_hooks(config, MiddlewareStage::Inbound) {
+        stages.push(Box::new(hook));
+    }
+    stages
+}
+
+/// Outbound stages in order; the footer comes last so nothing rewrites it.
+pub fn outbound_stages(config: &Config) -> Vec<Box<dyn OutboundStage>> {
+    let mut stages: Vec<Box<dyn OutboundStage>> = vec![Box::new(Format)];
+    if let Some(vault) = crate::vault_index::obsidian_vault_name(config) {
+        stages.push(Box::new(VaultLinks { vault }));
+    }
+    stages.push(Box::new(Redact));
+    stages.push(Box::new(SafetyFilter));
+    for hook in script_hooks(config, MiddlewareStage::Outbound) {
+        stages.push(Box::new(hook));
+    }
+    stages.push(Box::new(Footer));
+    stages
+}
+
+/// Run a user message through the inbound stages. `None` means it was dropped and must not be
+/// stored or answered.
+pub async fn run_inbound(
+    state: &AppState,
+    ctx: InboundContext<'_>,
+    text: String,
+) -> Option<String> {
+    let mut text = text;
+    for stage in inbound_stages(&state.config) {
+        match stage.process(state, &ctx, text).await {
+            Some(next) => text = next,
+            None => {
+                info!(
+                    "middleware: inbound stage `{}` dropped a message in chat {}",
+                    stage.name(),
+                    ctx.chat_id
+                );
+                return None;
+            }
+        }
+    }
+    Some(text)
+}
+
+/// Run a reply through the outbound stages and return the text to deliver.
+pub async fn run_outbound(
+    config: &Config,
+    db: Arc<Database>,
+    ctx: OutboundContext,
+    text: String,
+) -> String {
+    let mut text = text;
+    for stage in outbound_stages(config) {
+        text = stage.process(config, &db, &ctx, text).await;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n").unwrap()
+    }
+
+    fn test_db() -> (Arc<Database>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("microclaw_mw_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (Arc::new(Database::new(dir.to_str().unwrap()).unwrap()), dir)
+    }
+
+    #[tokio::test]
+    async fn test_outbound_pipeline() {
+        let (db, dir) = test_db();
+        let mut config = test_config();
+        config.api_key = "sk-secret-value-123".into();
+        config.show_thinking = crate::config::ThinkingLevel::Off;
+
+        let ctx = OutboundContext {
+            chat_id: 1,
+            footer: Some("(12 tokens)".into()),
+            allow_empty: false,
+        };
+        let out = run_outbound(
+            &config,
+            db.clone(),
+            ctx.clone(),
+            "<think>plan</think>Your key is sk-secret-value-123".into(),
+        )
+        .await;
+        assert_eq!(out, "Your key is [redacted]\n\n(12 tokens)");
+
+        let out = run_outbound(&config, db.clone(), ctx, String::new()).await;
+        assert_eq!(out, "Done.\n\n(12 tokens)");
+        let quiet = OutboundContext {
+            chat_id: 1,
+            footer: Some("(12 tokens)".into()),
+            allow_empty: true,
+        };
+        assert_eq!(run_outbound(&config, db, quiet, String::new()).await, "");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_script_hooks() {
+        let (db, dir) = test_db();
+        let mut config = test_config();
+        config.workspace_dir = dir.to_string_lossy().to_string();
+        let hook = |command: &str| MiddlewareHookConfig {
+            stage: MiddlewareStage::Outbound,
+            command: command.into(),
+            chat_ids: Vec::new(),
+            timeout_secs: 5,
+        };
+        config.middleware_hooks = vec![hook("tr a-z A-Z; echo \" ($MICROCLAW_CHAT_ID)\"")];
+        let ctx = OutboundContext {
+            chat_id: 7,
+            ..Default::default()
+        };
+        let out = run_outbound(&config, db.clone(), ctx.clone(), "hello".into()).await;
+        assert_eq!(out, "HELLO (7)");
+
+        // Withheld replies stay empty (formatting already ran)
+        config.middleware_hooks = vec![hook("exit 2")];
+        assert_eq!(
+            run_outbound(&config, db.clone(), ctx.clone(), "hi".into()).await,
+            ""
+        );
+        // Failing hooks leave the text alone
+        config.middleware_hooks = vec![hook("exit 1")];
+        assert_eq!(run_outbound(&config, db, ctx, "hi".into()).await, "hi");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,242 @@
+//! Notification coalescer: proactive messages (feed alerts, scheduled task results) for the same
+//! chat within `notifications.window_secs` are held and sent as one grouped message with a
+//! section per source. Urgent sources bypass the digest.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::config::{Config, NotificationPriority};
+use crate::feature_flags::{is_enabled, FeatureFlag};
+use crate::push::deliver_proactive;
+use crate::telegram::AppState;
+
+/// One held message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub source: String,
+    pub text: String,
+}
+
+struct PendingDigest {
+    persona_id: i64,
+    items: Vec<Notification>,
+}
+
+static PENDING: Mutex<Option<HashMap<i64, PendingDigest>>> = Mutex::new(None);
+
+/// Built-in priority of a source; failures are urgent, everything else can wait.
+fn default_priority(source: &str) -> NotificationPriority {
+    match source {
+        "task_failure" | "task_escalation" => NotificationPriority::Urgent,
+        _ => NotificationPriority::Normal,
+    }
+}
+
+/// Priority of `source` after config overrides.
+pub fn priority(config: &Config, source: &str) -> NotificationPriority {
+    config
+        .notifications
+        .as_ref()
+        .and_then(|n| n.priorities.get(source).copied())
+        .unwrap_or_else(|| default_priority(source))
+}
+
+/// Digest window, or `None` when digesting is off.
+fn window(config: &Config) -> Option<Duration> {
+    config
+        .notifications
+        .as_ref()
+        .map(|n| n.window_secs)
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+fn source_label(source: &str) -> &str {
+    match source {
+        "social_monitor" => "Social feeds",
+        "feeds" => "Feeds",
+        "scheduled_task" => "Scheduled tasks",
+        "task_failure" => "Failed tasks",
+        "task_escalation" => "Task escalations",
+        other => other,
+    }
+}
+
+/// One message for the held items: a single item is sent as is, several are grouped under a
+/// heading per source (in order of first appearance).
+pub fn render_digest(items: &[Notification]) -> String {
+    if let [only] = items {
+        return only.text.clone();
+    }
+    let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+    for item in items {
+        match sections.iter_mut().find(|(s, _)| *s == item.source) {
+            Some((_, texts)) => texts.push(&item.text),
+            None => sections.push((&item.source, vec![&item.text])),
+        }
+    }
+    let mut out = format!("🔔 {} updates", items.len());
+    for (source, texts) in sections {
+        out.push_str(&format!("\n\n**{}**", source_label(source)));
+        for text in texts {
+            out.push('\n');
+            out.push_str(text.trim());
+        }
+    }
+    out
+}
+
+/// Hold `item` for `chat_id`. Returns true when it is the first held item for the chat, i.e.
+/// the caller must schedule the flush.
+fn hold(chat_id: i64, persona_id: i64, item: Notification) -> bool {
+    let mut guard = PENDING.lock().unwrap();
+    let pending = guard.get_or_insert_with(HashMap::new);
+    match pending.get_mut(&chat_id) {
+        Some(digest) => {
+            digest.items.push(item);
+            false
+        }
+        None => {
+            pending.insert(
+                chat_id,
+                PendingDigest {
+                    persona_id,
+                    items: vec![item],
+                },
+            );
+            true
+        }
+    }
+}
+
+fn take(chat_id: i64) -> Option<PendingDigest> {
+    PENDING.lock().unwrap().as_mut()?.remove(&chat_id)
+}
+
+async fn flush(state: &AppState, chat_id: i64) {
+    let Some(digest) = take(chat_id) else {
+        return;
+    };
+    if let Err(e) =
+        deliver_proactive(state, chat_id, digest.persona_id, &render_digest(&digest.items)).await
+    {
+        warn!("Notification digest for chat {chat_id} failed: {e}");
+    }
+}
+
+/// Send a proactive message from `source`, or hold it for the chat's digest when digesting is
+/// on and the source is not urgent. Dropped when the chat has `proactive_messages` off. Sources
+/// with the `incident` priority open an incident instead (see [`crate::incidents`]). Chats that
+/// chose `/push on` get it as a push notification (see [`crate::push`]).
+pub async fn notify(
+    state: &Arc<AppState>,
+    chat_id: i64,
+    persona_id: i64,
+    source: &str,
+    text: &str,
+) -> Result<(), String> {
+    if priority(&state.config, source) == NotificationPriority::Incident {
+        let (title, details) = text.trim().split_once('\n').unwrap_or((text.trim(), ""));
+        return crate::incidents::raise(
+            &state.bot,
+            state.db.clone(),
+            &state.config,
+            source,
+            source,
+            title,
+            Some(details),
+        )
+        .await
+        .map(|_| ());
+    }
+    if !is_enabled(
+        &state.config,
+        state.db.clone(),
+        chat_id,
+        FeatureFlag::ProactiveMessages,
+    )
+    .await
+    {
+        info!("Dropping {source} message for chat {chat_id}: proactive messages are off");
+        return Ok(());
+    }
+    let window = match window(&state.config) {
+        Some(w) if priority(&state.config, source) == NotificationPriority::Normal => w,
+        _ => {
+            return deliver_proactive(state, chat_id, persona_id, text).await
+        }
+    };
+    let item = Notification {
+        source: source.to_string(),
+        text: text.to_string(),
+    };
+    if hold(chat_id, persona_id, item) {
+        let state = state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            flush(&state, chat_id).await;
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(source: &str, text: &str) -> Notification {
+        Notification {
+            source: source.into(),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_render_digest_groups_by_source() {
+        assert_eq!(render_digest(&[item("social_monitor", "📈 a")]), "📈 a");
+        let out = render_digest(&[
+            item("social_monitor", "📈 a"),
+            item("scheduled_task", "Weather: sunny\n"),
+            item("social_monitor", "📈 b"),
+        ]);
+        assert_eq!(
+            out,
+            "🔔 3 updates\n\n**Social feeds**\n📈 a\n📈 b\n\n**Scheduled tasks**\nWeather: sunny"
+        );
+    }
+
+    #[test]
+    fn test_priority_overrides_and_hold() {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        assert!(window(&config).is_none());
+        config.notifications = Some(
+            serde_yaml::from_str("window_secs: 60\npriorities:\n  social_monitor: urgent\n")
+                .unwrap(),
+        );
+        assert_eq!(window(&config), Some(Duration::from_secs(60)));
+        assert_eq!(
+            priority(&config, "social_monitor"),
+            NotificationPriority::Urgent
+        );
+        assert_eq!(
+            priority(&config, "task_failure"),
+            NotificationPriority::Urgent
+        );
+        assert_eq!(
+            priority(&config, "scheduled_task"),
+            NotificationPriority::Normal
+        );
+
+        let chat_id = -987_654_321;
+        assert!(hold(chat_id, 1, item("scheduled_task", "one")));
+        assert!(!hold(chat_id, 1, item("scheduled_task", "two")));
+        let digest = take(chat_id).unwrap();
+        assert_eq!(digest.items.len(), 2);
+        assert!(take(chat_id).is_none());
+    }
+}
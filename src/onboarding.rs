@@ -0,0 +1,497 @@
+//! Onboarding: the first message of a new private chat (one the bot has never replied in)
+//! starts a short guided conversation — name, timezone, reply language, notifications, what
+//! the user wants help with, persona — instead of a blank slate. Answers become chat settings
+//! (`timezone`, `language`, push delivery or the `proactive_messages` flag), a profile in the
+//! persona's Tier 1 memory and the active persona. Progress is the `onboarding` chat setting.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::db::{call_blocking, Database};
+use crate::feature_flags::FeatureFlag;
+use crate::telegram::AppState;
+use crate::tools::tiered_memory::{memory_path, parse_tier_content, write_tier};
+
+/// Chat setting holding the onboarding progress (JSON), or `done`.
+const STATE_SETTING: &str = "onboarding";
+const DONE: &str = "done";
+/// Chat setting with the chat's IANA timezone (overrides `timezone` from the config).
+pub const TIMEZONE_SETTING: &str = "timezone";
+/// Chat setting with the language replies should be written in.
+pub const LANGUAGE_SETTING: &str = "language";
+const MAX_ANSWER_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Step {
+    Name,
+    Timezone,
+    Language,
+    Notifications,
+    Goals,
+    Persona,
+}
+
+impl Step {
+    fn next(self) -> Option<Step> {
+        match self {
+            Step::Name => Some(Step::Timezone),
+            Step::Timezone => Some(Step::Language),
+            Step::Language => Some(Step::Notifications),
+            Step::Notifications => Some(Step::Goals),
+            Step::Goals => Some(Step::Persona),
+            Step::Persona => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Notifications {
+    Chat,
+    Push,
+    Off,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Progress {
+    step: Step,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    notifications: Option<Notifications>,
+    #[serde(default)]
+    goals: Option<String>,
+    #[serde(default)]
+    persona: Option<String>,
+}
+
+impl Progress {
+    fn new() -> Self {
+        Progress {
+            step: Step::Name,
+            name: None,
+            timezone: None,
+            language: None,
+            notifications: None,
+            goals: None,
+            persona: None,
+        }
+    }
+}
+
+fn is_skip(answer: &str) -> bool {
+    matches!(
+        answer.trim().to_lowercase().trim_end_matches('.'),
+        "skip" | "/skip" | "pass" | "-" | "n/a"
+    )
+}
+
+fn is_stop(answer: &str) -> bool {
+    matches!(
+        answer.trim().to_lowercase().trim_end_matches('.'),
+        "skip all" | "stop" | "cancel" | "later" | "not now"
+    )
+}
+
+/// An IANA name ("Europe/Berlin", case-insensitive) or a city in one ("berlin", "new york").
+pub fn parse_timezone(answer: &str) -> Option<chrono_tz::Tz> {
+    let answer = answer.trim();
+    if let Ok(tz) = answer.parse::<chrono_tz::Tz>() {
+        return Some(tz);
+    }
+    let wanted = answer.to_lowercase().replace(' ', "_");
+    if wanted.is_empty() {
+        return None;
+    }
+    chrono_tz::TZ_VARIANTS.iter().copied().find(|tz| {
+        let name = tz.name().to_lowercase();
+        name == wanted || name.rsplit('/').next() == Some(wanted.as_str())
+    })
+}
+
+fn parse_notifications(answer: &str, push_available: bool) -> Result<Notifications, String> {
+    let answer = answer.trim().to_lowercase();
+    let word = answer.split_whitespace().next().unwrap_or("");
+    match word {
+        "here" | "chat" | "all" | "yes" | "everything" => Ok(Notifications::Chat),
+        "push" | "ntfy" | "gotify" if push_available => Ok(Notifications::Push),
+        "push" | "ntfy" | "gotify" => {
+            Err("Push notifications are not set up on this bot, so they would come here.".into())
+        }
+        "off" | "no" | "none" | "nothing" | "never" | "quiet" => Ok(Notifications::Off),
+        _ => Err("Please answer here, push or off.".into()),
+    }
+}
+
+fn valid_persona_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+fn notifications_label(n: Notifications) -> &'static str {
+    match n {
+        Notifications::Chat => "in this chat",
+        Notifications::Push => "as push notifications",
+        Notifications::Off => "off (only replies to messages)",
+    }
+}
+
+fn question(step: Step, personas: &[String], push_available: bool) -> String {
+    match step {
+        Step::Name => "First, what should I call you?".into(),
+        Step::Timezone => {
+            "Which timezone are you in? A city or an IANA name works (e.g. Berlin, America/New_York).".into()
+        }
+        Step::Language => "Which language should I reply in?".into(),
+        Step::Notifications => {
+            let push = if push_available {
+                "\n• push — as push notifications on your phone"
+            } else {
+                ""
+            };
+            format!(
+                "How should reminders, scheduled results and alerts reach you?\n• here — in this chat{push}\n• off — only reply when you write"
+            )
+        }
+        Step::Goals => "What would you like help with? (e.g. reminders, home automation, research, coding)".into(),
+        Step::Persona => format!(
+            "Last one: which persona should I use? Existing: {}. Reply with one of them, or a one-word name to create a new persona with its own memory.",
+            personas.join(", ")
+        ),
+    }
+}
+
+/// Record `answer` for the current step. Ok(true) when onboarding is complete; Err is a
+/// message to show before asking the same question again.
+fn record_answer(
+    progress: &mut Progress,
+    answer: &str,
+    push_available: bool,
+) -> Result<bool, String> {
+    let answer = answer.trim();
+    if !is_skip(answer) {
+        let text: String = answer.chars().take(MAX_ANSWER_CHARS).collect();
+        match progress.step {
+            Step::Name => progress.name = Some(text),
+            Step::Timezone => {
+                let tz = parse_timezone(answer)
+                    .ok_or_else(|| format!("I don't know the timezone '{answer}'."))?;
+                progress.timezone = Some(tz.name().to_string());
+            }
+            Step::Language => progress.language = Some(text),
+            Step::Notifications => {
+                progress.notifications = Some(parse_notifications(answer, push_available)?)
+            }
+            Step::Goals => progress.goals = Some(text),
+            Step::Persona => {
+                if !valid_persona_name(answer) {
+                    return Err("Persona names are one word (letters, digits, - or _).".into());
+                }
+                progress.persona = Some(answer.to_string());
+            }
+        }
+    }
+    match progress.step.next() {
+        Some(next) => {
+            progress.step = next;
+            Ok(false)
+        }
+        None => Ok(true),
+    }
+}
+
+/// The Tier 1 profile lines for the answers given.
+fn profile(progress: &Progress) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(name) = &progress.name {
+        lines.push(format!("- Name: {name}"));
+    }
+    if let Some(tz) = &progress.timezone {
+        lines.push(format!("- Timezone: {tz}"));
+    }
+    if let Some(language) = &progress.language {
+        lines.push(format!("- Preferred language: {language}"));
+    }
+    if let Some(n) = progress.notifications {
+        lines.push(format!(
+            "- Proactive messages (reminders, alerts): {}",
+            notifications_label(n)
+        ));
+    }
+    if let Some(goals) = &progress.goals {
+        lines.push(format!("- Wants help with: {goals}"));
+    }
+    lines
+}
+
+/// The chat's timezone: its `timezone` setting, else `default`.
+pub async fn chat_timezone(db: Arc<Database>, chat_id: i64, default: &str) -> String {
+    call_blocking(db, move |d| d.get_chat_setting(chat_id, TIMEZONE_SETTING))
+        .await
+        .ok()
+        .flatten()
+        .filter(|tz| tz.parse::<chrono_tz::Tz>().is_ok())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// System prompt lines for the chat's language preference, if it has one.
+pub async fn prompt_section(db: Arc<Database>, chat_id: i64) -> Option<String> {
+    let language = call_blocking(db, move |d| d.get_chat_setting(chat_id, LANGUAGE_SETTING))
+        .await
+        .ok()
+        .flatten()
+        .filter(|l| !l.trim().is_empty())?;
+    Some(format!(
+        "**Reply language:** The user asked to be answered in {}. Reply in that language unless they write in or ask for another.",
+        language.trim()
+    ))
+}
+
+async fn save(db: Arc<Database>, chat_id: i64, value: String) {
+    if let Err(e) = call_blocking(db, move |d| {
+        d.set_chat_setting(chat_id, STATE_SETTING, Some(&value))
+    })
+    .await
+    {
+        warn!("onboarding: failed to save progress for chat {chat_id}: {e}");
+    }
+}
+
+/// Apply the answers; returns the closing message.
+async fn finish(state: &AppState, chat_id: i64, persona_id: i64, progress: &Progress) -> String {
+    let db = state.db.clone();
+    let mut notes = Vec::new();
+
+    let mut persona_id = persona_id;
+    if let Some(name) = progress.persona.clone() {
+        let result = call_blocking(db.clone(), move |d| {
+            let id = match d.get_persona_by_name(chat_id, &name)? {
+                Some(p) => p.id,
+                None => d.create_persona(chat_id, &name, None)?,
+            };
+            d.set_active_persona(chat_id, id)?;
+            Ok(id)
+        })
+        .await;
+        match result {
+            Ok(id) => persona_id = id,
+            Err(e) => notes.push(format!("Could not switch persona: {e}")),
+        }
+    }
+
+    let (tz, language) = (progress.timezone.clone(), progress.language.clone());
+    if let Err(e) = call_blocking(db.clone(), move |d| {
+        if let Some(tz) = tz {
+            d.set_chat_setting(chat_id, TIMEZONE_SETTING, Some(&tz))?;
+        }
+        if let Some(language) = language {
+            d.set_chat_setting(chat_id, LANGUAGE_SETTING, Some(&language))?;
+        }
+        Ok(())
+    })
+    .await
+    {
+        notes.push(format!("Could not save your preferences: {e}"));
+    }
+
+    match progress.notifications {
+        Some(Notifications::Push) => {
+            let reply =
+                crate::push::handle_push_command(&state.config, db.clone(), chat_id, "/push on")
+                    .await;
+            if reply.starts_with("Usage") {
+                notes.push(
+                    "Push needs a topic: send /push on <topic> to finish setting it up.".into(),
+                );
+            }
+        }
+        Some(Notifications::Off) => {
+            if let Err(e) = call_blocking(db.clone(), move |d| {
+                d.set_feature_flag(chat_id, FeatureFlag::ProactiveMessages.name(), Some(false))
+            })
+            .await
+            {
+                notes.push(format!("Could not turn off proactive messages: {e}"));
+            }
+        }
+        Some(Notifications::Chat) | None => {}
+    }
+
+    let lines = profile(progress);
+    if !lines.is_empty() {
+        let groups_dir = Path::new(&state.config.runtime_data_dir()).join("groups");
+        let path = memory_path(&groups_dir, chat_id, persona_id);
+        let tier1 = std::fs::read_to_string(&path)
+            .map(|full| parse_tier_content(&full, 1))
+            .unwrap_or_default();
+        let content = format!(
+            "{}\n\nUser profile (from onboarding):\n{}",
+            tier1.trim(),
+            lines.join("\n")
+        );
+        if let Err(e) = write_tier(&path, 1, content.trim()) {
+            warn!("onboarding: failed to write {}: {e}", path.display());
+        }
+    }
+    info!("Onboarding finished for chat {chat_id}");
+
+    let greeting = match &progress.name {
+        Some(name) => format!("Thanks, {name}! You're all set."),
+        None => "Thanks! You're all set.".to_string(),
+    };
+    let mut out = greeting;
+    if !lines.is_empty() {
+        out.push_str("\n\n");
+        out.push_str(
+            &lines
+                .iter()
+                .map(|l| l.replacen("- ", "• ", 1))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+    if let Some(name) = &progress.persona {
+        out.push_str(&format!("\n• Persona: {name}"));
+    }
+    for note in notes {
+        out.push_str(&format!("\n\n⚠️ {note}"));
+    }
+    out.push_str("\n\nTell me any time if something changes. What can I do for you?");
+    out
+}
+
+/// Run onboarding for a message in a private chat (already stored). Returns the reply to send
+/// instead of running the agent, or None when the chat is not onboarding.
+pub async fn handle_message(
+    state: &Arc<AppState>,
+    chat_id: i64,
+    persona_id: i64,
+    text: &str,
+) -> Option<String> {
+    if !state.config.onboarding_enabled {
+        return None;
+    }
+    let db = state.db.clone();
+    let setting = call_blocking(db.clone(), move |d| {
+        d.get_chat_setting(chat_id, STATE_SETTING)
+    })
+    .await
+    .ok()?;
+    let push_available = state.config.push.is_some();
+    let personas = || async {
+        call_blocking(state.db.clone(), move |d| d.list_personas(chat_id))
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| p.name)
+            .collect::<Vec<_>>()
+    };
+
+    let mut progress = match setting.as_deref() {
+        Some(DONE) => return None,
+        None => {
+            // Only chats the bot has never answered; existing chats are marked done
+            let answered = call_blocking(db.clone(), move |d| d.has_bot_messages(chat_id))
+                .await
+                .unwrap_or(true);
+            if answered {
+                save(db, chat_id, DONE.into()).await;
+                return None;
+            }
+            let progress = Progress::new();
+            save(db, chat_id, serde_json::to_string(&progress).ok()?).await;
+            info!("Onboarding started for chat {chat_id}");
+            return Some(format!(
+                "👋 Hi, I'm {}! A few quick questions so I can help you better. Answer skip to pass one, or skip all to start right away.\n\n{}",
+                state.config.bot_username,
+                question(Step::Name, &[], push_available)
+            ));
+        }
+        Some(json) => match serde_json::from_str::<Progress>(json) {
+            Ok(progress) => progress,
+            Err(_) => {
+                save(db, chat_id, DONE.into()).await;
+                return None;
+            }
+        },
+    };
+
+    if is_stop(text) {
+        save(db, chat_id, DONE.into()).await;
+        return Some("No problem, we can skip that. What can I do for you?".into());
+    }
+    match record_answer(&mut progress, text, push_available) {
+        Err(problem) => {
+            let ask = question(progress.step, &personas().await, push_available);
+            Some(format!("{problem} {ask}"))
+        }
+        Ok(false) => {
+            save(db, chat_id, serde_json::to_string(&progress).ok()?).await;
+            Some(question(progress.step, &personas().await, push_available))
+        }
+        Ok(true) => {
+            save(db, chat_id, DONE.into()).await;
+            Some(finish(state, chat_id, persona_id, &progress).await)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_onboarding_answers() {
+        assert_eq!(
+            parse_timezone("Europe/Berlin"),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(
+            parse_timezone("new york"),
+            Some(chrono_tz::America::New_York)
+        );
+        assert_eq!(parse_timezone("atlantis"), None);
+
+        let mut progress = Progress::new();
+        assert_eq!(record_answer(&mut progress, "Sam", false), Ok(false));
+        assert!(record_answer(&mut progress, "atlantis", false)
+            .unwrap_err()
+            .contains("'atlantis'"));
+        assert_eq!(progress.step, Step::Timezone);
+        assert_eq!(record_answer(&mut progress, "Lisbon", false), Ok(false));
+        assert_eq!(record_answer(&mut progress, "skip", false), Ok(false));
+        assert!(record_answer(&mut progress, "push", false).is_err());
+        assert_eq!(record_answer(&mut progress, "off", false), Ok(false));
+        assert_eq!(
+            record_answer(&mut progress, "reminders and recipes", false),
+            Ok(false)
+        );
+        assert!(record_answer(&mut progress, "two words", false).is_err());
+        assert_eq!(record_answer(&mut progress, "Jarvis", false), Ok(true));
+
+        assert_eq!(progress.timezone.as_deref(), Some("Europe/Lisbon"));
+        assert_eq!(progress.language, None);
+        assert_eq!(progress.persona.as_deref(), Some("Jarvis"));
+        assert_eq!(
+            profile(&progress),
+            vec![
+                "- Name: Sam",
+                "- Timezone: Europe/Lisbon",
+                "- Proactive messages (reminders, alerts): off (only replies to messages)",
+                "- Wants help with: reminders and recipes",
+            ]
+        );
+        assert!(is_stop("Skip all"));
+    }
+}
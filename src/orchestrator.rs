@@ -1,11 +1,13 @@
 //! Orchestrator: plan-first architecture. Produces a plan for every user message before
 //! the main agent loop. Simple plans → direct reply; complex plans → delegate to sub-agents.
 
-use crate::claude::{Message, MessageContent, ResponseContentBlock};
+use crate::claude::{Message, MessageContent, ResponseContentBlock, ToolChoice, ToolDefinition};
 use crate::config::Config;
 use crate::error::MicroClawError;
 use crate::llm;
+use crate::tools::schema_object;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use tracing::info;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -35,6 +37,30 @@ Rules:
 - delegate_tasks: required when strategy is "delegate"; list clear, independent tasks. Omit or empty array when strategy is "direct".
 - Prefer "direct" when unsure; avoid over-delegation."#;
 
+const SUBMIT_PLAN_TOOL: &str = "submit_plan";
+
+/// The plan as a tool call; the orchestrator forces it so providers with tool-choice support
+/// return structured input instead of free-form JSON text.
+fn submit_plan_tool() -> ToolDefinition {
+    ToolDefinition {
+        name: SUBMIT_PLAN_TOOL.into(),
+        description: "Submit the plan for the user message.".into(),
+        input_schema: schema_object(
+            json!({
+                "strategy": {"type": "string", "enum": ["direct", "delegate"]},
+                "summary": {"type": "string", "description": "Brief rationale"},
+                "delegate_tasks": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Independent tasks; only when strategy is delegate"
+                }
+            }),
+            &["strategy", "summary"],
+        ),
+        examples: Vec::new(),
+    }
+}
+
 /// Run the orchestrator to produce a plan for the user message.
 /// Uses config.model unless config.orchestrator_model is set.
 pub async fn run_orchestrator_plan(
@@ -60,20 +86,15 @@ pub async fn run_orchestrator_plan(
 
     let provider = llm::create_provider(&llm_config);
     let response = provider
-        .send_message(ORCHESTRATOR_SYSTEM, messages, None)
+        .send_message_with_tool_choice(
+            ORCHESTRATOR_SYSTEM,
+            messages,
+            Some(vec![submit_plan_tool()]),
+            ToolChoice::Tool(SUBMIT_PLAN_TOOL.into()),
+        )
         .await?;
 
-    let text: String = response
-        .content
-        .iter()
-        .filter_map(|block| match block {
-            ResponseContentBlock::Text { text } => Some(text.as_str()),
-            _ => None,
-        })
-        .collect::<Vec<_>>()
-        .join("");
-
-    let plan = parse_plan(&text)?;
+    let plan = plan_from_response(&response.content)?;
     info!(
         "Orchestrator plan: strategy={:?} summary={} delegate_tasks={:?}",
         plan.strategy,
@@ -83,6 +104,29 @@ pub async fn run_orchestrator_plan(
     Ok(plan)
 }
 
+/// The `submit_plan` call if there is one, else the plan JSON in the text (providers that
+/// ignore tool choice).
+fn plan_from_response(content: &[ResponseContentBlock]) -> Result<Plan, MicroClawError> {
+    let submitted = content.iter().find_map(|block| match block {
+        ResponseContentBlock::ToolUse { name, input, .. } if name == SUBMIT_PLAN_TOOL => {
+            serde_json::from_value::<Plan>(input.clone()).ok()
+        }
+        _ => None,
+    });
+    if let Some(plan) = submitted {
+        return Ok(plan);
+    }
+    let text: String = content
+        .iter()
+        .filter_map(|block| match block {
+            ResponseContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("");
+    parse_plan(&text)
+}
+
 fn parse_plan(text: &str) -> Result<Plan, MicroClawError> {
     let trimmed = text.trim();
     // Strip markdown code blocks if present
@@ -135,4 +179,25 @@ mod tests {
         let plan = parse_plan(text).unwrap();
         assert_eq!(plan.strategy, PlanStrategy::Direct);
     }
+
+    #[test]
+    fn test_plan_from_submit_plan_call() {
+        let content = vec![ResponseContentBlock::ToolUse {
+            id: "t1".into(),
+            name: SUBMIT_PLAN_TOOL.into(),
+            input: json!({"strategy": "delegate", "summary": "split", "delegate_tasks": ["a"]}),
+            thought_signature: None,
+        }];
+        let plan = plan_from_response(&content).unwrap();
+        assert_eq!(plan.strategy, PlanStrategy::Delegate);
+        assert_eq!(plan.delegate_tasks.unwrap(), vec!["a".to_string()]);
+
+        let content = vec![ResponseContentBlock::Text {
+            text: "{\"strategy\": \"direct\"}".into(),
+        }];
+        assert_eq!(
+            plan_from_response(&content).unwrap().strategy,
+            PlanStrategy::Direct
+        );
+    }
 }
@@ -0,0 +1,266 @@
+//! Output filter: masks or withholds replies in group-facing chats using wordlists and an
+//! optional moderation API, logging every filtered reply to the `filtered_outputs` table.
+
+use std::sync::Arc;
+
+use regex::Regex;
+use tracing::warn;
+
+use crate::config::{Config, OutputFilterConfig};
+use crate::db::{call_blocking, Database};
+
+const DEFAULT_CHAT_TYPES: &[&str] = &[
+    "telegram_group",
+    "telegram_supergroup",
+    "telegram_channel",
+    "discord",
+];
+const DEFAULT_BLOCKED_MESSAGE: &str = "Sorry, I can't share that reply here.";
+const DEFAULT_MODERATION_URL: &str = "https://api.openai.com/v1/moderations";
+
+/// Whether the filter covers a chat of this type (or this specific chat).
+pub fn applies_to(cfg: &OutputFilterConfig, chat_id: i64, chat_type: &str) -> bool {
+    if cfg.chat_ids.contains(&chat_id) {
+        return true;
+    }
+    if cfg.chat_types.is_empty() {
+        DEFAULT_CHAT_TYPES.contains(&chat_type)
+    } else {
+        cfg.chat_types.iter().any(|t| t == chat_type)
+    }
+}
+
+/// Configured words plus the wordlist file (one entry per line, `#` comments).
+pub fn load_words(config: &Config, cfg: &OutputFilterConfig) -> Vec<String> {
+    let mut words: Vec<String> = cfg
+        .words
+        .iter()
+        .map(|w| w.trim().to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if let Some(path) = cfg
+        .wordlist_path
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        let path = config.workspace_root_absolute().join(path.trim());
+        match std::fs::read_to_string(&path) {
+            Ok(content) => words.extend(
+                content
+                    .lines()
+                    .map(|l| l.trim().to_lowercase())
+                    .filter(|l| !l.is_empty() && !l.starts_with('#')),
+            ),
+            Err(e) => warn!("output filter: failed to read {}: {e}", path.display()),
+        }
+    }
+    words.sort_by_key(|w| std::cmp::Reverse(w.chars().count()));
+    words.dedup();
+    words
+}
+
+/// Replace whole-word matches (case-insensitive) with asterisks. Returns the masked text and
+/// the distinct words that matched.
+pub fn mask_words(text: &str, words: &[String]) -> (String, Vec<String>) {
+    if words.is_empty() {
+        return (text.to_string(), Vec::new());
+    }
+    let alternation = words
+        .iter()
+        .map(|w| regex::escape(w))
+        .collect::<Vec<_>>()
+        .join("|");
+    let Ok(re) = Regex::new(&format!(r"(?i)\b(?:{alternation})\b")) else {
+        return (text.to_string(), Vec::new());
+    };
+    let mut matched: Vec<String> = Vec::new();
+    let masked = re.replace_all(text, |caps: &regex::Captures| {
+        let word = caps[0].to_lowercase();
+        if !matched.contains(&word) {
+            matched.push(word);
+        }
+        caps[0]
+            .chars()
+            .map(|c| if c.is_whitespace() { c } else { '*' })
+            .collect::<String>()
+    });
+    (masked.into_owned(), matched)
+}
+
+/// Flagged moderation categories for `text`, or an empty list when it passes.
+async fn moderation_categories(
+    config: &Config,
+    cfg: &OutputFilterConfig,
+    text: &str,
+) -> Result<Vec<String>, String> {
+    let api_key = config
+        .openai_api_key
+        .as_deref()
+        .filter(|k| !k.trim().is_empty())
+        .ok_or_else(|| "openai_api_key is not set".to_string())?;
+    let url = cfg
+        .moderation_api_url
+        .as_deref()
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or(DEFAULT_MODERATION_URL);
+    let resp = reqwest::Client::new()
+        .post(url)
+        .bearer_auth(api_key)
+        .json(&serde_json::json!({ "input": text }))
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("moderation request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("moderation API returned HTTP {}", resp.status()));
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("invalid moderation response: {e}"))?;
+    let result = &body["results"][0];
+    if !result["flagged"].as_bool().unwrap_or(false) {
+        return Ok(Vec::new());
+    }
+    let mut categories: Vec<String> = result["categories"]
+        .as_object()
+        .map(|m| {
+            m.iter()
+                .filter(|(_, v)| v.as_bool() == Some(true))
+                .map(|(k, _)| k.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    if categories.is_empty() {
+        categories.push("flagged".into());
+    }
+    Ok(categories)
+}
+
+/// Apply the output filter to a reply bound for `chat_id`. Returns the text to deliver:
+/// unchanged, masked, or replaced by the blocked message. Filtered replies are logged.
+/// Moderation API failures are logged and fall back to the wordlist result.
+pub async fn filter_reply(config: &Config, db: Arc<Database>, chat_id: i64, text: &str) -> String {
+    let Some(cfg) = config.output_filter.as_ref() else {
+        return text.to_string();
+    };
+    if text.trim().is_empty() {
+        return text.to_string();
+    }
+    let chat_type = call_blocking(db.clone(), move |d| d.get_chat_type(chat_id))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if !applies_to(cfg, chat_id, &chat_type) {
+        return text.to_string();
+    }
+
+    let blocked_message = cfg
+        .blocked_message
+        .as_deref()
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or(DEFAULT_BLOCKED_MESSAGE)
+        .to_string();
+    let block = cfg
+        .action
+        .as_deref()
+        .is_some_and(|a| a.trim().eq_ignore_ascii_case("block"));
+
+    let words = load_words(config, cfg);
+    let (masked, matched) = mask_words(text, &words);
+    let mut reason = None;
+    let mut delivered = text.to_string();
+    if !matched.is_empty() {
+        reason = Some(format!("wordlist: {}", matched.join(", ")));
+        delivered = if block {
+            blocked_message.clone()
+        } else {
+            masked
+        };
+    }
+
+    if cfg.moderation_api {
+        match moderation_categories(config, cfg, text).await {
+            Ok(categories) if !categories.is_empty() => {
+                reason = Some(format!("moderation: {}", categories.join(", ")));
+                delivered = blocked_message;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("output filter: {e}; using wordlist result for chat {chat_id}"),
+        }
+    }
+
+    if let Some(reason) = reason {
+        let original = text.to_string();
+        let logged = delivered.clone();
+        if let Err(e) = call_blocking(db, move |d| {
+            d.log_filtered_output(chat_id, &chat_type, &reason, &original, &logged)
+        })
+        .await
+        {
+            warn!("output filter: failed to log filtered reply for chat {chat_id}: {e}");
+        }
+    }
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_words_whole_word_case_insensitive() {
+        let words = vec!["darn".to_string(), "heck no".to_string()];
+        let (masked, matched) = mask_words("Darn it, HECK NO. darned", &words);
+        assert_eq!(masked, "**** it, **** **. darned");
+        assert_eq!(matched, vec!["darn".to_string(), "heck no".to_string()]);
+
+        let (unchanged, matched) = mask_words("all clean", &words);
+        assert_eq!(unchanged, "all clean");
+        assert!(matched.is_empty());
+    }
+
+    #[test]
+    fn test_applies_to_defaults_and_overrides() {
+        let mut cfg = OutputFilterConfig::default();
+        assert!(applies_to(&cfg, 1, "telegram_group"));
+        assert!(!applies_to(&cfg, 1, "telegram_private"));
+        cfg.chat_ids = vec![7];
+        assert!(applies_to(&cfg, 7, "web"));
+        cfg.chat_types = vec!["web".into()];
+        assert!(applies_to(&cfg, 2, "web"));
+        assert!(!applies_to(&cfg, 2, "discord"));
+    }
+
+    #[tokio::test]
+    async fn test_filter_reply_masks_blocks_and_logs() {
+        let dir = std::env::temp_dir().join(format!("microclaw_ofilter_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(1, Some("Family"), "telegram_group").unwrap();
+        db.upsert_chat(2, Some("Me"), "telegram_private").unwrap();
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.output_filter = Some(OutputFilterConfig {
+            words: vec!["darn".into()],
+            ..Default::default()
+        });
+
+        let out = filter_reply(&config, db.clone(), 1, "Oh darn, the oven.").await;
+        assert_eq!(out, "Oh ****, the oven.");
+        let out = filter_reply(&config, db.clone(), 2, "Oh darn, the oven.").await;
+        assert_eq!(out, "Oh darn, the oven.");
+
+        config.output_filter.as_mut().unwrap().action = Some("block".into());
+        let out = filter_reply(&config, db.clone(), 1, "darn").await;
+        assert_eq!(out, DEFAULT_BLOCKED_MESSAGE);
+
+        let log = db.list_filtered_outputs(1, 10).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].0, "wordlist: darn");
+        assert_eq!(log[0].2, DEFAULT_BLOCKED_MESSAGE);
+        assert!(db.list_filtered_outputs(2, 10).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
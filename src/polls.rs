@@ -0,0 +1,418 @@
+//! Polls: native Telegram polls, Discord button polls, and markdown polls for web/WhatsApp
+//! (voted through the `poll` tool). Votes are collected into the DB; the scheduler closes
+//! polls when their time is up, posts the results, and runs the optional `on_close` action.
+
+use std::sync::Arc;
+
+use serde_json::json;
+use teloxide::prelude::*;
+use teloxide::types::MessageId;
+use tracing::{error, info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::config::Config;
+use crate::db::{call_blocking, Database, Poll};
+use crate::error::MicroClawError;
+use crate::telegram::{AgentRequestContext, AppState};
+
+pub const MAX_POLL_OPTIONS: usize = 10;
+/// Discord button custom_id prefix: `poll:<poll_id>:<option_index>`.
+pub const DISCORD_POLL_PREFIX: &str = "poll:";
+
+const DISCORD_API: &str = "https://discord.com/api/v10";
+
+/// Per-option vote counts and voters, in option order.
+pub fn tally(poll: &Poll, votes: &[(String, usize)]) -> Vec<(usize, Vec<String>)> {
+    let mut out = vec![(0usize, Vec::new()); poll.options.len()];
+    for (voter, idx) in votes {
+        if let Some((count, voters)) = out.get_mut(*idx) {
+            *count += 1;
+            voters.push(voter.clone());
+        }
+    }
+    out
+}
+
+/// Indices of the options with the most votes (empty when nobody voted).
+pub fn winners(counts: &[(usize, Vec<String>)]) -> Vec<usize> {
+    let max = counts.iter().map(|(c, _)| *c).max().unwrap_or(0);
+    if max == 0 {
+        return Vec::new();
+    }
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, (c, _))| *c == max)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Markdown poll for channels without native polls (web, WhatsApp).
+pub fn format_poll_message(poll: &Poll) -> String {
+    let mut out = format!("📊 **{}**\n", poll.question);
+    for (i, option) in poll.options.iter().enumerate() {
+        out.push_str(&format!("{}. {option}\n", i + 1));
+    }
+    out.push_str(if poll.multiple_answers {
+        "\nReply with the numbers of your choices to vote."
+    } else {
+        "\nReply with the number of your choice to vote."
+    });
+    out
+}
+
+pub fn format_results(poll: &Poll, votes: &[(String, usize)]) -> String {
+    let counts = tally(poll, votes);
+    let total_voters = {
+        let mut voters: Vec<&str> = votes.iter().map(|(v, _)| v.as_str()).collect();
+        voters.sort();
+        voters.dedup();
+        voters.len()
+    };
+    let mut out = format!("📊 {}\n", poll.question);
+    for (option, (count, voters)) in poll.options.iter().zip(&counts) {
+        out.push_str(&format!("- {option}: {count}"));
+        if !voters.is_empty() {
+            out.push_str(&format!(" ({})", voters.join(", ")));
+        }
+        out.push('\n');
+    }
+    let winning = winners(&counts);
+    match winning.as_slice() {
+        [] => out.push_str("No votes."),
+        [i] => out.push_str(&format!(
+            "Winner: {} ({total_voters} voters)",
+            poll.options[*i]
+        )),
+        tied => out.push_str(&format!(
+            "Tie: {} ({total_voters} voters)",
+            tied.iter()
+                .map(|i| poll.options[*i].as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+    out
+}
+
+/// Record a single-option vote (Discord buttons, poll tool). Single-answer polls replace the
+/// voter's choice; multiple-answer polls toggle the option. Returns a confirmation message.
+pub async fn record_vote(
+    db: Arc<Database>,
+    poll_id: &str,
+    voter: &str,
+    option_index: usize,
+) -> Result<String, String> {
+    let poll_id = poll_id.to_string();
+    let voter = voter.to_string();
+    call_blocking(db, move |d| {
+        let Some(poll) = d.get_poll(&poll_id)? else {
+            return Ok(Err("Poll not found".to_string()));
+        };
+        if poll.status != "open" {
+            return Ok(Err("This poll is closed".to_string()));
+        }
+        let Some(option) = poll.options.get(option_index).cloned() else {
+            return Ok(Err(format!(
+                "Invalid option; choose 1-{}",
+                poll.options.len()
+            )));
+        };
+        let mut mine: Vec<usize> = d
+            .get_poll_votes(&poll_id)?
+            .into_iter()
+            .filter(|(v, _)| *v == voter)
+            .map(|(_, i)| i)
+            .collect();
+        let message = if !poll.multiple_answers {
+            mine = vec![option_index];
+            format!("Voted for {option}.")
+        } else if let Some(pos) = mine.iter().position(|i| *i == option_index) {
+            mine.remove(pos);
+            format!("Removed your vote for {option}.")
+        } else {
+            mine.push(option_index);
+            format!("Voted for {option}.")
+        };
+        d.set_poll_votes(&poll_id, &voter, &mine)?;
+        Ok(Ok(message))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// POST a message to a Discord channel through the REST API; returns the created message.
+pub async fn post_discord_message(
+    http: &reqwest::Client,
+    token: &str,
+    channel_id: i64,
+    payload: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let resp = http
+        .post(format!("{DISCORD_API}/channels/{channel_id}/messages"))
+        .header(reqwest::header::AUTHORIZATION, format!("Bot {token}"))
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send Discord message: {e}"))?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!(
+            "Failed to send Discord message: HTTP {status} {}",
+            body.to_string().chars().take(300).collect::<String>()
+        ));
+    }
+    Ok(body)
+}
+
+/// Discord message payload with one button per option (5 per row).
+pub fn discord_poll_payload(poll: &Poll) -> serde_json::Value {
+    let rows: Vec<serde_json::Value> = poll
+        .options
+        .chunks(5)
+        .enumerate()
+        .map(|(row, options)| {
+            let buttons: Vec<serde_json::Value> = options
+                .iter()
+                .enumerate()
+                .map(|(i, option)| {
+                    let idx = row * 5 + i;
+                    json!({
+                        "type": 2,
+                        "style": 1,
+                        "label": option.chars().take(80).collect::<String>(),
+                        "custom_id": format!("{DISCORD_POLL_PREFIX}{}:{idx}", poll.id),
+                    })
+                })
+                .collect();
+            json!({ "type": 1, "components": buttons })
+        })
+        .collect();
+    let hint = if poll.multiple_answers {
+        "Click to vote (multiple choices allowed; click again to remove)."
+    } else {
+        "Click to vote."
+    };
+    json!({
+        "content": format!("📊 **{}**\n{hint}", poll.question),
+        "components": rows,
+    })
+}
+
+/// Post a text message to a chat on its own channel and store it as a bot message.
+pub async fn post_text(
+    bot: &Bot,
+    db: Arc<Database>,
+    config: &Config,
+    chat_id: i64,
+    persona_id: i64,
+    text: &str,
+) -> Result<(), String> {
+    deliver_and_store_bot_message(bot, db, &config.bot_username, chat_id, persona_id, text).await
+}
+
+async fn post_to_chat(state: &AppState, chat_id: i64, persona_id: i64, text: &str) {
+    if let Err(e) = post_text(
+        &state.bot,
+        state.db.clone(),
+        &state.config,
+        chat_id,
+        persona_id,
+        text,
+    )
+    .await
+    {
+        warn!("Polls: failed to post to chat {chat_id}: {e}");
+    }
+}
+
+/// Close a poll: stop the Telegram poll, post the results, and run the on_close action.
+pub async fn close_and_report(state: &AppState, poll: Poll) -> Result<(), MicroClawError> {
+    let poll_id = poll.id.clone();
+    if !call_blocking(state.db.clone(), move |d| d.close_poll(&poll_id)).await? {
+        return Ok(());
+    }
+    info!("Polls: closing poll {} in chat {}", poll.id, poll.chat_id);
+    let chat_id = poll.chat_id;
+
+    if poll.external_poll_id.is_some() {
+        if let Some(mid) = poll
+            .message_id
+            .as_deref()
+            .and_then(|m| m.parse::<i32>().ok())
+        {
+            if let Err(e) = state.bot.stop_poll(ChatId(chat_id), MessageId(mid)).await {
+                warn!("Polls: failed to stop Telegram poll {}: {e}", poll.id);
+            }
+        }
+    }
+
+    let poll_id = poll.id.clone();
+    let votes = call_blocking(state.db.clone(), move |d| d.get_poll_votes(&poll_id)).await?;
+    let results = format_results(&poll, &votes);
+    let persona_id = call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .unwrap_or(0);
+    post_to_chat(
+        state,
+        chat_id,
+        persona_id,
+        &format!("Poll closed.\n{results}"),
+    )
+    .await;
+
+    let Some(action) = poll.on_close.as_deref().filter(|a| !a.trim().is_empty()) else {
+        return Ok(());
+    };
+    if persona_id == 0 {
+        return Ok(());
+    }
+    let chat_type = call_blocking(state.db.clone(), move |d| d.get_chat_type(chat_id))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let channel = match chat_type.as_str() {
+        "discord" => "discord",
+        "whatsapp" => "whatsapp",
+        "web" => "web",
+        _ => "telegram",
+    };
+    let prompt = format!(
+        "[poll closed] The poll \"{}\" has closed.\n\n{results}\n\nWhen the poll was created you were asked to do this once it closed: {action}",
+        poll.question
+    );
+    match crate::telegram::process_with_agent(
+        state,
+        AgentRequestContext {
+            caller_channel: channel,
+            chat_id,
+            chat_type: "private",
+            persona_id,
+        },
+        Some(&prompt),
+        None,
+    )
+    .await
+    {
+        Ok(response) if !response.is_empty() => {
+            post_to_chat(state, chat_id, persona_id, &response).await;
+        }
+        Ok(_) => {}
+        Err(e) => error!("Polls: on_close action for {} failed: {e}", poll.id),
+    }
+    Ok(())
+}
+
+/// Close every poll whose time is up (called from the scheduler loop).
+pub async fn close_due_polls(state: &Arc<AppState>) {
+    let now = chrono::Utc::now().to_rfc3339();
+    let polls = match call_blocking(state.db.clone(), move |d| d.get_due_polls(&now)).await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("Polls: failed to query due polls: {e}");
+            return;
+        }
+    };
+    for poll in polls {
+        let id = poll.id.clone();
+        if let Err(e) = close_and_report(state, poll).await {
+            error!("Polls: failed to close poll {id}: {e}");
+        }
+    }
+}
+
+/// Record a Telegram poll answer (the full choice list; empty retracts the vote).
+pub async fn record_telegram_answer(
+    db: Arc<Database>,
+    telegram_poll_id: &str,
+    voter: &str,
+    option_ids: Vec<usize>,
+) -> Result<(), MicroClawError> {
+    let telegram_poll_id = telegram_poll_id.to_string();
+    let voter = voter.to_string();
+    call_blocking(db, move |d| {
+        let Some(poll) = d.get_poll_by_external_id(&telegram_poll_id)? else {
+            return Ok(());
+        };
+        if poll.status != "open" {
+            return Ok(());
+        }
+        d.set_poll_votes(&poll.id, &voter, &option_ids)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll(multiple: bool) -> Poll {
+        Poll {
+            id: "p1".into(),
+            chat_id: 1,
+            question: "Pizza?".into(),
+            options: vec!["Margherita".into(), "Pepperoni".into(), "Veggie".into()],
+            multiple_answers: multiple,
+            message_id: None,
+            external_poll_id: None,
+            closes_at: None,
+            on_close: None,
+            status: "open".into(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_format_results_winner_and_tie() {
+        let p = poll(false);
+        let votes = vec![
+            ("alice".to_string(), 1),
+            ("bob".to_string(), 1),
+            ("carol".to_string(), 0),
+        ];
+        let out = format_results(&p, &votes);
+        assert!(out.contains("- Pepperoni: 2 (alice, bob)"));
+        assert!(out.contains("Winner: Pepperoni (3 voters)"));
+
+        let votes = vec![("alice".to_string(), 0), ("bob".to_string(), 2)];
+        assert!(format_results(&p, &votes).contains("Tie: Margherita, Veggie"));
+        assert!(format_results(&p, &[]).ends_with("No votes."));
+    }
+
+    #[test]
+    fn test_discord_payload_rows() {
+        let mut p = poll(false);
+        p.options = (1..=7).map(|i| format!("opt{i}")).collect();
+        let payload = discord_poll_payload(&p);
+        let rows = payload["components"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["components"][1]["custom_id"], "poll:p1:6");
+    }
+
+    #[tokio::test]
+    async fn test_record_vote_single_and_multiple() {
+        let dir = std::env::temp_dir().join(format!("microclaw_polls_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.create_poll(&poll(false)).unwrap();
+        record_vote(db.clone(), "p1", "alice", 0).await.unwrap();
+        record_vote(db.clone(), "p1", "alice", 2).await.unwrap();
+        assert_eq!(db.get_poll_votes("p1").unwrap(), vec![("alice".into(), 2)]);
+        assert!(record_vote(db.clone(), "p1", "alice", 9).await.is_err());
+
+        let mut multi = poll(true);
+        multi.id = "p2".into();
+        db.create_poll(&multi).unwrap();
+        record_vote(db.clone(), "p2", "bob", 0).await.unwrap();
+        record_vote(db.clone(), "p2", "bob", 1).await.unwrap();
+        let msg = record_vote(db.clone(), "p2", "bob", 0).await.unwrap();
+        assert!(msg.starts_with("Removed"));
+        assert_eq!(db.get_poll_votes("p2").unwrap(), vec![("bob".into(), 1)]);
+
+        assert!(db.close_poll("p2").unwrap());
+        assert!(!db.close_poll("p2").unwrap());
+        assert!(record_vote(db, "p2", "bob", 0).await.is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
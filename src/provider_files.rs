@@ -0,0 +1,478 @@
+//! Provider file passthrough: documents users send (`[document] saved_path=...` messages) are
+//! uploaded to the LLM provider's Files API and attached to the turn as a
+//! [`ContentBlock::ProviderFile`], so the model reads them natively instead of pulling them into
+//! the context with `read_file`. Anthropic takes PDFs and text files as documents and, with
+//! `code_interpreter`, data files in a code execution container; OpenAI takes PDFs. Uploads are
+//! recorded in `provider_files` and deleted from the provider after `retention_hours`.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::claude::{ContentBlock, Message, MessageContent};
+use crate::config::{Config, ProviderFilesConfig};
+use crate::db::{call_blocking, Database};
+use crate::telegram::AppState;
+
+const DOCUMENT_MARKER: &str = "[document] saved_path=";
+const ANTHROPIC_FILES_BETA: &str = "files-api-2025-04-14";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileProvider {
+    Anthropic,
+    OpenAi,
+}
+
+impl FileProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            FileProvider::Anthropic => "anthropic",
+            FileProvider::OpenAi => "openai",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "anthropic" => Some(FileProvider::Anthropic),
+            "openai" => Some(FileProvider::OpenAi),
+            _ => None,
+        }
+    }
+}
+
+/// The configured provider if it has a Files API. OpenAI-compatible servers other than
+/// api.openai.com are assumed not to.
+pub fn file_provider(config: &Config) -> Option<FileProvider> {
+    match config.llm_provider.trim().to_lowercase().as_str() {
+        "anthropic" => Some(FileProvider::Anthropic),
+        "openai"
+            if config
+                .llm_base_url
+                .as_deref()
+                .is_none_or(|u| u.contains("api.openai.com")) =>
+        {
+            Some(FileProvider::OpenAi)
+        }
+        _ => None,
+    }
+}
+
+/// Files endpoint next to the provider's chat endpoint.
+pub fn files_url(provider: FileProvider, config: &Config) -> String {
+    match provider {
+        FileProvider::Anthropic => {
+            let base = config
+                .llm_base_url
+                .as_deref()
+                .unwrap_or("https://api.anthropic.com/v1/messages")
+                .trim_end_matches('/');
+            format!("{}/files", base.strip_suffix("/messages").unwrap_or(base))
+        }
+        FileProvider::OpenAi => format!(
+            "{}/files",
+            config
+                .llm_base_url
+                .as_deref()
+                .unwrap_or("https://api.openai.com/v1")
+                .trim_end_matches('/')
+        ),
+    }
+}
+
+/// Media type of a file the provider can take, and whether it goes into the code execution
+/// container rather than being read as a document.
+pub fn file_kind(
+    provider: FileProvider,
+    path: &str,
+    code_interpreter: bool,
+) -> Option<(&'static str, bool)> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())?
+        .to_lowercase();
+    let anthropic = provider == FileProvider::Anthropic;
+    match ext.as_str() {
+        "pdf" => Some(("application/pdf", false)),
+        "txt" | "md" if anthropic => Some(("text/plain", false)),
+        "csv" if anthropic && code_interpreter => Some(("text/csv", true)),
+        "tsv" if anthropic && code_interpreter => Some(("text/tab-separated-values", true)),
+        "json" if anthropic && code_interpreter => Some(("application/json", true)),
+        "xlsx" if anthropic && code_interpreter => Some((
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            true,
+        )),
+        "xls" if anthropic && code_interpreter => Some(("application/vnd.ms-excel", true)),
+        _ => None,
+    }
+}
+
+/// Saved paths of the documents mentioned in a message.
+pub fn document_paths(text: &str) -> Vec<String> {
+    text.match_indices(DOCUMENT_MARKER)
+        .filter_map(|(i, _)| {
+            text[i + DOCUMENT_MARKER.len()..]
+                .split_whitespace()
+                .next()
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(120))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn authorize(
+    req: reqwest::RequestBuilder,
+    provider: FileProvider,
+    config: &Config,
+) -> reqwest::RequestBuilder {
+    match provider {
+        FileProvider::Anthropic => req
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("anthropic-beta", ANTHROPIC_FILES_BETA),
+        FileProvider::OpenAi => req.bearer_auth(&config.api_key),
+    }
+}
+
+async fn upload(
+    config: &Config,
+    provider: FileProvider,
+    path: &str,
+    media_type: &str,
+) -> Result<String, String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("read {path}: {e}"))?;
+    let filename = Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("document")
+        .to_string();
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name(filename)
+        .mime_str(media_type)
+        .map_err(|e| e.to_string())?;
+    let mut form = reqwest::multipart::Form::new().part("file", part);
+    if provider == FileProvider::OpenAi {
+        form = form.text("purpose", "user_data");
+    }
+    let req = http_client()?
+        .post(files_url(provider, config))
+        .multipart(form);
+    let resp = authorize(req, provider, config)
+        .send()
+        .await
+        .map_err(|e| format!("upload failed: {e}"))?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("upload returned HTTP {status}: {body}"));
+    }
+    body.get("id")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("upload response has no id: {body}"))
+}
+
+async fn delete_upload(
+    config: &Config,
+    provider: FileProvider,
+    file_id: &str,
+) -> Result<(), String> {
+    let url = format!("{}/{file_id}", files_url(provider, config));
+    let resp = authorize(http_client()?.delete(url), provider, config)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    // Already gone counts as deleted
+    if resp.status().is_success() || resp.status() == reqwest::StatusCode::NOT_FOUND {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", resp.status()))
+    }
+}
+
+/// The provider file for a saved document: an earlier upload of the same path, or a new one.
+async fn provider_file_block(
+    config: &Config,
+    files: &ProviderFilesConfig,
+    provider: FileProvider,
+    db: Arc<Database>,
+    chat_id: i64,
+    path: &str,
+) -> Option<ContentBlock> {
+    let (media_type, container) = file_kind(provider, path, files.code_interpreter)?;
+    let size = tokio::fs::metadata(path).await.ok()?.len();
+    if size < files.min_kb * 1024 || size > files.max_mb * 1024 * 1024 {
+        return None;
+    }
+    let lookup_path = path.to_string();
+    let existing = call_blocking(db.clone(), move |d| {
+        d.find_provider_file(provider.name(), &lookup_path)
+    })
+    .await
+    .ok()
+    .flatten();
+    let file_id = match existing {
+        Some(id) => id,
+        None => {
+            let id = match upload(config, provider, path, media_type).await {
+                Ok(id) => id,
+                Err(e) => {
+                    warn!("Provider file upload of {path} failed, leaving it to read_file: {e}");
+                    return None;
+                }
+            };
+            let (record_id, record_path) = (id.clone(), path.to_string());
+            if let Err(e) = call_blocking(db, move |d| {
+                d.record_provider_file(provider.name(), &record_id, chat_id, &record_path)
+            })
+            .await
+            {
+                warn!("Failed to record provider file {id}: {e}");
+            }
+            info!(
+                "Uploaded {path} to the {} Files API as {id}",
+                provider.name()
+            );
+            id
+        }
+    };
+    Some(ContentBlock::ProviderFile {
+        provider: provider.name().into(),
+        file_id,
+        filename: Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("document")
+            .to_string(),
+        media_type: media_type.into(),
+        container,
+    })
+}
+
+/// Replace provider files the current provider cannot read (another provider's, or deleted
+/// uploads) with a note, then attach the documents of the newest user messages. The message
+/// text, with its saved_path, stays after the file.
+pub async fn attach_documents(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    messages: &mut [Message],
+) {
+    let provider = config
+        .provider_files
+        .as_ref()
+        .and_then(|_| file_provider(config));
+
+    for msg in messages.iter_mut() {
+        let MessageContent::Blocks(blocks) = &mut msg.content else {
+            continue;
+        };
+        for block in blocks.iter_mut() {
+            let ContentBlock::ProviderFile {
+                provider: owner,
+                file_id,
+                filename,
+                ..
+            } = block
+            else {
+                continue;
+            };
+            let readable = match provider.filter(|p| Some(*p) == FileProvider::from_name(owner)) {
+                Some(p) => {
+                    let id = file_id.clone();
+                    call_blocking(db.clone(), move |d| d.has_provider_file(p.name(), &id))
+                        .await
+                        .unwrap_or(false)
+                }
+                None => false,
+            };
+            if !readable {
+                *block = ContentBlock::Text {
+                    text: ContentBlock::provider_file_placeholder(filename),
+                };
+            }
+        }
+    }
+
+    let (Some(files), Some(provider)) = (config.provider_files.as_ref(), provider) else {
+        return;
+    };
+    let newest_user = messages
+        .iter()
+        .rposition(|m| m.role != "user")
+        .map_or(0, |i| i + 1);
+    for msg in &mut messages[newest_user..] {
+        let MessageContent::Text(text) = &msg.content else {
+            continue;
+        };
+        let mut blocks = Vec::new();
+        for path in document_paths(text) {
+            if let Some(block) =
+                provider_file_block(config, files, provider, db.clone(), chat_id, &path).await
+            {
+                blocks.push(block);
+            }
+        }
+        if !blocks.is_empty() {
+            blocks.push(ContentBlock::Text { text: text.clone() });
+            msg.content = MessageContent::Blocks(blocks);
+        }
+    }
+}
+
+/// Delete uploads older than `retention_hours` from the provider (run by the scheduler).
+pub async fn delete_expired(state: &Arc<AppState>) {
+    let Some(files) = state.config.provider_files.as_ref() else {
+        return;
+    };
+    let cutoff =
+        (chrono::Utc::now() - chrono::Duration::hours(files.retention_hours as i64)).to_rfc3339();
+    let expired =
+        match call_blocking(state.db.clone(), move |d| d.provider_files_before(&cutoff)).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!("Failed to list expired provider files: {e}");
+                return;
+            }
+        };
+    let current = file_provider(&state.config);
+    for (owner, file_id) in expired {
+        let provider = FileProvider::from_name(&owner).filter(|p| Some(*p) == current);
+        if let Some(provider) = provider {
+            if let Err(e) = delete_upload(&state.config, provider, &file_id).await {
+                warn!("Failed to delete provider file {file_id}, retrying later: {e}");
+                continue;
+            }
+        } else {
+            // The API key for it is no longer configured; it expires on the provider's side
+            warn!("Forgetting {owner} file {file_id}: {owner} is no longer the LLM provider");
+        }
+        let _ = call_blocking(state.db.clone(), move |d| {
+            d.delete_provider_file(&owner, &file_id)
+        })
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(provider: &str, base_url: Option<&str>) -> Config {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.llm_provider = provider.into();
+        config.llm_base_url = base_url.map(str::to_string);
+        config
+    }
+
+    #[test]
+    fn test_file_provider_and_urls() {
+        let anthropic = config("anthropic", None);
+        assert_eq!(file_provider(&anthropic), Some(FileProvider::Anthropic));
+        assert_eq!(
+            files_url(FileProvider::Anthropic, &anthropic),
+            "https://api.anthropic.com/v1/files"
+        );
+        let openai = config("openai", None);
+        assert_eq!(
+            files_url(FileProvider::OpenAi, &openai),
+            "https://api.openai.com/v1/files"
+        );
+        assert_eq!(file_provider(&openai), Some(FileProvider::OpenAi));
+        assert_eq!(
+            file_provider(&config("openai", Some("http://localhost:11434/v1"))),
+            None
+        );
+        assert_eq!(file_provider(&config("google", None)), None);
+    }
+
+    #[test]
+    fn test_file_kind() {
+        assert_eq!(
+            file_kind(FileProvider::OpenAi, "/x/Report.PDF", false),
+            Some(("application/pdf", false))
+        );
+        assert_eq!(file_kind(FileProvider::OpenAi, "/x/notes.txt", false), None);
+        assert_eq!(
+            file_kind(FileProvider::Anthropic, "/x/notes.md", false),
+            Some(("text/plain", false))
+        );
+        assert_eq!(
+            file_kind(FileProvider::Anthropic, "/x/data.csv", false),
+            None
+        );
+        assert_eq!(
+            file_kind(FileProvider::Anthropic, "/x/data.csv", true),
+            Some(("text/csv", true))
+        );
+        assert_eq!(file_kind(FileProvider::Anthropic, "/x/noext", true), None);
+    }
+
+    #[test]
+    fn test_document_paths() {
+        assert_eq!(
+            document_paths("[document] saved_path=/w/lease.pdf please summarize"),
+            vec!["/w/lease.pdf".to_string()]
+        );
+        assert_eq!(
+            document_paths(
+                "[Alice]: [document] saved_path=/w/a.pdf\n[document] saved_path=/w/b.csv"
+            ),
+            vec!["/w/a.pdf".to_string(), "/w/b.csv".to_string()]
+        );
+        assert!(document_paths("no documents here").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_attach_documents_replaces_unreadable_files() {
+        let dir = std::env::temp_dir().join(format!("mc_pfiles_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.record_provider_file("anthropic", "file_ok", 1, "/w/a.pdf")
+            .unwrap();
+        let file = |id: &str| ContentBlock::ProviderFile {
+            provider: "anthropic".into(),
+            file_id: id.into(),
+            filename: "a.pdf".into(),
+            media_type: "application/pdf".into(),
+            container: false,
+        };
+        let mut messages = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Blocks(vec![file("file_ok"), file("file_gone")]),
+        }];
+        let mut cfg = config("anthropic", None);
+        cfg.provider_files = Some(ProviderFilesConfig {
+            min_kb: 0,
+            max_mb: 32,
+            code_interpreter: false,
+            retention_hours: 24,
+        });
+        attach_documents(&cfg, db.clone(), 1, &mut messages).await;
+        let MessageContent::Blocks(blocks) = &messages[0].content else {
+            panic!("expected blocks");
+        };
+        assert!(
+            matches!(&blocks[0], ContentBlock::ProviderFile { file_id, .. } if file_id == "file_ok")
+        );
+        assert!(matches!(&blocks[1], ContentBlock::Text { .. }));
+
+        // Another provider cannot read Anthropic uploads
+        attach_documents(&config("openai", None), db, 1, &mut messages).await;
+        let MessageContent::Blocks(blocks) = &messages[0].content else {
+            panic!("expected blocks");
+        };
+        assert!(matches!(&blocks[0], ContentBlock::Text { .. }));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
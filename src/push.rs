@@ -0,0 +1,376 @@
+//! Push notifications: chats that turn on `/push` get their proactive messages (feed alerts,
+//! scheduled task results, digests) on ntfy or Gotify instead of in the chat. Each push links
+//! back to the chat's session in the web UI. The message is still stored in the conversation,
+//! and the chat gets it when the push service fails.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use tracing::warn;
+
+use crate::channel::{deliver_and_store_bot_message, store_bot_message};
+use crate::config::{Config, PushConfig, PushProvider};
+use crate::db::{call_blocking, Database};
+use crate::telegram::AppState;
+use crate::text::truncate_chars;
+
+/// Chat setting holding the preferred delivery for proactive messages ("push" or unset).
+const DELIVERY_SETTING: &str = "delivery";
+/// Chat setting with the chat's own ntfy topic.
+const TOPIC_SETTING: &str = "push_topic";
+
+const DEFAULT_NTFY_URL: &str = "https://ntfy.sh";
+/// ntfy rejects larger message bodies (it would attach them as a file instead).
+const MAX_BODY_CHARS: usize = 4000;
+
+/// One notification, before it is shaped for a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// Web UI link to the chat, shown as an "Open chat" action.
+    pub open_url: Option<String>,
+}
+
+/// Where a chat's pushes go: ntfy needs a topic, Gotify routes by its application token.
+fn ntfy_topic(push: &PushConfig, chat_topic: Option<&str>) -> Option<String> {
+    chat_topic
+        .or(push.topic.as_deref())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+/// URL and JSON body of the publish request.
+pub fn build_request(
+    push: &PushConfig,
+    topic: Option<&str>,
+    msg: &PushMessage,
+) -> Result<(String, serde_json::Value), String> {
+    // One character is left for the ellipsis
+    let body = truncate_chars(&msg.body, MAX_BODY_CHARS - 1);
+    match push.provider {
+        PushProvider::Ntfy => {
+            let topic = ntfy_topic(push, topic)
+                .ok_or("No ntfy topic: set push.topic or use /push on <topic>")?;
+            let url = push
+                .url
+                .as_deref()
+                .unwrap_or(DEFAULT_NTFY_URL)
+                .trim_end_matches('/')
+                .to_string();
+            let mut payload = json!({
+                "topic": topic,
+                "title": msg.title,
+                "message": body,
+                "markdown": true,
+            });
+            if let Some(open) = &msg.open_url {
+                payload["click"] = json!(open);
+                payload["actions"] = json!([{"action": "view", "label": "Open chat", "url": open}]);
+            }
+            Ok((url, payload))
+        }
+        PushProvider::Gotify => {
+            let base = push.url.as_deref().ok_or("push.url is not set")?;
+            let mut payload = json!({
+                "title": msg.title,
+                "message": body,
+                "priority": 5,
+                "extras": {"client::display": {"contentType": "text/markdown"}},
+            });
+            // Gotify has no action buttons; tapping the notification opens the link
+            if let Some(open) = &msg.open_url {
+                payload["extras"]["client::notification"] = json!({"click": {"url": open}});
+            }
+            Ok((format!("{}/message", base.trim_end_matches('/')), payload))
+        }
+    }
+}
+
+/// Web UI address of a chat's session: web chats by their session key, others as `chat:<id>`.
+pub fn session_url(base: &str, chat_id: i64, chat_type: &str, chat_title: Option<&str>) -> String {
+    let key = match chat_title.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(title) if chat_type == "web" => title.to_string(),
+        _ => format!("chat:{chat_id}"),
+    };
+    format!(
+        "{}/?session={}",
+        base.trim_end_matches('/'),
+        urlencoding::encode(&key)
+    )
+}
+
+async fn open_chat_url(config: &Config, db: Arc<Database>, chat_id: i64) -> Option<String> {
+    let base = config
+        .push
+        .as_ref()
+        .and_then(|p| p.web_url.clone())
+        .filter(|u| !u.trim().is_empty())
+        .or_else(|| crate::social_oauth::oauth_base_url(config))?;
+    let (chat_type, title) = call_blocking(db, move |d| {
+        Ok((d.get_chat_type(chat_id)?, d.get_chat_title(chat_id)?))
+    })
+    .await
+    .ok()?;
+    Some(session_url(
+        &base,
+        chat_id,
+        chat_type.as_deref().unwrap_or_default(),
+        title.as_deref(),
+    ))
+}
+
+/// Publish `msg` to the configured service.
+pub async fn send_push(
+    push: &PushConfig,
+    topic: Option<&str>,
+    msg: &PushMessage,
+) -> Result<(), String> {
+    let (url, payload) = build_request(push, topic, msg)?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut req = client.post(&url).json(&payload);
+    if let Some(token) = push.token.as_deref() {
+        req = match push.provider {
+            PushProvider::Ntfy => req.bearer_auth(token),
+            PushProvider::Gotify => req.header("X-Gotify-Key", token),
+        };
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| format!("Push request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("Push service returned HTTP {}", resp.status()));
+    }
+    Ok(())
+}
+
+/// The chat's own ntfy topic when it prefers push, `Some(None)` for the default topic, or
+/// `None` when proactive messages go to the chat.
+async fn push_preference(db: Arc<Database>, chat_id: i64) -> Option<Option<String>> {
+    let (delivery, topic) = call_blocking(db, move |d| {
+        Ok((
+            d.get_chat_setting(chat_id, DELIVERY_SETTING)?,
+            d.get_chat_setting(chat_id, TOPIC_SETTING)?,
+        ))
+    })
+    .await
+    .ok()?;
+    (delivery.as_deref() == Some("push")).then_some(topic)
+}
+
+/// Deliver a proactive message: as a push when push is configured and the chat prefers it
+/// (stored in the conversation so the web UI shows it), otherwise to the chat.
+pub async fn deliver_proactive(
+    state: &AppState,
+    chat_id: i64,
+    persona_id: i64,
+    text: &str,
+) -> Result<(), String> {
+    if let Some(push) = &state.config.push {
+        if let Some(topic) = push_preference(state.db.clone(), chat_id).await {
+            let msg = PushMessage {
+                title: state.config.bot_username.clone(),
+                body: text.to_string(),
+                open_url: open_chat_url(&state.config, state.db.clone(), chat_id).await,
+            };
+            match send_push(push, topic.as_deref(), &msg).await {
+                Ok(()) => {
+                    return store_bot_message(
+                        state.db.clone(),
+                        &state.config.bot_username,
+                        chat_id,
+                        persona_id,
+                        text.to_string(),
+                    )
+                    .await
+                }
+                Err(e) => warn!("Push for chat {chat_id} failed, sending to the chat: {e}"),
+            }
+        }
+    }
+    deliver_and_store_bot_message(
+        &state.bot,
+        state.db.clone(),
+        &state.config.bot_username,
+        chat_id,
+        persona_id,
+        text,
+    )
+    .await
+}
+
+/// Handle `/push [on [topic]|off]`: choose push or chat delivery of proactive messages.
+pub async fn handle_push_command(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    text: &str,
+) -> String {
+    let Some(push) = config.push.as_ref() else {
+        return "Push notifications are not configured (see `push` in the config).".into();
+    };
+    let mut args = text.split_whitespace().skip(1);
+    let arg = args.next().unwrap_or("").to_lowercase();
+    let topic = args.next().map(str::to_string);
+    match arg.as_str() {
+        "" => match push_preference(db, chat_id).await {
+            Some(topic) => {
+                let target = match push.provider {
+                    PushProvider::Ntfy => ntfy_topic(push, topic.as_deref())
+                        .map(|t| format!("ntfy topic '{t}'"))
+                        .unwrap_or_else(|| "ntfy (no topic set!)".into()),
+                    PushProvider::Gotify => "Gotify".into(),
+                };
+                format!("Alerts and scheduled results go to {target}. Use /push off to get them here.")
+            }
+            None => "Alerts and scheduled results are sent to this chat. Use /push on [topic] to get them as push notifications.".into(),
+        },
+        "on" => {
+            if push.provider == PushProvider::Ntfy
+                && ntfy_topic(push, topic.as_deref()).is_none()
+            {
+                return "Usage: /push on <ntfy topic> (no default topic is configured)".into();
+            }
+            let stored_topic = topic.clone();
+            let result = call_blocking(db, move |d| {
+                d.set_chat_setting(chat_id, DELIVERY_SETTING, Some("push"))?;
+                if stored_topic.is_some() {
+                    d.set_chat_setting(chat_id, TOPIC_SETTING, stored_topic.as_deref())?;
+                }
+                Ok(())
+            })
+            .await;
+            match result {
+                Ok(()) => "Alerts and scheduled results for this chat will arrive as push notifications.".into(),
+                Err(e) => format!("Failed to update push setting: {e}"),
+            }
+        }
+        "off" => {
+            let result = call_blocking(db, move |d| {
+                d.set_chat_setting(chat_id, DELIVERY_SETTING, None)?;
+                d.set_chat_setting(chat_id, TOPIC_SETTING, None)
+            })
+            .await;
+            match result {
+                Ok(()) => "Alerts and scheduled results will be sent to this chat again.".into(),
+                Err(e) => format!("Failed to update push setting: {e}"),
+            }
+        }
+        _ => "Usage: /push [on [topic]|off]".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_config(provider: PushProvider) -> PushConfig {
+        PushConfig {
+            provider,
+            url: None,
+            topic: Some("home-alerts".into()),
+            token: Some("tk".into()),
+            web_url: None,
+        }
+    }
+
+    #[test]
+    fn test_build_request_per_provider() {
+        let msg = PushMessage {
+            title: "HomeBot".into(),
+            body: "Feed: new post".into(),
+            open_url: Some("https://bot.example.com/?session=chat%3A42".into()),
+        };
+        let ntfy = push_config(PushProvider::Ntfy);
+        let (url, body) = build_request(&ntfy, None, &msg).unwrap();
+        assert_eq!(url, "https://ntfy.sh");
+        assert_eq!(body["topic"], "home-alerts");
+        assert_eq!(body["actions"][0]["action"], "view");
+        assert_eq!(body["actions"][0]["url"], body["click"]);
+        let (_, body) = build_request(&ntfy, Some("alice"), &msg).unwrap();
+        assert_eq!(body["topic"], "alice");
+        let no_topic = PushConfig {
+            topic: None,
+            ..ntfy.clone()
+        };
+        assert!(build_request(&no_topic, None, &msg).is_err());
+
+        let gotify = PushConfig {
+            url: Some("https://gotify.example.com/".into()),
+            ..push_config(PushProvider::Gotify)
+        };
+        let (url, body) = build_request(&gotify, None, &msg).unwrap();
+        assert_eq!(url, "https://gotify.example.com/message");
+        assert_eq!(
+            body["extras"]["client::notification"]["click"]["url"],
+            "https://bot.example.com/?session=chat%3A42"
+        );
+        assert!(body.get("topic").is_none());
+
+        let long = PushMessage {
+            body: "x".repeat(MAX_BODY_CHARS + 10),
+            open_url: None,
+            ..msg
+        };
+        let (_, body) = build_request(&ntfy, None, &long).unwrap();
+        assert_eq!(
+            body["message"].as_str().unwrap().chars().count(),
+            MAX_BODY_CHARS
+        );
+        assert!(body.get("actions").is_none());
+    }
+
+    #[test]
+    fn test_session_url() {
+        assert_eq!(
+            session_url(
+                "https://bot.example.com/",
+                42,
+                "telegram_private",
+                Some("Alice")
+            ),
+            "https://bot.example.com/?session=chat%3A42"
+        );
+        assert_eq!(
+            session_url("http://127.0.0.1:10961", 7, "web", Some("kitchen tablet")),
+            "http://127.0.0.1:10961/?session=kitchen%20tablet"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_command_stores_preference() {
+        let dir = std::env::temp_dir().join(format!("mc_push_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        assert!(handle_push_command(&config, db.clone(), 1, "/push on")
+            .await
+            .contains("not configured"));
+
+        config.push = Some(PushConfig {
+            topic: None,
+            ..push_config(PushProvider::Ntfy)
+        });
+        assert!(handle_push_command(&config, db.clone(), 1, "/push on")
+            .await
+            .starts_with("Usage"));
+        handle_push_command(&config, db.clone(), 1, "/push on alice-phone").await;
+        assert_eq!(
+            push_preference(db.clone(), 1).await,
+            Some(Some("alice-phone".into()))
+        );
+        assert!(handle_push_command(&config, db.clone(), 1, "/push")
+            .await
+            .contains("'alice-phone'"));
+        handle_push_command(&config, db.clone(), 1, "/push off").await;
+        assert_eq!(push_preference(db.clone(), 1).await, None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
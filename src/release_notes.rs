@@ -0,0 +1,196 @@
+//! Release notes on upgrade: on the first start of a new version, the sections of the embedded
+//! `CHANGELOG.md` newer than the version a control chat last saw are posted there, config changes
+//! listed separately. The announced version is kept per chat in `chat_settings`, so each chat is
+//! told once (and again if delivery failed).
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::db::call_blocking;
+use crate::telegram::AppState;
+
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+
+/// `chat_settings` key holding the last version announced to the chat.
+pub const SEEN_VERSION_SETTING: &str = "release_notes_version";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    pub changes: Vec<String>,
+    pub config_changes: Vec<String>,
+}
+
+/// Releases in a changelog, in file order (newest first).
+pub fn parse_changelog(text: &str) -> Vec<Release> {
+    let mut releases: Vec<Release> = Vec::new();
+    let mut in_config = false;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if let Some(version) = line.strip_prefix("## ") {
+            releases.push(Release {
+                version: version.trim().trim_start_matches('v').to_string(),
+                changes: Vec::new(),
+                config_changes: Vec::new(),
+            });
+            in_config = false;
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            in_config = heading.trim().eq_ignore_ascii_case("config");
+        } else if let (Some(item), Some(release)) = (line.strip_prefix("- "), releases.last_mut()) {
+            let item = item.trim().to_string();
+            if in_config {
+                release.config_changes.push(item);
+            } else {
+                release.changes.push(item);
+            }
+        }
+    }
+    releases
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.split(['.', '-'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+/// Releases after `seen` up to and including `current`. Without a recorded version only the
+/// current release is returned, so an existing install is not flooded with history.
+pub fn releases_since<'a>(
+    releases: &'a [Release],
+    seen: Option<&str>,
+    current: &str,
+) -> Vec<&'a Release> {
+    releases
+        .iter()
+        .filter(|r| compare_versions(&r.version, current) != Ordering::Greater)
+        .filter(|r| match seen {
+            Some(seen) => compare_versions(&r.version, seen) == Ordering::Greater,
+            None => compare_versions(&r.version, current) == Ordering::Equal,
+        })
+        .collect()
+}
+
+/// The upgrade message, or `None` when there is nothing new to report.
+pub fn format_release_notes(
+    bot_username: &str,
+    current: &str,
+    releases: &[&Release],
+) -> Option<String> {
+    if releases.is_empty() {
+        return None;
+    }
+    let mut out = format!("🆕 {bot_username} was updated to v{current}.");
+    for release in releases {
+        if releases.len() > 1 {
+            out.push_str(&format!("\n\nv{}", release.version));
+        }
+        if !release.changes.is_empty() {
+            out.push_str("\n\nWhat's new:");
+            for change in &release.changes {
+                out.push_str(&format!("\n• {change}"));
+            }
+        }
+        if !release.config_changes.is_empty() {
+            out.push_str("\n\nConfig changes:");
+            for change in &release.config_changes {
+                out.push_str(&format!("\n• {change}"));
+            }
+        }
+    }
+    Some(out)
+}
+
+/// Post the release notes of this version to every control chat that has not seen them yet.
+pub async fn announce_upgrade(state: &Arc<AppState>) {
+    if !state.config.announce_release_notes {
+        return;
+    }
+    let current = env!("CARGO_PKG_VERSION");
+    let releases = parse_changelog(CHANGELOG);
+    for &chat_id in &state.config.control_chat_ids {
+        let seen = call_blocking(state.db.clone(), move |d| {
+            d.get_chat_setting(chat_id, SEEN_VERSION_SETTING)
+        })
+        .await
+        .unwrap_or(None);
+        if seen.as_deref() == Some(current) {
+            continue;
+        }
+        let new_releases = releases_since(&releases, seen.as_deref(), current);
+        if let Some(text) = format_release_notes(&state.config.bot_username, current, &new_releases)
+        {
+            let persona_id =
+                call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                    .await
+                    .unwrap_or(0);
+            if let Err(e) = deliver_and_store_bot_message(
+                &state.bot,
+                state.db.clone(),
+                &state.config.bot_username,
+                chat_id,
+                persona_id,
+                &text,
+            )
+            .await
+            {
+                warn!("Release notes: failed to send to chat {chat_id}: {e}");
+                continue;
+            }
+            info!("Posted release notes for v{current} to chat {chat_id}");
+        }
+        if let Err(e) = call_blocking(state.db.clone(), move |d| {
+            d.set_chat_setting(chat_id, SEEN_VERSION_SETTING, Some(current))
+        })
+        .await
+        {
+            warn!("Release notes: failed to record the version for chat {chat_id}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Changelog\n\nIntro text.\n\n## 0.1.0\n\n- Faster replies\n\n### Config\n\n- New `foo` option\n\n## 0.0.9\n\n- Older change\n\n## 0.0.8\n\n- Oldest change\n";
+
+    #[test]
+    fn test_release_notes_since_seen_version() {
+        let releases = parse_changelog(SAMPLE);
+        assert_eq!(releases.len(), 3);
+        assert_eq!(releases[0].changes, vec!["Faster replies"]);
+        assert_eq!(releases[0].config_changes, vec!["New `foo` option"]);
+
+        let since: Vec<&str> = releases_since(&releases, Some("0.0.8"), "0.1.0")
+            .iter()
+            .map(|r| r.version.as_str())
+            .collect();
+        assert_eq!(since, vec!["0.1.0", "0.0.9"]);
+        assert_eq!(releases_since(&releases, None, "0.1.0").len(), 1);
+        assert!(releases_since(&releases, Some("0.1.0"), "0.1.0").is_empty());
+        // A newer section than the running binary is not announced yet
+        assert_eq!(releases_since(&releases, Some("0.0.8"), "0.0.9").len(), 1);
+
+        let text = format_release_notes("bot", "0.1.0", &releases_since(&releases, None, "0.1.0"))
+            .unwrap();
+        assert!(text.starts_with("🆕 bot was updated to v0.1.0."));
+        assert!(text.contains("What's new:\n• Faster replies"));
+        assert!(text.contains("Config changes:\n• New `foo` option"));
+        assert!(format_release_notes("bot", "0.1.0", &[]).is_none());
+    }
+
+    #[test]
+    fn test_embedded_changelog_covers_current_version() {
+        let releases = parse_changelog(CHANGELOG);
+        assert!(releases
+            .iter()
+            .any(|r| r.version == env!("CARGO_PKG_VERSION")));
+    }
+}
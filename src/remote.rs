@@ -0,0 +1,377 @@
+//! `microclaw remote`: client mode for talking to a primary instance (e.g. the home server) from
+//! another machine. It never opens the database: the local web UI is served with every API call
+//! proxied to the primary's web API (adding its `web_auth_token`), and the CLI subcommands call
+//! the same API directly.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde_json::{json, Value};
+
+const DEFAULT_PORT: u16 = 10962;
+const DEFAULT_HOST: &str = "127.0.0.1";
+/// Largest request body forwarded to the primary.
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const USAGE: &str = "Usage: microclaw remote --url <primary web URL> [--token <web_auth_token>] [COMMAND]\n\nCommands:\n    serve [--host 127.0.0.1] [--port 10962]   Serve the web UI locally, proxying the API to the primary (default)\n    send <message> [--session <key>]         Send a message and print the reply\n    sessions                                 List sessions\n    history [--session <key>] [--limit <n>]  Print a session's messages\n    health                                   Check the primary is reachable\n\nThe URL and token can also be set with MICROCLAW_REMOTE_URL and MICROCLAW_REMOTE_TOKEN.";
+
+/// Flags that take a value; everything else is positional.
+const VALUE_FLAGS: &[&str] = &[
+    "--url",
+    "--token",
+    "--host",
+    "--port",
+    "--session",
+    "--limit",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RemoteArgs {
+    pub url: Option<String>,
+    pub token: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub session: Option<String>,
+    pub limit: Option<usize>,
+    pub positional: Vec<String>,
+}
+
+pub fn parse_args(args: &[String]) -> Result<RemoteArgs, String> {
+    let mut parsed = RemoteArgs::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !VALUE_FLAGS.contains(&arg.as_str()) {
+            parsed.positional.push(arg.clone());
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{arg} needs a value"))?
+            .clone();
+        match arg.as_str() {
+            "--url" => parsed.url = Some(value),
+            "--token" => parsed.token = Some(value),
+            "--host" => parsed.host = Some(value),
+            "--port" => {
+                parsed.port = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid --port: {value}"))?,
+                )
+            }
+            "--session" => parsed.session = Some(value),
+            _ => {
+                parsed.limit = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid --limit: {value}"))?,
+                )
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// HTTP client for a primary instance's web API.
+pub struct RemoteClient {
+    base_url: String,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl RemoteClient {
+    pub fn new(base_url: &str, token: Option<String>) -> Result<Self, String> {
+        let base_url = base_url.trim().trim_end_matches('/').to_string();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(format!(
+                "remote URL must start with http:// or https://: {base_url}"
+            ));
+        }
+        // No overall timeout: agent replies and event streams can take minutes.
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(RemoteClient {
+            base_url,
+            token: token.filter(|t| !t.trim().is_empty()),
+            http,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path_and_query: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{path_and_query}", self.base_url));
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn json(&self, builder: reqwest::RequestBuilder) -> Result<Value, String> {
+        let resp = builder
+            .send()
+            .await
+            .map_err(|e| format!("request to {} failed: {e}", self.base_url))?;
+        let status = resp.status();
+        let body = resp.text().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("{status}: {}", body.trim()));
+        }
+        serde_json::from_str(&body).map_err(|e| format!("invalid JSON from primary: {e}"))
+    }
+
+    pub async fn get_json(&self, path_and_query: &str) -> Result<Value, String> {
+        self.json(self.request(reqwest::Method::GET, path_and_query))
+            .await
+    }
+
+    pub async fn post_json(&self, path: &str, body: &Value) -> Result<Value, String> {
+        self.json(self.request(reqwest::Method::POST, path).json(body))
+            .await
+    }
+
+    /// Forward a web UI request to the primary and stream its response back.
+    async fn forward(&self, req: Request) -> Result<Response, String> {
+        let (parts, body) = req.into_parts();
+        let path_and_query = parts
+            .uri
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/");
+        let body = axum::body::to_bytes(body, MAX_BODY_BYTES)
+            .await
+            .map_err(|e| format!("request body: {e}"))?;
+        let mut builder = self.request(parts.method.clone(), path_and_query);
+        for name in ["content-type", "accept", "last-event-id"] {
+            if let Some(value) = parts.headers.get(name) {
+                builder = builder.header(name, value);
+            }
+        }
+        let mut resp = builder
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("request to {} failed: {e}", self.base_url))?;
+
+        let mut out = Response::builder().status(resp.status());
+        for name in ["content-type", "cache-control", "location"] {
+            if let Some(value) = resp.headers().get(name) {
+                out = out.header(name, value.clone());
+            }
+        }
+        let stream = async_stream::stream! {
+            loop {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => yield Ok(chunk),
+                    Ok(None) => break,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                }
+            }
+        };
+        out.body(Body::from_stream(stream))
+            .map_err(|e| e.to_string())
+    }
+}
+
+async fn proxy(State(client): State<Arc<RemoteClient>>, req: Request) -> Response {
+    match client.forward(req).await {
+        Ok(resp) => resp,
+        Err(e) => (StatusCode::BAD_GATEWAY, e).into_response(),
+    }
+}
+
+/// Local web UI whose API calls (everything but the UI assets) go to the primary.
+pub fn build_router(client: Arc<RemoteClient>) -> axum::Router {
+    crate::web::ui_routes().fallback(proxy).with_state(client)
+}
+
+async fn serve(client: RemoteClient, host: &str, port: u16) -> anyhow::Result<()> {
+    let health = client
+        .get_json("/api/health")
+        .await
+        .map_err(|e| anyhow::anyhow!("primary at {} is not reachable: {e}", client.base_url))?;
+    let primary = client.base_url.clone();
+    let listener = tokio::net::TcpListener::bind((host, port)).await?;
+    println!(
+        "Remote web UI at http://{host}:{port} -> {primary} (microclaw {})",
+        health["version"].as_str().unwrap_or("?")
+    );
+    axum::serve(listener, build_router(Arc::new(client))).await?;
+    Ok(())
+}
+
+fn print_history(value: &Value) {
+    let messages = value["messages"].as_array().cloned().unwrap_or_default();
+    if messages.is_empty() {
+        println!("No messages.");
+    }
+    for m in messages {
+        println!(
+            "[{}] {}: {}",
+            m["timestamp"].as_str().unwrap_or(""),
+            m["sender_name"].as_str().unwrap_or("?"),
+            m["content"].as_str().unwrap_or("")
+        );
+    }
+}
+
+/// Entry point for `microclaw remote`.
+pub async fn run_cli(args: &[String]) -> anyhow::Result<()> {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let parsed = parse_args(args).map_err(|e| anyhow::anyhow!("{e}\n\n{USAGE}"))?;
+    let Some(url) = parsed
+        .url
+        .clone()
+        .or_else(|| std::env::var("MICROCLAW_REMOTE_URL").ok())
+    else {
+        anyhow::bail!("--url is required\n\n{USAGE}");
+    };
+    let token = parsed
+        .token
+        .clone()
+        .or_else(|| std::env::var("MICROCLAW_REMOTE_TOKEN").ok());
+    let client = RemoteClient::new(&url, token).map_err(|e| anyhow::anyhow!(e))?;
+    let session = parsed.session.clone();
+
+    let command = parsed.positional.first().map(String::as_str);
+    let result = match command {
+        None | Some("serve") => {
+            let host = parsed.host.as_deref().unwrap_or(DEFAULT_HOST);
+            return serve(client, host, parsed.port.unwrap_or(DEFAULT_PORT)).await;
+        }
+        Some("send") => {
+            let message = parsed.positional[1..].join(" ");
+            if message.trim().is_empty() {
+                anyhow::bail!("send needs a message\n\n{USAGE}");
+            }
+            client
+                .post_json(
+                    "/api/send",
+                    &json!({ "session_key": session, "message": message }),
+                )
+                .await
+                .map(|v| println!("{}", v["response"].as_str().unwrap_or("")))
+        }
+        Some("sessions") => client.get_json("/api/sessions").await.map(|v| {
+            for s in v["sessions"].as_array().cloned().unwrap_or_default() {
+                println!(
+                    "{}\t{}\t{}",
+                    s["session_key"].as_str().unwrap_or(""),
+                    s["chat_type"].as_str().unwrap_or(""),
+                    s["label"].as_str().unwrap_or("")
+                );
+            }
+        }),
+        Some("history") => {
+            let mut path = format!(
+                "/api/history?session_key={}",
+                urlencoding::encode(session.as_deref().unwrap_or("main"))
+            );
+            if let Some(limit) = parsed.limit {
+                path.push_str(&format!("&limit={limit}"));
+            }
+            client.get_json(&path).await.map(|v| print_history(&v))
+        }
+        Some("health") => client.get_json("/api/health").await.map(|v| {
+            println!(
+                "{} is up (microclaw {}, leader: {})",
+                client.base_url,
+                v["version"].as_str().unwrap_or("?"),
+                v["leader"].as_bool().unwrap_or(true)
+            )
+        }),
+        Some(other) => anyhow::bail!("unknown remote command: {other}\n\n{USAGE}"),
+    };
+    result.map_err(|e| anyhow::anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = parse_args(&args(&[
+            "--url",
+            "http://home:10961",
+            "send",
+            "--session",
+            "laptop",
+            "hello",
+            "there",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.url.as_deref(), Some("http://home:10961"));
+        assert_eq!(parsed.session.as_deref(), Some("laptop"));
+        assert_eq!(parsed.positional, args(&["send", "hello", "there"]));
+        assert!(parse_args(&args(&["--port", "x"])).is_err());
+        assert!(parse_args(&args(&["--token"])).is_err());
+        assert!(RemoteClient::new("home:10961", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proxy_forwards_with_token() {
+        use axum::routing::post;
+
+        // Stand-in primary that echoes the auth header and body
+        let upstream = axum::Router::new().route(
+            "/api/send",
+            post(|headers: axum::http::HeaderMap, body: String| async move {
+                let auth = headers
+                    .get("authorization")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("")
+                    .to_string();
+                axum::Json(json!({ "auth": auth, "body": body }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, upstream).await.unwrap();
+        });
+
+        let client = RemoteClient::new(&format!("http://{addr}/"), Some("secret".into())).unwrap();
+        let router = build_router(Arc::new(client));
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/send")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"hi"}"#))
+            .unwrap();
+        let resp = tower::ServiceExt::oneshot(router.clone(), req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), 1 << 20)
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(value["auth"], "Bearer secret");
+        assert_eq!(value["body"], r#"{"message":"hi"}"#);
+
+        // The primary's status codes pass through
+        let req = Request::builder()
+            .uri("/api/nope")
+            .body(Body::empty())
+            .unwrap();
+        let resp = tower::ServiceExt::oneshot(router, req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,305 @@
+//! Roundtable: two or more personas (each with its own prompt and optionally its own model)
+//! discuss a question for a few rounds, then a synthesizer writes the final answer from the
+//! exchange. Used by `/debate` and the `roundtable` tool; the exchange itself is only shown
+//! when `show_thinking` is `full`.
+
+use crate::claude::{Message, MessageContent, ResponseContentBlock};
+use crate::config::{Config, RoundtableConfig, RoundtablePersonaConfig, ThinkingLevel};
+use crate::error::MicroClawError;
+use crate::llm::LlmProvider;
+
+const SYNTHESIZER_PROMPT: &str = "You moderate a roundtable. Read the question and the participants' discussion, then write the final answer for the user: weigh the arguments, settle disagreements where the discussion supports it, and say plainly where it doesn't. Answer the question directly; do not narrate the discussion.";
+
+/// One participant, ready to speak.
+pub struct Participant {
+    pub name: String,
+    pub prompt: String,
+    pub llm: Box<dyn LlmProvider>,
+}
+
+/// A finished roundtable: who said what, in order, and the synthesized answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundtableOutcome {
+    pub exchange: Vec<(String, String)>,
+    pub answer: String,
+}
+
+impl RoundtableOutcome {
+    /// The answer, preceded by the exchange when `show_exchange` is set.
+    pub fn render(&self, show_exchange: bool) -> String {
+        if !show_exchange || self.exchange.is_empty() {
+            return self.answer.clone();
+        }
+        let mut out = String::from("Discussion:\n\n");
+        for (name, text) in &self.exchange {
+            out.push_str(&format!("**{name}:** {text}\n\n"));
+        }
+        out.push_str("Conclusion:\n\n");
+        out.push_str(&self.answer);
+        out
+    }
+}
+
+/// Config for one persona's provider: its provider/model/key over the main ones, with
+/// the roundtable's output limit.
+pub fn persona_config(
+    config: &Config,
+    roundtable: &RoundtableConfig,
+    persona: &RoundtablePersonaConfig,
+) -> Config {
+    let mut persona_config = config.clone();
+    if let Some(provider) = persona.provider.as_ref().filter(|p| !p.trim().is_empty()) {
+        persona_config.llm_provider = provider.trim().to_string();
+        persona_config.llm_base_url = persona.base_url.clone();
+        // The main fallbacks are for the main provider
+        persona_config.llm_fallbacks.clear();
+    } else if persona.base_url.is_some() {
+        persona_config.llm_base_url = persona.base_url.clone();
+    }
+    if let Some(model) = persona.model.as_ref().filter(|m| !m.trim().is_empty()) {
+        persona_config.model = model.trim().to_string();
+    }
+    if let Some(key) = persona.api_key.as_ref().filter(|k| !k.trim().is_empty()) {
+        persona_config.api_key = key.clone();
+    }
+    persona_config.max_tokens = roundtable.max_tokens;
+    persona_config
+}
+
+fn response_text(content: &[ResponseContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ResponseContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("")
+        .trim()
+        .to_string()
+}
+
+fn transcript(question: &str, exchange: &[(String, String)]) -> String {
+    let mut out = format!("Question: {question}\n");
+    if !exchange.is_empty() {
+        out.push_str("\nDiscussion so far:\n");
+        for (name, text) in exchange {
+            out.push_str(&format!("\n[{name}]\n{text}\n"));
+        }
+    }
+    out
+}
+
+fn persona_system_prompt(participant: &Participant, others: &[&str]) -> String {
+    format!(
+        "You are {}, taking part in a roundtable discussion with {}. {}\n\nKeep each contribution short (at most a few paragraphs). Respond to the others' points by name where you agree or disagree, add something new rather than repeating yourself, and do not write the final answer; a moderator will.",
+        participant.name,
+        others.join(", "),
+        participant.prompt.trim()
+    )
+}
+
+/// Every participant speaks once per round, seeing the exchange so far; then `synthesizer`
+/// answers from the whole transcript.
+pub async fn run_roundtable(
+    question: &str,
+    participants: &[Participant],
+    synthesizer: &dyn LlmProvider,
+    turns: u32,
+) -> Result<RoundtableOutcome, MicroClawError> {
+    let mut exchange: Vec<(String, String)> = Vec::new();
+    for round in 1..=turns {
+        for participant in participants {
+            let others: Vec<&str> = participants
+                .iter()
+                .filter(|p| p.name != participant.name)
+                .map(|p| p.name.as_str())
+                .collect();
+            let mut prompt = transcript(question, &exchange);
+            prompt.push_str(&format!(
+                "\nRound {round} of {turns}. Your turn, {}.",
+                participant.name
+            ));
+            let response = participant
+                .llm
+                .send_message(
+                    &persona_system_prompt(participant, &others),
+                    vec![Message {
+                        role: "user".into(),
+                        content: MessageContent::Text(prompt),
+                    }],
+                    None,
+                )
+                .await?;
+            let text = crate::telegram::strip_thinking(&response_text(&response.content));
+            if !text.is_empty() {
+                exchange.push((participant.name.clone(), text));
+            }
+        }
+    }
+    if exchange.is_empty() {
+        return Err(MicroClawError::LlmApi(
+            "Roundtable participants produced no output".into(),
+        ));
+    }
+
+    let response = synthesizer
+        .send_message(
+            SYNTHESIZER_PROMPT,
+            vec![Message {
+                role: "user".into(),
+                content: MessageContent::Text(transcript(question, &exchange)),
+            }],
+            None,
+        )
+        .await?;
+    let answer = crate::telegram::strip_thinking(&response_text(&response.content));
+    if answer.is_empty() {
+        return Err(MicroClawError::LlmApi("Empty roundtable synthesis".into()));
+    }
+    Ok(RoundtableOutcome { exchange, answer })
+}
+
+/// Run a roundtable on `question` with the configured (or built-in) personas.
+pub async fn roundtable(
+    config: &Config,
+    question: &str,
+) -> Result<RoundtableOutcome, MicroClawError> {
+    let roundtable = config.roundtable.clone().unwrap_or_default();
+    let participants: Vec<Participant> = roundtable
+        .personas
+        .iter()
+        .map(|persona| Participant {
+            name: persona.name.trim().to_string(),
+            prompt: persona.prompt.clone(),
+            llm: crate::llm::create_provider(&persona_config(config, &roundtable, persona)),
+        })
+        .collect();
+    let mut synthesizer_config = config.clone();
+    if let Some(model) = roundtable
+        .synthesizer_model
+        .as_ref()
+        .filter(|m| !m.trim().is_empty())
+    {
+        synthesizer_config.model = model.trim().to_string();
+    }
+    let synthesizer = crate::llm::create_provider(&synthesizer_config);
+    run_roundtable(
+        question,
+        &participants,
+        synthesizer.as_ref(),
+        roundtable.turns,
+    )
+    .await
+}
+
+/// `/debate <question>`.
+pub async fn handle_debate_command(config: &Config, text: &str) -> String {
+    let question = text
+        .trim()
+        .split_once(char::is_whitespace)
+        .map(|(_, rest)| rest.trim())
+        .unwrap_or("");
+    if question.is_empty() {
+        let roundtable = config.roundtable.clone().unwrap_or_default();
+        let names: Vec<&str> = roundtable
+            .personas
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        return format!(
+            "Usage: /debate <question>\n{} discuss it for {} round(s), then I sum up.",
+            names.join(", "),
+            roundtable.turns
+        );
+    }
+    match roundtable(config, question).await {
+        Ok(outcome) => outcome.render(config.show_thinking == ThinkingLevel::Full),
+        Err(e) => format!("Roundtable failed: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::{MessagesResponse, ToolDefinition};
+    use std::sync::{Arc, Mutex};
+
+    /// Answers with its name and how many contributions it has seen; records its prompts.
+    struct EchoLlm {
+        name: &'static str,
+        prompts: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for EchoLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            let MessageContent::Text(prompt) = &messages[0].content else {
+                panic!("expected text prompt");
+            };
+            let seen = prompt.matches("\n[").count();
+            self.prompts.lock().unwrap().push(prompt.clone());
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text {
+                    text: format!("{} saw {seen}", self.name),
+                }],
+                stop_reason: Some("end_turn".into()),
+                usage: None,
+            })
+        }
+    }
+
+    fn participant(name: &'static str, prompts: &Arc<Mutex<Vec<String>>>) -> Participant {
+        Participant {
+            name: name.into(),
+            prompt: "Be brief.".into(),
+            llm: Box::new(EchoLlm {
+                name,
+                prompts: prompts.clone(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_roundtable_alternates_and_synthesizes() {
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let participants = vec![participant("A", &prompts), participant("B", &prompts)];
+        let synth_prompts = Arc::new(Mutex::new(Vec::new()));
+        let synthesizer = EchoLlm {
+            name: "S",
+            prompts: synth_prompts.clone(),
+        };
+        let outcome = run_roundtable("Heat pump?", &participants, &synthesizer, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.exchange,
+            vec![
+                ("A".to_string(), "A saw 0".to_string()),
+                ("B".to_string(), "B saw 1".to_string()),
+                ("A".to_string(), "A saw 2".to_string()),
+                ("B".to_string(), "B saw 3".to_string()),
+            ]
+        );
+        assert_eq!(outcome.answer, "S saw 4");
+        assert!(prompts.lock().unwrap()[3].contains("Round 2 of 2. Your turn, B."));
+        assert!(synth_prompts.lock().unwrap()[0].starts_with("Question: Heat pump?"));
+    }
+
+    #[test]
+    fn test_render_hides_exchange_unless_asked() {
+        let outcome = RoundtableOutcome {
+            exchange: vec![("A".into(), "yes".into())],
+            answer: "Probably.".into(),
+        };
+        assert_eq!(outcome.render(false), "Probably.");
+        let shown = outcome.render(true);
+        assert!(shown.contains("**A:** yes"));
+        assert!(shown.ends_with("Probably."));
+    }
+}
@@ -0,0 +1,618 @@
+//! Natural-language schedules for `schedule_task`: phrases like "every weekday at 8am", "every
+//! 15 minutes", "tomorrow at 9:30" or "in 45 minutes" become a 6-field cron expression or a
+//! one-shot UTC timestamp, read in the task's timezone, plus a summary of how the phrase was
+//! understood so it can be echoed back for confirmation. Anything not understood is an error
+//! rather than a guess.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedSchedule {
+    /// "cron" or "once", as stored in `scheduled_tasks.schedule_type`.
+    pub schedule_type: &'static str,
+    /// 6-field cron expression, or RFC 3339 timestamp (UTC) for one-shot tasks.
+    pub value: String,
+    /// How the phrase was read, e.g. "every weekday at 08:00".
+    pub summary: String,
+}
+
+/// Time when a day-based phrase gives none.
+const DEFAULT_HOUR: u32 = 9;
+const FILLER: &[&str] = &[
+    "at", "on", "the", "of", "and", "from", "starting", "o'clock", "st", "nd", "rd", "th",
+];
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Clock times: "8am", "8:30 pm", "20:15", "noon", "midnight", or a bare hour after "at".
+fn time_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"\b(?:(?:at\s+)?(?:noon|midnight|\d{1,2}(?::\d{2})?\s*(?:am|pm)|\d{1,2}:\d{2})|at\s+\d{1,2})\b",
+        )
+            .expect("valid time regex")
+    })
+}
+
+fn parse_time(text: &str) -> Option<(u32, u32)> {
+    let text = text.trim();
+    let text = text.strip_prefix("at").unwrap_or(text).trim();
+    match text {
+        "noon" => return Some((12, 0)),
+        "midnight" => return Some((0, 0)),
+        _ => {}
+    }
+    let (body, pm) = if let Some(b) = text.strip_suffix("am") {
+        (b.trim(), Some(false))
+    } else if let Some(b) = text.strip_suffix("pm") {
+        (b.trim(), Some(true))
+    } else {
+        (text, None)
+    };
+    let (hour, minute): (u32, u32) = match body.split_once(':') {
+        Some((h, m)) => (h.parse().ok()?, m.parse().ok()?),
+        None => (body.parse().ok()?, 0),
+    };
+    if minute > 59 {
+        return None;
+    }
+    match pm {
+        Some(pm) if (1..=12).contains(&hour) => Some((hour % 12 + if pm { 12 } else { 0 }, minute)),
+        Some(_) => None,
+        None if hour <= 23 => Some((hour, minute)),
+        None => None,
+    }
+}
+
+fn weekday_of(word: &str) -> Option<Weekday> {
+    let word = word.strip_suffix('s').unwrap_or(word);
+    Some(match word {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thur" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+fn month_of(word: &str) -> Option<u32> {
+    let prefix = word.get(..3)?;
+    let index = MONTHS.iter().position(|m| *m == prefix)?;
+    let full = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ][index];
+    (word.len() == 3 || full.starts_with(word) || word == "sept").then_some(index as u32 + 1)
+}
+
+/// Default time of day for "morning", "evening"... when no clock time is given.
+fn part_of_day(word: &str) -> Option<u32> {
+    match word {
+        "morning" => Some(8),
+        "afternoon" => Some(14),
+        "evening" => Some(18),
+        "night" | "tonight" | "nightly" => Some(21),
+        _ => None,
+    }
+}
+
+fn join_and(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+fn clock(hour: u32, minute: u32) -> String {
+    format!("{hour:02}:{minute:02}")
+}
+
+fn normalize(phrase: &str) -> String {
+    let lower = phrase
+        .to_lowercase()
+        .replace("a.m.", "am")
+        .replace("p.m.", "pm")
+        .replace([',', ';'], " ")
+        .replace('&', " and ");
+    lower.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split "45min" / "2h" into number and unit; other words are kept as they are.
+fn split_units(words: Vec<String>) -> Vec<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"^(\d+)([a-z]+)$").expect("valid unit regex"));
+    words
+        .into_iter()
+        .flat_map(|w| match re.captures(&w) {
+            Some(c) => vec![c[1].to_string(), c[2].to_string()],
+            None => vec![w],
+        })
+        .collect()
+}
+
+fn count_word(word: &str) -> Option<u32> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        "two" => Some(2),
+        "three" => Some(3),
+        _ => word.parse().ok().filter(|n| *n > 0),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+}
+
+fn unit_of(word: &str) -> Option<Unit> {
+    match word {
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(Unit::Minute),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(Unit::Hour),
+        "d" | "day" | "days" => Some(Unit::Day),
+        "w" | "wk" | "week" | "weeks" => Some(Unit::Week),
+        _ => None,
+    }
+}
+
+/// Parse `phrase` in timezone `tz`, relative to `now`.
+pub fn parse(phrase: &str, tz: Tz, now: DateTime<Utc>) -> Result<ParsedSchedule, String> {
+    let text = normalize(phrase);
+    if text.is_empty() {
+        return Err("Empty schedule".into());
+    }
+    let mut times = Vec::new();
+    for m in time_re().find_iter(&text) {
+        times.push(
+            parse_time(m.as_str())
+                .ok_or_else(|| format!("Invalid time '{}'", m.as_str().trim()))?,
+        );
+    }
+    let rest = time_re().replace_all(&text, " ");
+    let words: Vec<String> = split_units(rest.split_whitespace().map(String::from).collect())
+        .into_iter()
+        .filter(|w| !FILLER.contains(&w.as_str()))
+        .collect();
+    let recurring = words.iter().any(|w| {
+        matches!(
+            w.as_str(),
+            "every"
+                | "each"
+                | "daily"
+                | "hourly"
+                | "weekly"
+                | "monthly"
+                | "nightly"
+                | "weekdays"
+                | "weekends"
+                | "workdays"
+        ) || (w.ends_with("days") && weekday_of(w).is_some())
+    });
+    if recurring {
+        parse_recurring(&words, &times, tz, now)
+    } else {
+        parse_once(&words, &times, tz, now)
+    }
+}
+
+fn parse_recurring(
+    words: &[String],
+    times: &[(u32, u32)],
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<ParsedSchedule, String> {
+    let words: Vec<&str> = words
+        .iter()
+        .map(String::as_str)
+        .filter(|w| !matches!(*w, "every" | "each"))
+        .collect();
+    let cron = |value: String, summary: String| ParsedSchedule {
+        schedule_type: "cron",
+        value,
+        summary,
+    };
+
+    // Intervals: "every 15 minutes", "every minute", "hourly", "every 2 hours"
+    let interval = match words.as_slice() {
+        ["hourly"] => Some((1, Unit::Hour)),
+        [unit] => unit_of(unit).map(|u| (1, u)),
+        [n, unit] => count_word(n).zip(unit_of(unit)),
+        _ => None,
+    };
+    match interval {
+        Some((n, Unit::Minute)) if times.is_empty() => {
+            return match n {
+                1 => Ok(cron("0 * * * * *".into(), "every minute".into())),
+                2..=59 => Ok(cron(format!("0 */{n} * * * *"), format!("every {n} minutes"))),
+                _ => Err("Minute intervals must be between 1 and 59".into()),
+            }
+        }
+        Some((n, Unit::Hour)) if times.is_empty() => {
+            return match n {
+                1 => Ok(cron("0 0 * * * *".into(), "every hour".into())),
+                2..=23 => Ok(cron(format!("0 0 */{n} * * *"), format!("every {n} hours"))),
+                _ => Err("Hour intervals must be between 1 and 23".into()),
+            }
+        }
+        Some((n, _)) if n > 1 => {
+            return Err("Intervals of several days or weeks are not supported; name the days (e.g. 'every Monday and Thursday')".into())
+        }
+        _ => {}
+    }
+
+    let mut days: Vec<Weekday> = Vec::new();
+    let mut weekdays = false;
+    let mut weekends = false;
+    let mut weekly = false;
+    let mut monthly = false;
+    let mut day_of_month: Option<u32> = None;
+    let mut default_hour = DEFAULT_HOUR;
+    for word in &words {
+        if let Some(hour) = part_of_day(word) {
+            default_hour = hour;
+        } else if let Some(day) = weekday_of(word) {
+            if !days.contains(&day) {
+                days.push(day);
+            }
+        } else {
+            match *word {
+                "day" | "daily" => {}
+                "weekday" | "weekdays" | "workday" | "workdays" => weekdays = true,
+                "weekend" | "weekends" => weekends = true,
+                "week" | "weekly" => weekly = true,
+                "month" | "monthly" => monthly = true,
+                n => match n.parse::<u32>() {
+                    Ok(d) if monthly_context(&words) && (1..=31).contains(&d) => {
+                        day_of_month = Some(d)
+                    }
+                    _ => return Err(format!("Could not understand '{word}' in the schedule")),
+                },
+            }
+        }
+    }
+
+    let minute = times.first().map(|t| t.1).unwrap_or(0);
+    if times.iter().any(|t| t.1 != minute) {
+        return Err(
+            "Times in one schedule must share the same minute; create one task per time".into(),
+        );
+    }
+    let mut hours: Vec<u32> = times.iter().map(|t| t.0).collect();
+    if hours.is_empty() {
+        hours.push(default_hour);
+    }
+    hours.sort_unstable();
+    hours.dedup();
+    let hour_field = hours
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let at = format!(
+        "at {}",
+        join_and(&hours.iter().map(|h| clock(*h, minute)).collect::<Vec<_>>())
+    );
+
+    let (dom, dow, when) = if monthly {
+        let d = day_of_month.unwrap_or(1);
+        (
+            d.to_string(),
+            "*".to_string(),
+            format!("every month on day {d}"),
+        )
+    } else if weekdays {
+        ("*".into(), "Mon-Fri".into(), "every weekday".into())
+    } else if weekends {
+        ("*".into(), "Sat,Sun".into(), "every weekend day".into())
+    } else if !days.is_empty() {
+        days.sort_by_key(|d| d.num_days_from_monday());
+        let names: Vec<String> = days.iter().map(|d| weekday_name(*d).to_string()).collect();
+        let field = days
+            .iter()
+            .map(|d| weekday_name(*d)[..3].to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        ("*".into(), field, format!("every {}", join_and(&names)))
+    } else if weekly {
+        let today = now.with_timezone(&tz).weekday();
+        (
+            "*".into(),
+            weekday_name(today)[..3].to_string(),
+            format!("every {}", weekday_name(today)),
+        )
+    } else {
+        ("*".into(), "*".into(), "every day".into())
+    };
+    Ok(cron(
+        format!("0 {minute} {hour_field} {dom} * {dow}"),
+        format!("{when} {at}"),
+    ))
+}
+
+fn monthly_context(words: &[&str]) -> bool {
+    words.iter().any(|w| matches!(*w, "month" | "monthly"))
+}
+
+fn local_to_utc(tz: Tz, date: NaiveDate, hour: u32, minute: u32) -> Option<DateTime<Utc>> {
+    let naive = date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?);
+    // A time skipped by a DST change runs an hour later
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn once(at: DateTime<Utc>, tz: Tz) -> ParsedSchedule {
+    ParsedSchedule {
+        schedule_type: "once",
+        value: at.to_rfc3339(),
+        summary: format!(
+            "once on {}",
+            at.with_timezone(&tz).format("%a %-d %b %Y at %H:%M")
+        ),
+    }
+}
+
+fn parse_once(
+    words: &[String],
+    times: &[(u32, u32)],
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<ParsedSchedule, String> {
+    if times.len() > 1 {
+        return Err("A one-time schedule takes a single time".into());
+    }
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let today = now.with_timezone(&tz).date_naive();
+
+    // Relative: "in 45 minutes", "in an hour", "in half an hour", "in 2 days"
+    if words.first() == Some(&"in") {
+        let (n, unit) = match &words[1..] {
+            ["half", "an", "hour"] | ["half", "hour"] => (30, Unit::Minute),
+            [n, unit] => count_word(n)
+                .zip(unit_of(unit))
+                .ok_or_else(|| format!("Could not understand '{}'", words.join(" ")))?,
+            [unit] if unit_of(unit).is_some() => (1, unit_of(unit).unwrap_or(Unit::Minute)),
+            _ => return Err(format!("Could not understand '{}'", words.join(" "))),
+        };
+        return match (unit, times.first()) {
+            (Unit::Minute | Unit::Hour, Some(_)) => {
+                Err("Give either a delay ('in 2 hours') or a time, not both".into())
+            }
+            (Unit::Minute, None) => Ok(once(now + Duration::minutes(n as i64), tz)),
+            (Unit::Hour, None) => Ok(once(now + Duration::hours(n as i64), tz)),
+            (Unit::Day | Unit::Week, time) => {
+                let days = if unit == Unit::Week { n * 7 } else { n };
+                let Some(&(hour, minute)) = time else {
+                    return Ok(once(now + Duration::days(days as i64), tz));
+                };
+                let date = today + Duration::days(days as i64);
+                local_to_utc(tz, date, hour, minute)
+                    .map(|at| once(at, tz))
+                    .ok_or_else(|| "Invalid date or time".into())
+            }
+        };
+    }
+
+    let mut date: Option<NaiveDate> = None;
+    let mut default_hour = DEFAULT_HOUR;
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        if let Some(hour) = part_of_day(word) {
+            default_hour = hour;
+            if word == "tonight" {
+                date = Some(today);
+            }
+        } else if let Some(day) = weekday_of(word) {
+            // The next such day after today ("monday", "next monday" and "this monday" alike)
+            let mut d = today + Duration::days(1);
+            while d.weekday() != day {
+                d += Duration::days(1);
+            }
+            date = Some(d);
+        } else if let Ok(d) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+            date = Some(d);
+        } else if let Some(month) = month_of(word) {
+            // "oct 20" or "20 oct"
+            let after = words.get(i + 1).and_then(|w| w.parse::<u32>().ok());
+            let before = i
+                .checked_sub(1)
+                .and_then(|j| words.get(j))
+                .and_then(|w| w.parse::<u32>().ok());
+            if after.is_some() {
+                i += 1;
+            }
+            let day = after
+                .or(before)
+                .ok_or_else(|| format!("Which day of {word}?"))?;
+            let mut d = NaiveDate::from_ymd_opt(today.year(), month, day)
+                .ok_or_else(|| format!("Invalid date: {word} {day}"))?;
+            if d < today {
+                d = NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+                    .ok_or_else(|| format!("Invalid date: {word} {day}"))?;
+            }
+            date = Some(d);
+        } else {
+            match word {
+                "today" => date = Some(today),
+                "tomorrow" => {
+                    date = Some(if date.is_some() {
+                        // "day after tomorrow"
+                        today + Duration::days(2)
+                    } else {
+                        today + Duration::days(1)
+                    })
+                }
+                "day" | "after" if words.get(i + 1..).is_some_and(|r| r.contains(&"tomorrow")) => {
+                    date = Some(today)
+                }
+                "next" | "this" => {}
+                n if n.parse::<u32>().is_ok()
+                    && words.get(i + 1).is_some_and(|w| month_of(w).is_some()) => {}
+                _ => return Err(format!("Could not understand '{word}' in the schedule")),
+            }
+        }
+        i += 1;
+    }
+
+    let (hour, minute) = times.first().copied().unwrap_or((default_hour, 0));
+    let at = match date {
+        Some(d) => local_to_utc(tz, d, hour, minute),
+        None if times.is_empty() => {
+            return Err("Give a time or a date, e.g. 'tomorrow at 9am' or 'in 30 minutes'".into())
+        }
+        None => {
+            // A bare time: today if still ahead, else tomorrow
+            local_to_utc(tz, today, hour, minute)
+                .filter(|t| *t > now)
+                .or_else(|| local_to_utc(tz, today + Duration::days(1), hour, minute))
+        }
+    }
+    .ok_or("Invalid date or time")?;
+    if at <= now {
+        return Err(format!(
+            "{} has already passed",
+            at.with_timezone(&tz).format("%a %-d %b %Y %H:%M")
+        ));
+    }
+    Ok(once(at, tz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saturday 17 Oct 2026, 10:00 in London (09:00 UTC).
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-17T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn recurring(phrase: &str) -> (String, String) {
+        let p = parse(phrase, chrono_tz::Europe::London, now()).unwrap();
+        assert_eq!(p.schedule_type, "cron", "{phrase}");
+        assert!(cron::Schedule::from_str(&p.value).is_ok(), "{}", p.value);
+        (p.value, p.summary)
+    }
+
+    fn once_at(phrase: &str) -> (String, String) {
+        let p = parse(phrase, chrono_tz::Europe::London, now()).unwrap();
+        assert_eq!(p.schedule_type, "once", "{phrase}");
+        (p.value, p.summary)
+    }
+
+    use std::str::FromStr;
+
+    #[test]
+    fn test_recurring_phrases() {
+        assert_eq!(
+            recurring("every weekday at 8am"),
+            ("0 0 8 * * Mon-Fri".into(), "every weekday at 08:00".into())
+        );
+        assert_eq!(recurring("every 15 minutes").0, "0 */15 * * * *");
+        assert_eq!(recurring("hourly").1, "every hour");
+        assert_eq!(recurring("every 2 hours").0, "0 0 */2 * * *");
+        assert_eq!(recurring("daily at 7:30 p.m.").0, "0 30 19 * * *");
+        assert_eq!(
+            recurring("every Monday, Wed & Fri at 6:15pm"),
+            (
+                "0 15 18 * * Mon,Wed,Fri".into(),
+                "every Monday, Wednesday and Friday at 18:15".into()
+            )
+        );
+        assert_eq!(recurring("mondays at 9").0, "0 0 9 * * Mon");
+        assert_eq!(
+            recurring("every day at 8am and 6pm").1,
+            "every day at 08:00 and 18:00"
+        );
+        assert_eq!(recurring("every morning").0, "0 0 8 * * *");
+        assert_eq!(recurring("monthly on the 15th at noon").0, "0 0 12 15 * *");
+        assert_eq!(recurring("weekly").1, "every Saturday at 09:00");
+
+        let err = |p: &str| parse(p, chrono_tz::Europe::London, now()).unwrap_err();
+        assert!(err("every day at 8am and 6:30pm").contains("same minute"));
+        assert!(err("every 3 days").contains("not supported"));
+        assert!(err("every blue moon").contains("'blue'"));
+    }
+
+    #[test]
+    fn test_one_time_phrases() {
+        assert_eq!(
+            once_at("in 45 minutes"),
+            (
+                "2026-10-17T09:45:00+00:00".into(),
+                "once on Sat 17 Oct 2026 at 10:45".into()
+            )
+        );
+        assert_eq!(once_at("in an hour").0, "2026-10-17T10:00:00+00:00");
+        assert_eq!(once_at("in half an hour").0, "2026-10-17T09:30:00+00:00");
+        assert_eq!(once_at("in 2h").0, "2026-10-17T11:00:00+00:00");
+        // London is on BST (UTC+1) until 25 Oct
+        assert_eq!(once_at("tomorrow at 9:30").0, "2026-10-18T08:30:00+00:00");
+        assert_eq!(once_at("at 8am").0, "2026-10-18T07:00:00+00:00");
+        assert_eq!(once_at("5pm").0, "2026-10-17T16:00:00+00:00");
+        assert_eq!(once_at("tonight").0, "2026-10-17T20:00:00+00:00");
+        assert_eq!(once_at("on monday").1, "once on Mon 19 Oct 2026 at 09:00");
+        assert_eq!(
+            once_at("next tuesday at 9am").0,
+            "2026-10-20T08:00:00+00:00"
+        );
+        // After the clocks go back, 9:00 local is 09:00 UTC
+        assert_eq!(once_at("oct 27 at 9am").0, "2026-10-27T09:00:00+00:00");
+        assert_eq!(once_at("oct 30 at 14:00").0, "2026-10-30T14:00:00+00:00");
+        assert_eq!(once_at("2 november").0, "2026-11-02T09:00:00+00:00");
+        assert_eq!(
+            once_at("2026-12-24 at 18:00").0,
+            "2026-12-24T18:00:00+00:00"
+        );
+        assert_eq!(
+            once_at("the day after tomorrow at noon").0,
+            "2026-10-19T11:00:00+00:00"
+        );
+
+        let err = |p: &str| parse(p, chrono_tz::Europe::London, now()).unwrap_err();
+        assert!(err("today at 8am").contains("already passed"));
+        assert!(err("in 2 hours at 5pm").contains("not both"));
+        assert!(err("whenever").contains("'whenever'"));
+        assert!(err("at 25:00").contains("Invalid time"));
+    }
+}
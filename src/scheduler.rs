@@ -1,35 +1,234 @@
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 
-use chrono::Utc;
-use tracing::{error, info};
+use chrono::{DateTime, Duration, LocalResult, TimeZone, Utc};
+use chrono_tz::Tz;
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
 
-use crate::channel::deliver_and_store_bot_message;
-use crate::db::call_blocking;
+use crate::db::{call_blocking, ScheduledTask};
+use crate::notify::notify;
 use crate::telegram::{AgentRequestContext, AppState};
 
-fn channel_from_chat_type(chat_type: &str) -> &'static str {
-    match chat_type {
-        "discord" => "discord",
-        "whatsapp" => "whatsapp",
-        "web" => "web",
-        _ => "telegram",
-    }
-}
-
 pub fn spawn_scheduler(state: Arc<AppState>) {
     tokio::spawn(async move {
         info!("Scheduler started");
         loop {
             tokio::time::sleep(std::time::Duration::from_secs(60)).await;
             run_due_tasks(&state).await;
+            crate::polls::close_due_polls(&state).await;
+            crate::moderation::release_approved(&state).await;
+            crate::social_monitor::poll_social_feeds(&state).await;
+            crate::feeds::poll_feeds(&state).await;
+            crate::heartbeat::after_scheduler_cycle(&state).await;
+            purge_deleted_sessions(&state).await;
+            crate::idle_memory_flush::flush_idle_sessions(&state).await;
+            crate::provider_files::delete_expired(&state).await;
+            crate::chat_archive::archive_inactive(&state).await;
+            crate::feedback_report::send_due_report(&state).await;
+            crate::cursor_digest::send_due_digests(&state).await;
         }
     });
 }
 
+/// What happens when a task comes due while an earlier run of it is still going.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConcurrencyPolicy {
+    /// Drop this occurrence and wait for the next one.
+    #[default]
+    Skip,
+    /// Run once the earlier run has finished (missed occurrences collapse into one run).
+    Queue,
+    /// Start another run alongside.
+    Parallel,
+}
+
+impl ConcurrencyPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "skip" => Some(ConcurrencyPolicy::Skip),
+            "queue" => Some(ConcurrencyPolicy::Queue),
+            "parallel" => Some(ConcurrencyPolicy::Parallel),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConcurrencyPolicy::Skip => "skip",
+            ConcurrencyPolicy::Queue => "queue",
+            ConcurrencyPolicy::Parallel => "parallel",
+        }
+    }
+}
+
+/// Runs in flight (or waiting for a worker) per task id.
+fn running() -> &'static Mutex<HashMap<i64, usize>> {
+    static RUNNING: OnceLock<Mutex<HashMap<i64, usize>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn running_count(task_id: i64) -> usize {
+    running().lock().unwrap().get(&task_id).copied().unwrap_or(0)
+}
+
+/// Counts a run of a task from the claim until it ends, even if the run panics.
+struct RunGuard(i64);
+
+impl RunGuard {
+    fn start(task_id: i64) -> Self {
+        *running().lock().unwrap().entry(task_id).or_insert(0) += 1;
+        RunGuard(task_id)
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        let mut running = running().lock().unwrap();
+        if let Some(count) = running.get_mut(&self.0) {
+            *count -= 1;
+            if *count == 0 {
+                running.remove(&self.0);
+            }
+        }
+    }
+}
+
+/// Shared pool bounding how many task runs execute at once (`scheduler_max_concurrent_tasks`);
+/// claimed runs beyond it wait for a free worker in claim order.
+fn workers(state: &AppState) -> Arc<Semaphore> {
+    static WORKERS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    WORKERS
+        .get_or_init(|| {
+            Arc::new(Semaphore::new(
+                state.config.scheduler_max_concurrent_tasks.max(1),
+            ))
+        })
+        .clone()
+}
+
+/// Drop soft-deleted sessions older than `deleted_session_retention_days`.
+async fn purge_deleted_sessions(state: &Arc<AppState>) {
+    let days = state.config.deleted_session_retention_days as i64;
+    let cutoff = (Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    match call_blocking(state.db.clone(), move |db| {
+        db.purge_deleted_sessions(&cutoff)
+    })
+    .await
+    {
+        Ok(0) => {}
+        Ok(n) => info!("Scheduler: purged {n} deleted session(s)"),
+        Err(e) => error!("Scheduler: failed to purge deleted sessions: {e}"),
+    }
+}
+
+/// How far a DST change can move wall-clock time; candidates this far before `after` are checked
+/// because the repeated fall-back hour maps earlier wall times to later instants.
+const DST_SHIFT_HOURS: i64 = 1;
+
+/// The next run of a cron schedule strictly after `after`, with the expression read as
+/// wall-clock time in `tz`.
+///
+/// DST follows classic cron: a time skipped by the spring-forward change runs an hour later
+/// instead of being dropped, and a time that occurs twice when clocks fall back runs once, at
+/// its first occurrence. Schedules with a wildcard hour (every N minutes) follow real time
+/// instead: nothing runs in the missing hour and the repeated hour runs again.
+pub fn next_cron_run(
+    schedule: &cron::Schedule,
+    tz: Tz,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let every_hour = schedule.source().split_whitespace().nth(2) == Some("*");
+    let local_after = after.with_timezone(&tz).naive_local();
+    // Walk wall-clock times as if they were UTC, so none are skipped or doubled
+    let start = (local_after - Duration::hours(DST_SHIFT_HOURS)).and_utc();
+    let mut best: Option<DateTime<Utc>> = None;
+    for naive in schedule.after(&start).map(|t| t.naive_utc()) {
+        if best.is_some() && naive > local_after + Duration::hours(DST_SHIFT_HOURS) {
+            break;
+        }
+        let instants: Vec<DateTime<Utc>> = match tz.from_local_datetime(&naive) {
+            LocalResult::Single(t) => vec![t.with_timezone(&Utc)],
+            LocalResult::Ambiguous(first, second) if every_hour => {
+                vec![first.with_timezone(&Utc), second.with_timezone(&Utc)]
+            }
+            LocalResult::Ambiguous(first, _) => vec![first.with_timezone(&Utc)],
+            LocalResult::None if every_hour => Vec::new(),
+            LocalResult::None => tz
+                .from_local_datetime(&(naive + Duration::hours(DST_SHIFT_HOURS)))
+                .earliest()
+                .map(|t| t.with_timezone(&Utc))
+                .into_iter()
+                .collect(),
+        };
+        for t in instants {
+            if t > after && best.is_none_or(|b| t < b) {
+                best = Some(t);
+            }
+        }
+    }
+    best
+}
+
+/// Longest wait between retries of a failed task.
+const MAX_RETRY_DELAY_SECS: u64 = 6 * 3600;
+
+/// When to retry a task whose attempt `attempt` (1-based) failed at `failed_at`, or None when
+/// it was the last one. Delays double from `backoff_secs`; a retry of a recurring task must come
+/// before its next regular run (`next_regular`).
+fn retry_at(
+    attempt: u32,
+    max_retries: u32,
+    backoff_secs: u64,
+    failed_at: DateTime<Utc>,
+    next_regular: Option<&str>,
+) -> Option<DateTime<Utc>> {
+    if attempt > max_retries {
+        return None;
+    }
+    let delay = backoff_secs
+        .max(1)
+        .saturating_mul(1u64 << (attempt - 1).min(16))
+        .min(MAX_RETRY_DELAY_SECS);
+    let at = failed_at + chrono::Duration::seconds(delay as i64);
+    match next_regular.and_then(|n| DateTime::parse_from_rfc3339(n).ok()) {
+        Some(next) if at >= next => None,
+        _ => Some(at),
+    }
+}
+
+/// Tell the control chats (other than the task's own chat) that a task failed for good.
+async fn escalate_failure(state: &Arc<AppState>, task: &ScheduledTask, attempts: u32, error: &str) {
+    let prompt: String = task.prompt.chars().take(200).collect();
+    let text = format!(
+        "🚨 Scheduled task #{} in chat {} failed after {attempts} attempt(s): {error}\nPrompt: {prompt}",
+        task.id, task.chat_id
+    );
+    for &control_chat in &state.config.control_chat_ids {
+        if control_chat == task.chat_id {
+            continue;
+        }
+        let persona_id = call_blocking(state.db.clone(), move |db| {
+            db.get_current_persona_id(control_chat)
+        })
+        .await
+        .unwrap_or(0);
+        if let Err(e) = notify(state, control_chat, persona_id, "task_escalation", &text).await {
+            error!(
+                "Scheduler: failed to escalate task #{} to control chat {control_chat}: {e}",
+                task.id
+            );
+        }
+    }
+}
+
 async fn run_due_tasks(state: &Arc<AppState>) {
     let now = Utc::now().to_rfc3339();
-    let tasks = match call_blocking(state.db.clone(), move |db| db.get_due_tasks(&now)).await {
+    let now_for_query = now.clone();
+    let tasks = match call_blocking(state.db.clone(), move |db| db.get_due_tasks(&now_for_query))
+        .await
+    {
         Ok(t) => t,
         Err(e) => {
             error!("Scheduler: failed to query due tasks: {e}");
@@ -40,133 +239,326 @@ async fn run_due_tasks(state: &Arc<AppState>) {
     for task in tasks {
         let task_id = task.id;
         let chat_id = task.chat_id;
-        let prompt = task.prompt.clone();
+        // A run-now request on a task that is not due anyway runs once and leaves the schedule,
+        // status and retries alone
+        let manual = task.run_requested_at.is_some()
+            && !(task.status == "active" && task.next_run <= now);
+
+        let policy = task
+            .concurrency
+            .as_deref()
+            .and_then(ConcurrencyPolicy::parse)
+            .unwrap_or_default();
+        if running_count(task_id) > 0 && policy != ConcurrencyPolicy::Parallel {
+            if policy == ConcurrencyPolicy::Skip && !manual {
+                if let Some(next) = next_run_of(state, &task, Utc::now()).await {
+                    info!("Scheduler: skipping task #{task_id}, its previous run is still going");
+                    if let Err(e) =
+                        call_blocking(state.db.clone(), move |db| db.skip_task_run(task_id, &next))
+                            .await
+                    {
+                        error!("Scheduler: failed to skip task #{}: {e}", task_id);
+                    }
+                }
+            }
+            // Otherwise it stays due and starts on the first tick after the running one ends
+            continue;
+        }
 
         info!(
-            "Scheduler: executing task #{} for chat {}",
-            task_id, chat_id
+            "Scheduler: executing task #{} for chat {}{}",
+            task_id,
+            chat_id,
+            if manual { " (run now)" } else { "" }
         );
-
-        let started_at = Utc::now();
-        let started_at_str = started_at.to_rfc3339();
-
-        // Claim task immediately so the next scheduler tick (60s) won't pick it again
-        // while we're still running the agent (which can take minutes).
-        // Use after(started_at) so the next run is strictly in the future; store in UTC
-        // so get_due_tasks' string comparison (next_run <= now) is reliable.
-        let tz: chrono_tz::Tz = state.config.timezone.parse().unwrap_or(chrono_tz::Tz::UTC);
-        let next_run = if task.schedule_type == "cron" {
-            match cron::Schedule::from_str(&task.schedule_value) {
-                Ok(schedule) => schedule
-                    .after(&started_at.with_timezone(&tz))
-                    .next()
-                    .map(|t| t.with_timezone(&Utc).to_rfc3339()),
-                Err(e) => {
-                    error!("Scheduler: invalid cron for task #{}: {e}", task_id);
-                    None
-                }
+        if task.run_requested_at.is_some() {
+            if let Err(e) =
+                call_blocking(state.db.clone(), move |db| db.clear_task_run_request(task_id)).await
+            {
+                error!("Scheduler: failed to clear run-now request of task #{}: {e}", task_id);
+                continue;
             }
-        } else {
-            None // one-shot: will be marked completed by update_task_after_run
-        };
+        }
 
-        let started_for_claim = started_at_str.clone();
+        // Claim task immediately so the next scheduler tick (60s) won't pick it again
+        // while it waits for a worker or runs the agent (which can take minutes).
+        // Store in UTC so get_due_tasks' string comparison (next_run <= now) is reliable.
+        let claimed_at = Utc::now();
+        let next_run = next_run_of(state, &task, claimed_at).await;
+        let claimed_at_str = claimed_at.to_rfc3339();
         let next_run_claim = next_run.clone();
-        if let Err(e) = call_blocking(state.db.clone(), move |db| {
-            db.update_task_after_run(task_id, &started_for_claim, next_run_claim.as_deref())?;
-            Ok(())
-        })
-        .await
-        {
-            error!("Scheduler: failed to claim task #{}: {e}", task_id);
-            continue;
+        if !manual {
+            if let Err(e) = call_blocking(state.db.clone(), move |db| {
+                db.update_task_after_run(task_id, &claimed_at_str, next_run_claim.as_deref())?;
+                Ok(())
+            })
+            .await
+            {
+                error!("Scheduler: failed to claim task #{}: {e}", task_id);
+                continue;
+            }
         }
 
-        let channel =
-            match call_blocking(state.db.clone(), move |db| db.get_chat_type(chat_id)).await {
-                Ok(Some(chat_type)) => channel_from_chat_type(&chat_type),
-                _ => "telegram",
+        let guard = RunGuard::start(task_id);
+        let state = state.clone();
+        tokio::spawn(async move {
+            let _guard = guard;
+            let Ok(_permit) = workers(&state).acquire_owned().await else {
+                return;
             };
+            execute_task(&state, task, manual, next_run).await;
+        });
+    }
+}
 
-        let persona_id = call_blocking(state.db.clone(), move |db| db.get_current_persona_id(chat_id)).await.unwrap_or(0);
-        if persona_id == 0 {
-            error!("Scheduler: could not resolve persona for chat {}", chat_id);
-            continue;
+/// The next regular run of a cron task after `after`, in the task's timezone; None for one-shot
+/// tasks (they are marked completed when claimed).
+async fn next_run_of(
+    state: &Arc<AppState>,
+    task: &ScheduledTask,
+    after: DateTime<Utc>,
+) -> Option<String> {
+    if task.schedule_type != "cron" {
+        return None;
+    }
+    let tz_name = match &task.timezone {
+        Some(tz) => tz.clone(),
+        None => {
+            crate::onboarding::chat_timezone(state.db.clone(), task.chat_id, &state.config.timezone)
+                .await
         }
+    };
+    let tz: Tz = tz_name.parse().unwrap_or_else(|_| {
+        warn!(
+            "Scheduler: invalid timezone '{tz_name}' for task #{}, using UTC",
+            task.id
+        );
+        Tz::UTC
+    });
+    match cron::Schedule::from_str(&task.schedule_value) {
+        Ok(schedule) => next_cron_run(&schedule, tz, after).map(|t| t.to_rfc3339()),
+        Err(e) => {
+            error!("Scheduler: invalid cron for task #{}: {e}", task.id);
+            None
+        }
+    }
+}
 
-        // Run agent loop with the task prompt (may take a long time)
-        let (success, result_summary) = match crate::telegram::process_with_agent(
-            state,
-            AgentRequestContext {
-                caller_channel: channel,
-                chat_id,
-                chat_type: "private",
-                persona_id,
-            },
-            Some(&prompt),
-            None,
-        )
-        .await
-        {
-            Ok(response) => {
-                if !response.is_empty() {
-                    let _ = deliver_and_store_bot_message(
-                        &state.bot,
-                        state.db.clone(),
-                        &state.config.bot_username,
-                        chat_id,
-                        persona_id,
-                        &response,
-                    )
-                    .await;
+/// Run a claimed task on a worker: the agent turn, delivery, retries and the run log.
+async fn execute_task(
+    state: &Arc<AppState>,
+    task: ScheduledTask,
+    manual: bool,
+    next_run: Option<String>,
+) {
+    let task_id = task.id;
+    let chat_id = task.chat_id;
+    let prompt = task.prompt.clone();
+    let started_at = Utc::now();
+    let started_at_str = started_at.to_rfc3339();
+
+    let channel =
+        match call_blocking(state.db.clone(), move |db| db.get_chat_type(chat_id)).await {
+            Ok(Some(chat_type)) => crate::channel::channel_name(&chat_type),
+            _ => "telegram",
+        };
+
+    let persona_id = call_blocking(state.db.clone(), move |db| db.get_current_persona_id(chat_id)).await.unwrap_or(0);
+    if persona_id == 0 {
+        error!("Scheduler: could not resolve persona for chat {}", chat_id);
+        return;
+    }
+
+    // Run agent loop with the task prompt (may take a long time)
+    let (success, result_summary) = match crate::telegram::process_with_agent(
+        state,
+        AgentRequestContext {
+            caller_channel: channel,
+            chat_id,
+            chat_type: "private",
+            persona_id,
+        },
+        Some(&prompt),
+        None,
+    )
+    .await
+    {
+        Ok(response) => {
+            if !response.is_empty() {
+                let _ = notify(state, chat_id, persona_id, "scheduled_task", &response).await;
+            }
+            let summary = if response.len() > 200 {
+                format!("{}...", &response[..response.floor_char_boundary(200)])
+            } else {
+                response
+            };
+            (true, Some(summary))
+        }
+        Err(e) => {
+            error!("Scheduler: task #{} failed: {e}", task_id);
+            let attempt = task.retry_count + 1;
+            let max_retries = task.max_retries.unwrap_or(state.config.task_max_retries);
+            let retry = if manual {
+                None
+            } else {
+                retry_at(
+                    attempt,
+                    max_retries,
+                    state.config.task_retry_backoff_secs,
+                    Utc::now(),
+                    next_run.as_deref(),
+                )
+            };
+            let queued = match retry {
+                Some(at) => {
+                    let at_str = at.to_rfc3339();
+                    call_blocking(state.db.clone(), move |db| {
+                        db.schedule_task_retry(task_id, attempt, &at_str)
+                    })
+                    .await
+                    .unwrap_or(false)
                 }
-                let summary = if response.len() > 200 {
-                    format!("{}...", &response[..response.floor_char_boundary(200)])
+                None => false,
+            };
+            if let (true, Some(at)) = (queued, retry) {
+                warn!(
+                    "Scheduler: retrying task #{} at {} (retry {attempt} of {max_retries})",
+                    task_id,
+                    at.to_rfc3339()
+                );
+                (
+                    false,
+                    Some(format!(
+                        "Error (attempt {attempt}, retrying at {}): {e}",
+                        at.to_rfc3339()
+                    )),
+                )
+            } else {
+                let err_text = if attempt > 1 && !manual {
+                    format!("Scheduled task #{} failed after {attempt} attempts: {e}", task_id)
                 } else {
-                    response
+                    format!("Scheduled task #{} failed: {e}", task_id)
                 };
-                (true, Some(summary))
-            }
-            Err(e) => {
-                error!("Scheduler: task #{} failed: {e}", task_id);
-                let err_text = format!("Scheduled task #{} failed: {e}", task_id);
-                let _ = deliver_and_store_bot_message(
-                    &state.bot,
-                    state.db.clone(),
-                    &state.config.bot_username,
-                    chat_id,
-                    persona_id,
-                    &err_text,
-                )
-                .await;
+                let _ = notify(state, chat_id, persona_id, "task_failure", &err_text).await;
+                if !manual {
+                    escalate_failure(state, &task, attempt, &e.to_string()).await;
+                    let _ =
+                        call_blocking(state.db.clone(), move |db| db.reset_task_retries(task_id))
+                            .await;
+                }
                 (false, Some(format!("Error: {e}")))
             }
+        }
+    };
+    if success && task.retry_count > 0 && !manual {
+        let _ = call_blocking(state.db.clone(), move |db| db.reset_task_retries(task_id)).await;
+    }
+
+    let finished_at = Utc::now();
+    let finished_at_str = finished_at.to_rfc3339();
+    let duration_ms = (finished_at - started_at).num_milliseconds();
+
+    // Log the task run
+    let log_summary = result_summary.clone();
+    let started_for_log = started_at_str.clone();
+    let finished_for_log = finished_at_str.clone();
+    if let Err(e) = call_blocking(state.db.clone(), move |db| {
+        db.log_task_run(
+            task_id,
+            chat_id,
+            &started_for_log,
+            &finished_for_log,
+            duration_ms,
+            success,
+            log_summary.as_deref(),
+        )?;
+        Ok(())
+    })
+    .await
+    {
+        error!("Scheduler: failed to log task run for #{}: {e}", task_id);
+    }
+    // Task was already claimed (next_run / status updated) before the run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_cron_run_across_dst() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let next = |expr: &str, after: &str| {
+            let schedule = cron::Schedule::from_str(expr).unwrap();
+            let after = DateTime::parse_from_rfc3339(after).unwrap().with_timezone(&Utc);
+            next_cron_run(&schedule, tz, after).map(|t| t.with_timezone(&tz).to_rfc3339())
         };
+        // Plain days keep their wall-clock time on both sides of a change
+        assert_eq!(
+            next("0 0 8 * * *", "2026-03-07T14:00:00Z").as_deref(),
+            Some("2026-03-08T08:00:00-04:00")
+        );
+        // 02:30 does not exist on 8 Mar 2026: it runs at 03:30 instead of being dropped
+        assert_eq!(
+            next("0 30 2 * * *", "2026-03-08T05:00:00Z").as_deref(),
+            Some("2026-03-08T03:30:00-04:00")
+        );
+        // 01:30 happens twice on 1 Nov 2026: it runs once, the first time
+        assert_eq!(
+            next("0 30 1 * * *", "2026-11-01T04:00:00Z").as_deref(),
+            Some("2026-11-01T01:30:00-04:00")
+        );
+        assert_eq!(
+            next("0 30 1 * * *", "2026-11-01T05:30:01Z").as_deref(),
+            Some("2026-11-02T01:30:00-05:00")
+        );
+        // Every-15-minutes schedules run through the repeated hour in real time
+        assert_eq!(
+            next("0 */15 * * * *", "2026-11-01T05:50:00Z").as_deref(),
+            Some("2026-11-01T01:00:00-05:00")
+        );
+        assert_eq!(
+            next("0 */15 * * * *", "2026-11-01T06:05:00Z").as_deref(),
+            Some("2026-11-01T01:15:00-05:00")
+        );
+        // ...and skip the missing one
+        assert_eq!(
+            next("0 0 * * * *", "2026-03-08T06:30:00Z").as_deref(),
+            Some("2026-03-08T03:00:00-04:00")
+        );
+    }
 
-        let finished_at = Utc::now();
-        let finished_at_str = finished_at.to_rfc3339();
-        let duration_ms = (finished_at - started_at).num_milliseconds();
-
-        // Log the task run
-        let log_summary = result_summary.clone();
-        let started_for_log = started_at_str.clone();
-        let finished_for_log = finished_at_str.clone();
-        if let Err(e) = call_blocking(state.db.clone(), move |db| {
-            db.log_task_run(
-                task_id,
-                chat_id,
-                &started_for_log,
-                &finished_for_log,
-                duration_ms,
-                success,
-                log_summary.as_deref(),
-            )?;
-            Ok(())
-        })
-        .await
-        {
-            error!("Scheduler: failed to log task run for #{}: {e}", task_id);
-        }
-        // Task was already claimed (next_run / status updated) before the run
+    #[test]
+    fn test_concurrency_policy_and_run_guard() {
+        assert_eq!(ConcurrencyPolicy::parse("Queue"), Some(ConcurrencyPolicy::Queue));
+        assert_eq!(ConcurrencyPolicy::parse("later"), None);
+        assert_eq!(ConcurrencyPolicy::default().as_str(), "skip");
+
+        let first = RunGuard::start(-42);
+        let second = RunGuard::start(-42);
+        assert_eq!(running_count(-42), 2);
+        drop(first);
+        assert_eq!(running_count(-42), 1);
+        drop(second);
+        assert_eq!(running_count(-42), 0);
+    }
+
+    #[test]
+    fn test_retry_at_backoff() {
+        let failed = DateTime::parse_from_rfc3339("2026-10-17T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let at = |attempt, next: Option<&str>| {
+            retry_at(attempt, 3, 60, failed, next).map(|t| t.to_rfc3339())
+        };
+        assert_eq!(at(1, None).as_deref(), Some("2026-10-17T09:01:00+00:00"));
+        assert_eq!(at(3, None).as_deref(), Some("2026-10-17T09:04:00+00:00"));
+        assert_eq!(at(4, None), None);
+        // Never past the next regular run of a recurring task
+        assert_eq!(at(3, Some("2026-10-17T09:03:00+00:00")), None);
+        assert_eq!(
+            retry_at(10, 10, 3600, failed, None).map(|t| t.to_rfc3339()).as_deref(),
+            Some("2026-10-17T15:00:00+00:00")
+        );
     }
 }
@@ -0,0 +1,138 @@
+//! `/undelete`: bring back a conversation cleared by `/reset` (or the web UI's reset / delete).
+//! Deleted sessions stay in `deleted_sessions` for `deleted_session_retention_days` before the
+//! scheduler purges them.
+//!
+//! `/undelete` restores the most recent one, `/undelete list` shows what can be restored and
+//! `/undelete <id>` restores a specific one. The session it replaces is itself soft-deleted.
+
+use std::sync::Arc;
+
+use crate::db::{call_blocking, Database, DeletedSession};
+
+const LIST_LIMIT: usize = 10;
+
+fn command_args(text: &str) -> &str {
+    let t = text.trim();
+    match t.find(char::is_whitespace) {
+        Some(i) => t[i..].trim(),
+        None => "",
+    }
+}
+
+fn message_count(session: &DeletedSession) -> usize {
+    serde_json::from_str::<Vec<serde_json::Value>>(&session.messages_json)
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+fn render_list(sessions: &[DeletedSession], retention_days: u64) -> String {
+    if sessions.is_empty() {
+        return "No deleted conversations to restore.".into();
+    }
+    let mut out = format!("Deleted conversations (kept {retention_days} days):");
+    for s in sessions.iter().take(LIST_LIMIT) {
+        out.push_str(&format!(
+            "\n#{} — {} messages, deleted {}",
+            s.id,
+            message_count(s),
+            s.deleted_at
+                .get(..16)
+                .unwrap_or(&s.deleted_at)
+                .replace('T', " ")
+        ));
+    }
+    out.push_str("\n\nRestore one with /undelete <id>.");
+    out
+}
+
+/// Restore deleted session `id` of `chat_id`, or its most recent one when `id` is None.
+pub async fn undelete(
+    db: Arc<Database>,
+    chat_id: i64,
+    id: Option<i64>,
+) -> Result<DeletedSession, String> {
+    let deleted = call_blocking(db.clone(), move |d| d.list_deleted_sessions(chat_id))
+        .await
+        .map_err(|e| format!("Failed to list deleted conversations: {e}"))?;
+    let target = match id {
+        Some(id) => deleted
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("There is no deleted conversation #{id} in this chat."))?,
+        None => deleted
+            .first()
+            .ok_or_else(|| "No deleted conversations to restore.".to_string())?,
+    };
+    let target_id = target.id;
+    let fallback = call_blocking(db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .map_err(|e| format!("Failed to resolve persona: {e}"))?;
+    call_blocking(db, move |d| d.undelete_session(target_id, fallback))
+        .await
+        .map_err(|e| format!("Failed to restore conversation: {e}"))?
+        .ok_or_else(|| format!("There is no deleted conversation #{target_id} in this chat."))
+}
+
+/// Handle `/undelete [list | <id>]`.
+pub async fn handle_undelete_command(
+    db: Arc<Database>,
+    chat_id: i64,
+    text: &str,
+    retention_days: u64,
+) -> String {
+    let args = command_args(text);
+    let id = match args.to_lowercase().as_str() {
+        "" => None,
+        "list" => {
+            return match call_blocking(db, move |d| d.list_deleted_sessions(chat_id)).await {
+                Ok(sessions) => render_list(&sessions, retention_days),
+                Err(e) => format!("Failed to list deleted conversations: {e}"),
+            };
+        }
+        other => match other.trim_start_matches('#').parse::<i64>() {
+            Ok(id) => Some(id),
+            Err(_) => return "Usage: /undelete [list | <id>]".into(),
+        },
+    };
+    match undelete(db, chat_id, id).await {
+        Ok(session) => format!(
+            "Restored conversation #{} ({} messages).",
+            session.id,
+            message_count(&session)
+        ),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_undelete_command() {
+        let dir = std::env::temp_dir().join(format!("microclaw_trash_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(5, None, "private").unwrap();
+        let pid = db.get_or_create_default_persona(5).unwrap();
+
+        assert_eq!(
+            handle_undelete_command(db.clone(), 5, "/undelete", 30).await,
+            "No deleted conversations to restore."
+        );
+        db.save_session(5, pid, r#"[{"role":"user","content":"hi"}]"#)
+            .unwrap();
+        db.delete_session(5, pid).unwrap();
+        let list = handle_undelete_command(db.clone(), 5, "/undelete list", 30).await;
+        assert!(list.starts_with("Deleted conversations (kept 30 days):\n#1 — 1 messages"));
+        assert_eq!(
+            handle_undelete_command(db.clone(), 6, "/undelete 1", 30).await,
+            "There is no deleted conversation #1 in this chat."
+        );
+        assert_eq!(
+            handle_undelete_command(db.clone(), 5, "/undelete #1", 30).await,
+            "Restored conversation #1 (1 messages)."
+        );
+        assert!(db.load_session(5, pid).unwrap().is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
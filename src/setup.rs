@@ -67,6 +67,13 @@ const PROVIDER_PRESETS: &[ProviderPreset] = &[
         default_base_url: "http://127.0.0.1:11434/v1",
         models: &["llama3.2", "qwen2.5-coder:7b", "mistral"],
     },
+    ProviderPreset {
+        id: "llamacpp",
+        label: "llama.cpp server (local)",
+        protocol: ProviderProtocol::OpenAiCompat,
+        default_base_url: "http://127.0.0.1:8080/v1",
+        models: &["default"],
+    },
     ProviderPreset {
         id: "google",
         label: "Google DeepMind",
@@ -435,7 +442,12 @@ impl SetupApp {
 
     fn validate_local(&self) -> Result<(), MicroClawError> {
         for field in &self.fields {
-            if field.key == "LLM_API_KEY" && self.field_value("LLM_PROVIDER") == "ollama" {
+            if field.key == "LLM_API_KEY"
+                && matches!(
+                    self.field_value("LLM_PROVIDER").as_str(),
+                    "ollama" | "llamacpp"
+                )
+            {
                 continue;
             }
             if field.required && field.value.trim().is_empty() {
@@ -926,7 +938,9 @@ fn save_config_env(path: &Path, values: &HashMap<String, String>) -> Result<Opti
     lines.push(format!("TELEGRAM_BOT_TOKEN={}", escape_env(&get("TELEGRAM_BOT_TOKEN"))));
     lines.push(format!("BOT_USERNAME={}", escape_env(&get("BOT_USERNAME"))));
     lines.push("".into());
-    lines.push("# LLM (anthropic, ollama, openai, openrouter, deepseek, google, etc.)".into());
+    lines.push(
+        "# LLM (anthropic, ollama, llamacpp, openai, openrouter, deepseek, google, etc.)".into(),
+    );
     lines.push(format!("LLM_PROVIDER={}", escape_env(&get("LLM_PROVIDER"))));
     lines.push(format!("LLM_API_KEY={}", escape_env(&get("LLM_API_KEY"))));
     if !get("LLM_MODEL").is_empty() {
@@ -1319,31 +1333,35 @@ mod tests {
     }
 
     #[test]
-    fn test_save_config_yaml() {
-        let yaml_path = std::env::temp_dir().join(format!(
-            "microclaw_setup_test_{}.yaml",
-            Utc::now().timestamp_nanos_opt().unwrap_or_default()
-        ));
+    fn test_save_config_env() {
+        let stamp = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let env_path = std::env::temp_dir().join(format!("microclaw_setup_test_{stamp}.env"));
+        let workspace = std::env::temp_dir().join(format!("microclaw_setup_ws_{stamp}"));
 
         let mut values = HashMap::new();
         values.insert("TELEGRAM_BOT_TOKEN".into(), "new_tok".into());
         values.insert("BOT_USERNAME".into(), "new_bot".into());
         values.insert("LLM_PROVIDER".into(), "anthropic".into());
         values.insert("LLM_API_KEY".into(), "key".into());
+        values.insert("WORKSPACE_DIR".into(), workspace.display().to_string());
 
-        let backup = save_config_yaml(&yaml_path, &values).unwrap();
+        let backup = save_config_env(&env_path, &values).unwrap();
         assert!(backup.is_none()); // No previous file to back up
 
-        let s = fs::read_to_string(&yaml_path).unwrap();
-        assert!(s.contains("telegram_bot_token: \"new_tok\""));
-        assert!(s.contains("bot_username: \"new_bot\""));
-        assert!(s.contains("llm_provider: \"anthropic\""));
-        assert!(s.contains("api_key: \"key\""));
+        let s = fs::read_to_string(&env_path).unwrap();
+        assert!(s.contains("TELEGRAM_BOT_TOKEN=new_tok"));
+        assert!(s.contains("BOT_USERNAME=new_bot"));
+        assert!(s.contains("LLM_PROVIDER=anthropic"));
+        assert!(s.contains("LLM_API_KEY=key"));
 
         // Save again to test backup
-        let backup2 = save_config_yaml(&yaml_path, &values).unwrap();
+        let backup2 = save_config_env(&env_path, &values).unwrap();
         assert!(backup2.is_some());
 
-        let _ = fs::remove_file(&yaml_path);
+        let _ = fs::remove_file(&env_path);
+        if let Some(b) = backup2 {
+            let _ = fs::remove_file(b);
+        }
+        let _ = fs::remove_dir_all(&workspace);
     }
 }
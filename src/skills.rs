@@ -1,5 +1,8 @@
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::db::{call_blocking, Database};
 
 #[derive(Debug, Clone)]
 pub struct SkillMetadata {
@@ -11,6 +14,69 @@ pub struct SkillMetadata {
     pub source: String,
     pub version: Option<String>,
     pub updated_at: Option<String>,
+    /// Persona names (lowercase) the skill is limited to; empty means every persona.
+    pub personas: Vec<String>,
+    /// Chat types the skill is limited to (e.g. `private`, `group`, `telegram`, `web`,
+    /// `telegram_supergroup`); empty means every chat.
+    pub chat_types: Vec<String>,
+}
+
+/// Who a skill is listed or activated for. `None` fields are unknown and match every filter.
+#[derive(Debug, Clone, Default)]
+pub struct SkillAudience {
+    pub persona: Option<String>,
+    /// Stored chat type, e.g. `telegram_private`, `web`, `discord`.
+    pub chat_type: Option<String>,
+}
+
+impl SkillAudience {
+    /// Look up the persona name and chat type for a chat.
+    pub async fn load(db: Arc<Database>, chat_id: i64, persona_id: i64) -> Self {
+        call_blocking(db, move |d| {
+            Ok(SkillAudience {
+                persona: d.get_persona(persona_id)?.map(|p| p.name),
+                chat_type: d.get_chat_type(chat_id)?,
+            })
+        })
+        .await
+        .unwrap_or_default()
+    }
+}
+
+/// Names a stored chat type answers to in `chat_types:` (e.g. `telegram_supergroup` matches
+/// `telegram`, `supergroup` and `group`; web and WhatsApp chats are `private`).
+fn chat_type_aliases(chat_type: &str) -> Vec<String> {
+    let ct = chat_type.trim().to_lowercase();
+    let mut aliases = vec![ct.clone()];
+    if let Some((platform, kind)) = ct.split_once('_') {
+        aliases.push(platform.to_string());
+        aliases.push(kind.to_string());
+        if kind == "supergroup" {
+            aliases.push("group".into());
+        }
+    }
+    if ct == "web" || ct == "whatsapp" {
+        aliases.push("private".into());
+    }
+    aliases
+}
+
+impl SkillMetadata {
+    /// Whether the skill's `personas:` and `chat_types:` filters admit this audience.
+    pub fn visible_to(&self, audience: &SkillAudience) -> bool {
+        if let (false, Some(persona)) = (self.personas.is_empty(), &audience.persona) {
+            if !self.personas.contains(&persona.trim().to_lowercase()) {
+                return false;
+            }
+        }
+        if let (false, Some(chat_type)) = (self.chat_types.is_empty(), &audience.chat_type) {
+            let aliases = chat_type_aliases(chat_type);
+            if !self.chat_types.iter().any(|t| aliases.contains(t)) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -33,6 +99,10 @@ struct SkillFrontmatter {
     version: Option<String>,
     #[serde(default)]
     updated_at: Option<String>,
+    #[serde(default)]
+    personas: Vec<String>,
+    #[serde(default)]
+    chat_types: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -136,6 +206,15 @@ impl SkillManager {
 
     /// Load a skill with availability diagnostics.
     pub fn load_skill_checked(&self, name: &str) -> Result<(SkillMetadata, String), String> {
+        self.load_skill_for(name, &SkillAudience::default())
+    }
+
+    /// Load a skill with availability diagnostics, honoring its persona/chat-type filters.
+    pub fn load_skill_for(
+        &self,
+        name: &str,
+        audience: &SkillAudience,
+    ) -> Result<(SkillMetadata, String), String> {
         let all_skills = self.discover_skills_internal(true);
 
         for skill in all_skills {
@@ -144,6 +223,9 @@ impl SkillManager {
             }
 
             self.skill_is_available(&skill)?;
+            if !skill.visible_to(audience) {
+                return Err(format!("Skill '{name}' is not available in this chat."));
+            }
 
             for filename in ["SKILL.md", "skill.md"] {
                 let skill_md = skill.dir_path.join(filename);
@@ -158,7 +240,11 @@ impl SkillManager {
             return Err(format!("Skill '{name}' exists but could not be loaded."));
         }
 
-        let available = self.discover_skills();
+        let available: Vec<_> = self
+            .discover_skills()
+            .into_iter()
+            .filter(|s| s.visible_to(audience))
+            .collect();
         if available.is_empty() {
             Err(format!(
                 "Skill '{name}' not found. No skills are currently available."
@@ -197,7 +283,16 @@ impl SkillManager {
     /// Build a compact skills catalog for the system prompt.
     /// Returns empty string if no skills are available.
     pub fn build_skills_catalog(&self) -> String {
-        let skills = self.discover_skills();
+        self.build_skills_catalog_for(&SkillAudience::default())
+    }
+
+    /// Skills catalog limited to skills whose persona/chat-type filters admit `audience`.
+    pub fn build_skills_catalog_for(&self, audience: &SkillAudience) -> String {
+        let skills: Vec<_> = self
+            .discover_skills()
+            .into_iter()
+            .filter(|s| s.visible_to(audience))
+            .collect();
         if skills.is_empty() {
             return String::new();
         }
@@ -352,6 +447,58 @@ fn missing_deps(deps: &[String]) -> Vec<String> {
 
 /// Parse a SKILL.md file, extracting frontmatter via YAML and body.
 /// Returns None if the file lacks valid frontmatter with a name field.
+/// Problems that keep a SKILL.md from loading or being useful: missing or invalid YAML
+/// frontmatter, missing name/description, a name that differs from its folder, or a body
+/// without `## ` sections. Empty when the file is valid.
+pub fn validate_skill_md(content: &str, dir_path: &std::path::Path) -> Vec<String> {
+    let trimmed = content.trim_start_matches('\u{feff}');
+    if !trimmed.starts_with("---\n") && !trimmed.starts_with("---\r\n") {
+        return vec!["SKILL.md must start with YAML frontmatter between '---' lines".into()];
+    }
+    let yaml_block: String = trimmed
+        .lines()
+        .skip(1)
+        .take_while(|l| l.trim() != "---" && l.trim() != "...")
+        .map(|l| format!("{l}\n"))
+        .collect();
+    if let Err(e) = serde_yaml::from_str::<SkillFrontmatter>(&yaml_block) {
+        return vec![format!("frontmatter is not valid YAML: {e}")];
+    }
+    let Some((meta, body)) = parse_skill_md(content, dir_path) else {
+        return vec!["frontmatter is missing 'name'".into()];
+    };
+
+    let mut problems = Vec::new();
+    if let Some(folder) = dir_path.file_name().and_then(|n| n.to_str()) {
+        if meta.name != folder {
+            problems.push(format!(
+                "frontmatter name '{}' does not match folder name '{folder}'",
+                meta.name
+            ));
+        }
+    }
+    if meta.description.trim().is_empty() {
+        problems.push("frontmatter is missing 'description'".into());
+    }
+    if body.trim().is_empty() {
+        problems.push("instructions body is empty".into());
+    } else if !body.lines().any(|l| l.starts_with("## ")) {
+        problems.push("instructions have no '## ' sections (e.g. '## When to use', '## Steps')".into());
+    }
+    problems
+}
+
+fn normalize_filter(values: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = values
+        .into_iter()
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
 fn parse_skill_md(content: &str, dir_path: &std::path::Path) -> Option<(SkillMetadata, String)> {
     let trimmed = content.trim_start_matches('\u{feff}');
     if !trimmed.starts_with("---\n") && !trimmed.starts_with("---\r\n") {
@@ -439,6 +586,8 @@ fn parse_skill_md(content: &str, dir_path: &std::path::Path) -> Option<(SkillMet
                 .updated_at
                 .map(|s| s.trim().to_string())
                 .filter(|s| !s.is_empty()),
+            personas: normalize_filter(fm.personas),
+            chat_types: normalize_filter(fm.chat_types),
         },
         body,
     ))
@@ -501,6 +650,38 @@ Instructions.
         assert!(platform_allowed(&[]));
     }
 
+    #[test]
+    fn test_skill_filters_by_persona_and_chat_type() {
+        let content = "---\nname: sysadmin\ndescription: Server care\npersonas: [Admin]\nchat_types: [private, web]\n---\nBody\n";
+        let (meta, _) = parse_skill_md(content, &PathBuf::from("/tmp/skills/sysadmin")).unwrap();
+        assert_eq!(meta.personas, vec!["admin"]);
+        let audience = |persona: &str, chat_type: &str| SkillAudience {
+            persona: Some(persona.into()),
+            chat_type: Some(chat_type.into()),
+        };
+        assert!(meta.visible_to(&audience("admin", "telegram_private")));
+        assert!(meta.visible_to(&audience("Admin", "web")));
+        assert!(!meta.visible_to(&audience("kids", "telegram_private")));
+        assert!(!meta.visible_to(&audience("admin", "telegram_group")));
+        assert!(meta.visible_to(&SkillAudience::default()));
+
+        let group_only = "---\nname: chores\ndescription: Chores\nchat_types: [group]\n---\n";
+        let (meta, _) = parse_skill_md(group_only, &PathBuf::from("/tmp/skills/chores")).unwrap();
+        assert!(meta.visible_to(&audience("default", "telegram_supergroup")));
+        assert!(!meta.visible_to(&audience("default", "discord")));
+    }
+
+    #[test]
+    fn test_validate_skill_md() {
+        let dir = PathBuf::from("/tmp/skills/demo");
+        let valid = "---\nname: demo\ndescription: Demo\n---\n## Steps\nDo it.\n";
+        assert!(validate_skill_md(valid, &dir).is_empty());
+        assert_eq!(validate_skill_md("no frontmatter", &dir).len(), 1);
+        let problems = validate_skill_md("---\nname: other\n---\nJust text.\n", &dir);
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].contains("does not match folder"));
+    }
+
     #[test]
     fn test_build_skills_catalog_empty() {
         let dir =
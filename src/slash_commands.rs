@@ -9,6 +9,15 @@ pub enum SlashCommand {
     Persona,
     Archive,
     Schedule,
+    CatchUp,
+    Footer,
+    Memory,
+    Forget,
+    Undelete,
+    Ack,
+    Push,
+    Debate,
+    Thinking,
 }
 
 /// Normalize message text for command detection: trim, slash-like and invisible chars so commands are recognized.
@@ -74,6 +83,38 @@ pub fn parse(text: &str) -> Option<SlashCommand> {
     {
         return Some(SlashCommand::Schedule);
     }
+    for name in ["/catchup", "/catch_me_up", "/catchmeup"] {
+        if lower == name
+            || lower.starts_with(&format!("{name} "))
+            || lower.starts_with(&format!("{name}@"))
+        {
+            return Some(SlashCommand::CatchUp);
+        }
+    }
+    if lower == "/footer" || lower.starts_with("/footer ") || lower.starts_with("/footer@") {
+        return Some(SlashCommand::Footer);
+    }
+    if lower == "/memory" || lower.starts_with("/memory ") || lower.starts_with("/memory@") {
+        return Some(SlashCommand::Memory);
+    }
+    if lower == "/forget" || lower.starts_with("/forget ") || lower.starts_with("/forget@") {
+        return Some(SlashCommand::Forget);
+    }
+    if lower == "/undelete" || lower.starts_with("/undelete ") || lower.starts_with("/undelete@") {
+        return Some(SlashCommand::Undelete);
+    }
+    if lower == "/ack" || lower.starts_with("/ack ") || lower.starts_with("/ack@") {
+        return Some(SlashCommand::Ack);
+    }
+    if lower == "/push" || lower.starts_with("/push ") || lower.starts_with("/push@") {
+        return Some(SlashCommand::Push);
+    }
+    if lower == "/debate" || lower.starts_with("/debate ") || lower.starts_with("/debate@") {
+        return Some(SlashCommand::Debate);
+    }
+    if lower == "/thinking" || lower.starts_with("/thinking ") || lower.starts_with("/thinking@") {
+        return Some(SlashCommand::Thinking);
+    }
     None
 }
 
@@ -117,6 +158,67 @@ mod tests {
         assert_eq!(parse("/jobs@HomeBot"), Some(SlashCommand::Schedule));
     }
 
+    #[test]
+    fn parse_catch_up() {
+        assert_eq!(parse("/catchup"), Some(SlashCommand::CatchUp));
+        assert_eq!(parse("/catch_me_up"), Some(SlashCommand::CatchUp));
+        assert_eq!(parse("/catchmeup@HomeBot"), Some(SlashCommand::CatchUp));
+        assert_eq!(parse("/catchupnow"), None);
+    }
+
+    #[test]
+    fn parse_footer() {
+        assert_eq!(parse("/footer"), Some(SlashCommand::Footer));
+        assert_eq!(parse("/footer on"), Some(SlashCommand::Footer));
+        assert_eq!(parse("/footer@HomeBot off"), Some(SlashCommand::Footer));
+        assert_eq!(parse("/footers"), None);
+    }
+
+    #[test]
+    fn parse_push() {
+        assert_eq!(parse("/push"), Some(SlashCommand::Push));
+        assert_eq!(parse("/push on alice-phone"), Some(SlashCommand::Push));
+        assert_eq!(parse("/push@HomeBot off"), Some(SlashCommand::Push));
+        assert_eq!(parse("/pushy"), None);
+    }
+
+    #[test]
+    fn parse_debate() {
+        assert_eq!(parse("/debate"), Some(SlashCommand::Debate));
+        assert_eq!(parse("/debate Should we get a heat pump?"), Some(SlashCommand::Debate));
+        assert_eq!(parse("/debate@HomeBot rent or buy"), Some(SlashCommand::Debate));
+        assert_eq!(parse("/debates"), None);
+    }
+
+    #[test]
+    fn parse_thinking() {
+        assert_eq!(parse("/thinking"), Some(SlashCommand::Thinking));
+        assert_eq!(parse("/thinking summary"), Some(SlashCommand::Thinking));
+        assert_eq!(parse("/thinking@HomeBot last"), Some(SlashCommand::Thinking));
+        assert_eq!(parse("/thinkingcap"), None);
+    }
+
+    #[test]
+    fn parse_memory_forget() {
+        assert_eq!(parse("/memory"), Some(SlashCommand::Memory));
+        assert_eq!(parse("/memory edit 2 Lives in Bergen"), Some(SlashCommand::Memory));
+        assert_eq!(parse("/memory@HomeBot"), Some(SlashCommand::Memory));
+        assert_eq!(parse("/forget my old address"), Some(SlashCommand::Forget));
+        assert_eq!(parse("/forgetful"), None);
+        assert_eq!(parse("what do you have in /memory"), None);
+    }
+
+    #[test]
+    fn parse_undelete() {
+        assert_eq!(parse("/undelete"), Some(SlashCommand::Undelete));
+        assert_eq!(parse("/undelete list"), Some(SlashCommand::Undelete));
+        assert_eq!(parse("/undelete@HomeBot 3"), Some(SlashCommand::Undelete));
+        assert_eq!(parse("/ack"), Some(SlashCommand::Ack));
+        assert_eq!(parse("/ack 12"), Some(SlashCommand::Ack));
+        assert_eq!(parse("/acknowledge"), None);
+        assert_eq!(parse("/undeleted"), None);
+    }
+
     #[test]
     fn parse_not_commands() {
         assert_eq!(parse(""), None);
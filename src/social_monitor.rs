@@ -0,0 +1,396 @@
+//! Scheduled social feed monitoring: every `social.monitor_interval_mins` the scheduler tick
+//! fetches each feed connected via OAuth, compares it with the previous snapshot and tells the
+//! chat about new posts and metric milestones ("your TikTok video passed 1k views"). Every poll
+//! is persisted in `social_snapshots` (and follower counts, where the API allows, in
+//! `social_follower_counts`) for the `social_report` tool.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{error, info, warn};
+
+use crate::db::{call_blocking, SocialOAuthToken, SocialPostSnapshot};
+use crate::notify::notify;
+use crate::telegram::AppState;
+use crate::tools::social_feed::{
+    fetch_follower_count, fetch_instagram_media, fetch_linkedin_posts, fetch_tiktok_videos,
+    http_client,
+};
+
+const DEFAULT_MILESTONES: &[u64] = &[100, 1_000, 10_000, 100_000, 1_000_000];
+/// Most recent posts captured per feed on each poll.
+const MONITOR_MAX_POSTS: i64 = 20;
+const TITLE_PREVIEW_LEN: usize = 60;
+
+static LAST_POLL: Mutex<Option<Instant>> = Mutex::new(None);
+
+pub fn platform_label(platform: &str) -> &str {
+    match platform {
+        "tiktok" => "TikTok",
+        "instagram" => "Instagram",
+        "linkedin" => "LinkedIn",
+        "google" => "Google",
+        "spotify" => "Spotify",
+        other => other,
+    }
+}
+
+fn post_noun(platform: &str) -> &'static str {
+    if platform == "tiktok" {
+        "video"
+    } else {
+        "post"
+    }
+}
+
+fn int_field(item: &serde_json::Value, key: &str) -> Option<i64> {
+    let v = item.get(key)?;
+    v.as_i64()
+        .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+}
+
+fn str_field(item: &serde_json::Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| item.get(*k).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+        .map(String::from)
+}
+
+/// Map raw API items of a platform to snapshots. Items without an id are skipped.
+pub fn normalize_posts(platform: &str, items: &[serde_json::Value]) -> Vec<SocialPostSnapshot> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let post_id = str_field(item, &["id"]).or_else(|| {
+                item.get("id")
+                    .and_then(|v| v.as_i64())
+                    .map(|n| n.to_string())
+            })?;
+            let post = match platform {
+                "tiktok" => SocialPostSnapshot {
+                    title: str_field(item, &["title", "video_description"]).unwrap_or_default(),
+                    url: str_field(item, &["share_url", "embed_link"]),
+                    views: int_field(item, "view_count"),
+                    likes: int_field(item, "like_count"),
+                    comments: int_field(item, "comment_count"),
+                    shares: int_field(item, "share_count"),
+                    post_id,
+                },
+                "instagram" => SocialPostSnapshot {
+                    title: str_field(item, &["caption"]).unwrap_or_default(),
+                    url: str_field(item, &["permalink"]),
+                    likes: int_field(item, "like_count"),
+                    comments: int_field(item, "comments_count"),
+                    post_id,
+                    ..Default::default()
+                },
+                _ => SocialPostSnapshot {
+                    title: str_field(item, &["commentary"]).unwrap_or_default(),
+                    url: Some(format!("https://www.linkedin.com/feed/update/{post_id}")),
+                    post_id,
+                    ..Default::default()
+                },
+            };
+            Some(post)
+        })
+        .collect()
+}
+
+/// 950 -> "950", 1000 -> "1k", 1500 -> "1.5k", 2000000 -> "2M".
+pub fn format_count(n: u64) -> String {
+    let (value, suffix) = if n >= 1_000_000 {
+        (n as f64 / 1_000_000.0, "M")
+    } else if n >= 1_000 {
+        (n as f64 / 1_000.0, "k")
+    } else {
+        return n.to_string();
+    };
+    let s = format!("{value:.1}");
+    format!("{}{suffix}", s.trim_end_matches(".0"))
+}
+
+fn short_title(platform: &str, title: &str) -> String {
+    let line = title.lines().next().unwrap_or("").trim();
+    if line.is_empty() {
+        return format!("{} {}", platform_label(platform), post_noun(platform));
+    }
+    let line = if line.len() > TITLE_PREVIEW_LEN {
+        format!("{}…", &line[..line.floor_char_boundary(TITLE_PREVIEW_LEN)])
+    } else {
+        line.to_string()
+    };
+    format!(
+        "{} {} \"{line}\"",
+        platform_label(platform),
+        post_noun(platform)
+    )
+}
+
+/// Alerts for posts that are new since `previous`, and for metrics that crossed a milestone
+/// (only the highest milestone crossed is reported). The first poll of a feed (empty
+/// `previous`) is a baseline and produces no alerts.
+pub fn feed_alerts(
+    platform: &str,
+    previous: &[SocialPostSnapshot],
+    current: &[SocialPostSnapshot],
+    milestones: &[u64],
+) -> Vec<String> {
+    if previous.is_empty() {
+        return Vec::new();
+    }
+    let milestones = if milestones.is_empty() {
+        DEFAULT_MILESTONES
+    } else {
+        milestones
+    };
+    let mut alerts = Vec::new();
+    for post in current {
+        let Some(old) = previous.iter().find(|p| p.post_id == post.post_id) else {
+            let mut alert = format!("New {}", short_title(platform, &post.title));
+            if let Some(url) = &post.url {
+                alert.push_str(&format!(" {url}"));
+            }
+            alerts.push(alert);
+            continue;
+        };
+        for (name, before, after) in [
+            ("views", old.views, post.views),
+            ("likes", old.likes, post.likes),
+            ("comments", old.comments, post.comments),
+            ("shares", old.shares, post.shares),
+        ] {
+            let (Some(before), Some(after)) = (before, after) else {
+                continue;
+            };
+            let crossed = milestones
+                .iter()
+                .filter(|m| before < **m as i64 && after >= **m as i64)
+                .max();
+            if let Some(m) = crossed {
+                alerts.push(format!(
+                    "Your {} passed {} {name}",
+                    short_title(platform, &post.title),
+                    format_count(*m)
+                ));
+            }
+        }
+    }
+    alerts
+}
+
+async fn fetch_feed(
+    client: &reqwest::Client,
+    platform: &str,
+    token: &str,
+) -> Result<Vec<SocialPostSnapshot>, String> {
+    let items = match platform {
+        "tiktok" => {
+            fetch_tiktok_videos(client, token, MONITOR_MAX_POSTS, None)
+                .await?
+                .0
+        }
+        "instagram" => {
+            fetch_instagram_media(client, token, MONITOR_MAX_POSTS, None)
+                .await?
+                .0
+        }
+        "linkedin" => fetch_linkedin_posts(client, token, MONITOR_MAX_POSTS).await?,
+        other => return Err(format!("Unsupported platform '{other}'")),
+    };
+    Ok(normalize_posts(platform, &items))
+}
+
+async fn poll_feed(
+    state: &Arc<AppState>,
+    token: SocialOAuthToken,
+    milestones: &[u64],
+) -> Result<(), String> {
+    let platform = token.platform.clone();
+    let chat_id = token.chat_id;
+    let client = http_client()?;
+    let current = fetch_feed(&client, &platform, &token.access_token).await?;
+    // Follower counts need extra scopes on some platforms; a missing one must not stop the poll.
+    let followers = match fetch_follower_count(&client, &platform, &token.access_token).await {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Social monitor: no follower count for {platform} (chat {chat_id}): {e}");
+            None
+        }
+    };
+
+    let p = platform.clone();
+    let previous = call_blocking(state.db.clone(), move |d| {
+        d.latest_social_snapshots(&p, chat_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let alerts = feed_alerts(&platform, &previous, &current, milestones);
+
+    let p = platform.clone();
+    let captured_at = chrono::Utc::now().to_rfc3339();
+    call_blocking(state.db.clone(), move |d| {
+        d.insert_social_snapshots(&p, chat_id, &current, &captured_at)?;
+        if let Some(f) = followers {
+            d.insert_social_follower_count(&p, chat_id, f, &captured_at)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if alerts.is_empty() {
+        return Ok(());
+    }
+    info!(
+        "Social monitor: {} alert(s) for {} feed of chat {}",
+        alerts.len(),
+        platform,
+        chat_id
+    );
+    let persona_id = call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+        .await
+        .unwrap_or(0);
+    notify(
+        state,
+        chat_id,
+        persona_id,
+        "social_monitor",
+        &format!("📈 {}", alerts.join("\n📈 ")),
+    )
+    .await
+}
+
+/// Poll every connected feed when the monitor interval has elapsed. Called from the
+/// scheduler tick; a no-op when `social.monitor_interval_mins` is 0.
+pub async fn poll_social_feeds(state: &Arc<AppState>) {
+    let Some(social) = state.config.social.as_ref() else {
+        return;
+    };
+    if social.monitor_interval_mins == 0 {
+        return;
+    }
+    {
+        let interval = Duration::from_secs(social.monitor_interval_mins * 60);
+        let mut last = LAST_POLL.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < interval) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+
+    let tokens = match call_blocking(state.db.clone(), |d| d.list_social_tokens(None)).await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("Social monitor: failed to list connected feeds: {e}");
+            return;
+        }
+    };
+    // Google and Spotify tokens back account tools, not a feed.
+    let feeds = tokens
+        .into_iter()
+        .filter(|t| matches!(t.platform.as_str(), "tiktok" | "instagram" | "linkedin"));
+    for token in feeds {
+        let platform = token.platform.clone();
+        let chat_id = token.chat_id;
+        if let Err(e) = poll_feed(state, token, &social.milestones).await {
+            warn!("Social monitor: failed to poll {platform} feed for chat {chat_id}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn post(id: &str, views: Option<i64>, likes: Option<i64>) -> SocialPostSnapshot {
+        SocialPostSnapshot {
+            post_id: id.into(),
+            title: format!("Post {id}"),
+            views,
+            likes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize_posts() {
+        let tiktok = normalize_posts(
+            "tiktok",
+            &[
+                json!({"id": "v1", "title": "Dance", "view_count": 1200, "like_count": 30, "embed_link": "https://t/v1"}),
+                json!({"title": "no id"}),
+            ],
+        );
+        assert_eq!(tiktok.len(), 1);
+        assert_eq!(tiktok[0].views, Some(1200));
+        assert_eq!(tiktok[0].url.as_deref(), Some("https://t/v1"));
+
+        let insta = normalize_posts(
+            "instagram",
+            &[json!({"id": "m1", "caption": "Sunset", "like_count": 5, "comments_count": 2})],
+        );
+        assert_eq!(insta[0].title, "Sunset");
+        assert_eq!(insta[0].comments, Some(2));
+        assert_eq!(insta[0].views, None);
+    }
+
+    #[test]
+    fn test_feed_alerts_new_posts_and_milestones() {
+        let previous = vec![post("a", Some(950), Some(8)), post("b", Some(20), None)];
+        let current = vec![
+            post("c", Some(3), Some(0)),
+            post("a", Some(12_500), Some(12)),
+            post("b", Some(40), None),
+        ];
+        let alerts = feed_alerts("tiktok", &previous, &current, &[]);
+        assert_eq!(
+            alerts,
+            vec![
+                "New TikTok video \"Post c\"".to_string(),
+                "Your TikTok video \"Post a\" passed 10k views".to_string(),
+            ]
+        );
+        let custom = feed_alerts("tiktok", &previous, &current, &[10]);
+        assert!(custom.contains(&"Your TikTok video \"Post a\" passed 10 likes".to_string()));
+        assert!(feed_alerts("tiktok", &[], &current, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(950), "950");
+        assert_eq!(format_count(1_000), "1k");
+        assert_eq!(format_count(1_500), "1.5k");
+        assert_eq!(format_count(2_000_000), "2M");
+    }
+
+    #[test]
+    fn test_snapshots_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_social_mon_{}", uuid::Uuid::new_v4()));
+        let db = crate::db::Database::new(dir.to_str().unwrap()).unwrap();
+        db.insert_social_snapshots(
+            "tiktok",
+            7,
+            &[post("a", Some(1), None)],
+            "2026-01-01T00:00:00Z",
+        )
+        .unwrap();
+        db.insert_social_snapshots(
+            "tiktok",
+            7,
+            &[post("a", Some(5), None), post("b", None, None)],
+            "2026-01-01T01:00:00Z",
+        )
+        .unwrap();
+        let latest = db.latest_social_snapshots("tiktok", 7).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].views, Some(5));
+        assert!(db
+            .latest_social_snapshots("instagram", 7)
+            .unwrap()
+            .is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,8 +1,106 @@
-//! OAuth 2.0 helpers for social media platforms (TikTok, Instagram, LinkedIn).
+//! OAuth 2.0 helpers for social media platforms (TikTok, Instagram, LinkedIn), Google and
+//! Spotify.
+
+use std::sync::Arc;
 
 use crate::config::Config;
+use crate::db::{call_blocking, Database, SocialOAuthToken};
 use crate::error::MicroClawError;
 
+/// How long an authorization link (and its state token) stays valid.
+pub const STATE_TTL_MINUTES: i64 = 10;
+
+/// Platforms whose web authorization flow accepts PKCE (RFC 7636, S256).
+pub fn supports_pkce(platform: &str) -> bool {
+    matches!(platform, "tiktok" | "google" | "spotify")
+}
+
+/// Random PKCE code verifier (64 unreserved characters).
+pub fn new_code_verifier() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// S256 code challenge: base64url(SHA-256(verifier)) without padding.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    use base64::Engine;
+    let digest = ring::digest::digest(&ring::digest::SHA256, code_verifier.as_bytes());
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest.as_ref())
+}
+
+/// Create a single-use state token bound to `chat_id` (with a PKCE verifier where supported)
+/// and return the link the user opens to authorize. The raw chat_id never appears in the link.
+/// `scopes` overrides the platform's default scopes (used for incremental Google consent).
+pub async fn start_authorization(
+    config: &Config,
+    db: Arc<Database>,
+    platform: &str,
+    chat_id: i64,
+    scopes: Option<&str>,
+) -> Result<String, MicroClawError> {
+    let base = oauth_base_url(config).unwrap_or_else(|| "http://127.0.0.1:10961".into());
+    let state_token = uuid::Uuid::new_v4().simple().to_string();
+    let expires_at =
+        (chrono::Utc::now() + chrono::Duration::minutes(STATE_TTL_MINUTES)).to_rfc3339();
+    let code_verifier = supports_pkce(platform).then(new_code_verifier);
+    let (token, p) = (state_token.clone(), platform.to_string());
+    let scopes = scopes.map(String::from);
+    call_blocking(db, move |d| {
+        d.create_oauth_pending_state(
+            &token,
+            &p,
+            chat_id,
+            &expires_at,
+            code_verifier.as_deref(),
+            scopes.as_deref(),
+        )
+    })
+    .await?;
+    Ok(format!(
+        "{}/api/oauth/authorize/{}?state={}",
+        base.trim_end_matches('/'),
+        platform,
+        urlencoding::encode(&state_token)
+    ))
+}
+
+/// Scopes requested in the authorize URL of each platform.
+pub fn requested_scopes(platform: &str) -> Option<&'static str> {
+    match platform {
+        "tiktok" => Some("user.info.basic,video.list"),
+        "instagram" => Some("instagram_basic,user_media"),
+        "linkedin" => Some("openid profile email w_member_social r_organization_social"),
+        // Google tools ask for the one read-only scope they need on top of this.
+        "google" => Some("openid email"),
+        "spotify" => {
+            Some("user-read-playback-state user-modify-playback-state user-read-currently-playing")
+        }
+        _ => None,
+    }
+}
+
+/// Whether a stored token was granted `scope`.
+pub fn has_scope(token: &SocialOAuthToken, scope: &str) -> bool {
+    token
+        .scopes
+        .as_deref()
+        .unwrap_or("")
+        .split([',', ' '])
+        .any(|s| s == scope)
+}
+
+/// Whether the token's expiry (if any) has passed.
+pub fn is_expired(token: &SocialOAuthToken) -> bool {
+    token
+        .expires_at
+        .as_deref()
+        .and_then(|e| chrono::DateTime::parse_from_rfc3339(e).ok())
+        .is_some_and(|e| e < chrono::Utc::now())
+}
+
 /// Build the OAuth redirect base URL from config. Uses social.base_url if set,
 /// otherwise derives from web_host:web_port (for local dev).
 pub fn oauth_base_url(config: &Config) -> Option<String> {
@@ -28,10 +126,13 @@ pub fn oauth_base_url(config: &Config) -> Option<String> {
 }
 
 /// Generate authorize URL for a platform. Returns None if platform is not configured.
+/// `code_challenge` (S256) is appended for PKCE-capable platforms.
 pub fn authorize_url(
     config: &Config,
     platform: &str,
     state: &str,
+    code_challenge: Option<&str>,
+    scopes: Option<&str>,
 ) -> Result<Option<String>, MicroClawError> {
     let Some(base) = oauth_base_url(config) else {
         return Ok(None);
@@ -52,7 +153,7 @@ pub fn authorize_url(
             if client_id.is_empty() {
                 return Ok(None);
             }
-            let scopes = "user.info.basic,video.list";
+            let scopes = requested_scopes("tiktok").unwrap_or_default();
             format!(
                 "https://www.tiktok.com/v2/auth/authorize/?client_key={}&scope={}&response_type=code&redirect_uri={}&state={}",
                 urlencoding::encode(client_id),
@@ -73,7 +174,7 @@ pub fn authorize_url(
             if client_id.is_empty() {
                 return Ok(None);
             }
-            let scope = "instagram_basic,user_media";
+            let scope = requested_scopes("instagram").unwrap_or_default();
             format!(
                 "https://api.instagram.com/oauth/authorize?client_id={}&redirect_uri={}&scope={}&response_type=code&state={}",
                 urlencoding::encode(client_id),
@@ -94,7 +195,7 @@ pub fn authorize_url(
             if client_id.is_empty() {
                 return Ok(None);
             }
-            let scope = "openid profile email w_member_social r_organization_social";
+            let scope = requested_scopes("linkedin").unwrap_or_default();
             format!(
                 "https://www.linkedin.com/oauth/v2/authorization?response_type=code&client_id={}&redirect_uri={}&state={}&scope={}",
                 urlencoding::encode(client_id),
@@ -103,10 +204,49 @@ pub fn authorize_url(
                 urlencoding::encode(scope),
             )
         }
+        "google" => {
+            let cfg = social
+                .map(|s| &s.google)
+                .filter(|g| g.client_id.is_some() && g.client_secret.is_some())
+                .ok_or_else(|| MicroClawError::Config("Google OAuth not configured".into()))?;
+            let client_id = cfg.client_id.as_deref().unwrap_or("");
+            if client_id.is_empty() {
+                return Ok(None);
+            }
+            let scope = scopes.or(requested_scopes("google")).unwrap_or_default();
+            // include_granted_scopes keeps earlier grants, so each tool only asks for its own
+            // scope; prompt=consent makes Google issue a refresh token every time.
+            format!(
+                "https://accounts.google.com/o/oauth2/v2/auth?client_id={}&redirect_uri={}&response_type=code&scope={}&state={}&access_type=offline&prompt=consent&include_granted_scopes=true",
+                urlencoding::encode(client_id),
+                urlencoding::encode(&redirect_uri),
+                urlencoding::encode(scope),
+                urlencoding::encode(state),
+            )
+        }
+        "spotify" => {
+            let client_id = social
+                .and_then(|s| s.spotify.client_id.as_deref())
+                .filter(|id| !id.is_empty())
+                .ok_or_else(|| MicroClawError::Config("Spotify OAuth not configured".into()))?;
+            format!(
+                "https://accounts.spotify.com/authorize?client_id={}&response_type=code&redirect_uri={}&scope={}&state={}",
+                urlencoding::encode(client_id),
+                urlencoding::encode(&redirect_uri),
+                urlencoding::encode(scopes.or(requested_scopes("spotify")).unwrap_or_default()),
+                urlencoding::encode(state),
+            )
+        }
         _ => return Ok(None),
     };
 
-    Ok(Some(url))
+    match code_challenge {
+        Some(challenge) if supports_pkce(platform) => Ok(Some(format!(
+            "{url}&code_challenge={}&code_challenge_method=S256",
+            urlencoding::encode(challenge)
+        ))),
+        _ => Ok(Some(url)),
+    }
 }
 
 /// Token exchange result.
@@ -115,14 +255,18 @@ pub struct TokenResult {
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub expires_at: Option<String>,
+    /// Granted scopes as reported by the provider (falls back to the requested ones).
+    pub scopes: Option<String>,
 }
 
-/// Exchange authorization code for access token.
+/// Exchange authorization code for access token. `code_verifier` is the PKCE verifier stored
+/// with the state, for platforms that use PKCE.
 pub async fn exchange_code(
     config: &Config,
     platform: &str,
     code: &str,
     redirect_uri: &str,
+    code_verifier: Option<&str>,
 ) -> Result<TokenResult, MicroClawError> {
     let social = config.social.as_ref().ok_or_else(|| {
         MicroClawError::Config("Social OAuth not configured".into())
@@ -140,13 +284,16 @@ pub async fn exchange_code(
             let client_secret = social.tiktok.client_secret.as_deref()
                 .ok_or_else(|| MicroClawError::Config("TikTok client_secret not set".into()))?;
 
-            let params = [
+            let mut params = vec![
                 ("client_key", client_key),
                 ("client_secret", client_secret),
                 ("code", code),
                 ("grant_type", "authorization_code"),
                 ("redirect_uri", redirect_uri),
             ];
+            if let Some(verifier) = code_verifier {
+                params.push(("code_verifier", verifier));
+            }
             let resp = client
                 .post("https://open.tiktokapis.com/v2/oauth/token/")
                 .header("Content-Type", "application/x-www-form-urlencoded")
@@ -189,10 +336,17 @@ pub async fn exchange_code(
                 })
                 .map(|dt| dt.to_rfc3339());
 
+            let scopes = data
+                .get("scope")
+                .and_then(|v| v.as_str())
+                .or(requested_scopes("tiktok"))
+                .map(String::from);
+
             Ok(TokenResult {
                 access_token,
                 refresh_token,
                 expires_at,
+                scopes,
             })
         }
         "instagram" => {
@@ -241,6 +395,7 @@ pub async fn exchange_code(
                 access_token,
                 refresh_token: None,
                 expires_at: None,
+                scopes: requested_scopes("instagram").map(String::from),
             })
         }
         "linkedin" => {
@@ -295,12 +450,368 @@ pub async fn exchange_code(
                 })
                 .map(|dt| dt.to_rfc3339());
 
+            let scopes = body
+                .get("scope")
+                .and_then(|v| v.as_str())
+                .or(requested_scopes("linkedin"))
+                .map(String::from);
+
             Ok(TokenResult {
                 access_token,
                 refresh_token,
                 expires_at,
+                scopes,
+            })
+        }
+        "google" | "spotify" => {
+            let (client_id, client_secret) = client_credentials(social, platform)?;
+
+            let mut params = vec![
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("redirect_uri", redirect_uri),
+            ];
+            if let Some(verifier) = code_verifier {
+                params.push(("code_verifier", verifier));
+            }
+            let body = token_request(&client, platform, &params).await?;
+            let access_token = body
+                .get("access_token")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| MicroClawError::ToolExecution("No access_token in response".into()))?
+                .to_string();
+
+            Ok(TokenResult {
+                access_token,
+                refresh_token: body.get("refresh_token").and_then(|v| v.as_str()).map(String::from),
+                expires_at: expires_at_from(&body),
+                scopes: body.get("scope").and_then(|v| v.as_str()).map(String::from),
             })
         }
         _ => Err(MicroClawError::Config(format!("Unknown platform: {platform}"))),
     }
 }
+
+fn expires_at_from(body: &serde_json::Value) -> Option<String> {
+    body.get("expires_in")
+        .and_then(|v| v.as_i64())
+        .map(|secs| (chrono::Utc::now() + chrono::Duration::seconds(secs)).to_rfc3339())
+}
+
+/// Token endpoint for platforms whose access tokens are refreshed in place.
+fn token_endpoint(platform: &str) -> Option<&'static str> {
+    match platform {
+        "google" => Some("https://oauth2.googleapis.com/token"),
+        "spotify" => Some("https://accounts.spotify.com/api/token"),
+        _ => None,
+    }
+}
+
+fn client_credentials<'a>(
+    social: &'a crate::config::SocialConfig,
+    platform: &str,
+) -> Result<(&'a str, &'a str), MicroClawError> {
+    let cfg = social.platform(platform);
+    match (
+        cfg.and_then(|c| c.client_id.as_deref()),
+        cfg.and_then(|c| c.client_secret.as_deref()),
+    ) {
+        (Some(id), Some(secret)) => Ok((id, secret)),
+        _ => Err(MicroClawError::Config(format!(
+            "{platform} OAuth not configured"
+        ))),
+    }
+}
+
+async fn token_request(
+    client: &reqwest::Client,
+    platform: &str,
+    params: &[(&str, &str)],
+) -> Result<serde_json::Value, MicroClawError> {
+    let url = token_endpoint(platform)
+        .ok_or_else(|| MicroClawError::Config(format!("Unknown platform: {platform}")))?;
+    let resp = client
+        .post(url)
+        .form(params)
+        .send()
+        .await
+        .map_err(|e| MicroClawError::ToolExecution(e.to_string()))?;
+    let status = resp.status();
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| MicroClawError::ToolExecution(e.to_string()))?;
+    if !status.is_success() {
+        let err_msg = body
+            .get("error_description")
+            .and_then(|v| v.as_str())
+            .or_else(|| body.get("error").and_then(|v| v.as_str()))
+            .unwrap_or("Token request failed");
+        return Err(MicroClawError::ToolExecution(err_msg.to_string()));
+    }
+    Ok(body)
+}
+
+/// Refresh an expired Google or Spotify access token with its refresh token and store the new
+/// one. Returns the access token to use (the stored one if it has not expired).
+pub async fn fresh_access_token(
+    config: &Config,
+    db: Arc<Database>,
+    token: &SocialOAuthToken,
+) -> Result<String, MicroClawError> {
+    if !is_expired(token) {
+        return Ok(token.access_token.clone());
+    }
+    let refresh_token = token.refresh_token.as_deref().ok_or_else(|| {
+        MicroClawError::ToolExecution("Token expired and no refresh token is stored".into())
+    })?;
+    let social = config
+        .social
+        .as_ref()
+        .ok_or_else(|| MicroClawError::Config("Social OAuth not configured".into()))?;
+    let (client_id, client_secret) = client_credentials(social, &token.platform)?;
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| MicroClawError::ToolExecution(e.to_string()))?;
+    let body = token_request(
+        &client,
+        &token.platform,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await?;
+    let access_token = body
+        .get("access_token")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| MicroClawError::ToolExecution("No access_token in response".into()))?
+        .to_string();
+    let new_refresh = body
+        .get("refresh_token")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    let expires_at = expires_at_from(&body);
+    let (platform, chat_id, access) = (token.platform.clone(), token.chat_id, access_token.clone());
+    call_blocking(db, move |d| {
+        d.update_social_access_token(
+            &platform,
+            chat_id,
+            &access,
+            new_refresh.as_deref(),
+            expires_at.as_deref(),
+        )
+    })
+    .await?;
+    Ok(access_token)
+}
+
+/// Outcome of asking the provider to revoke a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revocation {
+    Revoked,
+    /// The platform has no revoke endpoint for this kind of token.
+    Unsupported,
+    Failed(String),
+}
+
+impl Revocation {
+    pub fn describe(&self, platform: &str) -> String {
+        match self {
+            Revocation::Revoked => "token revoked with the provider".into(),
+            Revocation::Unsupported => format!(
+                "{platform} has no token revoke API; remove the app from your account settings to fully revoke access"
+            ),
+            Revocation::Failed(e) => format!("provider revocation failed: {e}"),
+        }
+    }
+}
+
+/// Call the provider's revoke endpoint (TikTok, LinkedIn and Google support it; Instagram and
+/// Spotify do not).
+pub async fn revoke_token(config: &Config, platform: &str, token: &str) -> Revocation {
+    if platform == "google" {
+        let client = match reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+        {
+            Ok(c) => c,
+            Err(e) => return Revocation::Failed(e.to_string()),
+        };
+        return match client
+            .post("https://oauth2.googleapis.com/revoke")
+            .form(&[("token", token)])
+            .send()
+            .await
+        {
+            Ok(resp) if resp.status().is_success() => Revocation::Revoked,
+            Ok(resp) => Revocation::Failed(format!("HTTP {}", resp.status())),
+            Err(e) => Revocation::Failed(e.to_string()),
+        };
+    }
+    if !matches!(platform, "tiktok" | "linkedin") {
+        return Revocation::Unsupported;
+    }
+    let Some(social) = config.social.as_ref() else {
+        return Revocation::Failed("Social OAuth not configured".into());
+    };
+    let (url, id_key, cfg) = match platform {
+        "tiktok" => (
+            "https://open.tiktokapis.com/v2/oauth/revoke/",
+            "client_key",
+            &social.tiktok,
+        ),
+        "linkedin" => (
+            "https://www.linkedin.com/oauth/v2/revoke",
+            "client_id",
+            &social.linkedin,
+        ),
+        _ => return Revocation::Unsupported,
+    };
+    let (Some(client_id), Some(client_secret)) =
+        (cfg.client_id.as_deref(), cfg.client_secret.as_deref())
+    else {
+        return Revocation::Failed(format!("{platform} client credentials not set"));
+    };
+    let client = match reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => return Revocation::Failed(e.to_string()),
+    };
+    let params = [
+        (id_key, client_id),
+        ("client_secret", client_secret),
+        ("token", token),
+    ];
+    match client.post(url).form(&params).send().await {
+        Ok(resp) if resp.status().is_success() => Revocation::Revoked,
+        Ok(resp) => Revocation::Failed(format!("HTTP {}", resp.status())),
+        Err(e) => Revocation::Failed(e.to_string()),
+    }
+}
+
+/// Revoke a chat's token with the provider and delete it locally (even if the provider call
+/// fails, so the bot can no longer use it). Returns `None` when the chat has no token for the
+/// platform.
+pub async fn disconnect(
+    config: &Config,
+    db: Arc<Database>,
+    platform: &str,
+    chat_id: i64,
+) -> Result<Option<Revocation>, MicroClawError> {
+    let p = platform.to_string();
+    let Some(token) = call_blocking(db.clone(), move |d| d.get_social_token(&p, chat_id)).await?
+    else {
+        return Ok(None);
+    };
+    let revocation = revoke_token(config, platform, &token.access_token).await;
+    let p = platform.to_string();
+    call_blocking(db, move |d| d.delete_social_token(&p, chat_id)).await?;
+    Ok(Some(revocation))
+}
+
+/// Public view of a stored token: never includes the token itself.
+pub fn connection_json(token: &SocialOAuthToken) -> serde_json::Value {
+    let expired = token.expires_at.as_ref().map(|_| is_expired(token));
+    let scopes: Vec<&str> = token
+        .scopes
+        .as_deref()
+        .unwrap_or("")
+        .split([',', ' '])
+        .filter(|s| !s.is_empty())
+        .collect();
+    serde_json::json!({
+        "platform": token.platform,
+        "chat_id": token.chat_id,
+        "scopes": scopes,
+        "expires_at": token.expires_at,
+        "expired": expired,
+        "has_refresh_token": token.refresh_token.is_some(),
+        "connected_at": token.connected_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge_is_base64url_sha256() {
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mJ0Tn3EjkAH6t9tA3Zn_v9iYwVPCn4"),
+            "KuCWBzuSsNF671MS1JejT14s4-pJ1oIyaXl_Lco-YhI"
+        );
+        let verifier = new_code_verifier();
+        assert_eq!(verifier.len(), 64);
+        assert_ne!(verifier, new_code_verifier());
+    }
+
+    #[tokio::test]
+    async fn test_start_authorization_issues_single_use_bound_state() {
+        let dir = std::env::temp_dir().join(format!("microclaw_oauth_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let link = start_authorization(&config, db.clone(), "tiktok", 42, None)
+            .await
+            .unwrap();
+        let (path, state) = link.split_once("?state=").unwrap();
+        let state = state.to_string();
+        assert!(path.ends_with("/api/oauth/authorize/tiktok"), "{link}");
+        assert!(!state.contains('&'), "{link}");
+
+        assert!(db
+            .bind_oauth_pending_state(&state, "linkedin", "b1")
+            .unwrap()
+            .is_none());
+        let pending = db
+            .bind_oauth_pending_state(&state, "tiktok", "b1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(pending.chat_id, 42);
+        assert!(pending.code_verifier.is_some());
+        // A second browser cannot reuse the link.
+        assert!(db
+            .bind_oauth_pending_state(&state, "tiktok", "b2")
+            .unwrap()
+            .is_none());
+
+        // Only the browser that started the flow consumes it.
+        assert!(db
+            .consume_oauth_pending_state(&state, "b2")
+            .unwrap()
+            .is_none());
+        let consumed = db
+            .consume_oauth_pending_state(&state, "b1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(consumed.browser_binding.as_deref(), Some("b1"));
+        assert!(db
+            .consume_oauth_pending_state(&state, "b1")
+            .unwrap()
+            .is_none());
+
+        db.create_oauth_pending_state(
+            "old",
+            "tiktok",
+            42,
+            "2000-01-01T00:00:00+00:00",
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(db
+            .bind_oauth_pending_state("old", "tiktok", "b1")
+            .unwrap()
+            .is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,635 @@
+//! `microclaw export-state` / `microclaw import-state`: the whole assistant as one `.tar.gz`, so
+//! moving to new hardware is one command on each side. The archive holds:
+//!
+//! - `manifest.json`: archive format, the MicroClaw version that wrote it, original workspace path
+//! - `config.template.env`: the `.env` with every secret value blanked
+//! - `secrets.enc`: the blanked values, sealed with the state passphrase (same AES-256-GCM
+//!   scheme as workspace encryption)
+//! - `workspace/`: the workspace layout, with `runtime/microclaw.db` as a consistent snapshot,
+//!   memory files, skills, `AGENTS.md` and (unless `--no-shared`) `shared/`
+//!
+//! Importing checks the manifest first: a newer archive format is refused, and so is an archive
+//! written by a newer MicroClaw (its database may have columns this build does not know) unless
+//! `--force`. Nothing in the target is touched until the whole archive has unpacked and the
+//! secrets have decrypted. The restored `.env` points `WORKSPACE_DIR` at the new location.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::workspace_crypto;
+
+/// Bump when the archive layout changes in a way older builds cannot read.
+pub const FORMAT_VERSION: u32 = 1;
+pub const PASSPHRASE_ENV: &str = "MICROCLAW_STATE_PASSPHRASE";
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const MANIFEST: &str = "manifest.json";
+const CONFIG_TEMPLATE: &str = "config.template.env";
+const SECRETS: &str = "secrets.enc";
+const WORKSPACE: &str = "workspace";
+const DB_FILE: &str = "microclaw.db";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub microclaw_version: String,
+    pub created_at: String,
+    pub workspace_dir: String,
+    pub has_secrets: bool,
+    pub includes_shared: bool,
+}
+
+pub struct ExportOptions {
+    pub include_shared: bool,
+    /// Seals the secrets; without it the archive only carries the blanked template.
+    pub passphrase: Option<String>,
+}
+
+pub struct ImportOptions {
+    /// Replace existing state and skip the version check (the format check stays).
+    pub force: bool,
+    /// Restore the config with secrets left blank instead of requiring the passphrase.
+    pub skip_secrets: bool,
+    pub passphrase: Option<String>,
+}
+
+/// Whether a `.env` key holds a credential.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    ["TOKEN", "SECRET", "PASSWORD", "PASSPHRASE", "_KEY_"]
+        .iter()
+        .any(|part| key.contains(part))
+        || key.ends_with("KEY")
+}
+
+/// The byte offset of `=` and the key of a `KEY=value` line (`export ` prefix allowed).
+fn env_key(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        return None;
+    }
+    let eq = line.find('=')?;
+    let key = line[..eq].trim();
+    let key = key.strip_prefix("export ").unwrap_or(key).trim();
+    (!key.is_empty()).then_some((eq, key))
+}
+
+/// Split `.env` text into a template with secret values blanked and the (key, raw value) pairs
+/// that were blanked.
+pub fn split_secrets(env_text: &str) -> (String, Vec<(String, String)>) {
+    let mut template = String::new();
+    let mut secrets = Vec::new();
+    for line in env_text.lines() {
+        match env_key(line) {
+            Some((eq, key)) if is_secret_key(key) => {
+                let value = line[eq + 1..].trim();
+                if !value.is_empty() && value != "\"\"" {
+                    secrets.push((key.to_string(), value.to_string()));
+                }
+                template.push_str(&line[..=eq]);
+            }
+            _ => template.push_str(line),
+        }
+        template.push('\n');
+    }
+    (template, secrets)
+}
+
+/// Put secrets back into a template and point `WORKSPACE_DIR` at `workspace_dir`.
+pub fn fill_template(template: &str, secrets: &[(String, String)], workspace_dir: &str) -> String {
+    let workspace_value = if workspace_dir.contains(' ') || workspace_dir.contains('#') {
+        format!("\"{workspace_dir}\"")
+    } else {
+        workspace_dir.to_string()
+    };
+    let mut out = String::new();
+    let mut has_workspace = false;
+    for line in template.lines() {
+        match env_key(line) {
+            Some((eq, "WORKSPACE_DIR")) => {
+                has_workspace = true;
+                out.push_str(&format!("{}={workspace_value}", &line[..eq]));
+            }
+            Some((eq, key)) => match secrets.iter().find(|(k, _)| k == key) {
+                Some((_, value)) => out.push_str(&format!("{}={value}", &line[..eq])),
+                None => out.push_str(line),
+            },
+            None => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    if !has_workspace {
+        out.push_str(&format!("WORKSPACE_DIR={workspace_value}\n"));
+    }
+    out
+}
+
+fn parse_version(v: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = v.trim().split(['.', '-']).map(|p| p.parse::<u64>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+/// Refuse archives this build cannot read. `force` only overrides the version check.
+pub fn check_compatible(manifest: &Manifest, current: &str, force: bool) -> Result<(), String> {
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!(
+            "archive format {} is newer than this build reads ({FORMAT_VERSION}); upgrade microclaw first",
+            manifest.format
+        ));
+    }
+    if force {
+        return Ok(());
+    }
+    let archive = parse_version(&manifest.microclaw_version)
+        .ok_or_else(|| format!("unreadable version '{}'", manifest.microclaw_version))?;
+    match parse_version(current) {
+        Some(current_v) if archive > current_v => Err(format!(
+            "archive was written by microclaw {} but this is {current}; upgrade first or pass --force",
+            manifest.microclaw_version
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn append_bytes<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    tar.append_data(&mut header, name, data)
+}
+
+/// Add every regular file under `dir` as `prefix/<relative path>`, skipping entries for which
+/// `skip(relative path)` is true. Symlinks are left out.
+fn append_tree<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    dir: &Path,
+    rel: &Path,
+    prefix: &str,
+    skip: &dyn Fn(&Path) -> bool,
+) -> std::io::Result<usize> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir.join(rel))?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    let mut count = 0;
+    for entry in entries {
+        let child = rel.join(entry.file_name());
+        if skip(&child) {
+            continue;
+        }
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            count += append_tree(tar, dir, &child, prefix, skip)?;
+        } else if kind.is_file() {
+            let name = Path::new(prefix).join(&child);
+            tar.append_path_with_name(entry.path(), name)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Write the archive to `out`. Returns the manifest and the number of workspace files.
+pub fn export_state(
+    config: &Config,
+    db: &Database,
+    env_text: &str,
+    out: &Path,
+    opts: &ExportOptions,
+) -> anyhow::Result<(Manifest, usize)> {
+    let root = config.workspace_root_absolute();
+    let runtime = PathBuf::from(config.runtime_data_dir());
+    let (template, secrets) = split_secrets(env_text);
+    let sealed = match &opts.passphrase {
+        Some(passphrase) if !secrets.is_empty() => Some(workspace_crypto::encrypt(
+            serde_json::to_string(&secrets)?.as_bytes(),
+            passphrase,
+        )?),
+        _ => None,
+    };
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        microclaw_version: VERSION.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        workspace_dir: root.to_string_lossy().to_string(),
+        has_secrets: sealed.is_some(),
+        includes_shared: opts.include_shared,
+    };
+
+    let snapshot_dir =
+        std::env::temp_dir().join(format!("microclaw_state_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&snapshot_dir)?;
+    let snapshot = snapshot_dir.join(DB_FILE);
+    let tmp_out = out.with_extension("partial");
+    // The archive may be written inside the workspace; keep it out of itself
+    let written = [
+        std::path::absolute(out).unwrap_or_else(|_| out.to_path_buf()),
+        std::path::absolute(&tmp_out).unwrap_or_else(|_| tmp_out.clone()),
+    ];
+    let result = (|| -> anyhow::Result<usize> {
+        db.snapshot_to(&snapshot)?;
+        let file = File::create(&tmp_out)?;
+        let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        append_bytes(
+            &mut tar,
+            MANIFEST,
+            serde_json::to_string_pretty(&manifest)?.as_bytes(),
+        )?;
+        append_bytes(&mut tar, CONFIG_TEMPLATE, template.as_bytes())?;
+        if let Some(sealed) = &sealed {
+            append_bytes(&mut tar, SECRETS, sealed)?;
+        }
+
+        tar.append_path_with_name(&snapshot, format!("{WORKSPACE}/runtime/{DB_FILE}"))?;
+        let mut files = 1;
+        if runtime.is_dir() {
+            let skip_runtime = |rel: &Path| {
+                let name = rel.to_string_lossy();
+                name.starts_with(DB_FILE) || rel == Path::new("logs")
+            };
+            files += append_tree(
+                &mut tar,
+                &runtime,
+                Path::new(""),
+                &format!("{WORKSPACE}/runtime"),
+                &skip_runtime,
+            )?;
+        }
+        let skip_root = |rel: &Path| {
+            let top = rel.to_string_lossy();
+            top == "runtime"
+                || top.starts_with("runtime.enc")
+                || (top == "shared" && !opts.include_shared)
+                || written.contains(&root.join(rel))
+        };
+        if root.is_dir() {
+            files += append_tree(&mut tar, &root, Path::new(""), WORKSPACE, &skip_root)?;
+        }
+        tar.into_inner()?.finish()?;
+        Ok(files)
+    })();
+    let _ = std::fs::remove_dir_all(&snapshot_dir);
+    match result {
+        Ok(files) => {
+            std::fs::rename(&tmp_out, out)?;
+            Ok((manifest, files))
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_out);
+            Err(e)
+        }
+    }
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Restore an archive into `workspace` and write the config to `config_path`. An existing config
+/// replaced with `force` is kept as `<config>.bak`.
+pub fn import_state(
+    archive: &Path,
+    workspace: &Path,
+    config_path: &Path,
+    opts: &ImportOptions,
+) -> anyhow::Result<Manifest> {
+    let has_state = workspace.join("runtime").join(DB_FILE).exists()
+        || workspace.join("runtime.enc").exists()
+        || config_path.exists();
+    if has_state && !opts.force {
+        anyhow::bail!(
+            "{} or {} already holds MicroClaw state; pass --force to replace it",
+            workspace.display(),
+            config_path.display()
+        );
+    }
+
+    let parent = workspace.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    let staging = parent.join(format!(".microclaw-import-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&staging)?;
+    let result = unpack_and_apply(archive, &staging, workspace, config_path, opts);
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn unpack_and_apply(
+    archive: &Path,
+    staging: &Path,
+    workspace: &Path,
+    config_path: &Path,
+    opts: &ImportOptions,
+) -> anyhow::Result<Manifest> {
+    let file = File::open(archive)
+        .map_err(|e| anyhow::anyhow!("cannot open {}: {e}", archive.display()))?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    let mut manifest: Option<Manifest> = None;
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if manifest.is_none() {
+            if path != Path::new(MANIFEST) {
+                anyhow::bail!("not a MicroClaw state archive (no manifest)");
+            }
+            let mut text = String::new();
+            entry.read_to_string(&mut text)?;
+            let found: Manifest = serde_json::from_str(&text)
+                .map_err(|e| anyhow::anyhow!("unreadable manifest: {e}"))?;
+            check_compatible(&found, VERSION, opts.force).map_err(|e| anyhow::anyhow!(e))?;
+            if found.has_secrets && opts.passphrase.is_none() && !opts.skip_secrets {
+                anyhow::bail!(
+                    "the archive holds encrypted secrets: set {PASSPHRASE_ENV}, or pass --no-secrets to restore the config without them"
+                );
+            }
+            manifest = Some(found);
+            continue;
+        }
+        let kind = entry.header().entry_type();
+        if kind.is_file() || kind.is_dir() {
+            // unpack_in refuses paths that would land outside `staging`
+            entry.unpack_in(staging)?;
+        }
+    }
+    let manifest = manifest.ok_or_else(|| anyhow::anyhow!("empty archive"))?;
+
+    let secrets: Vec<(String, String)> = match (&opts.passphrase, opts.skip_secrets) {
+        (Some(passphrase), false) if staging.join(SECRETS).exists() => {
+            let plain =
+                workspace_crypto::decrypt(&std::fs::read(staging.join(SECRETS))?, passphrase)?;
+            serde_json::from_slice(&plain)?
+        }
+        _ => Vec::new(),
+    };
+    let template = std::fs::read_to_string(staging.join(CONFIG_TEMPLATE)).unwrap_or_default();
+    let workspace_abs = std::path::absolute(workspace)?;
+    let config_text = fill_template(&template, &secrets, &workspace_abs.to_string_lossy());
+
+    // Everything read back; now replace the target
+    std::fs::create_dir_all(workspace)?;
+    if opts.force {
+        // The encrypted archive and any half-written copies of it
+        for entry in std::fs::read_dir(workspace)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "runtime.enc"
+                || (name.starts_with("runtime.enc.") && name.ends_with(".tmp"))
+            {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+    }
+    let unpacked = staging.join(WORKSPACE);
+    if unpacked.is_dir() {
+        for entry in std::fs::read_dir(&unpacked)? {
+            let entry = entry?;
+            let dst = workspace.join(entry.file_name());
+            if dst.exists() {
+                remove_path(&dst)?;
+            }
+            std::fs::rename(entry.path(), &dst)?;
+        }
+    }
+    if config_path.exists() {
+        let mut backup = config_path.as_os_str().to_owned();
+        backup.push(".bak");
+        std::fs::rename(config_path, PathBuf::from(backup))?;
+    }
+    if let Some(dir) = config_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(config_path, config_text)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(config_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(manifest)
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn state_passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+/// The first argument that is neither a flag nor a flag's value.
+fn positional<'a>(args: &'a [String], value_flags: &[&str]) -> Option<&'a str> {
+    let mut i = 0;
+    while i < args.len() {
+        if value_flags.contains(&args[i].as_str()) {
+            i += 2;
+        } else if args[i].starts_with("--") {
+            i += 1;
+        } else {
+            return Some(&args[i]);
+        }
+    }
+    None
+}
+
+/// Entry point for `microclaw export-state [<out.tar.gz>] [--no-shared] [--no-secrets]`.
+pub fn run_export_cli(args: &[String]) -> anyhow::Result<()> {
+    let usage = format!("Usage: microclaw export-state [<out.tar.gz>] [--no-shared] [--no-secrets]\n\nWrites the database, memory files, skills, shared files and a config template into one archive for `microclaw import-state` on another machine. Secret config values are sealed with the passphrase in {PASSPHRASE_ENV}; pass --no-secrets to leave them out instead. The archive still holds chat history in plain form, so keep it private.");
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{usage}");
+        return Ok(());
+    }
+    let skip_secrets = args.iter().any(|a| a == "--no-secrets");
+    let passphrase = state_passphrase();
+    if passphrase.is_none() && !skip_secrets {
+        anyhow::bail!("set {PASSPHRASE_ENV} to seal the secrets, or pass --no-secrets\n\n{usage}");
+    }
+    let out = positional(args, &[]).map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "microclaw-state-{VERSION}-{}.tar.gz",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+
+    let config = Config::load()?;
+    let config_path = Config::resolve_config_path()?.unwrap_or_else(|| PathBuf::from("./.env"));
+    let env_text = std::fs::read_to_string(&config_path).unwrap_or_default();
+    let mounted = workspace_crypto::mount(&config)?;
+    let db = Database::new(&config.runtime_data_dir())?;
+    let opts = ExportOptions {
+        include_shared: !args.iter().any(|a| a == "--no-shared"),
+        passphrase: if skip_secrets { None } else { passphrase },
+    };
+    let result = export_state(&config, &db, &env_text, &out, &opts);
+    if mounted {
+        workspace_crypto::unmount(&config, Some(&db))?;
+    }
+    let (manifest, files) = result?;
+    println!(
+        "Exported {files} workspace files{} to {} (microclaw {}).",
+        if manifest.has_secrets {
+            " and sealed secrets"
+        } else {
+            ""
+        },
+        out.display(),
+        manifest.microclaw_version
+    );
+    Ok(())
+}
+
+/// Entry point for `microclaw import-state <archive> [--workspace <dir>] [--config <path>] [--force] [--no-secrets]`.
+pub fn run_import_cli(args: &[String]) -> anyhow::Result<()> {
+    let usage = format!("Usage: microclaw import-state <archive.tar.gz> [--workspace <dir>] [--config <path>] [--force] [--no-secrets]\n\nRestores an archive from `microclaw export-state`. The workspace defaults to ./workspace and the config to $MICROCLAW_CONFIG or ./.env; WORKSPACE_DIR in the restored config points at the new workspace. Set {PASSPHRASE_ENV} to restore the secrets. --force replaces existing state (the old config is kept as <config>.bak) and accepts archives from newer versions.");
+    let value_flags = ["--workspace", "--config"];
+    let Some(archive) = positional(args, &value_flags) else {
+        println!("{usage}");
+        return Ok(());
+    };
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{usage}");
+        return Ok(());
+    }
+    let workspace = PathBuf::from(flag_value(args, "--workspace").unwrap_or("./workspace"));
+    let config_path = flag_value(args, "--config")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("MICROCLAW_CONFIG").ok().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("./.env"));
+    let opts = ImportOptions {
+        force: args.iter().any(|a| a == "--force"),
+        skip_secrets: args.iter().any(|a| a == "--no-secrets"),
+        passphrase: state_passphrase(),
+    };
+    let manifest = import_state(Path::new(archive), &workspace, &config_path, &opts)?;
+    println!(
+        "Restored state from microclaw {} (exported {} from {}) into {}; config written to {}.",
+        manifest.microclaw_version,
+        manifest.created_at,
+        manifest.workspace_dir,
+        workspace.display(),
+        config_path.display()
+    );
+    if manifest.has_secrets && opts.skip_secrets {
+        println!(
+            "Secrets were not restored; fill in the blank keys in the config before starting."
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_split_and_refill() {
+        let env = "# LLM\nLLM_API_KEY=sk-123\nTELEGRAM_BOT_TOKEN=\"12:ab\"\nexport SLACK_APP_TOKEN=xapp\nEMPTY_TOKEN=\nTIMEZONE=Europe/Berlin\nWORKSPACE_DIR=/old/ws\n";
+        let (template, secrets) = split_secrets(env);
+        assert!(!template.contains("sk-123") && !template.contains("12:ab"));
+        assert!(template.contains("LLM_API_KEY=\n") && template.contains("TIMEZONE=Europe/Berlin"));
+        assert_eq!(secrets.len(), 3);
+        let restored = fill_template(&template, &secrets, "/new ws");
+        assert!(restored.contains("LLM_API_KEY=sk-123\n"));
+        assert!(restored.contains("TELEGRAM_BOT_TOKEN=\"12:ab\"\n"));
+        assert!(restored.contains("export SLACK_APP_TOKEN=xapp\n"));
+        assert!(restored.contains("WORKSPACE_DIR=\"/new ws\"\n"));
+        assert!(!restored.contains("/old/ws"));
+    }
+
+    #[test]
+    fn test_version_compatibility() {
+        let mut manifest = Manifest {
+            format: FORMAT_VERSION,
+            microclaw_version: "0.0.36".into(),
+            created_at: String::new(),
+            workspace_dir: String::new(),
+            has_secrets: false,
+            includes_shared: true,
+        };
+        assert!(check_compatible(&manifest, "0.0.37", false).is_ok());
+        manifest.microclaw_version = "0.1.0".into();
+        assert!(check_compatible(&manifest, "0.0.37", false)
+            .unwrap_err()
+            .contains("upgrade first"));
+        assert!(check_compatible(&manifest, "0.0.37", true).is_ok());
+        manifest.format = FORMAT_VERSION + 1;
+        assert!(check_compatible(&manifest, "0.0.37", true).is_err());
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let base = std::env::temp_dir().join(format!("microclaw_state_{}", uuid::Uuid::new_v4()));
+        let old_ws = base.join("old");
+        std::fs::create_dir_all(old_ws.join("skills/weather")).unwrap();
+        std::fs::create_dir_all(old_ws.join("shared")).unwrap();
+        std::fs::write(old_ws.join("skills/weather/SKILL.md"), "forecast").unwrap();
+        std::fs::write(old_ws.join("shared/notes.md"), "big file").unwrap();
+        std::fs::write(old_ws.join("AGENTS.md"), "be kind").unwrap();
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = old_ws.to_string_lossy().to_string();
+        let db = Database::new(&config.runtime_data_dir()).unwrap();
+        db.upsert_chat(7, Some("family"), "private").unwrap();
+        std::fs::create_dir_all(old_ws.join("runtime/groups/7")).unwrap();
+        std::fs::write(old_ws.join("runtime/groups/7/AGENTS.md"), "likes tea").unwrap();
+
+        let out = base.join("state.tar.gz");
+        let opts = ExportOptions {
+            include_shared: false,
+            passphrase: Some("pw".into()),
+        };
+        let env = "LLM_API_KEY=sk-1\nWORKSPACE_DIR=/old\n";
+        let (manifest, _) = export_state(&config, &db, env, &out, &opts).unwrap();
+        assert!(manifest.has_secrets);
+
+        let new_ws = base.join("new");
+        let env_path = base.join("new.env");
+        let mut import_opts = ImportOptions {
+            force: false,
+            skip_secrets: false,
+            passphrase: Some("wrong".into()),
+        };
+        assert!(import_state(&out, &new_ws, &env_path, &import_opts).is_err());
+        assert!(!env_path.exists() && !new_ws.join("AGENTS.md").exists());
+
+        import_opts.passphrase = Some("pw".into());
+        import_state(&out, &new_ws, &env_path, &import_opts).unwrap();
+        let restored = std::fs::read_to_string(&env_path).unwrap();
+        assert!(restored.contains("LLM_API_KEY=sk-1"));
+        assert!(restored.contains(&format!("WORKSPACE_DIR={}", new_ws.display())));
+        assert_eq!(
+            std::fs::read_to_string(new_ws.join("runtime/groups/7/AGENTS.md")).unwrap(),
+            "likes tea"
+        );
+        assert!(new_ws.join("skills/weather/SKILL.md").exists());
+        assert!(!new_ws.join("shared/notes.md").exists());
+        let restored_db = Database::new(new_ws.join("runtime").to_str().unwrap()).unwrap();
+        assert_eq!(
+            restored_db.get_chat_type(7).unwrap().as_deref(),
+            Some("private")
+        );
+
+        // Existing state is only replaced with --force
+        assert!(import_state(&out, &new_ws, &env_path, &import_opts).is_err());
+        import_opts.force = true;
+        import_state(&out, &new_ws, &env_path, &import_opts).unwrap();
+        assert!(base.join("new.env.bak").exists());
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,49 @@
+//! Character-safe text shortening shared by reports, tool output and notifications.
+
+/// `text` cut to its first `max_chars` characters, with `…` appended when something was cut.
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    truncate_chars_with(text, max_chars, "…")
+}
+
+/// `text` cut to its first `max_chars` characters, with `marker` appended when something was
+/// cut.
+pub fn truncate_chars_with(text: &str, max_chars: usize, marker: &str) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}{marker}", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// The last `max_chars` characters of `text`.
+pub fn tail_chars(text: &str, max_chars: usize) -> &str {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text;
+    }
+    let start = text
+        .char_indices()
+        .nth(count - max_chars)
+        .map_or(0, |(i, _)| i);
+    &text[start..]
+}
+
+/// `text` on one line: runs of whitespace, newlines included, become single spaces.
+pub fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars_counts_characters() {
+        assert_eq!(truncate_chars("héllo", 5), "héllo");
+        assert_eq!(truncate_chars("héllo wörld", 5), "héllo…");
+        assert_eq!(truncate_chars_with("ééé", 2, "..."), "éé...");
+        assert_eq!(truncate_chars("", 0), "");
+        assert_eq!(tail_chars("héllo wörld", 5), "wörld");
+        assert_eq!(tail_chars("hé", 5), "hé");
+        assert_eq!(one_line("  a\n\tb  c "), "a b c");
+    }
+}
@@ -0,0 +1,258 @@
+//! Thinking levels and transcripts. A reply's `<think>` reasoning is split off before the session
+//! is saved, so it never counts against the context again, and kept per chat in
+//! `thinking_transcripts` for `/thinking last` and the `show_last_thinking` tool. What the chat
+//! sees is set by its level: `off` (stripped), `summary` (a one-line excerpt) or `full`. The
+//! level is `show_thinking` from the config unless the chat overrides it with `/thinking`.
+
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::config::{Config, ThinkingLevel};
+use crate::db::{call_blocking, Database};
+
+/// `chat_settings` key holding the chat's thinking level override.
+pub const SETTING: &str = "show_thinking";
+/// Transcripts kept per chat; older ones are dropped.
+const KEEP_PER_CHAT: usize = 20;
+/// Length of the excerpt shown at the `summary` level.
+const SUMMARY_CHARS: usize = 200;
+
+/// Split `text` into the reply without `<think>` blocks and the reasoning inside them.
+/// An unclosed `<think>` runs to the end of the text.
+pub fn split_thinking(text: &str) -> (String, String) {
+    let mut reply = String::with_capacity(text.len());
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<think>") {
+        reply.push_str(&rest[..start]);
+        let inner = &rest[start + "<think>".len()..];
+        match inner.find("</think>") {
+            Some(end) => {
+                blocks.push(inner[..end].trim().to_string());
+                rest = &inner[end + "</think>".len()..];
+            }
+            None => {
+                blocks.push(inner.trim().to_string());
+                rest = "";
+            }
+        }
+    }
+    reply.push_str(rest);
+    let thinking = blocks
+        .into_iter()
+        .filter(|b| !b.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    (reply.trim().to_string(), thinking)
+}
+
+/// First words of the reasoning on one line, cut at a word boundary.
+pub fn summarize(thinking: &str) -> String {
+    let flat = thinking.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= SUMMARY_CHARS {
+        return flat;
+    }
+    let cut: String = flat.chars().take(SUMMARY_CHARS).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > SUMMARY_CHARS / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([',', ';', ':', ' ']))
+}
+
+/// The reply as the chat sees it at `level`.
+pub fn apply(level: ThinkingLevel, text: &str) -> String {
+    match level {
+        ThinkingLevel::Full => text.to_string(),
+        ThinkingLevel::Off => split_thinking(text).0,
+        ThinkingLevel::Summary => {
+            let (reply, thinking) = split_thinking(text);
+            if thinking.is_empty() {
+                reply
+            } else {
+                format!("(Thinking: {})\n\n{reply}", summarize(&thinking))
+            }
+        }
+    }
+}
+
+/// The chat's level: its `/thinking` override, else `show_thinking` from the config.
+pub async fn level_for(config: &Config, db: Arc<Database>, chat_id: i64) -> ThinkingLevel {
+    call_blocking(db, move |d| d.get_chat_setting(chat_id, SETTING))
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| ThinkingLevel::parse(&v))
+        .unwrap_or(config.show_thinking)
+}
+
+/// Keep a turn's reasoning for later review.
+pub async fn record(db: Arc<Database>, chat_id: i64, persona_id: i64, thinking: String) {
+    if thinking.trim().is_empty() {
+        return;
+    }
+    if let Err(e) = call_blocking(db, move |d| {
+        d.save_thinking(chat_id, persona_id, &thinking, KEEP_PER_CHAT)
+    })
+    .await
+    {
+        warn!("thinking: failed to store transcript for chat {chat_id}: {e}");
+    }
+}
+
+/// The chat's `count` newest transcripts, oldest first, each under its timestamp.
+pub async fn last_thinking(db: Arc<Database>, chat_id: i64, count: usize) -> Result<String, String> {
+    let rows = call_blocking(db, move |d| d.recent_thinking(chat_id, count))
+        .await
+        .map_err(|e| format!("Failed to load thinking: {e}"))?;
+    if rows.is_empty() {
+        return Ok("No thinking recorded for this chat yet.".into());
+    }
+    let sections: Vec<String> = rows
+        .iter()
+        .rev()
+        .map(|(content, created_at)| {
+            let when = chrono::DateTime::parse_from_rfc3339(created_at)
+                .map(|t| {
+                    t.with_timezone(&chrono::Utc)
+                        .format("%Y-%m-%d %H:%M UTC")
+                        .to_string()
+                })
+                .unwrap_or_else(|_| created_at.clone());
+            format!("Thinking ({when}):\n{content}")
+        })
+        .collect();
+    Ok(sections.join("\n\n"))
+}
+
+/// Handle `/thinking [off|summary|full|default|last]`.
+pub async fn handle_thinking_command(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    text: &str,
+) -> String {
+    let arg = text
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("")
+        .to_lowercase();
+    let value = match arg.as_str() {
+        "" => {
+            let level = level_for(config, db, chat_id).await;
+            return format!(
+                "Thinking is {} in this chat. Use /thinking off, summary or full (or default), and /thinking last to read the last reasoning.",
+                level.as_str()
+            );
+        }
+        "last" => {
+            return last_thinking(db, chat_id, 1)
+                .await
+                .unwrap_or_else(|e| e);
+        }
+        "default" => None,
+        other => match ThinkingLevel::parse(other) {
+            Some(level) => Some(level.as_str()),
+            None => return "Usage: /thinking [off|summary|full|default|last]".into(),
+        },
+    };
+    match call_blocking(db, move |d| d.set_chat_setting(chat_id, SETTING, value)).await {
+        Ok(()) => match value {
+            Some(level) => format!("Thinking set to {level} for this chat."),
+            None => format!(
+                "Thinking back to the default ({}) for this chat.",
+                config.show_thinking.as_str()
+            ),
+        },
+        Err(e) => format!("Failed to update thinking setting: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n").unwrap()
+    }
+
+    #[test]
+    fn test_split_and_apply_levels() {
+        let text = "<think>The user asks about rain.\nCheck the forecast.</think>Bring an umbrella.";
+        assert_eq!(
+            split_thinking(text),
+            (
+                "Bring an umbrella.".to_string(),
+                "The user asks about rain.\nCheck the forecast.".to_string()
+            )
+        );
+        assert_eq!(apply(ThinkingLevel::Off, text), "Bring an umbrella.");
+        assert_eq!(apply(ThinkingLevel::Full, text), text);
+        assert_eq!(
+            apply(ThinkingLevel::Summary, text),
+            "(Thinking: The user asks about rain. Check the forecast.)\n\nBring an umbrella."
+        );
+        assert_eq!(apply(ThinkingLevel::Summary, "Plain"), "Plain");
+        assert_eq!(split_thinking("a<think>x</think>b<think>y").1, "x\n\ny");
+
+        let long = "word ".repeat(100);
+        let summary = summarize(&long);
+        assert!(summary.ends_with("word…") && summary.chars().count() <= SUMMARY_CHARS + 1);
+    }
+
+    #[test]
+    fn test_level_accepts_old_booleans() {
+        let config: Config = serde_yaml::from_str(
+            "telegram_bot_token: tok\nbot_username: bot\napi_key: key\nshow_thinking: true\n",
+        )
+        .unwrap();
+        assert_eq!(config.show_thinking, ThinkingLevel::Full);
+        let config: Config = serde_yaml::from_str(
+            "telegram_bot_token: tok\nbot_username: bot\napi_key: key\nshow_thinking: summary\n",
+        )
+        .unwrap();
+        assert_eq!(config.show_thinking, ThinkingLevel::Summary);
+        assert_eq!(test_config().show_thinking, ThinkingLevel::Off);
+        assert!(serde_yaml::from_str::<Config>(
+            "telegram_bot_token: tok\nbot_username: bot\napi_key: key\nshow_thinking: loud\n"
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_command_and_transcripts() {
+        let dir = std::env::temp_dir().join(format!("microclaw_thinking_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config = test_config();
+
+        assert!(handle_thinking_command(&config, db.clone(), 5, "/thinking")
+            .await
+            .starts_with("Thinking is off"));
+        assert_eq!(
+            handle_thinking_command(&config, db.clone(), 5, "/thinking summary").await,
+            "Thinking set to summary for this chat."
+        );
+        assert_eq!(level_for(&config, db.clone(), 5).await, ThinkingLevel::Summary);
+        assert_eq!(level_for(&config, db.clone(), 6).await, ThinkingLevel::Off);
+        handle_thinking_command(&config, db.clone(), 5, "/thinking default").await;
+        assert_eq!(level_for(&config, db.clone(), 5).await, ThinkingLevel::Off);
+
+        assert_eq!(
+            handle_thinking_command(&config, db.clone(), 5, "/thinking last").await,
+            "No thinking recorded for this chat yet."
+        );
+        for i in 0..(KEEP_PER_CHAT + 2) {
+            record(db.clone(), 5, 1, format!("step {i}")).await;
+        }
+        let last = handle_thinking_command(&config, db.clone(), 5, "/thinking last").await;
+        assert!(last.ends_with(&format!("step {}", KEEP_PER_CHAT + 1)), "{last}");
+        let two = last_thinking(db.clone(), 5, 2).await.unwrap();
+        assert!(two.find("step 20").unwrap() < two.find("step 21").unwrap());
+        let kept = call_blocking(db.clone(), |d| d.recent_thinking(5, 100))
+            .await
+            .unwrap();
+        assert_eq!(kept.len(), KEEP_PER_CHAT);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
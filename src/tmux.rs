@@ -0,0 +1,289 @@
+//! tmux session manager for tools that keep work running outside an agent turn. Every session
+//! the bot creates carries [`SESSION_PREFIX`]; operations refuse names without it so tools can
+//! never touch the operator's own sessions.
+
+use std::path::Path;
+
+use thiserror::Error;
+use tokio::process::Command;
+
+/// Prefix of every bot-managed session name.
+pub const SESSION_PREFIX: &str = "microclaw-";
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum TmuxError {
+    #[error("tmux is not installed or not on PATH")]
+    NotInstalled,
+
+    #[error("Invalid tmux session name '{0}' (letters, digits, '-' and '_' only)")]
+    InvalidName(String),
+
+    #[error("Session '{0}' is not managed by the bot (names must start with '{SESSION_PREFIX}')")]
+    NotManaged(String),
+
+    #[error("tmux session '{0}' already exists")]
+    SessionExists(String),
+
+    #[error("tmux session '{0}' not found")]
+    SessionNotFound(String),
+
+    #[error("tmux {command} failed: {stderr}")]
+    Command { command: String, stderr: String },
+}
+
+/// A bot-managed session as reported by `tmux list-sessions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxSession {
+    pub name: String,
+    /// Unix timestamp the session was created.
+    pub created: i64,
+    pub attached: bool,
+    pub windows: u32,
+}
+
+/// Full session name for `suffix` (e.g. "cursor-42" -> "microclaw-cursor-42").
+pub fn session_name(suffix: &str) -> String {
+    if suffix.starts_with(SESSION_PREFIX) {
+        suffix.to_string()
+    } else {
+        format!("{SESSION_PREFIX}{suffix}")
+    }
+}
+
+/// Check that `name` is a well-formed bot-managed session name.
+pub fn validate_name(name: &str) -> Result<(), TmuxError> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(TmuxError::InvalidName(name.to_string()));
+    }
+    if !name.starts_with(SESSION_PREFIX) || name.len() == SESSION_PREFIX.len() {
+        return Err(TmuxError::NotManaged(name.to_string()));
+    }
+    Ok(())
+}
+
+const LIST_FORMAT: &str =
+    "#{session_name}\t#{session_created}\t#{session_attached}\t#{session_windows}";
+
+/// Parse `list-sessions` output in the [`LIST_FORMAT`] layout, keeping bot-managed sessions.
+pub fn parse_sessions(output: &str) -> Vec<TmuxSession> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let name = parts.next()?.to_string();
+            if !name.starts_with(SESSION_PREFIX) {
+                return None;
+            }
+            Some(TmuxSession {
+                name,
+                created: parts.next()?.trim().parse().unwrap_or(0),
+                attached: parts.next()?.trim() != "0",
+                windows: parts.next()?.trim().parse().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Map tmux's stderr to a structured error.
+fn classify_error(command: &str, session: &str, stderr: &str) -> TmuxError {
+    let lower = stderr.to_lowercase();
+    if lower.contains("duplicate session") {
+        TmuxError::SessionExists(session.to_string())
+    } else if lower.contains("can't find session")
+        || lower.contains("session not found")
+        || lower.contains("no server running")
+        || lower.contains("error connecting to")
+    {
+        TmuxError::SessionNotFound(session.to_string())
+    } else {
+        TmuxError::Command {
+            command: command.to_string(),
+            stderr: stderr.trim().to_string(),
+        }
+    }
+}
+
+async fn run(args: &[&str], session: &str) -> Result<String, TmuxError> {
+    let output = Command::new("tmux")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => TmuxError::NotInstalled,
+            _ => TmuxError::Command {
+                command: args.first().copied().unwrap_or("").to_string(),
+                stderr: e.to_string(),
+            },
+        })?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(classify_error(
+            args.first().copied().unwrap_or(""),
+            session,
+            &String::from_utf8_lossy(&output.stderr),
+        ))
+    }
+}
+
+/// Whether a usable tmux binary is on PATH.
+pub async fn is_installed() -> bool {
+    Command::new("tmux")
+        .arg("-V")
+        .output()
+        .await
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Bot-managed sessions. No tmux server running means no sessions.
+pub async fn list_sessions() -> Result<Vec<TmuxSession>, TmuxError> {
+    match run(&["list-sessions", "-F", LIST_FORMAT], "").await {
+        Ok(out) => Ok(parse_sessions(&out)),
+        Err(TmuxError::SessionNotFound(_)) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn has_session(name: &str) -> Result<bool, TmuxError> {
+    validate_name(name)?;
+    match run(&["has-session", "-t", &format!("={name}")], name).await {
+        Ok(_) => Ok(true),
+        Err(TmuxError::SessionNotFound(_)) => Ok(false),
+        // has-session reports a missing session with a bare non-zero exit on some versions.
+        Err(TmuxError::Command { .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Start a detached session running `command` (through the shell) in `cwd`.
+pub async fn create_session(name: &str, cwd: &Path, command: &str) -> Result<(), TmuxError> {
+    validate_name(name)?;
+    let cwd = cwd.to_string_lossy();
+    run(
+        &["new-session", "-d", "-s", name, "-c", &cwd, command],
+        name,
+    )
+    .await
+    .map(|_| ())
+}
+
+/// The last `lines` lines of the session's active pane.
+pub async fn capture(name: &str, lines: usize) -> Result<String, TmuxError> {
+    validate_name(name)?;
+    let start = format!("-{}", lines.max(1));
+    let out = run(
+        &[
+            "capture-pane",
+            "-p",
+            "-J",
+            "-t",
+            &format!("={name}"),
+            "-S",
+            &start,
+        ],
+        name,
+    )
+    .await?;
+    Ok(out.trim_end().to_string())
+}
+
+/// Type `text` into the session (literally) and press Enter.
+pub async fn send_keys(name: &str, text: &str) -> Result<(), TmuxError> {
+    validate_name(name)?;
+    let target = format!("={name}");
+    run(&["send-keys", "-t", &target, "-l", text], name).await?;
+    run(&["send-keys", "-t", &target, "Enter"], name)
+        .await
+        .map(|_| ())
+}
+
+pub async fn kill_session(name: &str) -> Result<(), TmuxError> {
+    validate_name(name)?;
+    run(&["kill-session", "-t", &format!("={name}")], name)
+        .await
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_must_carry_prefix() {
+        assert_eq!(session_name("cursor-42"), "microclaw-cursor-42");
+        assert_eq!(session_name("microclaw-job-1"), "microclaw-job-1");
+        assert!(validate_name("microclaw-job-1").is_ok());
+        assert!(matches!(
+            validate_name("work"),
+            Err(TmuxError::NotManaged(_))
+        ));
+        assert!(matches!(
+            validate_name("microclaw-"),
+            Err(TmuxError::NotManaged(_))
+        ));
+        assert!(matches!(
+            validate_name("microclaw-a;rm -rf"),
+            Err(TmuxError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_sessions_keeps_managed_only() {
+        let out = "microclaw-cursor-1\t1700000000\t0\t1\nwork\t1700000001\t1\t3\nmicroclaw-job-2\t1700000002\t1\t2\n";
+        assert_eq!(
+            parse_sessions(out),
+            vec![
+                TmuxSession {
+                    name: "microclaw-cursor-1".into(),
+                    created: 1_700_000_000,
+                    attached: false,
+                    windows: 1,
+                },
+                TmuxSession {
+                    name: "microclaw-job-2".into(),
+                    created: 1_700_000_002,
+                    attached: true,
+                    windows: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_error() {
+        assert!(matches!(
+            classify_error(
+                "new-session",
+                "microclaw-a",
+                "duplicate session: microclaw-a"
+            ),
+            TmuxError::SessionExists(_)
+        ));
+        assert!(matches!(
+            classify_error(
+                "kill-session",
+                "microclaw-a",
+                "can't find session: microclaw-a"
+            ),
+            TmuxError::SessionNotFound(_)
+        ));
+        assert!(matches!(
+            classify_error(
+                "list-sessions",
+                "",
+                "no server running on /tmp/tmux-0/default"
+            ),
+            TmuxError::SessionNotFound(_)
+        ));
+        assert_eq!(
+            classify_error("send-keys", "microclaw-a", "bad things\n").to_string(),
+            "tmux send-keys failed: bad things"
+        );
+    }
+}
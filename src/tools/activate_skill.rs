@@ -1,15 +1,19 @@
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::Path;
+use std::sync::Arc;
 use tracing::info;
 
 use crate::claude::ToolDefinition;
-use crate::skills::SkillManager;
+use crate::db::Database;
+use crate::skills::{SkillAudience, SkillManager};
 
-use super::{schema_object, Tool, ToolResult};
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
 
 pub struct ActivateSkillTool {
     skill_manager: SkillManager,
+    /// Used to resolve the caller's persona and chat type for skill `personas:`/`chat_types:` filters.
+    db: Option<Arc<Database>>,
 }
 
 impl ActivateSkillTool {
@@ -17,6 +21,7 @@ impl ActivateSkillTool {
     pub fn new(skills_dir: &str) -> Self {
         ActivateSkillTool {
             skill_manager: SkillManager::from_skills_dir(skills_dir),
+            db: None,
         }
     }
 
@@ -25,8 +30,15 @@ impl ActivateSkillTool {
     pub fn new_with_dirs(dirs: impl IntoIterator<Item = impl AsRef<Path>>) -> Self {
         ActivateSkillTool {
             skill_manager: SkillManager::from_skills_dirs(dirs),
+            db: None,
         }
     }
+
+    /// Enforce skill persona/chat-type filters for the calling chat.
+    pub fn with_db(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
 }
 
 #[async_trait]
@@ -38,7 +50,7 @@ impl Tool for ActivateSkillTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "activate_skill".into(),
-            description: "Activate an agent skill to load its full instructions. Use this when you see a relevant skill in the available skills list and need its detailed instructions to complete a task. Skills are filtered by platform/dependencies before they are listed.".into(),
+            description: "Activate an agent skill to load its full instructions. Use this when you see a relevant skill in the available skills list and need its detailed instructions to complete a task. Skills are filtered by platform/dependencies, persona and chat type before they are listed.".into(),
             input_schema: schema_object(
                 json!({
                     "skill_name": {
@@ -48,6 +60,7 @@ impl Tool for ActivateSkillTool {
                 }),
                 &["skill_name"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -59,7 +72,14 @@ impl Tool for ActivateSkillTool {
 
         info!("Activating skill: {}", skill_name);
 
-        match self.skill_manager.load_skill_checked(skill_name) {
+        let audience = match (&self.db, auth_context_from_input(&input)) {
+            (Some(db), Some(auth)) => {
+                SkillAudience::load(db.clone(), auth.caller_chat_id, auth.caller_persona_id).await
+            }
+            _ => SkillAudience::default(),
+        };
+
+        match self.skill_manager.load_skill_for(skill_name, &audience) {
             Ok((meta, body)) => {
                 let mut result = format!("# Skill: {}\n\n", meta.name);
                 result.push_str(&format!("Description: {}\n", meta.description));
@@ -170,6 +190,32 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_activate_skill_respects_persona_filter() {
+        let dir = test_dir();
+        let skill_dir = dir.join("sysadmin");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        std::fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\nname: sysadmin\ndescription: Servers\npersonas: [admin]\n---\nRestart things.\n",
+        )
+        .unwrap();
+        let db = Arc::new(Database::new(dir.join("db").to_str().unwrap()).unwrap());
+        db.upsert_chat(7, Some("kids"), "telegram_group").unwrap();
+        let persona_id = db.get_current_persona_id(7).unwrap();
+
+        let tool = ActivateSkillTool::new(dir.to_str().unwrap()).with_db(db);
+        let result = tool
+            .execute(json!({
+                "skill_name": "sysadmin",
+                "__microclaw_auth": {"caller_chat_id": 7, "caller_persona_id": persona_id}
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("not available in this chat"));
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_activate_skill_missing_param() {
         let dir = test_dir();
@@ -0,0 +1,620 @@
+//! `zip` / `unzip`: pack workspace files into a .zip or .tar.gz archive and unpack one, without
+//! going through bash. Every source, archive and extracted path goes through the path guard;
+//! blocked files (keys, credentials) are left out of archives. Extraction refuses entries that
+//! would land outside the destination, skips links, and stops at `MAX_ENTRIES` entries or
+//! `MAX_EXTRACTED_BYTES` so an archive bomb cannot fill the disk.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use super::path_guard;
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+
+const MAX_ENTRIES: usize = 10_000;
+const MAX_EXTRACTED_BYTES: u64 = 1024 * 1024 * 1024;
+/// Entries shown by `unzip` with `list`.
+const MAX_LISTED: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Format::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+fn resolve(working_dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let resolved = super::resolve_tool_path(working_dir, path);
+    path_guard::check_path(&resolved.to_string_lossy())?;
+    Ok(resolved)
+}
+
+/// A relative entry name with no root, prefix or `..` component.
+fn safe_entry_path(name: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+/// Files under `source` (or `source` itself) as (path on disk, name in the archive). Links are
+/// not followed and blocked files are skipped; their count is returned too.
+fn collect_files(source: &Path) -> Result<(Vec<(PathBuf, String)>, usize), String> {
+    let base = source.parent().unwrap_or(Path::new(""));
+    let mut files = Vec::new();
+    let mut skipped = 0;
+    let mut stack = vec![source.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let meta = std::fs::symlink_metadata(&path)
+            .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+        if path_guard::is_blocked(&path) || meta.file_type().is_symlink() {
+            skipped += 1;
+            continue;
+        }
+        if meta.is_dir() {
+            let mut children: Vec<PathBuf> = std::fs::read_dir(&path)
+                .map_err(|e| format!("Cannot read {}: {e}", path.display()))?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect();
+            children.sort();
+            stack.extend(children.into_iter().rev());
+        } else if meta.is_file() {
+            let name = path
+                .strip_prefix(base)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.push((path, name));
+            if files.len() > MAX_ENTRIES {
+                return Err(format!("Too many files (max {MAX_ENTRIES})"));
+            }
+        }
+    }
+    Ok((files, skipped))
+}
+
+fn write_archive(
+    format: Format,
+    archive: &Path,
+    files: &[(PathBuf, String)],
+) -> Result<u64, String> {
+    if let Some(parent) = archive.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Cannot create {}: {e}", parent.display()))?;
+    }
+    let out = File::create(archive).map_err(|e| format!("Cannot create archive: {e}"))?;
+    let mut total = 0u64;
+    match format {
+        Format::Zip => {
+            let mut writer = zip::ZipWriter::new(out);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .large_file(true);
+            for (path, name) in files {
+                writer
+                    .start_file(name.as_str(), options)
+                    .map_err(|e| format!("Failed to add {name}: {e}"))?;
+                let mut file =
+                    File::open(path).map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+                total += std::io::copy(&mut file, &mut writer)
+                    .map_err(|e| format!("Failed to add {name}: {e}"))?;
+            }
+            writer
+                .finish()
+                .map_err(|e| format!("Failed to write archive: {e}"))?;
+        }
+        Format::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            for (path, name) in files {
+                builder
+                    .append_path_with_name(path, name)
+                    .map_err(|e| format!("Failed to add {name}: {e}"))?;
+                total += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            }
+            builder
+                .into_inner()
+                .and_then(|encoder| encoder.finish())
+                .and_then(|mut file| file.flush())
+                .map_err(|e| format!("Failed to write archive: {e}"))?;
+        }
+    }
+    Ok(total)
+}
+
+/// Reads entries one at a time and hands each regular file to `sink` with its checked path.
+/// Returns the number of links/special entries skipped.
+fn for_each_entry(
+    format: Format,
+    archive: &Path,
+    mut sink: impl FnMut(PathBuf, u64, &mut dyn Read) -> Result<(), String>,
+) -> Result<usize, String> {
+    let file = File::open(archive).map_err(|e| format!("Cannot open archive: {e}"))?;
+    let mut skipped = 0;
+    match format {
+        Format::Zip => {
+            let mut zip =
+                zip::ZipArchive::new(file).map_err(|e| format!("Not a valid zip file: {e}"))?;
+            if zip.len() > MAX_ENTRIES {
+                return Err(format!("Archive has too many entries (max {MAX_ENTRIES})"));
+            }
+            for i in 0..zip.len() {
+                let mut entry = zip
+                    .by_index(i)
+                    .map_err(|e| format!("Cannot read entry {i}: {e}"))?;
+                if entry.is_dir() {
+                    continue;
+                }
+                if entry.is_symlink() {
+                    skipped += 1;
+                    continue;
+                }
+                let name = entry.name().to_string();
+                let path = entry
+                    .enclosed_name()
+                    .and_then(|p| safe_entry_path(&p))
+                    .ok_or_else(|| format!("Unsafe path in archive: {name}"))?;
+                let size = entry.size();
+                sink(path, size, &mut entry)?;
+            }
+        }
+        Format::TarGz => {
+            let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            let entries = tar
+                .entries()
+                .map_err(|e| format!("Not a valid tar.gz file: {e}"))?;
+            for (i, entry) in entries.enumerate() {
+                if i >= MAX_ENTRIES {
+                    return Err(format!("Archive has too many entries (max {MAX_ENTRIES})"));
+                }
+                let mut entry = entry.map_err(|e| format!("Cannot read entry {i}: {e}"))?;
+                let kind = entry.header().entry_type();
+                if kind.is_dir() {
+                    continue;
+                }
+                if !kind.is_file() {
+                    skipped += 1;
+                    continue;
+                }
+                let raw = entry
+                    .path()
+                    .map_err(|e| format!("Bad path in entry {i}: {e}"))?
+                    .into_owned();
+                let path = safe_entry_path(&raw)
+                    .ok_or_else(|| format!("Unsafe path in archive: {}", raw.display()))?;
+                let size = entry.size();
+                sink(path, size, &mut entry)?;
+            }
+        }
+    }
+    Ok(skipped)
+}
+
+fn size_label(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+pub struct ZipTool {
+    working_dir: PathBuf,
+}
+
+impl ZipTool {
+    pub fn new(working_dir: &str) -> Self {
+        ZipTool {
+            working_dir: PathBuf::from(working_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ZipTool {
+    fn name(&self) -> &str {
+        "zip"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "zip".into(),
+            description: "Pack files and directories into an archive, e.g. to send an export as one file. The format follows the archive name: .zip, or .tar.gz/.tgz. Directories are added with their contents under their own name; links and sensitive files (keys, credentials) are left out.".into(),
+            input_schema: schema_object(
+                json!({
+                    "sources": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Files or directories to add"
+                    },
+                    "archive": {
+                        "type": "string",
+                        "description": "Archive to create, ending in .zip, .tar.gz or .tgz"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace the archive if it exists (default false)"
+                    }
+                }),
+                &["sources", "archive"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let sources: Vec<String> = input
+            .get("sources")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|s| s.as_str())
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if sources.is_empty() {
+            return ToolResult::error("Missing 'sources' parameter".into());
+        }
+        let Some(archive) = input.get("archive").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing 'archive' parameter".into());
+        };
+        let overwrite = input
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let working_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let archive_path = match resolve(&working_dir, archive) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(e),
+        };
+        let Some(format) = Format::of(&archive_path) else {
+            return ToolResult::error("The archive name must end in .zip, .tar.gz or .tgz".into());
+        };
+        if archive_path.exists() && !overwrite {
+            return ToolResult::error(format!(
+                "{archive} already exists; pass overwrite=true to replace it"
+            ));
+        }
+        let mut source_paths = Vec::new();
+        for source in &sources {
+            match resolve(&working_dir, source) {
+                Ok(p) if p.exists() => source_paths.push(p),
+                Ok(_) => return ToolResult::error(format!("{source} does not exist")),
+                Err(e) => return ToolResult::error(e),
+            }
+        }
+
+        info!("Creating archive {}", archive_path.display());
+        let target = archive_path.clone();
+        let result = tokio::task::spawn_blocking(move || -> Result<(usize, usize, u64), String> {
+            let mut files = Vec::new();
+            let mut skipped = 0;
+            for source in &source_paths {
+                let (found, blocked) = collect_files(source)?;
+                files.extend(found.into_iter().filter(|(p, _)| *p != target));
+                skipped += blocked;
+            }
+            if files.is_empty() {
+                return Err("Nothing to archive: no readable files in the sources".into());
+            }
+            let total = write_archive(format, &target, &files)?;
+            Ok((files.len(), skipped, total))
+        })
+        .await;
+        match result {
+            Ok(Ok((count, skipped, total))) => {
+                let size = std::fs::metadata(&archive_path).map(|m| m.len()).unwrap_or(0);
+                let mut out = format!(
+                    "Created {} with {count} file(s): {} packed into {}.",
+                    archive_path.display(),
+                    size_label(total),
+                    size_label(size)
+                );
+                if skipped > 0 {
+                    out.push_str(&format!(" Skipped {skipped} link(s) or sensitive file(s)."));
+                }
+                ToolResult::success(out)
+            }
+            Ok(Err(e)) => {
+                let _ = std::fs::remove_file(&archive_path);
+                ToolResult::error(e)
+            }
+            Err(e) => ToolResult::error(format!("Archiving failed: {e}")),
+        }
+    }
+}
+
+pub struct UnzipTool {
+    working_dir: PathBuf,
+}
+
+impl UnzipTool {
+    pub fn new(working_dir: &str) -> Self {
+        UnzipTool {
+            working_dir: PathBuf::from(working_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for UnzipTool {
+    fn name(&self) -> &str {
+        "unzip"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "unzip".into(),
+            description: "List or extract a .zip or .tar.gz/.tgz archive, e.g. a downloaded dataset. Extracts into `destination` (default: a directory named after the archive, next to it). Entries that would land outside the destination are refused, links are skipped, and existing files are kept unless overwrite is set.".into(),
+            input_schema: schema_object(
+                json!({
+                    "archive": {
+                        "type": "string",
+                        "description": "Archive to read"
+                    },
+                    "destination": {
+                        "type": "string",
+                        "description": "Directory to extract into"
+                    },
+                    "list": {
+                        "type": "boolean",
+                        "description": "Only list the entries, do not extract (default false)"
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace files that already exist (default false)"
+                    }
+                }),
+                &["archive"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(archive) = input.get("archive").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing 'archive' parameter".into());
+        };
+        let list_only = input.get("list").and_then(|v| v.as_bool()).unwrap_or(false);
+        let overwrite = input
+            .get("overwrite")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let working_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let archive_path = match resolve(&working_dir, archive) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(e),
+        };
+        let Some(format) = Format::of(&archive_path) else {
+            return ToolResult::error("Unsupported archive: expected .zip, .tar.gz or .tgz".into());
+        };
+        if !archive_path.is_file() {
+            return ToolResult::error(format!("{archive} does not exist"));
+        }
+
+        if list_only {
+            let path = archive_path.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let mut entries = Vec::new();
+                let mut total = 0u64;
+                let skipped = for_each_entry(format, &path, |name, size, _| {
+                    total += size;
+                    entries.push(format!("{} ({})", name.display(), size_label(size)));
+                    Ok(())
+                })?;
+                Ok::<_, String>((entries, total, skipped))
+            })
+            .await;
+            return match result {
+                Ok(Ok((entries, total, skipped))) => {
+                    let mut out = format!(
+                        "{} file(s), {} uncompressed:\n{}",
+                        entries.len(),
+                        size_label(total),
+                        entries
+                            .iter()
+                            .take(MAX_LISTED)
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    );
+                    if entries.len() > MAX_LISTED {
+                        out.push_str(&format!("\n... and {} more", entries.len() - MAX_LISTED));
+                    }
+                    if skipped > 0 {
+                        out.push_str(&format!("\n({skipped} link(s) would be skipped)"));
+                    }
+                    ToolResult::success(out)
+                }
+                Ok(Err(e)) => ToolResult::error(e),
+                Err(e) => ToolResult::error(format!("Listing failed: {e}")),
+            };
+        }
+
+        let destination = match input.get("destination").and_then(|v| v.as_str()) {
+            Some(d) if !d.trim().is_empty() => match resolve(&working_dir, d.trim()) {
+                Ok(p) => p,
+                Err(e) => return ToolResult::error(e),
+            },
+            _ => {
+                let name = archive_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let stem = ["tar.gz", "tgz", "zip"]
+                    .iter()
+                    .find_map(|ext| {
+                        let suffix = format!(".{ext}");
+                        name.to_lowercase()
+                            .ends_with(&suffix)
+                            .then(|| name[..name.len() - suffix.len()].to_string())
+                    })
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| "extracted".into());
+                archive_path.with_file_name(stem)
+            }
+        };
+
+        info!(
+            "Extracting {} into {}",
+            archive_path.display(),
+            destination.display()
+        );
+        let dest = destination.clone();
+        let path = archive_path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut written = 0usize;
+            let mut kept = 0usize;
+            let mut total = 0u64;
+            let skipped = for_each_entry(format, &path, |name, _, reader| {
+                let target = dest.join(&name);
+                path_guard::check_path(&target.to_string_lossy())?;
+                if target.exists() && !overwrite {
+                    kept += 1;
+                    return Ok(());
+                }
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)
+                        .map_err(|e| format!("Cannot create {}: {e}", parent.display()))?;
+                }
+                let mut out = File::create(&target)
+                    .map_err(|e| format!("Cannot write {}: {e}", target.display()))?;
+                // Never trust the size in the header: cap what is actually read
+                let budget = MAX_EXTRACTED_BYTES - total;
+                let copied = std::io::copy(&mut reader.take(budget + 1), &mut out)
+                    .map_err(|e| format!("Failed to extract {}: {e}", name.display()))?;
+                if copied > budget {
+                    drop(out);
+                    let _ = std::fs::remove_file(&target);
+                    return Err(format!(
+                        "Stopped: the archive unpacks to more than {}",
+                        size_label(MAX_EXTRACTED_BYTES)
+                    ));
+                }
+                total += copied;
+                written += 1;
+                Ok(())
+            })?;
+            Ok::<_, String>((written, kept, skipped, total))
+        })
+        .await;
+        match result {
+            Ok(Ok((written, kept, skipped, total))) => {
+                let mut out = format!(
+                    "Extracted {written} file(s) ({}) into {}.",
+                    size_label(total),
+                    destination.display()
+                );
+                if kept > 0 {
+                    out.push_str(&format!(
+                        " Kept {kept} existing file(s); pass overwrite=true to replace them."
+                    ));
+                }
+                if skipped > 0 {
+                    out.push_str(&format!(" Skipped {skipped} link(s)."));
+                }
+                ToolResult::success(out)
+            }
+            Ok(Err(e)) => ToolResult::error(e),
+            Err(e) => ToolResult::error(format!("Extraction failed: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (PathBuf, ZipTool, UnzipTool) {
+        let dir = std::env::temp_dir().join(format!("microclaw_archive_{}", uuid::Uuid::new_v4()));
+        let shared = dir.join("shared");
+        std::fs::create_dir_all(shared.join("export/data")).unwrap();
+        std::fs::write(shared.join("export/report.md"), "# Report").unwrap();
+        std::fs::write(shared.join("export/data/rows.csv"), "a,b\n1,2\n").unwrap();
+        std::fs::write(shared.join("export/.env"), "TOKEN=secret").unwrap();
+        let root = dir.to_str().unwrap();
+        (dir.clone(), ZipTool::new(root), UnzipTool::new(root))
+    }
+
+    #[tokio::test]
+    async fn test_zip_and_unzip_round_trip() {
+        for archive in ["out/export.zip", "out/export.tar.gz"] {
+            let (dir, zip, unzip) = setup();
+            let out = zip
+                .execute(json!({"sources": ["export"], "archive": archive}))
+                .await;
+            assert!(!out.is_error, "{}", out.content);
+            assert!(out.content.contains("with 2 file(s)"), "{}", out.content);
+            assert!(out.content.contains("Skipped 1"));
+
+            let again = zip
+                .execute(json!({"sources": ["export"], "archive": archive}))
+                .await;
+            assert!(again.is_error && again.content.contains("already exists"));
+
+            let listed = unzip.execute(json!({"archive": archive, "list": true})).await;
+            assert!(listed.content.contains("export/data/rows.csv"), "{}", listed.content);
+            assert!(!listed.content.contains(".env"));
+
+            let out = unzip.execute(json!({"archive": archive})).await;
+            assert!(out.content.starts_with("Extracted 2 file(s)"), "{}", out.content);
+            let extracted = dir.join("shared/out/export/export/data/rows.csv");
+            assert_eq!(std::fs::read_to_string(extracted).unwrap(), "a,b\n1,2\n");
+
+            let out = unzip.execute(json!({"archive": archive})).await;
+            assert!(out.content.contains("Kept 2 existing file(s)"), "{}", out.content);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unzip_refuses_paths_outside_destination() {
+        let (dir, _, unzip) = setup();
+        let archive = dir.join("shared/evil.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        writer
+            .start_file("../../escape.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"gotcha").unwrap();
+        writer.finish().unwrap();
+
+        let out = unzip
+            .execute(json!({"archive": "evil.zip", "destination": "unpacked"}))
+            .await;
+        assert!(out.is_error);
+        assert!(out.content.contains("Unsafe path"), "{}", out.content);
+        assert!(!dir.join("escape.txt").exists());
+
+        let out = unzip.execute(json!({"archive": "notes.txt"})).await;
+        assert!(out.is_error && out.content.contains("Unsupported archive"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
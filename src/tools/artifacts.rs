@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Artifact, Database};
+
+/// Artifact types the tools accept.
+pub const ARTIFACT_KINDS: &[&str] = &[
+    "plan",
+    "table",
+    "recipe",
+    "checklist",
+    "code",
+    "summary",
+    "note",
+    "other",
+];
+const MAX_TITLE_CHARS: usize = 120;
+const MAX_CONTENT_CHARS: usize = 100_000;
+const DEFAULT_LIST_LIMIT: u64 = 20;
+const MAX_LIST_LIMIT: u64 = 100;
+
+/// `chat_id` from the input, else the calling chat; checked against the caller's access.
+fn target_chat(input: &serde_json::Value) -> Result<i64, String> {
+    let chat_id = input
+        .get("chat_id")
+        .and_then(|v| v.as_i64())
+        .or_else(|| auth_context_from_input(input).map(|a| a.caller_chat_id))
+        .ok_or("Missing chat_id")?;
+    authorize_chat_access(input, chat_id)?;
+    Ok(chat_id)
+}
+
+fn date(created_at: &str) -> String {
+    created_at.get(..10).unwrap_or(created_at).to_string()
+}
+
+/// One line per artifact, e.g. `#12 [recipe] Lasagna — 2026-10-16, 1.4k chars`.
+pub fn format_artifact_list(artifacts: &[Artifact]) -> String {
+    artifacts
+        .iter()
+        .map(|a| {
+            let chars = a.content.chars().count();
+            let size = if chars >= 1000 {
+                format!("{:.1}k", chars as f64 / 1000.0)
+            } else {
+                chars.to_string()
+            };
+            format!(
+                "#{} [{}] {} — {}, {size} chars",
+                a.id,
+                a.kind,
+                a.title,
+                date(&a.created_at)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub struct SaveArtifactTool {
+    db: Arc<Database>,
+}
+
+impl SaveArtifactTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        SaveArtifactTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for SaveArtifactTool {
+    fn name(&self) -> &str {
+        "save_artifact"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "save_artifact".into(),
+            description: "Save a notable piece of output (a plan, table, recipe, checklist, code...) with a title so it can be found again later with list_artifacts or in the web UI, instead of getting lost in the chat history. Save the final version in full, as markdown; the user does not see this call, so mention that it was saved.".into(),
+            input_schema: schema_object(
+                json!({
+                    "title": {
+                        "type": "string",
+                        "description": "Short descriptive title, e.g. 'Weekly meal plan (Oct 20)'"
+                    },
+                    "type": {
+                        "type": "string",
+                        "enum": ARTIFACT_KINDS,
+                        "description": "Kind of artifact"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The artifact itself, in markdown"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to save it in (defaults to the current chat)"
+                    }
+                }),
+                &["title", "type", "content"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let title = match input.get("title").and_then(|v| v.as_str()) {
+            Some(t) if !t.trim().is_empty() => t.trim(),
+            _ => return ToolResult::error("Missing required parameter: title".into()),
+        };
+        if title.chars().count() > MAX_TITLE_CHARS {
+            return ToolResult::error(format!("Title is too long (max {MAX_TITLE_CHARS} characters)"));
+        }
+        let kind = input
+            .get("type")
+            .and_then(|v| v.as_str())
+            .map(|k| k.trim().to_lowercase())
+            .unwrap_or_default();
+        if !ARTIFACT_KINDS.contains(&kind.as_str()) {
+            return ToolResult::error(format!(
+                "Invalid type '{kind}' (one of: {})",
+                ARTIFACT_KINDS.join(", ")
+            ));
+        }
+        let content = match input.get("content").and_then(|v| v.as_str()) {
+            Some(c) if !c.trim().is_empty() => c.trim(),
+            _ => return ToolResult::error("Missing required parameter: content".into()),
+        };
+        if content.chars().count() > MAX_CONTENT_CHARS {
+            return ToolResult::error(format!(
+                "Content is too long (max {MAX_CONTENT_CHARS} characters); save it as a file instead"
+            ));
+        }
+        let chat_id = match target_chat(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        let artifact = Artifact {
+            chat_id,
+            persona_id: auth_context_from_input(&input)
+                .map(|a| a.caller_persona_id)
+                .unwrap_or(0),
+            title: title.to_string(),
+            kind: kind.clone(),
+            content: content.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            ..Default::default()
+        };
+        match call_blocking(self.db.clone(), move |d| d.save_artifact(&artifact)).await {
+            Ok(id) => ToolResult::success(format!(
+                "Saved artifact #{id} \"{title}\" ({kind}). It is listed by list_artifacts and in the web UI."
+            )),
+            Err(e) => ToolResult::error(format!("Failed to save artifact: {e}")),
+        }
+    }
+}
+
+pub struct ListArtifactsTool {
+    db: Arc<Database>,
+}
+
+impl ListArtifactsTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        ListArtifactsTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for ListArtifactsTool {
+    fn name(&self) -> &str {
+        "list_artifacts"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_artifacts".into(),
+            description: "List artifacts saved with save_artifact in this chat (newest first), optionally of one type or with words in the title. Pass `id` to get one artifact's full content.".into(),
+            input_schema: schema_object(
+                json!({
+                    "id": {
+                        "type": "integer",
+                        "description": "Return the full content of this artifact"
+                    },
+                    "type": {
+                        "type": "string",
+                        "enum": ARTIFACT_KINDS,
+                        "description": "Only artifacts of this type"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Only artifacts whose title contains this text"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "How many to list (default 20, max 100)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to list (defaults to the current chat)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match target_chat(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        if let Some(id) = input.get("id").and_then(|v| v.as_i64()) {
+            return match call_blocking(self.db.clone(), move |d| d.get_artifact(chat_id, id)).await
+            {
+                Ok(Some(a)) => ToolResult::success(format!(
+                    "#{} [{}] {} — {}\n\n{}",
+                    a.id,
+                    a.kind,
+                    a.title,
+                    date(&a.created_at),
+                    a.content
+                )),
+                Ok(None) => ToolResult::error(format!("No artifact #{id} in this chat")),
+                Err(e) => ToolResult::error(format!("Failed to load artifact: {e}")),
+            };
+        }
+        let kind = input
+            .get("type")
+            .and_then(|v| v.as_str())
+            .map(|k| k.trim().to_lowercase())
+            .filter(|k| !k.is_empty());
+        let query = input
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(|q| q.trim().to_string())
+            .filter(|q| !q.is_empty());
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .filter(|l| *l > 0)
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .min(MAX_LIST_LIMIT) as usize;
+        match call_blocking(self.db.clone(), move |d| {
+            d.list_artifacts(chat_id, kind.as_deref(), query.as_deref(), limit)
+        })
+        .await
+        {
+            Ok(list) if list.is_empty() => ToolResult::success("No saved artifacts found.".into()),
+            Ok(list) => ToolResult::success(format!(
+                "{}\n\nUse list_artifacts with id to read one.",
+                format_artifact_list(&list)
+            )),
+            Err(e) => ToolResult::error(format!("Failed to list artifacts: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save_list_and_show_artifacts() {
+        let dir = std::env::temp_dir().join(format!("microclaw_artifacts_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let save = SaveArtifactTool::new(db.clone());
+        let list = ListArtifactsTool::new(db.clone());
+        let auth = json!({"caller_chat_id": 5, "caller_persona_id": 2, "control_chat_ids": []});
+
+        let out = save
+            .execute(json!({"title": "Lasagna", "type": "recipe", "content": "1. Boil pasta", "__microclaw_auth": auth}))
+            .await;
+        assert_eq!(out.content, "Saved artifact #1 \"Lasagna\" (recipe). It is listed by list_artifacts and in the web UI.");
+        save.execute(json!({"title": "Week plan", "type": "Plan", "content": "Mon: gym", "__microclaw_auth": auth}))
+            .await;
+        let bad = save
+            .execute(json!({"title": "X", "type": "poem", "content": "roses", "__microclaw_auth": auth}))
+            .await;
+        assert!(bad.is_error && bad.content.contains("Invalid type"));
+        let denied = save
+            .execute(json!({"title": "X", "type": "note", "content": "y", "chat_id": 9, "__microclaw_auth": auth}))
+            .await;
+        assert!(denied.is_error);
+
+        let out = list.execute(json!({"__microclaw_auth": auth})).await;
+        assert!(out.content.starts_with("#2 [plan] Week plan — "), "{}", out.content);
+        assert!(out.content.contains("#1 [recipe] Lasagna — "));
+        let out = list.execute(json!({"type": "recipe", "__microclaw_auth": auth})).await;
+        assert!(!out.content.contains("Week plan"));
+        let out = list.execute(json!({"query": "WEEK", "__microclaw_auth": auth})).await;
+        assert!(out.content.contains("Week plan") && !out.content.contains("Lasagna"));
+        let out = list.execute(json!({"id": 1, "__microclaw_auth": auth})).await;
+        assert!(out.content.ends_with("\n\n1. Boil pasta"));
+
+        let other = json!({"caller_chat_id": 6, "control_chat_ids": []});
+        let out = list.execute(json!({"id": 1, "__microclaw_auth": other})).await;
+        assert!(out.is_error);
+        let out = list.execute(json!({"__microclaw_auth": other})).await;
+        assert_eq!(out.content, "No saved artifacts found.");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,22 +1,159 @@
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::info;
 
 use crate::claude::ToolDefinition;
+use crate::db::Database;
+use crate::jobs::{JobContext, JobRunner};
 use crate::tools::command_runner::{build_command, shell_command};
 
-use super::{schema_object, Tool, ToolResult};
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+
+/// Default timeout for `background: true` commands.
+const DEFAULT_BACKGROUND_TIMEOUT_SECS: u64 = 3600;
 
 pub struct BashTool {
     working_dir: PathBuf,
+    jobs: Option<JobRunner>,
+    db: Option<Arc<Database>>,
 }
 
 impl BashTool {
     pub fn new(working_dir: &str) -> Self {
         Self {
             working_dir: PathBuf::from(working_dir),
+            jobs: None,
+            db: None,
+        }
+    }
+
+    /// Pass the calling chat's `set_chat_env` variables to commands and resolve `project`.
+    pub fn with_db(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Allow `background: true` runs as background jobs.
+    pub fn with_jobs(mut self, jobs: JobRunner) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    async fn start_background(
+        &self,
+        input: &serde_json::Value,
+        command: &str,
+        working_dir: PathBuf,
+        env: Vec<(String, String)>,
+    ) -> ToolResult {
+        let Some(jobs) = &self.jobs else {
+            return ToolResult::error("Background commands are not available here".into());
+        };
+        let Some(auth) = auth_context_from_input(input) else {
+            return ToolResult::error("Background commands need a chat context".into());
+        };
+        let timeout_secs = input
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_BACKGROUND_TIMEOUT_SECS);
+        let description = format!("bash: {}", truncate_description(command));
+        let command = command.to_string();
+        let started = jobs
+            .submit(
+                auth.caller_chat_id,
+                auth.caller_persona_id,
+                "bash",
+                &description,
+                move |ctx| run_background(ctx, command, working_dir, env, timeout_secs),
+            )
+            .await;
+        match started {
+            Ok(id) => ToolResult::success(format!(
+                "Started background job #{id}. The chat is notified when it finishes; use job_status to check its output."
+            )),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+fn truncate_description(command: &str) -> String {
+    let first_line = command.lines().next().unwrap_or("").trim();
+    if first_line.chars().count() > 80 || command.trim().contains('\n') {
+        let short: String = first_line.chars().take(80).collect();
+        format!("{short}…")
+    } else {
+        first_line.to_string()
+    }
+}
+
+/// Run `command` as a background job, streaming stdout and stderr lines into the job output.
+async fn run_background(
+    ctx: JobContext,
+    command: String,
+    working_dir: PathBuf,
+    env: Vec<(String, String)>,
+    timeout_secs: u64,
+) -> Result<String, String> {
+    let spec = shell_command(&command);
+    let mut child = build_command(&spec, Some(&working_dir))
+        .envs(env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute command: {e}"))?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(format!("{line}\n"));
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(format!("STDERR: {line}\n"));
+            }
+        });
+    }
+    drop(tx);
+
+    let mut tail = String::new();
+    let collect = async {
+        while let Some(line) = rx.recv().await {
+            ctx.append_output(&line).await;
+            tail.push_str(&line);
+            if tail.len() > 4000 {
+                let cut = tail.len() - 2000;
+                let cut = (cut..tail.len())
+                    .find(|i| tail.is_char_boundary(*i))
+                    .unwrap_or(tail.len());
+                tail.drain(..cut);
+            }
+        }
+        child.wait().await
+    };
+    match tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), collect).await {
+        Ok(Ok(status)) => {
+            let exit_code = status.code().unwrap_or(-1);
+            if exit_code == 0 {
+                Ok(tail)
+            } else {
+                Err(format!("Exit code {exit_code}\n{tail}"))
+            }
         }
+        Ok(Err(e)) => Err(format!("Failed to wait for command: {e}")),
+        Err(_) => Err(format!("Command timed out after {timeout_secs} seconds")),
     }
 }
 
@@ -38,11 +175,20 @@ impl Tool for BashTool {
                     },
                     "timeout_secs": {
                         "type": "integer",
-                        "description": "Timeout in seconds (default: 120)"
+                        "description": "Timeout in seconds (default: 120, or 3600 in the background)"
+                    },
+                    "project": {
+                        "type": "string",
+                        "description": "Run in this registered project's directory (see list_projects) instead of the shared workspace"
+                    },
+                    "background": {
+                        "type": "boolean",
+                        "description": "Run as a background job and return immediately with its id; the chat is notified when it finishes. Use for long downloads, builds, indexing or transcodes."
                     }
                 }),
                 &["command"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -56,7 +202,20 @@ impl Tool for BashTool {
             .get("timeout_secs")
             .and_then(|v| v.as_u64())
             .unwrap_or(120);
-        let working_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let working_dir = match input
+            .get("project")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.trim().is_empty())
+        {
+            Some(project) => match &self.db {
+                Some(db) => match super::projects::resolve_project(db, project).await {
+                    Ok(dir) => dir,
+                    Err(e) => return ToolResult::error(e),
+                },
+                None => return ToolResult::error("Projects are not available here".into()),
+            },
+            None => super::resolve_tool_working_dir(&self.working_dir),
+        };
         if let Err(e) = tokio::fs::create_dir_all(&working_dir).await {
             return ToolResult::error(format!(
                 "Failed to create working directory {}: {e}",
@@ -64,12 +223,27 @@ impl Tool for BashTool {
             ));
         }
 
+        let env = match &self.db {
+            Some(db) => super::chat_env::env_for_input(db, &input).await,
+            None => Vec::new(),
+        };
+        if input
+            .get("background")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            info!("Starting background bash: {}", command);
+            return self
+                .start_background(&input, command, working_dir, env)
+                .await;
+        }
+
         info!("Executing bash: {}", command);
 
         let spec = shell_command(command);
         let result = tokio::time::timeout(
             std::time::Duration::from_secs(timeout_secs),
-            build_command(&spec, Some(&working_dir)).output(),
+            build_command(&spec, Some(&working_dir)).envs(env).output(),
         )
         .await;
 
@@ -188,4 +362,14 @@ mod tests {
         let _ = std::fs::remove_dir_all(&root);
     }
 
+    #[tokio::test]
+    async fn test_bash_background_requires_jobs() {
+        let tool = BashTool::new(".");
+        let result = tool
+            .execute(json!({"command": "echo hi", "background": true}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("not available"));
+        assert_eq!(truncate_description("echo hi\necho bye"), "echo hi…");
+    }
 }
@@ -1,18 +1,48 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
 use serde_json::json;
 use tracing::info;
 
 use crate::claude::ToolDefinition;
+use crate::config::{BrowserDownloadConfig, BrowserProfileConfig};
+use crate::db::{call_blocking, Database};
+use crate::egress::EgressPolicy;
 use crate::tools::command_runner::agent_browser_program;
 
-use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use super::{auth_context_from_input, schema_object, Tool, ToolAuthContext, ToolResult};
 
 pub struct BrowserTool {
     data_dir: PathBuf,
+    /// Directory holding named saved-login profiles (runtime/browser-profiles).
+    saved_profiles_dir: PathBuf,
     /// If set, use this path for the agent-browser executable; otherwise use default from PATH.
     agent_browser_path: Option<String>,
+    egress: EgressPolicy,
+    db: Option<Arc<Database>>,
+    saved_profiles: Vec<BrowserProfileConfig>,
+    /// Workspace directory downloads are moved to, and their limits. None = leave them in the
+    /// profile.
+    downloads: Option<(PathBuf, BrowserDownloadConfig)>,
+}
+
+/// Browser profile directory and agent-browser session a call runs in.
+#[derive(Debug, PartialEq)]
+struct BrowserProfile {
+    path: PathBuf,
+    session: String,
+}
+
+/// URL a browser command navigates to (`open <url>`, `goto`/`navigate <url>`, `tab new <url>`).
+fn navigation_target(args: &[String]) -> Option<&str> {
+    match args {
+        [cmd, url, ..] if matches!(cmd.as_str(), "open" | "goto" | "navigate") => Some(url),
+        [tab, new, url, ..] if tab == "tab" && new == "new" => Some(url),
+        _ => None,
+    }
 }
 
 fn split_browser_command(command: &str) -> Result<Vec<String>, String> {
@@ -72,13 +102,37 @@ fn split_browser_command(command: &str) -> Result<Vec<String>, String> {
 
 impl BrowserTool {
     /// Create a browser tool. `agent_browser_path`: optional full path to agent-browser CLI from config.
-    pub fn new(data_dir: &str, agent_browser_path: Option<String>) -> Self {
+    /// Navigation targets are checked against `egress` before the browser is invoked.
+    pub fn new(data_dir: &str, agent_browser_path: Option<String>, egress: EgressPolicy) -> Self {
         BrowserTool {
             data_dir: PathBuf::from(data_dir).join("groups"),
+            saved_profiles_dir: PathBuf::from(data_dir).join("browser-profiles"),
             agent_browser_path,
+            egress,
+            db: None,
+            saved_profiles: Vec::new(),
+            downloads: None,
         }
     }
 
+    /// Move files the browser downloads into `working_dir/shared/downloads/chat_<id>`, subject
+    /// to `limits`, and report them in the tool result.
+    pub fn with_downloads(mut self, working_dir: &str, limits: BrowserDownloadConfig) -> Self {
+        self.downloads = Some((PathBuf::from(working_dir), limits));
+        self
+    }
+
+    /// Enable per-persona profiles and the named saved-login profiles from config.
+    pub fn with_saved_profiles(
+        mut self,
+        db: Arc<Database>,
+        saved_profiles: Vec<BrowserProfileConfig>,
+    ) -> Self {
+        self.db = Some(db);
+        self.saved_profiles = saved_profiles;
+        self
+    }
+
     fn profile_path(&self, chat_id: i64) -> PathBuf {
         self.data_dir
             .join(chat_id.to_string())
@@ -93,6 +147,301 @@ impl BrowserTool {
         };
         format!("microclaw-chat-{normalized}")
     }
+
+    async fn persona_name(&self, persona_id: i64) -> Option<String> {
+        let db = self.db.clone()?;
+        call_blocking(db, move |d| d.get_persona(persona_id))
+            .await
+            .ok()
+            .flatten()
+            .map(|p| p.name)
+    }
+
+    /// The caller's own profile. The chat's "default" persona keeps the original per-chat
+    /// profile (so existing logins survive); other personas get separate cookie jars.
+    async fn persona_profile(&self, auth: &ToolAuthContext) -> BrowserProfile {
+        let chat_id = auth.caller_chat_id;
+        let persona_id = auth.caller_persona_id;
+        let is_default =
+            persona_id == 0 || self.persona_name(persona_id).await.as_deref() == Some("default");
+        if is_default {
+            return BrowserProfile {
+                path: self.profile_path(chat_id),
+                session: Self::session_name_for_chat(chat_id),
+            };
+        }
+        BrowserProfile {
+            path: self
+                .data_dir
+                .join(chat_id.to_string())
+                .join(format!("browser-profile-persona-{persona_id}")),
+            session: format!("{}-p{persona_id}", Self::session_name_for_chat(chat_id)),
+        }
+    }
+
+    /// A named saved-login profile, if the calling persona is on its access list.
+    async fn saved_profile(
+        &self,
+        name: &str,
+        auth: Option<&ToolAuthContext>,
+    ) -> Result<BrowserProfile, String> {
+        let Some(profile) = self
+            .saved_profiles
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+        else {
+            let available: Vec<&str> = self
+                .saved_profiles
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect();
+            return Err(format!(
+                "Unknown browser profile '{name}'. Configured profiles: {}",
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ));
+        };
+        if profile.name.is_empty()
+            || !profile
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("Invalid browser profile name '{}'", profile.name));
+        }
+        if let Some(auth) = auth {
+            let persona = self
+                .persona_name(auth.caller_persona_id)
+                .await
+                .unwrap_or_default();
+            let persona_allowed = profile
+                .personas
+                .iter()
+                .any(|p| p.trim().eq_ignore_ascii_case(&persona));
+            let chat_allowed =
+                profile.chat_ids.is_empty() || profile.chat_ids.contains(&auth.caller_chat_id);
+            if !persona_allowed || !chat_allowed {
+                return Err(format!(
+                    "Permission denied: persona '{persona}' in chat {} may not use browser profile '{}'",
+                    auth.caller_chat_id, profile.name
+                ));
+            }
+        }
+        let name = profile.name.to_lowercase();
+        Ok(BrowserProfile {
+            path: self.saved_profiles_dir.join(&name),
+            session: format!("microclaw-profile-{name}"),
+        })
+    }
+}
+
+/// Keep `state save/load <path>` inside the active profile directory so one persona cannot load
+/// another profile's exported cookies. Relative paths are resolved against the profile.
+fn confine_state_path(args: &mut [String], profile_dir: &Path) -> Result<(), String> {
+    if !(args.len() >= 3 && args[0] == "state" && matches!(args[1].as_str(), "save" | "load")) {
+        return Ok(());
+    }
+    let requested = Path::new(&args[2]);
+    if requested.is_absolute()
+        || requested
+            .components()
+            .any(|c| matches!(c, Component::ParentDir))
+    {
+        return Err(
+            "state save/load paths must be relative to the browser profile (no absolute paths or '..')"
+                .into(),
+        );
+    }
+    args[2] = profile_dir
+        .join("state")
+        .join(requested)
+        .to_string_lossy()
+        .to_string();
+    Ok(())
+}
+
+/// Where a profile's downloads land before they are checked and moved to the workspace.
+fn staging_dir(profile_dir: &Path) -> PathBuf {
+    profile_dir.join("Downloads")
+}
+
+/// Point the save path of `download <sel> <path>` and `wait --download <path>` into the staging
+/// directory, keeping only the file name.
+fn stage_download_path(args: &mut [String], staging: &Path) -> Result<(), String> {
+    let index = match args {
+        [cmd, _, _, ..] if cmd == "download" => 2,
+        [cmd, flag, _, ..] if cmd == "wait" && flag == "--download" => 2,
+        _ => return Ok(()),
+    };
+    let Some(name) = Path::new(&args[index]).file_name() else {
+        return Err(format!("Download path '{}' has no file name", args[index]));
+    };
+    args[index] = staging.join(name).to_string_lossy().to_string();
+    Ok(())
+}
+
+/// Files in `dir` with their modification times.
+fn list_files(dir: &Path) -> HashMap<PathBuf, SystemTime> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            meta.is_file()
+                .then(|| (e.path(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+        })
+        .collect()
+}
+
+/// Files added or rewritten since `before`, skipping unfinished Chromium/Firefox downloads.
+fn new_downloads(before: &HashMap<PathBuf, SystemTime>, dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = list_files(dir)
+        .into_iter()
+        .filter(|(path, modified)| before.get(path) != Some(modified))
+        .map(|(path, _)| path)
+        .filter(|path| {
+            !matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("crdownload" | "part" | "tmp")
+            )
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Why a download is not kept, if it breaks the type or size limits.
+fn check_download(name: &str, size: u64, limits: &BrowserDownloadConfig) -> Result<(), String> {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let listed = |types: &[String]| {
+        types
+            .iter()
+            .any(|t| t.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+    };
+    if listed(&limits.blocked_types) {
+        return Err(format!("file type .{ext} is blocked"));
+    }
+    if !limits.allowed_types.is_empty() && !listed(&limits.allowed_types) {
+        return Err(format!(
+            "file type {} is not in the allowed types ({})",
+            if ext.is_empty() {
+                "(none)".to_string()
+            } else {
+                format!(".{ext}")
+            },
+            limits.allowed_types.join(", ")
+        ));
+    }
+    if limits.max_mb > 0 && size > limits.max_mb * 1024 * 1024 {
+        return Err(format!(
+            "{:.1} MB exceeds the {} MB limit",
+            size as f64 / (1024.0 * 1024.0),
+            limits.max_mb
+        ));
+    }
+    Ok(())
+}
+
+/// URL of the page open in `session`, used as the source of downloads a click started.
+async fn current_url(program: &str, session: &str) -> Option<String> {
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        tokio::process::Command::new(program)
+            .args(["--session", session, "get", "url"])
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !url.is_empty()).then_some(url)
+}
+
+/// Download directory of a chat (`default` outside a chat).
+fn chat_download_dir(shared_dir: &Path, chat_id: Option<i64>) -> PathBuf {
+    let name = match chat_id {
+        Some(id) => format!("chat_{id}"),
+        None => "default".to_string(),
+    };
+    shared_dir.join("downloads").join(name)
+}
+
+/// A free path for `name` in `dir`, prefixing a timestamp if the name is taken.
+fn download_destination(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    dir.join(format!("{stamp}-{name}"))
+}
+
+/// Move new downloads from `staging` into the chat's download directory with a `.meta.json`
+/// sidecar, deleting those over the limits. Returns one report line per file.
+fn capture_downloads(
+    files: &[PathBuf],
+    target_dir: &Path,
+    limits: &BrowserDownloadConfig,
+    chat_id: Option<i64>,
+    source_url: Option<&str>,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for file in files {
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let size = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+        if let Err(reason) = check_download(&name, size, limits) {
+            let _ = std::fs::remove_file(file);
+            lines.push(format!("- rejected {name}: {reason}"));
+            continue;
+        }
+        if let Err(e) = std::fs::create_dir_all(target_dir) {
+            lines.push(format!("- failed to save {name}: {e}"));
+            continue;
+        }
+        let dest = download_destination(target_dir, &name);
+        // rename fails across filesystems (profile and workspace may differ)
+        let moved = std::fs::rename(file, &dest).or_else(|_| {
+            std::fs::copy(file, &dest)?;
+            std::fs::remove_file(file)
+        });
+        if let Err(e) = moved {
+            lines.push(format!("- failed to save {name}: {e}"));
+            continue;
+        }
+        let meta = json!({
+            "file": dest.file_name().map(|n| n.to_string_lossy().to_string()),
+            "source_url": source_url,
+            "downloaded_at": chrono::Utc::now().to_rfc3339(),
+            "size_bytes": size,
+            "chat_id": chat_id,
+        });
+        let mut meta_path = dest.clone().into_os_string();
+        meta_path.push(".meta.json");
+        let _ = std::fs::write(
+            &meta_path,
+            serde_json::to_string_pretty(&meta).unwrap_or_default(),
+        );
+        lines.push(format!(
+            "- {} ({size} bytes{})",
+            dest.display(),
+            source_url
+                .map(|u| format!(", from {u}"))
+                .unwrap_or_default()
+        ));
+    }
+    lines
 }
 
 #[async_trait]
@@ -104,7 +453,7 @@ impl Tool for BrowserTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "browser".into(),
-            description: "Browser automation via the agent-browser CLI (npm package). Use this tool with a command string (e.g. open, snapshot, click, fill). Do not run agent-browser in the shell — use this tool only. Browser state (cookies, localStorage, login sessions) persists across calls.\n\n\
+            description: "Browser automation via the agent-browser CLI (npm package). Use this tool with a command string (e.g. open, snapshot, click, fill). Do not run agent-browser in the shell — use this tool only. Browser state (cookies, localStorage, login sessions) persists across calls in a profile private to the current persona; pass `profile` to use a shared saved-login profile you are allowed to access.\n\n\
                 ## Basic workflow\n\
                 1. `open <url>` — navigate to a URL\n\
                 2. `snapshot -i` — get interactive elements with refs (@e1, @e2, ...)\n\
@@ -129,6 +478,7 @@ impl Tool for BrowserTool {
                 **Network**: network route <url> [--abort|--body <json>], network requests\n\
                 **Wait**: wait <sel|ms|--text|--url|--load|--fn>\n\
                 **Auth state**: state save <path>, state load <path>\n\
+                **Downloads**: download <sel> <file>, wait --download <file> — downloaded files are moved to the chat's `downloads/` folder in the workspace and their paths are listed in the result\n\
                 **Semantic find**: find role/text/label/placeholder <value> <action> [input]".into(),
             input_schema: schema_object(
                 json!({
@@ -139,10 +489,15 @@ impl Tool for BrowserTool {
                     "timeout_secs": {
                         "type": "integer",
                         "description": "Timeout in seconds (default: 30)"
+                    },
+                    "profile": {
+                        "type": "string",
+                        "description": "Optional named profile with saved logins (e.g. \"banking\"). Only personas on the profile's access list may use it. Omit to use this persona's own profile."
                     }
                 }),
                 &["command"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -158,24 +513,37 @@ impl Tool for BrowserTool {
             .unwrap_or(30);
 
         let auth = auth_context_from_input(&input);
-        let caller_chat_id = auth.as_ref().map(|a| a.caller_chat_id);
 
         let mut args: Vec<String> = Vec::new();
 
-        let session_name = caller_chat_id
-            .map(Self::session_name_for_chat)
+        let requested_profile = input
+            .get("profile")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|p| !p.is_empty());
+        let profile = match (requested_profile, auth.as_ref()) {
+            (Some(name), auth) => match self.saved_profile(name, auth).await {
+                Ok(p) => Some(p),
+                Err(e) => return ToolResult::error(e),
+            },
+            (None, Some(auth)) => Some(self.persona_profile(auth).await),
+            (None, None) => None,
+        };
+
+        let session_name = profile
+            .as_ref()
+            .map(|p| p.session.clone())
             .unwrap_or_else(|| "microclaw".to_string());
 
         args.push("--session".to_string());
-        args.push(session_name);
+        args.push(session_name.clone());
 
-        if let Some(chat_id) = caller_chat_id {
-            let path = self.profile_path(chat_id);
+        if let Some(profile) = &profile {
             args.push("--profile".to_string());
-            args.push(path.to_string_lossy().to_string());
+            args.push(profile.path.to_string_lossy().to_string());
         }
 
-        let command_args = match split_browser_command(command) {
+        let mut command_args = match split_browser_command(command) {
             Ok(parts) if !parts.is_empty() => parts,
             Ok(_) => return ToolResult::error("Empty browser command".into()),
             Err(e) => {
@@ -184,6 +552,28 @@ impl Tool for BrowserTool {
                 ));
             }
         };
+        if let Some(profile) = &profile {
+            if let Err(e) = confine_state_path(&mut command_args, &profile.path) {
+                return ToolResult::error(e);
+            }
+        }
+        let staging = profile
+            .as_ref()
+            .filter(|_| self.downloads.is_some())
+            .map(|p| staging_dir(&p.path));
+        if let Some(staging) = &staging {
+            if let Err(e) = stage_download_path(&mut command_args, staging) {
+                return ToolResult::error(e);
+            }
+            let _ = std::fs::create_dir_all(staging);
+        }
+        let before_downloads = staging.as_deref().map(list_files).unwrap_or_default();
+        let nav_url = navigation_target(&command_args).map(str::to_string);
+        if let Some(url) = nav_url.as_deref().filter(|u| !u.starts_with("about:")) {
+            if let Err(e) = self.egress.check_url_str(url).await {
+                return ToolResult::error(e);
+            }
+        }
         args.extend(command_args);
 
         let program = self
@@ -222,6 +612,35 @@ impl Tool for BrowserTool {
                     result_text = format!("Command completed with exit code {exit_code}");
                 }
 
+                if let (Some(staging), Some((working_dir, limits))) = (&staging, &self.downloads) {
+                    let files = new_downloads(&before_downloads, staging);
+                    if !files.is_empty() {
+                        let source_url = match nav_url {
+                            Some(url) => Some(url),
+                            None => current_url(&program, &session_name).await,
+                        };
+                        let chat_id = auth.as_ref().map(|a| a.caller_chat_id);
+                        let target = chat_download_dir(
+                            &super::resolve_tool_working_dir(working_dir),
+                            chat_id,
+                        );
+                        let lines = capture_downloads(
+                            &files,
+                            &target,
+                            limits,
+                            chat_id,
+                            source_url.as_deref(),
+                        );
+                        info!(
+                            "Captured {} browser download(s) into {}",
+                            files.len(),
+                            target.display()
+                        );
+                        result_text.push_str("\n\nDownloads:\n");
+                        result_text.push_str(&lines.join("\n"));
+                    }
+                }
+
                 // Detect wrong binary (microclaw-browser expects --port, not open/snapshot)
                 let wrong_binary = stderr.contains("microclaw-browser") || stderr.contains("missing --port");
                 if wrong_binary {
@@ -287,7 +706,7 @@ mod tests {
 
     #[test]
     fn test_browser_tool_name_and_definition() {
-        let tool = BrowserTool::new("/tmp/test-data", None);
+        let tool = BrowserTool::new("/tmp/test-data", None, EgressPolicy::default());
         assert_eq!(tool.name(), "browser");
         let def = tool.definition();
         assert_eq!(def.name, "browser");
@@ -301,7 +720,7 @@ mod tests {
 
     #[test]
     fn test_browser_profile_path() {
-        let tool = BrowserTool::new("/tmp/test-data", None);
+        let tool = BrowserTool::new("/tmp/test-data", None, EgressPolicy::default());
         let path = tool.profile_path(12345);
         assert_eq!(
             path,
@@ -323,9 +742,142 @@ mod tests {
 
     #[tokio::test]
     async fn test_browser_missing_command() {
-        let tool = BrowserTool::new("/tmp/test-data", None);
+        let tool = BrowserTool::new("/tmp/test-data", None, EgressPolicy::default());
         let result = tool.execute(json!({})).await;
         assert!(result.is_error);
         assert!(result.content.contains("Missing 'command'"));
     }
+
+    #[tokio::test]
+    async fn test_browser_profiles_isolated_per_persona() {
+        let dir = std::env::temp_dir().join(format!("microclaw_bprof_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let owner = db.get_or_create_default_persona(7).unwrap();
+        let guest = db.create_persona(7, "guest", None).unwrap();
+        let tool = BrowserTool::new("/tmp/test-data", None, EgressPolicy::default())
+            .with_saved_profiles(
+                db.clone(),
+                vec![BrowserProfileConfig {
+                    name: "banking".into(),
+                    personas: vec!["default".into()],
+                    chat_ids: vec![],
+                }],
+            );
+        let auth = |persona_id| ToolAuthContext {
+            caller_channel: "telegram".into(),
+            caller_chat_id: 7,
+            caller_persona_id: persona_id,
+            control_chat_ids: vec![],
+        };
+
+        let owner_profile = tool.persona_profile(&auth(owner)).await;
+        assert_eq!(owner_profile.path, tool.profile_path(7));
+        let guest_profile = tool.persona_profile(&auth(guest)).await;
+        assert_ne!(guest_profile.path, owner_profile.path);
+        assert_ne!(guest_profile.session, owner_profile.session);
+
+        let banking = tool
+            .saved_profile("banking", Some(&auth(owner)))
+            .await
+            .unwrap();
+        assert_eq!(banking.session, "microclaw-profile-banking");
+        let denied = tool
+            .saved_profile("banking", Some(&auth(guest)))
+            .await
+            .unwrap_err();
+        assert!(denied.contains("Permission denied"), "{denied}");
+        assert!(tool.saved_profile("shopping", None).await.is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_confine_state_path() {
+        let profile = Path::new("/data/groups/7/browser-profile");
+        let mut args: Vec<String> = vec!["state".into(), "save".into(), "auth.json".into()];
+        confine_state_path(&mut args, profile).unwrap();
+        assert_eq!(args[2], "/data/groups/7/browser-profile/state/auth.json");
+        for bad in [
+            "/tmp/other.json",
+            "../browser-profile-persona-3/state/auth.json",
+        ] {
+            let mut args: Vec<String> = vec!["state".into(), "load".into(), bad.into()];
+            assert!(confine_state_path(&mut args, profile).is_err(), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_browser_open_respects_egress_policy() {
+        let tool = BrowserTool::new("/tmp/test-data", None, EgressPolicy::default());
+        for command in ["open http://192.168.1.1/admin", "tab new localhost:8123"] {
+            let result = tool.execute(json!({ "command": command })).await;
+            assert!(result.is_error);
+            assert!(
+                result.content.contains("egress policy"),
+                "{}",
+                result.content
+            );
+        }
+    }
+
+    #[test]
+    fn test_download_paths_and_limits() {
+        let staging = Path::new("/data/groups/7/browser-profile/Downloads");
+        let mut args: Vec<String> =
+            vec!["download".into(), "@e4".into(), "../../report.pdf".into()];
+        stage_download_path(&mut args, staging).unwrap();
+        assert_eq!(
+            args[2],
+            "/data/groups/7/browser-profile/Downloads/report.pdf"
+        );
+        let mut args: Vec<String> = vec!["wait".into(), "--download".into(), "/tmp/x.csv".into()];
+        stage_download_path(&mut args, staging).unwrap();
+        assert_eq!(args[2], "/data/groups/7/browser-profile/Downloads/x.csv");
+
+        let limits = BrowserDownloadConfig::default();
+        assert!(check_download("report.pdf", 1024, &limits).is_ok());
+        assert!(check_download("setup.EXE", 1024, &limits).is_err());
+        assert!(check_download("huge.zip", 200 * 1024 * 1024, &limits).is_err());
+        let only_csv = BrowserDownloadConfig {
+            allowed_types: vec![".csv".into()],
+            ..Default::default()
+        };
+        assert!(check_download("data.csv", 10, &only_csv).is_ok());
+        assert!(check_download("notes", 10, &only_csv).is_err());
+    }
+
+    #[test]
+    fn test_capture_downloads_moves_files_with_metadata() {
+        let dir = std::env::temp_dir().join(format!("microclaw_bdl_{}", uuid::Uuid::new_v4()));
+        let staging = dir.join("profile").join("Downloads");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(staging.join("old.txt"), "seen").unwrap();
+        let before = list_files(&staging);
+        std::fs::write(staging.join("invoice.pdf"), "pdf").unwrap();
+        std::fs::write(staging.join("tool.exe"), "bin").unwrap();
+        std::fs::write(staging.join("partial.crdownload"), "..").unwrap();
+
+        let files = new_downloads(&before, &staging);
+        assert_eq!(files.len(), 2);
+        let target = chat_download_dir(&dir.join("shared"), Some(7));
+        let lines = capture_downloads(
+            &files,
+            &target,
+            &BrowserDownloadConfig::default(),
+            Some(7),
+            Some("https://example.com/billing"),
+        );
+        assert!(
+            lines.iter().any(|l| l.contains("rejected tool.exe")),
+            "{lines:?}"
+        );
+        assert!(target.join("invoice.pdf").exists());
+        assert!(!staging.join("tool.exe").exists());
+        let meta: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(target.join("invoice.pdf.meta.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(meta["source_url"], "https://example.com/billing");
+        assert_eq!(meta["size_bytes"], 3);
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,403 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::Database;
+use crate::skills::validate_skill_md;
+use crate::text::truncate_chars_with;
+
+use super::cursor_agent::{cursor_agent_command, run_cursor_agent, steps_log};
+use super::skill_versions::snapshot_skill;
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+
+const MAX_INSTRUCTIONS_LEN: usize = 20_000;
+const PROMPT_PREVIEW_LEN: usize = 200;
+const OUTPUT_PREVIEW_LEN: usize = 500;
+/// Validation failures trigger this many follow-up cursor-agent runs.
+const MAX_FIX_ATTEMPTS: usize = 1;
+
+/// Build (or update) a skill with cursor-agent and wait for it: the produced SKILL.md is
+/// validated, one fix-up run is attempted if validation fails, and the files created or changed
+/// in the skill folder are reported.
+pub struct BuildSkillTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl BuildSkillTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        Self {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+fn valid_skill_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Relative path -> content hash of every file under `dir`.
+fn snapshot_files(dir: &Path) -> BTreeMap<String, u64> {
+    fn walk(root: &Path, dir: &Path, out: &mut BTreeMap<String, u64>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, out);
+            } else if let Ok(bytes) = std::fs::read(&path) {
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                bytes.hash(&mut hasher);
+                let rel = path.strip_prefix(root).unwrap_or(&path);
+                out.insert(rel.to_string_lossy().to_string(), hasher.finish());
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(dir, dir, &mut out);
+    out
+}
+
+/// Created / modified / removed files between two snapshots.
+fn diff_files(before: &BTreeMap<String, u64>, after: &BTreeMap<String, u64>) -> String {
+    let created: Vec<&str> = after
+        .keys()
+        .filter(|k| !before.contains_key(*k))
+        .map(String::as_str)
+        .collect();
+    let modified: Vec<&str> = after
+        .iter()
+        .filter(|(k, v)| before.get(*k).is_some_and(|b| b != *v))
+        .map(|(k, _)| k.as_str())
+        .collect();
+    let removed: Vec<&str> = before
+        .keys()
+        .filter(|k| !after.contains_key(*k))
+        .map(String::as_str)
+        .collect();
+    let mut lines = Vec::new();
+    for (label, files) in [
+        ("Created", created),
+        ("Modified", modified),
+        ("Removed", removed),
+    ] {
+        if !files.is_empty() {
+            lines.push(format!("{label}: {}", files.join(", ")));
+        }
+    }
+    if lines.is_empty() {
+        "No files changed.".into()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn validate_skill_dir(skill_dir: &Path) -> Vec<String> {
+    for filename in ["SKILL.md", "skill.md"] {
+        if let Ok(content) = std::fs::read_to_string(skill_dir.join(filename)) {
+            return validate_skill_md(&content, skill_dir);
+        }
+    }
+    vec!["SKILL.md was not created".into()]
+}
+
+fn build_prompt(name: &str, skill_dir: &Path, instructions: &str) -> String {
+    format!(
+        "Create or update the agent skill '{name}' in the folder {dir}.\n\
+         Write {dir}/SKILL.md starting with YAML frontmatter between '---' lines containing \
+         `name: {name}` and a one-line `description`, followed by markdown instructions organized \
+         under '## ' sections (for example '## When to use' and '## Steps'). Put any helper \
+         scripts in the same folder and reference them by path from SKILL.md.\n\n\
+         Skill requirements:\n{instructions}",
+        dir = skill_dir.display()
+    )
+}
+
+fn fix_prompt(name: &str, skill_dir: &Path, problems: &[String]) -> String {
+    format!(
+        "The skill '{name}' in {dir} failed validation:\n- {}\n\n\
+         Fix {dir}/SKILL.md so these problems are resolved, keeping its existing instructions.",
+        problems.join("\n- "),
+        dir = skill_dir.display()
+    )
+}
+
+#[async_trait]
+impl Tool for BuildSkillTool {
+    fn name(&self) -> &str {
+        "build_skill"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "build_skill".into(),
+            description: "Build or update a skill in the skills directory with cursor-agent and wait for the result. The produced SKILL.md is validated (frontmatter with name and description, '## ' instruction sections); on failure cursor-agent is asked once to fix it. Returns the validation result and the files created, modified or removed in the skill folder.".into(),
+            input_schema: schema_object(
+                json!({
+                    "name": {
+                        "type": "string",
+                        "description": "Skill folder and frontmatter name (lowercase letters, digits, '-' or '_')"
+                    },
+                    "instructions": {
+                        "type": "string",
+                        "description": "What the skill should do, when to use it, and any commands or APIs it relies on"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Timeout per cursor-agent run in seconds (default from config)"
+                    },
+                    "model": {
+                        "type": "string",
+                        "description": "Override cursor-agent model for this build"
+                    }
+                }),
+                &["name", "instructions"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let name = input
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or("");
+        if !valid_skill_name(name) {
+            return ToolResult::error(
+                "Invalid 'name': use 1-64 lowercase letters, digits, '-' or '_'".into(),
+            );
+        }
+        let instructions = match input.get("instructions").and_then(|v| v.as_str()) {
+            Some(s) if !s.trim().is_empty() => s.trim(),
+            _ => return ToolResult::error("Missing 'instructions' parameter".into()),
+        };
+        if instructions.len() > MAX_INSTRUCTIONS_LEN {
+            return ToolResult::error(format!(
+                "Instructions exceed maximum length of {MAX_INSTRUCTIONS_LEN} characters"
+            ));
+        }
+        let cli_path = self.config.cursor_agent_cli_path.trim();
+        if cli_path.is_empty() {
+            return ToolResult::error("cursor_agent_cli_path is not configured".into());
+        }
+        let timeout_secs = input
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(self.config.cursor_agent_timeout_secs);
+        let model = input
+            .get("model")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .unwrap_or(self.config.cursor_agent_model.as_str())
+            .trim()
+            .to_string();
+
+        let skills_dir = self.config.skills_data_dir_absolute();
+        let skill_dir: PathBuf = skills_dir.join(name);
+        if let Err(msg) = crate::tools::path_guard::check_path(&skill_dir.to_string_lossy()) {
+            return ToolResult::error(msg);
+        }
+        let auth = auth_context_from_input(&input);
+        let saved_version = match snapshot_skill(
+            &skills_dir,
+            name,
+            "build_skill",
+            Some(self.db.clone()),
+            auth.as_ref().map(|a| a.caller_chat_id),
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(e),
+        };
+        if let Err(e) = tokio::fs::create_dir_all(&skill_dir).await {
+            return ToolResult::error(format!(
+                "Failed to create skill directory {}: {e}",
+                skill_dir.display()
+            ));
+        }
+
+        let before = snapshot_files(&skill_dir);
+        let mut prompt = build_prompt(name, &skill_dir, instructions);
+        let mut problems = Vec::new();
+        let mut last_output = String::new();
+
+        for attempt in 0..=MAX_FIX_ATTEMPTS {
+            info!(
+                "build_skill '{}': cursor-agent run {} (timeout {}s)",
+                name,
+                attempt + 1,
+                timeout_secs
+            );
+            let started_at = chrono::Utc::now().to_rfc3339();
+            let cmd = cursor_agent_command(cli_path, &prompt, &model, &skills_dir);
+            let (success, exit_code, output, steps_json) =
+                match run_cursor_agent(cmd, timeout_secs).await {
+                    Ok(run) if run.timed_out => (
+                        false,
+                        -1,
+                        format!("cursor-agent timed out after {timeout_secs} seconds"),
+                        Some(steps_log(&run.steps)),
+                    ),
+                    Ok(run) => (
+                        run.success(),
+                        run.exit_code.unwrap_or(-1),
+                        run.output,
+                        Some(steps_log(&run.steps)),
+                    ),
+                    Err(e) => (
+                        false,
+                        1,
+                        format!("Failed to execute cursor-agent: {e}"),
+                        None,
+                    ),
+                };
+
+            if let Some(ref a) = auth {
+                let finished_at = chrono::Utc::now().to_rfc3339();
+                let prompt_preview = truncate_chars_with(&prompt, PROMPT_PREVIEW_LEN, "...");
+                let output_preview = truncate_chars_with(&output, OUTPUT_PREVIEW_LEN, "...");
+                let workdir = skills_dir.to_string_lossy().to_string();
+                let chat_id = a.caller_chat_id;
+                let channel = a.caller_channel.clone();
+                let _ = crate::db::call_blocking(self.db.clone(), move |database| {
+                    database.insert_cursor_agent_run(
+                        chat_id,
+                        &channel,
+                        &prompt_preview,
+                        Some(&workdir),
+                        &started_at,
+                        &finished_at,
+                        success,
+                        Some(exit_code),
+                        Some(&output_preview),
+                        None::<&str>,
+                        steps_json.as_deref(),
+                    )
+                })
+                .await;
+            }
+
+            if !success {
+                let diff = diff_files(&before, &snapshot_files(&skill_dir));
+                return ToolResult::error(format!(
+                    "build_skill '{name}' failed (exit code {exit_code}).\n{diff}\n\n{output}"
+                ))
+                .with_status_code(exit_code)
+                .with_error_type("process_exit");
+            }
+            last_output = output;
+            problems = validate_skill_dir(&skill_dir);
+            if problems.is_empty() {
+                break;
+            }
+            prompt = fix_prompt(name, &skill_dir, &problems);
+        }
+
+        let mut diff = diff_files(&before, &snapshot_files(&skill_dir));
+        if saved_version.is_some() {
+            diff.push_str("\nPrevious version saved; use rollback_skill to restore it.");
+        }
+        let summary = truncate_chars_with(last_output.trim(), OUTPUT_PREVIEW_LEN, "...");
+        if problems.is_empty() {
+            ToolResult::success(format!(
+                "Skill '{name}' built and validated at {}.\n{diff}\n\ncursor-agent: {summary}",
+                skill_dir.display()
+            ))
+        } else {
+            ToolResult::error(format!(
+                "Skill '{name}' still fails validation after a fix-up run:\n- {}\n{diff}\n\ncursor-agent: {summary}",
+                problems.join("\n- ")
+            ))
+            .with_error_type("validation")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_build_skill_retries_invalid_skill_and_reports_diff() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_build_skill_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Fake cursor-agent: first run writes a SKILL.md without description or sections,
+        // the fix-up run writes a valid one.
+        let script = dir.join("fake-cursor-agent.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nif [ -f demo/SKILL.md ]; then\n  printf -- '---\\nname: demo\\ndescription: Demo skill\\n---\\n## Steps\\nDo it.\\n' > demo/SKILL.md\n  echo fixed\nelse\n  printf -- '---\\nname: demo\\n---\\nTodo\\n' > demo/SKILL.md\n  echo drafted\nfi\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = dir.join("workspace").to_string_lossy().to_string();
+        config.cursor_agent_cli_path = script.to_string_lossy().to_string();
+        let db = Arc::new(Database::new(dir.join("db").to_str().unwrap()).unwrap());
+        let tool = BuildSkillTool::new(&config, db.clone());
+
+        let result = tool
+            .execute(json!({
+                "name": "demo",
+                "instructions": "Say hello.",
+                "__microclaw_auth": {"caller_chat_id": 3, "caller_channel": "web"}
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(
+            result.content.contains("built and validated"),
+            "{}",
+            result.content
+        );
+        assert!(
+            result.content.contains("Created: SKILL.md"),
+            "{}",
+            result.content
+        );
+        assert!(
+            result.content.contains("cursor-agent: fixed"),
+            "{}",
+            result.content
+        );
+        assert_eq!(db.get_cursor_agent_runs(Some(3), 10).unwrap().len(), 2);
+
+        let invalid = tool
+            .execute(json!({"name": "../etc", "instructions": "x"}))
+            .await;
+        assert!(invalid.is_error);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let before = BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        let after = BTreeMap::from([
+            ("a".to_string(), 1),
+            ("b".to_string(), 3),
+            ("c".to_string(), 4),
+        ]);
+        assert_eq!(diff_files(&before, &after), "Created: c\nModified: b");
+        assert_eq!(diff_files(&after, &after), "No files changed.");
+    }
+}
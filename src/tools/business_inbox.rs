@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, BusinessChat, Database};
+
+pub struct BusinessInboxTool {
+    db: Arc<Database>,
+}
+
+impl BusinessInboxTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        BusinessInboxTool { db }
+    }
+}
+
+fn format_chats(chats: &[BusinessChat]) -> String {
+    chats
+        .iter()
+        .map(|c| {
+            format!(
+                "#{} {} — auto-replies {}",
+                c.id,
+                c.title,
+                if c.auto_reply { "on" } else { "off" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[async_trait]
+impl Tool for BusinessInboxTool {
+    fn name(&self) -> &str {
+        "business_inbox"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "business_inbox".into(),
+            description: "List the conversations in the owner's Telegram Business inbox, or switch auto-replies on or off for one of them. Only switch a conversation on when the owner asks for it.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "on", "off"],
+                        "description": "list (default), or turn auto-replies on/off for `id`"
+                    },
+                    "id": {
+                        "type": "integer",
+                        "description": "Conversation number from the list"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(auth) = auth_context_from_input(&input) else {
+            return ToolResult::error("business_inbox needs the calling chat".into());
+        };
+        // Owners manage their own inbox from their private chat (whose id is their user id)
+        let owner = (!auth.is_control_chat()).then_some(auth.caller_chat_id);
+        let chats = match call_blocking(self.db.clone(), move |d| d.list_business_chats(owner)).await
+        {
+            Ok(chats) => chats,
+            Err(e) => return ToolResult::error(format!("Failed to load business chats: {e}")),
+        };
+        let action = input
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("list");
+        let auto_reply = match action {
+            "list" => {
+                return ToolResult::success(if chats.is_empty() {
+                    "No Telegram Business conversations yet.".into()
+                } else {
+                    format_chats(&chats)
+                })
+            }
+            "on" => true,
+            "off" => false,
+            other => return ToolResult::error(format!("Unknown action '{other}' (list, on, off)")),
+        };
+        let Some(id) = input.get("id").and_then(|v| v.as_i64()) else {
+            return ToolResult::error("Missing required parameter: id".into());
+        };
+        let Some(chat) = chats.into_iter().find(|c| c.id == id) else {
+            return ToolResult::error(format!("No Business conversation #{id}"));
+        };
+        match call_blocking(self.db.clone(), move |d| {
+            d.set_business_auto_reply(id, auto_reply)
+        })
+        .await
+        {
+            Ok(_) => ToolResult::success(format!(
+                "Auto-replies {action} for #{id} {}.",
+                chat.title
+            )),
+            Err(e) => ToolResult::error(format!("Failed to update business chat: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_owner_switches_own_conversations() {
+        let dir = std::env::temp_dir().join(format!("microclaw_inbox_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_business_connection("conn-1", 42, true, true, "2026-10-17T00:00:00Z")
+            .unwrap();
+        db.ensure_business_chat(&BusinessChat {
+            chat_id: 9001,
+            connection_id: "conn-1".into(),
+            telegram_chat_id: 1001,
+            title: "Alice".into(),
+            created_at: "2026-10-17T00:00:00Z".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        let tool = BusinessInboxTool::new(db.clone());
+        let owner = json!({"caller_chat_id": 42, "control_chat_ids": []});
+
+        let out = tool.execute(json!({"__microclaw_auth": owner})).await;
+        assert_eq!(out.content, "#1 Alice — auto-replies off");
+        let out = tool
+            .execute(json!({"action": "on", "id": 1, "__microclaw_auth": owner}))
+            .await;
+        assert_eq!(out.content, "Auto-replies on for #1 Alice.");
+        assert!(db.get_business_chat(9001).unwrap().unwrap().auto_reply);
+
+        let stranger = json!({"caller_chat_id": 7, "control_chat_ids": []});
+        let out = tool
+            .execute(json!({"action": "off", "id": 1, "__microclaw_auth": stranger}))
+            .await;
+        assert!(out.is_error);
+        let control = json!({"caller_chat_id": 5, "control_chat_ids": [5]});
+        let out = tool.execute(json!({"__microclaw_auth": control})).await;
+        assert_eq!(out.content, "#1 Alice — auto-replies on");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,871 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+
+/// Public exchange-rate feed (no API key). Rates are relative to USD.
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/USD";
+/// Cached rates older than this are refreshed on the next currency conversion.
+const RATES_TTL_SECS: i64 = 12 * 3600;
+const MAX_DECIMALS: usize = 10;
+
+pub struct CalculateTool {
+    cache_path: PathBuf,
+}
+
+impl CalculateTool {
+    pub fn new(data_dir: &str) -> Self {
+        CalculateTool {
+            cache_path: PathBuf::from(data_dir).join("calculate").join("rates.json"),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CalculateTool {
+    fn name(&self) -> &str {
+        "calculate"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "calculate".into(),
+            description: "Evaluate arithmetic exactly and convert units or currencies. Always use this instead of doing math yourself. Supports + - * / % ^, parentheses, sqrt/abs/round/floor/ceil/ln/log/exp/sin/cos/tan/min/max, pi and e. Conversions use '<expr> <unit> to <unit>', e.g. '5 km to mi', '(3+4) lb in kg', '72 F to C', '100 USD to EUR' (currency rates from a cached daily feed).".into(),
+            input_schema: schema_object(
+                json!({
+                    "expression": {
+                        "type": "string",
+                        "description": "Expression to evaluate, optionally followed by a conversion ('... to <unit>')"
+                    }
+                }),
+                &["expression"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let expression = match input.get("expression").and_then(|v| v.as_str()) {
+            Some(e) if !e.trim().is_empty() => e.trim(),
+            _ => return ToolResult::error("Missing required parameter: expression".into()),
+        };
+
+        let Some((lhs, target)) = split_conversion(expression) else {
+            return match evaluate(expression) {
+                Ok(value) => {
+                    ToolResult::success(format!("{expression} = {}", format_number(value)))
+                }
+                Err(e) => ToolResult::error(format!("Calculation error: {e}")),
+            };
+        };
+
+        let (value_expr, source) = match split_value_unit(lhs) {
+            Some(parts) => parts,
+            None => {
+                return ToolResult::error(format!(
+                    "Calculation error: could not find a source unit in '{lhs}'"
+                ))
+            }
+        };
+        let value = match evaluate(value_expr) {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(format!("Calculation error: {e}")),
+        };
+
+        if let (Some(from), Some(to)) = (lookup_unit(source), lookup_unit(target)) {
+            return match convert_units(value, &from, &to) {
+                Ok(result) => ToolResult::success(format!(
+                    "{} {source} = {} {target}",
+                    format_number(value),
+                    format_number(result)
+                )),
+                Err(e) => ToolResult::error(format!("Calculation error: {e}")),
+            };
+        }
+
+        match (currency_code(source), currency_code(target)) {
+            (Some(from), Some(to)) => {
+                let rates = match load_rates(&self.cache_path).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        return ToolResult::error(format!("Failed to load currency rates: {e}"))
+                    }
+                };
+                match convert_currency(value, &from, &to, &rates.rates) {
+                    Ok(result) => ToolResult::success(format!(
+                        "{} {from} = {} {to} (rates as of {})",
+                        format_number(value),
+                        format_currency(result),
+                        rates.as_of()
+                    )),
+                    Err(e) => ToolResult::error(format!("Calculation error: {e}")),
+                }
+            }
+            _ => ToolResult::error(format!(
+                "Calculation error: cannot convert '{source}' to '{target}' (unknown unit or currency)"
+            )),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Expression evaluation
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            // Scientific notation: 1e3, 2.5E-4
+            if i < chars.len()
+                && (chars[i] == 'e' || chars[i] == 'E')
+                && chars
+                    .get(i + 1)
+                    .map(|n| {
+                        n.is_ascii_digit()
+                            || ((*n == '-' || *n == '+')
+                                && chars.get(i + 2).is_some_and(|d| d.is_ascii_digit()))
+                    })
+                    .unwrap_or(false)
+            {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{text}'"))?;
+            tokens.push(Token::Num(value));
+        } else if c.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(
+                chars[start..i].iter().collect::<String>().to_lowercase(),
+            ));
+        } else if c == '*' && chars.get(i + 1) == Some(&'*') {
+            tokens.push(Token::Op('^'));
+            i += 2;
+        } else {
+            let token = match c {
+                '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+                '×' => Token::Op('*'),
+                '÷' => Token::Op('/'),
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                ',' => Token::Comma,
+                other => return Err(format!("unexpected character '{other}'")),
+            };
+            tokens.push(token);
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(t) if t == expected => Ok(()),
+            Some(t) => Err(format!("expected {expected:?}, found {t:?}")),
+            None => Err(format!("expected {expected:?}, found end of input")),
+        }
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                _ if rhs == 0.0 => return Err("division by zero".into()),
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64, String> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                Ok(-self.unary()?)
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.primary()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.pos += 1;
+            // Right-associative, and binds tighter than unary minus on the left: -2^2 = -4.
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<f64, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(n),
+            Some(Token::LParen) => {
+                let value = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(Token::LParen) = self.peek() {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        args.push(self.expr()?);
+                        while let Some(Token::Comma) = self.peek() {
+                            self.pos += 1;
+                            args.push(self.expr()?);
+                        }
+                    }
+                    self.expect(Token::RParen)?;
+                    call_function(&name, &args)
+                } else {
+                    match name.as_str() {
+                        "pi" => Ok(std::f64::consts::PI),
+                        "e" => Ok(std::f64::consts::E),
+                        _ => Err(format!("unknown identifier '{name}'")),
+                    }
+                }
+            }
+            Some(t) => Err(format!("unexpected token {t:?}")),
+            None => Err("unexpected end of input".into()),
+        }
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, String> {
+    let one = |f: fn(f64) -> f64| -> Result<f64, String> {
+        match args {
+            [x] => Ok(f(*x)),
+            _ => Err(format!("{name}() takes exactly one argument")),
+        }
+    };
+    match name {
+        "sqrt" => match args {
+            [x] if *x < 0.0 => Err("sqrt of a negative number".into()),
+            _ => one(f64::sqrt),
+        },
+        "abs" => one(f64::abs),
+        "floor" => one(f64::floor),
+        "ceil" => one(f64::ceil),
+        "ln" => one(f64::ln),
+        "log" | "log10" => one(f64::log10),
+        "log2" => one(f64::log2),
+        "exp" => one(f64::exp),
+        "sin" => one(f64::sin),
+        "cos" => one(f64::cos),
+        "tan" => one(f64::tan),
+        "round" => match args {
+            [x] => Ok(x.round()),
+            [x, digits] => {
+                let factor = 10f64.powi(*digits as i32);
+                Ok((x * factor).round() / factor)
+            }
+            _ => Err("round() takes one or two arguments".into()),
+        },
+        "min" | "max" if args.is_empty() => Err(format!("{name}() needs at least one argument")),
+        "min" => Ok(args.iter().cloned().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(args.iter().cloned().fold(f64::NEG_INFINITY, f64::max)),
+        _ => Err(format!("unknown function '{name}'")),
+    }
+}
+
+/// Evaluate a plain arithmetic expression (no units).
+pub fn evaluate(expression: &str) -> Result<f64, String> {
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err("empty expression".into());
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.expr()?;
+    if let Some(t) = parser.peek() {
+        return Err(format!("unexpected token {t:?}"));
+    }
+    if !value.is_finite() {
+        return Err("result is not a finite number".into());
+    }
+    Ok(value)
+}
+
+fn format_number(value: f64) -> String {
+    if value == value.trunc() && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let formatted = format!("{value:.MAX_DECIMALS$}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "0" || trimmed == "-0" {
+        // Too small for fixed notation; fall back to scientific.
+        format!("{value:e}")
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn format_currency(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+// ---------------------------------------------------------------------------
+// Units
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Volume,
+    Area,
+    Speed,
+    Data,
+    Temperature,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Unit {
+    dimension: Dimension,
+    /// Multiplier to the dimension's base unit (ignored for temperature).
+    factor: f64,
+    name: &'static str,
+}
+
+/// (aliases, dimension, factor to base unit). Base units: m, kg, s, l, m2, m/s, byte, kelvin.
+const UNITS: &[(&[&str], Dimension, f64)] = &[
+    (
+        &["m", "meter", "meters", "metre", "metres"],
+        Dimension::Length,
+        1.0,
+    ),
+    (
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        Dimension::Length,
+        1000.0,
+    ),
+    (
+        &["cm", "centimeter", "centimeters"],
+        Dimension::Length,
+        0.01,
+    ),
+    (
+        &["mm", "millimeter", "millimeters"],
+        Dimension::Length,
+        0.001,
+    ),
+    (&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    (&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    (&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    (&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    (
+        &["nmi", "nautical_mile", "nautical_miles"],
+        Dimension::Length,
+        1852.0,
+    ),
+    (
+        &["kg", "kilogram", "kilograms", "kilo", "kilos"],
+        Dimension::Mass,
+        1.0,
+    ),
+    (&["g", "gram", "grams"], Dimension::Mass, 0.001),
+    (
+        &["mg", "milligram", "milligrams"],
+        Dimension::Mass,
+        0.000_001,
+    ),
+    (&["t", "tonne", "tonnes"], Dimension::Mass, 1000.0),
+    (
+        &["lb", "lbs", "pound", "pounds"],
+        Dimension::Mass,
+        0.453_592_37,
+    ),
+    (
+        &["oz", "ounce", "ounces"],
+        Dimension::Mass,
+        0.028_349_523_125,
+    ),
+    (&["st", "stone", "stones"], Dimension::Mass, 6.350_293_18),
+    (
+        &["s", "sec", "secs", "second", "seconds"],
+        Dimension::Time,
+        1.0,
+    ),
+    (
+        &["ms", "millisecond", "milliseconds"],
+        Dimension::Time,
+        0.001,
+    ),
+    (&["min", "mins", "minute", "minutes"], Dimension::Time, 60.0),
+    (
+        &["h", "hr", "hrs", "hour", "hours"],
+        Dimension::Time,
+        3600.0,
+    ),
+    (&["d", "day", "days"], Dimension::Time, 86_400.0),
+    (&["wk", "week", "weeks"], Dimension::Time, 604_800.0),
+    (
+        &["l", "liter", "liters", "litre", "litres"],
+        Dimension::Volume,
+        1.0,
+    ),
+    (
+        &[
+            "ml",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+        ],
+        Dimension::Volume,
+        0.001,
+    ),
+    (
+        &["m3", "cubic_meter", "cubic_meters"],
+        Dimension::Volume,
+        1000.0,
+    ),
+    (
+        &["gal", "gallon", "gallons"],
+        Dimension::Volume,
+        3.785_411_784,
+    ),
+    (&["qt", "quart", "quarts"], Dimension::Volume, 0.946_352_946),
+    (&["pt", "pint", "pints"], Dimension::Volume, 0.473_176_473),
+    (&["cup", "cups"], Dimension::Volume, 0.236_588_236_5),
+    (
+        &["floz", "fl_oz", "fluid_ounce", "fluid_ounces"],
+        Dimension::Volume,
+        0.029_573_529_562_5,
+    ),
+    (
+        &["tbsp", "tablespoon", "tablespoons"],
+        Dimension::Volume,
+        0.014_786_764_781_25,
+    ),
+    (
+        &["tsp", "teaspoon", "teaspoons"],
+        Dimension::Volume,
+        0.004_928_921_593_75,
+    ),
+    (
+        &["m2", "sqm", "square_meter", "square_meters"],
+        Dimension::Area,
+        1.0,
+    ),
+    (
+        &["km2", "square_kilometer", "square_kilometers"],
+        Dimension::Area,
+        1_000_000.0,
+    ),
+    (
+        &["ft2", "sqft", "square_foot", "square_feet"],
+        Dimension::Area,
+        0.092_903_04,
+    ),
+    (&["acre", "acres"], Dimension::Area, 4_046.856_422_4),
+    (&["ha", "hectare", "hectares"], Dimension::Area, 10_000.0),
+    (&["m/s", "mps"], Dimension::Speed, 1.0),
+    (&["km/h", "kmh", "kph"], Dimension::Speed, 1000.0 / 3600.0),
+    (&["mph", "mi/h"], Dimension::Speed, 0.447_04),
+    (&["kn", "knot", "knots"], Dimension::Speed, 1852.0 / 3600.0),
+    (&["b", "byte", "bytes"], Dimension::Data, 1.0),
+    (&["kb", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    (&["mb", "megabyte", "megabytes"], Dimension::Data, 1e6),
+    (&["gb", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    (&["tb", "terabyte", "terabytes"], Dimension::Data, 1e12),
+    (&["kib", "kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+    (
+        &["mib", "mebibyte", "mebibytes"],
+        Dimension::Data,
+        1_048_576.0,
+    ),
+    (
+        &["gib", "gibibyte", "gibibytes"],
+        Dimension::Data,
+        1_073_741_824.0,
+    ),
+    (&["c", "°c", "celsius"], Dimension::Temperature, 0.0),
+    (&["f", "°f", "fahrenheit"], Dimension::Temperature, 0.0),
+    (&["k", "kelvin"], Dimension::Temperature, 0.0),
+];
+
+fn lookup_unit(name: &str) -> Option<Unit> {
+    let lower = name.trim().to_lowercase();
+    UNITS.iter().find_map(|(aliases, dimension, factor)| {
+        aliases.contains(&lower.as_str()).then(|| Unit {
+            dimension: *dimension,
+            factor: *factor,
+            name: aliases[0],
+        })
+    })
+}
+
+fn convert_units(value: f64, from: &Unit, to: &Unit) -> Result<f64, String> {
+    if from.dimension != to.dimension {
+        return Err(format!(
+            "cannot convert {:?} ({}) to {:?} ({})",
+            from.dimension, from.name, to.dimension, to.name
+        ));
+    }
+    if from.dimension == Dimension::Temperature {
+        let kelvin = match from.name {
+            "c" => value + 273.15,
+            "f" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+            _ => value,
+        };
+        return Ok(match to.name {
+            "c" => kelvin - 273.15,
+            "f" => (kelvin - 273.15) * 9.0 / 5.0 + 32.0,
+            _ => kelvin,
+        });
+    }
+    Ok(value * from.factor / to.factor)
+}
+
+/// Split "X to Y" / "X in Y" into (X, Y). Uses the last separator so "5 in in cm" works.
+fn split_conversion(expression: &str) -> Option<(&str, &str)> {
+    let lower = expression.to_lowercase();
+    for sep in [" to ", " in ", " as "] {
+        if let Some(idx) = lower.rfind(sep) {
+            let lhs = expression[..idx].trim();
+            let rhs = expression[idx + sep.len()..].trim();
+            if !lhs.is_empty() && !rhs.is_empty() {
+                return Some((lhs, rhs));
+            }
+        }
+    }
+    None
+}
+
+/// Split "3 * 4 km" or "12km" into ("3 * 4", "km").
+fn split_value_unit(lhs: &str) -> Option<(&str, &str)> {
+    let is_known = |s: &str| lookup_unit(s).is_some() || currency_code(s).is_some();
+    if let Some(idx) = lhs.rfind(char::is_whitespace) {
+        let (value, unit) = (lhs[..idx].trim(), lhs[idx..].trim());
+        if !value.is_empty() && is_known(unit) {
+            return Some((value, unit));
+        }
+    }
+    let idx = lhs
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphabetic() || *c == '/' || *c == '°')
+        .last()
+        .map(|(i, _)| i)?;
+    let (value, unit) = (lhs[..idx].trim(), &lhs[idx..]);
+    if !value.is_empty() && is_known(unit) {
+        Some((value, unit))
+    } else {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Currency
+// ---------------------------------------------------------------------------
+
+fn currency_code(name: &str) -> Option<String> {
+    let name = name.trim();
+    let symbol = match name {
+        "$" => Some("USD"),
+        "€" => Some("EUR"),
+        "£" => Some("GBP"),
+        "¥" => Some("JPY"),
+        _ => None,
+    };
+    if let Some(code) = symbol {
+        return Some(code.to_string());
+    }
+    (name.len() == 3 && name.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| name.to_ascii_uppercase())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RatesCache {
+    fetched_at: i64,
+    rates: HashMap<String, f64>,
+}
+
+impl RatesCache {
+    fn is_fresh(&self, now: i64) -> bool {
+        now - self.fetched_at < RATES_TTL_SECS
+    }
+
+    fn as_of(&self) -> String {
+        chrono::DateTime::from_timestamp(self.fetched_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| "unknown".into())
+    }
+}
+
+fn convert_currency(
+    value: f64,
+    from: &str,
+    to: &str,
+    rates: &HashMap<String, f64>,
+) -> Result<f64, String> {
+    let from_rate = rates
+        .get(from)
+        .ok_or_else(|| format!("unknown currency '{from}'"))?;
+    let to_rate = rates
+        .get(to)
+        .ok_or_else(|| format!("unknown currency '{to}'"))?;
+    Ok(value / from_rate * to_rate)
+}
+
+/// Load rates from the on-disk cache, refreshing from the feed when stale.
+/// A stale cache is still used if the feed is unreachable.
+async fn load_rates(cache_path: &Path) -> Result<RatesCache, String> {
+    let now = chrono::Utc::now().timestamp();
+    let cached: Option<RatesCache> = std::fs::read_to_string(cache_path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok());
+    if let Some(ref c) = cached {
+        if c.is_fresh(now) {
+            return Ok(c.clone());
+        }
+    }
+
+    match fetch_rates(now).await {
+        Ok(fresh) => {
+            if let Some(parent) = cache_path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(s) = serde_json::to_string(&fresh) {
+                if let Err(e) = std::fs::write(cache_path, s) {
+                    tracing::warn!("Failed to write currency rates cache: {e}");
+                }
+            }
+            Ok(fresh)
+        }
+        Err(e) => match cached {
+            Some(c) => {
+                tracing::warn!("Currency rates refresh failed, using stale cache: {e}");
+                Ok(c)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+async fn fetch_rates(now: i64) -> Result<RatesCache, String> {
+    #[derive(Deserialize)]
+    struct FeedResponse {
+        result: String,
+        base_code: String,
+        rates: HashMap<String, f64>,
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .get(RATES_URL)
+        .header("User-Agent", "MicroClaw/1.0")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let feed: FeedResponse = resp.json().await.map_err(|e| e.to_string())?;
+    if feed.result != "success" {
+        return Err(format!("rates feed returned '{}'", feed.result));
+    }
+    let mut rates = feed.rates;
+    rates.entry(feed.base_code).or_insert(1.0);
+    Ok(RatesCache {
+        fetched_at: now,
+        rates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_tool() -> (CalculateTool, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("microclaw_calc_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (CalculateTool::new(dir.to_str().unwrap()), dir)
+    }
+
+    #[test]
+    fn test_evaluate_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4").unwrap(), 14.0);
+        assert_eq!(evaluate("(2 + 3) * 4").unwrap(), 20.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("-2^2").unwrap(), -4.0);
+        assert_eq!(evaluate("2**10").unwrap(), 1024.0);
+        assert_eq!(evaluate("17 % 5").unwrap(), 2.0);
+        assert_eq!(evaluate("1_000 * 1.5e3").unwrap(), 1_500_000.0);
+    }
+
+    #[test]
+    fn test_evaluate_functions() {
+        assert_eq!(evaluate("sqrt(16) + abs(-2)").unwrap(), 6.0);
+        assert_eq!(evaluate("max(1, 7, 3)").unwrap(), 7.0);
+        assert_eq!(evaluate("round(1.23456, 2)").unwrap(), 1.23);
+        assert!((evaluate("2 * pi").unwrap() - std::f64::consts::TAU).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert!(evaluate("1 / 0").unwrap_err().contains("division by zero"));
+        assert!(evaluate("2 +").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("foo(1)").unwrap_err().contains("unknown function"));
+        assert!(evaluate("1 $ 2").is_err());
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(42.0), "42");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(-1.5), "-1.5");
+    }
+
+    #[test]
+    fn test_split_conversion_and_unit() {
+        assert_eq!(split_conversion("5 in in cm"), Some(("5 in", "cm")));
+        assert_eq!(split_conversion("10 km to mi"), Some(("10 km", "mi")));
+        assert_eq!(split_conversion("2 + 2"), None);
+        assert_eq!(split_value_unit("3 * 4 km"), Some(("3 * 4", "km")));
+        assert_eq!(split_value_unit("12km"), Some(("12", "km")));
+        assert_eq!(split_value_unit("100 USD"), Some(("100", "USD")));
+    }
+
+    #[test]
+    fn test_convert_units() {
+        let km = lookup_unit("km").unwrap();
+        let mi = lookup_unit("miles").unwrap();
+        let result = convert_units(1.609344, &km, &mi).unwrap();
+        assert!((result - 1.0).abs() < 1e-12);
+
+        let f = lookup_unit("F").unwrap();
+        let c = lookup_unit("celsius").unwrap();
+        assert!((convert_units(212.0, &f, &c).unwrap() - 100.0).abs() < 1e-9);
+
+        let kg = lookup_unit("kg").unwrap();
+        assert!(convert_units(1.0, &km, &kg).is_err());
+    }
+
+    #[test]
+    fn test_convert_currency() {
+        let rates: HashMap<String, f64> =
+            [("USD".to_string(), 1.0), ("EUR".to_string(), 0.5)].into();
+        assert_eq!(convert_currency(10.0, "USD", "EUR", &rates).unwrap(), 5.0);
+        assert_eq!(convert_currency(10.0, "EUR", "USD", &rates).unwrap(), 20.0);
+        assert!(convert_currency(1.0, "USD", "XYZ", &rates).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_arithmetic_and_units() {
+        let (tool, dir) = temp_tool();
+        let result = tool.execute(json!({"expression": "0.1 + 0.2"})).await;
+        assert!(!result.is_error);
+        assert_eq!(result.content, "0.1 + 0.2 = 0.3");
+
+        let result = tool.execute(json!({"expression": "(3 + 2) km to m"})).await;
+        assert!(!result.is_error);
+        assert_eq!(result.content, "5 km = 5000 m");
+
+        let result = tool.execute(json!({"expression": "5 kg to mi"})).await;
+        assert!(result.is_error);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_execute_currency_uses_fresh_cache() {
+        let (tool, dir) = temp_tool();
+        let cache = RatesCache {
+            fetched_at: chrono::Utc::now().timestamp(),
+            rates: [("USD".to_string(), 1.0), ("EUR".to_string(), 0.8)].into(),
+        };
+        std::fs::create_dir_all(tool.cache_path.parent().unwrap()).unwrap();
+        std::fs::write(&tool.cache_path, serde_json::to_string(&cache).unwrap()).unwrap();
+
+        let result = tool.execute(json!({"expression": "100 usd to eur"})).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.starts_with("100 USD = 80.00 EUR"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_execute_missing_expression() {
+        let (tool, dir) = temp_tool();
+        let result = tool.execute(json!({})).await;
+        assert!(result.is_error);
+        assert!(result
+            .content
+            .contains("Missing required parameter: expression"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
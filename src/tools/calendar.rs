@@ -0,0 +1,929 @@
+//! Calendar tools: `list_events` and `create_event`. Events live on a CalDAV calendar
+//! (`calendar.caldav_url`, shared by the allowed chats) or in Google Calendar, where each chat
+//! uses its own Google connection and is asked for the Calendar scope on first use.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::json;
+
+use super::google::{get_json, google_token_or_authorize};
+use super::social_feed::http_client;
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::Database;
+
+pub(crate) const CALENDAR_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+
+const GOOGLE_CALENDAR_API: &str = "https://www.googleapis.com/calendar/v3/calendars";
+const DEFAULT_RANGE_DAYS: i64 = 7;
+const DEFAULT_DURATION_MINS: i64 = 60;
+const MAX_EVENTS: usize = 20;
+
+/// Where events are read from and written to.
+#[derive(Clone, Debug)]
+pub enum CalendarBackend {
+    CalDav {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+        chat_ids: Vec<i64>,
+    },
+    Google {
+        calendar_id: String,
+    },
+}
+
+impl CalendarBackend {
+    /// CalDAV when `calendar.caldav_url` is set, else Google Calendar when Google OAuth is
+    /// configured, else None (no calendar tools).
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let cal = config.calendar.clone().unwrap_or_default();
+        if let Some(url) = cal.caldav_url.filter(|u| !u.trim().is_empty()) {
+            return Some(CalendarBackend::CalDav {
+                url,
+                username: cal.username,
+                password: cal.password,
+                chat_ids: cal.chat_ids,
+            });
+        }
+        let google = config
+            .social
+            .as_ref()
+            .is_some_and(|s| s.is_platform_enabled("google"));
+        google.then_some(CalendarBackend::Google {
+            calendar_id: cal.google_calendar_id,
+        })
+    }
+}
+
+/// A time given to or returned by the tools: a whole day or a moment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum When {
+    Day(NaiveDate),
+    At(DateTime<Utc>),
+}
+
+impl When {
+    /// Start of the day (in `tz`) or the moment itself.
+    fn instant(self, tz: Tz) -> DateTime<Utc> {
+        match self {
+            When::At(t) => t,
+            When::Day(d) => local_to_utc(d.and_hms_opt(0, 0, 0).unwrap_or_default(), tz),
+        }
+    }
+}
+
+fn local_to_utc(naive: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        // A time skipped by a DST change: take it as UTC rather than failing
+        .unwrap_or_else(|| Utc.from_utc_datetime(&naive))
+}
+
+/// Parse `2026-10-23` (all day), `2026-10-23T15:00` or `2026-10-23 15:00:00` (in `tz`), or an
+/// RFC 3339 time with offset.
+pub fn parse_when(s: &str, tz: Tz) -> Result<When, String> {
+    let s = s.trim();
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(When::Day(d));
+    }
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(When::At(t.with_timezone(&Utc)));
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(When::At(local_to_utc(naive, tz)));
+        }
+    }
+    Err(format!(
+        "Invalid time '{s}': use YYYY-MM-DD for all-day or YYYY-MM-DDTHH:MM (local time)"
+    ))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalendarEvent {
+    pub id: String,
+    pub summary: String,
+    pub start: When,
+    /// Exclusive end (the day after the last day for all-day events).
+    pub end: Option<When>,
+    pub location: Option<String>,
+}
+
+/// One line per event, times in `tz`.
+fn format_event(event: &CalendarEvent, tz: Tz) -> String {
+    let when = match (event.start, event.end) {
+        (When::Day(start), end) => {
+            let last = match end {
+                Some(When::Day(end)) if end > start + Duration::days(1) => {
+                    Some(end - Duration::days(1))
+                }
+                _ => None,
+            };
+            match last {
+                Some(last) => format!(
+                    "{} – {} (all day)",
+                    start.format("%a %Y-%m-%d"),
+                    last.format("%a %Y-%m-%d")
+                ),
+                None => format!("{} (all day)", start.format("%a %Y-%m-%d")),
+            }
+        }
+        (When::At(start), end) => {
+            let start = start.with_timezone(&tz);
+            let end = end.map(|e| e.instant(tz).with_timezone(&tz));
+            match end {
+                Some(end) if end.date_naive() == start.date_naive() => format!(
+                    "{}–{}",
+                    start.format("%a %Y-%m-%d %H:%M"),
+                    end.format("%H:%M")
+                ),
+                Some(end) => format!(
+                    "{} – {}",
+                    start.format("%a %Y-%m-%d %H:%M"),
+                    end.format("%a %Y-%m-%d %H:%M")
+                ),
+                None => start.format("%a %Y-%m-%d %H:%M").to_string(),
+            }
+        }
+    };
+    let mut line = format!("- {when}: {}", event.summary);
+    if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+        line.push_str(&format!(" @ {location}"));
+    }
+    line
+}
+
+// --- iCalendar (CalDAV) ---
+
+fn ical_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn ical_unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// `DTSTART`/`DTEND` value: a date, a UTC time, or a local time in `TZID` (or `tz`).
+fn parse_ical_time(params: &str, value: &str, tz: Tz) -> Option<When> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(When::Day);
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(When::At(Utc.from_utc_datetime(&naive)));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = params
+        .split(';')
+        .find_map(|p| p.strip_prefix("TZID="))
+        .and_then(|id| id.trim_matches('"').parse::<Tz>().ok())
+        .unwrap_or(tz);
+    Some(When::At(local_to_utc(naive, zone)))
+}
+
+/// A VEVENT while its properties are being read.
+#[derive(Default)]
+struct PartialEvent {
+    uid: String,
+    summary: String,
+    start: Option<When>,
+    end: Option<When>,
+    location: Option<String>,
+}
+
+/// VEVENTs in an iCalendar document.
+pub fn parse_ical_events(ics: &str, tz: Tz) -> Vec<CalendarEvent> {
+    // Unfold continuation lines (RFC 5545 3.1)
+    let mut lines: Vec<String> = Vec::new();
+    for raw in ics.lines() {
+        match raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(raw.trim_end_matches('\r').to_string()),
+        }
+    }
+    let mut events = Vec::new();
+    let mut current: Option<PartialEvent> = None;
+    for line in lines {
+        if line == "BEGIN:VEVENT" {
+            current = Some(PartialEvent::default());
+            continue;
+        }
+        if line == "END:VEVENT" {
+            if let Some(PartialEvent {
+                uid,
+                summary,
+                start: Some(start),
+                end,
+                location,
+            }) = current.take()
+            {
+                events.push(CalendarEvent {
+                    id: uid,
+                    summary,
+                    start,
+                    end,
+                    location,
+                });
+            }
+            continue;
+        }
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = key.split_once(';').unwrap_or((key, ""));
+        match name {
+            "UID" => event.uid = value.to_string(),
+            "SUMMARY" => event.summary = ical_unescape(value),
+            "DTSTART" => event.start = parse_ical_time(params, value, tz),
+            "DTEND" => event.end = parse_ical_time(params, value, tz),
+            "LOCATION" => event.location = Some(ical_unescape(value)),
+            _ => {}
+        }
+    }
+    events
+}
+
+fn ical_time_line(name: &str, when: When) -> String {
+    match when {
+        When::Day(d) => format!("{name};VALUE=DATE:{}", d.format("%Y%m%d")),
+        When::At(t) => format!("{name}:{}", t.format("%Y%m%dT%H%M%SZ")),
+    }
+}
+
+/// A VCALENDAR with one VEVENT; times are written in UTC so no VTIMEZONE is needed.
+pub fn build_ical_event(
+    uid: &str,
+    summary: &str,
+    start: When,
+    end: When,
+    location: Option<&str>,
+    description: Option<&str>,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//microclaw//calendar//EN".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{uid}"),
+        format!("DTSTAMP:{}", Utc::now().format("%Y%m%dT%H%M%SZ")),
+        ical_time_line("DTSTART", start),
+        ical_time_line("DTEND", end),
+        format!("SUMMARY:{}", ical_escape(summary)),
+    ];
+    if let Some(location) = location {
+        lines.push(format!("LOCATION:{}", ical_escape(location)));
+    }
+    if let Some(description) = description {
+        lines.push(format!("DESCRIPTION:{}", ical_escape(description)));
+    }
+    lines.extend(["END:VEVENT".to_string(), "END:VCALENDAR".to_string()]);
+    lines.join("\r\n") + "\r\n"
+}
+
+/// `calendar-data` texts of a CalDAV multistatus response.
+pub fn calendar_data_from_multistatus(xml: &str) -> Result<Vec<String>, String> {
+    let doc =
+        roxmltree::Document::parse(xml).map_err(|e| format!("Invalid CalDAV response: {e}"))?;
+    Ok(doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "calendar-data")
+        .filter_map(|n| n.text().map(str::to_string))
+        .collect())
+}
+
+fn caldav_request(
+    client: &reqwest::Client,
+    method: reqwest::Method,
+    url: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let req = client.request(method, url);
+    match username {
+        Some(user) => req.basic_auth(user, password),
+        None => req,
+    }
+}
+
+async fn caldav_list(
+    url: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    tz: Tz,
+) -> Result<Vec<CalendarEvent>, String> {
+    let (start, end) = (from.format("%Y%m%dT%H%M%SZ"), to.format("%Y%m%dT%H%M%SZ"));
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data><C:expand start="{start}" end="{end}"/></C:calendar-data></D:prop>
+  <C:filter><C:comp-filter name="VCALENDAR"><C:comp-filter name="VEVENT">
+    <C:time-range start="{start}" end="{end}"/>
+  </C:comp-filter></C:comp-filter></C:filter>
+</C:calendar-query>"#
+    );
+    let client = http_client()?;
+    let method = reqwest::Method::from_bytes(b"REPORT").map_err(|e| e.to_string())?;
+    let resp = caldav_request(&client, method, url, username, password)
+        .header("Depth", "1")
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("CalDAV request failed: {e}"))?;
+    let status = resp.status();
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("CalDAV error (HTTP {status})"));
+    }
+    let mut events: Vec<CalendarEvent> = calendar_data_from_multistatus(&text)?
+        .iter()
+        .flat_map(|ics| parse_ical_events(ics, tz))
+        .collect();
+    events.sort_by_key(|e| e.start.instant(tz));
+    Ok(events)
+}
+
+async fn caldav_create(
+    url: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+    ics: String,
+    uid: &str,
+) -> Result<(), String> {
+    let client = http_client()?;
+    let event_url = format!("{}/{uid}.ics", url.trim_end_matches('/'));
+    let resp = caldav_request(
+        &client,
+        reqwest::Method::PUT,
+        &event_url,
+        username,
+        password,
+    )
+    .header("If-None-Match", "*")
+    .header("Content-Type", "text/calendar; charset=utf-8")
+    .body(ics)
+    .send()
+    .await
+    .map_err(|e| format!("CalDAV request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("CalDAV error (HTTP {})", resp.status()));
+    }
+    Ok(())
+}
+
+// --- Google Calendar ---
+
+fn google_time(value: &serde_json::Value) -> Option<When> {
+    if let Some(t) = value.get("dateTime").and_then(|v| v.as_str()) {
+        return DateTime::parse_from_rfc3339(t)
+            .ok()
+            .map(|t| When::At(t.with_timezone(&Utc)));
+    }
+    value
+        .get("date")
+        .and_then(|v| v.as_str())
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .map(When::Day)
+}
+
+fn google_event(item: &serde_json::Value) -> Option<CalendarEvent> {
+    Some(CalendarEvent {
+        id: item.get("id")?.as_str()?.to_string(),
+        summary: item
+            .get("summary")
+            .and_then(|v| v.as_str())
+            .unwrap_or("(no title)")
+            .to_string(),
+        start: google_time(item.get("start")?)?,
+        end: item.get("end").and_then(google_time),
+        location: item
+            .get("location")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+    })
+}
+
+fn google_time_json(when: When, tz: Tz) -> serde_json::Value {
+    match when {
+        When::Day(d) => json!({"date": d.format("%Y-%m-%d").to_string()}),
+        When::At(t) => json!({
+            "dateTime": t.with_timezone(&tz).to_rfc3339(),
+            "timeZone": tz.name(),
+        }),
+    }
+}
+
+fn events_url(calendar_id: &str) -> String {
+    format!(
+        "{GOOGLE_CALENDAR_API}/{}/events",
+        urlencoding::encode(calendar_id)
+    )
+}
+
+// --- Tools ---
+
+/// Google token for the calling chat, or the result asking it to connect Google.
+async fn google_token(
+    config: &Config,
+    db: &Arc<Database>,
+    input: &serde_json::Value,
+) -> Result<String, ToolResult> {
+    google_token_or_authorize(config, db.clone(), CALENDAR_SCOPE, "Google Calendar", input).await
+}
+
+/// The CalDAV calendar is shared; only the configured chats may use it.
+fn check_caldav_chat(chat_ids: &[i64], input: &serde_json::Value) -> Result<(), ToolResult> {
+    match auth_context_from_input(input) {
+        Some(auth) if !chat_ids.is_empty() && !chat_ids.contains(&auth.caller_chat_id) => {
+            Err(ToolResult::error(format!(
+                "Permission denied: chat {} may not use the calendar",
+                auth.caller_chat_id
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn str_arg<'a>(input: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    input
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+pub struct ListEventsTool {
+    config: Config,
+    db: Arc<Database>,
+    backend: Arc<CalendarBackend>,
+    tz: Tz,
+}
+
+impl ListEventsTool {
+    pub fn new(config: &Config, db: Arc<Database>, backend: Arc<CalendarBackend>) -> Self {
+        ListEventsTool {
+            config: config.clone(),
+            db,
+            backend,
+            tz: config.timezone.parse().unwrap_or(Tz::UTC),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ListEventsTool {
+    fn name(&self) -> &str {
+        "list_events"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_events".into(),
+            description: format!(
+                "List events on the user's calendar between two times (default: the next {DEFAULT_RANGE_DAYS} days). Times are in {} unless they carry an offset. Use before create_event to check for conflicts.",
+                self.tz.name()
+            ),
+            input_schema: schema_object(
+                json!({
+                    "from": {
+                        "type": "string",
+                        "description": "Start: YYYY-MM-DD or YYYY-MM-DDTHH:MM (default: now)"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "End (exclusive): YYYY-MM-DD or YYYY-MM-DDTHH:MM"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Only events whose title contains this text"
+                    }
+                }),
+                &[],
+            ),
+            examples: vec![json!({"from": "2026-10-23", "to": "2026-10-24"})],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let from = match str_arg(&input, "from").map(|s| parse_when(s, self.tz)) {
+            Some(Ok(w)) => w.instant(self.tz),
+            Some(Err(e)) => return ToolResult::error(e),
+            None => Utc::now(),
+        };
+        let to = match str_arg(&input, "to").map(|s| parse_when(s, self.tz)) {
+            Some(Ok(w)) => w.instant(self.tz),
+            Some(Err(e)) => return ToolResult::error(e),
+            None => from + Duration::days(DEFAULT_RANGE_DAYS),
+        };
+        if to <= from {
+            return ToolResult::error("'to' must be after 'from'".into());
+        }
+        let query = str_arg(&input, "query").map(str::to_lowercase);
+
+        let events = match self.backend.as_ref() {
+            CalendarBackend::CalDav {
+                url,
+                username,
+                password,
+                chat_ids,
+            } => {
+                if let Err(r) = check_caldav_chat(chat_ids, &input) {
+                    return r;
+                }
+                caldav_list(
+                    url,
+                    username.as_deref(),
+                    password.as_deref(),
+                    from,
+                    to,
+                    self.tz,
+                )
+                .await
+            }
+            CalendarBackend::Google { calendar_id } => {
+                let token = match google_token(&self.config, &self.db, &input).await {
+                    Ok(t) => t,
+                    Err(r) => return r,
+                };
+                let client = match http_client() {
+                    Ok(c) => c,
+                    Err(e) => return ToolResult::error(e),
+                };
+                let mut params = vec![
+                    ("timeMin", from.to_rfc3339()),
+                    ("timeMax", to.to_rfc3339()),
+                    ("singleEvents", "true".to_string()),
+                    ("orderBy", "startTime".to_string()),
+                    ("maxResults", "100".to_string()),
+                ];
+                if let Some(q) = &query {
+                    params.push(("q", q.clone()));
+                }
+                get_json(&client, &token, &events_url(calendar_id), &params)
+                    .await
+                    .map(|body| {
+                        body.get("items")
+                            .and_then(|v| v.as_array())
+                            .map(|items| items.iter().filter_map(google_event).collect())
+                            .unwrap_or_default()
+                    })
+            }
+        };
+        let events: Vec<CalendarEvent> = match events {
+            Ok(events) => events
+                .into_iter()
+                .filter(|e| {
+                    query
+                        .as_deref()
+                        .is_none_or(|q| e.summary.to_lowercase().contains(q))
+                })
+                .collect(),
+            Err(e) => return ToolResult::error(e),
+        };
+
+        let range = format!(
+            "{} – {}",
+            from.with_timezone(&self.tz).format("%a %Y-%m-%d %H:%M"),
+            to.with_timezone(&self.tz).format("%a %Y-%m-%d %H:%M")
+        );
+        if events.is_empty() {
+            return ToolResult::success(format!("No events {range}."));
+        }
+        let mut out = format!("{} event(s) {range} ({}):\n", events.len(), self.tz.name());
+        for event in events.iter().take(MAX_EVENTS) {
+            out.push_str(&format_event(event, self.tz));
+            out.push('\n');
+        }
+        if events.len() > MAX_EVENTS {
+            out.push_str(&format!(
+                "... and {} more; narrow the range to see them.\n",
+                events.len() - MAX_EVENTS
+            ));
+        }
+        ToolResult::success(out.trim_end().to_string())
+    }
+}
+
+pub struct CreateEventTool {
+    config: Config,
+    db: Arc<Database>,
+    backend: Arc<CalendarBackend>,
+    tz: Tz,
+}
+
+impl CreateEventTool {
+    pub fn new(config: &Config, db: Arc<Database>, backend: Arc<CalendarBackend>) -> Self {
+        CreateEventTool {
+            config: config.clone(),
+            db,
+            backend,
+            tz: config.timezone.parse().unwrap_or(Tz::UTC),
+        }
+    }
+}
+
+/// End of a new event: `end` if given, else the day after an all-day start, else start plus
+/// `duration_mins` (default one hour).
+fn event_end(start: When, end: Option<When>, duration_mins: Option<i64>) -> Result<When, String> {
+    let end = match (start, end) {
+        (_, Some(end)) => end,
+        (When::Day(d), None) => When::Day(d + Duration::days(1)),
+        (When::At(t), None) => {
+            When::At(t + Duration::minutes(duration_mins.unwrap_or(DEFAULT_DURATION_MINS).max(1)))
+        }
+    };
+    match (start, end) {
+        (When::Day(s), When::Day(e)) if e > s => Ok(end),
+        (When::At(s), When::At(e)) if e > s => Ok(end),
+        (When::Day(_), When::At(_)) | (When::At(_), When::Day(_)) => {
+            Err("'start' and 'end' must both be dates or both be times".into())
+        }
+        _ => Err("'end' must be after 'start'".into()),
+    }
+}
+
+#[async_trait]
+impl Tool for CreateEventTool {
+    fn name(&self) -> &str {
+        "create_event"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "create_event".into(),
+            description: format!(
+                "Add an event to the user's calendar. Resolve relative dates (\"Friday 3pm\") against the current date first; times are in {} unless they carry an offset. A date without a time makes an all-day event.",
+                self.tz.name()
+            ),
+            input_schema: schema_object(
+                json!({
+                    "title": {"type": "string", "description": "Event title"},
+                    "start": {
+                        "type": "string",
+                        "description": "YYYY-MM-DDTHH:MM, or YYYY-MM-DD for all-day"
+                    },
+                    "end": {
+                        "type": "string",
+                        "description": "Optional end (exclusive for all-day events)"
+                    },
+                    "duration_mins": {
+                        "type": "integer",
+                        "description": "Length when 'end' is omitted (default 60)"
+                    },
+                    "location": {"type": "string"},
+                    "description": {"type": "string"}
+                }),
+                &["title", "start"],
+            ),
+            examples: vec![
+                json!({"title": "Dentist", "start": "2026-10-23T15:00", "duration_mins": 45}),
+            ],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(title) = str_arg(&input, "title") else {
+            return ToolResult::error("Missing 'title'".into());
+        };
+        let start = match str_arg(&input, "start").map(|s| parse_when(s, self.tz)) {
+            Some(Ok(w)) => w,
+            Some(Err(e)) => return ToolResult::error(e),
+            None => return ToolResult::error("Missing 'start'".into()),
+        };
+        let end = match str_arg(&input, "end").map(|s| parse_when(s, self.tz)) {
+            Some(Ok(w)) => Some(w),
+            Some(Err(e)) => return ToolResult::error(e),
+            None => None,
+        };
+        let end = match event_end(
+            start,
+            end,
+            input.get("duration_mins").and_then(|v| v.as_i64()),
+        ) {
+            Ok(end) => end,
+            Err(e) => return ToolResult::error(e),
+        };
+        let location = str_arg(&input, "location");
+        let description = str_arg(&input, "description");
+
+        let created = match self.backend.as_ref() {
+            CalendarBackend::CalDav {
+                url,
+                username,
+                password,
+                chat_ids,
+            } => {
+                if let Err(r) = check_caldav_chat(chat_ids, &input) {
+                    return r;
+                }
+                let uid = format!("{}@microclaw", uuid::Uuid::new_v4());
+                let ics = build_ical_event(&uid, title, start, end, location, description);
+                caldav_create(url, username.as_deref(), password.as_deref(), ics, &uid)
+                    .await
+                    .map(|_| None)
+            }
+            CalendarBackend::Google { calendar_id } => {
+                let token = match google_token(&self.config, &self.db, &input).await {
+                    Ok(t) => t,
+                    Err(r) => return r,
+                };
+                let client = match http_client() {
+                    Ok(c) => c,
+                    Err(e) => return ToolResult::error(e),
+                };
+                let mut body = json!({
+                    "summary": title,
+                    "start": google_time_json(start, self.tz),
+                    "end": google_time_json(end, self.tz),
+                });
+                if let Some(location) = location {
+                    body["location"] = json!(location);
+                }
+                if let Some(description) = description {
+                    body["description"] = json!(description);
+                }
+                create_google_event(&client, &token, calendar_id, &body).await
+            }
+        };
+        match created {
+            Ok(link) => {
+                let event = CalendarEvent {
+                    id: String::new(),
+                    summary: title.to_string(),
+                    start,
+                    end: Some(end),
+                    location: location.map(str::to_string),
+                };
+                let mut text = format!(
+                    "Created:\n{} ({})",
+                    format_event(&event, self.tz),
+                    self.tz.name()
+                );
+                if let Some(link) = link {
+                    text.push_str(&format!("\n{link}"));
+                }
+                ToolResult::success(text)
+            }
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+/// Insert an event; returns its `htmlLink`.
+async fn create_google_event(
+    client: &reqwest::Client,
+    token: &str,
+    calendar_id: &str,
+    body: &serde_json::Value,
+) -> Result<Option<String>, String> {
+    let resp = client
+        .post(events_url(calendar_id))
+        .bearer_auth(token)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let msg = body
+            .pointer("/error/message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("request failed");
+        return Err(format!("Google API error (HTTP {status}): {msg}"));
+    }
+    Ok(body
+        .get("htmlLink")
+        .and_then(|v| v.as_str())
+        .map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_when_and_event_end() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        assert_eq!(
+            parse_when("2026-10-23", tz).unwrap(),
+            When::Day(NaiveDate::from_ymd_opt(2026, 10, 23).unwrap())
+        );
+        let at = parse_when("2026-10-23T15:00", tz).unwrap();
+        assert_eq!(
+            at,
+            When::At(Utc.with_ymd_and_hms(2026, 10, 23, 13, 0, 0).unwrap())
+        );
+        assert_eq!(parse_when("2026-10-23T13:00:00Z", tz).unwrap(), at);
+        assert!(parse_when("Friday 3pm", tz).is_err());
+
+        assert_eq!(
+            event_end(at, None, Some(45)).unwrap(),
+            When::At(Utc.with_ymd_and_hms(2026, 10, 23, 13, 45, 0).unwrap())
+        );
+        let day = parse_when("2026-10-23", tz).unwrap();
+        assert_eq!(
+            event_end(day, None, None).unwrap(),
+            When::Day(NaiveDate::from_ymd_opt(2026, 10, 24).unwrap())
+        );
+        assert!(event_end(at, Some(day), None).is_err());
+        assert!(event_end(at, Some(at), None).is_err());
+    }
+
+    #[test]
+    fn test_ical_round_trip_and_multistatus() {
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let start = When::At(Utc.with_ymd_and_hms(2026, 10, 23, 13, 0, 0).unwrap());
+        let end = When::At(Utc.with_ymd_and_hms(2026, 10, 23, 14, 0, 0).unwrap());
+        let ics = build_ical_event(
+            "abc@microclaw",
+            "Dentist, Dr. Lee",
+            start,
+            end,
+            Some("Main St; 2nd floor"),
+            None,
+        );
+        assert!(ics.contains("SUMMARY:Dentist\\, Dr. Lee\r\n"));
+        let events = parse_ical_events(&ics, tz);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Dentist, Dr. Lee");
+        assert_eq!(events[0].location.as_deref(), Some("Main St; 2nd floor"));
+        assert_eq!(
+            format_event(&events[0], tz),
+            "- Fri 2026-10-23 15:00–16:00: Dentist, Dr. Lee @ Main St; 2nd floor"
+        );
+
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response><d:href>/cal/a.ics</d:href><d:propstat><d:prop>
+    <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:a
+DTSTART;TZID=Europe/Berlin:20261024T090000
+DTEND;TZID=Europe/Berlin:20261024T093000
+SUMMARY:Standup with a very long
+  title
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data></d:prop></d:propstat></d:response>
+  <d:response><d:href>/cal/b.ics</d:href><d:propstat><d:prop>
+    <cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:b
+DTSTART;VALUE=DATE:20261025
+DTEND;VALUE=DATE:20261027
+SUMMARY:Trip
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data></d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+        let events: Vec<CalendarEvent> = calendar_data_from_multistatus(xml)
+            .unwrap()
+            .iter()
+            .flat_map(|ics| parse_ical_events(ics, tz))
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            format_event(&events[0], tz),
+            "- Sat 2026-10-24 09:00–09:30: Standup with a very long title"
+        );
+        assert_eq!(
+            format_event(&events[1], tz),
+            "- Sun 2026-10-25 – Mon 2026-10-26 (all day): Trip"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::{Message, MessageContent, ResponseContentBlock, ToolDefinition};
+use crate::db::{call_blocking, Database, ThreadedMessage};
+use crate::error::MicroClawError;
+use crate::llm::LlmProvider;
+use crate::text::truncate_chars;
+
+/// Most unread messages included in one digest (the newest are kept).
+pub const DEFAULT_CATCH_UP_LIMIT: usize = 200;
+/// Lookback when the user has no recorded activity in the chat.
+const FIRST_CATCH_UP_LOOKBACK_HOURS: i64 = 24;
+const MAX_MESSAGE_CHARS: usize = 500;
+
+pub struct CatchUpDigest {
+    pub since: String,
+    pub messages: Vec<ThreadedMessage>,
+}
+
+/// Collect messages from others since the user's last activity (or last catch-up).
+pub async fn collect_catch_up(
+    db: Arc<Database>,
+    chat_id: i64,
+    user_name: &str,
+    limit: usize,
+) -> Result<CatchUpDigest, MicroClawError> {
+    let user = user_name.to_string();
+    call_blocking(db, move |d| {
+        let since = d.get_catch_up_since(chat_id, &user)?.unwrap_or_else(|| {
+            (chrono::Utc::now() - chrono::Duration::hours(FIRST_CATCH_UP_LOOKBACK_HOURS))
+                .to_rfc3339()
+        });
+        let messages = d.get_messages_for_catch_up(chat_id, &user, Some(&since), limit)?;
+        Ok(CatchUpDigest { since, messages })
+    })
+    .await
+}
+
+/// Render unread messages as a transcript grouped by forum topic (first-seen order).
+pub fn format_catch_up_transcript(messages: &[ThreadedMessage]) -> String {
+    let mut groups: Vec<(Option<i64>, String, Vec<String>)> = Vec::new();
+    for m in messages {
+        let line = format!(
+            "[{}] {}: {}",
+            m.message
+                .timestamp
+                .get(..16)
+                .unwrap_or(&m.message.timestamp),
+            m.message.sender_name,
+            truncate_chars(&m.message.content, MAX_MESSAGE_CHARS)
+        );
+        match groups.iter_mut().find(|(tid, _, _)| *tid == m.thread_id) {
+            Some((_, _, lines)) => lines.push(line),
+            None => {
+                let title = match (m.thread_id, m.topic_name.as_deref()) {
+                    (None, _) => "General".to_string(),
+                    (Some(_), Some(name)) => name.to_string(),
+                    (Some(tid), None) => format!("Topic #{tid}"),
+                };
+                groups.push((m.thread_id, title, vec![line]));
+            }
+        }
+    }
+    if groups.len() == 1 && groups[0].0.is_none() {
+        return groups.remove(0).2.join("\n");
+    }
+    groups
+        .into_iter()
+        .map(|(_, title, lines)| format!("## {title}\n{}", lines.join("\n")))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+async fn mark_caught_up(db: Arc<Database>, chat_id: i64, user_name: &str) {
+    let user = user_name.to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(e) = call_blocking(db, move |d| d.mark_caught_up(chat_id, &user, &now)).await {
+        tracing::warn!("catch_me_up: failed to record catch-up for {chat_id}: {e}");
+    }
+}
+
+/// Digest for slash commands: collects unread messages and summarizes them with one LLM call
+/// (no tools). Falls back to the raw transcript if summarization fails.
+pub async fn summarize_catch_up(
+    db: Arc<Database>,
+    llm: &dyn LlmProvider,
+    chat_id: i64,
+    user_name: &str,
+) -> String {
+    let digest =
+        match collect_catch_up(db.clone(), chat_id, user_name, DEFAULT_CATCH_UP_LIMIT).await {
+            Ok(d) => d,
+            Err(e) => return format!("Error collecting messages: {e}"),
+        };
+    if digest.messages.is_empty() {
+        return "You're all caught up — nothing new since you were last here.".into();
+    }
+    let transcript = format_catch_up_transcript(&digest.messages);
+    let system = format!(
+        "Summarize these group chat messages for {user_name}, who missed them. Keep each topic \
+         heading if present. Be brief: key points, decisions, questions addressed to \
+         {user_name}, and action items. Do not invent details."
+    );
+    let messages = vec![Message {
+        role: "user".into(),
+        content: MessageContent::Text(transcript.clone()),
+    }];
+    let summary = match llm.send_message(&system, messages, None).await {
+        Ok(resp) => resp
+            .content
+            .iter()
+            .filter_map(|b| match b {
+                ResponseContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("")
+            .trim()
+            .to_string(),
+        Err(e) => {
+            tracing::warn!("catch_me_up: summarization failed: {e}");
+            String::new()
+        }
+    };
+    mark_caught_up(db, chat_id, user_name).await;
+    let count = digest.messages.len();
+    if summary.is_empty() {
+        format!("{count} new messages:\n\n{transcript}")
+    } else {
+        format!("Catch-up ({count} new messages):\n\n{summary}")
+    }
+}
+
+pub struct CatchMeUpTool {
+    db: Arc<Database>,
+}
+
+impl CatchMeUpTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        CatchMeUpTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for CatchMeUpTool {
+    fn name(&self) -> &str {
+        "catch_me_up"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "catch_me_up".into(),
+            description: "Get the messages a user missed in a group chat since their last activity (or last catch-up), grouped by forum topic. Summarize the result for them. Marks the user as caught up.".into(),
+            input_schema: schema_object(
+                json!({
+                    "user": {
+                        "type": "string",
+                        "description": "Sender name of the user asking (as shown in their message)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Group chat ID (defaults to the current chat)"
+                    },
+                    "max_messages": {
+                        "type": "integer",
+                        "description": "Maximum messages to include (default 200, newest kept)"
+                    }
+                }),
+                &["user"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let user = match input.get("user").and_then(|v| v.as_str()) {
+            Some(u) if !u.trim().is_empty() => u.trim().to_string(),
+            _ => return ToolResult::error("Missing required parameter: user".into()),
+        };
+        let Some(chat_id) = input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth_context_from_input(&input).map(|a| a.caller_chat_id))
+        else {
+            return ToolResult::error("Missing required parameter: chat_id".into());
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        let limit = input
+            .get("max_messages")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).clamp(1, 1000))
+            .unwrap_or(DEFAULT_CATCH_UP_LIMIT);
+
+        let digest = match collect_catch_up(self.db.clone(), chat_id, &user, limit).await {
+            Ok(d) => d,
+            Err(e) => return ToolResult::error(format!("Failed to load messages: {e}")),
+        };
+        mark_caught_up(self.db.clone(), chat_id, &user).await;
+        if digest.messages.is_empty() {
+            return ToolResult::success(format!(
+                "No new messages for {user} since {}.",
+                digest.since
+            ));
+        }
+        ToolResult::success(format!(
+            "{} messages {user} missed since {}:\n\n{}",
+            digest.messages.len(),
+            digest.since,
+            format_catch_up_transcript(&digest.messages)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::StoredMessage;
+
+    fn threaded(id: &str, sender: &str, thread: Option<(i64, &str)>) -> ThreadedMessage {
+        ThreadedMessage {
+            message: StoredMessage {
+                id: id.into(),
+                chat_id: 1,
+                persona_id: 1,
+                sender_name: sender.into(),
+                content: format!("text {id}"),
+                is_from_bot: false,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            },
+            thread_id: thread.map(|t| t.0),
+            topic_name: thread.map(|t| t.1.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_transcript_groups_by_topic() {
+        let msgs = vec![
+            threaded("1", "bob", Some((7, "Garden"))),
+            threaded("2", "carol", None),
+            threaded("3", "bob", Some((7, "Garden"))),
+        ];
+        let out = format_catch_up_transcript(&msgs);
+        let garden = out.find("## Garden").unwrap();
+        let general = out.find("## General").unwrap();
+        assert!(garden < general);
+        assert!(out[garden..general].contains("text 3"));
+    }
+
+    #[test]
+    fn test_transcript_plain_without_topics() {
+        let msgs = vec![threaded("1", "bob", None), threaded("2", "carol", None)];
+        let out = format_catch_up_transcript(&msgs);
+        assert!(!out.contains("##"));
+        assert!(out.contains("bob: text 1"));
+    }
+
+    #[tokio::test]
+    async fn test_catch_me_up_tool_marks_caught_up() {
+        let dir = std::env::temp_dir().join(format!("microclaw_catchup_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let now = chrono::Utc::now();
+        for (i, sender) in ["alice", "bob"].iter().enumerate() {
+            db.store_message(&StoredMessage {
+                id: i.to_string(),
+                chat_id: 9,
+                persona_id: 1,
+                sender_name: sender.to_string(),
+                content: format!("hello from {sender}"),
+                is_from_bot: false,
+                timestamp: (now - chrono::Duration::minutes(10 - i as i64)).to_rfc3339(),
+            })
+            .unwrap();
+        }
+        let tool = CatchMeUpTool::new(db);
+        let result = tool.execute(json!({"user": "alice", "chat_id": 9})).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("hello from bob"));
+        assert!(!result.content.contains("hello from alice"));
+
+        let again = tool.execute(json!({"user": "alice", "chat_id": 9})).await;
+        assert!(again.content.starts_with("No new messages"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,333 @@
+//! `allow_chat`, `block_chat` and `list_allowed_chats`: the DB allowlist in `chat_access`,
+//! layered over the static lists in the config (`allowed_groups`, `discord_allowed_channels`,
+//! `slack_allowed_channels`). Entries take effect on the next message, optionally expire, and
+//! are checked by [`crate::access::admit`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, ChatAccess, Database};
+
+const CHANNELS: &[&str] = &["telegram", "discord", "slack", "signal", "teams"];
+
+fn access_schema(action: &str) -> serde_json::Value {
+    schema_object(
+        json!({
+            "chat_id": {
+                "type": "integer",
+                "description": format!("Chat to {action}")
+            },
+            "channel": {
+                "type": "string",
+                "enum": CHANNELS,
+                "description": "Channel the chat belongs to (default: telegram)"
+            },
+            "hours": {
+                "type": "number",
+                "description": "Make the entry temporary: it lapses after this many hours (default: permanent)"
+            },
+            "note": {
+                "type": "string",
+                "description": "Why the entry was made"
+            },
+            "remove": {
+                "type": "boolean",
+                "description": "Delete the chat's entry instead, so only the config lists apply again"
+            }
+        }),
+        &["chat_id"],
+    )
+}
+
+/// Shared body of `allow_chat` (status "allowed") and `block_chat` (status "blocked").
+async fn set_access(db: &Arc<Database>, input: &serde_json::Value, status: &str) -> ToolResult {
+    if let Some(auth) = auth_context_from_input(input) {
+        if !auth.is_control_chat() {
+            return ToolResult::error(
+                "Permission denied: chat access can only be changed from a control chat".into(),
+            );
+        }
+    }
+    let Some(chat_id) = input.get("chat_id").and_then(|v| v.as_i64()) else {
+        return ToolResult::error("Missing 'chat_id' parameter".into());
+    };
+
+    if input
+        .get("remove")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        return match call_blocking(db.clone(), move |d| d.delete_chat_access(chat_id)).await {
+            Ok(true) => {
+                info!("Removed access entry for chat {chat_id}");
+                ToolResult::success(format!(
+                    "Removed the entry for chat {chat_id}; the config allowlist applies again."
+                ))
+            }
+            Ok(false) => ToolResult::error(format!("Chat {chat_id} has no access entry")),
+            Err(e) => ToolResult::error(format!("Failed to remove access entry: {e}")),
+        };
+    }
+
+    let channel = input
+        .get("channel")
+        .and_then(|v| v.as_str())
+        .unwrap_or("telegram")
+        .to_string();
+    if !CHANNELS.contains(&channel.as_str()) {
+        return ToolResult::error(format!(
+            "Unknown channel '{channel}' (one of: {})",
+            CHANNELS.join(", ")
+        ));
+    }
+    let expires_at = match input.get("hours").and_then(|v| v.as_f64()) {
+        Some(hours) if hours <= 0.0 || !hours.is_finite() => {
+            return ToolResult::error("'hours' must be a positive number".into());
+        }
+        Some(hours) => Some(
+            (chrono::Utc::now() + chrono::Duration::seconds((hours * 3600.0) as i64)).to_rfc3339(),
+        ),
+        None => None,
+    };
+    let note = input
+        .get("note")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string);
+
+    let (s, ch, exp) = (status.to_string(), channel.clone(), expires_at.clone());
+    if let Err(e) = call_blocking(db.clone(), move |d| {
+        d.set_chat_access(chat_id, &ch, &s, exp.as_deref(), note.as_deref())
+    })
+    .await
+    {
+        return ToolResult::error(format!("Failed to update chat access: {e}"));
+    }
+    info!("Chat {chat_id} ({channel}) {status} until {expires_at:?}");
+    let until = expires_at
+        .map(|e| format!(" until {e}"))
+        .unwrap_or_default();
+    ToolResult::success(format!("Chat {chat_id} ({channel}) is {status}{until}."))
+}
+
+pub struct AllowChatTool {
+    db: Arc<Database>,
+}
+
+impl AllowChatTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        AllowChatTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for AllowChatTool {
+    fn name(&self) -> &str {
+        "allow_chat"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "allow_chat".into(),
+            description: "Let the bot answer a chat that its channel's config allowlist leaves out (or lift a block), optionally for a limited number of hours. Takes effect on the next message, without a restart. Only available from control chats.".into(),
+            input_schema: access_schema("allow"),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        set_access(&self.db, &input, "allowed").await
+    }
+}
+
+pub struct BlockChatTool {
+    db: Arc<Database>,
+}
+
+impl BlockChatTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        BlockChatTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for BlockChatTool {
+    fn name(&self) -> &str {
+        "block_chat"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "block_chat".into(),
+            description: "Stop the bot from answering a chat, even one the config allowlist admits (private chats included), optionally for a limited number of hours. Only available from control chats.".into(),
+            input_schema: access_schema("block"),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        set_access(&self.db, &input, "blocked").await
+    }
+}
+
+fn format_entry(entry: &ChatAccess, now: &str) -> String {
+    let mut line = format!("- {} ({}) {}", entry.chat_id, entry.channel, entry.status);
+    match &entry.expires_at {
+        Some(e) if e.as_str() <= now => line.push_str(&format!(", expired {e}")),
+        Some(e) => line.push_str(&format!(" until {e}")),
+        None => {}
+    }
+    if let Some(note) = &entry.note {
+        line.push_str(&format!(": {note}"));
+    }
+    line
+}
+
+pub struct ListAllowedChatsTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl ListAllowedChatsTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        ListAllowedChatsTool {
+            config: config.clone(),
+            db,
+        }
+    }
+
+    fn static_lists(&self) -> Vec<String> {
+        fn list<T: ToString>(items: &[T]) -> String {
+            if items.is_empty() {
+                "all".into()
+            } else {
+                items
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        }
+        vec![
+            format!("- telegram groups: {}", list(&self.config.allowed_groups)),
+            format!(
+                "- discord channels: {}",
+                list(&self.config.discord_allowed_channels)
+            ),
+            format!(
+                "- slack channels: {}",
+                list(&self.config.slack_allowed_channels)
+            ),
+        ]
+    }
+}
+
+#[async_trait]
+impl Tool for ListAllowedChatsTool {
+    fn name(&self) -> &str {
+        "list_allowed_chats"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_allowed_chats".into(),
+            description: "Show which chats the bot answers: the config allowlists per channel and the allow/block entries made with allow_chat and block_chat (with expiry). Only available from control chats.".into(),
+            input_schema: schema_object(json!({}), &[]),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(auth) = auth_context_from_input(&input) {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: chat access can only be listed from a control chat".into(),
+                );
+            }
+        }
+        let entries = match call_blocking(self.db.clone(), |d| d.list_chat_access()).await {
+            Ok(e) => e,
+            Err(e) => return ToolResult::error(format!("Failed to list chat access: {e}")),
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut out = format!("Config allowlists:\n{}", self.static_lists().join("\n"));
+        if entries.is_empty() {
+            out.push_str("\n\nNo allow/block entries.");
+        } else {
+            let lines: Vec<String> = entries.iter().map(|e| format_entry(e, &now)).collect();
+            out.push_str(&format!(
+                "\n\n{} allow/block entr{}:\n{}",
+                entries.len(),
+                if entries.len() == 1 { "y" } else { "ies" },
+                lines.join("\n")
+            ));
+        }
+        ToolResult::success(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_allow_block_and_list() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_chat_access_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let control = json!({"caller_chat_id": 100, "control_chat_ids": [100]});
+
+        let out = AllowChatTool::new(db.clone())
+            .execute(json!({"chat_id": -5, "__microclaw_auth": {"caller_chat_id": -5, "control_chat_ids": [100]}}))
+            .await;
+        assert!(out.content.contains("control chat"));
+
+        let out = AllowChatTool::new(db.clone())
+            .execute(
+                json!({"chat_id": -5, "hours": 2, "note": "trial", "__microclaw_auth": control}),
+            )
+            .await;
+        assert!(out
+            .content
+            .starts_with("Chat -5 (telegram) is allowed until "));
+        assert_eq!(
+            db.get_chat_access_status(-5).unwrap().as_deref(),
+            Some("allowed")
+        );
+        let out = BlockChatTool::new(db.clone())
+            .execute(json!({"chat_id": 7, "channel": "slack", "__microclaw_auth": control}))
+            .await;
+        assert_eq!(out.content, "Chat 7 (slack) is blocked.");
+        let out = BlockChatTool::new(db.clone())
+            .execute(json!({"chat_id": 7, "hours": -1, "__microclaw_auth": control}))
+            .await;
+        assert!(out.is_error);
+
+        let config: Config = serde_yaml::from_str(
+            "telegram_bot_token: tok\nbot_username: bot\napi_key: key\nallowed_groups: [-1]\n",
+        )
+        .unwrap();
+        let out = ListAllowedChatsTool::new(&config, db.clone())
+            .execute(json!({"__microclaw_auth": control}))
+            .await;
+        assert!(out.content.starts_with(
+            "Config allowlists:\n- telegram groups: -1\n- discord channels: all\n- slack channels: all\n\n2 allow/block entries:\n"
+        ));
+        assert!(out.content.contains("- 7 (slack) blocked\n"));
+        assert!(out.content.ends_with(": trial"));
+
+        let out = AllowChatTool::new(db.clone())
+            .execute(json!({"chat_id": 7, "remove": true, "__microclaw_auth": control}))
+            .await;
+        assert!(out.content.starts_with("Removed the entry for chat 7"));
+        assert_eq!(db.get_chat_access_status(7).unwrap(), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
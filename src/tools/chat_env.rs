@@ -0,0 +1,248 @@
+//! `set_chat_env`: per-chat environment variables (project tokens, PATH additions) that are
+//! injected into the subprocesses tools start for that chat (bash, cursor-agent), instead of
+//! living in the bot's own process environment.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database};
+
+const MAX_KEY_LEN: usize = 128;
+const MAX_VALUE_LEN: usize = 8192;
+
+/// Variables the bot relies on that a chat may not replace.
+const RESERVED_KEYS: &[&str] = &["HOME", "USER", "SHELL", "PWD"];
+
+fn validate_key(key: &str) -> Result<(), String> {
+    let mut chars = key.chars();
+    let valid = key.len() <= MAX_KEY_LEN
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid variable name '{key}' (letters, digits and '_', not starting with a digit)"
+        ));
+    }
+    if RESERVED_KEYS.contains(&key) {
+        return Err(format!("'{key}' cannot be overridden per chat"));
+    }
+    Ok(())
+}
+
+/// Show only the first characters of a value; these are often secrets.
+fn mask(value: &str) -> String {
+    let count = value.chars().count();
+    if count <= 4 {
+        "****".into()
+    } else {
+        format!("{}****", value.chars().take(2).collect::<String>())
+    }
+}
+
+/// Environment for subprocesses started on behalf of the calling chat. A `PATH` entry is
+/// prepended to the inherited `PATH` rather than replacing it.
+pub async fn env_for_input(db: &Arc<Database>, input: &serde_json::Value) -> Vec<(String, String)> {
+    let Some(auth) = auth_context_from_input(input) else {
+        return Vec::new();
+    };
+    let chat_id = auth.caller_chat_id;
+    let vars = call_blocking(db.clone(), move |d| d.get_chat_env(chat_id))
+        .await
+        .unwrap_or_default();
+    vars.into_iter()
+        .map(|(key, value)| {
+            if key == "PATH" {
+                let inherited = std::env::var("PATH").unwrap_or_default();
+                let sep = if cfg!(target_os = "windows") {
+                    ';'
+                } else {
+                    ':'
+                };
+                (key, format!("{value}{sep}{inherited}"))
+            } else {
+                (key, value)
+            }
+        })
+        .collect()
+}
+
+pub struct SetChatEnvTool {
+    db: Arc<Database>,
+}
+
+impl SetChatEnvTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        SetChatEnvTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for SetChatEnvTool {
+    fn name(&self) -> &str {
+        "set_chat_env"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "set_chat_env".into(),
+            description: "Set, remove or list environment variables for one chat. They are passed to bash and cursor-agent runs for that chat only (PATH is prepended to the inherited PATH). Values are masked when listed. Only available from control chats.".into(),
+            input_schema: schema_object(
+                json!({
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to configure (default: the current chat)"
+                    },
+                    "key": {
+                        "type": "string",
+                        "description": "Variable name. Omit to list the chat's variables"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "Value to set. Omit (with key) to remove the variable"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let auth = auth_context_from_input(&input);
+        if let Some(auth) = &auth {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: chat environment variables can only be changed from a control chat"
+                        .into(),
+                );
+            }
+        }
+        let Some(chat_id) = input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth.as_ref().map(|a| a.caller_chat_id))
+        else {
+            return ToolResult::error("Missing 'chat_id' parameter".into());
+        };
+
+        let Some(key) = input
+            .get("key")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_string)
+        else {
+            let vars = match call_blocking(self.db.clone(), move |d| d.get_chat_env(chat_id)).await
+            {
+                Ok(v) => v,
+                Err(e) => return ToolResult::error(format!("Failed to load variables: {e}")),
+            };
+            if vars.is_empty() {
+                return ToolResult::success(format!(
+                    "No environment variables set for chat {chat_id}."
+                ));
+            }
+            let lines: Vec<String> = vars
+                .iter()
+                .map(|(k, v)| format!("{k}={}", mask(v)))
+                .collect();
+            return ToolResult::success(format!(
+                "Environment for chat {chat_id}:\n{}",
+                lines.join("\n")
+            ));
+        };
+        if let Err(e) = validate_key(&key) {
+            return ToolResult::error(e);
+        }
+
+        match input.get("value").and_then(|v| v.as_str()) {
+            Some(value) => {
+                if value.len() > MAX_VALUE_LEN {
+                    return ToolResult::error(format!("Value exceeds {MAX_VALUE_LEN} bytes"));
+                }
+                let (k, v) = (key.clone(), value.to_string());
+                match call_blocking(self.db.clone(), move |d| d.set_chat_env(chat_id, &k, &v)).await
+                {
+                    Ok(()) => ToolResult::success(format!("Set {key} for chat {chat_id}.")),
+                    Err(e) => ToolResult::error(format!("Failed to set {key}: {e}")),
+                }
+            }
+            None => {
+                let k = key.clone();
+                match call_blocking(self.db.clone(), move |d| d.delete_chat_env(chat_id, &k)).await
+                {
+                    Ok(true) => ToolResult::success(format!("Removed {key} for chat {chat_id}.")),
+                    Ok(false) => ToolResult::error(format!("{key} is not set for chat {chat_id}")),
+                    Err(e) => ToolResult::error(format!("Failed to remove {key}: {e}")),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_list_remove_and_inject() {
+        let dir = std::env::temp_dir().join(format!("microclaw_chat_env_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let tool = SetChatEnvTool::new(db.clone());
+        let control = json!({"caller_chat_id": 100, "control_chat_ids": [100]});
+
+        let out = tool
+            .execute(json!({"key": "TOKEN", "value": "x", "__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [100]}}))
+            .await;
+        assert!(out.content.contains("control chat"));
+        let out = tool
+            .execute(
+                json!({"chat_id": 5, "key": "1BAD", "value": "x", "__microclaw_auth": control}),
+            )
+            .await;
+        assert!(out.content.starts_with("Invalid variable name"));
+        let out = tool
+            .execute(
+                json!({"chat_id": 5, "key": "HOME", "value": "/", "__microclaw_auth": control}),
+            )
+            .await;
+        assert!(out.is_error);
+
+        for (key, value) in [("GH_TOKEN", "ghp_secret"), ("PATH", "/opt/proj/bin")] {
+            let out = tool
+                .execute(
+                    json!({"chat_id": 5, "key": key, "value": value, "__microclaw_auth": control}),
+                )
+                .await;
+            assert_eq!(out.content, format!("Set {key} for chat 5."));
+        }
+        let out = tool
+            .execute(json!({"chat_id": 5, "__microclaw_auth": control}))
+            .await;
+        assert_eq!(
+            out.content,
+            "Environment for chat 5:\nGH_TOKEN=gh****\nPATH=/o****"
+        );
+
+        let env = env_for_input(&db, &json!({"__microclaw_auth": {"caller_chat_id": 5}})).await;
+        assert_eq!(env[0], ("GH_TOKEN".into(), "ghp_secret".into()));
+        assert!(env[1].1.starts_with("/opt/proj/bin"));
+        assert!(
+            env_for_input(&db, &json!({"__microclaw_auth": {"caller_chat_id": 6}}))
+                .await
+                .is_empty()
+        );
+
+        let out = tool
+            .execute(json!({"chat_id": 5, "key": "GH_TOKEN", "__microclaw_auth": control}))
+            .await;
+        assert_eq!(out.content, "Removed GH_TOKEN for chat 5.");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,9 +1,13 @@
 use async_trait::async_trait;
+use serde::Serialize;
 use serde_json::json;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
 use tracing::info;
 
+use teloxide::Bot;
+
 use crate::claude::ToolDefinition;
 use crate::config::Config;
 use crate::db::Database;
@@ -18,6 +22,7 @@ const OUTPUT_PREVIEW_LEN: usize = 500;
 pub struct CursorAgentTool {
     config: Config,
     db: Arc<Database>,
+    bot: Option<Bot>,
 }
 
 impl CursorAgentTool {
@@ -25,8 +30,16 @@ impl CursorAgentTool {
         Self {
             config: config.clone(),
             db,
+            bot: None,
         }
     }
+
+    /// Post a review with Approve / Revert buttons after runs that change a git project
+    /// (when `cursor_agent_review` is on).
+    pub fn with_review(mut self, bot: Bot) -> Self {
+        self.bot = Some(bot);
+        self
+    }
 }
 
 #[async_trait]
@@ -38,7 +51,7 @@ impl Tool for CursorAgentTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "cursor_agent".into(),
-            description: "Run the Cursor CLI agent (cursor-agent) with a prompt. Use for research, code generation, or analysis that benefits from Cursor's native agent. Optional: timeout_secs, model override. Working directory is the shared tool workspace.".into(),
+            description: "Run the Cursor CLI agent (cursor-agent) with a prompt. Use for research, code generation, or analysis that benefits from Cursor's native agent. Optional: timeout_secs, model override. Working directory is the shared tool workspace, or a registered project (project) and/or a subdirectory (subdir) so separate codebases keep separate context. With detach (control chats only), the run keeps going in the background and is managed with detached_runs.".into(),
             input_schema: schema_object(
                 json!({
                     "prompt": {
@@ -52,10 +65,23 @@ impl Tool for CursorAgentTool {
                     "model": {
                         "type": "string",
                         "description": "Override model for this run (e.g. gpt-5). Omit to use config default or Cursor auto"
+                    },
+                    "project": {
+                        "type": "string",
+                        "description": "Run in this registered project's directory (see list_projects)"
+                    },
+                    "subdir": {
+                        "type": "string",
+                        "description": "Relative subdirectory (of the project, or of the shared workspace) to run in"
+                    },
+                    "detach": {
+                        "type": "boolean",
+                        "description": "Start the run detached and return its name immediately (control chats only)"
                     }
                 }),
                 &["prompt"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -75,10 +101,36 @@ impl Tool for CursorAgentTool {
         let auth = auth_context_from_input(&input);
         let started_at = chrono::Utc::now().to_rfc3339();
         let workdir_str_storage;
-        let working_dir = super::resolve_tool_working_dir(PathBuf::from(self.config.working_dir()).as_path());
-        if let Err(e) = tokio::fs::create_dir_all(&working_dir).await {
+        let project = input
+            .get("project")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.trim().is_empty());
+        let base_dir = match project {
+            Some(name) => match super::projects::resolve_project(&self.db, name).await {
+                Ok(dir) => dir,
+                Err(e) => return ToolResult::error(e),
+            },
+            None => {
+                let shared = super::resolve_tool_working_dir(
+                    PathBuf::from(self.config.working_dir()).as_path(),
+                );
+                if let Err(e) = tokio::fs::create_dir_all(&shared).await {
+                    return ToolResult::error(format!(
+                        "Failed to create working directory {}: {e}",
+                        shared.display()
+                    ));
+                }
+                shared
+            }
+        };
+        let subdir = input.get("subdir").and_then(|v| v.as_str()).unwrap_or("");
+        let working_dir = match scoped_working_dir(&base_dir, subdir) {
+            Ok(dir) => dir,
+            Err(e) => return ToolResult::error(e),
+        };
+        if !working_dir.is_dir() {
             return ToolResult::error(format!(
-                "Failed to create working directory {}: {e}",
+                "Working directory {} does not exist",
                 working_dir.display()
             ));
         }
@@ -101,21 +153,51 @@ impl Tool for CursorAgentTool {
             return ToolResult::error("cursor_agent_cli_path is not configured".into());
         }
 
+        if input.get("detach").and_then(|v| v.as_bool()).unwrap_or(false) {
+            if let Some(ref a) = auth {
+                if !a.is_control_chat() {
+                    return ToolResult::error(
+                        "Permission denied: detached runs can only be started from a control chat"
+                            .into(),
+                    );
+                }
+            }
+            let name = format!("cursor-{}", chrono::Utc::now().timestamp_millis());
+            let args = cursor_agent_args(prompt, model, "text");
+            let env = super::chat_env::env_for_input(&self.db, &input).await;
+            return match crate::detached::start(
+                &crate::detached::state_dir(&self.config),
+                &name,
+                &working_dir,
+                cli_path,
+                &args,
+                &env,
+            )
+            .await
+            {
+                Ok((name, backend)) => ToolResult::success(format!(
+                    "Started detached cursor-agent run {name} ({} backend). Use detached_runs to follow its output or stop it.",
+                    backend.as_str()
+                )),
+                Err(e) => ToolResult::error(format!("Failed to start detached cursor-agent: {e}"))
+                    .with_error_type("spawn_error"),
+            };
+        }
+
         info!("Running cursor-agent (timeout {}s)", timeout_secs);
 
-        let mut cmd = tokio::process::Command::new(cli_path);
-        cmd.arg("-p").arg(prompt);
-        if !model.is_empty() {
-            cmd.arg("--model").arg(model);
-        }
-        cmd.arg("--output-format").arg("text");
-        cmd.current_dir(&working_dir);
+        // Reviews use Telegram inline buttons, so only Telegram chats get them.
+        let review_base = match (&self.bot, &auth) {
+            (Some(_), Some(a)) if self.config.cursor_agent_review && a.caller_channel == "telegram" => {
+                crate::code_review::snapshot(&working_dir).await
+            }
+            _ => None,
+        };
 
-        let result = tokio::time::timeout(
-            std::time::Duration::from_secs(timeout_secs),
-            cmd.output(),
-        )
-        .await;
+        let mut cmd = cursor_agent_command(cli_path, prompt, model, &working_dir);
+        cmd.envs(super::chat_env::env_for_input(&self.db, &input).await);
+
+        let result = run_cursor_agent(cmd, timeout_secs).await;
 
         let finished_at = chrono::Utc::now().to_rfc3339();
         let prompt_preview: String = if prompt.len() <= PROMPT_PREVIEW_LEN {
@@ -124,41 +206,33 @@ impl Tool for CursorAgentTool {
             format!("{}...", &prompt[..prompt.floor_char_boundary(PROMPT_PREVIEW_LEN)])
         };
 
-        let (success, exit_code, result_content) = match &result {
-            Ok(Ok(output)) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                let code = output.status.code().unwrap_or(-1);
-                (code == 0, code, {
-                    let mut result_text = String::new();
-                    if !stdout.is_empty() {
-                        result_text.push_str(&stdout);
-                    }
-                    if !stderr.is_empty() {
-                        if !result_text.is_empty() {
-                            result_text.push('\n');
-                        }
-                        result_text.push_str("STDERR:\n");
-                        result_text.push_str(&stderr);
-                    }
-                    if result_text.is_empty() {
-                        result_text = format!("Command completed with exit code {code}");
-                    }
-                    if result_text.len() > MAX_OUTPUT_LEN {
-                        result_text.truncate(MAX_OUTPUT_LEN);
-                        result_text.push_str("\n... (output truncated)");
-                    }
-                    result_text
-                })
-            }
-            Ok(Err(_)) => (false, 1, "Failed to execute cursor-agent".to_string()),
-            Err(_) => (
+        let (success, exit_code, result_content, steps_json) = match &result {
+            Ok(run) if run.timed_out => (
                 false,
                 -1,
                 format!("Timed out after {} seconds", timeout_secs),
+                Some(steps_log(&run.steps)),
             ),
+            Ok(run) => (
+                run.success(),
+                run.exit_code.unwrap_or(-1),
+                run.output.clone(),
+                Some(steps_log(&run.steps)),
+            ),
+            Err(_) => (false, 1, "Failed to execute cursor-agent".to_string(), None),
         };
 
+        if let (true, Some(base), Some(bot), Some(a)) = (success, review_base, &self.bot, &auth) {
+            tokio::spawn(crate::code_review::review_run(
+                self.config.clone(),
+                bot.clone(),
+                self.db.clone(),
+                a.caller_chat_id,
+                base,
+                prompt_preview.clone(),
+            ));
+        }
+
         if let Some(ref a) = auth {
             let output_preview = if result_content.len() <= OUTPUT_PREVIEW_LEN {
                 result_content.clone()
@@ -183,15 +257,20 @@ impl Tool for CursorAgentTool {
                     Some(exit_code),
                     Some(&output_preview),
                     None::<&str>,
+                    steps_json.as_deref(),
                 )
             })
             .await;
         }
 
         match result {
-            Ok(Ok(output)) => {
-                let exit_code = output.status.code().unwrap_or(-1);
-                if exit_code == 0 {
+            Ok(run) if run.timed_out => ToolResult::error(format!(
+                "cursor-agent timed out after {} seconds",
+                timeout_secs
+            ))
+            .with_error_type("timeout"),
+            Ok(run) => {
+                if run.success() {
                     ToolResult::success(result_content).with_status_code(exit_code)
                 } else {
                     ToolResult::error(format!("Exit code {exit_code}\n{result_content}"))
@@ -199,17 +278,327 @@ impl Tool for CursorAgentTool {
                         .with_error_type("process_exit")
                 }
             }
-            Ok(Err(e)) => ToolResult::error(format!("Failed to execute cursor-agent: {e}"))
+            Err(e) => ToolResult::error(format!("Failed to execute cursor-agent: {e}"))
                 .with_error_type("spawn_error"),
-            Err(_) => ToolResult::error(format!(
-                "cursor-agent timed out after {} seconds",
-                timeout_secs
-            ))
-            .with_error_type("timeout"),
         }
     }
 }
 
+/// `base` joined with the relative `subdir`, refusing absolute paths and `..` so a run cannot
+/// leave the project or workspace it was scoped to.
+pub(crate) fn scoped_working_dir(base: &Path, subdir: &str) -> Result<PathBuf, String> {
+    let subdir = subdir.trim();
+    if subdir.is_empty() {
+        return Ok(base.to_path_buf());
+    }
+    let rel = Path::new(subdir);
+    if !rel
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!(
+            "subdir '{subdir}' must be a relative path without '..'"
+        ));
+    }
+    Ok(base.join(rel))
+}
+
+/// cursor-agent arguments for print mode. `output_format` is `text` for runs whose log is read
+/// by people (detached runs) and `stream-json` for runs parsed here.
+pub(crate) fn cursor_agent_args(prompt: &str, model: &str, output_format: &str) -> Vec<String> {
+    let mut args = vec!["-p".to_string(), prompt.to_string()];
+    if !model.is_empty() {
+        args.push("--model".into());
+        args.push(model.to_string());
+    }
+    args.push("--output-format".into());
+    args.push(output_format.into());
+    args
+}
+
+/// cursor-agent invocation in print mode with stream-json output (see [`run_cursor_agent`]).
+pub(crate) fn cursor_agent_command(
+    cli_path: &str,
+    prompt: &str,
+    model: &str,
+    working_dir: &Path,
+) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new(cli_path);
+    cmd.args(cursor_agent_args(prompt, model, "stream-json"));
+    cmd.current_dir(working_dir);
+    cmd
+}
+
+// --- stream-json parsing ---
+
+const MAX_LOGGED_STEPS: usize = 200;
+const STEP_TEXT_LOG_LEN: usize = 300;
+
+/// One event of a cursor-agent `stream-json` run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum CursorStep {
+    Init {
+        model: Option<String>,
+        session_id: Option<String>,
+    },
+    Text {
+        text: String,
+    },
+    ToolStarted {
+        tool: String,
+        detail: Option<String>,
+    },
+    ToolCompleted {
+        tool: String,
+        ok: bool,
+    },
+    Result {
+        text: String,
+        is_error: bool,
+        duration_ms: Option<u64>,
+    },
+}
+
+impl CursorStep {
+    /// Short line for chat status updates, for the steps worth showing.
+    pub(crate) fn progress_line(&self) -> Option<String> {
+        match self {
+            CursorStep::ToolStarted { tool, detail } => Some(match detail {
+                Some(d) => format!("{tool}: {d}"),
+                None => tool.clone(),
+            }),
+            CursorStep::Text { text } => text
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty())
+                .map(|l| l.chars().take(120).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// `readToolCall` -> `read`, `shellToolCall` -> `shell`.
+fn tool_kind(key: &str) -> String {
+    key.strip_suffix("ToolCall").unwrap_or(key).to_string()
+}
+
+/// Parse one stdout line of `--output-format stream-json`. Unknown events and non-JSON lines
+/// give `None`.
+pub(crate) fn parse_stream_line(line: &str) -> Option<CursorStep> {
+    let event: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    let str_at =
+        |v: &serde_json::Value, key: &str| v.get(key).and_then(|x| x.as_str()).map(str::to_string);
+    match event.get("type")?.as_str()? {
+        "system" if event.get("subtype").and_then(|v| v.as_str()) == Some("init") => {
+            Some(CursorStep::Init {
+                model: str_at(&event, "model"),
+                session_id: str_at(&event, "session_id"),
+            })
+        }
+        "assistant" => {
+            let text: String = event
+                .pointer("/message/content")?
+                .as_array()?
+                .iter()
+                .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect();
+            (!text.trim().is_empty()).then_some(CursorStep::Text { text })
+        }
+        "tool_call" => {
+            let (key, call) = event.get("tool_call")?.as_object()?.iter().next()?;
+            let tool = tool_kind(key);
+            match event.get("subtype").and_then(|v| v.as_str())? {
+                "started" => {
+                    let args = call.get("args");
+                    let detail = ["path", "command", "pattern", "query", "url"]
+                        .iter()
+                        .find_map(|k| args.and_then(|a| str_at(a, k)))
+                        .map(|d| d.chars().take(120).collect());
+                    Some(CursorStep::ToolStarted { tool, detail })
+                }
+                "completed" => {
+                    let ok = call
+                        .get("result")
+                        .map(|r| r.get("error").is_none() && r.get("rejected").is_none())
+                        .unwrap_or(true);
+                    Some(CursorStep::ToolCompleted { tool, ok })
+                }
+                _ => None,
+            }
+        }
+        "result" => Some(CursorStep::Result {
+            text: str_at(&event, "result").unwrap_or_default(),
+            is_error: event
+                .get("is_error")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            duration_ms: event.get("duration_ms").and_then(|v| v.as_u64()),
+        }),
+        _ => None,
+    }
+}
+
+/// Steps as stored in `cursor_agent_runs.steps_json`: message text clipped, count capped.
+pub(crate) fn steps_log(steps: &[CursorStep]) -> String {
+    let clip = |text: &str| {
+        if text.chars().count() > STEP_TEXT_LOG_LEN {
+            format!(
+                "{}...",
+                text.chars().take(STEP_TEXT_LOG_LEN).collect::<String>()
+            )
+        } else {
+            text.to_string()
+        }
+    };
+    let logged: Vec<CursorStep> = steps
+        .iter()
+        .take(MAX_LOGGED_STEPS)
+        .map(|step| match step {
+            CursorStep::Text { text } => CursorStep::Text { text: clip(text) },
+            CursorStep::Result {
+                text,
+                is_error,
+                duration_ms,
+            } => CursorStep::Result {
+                text: clip(text),
+                is_error: *is_error,
+                duration_ms: *duration_ms,
+            },
+            other => other.clone(),
+        })
+        .collect();
+    serde_json::to_string(&logged).unwrap_or_else(|_| "[]".into())
+}
+
+/// Outcome of a stream-json cursor-agent run.
+pub(crate) struct CursorRun {
+    /// `None` when the run timed out (or was killed by a signal).
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Final answer (the `result` event, else the assistant messages, else raw stdout) plus
+    /// stderr, truncated to `MAX_OUTPUT_LEN`.
+    pub output: String,
+    pub steps: Vec<CursorStep>,
+}
+
+impl CursorRun {
+    pub(crate) fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Run a command from [`cursor_agent_command`], parsing its events as they arrive and reporting
+/// each notable step through [`super::report_progress`].
+pub(crate) async fn run_cursor_agent(
+    mut cmd: tokio::process::Command,
+    timeout_secs: u64,
+) -> std::io::Result<CursorRun> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    cmd.stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = cmd.spawn()?;
+    let stdout = child.stdout.take();
+    let stderr_task = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut buf = String::new();
+            let _ = stderr.read_to_string(&mut buf).await;
+            buf
+        })
+    });
+
+    let mut steps = Vec::new();
+    let mut raw = String::new();
+    let read = async {
+        if let Some(stdout) = stdout {
+            let mut lines = tokio::io::BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match parse_stream_line(&line) {
+                    Some(step) => {
+                        if let Some(progress) = step.progress_line() {
+                            super::report_progress(progress);
+                        }
+                        steps.push(step);
+                    }
+                    None if !line.trim_start().starts_with('{') => {
+                        raw.push_str(&line);
+                        raw.push('\n');
+                    }
+                    None => {}
+                }
+            }
+        }
+        child.wait().await
+    };
+    let waited = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), read).await;
+    let (exit_code, timed_out) = match waited {
+        Ok(status) => (status?.code(), false),
+        Err(_) => {
+            let _ = child.kill().await;
+            (None, true)
+        }
+    };
+    let stderr = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => String::new(),
+    };
+
+    let answer = steps
+        .iter()
+        .rev()
+        .find_map(|s| match s {
+            CursorStep::Result { text, .. } if !text.is_empty() => Some(text.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| {
+            let texts: Vec<&str> = steps
+                .iter()
+                .filter_map(|s| match s {
+                    CursorStep::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            if texts.is_empty() {
+                raw.trim_end().to_string()
+            } else {
+                texts.join("\n")
+            }
+        });
+    let output = format_cursor_output(&answer, &stderr, exit_code.unwrap_or(-1));
+    Ok(CursorRun {
+        exit_code,
+        timed_out,
+        output,
+        steps,
+    })
+}
+
+/// Combined answer/stderr of a cursor-agent run, truncated to `MAX_OUTPUT_LEN`.
+fn format_cursor_output(stdout: &str, stderr: &str, code: i32) -> String {
+    let mut result_text = String::new();
+    if !stdout.is_empty() {
+        result_text.push_str(stdout);
+    }
+    if !stderr.is_empty() {
+        if !result_text.is_empty() {
+            result_text.push('\n');
+        }
+        result_text.push_str("STDERR:\n");
+        result_text.push_str(stderr);
+    }
+    if result_text.is_empty() {
+        result_text = format!("Command completed with exit code {code}");
+    }
+    if result_text.len() > MAX_OUTPUT_LEN {
+        result_text.truncate(result_text.floor_char_boundary(MAX_OUTPUT_LEN));
+        result_text.push_str("\n... (output truncated)");
+    }
+    result_text
+}
+
 // --- list_cursor_agent_runs ---
 
 pub struct ListCursorAgentRunsTool {
@@ -231,7 +620,7 @@ impl Tool for ListCursorAgentRunsTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "list_cursor_agent_runs".into(),
-            description: "List recent cursor-agent runs to monitor project status. By default returns runs for the current chat; use this to see last run outcome, success/failure, and output preview.".into(),
+            description: "List recent cursor-agent runs to monitor project status. By default returns runs for the current chat; use this to see last run outcome, success/failure, and output preview. Pass run_id to see one run's steps (tool calls and messages).".into(),
             input_schema: schema_object(
                 json!({
                     "limit": {
@@ -241,14 +630,33 @@ impl Tool for ListCursorAgentRunsTool {
                     "chat_id": {
                         "type": "integer",
                         "description": "Optional: list runs for this chat ID (control chats only). Omit to list runs for the current chat."
+                    },
+                    "run_id": {
+                        "type": "integer",
+                        "description": "Optional: show the step log of this run instead of the list"
                     }
                 }),
                 &[],
             ),
+            examples: Vec::new(),
         }
     }
 
     async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(run_id) = input.get("run_id").and_then(|v| v.as_i64()) {
+            return match crate::db::call_blocking(self.db.clone(), move |db| {
+                db.get_cursor_agent_run(run_id)
+            })
+            .await
+            {
+                Ok(Some(run)) => match super::authorize_chat_access(&input, run.chat_id) {
+                    Ok(()) => ToolResult::success(format_run_steps(&run)),
+                    Err(e) => ToolResult::error(e),
+                },
+                Ok(None) => ToolResult::error(format!("Cursor-agent run #{run_id} not found")),
+                Err(e) => ToolResult::error(format!("Failed to load cursor-agent run: {e}")),
+            };
+        }
         let auth = auth_context_from_input(&input);
         let chat_id = input.get("chat_id").and_then(|v| v.as_i64()).or_else(|| {
             auth.as_ref().map(|a| a.caller_chat_id)
@@ -292,3 +700,134 @@ impl Tool for ListCursorAgentRunsTool {
         }
     }
 }
+
+/// One run's stored steps, one line each.
+fn format_run_steps(run: &crate::db::CursorAgentRun) -> String {
+    let status = if run.success { "ok" } else { "failed" };
+    let mut out = format!(
+        "#{} {} {} | prompt: {}\n",
+        run.id, run.finished_at, status, run.prompt_preview
+    );
+    let steps: Vec<serde_json::Value> = run
+        .steps_json
+        .as_deref()
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default();
+    if steps.is_empty() {
+        out.push_str("No steps recorded for this run.");
+        return out;
+    }
+    for step in &steps {
+        let field = |k: &str| step.get(k).and_then(|v| v.as_str()).unwrap_or("");
+        let line = match field("kind") {
+            "init" => format!(
+                "start (model {})",
+                step.get("model")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("default")
+            ),
+            "text" => format!("message: {}", field("text").lines().next().unwrap_or("")),
+            "tool_started" => match step.get("detail").and_then(|v| v.as_str()) {
+                Some(d) => format!("{} {}", field("tool"), d),
+                None => field("tool").to_string(),
+            },
+            "tool_completed" => {
+                let ok = step.get("ok").and_then(|v| v.as_bool()).unwrap_or(true);
+                format!("{} {}", field("tool"), if ok { "done" } else { "failed" })
+            }
+            "result" => format!("result: {}", field("text").lines().next().unwrap_or("")),
+            other => other.to_string(),
+        };
+        out.push_str(&format!("- {line}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoped_working_dir() {
+        let base = Path::new("/work/shared/demo");
+        assert_eq!(scoped_working_dir(base, "").unwrap(), base);
+        assert_eq!(
+            scoped_working_dir(base, "crates/core").unwrap(),
+            base.join("crates/core")
+        );
+        assert!(scoped_working_dir(base, "../other").is_err());
+        assert!(scoped_working_dir(base, "/etc").is_err());
+    }
+
+    #[test]
+    fn test_cursor_agent_args() {
+        assert_eq!(
+            cursor_agent_args("hi", "gpt-5", "text"),
+            ["-p", "hi", "--model", "gpt-5", "--output-format", "text"]
+        );
+    }
+
+    #[test]
+    fn test_parse_stream_line() {
+        assert_eq!(
+            parse_stream_line(
+                r#"{"type":"system","subtype":"init","model":"gpt-5","session_id":"s1"}"#
+            ),
+            Some(CursorStep::Init {
+                model: Some("gpt-5".into()),
+                session_id: Some("s1".into())
+            })
+        );
+        let started = parse_stream_line(
+            r#"{"type":"tool_call","subtype":"started","call_id":"c1","tool_call":{"readToolCall":{"args":{"path":"src/main.rs"}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            started.progress_line().as_deref(),
+            Some("read: src/main.rs")
+        );
+        assert_eq!(
+            parse_stream_line(
+                r#"{"type":"tool_call","subtype":"completed","tool_call":{"shellToolCall":{"result":{"error":{"message":"x"}}}}}"#
+            ),
+            Some(CursorStep::ToolCompleted {
+                tool: "shell".into(),
+                ok: false
+            })
+        );
+        let text = parse_stream_line(
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Done."}]}}"#,
+        );
+        assert_eq!(
+            text,
+            Some(CursorStep::Text {
+                text: "Done.".into()
+            })
+        );
+        assert!(matches!(
+            parse_stream_line(r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":1200,"result":"All good"}"#),
+            Some(CursorStep::Result { ref text, is_error: false, duration_ms: Some(1200) }) if text == "All good"
+        ));
+        assert_eq!(parse_stream_line("plain text output"), None);
+        assert_eq!(parse_stream_line(r#"{"type":"user"}"#), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_cursor_agent_parses_stream() {
+        let script = r#"echo '{"type":"tool_call","subtype":"started","tool_call":{"editToolCall":{"args":{"path":"a.txt"}}}}'; echo '{"type":"result","is_error":false,"result":"Edited a.txt"}'"#;
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", script]);
+        let run = run_cursor_agent(cmd, 10).await.unwrap();
+        assert!(run.success());
+        assert_eq!(run.output, "Edited a.txt");
+        assert_eq!(run.steps.len(), 2);
+        let log: serde_json::Value = serde_json::from_str(&steps_log(&run.steps)).unwrap();
+        assert_eq!(log[0]["kind"], "tool_started");
+
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", "echo plain; sleep 5"]);
+        let run = run_cursor_agent(cmd, 1).await.unwrap();
+        assert!(run.timed_out && !run.success());
+    }
+}
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use teloxide::prelude::*;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::custom_commands::{
+    normalize_command_name, sync_telegram_chat_commands, MAX_CUSTOM_COMMANDS_PER_CHAT,
+};
+use crate::db::{call_blocking, Database};
+
+const MAX_TEMPLATE_CHARS: usize = 4000;
+const MAX_DESCRIPTION_CHARS: usize = 100;
+
+pub struct DefineCommandTool {
+    bot: Bot,
+    db: Arc<Database>,
+}
+
+impl DefineCommandTool {
+    pub fn new(bot: Bot, db: Arc<Database>) -> Self {
+        DefineCommandTool { bot, db }
+    }
+
+    /// Refresh the Telegram command menu for the chat; other channels pick up changes on use.
+    async fn sync_menu(&self, chat_id: i64) {
+        let chat_type = call_blocking(self.db.clone(), move |d| d.get_chat_type(chat_id))
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if !chat_type.starts_with("telegram") {
+            return;
+        }
+        if let Err(e) = sync_telegram_chat_commands(&self.bot, self.db.clone(), chat_id).await {
+            tracing::warn!("define_command: failed to update Telegram commands for {chat_id}: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DefineCommandTool {
+    fn name(&self) -> &str {
+        "define_command"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "define_command".into(),
+            description: "Manage custom slash commands for this chat. A command (e.g. /standup) maps to a prompt template; when someone sends the command, the template is filled in and handled like a normal message. Placeholders: {{args}} = everything after the command, {{1}}, {{2}}, ... = individual arguments (\"quoted words\" count as one). Without placeholders, arguments are appended. Built-in commands cannot be overridden. Actions: define (create or replace), remove, list.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["define", "remove", "list"],
+                        "description": "What to do"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Command name without the slash, e.g. \"standup\" (letters, digits, underscore)"
+                    },
+                    "template": {
+                        "type": "string",
+                        "description": "Prompt template (required for define), e.g. \"Run our standup for {{args}}: ask each person for yesterday, today, blockers.\""
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "Short description shown in the command menu"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat the command belongs to (defaults to the current chat)"
+                    }
+                }),
+                &["action"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let action = input
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let Some(chat_id) = input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth_context_from_input(&input).map(|a| a.caller_chat_id))
+        else {
+            return ToolResult::error("Missing required parameter: chat_id".into());
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+
+        if action == "list" {
+            return match call_blocking(self.db.clone(), move |d| d.list_custom_commands(&[chat_id]))
+                .await
+            {
+                Ok(commands) if commands.is_empty() => {
+                    ToolResult::success("No custom commands defined for this chat.".into())
+                }
+                Ok(commands) => {
+                    let lines: Vec<String> = commands
+                        .iter()
+                        .map(|c| {
+                            format!(
+                                "/{} — {}\n  template: {}",
+                                c.name, c.description, c.template
+                            )
+                        })
+                        .collect();
+                    ToolResult::success(lines.join("\n"))
+                }
+                Err(e) => ToolResult::error(format!("Failed to list commands: {e}")),
+            };
+        }
+
+        let name = match input.get("name").and_then(|v| v.as_str()) {
+            Some(raw) => match normalize_command_name(raw) {
+                Ok(n) => n,
+                Err(e) => return ToolResult::error(e),
+            },
+            None => return ToolResult::error("Missing required parameter: name".into()),
+        };
+
+        match action.as_str() {
+            "define" => {
+                let template = match input.get("template").and_then(|v| v.as_str()) {
+                    Some(t) if !t.trim().is_empty() => t.trim().to_string(),
+                    _ => return ToolResult::error("Missing required parameter: template".into()),
+                };
+                if template.chars().count() > MAX_TEMPLATE_CHARS {
+                    return ToolResult::error(format!(
+                        "Template too long (max {MAX_TEMPLATE_CHARS} characters)"
+                    ));
+                }
+                let description = input
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty())
+                    .map(|d| d.chars().take(MAX_DESCRIPTION_CHARS).collect::<String>())
+                    .unwrap_or_else(|| format!("Custom command /{name}"));
+                let name_for_db = name.clone();
+                let result = call_blocking(self.db.clone(), move |d| {
+                    let existing = d.list_custom_commands(&[chat_id])?;
+                    if existing.len() >= MAX_CUSTOM_COMMANDS_PER_CHAT
+                        && !existing.iter().any(|c| c.name == name_for_db)
+                    {
+                        return Ok(false);
+                    }
+                    d.upsert_custom_command(chat_id, &name_for_db, &description, &template)?;
+                    Ok(true)
+                })
+                .await;
+                match result {
+                    Ok(true) => {
+                        self.sync_menu(chat_id).await;
+                        ToolResult::success(format!("Defined /{name}."))
+                    }
+                    Ok(false) => ToolResult::error(format!(
+                        "This chat already has {MAX_CUSTOM_COMMANDS_PER_CHAT} custom commands; remove one first."
+                    )),
+                    Err(e) => ToolResult::error(format!("Failed to save command: {e}")),
+                }
+            }
+            "remove" => {
+                let name_for_db = name.clone();
+                match call_blocking(self.db.clone(), move |d| {
+                    d.delete_custom_command(chat_id, &name_for_db)
+                })
+                .await
+                {
+                    Ok(true) => {
+                        self.sync_menu(chat_id).await;
+                        ToolResult::success(format!("Removed /{name}."))
+                    }
+                    Ok(false) => {
+                        ToolResult::error(format!("No custom command /{name} in this chat."))
+                    }
+                    Err(e) => ToolResult::error(format!("Failed to remove command: {e}")),
+                }
+            }
+            other => ToolResult::error(format!(
+                "Unknown action '{other}'. Use define, remove, or list."
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_define_list_remove() {
+        let dir = std::env::temp_dir().join(format!("microclaw_defcmd_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(5, Some("home"), "web").unwrap();
+        let tool = DefineCommandTool::new(Bot::new("123456:TEST_TOKEN"), db.clone());
+
+        let result = tool
+            .execute(json!({
+                "action": "define",
+                "name": "/Standup",
+                "template": "Standup for {{args}}",
+                "chat_id": 5
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        let expanded =
+            crate::custom_commands::expand_custom_command(db.clone(), 5, "/standup team a").await;
+        assert_eq!(expanded.as_deref(), Some("Standup for team a"));
+
+        let result = tool.execute(json!({"action": "list", "chat_id": 5})).await;
+        assert!(result
+            .content
+            .contains("/standup — Custom command /standup"));
+
+        let result = tool
+            .execute(json!({"action": "define", "name": "reset", "template": "x", "chat_id": 5}))
+            .await;
+        assert!(result.is_error);
+
+        let result = tool
+            .execute(json!({"action": "remove", "name": "standup", "chat_id": 5}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(
+            crate::custom_commands::expand_custom_command(db, 5, "/standup")
+                .await
+                .is_none()
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,181 @@
+//! `detached_runs`: list, read, type into and stop detached runs (e.g. `cursor_agent` with
+//! `detach: true`) from a control chat, whichever backend runs them.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::detached::{self, DetachedRun, RunState};
+
+const DEFAULT_OUTPUT_LINES: usize = 30;
+const MAX_OUTPUT_LINES: usize = 500;
+
+fn render_runs(runs: &[DetachedRun]) -> String {
+    if runs.is_empty() {
+        return "No detached runs.".into();
+    }
+    let mut out = format!("{} detached run(s):\n", runs.len());
+    for run in runs {
+        let started = chrono::DateTime::from_timestamp(run.started, 0)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| "unknown".into());
+        let state = match run.state {
+            RunState::Running => "running".to_string(),
+            RunState::Exited(Some(code)) => format!("exited {code}"),
+            RunState::Exited(None) => "killed".to_string(),
+            RunState::Gone => "gone".to_string(),
+        };
+        out.push_str(&format!(
+            "- {} ({}, {}, started {})\n",
+            run.name,
+            run.backend.as_str(),
+            state,
+            started
+        ));
+    }
+    out
+}
+
+pub struct DetachedRunsTool {
+    state_dir: PathBuf,
+}
+
+impl DetachedRunsTool {
+    pub fn new(config: &Config) -> Self {
+        DetachedRunsTool {
+            state_dir: detached::state_dir(config),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DetachedRunsTool {
+    fn name(&self) -> &str {
+        "detached_runs"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "detached_runs".into(),
+            description: "Manage detached runs (processes that keep running outside a turn, e.g. cursor_agent with detach). Actions: list (default), output (recent output of 'name'), send (type 'text' into 'name'; tmux-backed runs only), stop. Only available from control chats.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "output", "send", "stop"],
+                        "description": "What to do (default: list)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Run name, as returned when it was started"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Input to send (action=send)"
+                    },
+                    "lines": {
+                        "type": "integer",
+                        "description": "Lines of output to show (default 30, max 500)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(auth) = auth_context_from_input(&input) {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: detached runs are only available from a control chat"
+                        .into(),
+                );
+            }
+        }
+        let action = input
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("list");
+        if action == "list" {
+            return match detached::list(&self.state_dir).await {
+                Ok(runs) => ToolResult::success(render_runs(&runs)),
+                Err(e) => ToolResult::error(e.to_string()),
+            };
+        }
+        let Some(name) = input
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        else {
+            return ToolResult::error(format!("'name' is required for action '{action}'"));
+        };
+        let result = match action {
+            "output" => {
+                let lines = input
+                    .get("lines")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize)
+                    .unwrap_or(DEFAULT_OUTPUT_LINES)
+                    .min(MAX_OUTPUT_LINES);
+                detached::output(&self.state_dir, name, lines).await
+            }
+            "send" => {
+                let Some(text) = input.get("text").and_then(|v| v.as_str()) else {
+                    return ToolResult::error("'text' is required for action 'send'".into());
+                };
+                detached::send_input(&self.state_dir, name, text)
+                    .await
+                    .map(|_| format!("Sent input to {name}."))
+            }
+            "stop" => detached::stop(&self.state_dir, name)
+                .await
+                .map(|_| format!("Stopped {name}.")),
+            other => return ToolResult::error(format!("Unknown action '{other}'")),
+        };
+        match result {
+            Ok(text) => ToolResult::success(text),
+            Err(e) => ToolResult::error(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detached::Backend;
+
+    #[tokio::test]
+    async fn test_requires_control_chat_and_renders() {
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let tool = DetachedRunsTool::new(&config);
+        let result = tool
+            .execute(json!({
+                "__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [100]}
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("control chat"));
+        let result = tool.execute(json!({"action": "stop"})).await;
+        assert_eq!(result.content, "'name' is required for action 'stop'");
+
+        assert_eq!(render_runs(&[]), "No detached runs.");
+        let out = render_runs(&[DetachedRun {
+            name: "microclaw-cursor-1".into(),
+            backend: Backend::Process,
+            state: RunState::Exited(Some(0)),
+            started: 0,
+        }]);
+        assert_eq!(
+            out,
+            "1 detached run(s):\n- microclaw-cursor-1 (process, exited 0, started 1970-01-01T00:00:00+00:00)\n"
+        );
+    }
+}
@@ -46,6 +46,7 @@ impl Tool for EditFileTool {
                 }),
                 &["path", "old_string", "new_string"],
             ),
+            examples: Vec::new(),
         }
     }
 
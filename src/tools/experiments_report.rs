@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database};
+use crate::experiments::format_report;
+
+pub struct ExperimentsReportTool {
+    db: Arc<Database>,
+}
+
+impl ExperimentsReportTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        ExperimentsReportTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for ExperimentsReportTool {
+    fn name(&self) -> &str {
+        "experiments_report"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "experiments_report".into(),
+            description: "Compare the variants (control vs treatment) of A/B prompt experiments: runs, average LLM rounds, tool calls, duration and output tokens, user feedback from reactions, retry requests, and error/limit rate. Without an experiment name, reports every experiment with recorded runs. Only available from control chats.".into(),
+            input_schema: schema_object(
+                json!({
+                    "experiment": {
+                        "type": "string",
+                        "description": "Experiment name (optional; default: all experiments)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(auth) = auth_context_from_input(&input) {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: experiment reports are only available from a control chat"
+                        .into(),
+                );
+            }
+        }
+        let requested = input
+            .get("experiment")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from);
+
+        let result = call_blocking(self.db.clone(), move |d| {
+            let names = match requested {
+                Some(name) => vec![name],
+                None => d
+                    .list_experiments()?
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect(),
+            };
+            names
+                .into_iter()
+                .map(|name| {
+                    let stats = d.experiment_variant_stats(&name)?;
+                    Ok(format_report(&name, &stats))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await;
+        match result {
+            Ok(reports) if reports.is_empty() => {
+                ToolResult::success("No experiment runs recorded yet.".into())
+            }
+            Ok(reports) => ToolResult::success(reports.join("\n\n")),
+            Err(e) => ToolResult::error(format!("Failed to build experiment report: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ExperimentRun;
+
+    #[tokio::test]
+    async fn test_report_requires_control_chat_and_lists_experiments() {
+        let dir = std::env::temp_dir().join(format!("microclaw_expr_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let tool = ExperimentsReportTool::new(db.clone());
+        let auth = json!({"caller_chat_id": 100, "control_chat_ids": [100]});
+
+        let empty = tool.execute(json!({"__microclaw_auth": auth})).await;
+        assert_eq!(empty.content, "No experiment runs recorded yet.");
+
+        db.record_experiment_run(&ExperimentRun {
+            experiment: "concise".into(),
+            variant: "treatment".into(),
+            chat_id: 5,
+            persona_id: 1,
+            model: "m".into(),
+            iterations: 2,
+            outcome: "ok".into(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let denied = tool
+            .execute(json!({
+                "__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [100]}
+            }))
+            .await;
+        assert!(denied.is_error);
+
+        let report = tool.execute(json!({"__microclaw_auth": auth})).await;
+        assert!(!report.is_error, "{}", report.content);
+        assert!(
+            report.content.contains("Experiment 'concise'"),
+            "{}",
+            report.content
+        );
+        assert!(
+            report.content.contains("treatment: 1 runs"),
+            "{}",
+            report.content
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
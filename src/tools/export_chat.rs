@@ -44,6 +44,7 @@ impl Tool for ExportChatTool {
                 }),
                 &["chat_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -56,6 +57,11 @@ impl Tool for ExportChatTool {
             return ToolResult::error(e);
         }
 
+        if let Err(e) =
+            crate::chat_archive::rehydrate(self.db.clone(), &self.data_dir, chat_id).await
+        {
+            return ToolResult::error(format!("Failed to restore archived chat: {e}"));
+        }
         let cid = chat_id;
         let persona_id = match call_blocking(self.db.clone(), move |db| db.get_or_create_default_persona(cid)).await {
             Ok(pid) => pid,
@@ -0,0 +1,630 @@
+//! `extract_document`: PDF, DOCX and XLSX files under workspace_dir as text/markdown, split into
+//! chunks the model reads one at a time. PDFs come out page by page, Word documents keep headings,
+//! lists and tables, and each spreadsheet sheet becomes a markdown table. Files larger than
+//! `max_document_size_mb` are refused.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+
+/// Characters per chunk; about 3k tokens.
+const CHUNK_CHARS: usize = 12_000;
+/// Rows per sheet before the rest is summarised as a count.
+const MAX_SHEET_ROWS: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DocKind {
+    Pdf,
+    Docx,
+    Xlsx,
+}
+
+impl DocKind {
+    fn label(self) -> &'static str {
+        match self {
+            DocKind::Pdf => "PDF",
+            DocKind::Docx => "DOCX",
+            DocKind::Xlsx => "XLSX",
+        }
+    }
+
+    /// By extension, falling back to the content (PDF header, or the parts inside a zip).
+    fn detect(path: &Path, bytes: &[u8]) -> Option<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("pdf") => return Some(DocKind::Pdf),
+            Some("docx") => return Some(DocKind::Docx),
+            Some("xlsx") | Some("xlsm") => return Some(DocKind::Xlsx),
+            _ => {}
+        }
+        if bytes.starts_with(b"%PDF") {
+            return Some(DocKind::Pdf);
+        }
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).ok()?;
+        let names: Vec<&str> = archive.file_names().collect();
+        if names.contains(&"word/document.xml") {
+            Some(DocKind::Docx)
+        } else if names.contains(&"xl/workbook.xml") {
+            Some(DocKind::Xlsx)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct ExtractDocumentTool {
+    working_dir: PathBuf,
+    workspace_root: PathBuf,
+    max_bytes: u64,
+}
+
+impl ExtractDocumentTool {
+    pub fn new(config: &Config) -> Self {
+        ExtractDocumentTool {
+            working_dir: PathBuf::from(config.working_dir()),
+            workspace_root: config.workspace_root_absolute(),
+            max_bytes: config
+                .max_document_size_mb
+                .saturating_mul(1024)
+                .saturating_mul(1024),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ExtractDocumentTool {
+    fn name(&self) -> &str {
+        "extract_document"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "extract_document".into(),
+            description: "Extract the text of a PDF, Word (.docx) or Excel (.xlsx) file under the workspace as markdown, e.g. a document the user sent (its saved_path). PDFs are split by page, sheets become markdown tables. Long documents are returned in chunks: the result says how many there are; call again with `chunk` to read further.".into(),
+            input_schema: schema_object(
+                json!({
+                    "path": {
+                        "type": "string",
+                        "description": "Path of the document (relative paths are resolved from the shared workspace)"
+                    },
+                    "chunk": {
+                        "type": "integer",
+                        "description": "Which chunk to return, starting at 1 (default 1)"
+                    }
+                }),
+                &["path"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let path = match input.get("path").and_then(|v| v.as_str()) {
+            Some(p) if !p.trim().is_empty() => p.trim(),
+            _ => return ToolResult::error("Missing 'path' parameter".into()),
+        };
+        let chunk = input
+            .get("chunk")
+            .and_then(|v| v.as_u64())
+            .filter(|c| *c > 0)
+            .unwrap_or(1) as usize;
+
+        let working_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let resolved = super::resolve_tool_path(&working_dir, path);
+        if let Err(msg) = super::path_guard::check_path(&resolved.to_string_lossy()) {
+            return ToolResult::error(msg);
+        }
+        let resolved = match resolved.canonicalize() {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Cannot open {path}: {e}")),
+        };
+        let root = self
+            .workspace_root
+            .canonicalize()
+            .unwrap_or_else(|_| self.workspace_root.clone());
+        if !resolved.starts_with(&root) {
+            return ToolResult::error(format!(
+                "{path} is outside the workspace; only files under {} can be extracted",
+                root.display()
+            ));
+        }
+        let size = match tokio::fs::metadata(&resolved).await {
+            Ok(meta) if meta.is_file() => meta.len(),
+            Ok(_) => return ToolResult::error(format!("{path} is not a file")),
+            Err(e) => return ToolResult::error(format!("Cannot open {path}: {e}")),
+        };
+        if size > self.max_bytes {
+            return ToolResult::error(format!(
+                "{path} is too large ({size} bytes). Max allowed is {} MB.",
+                self.max_bytes / (1024 * 1024)
+            ));
+        }
+
+        info!("Extracting document: {}", resolved.display());
+        let file = resolved.clone();
+        let extracted = tokio::task::spawn_blocking(move || -> Result<_, String> {
+            let bytes = std::fs::read(&file).map_err(|e| format!("Failed to read file: {e}"))?;
+            let kind = DocKind::detect(&file, &bytes)
+                .ok_or("Unsupported document: expected a PDF, DOCX or XLSX file")?;
+            let text = match kind {
+                DocKind::Pdf => pdf_markdown(&bytes)?,
+                DocKind::Docx => docx_markdown(&bytes)?,
+                DocKind::Xlsx => xlsx_markdown(&bytes)?,
+            };
+            Ok((kind, text))
+        })
+        .await;
+        let (kind, text) = match extracted {
+            Ok(Ok(out)) => out,
+            Ok(Err(e)) => return ToolResult::error(e),
+            // pdf-extract panics on some malformed files
+            Err(_) => return ToolResult::error(format!("Could not parse {path}")),
+        };
+        if text.trim().is_empty() {
+            return ToolResult::success(format!(
+                "{path} ({}) has no extractable text (a scanned PDF needs OCR).",
+                kind.label()
+            ));
+        }
+
+        let chunks = split_chunks(&text, CHUNK_CHARS);
+        if chunk > chunks.len() {
+            return ToolResult::error(format!(
+                "{path} has {} chunk(s); chunk {chunk} does not exist",
+                chunks.len()
+            ));
+        }
+        let mut out = format!(
+            "{} ({}, {} chunk{}) — chunk {chunk}/{}:\n\n{}",
+            resolved.display(),
+            kind.label(),
+            chunks.len(),
+            if chunks.len() == 1 { "" } else { "s" },
+            chunks.len(),
+            chunks[chunk - 1]
+        );
+        if chunk < chunks.len() {
+            out.push_str(&format!(
+                "\n\n[Call extract_document again with chunk={} for more.]",
+                chunk + 1
+            ));
+        }
+        ToolResult::success(out)
+    }
+}
+
+/// Split on line boundaries into pieces of at most `max_chars` characters; a longer line is cut.
+fn split_chunks(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    for line in text.trim().lines() {
+        let mut line: Vec<char> = line.chars().collect();
+        loop {
+            let room = max_chars.saturating_sub(current_chars);
+            if line.len() < room || (current.is_empty() && line.len() <= max_chars) {
+                current.extend(line.iter());
+                current.push('\n');
+                current_chars += line.len() + 1;
+                break;
+            }
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current).trim_end().to_string());
+                current_chars = 0;
+                continue;
+            }
+            let rest = line.split_off(max_chars);
+            chunks.push(line.into_iter().collect());
+            line = rest;
+        }
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current.trim_end().to_string());
+    }
+    chunks
+}
+
+fn pdf_markdown(bytes: &[u8]) -> Result<String, String> {
+    let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+        .map_err(|e| format!("Could not parse PDF: {e}"))?;
+    let mut out = String::new();
+    for (i, page) in pages.iter().enumerate() {
+        let text = page.trim();
+        if text.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("## Page {}\n\n{text}\n\n", i + 1));
+    }
+    Ok(out)
+}
+
+fn zip_part(bytes: &[u8], name: &str) -> Result<Option<String>, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("Not a valid Office file: {e}"))?;
+    let mut part = match archive.by_name(name) {
+        Ok(part) => part,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Could not read {name}: {e}")),
+    };
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)
+        .map_err(|e| format!("Could not read {name}: {e}"))?;
+    Ok(Some(xml))
+}
+
+fn parse_xml<'a>(xml: &'a str, name: &str) -> Result<roxmltree::Document<'a>, String> {
+    roxmltree::Document::parse(xml).map_err(|e| format!("Could not parse {name}: {e}"))
+}
+
+/// `|`-separated row; pipes and line breaks inside cells are escaped.
+fn table_row(cells: &[String]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|c| c.replace('|', "\\|").replace('\n', " "))
+        .collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+fn markdown_table(rows: &[Vec<String>]) -> String {
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if width == 0 {
+        return String::new();
+    }
+    let mut out = Vec::with_capacity(rows.len() + 1);
+    for (i, row) in rows.iter().enumerate() {
+        let mut row = row.clone();
+        row.resize(width, String::new());
+        out.push(table_row(&row));
+        if i == 0 {
+            out.push(format!("|{}", " --- |".repeat(width)));
+        }
+    }
+    out.join("\n")
+}
+
+fn docx_paragraph(p: roxmltree::Node) -> String {
+    let mut text = String::new();
+    for node in p.descendants() {
+        match node.tag_name().name() {
+            "t" => text.push_str(node.text().unwrap_or("")),
+            "tab" => text.push('\t'),
+            "br" | "cr" => text.push('\n'),
+            _ => {}
+        }
+    }
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return text;
+    }
+    let props = p.children().find(|n| n.tag_name().name() == "pPr");
+    let attr = |name: &str| -> Option<String> {
+        props?
+            .children()
+            .find(|n| n.tag_name().name() == name)?
+            .attributes()
+            .find(|a| a.name() == "val")
+            .map(|a| a.value().to_string())
+    };
+    let style = attr("pStyle").unwrap_or_default().to_ascii_lowercase();
+    if let Some(level) = style.strip_prefix("heading").and_then(|l| l.parse::<usize>().ok()) {
+        return format!("{} {text}", "#".repeat(level.clamp(1, 6)));
+    }
+    if style == "title" {
+        return format!("# {text}");
+    }
+    let is_list = props
+        .map(|p| p.children().any(|n| n.tag_name().name() == "numPr"))
+        .unwrap_or(false);
+    if is_list || style.starts_with("list") {
+        return format!("- {text}");
+    }
+    text
+}
+
+fn docx_markdown(bytes: &[u8]) -> Result<String, String> {
+    let xml = zip_part(bytes, "word/document.xml")?.ok_or("DOCX has no word/document.xml")?;
+    let doc = parse_xml(&xml, "word/document.xml")?;
+    let body = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "body")
+        .ok_or("DOCX has no document body")?;
+    let mut blocks = Vec::new();
+    for node in body.children() {
+        match node.tag_name().name() {
+            "p" => blocks.push(docx_paragraph(node)),
+            "tbl" => {
+                let rows: Vec<Vec<String>> = node
+                    .children()
+                    .filter(|n| n.tag_name().name() == "tr")
+                    .map(|tr| {
+                        tr.children()
+                            .filter(|n| n.tag_name().name() == "tc")
+                            .map(|tc| {
+                                tc.children()
+                                    .filter(|n| n.tag_name().name() == "p")
+                                    .map(docx_paragraph)
+                                    .filter(|t| !t.is_empty())
+                                    .collect::<Vec<_>>()
+                                    .join(" ")
+                            })
+                            .collect()
+                    })
+                    .collect();
+                blocks.push(markdown_table(&rows));
+            }
+            _ => {}
+        }
+    }
+    let blocks: Vec<String> = blocks.into_iter().filter(|b| !b.is_empty()).collect();
+    Ok(blocks.join("\n\n"))
+}
+
+/// Zero-based column of a cell reference such as `C7` or `AB12`.
+fn column_index(cell_ref: &str) -> Option<usize> {
+    let letters: String = cell_ref
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let n = letters.chars().fold(0usize, |acc, c| {
+        acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+    });
+    Some(n - 1)
+}
+
+/// Text of a shared string or inline string (`<si>`/`<is>`), joining rich-text runs.
+fn string_item(node: roxmltree::Node) -> String {
+    node.descendants()
+        .filter(|n| n.tag_name().name() == "t")
+        .filter(|n| n.ancestors().all(|a| a.tag_name().name() != "rPh"))
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+fn xlsx_markdown(bytes: &[u8]) -> Result<String, String> {
+    let shared: Vec<String> = match zip_part(bytes, "xl/sharedStrings.xml")? {
+        Some(xml) => parse_xml(&xml, "xl/sharedStrings.xml")?
+            .root_element()
+            .children()
+            .filter(|n| n.tag_name().name() == "si")
+            .map(string_item)
+            .collect(),
+        None => Vec::new(),
+    };
+    let workbook = zip_part(bytes, "xl/workbook.xml")?.ok_or("XLSX has no xl/workbook.xml")?;
+    let workbook = parse_xml(&workbook, "xl/workbook.xml")?;
+    let rels = zip_part(bytes, "xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    let rels = if rels.is_empty() {
+        None
+    } else {
+        Some(parse_xml(&rels, "xl/_rels/workbook.xml.rels")?)
+    };
+
+    let sheets: Vec<(String, String)> = workbook
+        .descendants()
+        .filter(|n| n.tag_name().name() == "sheet")
+        .enumerate()
+        .map(|(i, sheet)| {
+            let name = sheet.attribute("name").unwrap_or("Sheet").to_string();
+            let rel_id = sheet
+                .attributes()
+                .find(|a| a.name() == "id")
+                .map(|a| a.value().to_string());
+            let target = rels
+                .as_ref()
+                .zip(rel_id)
+                .and_then(|(rels, id)| {
+                    rels.descendants()
+                        .find(|n| n.attribute("Id") == Some(id.as_str()))
+                        .and_then(|n| n.attribute("Target"))
+                        .map(|t| match t.strip_prefix('/') {
+                            Some(abs) => abs.to_string(),
+                            None => format!("xl/{t}"),
+                        })
+                })
+                .unwrap_or_else(|| format!("xl/worksheets/sheet{}.xml", i + 1));
+            (name, target)
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    for (name, part) in sheets {
+        let Some(xml) = zip_part(bytes, &part)? else {
+            continue;
+        };
+        let doc = parse_xml(&xml, &part)?;
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut skipped = 0;
+        for row in doc.descendants().filter(|n| n.tag_name().name() == "row") {
+            let mut cells: Vec<String> = Vec::new();
+            for cell in row.children().filter(|n| n.tag_name().name() == "c") {
+                let col = cell
+                    .attribute("r")
+                    .and_then(column_index)
+                    .unwrap_or(cells.len());
+                let value = cell
+                    .children()
+                    .find(|n| n.tag_name().name() == "v")
+                    .and_then(|v| v.text())
+                    .unwrap_or("");
+                let text = match cell.attribute("t") {
+                    Some("s") => value
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| shared.get(i).cloned())
+                        .unwrap_or_default(),
+                    Some("inlineStr") => cell
+                        .children()
+                        .find(|n| n.tag_name().name() == "is")
+                        .map(string_item)
+                        .unwrap_or_default(),
+                    Some("b") => if value == "1" { "TRUE" } else { "FALSE" }.to_string(),
+                    _ => value.to_string(),
+                };
+                if text.is_empty() {
+                    continue;
+                }
+                if cells.len() <= col {
+                    cells.resize(col + 1, String::new());
+                }
+                cells[col] = text;
+            }
+            if cells.is_empty() {
+                continue;
+            }
+            if rows.len() >= MAX_SHEET_ROWS {
+                skipped += 1;
+                continue;
+            }
+            rows.push(cells);
+        }
+        let mut section = format!("## Sheet: {name}\n\n");
+        if rows.is_empty() {
+            section.push_str("(empty)");
+        } else {
+            section.push_str(&markdown_table(&rows));
+        }
+        if skipped > 0 {
+            section.push_str(&format!("\n\n({skipped} more rows not shown)"));
+        }
+        out.push(section);
+    }
+    Ok(out.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn office_file(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in parts {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tool_for(dir: &Path, max_document_size_mb: u64) -> ExtractDocumentTool {
+        ExtractDocumentTool {
+            working_dir: dir.to_path_buf(),
+            workspace_root: dir.to_path_buf(),
+            max_bytes: max_document_size_mb * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_docx_headings_lists_and_tables() {
+        let doc = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Lease</w:t></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">Rent is </w:t></w:r><w:r><w:t>due monthly.</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:t>No pets</w:t></w:r></w:p>
+            <w:tbl>
+              <w:tr><w:tc><w:p><w:r><w:t>Item</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Cost</w:t></w:r></w:p></w:tc></w:tr>
+              <w:tr><w:tc><w:p><w:r><w:t>Rent</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>1200</w:t></w:r></w:p></w:tc></w:tr>
+            </w:tbl>
+        </w:body></w:document>"#;
+        let bytes = office_file(&[("word/document.xml", doc)]);
+        assert_eq!(DocKind::detect(Path::new("upload.bin"), &bytes), Some(DocKind::Docx));
+        assert_eq!(
+            docx_markdown(&bytes).unwrap(),
+            "# Lease\n\nRent is due monthly.\n\n- No pets\n\n| Item | Cost |\n| --- | --- |\n| Rent | 1200 |"
+        );
+    }
+
+    #[test]
+    fn test_xlsx_sheets_as_tables() {
+        let workbook = r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+            <sheets><sheet name="Budget" sheetId="1" r:id="rId1"/></sheets></workbook>"#;
+        let rels = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+            <Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#;
+        let shared = r#"<sst><si><t>Category</t></si><si><t>Amount</t></si><si><r><t>Gro</t></r><r><t>ceries</t></r></si></sst>"#;
+        let sheet = r#"<worksheet><sheetData>
+            <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row>
+            <row r="2"><c r="A2" t="s"><v>2</v></c><c r="C2"><v>412.5</v></c></row>
+            <row r="3"><c r="A3" t="inlineStr"><is><t>Paid</t></is></c><c r="B3" t="b"><v>1</v></c></row>
+        </sheetData></worksheet>"#;
+        let bytes = office_file(&[
+            ("xl/workbook.xml", workbook),
+            ("xl/_rels/workbook.xml.rels", rels),
+            ("xl/sharedStrings.xml", shared),
+            ("xl/worksheets/sheet1.xml", sheet),
+        ]);
+        assert_eq!(
+            xlsx_markdown(&bytes).unwrap(),
+            "## Sheet: Budget\n\n| Category | Amount |  |\n| --- | --- | --- |\n| Groceries |  | 412.5 |\n| Paid | TRUE |  |"
+        );
+    }
+
+    #[test]
+    fn test_split_chunks_on_line_boundaries() {
+        let chunks = split_chunks("aaaa\nbbbb\ncccc", 10);
+        assert_eq!(chunks, vec!["aaaa\nbbbb", "cccc"]);
+        let chunks = split_chunks("abcdefghijkl", 5);
+        assert_eq!(chunks, vec!["abcde", "fghij", "kl"]);
+    }
+
+    #[tokio::test]
+    async fn test_extract_document_limits_and_chunks() {
+        let dir = std::env::temp_dir().join(format!("microclaw_extract_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        let paragraphs: String = (0..400)
+            .map(|i| format!("<w:p><w:r><w:t>Paragraph {i} of the contract text.</w:t></w:r></w:p>"))
+            .collect();
+        let doc = format!("<w:document xmlns:w=\"urn:w\"><w:body>{paragraphs}</w:body></w:document>");
+        std::fs::write(
+            dir.join("shared/contract.docx"),
+            office_file(&[("word/document.xml", &doc)]),
+        )
+        .unwrap();
+
+        let tool = tool_for(&dir, 1);
+        let out = tool.execute(json!({"path": "contract.docx"})).await;
+        assert!(!out.is_error, "{}", out.content);
+        assert!(out.content.contains("(DOCX, 2 chunks) — chunk 1/2"));
+        assert!(out.content.contains("Paragraph 0 of the contract text."));
+        assert!(out.content.ends_with("[Call extract_document again with chunk=2 for more.]"));
+
+        let out = tool.execute(json!({"path": "contract.docx", "chunk": 2})).await;
+        assert!(out.content.contains("Paragraph 399 of the contract text."));
+        assert!(!out.content.contains("chunk=3"));
+        assert!(tool
+            .execute(json!({"path": "contract.docx", "chunk": 3}))
+            .await
+            .is_error);
+
+        std::fs::write(dir.join("shared/notes.txt"), "plain text").unwrap();
+        let out = tool.execute(json!({"path": "notes.txt"})).await;
+        assert!(out.is_error);
+        assert!(out.content.contains("Unsupported document"));
+
+        std::fs::write(dir.join("shared/big.pdf"), vec![b'x'; 1024 * 1024 + 1]).unwrap();
+        let out = tool.execute(json!({"path": "big.pdf"})).await;
+        assert!(out.content.contains("too large"), "{}", out.content);
+
+        let outside = std::env::temp_dir().join(format!("microclaw_outside_{}.pdf", uuid::Uuid::new_v4()));
+        std::fs::write(&outside, b"%PDF-1.4").unwrap();
+        let out = tool
+            .execute(json!({"path": outside.to_str().unwrap()}))
+            .await;
+        assert!(out.content.contains("outside the workspace"));
+
+        let _ = std::fs::remove_file(&outside);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
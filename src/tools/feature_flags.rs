@@ -0,0 +1,167 @@
+//! `feature_flags`: show a chat's feature flags, or (from a control chat) override one for that
+//! chat or drop the override so the configured default applies again. See
+//! [`crate::feature_flags`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::feature_flags::{chat_flags, format_flags, FeatureFlag};
+
+pub struct FeatureFlagsTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl FeatureFlagsTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        FeatureFlagsTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for FeatureFlagsTool {
+    fn name(&self) -> &str {
+        "feature_flags"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        let flags: Vec<&str> = FeatureFlag::ALL.iter().map(|f| f.name()).collect();
+        ToolDefinition {
+            name: "feature_flags".into(),
+            description: "Show which optional capabilities (proactive messages, image input, web search, social tools, provider-side web search) are on for a chat. From a control chat, pass `flag` with `enabled` to turn one on or off for that chat only, or with `clear: true` to go back to the configured default.".into(),
+            input_schema: schema_object(
+                json!({
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to show or change (default: the current chat)"
+                    },
+                    "flag": {
+                        "type": "string",
+                        "enum": flags,
+                        "description": "Flag to change"
+                    },
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "New state of the flag for this chat"
+                    },
+                    "clear": {
+                        "type": "boolean",
+                        "description": "Remove the chat's override instead"
+                    }
+                }),
+                &[],
+            ),
+            examples: vec![
+                json!({}),
+                json!({"chat_id": -1001234, "flag": "web_search", "enabled": true}),
+            ],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let auth = auth_context_from_input(&input);
+        let Some(chat_id) = input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth.as_ref().map(|a| a.caller_chat_id))
+        else {
+            return ToolResult::error("Missing 'chat_id' parameter".into());
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+
+        if let Some(name) = input.get("flag").and_then(|v| v.as_str()) {
+            if auth.as_ref().is_some_and(|a| !a.is_control_chat()) {
+                return ToolResult::error(
+                    "Permission denied: feature flags can only be changed from a control chat"
+                        .into(),
+                );
+            }
+            let Some(flag) = FeatureFlag::parse(name) else {
+                return ToolResult::error(format!(
+                    "Unknown flag '{name}' (one of: {})",
+                    FeatureFlag::names()
+                ));
+            };
+            let enabled = if input.get("clear").and_then(|v| v.as_bool()) == Some(true) {
+                None
+            } else {
+                match input.get("enabled").and_then(|v| v.as_bool()) {
+                    Some(enabled) => Some(enabled),
+                    None => {
+                        return ToolResult::error(
+                            "Pass 'enabled' (true/false) or 'clear': true with 'flag'".into(),
+                        )
+                    }
+                }
+            };
+            if let Err(e) = call_blocking(self.db.clone(), move |d| {
+                d.set_feature_flag(chat_id, flag.name(), enabled)
+            })
+            .await
+            {
+                return ToolResult::error(format!("Failed to update feature flag: {e}"));
+            }
+            info!(
+                "Feature flag {} for chat {chat_id} set to {enabled:?}",
+                flag.name()
+            );
+        }
+
+        let flags = chat_flags(&self.config, self.db.clone(), chat_id).await;
+        ToolResult::success(format_flags(chat_id, &flags))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_show_and_override_flags() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_feature_flags_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let tool = FeatureFlagsTool::new(&config, db.clone());
+
+        let out = tool
+            .execute(json!({"__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": []}}))
+            .await;
+        assert!(out.content.starts_with("Feature flags for chat 5:"));
+        assert!(out.content.contains("- vision: on —"));
+
+        let out = tool
+            .execute(json!({"flag": "vision", "enabled": false, "__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": []}}))
+            .await;
+        assert!(out.is_error);
+        assert!(out.content.contains("control chat"));
+
+        let control = json!({"caller_chat_id": 100, "control_chat_ids": [100]});
+        let out = tool
+            .execute(json!({"chat_id": 5, "flag": "vision", "enabled": false, "__microclaw_auth": control}))
+            .await;
+        assert!(out.content.contains("- vision: off (chat override)"));
+        let out = tool
+            .execute(
+                json!({"chat_id": 5, "flag": "vision", "clear": true, "__microclaw_auth": control}),
+            )
+            .await;
+        assert!(out.content.contains("- vision: on —"));
+        assert!(db.list_feature_flags(5).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::Database;
+use crate::feedback_report::generate;
+
+const DEFAULT_DAYS: u64 = 7;
+
+pub struct FeedbackReportTool {
+    db: Arc<Database>,
+}
+
+impl FeedbackReportTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        FeedbackReportTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for FeedbackReportTool {
+    fn name(&self) -> &str {
+        "feedback_report"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "feedback_report".into(),
+            description: "Report low-rated replies (negative reactions) and failed runs (scheduled tasks, background jobs, experiment turns) over the last days, grouped by kind with examples and suggested system-prompt or skill adjustments. Only available from control chats.".into(),
+            input_schema: schema_object(
+                json!({
+                    "days": {
+                        "type": "integer",
+                        "description": "How many days back to look (default 7)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(auth) = auth_context_from_input(&input) {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: feedback reports are only available from a control chat"
+                        .into(),
+                );
+            }
+        }
+        let days = input
+            .get("days")
+            .and_then(|v| v.as_u64())
+            .filter(|d| *d > 0)
+            .unwrap_or(DEFAULT_DAYS);
+        match generate(self.db.clone(), days).await {
+            Ok(report) => ToolResult::success(report),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_feedback_report_requires_control_chat() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_feedback_tool_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let tool = FeedbackReportTool::new(db);
+
+        let out = tool
+            .execute(json!({"__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [1]}}))
+            .await;
+        assert!(out.is_error);
+
+        let out = tool
+            .execute(json!({"days": 3, "__microclaw_auth": {"caller_chat_id": 1, "control_chat_ids": [1]}}))
+            .await;
+        assert_eq!(
+            out.content,
+            "No low-rated replies or failed runs in the last 3 days (0 positive reactions)."
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database, StoredMessage};
+use crate::text::truncate_chars_with;
+use crate::topics::{fts_any_terms_query, rank_segments};
+
+const EXCERPTS_PER_SEGMENT: usize = 3;
+const EXCERPT_CHARS: usize = 200;
+
+pub struct FindConversationsTool {
+    db: Arc<Database>,
+}
+
+impl FindConversationsTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        FindConversationsTool { db }
+    }
+}
+
+fn excerpt(m: &StoredMessage) -> String {
+    format!(
+        "  - [{}] {}: {}",
+        m.timestamp.get(..16).unwrap_or(&m.timestamp),
+        m.sender_name,
+        truncate_chars_with(&m.content, EXCERPT_CHARS, "...")
+    )
+}
+
+#[async_trait]
+impl Tool for FindConversationsTool {
+    fn name(&self) -> &str {
+        "find_conversations"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "find_conversations".into(),
+            description: "Find past conversations in this chat about a topic (e.g. about: \"car insurance\"). Matches topic tags recorded when conversations are compacted, so it finds discussions even when the exact words differ; falls back to keyword search. Prefer this over search_chat_history for \"when did we talk about X\" questions.".into(),
+            input_schema: schema_object(
+                json!({
+                    "about": {
+                        "type": "string",
+                        "description": "Topic to look for, e.g. \"car insurance\""
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to search (defaults to the current chat)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum conversations to return (default 5, max 20)"
+                    }
+                }),
+                &["about"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let about = match input.get("about").and_then(|v| v.as_str()) {
+            Some(a) if !a.trim().is_empty() => a.trim().to_string(),
+            _ => return ToolResult::error("Missing required parameter: about".into()),
+        };
+        let auth = auth_context_from_input(&input);
+        let Some(chat_id) = input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth.as_ref().map(|a| a.caller_chat_id))
+        else {
+            return ToolResult::error("Missing required parameter: chat_id".into());
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        let persona_id = auth.map(|a| a.caller_persona_id).unwrap_or(0);
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(5)
+            .clamp(1, 20) as usize;
+        let fts_query = fts_any_terms_query(&about);
+
+        let about_for_db = about.clone();
+        let result = call_blocking(self.db.clone(), move |db| {
+            let segments = db.get_conversation_segments(chat_id, persona_id)?;
+            let ranked: Vec<_> = rank_segments(&segments, &about_for_db, limit)
+                .into_iter()
+                .map(|(_, s)| s.clone())
+                .collect();
+            let mut found = Vec::new();
+            for segment in ranked {
+                let excerpts = match &fts_query {
+                    Some(q) => db
+                        .search_messages(
+                            chat_id,
+                            persona_id,
+                            q,
+                            EXCERPTS_PER_SEGMENT,
+                            Some(&segment.start_ts),
+                            Some(&segment.end_ts),
+                        )
+                        .unwrap_or_default(),
+                    None => Vec::new(),
+                };
+                found.push((segment, excerpts));
+            }
+            let keyword_hits = if found.is_empty() {
+                match &fts_query {
+                    Some(q) => db.search_messages(chat_id, persona_id, q, 10, None, None)?,
+                    None => Vec::new(),
+                }
+            } else {
+                Vec::new()
+            };
+            Ok((found, keyword_hits))
+        })
+        .await;
+
+        let (found, keyword_hits) = match result {
+            Ok(r) => r,
+            Err(e) => return ToolResult::error(format!("Search failed: {e}")),
+        };
+
+        if found.is_empty() {
+            if keyword_hits.is_empty() {
+                return ToolResult::success(format!(
+                    "No past conversations found about '{about}'."
+                ));
+            }
+            let lines: Vec<String> = keyword_hits.iter().map(excerpt).collect();
+            return ToolResult::success(format!(
+                "No tagged conversations about '{about}'. Keyword matches:\n{}",
+                lines.join("\n")
+            ));
+        }
+
+        let sections: Vec<String> = found
+            .iter()
+            .map(|(segment, excerpts)| {
+                let mut section = format!(
+                    "### {} → {} (topics: {})",
+                    segment.start_ts.get(..10).unwrap_or(&segment.start_ts),
+                    segment.end_ts.get(..10).unwrap_or(&segment.end_ts),
+                    segment.tags.join(", ")
+                );
+                if let Some(summary) = &segment.summary {
+                    section.push('\n');
+                    section.push_str(summary);
+                }
+                for m in excerpts {
+                    section.push('\n');
+                    section.push_str(&excerpt(m));
+                }
+                section
+            })
+            .collect();
+        ToolResult::success(format!(
+            "Conversations about '{about}':\n\n{}",
+            sections.join("\n\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_find_conversations_by_tag_and_fallback() {
+        let dir = std::env::temp_dir().join(format!("microclaw_findconv_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let messages = [
+            (
+                "1",
+                "2024-01-01T10:00:00+00:00",
+                "Our premium went up again",
+            ),
+            (
+                "2",
+                "2024-01-01T10:01:00+00:00",
+                "Let's compare quotes for the Honda",
+            ),
+            (
+                "3",
+                "2024-02-01T10:00:00+00:00",
+                "Book the flights to Lisbon",
+            ),
+        ];
+        for (id, ts, content) in messages {
+            db.store_message(&StoredMessage {
+                id: id.into(),
+                chat_id: 3,
+                persona_id: 0,
+                sender_name: "alice".into(),
+                content: content.into(),
+                is_from_bot: false,
+                timestamp: ts.into(),
+            })
+            .unwrap();
+        }
+        db.insert_conversation_segment(
+            3,
+            0,
+            "2024-01-01T10:00:00+00:00",
+            "2024-01-01T10:01:00+00:00",
+            Some("Premium increase; comparing quotes."),
+            &["car insurance".into(), "premium".into()],
+        )
+        .unwrap();
+
+        let tool = FindConversationsTool::new(db);
+        // Tag match even though no message contains "car insurance"
+        let result = tool
+            .execute(json!({"about": "car insurance", "chat_id": 3}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("topics: car insurance, premium"));
+        assert!(result.content.contains("Premium increase"));
+
+        // Untagged topic falls back to keyword search
+        let result = tool.execute(json!({"about": "Lisbon", "chat_id": 3})).await;
+        assert!(result.content.contains("Keyword matches"));
+        assert!(result.content.contains("flights to Lisbon"));
+
+        let result = tool
+            .execute(json!({"about": "gardening", "chat_id": 3}))
+            .await;
+        assert!(result.content.starts_with("No past conversations"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
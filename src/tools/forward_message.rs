@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use teloxide::prelude::*;
+
+use super::send_message::SendMessageTool;
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database, StoredMessage};
+
+/// Split stored message content into (text, attachment path). Attachments are recorded as
+/// `[document] saved_path=<path> <caption>` (received files) or `[attachment:<path>] <caption>`
+/// (files the bot sent).
+pub fn split_attachment(content: &str) -> (String, Option<String>) {
+    if let Some(rest) = content.strip_prefix("[document] saved_path=") {
+        let (path, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        return (text.trim().to_string(), Some(path.to_string()));
+    }
+    if let Some(rest) = content.strip_prefix("[attachment:") {
+        if let Some((path, text)) = rest.split_once(']') {
+            return (text.trim().to_string(), Some(path.to_string()));
+        }
+    }
+    (content.to_string(), None)
+}
+
+fn attribution(message: &StoredMessage, chat_title: Option<&str>) -> String {
+    let date = message.timestamp.get(..10).unwrap_or(&message.timestamp);
+    match chat_title {
+        Some(title) => format!("Forwarded from {} in {title} ({date})", message.sender_name),
+        None => format!("Forwarded from {} ({date})", message.sender_name),
+    }
+}
+
+pub struct ForwardMessageTool {
+    db: Arc<Database>,
+    sender: SendMessageTool,
+}
+
+impl ForwardMessageTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        ForwardMessageTool {
+            sender: SendMessageTool::new_with_config(
+                bot,
+                db.clone(),
+                config.bot_username.clone(),
+                config.clone(),
+            ),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ForwardMessageTool {
+    fn name(&self) -> &str {
+        "forward_message"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "forward_message".into(),
+            description: "Copy a stored message (with attribution and its file attachment, if any) to another chat, e.g. \"send that to the family group\". Pick the message with message_id, or with containing (most recent message with that text); by default the most recent bot message in the source chat is forwarded. The caller must be allowed to access both chats.".into(),
+            input_schema: schema_object(
+                json!({
+                    "to_chat_id": {
+                        "type": "integer",
+                        "description": "Chat to send the copy to"
+                    },
+                    "from_chat_id": {
+                        "type": "integer",
+                        "description": "Chat the message is in (defaults to the current chat)"
+                    },
+                    "message_id": {
+                        "type": "string",
+                        "description": "Stored message id"
+                    },
+                    "containing": {
+                        "type": "string",
+                        "description": "Forward the most recent message containing this text"
+                    },
+                    "from_user": {
+                        "type": "boolean",
+                        "description": "With no message_id: pick a user message instead of a bot message (default false)"
+                    },
+                    "comment": {
+                        "type": "string",
+                        "description": "Optional note added above the forwarded message"
+                    }
+                }),
+                &["to_chat_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(to_chat_id) = input.get("to_chat_id").and_then(|v| v.as_i64()) else {
+            return ToolResult::error("Missing required parameter: to_chat_id".into());
+        };
+        let Some(from_chat_id) = input
+            .get("from_chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth_context_from_input(&input).map(|a| a.caller_chat_id))
+        else {
+            return ToolResult::error("Missing required parameter: from_chat_id".into());
+        };
+        if let Err(e) = authorize_chat_access(&input, from_chat_id) {
+            return ToolResult::error(e);
+        }
+        if from_chat_id == to_chat_id {
+            return ToolResult::error("Source and target chat are the same".into());
+        }
+
+        let message_id = input
+            .get("message_id")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let containing = input
+            .get("containing")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from);
+        let from_user = input
+            .get("from_user")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let found = call_blocking(self.db.clone(), move |d| {
+            let message = match (message_id, containing) {
+                (Some(id), _) => d.get_message(from_chat_id, &id)?,
+                (None, Some(text)) => d.find_latest_message(from_chat_id, None, Some(&text))?,
+                (None, None) => d.find_latest_message(from_chat_id, Some(!from_user), None)?,
+            };
+            let title = d.get_chat_title(from_chat_id)?;
+            Ok((message, title))
+        })
+        .await;
+        let (message, title) = match found {
+            Ok((Some(m), title)) => (m, title),
+            Ok((None, _)) => return ToolResult::error("No matching message found".into()),
+            Err(e) => return ToolResult::error(format!("Failed to load message: {e}")),
+        };
+
+        let (body, attachment) = split_attachment(&message.content);
+        let mut text = String::new();
+        if let Some(comment) = input
+            .get("comment")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+        {
+            text.push_str(comment);
+            text.push_str("\n\n");
+        }
+        text.push_str(&attribution(&message, title.as_deref()));
+        if !body.is_empty() {
+            text.push_str(":\n");
+            text.push_str(&body);
+        }
+
+        // send_message re-checks target authorization and channel policy with the caller's context
+        let mut send_input = input.clone();
+        if let Some(obj) = send_input.as_object_mut() {
+            obj.retain(|k, _| k.starts_with("__"));
+            obj.insert("chat_id".into(), json!(to_chat_id));
+            match attachment.filter(|p| std::path::Path::new(p).is_file()) {
+                Some(path) => {
+                    obj.insert("attachment_path".into(), json!(path));
+                    obj.insert("caption".into(), json!(text));
+                }
+                None => {
+                    obj.insert("text".into(), json!(text));
+                }
+            }
+        }
+        let result = self.sender.execute(send_input).await;
+        if result.is_error {
+            return result;
+        }
+        ToolResult::success(format!(
+            "Forwarded message {} to chat {to_chat_id}.",
+            message.id
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_attachment() {
+        assert_eq!(
+            split_attachment("[document] saved_path=/tmp/a.pdf the lease"),
+            ("the lease".into(), Some("/tmp/a.pdf".into()))
+        );
+        assert_eq!(
+            split_attachment("[attachment:/tmp/b.png] chart"),
+            ("chart".into(), Some("/tmp/b.png".into()))
+        );
+        assert_eq!(split_attachment("plain"), ("plain".into(), None));
+    }
+
+    #[tokio::test]
+    async fn test_forward_latest_bot_message_to_web_chat() {
+        let dir = std::env::temp_dir().join(format!("microclaw_fwd_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(1, Some("Kitchen"), "web").unwrap();
+        db.upsert_chat(2, Some("Family"), "web").unwrap();
+        for (id, from_bot, content) in [
+            ("m1", true, "Soup recipe: lentils, carrots"),
+            ("m2", false, "thanks!"),
+        ] {
+            db.store_message(&StoredMessage {
+                id: id.into(),
+                chat_id: 1,
+                persona_id: 0,
+                sender_name: if from_bot {
+                    "bot".into()
+                } else {
+                    "alice".into()
+                },
+                content: content.into(),
+                is_from_bot: from_bot,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            })
+            .unwrap();
+        }
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let tool = ForwardMessageTool::new(&config, Bot::new("123456:TEST_TOKEN"), db.clone());
+        let result = tool
+            .execute(json!({"to_chat_id": 2, "from_chat_id": 1, "comment": "For Sunday"}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("m1"));
+
+        let forwarded = db
+            .find_latest_message(2, Some(true), None)
+            .unwrap()
+            .unwrap();
+        assert!(forwarded
+            .content
+            .starts_with("For Sunday\n\nForwarded from bot in Kitchen"));
+        assert!(forwarded.content.ends_with("Soup recipe: lentils, carrots"));
+
+        // Caller in chat 1 cannot forward out of another chat
+        let denied = tool
+            .execute(json!({
+                "to_chat_id": 1,
+                "from_chat_id": 2,
+                "__microclaw_auth": {"caller_chat_id": 1, "control_chat_ids": []}
+            }))
+            .await;
+        assert!(denied.is_error);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -42,6 +42,7 @@ impl Tool for GlobTool {
                 }),
                 &["pattern"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -0,0 +1,765 @@
+//! Google account tools: `search_gmail`, `read_email`, `search_drive`, `fetch_drive_file`.
+//! Read-only. Each chat connects its own Google account through the social OAuth flow, and a
+//! tool asks only for the one scope it needs (Gmail or Drive read-only) the first time it runs.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use base64::Engine;
+use serde_json::json;
+
+use super::social_feed::http_client;
+use super::web_html::html_to_text;
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::social_oauth;
+use crate::text::truncate_chars_with;
+
+pub(crate) const GMAIL_SCOPE: &str = "https://www.googleapis.com/auth/gmail.readonly";
+pub(crate) const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive.readonly";
+
+const GMAIL_API: &str = "https://gmail.googleapis.com/gmail/v1/users/me";
+const DRIVE_API: &str = "https://www.googleapis.com/drive/v3/files";
+const MAX_RESULTS: i64 = 10;
+const MAX_TEXT_CHARS: usize = 20_000;
+/// Binary Drive files larger than this are not downloaded.
+const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Resolve the chat's Google token. When the chat has not connected Google yet, or the grant
+/// lacks `scope`, return a link that asks for just that scope (earlier grants are kept).
+pub(crate) async fn google_token_or_authorize(
+    config: &Config,
+    db: Arc<Database>,
+    scope: &str,
+    access: &str,
+    input: &serde_json::Value,
+) -> Result<String, ToolResult> {
+    let Some(auth) = auth_context_from_input(input) else {
+        return Err(ToolResult::error("Missing auth context".into()));
+    };
+    let chat_id = auth.caller_chat_id;
+    let token = call_blocking(db.clone(), move |d| d.get_social_token("google", chat_id))
+        .await
+        .map_err(|e| ToolResult::error(e.to_string()))?;
+
+    if let Some(token) = token.filter(|t| social_oauth::has_scope(t, scope)) {
+        return social_oauth::fresh_access_token(config, db, &token)
+            .await
+            .map_err(|e| {
+                ToolResult::error(format!(
+                    "Google token refresh failed ({e}). Disconnect Google and authorize again."
+                ))
+            });
+    }
+
+    let scopes = format!(
+        "{} {scope}",
+        social_oauth::requested_scopes("google").unwrap_or("")
+    );
+    let url = social_oauth::start_authorization(config, db, "google", chat_id, Some(scopes.trim()))
+        .await
+        .map_err(|e| ToolResult::error(e.to_string()))?;
+    Err(ToolResult::error(format!(
+        "To use this, connect your Google account with {access} access. Click this link (valid for {} minutes, single use): {url}",
+        social_oauth::STATE_TTL_MINUTES
+    )))
+}
+
+pub(crate) async fn get_json(
+    client: &reqwest::Client,
+    token: &str,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<serde_json::Value, String> {
+    let resp = client
+        .get(url)
+        .bearer_auth(token)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let msg = body
+            .pointer("/error/message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("request failed");
+        return Err(format!("Google API error (HTTP {status}): {msg}"));
+    }
+    Ok(body)
+}
+
+/// Gmail message and Drive file ids are URL-safe tokens; reject anything else.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn truncate_text(text: &str) -> String {
+    truncate_chars_with(text, MAX_TEXT_CHARS, "\n\n[truncated]")
+}
+
+fn header<'a>(payload: &'a serde_json::Value, name: &str) -> &'a str {
+    payload
+        .get("headers")
+        .and_then(|h| h.as_array())
+        .and_then(|h| {
+            h.iter().find(|x| {
+                x.get("name")
+                    .and_then(|n| n.as_str())
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+        })
+        .and_then(|x| x.get("value"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+fn decode_base64url(data: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(data.trim_end_matches('='))
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Text of a Gmail message payload: the first text/plain part, else the first text/html part
+/// converted to text.
+pub(crate) fn message_body(payload: &serde_json::Value) -> String {
+    fn find(part: &serde_json::Value, mime: &str) -> Option<String> {
+        let part_mime = part.get("mimeType").and_then(|v| v.as_str()).unwrap_or("");
+        let is_attachment = part
+            .get("filename")
+            .and_then(|v| v.as_str())
+            .is_some_and(|f| !f.is_empty());
+        if part_mime == mime && !is_attachment {
+            if let Some(text) = part
+                .pointer("/body/data")
+                .and_then(|v| v.as_str())
+                .and_then(decode_base64url)
+            {
+                return Some(text);
+            }
+        }
+        part.get("parts")?
+            .as_array()?
+            .iter()
+            .find_map(|p| find(p, mime))
+    }
+    find(payload, "text/plain")
+        .or_else(|| find(payload, "text/html").map(|h| html_to_text(&h)))
+        .unwrap_or_default()
+}
+
+/// Attachment file names in a Gmail message payload.
+pub(crate) fn attachment_names(payload: &serde_json::Value) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut stack = vec![payload];
+    while let Some(part) = stack.pop() {
+        if let Some(f) = part
+            .get("filename")
+            .and_then(|v| v.as_str())
+            .filter(|f| !f.is_empty())
+        {
+            names.push(f.to_string());
+        }
+        if let Some(parts) = part.get("parts").and_then(|v| v.as_array()) {
+            stack.extend(parts.iter().rev());
+        }
+    }
+    names
+}
+
+/// Drive `q` expression matching file names or contents, excluding trashed files.
+pub(crate) fn drive_query(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('\'', "\\'");
+    format!("(name contains '{escaped}' or fullText contains '{escaped}') and trashed = false")
+}
+
+/// Export format for Google Docs editors files, which have no binary content of their own.
+fn export_mime(mime: &str) -> Option<&'static str> {
+    match mime {
+        "application/vnd.google-apps.document" | "application/vnd.google-apps.presentation" => {
+            Some("text/plain")
+        }
+        "application/vnd.google-apps.spreadsheet" => Some("text/csv"),
+        _ => None,
+    }
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json" | "application/xml" | "application/x-yaml" | "application/yaml"
+        )
+}
+
+fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.');
+    if cleaned.is_empty() {
+        "drive_file".into()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn limit_arg(input: &serde_json::Value) -> i64 {
+    input
+        .get("max_results")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(MAX_RESULTS)
+        .clamp(1, 50)
+}
+
+pub struct SearchGmailTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl SearchGmailTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        SearchGmailTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SearchGmailTool {
+    fn name(&self) -> &str {
+        "search_gmail"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "search_gmail".into(),
+            description: "Search the user's Gmail (read-only) with Gmail search syntax (e.g. 'from:alice is:unread newer_than:7d'). Returns message ids with sender, subject, date and snippet; use read_email for the full text. The first use returns a link to connect Google.".into(),
+            input_schema: schema_object(
+                json!({
+                    "query": {
+                        "type": "string",
+                        "description": "Gmail search query"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum messages to return (default 10, max 50)"
+                    }
+                }),
+                &["query"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let query = input
+            .get("query")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        if query.is_empty() {
+            return ToolResult::error("Missing 'query'".into());
+        }
+        let token = match google_token_or_authorize(
+            &self.config,
+            self.db.clone(),
+            GMAIL_SCOPE,
+            "read-only Gmail",
+            &input,
+        )
+        .await
+        {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        let list = match get_json(
+            &client,
+            &token,
+            &format!("{GMAIL_API}/messages"),
+            &[
+                ("q", query.clone()),
+                ("maxResults", limit_arg(&input).to_string()),
+            ],
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(e),
+        };
+        let ids: Vec<String> = list
+            .get("messages")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|m| m.get("id").and_then(|v| v.as_str()).map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if ids.is_empty() {
+            return ToolResult::success(format!("No messages match '{query}'."));
+        }
+
+        let mut out = format!("{} message(s) matching '{query}':\n", ids.len());
+        for id in ids {
+            let msg = match get_json(
+                &client,
+                &token,
+                &format!("{GMAIL_API}/messages/{id}"),
+                &[
+                    ("format", "metadata".into()),
+                    ("metadataHeaders", "From".into()),
+                    ("metadataHeaders", "Subject".into()),
+                    ("metadataHeaders", "Date".into()),
+                ],
+            )
+            .await
+            {
+                Ok(m) => m,
+                Err(e) => {
+                    out.push_str(&format!("- [{id}] (failed to load: {e})\n"));
+                    continue;
+                }
+            };
+            let payload = msg.get("payload").cloned().unwrap_or_default();
+            out.push_str(&format!(
+                "- [{id}] {} | {} | {}\n  {}\n",
+                header(&payload, "Date"),
+                header(&payload, "From"),
+                header(&payload, "Subject"),
+                msg.get("snippet").and_then(|v| v.as_str()).unwrap_or("")
+            ));
+        }
+        ToolResult::success(out)
+    }
+}
+
+pub struct ReadEmailTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl ReadEmailTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        ReadEmailTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadEmailTool {
+    fn name(&self) -> &str {
+        "read_email"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "read_email".into(),
+            description: "Read one Gmail message (read-only) by id from search_gmail: headers, plain-text body and attachment names.".into(),
+            input_schema: schema_object(
+                json!({
+                    "message_id": {
+                        "type": "string",
+                        "description": "Gmail message id"
+                    }
+                }),
+                &["message_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let id = input
+            .get("message_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        if !valid_id(&id) {
+            return ToolResult::error("Missing or invalid 'message_id'".into());
+        }
+        let token = match google_token_or_authorize(
+            &self.config,
+            self.db.clone(),
+            GMAIL_SCOPE,
+            "read-only Gmail",
+            &input,
+        )
+        .await
+        {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+        let msg = match get_json(
+            &client,
+            &token,
+            &format!("{GMAIL_API}/messages/{id}"),
+            &[("format", "full".into())],
+        )
+        .await
+        {
+            Ok(m) => m,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        let payload = msg.get("payload").cloned().unwrap_or_default();
+        let mut out = String::new();
+        for name in ["From", "To", "Cc", "Date", "Subject"] {
+            let value = header(&payload, name);
+            if !value.is_empty() {
+                out.push_str(&format!("{name}: {value}\n"));
+            }
+        }
+        let attachments = attachment_names(&payload);
+        if !attachments.is_empty() {
+            out.push_str(&format!("Attachments: {}\n", attachments.join(", ")));
+        }
+        out.push('\n');
+        out.push_str(message_body(&payload).trim());
+        ToolResult::success(truncate_text(&out))
+    }
+}
+
+pub struct SearchDriveTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl SearchDriveTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        SearchDriveTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SearchDriveTool {
+    fn name(&self) -> &str {
+        "search_drive"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "search_drive".into(),
+            description: "Search the user's Google Drive (read-only) by file name or content. Returns file ids with name, type, modified time and link; use fetch_drive_file to read one. The first use returns a link to connect Google.".into(),
+            input_schema: schema_object(
+                json!({
+                    "query": {
+                        "type": "string",
+                        "description": "Text to look for in file names and contents"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum files to return (default 10, max 50)"
+                    }
+                }),
+                &["query"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let query = input
+            .get("query")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        if query.is_empty() {
+            return ToolResult::error("Missing 'query'".into());
+        }
+        let token = match google_token_or_authorize(
+            &self.config,
+            self.db.clone(),
+            DRIVE_SCOPE,
+            "read-only Google Drive",
+            &input,
+        )
+        .await
+        {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+        let body = match get_json(
+            &client,
+            &token,
+            DRIVE_API,
+            &[
+                ("q", drive_query(&query)),
+                ("pageSize", limit_arg(&input).to_string()),
+                (
+                    "fields",
+                    "files(id,name,mimeType,modifiedTime,webViewLink,size)".into(),
+                ),
+            ],
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(e),
+        };
+        let files = body
+            .get("files")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if files.is_empty() {
+            return ToolResult::success(format!("No Drive files match '{query}'."));
+        }
+        let mut out = format!("{} file(s) matching '{query}':\n", files.len());
+        for f in files {
+            let s = |k: &str| f.get(k).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            out.push_str(&format!(
+                "- [{}] {} ({}, modified {}) {}\n",
+                s("id"),
+                s("name"),
+                s("mimeType"),
+                s("modifiedTime"),
+                s("webViewLink")
+            ));
+        }
+        ToolResult::success(out)
+    }
+}
+
+pub struct FetchDriveFileTool {
+    config: Config,
+    db: Arc<Database>,
+    downloads_dir: PathBuf,
+}
+
+impl FetchDriveFileTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        FetchDriveFileTool {
+            config: config.clone(),
+            db,
+            downloads_dir: config
+                .workspace_root_absolute()
+                .join("shared")
+                .join("downloads"),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for FetchDriveFileTool {
+    fn name(&self) -> &str {
+        "fetch_drive_file"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "fetch_drive_file".into(),
+            description: "Read a Google Drive file (read-only) by id from search_drive. Google Docs and Slides are returned as text, Sheets as CSV, text files inline; other files are saved to the shared downloads folder and the path is returned.".into(),
+            input_schema: schema_object(
+                json!({
+                    "file_id": {
+                        "type": "string",
+                        "description": "Drive file id"
+                    }
+                }),
+                &["file_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let id = input
+            .get("file_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        if !valid_id(&id) {
+            return ToolResult::error("Missing or invalid 'file_id'".into());
+        }
+        let token = match google_token_or_authorize(
+            &self.config,
+            self.db.clone(),
+            DRIVE_SCOPE,
+            "read-only Google Drive",
+            &input,
+        )
+        .await
+        {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+        let meta = match get_json(
+            &client,
+            &token,
+            &format!("{DRIVE_API}/{id}"),
+            &[("fields", "id,name,mimeType,size".into())],
+        )
+        .await
+        {
+            Ok(m) => m,
+            Err(e) => return ToolResult::error(e),
+        };
+        let name = meta
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("drive_file")
+            .to_string();
+        let mime = meta
+            .get("mimeType")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let size = meta
+            .get("size")
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        let request = if let Some(export) = export_mime(&mime) {
+            client
+                .get(format!("{DRIVE_API}/{id}/export"))
+                .query(&[("mimeType", export)])
+        } else if mime.starts_with("application/vnd.google-apps.") {
+            return ToolResult::error(format!("'{name}' ({mime}) cannot be exported as text."));
+        } else if size > MAX_DOWNLOAD_BYTES {
+            return ToolResult::error(format!(
+                "'{name}' is {size} bytes; files over {MAX_DOWNLOAD_BYTES} bytes are not downloaded."
+            ));
+        } else {
+            client
+                .get(format!("{DRIVE_API}/{id}"))
+                .query(&[("alt", "media")])
+        };
+        let resp = match request.bearer_auth(&token).send().await {
+            Ok(r) => r,
+            Err(e) => return ToolResult::error(e.to_string()),
+        };
+        if !resp.status().is_success() {
+            return ToolResult::error(format!(
+                "Google API error (HTTP {}) downloading '{name}'",
+                resp.status()
+            ));
+        }
+        let bytes = match resp.bytes().await {
+            Ok(b) => b,
+            Err(e) => return ToolResult::error(e.to_string()),
+        };
+
+        if export_mime(&mime).is_some() || is_text_mime(&mime) {
+            let text = String::from_utf8_lossy(&bytes).into_owned();
+            return ToolResult::success(truncate_text(&format!("{name} ({mime}):\n\n{text}")));
+        }
+
+        let path = self
+            .downloads_dir
+            .join(format!("{id}_{}", safe_file_name(&name)));
+        if let Err(e) =
+            std::fs::create_dir_all(&self.downloads_dir).and_then(|_| std::fs::write(&path, &bytes))
+        {
+            return ToolResult::error(format!("Failed to save '{name}': {e}"));
+        }
+        ToolResult::success(format!(
+            "Saved '{name}' ({mime}, {} bytes) to {}",
+            bytes.len(),
+            path.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::SocialOAuthToken;
+
+    fn b64(s: &str) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(s)
+    }
+
+    #[test]
+    fn test_message_body_prefers_plain_text_and_lists_attachments() {
+        let payload = json!({
+            "mimeType": "multipart/mixed",
+            "parts": [
+                {
+                    "mimeType": "multipart/alternative",
+                    "parts": [
+                        {"mimeType": "text/html", "body": {"data": b64("<p>Hi <b>there</b></p>")}},
+                        {"mimeType": "text/plain", "body": {"data": b64("Hi there")}}
+                    ]
+                },
+                {"mimeType": "text/plain", "filename": "notes.txt", "body": {"attachmentId": "a1"}},
+                {"mimeType": "application/pdf", "filename": "invoice.pdf", "body": {"attachmentId": "a2"}}
+            ]
+        });
+        assert_eq!(message_body(&payload), "Hi there");
+        assert_eq!(attachment_names(&payload), vec!["notes.txt", "invoice.pdf"]);
+
+        let html_only =
+            json!({"mimeType": "text/html", "body": {"data": b64("<p>Hello &amp; bye</p>")}});
+        assert_eq!(message_body(&html_only), "Hello & bye");
+    }
+
+    #[test]
+    fn test_drive_query_escapes_quotes() {
+        assert_eq!(
+            drive_query(r"bob's \notes"),
+            r"(name contains 'bob\'s \\notes' or fullText contains 'bob\'s \\notes') and trashed = false"
+        );
+        assert!(valid_id("1AbC-d_9"));
+        assert!(!valid_id("abc/../x"));
+        assert_eq!(safe_file_name("../Q3 report.pdf"), "_Q3_report.pdf");
+    }
+
+    #[test]
+    fn test_scope_check_uses_granted_scopes() {
+        let token = SocialOAuthToken {
+            platform: "google".into(),
+            chat_id: 1,
+            access_token: "t".into(),
+            refresh_token: None,
+            expires_at: None,
+            scopes: Some(format!("openid {GMAIL_SCOPE} email")),
+            connected_at: None,
+        };
+        assert!(social_oauth::has_scope(&token, GMAIL_SCOPE));
+        assert!(!social_oauth::has_scope(&token, DRIVE_SCOPE));
+    }
+}
@@ -46,6 +46,7 @@ impl Tool for GrepTool {
                 }),
                 &["pattern"],
             ),
+            examples: Vec::new(),
         }
     }
 
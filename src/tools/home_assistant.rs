@@ -0,0 +1,324 @@
+//! `home_assistant`: find entities, read their state and call services ("turn off the living
+//! room lights") through the Home Assistant REST API. See [`crate::home_assistant`].
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::HomeAssistantConfig;
+use crate::home_assistant::{EntityState, HomeAssistant};
+
+const DEFAULT_LIST_LIMIT: usize = 50;
+const MAX_ATTRIBUTES_CHARS: usize = 3000;
+
+/// Lowercase letters, digits and underscores, as in HA domains, services and object ids.
+fn valid_slug(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn valid_entity_id(id: &str) -> bool {
+    id.split_once('.')
+        .is_some_and(|(domain, object)| valid_slug(domain) && valid_slug(object))
+}
+
+/// `- light.kitchen (Kitchen): on`, with the unit for sensors.
+pub fn format_state(state: &EntityState) -> String {
+    let unit = state
+        .attributes
+        .get("unit_of_measurement")
+        .and_then(|v| v.as_str())
+        .map(|u| format!(" {u}"))
+        .unwrap_or_default();
+    let name = state.friendly_name();
+    if name == state.entity_id {
+        format!("- {}: {}{unit}", state.entity_id, state.state)
+    } else {
+        format!("- {} ({name}): {}{unit}", state.entity_id, state.state)
+    }
+}
+
+/// Entities in `domain` whose id or friendly name contains every word of `query`.
+pub fn filter_states<'a>(
+    states: &'a [EntityState],
+    domain: Option<&str>,
+    query: Option<&str>,
+) -> Vec<&'a EntityState> {
+    let words: Vec<String> = query
+        .unwrap_or("")
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let mut matches: Vec<&EntityState> = states
+        .iter()
+        .filter(|s| domain.is_none_or(|d| s.entity_id.split('.').next() == Some(d)))
+        .filter(|s| {
+            let haystack = format!(
+                "{} {}",
+                s.entity_id.replace('_', " "),
+                s.friendly_name().to_lowercase()
+            );
+            words.iter().all(|w| haystack.contains(w.as_str()))
+        })
+        .collect();
+    matches.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
+    matches
+}
+
+/// Service data: `data` plus `entity_id` (a string or list) when given.
+pub fn service_data(input: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut data = match input.get("data") {
+        None | Some(serde_json::Value::Null) => json!({}),
+        Some(serde_json::Value::Object(map)) => serde_json::Value::Object(map.clone()),
+        Some(_) => return Err("'data' must be an object".into()),
+    };
+    let entity_ids: Vec<&str> = match input.get("entity_id") {
+        None | Some(serde_json::Value::Null) => Vec::new(),
+        Some(serde_json::Value::String(id)) => vec![id.as_str()],
+        Some(serde_json::Value::Array(ids)) => ids.iter().filter_map(|v| v.as_str()).collect(),
+        Some(_) => return Err("'entity_id' must be a string or a list of strings".into()),
+    };
+    if let Some(bad) = entity_ids.iter().find(|id| !valid_entity_id(id)) {
+        return Err(format!("Invalid entity_id '{bad}'"));
+    }
+    match entity_ids.as_slice() {
+        [] => {}
+        [one] => data["entity_id"] = json!(one),
+        many => data["entity_id"] = json!(many),
+    }
+    Ok(data)
+}
+
+pub struct HomeAssistantTool {
+    config: HomeAssistantConfig,
+}
+
+impl HomeAssistantTool {
+    pub fn new(config: &HomeAssistantConfig) -> Self {
+        HomeAssistantTool {
+            config: config.clone(),
+        }
+    }
+
+    async fn list(&self, client: &HomeAssistant, input: &serde_json::Value) -> ToolResult {
+        let states = match client.states().await {
+            Ok(s) => s,
+            Err(e) => return ToolResult::error(e),
+        };
+        let domain = input.get("domain").and_then(|v| v.as_str());
+        let query = input.get("query").and_then(|v| v.as_str());
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|l| l.clamp(1, 500) as usize)
+            .unwrap_or(DEFAULT_LIST_LIMIT);
+        let matches = filter_states(&states, domain, query);
+        if matches.is_empty() {
+            return ToolResult::success("No matching entities.".into());
+        }
+        let mut out = format!("{} entities:\n", matches.len());
+        for state in matches.iter().take(limit) {
+            out.push_str(&format_state(state));
+            out.push('\n');
+        }
+        if matches.len() > limit {
+            out.push_str(&format!(
+                "... and {} more; narrow with domain or query.\n",
+                matches.len() - limit
+            ));
+        }
+        ToolResult::success(out.trim_end().to_string())
+    }
+
+    async fn get(&self, client: &HomeAssistant, input: &serde_json::Value) -> ToolResult {
+        let Some(entity_id) = input.get("entity_id").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing 'entity_id'".into());
+        };
+        if !valid_entity_id(entity_id) {
+            return ToolResult::error(format!("Invalid entity_id '{entity_id}'"));
+        }
+        let state = match client.state(entity_id).await {
+            Ok(s) => s,
+            Err(e) => return ToolResult::error(e),
+        };
+        let mut attributes = serde_json::to_string_pretty(&state.attributes).unwrap_or_default();
+        if attributes.len() > MAX_ATTRIBUTES_CHARS {
+            attributes.truncate(attributes.floor_char_boundary(MAX_ATTRIBUTES_CHARS));
+            attributes.push_str("\n...");
+        }
+        let mut out = format_state(&state);
+        if let Some(changed) = &state.last_changed {
+            out.push_str(&format!("\nLast changed: {changed}"));
+        }
+        out.push_str(&format!("\nAttributes:\n{attributes}"));
+        ToolResult::success(out)
+    }
+
+    async fn call(&self, client: &HomeAssistant, input: &serde_json::Value) -> ToolResult {
+        let domain = input.get("domain").and_then(|v| v.as_str()).unwrap_or("");
+        let service = input.get("service").and_then(|v| v.as_str()).unwrap_or("");
+        if !valid_slug(domain) || !valid_slug(service) {
+            return ToolResult::error(
+                "'domain' and 'service' are required, e.g. domain \"light\", service \"turn_off\""
+                    .into(),
+            );
+        }
+        if !self.config.service_domains.is_empty()
+            && !self.config.service_domains.iter().any(|d| d == domain)
+        {
+            return ToolResult::error(format!(
+                "Permission denied: services in '{domain}' are not allowed (allowed: {})",
+                self.config.service_domains.join(", ")
+            ));
+        }
+        let data = match service_data(input) {
+            Ok(d) => d,
+            Err(e) => return ToolResult::error(e),
+        };
+        let changed = match client.call_service(domain, service, &data).await {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+        let mut out = format!("Called {domain}.{service}.");
+        if changed.is_empty() {
+            out.push_str(" No entity changed state (it may already have been in that state).");
+        } else {
+            out.push_str("\nNow:");
+            for state in &changed {
+                out.push('\n');
+                out.push_str(&format_state(state));
+            }
+        }
+        ToolResult::success(out)
+    }
+}
+
+#[async_trait]
+impl Tool for HomeAssistantTool {
+    fn name(&self) -> &str {
+        "home_assistant"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "home_assistant".into(),
+            description: "Control the home through Home Assistant. Actions: 'list' finds entities by domain and/or words of their name (use it to get entity ids, e.g. query \"living room\" with domain \"light\"); 'get' shows an entity's state and attributes; 'call_service' runs a service such as light.turn_off, switch.toggle, climate.set_temperature (with data {\"temperature\": 21}) or cover.open_cover on entity_id. Look entity ids up with 'list' instead of guessing them.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "get", "call_service"]
+                    },
+                    "domain": {
+                        "type": "string",
+                        "description": "Entity domain for 'list', service domain for 'call_service' (light, switch, climate, cover, media_player, ...)"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "'list': words that must all appear in the entity id or name"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "'list': max entities shown (default 50)"
+                    },
+                    "entity_id": {
+                        "description": "Entity id, or a list of them for 'call_service'",
+                        "anyOf": [
+                            {"type": "string"},
+                            {"type": "array", "items": {"type": "string"}}
+                        ]
+                    },
+                    "service": {
+                        "type": "string",
+                        "description": "'call_service': service name, e.g. turn_off"
+                    },
+                    "data": {
+                        "type": "object",
+                        "description": "'call_service': extra service data, e.g. {\"brightness_pct\": 30}"
+                    }
+                }),
+                &["action"],
+            ),
+            examples: vec![
+                json!({"action": "list", "domain": "light", "query": "living room"}),
+                json!({"action": "call_service", "domain": "light", "service": "turn_off", "entity_id": "light.living_room"}),
+            ],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let client = HomeAssistant::new(&self.config);
+        match input.get("action").and_then(|v| v.as_str()).unwrap_or("") {
+            "list" => self.list(&client, &input).await,
+            "get" => self.get(&client, &input).await,
+            "call_service" => self.call(&client, &input).await,
+            other => ToolResult::error(format!(
+                "Unknown action '{other}': use list, get or call_service"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(id: &str, state: &str, attributes: serde_json::Value) -> EntityState {
+        EntityState {
+            entity_id: id.into(),
+            state: state.into(),
+            attributes,
+            last_changed: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_and_format_states() {
+        let states = vec![
+            entity(
+                "light.living_room_lamp",
+                "on",
+                json!({"friendly_name": "Living Room Lamp"}),
+            ),
+            entity("light.kitchen", "off", json!({})),
+            entity(
+                "sensor.living_room_temperature",
+                "21.5",
+                json!({"friendly_name": "Living Room Temperature", "unit_of_measurement": "°C"}),
+            ),
+        ];
+        let lights = filter_states(&states, Some("light"), Some("Living room"));
+        assert_eq!(lights.len(), 1);
+        assert_eq!(
+            format_state(lights[0]),
+            "- light.living_room_lamp (Living Room Lamp): on"
+        );
+        assert_eq!(filter_states(&states, None, Some("living")).len(), 2);
+        assert_eq!(filter_states(&states, Some("light"), None).len(), 2);
+        assert_eq!(
+            format_state(&states[2]),
+            "- sensor.living_room_temperature (Living Room Temperature): 21.5 °C"
+        );
+        assert_eq!(format_state(&states[1]), "- light.kitchen: off");
+    }
+
+    #[test]
+    fn test_service_data() {
+        let data = service_data(&json!({
+            "entity_id": "light.kitchen",
+            "data": {"brightness_pct": 30}
+        }))
+        .unwrap();
+        assert_eq!(
+            data,
+            json!({"brightness_pct": 30, "entity_id": "light.kitchen"})
+        );
+        let data = service_data(&json!({"entity_id": ["light.a", "light.b"]})).unwrap();
+        assert_eq!(data, json!({"entity_id": ["light.a", "light.b"]}));
+        assert_eq!(service_data(&json!({})).unwrap(), json!({}));
+        assert!(service_data(&json!({"entity_id": "../config"})).is_err());
+        assert!(service_data(&json!({"data": "on"})).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//! `incident`: list incidents, raise one, log an update, or acknowledge / resolve one. See
+//! [`crate::incidents`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use teloxide::Bot;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::incidents::{format_incidents, incident_chat_id, raise, set_status};
+
+pub struct IncidentTool {
+    config: Config,
+    bot: Bot,
+    db: Arc<Database>,
+}
+
+impl IncidentTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        IncidentTool {
+            config: config.clone(),
+            bot,
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for IncidentTool {
+    fn name(&self) -> &str {
+        "incident"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "incident".into(),
+            description: "Work with incidents: urgent alerts that page the incident chat repeatedly until acknowledged. `raise` opens one (or logs an update when one with the same key is open), `update` adds a note to its session, `ack` stops the paging, `resolve` closes it, `list` shows open ones. Use raise only for things that need a person now (outage, alarm, leak), not for routine news.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "raise", "update", "ack", "resolve"],
+                        "description": "What to do"
+                    },
+                    "id": {
+                        "type": "integer",
+                        "description": "Incident id (update, ack, resolve)"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Short summary (raise) or the update text (update)"
+                    },
+                    "details": {
+                        "type": "string",
+                        "description": "More context for raise"
+                    },
+                    "key": {
+                        "type": "string",
+                        "description": "Dedup key for raise; alerts with the key of an open incident become updates (default: the title)"
+                    },
+                    "include_resolved": {
+                        "type": "boolean",
+                        "description": "list: include resolved incidents"
+                    }
+                }),
+                &["action"],
+            ),
+            examples: vec![
+                json!({"action": "raise", "title": "Water leak sensor in basement", "key": "leak-basement"}),
+                json!({"action": "ack", "id": 3}),
+            ],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let action = input.get("action").and_then(|v| v.as_str()).unwrap_or("");
+        let auth = auth_context_from_input(&input);
+        let by = match &auth {
+            Some(auth) => format!("chat {}", auth.caller_chat_id),
+            None => "agent".to_string(),
+        };
+        let id = input.get("id").and_then(|v| v.as_i64());
+        let title = input
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+
+        match action {
+            "list" => {
+                let include_resolved = input
+                    .get("include_resolved")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                match call_blocking(self.db.clone(), move |d| {
+                    d.list_incidents(include_resolved, 30)
+                })
+                .await
+                {
+                    Ok(list) => ToolResult::success(format_incidents(&list)),
+                    Err(e) => ToolResult::error(format!("Failed to list incidents: {e}")),
+                }
+            }
+            "raise" => {
+                let Some(title) = title else {
+                    return ToolResult::error("Missing 'title' for raise".into());
+                };
+                let key = input
+                    .get("key")
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty())
+                    .unwrap_or(title);
+                let details = input.get("details").and_then(|v| v.as_str());
+                match raise(
+                    &self.bot,
+                    self.db.clone(),
+                    &self.config,
+                    "agent",
+                    key,
+                    title,
+                    details,
+                )
+                .await
+                {
+                    Ok(r) if r.new => ToolResult::success(format!(
+                        "Incident #{} opened; the incident chat is being paged until someone acknowledges it.",
+                        r.id
+                    )),
+                    Ok(r) => ToolResult::success(format!(
+                        "Incident #{} is already open; logged this as an update.",
+                        r.id
+                    )),
+                    Err(e) => ToolResult::error(e),
+                }
+            }
+            "update" => {
+                let (Some(id), Some(text)) = (id, title) else {
+                    return ToolResult::error("update needs 'id' and 'title' (the update)".into());
+                };
+                let incident =
+                    match call_blocking(self.db.clone(), move |d| d.get_incident(id)).await {
+                        Ok(Some(i)) => i,
+                        Ok(None) => return ToolResult::error(format!("Incident #{id} not found")),
+                        Err(e) => return ToolResult::error(e.to_string()),
+                    };
+                if incident.status == "resolved" {
+                    return ToolResult::error(format!("Incident #{id} is already resolved"));
+                }
+                match raise(
+                    &self.bot,
+                    self.db.clone(),
+                    &self.config,
+                    "agent",
+                    &incident.key,
+                    text,
+                    None,
+                )
+                .await
+                {
+                    Ok(r) => ToolResult::success(format!("Logged update on incident #{}.", r.id)),
+                    Err(e) => ToolResult::error(e),
+                }
+            }
+            "ack" | "resolve" => {
+                let Some(id) = id else {
+                    return ToolResult::error(format!("{action} needs 'id'"));
+                };
+                if auth.as_ref().is_some_and(|a| {
+                    !a.is_control_chat() && Some(a.caller_chat_id) != incident_chat_id(&self.config)
+                }) {
+                    return ToolResult::error(
+                        "Permission denied: incidents can only be closed from a control chat or the incident chat"
+                            .into(),
+                    );
+                }
+                let status = if action == "ack" {
+                    "acknowledged"
+                } else {
+                    "resolved"
+                };
+                match set_status(self.db.clone(), id, status, &by).await {
+                    Ok(i) => {
+                        ToolResult::success(format!("Incident #{} {status}: {}", i.id, i.title))
+                    }
+                    Err(e) => ToolResult::error(e),
+                }
+            }
+            _ => {
+                ToolResult::error("Unknown action; use list, raise, update, ack or resolve".into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_raise_list_and_resolve() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_incident_tool_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.control_chat_ids = vec![100];
+        db.upsert_chat(100, Some("ops"), "web").unwrap();
+        let tool = IncidentTool::new(&config, Bot::new("tok"), db.clone());
+
+        let out = tool
+            .execute(json!({"action": "raise", "title": "Basement leak", "key": "leak"}))
+            .await;
+        assert!(!out.is_error, "{}", out.content);
+        assert!(out.content.contains("opened"));
+        let out = tool
+            .execute(json!({"action": "raise", "title": "Still wet", "key": "leak"}))
+            .await;
+        assert!(out.content.contains("already open"));
+        let id = db.find_active_incident("leak").unwrap().unwrap().id;
+
+        let out = tool
+            .execute(json!({"action": "resolve", "id": id, "__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [100]}}))
+            .await;
+        assert!(out.is_error);
+        let out = tool
+            .execute(json!({"action": "resolve", "id": id, "__microclaw_auth": {"caller_chat_id": 100, "control_chat_ids": [100]}}))
+            .await;
+        assert!(!out.is_error, "{}", out.content);
+        let out = tool.execute(json!({"action": "list"})).await;
+        assert_eq!(out.content, "No open incidents.");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,58 @@
+//! `index_vault`: re-index the vault into ChromaDB with the native indexer. See
+//! [`crate::vault_index`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::embeddings::Embedder;
+use crate::vault_index::index_vault;
+
+static INDEXING: AtomicBool = AtomicBool::new(false);
+
+pub struct IndexVaultTool {
+    config: Config,
+    embedder: Arc<Embedder>,
+}
+
+impl IndexVaultTool {
+    pub fn new(config: &Config, embedder: Arc<Embedder>) -> Self {
+        IndexVaultTool {
+            config: config.clone(),
+            embedder,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for IndexVaultTool {
+    fn name(&self) -> &str {
+        "index_vault"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "index_vault".into(),
+            description: "Re-index the ORIGIN vault for search_vault: chunks every note, embeds new or changed chunks (unchanged ones come from the embedding cache) and removes chunks of deleted notes. Run after editing or syncing the vault.".into(),
+            input_schema: schema_object(json!({}), &[]),
+            examples: vec![json!({})],
+        }
+    }
+
+    async fn execute(&self, _input: serde_json::Value) -> ToolResult {
+        if INDEXING.swap(true, Ordering::SeqCst) {
+            return ToolResult::error("The vault is already being indexed".into());
+        }
+        let result = index_vault(&self.config, &self.embedder).await;
+        INDEXING.store(false, Ordering::SeqCst);
+        match result {
+            Ok(report) => ToolResult::success(report.summary()),
+            Err(e) => ToolResult::error(format!("Vault indexing failed: {e}")),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! `list_jobs`, `job_status` and `cancel_job`: inspect and stop background jobs started by tools
+//! (e.g. `bash` with `background: true`). Jobs are scoped to the chat that started them; control
+//! chats see every chat's jobs.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, BackgroundJob, Database};
+use crate::jobs;
+use crate::text::tail_chars;
+
+const DEFAULT_LIST_LIMIT: usize = 20;
+const MAX_LIST_LIMIT: usize = 100;
+const STATUS_OUTPUT_CHARS: usize = 4000;
+
+fn render_job_line(job: &BackgroundJob) -> String {
+    format!(
+        "#{} [{}] {} (chat {}, started {}{})",
+        job.id,
+        job.status,
+        job.description,
+        job.chat_id,
+        job.created_at,
+        job.finished_at
+            .as_deref()
+            .map(|t| format!(", finished {t}"))
+            .unwrap_or_default()
+    )
+}
+
+fn render_job(job: &BackgroundJob) -> String {
+    let mut out = render_job_line(job);
+    out.push_str(&format!("\nKind: {}", job.kind));
+    if let Some(error) = &job.error {
+        out.push_str(&format!("\nError: {error}"));
+    }
+    if job.output.is_empty() {
+        out.push_str("\nNo output yet.");
+    } else {
+        out.push_str(&format!("\nOutput:\n{}", tail_chars(&job.output, STATUS_OUTPUT_CHARS)));
+    }
+    out
+}
+
+fn job_id(input: &serde_json::Value) -> Result<i64, ToolResult> {
+    input
+        .get("job_id")
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| ToolResult::error("Missing 'job_id' parameter".into()))
+}
+
+/// Load a job the caller may see.
+async fn load_job(
+    db: &Arc<Database>,
+    input: &serde_json::Value,
+    id: i64,
+) -> Result<BackgroundJob, ToolResult> {
+    let job = call_blocking(db.clone(), move |d| d.get_background_job(id))
+        .await
+        .map_err(|e| ToolResult::error(format!("Failed to load job: {e}")))?
+        .ok_or_else(|| ToolResult::error(format!("Job #{id} not found")))?;
+    authorize_chat_access(input, job.chat_id).map_err(ToolResult::error)?;
+    Ok(job)
+}
+
+pub struct ListJobsTool {
+    db: Arc<Database>,
+}
+
+impl ListJobsTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        ListJobsTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for ListJobsTool {
+    fn name(&self) -> &str {
+        "list_jobs"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_jobs".into(),
+            description: "List background jobs (long-running commands, downloads, indexing, transcodes) started from this chat, newest first, with their status. Control chats see all chats' jobs.".into(),
+            input_schema: schema_object(
+                json!({
+                    "status": {
+                        "type": "string",
+                        "enum": ["running", "succeeded", "failed", "cancelled"],
+                        "description": "Only show jobs with this status"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum jobs to list (default 20, max 100)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_filter = match auth_context_from_input(&input) {
+            Some(auth) if !auth.is_control_chat() => Some(auth.caller_chat_id),
+            _ => None,
+        };
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT);
+        let status = input
+            .get("status")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let jobs = match call_blocking(self.db.clone(), move |d| {
+            d.list_background_jobs(chat_filter, MAX_LIST_LIMIT)
+        })
+        .await
+        {
+            Ok(jobs) => jobs,
+            Err(e) => return ToolResult::error(format!("Failed to list jobs: {e}")),
+        };
+        let jobs: Vec<_> = jobs
+            .into_iter()
+            .filter(|j| status.as_deref().is_none_or(|s| j.status == s))
+            .take(limit)
+            .collect();
+        if jobs.is_empty() {
+            return ToolResult::success("No background jobs.".into());
+        }
+        let lines: Vec<String> = jobs.iter().map(render_job_line).collect();
+        ToolResult::success(format!("{} job(s):\n{}", jobs.len(), lines.join("\n")))
+    }
+}
+
+pub struct JobStatusTool {
+    db: Arc<Database>,
+}
+
+impl JobStatusTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        JobStatusTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for JobStatusTool {
+    fn name(&self) -> &str {
+        "job_status"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "job_status".into(),
+            description: "Show a background job's status, error and the latest part of its output (updated while it runs).".into(),
+            input_schema: schema_object(
+                json!({
+                    "job_id": {
+                        "type": "integer",
+                        "description": "Job id (from list_jobs or the tool that started it)"
+                    }
+                }),
+                &["job_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let id = match job_id(&input) {
+            Ok(id) => id,
+            Err(e) => return e,
+        };
+        match load_job(&self.db, &input, id).await {
+            Ok(job) => ToolResult::success(render_job(&job)),
+            Err(e) => e,
+        }
+    }
+}
+
+pub struct CancelJobTool {
+    db: Arc<Database>,
+}
+
+impl CancelJobTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        CancelJobTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for CancelJobTool {
+    fn name(&self) -> &str {
+        "cancel_job"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "cancel_job".into(),
+            description: "Stop a running background job. Its process is killed and the job is marked cancelled.".into(),
+            input_schema: schema_object(
+                json!({
+                    "job_id": {
+                        "type": "integer",
+                        "description": "Job id to cancel"
+                    }
+                }),
+                &["job_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let id = match job_id(&input) {
+            Ok(id) => id,
+            Err(e) => return e,
+        };
+        let job = match load_job(&self.db, &input, id).await {
+            Ok(job) => job,
+            Err(e) => return e,
+        };
+        if job.status != "running" {
+            return ToolResult::error(format!("Job #{id} is not running ({})", job.status));
+        }
+        match jobs::cancel(self.db.clone(), id).await {
+            Ok(true) => ToolResult::success(format!("Cancelled job #{id}: {}", job.description)),
+            Ok(false) => ToolResult::error(format!("Job #{id} already finished")),
+            Err(e) => ToolResult::error(format!("Failed to cancel job #{id}: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (Arc<Database>, std::path::PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_jobs_tool_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        (db, dir)
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_scoped_to_chat() {
+        let (db, dir) = test_db();
+        let mine = db
+            .create_background_job(5, 1, "bash", "bash: make")
+            .unwrap();
+        let theirs = db
+            .create_background_job(6, 1, "bash", "bash: rsync")
+            .unwrap();
+        db.append_background_job_output(mine, "building\n", 100)
+            .unwrap();
+        let auth = json!({"caller_chat_id": 5, "control_chat_ids": [100]});
+
+        let out = ListJobsTool::new(db.clone())
+            .execute(json!({"__microclaw_auth": auth}))
+            .await;
+        assert!(!out.is_error);
+        assert!(out.content.starts_with("1 job(s):\n#"));
+        assert!(out.content.contains("[running] bash: make (chat 5"));
+
+        let status = JobStatusTool::new(db.clone());
+        let out = status
+            .execute(json!({"job_id": mine, "__microclaw_auth": auth}))
+            .await;
+        assert!(out.content.ends_with("Output:\nbuilding\n"));
+        let out = status
+            .execute(json!({"job_id": theirs, "__microclaw_auth": auth}))
+            .await;
+        assert!(out.is_error);
+        assert!(out.content.contains("Permission denied"));
+
+        let cancel = CancelJobTool::new(db.clone());
+        let out = cancel
+            .execute(json!({"job_id": mine, "__microclaw_auth": auth}))
+            .await;
+        assert_eq!(out.content, format!("Cancelled job #{mine}: bash: make"));
+        let out = cancel
+            .execute(json!({"job_id": mine, "__microclaw_auth": auth}))
+            .await;
+        assert!(out.content.contains("is not running (cancelled)"));
+
+        let control = json!({"caller_chat_id": 100, "control_chat_ids": [100]});
+        let out = ListJobsTool::new(db.clone())
+            .execute(json!({"status": "running", "__microclaw_auth": control}))
+            .await;
+        assert!(out.content.contains("bash: rsync"));
+        assert!(!out.content.contains("bash: make"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -52,6 +52,7 @@ impl Tool for McpTool {
                 self.tool_info.server_name, self.tool_info.description
             ),
             input_schema: self.tool_info.input_schema.clone(),
+            examples: Vec::new(),
         }
     }
 
@@ -53,6 +53,7 @@ impl Tool for ReadMemoryTool {
                 }),
                 &["scope"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -151,6 +152,7 @@ impl Tool for WriteMemoryTool {
                 }),
                 &["scope", "content"],
             ),
+            examples: Vec::new(),
         }
     }
 
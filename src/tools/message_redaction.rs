@@ -0,0 +1,369 @@
+//! `delete_message` and `redact_message`: remove or mask stored messages (e.g. a pasted
+//! password). Changes reach the messages table, its FTS index (through the triggers) and the
+//! saved sessions, including soft-deleted ones, so the text is gone from future context too.
+//! The web UI uses the same functions for `/api/delete_message` and `/api/redact_message`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database, StoredMessage};
+use crate::telegram::{format_user_message, sanitize_xml};
+
+pub const REDACTED: &str = "[redacted]";
+pub const DELETED: &str = "[deleted]";
+/// Bot replies shorter than this are not scrubbed from sessions: matching such short text
+/// could mask unrelated content.
+const MIN_SCRUB_CHARS: usize = 20;
+
+type Replacements = Vec<(String, String)>;
+
+/// Replace every `(from, to)` pair in the string values of a session's messages JSON. Returns
+/// the new JSON when anything changed.
+fn scrub_json(json: &str, replacements: &[(String, String)]) -> Option<String> {
+    fn walk(value: &mut serde_json::Value, replacements: &[(String, String)]) -> bool {
+        match value {
+            serde_json::Value::String(s) => {
+                let mut changed = false;
+                for (from, to) in replacements {
+                    if s.contains(from.as_str()) {
+                        *s = s.replace(from.as_str(), to);
+                        changed = true;
+                    }
+                }
+                changed
+            }
+            serde_json::Value::Array(items) => items
+                .iter_mut()
+                .fold(false, |acc, v| walk(v, replacements) | acc),
+            serde_json::Value::Object(map) => map
+                .values_mut()
+                .fold(false, |acc, v| walk(v, replacements) | acc),
+            _ => false,
+        }
+    }
+    let mut value: serde_json::Value = serde_json::from_str(json).ok()?;
+    if walk(&mut value, replacements) {
+        serde_json::to_string(&value).ok()
+    } else {
+        None
+    }
+}
+
+/// How a whole stored message appears in sessions, paired with its masked form.
+fn message_replacements(message: &StoredMessage, marker: &str) -> Vec<(String, String)> {
+    if message.is_from_bot {
+        if message.content.chars().count() < MIN_SCRUB_CHARS {
+            return Vec::new();
+        }
+        vec![(message.content.clone(), marker.to_string())]
+    } else {
+        vec![(
+            format_user_message(&message.sender_name, &message.content),
+            format_user_message(&message.sender_name, marker),
+        )]
+    }
+}
+
+/// A text fragment as it appears in sessions (raw and XML-escaped), paired with `marker`.
+fn text_replacements(text: &str, marker: &str) -> Vec<(String, String)> {
+    let mut out = vec![(text.to_string(), marker.to_string())];
+    let escaped = sanitize_xml(text);
+    if escaped != text {
+        out.push((escaped, marker.to_string()));
+    }
+    out
+}
+
+/// Scrubs waiting for the chat's running turn, if any: the agent loop keeps the session in
+/// memory and would otherwise save the masked text back when the turn ends.
+fn pending_scrubs() -> &'static Mutex<HashMap<i64, Replacements>> {
+    static PENDING: OnceLock<Mutex<HashMap<i64, Replacements>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Apply (and clear) the scrubs recorded for `chat_id` to a session's messages JSON before it
+/// is saved.
+pub fn apply_pending_scrubs(chat_id: i64, json: String) -> String {
+    let pending = pending_scrubs().lock().unwrap().remove(&chat_id);
+    match pending {
+        Some(replacements) => scrub_json(&json, &replacements).unwrap_or(json),
+        None => json,
+    }
+}
+
+async fn scrub_sessions(db: &Arc<Database>, chat_id: i64, replacements: Vec<(String, String)>) {
+    if replacements.is_empty() {
+        return;
+    }
+    pending_scrubs()
+        .lock()
+        .unwrap()
+        .entry(chat_id)
+        .or_default()
+        .extend(replacements.iter().cloned());
+    let _ = call_blocking(db.clone(), move |d| {
+        d.rewrite_sessions(chat_id, |json| scrub_json(json, &replacements))
+    })
+    .await;
+}
+
+/// Delete stored message `message_id` of `chat_id` and blank it out of the chat's sessions.
+pub async fn delete_stored_message(
+    db: &Arc<Database>,
+    chat_id: i64,
+    message_id: &str,
+) -> Result<(), String> {
+    let id = message_id.to_string();
+    let message = call_blocking(db.clone(), move |d| d.get_message(chat_id, &id))
+        .await
+        .map_err(|e| format!("Failed to load message: {e}"))?
+        .ok_or_else(|| format!("Message {message_id} not found in chat {chat_id}"))?;
+    let id = message_id.to_string();
+    call_blocking(db.clone(), move |d| d.delete_message(chat_id, &id))
+        .await
+        .map_err(|e| format!("Failed to delete message: {e}"))?;
+    scrub_sessions(db, chat_id, message_replacements(&message, DELETED)).await;
+    info!("Deleted message {message_id} in chat {chat_id}");
+    Ok(())
+}
+
+/// Mask `text` (in `message_id` only, or in every message of the chat), or the whole of
+/// `message_id` when `text` is None. Sessions are scrubbed the same way. Returns how many
+/// stored messages changed.
+pub async fn redact_stored_messages(
+    db: &Arc<Database>,
+    chat_id: i64,
+    message_id: Option<&str>,
+    text: Option<&str>,
+) -> Result<usize, String> {
+    let replacements = match (message_id, text) {
+        (_, Some("")) => return Err("'text' must not be empty".into()),
+        (_, Some(text)) => text_replacements(text, REDACTED),
+        (Some(id), None) => {
+            let id = id.to_string();
+            let message = call_blocking(db.clone(), move |d| d.get_message(chat_id, &id))
+                .await
+                .map_err(|e| format!("Failed to load message: {e}"))?;
+            match message {
+                Some(m) => message_replacements(&m, REDACTED),
+                None => Vec::new(),
+            }
+        }
+        (None, None) => return Err("Give a message_id, a text to mask, or both".into()),
+    };
+    let (id, t) = (message_id.map(str::to_string), text.map(str::to_string));
+    let changed = call_blocking(db.clone(), move |d| {
+        d.redact_messages(chat_id, id.as_deref(), t.as_deref(), REDACTED)
+    })
+    .await
+    .map_err(|e| format!("Failed to redact messages: {e}"))?;
+    scrub_sessions(db, chat_id, replacements).await;
+    info!("Redacted {changed} message(s) in chat {chat_id}");
+    Ok(changed)
+}
+
+/// Target chat (default: the caller's), checked against the caller's access.
+fn target_chat(input: &serde_json::Value) -> Result<i64, String> {
+    let chat_id = input
+        .get("chat_id")
+        .and_then(|v| v.as_i64())
+        .or_else(|| auth_context_from_input(input).map(|a| a.caller_chat_id))
+        .ok_or_else(|| "Missing 'chat_id' parameter".to_string())?;
+    authorize_chat_access(input, chat_id)?;
+    Ok(chat_id)
+}
+
+pub struct DeleteMessageTool {
+    db: Arc<Database>,
+}
+
+impl DeleteMessageTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        DeleteMessageTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for DeleteMessageTool {
+    fn name(&self) -> &str {
+        "delete_message"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "delete_message".into(),
+            description: "Permanently delete one stored message from the chat history, its search index and the saved conversation context. Message ids come from search_history. Cannot be undone.".into(),
+            input_schema: schema_object(
+                json!({
+                    "message_id": {
+                        "type": "string",
+                        "description": "Id of the stored message"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat ID (default: current chat)"
+                    }
+                }),
+                &["message_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match target_chat(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        let Some(message_id) = input.get("message_id").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing 'message_id' parameter".into());
+        };
+        match delete_stored_message(&self.db, chat_id, message_id).await {
+            Ok(()) => ToolResult::success(format!("Deleted message {message_id}.")),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+pub struct RedactMessageTool {
+    db: Arc<Database>,
+}
+
+impl RedactMessageTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        RedactMessageTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for RedactMessageTool {
+    fn name(&self) -> &str {
+        "redact_message"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "redact_message".into(),
+            description: "Mask sensitive text (e.g. an accidentally pasted password) in the stored chat history, its search index and the saved conversation context, replacing it with [redacted]. Give text to mask it everywhere in the chat (or only in message_id), or just message_id to mask that whole message. Cannot be undone.".into(),
+            input_schema: schema_object(
+                json!({
+                    "text": {
+                        "type": "string",
+                        "description": "Exact text to mask"
+                    },
+                    "message_id": {
+                        "type": "string",
+                        "description": "Limit to this stored message (ids from search_history)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat ID (default: current chat)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match target_chat(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        let message_id = input.get("message_id").and_then(|v| v.as_str());
+        let text = input.get("text").and_then(|v| v.as_str());
+        match redact_stored_messages(&self.db, chat_id, message_id, text).await {
+            Ok(0) => ToolResult::error("No stored messages matched".into()),
+            Ok(n) => ToolResult::success(format!(
+                "Redacted {n} message{}.",
+                if n == 1 { "" } else { "s" }
+            )),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(db: &Database, id: &str, pid: i64, sender: &str, content: &str, from_bot: bool) {
+        db.store_message(&StoredMessage {
+            id: id.into(),
+            chat_id: 1,
+            persona_id: pid,
+            sender_name: sender.into(),
+            content: content.into(),
+            is_from_bot: from_bot,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_redact() {
+        let dir = std::env::temp_dir().join(format!("microclaw_redact_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(1, None, "private").unwrap();
+        let pid = db.get_or_create_default_persona(1).unwrap();
+        store(
+            &db,
+            "m1",
+            pid,
+            "alice",
+            "my password is hunter2 & more",
+            false,
+        );
+        store(&db, "m2", pid, "alice", "call me <later>", false);
+        let session = json!([
+            {"role": "user", "content": format_user_message("alice", "my password is hunter2 & more")},
+            {"role": "user", "content": [{"type": "text", "text": format_user_message("alice", "call me <later>")}]}
+        ]);
+        db.save_session(1, pid, &session.to_string()).unwrap();
+        let auth = json!({"caller_chat_id": 1, "control_chat_ids": []});
+
+        let out = RedactMessageTool::new(db.clone())
+            .execute(json!({"text": "hunter2", "__microclaw_auth": auth}))
+            .await;
+        assert_eq!(out.content, "Redacted 1 message.");
+        assert_eq!(
+            db.get_message(1, "m1").unwrap().unwrap().content,
+            "my password is [redacted] & more"
+        );
+        assert!(db
+            .search_messages(1, pid, "hunter2", 10, None, None)
+            .unwrap()
+            .is_empty());
+        let (json, _) = db.load_session(1, pid).unwrap().unwrap();
+        assert!(!json.contains("hunter2") && json.contains("[redacted]"));
+
+        let out = DeleteMessageTool::new(db.clone())
+            .execute(json!({"message_id": "m2", "__microclaw_auth": auth}))
+            .await;
+        assert_eq!(out.content, "Deleted message m2.");
+        assert!(db.get_message(1, "m2").unwrap().is_none());
+        let (json, _) = db.load_session(1, pid).unwrap().unwrap();
+        assert!(!json.contains("later") && json.contains(DELETED));
+        // A turn still holding the old text masks it when saving its session
+        let saved = apply_pending_scrubs(1, session.to_string());
+        assert!(!saved.contains("hunter2") && !saved.contains("later"));
+        assert_eq!(apply_pending_scrubs(1, "[]".into()), "[]");
+
+        let out = DeleteMessageTool::new(db.clone())
+            .execute(json!({"message_id": "m1", "chat_id": 2, "__microclaw_auth": auth}))
+            .await;
+        assert!(out.content.starts_with("Permission denied"));
+        let out = RedactMessageTool::new(db.clone())
+            .execute(json!({"__microclaw_auth": auth}))
+            .await;
+        assert!(out.is_error);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
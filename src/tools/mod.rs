@@ -1,27 +1,72 @@
 pub mod activate_skill;
+pub mod archive;
+pub mod artifacts;
 pub mod bash;
 pub mod browser;
+pub mod build_skill;
+pub mod business_inbox;
+pub mod calculate;
+pub mod calendar;
+pub mod catch_me_up;
+pub mod chat_access;
+pub mod chat_env;
 pub mod command_runner;
 pub mod cursor_agent;
+pub mod define_command;
+pub mod detached_runs;
 pub mod edit_file;
+pub mod experiments_report;
 pub mod export_chat;
+pub mod extract_document;
+pub mod feature_flags;
+pub mod feedback_report;
+pub mod find_conversations;
+pub mod forward_message;
 pub mod glob;
+pub mod google;
 pub mod grep;
+pub mod home_assistant;
+pub mod incident;
+pub mod index_vault;
+pub mod jobs;
 pub mod mcp;
 pub mod memory;
+pub mod message_redaction;
 pub mod path_guard;
+pub mod poll;
+pub mod projects;
+pub mod python;
+pub mod quarantine;
+pub mod query_csv;
+pub mod react;
 pub mod read_file;
+pub mod render_pdf;
+pub mod roundtable;
 pub mod schedule;
+pub mod schema_validation;
 pub mod search_history;
 pub mod search_vault;
 pub mod send_message;
+pub mod show_last_thinking;
+pub mod session_snapshot;
+pub mod skill_versions;
+pub mod slow_report;
+pub mod social_connections;
 pub mod social_feed;
+pub mod social_report;
+pub mod spotify;
 pub mod sub_agent;
 pub mod sync_skills;
 pub mod tiered_memory;
+pub mod tmux_sessions;
+pub mod todos;
+pub mod tool_help;
+pub mod translate;
 pub mod web_fetch;
 pub mod web_html;
 pub mod web_search;
+pub mod who_is_home;
+pub mod workflows;
 pub mod write_file;
 
 use std::collections::HashMap;
@@ -35,6 +80,7 @@ use teloxide::prelude::*;
 use crate::claude::ToolDefinition;
 use crate::config::Config;
 use crate::db::Database;
+use crate::egress::EgressPolicy;
 
 pub struct ToolResult {
     pub content: String,
@@ -100,17 +146,39 @@ impl ToolRisk {
 
 pub fn tool_risk(name: &str) -> ToolRisk {
     match name {
-        "bash" | "cursor_agent" => ToolRisk::High,
+        "bash" | "cursor_agent" | "build_skill" | "python" => ToolRisk::High,
         "write_file"
         | "edit_file"
         | "write_memory"
         | "write_tiered_memory"
         | "send_message"
+        | "forward_message"
         | "sync_skills"
+        | "rollback_skill"
+        | "disconnect_platform"
+        | "translation_glossary"
+        | "define_command"
+        | "create_poll"
+        | "quarantine"
+        | "restore_session"
         | "schedule_task"
         | "pause_scheduled_task"
         | "resume_scheduled_task"
-        | "cancel_scheduled_task" => ToolRisk::Medium,
+        | "run_scheduled_task_now"
+        | "cancel_scheduled_task"
+        | "cancel_job"
+        | "set_chat_env"
+        | "create_project"
+        | "allow_chat"
+        | "block_chat"
+        | "feature_flags"
+        | "incident"
+        | "who_is_home"
+        | "home_assistant"
+        | "index_vault"
+        | "create_event"
+        | "delete_message"
+        | "redact_message" => ToolRisk::Medium,
         _ => ToolRisk::Low,
     }
 }
@@ -267,6 +335,27 @@ pub fn resolve_tool_path(working_dir: &Path, path: &str) -> PathBuf {
     }
 }
 
+type ProgressFn = Box<dyn Fn(String) + Send + Sync>;
+
+tokio::task_local! {
+    static TOOL_PROGRESS: ProgressFn;
+}
+
+/// Run a tool future with `report` receiving the progress lines it emits via [`report_progress`].
+pub async fn with_progress<F: std::future::Future>(
+    report: impl Fn(String) + Send + Sync + 'static,
+    fut: F,
+) -> F::Output {
+    TOOL_PROGRESS.scope(Box::new(report), fut).await
+}
+
+/// Report a progress line for the running tool (e.g. a step of a long cursor-agent run).
+/// A no-op outside [`with_progress`].
+pub fn report_progress(message: impl Into<String>) {
+    let message = message.into();
+    let _ = TOOL_PROGRESS.try_with(|report| report(message));
+}
+
 /// Resolve the tool working directory. Always uses the shared workspace (base/shared).
 pub fn resolve_tool_working_dir(base_working_dir: &Path) -> PathBuf {
     let resolved = base_working_dir.join("shared");
@@ -288,23 +377,41 @@ impl ToolRegistry {
         let workspace_root = config.workspace_root_absolute();
         let primary_skills = workspace_root.join("skills");
         let shared_skills = workspace_root.join("shared").join("skills");
+        let job_runner = crate::jobs::JobRunner::new(config, bot.clone(), db.clone());
         let tools: Vec<Box<dyn Tool>> = vec![
-            Box::new(bash::BashTool::new(config.working_dir())),
-            Box::new(browser::BrowserTool::new(
-                &config.runtime_data_dir(),
-                config.agent_browser_path.clone(),
-            )),
+            Box::new(
+                bash::BashTool::new(config.working_dir())
+                    .with_jobs(job_runner)
+                    .with_db(db.clone()),
+            ),
+            Box::new(
+                browser::BrowserTool::new(
+                    &config.runtime_data_dir(),
+                    config.agent_browser_path.clone(),
+                    EgressPolicy::from_config(config),
+                )
+                .with_saved_profiles(db.clone(), config.browser_profiles.clone())
+                .with_downloads(
+                    config.working_dir(),
+                    config.browser_downloads.clone().unwrap_or_default(),
+                ),
+            ),
             Box::new(read_file::ReadFileTool::new(config.working_dir())),
+            Box::new(extract_document::ExtractDocumentTool::new(config)),
+            Box::new(archive::ZipTool::new(config.working_dir())),
+            Box::new(archive::UnzipTool::new(config.working_dir())),
+            Box::new(query_csv::QueryCsvTool::new(config.working_dir())),
             Box::new(write_file::WriteFileTool::new(config.working_dir())),
             Box::new(edit_file::EditFileTool::new(config.working_dir())),
             Box::new(glob::GlobTool::new(config.working_dir())),
             Box::new(grep::GrepTool::new(config.working_dir())),
             Box::new(memory::ReadMemoryTool::new(&config.runtime_data_dir(), config.working_dir())),
             Box::new(memory::WriteMemoryTool::new(&config.runtime_data_dir(), config.working_dir())),
-            Box::new(web_fetch::WebFetchTool),
+            Box::new(web_fetch::WebFetchTool::new(EgressPolicy::from_config(config))),
             Box::new(web_search::WebSearchTool),
+            Box::new(calculate::CalculateTool::new(&config.runtime_data_dir())),
             Box::new(send_message::SendMessageTool::new_with_config(
-                bot,
+                bot.clone(),
                 db.clone(),
                 config.bot_username.clone(),
                 config.clone(),
@@ -316,24 +423,90 @@ impl ToolRegistry {
             Box::new(schedule::ListTasksTool::new(db.clone())),
             Box::new(schedule::PauseTaskTool::new(db.clone())),
             Box::new(schedule::ResumeTaskTool::new(db.clone())),
+            Box::new(schedule::RunTaskNowTool::new(db.clone())),
             Box::new(schedule::CancelTaskTool::new(db.clone())),
             Box::new(schedule::GetTaskHistoryTool::new(db.clone())),
             Box::new(export_chat::ExportChatTool::new(db.clone(), &config.runtime_data_dir())),
+            Box::new(render_pdf::RenderPdfTool::new(config, bot.clone(), db.clone())),
             Box::new(sub_agent::SubAgentTool::new(config, db.clone())),
-            Box::new(cursor_agent::CursorAgentTool::new(config, db.clone())),
+            Box::new(roundtable::RoundtableTool::new(config)),
+            Box::new(
+                cursor_agent::CursorAgentTool::new(config, db.clone()).with_review(bot.clone()),
+            ),
             Box::new(cursor_agent::ListCursorAgentRunsTool::new(db.clone())),
-            Box::new(activate_skill::ActivateSkillTool::new_with_dirs([
-                &primary_skills,
-                &shared_skills,
-            ])),
-            Box::new(sync_skills::SyncSkillsTool::new(&skills_data_dir)),
+            Box::new(tmux_sessions::ListTmuxSessionsTool),
+            Box::new(detached_runs::DetachedRunsTool::new(config)),
+            Box::new(chat_env::SetChatEnvTool::new(db.clone())),
+            Box::new(chat_access::AllowChatTool::new(db.clone())),
+            Box::new(chat_access::BlockChatTool::new(db.clone())),
+            Box::new(chat_access::ListAllowedChatsTool::new(config, db.clone())),
+            Box::new(feature_flags::FeatureFlagsTool::new(config, db.clone())),
+            Box::new(incident::IncidentTool::new(config, bot.clone(), db.clone())),
+            Box::new(who_is_home::WhoIsHomeTool::new(
+                config,
+                bot.clone(),
+                db.clone(),
+            )),
+            Box::new(python::PythonTool::new(config, db.clone())),
+            Box::new(projects::CreateProjectTool::new(config.working_dir(), db.clone())),
+            Box::new(projects::ListProjectsTool::new(db.clone())),
+            Box::new(jobs::ListJobsTool::new(db.clone())),
+            Box::new(jobs::JobStatusTool::new(db.clone())),
+            Box::new(jobs::CancelJobTool::new(db.clone())),
+            Box::new(build_skill::BuildSkillTool::new(config, db.clone())),
+            Box::new(
+                activate_skill::ActivateSkillTool::new_with_dirs([&primary_skills, &shared_skills])
+                    .with_db(db.clone()),
+            ),
+            Box::new(sync_skills::SyncSkillsTool::new(&skills_data_dir).with_db(db.clone())),
+            Box::new(workflows::StartWorkflowTool),
+            Box::new(tool_help::ToolHelpTool),
+            Box::new(skill_versions::RollbackSkillTool::new(&skills_data_dir, db.clone())),
             Box::new(tiered_memory::ReadTieredMemoryTool::new(&config.runtime_data_dir())),
             Box::new(tiered_memory::WriteTieredMemoryTool::new(&config.runtime_data_dir())),
+            Box::new(session_snapshot::SnapshotSessionTool::new(&config.runtime_data_dir(), db.clone())),
+            Box::new(session_snapshot::RestoreSessionTool::new(&config.runtime_data_dir(), db.clone())),
             Box::new(search_history::SearchHistoryTool::new(db.clone())),
+            Box::new(message_redaction::DeleteMessageTool::new(db.clone())),
+            Box::new(message_redaction::RedactMessageTool::new(db.clone())),
+            Box::new(find_conversations::FindConversationsTool::new(db.clone())),
+            Box::new(catch_me_up::CatchMeUpTool::new(db.clone())),
+            Box::new(feedback_report::FeedbackReportTool::new(db.clone())),
+            Box::new(slow_report::SlowReportTool::new(db.clone())),
+            Box::new(show_last_thinking::ShowLastThinkingTool::new(db.clone())),
+            Box::new(artifacts::SaveArtifactTool::new(db.clone())),
+            Box::new(artifacts::ListArtifactsTool::new(db.clone())),
+            Box::new(todos::TodoAddTool::new(db.clone())),
+            Box::new(todos::TodoListTool::new(db.clone())),
+            Box::new(todos::TodoCompleteTool::new(db.clone())),
+            Box::new(translate::TranslateTool::new(config, db.clone())),
+            Box::new(translate::TranslationGlossaryTool::new(db.clone())),
+            Box::new(define_command::DefineCommandTool::new(bot.clone(), db.clone())),
+            Box::new(react::ReactTool::new(config, bot.clone(), db.clone())),
+            Box::new(poll::CreatePollTool::new(config, bot.clone(), db.clone())),
+            Box::new(poll::PollTool::new(db.clone())),
+            Box::new(forward_message::ForwardMessageTool::new(config, bot.clone(), db.clone())),
         ];
 
         let mut tools: Vec<Box<dyn Tool>> = tools;
 
+        if config.inbound_moderation.is_some() {
+            tools.push(Box::new(quarantine::QuarantineTool::new(db.clone())));
+        }
+
+        if !config.experiments.is_empty() {
+            tools.push(Box::new(experiments_report::ExperimentsReportTool::new(db.clone())));
+        }
+
+        // Offered instead of web_search in chats with the provider_search flag
+        if crate::llm::supports_provider_search(config) {
+            tools.push(Box::new(web_search::ProviderWebSearchTool::new(config)));
+        }
+
+        if let Some(pack) = config.sticker_pack_path.as_deref().filter(|p| !p.trim().is_empty()) {
+            tools.push(Box::new(react::SendStickerTool::new(config, bot, db.clone(), pack)));
+        }
+
         // Register SearchVaultTool: native mode (embedding + ChromaDB HTTP) or command mode (vault_search_command)
         if let Some(ref vault) = config.vault {
             let use_native = vault.embedding_server_url.is_some() && vault.vector_db_url.is_some();
@@ -342,18 +515,25 @@ impl ToolRegistry {
                 .as_ref()
                 .map_or(false, |c| !c.trim().is_empty());
 
-            if use_native {
-                let embed_url = vault.embedding_server_url.as_ref().unwrap();
+            let embedder = if use_native {
+                crate::embeddings::Embedder::from_config(config).map(Arc::new)
+            } else {
+                None
+            };
+
+            if let Some(embedder) = embedder {
                 let db_url = vault.vector_db_url.as_ref().unwrap();
                 let collection = vault
                     .vector_db_collection
                     .as_deref()
                     .unwrap_or("vault");
-                tools.push(Box::new(search_vault::SearchVaultTool::new_native(
-                    embed_url,
-                    db_url,
-                    collection,
-                )));
+                tools.push(Box::new(
+                    search_vault::SearchVaultTool::new_native(embedder.clone(), db_url, collection)
+                        .with_vault_root(crate::vault_index::vault_root(config)),
+                ));
+                if vault.origin_vault_path.is_some() {
+                    tools.push(Box::new(index_vault::IndexVaultTool::new(config, embedder)));
+                }
                 tracing::info!(
                     "search_vault tool registered (native: collection={}, db={})",
                     collection,
@@ -372,8 +552,25 @@ impl ToolRegistry {
             }
         }
 
+        if let Some(backend) = calendar::CalendarBackend::from_config(config) {
+            let backend = Arc::new(backend);
+            tools.push(Box::new(calendar::ListEventsTool::new(config, db.clone(), backend.clone())));
+            tools.push(Box::new(calendar::CreateEventTool::new(config, db.clone(), backend)));
+        }
+
+        if let Some(ref ha) = config.home_assistant {
+            tools.push(Box::new(home_assistant::HomeAssistantTool::new(ha)));
+        }
+
+        if config.telegram_business.is_some() {
+            tools.push(Box::new(business_inbox::BusinessInboxTool::new(db.clone())));
+        }
+
         let mut social_added = Vec::new();
         if let Some(ref social) = config.social {
+            tools.push(Box::new(social_connections::ListConnectionsTool::new(config, db.clone())));
+            tools.push(Box::new(social_connections::DisconnectPlatformTool::new(config, db.clone())));
+            social_added.extend(["list_connections", "disconnect_platform"]);
             if social.is_platform_enabled("tiktok") {
                 tools.push(Box::new(social_feed::FetchTiktokFeedTool::new(config, db.clone())));
                 social_added.push("fetch_tiktok_feed");
@@ -383,9 +580,26 @@ impl ToolRegistry {
                 social_added.push("fetch_instagram_feed");
             }
             if social.is_platform_enabled("linkedin") {
-                tools.push(Box::new(social_feed::FetchLinkedinFeedTool::new(config, db)));
+                tools.push(Box::new(social_feed::FetchLinkedinFeedTool::new(config, db.clone())));
                 social_added.push("fetch_linkedin_feed");
             }
+            if social.is_platform_enabled("google") {
+                tools.push(Box::new(google::SearchGmailTool::new(config, db.clone())));
+                tools.push(Box::new(google::ReadEmailTool::new(config, db.clone())));
+                tools.push(Box::new(google::SearchDriveTool::new(config, db.clone())));
+                tools.push(Box::new(google::FetchDriveFileTool::new(config, db.clone())));
+                social_added.extend(["search_gmail", "read_email", "search_drive", "fetch_drive_file"]);
+            }
+            if social.is_platform_enabled("spotify") {
+                tools.push(Box::new(spotify::PlayMusicTool::new(config, db.clone())));
+                tools.push(Box::new(spotify::PauseMusicTool::new(config, db.clone())));
+                tools.push(Box::new(spotify::NowPlayingTool::new(config, db.clone())));
+                social_added.extend(["play_music", "pause", "now_playing"]);
+            }
+            if social.monitor_interval_mins > 0 {
+                tools.push(Box::new(social_report::SocialReportTool::new(config, db)));
+                social_added.push("social_report");
+            }
         }
         if !social_added.is_empty() {
             tracing::info!("Social feed tools registered: {}", social_added.join(", "));
@@ -407,25 +621,38 @@ impl ToolRegistry {
         let workspace_root = config.workspace_root_absolute();
         let primary_skills = workspace_root.join("skills");
         let shared_skills = workspace_root.join("shared").join("skills");
+        let mut browser = browser::BrowserTool::new(
+            &config.runtime_data_dir(),
+            config.agent_browser_path.clone(),
+            EgressPolicy::from_config(config),
+        )
+        .with_downloads(
+            config.working_dir(),
+            config.browser_downloads.clone().unwrap_or_default(),
+        );
+        let mut activate_skill =
+            activate_skill::ActivateSkillTool::new_with_dirs([&primary_skills, &shared_skills]);
+        if let Some(db) = &db {
+            browser = browser.with_saved_profiles(db.clone(), config.browser_profiles.clone());
+            activate_skill = activate_skill.with_db(db.clone());
+        }
         let mut tools: Vec<Box<dyn Tool>> = vec![
             Box::new(bash::BashTool::new(config.working_dir())),
-            Box::new(browser::BrowserTool::new(
-                &config.runtime_data_dir(),
-                config.agent_browser_path.clone(),
-            )),
+            Box::new(browser),
             Box::new(read_file::ReadFileTool::new(config.working_dir())),
+            Box::new(extract_document::ExtractDocumentTool::new(config)),
+            Box::new(query_csv::QueryCsvTool::new(config.working_dir())),
             Box::new(write_file::WriteFileTool::new(config.working_dir())),
             Box::new(edit_file::EditFileTool::new(config.working_dir())),
             Box::new(glob::GlobTool::new(config.working_dir())),
             Box::new(grep::GrepTool::new(config.working_dir())),
             Box::new(memory::ReadMemoryTool::new(&config.runtime_data_dir(), config.working_dir())),
             Box::new(tiered_memory::ReadTieredMemoryTool::new(&config.runtime_data_dir())),
-            Box::new(web_fetch::WebFetchTool),
+            Box::new(web_fetch::WebFetchTool::new(EgressPolicy::from_config(config))),
             Box::new(web_search::WebSearchTool),
-            Box::new(activate_skill::ActivateSkillTool::new_with_dirs([
-                &primary_skills,
-                &shared_skills,
-            ])),
+            Box::new(calculate::CalculateTool::new(&config.runtime_data_dir())),
+            Box::new(activate_skill),
+            Box::new(tool_help::ToolHelpTool),
         ];
         if let Some(db) = db {
             tools.push(Box::new(search_history::SearchHistoryTool::new(db)));
@@ -441,11 +668,36 @@ impl ToolRegistry {
         self.tools.iter().map(|t| t.definition()).collect()
     }
 
+    /// Reject input that does not match the tool's schema before it runs.
+    fn invalid_input(&self, name: &str, input: &serde_json::Value) -> Option<ToolResult> {
+        let tool = self.tools.iter().find(|t| t.name() == name)?;
+        let issues = schema_validation::validate(&tool.definition().input_schema, input);
+        if issues.is_empty() {
+            return None;
+        }
+        Some(
+            ToolResult::error(schema_validation::error_message(name, &issues))
+                .with_error_type("invalid_input"),
+        )
+    }
+
     pub async fn execute(&self, name: &str, input: serde_json::Value) -> ToolResult {
+        if let Some(invalid) = self.invalid_input(name, &input) {
+            return invalid;
+        }
+        self.run(name, input).await
+    }
+
+    async fn run(&self, name: &str, input: serde_json::Value) -> ToolResult {
         for tool in &self.tools {
             if tool.name() == name {
                 let started = Instant::now();
-                let mut result = tool.execute(input).await;
+                // tool_help needs every registered definition, which only the registry has
+                let mut result = if name == tool_help::NAME {
+                    tool_help::render_help(&self.definitions(), &input)
+                } else {
+                    tool.execute(input).await
+                };
                 result.duration_ms = Some(started.elapsed().as_millis());
                 result.bytes = result.content.len();
                 if result.is_error && result.error_type.is_none() {
@@ -466,6 +718,10 @@ impl ToolRegistry {
         input: serde_json::Value,
         auth: &ToolAuthContext,
     ) -> ToolResult {
+        // Validate before asking for approval, so an approved call cannot fail on its arguments
+        if let Some(invalid) = self.invalid_input(name, &input) {
+            return invalid;
+        }
         if requires_high_risk_approval(name, auth) {
             let provided = approval_token_from_input(&input);
             let key = approval_key(auth, name);
@@ -502,7 +758,7 @@ impl ToolRegistry {
         }
 
         let input = inject_auth_context(input, auth);
-        self.execute(name, input).await
+        self.run(name, input).await
     }
 }
 
@@ -607,6 +863,7 @@ mod tests {
                 name: self.tool_name.clone(),
                 description: "dummy".into(),
                 input_schema: schema_object(json!({}), &[]),
+                examples: Vec::new(),
             }
         }
 
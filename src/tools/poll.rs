@@ -0,0 +1,421 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use teloxide::prelude::*;
+use teloxide::types::InputPollOption;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::channel::enforce_channel_policy;
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database, Poll};
+use crate::polls::{
+    discord_poll_payload, format_poll_message, format_results, post_discord_message, post_text,
+    record_vote, MAX_POLL_OPTIONS,
+};
+
+fn chat_id_from_input(input: &serde_json::Value) -> Result<i64, String> {
+    input
+        .get("chat_id")
+        .and_then(|v| v.as_i64())
+        .or_else(|| auth_context_from_input(input).map(|a| a.caller_chat_id))
+        .ok_or_else(|| "Missing required parameter: chat_id".to_string())
+}
+
+pub struct CreatePollTool {
+    bot: Bot,
+    db: Arc<Database>,
+    config: Config,
+    http_client: reqwest::Client,
+}
+
+impl CreatePollTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        CreatePollTool {
+            bot,
+            db,
+            config: config.clone(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Post the poll to its chat; fills in message/poll ids for native polls.
+    async fn post(&self, poll: &mut Poll) -> Result<(), String> {
+        let chat_id = poll.chat_id;
+        let chat_type = call_blocking(self.db.clone(), move |d| d.get_chat_type(chat_id))
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        match chat_type.as_str() {
+            "discord" => {
+                let token = self
+                    .config
+                    .discord_bot_token
+                    .as_deref()
+                    .filter(|t| !t.trim().is_empty())
+                    .ok_or_else(|| "discord_bot_token not configured".to_string())?;
+                let sent = post_discord_message(
+                    &self.http_client,
+                    token,
+                    chat_id,
+                    discord_poll_payload(poll),
+                )
+                .await?;
+                poll.message_id = sent.get("id").and_then(|v| v.as_str()).map(String::from);
+                Ok(())
+            }
+            "web" | "whatsapp" => {
+                let persona_id =
+                    call_blocking(self.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                        .await
+                        .map_err(|e| e.to_string())?;
+                post_text(
+                    &self.bot,
+                    self.db.clone(),
+                    &self.config,
+                    chat_id,
+                    persona_id,
+                    &format_poll_message(poll),
+                )
+                .await
+            }
+            _ => {
+                let options = poll.options.iter().map(InputPollOption::new);
+                let sent = self
+                    .bot
+                    .send_poll(ChatId(chat_id), poll.question.clone(), options)
+                    .is_anonymous(false)
+                    .allows_multiple_answers(poll.multiple_answers)
+                    .await
+                    .map_err(|e| format!("Failed to send Telegram poll: {e}"))?;
+                poll.message_id = Some(sent.id.0.to_string());
+                poll.external_poll_id = sent.poll().map(|p| p.id.0.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CreatePollTool {
+    fn name(&self) -> &str {
+        "create_poll"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "create_poll".into(),
+            description: "Create a poll in a chat (native Telegram poll, Discord buttons, numbered list on web/WhatsApp). Votes are recorded; use the `poll` tool for results. With closes_in_minutes the poll closes automatically and results are posted; with on_close you will be asked to act on the results then (e.g. \"order whichever pizza wins\").".into(),
+            input_schema: schema_object(
+                json!({
+                    "question": {
+                        "type": "string",
+                        "description": "Poll question"
+                    },
+                    "options": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "2-10 answer options"
+                    },
+                    "multiple_answers": {
+                        "type": "boolean",
+                        "description": "Allow choosing several options (default false)"
+                    },
+                    "closes_in_minutes": {
+                        "type": "integer",
+                        "description": "Close automatically after this many minutes (default: stays open until closed with the poll tool)"
+                    },
+                    "on_close": {
+                        "type": "string",
+                        "description": "Instruction to carry out with the results when the poll closes"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Target chat (defaults to the current chat)"
+                    }
+                }),
+                &["question", "options"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let question = match input.get("question").and_then(|v| v.as_str()) {
+            Some(q) if !q.trim().is_empty() => q.trim().to_string(),
+            _ => return ToolResult::error("Missing required parameter: question".into()),
+        };
+        let options: Vec<String> = input
+            .get("options")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|o| o.as_str())
+                    .map(|o| o.trim().to_string())
+                    .filter(|o| !o.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if options.len() < 2 || options.len() > MAX_POLL_OPTIONS {
+            return ToolResult::error(format!("A poll needs 2-{MAX_POLL_OPTIONS} options"));
+        }
+        let chat_id = match chat_id_from_input(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        if let Err(e) = enforce_channel_policy(self.db.clone(), &input, chat_id).await {
+            return ToolResult::error(e);
+        }
+        let closes_at = input
+            .get("closes_in_minutes")
+            .and_then(|v| v.as_i64())
+            .filter(|m| *m > 0)
+            .map(|m| (chrono::Utc::now() + chrono::Duration::minutes(m)).to_rfc3339());
+
+        let mut poll = Poll {
+            id: uuid::Uuid::new_v4().to_string(),
+            chat_id,
+            question,
+            options,
+            multiple_answers: input
+                .get("multiple_answers")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            message_id: None,
+            external_poll_id: None,
+            closes_at,
+            on_close: input
+                .get("on_close")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+            status: "open".into(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        if let Err(e) = self.post(&mut poll).await {
+            return ToolResult::error(e);
+        }
+        let id = poll.id.clone();
+        let closes = poll.closes_at.clone();
+        if let Err(e) = call_blocking(self.db.clone(), move |d| d.create_poll(&poll)).await {
+            return ToolResult::error(format!("Poll was posted but could not be saved: {e}"));
+        }
+        ToolResult::success(match closes {
+            Some(at) => format!("Poll created (id {id}); closes at {at}."),
+            None => format!("Poll created (id {id})."),
+        })
+    }
+}
+
+pub struct PollTool {
+    db: Arc<Database>,
+}
+
+impl PollTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        PollTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for PollTool {
+    fn name(&self) -> &str {
+        "poll"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "poll".into(),
+            description: "Work with polls created by create_poll. Actions: results (current tally), vote (record a vote for a user — for web/WhatsApp polls where people reply with a number), close (close now; results are posted and any on_close action runs within a minute), list (polls in this chat). poll_id defaults to the latest open poll in the chat.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["results", "vote", "close", "list"],
+                        "description": "What to do"
+                    },
+                    "poll_id": {
+                        "type": "string",
+                        "description": "Poll id (defaults to the latest open poll)"
+                    },
+                    "voter": {
+                        "type": "string",
+                        "description": "For vote: sender name of the person voting"
+                    },
+                    "option": {
+                        "type": "integer",
+                        "description": "For vote: 1-based option number"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat of the poll (defaults to the current chat)"
+                    }
+                }),
+                &["action"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let action = input
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let chat_id = match chat_id_from_input(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+
+        if action == "list" {
+            return match call_blocking(self.db.clone(), move |d| d.list_polls(chat_id, false)).await
+            {
+                Ok(polls) if polls.is_empty() => {
+                    ToolResult::success("No polls in this chat.".into())
+                }
+                Ok(polls) => ToolResult::success(
+                    polls
+                        .iter()
+                        .take(20)
+                        .map(|p| format!("{} [{}] {}", p.id, p.status, p.question))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ),
+                Err(e) => ToolResult::error(format!("Failed to list polls: {e}")),
+            };
+        }
+
+        let requested = input
+            .get("poll_id")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let poll = match call_blocking(self.db.clone(), move |d| match requested {
+            Some(id) => d.get_poll(&id),
+            None => Ok(d.list_polls(chat_id, true)?.into_iter().next()),
+        })
+        .await
+        {
+            Ok(Some(p)) if p.chat_id == chat_id => p,
+            Ok(_) => return ToolResult::error("Poll not found in this chat".into()),
+            Err(e) => return ToolResult::error(format!("Failed to load poll: {e}")),
+        };
+
+        match action.as_str() {
+            "results" => {
+                let poll_id = poll.id.clone();
+                match call_blocking(self.db.clone(), move |d| d.get_poll_votes(&poll_id)).await {
+                    Ok(votes) => ToolResult::success(format!(
+                        "[{}] {}",
+                        poll.status,
+                        format_results(&poll, &votes)
+                    )),
+                    Err(e) => ToolResult::error(format!("Failed to load votes: {e}")),
+                }
+            }
+            "vote" => {
+                let Some(voter) = input
+                    .get("voter")
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                else {
+                    return ToolResult::error("Missing required parameter: voter".into());
+                };
+                let Some(option) = input.get("option").and_then(|v| v.as_u64()) else {
+                    return ToolResult::error("Missing required parameter: option".into());
+                };
+                if option == 0 {
+                    return ToolResult::error("option is 1-based".into());
+                }
+                match record_vote(self.db.clone(), &poll.id, voter, option as usize - 1).await {
+                    Ok(msg) => ToolResult::success(format!("{voter}: {msg}")),
+                    Err(e) => ToolResult::error(e),
+                }
+            }
+            "close" => {
+                let poll_id = poll.id.clone();
+                let now = chrono::Utc::now().to_rfc3339();
+                match call_blocking(self.db.clone(), move |d| {
+                    d.set_poll_closes_at(&poll_id, &now)
+                })
+                .await
+                {
+                    Ok(true) => ToolResult::success(
+                        "Poll will close within a minute; results will be posted to the chat."
+                            .into(),
+                    ),
+                    Ok(false) => ToolResult::error("Poll is already closed".into()),
+                    Err(e) => ToolResult::error(format!("Failed to close poll: {e}")),
+                }
+            }
+            other => ToolResult::error(format!(
+                "Unknown action '{other}'. Use results, vote, close, or list."
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_web_poll_create_vote_results() {
+        let dir = std::env::temp_dir().join(format!("microclaw_polltool_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(11, Some("web-main"), "web").unwrap();
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let create = CreatePollTool::new(&config, Bot::new("123456:TEST_TOKEN"), db.clone());
+        let result = create
+            .execute(json!({
+                "question": "Pizza tonight?",
+                "options": ["Margherita", "Pepperoni"],
+                "closes_in_minutes": 30,
+                "on_close": "order the winner",
+                "chat_id": 11
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+
+        let pid = db.get_current_persona_id(11).unwrap();
+        let messages = db.get_recent_messages(11, pid, 5).unwrap();
+        assert!(messages[0].content.contains("2. Pepperoni"));
+
+        let tool = PollTool::new(db.clone());
+        let vote = tool
+            .execute(json!({"action": "vote", "voter": "alice", "option": 2, "chat_id": 11}))
+            .await;
+        assert!(!vote.is_error, "{}", vote.content);
+        let results = tool
+            .execute(json!({"action": "results", "chat_id": 11}))
+            .await;
+        assert!(results.content.contains("Winner: Pepperoni"));
+
+        let close = tool
+            .execute(json!({"action": "close", "chat_id": 11}))
+            .await;
+        assert!(!close.is_error);
+        let due = db
+            .get_due_polls(&(chrono::Utc::now() + chrono::Duration::seconds(1)).to_rfc3339())
+            .unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].on_close.as_deref(), Some("order the winner"));
+
+        let too_few = create
+            .execute(json!({"question": "?", "options": ["only"], "chat_id": 11}))
+            .await;
+        assert!(too_few.is_error);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,315 @@
+//! `create_project` and `list_projects`: named project directories under the shared workspace.
+//! Registered projects can be referenced by name (e.g. `bash` with `project`) instead of by
+//! relative path.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database};
+
+const MAX_NAME_LEN: usize = 64;
+pub const TEMPLATES: &[&str] = &["rust", "python", "node", "plain"];
+
+/// Check a project name: lowercase letters, digits, '-' and '_', starting with a letter or digit.
+pub fn validate_project_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid project name '{name}' (lowercase letters, digits, '-' and '_')"
+        ))
+    }
+}
+
+/// Files (relative path, content) a template starts with.
+pub fn template_files(template: &str, name: &str) -> Vec<(&'static str, String)> {
+    let readme = ("README.md", format!("# {name}\n"));
+    match template {
+        "rust" => vec![
+            readme,
+            (
+                "Cargo.toml",
+                format!(
+                    "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n"
+                ),
+            ),
+            (
+                "src/main.rs",
+                "fn main() {\n    println!(\"Hello, world!\");\n}\n".into(),
+            ),
+            (".gitignore", "/target\n".into()),
+        ],
+        "python" => vec![
+            readme,
+            (
+                "pyproject.toml",
+                format!(
+                    "[project]\nname = \"{name}\"\nversion = \"0.1.0\"\nrequires-python = \">=3.9\"\ndependencies = []\n"
+                ),
+            ),
+            (
+                "main.py",
+                "def main():\n    print(\"Hello, world!\")\n\n\nif __name__ == \"__main__\":\n    main()\n"
+                    .into(),
+            ),
+            (".gitignore", "__pycache__/\n.venv/\n*.pyc\n".into()),
+        ],
+        "node" => vec![
+            readme,
+            (
+                "package.json",
+                format!(
+                    "{{\n  \"name\": \"{name}\",\n  \"version\": \"0.1.0\",\n  \"private\": true,\n  \"main\": \"index.js\",\n  \"scripts\": {{\n    \"start\": \"node index.js\"\n  }}\n}}\n"
+                ),
+            ),
+            ("index.js", "console.log(\"Hello, world!\");\n".into()),
+            (".gitignore", "node_modules/\n".into()),
+        ],
+        _ => vec![readme],
+    }
+}
+
+/// Directory of the registered project `name`.
+pub async fn resolve_project(db: &Arc<Database>, name: &str) -> Result<PathBuf, String> {
+    let key = name.trim().to_string();
+    let project = call_blocking(db.clone(), move |d| d.get_project(&key))
+        .await
+        .map_err(|e| format!("Failed to look up project: {e}"))?
+        .ok_or_else(|| format!("Unknown project '{}' (see list_projects)", name.trim()))?;
+    Ok(PathBuf::from(project.path))
+}
+
+async fn git_init(dir: &Path) -> Result<(), String> {
+    let output = tokio::process::Command::new("git")
+        .arg("init")
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+pub struct CreateProjectTool {
+    working_dir: PathBuf,
+    db: Arc<Database>,
+}
+
+impl CreateProjectTool {
+    pub fn new(working_dir: &str, db: Arc<Database>) -> Self {
+        CreateProjectTool {
+            working_dir: PathBuf::from(working_dir),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CreateProjectTool {
+    fn name(&self) -> &str {
+        "create_project"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "create_project".into(),
+            description: "Create a named project directory in the shared workspace from a template (rust, python, node or plain), run git init in it and register it, so later bash and cursor_agent calls can use it by name (project parameter).".into(),
+            input_schema: schema_object(
+                json!({
+                    "name": {
+                        "type": "string",
+                        "description": "Project name (lowercase letters, digits, '-' and '_'); also the directory name"
+                    },
+                    "template": {
+                        "type": "string",
+                        "enum": TEMPLATES,
+                        "description": "Starting files (default: plain)"
+                    }
+                }),
+                &["name"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let name = match input.get("name").and_then(|v| v.as_str()) {
+            Some(n) => n.trim(),
+            None => return ToolResult::error("Missing 'name' parameter".into()),
+        };
+        if let Err(e) = validate_project_name(name) {
+            return ToolResult::error(e);
+        }
+        let template = input
+            .get("template")
+            .and_then(|v| v.as_str())
+            .unwrap_or("plain");
+        if !TEMPLATES.contains(&template) {
+            return ToolResult::error(format!(
+                "Unknown template '{template}' (one of: {})",
+                TEMPLATES.join(", ")
+            ));
+        }
+
+        let dir = super::resolve_tool_working_dir(&self.working_dir).join(name);
+        if dir.exists() {
+            return ToolResult::error(format!("{} already exists", dir.display()));
+        }
+        for (rel, content) in template_files(template, name) {
+            let path = dir.join(rel);
+            if let Some(parent) = path.parent() {
+                if let Err(e) = tokio::fs::create_dir_all(parent).await {
+                    return ToolResult::error(format!(
+                        "Failed to create {}: {e}",
+                        parent.display()
+                    ));
+                }
+            }
+            if let Err(e) = tokio::fs::write(&path, content).await {
+                return ToolResult::error(format!("Failed to write {}: {e}", path.display()));
+            }
+        }
+
+        let chat_id = auth_context_from_input(&input)
+            .map(|a| a.caller_chat_id)
+            .unwrap_or(0);
+        let (n, path, t) = (
+            name.to_string(),
+            dir.to_string_lossy().to_string(),
+            template.to_string(),
+        );
+        match call_blocking(self.db.clone(), move |d| {
+            d.insert_project(&n, &path, &t, chat_id)
+        })
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = tokio::fs::remove_dir_all(&dir).await;
+                return ToolResult::error(format!("Project '{name}' is already registered"));
+            }
+            Err(e) => return ToolResult::error(format!("Failed to register project: {e}")),
+        }
+        info!("Created project {name} ({template}) at {}", dir.display());
+
+        let mut out = format!("Created {template} project '{name}' at {}", dir.display());
+        match git_init(&dir).await {
+            Ok(()) => out.push_str(" (git initialized)."),
+            Err(e) => {
+                warn!("git init in {} failed: {e}", dir.display());
+                out.push_str(&format!(". git init failed: {e}"));
+            }
+        }
+        ToolResult::success(out)
+    }
+}
+
+pub struct ListProjectsTool {
+    db: Arc<Database>,
+}
+
+impl ListProjectsTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        ListProjectsTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for ListProjectsTool {
+    fn name(&self) -> &str {
+        "list_projects"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_projects".into(),
+            description:
+                "List the projects registered with create_project: name, template and directory."
+                    .into(),
+            input_schema: schema_object(json!({}), &[]),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, _input: serde_json::Value) -> ToolResult {
+        match call_blocking(self.db.clone(), |d| d.list_projects()).await {
+            Ok(projects) if projects.is_empty() => {
+                ToolResult::success("No projects yet. Create one with create_project.".into())
+            }
+            Ok(projects) => {
+                let lines: Vec<String> = projects
+                    .iter()
+                    .map(|p| format!("- {} ({}) {}", p.name, p.template, p.path))
+                    .collect();
+                ToolResult::success(format!(
+                    "{} project(s):\n{}",
+                    projects.len(),
+                    lines.join("\n")
+                ))
+            }
+            Err(e) => ToolResult::error(format!("Failed to list projects: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_names_and_templates() {
+        assert!(validate_project_name("home-bot_2").is_ok());
+        assert!(validate_project_name("Home").is_err());
+        assert!(validate_project_name("-x").is_err());
+        assert!(validate_project_name("../x").is_err());
+        let files = template_files("rust", "demo");
+        assert!(files
+            .iter()
+            .any(|(p, c)| *p == "Cargo.toml" && c.contains("name = \"demo\"")));
+        assert_eq!(template_files("plain", "demo").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_resolve_project() {
+        let root =
+            std::env::temp_dir().join(format!("microclaw_projects_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(root.join("db").to_str().unwrap()).unwrap());
+        let tool = CreateProjectTool::new(root.to_str().unwrap(), db.clone());
+
+        let out = tool
+            .execute(json!({"name": "demo", "template": "python"}))
+            .await;
+        assert!(!out.is_error, "{}", out.content);
+        let dir = root.join("shared").join("demo");
+        assert!(dir.join("main.py").exists());
+        assert_eq!(resolve_project(&db, "demo").await.unwrap(), dir);
+        assert!(resolve_project(&db, "nope").await.is_err());
+
+        let out = tool.execute(json!({"name": "demo"})).await;
+        assert!(out.content.contains("already exists"));
+        let out = tool.execute(json!({"name": "x", "template": "go"})).await;
+        assert!(out.content.starts_with("Unknown template"));
+
+        let out = ListProjectsTool::new(db.clone()).execute(json!({})).await;
+        assert!(out.content.starts_with("1 project(s):\n- demo (python) "));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
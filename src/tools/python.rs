@@ -0,0 +1,361 @@
+//! `python`: run Python code in the calling chat's own virtualenv
+//! (`workspace_dir/shared/.python/chat_<id>`), so installed packages persist between calls
+//! while staying out of the system interpreter and other chats. Runs in the shared workspace
+//! directory with `-I` (no user site-packages, no `PYTHON*` variables) and, on Unix, under
+//! `ulimit` CPU-time and memory limits.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::{Config, PythonConfig};
+use crate::db::Database;
+
+const VENV_TIMEOUT_SECS: u64 = 180;
+const PIP_TIMEOUT_SECS: u64 = 600;
+const MAX_OUTPUT_CHARS: usize = 30000;
+
+pub struct PythonTool {
+    working_dir: PathBuf,
+    settings: PythonConfig,
+    db: Arc<Database>,
+}
+
+impl PythonTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        PythonTool {
+            working_dir: PathBuf::from(config.working_dir()),
+            settings: config.python.clone().unwrap_or_default(),
+            db,
+        }
+    }
+}
+
+/// Virtualenv of a chat (`default` outside a chat).
+pub fn venv_dir(shared_dir: &Path, chat_id: Option<i64>) -> PathBuf {
+    let name = match chat_id {
+        Some(id) => format!("chat_{id}"),
+        None => "default".to_string(),
+    };
+    shared_dir.join(".python").join(name)
+}
+
+fn venv_python(venv: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+/// A pip requirement like `pandas`, `requests==2.32.3` or `uvicorn[standard]>=0.30`; rejects
+/// options, paths and URLs.
+pub fn valid_package_spec(spec: &str) -> bool {
+    !spec.is_empty()
+        && spec.len() <= 100
+        && spec
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        && spec
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-[],<>=!~".contains(c))
+}
+
+/// `program args...`, wrapped in `ulimit` on Unix.
+fn limited_command(
+    program: &Path,
+    args: &[&str],
+    cpu_secs: u64,
+    memory_mb: u64,
+) -> tokio::process::Command {
+    if cfg!(target_os = "windows") {
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(args);
+        return cmd;
+    }
+    let mut limits = format!("ulimit -t {cpu_secs}");
+    if memory_mb > 0 {
+        // One limit per `ulimit`: dash rejects several
+        limits.push_str(&format!(" && ulimit -v {}", memory_mb * 1024));
+    }
+    let mut cmd = tokio::process::Command::new("/bin/sh");
+    cmd.arg("-c")
+        .arg(format!("{limits} && exec \"$0\" \"$@\""))
+        .arg(program)
+        .args(args);
+    cmd
+}
+
+/// Output of a finished process as the bash tool formats it.
+fn format_output(stdout: &[u8], stderr: &[u8]) -> String {
+    let stdout = String::from_utf8_lossy(stdout);
+    let stderr = String::from_utf8_lossy(stderr);
+    let mut text = stdout.into_owned();
+    if !stderr.is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str("STDERR:\n");
+        text.push_str(&stderr);
+    }
+    if text.chars().count() > MAX_OUTPUT_CHARS {
+        text = text.chars().take(MAX_OUTPUT_CHARS).collect();
+        text.push_str("\n... (output truncated)");
+    }
+    text
+}
+
+async fn run_with_timeout(
+    mut cmd: tokio::process::Command,
+    stdin: Option<&str>,
+    timeout_secs: u64,
+) -> Result<std::process::Output, String> {
+    cmd.stdin(if stdin.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start Python: {e}"))?;
+    if let (Some(code), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        // A write error means the process already exited; its stderr says why
+        let _ = pipe.write_all(code.as_bytes()).await;
+    }
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), child.wait_with_output()).await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(e)) => Err(format!("Failed to run Python: {e}")),
+        Err(_) => Err(format!("Timed out after {timeout_secs} seconds")),
+    }
+}
+
+impl PythonTool {
+    /// Create the chat's virtualenv on first use.
+    async fn ensure_venv(&self, venv: &Path) -> Result<PathBuf, String> {
+        let python = venv_python(venv);
+        if python.exists() {
+            return Ok(python);
+        }
+        if let Some(parent) = venv.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        info!("Creating Python virtualenv {}", venv.display());
+        let mut cmd = tokio::process::Command::new(&self.settings.interpreter);
+        cmd.arg("-m").arg("venv").arg(venv);
+        let output = run_with_timeout(cmd, None, VENV_TIMEOUT_SECS).await?;
+        if !output.status.success() || !python.exists() {
+            return Err(format!(
+                "Failed to create a virtualenv with {}: {}",
+                self.settings.interpreter,
+                format_output(&output.stdout, &output.stderr)
+            ));
+        }
+        Ok(python)
+    }
+
+    async fn install(&self, python: &Path, packages: &[String]) -> Result<String, String> {
+        let mut args = vec![
+            "-m",
+            "pip",
+            "install",
+            "--disable-pip-version-check",
+            "--quiet",
+        ];
+        args.extend(packages.iter().map(String::as_str));
+        let mut cmd = tokio::process::Command::new(python);
+        cmd.args(&args);
+        let output = run_with_timeout(cmd, None, PIP_TIMEOUT_SECS).await?;
+        let text = format_output(&output.stdout, &output.stderr);
+        if output.status.success() {
+            Ok(format!("Installed: {}", packages.join(", ")))
+        } else {
+            Err(format!("pip install failed:\n{text}"))
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for PythonTool {
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "python".into(),
+            description: "Run Python code and return its stdout/stderr (print what you need). Runs in this chat's own virtualenv, so `packages` installed once stay available, in the shared workspace directory (files written there persist). Limited in CPU time and memory; use bash for shell commands.".into(),
+            input_schema: schema_object(
+                json!({
+                    "code": {
+                        "type": "string",
+                        "description": "Python source to run"
+                    },
+                    "packages": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "pip packages to install into the chat's virtualenv before running, e.g. [\"pandas\", \"requests==2.32.3\"]"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Timeout in seconds (default: 60)"
+                    }
+                }),
+                &["code"],
+            ),
+            examples: vec![
+                json!({"code": "import math\nprint(math.factorial(30))"}),
+                json!({"code": "import pandas as pd\nprint(pd.read_csv('data.csv').describe())", "packages": ["pandas"]}),
+            ],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(code) = input.get("code").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing 'code' parameter".into());
+        };
+        let packages: Vec<String> = input
+            .get("packages")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|p| p.as_str())
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| !p.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !packages.is_empty() && !self.settings.allow_packages {
+            return ToolResult::error(
+                "Installing packages is disabled (python.allow_packages)".into(),
+            );
+        }
+        if let Some(bad) = packages.iter().find(|p| !valid_package_spec(p)) {
+            return ToolResult::error(format!("Not a package name: {bad}"));
+        }
+        let timeout_secs = input
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(self.settings.timeout_secs)
+            .clamp(1, self.settings.timeout_secs.max(1) * 10);
+
+        let shared_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let chat_id = auth_context_from_input(&input).map(|a| a.caller_chat_id);
+        let venv = venv_dir(&shared_dir, chat_id);
+        let python = match self.ensure_venv(&venv).await {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(e).with_error_type("spawn_error"),
+        };
+        let mut notes = String::new();
+        if !packages.is_empty() {
+            match self.install(&python, &packages).await {
+                Ok(msg) => notes = format!("{msg}\n"),
+                Err(e) => return ToolResult::error(e),
+            }
+        }
+
+        let env = super::chat_env::env_for_input(&self.db, &input).await;
+        let mut cmd = limited_command(&python, &["-I", "-"], timeout_secs, self.settings.memory_mb);
+        cmd.current_dir(&shared_dir).envs(env);
+        info!(
+            "Running python ({} bytes) in {}",
+            code.len(),
+            venv.display()
+        );
+        let output = match run_with_timeout(cmd, Some(code), timeout_secs).await {
+            Ok(o) => o,
+            Err(e) if e.starts_with("Timed out") => {
+                return ToolResult::error(e).with_error_type("timeout")
+            }
+            Err(e) => return ToolResult::error(e).with_error_type("spawn_error"),
+        };
+        let exit_code = output.status.code().unwrap_or(-1);
+        let text = notes + &format_output(&output.stdout, &output.stderr);
+        if output.status.success() {
+            let text = if text.is_empty() {
+                "Finished with no output (use print)".to_string()
+            } else {
+                text
+            };
+            ToolResult::success(text).with_status_code(exit_code)
+        } else {
+            ToolResult::error(
+                format!("Exit code {exit_code}\n{text}")
+                    .trim_end()
+                    .to_string(),
+            )
+            .with_status_code(exit_code)
+            .with_error_type("process_exit")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_specs_and_venv_dirs() {
+        for ok in [
+            "pandas",
+            "requests==2.32.3",
+            "uvicorn[standard]>=0.30",
+            "ruamel.yaml",
+        ] {
+            assert!(valid_package_spec(ok), "{ok}");
+        }
+        for bad in [
+            "",
+            "-r requirements.txt",
+            "--index-url=http://x",
+            "../evil",
+            "git+https://x",
+            "a b",
+        ] {
+            assert!(!valid_package_spec(bad), "{bad}");
+        }
+        let shared = Path::new("/w/shared");
+        assert_eq!(
+            venv_dir(shared, Some(-100)),
+            PathBuf::from("/w/shared/.python/chat_-100")
+        );
+        assert_eq!(
+            venv_dir(shared, None),
+            PathBuf::from("/w/shared/.python/default")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_python_rejects_bad_input() {
+        let dir = std::env::temp_dir().join(format!("microclaw_python_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let tool = PythonTool::new(&config, db);
+        assert!(tool
+            .execute(json!({}))
+            .await
+            .content
+            .contains("Missing 'code'"));
+        let out = tool
+            .execute(json!({"code": "print(1)", "packages": ["--pre"]}))
+            .await;
+        assert!(out.is_error);
+        assert!(out.content.contains("Not a package name"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
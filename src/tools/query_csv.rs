@@ -0,0 +1,542 @@
+//! `query_csv`: load a CSV/TSV or Parquet file from the workspace into an in-memory SQLite
+//! table named `data` and run one read-only SQL query over it, e.g. to summarise an expenses
+//! export without writing a script. Column types are inferred (INTEGER, REAL, else TEXT);
+//! loading stops at `MAX_ROWS` and queries are interrupted after `QUERY_TIMEOUT`.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use rusqlite::types::Value;
+use serde_json::json;
+
+use super::path_guard;
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+
+const MAX_FILE_BYTES: u64 = 200 * 1024 * 1024;
+const MAX_ROWS: usize = 1_000_000;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(15);
+const DEFAULT_RESULT_ROWS: u64 = 50;
+const MAX_RESULT_ROWS: u64 = 500;
+const MAX_CELL_CHARS: usize = 80;
+
+/// A loaded file: column names and rows of typed values.
+struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    truncated: bool,
+}
+
+/// Unique, non-empty column names (`column_3` for a blank header, `amount_2` for a repeat).
+fn column_names(headers: impl Iterator<Item = String>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (i, header) in headers.enumerate() {
+        let base = match header.trim() {
+            "" => format!("column_{}", i + 1),
+            h => h.to_string(),
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while names
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&name))
+        {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Field delimiter: the `delimiter` parameter, tab for .tsv, else ';' when the header line has
+/// semicolons but no commas (common in European spreadsheet exports), else ','.
+fn csv_delimiter(path: &Path, explicit: Option<&str>) -> Result<u8, String> {
+    if let Some(d) = explicit {
+        let d = if d == "\\t" { "\t" } else { d };
+        return match d.as_bytes() {
+            [b] => Ok(*b),
+            _ => Err("delimiter must be a single character".into()),
+        };
+    }
+    if has_extension(path, &["tsv", "tab"]) {
+        return Ok(b'\t');
+    }
+    let mut first_line = String::new();
+    let file = File::open(path).map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    std::io::BufRead::read_line(&mut std::io::BufReader::new(file), &mut first_line)
+        .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    Ok(if first_line.contains(';') && !first_line.contains(',') {
+        b';'
+    } else {
+        b','
+    })
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| extensions.contains(&e.as_str()))
+}
+
+/// Per column: integers if every non-empty cell is one, else reals if every one is a number,
+/// else text. Empty cells become NULL.
+fn typed_columns(raw: Vec<Vec<String>>, width: usize) -> Vec<Vec<Value>> {
+    let mut kinds = vec![(true, true); width];
+    for row in &raw {
+        for (cell, kind) in row.iter().zip(kinds.iter_mut()) {
+            let cell = cell.trim();
+            if cell.is_empty() {
+                continue;
+            }
+            kind.0 &= cell.parse::<i64>().is_ok();
+            kind.1 &= cell.parse::<f64>().is_ok_and(|f| f.is_finite());
+        }
+    }
+    raw.into_iter()
+        .map(|row| {
+            let mut row: Vec<Value> = row
+                .into_iter()
+                .zip(&kinds)
+                .map(|(cell, &(int, real))| {
+                    let trimmed = cell.trim();
+                    if trimmed.is_empty() {
+                        Value::Null
+                    } else if int {
+                        trimmed
+                            .parse()
+                            .map(Value::Integer)
+                            .unwrap_or(Value::Text(cell))
+                    } else if real {
+                        trimmed
+                            .parse()
+                            .map(Value::Real)
+                            .unwrap_or(Value::Text(cell))
+                    } else {
+                        Value::Text(cell)
+                    }
+                })
+                .collect();
+            row.resize(width, Value::Null);
+            row
+        })
+        .collect()
+}
+
+fn load_csv(path: &Path, delimiter: u8) -> Result<Table, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV header: {e}"))?
+        .clone();
+    let columns = column_names(
+        headers
+            .iter()
+            .map(|h| h.trim_start_matches('\u{feff}').into()),
+    );
+    let mut raw = Vec::new();
+    let mut truncated = false;
+    for record in reader.records() {
+        if raw.len() >= MAX_ROWS {
+            truncated = true;
+            break;
+        }
+        let record = record.map_err(|e| format!("Invalid CSV: {e}"))?;
+        raw.push(
+            record
+                .iter()
+                .take(columns.len())
+                .map(str::to_string)
+                .collect(),
+        );
+    }
+    let width = columns.len();
+    Ok(Table {
+        columns,
+        rows: typed_columns(raw, width),
+        truncated,
+    })
+}
+
+fn parquet_value(field: &Field) -> Value {
+    match field {
+        Field::Null => Value::Null,
+        Field::Bool(b) => Value::Integer(*b as i64),
+        Field::Byte(v) => Value::Integer(*v as i64),
+        Field::Short(v) => Value::Integer(*v as i64),
+        Field::Int(v) => Value::Integer(*v as i64),
+        Field::Long(v) => Value::Integer(*v),
+        Field::UByte(v) => Value::Integer(*v as i64),
+        Field::UShort(v) => Value::Integer(*v as i64),
+        Field::UInt(v) => Value::Integer(*v as i64),
+        Field::ULong(v) => i64::try_from(*v)
+            .map(Value::Integer)
+            .unwrap_or(Value::Real(*v as f64)),
+        Field::Float16(v) => Value::Real(f64::from(*v)),
+        Field::Float(v) => Value::Real(*v as f64),
+        Field::Double(v) => Value::Real(*v),
+        Field::Str(s) => Value::Text(s.clone()),
+        Field::Date(days) => chrono::NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(*days as i64)))
+            .map(|d| Value::Text(d.format("%Y-%m-%d").to_string()))
+            .unwrap_or(Value::Null),
+        Field::TimestampMillis(ms) => chrono::DateTime::from_timestamp_millis(*ms)
+            .map(|t| Value::Text(t.format("%Y-%m-%d %H:%M:%S").to_string()))
+            .unwrap_or(Value::Null),
+        Field::TimestampMicros(us) => chrono::DateTime::from_timestamp_micros(*us)
+            .map(|t| Value::Text(t.format("%Y-%m-%d %H:%M:%S").to_string()))
+            .unwrap_or(Value::Null),
+        Field::Decimal(_) => field
+            .to_string()
+            .parse()
+            .map(Value::Real)
+            .unwrap_or_else(|_| Value::Text(field.to_string())),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+fn load_parquet(path: &Path) -> Result<Table, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    let reader =
+        SerializedFileReader::new(file).map_err(|e| format!("Invalid Parquet file: {e}"))?;
+    let schema = reader.metadata().file_metadata().schema();
+    let columns = column_names(schema.get_fields().iter().map(|f| f.name().to_string()));
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let iter = reader
+        .get_row_iter(None)
+        .map_err(|e| format!("Invalid Parquet file: {e}"))?;
+    for row in iter {
+        if rows.len() >= MAX_ROWS {
+            truncated = true;
+            break;
+        }
+        let row = row.map_err(|e| format!("Invalid Parquet row: {e}"))?;
+        let mut values: Vec<Value> = row
+            .get_column_iter()
+            .take(columns.len())
+            .map(|(_, field)| parquet_value(field))
+            .collect();
+        values.resize(columns.len(), Value::Null);
+        rows.push(values);
+    }
+    Ok(Table {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+fn value_text(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => {
+            let rounded = (f * 1e6).round() / 1e6;
+            rounded.to_string()
+        }
+        Value::Text(s) => s.split_whitespace().collect::<Vec<_>>().join(" "),
+        Value::Blob(b) => format!("<{} bytes>", b.len()),
+    };
+    let text = text.replace('|', "\\|");
+    if text.chars().count() > MAX_CELL_CHARS {
+        format!("{}…", text.chars().take(MAX_CELL_CHARS).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// Markdown table of the result.
+fn format_result(columns: &[String], rows: &[Vec<Value>]) -> String {
+    let mut out = format!("| {} |\n", columns.join(" | "));
+    out.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(value_text).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// Load `table` into an in-memory database and run `sql` against it.
+fn run_query(table: Table, sql: &str, max_rows: usize) -> Result<String, String> {
+    let conn = rusqlite::Connection::open_in_memory().map_err(|e| e.to_string())?;
+    let definition = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let mut kinds = table
+                .rows
+                .iter()
+                .map(|r| &r[i])
+                .filter(|v| **v != Value::Null);
+            let kind = if kinds.clone().all(|v| matches!(v, Value::Integer(_))) {
+                "INTEGER"
+            } else if kinds.all(|v| matches!(v, Value::Integer(_) | Value::Real(_))) {
+                "REAL"
+            } else {
+                "TEXT"
+            };
+            format!("{} {kind}", quote_ident(name))
+        })
+        .collect::<Vec<_>>();
+    conn.execute(
+        &format!("CREATE TABLE data ({})", definition.join(", ")),
+        [],
+    )
+    .map_err(|e| format!("Failed to create table: {e}"))?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    {
+        let placeholders = vec!["?"; table.columns.len()].join(", ");
+        let mut insert = tx
+            .prepare(&format!("INSERT INTO data VALUES ({placeholders})"))
+            .map_err(|e| e.to_string())?;
+        for row in &table.rows {
+            insert
+                .execute(rusqlite::params_from_iter(row.iter()))
+                .map_err(|e| format!("Failed to load row: {e}"))?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "query_only", true)
+        .map_err(|e| e.to_string())?;
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    conn.progress_handler(10_000, Some(move || Instant::now() > deadline));
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("SQL error: {e}"))?;
+    if !stmt.readonly() {
+        return Err("Only read-only queries (SELECT) are allowed".into());
+    }
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    if columns.is_empty() {
+        return Err("The query returns no columns; use a SELECT".into());
+    }
+    let mut rows = stmt.query([]).map_err(|e| format!("SQL error: {e}"))?;
+    let mut result = Vec::new();
+    let mut more = false;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == rusqlite::ErrorCode::OperationInterrupted =>
+            {
+                return Err(format!(
+                    "The query took longer than {}s and was stopped",
+                    QUERY_TIMEOUT.as_secs()
+                ))
+            }
+            Err(e) => return Err(format!("SQL error: {e}")),
+        };
+        if result.len() >= max_rows {
+            more = true;
+            break;
+        }
+        let values = (0..columns.len())
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        result.push(values);
+    }
+
+    let mut out = format_result(&columns, &result);
+    out.push_str(&format!(
+        "\n{} row(s){}",
+        result.len(),
+        if more {
+            format!(" shown (more available; raise limit up to {MAX_RESULT_ROWS} or aggregate)")
+        } else {
+            String::new()
+        }
+    ));
+    if table.truncated {
+        out.push_str(&format!(
+            "\nNote: only the first {MAX_ROWS} rows of the file were loaded."
+        ));
+    }
+    Ok(out)
+}
+
+pub struct QueryCsvTool {
+    working_dir: PathBuf,
+}
+
+impl QueryCsvTool {
+    pub fn new(working_dir: &str) -> Self {
+        QueryCsvTool {
+            working_dir: PathBuf::from(working_dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for QueryCsvTool {
+    fn name(&self) -> &str {
+        "query_csv"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "query_csv".into(),
+            description: "Run a SQL query (SQLite dialect) over a CSV, TSV or Parquet file in the workspace, e.g. to total expenses by category. The file is loaded as the table `data`; column names come from the header (quote them with double quotes if they have spaces) and types are inferred. Omit sql to see the columns and first rows. Only read-only queries are allowed.".into(),
+            input_schema: schema_object(
+                json!({
+                    "path": {
+                        "type": "string",
+                        "description": "File to query (.csv, .tsv or .parquet)"
+                    },
+                    "sql": {
+                        "type": "string",
+                        "description": "Query over the table `data`, e.g. SELECT category, SUM(amount) FROM data GROUP BY 1 ORDER BY 2 DESC"
+                    },
+                    "delimiter": {
+                        "type": "string",
+                        "description": "CSV field delimiter (default: ',' or ';' detected from the header, tab for .tsv)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum result rows to return (default 50, max 500)"
+                    }
+                }),
+                &["path"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(path) = input.get("path").and_then(|v| v.as_str()) else {
+            return ToolResult::error("Missing 'path' parameter".into());
+        };
+        let sql = input
+            .get("sql")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .filter(|l| *l > 0)
+            .unwrap_or(DEFAULT_RESULT_ROWS)
+            .min(MAX_RESULT_ROWS) as usize;
+        let delimiter = input
+            .get("delimiter")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        let working_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let resolved = super::resolve_tool_path(&working_dir, path);
+        if let Err(e) = path_guard::check_path(&resolved.to_string_lossy()) {
+            return ToolResult::error(e);
+        }
+        let size = match std::fs::metadata(&resolved) {
+            Ok(meta) if meta.is_file() => meta.len(),
+            _ => return ToolResult::error(format!("{path} does not exist")),
+        };
+        if size > MAX_FILE_BYTES {
+            return ToolResult::error(format!(
+                "{path} is too large to query ({} MB, max {} MB)",
+                size / (1024 * 1024),
+                MAX_FILE_BYTES / (1024 * 1024)
+            ));
+        }
+        let parquet = has_extension(&resolved, &["parquet"]);
+        if !parquet && !has_extension(&resolved, &["csv", "tsv", "tab", "txt"]) {
+            return ToolResult::error("Unsupported file: expected .csv, .tsv or .parquet".into());
+        }
+        let sql = sql.map(String::from);
+        let result = tokio::task::spawn_blocking(move || {
+            let table = if parquet {
+                load_parquet(&resolved)?
+            } else {
+                let delimiter = csv_delimiter(&resolved, delimiter.as_deref())?;
+                load_csv(&resolved, delimiter)?
+            };
+            match sql {
+                Some(sql) => run_query(table, &sql, limit),
+                None => {
+                    let total = table.rows.len();
+                    let preview = run_query(table, "SELECT * FROM data LIMIT 10", 10)?;
+                    Ok(format!("Table `data` ({total} rows):\n\n{preview}"))
+                }
+            }
+        })
+        .await;
+        match result {
+            Ok(Ok(out)) => ToolResult::success(out),
+            Ok(Err(e)) => ToolResult::error(e),
+            Err(e) => ToolResult::error(format!("Query failed: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_csv_sums_and_rejects_writes() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_query_csv_{}", uuid::Uuid::new_v4()));
+        let shared = dir.join("shared");
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(
+            shared.join("expenses.csv"),
+            "date,category,amount,note\n2026-10-01,food,12.5,lunch\n2026-10-02,rent,800,\n2026-10-03,food,7.25,\"coffee, beans\"\n",
+        )
+        .unwrap();
+        std::fs::write(shared.join("eu.csv"), "name;qty\napple;3\npear;4\n").unwrap();
+        let tool = QueryCsvTool::new(dir.to_str().unwrap());
+
+        let out = tool
+            .execute(json!({
+                "path": "expenses.csv",
+                "sql": "SELECT category, SUM(amount) AS total, COUNT(*) AS n FROM data GROUP BY category ORDER BY total DESC"
+            }))
+            .await;
+        assert!(!out.is_error, "{}", out.content);
+        assert!(out.content.starts_with("| category | total | n |\n| --- | --- | --- |\n| rent | 800 | 1 |\n| food | 19.75 | 2 |\n"), "{}", out.content);
+        assert!(out.content.ends_with("2 row(s)"));
+
+        let out = tool.execute(json!({"path": "expenses.csv"})).await;
+        assert!(
+            out.content.starts_with("Table `data` (3 rows):"),
+            "{}",
+            out.content
+        );
+        assert!(out
+            .content
+            .contains("| 2026-10-03 | food | 7.25 | coffee, beans |"));
+
+        let out = tool
+            .execute(json!({"path": "eu.csv", "sql": "SELECT SUM(qty) FROM data", "limit": 1}))
+            .await;
+        assert!(out.content.contains("| 7 |"), "{}", out.content);
+
+        let out = tool
+            .execute(json!({"path": "expenses.csv", "sql": "DELETE FROM data"}))
+            .await;
+        assert!(
+            out.is_error && out.content.contains("read-only"),
+            "{}",
+            out.content
+        );
+        let out = tool
+            .execute(json!({"path": "expenses.csv", "sql": "SELECT * FROM nope"}))
+            .await;
+        assert!(out.is_error && out.content.contains("no such table"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
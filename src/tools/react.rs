@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use teloxide::prelude::*;
+use teloxide::types::{FileId, InputFile};
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::channel::{channel_for_chat, enforce_channel_policy};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database, StoredMessage};
+
+const DISCORD_API: &str = "https://discord.com/api/v10";
+
+/// One entry of the sticker pack file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StickerEntry {
+    /// Telegram sticker file_id (sent with sendSticker).
+    #[serde(default)]
+    pub telegram_file_id: Option<String>,
+    /// GIF/image URL (Telegram animation, Discord embed, web markdown image).
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Load the sticker pack (YAML map of name -> entry).
+pub fn load_sticker_pack(path: &std::path::Path) -> Result<BTreeMap<String, StickerEntry>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read sticker pack {}: {e}", path.display()))?;
+    serde_yaml::from_str(&text).map_err(|e| format!("Invalid sticker pack {}: {e}", path.display()))
+}
+
+enum ChatChannel {
+    Telegram,
+    Discord,
+    Web,
+    Other(String),
+}
+
+async fn resolve_channel(db: Arc<Database>, chat_id: i64) -> ChatChannel {
+    let chat_type = call_blocking(db, move |d| d.get_chat_type(chat_id))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    match chat_type.as_str() {
+        t if t.starts_with("telegram") => ChatChannel::Telegram,
+        "private" | "group" | "supergroup" | "channel" => ChatChannel::Telegram,
+        "discord" => ChatChannel::Discord,
+        "web" => ChatChannel::Web,
+        other => ChatChannel::Other(other.to_string()),
+    }
+}
+
+fn chat_id_from_input(input: &serde_json::Value) -> Result<i64, String> {
+    input
+        .get("chat_id")
+        .and_then(|v| v.as_i64())
+        .or_else(|| auth_context_from_input(input).map(|a| a.caller_chat_id))
+        .ok_or_else(|| "Missing required parameter: chat_id".to_string())
+}
+
+async fn store_bot_message(
+    db: Arc<Database>,
+    bot_username: &str,
+    chat_id: i64,
+    content: String,
+) -> Result<(), String> {
+    let msg_sender = bot_username.to_string();
+    call_blocking(db, move |d| {
+        let persona_id = d.get_current_persona_id(chat_id)?;
+        d.store_message(&StoredMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            chat_id,
+            persona_id,
+            sender_name: msg_sender,
+            content,
+            is_from_bot: true,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to store message: {e}"))
+}
+
+pub struct ReactTool {
+    bot: Bot,
+    db: Arc<Database>,
+    bot_username: String,
+}
+
+impl ReactTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        ReactTool {
+            bot,
+            db,
+            bot_username: config.bot_username.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ReactTool {
+    fn name(&self) -> &str {
+        "react"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "react".into(),
+            description: "React to a message with an emoji (Telegram message reaction, Discord reaction) instead of a full text reply — e.g. 👍 to acknowledge \"thanks\" or \"done\". Defaults to the latest user message in the chat. If a reaction is all the message needs, end your turn without any text and no reply is sent. Telegram only allows its standard reaction emoji (👍 ❤ 🔥 🎉 👀 🙏 👌 😁 🤔 etc.).".into(),
+            input_schema: schema_object(
+                json!({
+                    "emoji": {
+                        "type": "string",
+                        "description": "A single emoji, e.g. \"👍\""
+                    },
+                    "message_id": {
+                        "type": "string",
+                        "description": "Message to react to (defaults to the latest user message)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat of the message (defaults to the current chat)"
+                    }
+                }),
+                &["emoji"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let emoji = match input.get("emoji").and_then(|v| v.as_str()) {
+            Some(e) if !e.trim().is_empty() => e.trim().to_string(),
+            _ => return ToolResult::error("Missing required parameter: emoji".into()),
+        };
+        let chat_id = match chat_id_from_input(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        if let Err(e) = enforce_channel_policy(self.db.clone(), &input, chat_id).await {
+            return ToolResult::error(e);
+        }
+
+        if let ChatChannel::Web = resolve_channel(self.db.clone(), chat_id).await {
+            // Web UI has no reactions: show the emoji as a short reply
+            return match store_bot_message(
+                self.db.clone(),
+                &self.bot_username,
+                chat_id,
+                emoji.clone(),
+            )
+            .await
+            {
+                Ok(()) => ToolResult::success(format!("Reacted with {emoji}.")),
+                Err(e) => ToolResult::error(e),
+            };
+        }
+
+        let message_id = match input.get("message_id") {
+            Some(v) if v.is_string() || v.is_number() => {
+                v.to_string().trim_matches('"').to_string()
+            }
+            _ => {
+                match call_blocking(self.db.clone(), move |d| d.get_latest_user_message(chat_id))
+                    .await
+                {
+                    Ok(Some(m)) => m.id,
+                    Ok(None) => {
+                        return ToolResult::error("No message to react to in this chat".into())
+                    }
+                    Err(e) => return ToolResult::error(format!("Failed to find message: {e}")),
+                }
+            }
+        };
+
+        let result = match channel_for_chat(self.db.clone(), &self.bot, chat_id).await {
+            Ok(channel) => channel.react(chat_id, &message_id, &emoji).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => ToolResult::success(format!("Reacted with {emoji}.")),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+pub struct SendStickerTool {
+    bot: Bot,
+    db: Arc<Database>,
+    bot_username: String,
+    pack_path: PathBuf,
+    discord_bot_token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+impl SendStickerTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>, pack_path: &str) -> Self {
+        SendStickerTool {
+            bot,
+            db,
+            bot_username: config.bot_username.clone(),
+            pack_path: config.workspace_root_absolute().join(pack_path),
+            discord_bot_token: config.discord_bot_token.clone(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    async fn send_discord(&self, chat_id: i64, url: &str) -> Result<(), String> {
+        let token = self
+            .discord_bot_token
+            .as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .ok_or_else(|| "discord_bot_token not configured".to_string())?;
+        let resp = self
+            .http_client
+            .post(format!("{DISCORD_API}/channels/{chat_id}/messages"))
+            .header(reqwest::header::AUTHORIZATION, format!("Bot {token}"))
+            .json(&json!({ "content": url }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send Discord GIF: {e}"))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!(
+                "Failed to send Discord GIF: HTTP {status} {}",
+                body.chars().take(300).collect::<String>()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Tool for SendStickerTool {
+    fn name(&self) -> &str {
+        "send_sticker"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        let available = match load_sticker_pack(&self.pack_path) {
+            Ok(pack) => pack
+                .iter()
+                .map(|(name, entry)| match &entry.description {
+                    Some(d) => format!("{name} ({d})"),
+                    None => name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            Err(_) => "none (sticker pack missing)".into(),
+        };
+        ToolDefinition {
+            name: "send_sticker".into(),
+            description: format!(
+                "Send a sticker or GIF from the configured pack as a lightweight reply (Telegram sticker/animation, Discord GIF, web image). If the sticker says it all, end your turn without any text. Available: {available}."
+            ),
+            input_schema: schema_object(
+                json!({
+                    "name": {
+                        "type": "string",
+                        "description": "Sticker name from the pack"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Target chat (defaults to the current chat)"
+                    }
+                }),
+                &["name"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let name = match input.get("name").and_then(|v| v.as_str()) {
+            Some(n) if !n.trim().is_empty() => n.trim().to_string(),
+            _ => return ToolResult::error("Missing required parameter: name".into()),
+        };
+        let chat_id = match chat_id_from_input(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        if let Err(e) = enforce_channel_policy(self.db.clone(), &input, chat_id).await {
+            return ToolResult::error(e);
+        }
+        let pack = match load_sticker_pack(&self.pack_path) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(e),
+        };
+        let Some(entry) = pack.get(&name) else {
+            let names: Vec<&str> = pack.keys().map(String::as_str).collect();
+            return ToolResult::error(format!(
+                "Unknown sticker '{name}'. Available: {}",
+                names.join(", ")
+            ));
+        };
+
+        let result = match resolve_channel(self.db.clone(), chat_id).await {
+            ChatChannel::Telegram => match (&entry.telegram_file_id, &entry.url) {
+                (Some(file_id), _) => self
+                    .bot
+                    .send_sticker(ChatId(chat_id), InputFile::file_id(FileId(file_id.clone())))
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| format!("Failed to send Telegram sticker: {e}")),
+                (None, Some(url)) => match reqwest::Url::parse(url) {
+                    Ok(url) => self
+                        .bot
+                        .send_animation(ChatId(chat_id), InputFile::url(url))
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| format!("Failed to send Telegram animation: {e}")),
+                    Err(e) => Err(format!("Invalid sticker URL: {e}")),
+                },
+                (None, None) => Err(format!("Sticker '{name}' has no telegram_file_id or url")),
+            },
+            ChatChannel::Discord => match &entry.url {
+                Some(url) => self.send_discord(chat_id, url).await,
+                None => Err(format!("Sticker '{name}' has no url for Discord")),
+            },
+            ChatChannel::Web => match &entry.url {
+                Some(_) => Ok(()),
+                None => Err(format!("Sticker '{name}' has no url for the web UI")),
+            },
+            ChatChannel::Other(t) => Err(format!("Stickers are not supported for {t} chats")),
+        };
+        if let Err(e) = result {
+            return ToolResult::error(e);
+        }
+
+        let content = match &entry.url {
+            Some(url) => format!("![{name}]({url})"),
+            None => format!("[sticker: {name}]"),
+        };
+        if let Err(e) =
+            store_bot_message(self.db.clone(), &self.bot_username, chat_id, content).await
+        {
+            return ToolResult::error(e);
+        }
+        ToolResult::success(format!("Sent sticker '{name}'."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(dir: &std::path::Path) -> Config {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = dir.to_string_lossy().to_string();
+        config
+    }
+
+    #[tokio::test]
+    async fn test_react_and_sticker_in_web_chat() {
+        let dir = std::env::temp_dir().join(format!("microclaw_react_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("stickers.yaml"),
+            "party:\n  url: https://example.com/party.gif\n  description: Celebration\n",
+        )
+        .unwrap();
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.upsert_chat(7, Some("web-main"), "web").unwrap();
+        let config = test_config(&dir);
+        let bot = Bot::new("123456:TEST_TOKEN");
+
+        let react = ReactTool::new(&config, bot.clone(), db.clone());
+        let result = react.execute(json!({"emoji": "👍", "chat_id": 7})).await;
+        assert!(!result.is_error, "{}", result.content);
+
+        let sticker = SendStickerTool::new(&config, bot, db.clone(), "stickers.yaml");
+        assert!(sticker
+            .definition()
+            .description
+            .contains("party (Celebration)"));
+        let result = sticker
+            .execute(json!({"name": "party", "chat_id": 7}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        let result = sticker.execute(json!({"name": "nope", "chat_id": 7})).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Available: party"));
+
+        let pid = db.get_current_persona_id(7).unwrap();
+        let messages = db.get_recent_messages(7, pid, 10).unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["👍", "![party](https://example.com/party.gif)"]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -46,6 +46,7 @@ impl Tool for ReadFileTool {
                 }),
                 &["path"],
             ),
+            examples: Vec::new(),
         }
     }
 
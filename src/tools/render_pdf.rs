@@ -0,0 +1,393 @@
+//! `render_pdf`: markdown (reports, summaries, chat exports) to a styled, printable PDF. The
+//! markdown becomes a self-contained HTML page that agent-browser, the browser tool's headless
+//! Chromium, prints to PDF. The file is saved under `shared/reports/chat_<id>` and sent to the
+//! chat as an attachment.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser};
+use serde_json::json;
+use teloxide::prelude::*;
+use tracing::info;
+
+use super::command_runner::agent_browser_program;
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::channel::{channel_for_chat_type, store_bot_message};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+
+const BROWSER_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_MARKDOWN_BYTES: usize = 2 * 1024 * 1024;
+
+/// Print stylesheet: A4 pages, readable type, bordered tables, code that wraps.
+const REPORT_CSS: &str = r#"
+@page { size: A4; margin: 18mm 16mm; }
+body { font-family: -apple-system, "Segoe UI", Roboto, "Helvetica Neue", Arial, sans-serif;
+       font-size: 11pt; line-height: 1.5; color: #1f2328; }
+header { border-bottom: 2px solid #1f6feb; margin-bottom: 1.2em; padding-bottom: 0.4em; }
+header h1 { margin: 0; font-size: 20pt; }
+header .meta { color: #656d76; font-size: 9pt; }
+h1, h2, h3 { page-break-after: avoid; }
+h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 0.2em; }
+table { border-collapse: collapse; width: 100%; margin: 0.8em 0; page-break-inside: avoid; }
+th, td { border: 1px solid #d0d7de; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; }
+tr:nth-child(even) td { background: #fbfcfd; }
+code { font-family: "SFMono-Regular", Menlo, Consolas, monospace; font-size: 9.5pt;
+       background: #f6f8fa; padding: 0 3px; border-radius: 3px; }
+pre { background: #f6f8fa; padding: 8px 10px; border-radius: 4px; white-space: pre-wrap;
+      page-break-inside: avoid; }
+pre code { padding: 0; }
+blockquote { margin: 0.8em 0; padding: 0 1em; color: #656d76; border-left: 3px solid #d0d7de; }
+ul.contains-task-list { list-style: none; padding-left: 1.2em; }
+img { max-width: 100%; }
+"#;
+
+/// A self-contained HTML page for `markdown`. Raw HTML in the markdown is shown as text, and the
+/// page may not load anything (scripts, remote images), so printing never touches the network.
+pub fn render_html(title: &str, markdown: &str, generated: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(CowStr::from(raw.into_string())),
+        other => other,
+    });
+    let mut body = String::new();
+    html::push_html(&mut body, events);
+    let title = html_escape::encode_text(title);
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8">
+<meta http-equiv="Content-Security-Policy" content="default-src 'none'; style-src 'unsafe-inline'; img-src data:">
+<title>{title}</title>
+<style>{REPORT_CSS}</style>
+</head><body>
+<header><h1>{title}</h1><div class="meta">{generated}</div></header>
+<main>
+{body}</main>
+</body></html>
+"#,
+        generated = html_escape::encode_text(generated),
+    )
+}
+
+/// The title: `title` if given, else the first heading, else "Report". A leading heading equal
+/// to the title is dropped from the body, since the page header shows it.
+pub fn title_and_body<'a>(title: Option<&str>, markdown: &'a str) -> (String, &'a str) {
+    let trimmed = markdown.trim_start();
+    let first_heading = trimmed
+        .lines()
+        .next()
+        .and_then(|l| l.strip_prefix("# "))
+        .map(str::trim);
+    let title = title
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .or(first_heading)
+        .unwrap_or("Report")
+        .to_string();
+    match first_heading {
+        Some(h) if h == title => {
+            let rest = trimmed.split_once('\n').map(|(_, r)| r).unwrap_or("");
+            (title, rest)
+        }
+        _ => (title, markdown),
+    }
+}
+
+/// File name for a report: a slug of `name` plus `.pdf`.
+pub fn pdf_file_name(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().trim_end_matches(".pdf").chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_end_matches('-').chars().take(80).collect();
+    if slug.is_empty() {
+        "report.pdf".into()
+    } else {
+        format!("{slug}.pdf")
+    }
+}
+
+/// A free path for `name` in `dir`, adding a time suffix if the name is taken.
+fn report_destination(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let stem = name.trim_end_matches(".pdf");
+    dir.join(format!(
+        "{stem}-{}.pdf",
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    ))
+}
+
+async fn run_browser(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = tokio::time::timeout(
+        BROWSER_TIMEOUT,
+        tokio::process::Command::new(program).args(args).output(),
+    )
+    .await
+    .map_err(|_| format!("agent-browser {} timed out", args.get(2).unwrap_or(&"")))?
+    .map_err(|e| {
+        format!(
+            "PDF rendering needs agent-browser (npm install -g agent-browser && agent-browser install, or set AGENT_BROWSER_PATH): {e}"
+        )
+    })?;
+    if !output.status.success() {
+        return Err(format!(
+            "agent-browser {} failed: {}",
+            args.get(2).unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Print `html_path` to `pdf_path` in a throwaway browser session.
+async fn print_to_pdf(program: &str, html_path: &Path, pdf_path: &Path) -> Result<(), String> {
+    let session = format!("render-pdf-{}", uuid::Uuid::new_v4());
+    let url = format!("file://{}", html_path.display());
+    let pdf = pdf_path.to_string_lossy();
+    let result = match run_browser(program, &["--session", &session, "open", &url]).await {
+        Ok(()) => run_browser(program, &["--session", &session, "pdf", &pdf]).await,
+        Err(e) => Err(e),
+    };
+    let _ = run_browser(program, &["--session", &session, "close"]).await;
+    result?;
+    if !pdf_path.is_file() {
+        return Err("agent-browser did not write the PDF".into());
+    }
+    Ok(())
+}
+
+pub struct RenderPdfTool {
+    bot: Bot,
+    db: Arc<Database>,
+    working_dir: PathBuf,
+    agent_browser_path: Option<String>,
+    bot_username: String,
+    tz: Tz,
+}
+
+impl RenderPdfTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        RenderPdfTool {
+            bot,
+            db,
+            working_dir: PathBuf::from(config.working_dir()),
+            agent_browser_path: config.agent_browser_path.clone(),
+            bot_username: config.bot_username.clone(),
+            tz: config.timezone.parse().unwrap_or(Tz::UTC),
+        }
+    }
+
+    /// Send the PDF to `chat_id` and record it in the conversation. Web chats have no
+    /// attachments, so there the saved path is the result.
+    async fn send(&self, chat_id: i64, path: &Path, caption: &str) -> Result<bool, String> {
+        let chat_type = call_blocking(self.db.clone(), move |d| d.get_chat_type(chat_id))
+            .await
+            .map_err(|e| format!("Failed to read chat type: {e}"))?
+            .unwrap_or_default();
+        if chat_type == "web" {
+            return Ok(false);
+        }
+        let channel = channel_for_chat_type(&chat_type, &self.bot)?;
+        channel
+            .send_attachment(chat_id, path, Some(caption))
+            .await?;
+        let persona_id = call_blocking(self.db.clone(), move |d| {
+            d.get_or_create_default_persona(chat_id)
+        })
+        .await
+        .map_err(|e| format!("Failed to resolve persona: {e}"))?;
+        store_bot_message(
+            self.db.clone(),
+            &self.bot_username,
+            chat_id,
+            persona_id,
+            format!("[attachment:{}] {caption}", path.display()),
+        )
+        .await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Tool for RenderPdfTool {
+    fn name(&self) -> &str {
+        "render_pdf"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "render_pdf".into(),
+            description: "Render markdown (a report, summary or chat export) as a styled, printable PDF. Saves it in the workspace under reports/ and sends it to the current chat as a file. Tables, task lists and code blocks are supported; images and raw HTML are not.".into(),
+            input_schema: schema_object(
+                json!({
+                    "markdown": {
+                        "type": "string",
+                        "description": "Markdown to render"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Markdown file to render instead (relative to the workspace), e.g. an export_chat file"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Document title (default: the first # heading)"
+                    },
+                    "filename": {
+                        "type": "string",
+                        "description": "PDF file name (default: from the title)"
+                    },
+                    "send": {
+                        "type": "boolean",
+                        "description": "Send the PDF to the chat (default true)"
+                    }
+                }),
+                &[],
+            ),
+            examples: vec![json!({
+                "title": "Weekly report 2026-W42",
+                "markdown": "## Chores\n- [x] Laundry\n- [ ] Gutters\n\n| Day | Spent |\n|---|---|\n| Mon | $42 |"
+            })],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let shared_dir = super::resolve_tool_working_dir(&self.working_dir);
+        let markdown = match (
+            input.get("markdown").and_then(|v| v.as_str()),
+            input.get("path").and_then(|v| v.as_str()),
+        ) {
+            (Some(md), _) if !md.trim().is_empty() => md.to_string(),
+            (_, Some(path)) if !path.trim().is_empty() => {
+                let resolved = super::resolve_tool_path(&shared_dir, path.trim());
+                if let Err(e) = super::path_guard::check_path(&resolved.to_string_lossy()) {
+                    return ToolResult::error(e);
+                }
+                match tokio::fs::read_to_string(&resolved).await {
+                    Ok(md) => md,
+                    Err(e) => return ToolResult::error(format!("Failed to read {path}: {e}")),
+                }
+            }
+            _ => return ToolResult::error("Provide 'markdown' or 'path'".into()),
+        };
+        if markdown.len() > MAX_MARKDOWN_BYTES {
+            return ToolResult::error(format!(
+                "Markdown is too large ({} KB, limit {} KB)",
+                markdown.len() / 1024,
+                MAX_MARKDOWN_BYTES / 1024
+            ));
+        }
+
+        let (title, body) = title_and_body(input.get("title").and_then(|v| v.as_str()), &markdown);
+        let generated = format!(
+            "{} · {}",
+            self.bot_username,
+            chrono::Utc::now()
+                .with_timezone(&self.tz)
+                .format("%Y-%m-%d %H:%M %Z")
+        );
+        let page = render_html(&title, body, &generated);
+
+        let chat_id = auth_context_from_input(&input).map(|a| a.caller_chat_id);
+        let dir = shared_dir.join("reports").join(match chat_id {
+            Some(id) => format!("chat_{id}"),
+            None => "default".into(),
+        });
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            return ToolResult::error(format!("Failed to create {}: {e}", dir.display()));
+        }
+        let name = pdf_file_name(
+            input
+                .get("filename")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&title),
+        );
+        let pdf_path = report_destination(&dir, &name);
+        let html_path =
+            std::env::temp_dir().join(format!("microclaw-report-{}.html", uuid::Uuid::new_v4()));
+        if let Err(e) = tokio::fs::write(&html_path, page).await {
+            return ToolResult::error(format!("Failed to write HTML: {e}"));
+        }
+
+        let program = self
+            .agent_browser_path
+            .clone()
+            .unwrap_or_else(agent_browser_program);
+        info!("Rendering PDF {} via '{}'", pdf_path.display(), program);
+        let printed = print_to_pdf(&program, &html_path, &pdf_path).await;
+        let _ = tokio::fs::remove_file(&html_path).await;
+        if let Err(e) = printed {
+            return ToolResult::error(e);
+        }
+        let size_kb = std::fs::metadata(&pdf_path)
+            .map(|m| m.len().div_ceil(1024))
+            .unwrap_or(0);
+        let mut text = format!("Saved PDF: {} ({size_kb} KB)", pdf_path.display());
+
+        let send = input.get("send").and_then(|v| v.as_bool()).unwrap_or(true);
+        if let (true, Some(chat_id)) = (send, chat_id) {
+            match self.send(chat_id, &pdf_path, &title).await {
+                Ok(true) => text.push_str("\nSent to the chat."),
+                Ok(false) => text.push_str(
+                    "\nWeb chats cannot receive files; tell the user where the PDF was saved.",
+                ),
+                Err(e) => text.push_str(&format!("\nCould not send it to the chat: {e}")),
+            }
+        }
+        ToolResult::success(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_html_escapes_raw_html_and_renders_tables() {
+        let page = render_html(
+            "Bills <Q4>",
+            "| Item | Cost |\n|---|---|\n| Power | $80 |\n\n<script>alert(1)</script>\n\n- [x] paid",
+            "bot · 2026-10-16",
+        );
+        assert!(page.contains("<title>Bills &lt;Q4&gt;</title>"));
+        assert!(page.contains("<td>Power</td>"));
+        assert!(page.contains("&lt;script&gt;"));
+        assert!(!page.contains("<script>"));
+        assert!(page.contains("type=\"checkbox\""));
+        assert!(page.contains("default-src 'none'"));
+    }
+
+    #[test]
+    fn test_title_and_file_name() {
+        let md = "# Weekly report\n\nAll good.";
+        let (title, body) = title_and_body(None, md);
+        assert_eq!(title, "Weekly report");
+        assert_eq!(body.trim(), "All good.");
+        let (title, body) = title_and_body(Some("October"), md);
+        assert_eq!(title, "October");
+        assert_eq!(body, md);
+        assert_eq!(title_and_body(None, "no heading").0, "Report");
+
+        assert_eq!(
+            pdf_file_name("Weekly report: 2026/W42"),
+            "weekly-report-2026-w42.pdf"
+        );
+        assert_eq!(pdf_file_name("summary.pdf"), "summary.pdf");
+        assert_eq!(pdf_file_name("../.."), "report.pdf");
+    }
+}
@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::{Config, ThinkingLevel};
+
+pub struct RoundtableTool {
+    config: Config,
+}
+
+impl RoundtableTool {
+    pub fn new(config: &Config) -> Self {
+        RoundtableTool {
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RoundtableTool {
+    fn name(&self) -> &str {
+        "roundtable"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "roundtable".into(),
+            description: "Have the configured personas (e.g. an advocate and a skeptic, possibly on different models) discuss a question for a few rounds, then get a synthesized answer. Use it for decisions and open questions that benefit from weighing several viewpoints, not for facts or quick lookups; it takes several model calls.".into(),
+            input_schema: schema_object(
+                json!({
+                    "question": {
+                        "type": "string",
+                        "description": "The question or decision to discuss, with any context the personas need"
+                    }
+                }),
+                &["question"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let question = match input.get("question").and_then(|v| v.as_str()) {
+            Some(q) if !q.trim().is_empty() => q.trim(),
+            _ => return ToolResult::error("Missing required parameter: question".into()),
+        };
+        match crate::roundtable::roundtable(&self.config, question).await {
+            Ok(outcome) if self.config.show_thinking == ThinkingLevel::Full => ToolResult::success(format!(
+                "{}\n\n(show_thinking is full: include the discussion in your reply.)",
+                outcome.render(true)
+            )),
+            Ok(outcome) => ToolResult::success(outcome.answer),
+            Err(e) => ToolResult::error(format!("Roundtable failed: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_roundtable_requires_question() {
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let tool = RoundtableTool::new(&config);
+        let result = tool.execute(json!({"question": "  "})).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("question"));
+    }
+}
@@ -25,19 +25,24 @@ fn format_tasks_list_impl(tasks: &[ScheduledTask], include_chat_id: bool) -> Str
     }
     let mut output = String::from("Scheduled tasks (all chats/personas):\n");
     for t in tasks {
+        let tz = t
+            .timezone
+            .as_deref()
+            .map(|tz| format!(" ({tz})"))
+            .unwrap_or_default();
         if include_chat_id {
             output.push_str(&format!(
-                "#{} [{}] chat:{} | {} | {} '{}' | next: {}\n",
+                "#{} [{}] chat:{} | {} | {} '{}'{tz} | next: {}\n",
                 t.id, t.status, t.chat_id, t.prompt, t.schedule_type, t.schedule_value, t.next_run
             ));
         } else {
             output.push_str(&format!(
-                "#{} [{}] {} | {} '{}' | next: {}\n",
+                "#{} [{}] {} | {} '{}'{tz} | next: {}\n",
                 t.id, t.status, t.prompt, t.schedule_type, t.schedule_value, t.next_run
             ));
         }
     }
-    output.push_str("\nUse `schedule_task` to add; `pause_scheduled_task` / `resume_scheduled_task` / `cancel_scheduled_task` to manage.");
+    output.push_str("\nUse `schedule_task` to add; `pause_scheduled_task` / `resume_scheduled_task` / `run_scheduled_task_now` / `cancel_scheduled_task` to manage.");
     output
 }
 
@@ -47,15 +52,16 @@ fn compute_next_run(cron_expr: &str, tz_name: &str) -> Result<String, String> {
         .map_err(|_| format!("Invalid timezone: {tz_name}"))?;
     let schedule =
         cron::Schedule::from_str(cron_expr).map_err(|e| format!("Invalid cron expression: {e}"))?;
-    let next = schedule
-        .upcoming(tz)
-        .next()
+    let next = crate::scheduler::next_cron_run(&schedule, tz, chrono::Utc::now())
         .ok_or_else(|| "No upcoming run found for this cron expression".to_string())?;
     Ok(next.to_rfc3339())
 }
 
 // --- schedule_task ---
 
+/// Upper bound for a task's `max_retries`.
+const MAX_TASK_RETRIES: u64 = 10;
+
 pub struct ScheduleTaskTool {
     db: Arc<Database>,
     default_timezone: String,
@@ -68,6 +74,77 @@ impl ScheduleTaskTool {
             default_timezone,
         }
     }
+
+    /// Create a task from a natural-language `when`, echoing how it was read.
+    async fn schedule_phrase(
+        &self,
+        chat_id: i64,
+        prompt: &str,
+        when: &str,
+        tz_name: &str,
+        max_retries: Option<u32>,
+        concurrency: Option<&'static str>,
+    ) -> ToolResult {
+        let tz: chrono_tz::Tz = match tz_name.parse() {
+            Ok(tz) => tz,
+            Err(_) => return ToolResult::error(format!("Invalid timezone: {tz_name}")),
+        };
+        let parsed = match crate::schedule_phrase::parse(when, tz, chrono::Utc::now()) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return ToolResult::error(format!(
+                    "Could not read the schedule '{when}': {e}. Rephrase it, or pass schedule_type and schedule_value."
+                ))
+            }
+        };
+        let next_run = if parsed.schedule_type == "cron" {
+            match compute_next_run(&parsed.value, tz_name) {
+                Ok(nr) => nr,
+                Err(e) => return ToolResult::error(e),
+            }
+        } else {
+            parsed.value.clone()
+        };
+        let next_local = chrono::DateTime::parse_from_rfc3339(&next_run)
+            .map(|t| t.with_timezone(&tz).format("%a %-d %b %Y %H:%M").to_string())
+            .unwrap_or_else(|_| next_run.clone());
+        let detail = if parsed.schedule_type == "cron" {
+            format!("cron '{}'", parsed.value)
+        } else {
+            "one-time".to_string()
+        };
+
+        let prompt_owned = prompt.to_string();
+        let schedule_type = parsed.schedule_type;
+        let schedule_value = parsed.value.clone();
+        let next_run_owned = next_run.clone();
+        let tz_owned = tz_name.to_string();
+        match call_blocking(self.db.clone(), move |db| {
+            let id = db.create_scheduled_task(
+                chat_id,
+                &prompt_owned,
+                schedule_type,
+                &schedule_value,
+                &next_run_owned,
+            )?;
+            db.set_task_timezone(id, Some(&tz_owned))?;
+            if max_retries.is_some() {
+                db.set_task_max_retries(id, max_retries)?;
+            }
+            if concurrency.is_some() {
+                db.set_task_concurrency(id, concurrency)?;
+            }
+            Ok(id)
+        })
+        .await
+        {
+            Ok(id) => ToolResult::success(format!(
+                "Task #{id} scheduled {} ({detail}, tz: {tz_name}). Next run: {next_local} ({next_run}). If this is not what was meant, cancel_scheduled_task #{id} and schedule again.",
+                parsed.summary
+            )),
+            Err(e) => ToolResult::error(format!("Failed to create task: {e}")),
+        }
+    }
 }
 
 #[async_trait]
@@ -79,7 +156,7 @@ impl Tool for ScheduleTaskTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "schedule_task".into(),
-            description: "Schedule a recurring or one-time task. For recurring tasks, provide a 6-field cron expression (sec min hour dom month dow). For one-time tasks, provide an ISO 8601 timestamp. The bot will execute the prompt at the scheduled time and send the result to this chat.".into(),
+            description: "Schedule a recurring or one-time task. Either describe the schedule in plain words with `when` (e.g. 'every weekday at 8am', 'in 45 minutes', 'tomorrow at 9:30'), or give schedule_type and schedule_value: a 6-field cron expression (sec min hour dom month dow) for recurring tasks, an ISO 8601 timestamp for one-time tasks. The result echoes how the schedule was read; repeat it to the user so they can confirm. The bot will execute the prompt at the scheduled time and send the result to this chat.".into(),
             input_schema: schema_object(
                 json!({
                     "chat_id": {
@@ -90,6 +167,10 @@ impl Tool for ScheduleTaskTool {
                         "type": "string",
                         "description": "The prompt/instruction to execute at the scheduled time"
                     },
+                    "when": {
+                        "type": "string",
+                        "description": "Schedule in plain words, read in the task's timezone: 'every 15 minutes', 'daily at 7pm', 'every Monday and Thursday at 6:30pm', 'monthly on the 1st', 'in 2 hours', 'friday at noon', 'oct 20 at 14:00'. Use instead of schedule_type/schedule_value."
+                    },
                     "schedule_type": {
                         "type": "string",
                         "enum": ["cron", "once"],
@@ -101,11 +182,25 @@ impl Tool for ScheduleTaskTool {
                     },
                     "timezone": {
                         "type": "string",
-                        "description": "Optional IANA timezone name (e.g. 'US/Eastern', 'Europe/London'). Defaults to server timezone setting."
+                        "description": "Optional IANA timezone name (e.g. 'US/Eastern', 'Europe/London'). Defaults to the chat's timezone, else the server timezone setting. The task keeps it for every run, so times stay put across DST changes."
+                    },
+                    "max_retries": {
+                        "type": "integer",
+                        "description": "Optional: how often to retry a failed run (with growing delays) before reporting the failure to the control chats, 0-10. Defaults to the task_max_retries setting."
+                    },
+                    "concurrency": {
+                        "type": "string",
+                        "enum": ["skip", "queue", "parallel"],
+                        "description": "Optional: what to do when the task comes due while its previous run is still going: 'skip' that run (default), 'queue' it until the previous one ends, or run in 'parallel'."
                     }
                 }),
-                &["chat_id", "prompt", "schedule_type", "schedule_value"],
+                &["chat_id", "prompt"],
             ),
+            examples: vec![
+                json!({"chat_id": 123456789, "prompt": "Post a summary of unread email", "when": "every weekday at 8am"}),
+                json!({"chat_id": 123456789, "prompt": "Send me a short weather forecast for today", "schedule_type": "cron", "schedule_value": "0 0 8 * * Mon-Fri", "timezone": "Europe/London"}),
+                json!({"chat_id": 123456789, "prompt": "Remind me to call the dentist", "schedule_type": "once", "schedule_value": "2027-03-14T15:00:00+00:00"}),
+            ],
         }
     }
 
@@ -124,18 +219,58 @@ impl Tool for ScheduleTaskTool {
             Some(p) => p,
             None => return ToolResult::error("Missing required parameter: prompt".into()),
         };
+        let tz_name = match input.get("timezone").and_then(|v| v.as_str()) {
+            Some(tz) => tz.to_string(),
+            None => {
+                crate::onboarding::chat_timezone(self.db.clone(), chat_id, &self.default_timezone)
+                    .await
+            }
+        };
+        let tz_name = tz_name.as_str();
+        let max_retries = match input.get("max_retries").filter(|v| !v.is_null()) {
+            None => None,
+            Some(v) => match v.as_u64() {
+                Some(n) if n <= MAX_TASK_RETRIES => Some(n as u32),
+                _ => {
+                    return ToolResult::error(format!(
+                        "max_retries must be between 0 and {MAX_TASK_RETRIES}"
+                    ))
+                }
+            },
+        };
+        let concurrency = match input.get("concurrency").and_then(|v| v.as_str()) {
+            None => None,
+            Some(c) => match crate::scheduler::ConcurrencyPolicy::parse(c) {
+                Some(policy) => Some(policy.as_str()),
+                None => {
+                    return ToolResult::error(
+                        "concurrency must be 'skip', 'queue' or 'parallel'".into(),
+                    )
+                }
+            },
+        };
+        let when = input
+            .get("when")
+            .and_then(|v| v.as_str())
+            .filter(|w| !w.trim().is_empty());
+        if let Some(when) = when {
+            return self
+                .schedule_phrase(chat_id, prompt, when, tz_name, max_retries, concurrency)
+                .await;
+        }
         let schedule_type = match input.get("schedule_type").and_then(|v| v.as_str()) {
             Some(t) => t,
-            None => return ToolResult::error("Missing required parameter: schedule_type".into()),
+            None => {
+                return ToolResult::error(
+                    "Missing required parameter: when (or schedule_type and schedule_value)"
+                        .into(),
+                )
+            }
         };
         let schedule_value = match input.get("schedule_value").and_then(|v| v.as_str()) {
             Some(v) => v,
             None => return ToolResult::error("Missing required parameter: schedule_value".into()),
         };
-        let tz_name = input
-            .get("timezone")
-            .and_then(|v| v.as_str())
-            .unwrap_or(&self.default_timezone);
 
         let next_run = match schedule_type {
             "cron" => match compute_next_run(schedule_value, tz_name) {
@@ -158,14 +293,23 @@ impl Tool for ScheduleTaskTool {
         let schedule_type_owned = schedule_type.to_string();
         let schedule_value_owned = schedule_value.to_string();
         let next_run_owned = next_run.clone();
+        let tz_owned = tz_name.to_string();
         match call_blocking(self.db.clone(), move |db| {
-            db.create_scheduled_task(
+            let id = db.create_scheduled_task(
                 chat_id,
                 &prompt_owned,
                 &schedule_type_owned,
                 &schedule_value_owned,
                 &next_run_owned,
-            )
+            )?;
+            db.set_task_timezone(id, Some(&tz_owned))?;
+            if max_retries.is_some() {
+                db.set_task_max_retries(id, max_retries)?;
+            }
+            if concurrency.is_some() {
+                db.set_task_concurrency(id, concurrency)?;
+            }
+            Ok(id)
         })
         .await
         {
@@ -208,6 +352,7 @@ impl Tool for ListTasksTool {
                 }),
                 &["chat_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -261,6 +406,7 @@ impl Tool for PauseTaskTool {
                 }),
                 &["task_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -325,6 +471,7 @@ impl Tool for ResumeTaskTool {
                 }),
                 &["task_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -358,6 +505,71 @@ impl Tool for ResumeTaskTool {
     }
 }
 
+// --- run_task_now ---
+
+pub struct RunTaskNowTool {
+    db: Arc<Database>,
+}
+
+impl RunTaskNowTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        RunTaskNowTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for RunTaskNowTool {
+    fn name(&self) -> &str {
+        "run_scheduled_task_now"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "run_scheduled_task_now".into(),
+            description: "Run a scheduled task once right away (within a minute), e.g. to try it out. Its schedule and status stay as they are, so this also works for paused and completed tasks.".into(),
+            input_schema: schema_object(
+                json!({
+                    "task_id": {
+                        "type": "integer",
+                        "description": "The task ID to run"
+                    }
+                }),
+                &["task_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let task_id = match input.get("task_id").and_then(|v| v.as_i64()) {
+            Some(id) => id,
+            None => return ToolResult::error("Missing required parameter: task_id".into()),
+        };
+        let task = match call_blocking(self.db.clone(), move |db| db.get_task_by_id(task_id)).await
+        {
+            Ok(Some(t)) => t,
+            Ok(None) => return ToolResult::error(format!("Task #{task_id} not found.")),
+            Err(e) => return ToolResult::error(format!("Failed to load task: {e}")),
+        };
+        if let Err(e) = authorize_chat_access(&input, task.chat_id) {
+            return ToolResult::error(e);
+        }
+        if let Err(e) = enforce_channel_policy(self.db.clone(), &input, task.chat_id).await {
+            return ToolResult::error(e);
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        match call_blocking(self.db.clone(), move |db| db.request_task_run(task_id, &now)).await {
+            Ok(true) => ToolResult::success(format!(
+                "Task #{task_id} will run within a minute; the result is posted to chat {}.",
+                task.chat_id
+            )),
+            Ok(false) => ToolResult::error(format!("Task #{task_id} is cancelled.")),
+            Err(e) => ToolResult::error(format!("Failed to run task: {e}")),
+        }
+    }
+}
+
 // --- cancel_task ---
 
 pub struct CancelTaskTool {
@@ -389,6 +601,7 @@ impl Tool for CancelTaskTool {
                 }),
                 &["task_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -457,6 +670,7 @@ impl Tool for GetTaskHistoryTool {
                 }),
                 &["task_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -695,6 +909,27 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_run_task_now() {
+        let (db, dir) = test_db();
+        let id = db
+            .create_scheduled_task(100, "test", "cron", "0 * * * * *", "2099-01-01T00:00:00Z")
+            .unwrap();
+        db.update_task_status(id, "paused").unwrap();
+
+        let tool = RunTaskNowTool::new(db.clone());
+        let result = tool.execute(json!({"task_id": id})).await;
+        assert!(!result.is_error, "{}", result.content);
+        let task = db.get_task_by_id(id).unwrap().unwrap();
+        assert!(task.run_requested_at.is_some());
+        assert_eq!(task.status, "paused");
+
+        db.update_task_status(id, "cancelled").unwrap();
+        let result = tool.execute(json!({"task_id": id})).await;
+        assert!(result.is_error);
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_cancel_task() {
         let (db, dir) = test_db();
@@ -716,7 +951,7 @@ mod tests {
     #[tokio::test]
     async fn test_schedule_task_with_timezone() {
         let (db, dir) = test_db();
-        let tool = ScheduleTaskTool::new(db, "UTC".into());
+        let tool = ScheduleTaskTool::new(db.clone(), "UTC".into());
         let result = tool
             .execute(json!({
                 "chat_id": 100,
@@ -729,6 +964,63 @@ mod tests {
         assert!(!result.is_error, "Error: {}", result.content);
         assert!(result.content.contains("scheduled"));
         assert!(result.content.contains("US/Eastern"));
+        let task = db.get_tasks_for_chat(100).unwrap().remove(0);
+        assert_eq!(task.timezone.as_deref(), Some("US/Eastern"));
+        assert!(task.concurrency.is_none());
+
+        let result = tool
+            .execute(json!({
+                "chat_id": 100,
+                "prompt": "backup photos",
+                "when": "every hour",
+                "concurrency": "queue"
+            }))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        let task = db.get_tasks_for_chat(100).unwrap().remove(1);
+        assert_eq!(task.concurrency.as_deref(), Some("queue"));
+        let result = tool
+            .execute(json!({
+                "chat_id": 100,
+                "prompt": "x",
+                "when": "every hour",
+                "concurrency": "sometimes"
+            }))
+            .await;
+        assert!(result.is_error);
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_schedule_task_natural_language() {
+        let (db, dir) = test_db();
+        let tool = ScheduleTaskTool::new(db.clone(), "UTC".into());
+        let result = tool
+            .execute(json!({
+                "chat_id": 100,
+                "prompt": "standup notes",
+                "when": "every weekday at 8:30am",
+                "timezone": "Europe/Berlin"
+            }))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains(
+            "scheduled every weekday at 08:30 (cron '0 30 8 * * Mon-Fri', tz: Europe/Berlin)"
+        ));
+        let tasks = db.get_tasks_for_chat(100).unwrap();
+        assert_eq!(tasks[0].schedule_value, "0 30 8 * * Mon-Fri");
+
+        let result = tool
+            .execute(json!({"chat_id": 100, "prompt": "stretch", "when": "in 45 minutes"}))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains("one-time"));
+
+        let result = tool
+            .execute(json!({"chat_id": 100, "prompt": "x", "when": "now and then"}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Could not read the schedule"));
         cleanup(&dir);
     }
 
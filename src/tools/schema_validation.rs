@@ -0,0 +1,212 @@
+//! Checks model-provided tool input against the tool's JSON schema before it runs: types,
+//! required properties and enums, recursing into object properties and array items. Failures come
+//! back as one JSON error listing every problem, so the model can fix its call in one go.
+//! Keywords outside that subset are ignored, and the `__microclaw_*` context keys are skipped.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationIssue {
+    /// Dotted path of the offending value, e.g. `items[2].name` (empty for the input itself).
+    pub path: String,
+    pub problem: String,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => match value {
+            Value::Number(n) => {
+                n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+            }
+            _ => false,
+        },
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str, issues: &mut Vec<ValidationIssue>) {
+    let mut issue = |problem: String| {
+        issues.push(ValidationIssue {
+            path: path.to_string(),
+            problem,
+        })
+    };
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+        issue(format!(
+            "expected {}, got {}",
+            types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !options.contains(value) {
+            let allowed: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+            issue(format!(
+                "must be one of {}, got {value}",
+                allowed.join(", ")
+            ));
+            return;
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            let required = schema.get("required").and_then(|r| r.as_array());
+            for key in required.into_iter().flatten().filter_map(|k| k.as_str()) {
+                if map.get(key).is_none_or(|v| v.is_null()) {
+                    issues.push(ValidationIssue {
+                        path: join(path, key),
+                        problem: "required property is missing".into(),
+                    });
+                }
+            }
+            for (key, child) in map {
+                if key.starts_with("__microclaw") {
+                    continue;
+                }
+                match properties.and_then(|p| p.get(key)) {
+                    // Optional properties sent as null are treated as omitted
+                    Some(_) if child.is_null() => {}
+                    Some(child_schema) => check(child_schema, child, &join(path, key), issues),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        issues.push(ValidationIssue {
+                            path: join(path, key),
+                            problem: "unknown property".into(),
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}[{i}]"), issues);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Every way `input` violates `schema`; empty when it is valid.
+pub fn validate(schema: &Value, input: &Value) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    check(schema, input, "", &mut issues);
+    issues
+}
+
+/// The error returned to the model for invalid input.
+pub fn error_message(tool: &str, issues: &[ValidationIssue]) -> String {
+    json!({
+        "error": "invalid_input",
+        "tool": tool,
+        "issues": issues,
+        "hint": "Fix these arguments and call the tool again (tool_help shows its full usage).",
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::schema_object;
+
+    fn schema() -> Value {
+        schema_object(
+            json!({
+                "path": {"type": "string"},
+                "limit": {"type": "integer"},
+                "mode": {"type": "string", "enum": ["fast", "slow"]},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "options": {
+                    "type": "object",
+                    "properties": {"depth": {"type": "number"}},
+                    "required": ["depth"]
+                }
+            }),
+            &["path"],
+        )
+    }
+
+    #[test]
+    fn test_valid_input() {
+        let input = json!({
+            "path": "a.txt",
+            "limit": 5,
+            "mode": "fast",
+            "tags": ["x"],
+            "options": {"depth": 1.5},
+            "goal": null,
+            "extra": true,
+            "__microclaw_auth": {"caller_chat_id": 1}
+        });
+        assert!(validate(&schema(), &input).is_empty());
+        // Whole floats are accepted as integers, null counts as omitted
+        assert!(validate(&schema(), &json!({"path": "a", "limit": 5.0, "mode": null})).is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_issue() {
+        let input = json!({
+            "limit": "5",
+            "mode": "medium",
+            "options": {},
+            "tags": ["x", 2]
+        });
+        let issues = validate(&schema(), &input);
+        let paths: Vec<&str> = issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["path", "limit", "mode", "options.depth", "tags[1]"]
+        );
+        assert_eq!(issues[1].problem, "expected integer, got string");
+        assert_eq!(
+            issues[2].problem,
+            "must be one of \"fast\", \"slow\", got \"medium\""
+        );
+
+        let msg: Value = serde_json::from_str(&error_message("read_file", &issues)).unwrap();
+        assert_eq!(msg["error"], "invalid_input");
+        assert_eq!(msg["issues"][0]["problem"], "required property is missing");
+
+        let strict = json!({"type": "object", "properties": {}, "additionalProperties": false});
+        assert_eq!(
+            validate(&strict, &json!({"x": 1}))[0].problem,
+            "unknown property"
+        );
+        assert_eq!(
+            validate(&strict, &json!([]))[0].problem,
+            "expected object, got array"
+        );
+    }
+}
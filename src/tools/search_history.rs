@@ -51,6 +51,7 @@ impl Tool for SearchHistoryTool {
                 }),
                 &["query", "chat_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -127,6 +128,7 @@ impl Tool for SearchHistoryTool {
                             excerpt
                         };
                         json!({
+                            "id": m.id,
                             "timestamp": m.timestamp,
                             "sender": m.sender_name,
                             "is_bot": m.is_from_bot,
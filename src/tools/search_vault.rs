@@ -1,16 +1,19 @@
 use async_trait::async_trait;
 use serde_json::json;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use super::command_runner::{build_command, shell_command};
 use super::{resolve_tool_working_dir, schema_object, Tool, ToolResult};
 use crate::claude::ToolDefinition;
+use crate::embeddings::{Embedder, DEFAULT_BATCH_SIZE};
+use crate::vault_index::{citation, note_path};
 
 /// Search mode: native (embedding + ChromaDB HTTP) or command (run vault_search_command).
 #[derive(Clone)]
 pub enum SearchVaultMode {
     Native {
-        embedding_url: String,
+        embedder: Arc<Embedder>,
         vector_db_url: String,
         collection: String,
         http_client: reqwest::Client,
@@ -23,14 +26,17 @@ pub enum SearchVaultMode {
 
 pub struct SearchVaultTool {
     mode: SearchVaultMode,
+    vault_root: Option<PathBuf>,
 }
 
 impl SearchVaultTool {
     /// Native mode: call embedding server + ChromaDB HTTP API. Requires both to be running.
-    pub fn new_native(embedding_url: &str, vector_db_url: &str, collection: &str) -> Self {
+    /// Query embeddings go through `embedder`, so repeated queries hit its cache.
+    pub fn new_native(embedder: Arc<Embedder>, vector_db_url: &str, collection: &str) -> Self {
         Self {
+            vault_root: None,
             mode: SearchVaultMode::Native {
-                embedding_url: embedding_url.trim_end_matches('/').to_string(),
+                embedder,
                 vector_db_url: vector_db_url.trim_end_matches('/').to_string(),
                 collection: collection.to_string(),
                 http_client: reqwest::Client::new(),
@@ -41,6 +47,7 @@ impl SearchVaultTool {
     /// Command mode: run vault_search_command with {query} substituted. No ChromaDB server needed.
     pub fn new_command(vault_search_command: &str, working_dir: &str) -> Self {
         Self {
+            vault_root: None,
             mode: SearchVaultMode::Command {
                 vault_search_command: vault_search_command.to_string(),
                 working_dir: PathBuf::from(working_dir),
@@ -48,9 +55,16 @@ impl SearchVaultTool {
         }
     }
 
-    /// Legacy constructor for native mode (backwards compatible).
+    /// Vault directory, so absolute `source` paths from external indexers cite as vault-relative.
+    pub fn with_vault_root(mut self, root: Option<PathBuf>) -> Self {
+        self.vault_root = root;
+        self
+    }
+
+    /// Legacy constructor for native mode (backwards compatible), without an embedding cache.
     pub fn new(embedding_url: &str, vector_db_url: &str, collection: &str) -> Self {
-        Self::new_native(embedding_url, vector_db_url, collection)
+        let embedder = Embedder::new(embedding_url, "default", DEFAULT_BATCH_SIZE, None);
+        Self::new_native(Arc::new(embedder), vector_db_url, collection)
     }
 
     /// Command mode: run vault_search_command with {query} substituted.
@@ -132,7 +146,7 @@ impl Tool for SearchVaultTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "search_vault".into(),
-            description: "Semantically search the ORIGIN vault (Obsidian notes, documents) using vector similarity. Use this to find relevant knowledge base entries. This searches the vault knowledge base, NOT conversation history — use search_chat_history for that.".into(),
+            description: "Semantically search the ORIGIN vault (Obsidian notes, documents) using vector similarity. Use this to find relevant knowledge base entries. Cite what you use with each result's `cite` link (e.g. [[Projects/home#Plans]]); it becomes a link to the note. This searches the vault knowledge base, NOT conversation history — use search_chat_history for that.".into(),
             input_schema: schema_object(
                 json!({
                     "query": {
@@ -146,6 +160,7 @@ impl Tool for SearchVaultTool {
                 }),
                 &["query"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -174,7 +189,7 @@ impl Tool for SearchVaultTool {
             .min(20) as usize;
 
         let SearchVaultMode::Native {
-            embedding_url,
+            embedder,
             vector_db_url,
             collection,
             http_client,
@@ -183,59 +198,12 @@ impl Tool for SearchVaultTool {
             unreachable!()
         };
 
-        // Step 1: Get embedding from embedding server
-        let embed_resp = match http_client
-            .post(format!("{}/embedding", embedding_url))
-            .json(&json!({"content": query}))
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => return ToolResult::error(format!("Embedding server unreachable: {e}")),
+        // Step 1: Embed the query (cached by the embedder)
+        let embedding = match embedder.embed_one(&query).await {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(e),
         };
 
-        if !embed_resp.status().is_success() {
-            let status = embed_resp.status();
-            let body = embed_resp.text().await.unwrap_or_default();
-            return ToolResult::error(format!(
-                "Embedding server returned {status}: {body}"
-            ));
-        }
-
-        let embed_json: serde_json::Value = match embed_resp.json().await {
-            Ok(j) => j,
-            Err(e) => {
-                return ToolResult::error(format!("Failed to parse embedding response: {e}"))
-            }
-        };
-
-        // Handle both llama.cpp formats:
-        //   {"embedding": [[0.1, 0.2, ...]]}  — list of embedding vectors
-        //   {"embedding": [0.1, 0.2, ...]}     — single flat vector
-        let embedding: Vec<serde_json::Value> = if let Some(outer) =
-            embed_json.get("embedding").and_then(|v| v.as_array())
-        {
-            if outer.first().and_then(|v| v.as_array()).is_some() {
-                // Nested: [[...]] — take first vector
-                outer
-                    .first()
-                    .and_then(|v| v.as_array())
-                    .cloned()
-                    .unwrap_or_default()
-            } else {
-                // Flat: [...] — use directly
-                outer.clone()
-            }
-        } else {
-            return ToolResult::error(
-                "Unexpected embedding response format (missing 'embedding' field)".into(),
-            );
-        };
-
-        if embedding.is_empty() {
-            return ToolResult::error("Embedding server returned empty embedding vector".into());
-        }
-
         // Step 2: Get ChromaDB collection ID
         let col_resp = match http_client
             .get(format!(
@@ -359,9 +327,8 @@ impl Tool for SearchVaultTool {
                     .and_then(|v| v.as_f64())
                     .unwrap_or(0.0);
 
-                let source = metadatas
-                    .get(i)
-                    .and_then(|m| m.as_object())
+                let metadata = metadatas.get(i).and_then(|m| m.as_object());
+                let source = metadata
                     .and_then(|o| {
                         o.get("source")
                             .or_else(|| o.get("file"))
@@ -369,10 +336,17 @@ impl Tool for SearchVaultTool {
                     })
                     .and_then(|v| v.as_str())
                     .unwrap_or("unknown");
+                let heading = metadata
+                    .and_then(|o| o.get("heading"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let path = note_path(source, self.vault_root.as_deref());
 
                 json!({
                     "rank": i + 1,
                     "source": source,
+                    "heading": heading,
+                    "cite": citation(&path, heading),
                     "distance": dist,
                     "content": content
                 })
@@ -4,10 +4,11 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::json;
 use teloxide::prelude::*;
-use teloxide::types::InputFile;
 
 use super::{authorize_chat_access, schema_object, Tool, ToolResult};
-use crate::channel::{deliver_and_store_bot_message, enforce_channel_policy};
+use crate::channel::{
+    channel_for_chat_type, deliver_and_store_bot_message, enforce_channel_policy,
+};
 use crate::claude::ToolDefinition;
 use crate::config::Config;
 use crate::db::{call_blocking, Database, StoredMessage};
@@ -17,7 +18,6 @@ pub struct SendMessageTool {
     db: Arc<Database>,
     bot_username: String,
     config: Option<Config>,
-    http_client: reqwest::Client,
 }
 
 impl SendMessageTool {
@@ -27,7 +27,6 @@ impl SendMessageTool {
             db,
             bot_username,
             config: None,
-            http_client: reqwest::Client::new(),
         }
     }
 
@@ -42,7 +41,6 @@ impl SendMessageTool {
             db,
             bot_username,
             config: Some(config),
-            http_client: reqwest::Client::new(),
         }
     }
 
@@ -63,183 +61,6 @@ impl SendMessageTool {
             .await
             .map_err(|e| format!("Failed to store sent message: {e}"))
     }
-
-    async fn send_telegram_attachment(
-        &self,
-        chat_id: i64,
-        file_path: PathBuf,
-        caption: Option<String>,
-    ) -> Result<String, String> {
-        let mut req = self
-            .bot
-            .send_document(ChatId(chat_id), InputFile::file(file_path.clone()));
-        if let Some(c) = &caption {
-            req = req.caption(c.clone());
-        }
-        req.await
-            .map_err(|e| format!("Failed to send Telegram attachment: {e}"))?;
-
-        Ok(match caption {
-            Some(c) => format!("[attachment:{}] {}", file_path.display(), c),
-            None => format!("[attachment:{}]", file_path.display()),
-        })
-    }
-
-    async fn send_discord_attachment(
-        &self,
-        chat_id: i64,
-        file_path: PathBuf,
-        caption: Option<String>,
-    ) -> Result<String, String> {
-        let cfg = self
-            .config
-            .as_ref()
-            .ok_or_else(|| "send_message config unavailable".to_string())?;
-        let token = cfg
-            .discord_bot_token
-            .as_deref()
-            .filter(|v| !v.trim().is_empty())
-            .ok_or_else(|| "discord_bot_token not configured".to_string())?;
-
-        let filename = file_path
-            .file_name()
-            .and_then(|v| v.to_str())
-            .unwrap_or("attachment.bin")
-            .to_string();
-        let bytes = tokio::fs::read(&file_path)
-            .await
-            .map_err(|e| format!("Failed to read attachment file: {e}"))?;
-
-        let payload = json!({ "content": caption.clone().unwrap_or_default() });
-        let form = reqwest::multipart::Form::new()
-            .text("payload_json", payload.to_string())
-            .part(
-                "files[0]",
-                reqwest::multipart::Part::bytes(bytes).file_name(filename),
-            );
-
-        let url = format!("https://discord.com/api/v10/channels/{chat_id}/messages");
-        let resp = self
-            .http_client
-            .post(url)
-            .header(reqwest::header::AUTHORIZATION, format!("Bot {token}"))
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to send Discord attachment: {e}"))?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!(
-                "Failed to send Discord attachment: HTTP {status} {}",
-                body.chars().take(300).collect::<String>()
-            ));
-        }
-
-        Ok(match caption {
-            Some(c) => format!("[attachment:{}] {}", file_path.display(), c),
-            None => format!("[attachment:{}]", file_path.display()),
-        })
-    }
-
-    async fn send_whatsapp_attachment(
-        &self,
-        chat_id: i64,
-        file_path: PathBuf,
-        caption: Option<String>,
-    ) -> Result<String, String> {
-        let cfg = self
-            .config
-            .as_ref()
-            .ok_or_else(|| "send_message config unavailable".to_string())?;
-        let access_token = cfg
-            .whatsapp_access_token
-            .as_deref()
-            .filter(|v| !v.trim().is_empty())
-            .ok_or_else(|| "whatsapp_access_token not configured".to_string())?;
-        let phone_number_id = cfg
-            .whatsapp_phone_number_id
-            .as_deref()
-            .filter(|v| !v.trim().is_empty())
-            .ok_or_else(|| "whatsapp_phone_number_id not configured".to_string())?;
-
-        let filename = file_path
-            .file_name()
-            .and_then(|v| v.to_str())
-            .unwrap_or("attachment.bin")
-            .to_string();
-        let bytes = tokio::fs::read(&file_path)
-            .await
-            .map_err(|e| format!("Failed to read attachment file: {e}"))?;
-
-        let upload_url = format!("https://graph.facebook.com/v23.0/{phone_number_id}/media");
-        let form = reqwest::multipart::Form::new()
-            .text("messaging_product", "whatsapp")
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(bytes)
-                    .file_name(filename.clone())
-                    .mime_str("application/octet-stream")
-                    .map_err(|e| format!("Invalid attachment mime: {e}"))?,
-            );
-        let upload_resp = self
-            .http_client
-            .post(upload_url)
-            .bearer_auth(access_token)
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to upload WhatsApp media: {e}"))?;
-        if !upload_resp.status().is_success() {
-            let status = upload_resp.status();
-            let body = upload_resp.text().await.unwrap_or_default();
-            return Err(format!(
-                "Failed to upload WhatsApp media: HTTP {status} {}",
-                body.chars().take(300).collect::<String>()
-            ));
-        }
-        let upload_json: serde_json::Value = upload_resp
-            .json()
-            .await
-            .map_err(|e| format!("Invalid WhatsApp media upload response: {e}"))?;
-        let media_id = upload_json
-            .get("id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| "WhatsApp media upload did not return id".to_string())?;
-
-        let mut document = json!({ "id": media_id, "filename": filename });
-        if let Some(c) = &caption {
-            document["caption"] = json!(c);
-        }
-        let payload = json!({
-            "messaging_product": "whatsapp",
-            "to": chat_id.to_string(),
-            "type": "document",
-            "document": document,
-        });
-        let send_url = format!("https://graph.facebook.com/v23.0/{phone_number_id}/messages");
-        let send_resp = self
-            .http_client
-            .post(send_url)
-            .bearer_auth(access_token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| format!("Failed to send WhatsApp attachment: {e}"))?;
-        if !send_resp.status().is_success() {
-            let status = send_resp.status();
-            let body = send_resp.text().await.unwrap_or_default();
-            return Err(format!(
-                "Failed to send WhatsApp attachment: HTTP {status} {}",
-                body.chars().take(300).collect::<String>()
-            ));
-        }
-
-        Ok(match caption {
-            Some(c) => format!("[attachment:{}] {}", file_path.display(), c),
-            None => format!("[attachment:{}]", file_path.display()),
-        })
-    }
 }
 
 #[async_trait]
@@ -273,6 +94,7 @@ impl Tool for SendMessageTool {
                 }),
                 &["chat_id"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -310,6 +132,27 @@ impl Tool for SendMessageTool {
             return ToolResult::error(e);
         }
 
+        let (text, caption) = match self.config.as_ref() {
+            Some(config) => {
+                let ctx = crate::middleware::OutboundContext {
+                    chat_id,
+                    footer: None,
+                    allow_empty: true,
+                };
+                let text =
+                    crate::middleware::run_outbound(config, self.db.clone(), ctx.clone(), text)
+                        .await;
+                let caption = match caption {
+                    Some(c) => Some(
+                        crate::middleware::run_outbound(config, self.db.clone(), ctx, c).await,
+                    ),
+                    None => None,
+                };
+                (text, caption)
+            }
+            None => (text, caption),
+        };
+
         if let Some(path) = attachment_path {
             let chat_type =
                 match call_blocking(self.db.clone(), move |db| db.get_chat_type(chat_id)).await {
@@ -333,29 +176,16 @@ impl Tool for SendMessageTool {
             });
 
             let send_result = match chat_type.as_deref() {
-                Some("telegram_private")
-                | Some("telegram_group")
-                | Some("telegram_supergroup")
-                | Some("telegram_channel")
-                | Some("private")
-                | Some("group")
-                | Some("supergroup")
-                | Some("channel") => {
-                    self.send_telegram_attachment(chat_id, file_path.clone(), used_caption.clone())
-                        .await
-                }
-                Some("discord") => {
-                    self.send_discord_attachment(chat_id, file_path.clone(), used_caption.clone())
-                        .await
-                }
-                Some("whatsapp") => {
-                    self.send_whatsapp_attachment(chat_id, file_path.clone(), used_caption.clone())
+                Some(chat_type) => match channel_for_chat_type(chat_type, &self.bot) {
+                    Ok(channel) => channel
+                        .send_attachment(chat_id, &file_path, used_caption.as_deref())
                         .await
-                }
-                Some("web") => Err("attachment sending is not supported for web chat".to_string()),
-                Some(other) => Err(format!(
-                    "attachment sending is not supported for chat type: {other}"
-                )),
+                        .map(|_| match &used_caption {
+                            Some(c) => format!("[attachment:{}] {}", file_path.display(), c),
+                            None => format!("[attachment:{}]", file_path.display()),
+                        }),
+                    Err(e) => Err(e),
+                },
                 None => Err("target chat not found".to_string()),
             };
 
@@ -529,7 +359,7 @@ mod tests {
             }))
             .await;
         assert!(result.is_error);
-        assert!(result.content.contains("config unavailable"));
+        assert!(result.content.contains("No running channel"));
         cleanup(&dir);
     }
 
@@ -550,7 +380,7 @@ mod tests {
             }))
             .await;
         assert!(result.is_error);
-        assert!(result.content.contains("config unavailable"));
+        assert!(result.content.contains("No running channel"));
         cleanup(&dir);
     }
 }
@@ -0,0 +1,398 @@
+//! Session restore points: snapshot_session captures a chat/persona's stored messages, agent
+//! session, tiered memory (MEMORY.md) and chat settings; restore_session puts them back.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{
+    auth_context_from_input, authorize_chat_persona_access, schema_object, Tool, ToolResult,
+};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database, SessionSnapshot, StoredMessage};
+
+const LIST_LIMIT: usize = 20;
+
+fn memory_path(groups_dir: &Path, chat_id: i64, persona_id: i64) -> PathBuf {
+    groups_dir
+        .join(chat_id.to_string())
+        .join(persona_id.to_string())
+        .join("MEMORY.md")
+}
+
+/// Resolve target chat/persona (default: caller) and check access.
+fn target(input: &serde_json::Value) -> Result<(i64, i64), String> {
+    let auth = auth_context_from_input(input).ok_or_else(|| "Missing auth context".to_string())?;
+    let chat_id = input
+        .get("chat_id")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(auth.caller_chat_id);
+    let persona_id = input
+        .get("persona_id")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(auth.caller_persona_id);
+    authorize_chat_persona_access(input, chat_id, persona_id)?;
+    Ok((chat_id, persona_id))
+}
+
+/// Capture the current state as a snapshot and store it. Returns the snapshot with its counts.
+async fn take_snapshot(
+    db: Arc<Database>,
+    groups_dir: &Path,
+    chat_id: i64,
+    persona_id: i64,
+    label: String,
+) -> Result<(SessionSnapshot, usize), String> {
+    let memory = std::fs::read_to_string(memory_path(groups_dir, chat_id, persona_id)).ok();
+    call_blocking(db, move |d| {
+        let messages = d.get_all_messages(chat_id, persona_id)?;
+        let session = d.load_session(chat_id, persona_id)?.map(|(json, _)| json);
+        let settings = d.list_chat_settings(chat_id)?;
+        let count = messages.len();
+        let data = json!({
+            "messages": messages
+                .iter()
+                .map(|m| json!({
+                    "id": m.id,
+                    "sender_name": m.sender_name,
+                    "content": m.content,
+                    "is_from_bot": m.is_from_bot,
+                    "timestamp": m.timestamp,
+                }))
+                .collect::<Vec<_>>(),
+            "session": session,
+            "memory": memory,
+            "settings": settings,
+        });
+        let snapshot = SessionSnapshot {
+            id: uuid::Uuid::new_v4().to_string()[..8].to_string(),
+            chat_id,
+            persona_id,
+            label,
+            data: data.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        d.create_session_snapshot(&snapshot)?;
+        Ok((snapshot, count))
+    })
+    .await
+    .map_err(|e| format!("Failed to create snapshot: {e}"))
+}
+
+pub struct SnapshotSessionTool {
+    db: Arc<Database>,
+    groups_dir: PathBuf,
+}
+
+impl SnapshotSessionTool {
+    pub fn new(data_dir: &str, db: Arc<Database>) -> Self {
+        SnapshotSessionTool {
+            db,
+            groups_dir: PathBuf::from(data_dir).join("groups"),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SnapshotSessionTool {
+    fn name(&self) -> &str {
+        "snapshot_session"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "snapshot_session".into(),
+            description: "Create a restore point of this chat: stored message history, the current conversation session, tiered memory (MEMORY.md) and chat settings. Use before risky prompt experiments or bulk memory edits; restore with restore_session. Action 'list' shows existing snapshots.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["create", "list"],
+                        "description": "create (default) or list"
+                    },
+                    "label": {
+                        "type": "string",
+                        "description": "Short description of the restore point"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat ID (default: current chat)"
+                    },
+                    "persona_id": {
+                        "type": "integer",
+                        "description": "Persona ID (default: current persona)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let (chat_id, persona_id) = match target(&input) {
+            Ok(t) => t,
+            Err(e) => return ToolResult::error(e),
+        };
+        let action = input
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("create");
+        match action {
+            "list" => {
+                match call_blocking(self.db.clone(), move |d| {
+                    d.list_session_snapshots(chat_id, persona_id, LIST_LIMIT)
+                })
+                .await
+                {
+                    Ok(list) if list.is_empty() => {
+                        ToolResult::success("No snapshots for this chat.".into())
+                    }
+                    Ok(list) => ToolResult::success(
+                        list.iter()
+                            .map(|s| {
+                                format!(
+                                    "[{}] {} {}",
+                                    s.id,
+                                    s.created_at.get(..16).unwrap_or(&s.created_at),
+                                    s.label
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                    Err(e) => ToolResult::error(format!("Failed to list snapshots: {e}")),
+                }
+            }
+            "create" => {
+                let label = input
+                    .get("label")
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or("manual snapshot")
+                    .to_string();
+                match take_snapshot(self.db.clone(), &self.groups_dir, chat_id, persona_id, label)
+                    .await
+                {
+                    Ok((snapshot, count)) => ToolResult::success(format!(
+                        "Snapshot {} created ({count} messages, session, memory and settings). Restore with restore_session.",
+                        snapshot.id
+                    )),
+                    Err(e) => ToolResult::error(e),
+                }
+            }
+            other => ToolResult::error(format!("Unknown action '{other}'. Use create or list.")),
+        }
+    }
+}
+
+pub struct RestoreSessionTool {
+    db: Arc<Database>,
+    groups_dir: PathBuf,
+}
+
+impl RestoreSessionTool {
+    pub fn new(data_dir: &str, db: Arc<Database>) -> Self {
+        RestoreSessionTool {
+            db,
+            groups_dir: PathBuf::from(data_dir).join("groups"),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RestoreSessionTool {
+    fn name(&self) -> &str {
+        "restore_session"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "restore_session".into(),
+            description: "Restore a chat to a snapshot taken with snapshot_session: replaces the stored message history, conversation session, tiered memory and chat settings. The current state is snapshotted first so the restore can be undone.".into(),
+            input_schema: schema_object(
+                json!({
+                    "snapshot_id": {
+                        "type": "string",
+                        "description": "Snapshot ID from snapshot_session"
+                    }
+                }),
+                &["snapshot_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(id) = input
+            .get("snapshot_id")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+        else {
+            return ToolResult::error("Missing required parameter: snapshot_id".into());
+        };
+        let lookup = id.clone();
+        let snapshot =
+            match call_blocking(self.db.clone(), move |d| d.get_session_snapshot(&lookup)).await {
+                Ok(Some(s)) => s,
+                Ok(None) => return ToolResult::error(format!("Snapshot {id} not found")),
+                Err(e) => return ToolResult::error(format!("Failed to load snapshot: {e}")),
+            };
+        let (chat_id, persona_id) = (snapshot.chat_id, snapshot.persona_id);
+        if let Err(e) = authorize_chat_persona_access(&input, chat_id, persona_id) {
+            return ToolResult::error(e);
+        }
+        let data: serde_json::Value = match serde_json::from_str(&snapshot.data) {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(format!("Snapshot {id} is corrupt: {e}")),
+        };
+
+        let backup = match take_snapshot(
+            self.db.clone(),
+            &self.groups_dir,
+            chat_id,
+            persona_id,
+            format!("before restoring {id}"),
+        )
+        .await
+        {
+            Ok((backup, _)) => backup,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        let messages: Vec<StoredMessage> = data["messages"]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .map(|m| StoredMessage {
+                        id: m["id"].as_str().unwrap_or_default().to_string(),
+                        chat_id,
+                        persona_id,
+                        sender_name: m["sender_name"].as_str().unwrap_or_default().to_string(),
+                        content: m["content"].as_str().unwrap_or_default().to_string(),
+                        is_from_bot: m["is_from_bot"].as_bool().unwrap_or(false),
+                        timestamp: m["timestamp"].as_str().unwrap_or_default().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let count = messages.len();
+        let session = data["session"].as_str().map(String::from);
+        let settings: Vec<(String, String)> =
+            serde_json::from_value(data["settings"].clone()).unwrap_or_default();
+        if let Err(e) = call_blocking(self.db.clone(), move |d| {
+            d.replace_chat_messages(chat_id, persona_id, &messages)?;
+            match session {
+                Some(json) => d.save_session(chat_id, persona_id, &json)?,
+                None => {
+                    d.delete_session(chat_id, persona_id)?;
+                }
+            }
+            d.replace_chat_settings(chat_id, &settings)
+        })
+        .await
+        {
+            return ToolResult::error(format!(
+                "Failed to restore snapshot {id}: {e} (previous state saved as {})",
+                backup.id
+            ));
+        }
+
+        let path = memory_path(&self.groups_dir, chat_id, persona_id);
+        let memory_result = match data["memory"].as_str() {
+            Some(content) => path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(&path, content)),
+            None if path.exists() => std::fs::remove_file(&path),
+            None => Ok(()),
+        };
+        if let Err(e) = memory_result {
+            return ToolResult::error(format!(
+                "Restored history from {id}, but failed to restore memory: {e} (previous state saved as {})",
+                backup.id
+            ));
+        }
+
+        ToolResult::success(format!(
+            "Restored snapshot {id} ({}; {count} messages). Previous state saved as {}.",
+            snapshot.label, backup.id
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore_round_trip() {
+        let dir = std::env::temp_dir().join(format!("microclaw_snap_{}", uuid::Uuid::new_v4()));
+        let data_dir = dir.to_str().unwrap();
+        let db = Arc::new(Database::new(data_dir).unwrap());
+        let store = |id: &str, content: &str| StoredMessage {
+            id: id.into(),
+            chat_id: 7,
+            persona_id: 1,
+            sender_name: "alice".into(),
+            content: content.into(),
+            is_from_bot: false,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        db.store_message(&store("m1", "original")).unwrap();
+        db.save_session(7, 1, "[]").unwrap();
+        db.set_chat_setting(7, "usage_footer", Some("on")).unwrap();
+        let mem = memory_path(&dir.join("groups"), 7, 1);
+        std::fs::create_dir_all(mem.parent().unwrap()).unwrap();
+        std::fs::write(&mem, "## Tier 1 — Long term\nlikes tea\n").unwrap();
+
+        let auth = json!({"caller_chat_id": 7, "caller_persona_id": 1, "control_chat_ids": []});
+        let snap = SnapshotSessionTool::new(data_dir, db.clone());
+        let created = snap
+            .execute(json!({"label": "before cleanup", "__microclaw_auth": auth}))
+            .await;
+        assert!(!created.is_error, "{}", created.content);
+        let id = created
+            .content
+            .split_whitespace()
+            .nth(1)
+            .unwrap()
+            .to_string();
+
+        db.store_message(&store("m2", "after")).unwrap();
+        db.set_chat_setting(7, "usage_footer", None).unwrap();
+        std::fs::write(&mem, "wiped").unwrap();
+
+        let other = json!({"caller_chat_id": 8, "caller_persona_id": 1, "control_chat_ids": []});
+        let restore = RestoreSessionTool::new(data_dir, db.clone());
+        let denied = restore
+            .execute(json!({"snapshot_id": id, "__microclaw_auth": other}))
+            .await;
+        assert!(denied.is_error);
+
+        let restored = restore
+            .execute(json!({"snapshot_id": id, "__microclaw_auth": auth}))
+            .await;
+        assert!(!restored.is_error, "{}", restored.content);
+        let messages = db.get_all_messages(7, 1).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "original");
+        assert_eq!(
+            db.get_chat_setting(7, "usage_footer").unwrap().as_deref(),
+            Some("on")
+        );
+        assert!(std::fs::read_to_string(&mem).unwrap().contains("likes tea"));
+
+        let listed = snap
+            .execute(json!({"action": "list", "__microclaw_auth": auth}))
+            .await;
+        assert!(listed.content.contains("before cleanup"));
+        assert!(listed.content.contains(&format!("before restoring {id}")));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
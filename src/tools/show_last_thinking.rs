@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::Database;
+use crate::thinking::last_thinking;
+
+const MAX_COUNT: u64 = 5;
+
+pub struct ShowLastThinkingTool {
+    db: Arc<Database>,
+}
+
+impl ShowLastThinkingTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        ShowLastThinkingTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for ShowLastThinkingTool {
+    fn name(&self) -> &str {
+        "show_last_thinking"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "show_last_thinking".into(),
+            description: "Show the reasoning behind your most recent replies in this chat. It is kept out of the conversation history, so use this when the user asks why you answered the way you did or how you got to a result.".into(),
+            input_schema: schema_object(
+                json!({
+                    "count": {
+                        "type": "integer",
+                        "description": "How many recent replies to include (default 1, max 5)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(auth) = auth_context_from_input(&input) else {
+            return ToolResult::error("show_last_thinking needs the calling chat".into());
+        };
+        let count = input
+            .get("count")
+            .and_then(|v| v.as_u64())
+            .filter(|c| *c > 0)
+            .unwrap_or(1)
+            .min(MAX_COUNT);
+        match last_thinking(self.db.clone(), auth.caller_chat_id, count as usize).await {
+            Ok(text) => ToolResult::success(text),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
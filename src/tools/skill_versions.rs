@@ -0,0 +1,296 @@
+//! Skill folder snapshots under `skills/.versions/<name>/<timestamp>`, taken before
+//! `build_skill` or `sync_skills` changes a skill, so a bad update can be reverted from chat with
+//! `rollback_skill`.
+
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database};
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+
+/// Folder inside the skills directory holding saved versions (never discovered as a skill).
+pub(crate) const VERSIONS_DIR: &str = ".versions";
+
+fn valid_skill_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains('/')
+        && !name.contains('\\')
+        && !name.contains("..")
+}
+
+fn copy_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = dst.join(entry.file_name());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy `skills_dir/<name>` to `skills_dir/.versions/<name>/<timestamp>` and record it. Returns
+/// `None` when the skill folder does not exist or is empty (nothing to save).
+pub(crate) async fn snapshot_skill(
+    skills_dir: &Path,
+    name: &str,
+    reason: &str,
+    db: Option<Arc<Database>>,
+    chat_id: Option<i64>,
+) -> Result<Option<PathBuf>, String> {
+    if !valid_skill_name(name) {
+        return Err(format!("Invalid skill name '{name}'"));
+    }
+    let skill_dir = skills_dir.join(name);
+    let has_files = std::fs::read_dir(&skill_dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if !has_files {
+        return Ok(None);
+    }
+    let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%3fZ").to_string();
+    let versions_dir = skills_dir.join(VERSIONS_DIR).join(name);
+    let mut version_dir = versions_dir.join(&timestamp);
+    // Two snapshots in the same millisecond must not share (and overwrite) a folder.
+    let mut n = 1;
+    while version_dir.exists() {
+        version_dir = versions_dir.join(format!("{timestamp}-{n}"));
+        n += 1;
+    }
+    copy_dir(&skill_dir, &version_dir)
+        .map_err(|e| format!("Failed to save a version of skill '{name}': {e}"))?;
+    info!(
+        "Saved skill '{}' version at {}",
+        name,
+        version_dir.display()
+    );
+
+    if let Some(db) = db {
+        let skill_name = name.to_string();
+        let version_path = version_dir.to_string_lossy().to_string();
+        let reason = reason.to_string();
+        call_blocking(db, move |d| {
+            d.record_skill_version(&skill_name, &version_path, &reason, chat_id)
+        })
+        .await
+        .map_err(|e| format!("Failed to record skill version: {e}"))?;
+    }
+    Ok(Some(version_dir))
+}
+
+pub struct RollbackSkillTool {
+    skills_dir: PathBuf,
+    db: Arc<Database>,
+}
+
+impl RollbackSkillTool {
+    pub fn new(skills_dir: &str, db: Arc<Database>) -> Self {
+        RollbackSkillTool {
+            skills_dir: PathBuf::from(skills_dir),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RollbackSkillTool {
+    fn name(&self) -> &str {
+        "rollback_skill"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "rollback_skill".into(),
+            description: "List or restore saved versions of a skill. A version is saved automatically before build_skill or sync_skills changes a skill. action 'list' shows saved versions (newest first); action 'rollback' restores a version (default: the newest) after saving the current state, so a rollback can itself be undone.".into(),
+            input_schema: schema_object(
+                json!({
+                    "skill_name": {
+                        "type": "string",
+                        "description": "Skill folder name"
+                    },
+                    "action": {
+                        "type": "string",
+                        "enum": ["list", "rollback"],
+                        "description": "list or rollback (default: rollback)"
+                    },
+                    "version_id": {
+                        "type": "integer",
+                        "description": "Version to restore (from action 'list'); omit for the newest"
+                    }
+                }),
+                &["skill_name"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let name = input
+            .get("skill_name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or("")
+            .to_string();
+        if !valid_skill_name(&name) {
+            return ToolResult::error("Missing or invalid 'skill_name'".into());
+        }
+        let action = input
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("rollback");
+        let version_id = input.get("version_id").and_then(|v| v.as_i64());
+
+        let lookup_name = name.clone();
+        let versions = match call_blocking(self.db.clone(), move |d| {
+            d.list_skill_versions(&lookup_name, 20)
+        })
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(format!("Failed to list skill versions: {e}")),
+        };
+
+        match action {
+            "list" => {
+                if versions.is_empty() {
+                    return ToolResult::success(format!("No saved versions of skill '{name}'."));
+                }
+                let mut out = format!("Saved versions of skill '{name}':\n");
+                for v in &versions {
+                    out.push_str(&format!(
+                        "#{} {} (before {})\n",
+                        v.id, v.created_at, v.reason
+                    ));
+                }
+                ToolResult::success(out)
+            }
+            "rollback" => {
+                let version = match version_id {
+                    Some(id) => call_blocking(self.db.clone(), move |d| d.get_skill_version(id))
+                        .await
+                        .ok()
+                        .flatten()
+                        .filter(|v| v.skill_name == name),
+                    None => versions.into_iter().next(),
+                };
+                let Some(version) = version else {
+                    return ToolResult::error(match version_id {
+                        Some(id) => format!("Skill '{name}' has no saved version #{id}."),
+                        None => format!("No saved versions of skill '{name}'."),
+                    });
+                };
+                let version_dir = PathBuf::from(&version.version_path);
+                if !version_dir.is_dir() {
+                    return ToolResult::error(format!(
+                        "Version #{} of skill '{name}' is missing on disk ({}).",
+                        version.id,
+                        version_dir.display()
+                    ));
+                }
+
+                let chat_id = auth_context_from_input(&input).map(|a| a.caller_chat_id);
+                let backup = match snapshot_skill(
+                    &self.skills_dir,
+                    &name,
+                    "rollback",
+                    Some(self.db.clone()),
+                    chat_id,
+                )
+                .await
+                {
+                    Ok(b) => b,
+                    Err(e) => return ToolResult::error(e),
+                };
+
+                let skill_dir = self.skills_dir.join(&name);
+                if skill_dir.exists() {
+                    if let Err(e) = std::fs::remove_dir_all(&skill_dir) {
+                        return ToolResult::error(format!("Failed to clear skill folder: {e}"));
+                    }
+                }
+                if let Err(e) = copy_dir(&version_dir, &skill_dir) {
+                    return ToolResult::error(format!("Failed to restore skill '{name}': {e}"));
+                }
+                info!("Rolled back skill '{}' to version #{}", name, version.id);
+
+                let mut out = format!(
+                    "Skill '{name}' rolled back to version #{} ({}).",
+                    version.id, version.created_at
+                );
+                if backup.is_some() {
+                    out.push_str(" The replaced state was saved as a new version.");
+                }
+                ToolResult::success(out)
+            }
+            other => ToolResult::error(format!(
+                "Unknown action '{other}'. Use 'list' or 'rollback'."
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_snapshot_and_rollback_skill() {
+        let dir = std::env::temp_dir().join(format!("microclaw_skillver_{}", uuid::Uuid::new_v4()));
+        let skills_dir = dir.join("skills");
+        let skill_md = skills_dir.join("demo").join("SKILL.md");
+        std::fs::create_dir_all(skill_md.parent().unwrap()).unwrap();
+        std::fs::write(&skill_md, "v1").unwrap();
+        let db = Arc::new(Database::new(dir.join("db").to_str().unwrap()).unwrap());
+
+        let saved = snapshot_skill(
+            &skills_dir,
+            "demo",
+            "build_skill",
+            Some(db.clone()),
+            Some(1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(saved.starts_with(skills_dir.join(VERSIONS_DIR).join("demo")));
+        assert!(
+            snapshot_skill(&skills_dir, "missing", "sync_skills", None, None)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        std::fs::write(&skill_md, "v2 broken").unwrap();
+        std::fs::write(skills_dir.join("demo").join("extra.sh"), "x").unwrap();
+
+        let tool = RollbackSkillTool::new(skills_dir.to_str().unwrap(), db.clone());
+        let listed = tool
+            .execute(json!({"skill_name": "demo", "action": "list"}))
+            .await;
+        assert!(
+            listed.content.contains("(before build_skill)"),
+            "{}",
+            listed.content
+        );
+
+        let result = tool.execute(json!({"skill_name": "demo"})).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(std::fs::read_to_string(&skill_md).unwrap(), "v1");
+        assert!(!skills_dir.join("demo").join("extra.sh").exists());
+
+        let versions = db.list_skill_versions("demo", 10).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].reason, "rollback");
+        assert!(tool.execute(json!({"skill_name": "../etc"})).await.is_error);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::Database;
+use crate::turn_metrics::slow_report;
+
+const DEFAULT_HOURS: u64 = 24;
+const DEFAULT_LIMIT: u64 = 10;
+const MAX_LIMIT: u64 = 50;
+
+pub struct SlowReportTool {
+    db: Arc<Database>,
+}
+
+impl SlowReportTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        SlowReportTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for SlowReportTool {
+    fn name(&self) -> &str {
+        "slow_report"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "slow_report".into(),
+            description: "List the slowest recent replies with a per-stage breakdown (queue wait, database, context building, each LLM call, each tool, delivery) and the share of time spent in each, to tell whether latency comes from the model, tools or the database. Only available from control chats.".into(),
+            input_schema: schema_object(
+                json!({
+                    "hours": {
+                        "type": "integer",
+                        "description": "How many hours back to look (default 24)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Only turns in this chat (default: all chats)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "How many turns to list (default 10, max 50)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(auth) = auth_context_from_input(&input) {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: slow reports are only available from a control chat".into(),
+                );
+            }
+        }
+        let hours = input
+            .get("hours")
+            .and_then(|v| v.as_u64())
+            .filter(|h| *h > 0)
+            .unwrap_or(DEFAULT_HOURS);
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .filter(|l| *l > 0)
+            .unwrap_or(DEFAULT_LIMIT)
+            .min(MAX_LIMIT);
+        let chat_id = input.get("chat_id").and_then(|v| v.as_i64());
+        match slow_report(self.db.clone(), hours, chat_id, limit as usize).await {
+            Ok(report) => ToolResult::success(report),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_slow_report_requires_control_chat() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_slow_tool_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let tool = SlowReportTool::new(db);
+
+        let out = tool
+            .execute(json!({"__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [1]}}))
+            .await;
+        assert!(out.is_error);
+
+        let out = tool
+            .execute(json!({"hours": 6, "__microclaw_auth": {"caller_chat_id": 1, "control_chat_ids": [1]}}))
+            .await;
+        assert_eq!(out.content, "No timed turns in the last 6h.");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,269 @@
+//! `list_connections` / `disconnect_platform`: let a chat see which social platforms it has
+//! authorized and revoke them.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::social_monitor::platform_label;
+use crate::social_oauth;
+
+const PLATFORMS: [&str; 5] = ["tiktok", "instagram", "linkedin", "google", "spotify"];
+
+fn target_chat(input: &serde_json::Value) -> Result<i64, String> {
+    let chat_id = input
+        .get("chat_id")
+        .and_then(|v| v.as_i64())
+        .or_else(|| auth_context_from_input(input).map(|a| a.caller_chat_id))
+        .ok_or_else(|| "Missing chat_id".to_string())?;
+    authorize_chat_access(input, chat_id)?;
+    Ok(chat_id)
+}
+
+pub struct ListConnectionsTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl ListConnectionsTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        ListConnectionsTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ListConnectionsTool {
+    fn name(&self) -> &str {
+        "list_connections"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_connections".into(),
+            description: "List the accounts (TikTok, Instagram, LinkedIn, Google, Spotify) this chat has authorized via OAuth, with granted scopes, when access was granted and when the token expires. Also lists configured platforms that are not connected yet.".into(),
+            input_schema: schema_object(
+                json!({
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to inspect (default: current chat)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match target_chat(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        let tokens = match call_blocking(self.db.clone(), move |d| {
+            d.list_social_tokens(Some(chat_id))
+        })
+        .await
+        {
+            Ok(t) => t,
+            Err(e) => return ToolResult::error(format!("Failed to load connections: {e}")),
+        };
+
+        let mut out = String::new();
+        for token in &tokens {
+            let info = social_oauth::connection_json(token);
+            let scopes = info["scopes"]
+                .as_array()
+                .map(|s| {
+                    s.iter()
+                        .filter_map(|v| v.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "unknown".into());
+            let expiry = match (token.expires_at.as_deref(), info["expired"].as_bool()) {
+                (Some(at), Some(true)) => format!("expired {at}"),
+                (Some(at), _) => format!("expires {at}"),
+                (None, _) => "no expiry reported".into(),
+            };
+            out.push_str(&format!(
+                "- {}: connected{}; {expiry}; scopes: {scopes}\n",
+                platform_label(&token.platform),
+                token
+                    .connected_at
+                    .as_deref()
+                    .map(|at| format!(" {at}"))
+                    .unwrap_or_default()
+            ));
+        }
+        let available: Vec<&str> = PLATFORMS
+            .iter()
+            .filter(|p| {
+                self.config
+                    .social
+                    .as_ref()
+                    .is_some_and(|s| s.is_platform_enabled(p))
+                    && !tokens.iter().any(|t| t.platform == **p)
+            })
+            .map(|p| platform_label(p))
+            .collect();
+        if out.is_empty() {
+            out.push_str("No social platforms are connected for this chat.\n");
+        }
+        if !available.is_empty() {
+            out.push_str(&format!(
+                "Not connected (available): {}\n",
+                available.join(", ")
+            ));
+        }
+        ToolResult::success(out.trim_end().to_string())
+    }
+}
+
+pub struct DisconnectPlatformTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl DisconnectPlatformTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        DisconnectPlatformTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DisconnectPlatformTool {
+    fn name(&self) -> &str {
+        "disconnect_platform"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "disconnect_platform".into(),
+            description: "Disconnect a social platform from this chat: revokes the OAuth token with the provider where supported (TikTok, LinkedIn, Google) and deletes the stored token. Feed, Gmail/Drive, Spotify tools and monitoring for that platform stop until the user authorizes again.".into(),
+            input_schema: schema_object(
+                json!({
+                    "platform": {
+                        "type": "string",
+                        "enum": PLATFORMS,
+                        "description": "Platform to disconnect"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to disconnect (default: current chat)"
+                    }
+                }),
+                &["platform"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let platform = input
+            .get("platform")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .unwrap_or_default();
+        if !PLATFORMS.contains(&platform.as_str()) {
+            return ToolResult::error(format!(
+                "Unknown platform '{platform}'. Use tiktok, instagram or linkedin."
+            ));
+        }
+        let chat_id = match target_chat(&input) {
+            Ok(id) => id,
+            Err(e) => return ToolResult::error(e),
+        };
+        let label = platform_label(&platform);
+        match social_oauth::disconnect(&self.config, self.db.clone(), &platform, chat_id).await {
+            Ok(Some(revocation)) => ToolResult::success(format!(
+                "{label} disconnected ({}).",
+                revocation.describe(label)
+            )),
+            Ok(None) => ToolResult::error(format!("{label} is not connected for this chat.")),
+            Err(e) => ToolResult::error(format!("Failed to disconnect {label}: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_and_disconnect_connections() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_social_conn_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.social = Some(crate::config::SocialConfig {
+            linkedin: crate::config::SocialPlatformConfig {
+                client_id: Some("id".into()),
+                client_secret: Some("secret".into()),
+            },
+            ..Default::default()
+        });
+        db.upsert_social_token(
+            "instagram",
+            8,
+            "tok",
+            None,
+            None,
+            Some("instagram_basic,user_media"),
+        )
+        .unwrap();
+        let auth = json!({"caller_chat_id": 8, "caller_channel": "web"});
+
+        let list = ListConnectionsTool::new(&config, db.clone());
+        let result = list.execute(json!({"__microclaw_auth": auth})).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(
+            result
+                .content
+                .contains("no expiry reported; scopes: instagram_basic, user_media"),
+            "{}",
+            result.content
+        );
+        assert!(
+            result
+                .content
+                .contains("Not connected (available): LinkedIn"),
+            "{}",
+            result.content
+        );
+        let denied = list
+            .execute(json!({"chat_id": 9, "__microclaw_auth": auth}))
+            .await;
+        assert!(denied.is_error);
+
+        let disconnect = DisconnectPlatformTool::new(&config, db.clone());
+        let result = disconnect
+            .execute(json!({"platform": "instagram", "__microclaw_auth": auth}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(
+            result.content.contains("no token revoke API"),
+            "{}",
+            result.content
+        );
+        assert!(db.get_social_token("instagram", 8).unwrap().is_none());
+        let again = disconnect
+            .execute(json!({"platform": "instagram", "__microclaw_auth": auth}))
+            .await;
+        assert!(again.is_error);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
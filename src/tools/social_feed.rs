@@ -14,8 +14,10 @@ use crate::social_oauth;
 
 fn authorize_msg(platform: &str, url: &str) -> String {
     format!(
-        "To fetch your {} feed, you must authorize first. Click this link to connect: {}",
-        platform, url
+        "To fetch your {} feed, you must authorize first. Click this link to connect (valid for {} minutes, single use): {}",
+        platform,
+        social_oauth::STATE_TTL_MINUTES,
+        url
     )
 }
 
@@ -33,7 +35,7 @@ async fn get_token_or_authorize(
     let chat_id = auth.caller_chat_id;
     let platform_owned = platform.to_string();
 
-    let token_opt = match call_blocking(db, move |db| db.get_social_token(&platform_owned, chat_id)).await {
+    let token_opt = match call_blocking(db.clone(), move |db| db.get_social_token(&platform_owned, chat_id)).await {
         Ok(opt) => opt.map(|t| t.access_token),
         Err(e) => return Err(ToolResult::error(e.to_string())),
     };
@@ -42,17 +44,232 @@ async fn get_token_or_authorize(
         return Ok(t);
     }
 
-    let base = social_oauth::oauth_base_url(config)
-        .unwrap_or_else(|| "http://127.0.0.1:10961".into());
-    let auth_path = format!("{}/api/oauth/authorize/{}", base.trim_end_matches('/'), platform);
-    let url = format!(
-        "{}?chat_id={}",
-        auth_path,
-        urlencoding::encode(&chat_id.to_string())
-    );
+    let url = match social_oauth::start_authorization(config, db, platform, chat_id, None).await {
+        Ok(u) => u,
+        Err(e) => return Err(ToolResult::error(e.to_string())),
+    };
     Err(ToolResult::error(authorize_msg(platform, &url)))
 }
 
+pub(crate) fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// One page of the caller's TikTok videos and the cursor for the next page.
+pub(crate) async fn fetch_tiktok_videos(
+    client: &reqwest::Client,
+    token: &str,
+    max_count: i64,
+    cursor: Option<&str>,
+) -> Result<(Vec<serde_json::Value>, Option<String>), String> {
+    let mut body = json!({
+        "max_count": max_count,
+        "fields": "id,title,create_time,cover_image_url,video_description,view_count,like_count,comment_count,share_count,duration,embed_link"
+    });
+    if let Some(c) = cursor {
+        body["cursor"] = json!(c);
+    }
+
+    let resp = client
+        .post("https://open.tiktokapis.com/v2/video/list/")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+
+    if !status.is_success() {
+        let err = body
+            .get("error")
+            .and_then(|e| e.get("message"))
+            .and_then(|m| m.as_str())
+            .unwrap_or("API request failed");
+        return Err(format!("TikTok API error: {}", err));
+    }
+
+    let videos = body
+        .get("data")
+        .and_then(|d| d.get("videos"))
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let next_cursor = body
+        .get("data")
+        .and_then(|d| d.get("cursor"))
+        .and_then(|c| c.as_str())
+        .map(String::from);
+    Ok((videos, next_cursor))
+}
+
+/// One page of the caller's Instagram media and the cursor for the next page.
+pub(crate) async fn fetch_instagram_media(
+    client: &reqwest::Client,
+    token: &str,
+    limit: i64,
+    cursor: Option<&str>,
+) -> Result<(Vec<serde_json::Value>, Option<String>), String> {
+    // Instagram Graph API: need ig-user-id first. Get /me?fields=id to get user id, then /{id}/media
+    let me: serde_json::Value = client
+        .get("https://graph.instagram.com/me")
+        .query(&[("fields", "id"), ("access_token", token)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(err) = me.get("error") {
+        let msg = err
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Instagram API error");
+        return Err(format!("Instagram API: {}", msg));
+    }
+
+    let user_id = me
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Could not get Instagram user ID".to_string())?;
+
+    let limit_str = limit.to_string();
+    let mut params = vec![
+        (
+            "fields",
+            "id,caption,media_type,media_url,permalink,timestamp,like_count,comments_count",
+        ),
+        ("limit", limit_str.as_str()),
+    ];
+    if let Some(c) = cursor {
+        params.push(("after", c));
+    }
+
+    let media: serde_json::Value = client
+        .get(format!("https://graph.instagram.com/{}/media", user_id))
+        .query(&params)
+        .query(&[("access_token", token)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let data = media
+        .get("data")
+        .and_then(|d| d.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let next = media
+        .get("paging")
+        .and_then(|p| p.get("cursors"))
+        .and_then(|c| c.get("after"))
+        .and_then(|a| a.as_str())
+        .map(String::from);
+    Ok((data, next))
+}
+
+/// The caller's most recent LinkedIn posts.
+pub(crate) async fn fetch_linkedin_posts(
+    client: &reqwest::Client,
+    token: &str,
+    count: i64,
+) -> Result<Vec<serde_json::Value>, String> {
+    // LinkedIn Posts API: GET /posts with author URN
+    // First get current user URN via /me
+    let me: serde_json::Value = client
+        .get("https://api.linkedin.com/v2/me")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Linkedin-Version", "202401")
+        .header("X-Restli-Protocol-Version", "2.0.0")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let id = me
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Could not get LinkedIn user ID".to_string())?;
+    let author_urn = format!("urn:li:person:{}", id);
+
+    let count_str = count.to_string();
+    let params = [
+        ("author", author_urn.as_str()),
+        ("count", count_str.as_str()),
+    ];
+
+    let posts: serde_json::Value = client
+        .get("https://api.linkedin.com/rest/posts")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Linkedin-Version", "202401")
+        .header("X-Restli-Protocol-Version", "2.0.0")
+        .query(&params)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(posts
+        .get("elements")
+        .and_then(|e| e.as_array())
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// Follower count of the caller's account, where the platform exposes it to this token
+/// (TikTok needs the user.info.stats scope, Instagram a professional account). `None` when the
+/// platform has no follower count in its API (LinkedIn personal profiles).
+pub(crate) async fn fetch_follower_count(
+    client: &reqwest::Client,
+    platform: &str,
+    token: &str,
+) -> Result<Option<i64>, String> {
+    match platform {
+        "tiktok" => {
+            let body: serde_json::Value = client
+                .get("https://open.tiktokapis.com/v2/user/info/")
+                .query(&[("fields", "follower_count")])
+                .header("Authorization", format!("Bearer {}", token))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?
+                .json()
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(body
+                .get("data")
+                .and_then(|d| d.get("user"))
+                .and_then(|u| u.get("follower_count"))
+                .and_then(|c| c.as_i64()))
+        }
+        "instagram" => {
+            let me: serde_json::Value = client
+                .get("https://graph.instagram.com/me")
+                .query(&[("fields", "followers_count"), ("access_token", token)])
+                .send()
+                .await
+                .map_err(|e| e.to_string())?
+                .json()
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(me.get("followers_count").and_then(|c| c.as_i64()))
+        }
+        _ => Ok(None),
+    }
+}
+
 // --- TikTok ---
 
 pub struct FetchTiktokFeedTool {
@@ -96,6 +313,7 @@ impl Tool for FetchTiktokFeedTool {
                 }),
                 &[],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -118,61 +336,15 @@ impl Tool for FetchTiktokFeedTool {
             .clamp(1, 20) as i64;
         let cursor = input.get("cursor").and_then(|v| v.as_str());
 
-        let client = match reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-        {
+        let client = match http_client() {
             Ok(c) => c,
-            Err(e) => return ToolResult::error(e.to_string()),
+            Err(e) => return ToolResult::error(e),
         };
-
-        let mut body = json!({
-            "max_count": max_count,
-            "fields": "id,title,create_time,cover_image_url,video_description,view_count,like_count,comment_count,share_count,duration,embed_link"
-        });
-        if let Some(c) = cursor {
-            body["cursor"] = json!(c);
-        }
-
-        let resp = match client
-            .post("https://open.tiktokapis.com/v2/video/list/")
-            .header("Authorization", format!("Bearer {}", token))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-
-        let status = resp.status();
-        let body: serde_json::Value = match resp.json().await {
-            Ok(b) => b,
-            Err(e) => return ToolResult::error(e.to_string()),
+        let (videos, next_cursor) = match fetch_tiktok_videos(&client, &token, max_count, cursor).await {
+            Ok(page) => page,
+            Err(e) => return ToolResult::error(e),
         };
 
-        if !status.is_success() {
-            let err = body
-                .get("error")
-                .and_then(|e| e.get("message"))
-                .and_then(|m| m.as_str())
-                .unwrap_or("API request failed");
-            return ToolResult::error(format!("TikTok API error: {}", err));
-        }
-
-        let videos = body
-            .get("data")
-            .and_then(|d| d.get("videos"))
-            .and_then(|v| v.as_array())
-            .cloned()
-            .unwrap_or_default();
-        let next_cursor = body
-            .get("data")
-            .and_then(|d| d.get("cursor"))
-            .and_then(|c| c.as_str())
-            .map(String::from);
-
         let mut out = serde_json::Map::new();
         out.insert("videos".into(), json!(videos));
         if let Some(c) = next_cursor {
@@ -227,6 +399,7 @@ impl Tool for FetchInstagramFeedTool {
                 }),
                 &[],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -242,78 +415,21 @@ impl Tool for FetchInstagramFeedTool {
             Err(e) => return e,
         };
 
-        // Instagram Graph API: need ig-user-id first. Get /me?fields=id to get user id, then /{id}/media
-        let client = match reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-        {
-            Ok(c) => c,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-
-        let resp = match client
-            .get("https://graph.instagram.com/me")
-            .query(&[("fields", "id"), ("access_token", &token)])
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-        let me: serde_json::Value = match resp.json().await {
-            Ok(j) => j,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-
-        if let Some(err) = me.get("error") {
-            let msg = err
-                .get("message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("Instagram API error");
-            return ToolResult::error(format!("Instagram API: {}", msg));
-        }
-
-        let user_id = match me.get("id").and_then(|v| v.as_str()) {
-            Some(s) => s,
-            None => return ToolResult::error("Could not get Instagram user ID".into()),
-        };
-
         let limit = input
             .get("max_items")
             .and_then(|v| v.as_i64())
             .unwrap_or(10)
             .clamp(1, 50);
-        let limit_str = limit.to_string();
-        let mut params = vec![
-            ("fields", "id,caption,media_type,media_url,permalink,timestamp"),
-            ("limit", limit_str.as_str()),
-        ];
-        if let Some(c) = input.get("cursor").and_then(|v| v.as_str()) {
-            params.push(("after", c));
-        }
-
-        let media_resp = match client
-            .get(format!("https://graph.instagram.com/{}/media", user_id))
-            .query(&params)
-            .query(&[("access_token", token)])
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => return ToolResult::error(e.to_string()),
+        let cursor = input.get("cursor").and_then(|v| v.as_str());
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
         };
-        let media: serde_json::Value = match media_resp.json().await {
-            Ok(j) => j,
-            Err(e) => return ToolResult::error(e.to_string()),
+        let (data, next) = match fetch_instagram_media(&client, &token, limit, cursor).await {
+            Ok(page) => page,
+            Err(e) => return ToolResult::error(e),
         };
 
-        let data = media.get("data").and_then(|d| d.as_array()).cloned().unwrap_or_default();
-        let next = media.get("paging")
-            .and_then(|p| p.get("cursors"))
-            .and_then(|c| c.get("after"))
-            .and_then(|a| a.as_str())
-            .map(String::from);
-
         let mut out = serde_json::Map::new();
         out.insert("media".into(), json!(data));
         if let Some(n) = next {
@@ -364,6 +480,7 @@ impl Tool for FetchLinkedinFeedTool {
                 }),
                 &[],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -379,73 +496,20 @@ impl Tool for FetchLinkedinFeedTool {
             Err(e) => return e,
         };
 
-        // LinkedIn Posts API: GET /posts with author URN
-        // First get current user URN via /me
-        let client = match reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-        {
-            Ok(c) => c,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-
-        let me_resp = match client
-            .get("https://api.linkedin.com/v2/me")
-            .header("Authorization", format!("Bearer {}", token))
-            .header("Linkedin-Version", "202401")
-            .header("X-Restli-Protocol-Version", "2.0.0")
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-        let me: serde_json::Value = match me_resp.json().await {
-            Ok(j) => j,
-            Err(e) => return ToolResult::error(e.to_string()),
-        };
-
-        let id = match me.get("id").and_then(|v| v.as_str()) {
-            Some(s) => s,
-            None => return ToolResult::error("Could not get LinkedIn user ID".into()),
-        };
-        let author_urn = format!("urn:li:person:{}", id);
-
         let count = input
             .get("max_items")
             .and_then(|v| v.as_i64())
             .unwrap_or(10)
             .clamp(1, 100);
-
-        let count_str = count.to_string();
-        let params = [
-            ("author", author_urn.as_str()),
-            ("count", count_str.as_str()),
-        ];
-
-        let posts_resp = match client
-            .get("https://api.linkedin.com/rest/posts")
-            .header("Authorization", format!("Bearer {}", token))
-            .header("Linkedin-Version", "202401")
-            .header("X-Restli-Protocol-Version", "2.0.0")
-            .query(&params)
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => return ToolResult::error(e.to_string()),
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
         };
-        let posts: serde_json::Value = match posts_resp.json().await {
-            Ok(j) => j,
-            Err(e) => return ToolResult::error(e.to_string()),
+        let elements = match fetch_linkedin_posts(&client, &token, count).await {
+            Ok(posts) => posts,
+            Err(e) => return ToolResult::error(e),
         };
 
-        let elements = posts
-            .get("elements")
-            .and_then(|e| e.as_array())
-            .cloned()
-            .unwrap_or_default();
-
         let mut out = serde_json::Map::new();
         out.insert("posts".into(), json!(elements));
         out.insert("count".into(), json!(elements.len()));
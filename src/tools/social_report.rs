@@ -0,0 +1,535 @@
+//! `social_report`: metrics trends for the feeds a chat connected, built from the snapshots the
+//! social feed monitor stores on every poll.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{
+    call_blocking, Database, SocialFollowerCount, SocialPostSnapshot, SocialSnapshotRow,
+};
+use crate::social_monitor::{format_count, platform_label};
+
+const METRICS: [&str; 4] = ["views", "likes", "comments", "shares"];
+const TOP_POSTS: usize = 3;
+const CHART_WIDTH: f64 = 640.0;
+const CHART_HEIGHT: f64 = 320.0;
+const CHART_MARGIN: f64 = 40.0;
+const CHART_COLORS: [&str; 4] = ["#ff0050", "#c13584", "#0a66c2", "#2e7d32"];
+
+fn metric(post: &SocialPostSnapshot, i: usize) -> Option<i64> {
+    match i {
+        0 => post.views,
+        1 => post.likes,
+        2 => post.comments,
+        _ => post.shares,
+    }
+}
+
+fn add(acc: &mut Option<i64>, v: Option<i64>) {
+    if let Some(v) = v {
+        *acc = Some(acc.unwrap_or(0) + v);
+    }
+}
+
+fn signed(n: i64) -> String {
+    if n < 0 {
+        format!("-{}", format_count(n.unsigned_abs()))
+    } else {
+        format!("+{}", format_count(n as u64))
+    }
+}
+
+/// Aggregated metrics of one platform over the report range.
+#[derive(Debug, Default)]
+struct PlatformSummary {
+    polls: usize,
+    posts: usize,
+    /// Sum over posts in the latest poll.
+    totals: [Option<i64>; 4],
+    /// Sum over posts of (last - first) observation in range.
+    gains: [Option<i64>; 4],
+    /// (first, last) follower count in range.
+    followers: Option<(i64, i64)>,
+    /// (title, url, gain of the chart metric), largest gain first.
+    top: Vec<(String, Option<String>, i64)>,
+}
+
+impl PlatformSummary {
+    /// Index of the metric used for top posts and the chart: views where the platform reports
+    /// them, likes otherwise.
+    fn primary_metric(&self) -> usize {
+        if self.totals[0].is_some() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+fn summarize(
+    rows: &[SocialSnapshotRow],
+    followers: &[SocialFollowerCount],
+) -> BTreeMap<String, PlatformSummary> {
+    let mut by_platform: BTreeMap<String, Vec<&SocialSnapshotRow>> = BTreeMap::new();
+    for row in rows {
+        by_platform
+            .entry(row.platform.clone())
+            .or_default()
+            .push(row);
+    }
+
+    let mut out = BTreeMap::new();
+    for (platform, rows) in by_platform {
+        let mut summary = PlatformSummary::default();
+        let latest = rows
+            .last()
+            .map(|r| r.captured_at.clone())
+            .unwrap_or_default();
+        let mut polls: Vec<&str> = rows.iter().map(|r| r.captured_at.as_str()).collect();
+        polls.dedup();
+        summary.polls = polls.len();
+
+        // post_id -> (first, last) observation; rows are oldest first.
+        let mut posts: BTreeMap<&str, (&SocialPostSnapshot, &SocialPostSnapshot)> = BTreeMap::new();
+        for row in &rows {
+            posts
+                .entry(row.post.post_id.as_str())
+                .and_modify(|e| e.1 = &row.post)
+                .or_insert((&row.post, &row.post));
+            if row.captured_at == latest {
+                for (i, total) in summary.totals.iter_mut().enumerate() {
+                    add(total, metric(&row.post, i));
+                }
+            }
+        }
+        summary.posts = posts.len();
+        let primary = summary.primary_metric();
+        for (first, last) in posts.values() {
+            for (i, gain) in summary.gains.iter_mut().enumerate() {
+                if let (Some(a), Some(b)) = (metric(first, i), metric(last, i)) {
+                    add(gain, Some(b - a));
+                }
+            }
+            if let (Some(a), Some(b)) = (metric(first, primary), metric(last, primary)) {
+                if b > a {
+                    summary
+                        .top
+                        .push((last.title.clone(), last.url.clone(), b - a));
+                }
+            }
+        }
+        summary.top.sort_by_key(|t| std::cmp::Reverse(t.2));
+        summary.top.truncate(TOP_POSTS);
+
+        let counts: Vec<i64> = followers
+            .iter()
+            .filter(|f| f.platform == platform)
+            .map(|f| f.followers)
+            .collect();
+        if let (Some(first), Some(last)) = (counts.first(), counts.last()) {
+            summary.followers = Some((*first, *last));
+        }
+        out.insert(platform, summary);
+    }
+    out
+}
+
+fn render_report(summaries: &BTreeMap<String, PlatformSummary>, days: i64) -> String {
+    let mut out = format!(
+        "Social report — last {days} day{}\n",
+        if days == 1 { "" } else { "s" }
+    );
+    for (platform, s) in summaries {
+        out.push_str(&format!(
+            "\n{} ({} poll{}, {} post{} tracked)\n",
+            platform_label(platform),
+            s.polls,
+            if s.polls == 1 { "" } else { "s" },
+            s.posts,
+            if s.posts == 1 { "" } else { "s" }
+        ));
+        for (i, name) in METRICS.iter().enumerate() {
+            let Some(total) = s.totals[i] else {
+                continue;
+            };
+            out.push_str(&format!(
+                "- {name}: {} ({} in range)\n",
+                format_count(total.max(0) as u64),
+                signed(s.gains[i].unwrap_or(0))
+            ));
+        }
+        if let Some((first, last)) = s.followers {
+            out.push_str(&format!(
+                "- followers: {} ({} in range)\n",
+                format_count(last.max(0) as u64),
+                signed(last - first)
+            ));
+        }
+        if !s.top.is_empty() {
+            out.push_str("Top posts:\n");
+            for (n, (title, url, gain)) in s.top.iter().enumerate() {
+                let title = title.lines().next().unwrap_or("").trim();
+                let title = if title.is_empty() {
+                    "(untitled)"
+                } else {
+                    title
+                };
+                out.push_str(&format!(
+                    "{}. {title} — {} {}",
+                    n + 1,
+                    signed(*gain),
+                    METRICS[s.primary_metric()]
+                ));
+                if let Some(url) = url {
+                    out.push_str(&format!(" {url}"));
+                }
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Line chart of each platform's primary metric total per poll over the range.
+fn render_chart_svg(
+    rows: &[SocialSnapshotRow],
+    summaries: &BTreeMap<String, PlatformSummary>,
+    since: chrono::DateTime<chrono::Utc>,
+    until: chrono::DateTime<chrono::Utc>,
+) -> String {
+    // platform -> captured_at -> total
+    let mut series: BTreeMap<&str, BTreeMap<&str, i64>> = BTreeMap::new();
+    for row in rows {
+        let primary = summaries
+            .get(&row.platform)
+            .map(|s| s.primary_metric())
+            .unwrap_or(0);
+        if let Some(v) = metric(&row.post, primary) {
+            *series
+                .entry(row.platform.as_str())
+                .or_default()
+                .entry(row.captured_at.as_str())
+                .or_insert(0) += v;
+        }
+    }
+    let max = series
+        .values()
+        .flat_map(|s| s.values())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let span = (until - since).num_seconds().max(1) as f64;
+    let plot_w = CHART_WIDTH - 2.0 * CHART_MARGIN;
+    let plot_h = CHART_HEIGHT - 2.0 * CHART_MARGIN;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n\
+         <line x1=\"{m}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#999\"/>\n\
+         <line x1=\"{m}\" y1=\"{m}\" x2=\"{m}\" y2=\"{b}\" stroke=\"#999\"/>\n\
+         <text x=\"{m}\" y=\"{t}\">{}</text>\n\
+         <text x=\"{m}\" y=\"{l}\">{}</text>\n\
+         <text x=\"{r}\" y=\"{l}\" text-anchor=\"end\">{}</text>\n",
+        format_count(max as u64),
+        since.format("%Y-%m-%d"),
+        until.format("%Y-%m-%d"),
+        m = CHART_MARGIN,
+        b = CHART_HEIGHT - CHART_MARGIN,
+        r = CHART_WIDTH - CHART_MARGIN,
+        t = CHART_MARGIN - 8.0,
+        l = CHART_HEIGHT - CHART_MARGIN + 16.0,
+    );
+    for (n, (platform, points)) in series.iter().enumerate() {
+        let color = CHART_COLORS[n % CHART_COLORS.len()];
+        let coords: Vec<String> = points
+            .iter()
+            .filter_map(|(at, v)| {
+                let at = chrono::DateTime::parse_from_rfc3339(at).ok()?;
+                let x = CHART_MARGIN
+                    + plot_w * (at.with_timezone(&chrono::Utc) - since).num_seconds() as f64 / span;
+                let y = CHART_HEIGHT - CHART_MARGIN - plot_h * (*v as f64) / max;
+                Some(format!("{x:.1},{y:.1}"))
+            })
+            .collect();
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"2\" points=\"{}\"/>\n",
+            coords.join(" ")
+        ));
+        let metric_name = summaries
+            .get(*platform)
+            .map(|s| METRICS[s.primary_metric()])
+            .unwrap_or("views");
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"{color}\">{} ({metric_name})</text>\n",
+            CHART_WIDTH - CHART_MARGIN - 160.0,
+            CHART_MARGIN + 16.0 * n as f64,
+            platform_label(platform)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub struct SocialReportTool {
+    db: Arc<Database>,
+    reports_dir: PathBuf,
+}
+
+impl SocialReportTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        SocialReportTool {
+            db,
+            reports_dir: config
+                .workspace_root_absolute()
+                .join("shared")
+                .join("social_reports"),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SocialReportTool {
+    fn name(&self) -> &str {
+        "social_report"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "social_report".into(),
+            description: "Report social media metrics for the chat's connected platforms over a time range: current totals and change in views, likes, comments and shares, follower change where the platform exposes it, and the top posts. Built from the snapshots taken by the social feed monitor. Optionally renders a line chart (SVG) to the workspace and returns its path.".into(),
+            input_schema: schema_object(
+                json!({
+                    "days": {
+                        "type": "integer",
+                        "description": "Report range in days, ending now (default 7, max 365)"
+                    },
+                    "platform": {
+                        "type": "string",
+                        "enum": ["tiktok", "instagram", "linkedin"],
+                        "description": "Only report this platform"
+                    },
+                    "chart": {
+                        "type": "boolean",
+                        "description": "Also render a chart image of the trend (default false)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat whose feeds to report (default: current chat)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth_context_from_input(&input).map(|a| a.caller_chat_id))
+        {
+            Some(id) => id,
+            None => return ToolResult::error("Missing chat_id".into()),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        let days = input
+            .get("days")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(7)
+            .clamp(1, 365);
+        let platform = input
+            .get("platform")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty());
+        let chart = input
+            .get("chart")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let until = chrono::Utc::now();
+        let since = until - chrono::Duration::days(days);
+        let (since_s, until_s) = (since.to_rfc3339(), until.to_rfc3339());
+        let data = call_blocking(self.db.clone(), move |d| {
+            Ok((
+                d.social_snapshots_between(chat_id, &since_s, &until_s)?,
+                d.social_follower_counts_between(chat_id, &since_s, &until_s)?,
+            ))
+        })
+        .await;
+        let (mut rows, mut followers) = match data {
+            Ok(d) => d,
+            Err(e) => return ToolResult::error(format!("Failed to load social snapshots: {e}")),
+        };
+        if let Some(ref p) = platform {
+            rows.retain(|r| &r.platform == p);
+            followers.retain(|f| &f.platform == p);
+        }
+        if rows.is_empty() {
+            return ToolResult::success(format!(
+                "No social snapshots for this chat in the last {days} days. Snapshots are taken by the social feed monitor (social.monitor_interval_mins) for platforms the chat has authorized."
+            ));
+        }
+
+        let summaries = summarize(&rows, &followers);
+        let mut report = render_report(&summaries, days);
+        if chart {
+            let svg = render_chart_svg(&rows, &summaries, since, until);
+            let path = self.reports_dir.join(format!(
+                "social_report_{chat_id}_{}.svg",
+                until.format("%Y%m%d_%H%M%S")
+            ));
+            let written =
+                std::fs::create_dir_all(&self.reports_dir).and_then(|_| std::fs::write(&path, svg));
+            match written {
+                Ok(()) => report.push_str(&format!("\nChart: {}\n", path.display())),
+                Err(e) => report.push_str(&format!("\nFailed to write chart: {e}\n")),
+            }
+        }
+        ToolResult::success(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(
+        platform: &str,
+        at: &str,
+        id: &str,
+        views: Option<i64>,
+        likes: i64,
+    ) -> SocialSnapshotRow {
+        SocialSnapshotRow {
+            platform: platform.into(),
+            captured_at: at.into(),
+            post: SocialPostSnapshot {
+                post_id: id.into(),
+                title: format!("Post {id}"),
+                views,
+                likes: Some(likes),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_summarize_totals_gains_and_top_posts() {
+        let rows = vec![
+            snap("tiktok", "2026-01-01T00:00:00Z", "a", Some(100), 5),
+            snap("tiktok", "2026-01-01T00:00:00Z", "b", Some(10), 1),
+            snap("tiktok", "2026-01-02T00:00:00Z", "a", Some(1_600), 9),
+            snap("tiktok", "2026-01-02T00:00:00Z", "b", Some(40), 1),
+            snap("instagram", "2026-01-02T00:00:00Z", "m", None, 7),
+        ];
+        let followers = vec![
+            SocialFollowerCount {
+                platform: "tiktok".into(),
+                followers: 900,
+                captured_at: "2026-01-01T00:00:00Z".into(),
+            },
+            SocialFollowerCount {
+                platform: "tiktok".into(),
+                followers: 1_200,
+                captured_at: "2026-01-02T00:00:00Z".into(),
+            },
+        ];
+        let summaries = summarize(&rows, &followers);
+        let tiktok = &summaries["tiktok"];
+        assert_eq!(tiktok.polls, 2);
+        assert_eq!(tiktok.totals[0], Some(1_640));
+        assert_eq!(tiktok.gains[0], Some(1_530));
+        assert_eq!(tiktok.gains[1], Some(4));
+        assert_eq!(tiktok.top[0].0, "Post a");
+        assert_eq!(summaries["instagram"].primary_metric(), 1);
+
+        let report = render_report(&summaries, 7);
+        assert!(
+            report.contains("TikTok (2 polls, 2 posts tracked)"),
+            "{report}"
+        );
+        assert!(
+            report.contains("- views: 1.6k (+1.5k in range)"),
+            "{report}"
+        );
+        assert!(
+            report.contains("- followers: 1.2k (+300 in range)"),
+            "{report}"
+        );
+        assert!(report.contains("1. Post a — +1.5k views"), "{report}");
+        assert!(
+            report.contains("Instagram (1 poll, 1 post tracked)\n- likes: 7 (+0 in range)"),
+            "{report}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_social_report_tool_with_chart() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_social_report_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.join("db").to_str().unwrap()).unwrap());
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = dir.join("workspace").to_string_lossy().to_string();
+        let tool = SocialReportTool::new(&config, db.clone());
+
+        let empty = tool.execute(json!({"chat_id": 4})).await;
+        assert!(
+            empty.content.starts_with("No social snapshots"),
+            "{}",
+            empty.content
+        );
+
+        let now = chrono::Utc::now();
+        for (hours_ago, views) in [(30, 10), (2, 250)] {
+            let at = (now - chrono::Duration::hours(hours_ago)).to_rfc3339();
+            let post = SocialPostSnapshot {
+                post_id: "v1".into(),
+                title: "Clip".into(),
+                views: Some(views),
+                ..Default::default()
+            };
+            db.insert_social_snapshots("tiktok", 4, &[post], &at)
+                .unwrap();
+        }
+        let result = tool
+            .execute(json!({
+                "chart": true,
+                "__microclaw_auth": {"caller_chat_id": 4, "caller_channel": "web"}
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("+240"), "{}", result.content);
+        let chart_path = result
+            .content
+            .lines()
+            .last()
+            .unwrap()
+            .trim_start_matches("Chart: ");
+        assert!(std::fs::read_to_string(chart_path)
+            .unwrap()
+            .contains("<polyline"));
+
+        let denied = tool
+            .execute(json!({
+                "chat_id": 5,
+                "__microclaw_auth": {"caller_chat_id": 4, "caller_channel": "web"}
+            }))
+            .await;
+        assert!(denied.is_error);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,453 @@
+//! Spotify playback tools: `play_music`, `pause`, `now_playing`.
+//! Control the chat's own Spotify account through the social OAuth token store. Starting or
+//! pausing playback needs Spotify Premium and an active device (app open on a phone, computer
+//! or speaker).
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::social_feed::http_client;
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::social_oauth;
+
+const SPOTIFY_API: &str = "https://api.spotify.com/v1";
+const SEARCH_TYPES: [&str; 4] = ["track", "album", "playlist", "artist"];
+
+async fn spotify_token_or_authorize(
+    config: &Config,
+    db: Arc<Database>,
+    input: &serde_json::Value,
+) -> Result<String, ToolResult> {
+    let Some(auth) = auth_context_from_input(input) else {
+        return Err(ToolResult::error("Missing auth context".into()));
+    };
+    let chat_id = auth.caller_chat_id;
+    let token = call_blocking(db.clone(), move |d| d.get_social_token("spotify", chat_id))
+        .await
+        .map_err(|e| ToolResult::error(e.to_string()))?;
+
+    if let Some(token) = token {
+        return social_oauth::fresh_access_token(config, db, &token)
+            .await
+            .map_err(|e| {
+                ToolResult::error(format!(
+                    "Spotify token refresh failed ({e}). Disconnect Spotify and authorize again."
+                ))
+            });
+    }
+
+    let url = social_oauth::start_authorization(config, db, "spotify", chat_id, None)
+        .await
+        .map_err(|e| ToolResult::error(e.to_string()))?;
+    Err(ToolResult::error(format!(
+        "To control playback, connect your Spotify account first. Click this link (valid for {} minutes, single use): {url}",
+        social_oauth::STATE_TTL_MINUTES
+    )))
+}
+
+/// Call the Web API. Returns `None` for empty (204) responses.
+async fn spotify_request(
+    request: reqwest::RequestBuilder,
+    token: &str,
+) -> Result<Option<serde_json::Value>, String> {
+    let resp = request
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    let body: Option<serde_json::Value> = serde_json::from_str(&text).ok();
+    if !status.is_success() {
+        let reason = body
+            .as_ref()
+            .and_then(|b| b.pointer("/error/reason"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if reason == "NO_ACTIVE_DEVICE" {
+            return Err(
+                "No active Spotify device. Open Spotify on a phone, computer or speaker and try again."
+                    .into(),
+            );
+        }
+        if reason == "PREMIUM_REQUIRED" {
+            return Err("Playback control requires Spotify Premium.".into());
+        }
+        let msg = body
+            .as_ref()
+            .and_then(|b| b.pointer("/error/message"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("request failed");
+        return Err(format!("Spotify API error (HTTP {status}): {msg}"));
+    }
+    Ok(body)
+}
+
+fn format_duration_ms(ms: i64) -> String {
+    let secs = ms.max(0) / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// "Name — Artist, Artist (Album)" for tracks; "Name — Show" for episodes; plain name otherwise.
+pub(crate) fn describe_item(item: &serde_json::Value) -> String {
+    let name = item
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Unknown");
+    let artists: Vec<&str> = item
+        .get("artists")
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|x| x.get("name").and_then(|v| v.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(show) = item.pointer("/show/name").and_then(|v| v.as_str()) {
+        return format!("{name} — {show}");
+    }
+    let mut out = name.to_string();
+    if !artists.is_empty() {
+        out.push_str(&format!(" — {}", artists.join(", ")));
+    }
+    if let Some(album) = item.pointer("/album/name").and_then(|v| v.as_str()) {
+        out.push_str(&format!(" ({album})"));
+    }
+    out
+}
+
+/// Human-readable summary of a currently-playing response (`None` = nothing playing).
+pub(crate) fn now_playing_text(body: Option<&serde_json::Value>) -> String {
+    let Some(item) = body.and_then(|b| b.get("item")).filter(|i| !i.is_null()) else {
+        return "Nothing is playing on Spotify right now.".into();
+    };
+    let body = body.unwrap_or(&serde_json::Value::Null);
+    let playing = body
+        .get("is_playing")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let mut out = format!(
+        "{}: {}",
+        if playing { "Now playing" } else { "Paused" },
+        describe_item(item)
+    );
+    if let (Some(progress), Some(duration)) = (
+        body.get("progress_ms").and_then(|v| v.as_i64()),
+        item.get("duration_ms").and_then(|v| v.as_i64()),
+    ) {
+        out.push_str(&format!(
+            " [{} / {}]",
+            format_duration_ms(progress),
+            format_duration_ms(duration)
+        ));
+    }
+    if let Some(url) = item
+        .pointer("/external_urls/spotify")
+        .and_then(|v| v.as_str())
+    {
+        out.push_str(&format!("\n{url}"));
+    }
+    out
+}
+
+/// Body for `PUT /me/player/play`: tracks play as a list of URIs, everything else as a context.
+pub(crate) fn play_body(uri: &str) -> serde_json::Value {
+    if uri.starts_with("spotify:track:") || uri.starts_with("spotify:episode:") {
+        json!({ "uris": [uri] })
+    } else {
+        json!({ "context_uri": uri })
+    }
+}
+
+fn with_device(
+    request: reqwest::RequestBuilder,
+    input: &serde_json::Value,
+) -> reqwest::RequestBuilder {
+    match input
+        .get("device_id")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+    {
+        Some(device) => request.query(&[("device_id", device.trim())]),
+        None => request,
+    }
+}
+
+pub struct PlayMusicTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl PlayMusicTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        PlayMusicTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for PlayMusicTool {
+    fn name(&self) -> &str {
+        "play_music"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "play_music".into(),
+            description: "Play music on the user's Spotify. With 'query', searches Spotify (track by default, or album/playlist/artist via 'type') and plays the best match; with 'uri', plays that Spotify URI; with neither, resumes playback. Plays on the active device unless 'device_id' is given. The first use returns a link to connect Spotify.".into(),
+            input_schema: schema_object(
+                json!({
+                    "query": {
+                        "type": "string",
+                        "description": "What to play, e.g. 'bohemian rhapsody' or 'lofi beats'"
+                    },
+                    "type": {
+                        "type": "string",
+                        "enum": SEARCH_TYPES,
+                        "description": "What to search for (default: track)"
+                    },
+                    "uri": {
+                        "type": "string",
+                        "description": "Spotify URI to play (e.g. spotify:album:...)"
+                    },
+                    "device_id": {
+                        "type": "string",
+                        "description": "Spotify device id (default: the active device)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let query = input
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let uri = input
+            .get("uri")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        let kind = input
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("track");
+        if !SEARCH_TYPES.contains(&kind) {
+            return ToolResult::error(format!(
+                "Invalid type '{kind}'. Use one of: {}",
+                SEARCH_TYPES.join(", ")
+            ));
+        }
+        if uri.is_some_and(|u| !u.starts_with("spotify:")) {
+            return ToolResult::error("'uri' must be a Spotify URI (spotify:...)".into());
+        }
+
+        let token = match spotify_token_or_authorize(&self.config, self.db.clone(), &input).await {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        let (target, label) = match (uri, query) {
+            (Some(u), _) => (Some(u.to_string()), u.to_string()),
+            (None, Some(q)) => {
+                let search = client.get(format!("{SPOTIFY_API}/search")).query(&[
+                    ("q", q),
+                    ("type", kind),
+                    ("limit", "1"),
+                ]);
+                let body = match spotify_request(search, &token).await {
+                    Ok(b) => b.unwrap_or_default(),
+                    Err(e) => return ToolResult::error(e),
+                };
+                let Some(item) = body
+                    .pointer(&format!("/{kind}s/items"))
+                    .and_then(|v| v.as_array())
+                    .and_then(|items| items.iter().find(|i| !i.is_null()))
+                else {
+                    return ToolResult::success(format!("No Spotify {kind} matches '{q}'."));
+                };
+                let Some(found) = item.get("uri").and_then(|v| v.as_str()) else {
+                    return ToolResult::error("Spotify search result has no URI".into());
+                };
+                (Some(found.to_string()), describe_item(item))
+            }
+            (None, None) => (None, String::new()),
+        };
+
+        let mut request = with_device(client.put(format!("{SPOTIFY_API}/me/player/play")), &input);
+        if let Some(ref target) = target {
+            request = request.json(&play_body(target));
+        } else {
+            // Spotify's play endpoint wants a (possibly empty) JSON body to resume.
+            request = request.json(&json!({}));
+        }
+        if let Err(e) = spotify_request(request, &token).await {
+            return ToolResult::error(e);
+        }
+        if target.is_some() {
+            ToolResult::success(format!("Playing {label} on Spotify."))
+        } else {
+            ToolResult::success("Resumed Spotify playback.".into())
+        }
+    }
+}
+
+pub struct PauseMusicTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl PauseMusicTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        PauseMusicTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for PauseMusicTool {
+    fn name(&self) -> &str {
+        "pause"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "pause".into(),
+            description: "Pause playback on the user's Spotify (active device unless 'device_id' is given). Use play_music without arguments to resume.".into(),
+            input_schema: schema_object(
+                json!({
+                    "device_id": {
+                        "type": "string",
+                        "description": "Spotify device id (default: the active device)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let token = match spotify_token_or_authorize(&self.config, self.db.clone(), &input).await {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+        let request = with_device(client.put(format!("{SPOTIFY_API}/me/player/pause")), &input);
+        match spotify_request(request, &token).await {
+            Ok(_) => ToolResult::success("Paused Spotify playback.".into()),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+pub struct NowPlayingTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl NowPlayingTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        NowPlayingTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for NowPlayingTool {
+    fn name(&self) -> &str {
+        "now_playing"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "now_playing".into(),
+            description: "Show what is playing on the user's Spotify: track or episode, artists, album, progress and link.".into(),
+            input_schema: schema_object(json!({}), &[]),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let token = match spotify_token_or_authorize(&self.config, self.db.clone(), &input).await {
+            Ok(t) => t,
+            Err(r) => return r,
+        };
+        let client = match http_client() {
+            Ok(c) => c,
+            Err(e) => return ToolResult::error(e),
+        };
+        let request = client
+            .get(format!("{SPOTIFY_API}/me/player/currently-playing"))
+            .query(&[("additional_types", "track,episode")]);
+        match spotify_request(request, &token).await {
+            Ok(body) => ToolResult::success(now_playing_text(body.as_ref())),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now_playing_text() {
+        let body = json!({
+            "is_playing": true,
+            "progress_ms": 65_000,
+            "item": {
+                "name": "Bohemian Rhapsody",
+                "duration_ms": 354_000,
+                "artists": [{"name": "Queen"}],
+                "album": {"name": "A Night at the Opera"},
+                "external_urls": {"spotify": "https://open.spotify.com/track/x"}
+            }
+        });
+        assert_eq!(
+            now_playing_text(Some(&body)),
+            "Now playing: Bohemian Rhapsody — Queen (A Night at the Opera) [1:05 / 5:54]\nhttps://open.spotify.com/track/x"
+        );
+        let episode =
+            json!({"is_playing": false, "item": {"name": "Ep 1", "show": {"name": "Pod"}}});
+        assert_eq!(now_playing_text(Some(&episode)), "Paused: Ep 1 — Pod");
+        assert_eq!(
+            now_playing_text(None),
+            "Nothing is playing on Spotify right now."
+        );
+    }
+
+    #[test]
+    fn test_play_body_uses_uris_for_tracks_and_context_otherwise() {
+        assert_eq!(
+            play_body("spotify:track:abc"),
+            json!({"uris": ["spotify:track:abc"]})
+        );
+        assert_eq!(
+            play_body("spotify:playlist:xyz"),
+            json!({"context_uri": "spotify:playlist:xyz"})
+        );
+    }
+}
@@ -47,6 +47,7 @@ impl Tool for SubAgentTool {
                 }),
                 &["task"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -202,6 +203,7 @@ mod tests {
             api_key: "key".into(),
             model: "claude-test".into(),
             llm_base_url: None,
+            llm_fallbacks: Vec::new(),
             max_tokens: 4096,
             max_tool_iterations: 100,
             max_history_messages: 50,
@@ -213,20 +215,42 @@ mod tests {
             control_chat_ids: vec![],
             max_session_messages: 40,
             compact_keep_recent: 20,
+            memory_flush_idle_minutes: 30,
+            memory_flush_model: String::new(),
+            onboarding_enabled: true,
+            task_max_retries: 2,
+            task_retry_backoff_secs: 60,
+            scheduler_max_concurrent_tasks: 4,
+            deleted_session_retention_days: 30,
             whatsapp_access_token: None,
             whatsapp_phone_number_id: None,
             whatsapp_verify_token: None,
             whatsapp_webhook_port: 8080,
             discord_bot_token: None,
             discord_allowed_channels: vec![],
-            show_thinking: false,
+            slack_bot_token: None,
+            slack_app_token: None,
+            slack_allowed_channels: vec![],
+            signal: None,
+            teams: None,
+            wyoming: None,
+            home_assistant: None,
+            python: None,
+            browser_downloads: None,
+            web_embed: None,
+            transcription: None,
+            calendar: None,
+            show_thinking: crate::config::ThinkingLevel::Off,
             web_enabled: false,
             web_host: "127.0.0.1".into(),
             web_port: 3900,
             web_auth_token: None,
+            web_trusted_proxies: vec![],
             web_max_inflight_per_session: 2,
             web_max_requests_per_window: 8,
             web_rate_window_seconds: 10,
+            web_max_reads_per_window: 120,
+            web_max_writes_per_window: 30,
             web_run_history_limit: 512,
             web_session_idle_ttl_seconds: 300,
             browser_managed: false,
@@ -238,10 +262,39 @@ mod tests {
             cursor_agent_cli_path: "cursor-agent".into(),
             cursor_agent_model: String::new(),
             cursor_agent_timeout_secs: 600,
+            cursor_agent_review: true,
+            cursor_agent_digest_days: 7,
             social: None,
             vault: None,
             orchestrator_enabled: true,
             orchestrator_model: String::new(),
+            translation: None,
+            public_ask: None,
+            sticker_pack_path: None,
+            output_filter: None,
+            inbound_moderation: None,
+            egress: Default::default(),
+            browser_profiles: Vec::new(),
+            model_prices: Default::default(),
+            experiments: Vec::new(),
+            feedback_report_days: 0,
+            instance_lock: false,
+            feeds: Vec::new(),
+            middleware_hooks: Vec::new(),
+            feature_flags: Default::default(),
+            announce_release_notes: true,
+            anthropic_prompt_caching: true,
+            notifications: None,
+            heartbeat: None,
+            workspace_encryption: None,
+            incidents: None,
+            push: None,
+            provider_files: None,
+            roundtable: None,
+            benchmark: None,
+            chat_archive: None,
+            provider_search: None,
+            telegram_business: None,
         }
     }
 
@@ -278,7 +331,7 @@ mod tests {
         let config = test_config();
         let registry = ToolRegistry::new_sub_agent(&config, None);
         let defs = registry.definitions();
-        assert_eq!(defs.len(), 12);
+        assert_eq!(defs.len(), 16);
     }
 
     #[test]
@@ -298,7 +351,9 @@ mod tests {
         assert!(names.contains(&"web_search"));
         assert!(names.contains(&"web_fetch"));
         assert!(names.contains(&"read_memory"));
+        assert!(names.contains(&"calculate"));
         assert!(names.contains(&"read_tiered_memory"));
+        assert!(names.contains(&"tool_help"));
 
         // Should NOT include
         assert!(!names.contains(&"sub_agent"));
@@ -1,22 +1,33 @@
 use async_trait::async_trait;
 use chrono::Utc;
 use serde_json::json;
+use std::sync::Arc;
 
 use crate::claude::ToolDefinition;
+use crate::db::Database;
 
-use super::{schema_object, Tool, ToolResult};
+use super::skill_versions::snapshot_skill;
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
 
 pub struct SyncSkillsTool {
     skills_dir: std::path::PathBuf,
+    /// Records the version saved before an existing skill is overwritten.
+    db: Option<Arc<Database>>,
 }
 
 impl SyncSkillsTool {
     pub fn new(skills_dir: &str) -> Self {
         Self {
             skills_dir: std::path::PathBuf::from(skills_dir),
+            db: None,
         }
     }
 
+    pub fn with_db(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
     async fn fetch_skill_content(
         source_repo: &str,
         skill_name: &str,
@@ -218,6 +229,7 @@ impl Tool for SyncSkillsTool {
                 }),
                 &["skill_name"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -256,6 +268,20 @@ impl Tool for SyncSkillsTool {
         let normalized =
             Self::normalize_skill_markdown(&raw, source_repo, git_ref, skill_name, target_name);
 
+        let chat_id = auth_context_from_input(&input).map(|a| a.caller_chat_id);
+        let saved_version = match snapshot_skill(
+            &self.skills_dir,
+            target_name,
+            "sync_skills",
+            self.db.clone(),
+            chat_id,
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => return ToolResult::error(e).with_error_type("sync_write_failed"),
+        };
+
         let out_dir = self.skills_dir.join(target_name);
         if let Err(e) = std::fs::create_dir_all(&out_dir) {
             return ToolResult::error(format!("Failed to create skill directory: {e}"))
@@ -268,14 +294,18 @@ impl Tool for SyncSkillsTool {
                 .with_error_type("sync_write_failed");
         }
 
-        ToolResult::success(format!(
+        let mut out = format!(
             "Skill synced: {} -> {}\nSource: {}@{}\nPath: {}",
             skill_name,
             target_name,
             source_repo,
             git_ref,
             out_file.display()
-        ))
+        );
+        if saved_version.is_some() {
+            out.push_str("\nPrevious version saved; use rollback_skill to restore it.");
+        }
+        ToolResult::success(out)
     }
 }
 
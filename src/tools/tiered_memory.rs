@@ -9,13 +9,13 @@ use crate::claude::ToolDefinition;
 
 use super::{auth_context_from_input, authorize_chat_persona_access, schema_object, Tool, ToolResult};
 
-const TIER_HEADERS: [(u8, &str); 3] = [
+pub(crate) const TIER_HEADERS: [(u8, &str); 3] = [
     (1, "## Tier 1 — Long term"),
     (2, "## Tier 2 — Mid term"),
     (3, "## Tier 3 — Short term"),
 ];
 
-fn memory_path(groups_dir: &Path, chat_id: i64, persona_id: i64) -> PathBuf {
+pub(crate) fn memory_path(groups_dir: &Path, chat_id: i64, persona_id: i64) -> PathBuf {
     groups_dir
         .join(chat_id.to_string())
         .join(persona_id.to_string())
@@ -23,7 +23,7 @@ fn memory_path(groups_dir: &Path, chat_id: i64, persona_id: i64) -> PathBuf {
 }
 
 /// Parse MEMORY.md and extract one tier's content (between its header and the next ## or EOF).
-fn parse_tier_content(full: &str, tier: u8) -> String {
+pub(crate) fn parse_tier_content(full: &str, tier: u8) -> String {
     let header = TIER_HEADERS
         .iter()
         .find(|(n, _)| *n == tier)
@@ -53,7 +53,7 @@ fn parse_tier_content(full: &str, tier: u8) -> String {
 }
 
 /// Replace content for one tier in the full markdown; preserve others. Creates template if needed.
-fn replace_tier_content(full: &str, tier: u8, new_content: &str) -> String {
+pub(crate) fn replace_tier_content(full: &str, tier: u8, new_content: &str) -> String {
     let mut out = String::new();
     let header = TIER_HEADERS
         .iter()
@@ -146,6 +146,32 @@ fn replace_tier_content(full: &str, tier: u8, new_content: &str) -> String {
     out
 }
 
+/// Replace one tier in the MEMORY.md at `path`, creating the file from the template if needed.
+pub(crate) fn write_tier(path: &Path, tier: u8, content: &str) -> std::io::Result<()> {
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let new_content = if existing.trim().is_empty() {
+        let mut out = String::from("# Memory\n\n");
+        for (n, h) in &TIER_HEADERS {
+            out.push_str(h);
+            out.push_str("\n\n");
+            if *n == tier {
+                out.push_str(content.trim());
+                if !content.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            out.push('\n');
+        }
+        out
+    } else {
+        replace_tier_content(&existing, tier, content)
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, new_content)
+}
+
 pub struct ReadTieredMemoryTool {
     groups_dir: PathBuf,
 }
@@ -185,6 +211,7 @@ impl Tool for ReadTieredMemoryTool {
                 }),
                 &[],
             ),
+            examples: vec![json!({}), json!({"tier": 2})],
         }
     }
 
@@ -280,6 +307,10 @@ impl Tool for WriteTieredMemoryTool {
                 }),
                 &["tier", "content"],
             ),
+            examples: vec![
+                json!({"tier": 3, "content": "- Focused on the kitchen renovation this week\n- Tired after late nights; keep replies short"}),
+                json!({"tier": 2, "content": "- Kitchen renovation: contractor starts March 10, budget 12k\n- Learning Spanish: 20 min/day on weekdays"}),
+            ],
         }
     }
 
@@ -316,32 +347,7 @@ impl Tool for WriteTieredMemoryTool {
         let path = memory_path(&self.groups_dir, chat_id, persona_id);
         info!("Writing tiered memory tier {}: {}", tier, path.display());
 
-        let existing = std::fs::read_to_string(&path).unwrap_or_default();
-        let new_content = if existing.trim().is_empty() {
-            let mut out = String::from("# Memory\n\n");
-            for (n, h) in &TIER_HEADERS {
-                out.push_str(h);
-                out.push_str("\n\n");
-                if *n == tier {
-                    out.push_str(content.trim());
-                    if !content.ends_with('\n') {
-                        out.push('\n');
-                    }
-                }
-                out.push('\n');
-            }
-            out
-        } else {
-            replace_tier_content(&existing, tier, content)
-        };
-
-        if let Some(parent) = path.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                return ToolResult::error(format!("Failed to create directory: {e}"));
-            }
-        }
-
-        match std::fs::write(&path, new_content) {
+        match write_tier(&path, tier, content) {
             Ok(()) => ToolResult::success(format!("Tier {} updated.", tier)),
             Err(e) => ToolResult::error(format!("Failed to write memory: {e}")),
         }
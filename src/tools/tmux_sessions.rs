@@ -0,0 +1,133 @@
+//! `list_tmux_sessions`: show the bot-managed tmux sessions (and optionally the recent output of
+//! one) from a control chat.
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::tmux::{self, TmuxSession};
+
+const DEFAULT_CAPTURE_LINES: usize = 30;
+const MAX_CAPTURE_LINES: usize = 500;
+
+fn render_sessions(sessions: &[TmuxSession]) -> String {
+    if sessions.is_empty() {
+        return "No bot-managed tmux sessions are running.".into();
+    }
+    let mut out = format!("{} tmux session(s):\n", sessions.len());
+    for s in sessions {
+        let created = chrono::DateTime::from_timestamp(s.created, 0)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| "unknown".into());
+        out.push_str(&format!(
+            "- {} (created {}, {} window{}, {})\n",
+            s.name,
+            created,
+            s.windows,
+            if s.windows == 1 { "" } else { "s" },
+            if s.attached { "attached" } else { "detached" }
+        ));
+    }
+    out
+}
+
+pub struct ListTmuxSessionsTool;
+
+#[async_trait]
+impl Tool for ListTmuxSessionsTool {
+    fn name(&self) -> &str {
+        "list_tmux_sessions"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_tmux_sessions".into(),
+            description: format!(
+                "List the tmux sessions the bot manages (names start with '{}'): creation time, windows and whether someone is attached. With 'session', also shows that session's recent output. Only available from control chats.",
+                tmux::SESSION_PREFIX
+            ),
+            input_schema: schema_object(
+                json!({
+                    "session": {
+                        "type": "string",
+                        "description": "Session to show recent output for"
+                    },
+                    "lines": {
+                        "type": "integer",
+                        "description": "Lines of output to show (default 30, max 500)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        if let Some(auth) = auth_context_from_input(&input) {
+            if !auth.is_control_chat() {
+                return ToolResult::error(
+                    "Permission denied: tmux sessions are only visible from a control chat".into(),
+                );
+            }
+        }
+        let sessions = match tmux::list_sessions().await {
+            Ok(s) => s,
+            Err(e) => return ToolResult::error(e.to_string()),
+        };
+        let mut out = render_sessions(&sessions);
+
+        if let Some(session) = input
+            .get("session")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            let name = tmux::session_name(session);
+            let lines = input
+                .get("lines")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(DEFAULT_CAPTURE_LINES)
+                .min(MAX_CAPTURE_LINES);
+            match tmux::capture(&name, lines).await {
+                Ok(text) => out.push_str(&format!("\nLast {lines} lines of {name}:\n{text}")),
+                Err(e) => return ToolResult::error(e.to_string()),
+            }
+        }
+        ToolResult::success(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requires_control_chat_and_renders() {
+        let tool = ListTmuxSessionsTool;
+        let result = tool
+            .execute(json!({
+                "__microclaw_auth": {"caller_chat_id": 5, "control_chat_ids": [100]}
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("control chat"));
+
+        assert_eq!(
+            render_sessions(&[]),
+            "No bot-managed tmux sessions are running."
+        );
+        let out = render_sessions(&[TmuxSession {
+            name: "microclaw-job-1".into(),
+            created: 0,
+            attached: false,
+            windows: 1,
+        }]);
+        assert_eq!(
+            out,
+            "1 tmux session(s):\n- microclaw-job-1 (created 1970-01-01T00:00:00+00:00, 1 window, detached)\n"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::db::{call_blocking, Database, TodoItem};
+
+const DEFAULT_LIST: &str = "todo";
+const MAX_LIST_CHARS: usize = 40;
+const MAX_TEXT_CHARS: usize = 500;
+const MAX_COMPLETE_IDS: usize = 50;
+
+/// The calling chat and persona; todo lists belong to both.
+fn owner(input: &serde_json::Value) -> Result<(i64, i64), String> {
+    auth_context_from_input(input)
+        .map(|a| (a.caller_chat_id, a.caller_persona_id))
+        .ok_or_else(|| "Todo lists need the calling chat".to_string())
+}
+
+/// List name from the input: trimmed and lowercased, so "Shopping" and "shopping" are one list.
+fn list_name(input: &serde_json::Value) -> Result<Option<String>, String> {
+    let Some(name) = input
+        .get("list")
+        .and_then(|v| v.as_str())
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+    else {
+        return Ok(None);
+    };
+    if name.chars().count() > MAX_LIST_CHARS {
+        return Err(format!("List name is too long (max {MAX_LIST_CHARS} characters)"));
+    }
+    Ok(Some(name))
+}
+
+/// Items grouped under their list, e.g. `shopping:\n  #3 [ ] Milk (due 2026-10-20)`.
+pub fn format_todos(items: &[TodoItem]) -> String {
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+    for item in items {
+        if current != Some(item.list.as_str()) {
+            if current.is_some() {
+                out.push('\n');
+            }
+            out.push_str(&format!("{}:\n", item.list));
+            current = Some(item.list.as_str());
+        }
+        let mark = if item.done { "x" } else { " " };
+        out.push_str(&format!("  #{} [{mark}] {}", item.id, item.text));
+        if let Some(due) = &item.due {
+            out.push_str(&format!(" (due {due})"));
+        }
+        if let Some(completed) = &item.completed_at {
+            out.push_str(&format!(" (done {})", completed.get(..10).unwrap_or(completed)));
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+pub struct TodoAddTool {
+    db: Arc<Database>,
+}
+
+impl TodoAddTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        TodoAddTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoAddTool {
+    fn name(&self) -> &str {
+        "todo_add"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "todo_add".into(),
+            description: "Add an item to one of this chat's todo lists (e.g. 'shopping', 'chores'; created on first use). Use this for tasks and lists instead of writing them to memory.".into(),
+            input_schema: schema_object(
+                json!({
+                    "text": {
+                        "type": "string",
+                        "description": "The item, e.g. 'Buy milk' or 'Call the plumber'"
+                    },
+                    "list": {
+                        "type": "string",
+                        "description": "List name (default 'todo')"
+                    },
+                    "due": {
+                        "type": "string",
+                        "description": "Optional due date, YYYY-MM-DD"
+                    }
+                }),
+                &["text"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let (chat_id, persona_id) = match owner(&input) {
+            Ok(o) => o,
+            Err(e) => return ToolResult::error(e),
+        };
+        let text = match input.get("text").and_then(|v| v.as_str()) {
+            Some(t) if !t.trim().is_empty() => t.trim().to_string(),
+            _ => return ToolResult::error("Missing required parameter: text".into()),
+        };
+        if text.chars().count() > MAX_TEXT_CHARS {
+            return ToolResult::error(format!("Item is too long (max {MAX_TEXT_CHARS} characters)"));
+        }
+        let list = match list_name(&input) {
+            Ok(l) => l.unwrap_or_else(|| DEFAULT_LIST.to_string()),
+            Err(e) => return ToolResult::error(e),
+        };
+        let due = match input.get("due").and_then(|v| v.as_str()).map(str::trim) {
+            Some(d) if !d.is_empty() => {
+                if chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err() {
+                    return ToolResult::error(format!("Invalid due date '{d}' (use YYYY-MM-DD)"));
+                }
+                Some(d.to_string())
+            }
+            _ => None,
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+        let (list_for_db, text_for_db, due_for_db) = (list.clone(), text.clone(), due.clone());
+        match call_blocking(self.db.clone(), move |d| {
+            d.add_todo(
+                chat_id,
+                persona_id,
+                &list_for_db,
+                &text_for_db,
+                due_for_db.as_deref(),
+                &now,
+            )
+        })
+        .await
+        {
+            Ok(id) => {
+                let due = due.map(|d| format!(" (due {d})")).unwrap_or_default();
+                ToolResult::success(format!("Added #{id} to {list}: {text}{due}"))
+            }
+            Err(e) => ToolResult::error(format!("Failed to add todo: {e}")),
+        }
+    }
+}
+
+pub struct TodoListTool {
+    db: Arc<Database>,
+}
+
+impl TodoListTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        TodoListTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoListTool {
+    fn name(&self) -> &str {
+        "todo_list"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "todo_list".into(),
+            description: "Show this chat's todo lists (or one of them) with each item's id, for todo_complete. Open items only unless include_done is set.".into(),
+            input_schema: schema_object(
+                json!({
+                    "list": {
+                        "type": "string",
+                        "description": "Only this list"
+                    },
+                    "include_done": {
+                        "type": "boolean",
+                        "description": "Also show completed items (default false)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let (chat_id, persona_id) = match owner(&input) {
+            Ok(o) => o,
+            Err(e) => return ToolResult::error(e),
+        };
+        let list = match list_name(&input) {
+            Ok(l) => l,
+            Err(e) => return ToolResult::error(e),
+        };
+        let include_done = input
+            .get("include_done")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let list_for_db = list.clone();
+        match call_blocking(self.db.clone(), move |d| {
+            d.list_todos(chat_id, persona_id, list_for_db.as_deref(), include_done)
+        })
+        .await
+        {
+            Ok(items) if items.is_empty() => ToolResult::success(match list {
+                Some(name) => format!("Nothing on the {name} list."),
+                None => "No todo items.".into(),
+            }),
+            Ok(items) => ToolResult::success(format_todos(&items)),
+            Err(e) => ToolResult::error(format!("Failed to list todos: {e}")),
+        }
+    }
+}
+
+pub struct TodoCompleteTool {
+    db: Arc<Database>,
+}
+
+impl TodoCompleteTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        TodoCompleteTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for TodoCompleteTool {
+    fn name(&self) -> &str {
+        "todo_complete"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "todo_complete".into(),
+            description: "Mark todo items done by id (see todo_list).".into(),
+            input_schema: schema_object(
+                json!({
+                    "ids": {
+                        "type": "array",
+                        "items": {"type": "integer"},
+                        "description": "Ids of the items that are done"
+                    }
+                }),
+                &["ids"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let (chat_id, persona_id) = match owner(&input) {
+            Ok(o) => o,
+            Err(e) => return ToolResult::error(e),
+        };
+        let ids: Vec<i64> = input
+            .get("ids")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_i64()).collect())
+            .unwrap_or_default();
+        if ids.is_empty() {
+            return ToolResult::error("Missing required parameter: ids".into());
+        }
+        if ids.len() > MAX_COMPLETE_IDS {
+            return ToolResult::error(format!("At most {MAX_COMPLETE_IDS} ids at a time"));
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        let results = match call_blocking(self.db.clone(), move |d| {
+            ids.into_iter()
+                .map(|id| Ok((id, d.complete_todo(chat_id, persona_id, id, &now)?)))
+                .collect::<Result<Vec<_>, crate::error::MicroClawError>>()
+        })
+        .await
+        {
+            Ok(r) => r,
+            Err(e) => return ToolResult::error(format!("Failed to complete todos: {e}")),
+        };
+        let mut lines = Vec::new();
+        let mut missing = Vec::new();
+        for (id, text) in results {
+            match text {
+                Some(text) => lines.push(format!("Done: #{id} {text}")),
+                None => missing.push(format!("#{id}")),
+            }
+        }
+        if !missing.is_empty() {
+            lines.push(format!(
+                "No open item {} on this chat's lists.",
+                missing.join(", ")
+            ));
+        }
+        let text = lines.join("\n");
+        if lines.len() == 1 && text.starts_with("No open item") {
+            ToolResult::error(text)
+        } else {
+            ToolResult::success(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_list_and_complete_todos() {
+        let dir = std::env::temp_dir().join(format!("microclaw_todos_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let add = TodoAddTool::new(db.clone());
+        let list = TodoListTool::new(db.clone());
+        let complete = TodoCompleteTool::new(db.clone());
+        let auth = json!({"caller_chat_id": 5, "caller_persona_id": 1, "control_chat_ids": []});
+
+        let out = add
+            .execute(json!({"text": "Milk", "list": "Shopping", "__microclaw_auth": auth}))
+            .await;
+        assert_eq!(out.content, "Added #1 to shopping: Milk");
+        add.execute(json!({"text": "Eggs", "list": "shopping", "__microclaw_auth": auth}))
+            .await;
+        let out = add
+            .execute(json!({"text": "Call plumber", "due": "2026-10-20", "__microclaw_auth": auth}))
+            .await;
+        assert_eq!(out.content, "Added #3 to todo: Call plumber (due 2026-10-20)");
+        let bad = add
+            .execute(json!({"text": "X", "due": "next week", "__microclaw_auth": auth}))
+            .await;
+        assert!(bad.is_error && bad.content.contains("Invalid due date"));
+
+        let out = list.execute(json!({"__microclaw_auth": auth})).await;
+        assert_eq!(
+            out.content,
+            "shopping:\n  #1 [ ] Milk\n  #2 [ ] Eggs\n\ntodo:\n  #3 [ ] Call plumber (due 2026-10-20)"
+        );
+
+        let out = complete
+            .execute(json!({"ids": [2, 3, 99], "__microclaw_auth": auth}))
+            .await;
+        assert!(!out.is_error);
+        assert_eq!(
+            out.content,
+            "Done: #2 Eggs\nDone: #3 Call plumber\nNo open item #99 on this chat's lists."
+        );
+        let again = complete.execute(json!({"ids": [2], "__microclaw_auth": auth})).await;
+        assert!(again.is_error);
+
+        let out = list.execute(json!({"list": "todo", "__microclaw_auth": auth})).await;
+        assert_eq!(out.content, "Nothing on the todo list.");
+        let out = list
+            .execute(json!({"list": "shopping", "include_done": true, "__microclaw_auth": auth}))
+            .await;
+        assert!(out.content.starts_with("shopping:\n  #1 [ ] Milk\n  #2 [x] Eggs (done "));
+
+        // Another persona in the same chat, and another chat, have lists of their own.
+        let persona = json!({"caller_chat_id": 5, "caller_persona_id": 2, "control_chat_ids": []});
+        let out = list.execute(json!({"__microclaw_auth": persona})).await;
+        assert_eq!(out.content, "No todo items.");
+        let other = json!({"caller_chat_id": 6, "caller_persona_id": 1, "control_chat_ids": []});
+        let out = complete.execute(json!({"ids": [1], "__microclaw_auth": other})).await;
+        assert!(out.is_error);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
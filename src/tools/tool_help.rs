@@ -0,0 +1,163 @@
+//! `tool_help`: detailed usage of one tool (every parameter with its type, whether it is
+//! required and allowed values, plus example inputs), so the model can check a complex tool
+//! before calling it instead of guessing. It is answered by the `ToolRegistry`, which knows
+//! every registered tool, including MCP ones.
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+
+pub const NAME: &str = "tool_help";
+
+fn type_label(schema: &serde_json::Value) -> String {
+    let base = match schema.get("type") {
+        Some(serde_json::Value::String(t)) => t.clone(),
+        Some(serde_json::Value::Array(ts)) => ts
+            .iter()
+            .filter_map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join(" or "),
+        _ => "any".into(),
+    };
+    match schema.get("items") {
+        Some(items) if base == "array" => format!("array of {}", type_label(items)),
+        _ => base,
+    }
+}
+
+fn render_tool(def: &ToolDefinition) -> String {
+    let mut out = format!("# {}\n\n{}\n", def.name, def.description);
+    let required: Vec<&str> = def
+        .input_schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let properties = def
+        .input_schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .filter(|p| !p.is_empty());
+    match properties {
+        Some(properties) => {
+            out.push_str("\nParameters:\n");
+            for (name, schema) in properties {
+                let mut notes = vec![type_label(schema)];
+                notes.push(if required.contains(&name.as_str()) {
+                    "required".into()
+                } else {
+                    "optional".into()
+                });
+                if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+                    let options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+                    notes.push(format!("one of: {}", options.join(", ")));
+                }
+                out.push_str(&format!("- {name} ({})", notes.join(", ")));
+                if let Some(desc) = schema.get("description").and_then(|d| d.as_str()) {
+                    out.push_str(&format!(": {desc}"));
+                }
+                out.push('\n');
+            }
+        }
+        None => out.push_str("\nNo parameters.\n"),
+    }
+    if !def.examples.is_empty() {
+        out.push_str("\nExample inputs:\n");
+        for example in &def.examples {
+            out.push_str(&format!("{example}\n"));
+        }
+    }
+    out
+}
+
+/// Usage of the tool named in `input`, looked up among `definitions`.
+pub fn render_help(definitions: &[ToolDefinition], input: &serde_json::Value) -> ToolResult {
+    let names = || {
+        definitions
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let Some(name) = input.get("tool").and_then(|v| v.as_str()).map(str::trim) else {
+        return ToolResult::error(format!("Missing 'tool'. Available tools: {}", names()));
+    };
+    match definitions.iter().find(|d| d.name == name) {
+        Some(def) => ToolResult::success(render_tool(def)),
+        None => ToolResult::error(format!(
+            "Unknown tool '{name}'. Available tools: {}",
+            names()
+        )),
+    }
+}
+
+pub struct ToolHelpTool;
+
+#[async_trait]
+impl Tool for ToolHelpTool {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: NAME.into(),
+            description: "Show detailed usage for a tool: each parameter's type, whether it is required, allowed values, and example inputs. Use it before calling a complex tool you are unsure about, or after a call was rejected as invalid input.".into(),
+            input_schema: schema_object(
+                json!({
+                    "tool": {
+                        "type": "string",
+                        "description": "Name of the tool, e.g. write_tiered_memory"
+                    }
+                }),
+                &["tool"],
+            ),
+            examples: vec![json!({"tool": "schedule_task"})],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        // Reached only outside a registry; it can describe just itself.
+        render_help(&[self.definition()], &input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_help() {
+        let defs = vec![ToolDefinition {
+            name: "schedule_task".into(),
+            description: "Schedule a task.".into(),
+            input_schema: schema_object(
+                json!({
+                    "prompt": {"type": "string", "description": "What to run"},
+                    "schedule_type": {"type": "string", "enum": ["cron", "once"]},
+                    "tags": {"type": "array", "items": {"type": "string"}}
+                }),
+                &["prompt"],
+            ),
+            examples: vec![json!({"prompt": "hi", "schedule_type": "once"})],
+        }];
+        let out = render_help(&defs, &json!({"tool": "schedule_task"}));
+        assert!(!out.is_error);
+        assert!(out
+            .content
+            .contains("- prompt (string, required): What to run"));
+        assert!(out
+            .content
+            .contains("- schedule_type (string, optional, one of: \"cron\", \"once\")"));
+        assert!(out.content.contains("- tags (array of string, optional)"));
+        assert!(out
+            .content
+            .contains("Example inputs:\n{\"prompt\":\"hi\",\"schedule_type\":\"once\"}"));
+
+        let out = render_help(&defs, &json!({"tool": "nope"}));
+        assert!(out.is_error);
+        assert!(out.content.contains("Available tools: schedule_task"));
+    }
+}
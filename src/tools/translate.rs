@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::translation::{detect_language, translate_text};
+
+pub struct TranslateTool {
+    config: Config,
+    db: Arc<Database>,
+}
+
+impl TranslateTool {
+    pub fn new(config: &Config, db: Arc<Database>) -> Self {
+        TranslateTool {
+            config: config.clone(),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for TranslateTool {
+    fn name(&self) -> &str {
+        "translate"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "translate".into(),
+            description: "Translate text into another language. Applies the chat's glossary of preferred translations (see translation_glossary). The source language is detected automatically when omitted.".into(),
+            input_schema: schema_object(
+                json!({
+                    "text": {
+                        "type": "string",
+                        "description": "Text to translate"
+                    },
+                    "target_lang": {
+                        "type": "string",
+                        "description": "Target language code (ISO 639-1, e.g. 'en', 'de', 'es')"
+                    },
+                    "source_lang": {
+                        "type": "string",
+                        "description": "Source language code (optional; detected when omitted)"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat whose glossary to apply (defaults to the current chat)"
+                    }
+                }),
+                &["text", "target_lang"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let text = match input.get("text").and_then(|v| v.as_str()) {
+            Some(t) if !t.trim().is_empty() => t.to_string(),
+            _ => return ToolResult::error("Missing required parameter: text".into()),
+        };
+        let target_lang = match input.get("target_lang").and_then(|v| v.as_str()) {
+            Some(t) if !t.trim().is_empty() => t.trim().to_lowercase(),
+            _ => return ToolResult::error("Missing required parameter: target_lang".into()),
+        };
+        let source_lang = input
+            .get("source_lang")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .or_else(|| detect_language(&text).map(str::to_string));
+
+        let chat_id = input
+            .get("chat_id")
+            .and_then(|v| v.as_i64())
+            .or_else(|| auth_context_from_input(&input).map(|a| a.caller_chat_id));
+        let glossary = match chat_id {
+            Some(chat_id) => {
+                if let Err(e) = authorize_chat_access(&input, chat_id) {
+                    return ToolResult::error(e);
+                }
+                let lang = target_lang.clone();
+                match call_blocking(self.db.clone(), move |db| {
+                    db.get_glossary_terms(chat_id, Some(&lang))
+                })
+                .await
+                {
+                    Ok(terms) => terms,
+                    Err(e) => return ToolResult::error(format!("Failed to load glossary: {e}")),
+                }
+            }
+            None => Vec::new(),
+        };
+
+        match translate_text(
+            &self.config,
+            &text,
+            source_lang.as_deref(),
+            &target_lang,
+            &glossary,
+        )
+        .await
+        {
+            Ok(translated) => ToolResult::success(translated),
+            Err(e) => ToolResult::error(format!("Translation failed: {e}")),
+        }
+    }
+}
+
+pub struct TranslationGlossaryTool {
+    db: Arc<Database>,
+}
+
+impl TranslationGlossaryTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        TranslationGlossaryTool { db }
+    }
+}
+
+#[async_trait]
+impl Tool for TranslationGlossaryTool {
+    fn name(&self) -> &str {
+        "translation_glossary"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "translation_glossary".into(),
+            description: "Manage a chat's glossary of preferred translations for household-specific terms (names, nicknames, places). Actions: 'add' (term, translation, target_lang), 'remove' (term, optional target_lang), 'list' (optional target_lang). The translate tool and inbound auto-translation always use these.".into(),
+            input_schema: schema_object(
+                json!({
+                    "action": {
+                        "type": "string",
+                        "enum": ["add", "remove", "list"],
+                        "description": "What to do"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "The chat ID the glossary belongs to"
+                    },
+                    "term": {
+                        "type": "string",
+                        "description": "Source term (for add/remove)"
+                    },
+                    "translation": {
+                        "type": "string",
+                        "description": "Preferred translation (for add)"
+                    },
+                    "target_lang": {
+                        "type": "string",
+                        "description": "Target language code (required for add; optional filter for list/remove)"
+                    }
+                }),
+                &["action", "chat_id"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let action = match input.get("action").and_then(|v| v.as_str()) {
+            Some(a) => a.to_string(),
+            None => return ToolResult::error("Missing required parameter: action".into()),
+        };
+        let chat_id = match input.get("chat_id").and_then(|v| v.as_i64()) {
+            Some(id) => id,
+            None => return ToolResult::error("Missing required parameter: chat_id".into()),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        let term = input
+            .get("term")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let target_lang = input
+            .get("target_lang")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty());
+
+        match action.as_str() {
+            "add" => {
+                let Some(term) = term else {
+                    return ToolResult::error("Missing required parameter: term".into());
+                };
+                let Some(lang) = target_lang else {
+                    return ToolResult::error("Missing required parameter: target_lang".into());
+                };
+                let translation = match input.get("translation").and_then(|v| v.as_str()) {
+                    Some(t) if !t.trim().is_empty() => t.trim().to_string(),
+                    _ => {
+                        return ToolResult::error("Missing required parameter: translation".into())
+                    }
+                };
+                let msg = format!("Glossary: \"{term}\" → \"{translation}\" ({lang}).");
+                match call_blocking(self.db.clone(), move |db| {
+                    db.upsert_glossary_term(chat_id, &term, &lang, &translation)
+                })
+                .await
+                {
+                    Ok(()) => ToolResult::success(msg),
+                    Err(e) => ToolResult::error(format!("Failed to save glossary term: {e}")),
+                }
+            }
+            "remove" => {
+                let Some(term) = term else {
+                    return ToolResult::error("Missing required parameter: term".into());
+                };
+                let term_for_msg = term.clone();
+                match call_blocking(self.db.clone(), move |db| {
+                    db.delete_glossary_term(chat_id, &term, target_lang.as_deref())
+                })
+                .await
+                {
+                    Ok(0) => ToolResult::error(format!("Term \"{term_for_msg}\" not in glossary.")),
+                    Ok(n) => ToolResult::success(format!(
+                        "Removed \"{term_for_msg}\" ({n} entr{}).",
+                        if n == 1 { "y" } else { "ies" }
+                    )),
+                    Err(e) => ToolResult::error(format!("Failed to remove glossary term: {e}")),
+                }
+            }
+            "list" => {
+                match call_blocking(self.db.clone(), move |db| {
+                    db.get_glossary_terms(chat_id, target_lang.as_deref())
+                })
+                .await
+                {
+                    Ok(terms) if terms.is_empty() => {
+                        ToolResult::success("Glossary is empty.".into())
+                    }
+                    Ok(terms) => {
+                        let lines: Vec<String> = terms
+                            .iter()
+                            .map(|t| {
+                                format!(
+                                    "- [{}] \"{}\" → \"{}\"",
+                                    t.target_lang, t.source_term, t.translation
+                                )
+                            })
+                            .collect();
+                        ToolResult::success(lines.join("\n"))
+                    }
+                    Err(e) => ToolResult::error(format!("Failed to load glossary: {e}")),
+                }
+            }
+            other => ToolResult::error(format!(
+                "Unknown action '{other}'. Use add, remove, or list."
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (Arc<Database>, std::path::PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_translate_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        (db, dir)
+    }
+
+    #[test]
+    fn test_translate_definitions() {
+        let (db, dir) = test_db();
+        let tool = TranslationGlossaryTool::new(db);
+        let def = tool.definition();
+        assert_eq!(def.name, "translation_glossary");
+        let required = def.input_schema["required"].as_array().unwrap();
+        assert!(required.iter().any(|v| v == "action"));
+        assert!(required.iter().any(|v| v == "chat_id"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_glossary_add_list_remove() {
+        let (db, dir) = test_db();
+        let tool = TranslationGlossaryTool::new(db);
+        let result = tool
+            .execute(json!({
+                "action": "add", "chat_id": 5, "term": "Oma",
+                "translation": "Nana", "target_lang": "EN"
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+
+        let result = tool.execute(json!({"action": "list", "chat_id": 5})).await;
+        assert!(result.content.contains("[en] \"Oma\" → \"Nana\""));
+
+        let result = tool
+            .execute(json!({"action": "remove", "chat_id": 5, "term": "Oma"}))
+            .await;
+        assert!(!result.is_error);
+        let result = tool.execute(json!({"action": "list", "chat_id": 5})).await;
+        assert_eq!(result.content, "Glossary is empty.");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_glossary_permission_denied() {
+        let (db, dir) = test_db();
+        let tool = TranslationGlossaryTool::new(db);
+        let result = tool
+            .execute(json!({
+                "action": "list",
+                "chat_id": 200,
+                "__microclaw_auth": {"caller_chat_id": 100, "control_chat_ids": []}
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Permission denied"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_glossary_add_missing_translation() {
+        let (db, dir) = test_db();
+        let tool = TranslationGlossaryTool::new(db);
+        let result = tool
+            .execute(json!({"action": "add", "chat_id": 5, "term": "Oma", "target_lang": "en"}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("translation"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
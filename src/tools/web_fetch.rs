@@ -4,8 +4,17 @@ use serde_json::json;
 use super::web_html::{extract_primary_html, html_to_text};
 use super::{schema_object, Tool, ToolResult};
 use crate::claude::ToolDefinition;
+use crate::egress::EgressPolicy;
 
-pub struct WebFetchTool;
+pub struct WebFetchTool {
+    egress: EgressPolicy,
+}
+
+impl WebFetchTool {
+    pub fn new(egress: EgressPolicy) -> Self {
+        WebFetchTool { egress }
+    }
+}
 
 #[async_trait]
 impl Tool for WebFetchTool {
@@ -28,6 +37,7 @@ impl Tool for WebFetchTool {
                 }),
                 &["url"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -37,26 +47,17 @@ impl Tool for WebFetchTool {
             None => return ToolResult::error("Missing required parameter: url".into()),
         };
 
-        match fetch_url(url).await {
+        match fetch_url(&self.egress, url).await {
             Ok(text) => ToolResult::success(text),
             Err(e) => ToolResult::error(format!("Failed to fetch URL: {e}")),
         }
     }
 }
 
-async fn fetch_url(url: &str) -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .redirect(reqwest::redirect::Policy::limited(5))
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let resp = client
-        .get(url)
-        .header("User-Agent", "MicroClaw/1.0")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+async fn fetch_url(egress: &EgressPolicy, url: &str) -> Result<String, String> {
+    let resp = egress
+        .get(url, std::time::Duration::from_secs(15))
+        .await?;
 
     if !resp.status().is_success() {
         return Err(format!("HTTP {}", resp.status()));
@@ -82,7 +83,7 @@ mod tests {
 
     #[test]
     fn test_web_fetch_definition() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::new(EgressPolicy::default());
         assert_eq!(tool.name(), "web_fetch");
         let def = tool.definition();
         assert_eq!(def.name, "web_fetch");
@@ -94,7 +95,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_missing_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::new(EgressPolicy::default());
         let result = tool.execute(json!({})).await;
         assert!(result.is_error);
         assert!(result.content.contains("Missing required parameter: url"));
@@ -102,7 +103,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_null_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::new(EgressPolicy::default());
         let result = tool.execute(json!({"url": null})).await;
         assert!(result.is_error);
         assert!(result.content.contains("Missing required parameter: url"));
@@ -110,11 +111,21 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_invalid_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::new(EgressPolicy::default());
         let result = tool
             .execute(json!({"url": "https://this-domain-does-not-exist-12345.example"}))
             .await;
         assert!(result.is_error);
         assert!(result.content.contains("Failed to fetch URL"));
     }
+
+    #[tokio::test]
+    async fn test_web_fetch_blocks_private_network() {
+        let tool = WebFetchTool::new(EgressPolicy::default());
+        let result = tool
+            .execute(json!({"url": "http://169.254.169.254/latest/meta-data/"}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("egress policy"), "{}", result.content);
+    }
 }
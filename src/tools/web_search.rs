@@ -4,6 +4,8 @@ use serde_json::json;
 use super::web_html::extract_ddg_results;
 use super::{schema_object, Tool, ToolResult};
 use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::llm::{add_source, format_sources, PROVIDER_WEB_SEARCH_TOOL};
 
 pub struct WebSearchTool;
 
@@ -27,6 +29,7 @@ impl Tool for WebSearchTool {
                 }),
                 &["query"],
             ),
+            examples: Vec::new(),
         }
     }
 
@@ -87,6 +90,113 @@ async fn search_ddg(query: &str) -> Result<String, String> {
     Ok(output)
 }
 
+/// Web search by the LLM provider, offered instead of `web_search` in chats with the
+/// `provider_search` flag. Anthropic runs it server-side (the request turns it into its
+/// `web_search` tool), so `execute` only runs for OpenAI, where it asks the configured search
+/// model and returns its answer with the cited sources.
+pub struct ProviderWebSearchTool {
+    config: Config,
+}
+
+impl ProviderWebSearchTool {
+    pub fn new(config: &Config) -> Self {
+        ProviderWebSearchTool {
+            config: config.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ProviderWebSearchTool {
+    fn name(&self) -> &str {
+        PROVIDER_WEB_SEARCH_TOOL
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: PROVIDER_WEB_SEARCH_TOOL.into(),
+            description: "Search the web and get an answer with cited sources. Cite the sources you use as markdown links.".into(),
+            input_schema: schema_object(
+                json!({
+                    "query": {
+                        "type": "string",
+                        "description": "What to search for, as a question or search query"
+                    }
+                }),
+                &["query"],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let query = match input.get("query").and_then(|v| v.as_str()) {
+            Some(q) if !q.trim().is_empty() => q.trim(),
+            _ => return ToolResult::error("Missing required parameter: query".into()),
+        };
+        if self.config.llm_provider != "openai" {
+            return ToolResult::error(format!(
+                "{PROVIDER_WEB_SEARCH_TOOL} is run by the provider and is not available with {}",
+                self.config.llm_provider
+            ));
+        }
+        match search_openai(&self.config, query).await {
+            Ok(answer) => ToolResult::success(answer),
+            Err(e) => ToolResult::error(format!("Search failed: {e}")),
+        }
+    }
+}
+
+async fn search_openai(config: &Config, query: &str) -> Result<String, String> {
+    let base = config
+        .llm_base_url
+        .as_deref()
+        .unwrap_or("https://api.openai.com/v1");
+    let model = config.provider_search.clone().unwrap_or_default().openai_model;
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(60))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .post(format!("{}/chat/completions", base.trim_end_matches('/')))
+        .bearer_auth(&config.api_key)
+        .json(&json!({
+            "model": model,
+            "web_search_options": {},
+            "messages": [{"role": "user", "content": query}]
+        }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+        return Err(format!("HTTP {status}: {message}"));
+    }
+    openai_search_answer(&body).ok_or_else(|| "empty answer".into())
+}
+
+/// The answer of a chat completion with web search, followed by its `url_citation` sources.
+fn openai_search_answer(body: &serde_json::Value) -> Option<String> {
+    let message = &body["choices"][0]["message"];
+    let mut out = message["content"].as_str()?.trim().to_string();
+    if out.is_empty() {
+        return None;
+    }
+    let mut sources = Vec::new();
+    for annotation in message["annotations"].as_array().into_iter().flatten() {
+        let citation = &annotation["url_citation"];
+        if let Some(url) = citation["url"].as_str() {
+            add_source(&mut sources, citation["title"].as_str(), url);
+        }
+    }
+    if !sources.is_empty() {
+        out.push_str(&format_sources(&sources));
+    }
+    Some(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,6 +222,23 @@ mod tests {
         assert!(result.content.contains("Missing required parameter: query"));
     }
 
+    #[test]
+    fn test_openai_search_answer_lists_sources_once() {
+        let body = json!({"choices": [{"message": {
+            "content": "Two reports agree.",
+            "annotations": [
+                {"type": "url_citation", "url_citation": {"url": "https://a.example", "title": "A"}},
+                {"type": "url_citation", "url_citation": {"url": "https://a.example", "title": "A"}},
+                {"type": "url_citation", "url_citation": {"url": "https://b.example"}}
+            ]
+        }}]});
+        assert_eq!(
+            openai_search_answer(&body).unwrap(),
+            "Two reports agree.\n\nSources:\n1. [A](https://a.example)\n2. [https://b.example](https://b.example)"
+        );
+        assert!(openai_search_answer(&json!({"choices": []})).is_none());
+    }
+
     #[tokio::test]
     async fn test_web_search_null_query() {
         let tool = WebSearchTool;
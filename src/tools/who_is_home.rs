@@ -0,0 +1,147 @@
+//! `who_is_home`: presence from Home Assistant, optionally messaging whoever is home. See
+//! [`crate::home_assistant`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use teloxide::Bot;
+
+use super::{authorize_chat_access, schema_object, Tool, ToolResult};
+use crate::channel::deliver_and_store_bot_message;
+use crate::claude::ToolDefinition;
+use crate::config::Config;
+use crate::db::{call_blocking, Database};
+use crate::home_assistant::{chats_of_people_home, format_presence, presence};
+
+pub struct WhoIsHomeTool {
+    config: Config,
+    bot: Bot,
+    db: Arc<Database>,
+}
+
+impl WhoIsHomeTool {
+    pub fn new(config: &Config, bot: Bot, db: Arc<Database>) -> Self {
+        WhoIsHomeTool {
+            config: config.clone(),
+            bot,
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for WhoIsHomeTool {
+    fn name(&self) -> &str {
+        "who_is_home"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "who_is_home".into(),
+            description: "Who is home right now, from Home Assistant presence (people and where the others are). Pass `notify` to send that text to the chat of each person who is home; nothing is sent when nobody is home, so it also works for \"only remind us if someone is home\".".into(),
+            input_schema: schema_object(
+                json!({
+                    "notify": {
+                        "type": "string",
+                        "description": "Message to send to whoever is home"
+                    }
+                }),
+                &[],
+            ),
+            examples: vec![
+                json!({}),
+                json!({"notify": "The package delivery is at the front door."}),
+            ],
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(ha) = self.config.home_assistant.as_ref() else {
+            return ToolResult::error(
+                "Home Assistant is not configured (home_assistant.url and token)".into(),
+            );
+        };
+        let presence = match presence(ha).await {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(e),
+        };
+        let mut out = format_presence(&presence);
+
+        let notify = input
+            .get("notify")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+        let Some(text) = notify else {
+            return ToolResult::success(out);
+        };
+        if !presence.iter().any(|p| p.home) {
+            out.push_str("\nNot sent: nobody is home.");
+            return ToolResult::success(out);
+        }
+        let targets = chats_of_people_home(ha, &presence);
+        if targets.is_empty() {
+            out.push_str(
+                "\nNot sent: no chat is set for the people at home (home_assistant.presence_chats).",
+            );
+            return ToolResult::success(out);
+        }
+        let mut sent = Vec::new();
+        let mut failed = Vec::new();
+        for (name, chat_id) in targets {
+            if let Err(e) = authorize_chat_access(&input, chat_id) {
+                failed.push(format!("{name}: {e}"));
+                continue;
+            }
+            let persona_id =
+                call_blocking(self.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                    .await
+                    .unwrap_or(0);
+            match deliver_and_store_bot_message(
+                &self.bot,
+                self.db.clone(),
+                &self.config.bot_username,
+                chat_id,
+                persona_id,
+                text,
+            )
+            .await
+            {
+                Ok(()) => sent.push(name),
+                Err(e) => failed.push(format!("{name}: {e}")),
+            }
+        }
+        if !sent.is_empty() {
+            out.push_str(&format!("\nSent to {}.", sent.join(", ")));
+        }
+        if !failed.is_empty() {
+            out.push_str(&format!("\nNot sent to {}.", failed.join("; ")));
+        }
+        if sent.is_empty() {
+            ToolResult::error(out)
+        } else {
+            ToolResult::success(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requires_home_assistant() {
+        let dir =
+            std::env::temp_dir().join(format!("microclaw_who_is_home_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        let tool = WhoIsHomeTool::new(&config, Bot::new("tok"), db);
+        let out = tool.execute(json!({})).await;
+        assert!(out.is_error);
+        assert!(out.content.contains("not configured"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
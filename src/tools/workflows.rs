@@ -0,0 +1,189 @@
+//! `start_workflow`: bundled conversation templates (trip planning, incident triage, weekly meal
+//! prep). Starting one puts a structured plan, the questions to ask first and the tools that
+//! help into the tool result, which stays in the session and guides the following turns.
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use super::{schema_object, Tool, ToolResult};
+use crate::claude::ToolDefinition;
+
+pub struct Workflow {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub summary: &'static str,
+    /// Asked up front, before any step is worked on.
+    pub questions: &'static [&'static str],
+    pub steps: &'static [&'static str],
+    pub tools: &'static [&'static str],
+}
+
+pub const WORKFLOWS: &[Workflow] = &[
+    Workflow {
+        id: "trip_planning",
+        title: "Trip planning",
+        summary: "Plan a trip from dates and budget to a day-by-day itinerary and packing list.",
+        questions: &[
+            "Where are you going (or which places are you choosing between)?",
+            "What are the travel dates, and how flexible are they?",
+            "Who is travelling (adults, kids, pets)?",
+            "What is the rough budget, and what matters most (comfort, price, speed)?",
+            "Any must-dos, dietary needs or accessibility requirements?",
+        ],
+        steps: &[
+            "Confirm destination, dates, travellers and budget.",
+            "Compare transport options and shortlist accommodation.",
+            "Draft a day-by-day itinerary and check opening hours and bookings.",
+            "Check the weather for the dates and build a packing list.",
+            "Summarise bookings to make and offer reminders before departure.",
+        ],
+        tools: &["web_search", "web_fetch", "schedule_task", "write_memory"],
+    },
+    Workflow {
+        id: "incident_triage",
+        title: "Incident triage",
+        summary: "Work through an outage or alert: scope the impact, find the cause, mitigate and write it up.",
+        questions: &[
+            "What is broken, and what are users or alerts reporting?",
+            "When did it start, and did anything change just before (deploy, config, traffic)?",
+            "Which systems or hosts are involved, and can I inspect them from here?",
+            "How severe is it, and who needs to be kept informed?",
+        ],
+        steps: &[
+            "Record the symptoms, start time and severity.",
+            "Check recent changes, logs and service status to narrow down the cause.",
+            "Propose a mitigation (rollback, restart, failover) and confirm before acting.",
+            "Verify recovery and keep watching for recurrence.",
+            "Write a short incident summary: timeline, cause, fix and follow-ups.",
+        ],
+        tools: &["bash", "read_file", "grep", "web_fetch", "send_message", "schedule_task"],
+    },
+    Workflow {
+        id: "weekly_meal_prep",
+        title: "Weekly meal prep",
+        summary: "Plan the week's meals, a batch-cooking schedule and a shopping list.",
+        questions: &[
+            "How many people, and how many meals per day should be covered?",
+            "Any diets, allergies or foods to avoid?",
+            "How much time is there for cooking, and on which days?",
+            "What is already in the fridge or pantry that should be used up?",
+        ],
+        steps: &[
+            "Agree on the meals for each day of the week.",
+            "Group shared ingredients and plan batch cooking sessions.",
+            "Write a shopping list grouped by store section, minus what is already at home.",
+            "Offer reminders for shopping and prep days.",
+        ],
+        tools: &["web_search", "write_memory", "schedule_task"],
+    },
+];
+
+pub fn find_workflow(id: &str) -> Option<&'static Workflow> {
+    let id = id.trim().to_lowercase().replace(['-', ' '], "_");
+    WORKFLOWS.iter().find(|w| w.id == id)
+}
+
+fn render_list() -> String {
+    let mut out = String::from("Available workflows:\n");
+    for w in WORKFLOWS {
+        out.push_str(&format!("- {} ({}): {}\n", w.id, w.title, w.summary));
+    }
+    out.push_str("\nStart one with start_workflow and its id.");
+    out
+}
+
+fn render_plan(w: &Workflow, goal: Option<&str>) -> String {
+    let mut out = format!("# Workflow: {}\n\n{}\n", w.title, w.summary);
+    if let Some(goal) = goal {
+        out.push_str(&format!("\nUser's goal: {goal}\n"));
+    }
+    out.push_str("\n## Ask first\n");
+    for q in w.questions {
+        out.push_str(&format!("- {q}\n"));
+    }
+    out.push_str("\n## Plan\n");
+    for (i, step) in w.steps.iter().enumerate() {
+        out.push_str(&format!("{}. {step}\n", i + 1));
+    }
+    out.push_str(&format!("\n## Useful tools\n{}\n", w.tools.join(", ")));
+    out.push_str(
+        "\nAsk the questions the user has not already answered (a few at a time), then work \
+         through the plan step by step over the next turns, saying which step you are on.",
+    );
+    out
+}
+
+pub struct StartWorkflowTool;
+
+#[async_trait]
+impl Tool for StartWorkflowTool {
+    fn name(&self) -> &str {
+        "start_workflow"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        let ids: Vec<&str> = WORKFLOWS.iter().map(|w| w.id).collect();
+        ToolDefinition {
+            name: "start_workflow".into(),
+            description: format!(
+                "Start a guided multi-turn workflow: returns a structured plan, the questions to ask first and tools to use. Available: {}. Call without 'workflow' to list them with descriptions.",
+                ids.join(", ")
+            ),
+            input_schema: schema_object(
+                json!({
+                    "workflow": {
+                        "type": "string",
+                        "enum": ids,
+                        "description": "Workflow id"
+                    },
+                    "goal": {
+                        "type": "string",
+                        "description": "What the user wants, in their words (optional)"
+                    }
+                }),
+                &[],
+            ),
+            examples: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(id) = input.get("workflow").and_then(|v| v.as_str()) else {
+            return ToolResult::success(render_list());
+        };
+        let Some(workflow) = find_workflow(id) else {
+            return ToolResult::error(format!("Unknown workflow '{id}'.\n\n{}", render_list()));
+        };
+        let goal = input
+            .get("goal")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|g| !g.is_empty());
+        info!("Starting workflow {}", workflow.id);
+        ToolResult::success(render_plan(workflow, goal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_start_workflow() {
+        let out = StartWorkflowTool.execute(json!({})).await;
+        assert!(!out.is_error);
+        assert!(out.content.contains("incident_triage (Incident triage)"));
+
+        let out = StartWorkflowTool
+            .execute(json!({"workflow": "Trip planning", "goal": "a week in Lisbon"}))
+            .await;
+        assert!(out.content.starts_with("# Workflow: Trip planning"));
+        assert!(out.content.contains("User's goal: a week in Lisbon"));
+        assert!(out.content.contains("1. Confirm destination"));
+
+        let out = StartWorkflowTool.execute(json!({"workflow": "nope"})).await;
+        assert!(out.is_error);
+        assert!(out.content.contains("weekly_meal_prep"));
+    }
+}
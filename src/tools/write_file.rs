@@ -42,6 +42,7 @@ impl Tool for WriteFileTool {
                 }),
                 &["path", "content"],
             ),
+            examples: Vec::new(),
         }
     }
 
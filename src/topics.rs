@@ -0,0 +1,349 @@
+//! Conversation topic tagging. When a session is compacted, the messages since the last
+//! tagged segment are tagged with a few short topics (LLM, falling back to keyword
+//! extraction) so `find_conversations` can retrieve past discussions by subject.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::claude::{Message, MessageContent, ResponseContentBlock};
+use crate::config::Config;
+use crate::db::{call_blocking, ConversationSegment, Database, StoredMessage};
+use crate::error::MicroClawError;
+
+/// Segments shorter than this are left for the next compaction.
+const MIN_SEGMENT_MESSAGES: usize = 4;
+const MAX_SEGMENT_MESSAGES: usize = 400;
+const MAX_TRANSCRIPT_CHARS: usize = 12_000;
+const MAX_TAGS: usize = 8;
+
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "but", "by", "can", "could", "did", "do", "does", "done",
+    "for", "from", "get", "got", "had", "has", "have", "he", "her", "here", "him", "his", "how",
+    "i", "if", "in", "into", "is", "it", "its", "just", "know", "let", "like", "me", "more", "my",
+    "need", "no", "not", "now", "of", "ok", "okay", "on", "one", "or", "our", "out", "please",
+    "re", "she", "should", "so", "some", "sure", "than", "thanks", "that", "the", "their", "them",
+    "then", "there", "these", "they", "this", "to", "too", "up", "us", "use", "user", "very",
+    "want", "was", "we", "well", "were", "what", "when", "where", "which", "who", "why", "will",
+    "with", "would", "yes", "you", "your",
+];
+
+fn is_stopword(word: &str) -> bool {
+    STOPWORDS.binary_search(&word).is_ok()
+}
+
+fn stem(word: &str) -> String {
+    if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+/// Lowercased word tokens with a light plural stem ("cars" -> "car").
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| stem(&w.to_lowercase()))
+        .collect()
+}
+
+/// Stemmed tokens with stopwords removed (checked before stemming, so "this" stays out).
+fn content_terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|w| w.to_lowercase())
+        .filter(|w| !w.is_empty() && !is_stopword(w))
+        .map(|w| stem(&w))
+        .collect()
+}
+
+fn normalize_tag(raw: &str) -> Option<String> {
+    let words: Vec<String> = tokenize(raw);
+    if words.is_empty() || words.len() > 4 {
+        return None;
+    }
+    let tag = words.join(" ");
+    (tag.len() >= 2 && tag.len() <= 40).then_some(tag)
+}
+
+/// Most frequent content words and two-word phrases, as fallback tags.
+pub fn extract_keywords(text: &str, max: usize) -> Vec<String> {
+    let words: Vec<String> = content_terms(text)
+        .into_iter()
+        .filter(|w| w.len() > 2 && !w.chars().all(|c| c.is_ascii_digit()))
+        .collect();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for w in &words {
+        *counts.entry(w.clone()).or_default() += 1;
+    }
+    for pair in words.windows(2) {
+        if pair[0] != pair[1] {
+            // Phrases count double so "car insurance" outranks "car" and "insurance" alone.
+            *counts
+                .entry(format!("{} {}", pair[0], pair[1]))
+                .or_default() += 2;
+        }
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().filter(|(_, n)| *n >= 2).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let mut tags: Vec<String> = Vec::new();
+    for (tag, _) in ranked {
+        if tags.len() >= max {
+            break;
+        }
+        if tags.iter().any(|t| t.contains(&tag)) {
+            continue;
+        }
+        tags.push(tag);
+    }
+    tags
+}
+
+/// Parse `{"tags": [...], "summary": "..."}` from an LLM reply; tolerates a bare
+/// comma-separated tag list.
+pub fn parse_tagging_response(text: &str) -> (Vec<String>, Option<String>) {
+    let json = text
+        .find('{')
+        .zip(text.rfind('}'))
+        .and_then(|(start, end)| {
+            serde_json::from_str::<serde_json::Value>(&text[start..=end]).ok()
+        });
+    let (raw_tags, summary): (Vec<String>, Option<String>) = match json {
+        Some(v) => (
+            v.get("tags")
+                .and_then(|t| t.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|t| t.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            v.get("summary")
+                .and_then(|s| s.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+        ),
+        None => (
+            text.split([',', '\n'])
+                .map(|s| s.trim_start_matches(['-', '*', ' ']).to_string())
+                .collect(),
+            None,
+        ),
+    };
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw_tags.iter().filter_map(|t| normalize_tag(t)) {
+        if !tags.contains(&tag) && tags.len() < MAX_TAGS {
+            tags.push(tag);
+        }
+    }
+    (tags, summary)
+}
+
+fn segment_transcript(messages: &[StoredMessage]) -> String {
+    let mut transcript = String::new();
+    for m in messages {
+        transcript.push_str(&format!("{}: {}\n", m.sender_name, m.content));
+        if transcript.len() > MAX_TRANSCRIPT_CHARS {
+            let mut cut = MAX_TRANSCRIPT_CHARS;
+            while !transcript.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            transcript.truncate(cut);
+            break;
+        }
+    }
+    transcript
+}
+
+async fn llm_tags(config: &Config, transcript: &str) -> Option<(Vec<String>, Option<String>)> {
+    let system = "You tag conversations for later retrieval. Reply with JSON only: \
+        {\"tags\": [3-8 short lowercase topic tags, 1-3 words each, most specific first, \
+        include common synonyms], \"summary\": \"one sentence\"}";
+    let messages = vec![Message {
+        role: "user".into(),
+        content: MessageContent::Text(transcript.to_string()),
+    }];
+    let provider = crate::llm::create_provider(config);
+    match provider.send_message(system, messages, None).await {
+        Ok(resp) => {
+            let text: String = resp
+                .content
+                .iter()
+                .filter_map(|b| match b {
+                    ResponseContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let parsed = parse_tagging_response(&text);
+            (!parsed.0.is_empty()).then_some(parsed)
+        }
+        Err(e) => {
+            tracing::warn!("Topic tagging failed: {e}, falling back to keywords");
+            None
+        }
+    }
+}
+
+/// Tag messages stored since the last tagged segment. Returns the new segment id, or `None`
+/// when there is not enough new conversation to tag.
+pub async fn tag_conversation_since_last_segment(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    persona_id: i64,
+) -> Result<Option<i64>, MicroClawError> {
+    let messages = call_blocking(db.clone(), move |d| {
+        let since = d.get_last_segment_end(chat_id, persona_id)?;
+        d.get_messages_after(chat_id, persona_id, since.as_deref(), MAX_SEGMENT_MESSAGES)
+    })
+    .await?;
+    if messages.len() < MIN_SEGMENT_MESSAGES {
+        return Ok(None);
+    }
+    let transcript = segment_transcript(&messages);
+    let (tags, summary) = match llm_tags(config, &transcript).await {
+        Some(parsed) => parsed,
+        None => (extract_keywords(&transcript, MAX_TAGS), None),
+    };
+    if tags.is_empty() {
+        return Ok(None);
+    }
+    let start = messages[0].timestamp.clone();
+    let end = messages[messages.len() - 1].timestamp.clone();
+    let id = call_blocking(db, move |d| {
+        d.insert_conversation_segment(chat_id, persona_id, &start, &end, summary.as_deref(), &tags)
+    })
+    .await?;
+    Ok(Some(id))
+}
+
+/// Relevance of a segment to a free-text topic query. Zero means no match.
+pub fn score_segment(segment: &ConversationSegment, query: &str) -> f64 {
+    let query_terms = content_terms(query);
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let phrase = query_terms.join(" ");
+    let mut score = 0.0;
+    for tag in &segment.tags {
+        if *tag == phrase {
+            score += 10.0;
+        } else if tag.contains(&phrase) || phrase.contains(tag.as_str()) {
+            score += 5.0;
+        }
+        let tag_terms = tokenize(tag);
+        let overlap = query_terms.iter().filter(|t| tag_terms.contains(t)).count();
+        score += 2.0 * overlap as f64;
+    }
+    if let Some(summary) = &segment.summary {
+        let summary_terms = tokenize(summary);
+        score += query_terms
+            .iter()
+            .filter(|t| summary_terms.contains(t))
+            .count() as f64;
+    }
+    score
+}
+
+/// Best-matching segments for a query, highest score first (newer wins ties).
+pub fn rank_segments<'a>(
+    segments: &'a [ConversationSegment],
+    query: &str,
+    limit: usize,
+) -> Vec<(f64, &'a ConversationSegment)> {
+    let mut ranked: Vec<(f64, &ConversationSegment)> = segments
+        .iter()
+        .map(|s| (score_segment(s, query), s))
+        .filter(|(score, _)| *score > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.1.end_ts.cmp(&a.1.end_ts))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+/// FTS5 query matching any of the content words in `query`.
+pub fn fts_any_terms_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|w| w.to_lowercase())
+        .filter(|w| w.len() > 1 && !is_stopword(w))
+        .map(|w| format!("\"{w}\""))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(tags: &[&str], summary: Option<&str>, end: &str) -> ConversationSegment {
+        ConversationSegment {
+            id: 1,
+            chat_id: 1,
+            persona_id: 1,
+            start_ts: "2024-01-01T00:00:00Z".into(),
+            end_ts: end.into(),
+            summary: summary.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_stopwords_sorted() {
+        let mut sorted = STOPWORDS.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, STOPWORDS);
+    }
+
+    #[test]
+    fn test_extract_keywords_prefers_phrases() {
+        let text = "We compared car insurance quotes. The car insurance renewal is due. \
+                    Insurance for the car costs more this year.";
+        let tags = extract_keywords(text, 3);
+        assert_eq!(tags[0], "car insurance");
+        assert!(!tags.contains(&"car".to_string()));
+    }
+
+    #[test]
+    fn test_parse_tagging_response() {
+        let (tags, summary) = parse_tagging_response(
+            "Sure:\n{\"tags\": [\"Car Insurance\", \"renewals\", \"car insurance\"], \"summary\": \"Renewal quotes.\"}",
+        );
+        assert_eq!(tags, vec!["car insurance", "renewal"]);
+        assert_eq!(summary.as_deref(), Some("Renewal quotes."));
+
+        let (tags, summary) = parse_tagging_response("- vacation, flights\n- hotel");
+        assert_eq!(tags, vec!["vacation", "flight", "hotel"]);
+        assert!(summary.is_none());
+    }
+
+    #[test]
+    fn test_rank_segments() {
+        let segments = vec![
+            segment(&["vacation", "flight"], None, "2024-02-01T00:00:00Z"),
+            segment(
+                &["car insurance", "renewal"],
+                Some("Compared quotes"),
+                "2024-01-01T00:00:00Z",
+            ),
+            segment(&["car", "oil change"], None, "2024-03-01T00:00:00Z"),
+        ];
+        let ranked = rank_segments(&segments, "car insurance", 5);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].1.tags[0], "car insurance");
+        assert!(rank_segments(&segments, "the", 5).is_empty());
+    }
+
+    #[test]
+    fn test_fts_any_terms_query() {
+        assert_eq!(
+            fts_any_terms_query("the car insurance").as_deref(),
+            Some("\"car\" OR \"insurance\"")
+        );
+        assert_eq!(fts_any_terms_query("the"), None);
+    }
+}
@@ -1,21 +1,67 @@
+//! Speech-to-text for voice messages: a local Whisper server (`transcription.url`, e.g.
+//! whisper.cpp's `/inference` or an OpenAI-compatible `/v1/audio/transcriptions`) if configured,
+//! otherwise the OpenAI Whisper API with `openai_api_key`.
+
 use reqwest::multipart;
 
-pub async fn transcribe_audio(api_key: &str, audio_bytes: &[u8]) -> Result<String, String> {
-    let client = reqwest::Client::new();
+use crate::config::Config;
+
+const OPENAI_TRANSCRIPTIONS_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+
+/// Whether voice messages can be transcribed with this config.
+pub fn is_configured(config: &Config) -> bool {
+    config.transcription.is_some() || config.openai_api_key.is_some()
+}
+
+/// File name Whisper servers use to detect the format of `mime` audio.
+pub fn audio_file_name(mime: &str) -> &'static str {
+    let base = mime.split(';').next().unwrap_or("").trim();
+    match base {
+        "audio/mpeg" | "audio/mp3" => "audio.mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" | "audio/aac" => "audio.m4a",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "audio.wav",
+        "audio/webm" => "audio.webm",
+        "audio/amr" => "audio.amr",
+        _ => "audio.ogg",
+    }
+}
 
+/// Transcribe a voice message with the configured Whisper backend.
+pub async fn transcribe(config: &Config, audio_bytes: &[u8], mime: &str) -> Result<String, String> {
+    let (url, model, language, api_key) = match (&config.transcription, &config.openai_api_key) {
+        (Some(t), _) => (
+            t.url.as_str(),
+            t.model.as_str(),
+            t.language.as_deref(),
+            t.api_key.as_deref(),
+        ),
+        (None, Some(key)) => (
+            OPENAI_TRANSCRIPTIONS_URL,
+            "whisper-1",
+            None,
+            Some(key.as_str()),
+        ),
+        (None, None) => return Err("no Whisper backend configured".into()),
+    };
+    let mime = mime.split(';').next().unwrap_or("audio/ogg").trim();
     let part = multipart::Part::bytes(audio_bytes.to_vec())
-        .file_name("audio.ogg")
-        .mime_str("audio/ogg")
+        .file_name(audio_file_name(mime))
+        .mime_str(mime)
         .map_err(|e| e.to_string())?;
 
-    let form = multipart::Form::new()
-        .text("model", "whisper-1")
+    let mut form = multipart::Form::new()
+        .text("model", model.to_string())
+        .text("response_format", "json")
         .part("file", part);
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
 
-    let resp = client
-        .post("https://api.openai.com/v1/audio/transcriptions")
-        .header("Authorization", format!("Bearer {api_key}"))
-        .multipart(form)
+    let mut req = reqwest::Client::new().post(url).multipart(form);
+    if let Some(key) = api_key {
+        req = req.header("Authorization", format!("Bearer {key}"));
+    }
+    let resp = req
         .send()
         .await
         .map_err(|e| format!("Whisper API request failed: {e}"))?;
@@ -33,14 +79,87 @@ pub async fn transcribe_audio(api_key: &str, audio_bytes: &[u8]) -> Result<Strin
 
     body.get("text")
         .and_then(|t| t.as_str())
-        .map(|s| s.to_string())
+        .map(|s| s.trim().to_string())
         .ok_or_else(|| "Whisper response missing 'text' field".into())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    fn test_transcribe_module_exists() {
-        // Basic smoke test that the module compiles
+    fn test_audio_file_names_and_backend_selection() {
+        assert_eq!(audio_file_name("audio/ogg; codecs=opus"), "audio.ogg");
+        assert_eq!(audio_file_name("audio/mpeg"), "audio.mp3");
+        assert_eq!(audio_file_name("audio/mp4"), "audio.m4a");
+        assert_eq!(audio_file_name("application/octet-stream"), "audio.ogg");
+
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        assert!(!is_configured(&config));
+        config.transcription = serde_yaml::from_str("url: http://localhost:8080/inference").ok();
+        assert!(is_configured(&config));
+        assert_eq!(config.transcription.as_ref().unwrap().model, "whisper-1");
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_sends_multipart_request_to_local_whisper() {
+        use axum::routing::post;
+
+        // Stand-in Whisper server that answers with the request it received
+        let server = axum::Router::new().route(
+            "/inference",
+            post(|headers: axum::http::HeaderMap, body: String| async move {
+                let header = |name: &str| {
+                    headers
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                let received = format!(
+                    "{}\n{}\n{body}",
+                    header("authorization"),
+                    header("content-type")
+                );
+                axum::Json(serde_json::json!({ "text": format!(" {received} ") }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, server).await.unwrap();
+        });
+
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.transcription = serde_yaml::from_str(&format!(
+            "url: http://{addr}/inference\nmodel: base.en\nlanguage: en\napi_key: local-key"
+        ))
+        .ok();
+        let received = transcribe(&config, b"OggS-voice", "audio/mpeg; rate=44100")
+            .await
+            .unwrap();
+
+        let mut lines = received.lines();
+        assert_eq!(lines.next(), Some("Bearer local-key"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("multipart/form-data; boundary="));
+        let body = lines.collect::<Vec<_>>().join("\n");
+        for expected in [
+            "name=\"model\"",
+            "base.en",
+            "name=\"response_format\"",
+            "name=\"language\"",
+            "name=\"file\"; filename=\"audio.mp3\"",
+            "Content-Type: audio/mpeg",
+            "OggS-voice",
+        ] {
+            assert!(body.contains(expected), "{expected} missing from {body}");
+        }
     }
 }
@@ -0,0 +1,400 @@
+//! Translation: DeepL- or LLM-backed text translation with per-chat glossaries of preferred
+//! terms, plus lightweight language detection used to auto-translate inbound messages.
+
+use std::sync::Arc;
+
+use serde_json::json;
+use tracing::warn;
+
+use crate::claude::{Message, MessageContent, ResponseContentBlock};
+use crate::config::Config;
+use crate::db::{call_blocking, Database, GlossaryTerm};
+use crate::error::MicroClawError;
+
+/// Stopwords used to tell Latin-script languages apart.
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "you", "to", "of", "what", "it", "this", "that", "with",
+            "for", "have", "can",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "que", "de", "y", "es", "por", "para", "una", "con", "está",
+            "qué", "cómo",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "que", "des", "une", "pour", "avec", "vous", "je",
+            "pas", "qui", "dans",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ich", "du", "mit", "ein", "eine", "wie",
+            "was", "zu", "auf",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "che", "di", "e", "è", "non", "per", "una", "sono", "gli", "con", "come", "cosa",
+            "della", "questo",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "que", "de", "e", "não", "uma", "para", "com", "os", "você", "está", "como",
+            "isso", "mas", "por",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "niet", "ik", "je", "van", "wat", "met", "voor", "dat",
+            "zijn", "hoe",
+        ],
+    ),
+];
+
+/// Best-effort language detection. Returns an ISO 639-1 code, or `None` when the
+/// text is too short or ambiguous to call.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() < 6 {
+        return None;
+    }
+
+    let count = |pred: fn(&char) -> bool| letters.iter().filter(|c| pred(c)).count();
+    let kana = count(|c| matches!(*c as u32, 0x3040..=0x30FF));
+    let han = count(|c| matches!(*c as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF));
+    let scripts: [(&'static str, usize); 9] = [
+        ("ja", if kana > 0 { kana + han } else { 0 }),
+        ("zh", if kana == 0 { han } else { 0 }),
+        (
+            "ko",
+            count(|c| matches!(*c as u32, 0xAC00..=0xD7AF | 0x1100..=0x11FF)),
+        ),
+        ("ru", count(|c| matches!(*c as u32, 0x0400..=0x04FF))),
+        ("ar", count(|c| matches!(*c as u32, 0x0600..=0x06FF))),
+        ("he", count(|c| matches!(*c as u32, 0x0590..=0x05FF))),
+        ("el", count(|c| matches!(*c as u32, 0x0370..=0x03FF))),
+        ("th", count(|c| matches!(*c as u32, 0x0E00..=0x0E7F))),
+        ("hi", count(|c| matches!(*c as u32, 0x0900..=0x097F))),
+    ];
+    if let Some((lang, n)) = scripts.iter().max_by_key(|(_, n)| *n) {
+        if *n * 2 >= letters.len() {
+            return Some(lang);
+        }
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    if words.len() < 3 {
+        return None;
+    }
+    let mut scores: Vec<(&'static str, usize)> = LATIN_STOPWORDS
+        .iter()
+        .map(|(lang, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|w| stopwords.contains(&w.as_str()))
+                .count();
+            (*lang, hits)
+        })
+        .collect();
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+    match scores.as_slice() {
+        [(lang, best), (_, second), ..] if *best >= 2 && best > second => Some(lang),
+        _ => None,
+    }
+}
+
+fn glossary_regex(term: &str) -> Option<regex::Regex> {
+    regex::Regex::new(&format!(r"(?i)\b{}\b", regex::escape(term))).ok()
+}
+
+/// Translate `text` into `target_lang`, honouring glossary terms. Uses DeepL when a key is
+/// configured, otherwise the configured LLM.
+pub async fn translate_text(
+    config: &Config,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: &str,
+    glossary: &[GlossaryTerm],
+) -> Result<String, MicroClawError> {
+    let deepl_key = config
+        .translation
+        .as_ref()
+        .and_then(|t| t.deepl_api_key.as_deref())
+        .filter(|k| !k.trim().is_empty());
+    match deepl_key {
+        Some(key) => {
+            let base_url = config
+                .translation
+                .as_ref()
+                .and_then(|t| t.deepl_api_url.clone())
+                .unwrap_or_else(|| default_deepl_url(key).to_string());
+            translate_with_deepl(&base_url, key, text, source_lang, target_lang, glossary).await
+        }
+        None => translate_with_llm(config, text, source_lang, target_lang, glossary).await,
+    }
+}
+
+fn default_deepl_url(key: &str) -> &'static str {
+    if key.trim().ends_with(":fx") {
+        "https://api-free.deepl.com"
+    } else {
+        "https://api.deepl.com"
+    }
+}
+
+/// Prepare text for DeepL: XML-escape it and replace glossary terms with their preferred
+/// translation wrapped in `<keep>` so DeepL leaves them untouched.
+fn protect_glossary_terms(text: &str, glossary: &[GlossaryTerm]) -> String {
+    let mut out = html_escape::encode_text(text).to_string();
+    for term in glossary {
+        if let Some(re) = glossary_regex(&html_escape::encode_text(&term.source_term)) {
+            let replacement = format!(
+                "<keep>{}</keep>",
+                html_escape::encode_text(&term.translation)
+            );
+            out = re
+                .replace_all(&out, regex::NoExpand(&replacement))
+                .into_owned();
+        }
+    }
+    out
+}
+
+fn unprotect_glossary_terms(text: &str) -> String {
+    let stripped = text.replace("<keep>", "").replace("</keep>", "");
+    html_escape::decode_html_entities(&stripped).into_owned()
+}
+
+async fn translate_with_deepl(
+    base_url: &str,
+    api_key: &str,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: &str,
+    glossary: &[GlossaryTerm],
+) -> Result<String, MicroClawError> {
+    let mut body = json!({
+        "text": [protect_glossary_terms(text, glossary)],
+        "target_lang": target_lang.to_uppercase(),
+        "tag_handling": "xml",
+        "ignore_tags": ["keep"],
+    });
+    if let Some(src) = source_lang {
+        body["source_lang"] = json!(src.to_uppercase());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?;
+    let resp = client
+        .post(format!("{}/v2/translate", base_url.trim_end_matches('/')))
+        .header("Authorization", format!("DeepL-Auth-Key {api_key}"))
+        .json(&body)
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(MicroClawError::ToolExecution(format!(
+            "DeepL API error HTTP {status}: {body}"
+        )));
+    }
+    let value: serde_json::Value = resp.json().await?;
+    value
+        .get("translations")
+        .and_then(|t| t.get(0))
+        .and_then(|t| t.get("text"))
+        .and_then(|t| t.as_str())
+        .map(unprotect_glossary_terms)
+        .ok_or_else(|| MicroClawError::ToolExecution("DeepL response missing translation".into()))
+}
+
+fn llm_translation_prompt(
+    source_lang: Option<&str>,
+    target_lang: &str,
+    glossary: &[GlossaryTerm],
+) -> String {
+    let mut prompt = format!(
+        "You are a translation engine. Translate the user's text {}into the language with code '{target_lang}'. Preserve meaning, tone, formatting, names and numbers. Output only the translation, with no commentary or quotes.",
+        source_lang
+            .map(|s| format!("from the language with code '{s}' "))
+            .unwrap_or_default()
+    );
+    if !glossary.is_empty() {
+        prompt.push_str(
+            "\n\nAlways use these preferred translations for household-specific terms:\n",
+        );
+        for term in glossary {
+            prompt.push_str(&format!(
+                "- \"{}\" → \"{}\"\n",
+                term.source_term, term.translation
+            ));
+        }
+    }
+    prompt
+}
+
+async fn translate_with_llm(
+    config: &Config,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: &str,
+    glossary: &[GlossaryTerm],
+) -> Result<String, MicroClawError> {
+    let system = llm_translation_prompt(source_lang, target_lang, glossary);
+    let messages = vec![Message {
+        role: "user".into(),
+        content: MessageContent::Text(text.to_string()),
+    }];
+    let provider = crate::llm::create_provider(config);
+    let response = provider.send_message(&system, messages, None).await?;
+    let translated: String = response
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ResponseContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("");
+    let translated = translated.trim();
+    if translated.is_empty() {
+        return Err(MicroClawError::LlmApi("Empty translation response".into()));
+    }
+    Ok(translated.to_string())
+}
+
+/// When inbound auto-translation is enabled and `text` is detected in another language,
+/// returns the content to store: the original followed by the translation. Returns `None`
+/// when nothing should change (disabled, same language, undetectable, or on error).
+pub async fn translate_inbound(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    text: &str,
+) -> Option<String> {
+    let translation = config.translation.as_ref()?;
+    if !translation.auto_translate_inbound
+        || text.trim().is_empty()
+        || text.trim_start().starts_with('/')
+    {
+        return None;
+    }
+    let target = translation.target_language().to_lowercase();
+    let detected = detect_language(text)?;
+    if target.split('-').next() == Some(detected) {
+        return None;
+    }
+
+    let target_for_db = target.clone();
+    let glossary = call_blocking(db, move |d| {
+        d.get_glossary_terms(chat_id, Some(&target_for_db))
+    })
+    .await
+    .unwrap_or_default();
+    match translate_text(config, text, Some(detected), &target, &glossary).await {
+        Ok(translated) => Some(format!(
+            "{text}\n\n[translated from {detected}]: {translated}"
+        )),
+        Err(e) => {
+            warn!("Inbound translation failed for chat {chat_id}: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(source: &str, translation: &str) -> GlossaryTerm {
+        GlossaryTerm {
+            chat_id: 1,
+            source_term: source.into(),
+            target_lang: "en".into(),
+            translation: translation.into(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_detect_language_scripts() {
+        assert_eq!(detect_language("Привет, как у тебя дела?"), Some("ru"));
+        assert_eq!(detect_language("今日はいい天気ですね"), Some("ja"));
+        assert_eq!(detect_language("今天天气很好我们去公园"), Some("zh"));
+        assert_eq!(detect_language("안녕하세요 오늘 날씨가 좋네요"), Some("ko"));
+    }
+
+    #[test]
+    fn test_detect_language_latin() {
+        assert_eq!(
+            detect_language("What is the weather like today and can you check it?"),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("¿Qué tiempo hace hoy en la ciudad de Madrid?"),
+            Some("es")
+        );
+        assert_eq!(
+            detect_language("Ich weiß nicht, wie das funktioniert und was ist das?"),
+            Some("de")
+        );
+        assert_eq!(
+            detect_language("Où est la gare? Je ne trouve pas le chemin avec vous"),
+            Some("fr")
+        );
+    }
+
+    #[test]
+    fn test_detect_language_too_short() {
+        assert_eq!(detect_language("ok"), None);
+        assert_eq!(detect_language("12345 !!!"), None);
+        assert_eq!(detect_language("Hello there"), None);
+    }
+
+    #[test]
+    fn test_protect_glossary_terms_roundtrip() {
+        let glossary = vec![term("Oma", "Nana")];
+        let protected = protect_glossary_terms("Ruf Oma an & sag <hallo>", &glossary);
+        assert_eq!(
+            protected,
+            "Ruf <keep>Nana</keep> an &amp; sag &lt;hallo&gt;"
+        );
+        assert_eq!(
+            unprotect_glossary_terms("Call <keep>Nana</keep> &amp; say &lt;hello&gt;"),
+            "Call Nana & say <hello>"
+        );
+    }
+
+    #[test]
+    fn test_llm_translation_prompt_includes_glossary() {
+        let prompt = llm_translation_prompt(Some("de"), "en", &[term("Oma", "Nana")]);
+        assert!(prompt.contains("from the language with code 'de'"));
+        assert!(prompt.contains("\"Oma\" → \"Nana\""));
+        let plain = llm_translation_prompt(None, "fr", &[]);
+        assert!(!plain.contains("preferred translations"));
+    }
+
+    #[test]
+    fn test_default_deepl_url() {
+        assert_eq!(default_deepl_url("abc:fx"), "https://api-free.deepl.com");
+        assert_eq!(default_deepl_url("abc"), "https://api.deepl.com");
+    }
+}
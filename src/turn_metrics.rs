@@ -0,0 +1,322 @@
+//! Per-turn pipeline timings: how long a message waited before the agent picked it up, then the
+//! time spent in the database, building context, in each LLM call and each tool, and delivering
+//! the reply. Stored in `turn_timings` (kept for `RETENTION_DAYS`) and reported by the
+//! `slow_report` tool, so slow replies can be traced to the model, a tool or the database.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::db::{call_blocking, Database, TurnTiming};
+
+const RETENTION_DAYS: i64 = 30;
+/// Turns looked at when computing where the time went.
+const MAX_TURNS_SCANNED: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTiming {
+    pub stage: String,
+    pub ms: u64,
+}
+
+/// Collects stage timings while the agent runs one turn.
+pub struct TurnTimer {
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Utc>,
+    queue_ms: Option<u64>,
+    stages: Vec<StageTiming>,
+}
+
+impl TurnTimer {
+    pub fn start() -> Self {
+        TurnTimer {
+            started: Instant::now(),
+            started_at: chrono::Utc::now(),
+            queue_ms: None,
+            stages: Vec::new(),
+        }
+    }
+
+    /// Queue wait: from when the triggering message was stored (RFC 3339) until the turn started.
+    pub fn set_queue_wait(&mut self, received_at: &str) {
+        if let Ok(received) = chrono::DateTime::parse_from_rfc3339(received_at) {
+            let waited = self.started_at - received.with_timezone(&chrono::Utc);
+            self.queue_ms = Some(waited.num_milliseconds().max(0) as u64);
+        }
+    }
+
+    /// Add a stage that ran from `since` until now. A stage may be added several times
+    /// (e.g. one `llm` entry per call).
+    pub fn add(&mut self, stage: impl Into<String>, since: Instant) {
+        self.stages.push(StageTiming {
+            stage: stage.into(),
+            ms: since.elapsed().as_millis() as u64,
+        });
+    }
+
+    /// Store the turn; the caller adds the delivery time with `record_delivery` once sent.
+    pub async fn record(&self, db: Arc<Database>, chat_id: i64, persona_id: i64, channel: &str) {
+        let timing = TurnTiming {
+            id: 0,
+            chat_id,
+            persona_id,
+            channel: channel.to_string(),
+            queue_ms: self.queue_ms,
+            agent_ms: self.started.elapsed().as_millis() as u64,
+            delivery_ms: None,
+            stages: serde_json::to_string(&self.stages).unwrap_or_else(|_| "[]".into()),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let prune_before =
+            (chrono::Utc::now() - chrono::Duration::days(RETENTION_DAYS)).to_rfc3339();
+        if let Err(e) =
+            call_blocking(db, move |d| d.record_turn_timing(&timing, &prune_before)).await
+        {
+            warn!("turn timings: failed to record turn for chat {chat_id}: {e}");
+        }
+    }
+}
+
+/// Record how long sending the reply took (from `since` until now) on the chat's latest turn.
+pub async fn record_delivery(db: Arc<Database>, chat_id: i64, since: Instant) {
+    let ms = since.elapsed().as_millis() as u64;
+    if let Err(e) = call_blocking(db, move |d| d.set_turn_delivery(chat_id, ms)).await {
+        warn!("turn timings: failed to record delivery for chat {chat_id}: {e}");
+    }
+}
+
+fn total_ms(turn: &TurnTiming) -> u64 {
+    turn.queue_ms.unwrap_or(0) + turn.agent_ms + turn.delivery_ms.unwrap_or(0)
+}
+
+fn stages(turn: &TurnTiming) -> Vec<StageTiming> {
+    serde_json::from_str(&turn.stages).unwrap_or_default()
+}
+
+/// Where a stage's time is attributed in the report summary.
+fn category(stage: &str) -> &'static str {
+    match stage {
+        "llm" | "compaction" | "orchestrator" => "model",
+        "db" => "db",
+        s if s.starts_with("tool:") => "tools",
+        _ => "context",
+    }
+}
+
+fn secs(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+/// One-line breakdown, e.g. `queue 0.2s · db 0.1s · llm 8.1s (3 calls) · tools 3.5s (web_fetch 3.1s, bash 0.4s)`.
+pub fn breakdown(turn: &TurnTiming) -> String {
+    let stages = stages(turn);
+    let sum = |name: &str| -> (u64, usize) {
+        stages
+            .iter()
+            .filter(|s| s.stage == name)
+            .fold((0, 0), |(ms, n), s| (ms + s.ms, n + 1))
+    };
+    let mut parts = Vec::new();
+    if let Some(queue) = turn.queue_ms {
+        parts.push(format!("queue {}", secs(queue)));
+    }
+    for name in ["db", "context", "compaction", "orchestrator"] {
+        let (ms, n) = sum(name);
+        if n > 0 {
+            parts.push(format!("{name} {}", secs(ms)));
+        }
+    }
+    let (llm_ms, llm_calls) = sum("llm");
+    if llm_calls > 0 {
+        parts.push(format!(
+            "llm {} ({llm_calls} call{})",
+            secs(llm_ms),
+            if llm_calls == 1 { "" } else { "s" }
+        ));
+    }
+    let mut tools: Vec<(String, u64)> = Vec::new();
+    for stage in &stages {
+        if let Some(name) = stage.stage.strip_prefix("tool:") {
+            match tools.iter_mut().find(|(n, _)| n == name) {
+                Some((_, ms)) => *ms += stage.ms,
+                None => tools.push((name.to_string(), stage.ms)),
+            }
+        }
+    }
+    if !tools.is_empty() {
+        tools.sort_by_key(|(_, ms)| std::cmp::Reverse(*ms));
+        let total: u64 = tools.iter().map(|(_, ms)| ms).sum();
+        let each: Vec<String> = tools
+            .iter()
+            .map(|(name, ms)| format!("{name} {}", secs(*ms)))
+            .collect();
+        parts.push(format!("tools {} ({})", secs(total), each.join(", ")));
+    }
+    let (outbound, n) = sum("outbound");
+    if n > 0 {
+        parts.push(format!("outbound {}", secs(outbound)));
+    }
+    if let Some(delivery) = turn.delivery_ms {
+        parts.push(format!("delivery {}", secs(delivery)));
+    }
+    parts.join(" · ")
+}
+
+/// Share of the time per category over `turns`, largest first, e.g. `model 61%, tools 28%`.
+fn time_shares(turns: &[TurnTiming]) -> String {
+    let mut totals: Vec<(&'static str, u64)> = Vec::new();
+    let mut add = |cat: &'static str, ms: u64| match totals.iter_mut().find(|(c, _)| *c == cat) {
+        Some((_, total)) => *total += ms,
+        None => totals.push((cat, ms)),
+    };
+    for turn in turns {
+        add("queue", turn.queue_ms.unwrap_or(0));
+        add("delivery", turn.delivery_ms.unwrap_or(0));
+        let stages = stages(turn);
+        let staged: u64 = stages.iter().map(|s| s.ms).sum();
+        for stage in &stages {
+            add(category(&stage.stage), stage.ms);
+        }
+        // Time between stages (session bookkeeping, event sends)
+        add("context", turn.agent_ms.saturating_sub(staged));
+    }
+    let all: u64 = totals.iter().map(|(_, ms)| ms).sum();
+    if all == 0 {
+        return String::new();
+    }
+    totals.sort_by_key(|(_, ms)| std::cmp::Reverse(*ms));
+    totals
+        .iter()
+        .filter(|(_, ms)| *ms * 100 >= all)
+        .map(|(cat, ms)| format!("{cat} {}%", ms * 100 / all))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The `limit` slowest turns of the last `hours`, optionally for one chat, with their breakdown.
+pub async fn slow_report(
+    db: Arc<Database>,
+    hours: u64,
+    chat_id: Option<i64>,
+    limit: usize,
+) -> Result<String, String> {
+    let since = (chrono::Utc::now() - chrono::Duration::hours(hours as i64)).to_rfc3339();
+    let turns = call_blocking(db, move |d| {
+        d.slowest_turns(&since, chat_id, MAX_TURNS_SCANNED)
+    })
+    .await
+    .map_err(|e| format!("Failed to load turn timings: {e}"))?;
+    if turns.is_empty() {
+        return Ok(format!("No timed turns in the last {hours}h."));
+    }
+    let mut out = format!(
+        "Slowest turns in the last {hours}h ({} timed; time spent: {}):\n",
+        turns.len(),
+        time_shares(&turns)
+    );
+    for (i, turn) in turns.iter().take(limit).enumerate() {
+        let when = chrono::DateTime::parse_from_rfc3339(&turn.created_at)
+            .map(|t| {
+                t.with_timezone(&chrono::Utc)
+                    .format("%Y-%m-%d %H:%M UTC")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| turn.created_at.clone());
+        out.push_str(&format!(
+            "\n{}. {} — chat {} ({}), {when}\n   {}\n",
+            i + 1,
+            secs(total_ms(turn)),
+            turn.chat_id,
+            turn.channel,
+            breakdown(turn)
+        ));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(stages: &[(&str, u64)], queue_ms: Option<u64>, delivery_ms: Option<u64>) -> TurnTiming {
+        let stages: Vec<StageTiming> = stages
+            .iter()
+            .map(|(stage, ms)| StageTiming {
+                stage: stage.to_string(),
+                ms: *ms,
+            })
+            .collect();
+        TurnTiming {
+            chat_id: 5,
+            channel: "telegram".into(),
+            queue_ms,
+            agent_ms: stages.iter().map(|s| s.ms).sum(),
+            delivery_ms,
+            stages: serde_json::to_string(&stages).unwrap(),
+            created_at: "2026-10-16T09:12:00Z".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_breakdown_groups_llm_calls_and_tools() {
+        let t = turn(
+            &[
+                ("db", 100),
+                ("context", 300),
+                ("llm", 2000),
+                ("tool:bash", 400),
+                ("tool:web_fetch", 3100),
+                ("llm", 1500),
+                ("tool:bash", 100),
+                ("llm", 1000),
+            ],
+            Some(200),
+            Some(150),
+        );
+        assert_eq!(
+            breakdown(&t),
+            "queue 0.2s · db 0.1s · context 0.3s · llm 4.5s (3 calls) · tools 3.6s (web_fetch 3.1s, bash 0.5s) · delivery 0.1s"
+        );
+    }
+
+    #[test]
+    fn test_time_shares_by_category() {
+        let turns = vec![
+            turn(
+                &[("llm", 6000), ("tool:bash", 3000), ("db", 500)],
+                Some(500),
+                None,
+            ),
+            turn(&[("compaction", 0)], None, None),
+        ];
+        assert_eq!(time_shares(&turns), "model 60%, tools 30%, queue 5%, db 5%");
+    }
+
+    #[tokio::test]
+    async fn test_timer_records_and_reports() {
+        let dir = std::env::temp_dir().join(format!("microclaw_turns_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        assert_eq!(
+            slow_report(db.clone(), 24, None, 5).await.unwrap(),
+            "No timed turns in the last 24h."
+        );
+
+        let mut timer = TurnTimer::start();
+        timer.set_queue_wait(&(chrono::Utc::now() - chrono::Duration::seconds(2)).to_rfc3339());
+        timer.add("llm", Instant::now());
+        timer.add("tool:bash", Instant::now());
+        timer.record(db.clone(), 5, 1, "telegram").await;
+        record_delivery(db.clone(), 5, Instant::now()).await;
+
+        let report = slow_report(db, 24, Some(5), 5).await.unwrap();
+        assert!(report.contains("1 timed"), "{report}");
+        assert!(report.contains("chat 5 (telegram)"));
+        assert!(report.contains("queue 2.0s"), "{report}");
+        assert!(report.contains("llm 0.0s (1 call) · tools 0.0s (bash 0.0s)"));
+        assert!(report.contains("delivery 0.0s"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
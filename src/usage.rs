@@ -0,0 +1,256 @@
+//! Per-turn LLM usage accounting (tokens, estimated cost, latency, tool calls) and the optional
+//! per-chat reply footer that surfaces it.
+
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::config::{Config, ModelPrice};
+use crate::db::{call_blocking, Database};
+
+/// `chat_settings` key for the reply footer toggle.
+pub const FOOTER_SETTING: &str = "usage_footer";
+
+/// Built-in prices (USD per million tokens) matched by model-name prefix; the longest prefix
+/// wins. `model_prices` in the config overrides these.
+const BUILTIN_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus", 15.0, 75.0),
+    ("claude-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-haiku", 1.0, 5.0),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1-nano", 0.1, 0.4),
+];
+
+/// Usage accumulated over one agent turn (all LLM rounds of a single reply).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TurnUsage {
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub tool_calls: u64,
+    pub duration_ms: u64,
+}
+
+/// Price for `model`: exact config entry, then longest matching built-in prefix.
+pub fn price_for(config: &Config, model: &str) -> Option<ModelPrice> {
+    if let Some(price) = config.model_prices.get(model) {
+        return Some(*price);
+    }
+    let lower = model.to_lowercase();
+    BUILTIN_PRICES
+        .iter()
+        .filter(|(prefix, _, _)| lower.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(_, input, output)| ModelPrice {
+            input: *input,
+            output: *output,
+        })
+}
+
+/// Estimated cost in USD, or `None` when the model has no known price.
+pub fn estimate_cost(config: &Config, usage: &TurnUsage) -> Option<f64> {
+    let price = price_for(config, &usage.model)?;
+    Some(
+        (usage.input_tokens as f64 * price.input + usage.output_tokens as f64 * price.output)
+            / 1_000_000.0,
+    )
+}
+
+fn compact_tokens(n: u64) -> String {
+    if n >= 1_000_000 {
+        format!("{:.1}M", n as f64 / 1_000_000.0)
+    } else if n >= 1_000 {
+        format!("{:.1}k", n as f64 / 1_000.0)
+    } else {
+        n.to_string()
+    }
+}
+
+/// One-line footer, e.g. `— claude-sonnet-4 · 1.2k in / 340 out · ~$0.0087 · 4.2s · 3 tools`.
+pub fn format_footer(usage: &TurnUsage, cost: Option<f64>) -> String {
+    let mut parts = vec![
+        usage.model.clone(),
+        format!(
+            "{} in / {} out",
+            compact_tokens(usage.input_tokens),
+            compact_tokens(usage.output_tokens)
+        ),
+    ];
+    if let Some(cost) = cost {
+        parts.push(if cost < 0.01 {
+            format!("~${cost:.4}")
+        } else {
+            format!("~${cost:.2}")
+        });
+    }
+    parts.push(format!("{:.1}s", usage.duration_ms as f64 / 1000.0));
+    if usage.tool_calls > 0 {
+        parts.push(format!(
+            "{} tool{}",
+            usage.tool_calls,
+            if usage.tool_calls == 1 { "" } else { "s" }
+        ));
+    }
+    format!("— {}", parts.join(" · "))
+}
+
+pub async fn footer_enabled(db: Arc<Database>, chat_id: i64) -> bool {
+    call_blocking(db, move |d| d.get_chat_setting(chat_id, FOOTER_SETTING))
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "on")
+}
+
+/// Record a finished turn and return the footer to append when the chat has it enabled.
+pub async fn record_turn(
+    config: &Config,
+    db: Arc<Database>,
+    chat_id: i64,
+    persona_id: i64,
+    usage: &TurnUsage,
+) -> Option<String> {
+    let cost = estimate_cost(config, usage);
+    let record = usage.clone();
+    if let Err(e) = call_blocking(db.clone(), move |d| {
+        d.record_llm_usage(
+            chat_id,
+            persona_id,
+            &record.model,
+            record.input_tokens,
+            record.output_tokens,
+            record.tool_calls,
+            record.duration_ms,
+            cost,
+        )
+    })
+    .await
+    {
+        warn!("usage: failed to record turn for chat {chat_id}: {e}");
+    }
+    if footer_enabled(db, chat_id).await {
+        Some(format_footer(usage, cost))
+    } else {
+        None
+    }
+}
+
+/// Handle `/footer [on|off]`: toggle or show the reply footer and today's usage for the chat.
+pub async fn handle_footer_command(db: Arc<Database>, chat_id: i64, text: &str) -> String {
+    let arg = text
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("")
+        .to_lowercase();
+    let value = match arg.as_str() {
+        "on" => Some("on"),
+        "off" => None,
+        "" => {
+            let enabled = footer_enabled(db.clone(), chat_id).await;
+            let since = chrono::Utc::now().format("%Y-%m-%dT00:00:00").to_string();
+            let totals =
+                call_blocking(db, move |d| d.get_llm_usage_totals(chat_id, &since)).await;
+            let mut resp = format!(
+                "Usage footer is {}. Use /footer on or /footer off.",
+                if enabled { "on" } else { "off" }
+            );
+            if let Ok((turns, input, output, cost)) = totals {
+                if turns > 0 {
+                    resp.push_str(&format!(
+                        "\nToday: {turns} replies, {} in / {} out tokens, ~${cost:.2}",
+                        compact_tokens(input),
+                        compact_tokens(output)
+                    ));
+                }
+            }
+            return resp;
+        }
+        _ => return "Usage: /footer [on|off]".into(),
+    };
+    match call_blocking(db, move |d| d.set_chat_setting(chat_id, FOOTER_SETTING, value)).await {
+        Ok(()) if value.is_some() => "Usage footer enabled for this chat.".into(),
+        Ok(()) => "Usage footer disabled for this chat.".into(),
+        Err(e) => format!("Failed to update footer setting: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n").unwrap()
+    }
+
+    #[test]
+    fn test_price_lookup_prefers_config_and_longest_prefix() {
+        let mut config = test_config();
+        assert_eq!(price_for(&config, "gpt-4o-mini-2024").unwrap().input, 0.15);
+        assert_eq!(price_for(&config, "gpt-4o-2024-08-06").unwrap().input, 2.5);
+        assert!(price_for(&config, "llama3").is_none());
+        config.model_prices.insert(
+            "llama3".into(),
+            ModelPrice {
+                input: 0.1,
+                output: 0.2,
+            },
+        );
+        assert_eq!(price_for(&config, "llama3").unwrap().output, 0.2);
+    }
+
+    #[test]
+    fn test_format_footer() {
+        let usage = TurnUsage {
+            model: "claude-sonnet-4".into(),
+            input_tokens: 1234,
+            output_tokens: 340,
+            tool_calls: 3,
+            duration_ms: 4210,
+        };
+        let cost = estimate_cost(&test_config(), &usage);
+        assert_eq!(
+            format_footer(&usage, cost),
+            "— claude-sonnet-4 · 1.2k in / 340 out · ~$0.0088 · 4.2s · 3 tools"
+        );
+        let usage = TurnUsage {
+            model: "local".into(),
+            tool_calls: 1,
+            ..usage
+        };
+        assert_eq!(
+            format_footer(&usage, None),
+            "— local · 1.2k in / 340 out · 4.2s · 1 tool"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_footer_toggle_and_record() {
+        let dir = std::env::temp_dir().join(format!("microclaw_usage_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let config = test_config();
+        let usage = TurnUsage {
+            model: "gpt-4o".into(),
+            input_tokens: 1000,
+            output_tokens: 100,
+            tool_calls: 0,
+            duration_ms: 900,
+        };
+        assert!(record_turn(&config, db.clone(), 5, 1, &usage).await.is_none());
+        assert!(handle_footer_command(db.clone(), 5, "/footer on")
+            .await
+            .contains("enabled"));
+        let footer = record_turn(&config, db.clone(), 5, 1, &usage).await.unwrap();
+        assert!(footer.starts_with("— gpt-4o · 1.0k in / 100 out"), "{footer}");
+        let status = handle_footer_command(db.clone(), 5, "/footer").await;
+        assert!(status.contains("is on") && status.contains("2 replies"), "{status}");
+        handle_footer_command(db.clone(), 5, "/footer off").await;
+        assert!(!footer_enabled(db.clone(), 5).await);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
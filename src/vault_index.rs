@@ -0,0 +1,469 @@
+//! Native vault indexer: walks the ORIGIN vault's markdown notes, splits them into
+//! heading-scoped chunks, embeds them through [`crate::embeddings::Embedder`] (batched and
+//! cached, so only changed chunks reach the embedding server) and upserts them into the
+//! ChromaDB collection that `search_vault` queries. Chunks of deleted notes are removed.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::config::Config;
+use crate::embeddings::Embedder;
+
+const MAX_CHUNK_CHARS: usize = 1500;
+const UPSERT_BATCH: usize = 256;
+
+/// Absolute path of the ORIGIN vault, when configured.
+pub fn vault_root(config: &Config) -> Option<PathBuf> {
+    let path = config.vault.as_ref()?.origin_vault_path.as_deref()?.trim();
+    if path.is_empty() {
+        return None;
+    }
+    let path = Path::new(path);
+    Some(if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        config.workspace_root_absolute().join(path)
+    })
+}
+
+/// Files under `root`, relative to it and sorted. Dot-directories and dot-files (`.obsidian`,
+/// `.git`, `.trash`) are skipped.
+pub fn vault_files(root: &Path) -> Vec<PathBuf> {
+    fn walk(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, out);
+            } else if let Ok(rel) = path.strip_prefix(root) {
+                out.push(rel.to_path_buf());
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(root, root, &mut out);
+    out.sort();
+    out
+}
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("md"))
+}
+
+/// Markdown notes under `root` (see [`vault_files`]).
+pub fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    vault_files(root)
+        .into_iter()
+        .filter(|p| is_markdown(p))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// `<path>#<n>`: stable as long as the note's structure doesn't change.
+    pub id: String,
+    pub path: String,
+    /// Nearest heading above the chunk (empty before the first heading).
+    pub heading: String,
+    pub text: String,
+}
+
+fn heading_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+        Some(trimmed[level..].trim())
+    } else {
+        None
+    }
+}
+
+/// Split a note into chunks of at most `max_chars`: one section per heading, long sections
+/// split on paragraph boundaries (and very long paragraphs on character count). YAML
+/// frontmatter is dropped.
+pub fn chunk_note(rel_path: &str, content: &str, max_chars: usize) -> Vec<Chunk> {
+    let mut body = content;
+    if let Some(rest) = content.strip_prefix("---\n") {
+        if let Some(end) = rest.find("\n---") {
+            body = rest[end + 4..]
+                .split_once('\n')
+                .map_or("", |(_, after)| after);
+        }
+    }
+
+    let mut sections: Vec<(String, Vec<&str>)> = vec![(String::new(), Vec::new())];
+    for line in body.lines() {
+        match heading_text(line) {
+            Some(h) => sections.push((h.to_string(), vec![line])),
+            None => sections.last_mut().unwrap().1.push(line),
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut push = |heading: &str, text: &str| {
+        let text = text.trim();
+        // A heading with nothing under it isn't worth a chunk
+        if text.is_empty() || (heading_text(text) == Some(heading) && !text.contains('\n')) {
+            return;
+        }
+        chunks.push(Chunk {
+            id: format!("{rel_path}#{}", chunks.len()),
+            path: rel_path.to_string(),
+            heading: heading.to_string(),
+            text: text.to_string(),
+        });
+    };
+    for (heading, lines) in sections {
+        let section = lines.join("\n");
+        let mut current = String::new();
+        for paragraph in section.split("\n\n") {
+            if !current.is_empty() && current.len() + paragraph.len() + 2 > max_chars {
+                push(&heading, &current);
+                current.clear();
+            }
+            if paragraph.len() > max_chars {
+                let chars: Vec<char> = paragraph.chars().collect();
+                for piece in chars.chunks(max_chars) {
+                    push(&heading, &piece.iter().collect::<String>());
+                }
+                continue;
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(paragraph);
+        }
+        push(&heading, &current);
+    }
+    chunks
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexReport {
+    pub notes: usize,
+    pub chunks: usize,
+    pub cached: usize,
+    pub embedded: usize,
+    pub embedding_requests: usize,
+    pub removed: usize,
+}
+
+impl IndexReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Indexed {} notes ({} chunks): {} embeddings from cache, {} new in {} requests; {} stale chunks removed.",
+            self.notes,
+            self.chunks,
+            self.cached,
+            self.embedded,
+            self.embedding_requests,
+            self.removed
+        )
+    }
+}
+
+async fn chroma_post(
+    http: &reqwest::Client,
+    url: String,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let response = http
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("ChromaDB unreachable: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("ChromaDB request failed ({status}): {body}"));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse ChromaDB response: {e}"))
+}
+
+/// Re-index the whole vault into the configured ChromaDB collection.
+pub async fn index_vault(config: &Config, embedder: &Embedder) -> Result<IndexReport, String> {
+    let vault = config.vault.as_ref().ok_or("Vault is not configured")?;
+    let root = vault_root(config).ok_or("vault.origin_vault_path is not set")?;
+    let db_url = vault
+        .vector_db_url
+        .as_deref()
+        .map(|u| u.trim().trim_end_matches('/'))
+        .filter(|u| !u.is_empty())
+        .ok_or("vault.vector_db_url is not set")?;
+    let collection = vault.vector_db_collection.as_deref().unwrap_or("vault");
+
+    let files = tokio::task::spawn_blocking(move || {
+        markdown_files(&root)
+            .into_iter()
+            .filter_map(|rel| {
+                let content = std::fs::read_to_string(root.join(&rel)).ok()?;
+                Some((rel.to_string_lossy().replace('\\', "/"), content))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let chunks: Vec<Chunk> = files
+        .iter()
+        .flat_map(|(rel, content)| chunk_note(rel, content, MAX_CHUNK_CHARS))
+        .collect();
+    let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+    let (vectors, stats) = embedder.embed(&texts).await?;
+
+    let http = reqwest::Client::new();
+    let created = chroma_post(
+        &http,
+        format!("{db_url}/api/v1/collections"),
+        json!({"name": collection, "get_or_create": true}),
+    )
+    .await?;
+    let collection_id = created
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or("Could not find collection ID in ChromaDB response")?;
+    let base = format!("{db_url}/api/v1/collections/{collection_id}");
+
+    for (batch, batch_vectors) in chunks
+        .chunks(UPSERT_BATCH)
+        .zip(vectors.chunks(UPSERT_BATCH))
+    {
+        chroma_post(
+            &http,
+            format!("{base}/upsert"),
+            json!({
+                "ids": batch.iter().map(|c| &c.id).collect::<Vec<_>>(),
+                "embeddings": batch_vectors,
+                "documents": batch.iter().map(|c| &c.text).collect::<Vec<_>>(),
+                "metadatas": batch
+                    .iter()
+                    .map(|c| json!({"source": c.path, "heading": c.heading}))
+                    .collect::<Vec<_>>(),
+            }),
+        )
+        .await?;
+    }
+
+    // The collection mirrors the vault: drop chunks that no longer exist
+    let existing = chroma_post(&http, format!("{base}/get"), json!({"include": []})).await?;
+    let current: HashSet<&str> = chunks.iter().map(|c| c.id.as_str()).collect();
+    let stale: Vec<&str> = existing
+        .get("ids")
+        .and_then(|v| v.as_array())
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .filter(|id| !current.contains(id))
+                .collect()
+        })
+        .unwrap_or_default();
+    if !stale.is_empty() {
+        chroma_post(&http, format!("{base}/delete"), json!({"ids": stale})).await?;
+    }
+
+    Ok(IndexReport {
+        notes: files.len(),
+        chunks: chunks.len(),
+        cached: stats.cached,
+        embedded: stats.embedded,
+        embedding_requests: stats.requests,
+        removed: stale.len(),
+    })
+}
+
+/// Name Obsidian knows the vault by: `obsidian_vault_name`, else the vault directory's name.
+pub fn obsidian_vault_name(config: &Config) -> Option<String> {
+    let configured = config
+        .vault
+        .as_ref()?
+        .obsidian_vault_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+    match configured {
+        Some(name) => Some(name.to_string()),
+        None => vault_root(config)?
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string()),
+    }
+}
+
+/// Vault-relative note path without `.md`, from a chunk's `source` metadata (relative, or
+/// absolute under `root` as some external indexers store it).
+pub fn note_path(source: &str, root: Option<&Path>) -> String {
+    let source = source.replace('\\', "/");
+    let rel = match root.and_then(|r| Path::new(&source).strip_prefix(r).ok()) {
+        Some(rel) => rel.to_string_lossy().replace('\\', "/"),
+        None => source.trim_start_matches("./").to_string(),
+    };
+    rel.strip_suffix(".md").unwrap_or(&rel).to_string()
+}
+
+/// Wiki-link citation of a note section, e.g. `[[Projects/home#Plans]]`.
+pub fn citation(path: &str, heading: &str) -> String {
+    let heading: String = heading
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | '|' | '#'))
+        .collect();
+    if heading.trim().is_empty() {
+        format!("[[{path}]]")
+    } else {
+        format!("[[{path}#{}]]", heading.trim())
+    }
+}
+
+/// `obsidian://` URI opening `target` (`path` or `path#heading`) in `vault`.
+pub fn obsidian_uri(vault: &str, target: &str) -> String {
+    format!(
+        "obsidian://open?vault={}&file={}",
+        urlencoding::encode(vault),
+        urlencoding::encode(target)
+    )
+}
+
+fn citation_link(inner: &str, vault: &str) -> Option<String> {
+    if !inner.chars().any(char::is_alphabetic) || inner.contains(['"', '[', '{', '\n']) {
+        return None;
+    }
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target.trim(), Some(alias.trim())),
+        None => (inner.trim(), None),
+    };
+    let label = match (alias, target.split_once('#')) {
+        (Some(alias), _) if !alias.is_empty() => alias.to_string(),
+        (_, Some((path, heading))) => format!(
+            "{} › {}",
+            path.rsplit('/').next().unwrap_or(path),
+            heading.trim()
+        ),
+        _ => target.rsplit('/').next().unwrap_or(target).to_string(),
+    };
+    Some(format!(
+        "[{}]({})",
+        label.replace(['[', ']'], ""),
+        obsidian_uri(vault, target)
+    ))
+}
+
+/// Turn `[[note#heading]]` citations (as `search_vault` returns them) into markdown links that
+/// open the note in Obsidian. Code spans and fenced blocks are left alone.
+pub fn link_citations(text: &str, vault: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let next = ["```", "`", "[["]
+            .iter()
+            .filter_map(|m| rest.find(m).map(|i| (i, *m)))
+            .min_by_key(|(i, m)| (*i, std::cmp::Reverse(m.len())));
+        let Some((i, marker)) = next else {
+            out.push_str(rest);
+            break;
+        };
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let inner_end = rest[marker.len()..].find(if marker == "[[" { "]]" } else { marker });
+        match (marker, inner_end) {
+            ("[[", Some(end)) => {
+                let inner = &rest[2..2 + end];
+                match citation_link(inner, vault) {
+                    Some(link) => out.push_str(&link),
+                    None => out.push_str(&rest[..end + 4]),
+                }
+                rest = &rest[end + 4..];
+            }
+            (_, Some(end)) => {
+                let stop = end + 2 * marker.len();
+                out.push_str(&rest[..stop]);
+                rest = &rest[stop..];
+            }
+            (_, None) => {
+                out.push_str(rest);
+                break;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_note_by_heading_and_size() {
+        let note = "---\ntags: [a]\n---\nIntro line.\n\n# Plans\n\nFirst paragraph.\n\nSecond paragraph.\n\n## Empty\n\n## Long\n\n".to_string()
+            + &"word ".repeat(10);
+        let chunks = chunk_note("Projects/home.md", &note, 30);
+        let summary: Vec<(&str, &str)> = chunks
+            .iter()
+            .map(|c| (c.heading.as_str(), c.id.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("", "Projects/home.md#0"),
+                ("Plans", "Projects/home.md#1"),
+                ("Plans", "Projects/home.md#2"),
+                ("Long", "Projects/home.md#3"),
+                ("Long", "Projects/home.md#4"),
+            ]
+        );
+        assert_eq!(chunks[0].text, "Intro line.");
+        assert_eq!(chunks[1].text, "# Plans\n\nFirst paragraph.");
+        assert!(chunks.iter().all(|c| !c.text.contains("tags:")));
+    }
+
+    #[test]
+    fn test_markdown_files_skips_hidden() {
+        let dir = std::env::temp_dir().join(format!("microclaw_vault_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("Daily")).unwrap();
+        std::fs::create_dir_all(dir.join(".obsidian")).unwrap();
+        std::fs::write(dir.join("Daily/2026-01-01.md"), "x").unwrap();
+        std::fs::write(dir.join("Inbox.MD"), "x").unwrap();
+        std::fs::write(dir.join("image.png"), "x").unwrap();
+        std::fs::write(dir.join(".obsidian/workspace.md"), "x").unwrap();
+        assert_eq!(
+            markdown_files(&dir),
+            vec![
+                PathBuf::from("Daily/2026-01-01.md"),
+                PathBuf::from("Inbox.MD")
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_citations_become_obsidian_links() {
+        let root = Path::new("/w/shared/ORIGIN");
+        assert_eq!(
+            note_path("/w/shared/ORIGIN/Projects/home.md", Some(root)),
+            "Projects/home"
+        );
+        assert_eq!(note_path("Inbox.md", Some(root)), "Inbox");
+        assert_eq!(
+            citation("Projects/home", "Plans [v2]"),
+            "[[Projects/home#Plans v2]]"
+        );
+        assert_eq!(citation("Inbox", ""), "[[Inbox]]");
+
+        let text =
+            "See [[Projects/home#Plans]] and [[Inbox|my inbox]]. Code: `[[x]]`, data [[1, 2]].";
+        assert_eq!(
+            link_citations(text, "My Vault"),
+            "See [home › Plans](obsidian://open?vault=My%20Vault&file=Projects%2Fhome%23Plans) \
+             and [my inbox](obsidian://open?vault=My%20Vault&file=Inbox). Code: `[[x]]`, data [[1, 2]]."
+        );
+        assert_eq!(link_citations("a [[unclosed", "v"), "a [[unclosed");
+    }
+}
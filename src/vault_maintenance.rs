@@ -0,0 +1,424 @@
+//! Vault maintenance: every `vault.maintenance.interval_days` the ORIGIN vault is scanned (with
+//! the indexer's file walk) for wiki-links and embeds that point nowhere, attachments no note
+//! references, and old daily notes that are empty or still have open tasks. The findings are
+//! written to a report note in the vault and summarised in a chat. The date of the newest report
+//! note is the schedule, so restarts neither skip nor repeat a run.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use tracing::{info, warn};
+
+use crate::channel::deliver_and_store_bot_message;
+use crate::config::{Config, VaultMaintenanceConfig};
+use crate::db::call_blocking;
+use crate::telegram::AppState;
+use crate::vault_index::{is_markdown, obsidian_uri, obsidian_vault_name, vault_files, vault_root};
+
+const CHECK_EVERY: Duration = Duration::from_secs(3600);
+const REPORT_PREFIX: &str = "Vault maintenance ";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MaintenanceReport {
+    pub notes: usize,
+    pub attachments: usize,
+    /// (note, link target)
+    pub broken_links: Vec<(String, String)>,
+    pub orphaned_attachments: Vec<String>,
+    /// (note, reason)
+    pub stale_daily_notes: Vec<(String, &'static str)>,
+}
+
+/// Link targets in a note: wiki-links and embeds (`[[x]]`, `![[x|alias]]`) and relative
+/// markdown links (`[x](path)`), without `#heading` parts. Fenced code is skipped.
+pub fn link_targets(content: &str) -> Vec<(String, bool)> {
+    let mut targets = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let mut rest = line;
+        while let Some(open) = rest.find("[[") {
+            let after = &rest[open + 2..];
+            let Some(close) = after.find("]]") else {
+                break;
+            };
+            let target = after[..close].split('|').next().unwrap_or("");
+            let target = target.split('#').next().unwrap_or("").trim();
+            if !target.is_empty() {
+                targets.push((target.to_string(), true));
+            }
+            rest = &after[close + 2..];
+        }
+        let mut rest = line;
+        while let Some(open) = rest.find("](") {
+            let after = &rest[open + 2..];
+            let Some(close) = after.find(')') else {
+                break;
+            };
+            let raw = after[..close]
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>');
+            let target = raw.split('#').next().unwrap_or("");
+            if !target.is_empty() && !target.contains(':') {
+                let decoded = urlencoding::decode(target)
+                    .map(|t| t.into_owned())
+                    .unwrap_or_else(|_| target.to_string());
+                targets.push((decoded, false));
+            }
+            rest = &after[close + 1..];
+        }
+    }
+    targets
+}
+
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            p => parts.push(p),
+        }
+    }
+    parts.join("/").to_lowercase()
+}
+
+/// The vault file a link from `note` points to, resolved like Obsidian does: by path (with or
+/// without `.md`), relative to the note for markdown links, else by file name anywhere.
+fn resolve<'a>(files: &'a [String], note: &str, target: &str, wiki: bool) -> Option<&'a String> {
+    let target = target.replace('\\', "/");
+    let mut candidates = vec![normalize(&target)];
+    if !wiki {
+        let dir = note.rsplit_once('/').map_or("", |(dir, _)| dir);
+        candidates.insert(0, normalize(&format!("{dir}/{target}")));
+    }
+    let with_md: Vec<String> = candidates.iter().map(|c| format!("{c}.md")).collect();
+    candidates.extend(with_md);
+    if let Some(found) = files
+        .iter()
+        .find(|f| candidates.contains(&f.to_lowercase()))
+    {
+        return Some(found);
+    }
+    // Links may leave out folders: match on the path suffix (usually just the file name)
+    files.iter().find(|f| {
+        let f = f.to_lowercase();
+        candidates
+            .iter()
+            .any(|c| f.ends_with(&format!("/{c}")) || f == *c)
+    })
+}
+
+fn daily_note_date(path: &str, settings: &VaultMaintenanceConfig) -> Option<NaiveDate> {
+    if let Some(folder) = settings.daily_notes_folder.as_deref() {
+        let folder = folder.trim().trim_matches('/');
+        if !folder.is_empty() && !path.starts_with(&format!("{folder}/")) {
+            return None;
+        }
+    }
+    let stem = path.rsplit('/').next()?.strip_suffix(".md")?;
+    NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+}
+
+/// Why an old daily note needs attention, if it does.
+fn stale_reason(content: &str) -> Option<&'static str> {
+    if content.contains("- [ ]") || content.contains("* [ ]") {
+        return Some("open tasks");
+    }
+    let mut body = content;
+    if let Some(rest) = content.strip_prefix("---\n") {
+        body = rest.split_once("\n---").map_or("", |(_, after)| after);
+    }
+    // Headings (usually the date) and empty bullets are template, not content
+    let has_text = body.lines().any(|l| {
+        let l = l.trim();
+        !l.starts_with('#') && !l.trim_start_matches(['-', '*']).trim().is_empty()
+    });
+    (!has_text).then_some("empty")
+}
+
+/// Scan the vault at `root` as of `today`.
+pub fn scan_vault(
+    root: &Path,
+    settings: &VaultMaintenanceConfig,
+    today: NaiveDate,
+) -> MaintenanceReport {
+    let report_folder = format!("{}/", settings.report_folder.trim().trim_matches('/'));
+    let files: Vec<String> = vault_files(root)
+        .into_iter()
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .collect();
+    let is_document = |p: &str| is_markdown(Path::new(p)) || p.ends_with(".canvas");
+
+    let mut report = MaintenanceReport::default();
+    let mut referenced: HashSet<&String> = HashSet::new();
+    let mut canvas_text = String::new();
+    // Reports are link targets but are not scanned themselves
+    let scanned = files.iter().filter(|p| !p.starts_with(&report_folder));
+    for path in scanned.clone() {
+        if !is_document(path) {
+            report.attachments += 1;
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(root.join(path)) else {
+            continue;
+        };
+        if path.ends_with(".canvas") {
+            canvas_text.push_str(&content);
+            continue;
+        }
+        report.notes += 1;
+        let note = path.strip_suffix(".md").unwrap_or(path).to_string();
+        for (target, wiki) in link_targets(&content) {
+            match resolve(&files, path, &target, wiki) {
+                Some(file) => {
+                    referenced.insert(file);
+                }
+                None => report.broken_links.push((note.clone(), target)),
+            }
+        }
+        if let Some(date) = daily_note_date(path, settings) {
+            if (today - date).num_days() > settings.stale_after_days {
+                if let Some(reason) = stale_reason(&content) {
+                    report.stale_daily_notes.push((note, reason));
+                }
+            }
+        }
+    }
+    report.broken_links.dedup();
+    report.orphaned_attachments = scanned
+        .filter(|p| !is_document(p) && !referenced.contains(p) && !canvas_text.contains(*p))
+        .cloned()
+        .collect();
+    report
+}
+
+/// The report note's markdown.
+pub fn render_report(report: &MaintenanceReport, today: NaiveDate) -> String {
+    let mut out = format!(
+        "# {REPORT_PREFIX}{today}\n\nScanned {} notes and {} attachments.\n",
+        report.notes, report.attachments
+    );
+    out.push_str(&format!(
+        "\n## Broken links ({})\n\n",
+        report.broken_links.len()
+    ));
+    for (note, target) in &report.broken_links {
+        out.push_str(&format!("- [[{note}]] → `{target}`\n"));
+    }
+    out.push_str(&format!(
+        "\n## Orphaned attachments ({})\n\n",
+        report.orphaned_attachments.len()
+    ));
+    for path in &report.orphaned_attachments {
+        out.push_str(&format!("- [[{path}]]\n"));
+    }
+    out.push_str(&format!(
+        "\n## Stale daily notes ({})\n\n",
+        report.stale_daily_notes.len()
+    ));
+    for (note, reason) in &report.stale_daily_notes {
+        out.push_str(&format!("- [[{note}]] ({reason})\n"));
+    }
+    out
+}
+
+fn plural(n: usize, word: &str) -> String {
+    format!("{n} {word}{}", if n == 1 { "" } else { "s" })
+}
+
+/// One-line chat summary.
+pub fn summary(report: &MaintenanceReport) -> String {
+    format!(
+        "Vault maintenance: {}, {}, {} ({} notes scanned).",
+        plural(report.broken_links.len(), "broken link"),
+        plural(report.orphaned_attachments.len(), "orphaned attachment"),
+        plural(report.stale_daily_notes.len(), "stale daily note"),
+        report.notes
+    )
+}
+
+/// Date of the newest report note in the report folder.
+fn last_report_date(root: &Path, settings: &VaultMaintenanceConfig) -> Option<NaiveDate> {
+    std::fs::read_dir(root.join(settings.report_folder.trim().trim_matches('/')))
+        .ok()?
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let date = name
+                .strip_prefix(REPORT_PREFIX)?
+                .strip_suffix(".md")?
+                .to_string();
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()
+        })
+        .max()
+}
+
+fn maintenance_chat_id(config: &Config, settings: &VaultMaintenanceConfig) -> Option<i64> {
+    settings
+        .chat_id
+        .or_else(|| config.control_chat_ids.first().copied())
+}
+
+/// Scan the vault, write today's report note and post the summary.
+pub async fn run_maintenance(state: &Arc<AppState>) -> Result<String, String> {
+    let config = &state.config;
+    let settings = config
+        .vault
+        .as_ref()
+        .and_then(|v| v.maintenance.clone())
+        .ok_or("vault.maintenance is not configured")?;
+    let root = vault_root(config).ok_or("vault.origin_vault_path is not set")?;
+    let today = chrono::Local::now().date_naive();
+
+    let (report, note_path) = {
+        let settings = settings.clone();
+        tokio::task::spawn_blocking(move || {
+            let report = scan_vault(&root, &settings, today);
+            let folder = settings.report_folder.trim().trim_matches('/').to_string();
+            let rel = format!("{folder}/{REPORT_PREFIX}{today}");
+            let path: PathBuf = root.join(format!("{rel}.md"));
+            std::fs::create_dir_all(root.join(&folder))
+                .and_then(|_| std::fs::write(&path, render_report(&report, today)))
+                .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+            Ok::<_, String>((report, rel))
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+
+    let mut text = summary(&report);
+    if let Some(vault) = obsidian_vault_name(config) {
+        text.push_str(&format!(
+            " Report: [{}]({})",
+            note_path.rsplit('/').next().unwrap_or(&note_path),
+            obsidian_uri(&vault, &note_path)
+        ));
+    }
+    if let Some(chat_id) = maintenance_chat_id(config, &settings) {
+        let persona_id =
+            call_blocking(state.db.clone(), move |d| d.get_current_persona_id(chat_id))
+                .await
+                .unwrap_or(0);
+        deliver_and_store_bot_message(
+            &state.bot,
+            state.db.clone(),
+            &config.bot_username,
+            chat_id,
+            persona_id,
+            &text,
+        )
+        .await?;
+    }
+    Ok(text)
+}
+
+/// Run the maintenance report whenever the last one is `interval_days` old.
+pub fn spawn_vault_maintenance(state: Arc<AppState>) {
+    let Some(settings) = state
+        .config
+        .vault
+        .as_ref()
+        .and_then(|v| v.maintenance.clone())
+    else {
+        return;
+    };
+    let Some(root) = vault_root(&state.config) else {
+        warn!("vault.maintenance is set but vault.origin_vault_path is not");
+        return;
+    };
+    info!(
+        "Vault maintenance every {} days ({})",
+        settings.interval_days,
+        root.display()
+    );
+    tokio::spawn(async move {
+        loop {
+            let today = chrono::Local::now().date_naive();
+            let due = last_report_date(&root, &settings)
+                .is_none_or(|last| (today - last).num_days() >= settings.interval_days.max(1));
+            if due {
+                match run_maintenance(&state).await {
+                    Ok(text) => info!("{text}"),
+                    Err(e) => warn!("Vault maintenance failed: {e}"),
+                }
+            }
+            tokio::time::sleep(CHECK_EVERY).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_vault_findings() {
+        let root =
+            std::env::temp_dir().join(format!("microclaw_vault_maint_{}", uuid::Uuid::new_v4()));
+        let write = |rel: &str, content: &str| {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "Projects/home.md",
+            "See [[Inbox]], [[Projects/garden#Beds]] and [[Missing note|alias]].\n\
+             ![[floorplan.png]] [photo](../assets/photo%201.jpg) [site](https://example.com)\n\
+             ```\n[[not a link]]\n```\n",
+        );
+        write("Inbox.md", "- [[Projects/Home]]");
+        write("Projects/garden.md", "# Beds");
+        write("assets/floorplan.png", "x");
+        write("assets/photo 1.jpg", "x");
+        write("assets/unused.pdf", "x");
+        write("Daily/2026-01-01.md", "# 2026-01-01\n- [ ] call plumber\n");
+        write(
+            "Daily/2026-01-02.md",
+            "---\ntags: [daily]\n---\n# 2026-01-02\n\n- \n",
+        );
+        write("Daily/2026-01-03.md", "Went hiking.");
+        write("Daily/2026-03-01.md", "- [ ] recent");
+        write("Maintenance/Vault maintenance 2026-01-01.md", "[[Gone]]");
+        write(".obsidian/app.json", "{}");
+
+        let settings = VaultMaintenanceConfig::default();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        let report = scan_vault(&root, &settings, today);
+        assert_eq!(
+            report.broken_links,
+            vec![("Projects/home".to_string(), "Missing note".to_string())]
+        );
+        assert_eq!(report.orphaned_attachments, vec!["assets/unused.pdf"]);
+        assert_eq!(
+            report.stale_daily_notes,
+            vec![
+                ("Daily/2026-01-01".to_string(), "open tasks"),
+                ("Daily/2026-01-02".to_string(), "empty"),
+            ]
+        );
+        assert_eq!((report.notes, report.attachments), (7, 3));
+        assert_eq!(
+            summary(&report),
+            "Vault maintenance: 1 broken link, 1 orphaned attachment, 2 stale daily notes (7 notes scanned)."
+        );
+        assert!(render_report(&report, today).contains("- [[Projects/home]] → `Missing note`"));
+        assert_eq!(
+            last_report_date(&root, &settings),
+            NaiveDate::from_ymd_opt(2026, 1, 1)
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,24 +1,27 @@
 use std::collections::{hash_map::DefaultHasher, HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use axum::extract::{Path, Query, State};
+use axum::extract::{ConnectInfo, Path, Query, State};
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::sse::{Event, KeepAlive, Sse};
-use axum::response::{Html, IntoResponse};
+use axum::extract::rejection::JsonRejection;
+use axum::response::{Html, IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use include_dir::{include_dir, Dir};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::sync::{broadcast, Mutex};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::channel::deliver_and_store_bot_message;
-use crate::config::Config;
+use crate::config::{Config, PublicAskConfig, WebEmbedConfig};
 use crate::db::{call_blocking, ChatSummary, Persona, StoredMessage};
+use crate::public_ask;
 use crate::social_oauth;
 use crate::claude::Message;
 use crate::slash_commands::{parse as parse_slash_command, SlashCommand};
@@ -60,6 +63,8 @@ struct SessionHub {
 struct WebLimits {
     max_inflight_per_session: usize,
     max_requests_per_window: usize,
+    max_reads_per_window: usize,
+    max_writes_per_window: usize,
     rate_window: Duration,
     run_history_limit: usize,
     session_idle_ttl: Duration,
@@ -70,6 +75,8 @@ impl Default for WebLimits {
         Self {
             max_inflight_per_session: 2,
             max_requests_per_window: 8,
+            max_reads_per_window: 120,
+            max_writes_per_window: 30,
             rate_window: Duration::from_secs(10),
             run_history_limit: 512,
             session_idle_ttl: Duration::from_secs(300),
@@ -82,6 +89,8 @@ impl WebLimits {
         Self {
             max_inflight_per_session: cfg.web_max_inflight_per_session,
             max_requests_per_window: cfg.web_max_requests_per_window,
+            max_reads_per_window: cfg.web_max_reads_per_window,
+            max_writes_per_window: cfg.web_max_writes_per_window,
             rate_window: Duration::from_secs(cfg.web_rate_window_seconds),
             run_history_limit: cfg.web_run_history_limit,
             session_idle_ttl: Duration::from_secs(cfg.web_session_idle_ttl_seconds),
@@ -92,6 +101,7 @@ impl WebLimits {
 #[derive(Clone, Default)]
 struct RequestHub {
     sessions: Arc<Mutex<HashMap<String, SessionQuota>>>,
+    budgets: Arc<Mutex<HashMap<String, VecDeque<Instant>>>>,
 }
 
 struct SessionQuota {
@@ -293,6 +303,59 @@ impl RequestHub {
     }
 }
 
+impl RequestHub {
+    /// Count a request against the sliding-window budget `key`. When the budget is spent, returns
+    /// how long until the oldest request leaves the window.
+    async fn admit(&self, key: &str, max: usize, window: Duration) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut guard = self.budgets.lock().await;
+        guard.retain(|_, recent| {
+            recent
+                .back()
+                .is_some_and(|ts| now.duration_since(*ts) <= window)
+        });
+        let recent = guard.entry(key.to_string()).or_default();
+        while recent
+            .front()
+            .is_some_and(|ts| now.duration_since(*ts) > window)
+        {
+            let _ = recent.pop_front();
+        }
+        if recent.len() >= max {
+            let oldest = recent.front().copied().unwrap_or(now);
+            return Err(window.saturating_sub(now.duration_since(oldest)));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+/// Budget key of a request: its bearer token (hashed), else the client address.
+fn rate_limit_key(req: &axum::extract::Request, trusted_proxies: &[String]) -> String {
+    let headers = req.headers();
+    match auth_token_from_headers(headers) {
+        Some(token) => {
+            let mut hasher = DefaultHasher::new();
+            token.hash(&mut hasher);
+            format!("token:{:016x}", hasher.finish())
+        }
+        None => format!(
+            "client:{}",
+            client_address(peer_address(req.extensions()), headers, trusted_proxies)
+        ),
+    }
+}
+
+fn too_many_requests(message: String, retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(axum::http::header::RETRY_AFTER, seconds.to_string())],
+        message,
+    )
+        .into_response()
+}
+
 fn auth_token_from_headers(headers: &HeaderMap) -> Option<String> {
     headers
         .get("authorization")
@@ -379,6 +442,27 @@ struct ResetRequest {
     session_key: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct DeleteMessageRequest {
+    session_key: Option<String>,
+    message_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RedactMessageRequest {
+    session_key: Option<String>,
+    message_id: Option<String>,
+    /// Text to mask; without it the whole message is masked.
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UndeleteRequest {
+    session_key: Option<String>,
+    /// Deleted session to restore (default: the most recent).
+    id: Option<i64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct PersonasQuery {
     session_key: Option<String>,
@@ -390,6 +474,36 @@ struct PersonasSwitchRequest {
     persona_name: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct IncidentsQuery {
+    #[serde(default)]
+    include_resolved: bool,
+}
+
+/// Ingest body for `POST /api/incidents`: raises an incident (or logs an update on the open one
+/// with the same key); `status: "resolved"` closes the open incident with that key instead.
+#[derive(Debug, Deserialize)]
+struct IncidentIngestRequest {
+    title: String,
+    key: Option<String>,
+    details: Option<String>,
+    source: Option<String>,
+    status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FeatureFlagRequest {
+    session_key: Option<String>,
+    flag: String,
+    /// `null` removes the chat's override.
+    enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryQuery {
+    persona_id: Option<i64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct RunStatusQuery {
     run_id: String,
@@ -404,7 +518,7 @@ struct UpdateConfigRequest {
     max_tokens: Option<u32>,
     max_tool_iterations: Option<usize>,
     max_document_size_mb: Option<u64>,
-    show_thinking: Option<bool>,
+    show_thinking: Option<crate::config::ThinkingLevel>,
     web_enabled: Option<bool>,
     web_host: Option<String>,
     web_port: Option<u16>,
@@ -447,6 +561,46 @@ fn redact_config(config: &Config) -> serde_json::Value {
     if cfg.web_auth_token.is_some() {
         cfg.web_auth_token = Some("***".into());
     }
+    if let Some(tr) = cfg.translation.as_mut() {
+        if tr.deepl_api_key.is_some() {
+            tr.deepl_api_key = Some("***".into());
+        }
+    }
+    if let Some(push) = cfg.push.as_mut() {
+        if push.token.is_some() {
+            push.token = Some("***".into());
+        }
+    }
+    if let Some(roundtable) = cfg.roundtable.as_mut() {
+        for persona in &mut roundtable.personas {
+            if persona.api_key.is_some() {
+                persona.api_key = Some("***".into());
+            }
+        }
+    }
+    if let Some(benchmark) = cfg.benchmark.as_mut() {
+        for candidate in &mut benchmark.models {
+            if candidate.api_key.is_some() {
+                candidate.api_key = Some("***".into());
+            }
+        }
+    }
+    if let Some(cal) = cfg.calendar.as_mut() {
+        if cal.password.is_some() {
+            cal.password = Some("***".into());
+        }
+    }
+    if let Some(tr) = cfg.transcription.as_mut() {
+        if tr.api_key.is_some() {
+            tr.api_key = Some("***".into());
+        }
+    }
+    if let Some(ask) = cfg.public_ask.as_mut() {
+        ask.tokens = ask.tokens.iter().map(|_| "***".to_string()).collect();
+        if ask.captcha_secret.is_some() {
+            ask.captcha_secret = Some("***".into());
+        }
+    }
 
     json!(cfg)
 }
@@ -467,6 +621,7 @@ async fn api_health(
         "ok": true,
         "version": env!("CARGO_PKG_VERSION"),
         "web_enabled": state.app_state.config.web_enabled,
+        "leader": crate::instance_lock::is_leader(),
     })))
 }
 
@@ -663,6 +818,22 @@ async fn api_sessions(
     Ok(Json(json!({ "ok": true, "sessions": sessions })))
 }
 
+/// Bring an archived chat back before reading or deleting it. A standby leaves it archived.
+async fn restore_archived_chat(state: &WebState, chat_id: i64) {
+    if !crate::instance_lock::is_leader() {
+        return;
+    }
+    if let Err(e) = crate::chat_archive::rehydrate(
+        state.app_state.db.clone(),
+        &state.app_state.config.runtime_data_dir(),
+        chat_id,
+    )
+    .await
+    {
+        warn!("Failed to restore archived chat {chat_id}: {e}");
+    }
+}
+
 async fn api_history(
     headers: HeaderMap,
     State(state): State<WebState>,
@@ -673,6 +844,7 @@ async fn api_history(
     let session_key = normalize_session_key(query.session_key.as_deref());
     let chat_id = resolve_chat_id(&session_key);
     let cid = chat_id;
+    restore_archived_chat(&state, chat_id).await;
 
     let persona_id = call_blocking(state.app_state.db.clone(), move |db| db.get_current_persona_id(cid))
         .await
@@ -830,6 +1002,16 @@ async fn api_send_stream(
                             )
                             .await;
                     }
+                    AgentEvent::ToolProgress { name, message } => {
+                        run_hub
+                            .publish(
+                                &run_id_for_events,
+                                "tool_progress",
+                                json!({"name": name, "message": message}).to_string(),
+                                run_history_limit,
+                            )
+                            .await;
+                    }
                     AgentEvent::ToolResult {
                         name,
                         is_error,
@@ -1109,6 +1291,68 @@ async fn send_and_store_response_with_events(
                     Err(e) => format!("Error listing tasks: {e}"),
                 }
             }
+            SlashCommand::Footer => {
+                crate::usage::handle_footer_command(state.app_state.db.clone(), chat_id, &text)
+                    .await
+            }
+            SlashCommand::Undelete => {
+                crate::session_trash::handle_undelete_command(
+                    state.app_state.db.clone(),
+                    chat_id,
+                    &text,
+                    state.app_state.config.deleted_session_retention_days,
+                )
+                .await
+            }
+            SlashCommand::Ack => {
+                crate::incidents::handle_ack_command(
+                    &state.app_state.config,
+                    state.app_state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await
+            }
+            SlashCommand::Push => {
+                crate::push::handle_push_command(
+                    &state.app_state.config,
+                    state.app_state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await
+            }
+            SlashCommand::Debate => {
+                crate::roundtable::handle_debate_command(&state.app_state.config, &text).await
+            }
+            SlashCommand::Thinking => {
+                crate::thinking::handle_thinking_command(
+                    &state.app_state.config,
+                    state.app_state.db.clone(),
+                    chat_id,
+                    &text,
+                )
+                .await
+            }
+            SlashCommand::Memory => {
+                crate::memory_commands::handle_memory_command(
+                    state.app_state.db.clone(),
+                    &state.app_state.memory,
+                    chat_id,
+                    &text,
+                )
+                .await
+            }
+            SlashCommand::Forget => {
+                crate::memory_commands::handle_forget_command(
+                    state.app_state.db.clone(),
+                    &state.app_state.memory,
+                    chat_id,
+                    &text,
+                )
+                .await
+            }
+            SlashCommand::CatchUp => "Catch-up digests are only available in group chats.".into(),
             SlashCommand::Archive => {
                 let cid2 = chat_id;
                 let pid = persona_id;
@@ -1195,6 +1439,28 @@ async fn send_and_store_response_with_events(
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    // Custom commands (define_command) expand into a normal prompt
+    let text = crate::custom_commands::expand_custom_command(
+        state.app_state.db.clone(),
+        chat_id,
+        &text,
+    )
+    .await
+    .unwrap_or(text);
+
+    let inbound = crate::middleware::InboundContext {
+        chat_id,
+        chat_type: "web",
+        sender_name: &sender_name,
+        will_respond: true,
+    };
+    let Some(text) = crate::middleware::run_inbound(&state.app_state, inbound, text).await else {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "message was dropped by inbound middleware".into(),
+        ));
+    };
+
     let user_msg = StoredMessage {
         id: uuid::Uuid::new_v4().to_string(),
         chat_id,
@@ -1241,16 +1507,19 @@ async fn send_and_store_response_with_events(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
     };
 
-    deliver_and_store_bot_message(
-        &state.app_state.bot,
-        state.app_state.db.clone(),
-        &state.app_state.config.bot_username,
-        chat_id,
-        persona_id,
-        &response,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    // Empty when the agent answered with a reaction or sticker (already stored by the tool)
+    if !response.is_empty() {
+        deliver_and_store_bot_message(
+            &state.app_state.bot,
+            state.app_state.db.clone(),
+            &state.app_state.config.bot_username,
+            chat_id,
+            persona_id,
+            &response,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    }
 
     Ok(Json(json!({
         "ok": true,
@@ -1277,6 +1546,7 @@ async fn api_reset(
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     let deleted = if matches!(chat_type.as_deref(), Some("web")) {
+        restore_archived_chat(&state, chat_id).await;
         let deleted = call_blocking(state.app_state.db.clone(), move |db| {
             db.delete_chat_data(chat_id)
         })
@@ -1319,6 +1589,7 @@ async fn api_delete_session(
 
     let session_key = normalize_session_key(body.session_key.as_deref());
     let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+    restore_archived_chat(&state, chat_id).await;
 
     let deleted = call_blocking(state.app_state.db.clone(), move |db| {
         db.delete_chat_data(chat_id)
@@ -1333,6 +1604,71 @@ async fn api_delete_session(
     })))
 }
 
+async fn api_undelete_session(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Json(body): Json<UndeleteRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let session_key = normalize_session_key(body.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+
+    let restored = crate::session_trash::undelete(state.app_state.db.clone(), chat_id, body.id)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    Ok(Json(json!({
+        "ok": true,
+        "id": restored.id,
+        "persona_id": restored.persona_id,
+        "message": "Conversation restored."
+    })))
+}
+
+async fn api_delete_message(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Json(body): Json<DeleteMessageRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let session_key = normalize_session_key(body.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+
+    crate::tools::message_redaction::delete_stored_message(
+        &state.app_state.db,
+        chat_id,
+        &body.message_id,
+    )
+    .await
+    .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    Ok(Json(json!({ "ok": true })))
+}
+
+async fn api_redact_message(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Json(body): Json<RedactMessageRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let session_key = normalize_session_key(body.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+
+    let redacted = crate::tools::message_redaction::redact_stored_messages(
+        &state.app_state.db,
+        chat_id,
+        body.message_id.as_deref(),
+        body.text.as_deref(),
+    )
+    .await
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(json!({ "ok": true, "redacted": redacted })))
+}
+
 async fn api_personas(
     headers: HeaderMap,
     State(state): State<WebState>,
@@ -1373,66 +1709,748 @@ async fn api_personas(
     })))
 }
 
-async fn api_personas_switch(
+async fn api_feature_flags(
     headers: HeaderMap,
     State(state): State<WebState>,
-    Json(body): Json<PersonasSwitchRequest>,
+    Query(query): Query<PersonasQuery>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     require_auth(&headers, state.auth_token.as_deref())?;
 
-    let session_key = normalize_session_key(body.session_key.as_deref());
+    let session_key = normalize_session_key(query.session_key.as_deref());
     let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
-    let persona_name = body.persona_name.clone();
-    let persona_name_for_msg = persona_name.clone();
+    let flags = crate::feature_flags::chat_flags(
+        &state.app_state.config,
+        state.app_state.db.clone(),
+        chat_id,
+    )
+    .await;
+    Ok(Json(json!({
+        "ok": true,
+        "session_key": session_key,
+        "chat_id": chat_id,
+        "flags": flags,
+    })))
+}
 
-    let persona = call_blocking(state.app_state.db.clone(), move |db| {
-        db.get_persona_by_name(chat_id, &persona_name)
-    })
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+async fn api_set_feature_flag(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Json(body): Json<FeatureFlagRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
 
-    let Some(persona) = persona else {
+    let Some(flag) = crate::feature_flags::FeatureFlag::parse(&body.flag) else {
         return Err((
-            StatusCode::NOT_FOUND,
-            format!("Persona '{}' not found", persona_name_for_msg),
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Unknown flag '{}' (one of: {})",
+                body.flag,
+                crate::feature_flags::FeatureFlag::names()
+            ),
         ));
     };
-
-    let ok = call_blocking(state.app_state.db.clone(), move |db| {
-        db.set_active_persona(chat_id, persona.id)
+    let session_key = normalize_session_key(body.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+    let enabled = body.enabled;
+    call_blocking(state.app_state.db.clone(), move |db| {
+        db.set_feature_flag(chat_id, flag.name(), enabled)
     })
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let flags = crate::feature_flags::chat_flags(
+        &state.app_state.config,
+        state.app_state.db.clone(),
+        chat_id,
+    )
+    .await;
+    Ok(Json(json!({
+        "ok": true,
+        "chat_id": chat_id,
+        "flags": flags,
+    })))
+}
 
-    if !ok {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to switch persona".into(),
-        ));
-    }
+async fn api_artifacts(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Query(query): Query<PersonasQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
 
+    let session_key = normalize_session_key(query.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+    let artifacts = call_blocking(state.app_state.db.clone(), move |db| {
+        db.list_artifacts(chat_id, None, None, 200)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let items: Vec<serde_json::Value> = artifacts
+        .iter()
+        .map(|a| {
+            json!({
+                "id": a.id,
+                "title": a.title,
+                "type": a.kind,
+                "content": a.content,
+                "created_at": a.created_at,
+            })
+        })
+        .collect();
     Ok(Json(json!({
         "ok": true,
-        "message": format!("Switched to {}", persona_name_for_msg),
+        "session_key": session_key,
+        "chat_id": chat_id,
+        "artifacts": items,
     })))
 }
 
-pub async fn start_web_server(state: Arc<AppState>) {
-    let limits = WebLimits::from_config(&state.config);
-    let web_state = WebState {
-        auth_token: state.config.web_auth_token.clone(),
-        app_state: state.clone(),
-        run_hub: RunHub::default(),
-        session_hub: SessionHub::default(),
-        request_hub: RequestHub::default(),
-        limits,
-    };
+async fn api_delete_artifact(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Path(id): Path<i64>,
+    Query(query): Query<PersonasQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
 
-    let router = build_router(web_state);
+    let session_key = normalize_session_key(query.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+    let deleted = call_blocking(state.app_state.db.clone(), move |db| {
+        db.delete_artifact(chat_id, id)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !deleted {
+        return Err((StatusCode::NOT_FOUND, format!("No artifact {id} in this chat")));
+    }
+    Ok(Json(json!({"ok": true, "id": id})))
+}
 
-    let addr = format!("{}:{}", state.config.web_host, state.config.web_port);
-    let listener = match tokio::net::TcpListener::bind(&addr).await {
-        Ok(listener) => listener,
+fn incident_json(incident: &crate::db::Incident) -> serde_json::Value {
+    json!({
+        "id": incident.id,
+        "key": incident.key,
+        "title": incident.title,
+        "source": incident.source,
+        "status": incident.status,
+        "chat_id": incident.chat_id,
+        "session_key": format!("incident-{}", incident.id),
+        "repeats": incident.repeats,
+        "next_ping_at": incident.next_ping_at,
+        "acknowledged_by": incident.acknowledged_by,
+        "created_at": incident.created_at,
+        "updated_at": incident.updated_at,
+    })
+}
+
+async fn api_incidents(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Query(query): Query<IncidentsQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let include_resolved = query.include_resolved;
+    let incidents = call_blocking(state.app_state.db.clone(), move |db| {
+        db.list_incidents(include_resolved, 100)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(json!({
+        "ok": true,
+        "incidents": incidents.iter().map(incident_json).collect::<Vec<_>>(),
+    })))
+}
+
+async fn api_ingest_incident(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Json(body): Json<IncidentIngestRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let title = body.title.trim();
+    if title.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "title is required".into()));
+    }
+    let key = body
+        .key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .unwrap_or(title)
+        .to_string();
+    let source = body
+        .source
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("api");
+
+    if body.status.as_deref() == Some("resolved") {
+        let lookup = key.clone();
+        let Some(open) = call_blocking(state.app_state.db.clone(), move |db| {
+            db.find_active_incident(&lookup)
+        })
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        else {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("No open incident with key '{key}'"),
+            ));
+        };
+        let incident =
+            crate::incidents::set_status(state.app_state.db.clone(), open.id, "resolved", source)
+                .await
+                .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+        return Ok(Json(
+            json!({"ok": true, "incident": incident_json(&incident)}),
+        ));
+    }
+
+    let raised = crate::incidents::raise(
+        &state.app_state.bot,
+        state.app_state.db.clone(),
+        &state.app_state.config,
+        source,
+        &key,
+        title,
+        body.details.as_deref(),
+    )
+    .await
+    .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let id = raised.id;
+    let incident = call_blocking(state.app_state.db.clone(), move |db| db.get_incident(id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, format!("Incident #{id} not found")))?;
+    Ok(Json(json!({
+        "ok": true,
+        "new": raised.new,
+        "incident": incident_json(&incident),
+    })))
+}
+
+async fn api_ack_incident(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Path(id): Path<i64>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let incident =
+        crate::incidents::set_status(state.app_state.db.clone(), id, "acknowledged", "web")
+            .await
+            .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+    Ok(Json(
+        json!({"ok": true, "incident": incident_json(&incident)}),
+    ))
+}
+
+/// `POST /api/tasks/:id/pause|resume|run`. `run` queues one run for the next scheduler tick
+/// without changing the schedule.
+async fn api_task_action(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Path((id, action)): Path<(i64, String)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let db = state.app_state.db.clone();
+    let task = call_blocking(db.clone(), move |db| db.get_task_by_id(id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, format!("Task #{id} not found")))?;
+    let changes_status = matches!(action.as_str(), "pause" | "resume");
+    if task.status == "cancelled" || (changes_status && task.status == "completed") {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Task #{id} is {}", task.status),
+        ));
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let ok = match action.as_str() {
+        "pause" => call_blocking(db.clone(), move |db| db.update_task_status(id, "paused")).await,
+        "resume" => call_blocking(db.clone(), move |db| db.update_task_status(id, "active")).await,
+        "run" => call_blocking(db.clone(), move |db| db.request_task_run(id, &now)).await,
+        _ => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Unknown task action '{action}' (pause, resume or run)"),
+            ))
+        }
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !ok {
+        return Err((StatusCode::NOT_FOUND, format!("Task #{id} not found")));
+    }
+    let task = call_blocking(db, move |db| db.get_task_by_id(id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, format!("Task #{id} not found")))?;
+    Ok(Json(json!({
+        "ok": true,
+        "task": {
+            "id": task.id,
+            "chat_id": task.chat_id,
+            "status": task.status,
+            "next_run": task.next_run,
+            "run_requested_at": task.run_requested_at,
+        },
+    })))
+}
+
+async fn api_personas_switch(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Json(body): Json<PersonasSwitchRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+
+    let session_key = normalize_session_key(body.session_key.as_deref());
+    let chat_id = resolve_chat_id_for_session_key(&state, &session_key).await?;
+    let persona_name = body.persona_name.clone();
+    let persona_name_for_msg = persona_name.clone();
+
+    let persona = call_blocking(state.app_state.db.clone(), move |db| {
+        db.get_persona_by_name(chat_id, &persona_name)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let Some(persona) = persona else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Persona '{}' not found", persona_name_for_msg),
+        ));
+    };
+
+    let ok = call_blocking(state.app_state.db.clone(), move |db| {
+        db.set_active_persona(chat_id, persona.id)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if !ok {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to switch persona".into(),
+        ));
+    }
+
+    Ok(Json(json!({
+        "ok": true,
+        "message": format!("Switched to {}", persona_name_for_msg),
+    })))
+}
+
+/// Memory tier addressed by `/api/memory/:chat/:tier`: `1`..`3`, or `all` for the whole file.
+fn parse_memory_tier(raw: &str) -> Result<Option<u8>, (StatusCode, String)> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "all" => Ok(None),
+        "1" | "tier1" => Ok(Some(1)),
+        "2" | "tier2" => Ok(Some(2)),
+        "3" | "tier3" => Ok(Some(3)),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            "tier must be 1, 2, 3 or all".into(),
+        )),
+    }
+}
+
+/// MEMORY.md path for a chat, using `persona_id` when given (it must belong to the chat) and the
+/// chat's active persona otherwise.
+async fn resolve_memory_path(
+    state: &WebState,
+    chat_id: i64,
+    persona_id: Option<i64>,
+) -> Result<PathBuf, (StatusCode, String)> {
+    let persona_id = call_blocking(state.app_state.db.clone(), move |db| {
+        if db.get_chat_type(chat_id)?.is_none() {
+            return Ok(None);
+        }
+        match persona_id {
+            Some(pid) => Ok(db
+                .get_persona(pid)?
+                .filter(|p| p.chat_id == chat_id)
+                .map(|p| p.id)),
+            None => db.get_current_persona_id(chat_id).map(Some),
+        }
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((
+        StatusCode::NOT_FOUND,
+        "chat or persona not found".to_string(),
+    ))?;
+    let groups_dir = PathBuf::from(state.app_state.config.runtime_data_dir()).join("groups");
+    Ok(crate::tools::tiered_memory::memory_path(
+        &groups_dir,
+        chat_id,
+        persona_id,
+    ))
+}
+
+/// Plain-markdown read of a chat's tiered memory, for editors such as Obsidian or Shortcuts.
+async fn api_memory_get(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Path((chat_id, tier)): Path<(i64, String)>,
+    Query(query): Query<MemoryQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+    let tier = parse_memory_tier(&tier)?;
+    let path = resolve_memory_path(&state, chat_id, query.persona_id).await?;
+
+    let full = std::fs::read_to_string(&path).unwrap_or_default();
+    let body = match tier {
+        Some(t) => crate::tools::tiered_memory::parse_tier_content(&full, t),
+        None => full,
+    };
+    Ok(([("content-type", "text/markdown; charset=utf-8")], body).into_response())
+}
+
+/// Replace one tier (or the whole file for `all`) with the markdown request body.
+async fn api_memory_put(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Path((chat_id, tier)): Path<(i64, String)>,
+    Query(query): Query<MemoryQuery>,
+    body: String,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+    let tier = parse_memory_tier(&tier)?;
+    let path = resolve_memory_path(&state, chat_id, query.persona_id).await?;
+
+    let result = match tier {
+        Some(t) => crate::tools::tiered_memory::write_tier(&path, t, &body),
+        None => path
+            .parent()
+            .map(std::fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| std::fs::write(&path, &body)),
+    };
+    result.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    info!(
+        "Memory for chat {} updated via API: {}",
+        chat_id,
+        path.display()
+    );
+
+    Ok(Json(json!({
+        "ok": true,
+        "chat_id": chat_id,
+        "tier": tier.map(|t| json!(t)).unwrap_or(json!("all")),
+        "bytes": body.len(),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectionsQuery {
+    #[serde(default)]
+    chat_id: Option<i64>,
+}
+
+/// Social platforms each chat (or one chat) has authorized, with token expiry and scopes.
+/// Tokens themselves are never returned.
+async fn api_social_connections(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Query(query): Query<ConnectionsQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+    let chat_id = query.chat_id;
+    let tokens = call_blocking(state.app_state.db.clone(), move |db| {
+        db.list_social_tokens(chat_id)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let connections: Vec<serde_json::Value> =
+        tokens.iter().map(social_oauth::connection_json).collect();
+    Ok(Json(json!({ "connections": connections })))
+}
+
+/// Revoke a chat's authorization for a platform (provider revoke endpoint where supported) and
+/// delete the stored token.
+async fn api_social_disconnect(
+    headers: HeaderMap,
+    State(state): State<WebState>,
+    Path((chat_id, platform)): Path<(i64, String)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_auth(&headers, state.auth_token.as_deref())?;
+    let revocation = social_oauth::disconnect(
+        &state.app_state.config,
+        state.app_state.db.clone(),
+        &platform,
+        chat_id,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((
+        StatusCode::NOT_FOUND,
+        format!("chat {chat_id} has no {platform} connection"),
+    ))?;
+    info!("Disconnected {} for chat {} via API", platform, chat_id);
+    Ok(Json(json!({
+        "ok": true,
+        "chat_id": chat_id,
+        "platform": platform,
+        "revoked": revocation == social_oauth::Revocation::Revoked,
+        "detail": revocation.describe(&platform),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct AskRequest {
+    question: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    captcha_token: Option<String>,
+}
+
+/// Client address for per-client rate limits: the connection's peer, or the forwarded client
+/// address when the peer is a trusted reverse proxy (`web_trusted_proxies`). Clients can set the
+/// forwarded headers to anything, so they are ignored on direct connections.
+fn client_address(
+    peer: Option<SocketAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[String],
+) -> String {
+    let Some(peer) = peer else {
+        return "unknown".into();
+    };
+    let is_trusted = |ip: IpAddr| {
+        trusted_proxies
+            .iter()
+            .any(|p| p.trim().parse::<IpAddr>().is_ok_and(|t| t == ip))
+    };
+    if !is_trusted(peer.ip()) {
+        return peer.ip().to_string();
+    }
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    // X-Forwarded-For is appended to by each proxy: the client is the last hop not trusted
+    let forwarded = header("cf-connecting-ip")
+        .map(str::to_string)
+        .or_else(|| {
+            header("x-forwarded-for").and_then(|v| {
+                v.split(',')
+                    .rev()
+                    .map(str::trim)
+                    .find(|hop| !hop.parse::<IpAddr>().is_ok_and(&is_trusted))
+                    .map(str::to_string)
+            })
+        })
+        .or_else(|| header("x-real-ip").map(str::to_string))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    forwarded.unwrap_or_else(|| peer.ip().to_string())
+}
+
+fn peer_address(extensions: &axum::http::Extensions) -> Option<SocketAddr> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr)
+}
+
+fn ask_limits(cfg: &PublicAskConfig, global: bool) -> WebLimits {
+    let rate_window = Duration::from_secs(cfg.rate_window_seconds.max(1));
+    WebLimits {
+        max_inflight_per_session: if global { 4 } else { 1 },
+        max_requests_per_window: if global {
+            cfg.global_max_requests_per_window
+        } else {
+            cfg.max_requests_per_window
+        },
+        max_reads_per_window: 0,
+        max_writes_per_window: 0,
+        rate_window,
+        run_history_limit: 0,
+        session_idle_ttl: rate_window * 2,
+    }
+}
+
+fn with_ask_cors(mut resp: Response, cfg: &PublicAskConfig) -> Response {
+    if let Some(origin) = cfg
+        .allowed_origin
+        .as_deref()
+        .and_then(|o| axum::http::HeaderValue::from_str(o).ok())
+    {
+        let headers = resp.headers_mut();
+        headers.insert("access-control-allow-origin", origin);
+        headers.insert(
+            "access-control-allow-methods",
+            axum::http::HeaderValue::from_static("POST, OPTIONS"),
+        );
+        headers.insert(
+            "access-control-allow-headers",
+            axum::http::HeaderValue::from_static("content-type, x-ask-token"),
+        );
+        headers.insert("vary", axum::http::HeaderValue::from_static("Origin"));
+    }
+    resp
+}
+
+async fn api_ask_preflight(State(state): State<WebState>) -> Response {
+    match state.app_state.config.public_ask.as_ref() {
+        Some(cfg) => with_ask_cors(StatusCode::NO_CONTENT.into_response(), cfg),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Public, unauthenticated question endpoint for a personal website. Protected by site
+/// tokens and/or captcha, and rate limited per client and globally.
+async fn api_ask(
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    State(state): State<WebState>,
+    body: Result<Json<AskRequest>, JsonRejection>,
+) -> Response {
+    let Some(cfg) = state.app_state.config.public_ask.clone() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let resp = match body {
+        Ok(Json(body)) => match ask_and_answer(
+            &state,
+            &cfg,
+            &headers,
+            connect_info.map(|ConnectInfo(addr)| addr),
+            body,
+        )
+        .await
+        {
+            Ok(answer) => Json(json!({"ok": true, "answer": answer})).into_response(),
+            Err((status, error)) => (status, Json(json!({"ok": false, "error": error}))).into_response(),
+        },
+        Err(rejection) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"ok": false, "error": rejection.body_text()})),
+        )
+            .into_response(),
+    };
+    with_ask_cors(resp, &cfg)
+}
+
+async fn ask_and_answer(
+    state: &WebState,
+    cfg: &PublicAskConfig,
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    body: AskRequest,
+) -> Result<String, (StatusCode, String)> {
+    if !cfg.is_protected() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "public ask requires site tokens or a captcha secret".into(),
+        ));
+    }
+    let question = body.question.trim().to_string();
+    if question.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "question is required".into()));
+    }
+    if question.chars().count() > cfg.max_question_chars {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("question exceeds {} characters", cfg.max_question_chars),
+        ));
+    }
+
+    // Rate limit before any captcha round-trip so floods never reach the verifier.
+    let client = client_address(peer, headers, &state.app_state.config.web_trusted_proxies);
+    let client_key = format!("ask:{client}");
+    let client_limits = ask_limits(cfg, false);
+    let global_limits = ask_limits(cfg, true);
+    state.request_hub.begin(&client_key, &client_limits).await?;
+    if let Err(e) = state.request_hub.begin("ask:*", &global_limits).await {
+        state
+            .request_hub
+            .end_with_limits(&client_key, &client_limits)
+            .await;
+        return Err(e);
+    }
+    let result = authorize_and_answer(state, cfg, headers, &client, body, &question).await;
+    state
+        .request_hub
+        .end_with_limits("ask:*", &global_limits)
+        .await;
+    state
+        .request_hub
+        .end_with_limits(&client_key, &client_limits)
+        .await;
+    result
+}
+
+async fn authorize_and_answer(
+    state: &WebState,
+    cfg: &PublicAskConfig,
+    headers: &HeaderMap,
+    client: &str,
+    body: AskRequest,
+    question: &str,
+) -> Result<String, (StatusCode, String)> {
+    let site_token = headers
+        .get("x-ask-token")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or(body.token);
+    let authorized = if public_ask::token_matches(cfg, site_token.as_deref()) {
+        true
+    } else if let Some(captcha) = body.captcha_token.as_deref().filter(|t| !t.is_empty()) {
+        let remote_ip = (client != "unknown").then_some(client);
+        public_ask::verify_captcha(cfg, captcha, remote_ip)
+            .await
+            .map_err(|e| {
+                error!("public ask: {e}");
+                (StatusCode::BAD_GATEWAY, "captcha verification unavailable".to_string())
+            })?
+    } else if site_token.is_none() {
+        return Err((StatusCode::UNAUTHORIZED, "token or captcha required".into()));
+    } else {
+        false
+    };
+    if !authorized {
+        return Err((StatusCode::FORBIDDEN, "forbidden".into()));
+    }
+
+    let visitor: String = body
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .unwrap_or("visitor")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(40)
+        .collect();
+    if crate::moderation::check_public_ask(&state.app_state, cfg.chat_id, &visitor, question).await {
+        return Ok(crate::moderation::HELD_REPLY.to_string());
+    }
+    public_ask::answer_guest_question(&state.app_state, cfg, &visitor, question)
+        .await
+        .map_err(|e| {
+            error!("public ask: failed to answer: {e}");
+            (StatusCode::BAD_GATEWAY, "failed to answer".to_string())
+        })
+}
+
+pub async fn start_web_server(state: Arc<AppState>) {
+    let limits = WebLimits::from_config(&state.config);
+    let web_state = WebState {
+        auth_token: state.config.web_auth_token.clone(),
+        app_state: state.clone(),
+        run_hub: RunHub::default(),
+        session_hub: SessionHub::default(),
+        request_hub: RequestHub::default(),
+        limits,
+    };
+
+    let router = build_router(web_state);
+
+    let addr = format!("{}:{}", state.config.web_host, state.config.web_port);
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
         Err(e) => {
             error!("Failed to bind web server at {}: {}", addr, e);
             return;
@@ -1440,7 +2458,8 @@ pub async fn start_web_server(state: Arc<AppState>) {
     };
 
     info!("Web UI available at http://{addr}");
-    if let Err(e) = axum::serve(listener, router).await {
+    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+    if let Err(e) = axum::serve(listener, service).await {
         error!("Web server error: {e}");
     }
 }
@@ -1481,47 +2500,83 @@ async fn favicon_file() -> impl IntoResponse {
 
 #[derive(Debug, Deserialize)]
 struct OAuthAuthorizeQuery {
-    chat_id: Option<i64>,
-    session_key: Option<String>,
+    state: Option<String>,
 }
 
+/// Cookie tying an OAuth callback to the browser that opened the authorization link, so a
+/// callback URL from someone else's flow cannot be replayed in the user's browser (login CSRF).
+const OAUTH_BINDING_COOKIE: &str = "microclaw_oauth_binding";
+
+fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| v.to_string())
+}
+
+/// Start an OAuth flow from a link issued by the bot (`social_oauth::start_authorization`).
+/// The single-use state is bound to this browser and the user is redirected to the provider.
 async fn api_oauth_authorize(
     State(state): State<WebState>,
     Path(platform): Path<String>,
     Query(query): Query<OAuthAuthorizeQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let platform = platform.to_lowercase();
-    if !["tiktok", "instagram", "linkedin"].contains(&platform.as_str()) {
+    if !["tiktok", "instagram", "linkedin", "google", "spotify"].contains(&platform.as_str()) {
         return Err((StatusCode::BAD_REQUEST, "Unknown platform".into()));
     }
     if state.app_state.config.social.as_ref().map_or(true, |s| !s.is_platform_enabled(&platform)) {
         return Err((StatusCode::BAD_REQUEST, "Platform not configured".into()));
     }
-
-    let chat_id = match (query.chat_id, query.session_key) {
-        (Some(id), _) => id,
-        (_, Some(sk)) => {
-            resolve_chat_id_for_session_key(&state, &normalize_session_key(Some(&sk))).await?
-        }
-        _ => return Err((StatusCode::BAD_REQUEST, "Provide chat_id or session_key".into())),
+    let Some(state_token) = query.state.filter(|s| !s.trim().is_empty()) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Missing state. Open the authorization link the bot sent in chat.".into(),
+        ));
     };
 
-    let state_token = uuid::Uuid::new_v4().simple().to_string();
-    let expires_at = (chrono::Utc::now() + chrono::Duration::minutes(10)).to_rfc3339();
-
-    let platform_clone = platform.clone();
-    let state_token_clone = state_token.clone();
-    call_blocking(state.app_state.db.clone(), move |db| {
-        db.create_oauth_pending_state(&state_token_clone, &platform_clone, chat_id, &expires_at)
+    let binding = uuid::Uuid::new_v4().simple().to_string();
+    let (token, p, b) = (state_token.clone(), platform.clone(), binding.clone());
+    let pending = call_blocking(state.app_state.db.clone(), move |db| {
+        db.bind_oauth_pending_state(&token, &p, &b)
     })
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let auth_url = social_oauth::authorize_url(&state.app_state.config, &platform, &state_token)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build authorize URL".into()))?;
-
-    Ok(axum::response::Redirect::temporary(&auth_url))
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((
+        StatusCode::BAD_REQUEST,
+        "This authorization link is invalid, expired or already used. Ask the bot for a new one."
+            .to_string(),
+    ))?;
+
+    let challenge = pending
+        .code_verifier
+        .as_deref()
+        .map(social_oauth::pkce_challenge);
+    let auth_url = social_oauth::authorize_url(
+        &state.app_state.config,
+        &platform,
+        &state_token,
+        challenge.as_deref(),
+        pending.scopes.as_deref(),
+    )
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build authorize URL".into()))?;
+
+    let secure = social_oauth::oauth_base_url(&state.app_state.config)
+        .is_some_and(|b| b.starts_with("https://"));
+    let cookie = format!(
+        "{OAUTH_BINDING_COOKIE}={binding}; Path=/api/oauth/callback/{platform}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        social_oauth::STATE_TTL_MINUTES * 60,
+        if secure { "; Secure" } else { "" }
+    );
+    Ok((
+        [(axum::http::header::SET_COOKIE, cookie)],
+        axum::response::Redirect::temporary(&auth_url),
+    ))
 }
 
 #[derive(Debug, Deserialize)]
@@ -1533,6 +2588,7 @@ struct OAuthCallbackQuery {
 }
 
 async fn api_oauth_callback(
+    headers: HeaderMap,
     State(state): State<WebState>,
     Path(platform): Path<String>,
     Query(query): Query<OAuthCallbackQuery>,
@@ -1566,8 +2622,19 @@ async fn api_oauth_callback(
         }
     };
 
-    let Some((stored_platform, chat_id)) = call_blocking(state.app_state.db.clone(), move |db| {
-        db.consume_oauth_pending_state(&state_token)
+    // Checked before the state is touched, so a callback replayed elsewhere cannot burn it
+    let Some(binding) = cookie_value(&headers, OAUTH_BINDING_COOKIE) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html(
+                r#"<!DOCTYPE html><html><head><title>OAuth Error</title></head><body>
+                <h1>Authorization was started in a different browser</h1><p>Open the link the bot sent you and finish authorizing in the same browser.</p></body></html>"#,
+            ),
+        )
+            .into_response();
+    };
+    let Some(pending) = call_blocking(state.app_state.db.clone(), move |db| {
+        db.consume_oauth_pending_state(&state_token, &binding)
     })
     .await
     .ok()
@@ -1577,13 +2644,13 @@ async fn api_oauth_callback(
             StatusCode::BAD_REQUEST,
             Html(
                 r#"<!DOCTYPE html><html><head><title>OAuth Error</title></head><body>
-                <h1>Invalid or expired state</h1><p>Please try the authorization flow again.</p></body></html>"#,
+                <h1>Invalid or expired state</h1><p>Please try the authorization flow again, and finish it in the browser where you opened the link.</p></body></html>"#,
             ),
         )
             .into_response();
     };
 
-    if stored_platform != platform {
+    if pending.platform != platform {
         return (
             StatusCode::BAD_REQUEST,
             Html(
@@ -1593,6 +2660,7 @@ async fn api_oauth_callback(
         )
             .into_response();
     }
+    let chat_id = pending.chat_id;
 
     let base = social_oauth::oauth_base_url(&state.app_state.config).unwrap_or_default();
     let redirect_uri = format!("{}/api/oauth/callback/{}", base.trim_end_matches('/'), platform);
@@ -1602,6 +2670,7 @@ async fn api_oauth_callback(
         &platform,
         &code,
         &redirect_uri,
+        pending.code_verifier.as_deref(),
     )
     .await
     {
@@ -1627,6 +2696,7 @@ async fn api_oauth_callback(
             &token_result.access_token,
             token_result.refresh_token.as_deref(),
             token_result.expires_at.as_deref(),
+            token_result.scopes.as_deref(),
         )
     })
     .await
@@ -1646,11 +2716,17 @@ async fn api_oauth_callback(
         "tiktok" => "TikTok",
         "instagram" => "Instagram",
         "linkedin" => "LinkedIn",
+        "google" => "Google",
+        "spotify" => "Spotify",
         _ => &platform,
     };
 
+    let clear_cookie = format!(
+        "{OAUTH_BINDING_COOKIE}=; Path=/api/oauth/callback/{platform}; Max-Age=0; HttpOnly; SameSite=Lax"
+    );
     (
         StatusCode::OK,
+        [(axum::http::header::SET_COOKIE, clear_cookie)],
         Html(format!(
             r#"<!DOCTYPE html><html><head><title>Authorization successful</title></head><body>
             <h1>Authorization successful</h1>
@@ -1662,12 +2738,17 @@ async fn api_oauth_callback(
         .into_response()
 }
 
-fn build_router(web_state: WebState) -> Router {
+/// Web UI page and static assets, also served by `microclaw remote` in front of its proxy.
+pub(crate) fn ui_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
     Router::new()
         .route("/", get(index))
         .route("/assets/*file", get(asset_file))
         .route("/icon.png", get(icon_file))
         .route("/favicon.ico", get(favicon_file))
+}
+
+fn build_router(web_state: WebState) -> Router {
+    ui_routes()
         .route("/api/health", get(api_health))
         .route("/api/config", get(api_get_config).put(api_update_config))
         .route("/api/sessions", get(api_sessions))
@@ -1678,13 +2759,179 @@ fn build_router(web_state: WebState) -> Router {
         .route("/api/run_status", get(api_run_status))
         .route("/api/reset", post(api_reset))
         .route("/api/delete_session", post(api_delete_session))
+        .route("/api/undelete_session", post(api_undelete_session))
+        .route("/api/delete_message", post(api_delete_message))
+        .route("/api/redact_message", post(api_redact_message))
         .route("/api/personas", get(api_personas))
         .route("/api/personas/switch", post(api_personas_switch))
+        .route(
+            "/api/feature_flags",
+            get(api_feature_flags).put(api_set_feature_flag),
+        )
+        .route("/api/artifacts", get(api_artifacts))
+        .route(
+            "/api/artifacts/:id",
+            axum::routing::delete(api_delete_artifact),
+        )
+        .route(
+            "/api/incidents",
+            get(api_incidents).post(api_ingest_incident),
+        )
+        .route("/api/incidents/:id/ack", post(api_ack_incident))
+        .route("/api/tasks/:id/:action", post(api_task_action))
+        .route(
+            "/api/memory/:chat/:tier",
+            get(api_memory_get).put(api_memory_put),
+        )
+        .route("/api/social/connections", get(api_social_connections))
+        .route(
+            "/api/social/connections/:chat/:platform",
+            axum::routing::delete(api_social_disconnect),
+        )
         .route("/api/oauth/authorize/:platform", get(api_oauth_authorize))
         .route("/api/oauth/callback/:platform", get(api_oauth_callback))
+        .route("/ask", post(api_ask).options(api_ask_preflight))
+        .layer(axum::middleware::from_fn(standby_read_only))
+        .layer(axum::middleware::from_fn_with_state(
+            web_state.clone(),
+            api_rate_limit,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            web_state.clone(),
+            security_headers,
+        ))
         .with_state(web_state)
 }
 
+/// Policy for the bundled UI: scripts and API calls stay on this origin; styles may be inline
+/// (the component library sets style attributes) and markdown may show images from anywhere.
+const DEFAULT_CSP: &str = "default-src 'self'; script-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data: blob: https:; font-src 'self' data:; connect-src 'self'; object-src 'none'; base-uri 'self'; form-action 'self'";
+
+/// `Content-Security-Policy` of the web UI, with `frame-ancestors` limited to this origin plus
+/// `web_embed.frame_ancestors`.
+fn content_security_policy(embed: Option<&WebEmbedConfig>) -> String {
+    let policy = embed
+        .and_then(|e| e.content_security_policy.as_deref())
+        .map(|p| p.trim().trim_end_matches(';').to_string())
+        .unwrap_or_else(|| DEFAULT_CSP.to_string());
+    if policy.contains("frame-ancestors") {
+        return policy;
+    }
+    let mut ancestors = vec!["'self'"];
+    if let Some(embed) = embed {
+        ancestors.extend(embed.frame_ancestors.iter().map(String::as_str));
+    }
+    format!("{policy}; frame-ancestors {}", ancestors.join(" "))
+}
+
+/// CSP on every response; `X-Frame-Options` too unless other origins may embed the UI (it
+/// cannot express an allowlist, and browsers that know CSP ignore it).
+async fn security_headers(
+    State(state): State<WebState>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let embed = state.app_state.config.web_embed.as_ref();
+    let mut resp = next.run(req).await;
+    let headers = resp.headers_mut();
+    if let Ok(csp) = axum::http::HeaderValue::from_str(&content_security_policy(embed)) {
+        headers.insert("content-security-policy", csp);
+    }
+    if embed.is_none_or(|e| e.frame_ancestors.is_empty()) {
+        headers.insert(
+            "x-frame-options",
+            axum::http::HeaderValue::from_static("SAMEORIGIN"),
+        );
+    }
+    headers.insert(
+        "x-content-type-options",
+        axum::http::HeaderValue::from_static("nosniff"),
+    );
+    resp
+}
+
+/// Per-caller budgets for `/api/*`, kept apart for reads and writes so a client flooding one
+/// kind cannot use up the other (or the per-session send budget of the UI). Every 429,
+/// including those of the per-session and `/ask` limiters, carries `Retry-After`.
+async fn api_rate_limit(
+    State(state): State<WebState>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    if path.starts_with("/api/") {
+        let read = matches!(
+            *req.method(),
+            axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+        );
+        let (class, max) = if read {
+            ("read", state.limits.max_reads_per_window)
+        } else {
+            ("write", state.limits.max_writes_per_window)
+        };
+        let key = format!(
+            "{}:{class}",
+            rate_limit_key(&req, &state.app_state.config.web_trusted_proxies)
+        );
+        if let Err(wait) = state
+            .request_hub
+            .admit(&key, max, state.limits.rate_window)
+            .await
+        {
+            info!(
+                target: "web",
+                endpoint = %path,
+                class,
+                "Request rejected by limiter"
+            );
+            return too_many_requests(format!("{class} rate limit exceeded"), wait);
+        }
+    }
+    let mut resp = next.run(req).await;
+    if resp.status() == StatusCode::TOO_MANY_REQUESTS
+        && !resp
+            .headers()
+            .contains_key(axum::http::header::RETRY_AFTER)
+    {
+        let window = match state.app_state.config.public_ask.as_ref() {
+            Some(ask) if path == "/ask" => Duration::from_secs(ask.rate_window_seconds.max(1)),
+            _ => state.limits.rate_window,
+        };
+        let seconds = window.as_secs().max(1).to_string();
+        if let Ok(value) = axum::http::HeaderValue::from_str(&seconds) {
+            resp.headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, value);
+        }
+    }
+    resp
+}
+
+/// GET routes that still write (the OAuth flow stores pending state and tokens).
+const WRITING_GET_PREFIXES: &[&str] = &["/api/oauth/"];
+
+/// Whether a request leaves the state untouched, so a standby may serve it.
+fn is_read_only_request(method: &axum::http::Method, path: &str) -> bool {
+    matches!(
+        *method,
+        axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+    ) && !WRITING_GET_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Standby instances (see `instance_lock`) serve the web UI read-only.
+async fn standby_read_only(req: axum::extract::Request, next: axum::middleware::Next) -> Response {
+    if !is_read_only_request(req.method(), req.uri().path()) && !crate::instance_lock::is_leader()
+    {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "This instance is a read-only standby",
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1821,6 +3068,7 @@ mod tests {
             api_key: "key".into(),
             model: "claude-sonnet-4-5-20250929".into(),
             llm_base_url: None,
+            llm_fallbacks: Vec::new(),
             max_tokens: 8192,
             max_tool_iterations: 100,
             max_history_messages: 50,
@@ -1832,20 +3080,42 @@ mod tests {
             control_chat_ids: vec![],
             max_session_messages: 40,
             compact_keep_recent: 20,
+            memory_flush_idle_minutes: 30,
+            memory_flush_model: String::new(),
+            onboarding_enabled: true,
+            task_max_retries: 2,
+            task_retry_backoff_secs: 60,
+            scheduler_max_concurrent_tasks: 4,
+            deleted_session_retention_days: 30,
             whatsapp_access_token: None,
             whatsapp_phone_number_id: None,
             whatsapp_verify_token: None,
             whatsapp_webhook_port: 8080,
             discord_bot_token: None,
             discord_allowed_channels: vec![],
-            show_thinking: false,
+            slack_bot_token: None,
+            slack_app_token: None,
+            slack_allowed_channels: vec![],
+            signal: None,
+            teams: None,
+            wyoming: None,
+            home_assistant: None,
+            python: None,
+            browser_downloads: None,
+            web_embed: None,
+            transcription: None,
+            calendar: None,
+            show_thinking: crate::config::ThinkingLevel::Off,
             web_enabled: true,
             web_host: "127.0.0.1".into(),
             web_port: 3900,
             web_auth_token: None,
+            web_trusted_proxies: vec![],
             web_max_inflight_per_session: 2,
             web_max_requests_per_window: 8,
             web_rate_window_seconds: 10,
+            web_max_reads_per_window: 120,
+            web_max_writes_per_window: 30,
             web_run_history_limit: 512,
             web_session_idle_ttl_seconds: 300,
             browser_managed: false,
@@ -1857,10 +3127,39 @@ mod tests {
             cursor_agent_cli_path: "cursor-agent".into(),
             cursor_agent_model: String::new(),
             cursor_agent_timeout_secs: 600,
+            cursor_agent_review: true,
+            cursor_agent_digest_days: 7,
             social: None,
             vault: None,
             orchestrator_enabled: true,
             orchestrator_model: String::new(),
+            translation: None,
+            public_ask: None,
+            sticker_pack_path: None,
+            output_filter: None,
+            inbound_moderation: None,
+            egress: Default::default(),
+            browser_profiles: Vec::new(),
+            model_prices: Default::default(),
+            experiments: Vec::new(),
+            feedback_report_days: 0,
+            instance_lock: false,
+            feeds: Vec::new(),
+            middleware_hooks: Vec::new(),
+            feature_flags: Default::default(),
+            announce_release_notes: true,
+            anthropic_prompt_caching: true,
+            notifications: None,
+            heartbeat: None,
+            workspace_encryption: None,
+            incidents: None,
+            push: None,
+            provider_files: None,
+            roundtable: None,
+            benchmark: None,
+            chat_archive: None,
+            provider_search: None,
+            telegram_business: None,
         };
         let dir = std::env::temp_dir().join(format!("microclaw_webtest_{}", uuid::Uuid::new_v4()));
         std::fs::create_dir_all(&dir).unwrap();
@@ -1884,23 +3183,222 @@ mod tests {
             llm,
             tools: ToolRegistry::new(&cfg, bot, db),
         };
-        Arc::new(state)
+        Arc::new(state)
+    }
+
+    fn test_web_state(
+        llm: Box<dyn LlmProvider>,
+        auth_token: Option<String>,
+        limits: WebLimits,
+    ) -> WebState {
+        let state = test_state(llm);
+        WebState {
+            app_state: state,
+            auth_token,
+            run_hub: RunHub::default(),
+            session_hub: SessionHub::default(),
+            request_hub: RequestHub::default(),
+            limits,
+        }
+    }
+
+    fn test_ask_app(cfg: Option<PublicAskConfig>) -> (Router, Arc<AppState>) {
+        let mut state = test_state(Box::new(DummyLlm));
+        Arc::get_mut(&mut state).unwrap().config.public_ask = cfg;
+        let web_state = WebState {
+            app_state: state.clone(),
+            auth_token: Some("admin".into()),
+            run_hub: RunHub::default(),
+            session_hub: SessionHub::default(),
+            request_hub: RequestHub::default(),
+            limits: WebLimits::default(),
+        };
+        (build_router(web_state), state)
+    }
+
+    fn ask_request(body: &str, token: Option<&str>) -> Request<Body> {
+        ask_request_from("198.51.100.1", "203.0.113.7", body, token)
+    }
+
+    /// A request arriving from `peer` that claims to be forwarded for `forwarded_for`.
+    fn ask_request_from(
+        peer: &str,
+        forwarded_for: &str,
+        body: &str,
+        token: Option<&str>,
+    ) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/ask")
+            .header("content-type", "application/json")
+            .header("x-forwarded-for", forwarded_for);
+        if let Some(token) = token {
+            builder = builder.header("x-ask-token", token);
+        }
+        let mut req = builder.body(Body::from(body.to_string())).unwrap();
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::new(
+            peer.parse().unwrap(),
+            40000,
+        )));
+        req
+    }
+
+    fn ask_config() -> PublicAskConfig {
+        PublicAskConfig {
+            chat_id: 4242,
+            tokens: vec!["site-token".into()],
+            allowed_origin: Some("https://example.com".into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ask_not_found_when_disabled() {
+        let (app, _) = test_ask_app(None);
+        let resp = app
+            .oneshot(ask_request(r#"{"question":"hi"}"#, Some("site-token")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_ask_requires_valid_token() {
+        let (app, _) = test_ask_app(Some(ask_config()));
+        let resp = app
+            .clone()
+            .oneshot(ask_request(r#"{"question":"hi"}"#, None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = app
+            .oneshot(ask_request(r#"{"question":"hi"}"#, Some("wrong")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_ask_answers_with_guest_persona() {
+        let (app, state) = test_ask_app(Some(ask_config()));
+        let resp = app
+            .oneshot(ask_request(
+                r#"{"question":"What do you do?","name":"Ann"}"#,
+                Some("site-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get("access-control-allow-origin")
+                .and_then(|v| v.to_str().ok()),
+            Some("https://example.com")
+        );
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(v["answer"], "hello from llm");
+
+        let guest = call_blocking(state.db.clone(), |db| {
+            db.get_persona_by_name(4242, "guest")
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let messages = call_blocking(state.db.clone(), move |db| {
+            db.get_all_messages(4242, guest.id)
+        })
+        .await
+        .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender_name, "guest:Ann");
+        assert!(messages[1].is_from_bot);
+        let active = call_blocking(state.db.clone(), |db| db.get_active_persona_id(4242))
+            .await
+            .unwrap();
+        assert_ne!(active, Some(guest.id));
+    }
+
+    #[tokio::test]
+    async fn test_ask_rate_limited_per_client() {
+        let cfg = PublicAskConfig {
+            max_requests_per_window: 1,
+            ..ask_config()
+        };
+        let (app, _) = test_ask_app(Some(cfg));
+        let resp = app
+            .clone()
+            .oneshot(ask_request(r#"{"question":"one"}"#, Some("site-token")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .oneshot(ask_request(r#"{"question":"two"}"#, Some("site-token")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_ask_rate_limit_ignores_spoofed_forwarded_for() {
+        let cfg = PublicAskConfig {
+            max_requests_per_window: 1,
+            ..ask_config()
+        };
+        let (app, _) = test_ask_app(Some(cfg));
+        let resp = app
+            .clone()
+            .oneshot(ask_request_from(
+                "198.51.100.1",
+                "203.0.113.7",
+                r#"{"question":"one"}"#,
+                Some("site-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .oneshot(ask_request_from(
+                "198.51.100.1",
+                "203.0.113.8",
+                r#"{"question":"two"}"#,
+                Some("site-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
     }
 
-    fn test_web_state(
-        llm: Box<dyn LlmProvider>,
-        auth_token: Option<String>,
-        limits: WebLimits,
-    ) -> WebState {
-        let state = test_state(llm);
-        WebState {
-            app_state: state,
-            auth_token,
-            run_hub: RunHub::default(),
-            session_hub: SessionHub::default(),
-            request_hub: RequestHub::default(),
-            limits,
-        }
+    #[test]
+    fn test_client_address_trusts_only_configured_proxies() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 203.0.113.7, 10.0.0.2".parse().unwrap());
+        let proxy: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        let trusted = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        // The client-supplied first hop is skipped; the last untrusted hop is the client
+        assert_eq!(client_address(Some(proxy), &headers, &trusted), "203.0.113.7");
+        assert_eq!(client_address(Some(proxy), &headers, &[]), "10.0.0.1");
+        assert_eq!(client_address(None, &headers, &trusted), "unknown");
+        assert_eq!(
+            client_address(Some(proxy), &HeaderMap::new(), &trusted),
+            "10.0.0.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ask_rejects_oversized_question() {
+        let cfg = PublicAskConfig {
+            max_question_chars: 5,
+            ..ask_config()
+        };
+        let (app, _) = test_ask_app(Some(cfg));
+        let resp = app
+            .oneshot(ask_request(r#"{"question":"too long"}"#, Some("site-token")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 
     #[tokio::test]
@@ -2004,11 +3502,463 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 
+    #[tokio::test]
+    async fn test_memory_api_reads_and_writes_tiers() {
+        let web_state = test_web_state(
+            Box::new(DummyLlm),
+            Some("secret-token".into()),
+            WebLimits::default(),
+        );
+        let db = web_state.app_state.db.clone();
+        db.upsert_chat(77, Some("notes"), "web").unwrap();
+        let app = build_router(web_state);
+        let memory_request = |method: &str, uri: &str, body: &str, token: Option<&str>| {
+            let mut builder = Request::builder().method(method).uri(uri);
+            if let Some(token) = token {
+                builder = builder.header("authorization", format!("Bearer {token}"));
+            }
+            builder.body(Body::from(body.to_string())).unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(memory_request("GET", "/api/memory/77/1", "", None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = app
+            .clone()
+            .oneshot(memory_request(
+                "PUT",
+                "/api/memory/77/2",
+                "- Garden redesign",
+                Some("secret-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = app
+            .clone()
+            .oneshot(memory_request(
+                "GET",
+                "/api/memory/77/2",
+                "",
+                Some("secret-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&body), "- Garden redesign");
+
+        let resp = app
+            .clone()
+            .oneshot(memory_request(
+                "GET",
+                "/api/memory/77/all",
+                "",
+                Some("secret-token"),
+            ))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let full = String::from_utf8_lossy(&body).to_string();
+        assert!(full.starts_with("# Memory"), "{full}");
+        assert!(
+            full.contains("## Tier 2 — Mid term\n\n- Garden redesign"),
+            "{full}"
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(memory_request(
+                "GET",
+                "/api/memory/77/9",
+                "",
+                Some("secret-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .oneshot(memory_request(
+                "GET",
+                "/api/memory/78/1",
+                "",
+                Some("secret-token"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_social_connections_api_lists_and_disconnects() {
+        let web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        let db = web_state.app_state.db.clone();
+        db.upsert_social_token(
+            "instagram",
+            12,
+            "secret-access-token",
+            None,
+            Some("2000-01-01T00:00:00Z"),
+            Some("instagram_basic,user_media"),
+        )
+        .unwrap();
+        let app = build_router(web_state);
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/social/connections?chat_id=12")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8_lossy(&body).to_string();
+        assert!(!text.contains("secret-access-token"), "{text}");
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let conn = &json["connections"][0];
+        assert_eq!(conn["platform"], "instagram");
+        assert_eq!(conn["scopes"], json!(["instagram_basic", "user_media"]));
+        assert_eq!(conn["expired"], true);
+
+        let disconnect = || {
+            Request::builder()
+                .method("DELETE")
+                .uri("/api/social/connections/12/instagram")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(disconnect()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(db.get_social_token("instagram", 12).unwrap().is_none());
+        let resp = app.oneshot(disconnect()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_feature_flags_api() {
+        let web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        let app = build_router(web_state);
+        let put = |body: serde_json::Value| {
+            Request::builder()
+                .method("PUT")
+                .uri("/api/feature_flags")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(put(
+                json!({"session_key": "main", "flag": "vision", "enabled": false}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/feature_flags?session_key=main")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let vision = json["flags"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|f| f["flag"] == "vision")
+            .unwrap();
+        assert_eq!(vision["enabled"], false);
+        assert_eq!(vision["overridden"], true);
+
+        let resp = app
+            .oneshot(put(
+                json!({"session_key": "main", "flag": "teleport", "enabled": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_api_lists_and_deletes() {
+        let web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        let db = web_state.app_state.db.clone();
+        let app = build_router(web_state);
+        let get_list = || {
+            Request::builder()
+                .uri("/api/artifacts?session_key=main")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let list = |resp: Response| async move {
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let json = list(app.clone().oneshot(get_list()).await.unwrap()).await;
+        assert_eq!(json["artifacts"].as_array().unwrap().len(), 0);
+        let chat_id = json["chat_id"].as_i64().unwrap();
+        let id = db
+            .save_artifact(&crate::db::Artifact {
+                chat_id,
+                title: "Packing list".into(),
+                kind: "checklist".into(),
+                content: "- passport".into(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let json = list(app.clone().oneshot(get_list()).await.unwrap()).await;
+        assert_eq!(json["artifacts"][0]["title"], "Packing list");
+        assert_eq!(json["artifacts"][0]["type"], "checklist");
+        assert_eq!(json["artifacts"][0]["content"], "- passport");
+
+        let delete = || {
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/artifacts/{id}?session_key=main"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(delete()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.clone().oneshot(delete()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_incident_ingest_ack_and_resolve() {
+        let mut web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        let app_state = Arc::get_mut(&mut web_state.app_state).unwrap();
+        app_state.config.control_chat_ids = vec![100];
+        app_state.db.upsert_chat(100, Some("ops"), "web").unwrap();
+        let app = build_router(web_state);
+        let post_json = |uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let read_json = |resp: axum::response::Response| async move {
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let alert = json!({"title": "UPS on battery", "key": "ups", "source": "nut"});
+        let resp = app
+            .clone()
+            .oneshot(post_json("/api/incidents", alert.clone()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let json = read_json(resp).await;
+        assert_eq!(json["new"], true);
+        let id = json["incident"]["id"].as_i64().unwrap();
+        let json = read_json(
+            app.clone()
+                .oneshot(post_json("/api/incidents", alert))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(json["new"], false);
+        assert_eq!(json["incident"]["id"].as_i64(), Some(id));
+
+        let resp = app
+            .clone()
+            .oneshot(post_json(&format!("/api/incidents/{id}/ack"), json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_json(resp).await["incident"]["status"], "acknowledged");
+
+        let resolve = json!({"title": "UPS back on mains", "key": "ups", "status": "resolved"});
+        let resp = app
+            .clone()
+            .oneshot(post_json("/api/incidents", resolve.clone()))
+            .await
+            .unwrap();
+        assert_eq!(read_json(resp).await["incident"]["status"], "resolved");
+        let resp = app
+            .clone()
+            .oneshot(post_json("/api/incidents", resolve))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/incidents")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(read_json(resp).await["incidents"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_task_pause_resume_run_endpoints() {
+        let web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        let db = web_state.app_state.db.clone();
+        let id = db
+            .create_scheduled_task(100, "water plants", "cron", "0 0 8 * * *", "2099-01-01T08:00:00Z")
+            .unwrap();
+        let app = build_router(web_state);
+        let post = |action: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/tasks/{id}/{action}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(post("pause")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(db.get_task_by_id(id).unwrap().unwrap().status, "paused");
+        let resp = app.clone().oneshot(post("run")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let task = db.get_task_by_id(id).unwrap().unwrap();
+        assert!(task.run_requested_at.is_some());
+        assert_eq!(task.status, "paused");
+        let resp = app.clone().oneshot(post("resume")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(db.get_task_by_id(id).unwrap().unwrap().status, "active");
+
+        let resp = app.clone().oneshot(post("explode")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        db.update_task_status(id, "cancelled").unwrap();
+        let resp = app.clone().oneshot(post("run")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/tasks/9999/run")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_oauth_flow_requires_bound_single_use_state() {
+        let mut web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        Arc::get_mut(&mut web_state.app_state)
+            .unwrap()
+            .config
+            .social = Some(crate::config::SocialConfig {
+            base_url: Some("https://bot.example.com".into()),
+            tiktok: crate::config::SocialPlatformConfig {
+                client_id: Some("key".into()),
+                client_secret: Some("secret".into()),
+            },
+            ..Default::default()
+        });
+        let config = web_state.app_state.config.clone();
+        let db = web_state.app_state.db.clone();
+        let app = build_router(web_state);
+        let get = |uri: &str, cookie: Option<&str>| {
+            let mut builder = Request::builder().uri(uri);
+            if let Some(c) = cookie {
+                builder = builder.header("cookie", c);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        // Raw chat ids are no longer accepted as the flow's state.
+        let resp = app
+            .clone()
+            .oneshot(get("/api/oauth/authorize/tiktok?chat_id=5", None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let link = social_oauth::start_authorization(&config, db.clone(), "tiktok", 5, None)
+            .await
+            .unwrap();
+        let path = link.trim_start_matches("https://bot.example.com");
+        let resp = app.clone().oneshot(get(path, None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TEMPORARY_REDIRECT);
+        let location = resp.headers()["location"].to_str().unwrap().to_string();
+        assert!(
+            location.contains("code_challenge_method=S256"),
+            "{location}"
+        );
+        let cookie = resp.headers()["set-cookie"].to_str().unwrap().to_string();
+        assert!(
+            cookie.contains("HttpOnly") && cookie.contains("Secure"),
+            "{cookie}"
+        );
+        let binding = cookie.split(';').next().unwrap().to_string();
+
+        // The link is single use.
+        let resp = app.clone().oneshot(get(path, None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // A callback replayed in a browser that did not start the flow is rejected.
+        let state_token = link.rsplit("state=").next().unwrap();
+        let callback = format!("/api/oauth/callback/tiktok?code=abc&state={state_token}");
+        let resp = app
+            .clone()
+            .oneshot(get(&callback, Some("microclaw_oauth_binding=other")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app.oneshot(get(&callback, None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        // ...without burning the state for the browser that started the flow.
+        assert!(db.get_social_token("tiktok", 5).unwrap().is_none());
+        let binding = binding.trim_start_matches("microclaw_oauth_binding=");
+        assert!(db
+            .consume_oauth_pending_state(state_token, binding)
+            .unwrap()
+            .is_some());
+    }
+
     #[tokio::test]
     async fn test_same_session_concurrency_limited() {
         let limits = WebLimits {
             max_inflight_per_session: 1,
             max_requests_per_window: 10,
+            max_reads_per_window: 120,
+            max_writes_per_window: 30,
             rate_window: Duration::from_secs(10),
             run_history_limit: 128,
             session_idle_ttl: Duration::from_secs(60),
@@ -2186,6 +4136,8 @@ mod tests {
         let limits = WebLimits {
             max_inflight_per_session: 2,
             max_requests_per_window: 1,
+            max_reads_per_window: 120,
+            max_writes_per_window: 30,
             rate_window: Duration::from_millis(200),
             run_history_limit: 128,
             session_idle_ttl: Duration::from_secs(60),
@@ -2210,12 +4162,50 @@ mod tests {
 
         let resp2 = app.clone().oneshot(mk_req("r2")).await.unwrap();
         assert_eq!(resp2.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp2.headers().contains_key("retry-after"));
 
         tokio::time::sleep(Duration::from_millis(260)).await;
         let resp3 = app.oneshot(mk_req("r3")).await.unwrap();
         assert_eq!(resp3.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_per_token_and_class_with_retry_after() {
+        let limits = WebLimits {
+            max_reads_per_window: 2,
+            max_writes_per_window: 1,
+            ..WebLimits::default()
+        };
+        let web_state = test_web_state(Box::new(DummyLlm), None, limits);
+        let app = build_router(web_state);
+        let req = |method: &str, uri: &str, token: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {token}"))
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"session_key":"main"}"#))
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let resp = app.clone().oneshot(req("GET", "/api/sessions", "bot")).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let resp = app.clone().oneshot(req("GET", "/api/sessions", "bot")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = resp.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+        assert!((1..=10).contains(&retry_after));
+
+        // Writes and other tokens have budgets of their own.
+        let resp = app.clone().oneshot(req("POST", "/api/reset", "bot")).await.unwrap();
+        assert_ne!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let resp = app.clone().oneshot(req("POST", "/api/reset", "bot")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let resp = app.oneshot(req("GET", "/api/sessions", "ui")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn test_db_paths_use_call_blocking_in_web_flow() {
         let state = test_state(Box::new(DummyLlm));
@@ -2229,4 +4219,44 @@ mod tests {
             .len();
         assert_eq!(message_count, 0);
     }
+
+    #[tokio::test]
+    async fn test_security_headers_and_frame_ancestors() {
+        let web_state = test_web_state(Box::new(DummyLlm), None, WebLimits::default());
+        let resp = build_router(web_state)
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let csp = resp.headers()["content-security-policy"].to_str().unwrap();
+        assert!(csp.ends_with("frame-ancestors 'self'"), "{csp}");
+        assert_eq!(resp.headers()["x-frame-options"], "SAMEORIGIN");
+
+        let embed = WebEmbedConfig {
+            frame_ancestors: vec!["https://ha.example.com:8123".into()],
+            content_security_policy: None,
+        };
+        assert!(content_security_policy(Some(&embed))
+            .ends_with("frame-ancestors 'self' https://ha.example.com:8123"));
+        let custom = WebEmbedConfig {
+            content_security_policy: Some("default-src 'self'; frame-ancestors *;".into()),
+            ..embed
+        };
+        assert_eq!(
+            content_security_policy(Some(&custom)),
+            "default-src 'self'; frame-ancestors *"
+        );
+        assert!(WebEmbedConfig::valid_origin("https://*.example.com"));
+        assert!(!WebEmbedConfig::valid_origin("https://x.com; script-src *"));
+        assert!(!WebEmbedConfig::valid_origin("ha.example.com"));
+    }
+
+    #[test]
+    fn test_standby_read_only_requests() {
+        use axum::http::Method;
+        assert!(is_read_only_request(&Method::GET, "/api/history"));
+        assert!(is_read_only_request(&Method::OPTIONS, "/ask"));
+        assert!(!is_read_only_request(&Method::POST, "/api/send"));
+        assert!(!is_read_only_request(&Method::GET, "/api/oauth/authorize/google"));
+        assert!(!is_read_only_request(&Method::GET, "/api/oauth/callback/google"));
+    }
 }
@@ -0,0 +1,951 @@
+//! Workspace encryption at rest. With `workspace_encryption` set, the runtime directory
+//! (database, memory, exports, logs) is kept on disk only as `<workspace>/runtime.enc`: one
+//! archive of every file, sealed in chunks with AES-256-GCM under a key derived from a passphrase
+//! (PBKDF2-SHA256). On start it is unpacked into the mount directory (memory-backed
+//! `/dev/shm` by default), re-sealed every `sync_interval_secs` (when something changed) and at
+//! shutdown, and the mount is removed after a clean shutdown. A stolen or shared machine then
+//! only holds ciphertext. Sealing streams the files through a temporary archive, so memory use
+//! does not grow with the runtime.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use tracing::{info, warn};
+
+use crate::config::{Config, WorkspaceEncryptionConfig};
+use crate::db::Database;
+use crate::error::MicroClawError;
+use crate::telegram::AppState;
+
+const ARCHIVE_FILE: &str = "runtime.enc";
+/// Written into the mount directory once it holds the unpacked archive; never sealed.
+const MOUNT_MARKER: &str = ".mounted";
+/// Held exclusively by the process owning the mount, and shared by processes using its copy.
+const OWNER_LOCK_FILE: &str = "runtime.enc.lock";
+const USERS_LOCK_FILE: &str = "runtime.enc.users";
+/// Seconds to wait for another process to finish unpacking before giving up.
+const MOUNT_ATTEMPTS: u32 = 120;
+/// [`encrypt`]ed in one block (small payloads, and archives before the streaming format).
+const ENCRYPTED_MAGIC: &[u8; 8] = b"MCWENC1\0";
+/// The archive, sealed in chunks (see [`SealWriter`]).
+const STREAM_MAGIC: &[u8; 8] = b"MCWENC2\0";
+const PACK_MAGIC: &[u8; 8] = b"MCWPACK1";
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+/// Plaintext bytes per sealed chunk of the archive.
+const CHUNK_LEN: usize = 1 << 20;
+const PBKDF2_ITERATIONS: u32 = 200_000;
+
+fn crypto_error(msg: impl Into<String>) -> MicroClawError {
+    MicroClawError::Config(msg.into())
+}
+
+/// The encrypted archive of the workspace.
+pub fn archive_path(config: &Config) -> PathBuf {
+    config.workspace_root_absolute().join(ARCHIVE_FILE)
+}
+
+/// The passphrase from the configured variable, else the first line of the passphrase file.
+pub fn passphrase(enc: &WorkspaceEncryptionConfig) -> Result<String, MicroClawError> {
+    if let Ok(value) = std::env::var(&enc.passphrase_env) {
+        if !value.is_empty() {
+            return Ok(value);
+        }
+    }
+    if let Some(file) = enc.passphrase_file.as_deref() {
+        let text = std::fs::read_to_string(file)?;
+        if let Some(line) = text.lines().next().filter(|l| !l.is_empty()) {
+            return Ok(line.to_string());
+        }
+    }
+    Err(crypto_error(format!(
+        "workspace encryption is on but no passphrase is set: export {} or set workspace_encryption.passphrase_file",
+        enc.passphrase_env
+    )))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey, MicroClawError> {
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(
+        ring::pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| crypto_error("failed to set up the workspace key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Seal `plain`: magic, salt, nonce, then the ciphertext with its tag.
+pub fn encrypt(plain: &[u8], passphrase: &str) -> Result<Vec<u8>, MicroClawError> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| crypto_error("no secure randomness available"))?;
+    let key = derive_key(passphrase, &salt)?;
+
+    let mut out = Vec::with_capacity(8 + SALT_LEN + NONCE_LEN + plain.len() + 16);
+    out.extend_from_slice(ENCRYPTED_MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    let mut body = plain.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(&out[..8 + SALT_LEN]),
+        &mut body,
+    )
+    .map_err(|_| crypto_error("failed to encrypt the workspace"))?;
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, MicroClawError> {
+    let header_len = 8 + SALT_LEN + NONCE_LEN;
+    if data.len() < header_len || &data[..8] != ENCRYPTED_MAGIC {
+        return Err(crypto_error("not an encrypted workspace archive"));
+    }
+    let key = derive_key(passphrase, &data[8..8 + SALT_LEN])?;
+    let nonce = Nonce::try_assume_unique_for_key(&data[8 + SALT_LEN..header_len])
+        .map_err(|_| crypto_error("corrupted workspace archive"))?;
+    let mut body = data[header_len..].to_vec();
+    let plain = key
+        .open_in_place(nonce, Aad::from(&data[..8 + SALT_LEN]), &mut body)
+        .map_err(|_| {
+            crypto_error("cannot decrypt the workspace: wrong passphrase or corrupted archive")
+        })?;
+    Ok(plain.to_vec())
+}
+
+fn collect_files(dir: &Path, root: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let kind = entry.file_type()?;
+        let path = entry.path();
+        if kind.is_dir() {
+            collect_files(&path, root, out)?;
+        } else if kind.is_file() {
+            out.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// The files to seal under `dir`, relative and sorted; the mount marker is left out.
+fn sealed_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if dir.is_dir() {
+        collect_files(dir, dir, &mut files)?;
+    }
+    files.retain(|rel| rel != Path::new(MOUNT_MARKER));
+    Ok(files)
+}
+
+/// Stream every regular file under `dir` into `out`: per file, the relative path and the
+/// contents, each prefixed with its length. Symlinks are skipped.
+fn pack_to(dir: &Path, out: &mut impl Write) -> Result<(), MicroClawError> {
+    out.write_all(PACK_MAGIC)?;
+    for rel in sealed_files(dir)? {
+        let file = File::open(dir.join(&rel))?;
+        let len = file.metadata()?.len();
+        let name = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        out.write_all(&(name.len() as u32).to_le_bytes())?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(&len.to_le_bytes())?;
+        // A file that grows meanwhile is cut at the announced length; one that shrinks fails
+        // this seal (the next one picks it up)
+        if std::io::copy(&mut file.take(len), out)? != len {
+            return Err(crypto_error(format!("{name} shrank while being sealed")));
+        }
+    }
+    Ok(())
+}
+
+/// Every regular file under `dir` as one blob (see [`pack_to`]).
+pub fn pack(dir: &Path) -> Result<Vec<u8>, MicroClawError> {
+    let mut out = Vec::new();
+    pack_to(dir, &mut out)?;
+    Ok(out)
+}
+
+/// Fill `buf` from `input`; false on a clean end of input before the first byte.
+fn read_field(input: &mut impl Read, buf: &mut [u8]) -> Result<bool, MicroClawError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..])? {
+            0 if filled == 0 => return Ok(false),
+            0 => return Err(crypto_error("corrupted workspace archive")),
+            n => filled += n,
+        }
+    }
+    Ok(true)
+}
+
+/// Write the files of a [`pack_to`] stream under `dir`; returns how many were written.
+fn unpack_from(input: &mut impl Read, dir: &Path) -> Result<usize, MicroClawError> {
+    let corrupt = || crypto_error("corrupted workspace archive");
+    let mut magic = [0u8; 8];
+    if !read_field(input, &mut magic)? || &magic != PACK_MAGIC {
+        return Err(corrupt());
+    }
+    let mut count = 0;
+    loop {
+        let mut len = [0u8; 4];
+        if !read_field(input, &mut len)? {
+            break;
+        }
+        let mut name = vec![0u8; u32::from_le_bytes(len) as usize];
+        let mut data_len = [0u8; 8];
+        if !read_field(input, &mut name)? && !name.is_empty()
+            || !read_field(input, &mut data_len)?
+        {
+            return Err(corrupt());
+        }
+        let name = String::from_utf8_lossy(&name).to_string();
+        let rel = Path::new(&name);
+        if rel.is_absolute()
+            || rel
+                .components()
+                .any(|c| !matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(crypto_error(format!(
+                "unsafe path in workspace archive: {name}"
+            )));
+        }
+        let path = dir.join(rel);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data_len = u64::from_le_bytes(data_len);
+        let mut file = File::create(path)?;
+        if std::io::copy(&mut input.take(data_len), &mut file)? != data_len {
+            return Err(corrupt());
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Write the files of a [`pack`] blob under `dir`; returns how many were written.
+pub fn unpack(blob: &[u8], dir: &Path) -> Result<usize, MicroClawError> {
+    unpack_from(&mut &blob[..], dir)
+}
+
+// --- Streaming archive format ---
+//
+// `STREAM_MAGIC`, salt, a random nonce prefix, then the packed runtime in sealed chunks of
+// `CHUNK_LEN` bytes. Each chunk's nonce is the prefix, the chunk counter and a last-chunk flag
+// (the STREAM construction), so chunks cannot be reordered, dropped or cut off unnoticed, and
+// neither sealing nor unpacking holds more than one chunk in memory.
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = u8::from(last);
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// Encrypts everything written to it into the streaming format; call [`SealWriter::finish`].
+struct SealWriter<W: Write> {
+    key: LessSafeKey,
+    header: Vec<u8>,
+    prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    buf: Vec<u8>,
+    out: W,
+}
+
+impl<W: Write> SealWriter<W> {
+    fn new(mut out: W, passphrase: &str) -> Result<Self, MicroClawError> {
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        rng.fill(&mut salt)
+            .and_then(|_| rng.fill(&mut prefix))
+            .map_err(|_| crypto_error("no secure randomness available"))?;
+        let mut header = STREAM_MAGIC.to_vec();
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&prefix);
+        out.write_all(&header)?;
+        Ok(SealWriter {
+            key: derive_key(passphrase, &salt)?,
+            header,
+            prefix,
+            counter: 0,
+            buf: Vec::with_capacity(CHUNK_LEN + TAG_LEN),
+            out,
+        })
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> std::io::Result<()> {
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        self.key
+            .seal_in_place_append_tag(nonce, Aad::from(&self.header), &mut self.buf)
+            .map_err(|_| std::io::Error::other("failed to encrypt the workspace"))?;
+        self.out.write_all(&self.buf)?;
+        self.buf.clear();
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| std::io::Error::other("workspace too large to seal"))?;
+        Ok(())
+    }
+
+    /// Seal the last (possibly empty) chunk and return the output.
+    fn finish(mut self) -> std::io::Result<W> {
+        self.seal_chunk(true)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for SealWriter<W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let n = data.len().min(CHUNK_LEN - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == CHUNK_LEN {
+            self.seal_chunk(false)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Decrypts a stream written by [`SealWriter`], chunk by chunk.
+struct OpenReader<R: BufRead> {
+    key: LessSafeKey,
+    header: Vec<u8>,
+    prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    chunk: Vec<u8>,
+    pos: usize,
+    plain_len: usize,
+    done: bool,
+    input: R,
+}
+
+impl<R: BufRead> OpenReader<R> {
+    /// `input` positioned just after the magic.
+    fn new(mut input: R, passphrase: &str) -> Result<Self, MicroClawError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        input.read_exact(&mut salt)?;
+        input.read_exact(&mut prefix)?;
+        let mut header = STREAM_MAGIC.to_vec();
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&prefix);
+        Ok(OpenReader {
+            key: derive_key(passphrase, &salt)?,
+            header,
+            prefix,
+            counter: 0,
+            chunk: Vec::with_capacity(CHUNK_LEN + TAG_LEN),
+            pos: 0,
+            plain_len: 0,
+            done: false,
+            input,
+        })
+    }
+
+    fn open_next(&mut self) -> std::io::Result<()> {
+        self.chunk.resize(CHUNK_LEN + TAG_LEN, 0);
+        let mut filled = 0;
+        while filled < self.chunk.len() {
+            match self.input.read(&mut self.chunk[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        self.chunk.truncate(filled);
+        let last = self.input.fill_buf()?.is_empty();
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        let plain = self
+            .key
+            .open_in_place(nonce, Aad::from(&self.header), &mut self.chunk)
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "cannot decrypt the workspace: wrong passphrase or corrupted archive",
+                )
+            })?;
+        self.plain_len = plain.len();
+        self.pos = 0;
+        self.done = last;
+        self.counter = self.counter.wrapping_add(1);
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for OpenReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.plain_len {
+            if self.done {
+                return Ok(0);
+            }
+            self.open_next()?;
+        }
+        let n = out.len().min(self.plain_len - self.pos);
+        out[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Passes bytes through while hashing them (SHA-256).
+struct Digesting<T> {
+    inner: T,
+    digest: ring::digest::Context,
+}
+
+impl<T> Digesting<T> {
+    fn new(inner: T) -> Self {
+        Digesting {
+            inner,
+            digest: ring::digest::Context::new(&ring::digest::SHA256),
+        }
+    }
+
+    fn finish(self) -> (T, Vec<u8>) {
+        (self.inner, self.digest.finish().as_ref().to_vec())
+    }
+}
+
+impl<W: Write> Write for Digesting<W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(data)?;
+        self.digest.update(&data[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: Read> Read for Digesting<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(out)?;
+        self.digest.update(&out[..n]);
+        Ok(n)
+    }
+}
+
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Seal `dir` into the archive through a temporary file; returns the digest of the packed
+/// stream.
+fn write_archive(config: &Config, passphrase: &str, dir: &Path) -> Result<Vec<u8>, MicroClawError> {
+    let path = archive_path(config);
+    // One temporary file per writer, so a crashed writer's leftover is never reused
+    let tmp = path.with_extension(format!("enc.{}.tmp", uuid::Uuid::new_v4()));
+    let result = (|| {
+        let sealer = SealWriter::new(BufWriter::new(File::create(&tmp)?), passphrase)?;
+        let mut packer = Digesting::new(sealer);
+        pack_to(dir, &mut packer)?;
+        let (sealer, digest) = packer.finish();
+        let file = sealer.finish()?.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(digest)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Unpack the archive into `dir`; returns the file count and the digest of the packed stream.
+fn read_archive(
+    archive: &Path,
+    passphrase: &str,
+    dir: &Path,
+) -> Result<(usize, Vec<u8>), MicroClawError> {
+    let mut input = BufReader::new(File::open(archive)?);
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic == ENCRYPTED_MAGIC {
+        // Written as one block before the streaming format
+        let mut data = magic.to_vec();
+        input.read_to_end(&mut data)?;
+        let blob = decrypt(&data, passphrase)?;
+        let digest = ring::digest::digest(&ring::digest::SHA256, &blob);
+        return Ok((unpack(&blob, dir)?, digest.as_ref().to_vec()));
+    }
+    if &magic != STREAM_MAGIC {
+        return Err(crypto_error("not an encrypted workspace archive"));
+    }
+    let mut unpacker = Digesting::new(OpenReader::new(input, passphrase)?);
+    let files = unpack_from(&mut unpacker, dir)?;
+    // Read to the end so a cut-off or tampered tail is noticed
+    std::io::copy(&mut unpacker, &mut std::io::sink())?;
+    Ok((files, unpacker.finish().1))
+}
+
+/// Sizes and modification times of the files to seal; equal fingerprints mean nothing to seal.
+fn fingerprint(dir: &Path) -> std::io::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    for rel in sealed_files(dir)? {
+        let meta = std::fs::metadata(dir.join(&rel))?;
+        rel.hash(&mut hasher);
+        meta.len().hash(&mut hasher);
+        meta.modified().ok().hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+fn dir_is_empty(dir: &Path) -> bool {
+    std::fs::read_dir(dir).map_or(true, |mut d| d.next().is_none())
+}
+
+// --- Who holds the mount ---
+//
+// One process owns the mounted workspace: it holds an exclusive lock on `OWNER_LOCK_FILE` and
+// alone seals and removes the mount. Other processes (CLI commands while the bot runs) use the
+// owner's live copy under a shared lock on `USERS_LOCK_FILE`; the owner leaves the mount in place
+// when it stops while they still hold it, and the next owner picks it up. Locks are released by
+// the OS when a process dies.
+
+enum Hold {
+    Owner {
+        _lock: File,
+        /// Fingerprint of the runtime at the last seal.
+        sealed: Option<u64>,
+    },
+    User {
+        _lock: File,
+    },
+}
+
+fn holds() -> &'static Mutex<HashMap<PathBuf, Hold>> {
+    static HOLDS: OnceLock<Mutex<HashMap<PathBuf, Hold>>> = OnceLock::new();
+    HOLDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lock_file(config: &Config, name: &str) -> std::io::Result<File> {
+    std::fs::create_dir_all(config.workspace_root_absolute())?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(config.workspace_root_absolute().join(name))
+}
+
+/// Unpack the archive (or encrypt a plaintext `runtime/` the first time) into the mount directory,
+/// as its new owner.
+fn unpack_as_owner(config: &Config, passphrase: &str) -> Result<(), MicroClawError> {
+    let mount_dir = PathBuf::from(config.runtime_data_dir());
+    if mount_dir.join(MOUNT_MARKER).exists() {
+        // Left by an owner that crashed, or that stopped while another process still used it
+        info!(
+            "Taking over the encrypted workspace already unpacked at {}",
+            mount_dir.display()
+        );
+        return Ok(());
+    }
+    if mount_dir.is_dir() && !dir_is_empty(&mount_dir) {
+        warn!(
+            "{} holds files that were not unpacked from the archive; restoring the archive over them",
+            mount_dir.display()
+        );
+        // The journal of a database opened there would be replayed into the restored one
+        for sidecar in ["microclaw.db-wal", "microclaw.db-shm"] {
+            let _ = std::fs::remove_file(mount_dir.join(sidecar));
+        }
+    }
+    create_private_dir(&mount_dir)?;
+
+    let archive = archive_path(config);
+    let plain_runtime = config.workspace_root_absolute().join("runtime");
+    if archive.exists() {
+        let (files, _) = read_archive(&archive, passphrase, &mount_dir)?;
+        info!(
+            "Unpacked encrypted workspace ({files} files) to {}",
+            mount_dir.display()
+        );
+    } else if plain_runtime.is_dir() && !dir_is_empty(&plain_runtime) {
+        let written = write_archive(config, passphrase, &plain_runtime)?;
+        let (files, read) = read_archive(&archive, passphrase, &mount_dir)?;
+        if read != written {
+            return Err(crypto_error(
+                "the new workspace archive did not read back correctly; runtime/ was left in place",
+            ));
+        }
+        std::fs::remove_dir_all(&plain_runtime)?;
+        info!(
+            "Encrypted the existing runtime ({files} files) into {} and removed the plaintext copy",
+            archive.display()
+        );
+    }
+    std::fs::write(mount_dir.join(MOUNT_MARKER), b"")?;
+    Ok(())
+}
+
+/// Make the runtime directory available before anything opens it. Returns whether this process
+/// became its owner and must [`unmount`] it; false when encryption is off, this process already
+/// holds it, or another process owns it (its live copy is used; the owner seals it).
+///
+/// Only a directory carrying the mount marker counts as a live copy. Anything else found there
+/// was written without mounting (e.g. service logs) and the archive is restored over it.
+///
+/// The first time encryption is enabled, the existing plaintext `runtime/` is moved into the
+/// archive and deleted once the archive has been read back successfully.
+pub fn mount(config: &Config) -> Result<bool, MicroClawError> {
+    mount_with(config, false)
+}
+
+/// [`mount`] for the bot, which must own the workspace to seal it: waits for another owner
+/// (e.g. a CLI command) to finish instead of sharing its copy.
+pub fn mount_owned(config: &Config) -> Result<(), MicroClawError> {
+    mount_with(config, true).map(|_| ())
+}
+
+fn mount_with(config: &Config, wait_for_ownership: bool) -> Result<bool, MicroClawError> {
+    let Some(enc) = &config.workspace_encryption else {
+        return Ok(false);
+    };
+    let passphrase = passphrase(enc)?;
+    let archive = archive_path(config);
+    let mut holds = holds().lock().unwrap();
+    if holds.contains_key(&archive) {
+        return Ok(false);
+    }
+    let marker = PathBuf::from(config.runtime_data_dir()).join(MOUNT_MARKER);
+    for attempt in 0..MOUNT_ATTEMPTS {
+        let owner = lock_file(config, OWNER_LOCK_FILE)?;
+        let acquired = if wait_for_ownership {
+            if attempt == 0 && owner.try_lock().is_err() {
+                info!("Waiting for another microclaw process to release the encrypted workspace");
+            }
+            owner.lock()?;
+            true
+        } else {
+            owner.try_lock().is_ok()
+        };
+        if acquired {
+            unpack_as_owner(config, &passphrase)?;
+            holds.insert(
+                archive,
+                Hold::Owner {
+                    _lock: owner,
+                    sealed: None,
+                },
+            );
+            return Ok(true);
+        }
+        let users = lock_file(config, USERS_LOCK_FILE)?;
+        users.lock_shared()?;
+        if marker.exists() {
+            info!(
+                "Using the encrypted workspace mounted by another process at {}",
+                config.runtime_data_dir()
+            );
+            holds.insert(archive, Hold::User { _lock: users });
+            return Ok(false);
+        }
+        // The owner is still unpacking, or just unmounted
+        drop(users);
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    Err(crypto_error(
+        "another microclaw process holds the encrypted workspace but has not unpacked it",
+    ))
+}
+
+/// Write the live runtime into the archive, unless nothing changed since the last seal. With
+/// `db`, its files are copied at a checkpoint. Only the owner of the mount seals.
+pub fn seal(config: &Config, db: Option<&Database>) -> Result<(), MicroClawError> {
+    let Some(enc) = &config.workspace_encryption else {
+        return Ok(());
+    };
+    let passphrase = passphrase(enc)?;
+    let archive = archive_path(config);
+    let mut holds = holds().lock().unwrap();
+    let Some(Hold::Owner { sealed, .. }) = holds.get_mut(&archive) else {
+        return Ok(());
+    };
+    let mount_dir = PathBuf::from(config.runtime_data_dir());
+    let mut write = || -> Result<(), MicroClawError> {
+        let current = fingerprint(&mount_dir)?;
+        if *sealed == Some(current) {
+            return Ok(());
+        }
+        write_archive(config, &passphrase, &mount_dir)?;
+        *sealed = Some(current);
+        Ok(())
+    };
+    match db {
+        Some(db) => db.with_checkpoint(write)?,
+        None => write(),
+    }
+}
+
+/// Seal for the last time and remove the unpacked copy (left in place while other processes
+/// still use it). A process that only used another owner's copy just lets go of it.
+pub fn unmount(config: &Config, db: Option<&Database>) -> Result<(), MicroClawError> {
+    if config.workspace_encryption.is_none() {
+        return Ok(());
+    }
+    seal(config, db)?;
+    let Some(hold) = holds().lock().unwrap().remove(&archive_path(config)) else {
+        return Ok(());
+    };
+    if let Hold::Owner { .. } = hold {
+        let users = lock_file(config, USERS_LOCK_FILE)?;
+        if users.try_lock().is_ok() {
+            std::fs::remove_dir_all(config.runtime_data_dir())?;
+            info!("Encrypted workspace sealed and unmounted");
+        } else {
+            warn!(
+                "Encrypted workspace sealed but left mounted at {}: another process still uses it",
+                config.runtime_data_dir()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Re-seal the live runtime on the configured interval (no-op without encryption).
+pub fn spawn_sync(state: Arc<AppState>) {
+    let Some(enc) = &state.config.workspace_encryption else {
+        return;
+    };
+    let interval = Duration::from_secs(enc.sync_interval_secs.max(30));
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let sync_state = state.clone();
+            let result =
+                tokio::task::spawn_blocking(move || seal(&sync_state.config, Some(&sync_state.db)))
+                    .await;
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!("Workspace encryption: periodic seal failed: {e}"),
+                Err(e) => warn!("Workspace encryption: periodic seal panicked: {e}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip_and_wrong_passphrase() {
+        let sealed = encrypt(b"family chat history", "correct horse").unwrap();
+        assert!(sealed.starts_with(ENCRYPTED_MAGIC));
+        assert!(!sealed
+            .windows(b"family".len())
+            .any(|w| w == b"family".as_slice()));
+        assert_eq!(
+            decrypt(&sealed, "correct horse").unwrap(),
+            b"family chat history"
+        );
+        assert!(decrypt(&sealed, "wrong")
+            .unwrap_err()
+            .to_string()
+            .contains("wrong passphrase"));
+        assert!(decrypt(b"plain", "x").is_err());
+    }
+
+    #[test]
+    fn test_mount_encrypts_plain_runtime_and_restores_it() {
+        let root =
+            std::env::temp_dir().join(format!("microclaw_ws_crypto_{}", uuid::Uuid::new_v4()));
+        let runtime = root.join("runtime");
+        std::fs::create_dir_all(runtime.join("groups/42")).unwrap();
+        std::fs::write(runtime.join("groups/42/AGENTS.md"), "likes tea").unwrap();
+        std::fs::write(runtime.join("notes.txt"), "hello").unwrap();
+
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = root.to_string_lossy().to_string();
+        let passphrase_file = root.join("pass");
+        std::fs::write(&passphrase_file, "s3cret\n").unwrap();
+        config.workspace_encryption = Some(WorkspaceEncryptionConfig {
+            passphrase_env: "MICROCLAW_TEST_UNSET_PASSPHRASE".into(),
+            passphrase_file: Some(passphrase_file.to_string_lossy().to_string()),
+            mount_dir: Some(root.join("mnt").to_string_lossy().to_string()),
+            sync_interval_secs: 300,
+        });
+
+        assert!(mount(&config).unwrap());
+        assert!(!runtime.exists());
+        let mount_dir = PathBuf::from(config.runtime_data_dir());
+        assert_eq!(
+            std::fs::read_to_string(mount_dir.join("groups/42/AGENTS.md")).unwrap(),
+            "likes tea"
+        );
+
+        std::fs::write(mount_dir.join("notes.txt"), "changed").unwrap();
+        unmount(&config, None).unwrap();
+        assert!(!mount_dir.exists());
+
+        assert!(mount(&config).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(mount_dir.join("notes.txt")).unwrap(),
+            "changed"
+        );
+        // Already mounted: left as is
+        assert!(!mount(&config).unwrap());
+
+        // A database opened without mounting must not pass for the live copy
+        unmount(&config, None).unwrap();
+        std::fs::create_dir_all(&mount_dir).unwrap();
+        std::fs::write(mount_dir.join("notes.txt"), "stray").unwrap();
+        std::fs::write(mount_dir.join("microclaw.db-wal"), "stray").unwrap();
+        assert!(mount(&config).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(mount_dir.join("notes.txt")).unwrap(),
+            "changed"
+        );
+        assert!(!mount_dir.join("microclaw.db-wal").exists());
+        assert!(!pack(&mount_dir)
+            .unwrap()
+            .windows(MOUNT_MARKER.len())
+            .any(|w| w == MOUNT_MARKER.as_bytes()));
+
+        let mut evil = PACK_MAGIC.to_vec();
+        evil.extend_from_slice(&5u32.to_le_bytes());
+        evil.extend_from_slice(b"../x1");
+        evil.extend_from_slice(&0u64.to_le_bytes());
+        assert!(unpack(&evil, &root).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    fn test_config(root: &Path) -> Config {
+        let mut config: Config =
+            serde_yaml::from_str("telegram_bot_token: tok\nbot_username: bot\napi_key: key\n")
+                .unwrap();
+        config.workspace_dir = root.to_string_lossy().to_string();
+        let passphrase_file = root.join("pass");
+        std::fs::create_dir_all(root).unwrap();
+        std::fs::write(&passphrase_file, "s3cret\n").unwrap();
+        config.workspace_encryption = Some(WorkspaceEncryptionConfig {
+            passphrase_env: "MICROCLAW_TEST_UNSET_PASSPHRASE".into(),
+            passphrase_file: Some(passphrase_file.to_string_lossy().to_string()),
+            mount_dir: Some(root.join("mnt").to_string_lossy().to_string()),
+            sync_interval_secs: 300,
+        });
+        config
+    }
+
+    #[test]
+    fn test_stream_round_trip_detects_truncation() {
+        let data: Vec<u8> = (0..CHUNK_LEN * 2 + 123).map(|i| i as u8).collect();
+        let mut sealer = SealWriter::new(Vec::new(), "pw").unwrap();
+        sealer.write_all(&data).unwrap();
+        let sealed = sealer.finish().unwrap();
+
+        let open = |bytes: &[u8], passphrase: &str| {
+            let mut reader = OpenReader::new(&bytes[STREAM_MAGIC.len()..], passphrase).unwrap();
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).map(|_| out)
+        };
+        assert_eq!(open(&sealed, "pw").unwrap(), data);
+        assert!(open(&sealed, "wrong").is_err());
+        // Dropping the last chunk must not pass for a shorter archive
+        let cut = sealed.len() - (123 + TAG_LEN);
+        assert!(open(&sealed[..cut], "pw").is_err());
+    }
+
+    #[test]
+    fn test_seal_skips_unchanged_runtime() {
+        let root =
+            std::env::temp_dir().join(format!("microclaw_ws_seal_{}", uuid::Uuid::new_v4()));
+        let config = test_config(&root);
+        assert!(mount(&config).unwrap());
+        let mount_dir = PathBuf::from(config.runtime_data_dir());
+        std::fs::write(mount_dir.join("notes.txt"), "likes tea").unwrap();
+
+        seal(&config, None).unwrap();
+        let first = std::fs::read(archive_path(&config)).unwrap();
+        assert!(first.starts_with(STREAM_MAGIC));
+        assert!(!first.windows(3).any(|w| w == b"tea".as_slice()));
+        seal(&config, None).unwrap();
+        assert_eq!(std::fs::read(archive_path(&config)).unwrap(), first);
+
+        std::fs::write(mount_dir.join("notes.txt"), "likes coffee").unwrap();
+        seal(&config, None).unwrap();
+        assert_ne!(std::fs::read(archive_path(&config)).unwrap(), first);
+
+        unmount(&config, None).unwrap();
+        assert!(mount(&config).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(mount_dir.join("notes.txt")).unwrap(),
+            "likes coffee"
+        );
+        unmount(&config, None).unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_mount_shares_another_owners_copy() {
+        let root =
+            std::env::temp_dir().join(format!("microclaw_ws_share_{}", uuid::Uuid::new_v4()));
+        let config = test_config(&root);
+        let mount_dir = PathBuf::from(config.runtime_data_dir());
+        std::fs::create_dir_all(&mount_dir).unwrap();
+        std::fs::write(mount_dir.join(MOUNT_MARKER), b"").unwrap();
+        std::fs::write(mount_dir.join("notes.txt"), "live").unwrap();
+
+        // Another process owns the mount: its live copy is used, never sealed or removed here
+        let owner = lock_file(&config, OWNER_LOCK_FILE).unwrap();
+        owner.try_lock().unwrap();
+        assert!(!mount(&config).unwrap());
+        seal(&config, None).unwrap();
+        assert!(!archive_path(&config).exists());
+        unmount(&config, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(mount_dir.join("notes.txt")).unwrap(),
+            "live"
+        );
+        drop(owner);
+
+        // The owner leaves the mount in place while another process still uses it
+        assert!(mount(&config).unwrap());
+        let user = lock_file(&config, USERS_LOCK_FILE).unwrap();
+        user.lock_shared().unwrap();
+        unmount(&config, None).unwrap();
+        assert!(archive_path(&config).exists());
+        assert!(mount_dir.join("notes.txt").exists());
+        drop(user);
+
+        // and the next owner takes over that copy
+        assert!(mount(&config).unwrap());
+        unmount(&config, None).unwrap();
+        assert!(!mount_dir.exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,8 +1,13 @@
 import React from 'react'
-import ReactMarkdown from 'react-markdown'
+import ReactMarkdown, { defaultUrlTransform } from 'react-markdown'
 import remarkBreaks from 'remark-breaks'
 import remarkGfm from 'remark-gfm'
 
+// Vault citations link to obsidian:// URIs, which the default transform strips
+export function allowObsidianUrls(url: string): string {
+  return url.startsWith('obsidian://') ? url : defaultUrlTransform(url)
+}
+
 type MessageMarkdownProps = {
   content: string
 }
@@ -12,6 +17,7 @@ export function MessageMarkdown({ content }: MessageMarkdownProps) {
     <div className="mt-2 text-[15px] leading-7 text-slate-800">
       <ReactMarkdown
         remarkPlugins={[remarkGfm, remarkBreaks]}
+        urlTransform={allowObsidianUrls}
         components={{
           p: ({ children }) => <p className="my-2 whitespace-pre-wrap">{children}</p>,
           ul: ({ children }) => <ul className="my-2 list-disc space-y-1 pl-6">{children}</ul>,
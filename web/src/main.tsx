@@ -37,10 +37,26 @@ import '@radix-ui/themes/styles.css'
 import '@assistant-ui/react-ui/styles/index.css'
 import './styles.css'
 import { SessionSidebar } from './components/session-sidebar'
+import { MessageMarkdown, allowObsidianUrls } from './components/message-markdown'
 import type { SessionItem } from './types'
 
 type ConfigPayload = Record<string, unknown>
 
+type FeatureFlagItem = {
+  flag: string
+  description: string
+  enabled: boolean
+  overridden: boolean
+}
+
+type ArtifactItem = {
+  id: number
+  title: string
+  type: string
+  content: string
+  created_at: string
+}
+
 type StreamEvent = {
   event: string
   payload: Record<string, unknown>
@@ -120,7 +136,7 @@ const DEFAULT_CONFIG_VALUES = {
   max_tokens: 8192,
   max_tool_iterations: 100,
   max_document_size_mb: 100,
-  show_thinking: false,
+  show_thinking: 'off',
   web_enabled: true,
   web_host: '127.0.0.1',
   web_port: 10961,
@@ -193,6 +209,15 @@ function getInitialSessionKey(): string {
   return fromUrl || DEFAULT_WEB_SESSION_KEY
 }
 
+// `?embed=compact` (or `?embed=1`): chat only, for iframes in dashboards (see web_embed).
+function isCompactEmbed(): boolean {
+  if (typeof window === 'undefined') return false
+  const embed = new URLSearchParams(window.location.search).get('embed')?.trim()
+  return embed === 'compact' || embed === '1'
+}
+
+const COMPACT_EMBED = isCompactEmbed()
+
 function pickLatestSessionKey(items: SessionItem[]): string {
   // Prefer web sessions so we don't land the user in a Telegram (read-only) chat.
   const webItems = items.filter((item) => item.chat_type === 'web')
@@ -539,7 +564,7 @@ type ThreadPaneProps = {
 }
 
 function ThreadPane({ adapter, initialMessages, runtimeKey }: ThreadPaneProps) {
-  const MarkdownText = makeMarkdownText()
+  const MarkdownText = makeMarkdownText({ urlTransform: allowObsidianUrls })
   const runtime = useLocalRuntime(adapter, {
     initialMessages,
     maxSteps: 100,
@@ -595,6 +620,10 @@ function App() {
   const [config, setConfig] = useState<ConfigPayload | null>(null)
   const [configDraft, setConfigDraft] = useState<Record<string, unknown>>({})
   const [saveStatus, setSaveStatus] = useState<string>('')
+  const [featureFlags, setFeatureFlags] = useState<FeatureFlagItem[]>([])
+  const [artifactsOpen, setArtifactsOpen] = useState<boolean>(false)
+  const [artifacts, setArtifacts] = useState<ArtifactItem[]>([])
+  const [openArtifactId, setOpenArtifactId] = useState<number | null>(null)
   const [authRequired, setAuthRequired] = useState<boolean>(false)
   const [authTokenInput, setAuthTokenInput] = useState<string>('')
 
@@ -962,14 +991,56 @@ function App() {
       max_tokens: Number(data.config?.max_tokens ?? 8192),
       max_tool_iterations: Number(data.config?.max_tool_iterations ?? 100),
       max_document_size_mb: Number(data.config?.max_document_size_mb ?? DEFAULT_CONFIG_VALUES.max_document_size_mb),
-      show_thinking: Boolean(data.config?.show_thinking),
+      show_thinking: String(data.config?.show_thinking || DEFAULT_CONFIG_VALUES.show_thinking),
       web_enabled: Boolean(data.config?.web_enabled),
       web_host: String(data.config?.web_host || '127.0.0.1'),
       web_port: Number(data.config?.web_port ?? 10961),
     })
+    try {
+      const flags = await api<{ flags?: FeatureFlagItem[] }>(
+        `/api/feature_flags?session_key=${encodeURIComponent(sessionKey)}`,
+      )
+      setFeatureFlags(flags.flags || [])
+    } catch {
+      setFeatureFlags([])
+    }
     setConfigOpen(true)
   }
 
+  async function openArtifacts(): Promise<void> {
+    try {
+      const data = await api<{ artifacts?: ArtifactItem[] }>(
+        `/api/artifacts?session_key=${encodeURIComponent(sessionKey)}`,
+      )
+      setArtifacts(data.artifacts || [])
+      setOpenArtifactId(null)
+      setArtifactsOpen(true)
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    }
+  }
+
+  async function deleteArtifact(id: number): Promise<void> {
+    try {
+      await api(`/api/artifacts/${id}?session_key=${encodeURIComponent(sessionKey)}`, { method: 'DELETE' })
+      setArtifacts((prev) => prev.filter((item) => item.id !== id))
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    }
+  }
+
+  async function setFeatureFlag(flag: string, enabled: boolean | null): Promise<void> {
+    try {
+      const data = await api<{ flags?: FeatureFlagItem[] }>('/api/feature_flags', {
+        method: 'PUT',
+        body: JSON.stringify({ session_key: sessionKey, flag, enabled }),
+      })
+      setFeatureFlags(data.flags || [])
+    } catch (e) {
+      setSaveStatus(`Save failed: ${e instanceof Error ? e.message : String(e)}`)
+    }
+  }
+
   function setConfigField(field: string, value: unknown): void {
     setConfigDraft((prev) => ({ ...prev, [field]: value }))
   }
@@ -1026,7 +1097,7 @@ function App() {
         max_document_size_mb: Number(
           configDraft.max_document_size_mb || DEFAULT_CONFIG_VALUES.max_document_size_mb,
         ),
-        show_thinking: Boolean(configDraft.show_thinking),
+        show_thinking: String(configDraft.show_thinking || DEFAULT_CONFIG_VALUES.show_thinking),
         web_enabled: Boolean(configDraft.web_enabled),
         web_host: String(configDraft.web_host || '127.0.0.1'),
         web_port: Number(configDraft.web_port || 10961),
@@ -1141,22 +1212,30 @@ function App() {
             : 'h-screen w-screen bg-[radial-gradient(1200px_560px_at_-8%_-10%,#d1fae5_0%,transparent_58%),radial-gradient(1200px_560px_at_108%_-12%,#e0f2fe_0%,transparent_58%),#f8fafc]'
         }
       >
-        <div className="grid h-full min-h-0 grid-cols-[320px_minmax(0,1fr)]">
-          <SessionSidebar
-            appearance={appearance}
-            onToggleAppearance={toggleAppearance}
-            uiTheme={uiTheme}
-            onUiThemeChange={(theme) => setUiTheme(theme as UiTheme)}
-            uiThemeOptions={UI_THEME_OPTIONS}
-            sessionItems={sessionItems}
-            selectedSessionKey={sessionKey}
-            onSessionSelect={(key) => setSessionKey(key)}
-            onRefreshSession={(key) => void onRefreshSessionByKey(key)}
-            onResetSession={(key) => void onResetSessionByKey(key)}
-            onDeleteSession={(key) => void onDeleteSessionByKey(key)}
-            onOpenConfig={openConfig}
-            onNewSession={createSession}
-          />
+        <div
+          className={
+            COMPACT_EMBED
+              ? 'grid h-full min-h-0 grid-cols-[minmax(0,1fr)]'
+              : 'grid h-full min-h-0 grid-cols-[320px_minmax(0,1fr)]'
+          }
+        >
+          {COMPACT_EMBED ? null : (
+            <SessionSidebar
+              appearance={appearance}
+              onToggleAppearance={toggleAppearance}
+              uiTheme={uiTheme}
+              onUiThemeChange={(theme) => setUiTheme(theme as UiTheme)}
+              uiThemeOptions={UI_THEME_OPTIONS}
+              sessionItems={sessionItems}
+              selectedSessionKey={sessionKey}
+              onSessionSelect={(key) => setSessionKey(key)}
+              onRefreshSession={(key) => void onRefreshSessionByKey(key)}
+              onResetSession={(key) => void onResetSessionByKey(key)}
+              onDeleteSession={(key) => void onDeleteSessionByKey(key)}
+              onOpenConfig={openConfig}
+              onNewSession={createSession}
+            />
+          )}
 
           <main
             className={
@@ -1171,10 +1250,16 @@ function App() {
                   ? 'sticky top-0 z-10 border-b border-[color:var(--mc-border-soft)] bg-[color:var(--mc-bg-panel)]/95 px-4 py-3 backdrop-blur-sm'
                   : 'sticky top-0 z-10 border-b border-slate-200 bg-white/92 px-4 py-3 backdrop-blur-sm'
               }
+              hidden={COMPACT_EMBED}
             >
-              <Heading size="6">
-                {selectedSessionLabel}
-              </Heading>
+              <Flex justify="between" align="center" gap="2">
+                <Heading size="6">
+                  {selectedSessionLabel}
+                </Heading>
+                <Button size="1" variant="soft" onClick={() => void openArtifacts()}>
+                  Artifacts
+                </Button>
+              </Flex>
             </header>
 
             {selectedSessionReadOnly ? (
@@ -1212,6 +1297,48 @@ function App() {
           </main>
         </div>
 
+        <Dialog.Root open={artifactsOpen} onOpenChange={setArtifactsOpen}>
+          <Dialog.Content maxWidth="760px">
+            <Dialog.Title>Artifacts</Dialog.Title>
+            <Dialog.Description size="2" mb="3">
+              Plans, tables, recipes and other outputs saved in this chat with save_artifact.
+            </Dialog.Description>
+            {artifacts.length === 0 ? (
+              <Text size="2" color="gray">
+                Nothing saved in this chat yet.
+              </Text>
+            ) : (
+              <Flex direction="column" gap="2">
+                {artifacts.map((item) => (
+                  <div key={item.id} className={toggleCardClass} style={toggleCardStyle}>
+                    <Flex justify="between" align="center" gap="2">
+                      <button
+                        type="button"
+                        className="min-w-0 flex-1 text-left"
+                        onClick={() => setOpenArtifactId(openArtifactId === item.id ? null : item.id)}
+                      >
+                        <Text size="2" weight="medium">{item.title}</Text>
+                        <Text size="1" color="gray" className="ml-2">
+                          {item.type} · {item.created_at.slice(0, 10)}
+                        </Text>
+                      </button>
+                      <Button size="1" variant="ghost" color="red" onClick={() => void deleteArtifact(item.id)}>
+                        Delete
+                      </Button>
+                    </Flex>
+                    {openArtifactId === item.id ? <MessageMarkdown content={item.content} /> : null}
+                  </div>
+                ))}
+              </Flex>
+            )}
+            <Flex justify="end" mt="3">
+              <Dialog.Close>
+                <Button variant="soft">Close</Button>
+              </Dialog.Close>
+            </Flex>
+          </Dialog.Content>
+        </Dialog.Root>
+
         <Dialog.Root open={configOpen} onOpenChange={setConfigOpen}>
           <Dialog.Content maxWidth="760px">
             <Dialog.Title>Runtime Config</Dialog.Title>
@@ -1361,10 +1488,19 @@ function App() {
                     <div className={toggleCardClass} style={toggleCardStyle}>
                       <Flex justify="between" align="center">
                         <Text size="2">show_thinking</Text>
-                        <Switch
-                          checked={Boolean(configDraft.show_thinking)}
-                          onCheckedChange={(checked) => setConfigField('show_thinking', checked)}
-                        />
+                        <Select.Root
+                          value={String(configDraft.show_thinking || DEFAULT_CONFIG_VALUES.show_thinking)}
+                          onValueChange={(value) => setConfigField('show_thinking', value)}
+                        >
+                          <Select.Trigger />
+                          <Select.Content>
+                            {['off', 'summary', 'full'].map((level) => (
+                              <Select.Item key={level} value={level}>
+                                {level}
+                              </Select.Item>
+                            ))}
+                          </Select.Content>
+                        </Select.Root>
                       </Flex>
                       <Button size="1" variant="ghost" className="mt-2" onClick={() => resetConfigField('show_thinking')}>
                         Reset to default
@@ -1385,6 +1521,31 @@ function App() {
                   </div>
                 </div>
 
+                {featureFlags.length > 0 ? (
+                  <div>
+                    <Text size="2" weight="medium">Feature flags for this chat</Text>
+                    <div className="mt-3 grid grid-cols-1 gap-3 md:grid-cols-2">
+                      {featureFlags.map((item) => (
+                        <div key={item.flag} className={toggleCardClass} style={toggleCardStyle}>
+                          <Flex justify="between" align="center">
+                            <Text size="2">{item.flag}</Text>
+                            <Switch
+                              checked={item.enabled}
+                              onCheckedChange={(checked) => void setFeatureFlag(item.flag, checked)}
+                            />
+                          </Flex>
+                          <Text size="1" color="gray">{item.description}</Text>
+                          {item.overridden ? (
+                            <Button size="1" variant="ghost" className="mt-2" onClick={() => void setFeatureFlag(item.flag, null)}>
+                              Reset to default
+                            </Button>
+                          ) : null}
+                        </div>
+                      ))}
+                    </div>
+                  </div>
+                ) : null}
+
                 {saveStatus ? (
                   <Text size="2" color={saveStatus.startsWith('Save failed') ? 'red' : 'green'}>
                     {saveStatus}